@@ -0,0 +1,81 @@
+//! Maximum throughput of a reliable, ordered data channel.
+//!
+//! Both ends run over an in-memory [`pipe`], so the numbers measure the cost of the
+//! DCEP/SCTP stack alone rather than a network path. Run with `cargo bench -p webrtc-data`,
+//! criterion reports the throughput of every message size.
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sctp::association::Association;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use util::conn::conn_pipe::pipe;
+use util::conn::Conn;
+use webrtc_data::data_channel::{Config, DataChannel};
+
+const MESSAGE_SIZES: [usize; 3] = [1024, 16 * 1024, 64 * 1024 - 1];
+const HEADER_SIZE: usize = 16;
+
+fn association_config(
+    net_conn: Arc<dyn Conn + Send + Sync>,
+    name: &str,
+) -> sctp::association::Config {
+    sctp::association::Config {
+        name: name.to_owned(),
+        ..sctp::association::Config::new(net_conn)
+    }
+}
+
+async fn create_data_channel_pair() -> (DataChannel, DataChannel) {
+    let (ca, cb) = pipe();
+    let (client, server) = tokio::join!(
+        Association::client(association_config(Arc::new(ca), "client")),
+        Association::server(association_config(Arc::new(cb), "server")),
+    );
+    let client = Arc::new(client.unwrap());
+    let server = Arc::new(server.unwrap());
+
+    let cfg = Config {
+        label: "bench".to_owned(),
+        ..Default::default()
+    };
+    let dc0 = DataChannel::dial(&client, 1, cfg).await.unwrap();
+    let existing_data_channels: Vec<DataChannel> = Vec::new();
+    let dc1 = DataChannel::accept(&server, Config::default(), &existing_data_channels)
+        .await
+        .unwrap();
+
+    (dc0, dc1)
+}
+
+fn benchmark_data_channel(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let (dc0, dc1) = rt.block_on(create_data_channel_pair());
+    let (dc0, dc1) = (&dc0, &dc1);
+
+    ///////////////////////////////////////////////////////////////////////////////////////////////
+    let mut group = c.benchmark_group("Benchmark DataChannel Throughput");
+    for size in MESSAGE_SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+
+        let data = Bytes::from(vec![0xa5u8; size]);
+        group.bench_with_input(BenchmarkId::new("Write", size), &data, |b, data| {
+            b.to_async(&rt).iter(|| async move {
+                dc0.write(data).await.unwrap();
+                dc1.read_data_channel_bytes().await.unwrap();
+            })
+        });
+
+        let bufs = [data.slice(..HEADER_SIZE), data.slice(HEADER_SIZE..)];
+        group.bench_with_input(BenchmarkId::new("WriteVectored", size), &bufs, |b, bufs| {
+            b.to_async(&rt).iter(|| async move {
+                dc0.write_data_channel_vectored(bufs, false).await.unwrap();
+                dc1.read_data_channel_bytes().await.unwrap();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_data_channel);
+criterion_main!(benches);
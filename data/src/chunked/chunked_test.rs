@@ -0,0 +1,142 @@
+use super::*;
+
+use sctp::stream::Stream;
+
+fn create_chunked_data_channel(config: Config) -> ChunkedDataChannel {
+    let dc = DataChannel::new(Arc::new(Stream::default()), Default::default());
+    ChunkedDataChannel::new(Arc::new(dc), config)
+}
+
+fn chunk_header(message_identifier: u32, message_length: u32, offset: u32) -> ChunkHeader {
+    ChunkHeader {
+        flags: 0,
+        message_identifier,
+        message_length,
+        offset,
+    }
+}
+
+#[test]
+fn test_chunk_header_marshal_unmarshal() -> Result<()> {
+    let header = ChunkHeader {
+        flags: CHUNK_FLAG_STRING,
+        message_identifier: 0x01020304,
+        message_length: 0x00100000,
+        offset: 0x0000fff0,
+    };
+
+    let raw = header.marshal()?;
+    assert_eq!(
+        &raw[..],
+        &[
+            0x01, 0x01, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00,
+            0xff, 0xf0
+        ]
+    );
+
+    let mut buf = raw.clone();
+    assert_eq!(header, ChunkHeader::unmarshal(&mut buf)?);
+
+    let mut buf = Bytes::from_static(&[0x02; CHUNK_HEADER_SIZE]);
+    assert_eq!(
+        Error::InvalidChunkVersion(2),
+        ChunkHeader::unmarshal(&mut buf).unwrap_err()
+    );
+
+    let mut buf = raw.slice(..CHUNK_HEADER_SIZE - 1);
+    assert!(ChunkHeader::unmarshal(&mut buf).is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_chunked_reassemble() -> Result<()> {
+    let dc = create_chunked_data_channel(Config::default());
+
+    let progress = Arc::new(std::sync::Mutex::new(vec![]));
+    let progress2 = Arc::clone(&progress);
+    let f: OnProgressFn = Box::new(move |p: ChunkProgress| {
+        progress2.lock().unwrap().push(p);
+        Box::pin(async {})
+    });
+    dc.on_receive_progress(f).await;
+
+    // a message made of a single chunk is delivered at once
+    let message = dc
+        .reassemble(chunk_header(0, 3, 0), Bytes::from_static(b"abc"))
+        .await?;
+    assert_eq!(Some((Bytes::from_static(b"abc"), false)), message);
+
+    // chunks may arrive out of order
+    let header = ChunkHeader {
+        flags: CHUNK_FLAG_STRING,
+        ..chunk_header(1, 6, 3)
+    };
+    assert_eq!(
+        None,
+        dc.reassemble(header.clone(), Bytes::from_static(b"def"))
+            .await?
+    );
+    let message = dc
+        .reassemble(
+            ChunkHeader {
+                offset: 0,
+                ..header
+            },
+            Bytes::from_static(b"abc"),
+        )
+        .await?;
+    assert_eq!(Some((Bytes::from_static(b"abcdef"), true)), message);
+    assert!(dc.reassembly.lock().await.is_empty());
+
+    assert_eq!(
+        vec![
+            ChunkProgress {
+                message_identifier: 0,
+                transferred: 3,
+                total: 3
+            },
+            ChunkProgress {
+                message_identifier: 1,
+                transferred: 3,
+                total: 6
+            },
+            ChunkProgress {
+                message_identifier: 1,
+                transferred: 6,
+                total: 6
+            },
+        ],
+        *progress.lock().unwrap()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_chunked_reassemble_limits() -> Result<()> {
+    let dc = create_chunked_data_channel(Config {
+        max_message_size: 0,
+        max_reassembly_size: 10,
+    });
+
+    assert_eq!(
+        Err(Error::ErrInvalidChunkOffset),
+        dc.reassemble(chunk_header(0, 4, 2), Bytes::from_static(b"abc"))
+            .await
+    );
+
+    assert_eq!(
+        None,
+        dc.reassemble(chunk_header(1, 8, 0), Bytes::from_static(b"abcd"))
+            .await?
+    );
+    assert_eq!(
+        Err(Error::ErrChunkedMessageTooLarge),
+        dc.reassemble(chunk_header(2, 4, 0), Bytes::from_static(b"ab"))
+            .await,
+        "8 of 10 bytes already being reassembled"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,357 @@
+#[cfg(test)]
+mod chunked_test;
+
+use crate::data_channel::DataChannel;
+use crate::error::{Error, Result};
+
+use util::marshal::*;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Size of the header of the chunks.
+pub const CHUNK_HEADER_SIZE: usize = 16;
+
+/// Version of the chunk header.
+pub const CHUNK_VERSION: u8 = 1;
+
+/// Flag of the chunks of a string message.
+pub const CHUNK_FLAG_STRING: u8 = 0x01;
+
+/// Default size of the data channel messages carrying the chunks, the default max message size
+/// of SCTP.
+const DEFAULT_MAX_MESSAGE_SIZE: u32 = 65536;
+
+/// Default limit of the bytes of the messages being reassembled.
+const DEFAULT_MAX_REASSEMBLY_SIZE: usize = 64 * 1024 * 1024;
+
+/// The header of a chunk of a message sent by a [`ChunkedDataChannel`]. Every message is sent as
+/// one or more binary data channel messages, each made of the header followed by the chunk data.
+///
+/// # Memory layout
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |    Version    |     Flags   |S|           Reserved            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                      Message Identifier                       |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                        Message Length                         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                            Offset                             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// |                          Chunk Data                           |
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// * Version: 1.
+/// * S: set if the message is a string.
+/// * Reserved: 0 when sent, ignored when received.
+/// * Message Identifier: the same for all the chunks of a message, incremented for every message.
+/// * Message Length: the length of the whole message, in bytes.
+/// * Offset: the position of the chunk data in the message, in bytes.
+///
+/// All the fields are in network byte order. An empty message is sent as a single chunk with no
+/// data.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub struct ChunkHeader {
+    pub flags: u8,
+    pub message_identifier: u32,
+    pub message_length: u32,
+    pub offset: u32,
+}
+
+impl MarshalSize for ChunkHeader {
+    fn marshal_size(&self) -> usize {
+        CHUNK_HEADER_SIZE
+    }
+}
+
+impl Marshal for ChunkHeader {
+    fn marshal_to(&self, mut buf: &mut [u8]) -> std::result::Result<usize, util::Error> {
+        if buf.remaining_mut() < CHUNK_HEADER_SIZE {
+            return Err(Error::UnexpectedEndOfBuffer {
+                expected: CHUNK_HEADER_SIZE,
+                actual: buf.remaining_mut(),
+            }
+            .into());
+        }
+
+        buf.put_u8(CHUNK_VERSION);
+        buf.put_u8(self.flags);
+        buf.put_u16(0);
+        buf.put_u32(self.message_identifier);
+        buf.put_u32(self.message_length);
+        buf.put_u32(self.offset);
+
+        Ok(CHUNK_HEADER_SIZE)
+    }
+}
+
+impl Unmarshal for ChunkHeader {
+    fn unmarshal<B>(buf: &mut B) -> std::result::Result<Self, util::Error>
+    where
+        Self: Sized,
+        B: Buf,
+    {
+        if buf.remaining() < CHUNK_HEADER_SIZE {
+            return Err(Error::UnexpectedEndOfBuffer {
+                expected: CHUNK_HEADER_SIZE,
+                actual: buf.remaining(),
+            }
+            .into());
+        }
+
+        let version = buf.get_u8();
+        if version != CHUNK_VERSION {
+            return Err(Error::InvalidChunkVersion(version).into());
+        }
+        let flags = buf.get_u8();
+        let _reserved = buf.get_u16();
+
+        Ok(ChunkHeader {
+            flags,
+            message_identifier: buf.get_u32(),
+            message_length: buf.get_u32(),
+            offset: buf.get_u32(),
+        })
+    }
+}
+
+/// ChunkProgress tells how many bytes of a message were sent or received.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct ChunkProgress {
+    pub message_identifier: u32,
+    pub transferred: usize,
+    pub total: usize,
+}
+
+pub type OnProgressFn = Box<
+    dyn (FnMut(ChunkProgress) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync,
+>;
+
+/// Config is used to configure a [`ChunkedDataChannel`].
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub struct Config {
+    /// max_message_size is the max message size negotiated with the peer, the size of the data
+    /// channel messages carrying the chunks. 65536 if 0.
+    pub max_message_size: u32,
+    /// max_reassembly_size limits the bytes of the messages being reassembled, 64 MiB if 0.
+    pub max_reassembly_size: usize,
+}
+
+/// A message partially received.
+struct PartialMessage {
+    is_string: bool,
+    data: BytesMut,
+    received: usize,
+}
+
+/// ChunkedDataChannel is an opt-in framing layer over a [`DataChannel`], which splits the
+/// messages larger than the max message size into chunks and reassembles them on the far side.
+/// Both endpoints must use it, see [`ChunkHeader`] for the format of the chunks.
+///
+/// It is meant for reliable data channels: a message missing a chunk is never delivered.
+pub struct ChunkedDataChannel {
+    data_channel: Arc<DataChannel>,
+    max_chunk_data_size: usize,
+    max_reassembly_size: usize,
+    next_message_identifier: AtomicU32,
+    reassembly: Mutex<HashMap<u32, PartialMessage>>,
+    on_send_progress: Mutex<Option<OnProgressFn>>,
+    on_receive_progress: Mutex<Option<OnProgressFn>>,
+}
+
+impl ChunkedDataChannel {
+    pub fn new(data_channel: Arc<DataChannel>, config: Config) -> Self {
+        let max_message_size = if config.max_message_size == 0 {
+            DEFAULT_MAX_MESSAGE_SIZE
+        } else {
+            config.max_message_size
+        } as usize;
+
+        ChunkedDataChannel {
+            data_channel,
+            max_chunk_data_size: std::cmp::max(max_message_size, CHUNK_HEADER_SIZE + 1)
+                - CHUNK_HEADER_SIZE,
+            max_reassembly_size: if config.max_reassembly_size == 0 {
+                DEFAULT_MAX_REASSEMBLY_SIZE
+            } else {
+                config.max_reassembly_size
+            },
+            next_message_identifier: AtomicU32::new(0),
+            reassembly: Mutex::new(HashMap::new()),
+            on_send_progress: Mutex::new(None),
+            on_receive_progress: Mutex::new(None),
+        }
+    }
+
+    /// Get back the inner data_channel.
+    pub fn into_inner(self) -> Arc<DataChannel> {
+        self.data_channel
+    }
+
+    /// Obtain a clone of the inner data_channel.
+    pub fn clone_inner(&self) -> Arc<DataChannel> {
+        self.data_channel.clone()
+    }
+
+    /// OnSendProgress sets the callback handler which would be called every time a chunk of a
+    /// message is written.
+    pub async fn on_send_progress(&self, f: OnProgressFn) {
+        *self.on_send_progress.lock().await = Some(f);
+    }
+
+    /// OnReceiveProgress sets the callback handler which would be called every time a chunk of a
+    /// message is received.
+    pub async fn on_receive_progress(&self, f: OnProgressFn) {
+        *self.on_receive_progress.lock().await = Some(f);
+    }
+
+    /// WriteMessage writes a message of any size, split into chunks. It waits for room in the
+    /// send buffer of the association before writing every chunk.
+    pub async fn write_message(&self, data: &Bytes, is_string: bool) -> Result<usize> {
+        if data.len() > u32::MAX as usize {
+            return Err(Error::ErrChunkedMessageTooLarge);
+        }
+
+        let header = ChunkHeader {
+            flags: if is_string { CHUNK_FLAG_STRING } else { 0 },
+            message_identifier: self.next_message_identifier.fetch_add(1, Ordering::SeqCst),
+            message_length: data.len() as u32,
+            offset: 0,
+        };
+
+        let mut offset = 0;
+        loop {
+            let end = std::cmp::min(offset + self.max_chunk_data_size, data.len());
+
+            let chunk_header = ChunkHeader {
+                offset: offset as u32,
+                ..header.clone()
+            };
+            let chunk = [chunk_header.marshal()?, data.slice(offset..end)];
+
+            self.data_channel.writable().await?;
+            self.data_channel
+                .write_data_channel_vectored(&chunk, false)
+                .await?;
+            offset = end;
+
+            let mut handler = self.on_send_progress.lock().await;
+            if let Some(f) = &mut *handler {
+                f(ChunkProgress {
+                    message_identifier: header.message_identifier,
+                    transferred: offset,
+                    total: data.len(),
+                })
+                .await;
+            }
+
+            if offset >= data.len() {
+                return Ok(data.len());
+            }
+        }
+    }
+
+    /// ReadMessage reads the next message. It returns the message and `true` if it is a string,
+    /// or None at EOF.
+    pub async fn read_message(&self) -> Result<Option<(Bytes, bool)>> {
+        loop {
+            let (mut chunk, _) = self.data_channel.read_data_channel_bytes().await?;
+            if chunk.is_empty() {
+                return Ok(None);
+            }
+
+            let header = ChunkHeader::unmarshal(&mut chunk)?;
+            if let Some(message) = self.reassemble(header, chunk).await? {
+                return Ok(Some(message));
+            }
+        }
+    }
+
+    /// reassemble adds a chunk to its message, and returns the message once complete.
+    async fn reassemble(&self, header: ChunkHeader, data: Bytes) -> Result<Option<(Bytes, bool)>> {
+        let total = header.message_length as usize;
+        let offset = header.offset as usize;
+        if offset + data.len() > total {
+            return Err(Error::ErrInvalidChunkOffset);
+        }
+        let is_string = header.flags & CHUNK_FLAG_STRING != 0;
+
+        let (transferred, message) = if offset == 0 && data.len() == total {
+            // A message made of a single chunk is not copied.
+            (total, Some((data, is_string)))
+        } else {
+            let mut reassembly = self.reassembly.lock().await;
+            if !reassembly.contains_key(&header.message_identifier) {
+                let pending = reassembly.values().fold(0, |n, m| n + m.data.len());
+                if total > self.max_reassembly_size.saturating_sub(pending) {
+                    return Err(Error::ErrChunkedMessageTooLarge);
+                }
+
+                let mut buf = BytesMut::with_capacity(total);
+                buf.resize(total, 0);
+                reassembly.insert(
+                    header.message_identifier,
+                    PartialMessage {
+                        is_string,
+                        data: buf,
+                        received: 0,
+                    },
+                );
+            }
+
+            let transferred = match reassembly.get_mut(&header.message_identifier) {
+                Some(m) => {
+                    m.data[offset..offset + data.len()].copy_from_slice(&data);
+                    m.received += data.len();
+                    m.received
+                }
+                None => return Ok(None),
+            };
+
+            let message = if transferred >= total {
+                reassembly
+                    .remove(&header.message_identifier)
+                    .map(|m| (m.data.freeze(), m.is_string))
+            } else {
+                None
+            };
+
+            (transferred, message)
+        };
+
+        let mut handler = self.on_receive_progress.lock().await;
+        if let Some(f) = &mut *handler {
+            f(ChunkProgress {
+                message_identifier: header.message_identifier,
+                transferred,
+                total,
+            })
+            .await;
+        }
+
+        Ok(message)
+    }
+}
+
+impl fmt::Debug for ChunkedDataChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkedDataChannel")
+            .field("data_channel", &self.data_channel)
+            .field("max_chunk_data_size", &self.max_chunk_data_size)
+            .field("max_reassembly_size", &self.max_reassembly_size)
+            .finish()
+    }
+}
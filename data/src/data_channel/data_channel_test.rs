@@ -5,6 +5,7 @@ use super::*;
 use util::conn::conn_bridge::*;
 use util::conn::*;
 
+use futures::future::poll_fn;
 use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWriteExt;
 use tokio::sync::{broadcast, mpsc};
@@ -34,10 +35,8 @@ async fn create_new_association_pair(
     // Setup client
     tokio::spawn(async move {
         let client = Association::client(sctp::association::Config {
-            net_conn: ca,
-            max_receive_buffer_size: 0,
-            max_message_size: 0,
             name: "client".to_owned(),
+            ..sctp::association::Config::new(ca)
         })
         .await;
 
@@ -50,10 +49,8 @@ async fn create_new_association_pair(
     // Setup server
     tokio::spawn(async move {
         let server = Association::server(sctp::association::Config {
-            net_conn: cb,
-            max_receive_buffer_size: 0,
-            max_message_size: 0,
             name: "server".to_owned(),
+            ..sctp::association::Config::new(cb)
         })
         .await;
 
@@ -269,6 +266,7 @@ async fn test_data_channel_channel_type_reliable_ordered() -> Result<()> {
         channel_type: ChannelType::Reliable,
         reliability_parameter: 123,
         label: "data".to_string(),
+        priority: CHANNEL_PRIORITY_HIGH,
         ..Default::default()
     };
 
@@ -281,6 +279,11 @@ async fn test_data_channel_channel_type_reliable_ordered() -> Result<()> {
 
     assert_eq!(dc0.config, cfg, "local config should match");
     assert_eq!(dc1.config, cfg, "remote config should match");
+    assert_eq!(
+        CHANNEL_PRIORITY_HIGH,
+        dc1.priority(),
+        "priority should match"
+    );
 
     br.reorder_next_nwrites(0, 2); // reordering on the wire
 
@@ -319,6 +322,52 @@ async fn test_data_channel_channel_type_reliable_ordered() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_data_channel_write_vectored() -> Result<()> {
+    let mut rbuf = vec![0u8; 4096];
+
+    let (br, ca, cb) = Bridge::new(0, None, None);
+
+    let (a0, a1) = create_new_association_pair(&br, Arc::new(ca), Arc::new(cb)).await?;
+
+    let cfg = Config {
+        channel_type: ChannelType::Reliable,
+        label: "data".to_string(),
+        ..Default::default()
+    };
+
+    let dc0 = DataChannel::dial(&a0, 100, cfg).await?;
+    bridge_process_at_least_one(&br).await;
+
+    let existing_data_channels: Vec<DataChannel> = Vec::new();
+    let dc1 = DataChannel::accept(&a1, Config::default(), &existing_data_channels).await?;
+    bridge_process_at_least_one(&br).await;
+
+    // a header followed by a payload spanning several DATA chunks
+    let header = Bytes::from_static(b"hdr:");
+    let payload = Bytes::from(vec![0xa5u8; 3000]);
+    let n = dc0
+        .write_data_channel_vectored(&[header.clone(), payload.clone()], true)
+        .await?;
+    assert_eq!(header.len() + payload.len(), n, "data length should match");
+
+    bridge_process_at_least_one(&br).await;
+
+    let (n, is_string) = dc1.read_data_channel(&mut rbuf[..]).await?;
+    assert!(is_string, "should be a string message");
+    assert_eq!(header.len() + payload.len(), n, "data length should match");
+    assert_eq!(&header[..], &rbuf[..4], "data should match");
+    assert_eq!(&payload[..], &rbuf[4..n], "data should match");
+
+    dc0.close().await?;
+    dc1.close().await?;
+    bridge_process_at_least_one(&br).await;
+
+    close_association_pair(&br, a0, a1).await;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_data_channel_channel_type_reliable_unordered() -> Result<()> {
     let mut sbuf = vec![0u8; 1000];
@@ -449,13 +498,14 @@ async fn test_data_channel_buffered_amount() -> Result<()> {
         bridge_process_at_least_one(&br).await;
     }
 
+    // the placeholder byte of an empty message is not application data
     let n = dc0.write(&Bytes::new()).await?;
     assert_eq!(0, n, "data length should match");
-    assert_eq!(1, dc0.buffered_amount(), "incorrect bufferedAmount");
+    assert_eq!(0, dc0.buffered_amount(), "incorrect bufferedAmount");
 
     let n = dc0.write(&Bytes::from_static(&[0])).await?;
     assert_eq!(1, n, "data length should match");
-    assert_eq!(2, dc0.buffered_amount(), "incorrect bufferedAmount");
+    assert_eq!(1, dc0.buffered_amount(), "incorrect bufferedAmount");
 
     bridge_process_at_least_one(&br).await;
 
@@ -482,7 +532,7 @@ async fn test_data_channel_buffered_amount() -> Result<()> {
         let n = dc0.write(&Bytes::from(sbuf.clone())).await?;
         assert_eq!(sbuf.len(), n, "data length should match");
         assert_eq!(
-            sbuf.len() * (i + 1) + 2,
+            sbuf.len() * (i + 1) + 1,
             dc0.buffered_amount(),
             "incorrect bufferedAmount"
         );
@@ -498,6 +548,9 @@ async fn test_data_channel_buffered_amount() -> Result<()> {
         }
     });
 
+    let dc0_cloned = Arc::clone(&dc0);
+    let low = tokio::spawn(async move { dc0_cloned.wait_buffered_amount_low().await });
+
     let since = tokio::time::Instant::now();
     loop {
         br.tick().await;
@@ -507,6 +560,12 @@ async fn test_data_channel_buffered_amount() -> Result<()> {
         }
     }
 
+    assert!(
+        dc0.buffered_amount() <= 1500,
+        "bufferedAmount should be low"
+    );
+    low.await.unwrap()?;
+
     dc0.close().await?;
     dc1.close().await?;
     bridge_process_at_least_one(&br).await;
@@ -671,3 +730,79 @@ async fn test_poll_data_channel() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_poll_data_channel_messages() -> Result<()> {
+    let (br, ca, cb) = Bridge::new(0, None, None);
+
+    let (a0, a1) = create_new_association_pair(&br, Arc::new(ca), Arc::new(cb)).await?;
+
+    let cfg = Config {
+        channel_type: ChannelType::Reliable,
+        label: "data".to_string(),
+        ..Default::default()
+    };
+
+    let dc0 = Arc::new(DataChannel::dial(&a0, 100, cfg.clone()).await?);
+    bridge_process_at_least_one(&br).await;
+
+    let existing_data_channels: Vec<DataChannel> = Vec::new();
+    let dc1 = Arc::new(DataChannel::accept(&a1, Config::default(), &existing_data_channels).await?);
+    bridge_process_at_least_one(&br).await;
+
+    let mut poll_dc0 = PollDataChannel::new(Arc::clone(&dc0));
+    let mut poll_dc1 = PollDataChannel::new(dc1);
+    let mut task = tokio_test::task::spawn(());
+
+    // messages keep their boundaries and type
+    let n =
+        task.enter(|cx, _| poll_dc0.poll_write_message(cx, &Bytes::from_static(b"hello"), true));
+    assert_eq!(Poll::Ready(Ok(5)), n);
+    let n =
+        task.enter(|cx, _| poll_dc0.poll_write_message(cx, &Bytes::from_static(b"world"), false));
+    assert_eq!(Poll::Ready(Ok(5)), n);
+    bridge_process_at_least_one(&br).await;
+
+    let msg = poll_fn(|cx| poll_dc1.poll_read_message(cx)).await?;
+    assert_eq!((Bytes::from_static(b"hello"), true), msg);
+    let msg = poll_fn(|cx| poll_dc1.poll_read_message(cx)).await?;
+    assert_eq!((Bytes::from_static(b"world"), false), msg);
+
+    // a message larger than the buffer is read in several calls
+    let sbuf = vec![7u8; 20000];
+    poll_dc0
+        .write_all(&sbuf)
+        .await
+        .map_err(|e| Error::new(e.to_string()))?;
+    // the last fragment is sent once the others are acknowledged
+    while dc0.buffered_amount() > 0 {
+        br.tick().await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let mut rbuf = vec![0u8; 20000];
+    let mut n = poll_dc1
+        .read(&mut rbuf[..1000])
+        .await
+        .map_err(|e| Error::new(e.to_string()))?;
+    assert_eq!(1000, n);
+    // the end of a partially read message comes first
+    let msg = task.enter(|cx, _| poll_dc1.poll_read_message(cx));
+    match msg {
+        Poll::Ready(Ok((data, false))) => {
+            rbuf[n..n + data.len()].copy_from_slice(&data);
+            n += data.len();
+        }
+        _ => panic!("unexpected message {:?}", msg),
+    }
+    assert_eq!(sbuf.len(), n, "data length should match");
+    assert_eq!(sbuf, rbuf, "data should match");
+
+    poll_dc0.into_inner().close().await?;
+    poll_dc1.into_inner().close().await?;
+    bridge_process_at_least_one(&br).await;
+
+    close_association_pair(&br, a0, a1).await;
+
+    Ok(())
+}
@@ -120,7 +120,11 @@ impl DataChannel {
 
             stream.write_sctp(&msg, PayloadProtocolIdentifier::Dcep)?;
         }
-        Ok(DataChannel::new(stream, config))
+
+        let data_channel = DataChannel::new(stream, config);
+        data_channel.commit_priority();
+
+        Ok(data_channel)
     }
 
     /// Server accepts a data channel over an SCTP stream
@@ -148,6 +152,7 @@ impl DataChannel {
 
         let data_channel = DataChannel::new(stream, config);
 
+        data_channel.commit_priority();
         data_channel.write_data_channel_ack().await?;
         data_channel.commit_reliability_params();
 
@@ -168,7 +173,7 @@ impl DataChannel {
     pub async fn read_data_channel(&self, buf: &mut [u8]) -> Result<(usize, bool)> {
         loop {
             //TODO: add handling of cancel read_data_channel
-            let (mut n, ppi) = match self.stream.read_sctp(buf).await {
+            let (n, ppi) = match self.stream.read_sctp(buf).await {
                 Ok((0, PayloadProtocolIdentifier::Unknown)) => {
                     // The incoming stream was reset or the reading half was shutdown
                     return Ok((0, false));
@@ -181,36 +186,75 @@ impl DataChannel {
                 }
             };
 
-            let mut is_string = false;
-            match ppi {
-                PayloadProtocolIdentifier::Dcep => {
-                    let mut data = &buf[..n];
-                    match self.handle_dcep(&mut data).await {
-                        Ok(()) => {}
-                        Err(err) => {
-                            log::error!("Failed to handle DCEP: {:?}", err);
-                        }
-                    }
-                    continue;
+            if let Some((n, is_string)) = self.handle_message(&buf[..n], ppi).await {
+                return Ok((n, is_string));
+            }
+        }
+    }
+
+    /// ReadDataChannelBytes reads the next message. It returns the message and `true` if it is a
+    /// string. Unlike `read_data_channel`, it does not copy messages which were not fragmented.
+    /// It returns an empty message at EOF.
+    ///
+    /// See [`sctp::stream::Stream::read_sctp_bytes`].
+    pub async fn read_data_channel_bytes(&self) -> Result<(Bytes, bool)> {
+        loop {
+            let (data, ppi) = match self.stream.read_sctp_bytes().await {
+                Ok((data, PayloadProtocolIdentifier::Unknown)) if data.is_empty() => {
+                    // The incoming stream was reset or the reading half was shutdown
+                    return Ok((Bytes::new(), false));
                 }
-                PayloadProtocolIdentifier::String | PayloadProtocolIdentifier::StringEmpty => {
-                    is_string = true;
+                Ok((data, ppi)) => (data, ppi),
+                Err(err) => {
+                    // Shutdown the stream and send the reset request to the remote.
+                    self.close().await?;
+                    return Err(err.into());
                 }
-                _ => {}
             };
 
-            match ppi {
-                PayloadProtocolIdentifier::StringEmpty | PayloadProtocolIdentifier::BinaryEmpty => {
-                    n = 0;
+            if let Some((n, is_string)) = self.handle_message(&data, ppi).await {
+                return Ok((data.slice(..n), is_string));
+            }
+        }
+    }
+
+    /// handle_message handles a message read from the stream. It returns the length of its data
+    /// and whether it is a string, or None if it was a DCEP message.
+    async fn handle_message(
+        &self,
+        data: &[u8],
+        ppi: PayloadProtocolIdentifier,
+    ) -> Option<(usize, bool)> {
+        let mut n = data.len();
+        let mut is_string = false;
+        match ppi {
+            PayloadProtocolIdentifier::Dcep => {
+                let mut data = data;
+                match self.handle_dcep(&mut data).await {
+                    Ok(()) => {}
+                    Err(err) => {
+                        log::error!("Failed to handle DCEP: {:?}", err);
+                    }
                 }
-                _ => {}
-            };
+                return None;
+            }
+            PayloadProtocolIdentifier::String | PayloadProtocolIdentifier::StringEmpty => {
+                is_string = true;
+            }
+            _ => {}
+        };
 
-            self.messages_received.fetch_add(1, Ordering::SeqCst);
-            self.bytes_received.fetch_add(n, Ordering::SeqCst);
+        match ppi {
+            PayloadProtocolIdentifier::StringEmpty | PayloadProtocolIdentifier::BinaryEmpty => {
+                n = 0;
+            }
+            _ => {}
+        };
 
-            return Ok((n, is_string));
-        }
+        self.messages_received.fetch_add(1, Ordering::SeqCst);
+        self.bytes_received.fetch_add(n, Ordering::SeqCst);
+
+        Some((n, is_string))
     }
 
     /// MessagesSent returns the number of messages sent
@@ -238,6 +282,12 @@ impl DataChannel {
         self.stream.stream_identifier()
     }
 
+    /// Priority returns the priority of the data channel, as announced in DATA_CHANNEL_OPEN.
+    /// Higher values are served first.
+    pub fn priority(&self) -> u16 {
+        self.config.priority
+    }
+
     async fn handle_dcep<B>(&self, data: &mut B) -> Result<()>
     where
         B: Buf,
@@ -267,7 +317,37 @@ impl DataChannel {
 
     /// WriteDataChannel writes len(p) bytes from p
     pub async fn write_data_channel(&self, data: &Bytes, is_string: bool) -> Result<usize> {
-        let data_len = data.len();
+        self.write_message(std::slice::from_ref(data), is_string)
+    }
+
+    /// WriteDataChannelVectored writes the concatenation of `bufs` as a single message.
+    ///
+    /// The buffers are handed down to the SCTP stream without being joined first,
+    /// see [`sctp::stream::Stream::write_sctp_vectored`].
+    pub async fn write_data_channel_vectored(
+        &self,
+        bufs: &[Bytes],
+        is_string: bool,
+    ) -> Result<usize> {
+        self.write_message(bufs, is_string)
+    }
+
+    /// IsWritable tells whether there is room in the send buffer of the association.
+    ///
+    /// See [`sctp::stream::Stream::is_writable`].
+    pub fn is_writable(&self) -> bool {
+        self.stream.is_writable()
+    }
+
+    /// Writable waits for room in the send buffer of the association.
+    ///
+    /// See [`sctp::stream::Stream::writable`].
+    pub async fn writable(&self) -> Result<()> {
+        Ok(self.stream.writable().await?)
+    }
+
+    fn write_message(&self, bufs: &[Bytes], is_string: bool) -> Result<usize> {
+        let data_len = bufs.iter().map(|b| b.len()).sum::<usize>();
 
         // https://tools.ietf.org/html/draft-ietf-rtcweb-data-channel-12#section-6.6
         // SCTP does not support the sending of empty user messages.  Therefore,
@@ -287,7 +367,7 @@ impl DataChannel {
             let _ = self.stream.write_sctp(&Bytes::from_static(&[0]), ppi)?;
             0
         } else {
-            let n = self.stream.write_sctp(data, ppi)?;
+            let n = self.stream.write_sctp_vectored(bufs, ppi)?;
             self.bytes_sent.fetch_add(n, Ordering::SeqCst);
             n
         };
@@ -319,8 +399,19 @@ impl DataChannel {
         Ok(self.stream.shutdown(Shutdown::Both).await?)
     }
 
-    /// BufferedAmount returns the number of bytes of data currently queued to be
-    /// sent over this stream.
+    /// is_association_established tells whether the SCTP association of the data
+    /// channel is established, so that the data channel can still be closed with
+    /// a stream reset.
+    ///
+    /// See [`sctp::stream::Stream::is_association_established`].
+    pub fn is_association_established(&self) -> bool {
+        self.stream.is_association_established()
+    }
+
+    /// BufferedAmount returns the number of bytes of application data written to
+    /// this data channel and not yet acknowledged by the peer.
+    ///
+    /// See [`sctp::stream::Stream::buffered_amount`].
     pub fn buffered_amount(&self) -> usize {
         self.stream.buffered_amount()
     }
@@ -337,12 +428,30 @@ impl DataChannel {
         self.stream.set_buffered_amount_low_threshold(threshold)
     }
 
+    /// WaitBufferedAmountLow waits until the number of bytes of outgoing data
+    /// buffered is lower than or equal to the threshold.
+    ///
+    /// See [`sctp::stream::Stream::wait_buffered_amount_low`].
+    pub async fn wait_buffered_amount_low(&self) -> Result<()> {
+        Ok(self.stream.wait_buffered_amount_low().await?)
+    }
+
     /// OnBufferedAmountLow sets the callback handler which would be called when the
     /// number of bytes of outgoing data buffered is lower than the threshold.
     pub fn on_buffered_amount_low(&self, f: OnBufferedAmountLowFn) {
         self.stream.on_buffered_amount_low(f)
     }
 
+    /// commit_priority maps the priority of the data channel onto the SCTP stream schedulers:
+    /// the weight doubles with each priority level above CHANNEL_PRIORITY_BELOW_NORMAL, and
+    /// higher priorities are served first by the strict priority scheduler.
+    fn commit_priority(&self) {
+        let priority = self.config.priority;
+        self.stream
+            .set_weight(std::cmp::max(priority / CHANNEL_PRIORITY_BELOW_NORMAL, 1));
+        self.stream.set_priority(u16::MAX - priority);
+    }
+
     fn commit_reliability_params(&self) {
         let (unordered, reliability_type) = match self.config.channel_type {
             ChannelType::Reliable => (false, ReliabilityType::Reliable),
@@ -361,46 +470,31 @@ impl DataChannel {
     }
 }
 
-/// Default capacity of the temporary read buffer used by [`PollStream`].
-const DEFAULT_READ_BUF_SIZE: usize = 8192;
+type ReadMessageFut = Pin<Box<dyn Future<Output = Result<(Bytes, bool)>> + Send>>;
+type ResultFut = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
 
-/// State of the read `Future` in [`PollStream`].
+/// State of the read `Future` in [`PollDataChannel`].
 enum ReadFut {
     /// Nothing in progress.
     Idle,
-    /// Reading data from the underlying stream.
-    Reading(Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send>>),
-    /// Finished reading, but there's unread data in the temporary buffer.
-    RemainingData(Vec<u8>),
-}
-
-impl ReadFut {
-    /// Gets a mutable reference to the future stored inside `Reading(future)`.
-    ///
-    /// # Panics
-    ///
-    /// Panics if `ReadFut` variant is not `Reading`.
-    fn get_reading_mut(&mut self) -> &mut Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send>> {
-        match self {
-            ReadFut::Reading(ref mut fut) => fut,
-            _ => panic!("expected ReadFut to be Reading"),
-        }
-    }
+    /// Reading the next message from the underlying data channel.
+    Reading(ReadMessageFut),
+    /// Finished reading, but the end of the message was not read yet.
+    RemainingData(Bytes, bool),
 }
 
 /// A wrapper around around [`DataChannel`], which implements [`AsyncRead`] and
-/// [`AsyncWrite`].
+/// [`AsyncWrite`], so that a data channel can be used with codecs and `tokio::io` utilities.
 ///
-/// Both `poll_read` and `poll_write` calls allocate temporary buffers, which results in an
-/// additional overhead.
+/// `poll_read` reads the messages as a stream of bytes, while `poll_read_message` and
+/// `poll_write_message` keep the message boundaries. `poll_write` waits for room in the send
+/// buffer of the association and copies `buf` into a message.
 pub struct PollDataChannel {
     data_channel: Arc<DataChannel>,
 
     read_fut: ReadFut,
-    write_fut: Option<Pin<Box<dyn Future<Output = Result<usize>> + Send>>>,
-    shutdown_fut: Option<Pin<Box<dyn Future<Output = Result<()>> + Send>>>,
-
-    read_buf_cap: usize,
+    ready_fut: Option<ResultFut>,
+    shutdown_fut: Option<ResultFut>,
 }
 
 impl PollDataChannel {
@@ -420,9 +514,83 @@ impl PollDataChannel {
         Self {
             data_channel,
             read_fut: ReadFut::Idle,
-            write_fut: None,
+            ready_fut: None,
             shutdown_fut: None,
-            read_buf_cap: DEFAULT_READ_BUF_SIZE,
+        }
+    }
+
+    /// Polls for the next message. It returns the message and `true` if it is a string, or an
+    /// empty message at EOF. The end of a message partially read by `poll_read` comes first.
+    ///
+    /// See [`DataChannel::read_data_channel_bytes`].
+    pub fn poll_read_message(&mut self, cx: &mut Context<'_>) -> Poll<Result<(Bytes, bool)>> {
+        let fut = match self.read_fut {
+            ReadFut::Idle => {
+                let data_channel = self.data_channel.clone();
+                self.read_fut = ReadFut::Reading(Box::pin(async move {
+                    data_channel.read_data_channel_bytes().await
+                }));
+                match self.read_fut {
+                    ReadFut::Reading(ref mut fut) => fut,
+                    _ => unreachable!(),
+                }
+            }
+            ReadFut::Reading(ref mut fut) => fut,
+            ReadFut::RemainingData(ref mut data, is_string) => {
+                let data = std::mem::take(data);
+                self.read_fut = ReadFut::Idle;
+                return Poll::Ready(Ok((data, is_string)));
+            }
+        };
+
+        let result = match fut.as_mut().poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(result) => result,
+        };
+        self.read_fut = ReadFut::Idle;
+        Poll::Ready(result)
+    }
+
+    /// Polls for room in the send buffer of the association, see [`DataChannel::writable`].
+    /// `poll_write` and `poll_write_message` wait for it before writing.
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let fut = match self.ready_fut.as_mut() {
+            Some(fut) => fut,
+            None => {
+                if self.data_channel.is_writable() {
+                    return Poll::Ready(Ok(()));
+                }
+                let data_channel = self.data_channel.clone();
+                self.ready_fut
+                    .get_or_insert(Box::pin(async move { data_channel.writable().await }))
+            }
+        };
+
+        let result = match fut.as_mut().poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(result) => result,
+        };
+        self.ready_fut = None;
+        Poll::Ready(result)
+    }
+
+    /// Polls for room in the send buffer of the association, then writes `data` as a single
+    /// message, as a string if `is_string` is `true`.
+    ///
+    /// See [`DataChannel::write_data_channel`].
+    pub fn poll_write_message(
+        &mut self,
+        cx: &mut Context<'_>,
+        data: &Bytes,
+        is_string: bool,
+    ) -> Poll<Result<usize>> {
+        match self.poll_ready(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Ready(Ok(())) => Poll::Ready(
+                self.data_channel
+                    .write_message(std::slice::from_ref(data), is_string),
+            ),
         }
     }
 
@@ -473,10 +641,10 @@ impl PollDataChannel {
         self.data_channel.buffered_amount_low_threshold()
     }
 
-    /// Set the capacity of the temporary read buffer (default: 8192).
-    pub fn set_read_buf_capacity(&mut self, capacity: usize) {
-        self.read_buf_cap = capacity
-    }
+    /// Set the capacity of the temporary read buffer. Messages are no longer read into a
+    /// temporary buffer, so it has no effect.
+    #[deprecated(note = "messages of any size are read without a temporary buffer")]
+    pub fn set_read_buf_capacity(&mut self, _capacity: usize) {}
 }
 
 impl AsyncRead for PollDataChannel {
@@ -489,62 +657,18 @@ impl AsyncRead for PollDataChannel {
             return Poll::Ready(Ok(()));
         }
 
-        let fut = match self.read_fut {
-            ReadFut::Idle => {
-                // read into a temporary buffer because `buf` has an unonymous lifetime, which can
-                // be shorter than the lifetime of `read_fut`.
-                let data_channel = self.data_channel.clone();
-                let mut temp_buf = vec![0; self.read_buf_cap];
-                self.read_fut = ReadFut::Reading(Box::pin(async move {
-                    data_channel.read(temp_buf.as_mut_slice()).await.map(|n| {
-                        temp_buf.truncate(n);
-                        temp_buf
-                    })
-                }));
-                self.read_fut.get_reading_mut()
-            }
-            ReadFut::Reading(ref mut fut) => fut,
-            ReadFut::RemainingData(ref mut data) => {
-                let remaining = buf.remaining();
-                let len = std::cmp::min(data.len(), remaining);
+        match self.poll_read_message(cx) {
+            Poll::Pending => Poll::Pending,
+            // EOF has been reached => don't touch buf and just return Ok
+            Poll::Ready(Err(Error::Sctp(sctp::Error::ErrEof))) => Poll::Ready(Ok(())),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e.into())),
+            Poll::Ready(Ok((data, is_string))) => {
+                let len = std::cmp::min(data.len(), buf.remaining());
                 buf.put_slice(&data[..len]);
-                if data.len() > remaining {
-                    // ReadFut remains to be RemainingData
-                    data.drain(..len);
-                } else {
-                    self.read_fut = ReadFut::Idle;
-                }
-                return Poll::Ready(Ok(()));
-            }
-        };
-
-        loop {
-            match fut.as_mut().poll(cx) {
-                Poll::Pending => return Poll::Pending,
-                // retry immediately upon empty data or incomplete chunks
-                // since there's no way to setup a waker.
-                Poll::Ready(Err(Error::Sctp(sctp::Error::ErrTryAgain))) => {}
-                // EOF has been reached => don't touch buf and just return Ok
-                Poll::Ready(Err(Error::Sctp(sctp::Error::ErrEof))) => {
-                    self.read_fut = ReadFut::Idle;
-                    return Poll::Ready(Ok(()));
-                }
-                Poll::Ready(Err(e)) => {
-                    self.read_fut = ReadFut::Idle;
-                    return Poll::Ready(Err(e.into()));
-                }
-                Poll::Ready(Ok(mut temp_buf)) => {
-                    let remaining = buf.remaining();
-                    let len = std::cmp::min(temp_buf.len(), remaining);
-                    buf.put_slice(&temp_buf[..len]);
-                    if temp_buf.len() > remaining {
-                        temp_buf.drain(..len);
-                        self.read_fut = ReadFut::RemainingData(temp_buf);
-                    } else {
-                        self.read_fut = ReadFut::Idle;
-                    }
-                    return Poll::Ready(Ok(()));
+                if len < data.len() {
+                    self.read_fut = ReadFut::RemainingData(data.slice(len..), is_string);
                 }
+                Poll::Ready(Ok(()))
             }
         }
     }
@@ -560,79 +684,19 @@ impl AsyncWrite for PollDataChannel {
             return Poll::Ready(Ok(0));
         }
 
-        if let Some(fut) = self.write_fut.as_mut() {
-            match fut.as_mut().poll(cx) {
-                Poll::Pending => Poll::Pending,
-                Poll::Ready(Err(e)) => {
-                    let data_channel = self.data_channel.clone();
-                    let bytes = Bytes::copy_from_slice(buf);
-                    self.write_fut =
-                        Some(Box::pin(async move { data_channel.write(&bytes).await }));
-                    Poll::Ready(Err(e.into()))
-                }
-                // Given the data is buffered, it's okay to ignore the number of written bytes.
-                //
-                // TODO: In the long term, `data_channel.write` should be made sync. Then we could
-                // remove the whole `if` condition and just call `data_channel.write`.
-                Poll::Ready(Ok(_)) => {
-                    let data_channel = self.data_channel.clone();
-                    let bytes = Bytes::copy_from_slice(buf);
-                    self.write_fut =
-                        Some(Box::pin(async move { data_channel.write(&bytes).await }));
-                    Poll::Ready(Ok(buf.len()))
-                }
-            }
-        } else {
-            let data_channel = self.data_channel.clone();
-            let bytes = Bytes::copy_from_slice(buf);
-            let fut = self
-                .write_fut
-                .insert(Box::pin(async move { data_channel.write(&bytes).await }));
-
-            match fut.as_mut().poll(cx) {
-                // If it's the first time we're polling the future, `Poll::Pending` can't be
-                // returned because that would mean the `PollDataChannel` is not ready for writing.
-                // And this is not true since we've just created a future, which is going to write
-                // the buf to the underlying stream.
-                //
-                // It's okay to return `Poll::Ready` if the data is buffered (this is what the
-                // buffered writer and `File` do).
-                Poll::Pending => Poll::Ready(Ok(buf.len())),
-                Poll::Ready(Err(e)) => {
-                    self.write_fut = None;
-                    Poll::Ready(Err(e.into()))
-                }
-                Poll::Ready(Ok(n)) => {
-                    self.write_fut = None;
-                    Poll::Ready(Ok(n))
-                }
-            }
+        let bytes = Bytes::copy_from_slice(buf);
+        match self.poll_write_message(cx, &bytes, false) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(result) => Poll::Ready(result.map_err(|e| e.into())),
         }
     }
 
-    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        match self.write_fut.as_mut() {
-            Some(fut) => match fut.as_mut().poll(cx) {
-                Poll::Pending => Poll::Pending,
-                Poll::Ready(Err(e)) => {
-                    self.write_fut = None;
-                    Poll::Ready(Err(e.into()))
-                }
-                Poll::Ready(Ok(_)) => {
-                    self.write_fut = None;
-                    Poll::Ready(Ok(()))
-                }
-            },
-            None => Poll::Ready(Ok(())),
-        }
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // The messages are buffered by the association as soon as they are written.
+        Poll::Ready(Ok(()))
     }
 
     fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        match self.as_mut().poll_flush(cx) {
-            Poll::Pending => return Poll::Pending,
-            Poll::Ready(_) => {}
-        }
-
         let fut = match self.shutdown_fut.as_mut() {
             Some(fut) => fut,
             None => {
@@ -671,7 +735,6 @@ impl fmt::Debug for PollDataChannel {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("PollDataChannel")
             .field("data_channel", &self.data_channel)
-            .field("read_buf_cap", &self.read_buf_cap)
             .finish()
     }
 }
@@ -19,6 +19,12 @@ pub enum Error {
     InvalidPayloadProtocolIdentifier(u8),
     #[error("Stream closed")]
     ErrStreamClosed,
+    #[error("Unknown chunk version {0}")]
+    InvalidChunkVersion(u8),
+    #[error("chunk beyond the end of its message")]
+    ErrInvalidChunkOffset,
+    #[error("chunked message too large")]
+    ErrChunkedMessageTooLarge,
 
     #[error("{0}")]
     Util(#[from] util::Error),
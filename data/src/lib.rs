@@ -1,6 +1,7 @@
 #![warn(rust_2018_idioms)]
 #![allow(dead_code)]
 
+pub mod chunked;
 pub mod data_channel;
 mod error;
 pub mod message;
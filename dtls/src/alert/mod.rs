@@ -154,6 +154,12 @@ impl fmt::Display for Alert {
 }
 
 impl Alert {
+    /// is_close_notify tells if the alert is a close_notify, which closes a connection
+    /// without an error.
+    pub fn is_close_notify(&self) -> bool {
+        self.alert_description == AlertDescription::CloseNotify
+    }
+
     pub fn content_type(&self) -> ContentType {
         ContentType::Alert
     }
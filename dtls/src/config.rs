@@ -181,14 +181,6 @@ pub(crate) fn validate_config(is_client: bool, config: &Config) -> Result<()> {
         return Err(Error::ErrIdentityNoPsk);
     }
 
-    for cert in &config.certificates {
-        match cert.private_key.kind {
-            CryptoPrivateKeyKind::Ed25519(_) => {}
-            CryptoPrivateKeyKind::Ecdsa256(_) => {}
-            _ => return Err(Error::ErrInvalidPrivateKey),
-        }
-    }
-
     parse_cipher_suites(
         &config.cipher_suites,
         config.psk.is_none(),
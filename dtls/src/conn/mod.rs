@@ -28,12 +28,13 @@ use util::{replay_detector::*, Conn};
 
 use async_trait::async_trait;
 use log::*;
+use std::future::Future;
 use std::io::{BufReader, BufWriter};
 use std::marker::{Send, Sync};
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, watch, Mutex};
 use tokio::time::Duration;
 
 pub(crate) const INITIAL_TICKER_INTERVAL: Duration = Duration::from_secs(1);
@@ -65,6 +66,7 @@ struct ConnReaderContext {
     handshake_tx: mpsc::Sender<mpsc::Sender<()>>,
     handshake_done_rx: mpsc::Receiver<()>,
     packet_tx: Arc<mpsc::Sender<PacketSendRequest>>,
+    closing_alert_tx: watch::Sender<Option<Alert>>,
 }
 
 // Conn represents a DTLS connection
@@ -77,7 +79,8 @@ pub struct DTLSConn {
     handshake_completed_successfully: Arc<AtomicBool>,
     connection_closed_by_user: bool,
     // closeLock              sync.Mutex
-    closed: AtomicBool, //  *closer.Closer
+    closed: Arc<AtomicBool>, //  *closer.Closer
+    closing_alert_rx: watch::Receiver<Option<Alert>>,
     //handshakeLoopsFinished sync.WaitGroup
 
     //readDeadline  :deadline.Deadline,
@@ -155,8 +158,20 @@ impl DTLSConn {
             config.psk.is_some(),
         )?
         .iter()
+        .filter(|cs| {
+            // A server only picks the suites which its certificates can sign
+            is_client
+                || cs.is_psk()
+                || config
+                    .certificates
+                    .iter()
+                    .any(|cert| cert.private_key.certificate_type() == cs.certificate_type())
+        })
         .map(|cs| cs.id())
         .collect();
+        if local_cipher_suites.is_empty() {
+            return Err(Error::ErrNoAvailableCipherSuites);
+        }
 
         let sigs: Vec<u16> = config.signature_schemes.iter().map(|x| *x as u16).collect();
         let local_signature_schemes = parse_signature_schemes(&sigs, config.insecure_hashes)?;
@@ -257,6 +272,7 @@ impl DTLSConn {
         let (packet_tx, mut packet_rx) = mpsc::channel(1);
         let (handle_queue_tx, mut handle_queue_rx) = mpsc::channel(1);
         let (reader_close_tx, mut reader_close_rx) = mpsc::channel(1);
+        let (closing_alert_tx, closing_alert_rx) = watch::channel(None);
 
         let packet_tx = Arc::new(packet_tx);
         let packet_tx2 = Arc::clone(&packet_tx);
@@ -275,7 +291,8 @@ impl DTLSConn {
             state,
             handshake_completed_successfully,
             connection_closed_by_user: false,
-            closed: AtomicBool::new(false),
+            closed: Arc::new(AtomicBool::new(false)),
+            closing_alert_rx,
 
             current_flight: flight,
             flights: None,
@@ -337,6 +354,7 @@ impl DTLSConn {
                 handshake_tx,
                 handshake_done_rx,
                 packet_tx: packet_tx2,
+                closing_alert_tx,
             };
 
             //trace!("before enter read_and_buffer: {}] ", srv_cli_str(is_client));
@@ -481,6 +499,28 @@ impl DTLSConn {
         Ok(())
     }
 
+    /// closed returns a future which resolves once the connection is closed, with the alert
+    /// which closed it: the close_notify of the remote peer, or a fatal alert received from
+    /// or sent to it. It resolves with None if the connection was closed with close, or if
+    /// the underlying connection failed. The future doesn't keep the connection alive.
+    pub fn closed(&self) -> impl Future<Output = Option<Alert>> + Send + 'static {
+        let mut closing_alert_rx = self.closing_alert_rx.clone();
+        let closed = Arc::clone(&self.closed);
+        async move {
+            loop {
+                let alert = *closing_alert_rx.borrow();
+                if alert.is_some() || closing_alert_rx.changed().await.is_err() {
+                    break;
+                }
+            }
+            if closed.load(Ordering::SeqCst) {
+                None
+            } else {
+                *closing_alert_rx.borrow()
+            }
+        }
+    }
+
     /// connection_state returns basic DTLS details about the connection.
     /// Note that this replaced the `Export` function of v1.
     pub async fn connection_state(&self) -> State {
@@ -785,6 +825,7 @@ impl DTLSConn {
                 if alert.alert_level == AlertLevel::Fatal
                     || alert.alert_description == AlertDescription::CloseNotify
                 {
+                    let _ = ctx.closing_alert_tx.send(Some(alert));
                     return Err(Error::ErrAlertFatalOrClose);
                 }
             }
@@ -865,6 +906,7 @@ impl DTLSConn {
                 if alert.alert_level == AlertLevel::Fatal
                     || alert.alert_description == AlertDescription::CloseNotify
                 {
+                    let _ = ctx.closing_alert_tx.send(Some(alert));
                     return Err(Error::ErrAlertFatalOrClose);
                 }
             }
@@ -220,3 +220,47 @@ fn test_certificate_verify() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_certificate_verify_ecdsa384() -> Result<()> {
+    let plain_text = b"certificate verify with ECDSA P-384".to_vec();
+
+    let certificate = Certificate::generate_self_signed_with_alg(
+        vec!["localhost".to_owned()],
+        &rcgen::PKCS_ECDSA_P384_SHA384,
+    )?;
+    assert!(matches!(
+        certificate.private_key.kind,
+        CryptoPrivateKeyKind::Ecdsa384(_)
+    ));
+    assert_eq!(certificate.private_key.clone(), certificate.private_key);
+
+    let cert_verify = generate_certificate_verify(&plain_text, &certificate.private_key)?;
+    let raw_certificates = certificate
+        .certificate
+        .iter()
+        .map(|x| x.0.clone())
+        .collect::<Vec<Vec<u8>>>();
+    verify_certificate_verify(
+        &plain_text,
+        &SignatureHashAlgorithm {
+            hash: HashAlgorithm::Sha384,
+            signature: SignatureAlgorithm::Ecdsa,
+        },
+        &cert_verify,
+        &raw_certificates,
+        false,
+    )?;
+
+    let p256 = SignatureHashAlgorithm {
+        hash: HashAlgorithm::Sha256,
+        signature: SignatureAlgorithm::Ecdsa,
+    };
+    assert!(!p256.is_compatible(&certificate.private_key));
+    assert!(
+        verify_certificate_verify(&plain_text, &p256, &cert_verify, &raw_certificates, false)
+            .is_err()
+    );
+
+    Ok(())
+}
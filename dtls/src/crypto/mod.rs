@@ -6,6 +6,7 @@ pub mod crypto_ccm;
 pub mod crypto_gcm;
 pub mod padding;
 
+use crate::client_certificate_type::ClientCertificateType;
 use crate::curve::named_curve::*;
 use crate::error::*;
 use crate::record_layer::record_layer_header::*;
@@ -133,11 +134,12 @@ pub(crate) fn value_key_message(
     plaintext
 }
 
-/// Either ED25519, ECDSA or RSA keypair.
+/// Either ED25519, ECDSA (P-256 or P-384) or RSA keypair.
 #[derive(Debug)]
 pub enum CryptoPrivateKeyKind {
     Ed25519(Ed25519KeyPair),
     Ecdsa256(EcdsaKeyPair),
+    Ecdsa384(EcdsaKeyPair),
     Rsa256(RsaKeyPair),
 }
 
@@ -164,6 +166,9 @@ impl PartialEq for CryptoPrivateKey {
             ) | (
                 CryptoPrivateKeyKind::Ecdsa256(_),
                 CryptoPrivateKeyKind::Ecdsa256(_)
+            ) | (
+                CryptoPrivateKeyKind::Ecdsa384(_),
+                CryptoPrivateKeyKind::Ecdsa384(_)
             ) | (
                 CryptoPrivateKeyKind::Ed25519(_),
                 CryptoPrivateKeyKind::Ed25519(_)
@@ -191,6 +196,16 @@ impl Clone for CryptoPrivateKey {
                 ),
                 serialized_der: self.serialized_der.clone(),
             },
+            CryptoPrivateKeyKind::Ecdsa384(_) => CryptoPrivateKey {
+                kind: CryptoPrivateKeyKind::Ecdsa384(
+                    EcdsaKeyPair::from_pkcs8(
+                        &ring::signature::ECDSA_P384_SHA384_ASN1_SIGNING,
+                        &self.serialized_der,
+                    )
+                    .unwrap(),
+                ),
+                serialized_der: self.serialized_der.clone(),
+            },
             CryptoPrivateKeyKind::Rsa256(_) => CryptoPrivateKey {
                 kind: CryptoPrivateKeyKind::Rsa256(
                     RsaKeyPair::from_pkcs8(&self.serialized_der).unwrap(),
@@ -225,36 +240,11 @@ impl TryFrom<&KeyPair> for CryptoPrivateKey {
                 ),
                 serialized_der,
             })
-        } else if key_pair.is_compatible(&rcgen::PKCS_RSA_SHA256) {
+        } else if key_pair.is_compatible(&rcgen::PKCS_ECDSA_P384_SHA384) {
             Ok(CryptoPrivateKey {
-                kind: CryptoPrivateKeyKind::Rsa256(
-                    RsaKeyPair::from_pkcs8(&serialized_der)
-                        .map_err(|e| Error::Other(e.to_string()))?,
-                ),
-                serialized_der,
-            })
-        } else {
-            Err(Error::Other("Unsupported key_pair".to_owned()))
-        }
-    }
-}
-
-impl CryptoPrivateKey {
-    pub fn from_key_pair(key_pair: &KeyPair) -> Result<Self> {
-        let serialized_der = key_pair.serialize_der();
-        if key_pair.is_compatible(&rcgen::PKCS_ED25519) {
-            Ok(CryptoPrivateKey {
-                kind: CryptoPrivateKeyKind::Ed25519(
-                    Ed25519KeyPair::from_pkcs8(&serialized_der)
-                        .map_err(|e| Error::Other(e.to_string()))?,
-                ),
-                serialized_der,
-            })
-        } else if key_pair.is_compatible(&rcgen::PKCS_ECDSA_P256_SHA256) {
-            Ok(CryptoPrivateKey {
-                kind: CryptoPrivateKeyKind::Ecdsa256(
+                kind: CryptoPrivateKeyKind::Ecdsa384(
                     EcdsaKeyPair::from_pkcs8(
-                        &ring::signature::ECDSA_P256_SHA256_ASN1_SIGNING,
+                        &ring::signature::ECDSA_P384_SHA384_ASN1_SIGNING,
                         &serialized_der,
                     )
                     .map_err(|e| Error::Other(e.to_string()))?,
@@ -275,6 +265,21 @@ impl CryptoPrivateKey {
     }
 }
 
+impl CryptoPrivateKey {
+    pub fn from_key_pair(key_pair: &KeyPair) -> Result<Self> {
+        Self::try_from(key_pair)
+    }
+
+    /// certificate_type returns the type of the certificates signed by this key, which the
+    /// cipher suite of a server must match.
+    pub(crate) fn certificate_type(&self) -> ClientCertificateType {
+        match self.kind {
+            CryptoPrivateKeyKind::Rsa256(_) => ClientCertificateType::RsaSign,
+            _ => ClientCertificateType::EcdsaSign,
+        }
+    }
+}
+
 // If the client provided a "signature_algorithms" extension, then all
 // certificates provided by the server MUST be signed by a
 // hash/signature algorithm pair that appears in that extension
@@ -290,7 +295,7 @@ pub(crate) fn generate_key_signature(
     let msg = value_key_message(client_random, server_random, public_key, named_curve);
     let signature = match &private_key.kind {
         CryptoPrivateKeyKind::Ed25519(kp) => kp.sign(&msg).as_ref().to_vec(),
-        CryptoPrivateKeyKind::Ecdsa256(kp) => {
+        CryptoPrivateKeyKind::Ecdsa256(kp) | CryptoPrivateKeyKind::Ecdsa384(kp) => {
             let system_random = SystemRandom::new();
             kp.sign(&system_random, &msg)
                 .map_err(|e| Error::Other(e.to_string()))?
@@ -412,7 +417,7 @@ pub(crate) fn generate_certificate_verify(
 ) -> Result<Vec<u8>> {
     let signature = match &private_key.kind {
         CryptoPrivateKeyKind::Ed25519(kp) => kp.sign(handshake_bodies).as_ref().to_vec(),
-        CryptoPrivateKeyKind::Ecdsa256(kp) => {
+        CryptoPrivateKeyKind::Ecdsa256(kp) | CryptoPrivateKeyKind::Ecdsa384(kp) => {
             let system_random = SystemRandom::new();
             kp.sign(&system_random, handshake_bodies)
                 .map_err(|e| Error::Other(e.to_string()))?
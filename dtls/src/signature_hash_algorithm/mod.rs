@@ -98,8 +98,15 @@ impl SignatureHashAlgorithm {
     pub(crate) fn is_compatible(&self, private_key: &CryptoPrivateKey) -> bool {
         match &private_key.kind {
             CryptoPrivateKeyKind::Ed25519(_) => self.signature == SignatureAlgorithm::Ed25519,
-            CryptoPrivateKeyKind::Ecdsa256(_) => self.signature == SignatureAlgorithm::Ecdsa,
-            CryptoPrivateKeyKind::Rsa256(_) => self.signature == SignatureAlgorithm::Rsa,
+            CryptoPrivateKeyKind::Ecdsa256(_) => {
+                self.signature == SignatureAlgorithm::Ecdsa && self.hash == HashAlgorithm::Sha256
+            }
+            CryptoPrivateKeyKind::Ecdsa384(_) => {
+                self.signature == SignatureAlgorithm::Ecdsa && self.hash == HashAlgorithm::Sha384
+            }
+            CryptoPrivateKeyKind::Rsa256(_) => {
+                self.signature == SignatureAlgorithm::Rsa && self.hash == HashAlgorithm::Sha256
+            }
         }
     }
 }
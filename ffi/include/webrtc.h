@@ -0,0 +1,126 @@
+/*
+ * C bindings of the WebRTC.rs stack.
+ *
+ * The objects are opaque handles which are freed with their *_free function. The calls
+ * block until they are done, and the callbacks are invoked on the threads of the library
+ * with the user_data they were registered with; they may call the library again. A call
+ * which fails or panics returns -1 or NULL, and webrtc_last_error describes the error.
+ *
+ * The session descriptions and the ICE candidates are exchanged as the JSON of the
+ * RTCSessionDescription and the RTCIceCandidateInit of the browsers.
+ */
+
+#ifndef WEBRTC_H
+#define WEBRTC_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct WebrtcPeerConnection WebrtcPeerConnection;
+typedef struct WebrtcDataChannel WebrtcDataChannel;
+typedef struct WebrtcTrackLocal WebrtcTrackLocal;
+typedef struct WebrtcTrackRemote WebrtcTrackRemote;
+
+/* RTCPeerConnectionState */
+enum {
+    WEBRTC_PEER_CONNECTION_STATE_UNSPECIFIED = 0,
+    WEBRTC_PEER_CONNECTION_STATE_NEW = 1,
+    WEBRTC_PEER_CONNECTION_STATE_CONNECTING = 2,
+    WEBRTC_PEER_CONNECTION_STATE_CONNECTED = 3,
+    WEBRTC_PEER_CONNECTION_STATE_DISCONNECTED = 4,
+    WEBRTC_PEER_CONNECTION_STATE_FAILED = 5,
+    WEBRTC_PEER_CONNECTION_STATE_CLOSED = 6,
+};
+
+/* RTCDataChannelState */
+enum {
+    WEBRTC_DATA_CHANNEL_STATE_UNSPECIFIED = 0,
+    WEBRTC_DATA_CHANNEL_STATE_CONNECTING = 1,
+    WEBRTC_DATA_CHANNEL_STATE_OPEN = 2,
+    WEBRTC_DATA_CHANNEL_STATE_CLOSING = 3,
+    WEBRTC_DATA_CHANNEL_STATE_CLOSED = 4,
+};
+
+typedef void (*WebrtcCallback)(void *user_data);
+typedef void (*WebrtcStateCallback)(void *user_data, int state);
+/* candidate is NULL once the gathering is finished, it is only valid during the call */
+typedef void (*WebrtcIceCandidateCallback)(void *user_data, const char *candidate);
+/* the callee frees the data channel with webrtc_data_channel_free */
+typedef void (*WebrtcDataChannelCallback)(void *user_data, WebrtcDataChannel *data_channel);
+/* the callee frees the track with webrtc_track_remote_free */
+typedef void (*WebrtcTrackCallback)(void *user_data, WebrtcTrackRemote *track);
+/* data is only valid during the call */
+typedef void (*WebrtcMessageCallback)(void *user_data, int is_string, const uint8_t *data,
+                                      size_t len);
+
+/* The error of the last call of the thread which failed or panicked, or NULL. */
+const char *webrtc_last_error(void);
+/* Frees a string returned by the library. */
+void webrtc_string_free(char *s);
+
+/* PeerConnection, ice_server_url may be NULL, e.g. "stun:stun.l.google.com:19302" */
+WebrtcPeerConnection *webrtc_peer_connection_new(const char *ice_server_url);
+void webrtc_peer_connection_free(WebrtcPeerConnection *pc);
+char *webrtc_peer_connection_create_offer(const WebrtcPeerConnection *pc);
+char *webrtc_peer_connection_create_answer(const WebrtcPeerConnection *pc);
+int webrtc_peer_connection_set_local_description(const WebrtcPeerConnection *pc,
+                                                 const char *desc);
+int webrtc_peer_connection_set_remote_description(const WebrtcPeerConnection *pc,
+                                                  const char *desc);
+/* NULL without an error if there is no local description */
+char *webrtc_peer_connection_local_description(const WebrtcPeerConnection *pc);
+int webrtc_peer_connection_gather_complete(const WebrtcPeerConnection *pc);
+int webrtc_peer_connection_add_ice_candidate(const WebrtcPeerConnection *pc,
+                                             const char *candidate);
+int webrtc_peer_connection_connection_state(const WebrtcPeerConnection *pc);
+int webrtc_peer_connection_on_connection_state_change(const WebrtcPeerConnection *pc,
+                                                      WebrtcStateCallback callback,
+                                                      void *user_data);
+int webrtc_peer_connection_on_ice_candidate(const WebrtcPeerConnection *pc,
+                                            WebrtcIceCandidateCallback callback,
+                                            void *user_data);
+int webrtc_peer_connection_on_data_channel(const WebrtcPeerConnection *pc,
+                                           WebrtcDataChannelCallback callback,
+                                           void *user_data);
+int webrtc_peer_connection_on_track(const WebrtcPeerConnection *pc,
+                                    WebrtcTrackCallback callback, void *user_data);
+WebrtcDataChannel *webrtc_peer_connection_create_data_channel(const WebrtcPeerConnection *pc,
+                                                              const char *label);
+int webrtc_peer_connection_add_track(const WebrtcPeerConnection *pc,
+                                     const WebrtcTrackLocal *track);
+int webrtc_peer_connection_close(const WebrtcPeerConnection *pc);
+
+/* DataChannel */
+void webrtc_data_channel_free(WebrtcDataChannel *dc);
+char *webrtc_data_channel_label(const WebrtcDataChannel *dc);
+int webrtc_data_channel_ready_state(const WebrtcDataChannel *dc);
+int webrtc_data_channel_send(const WebrtcDataChannel *dc, const uint8_t *data, size_t len);
+int webrtc_data_channel_send_text(const WebrtcDataChannel *dc, const char *text);
+int webrtc_data_channel_on_open(const WebrtcDataChannel *dc, WebrtcCallback callback,
+                                void *user_data);
+int webrtc_data_channel_on_message(const WebrtcDataChannel *dc, WebrtcMessageCallback callback,
+                                   void *user_data);
+int webrtc_data_channel_on_close(const WebrtcDataChannel *dc, WebrtcCallback callback,
+                                 void *user_data);
+int webrtc_data_channel_close(const WebrtcDataChannel *dc);
+
+/* Tracks, mime_type is e.g. "video/VP8" */
+WebrtcTrackLocal *webrtc_track_local_new(const char *mime_type, const char *id,
+                                         const char *stream_id);
+void webrtc_track_local_free(WebrtcTrackLocal *track);
+int webrtc_track_local_write_rtp(const WebrtcTrackLocal *track, const uint8_t *data, size_t len);
+void webrtc_track_remote_free(WebrtcTrackRemote *track);
+uint32_t webrtc_track_remote_ssrc(const WebrtcTrackRemote *track);
+char *webrtc_track_remote_mime_type(const WebrtcTrackRemote *track);
+/* returns the length of the packet copied into buf, or -1 */
+int webrtc_track_remote_read_rtp(const WebrtcTrackRemote *track, uint8_t *buf, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* WEBRTC_H */
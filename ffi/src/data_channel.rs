@@ -0,0 +1,185 @@
+use crate::*;
+
+use bytes::Bytes;
+use std::sync::Arc;
+use webrtc::blocking::block_on;
+use webrtc::data_channel::RTCDataChannel;
+
+/// WebrtcCallback is called with the user_data only.
+pub type WebrtcCallback = Option<extern "C" fn(user_data: *mut c_void)>;
+
+/// WebrtcMessageCallback is called with each message received on a data channel, is_string
+/// tells whether it is a text message. The data is only valid during the call.
+pub type WebrtcMessageCallback =
+    Option<extern "C" fn(user_data: *mut c_void, is_string: c_int, data: *const u8, len: usize)>;
+
+/// WebrtcDataChannel is the handle of a RTCDataChannel.
+pub struct WebrtcDataChannel {
+    pub(crate) dc: Arc<RTCDataChannel>,
+}
+
+/// webrtc_data_channel_free frees the handle of a data channel, without closing it.
+///
+/// # Safety
+///
+/// dc is NULL or a handle returned by the library which is not freed yet, and isn't used by
+/// another thread.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_data_channel_free(dc: *mut WebrtcDataChannel) {
+    ffi_code(|| {
+        if !dc.is_null() {
+            drop(Box::from_raw(dc));
+        }
+        Ok(())
+    });
+}
+
+/// webrtc_data_channel_label returns the label of the data channel, which the caller frees
+/// with webrtc_string_free.
+///
+/// # Safety
+///
+/// dc is NULL or a valid handle of a data channel.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_data_channel_label(dc: *const WebrtcDataChannel) -> *mut c_char {
+    ffi_ptr(|| Ok(into_c_string(ffi_ref(dc)?.dc.label().to_owned())))
+}
+
+/// webrtc_data_channel_ready_state returns the RTCDataChannelState, or -1.
+///
+/// # Safety
+///
+/// dc is NULL or a valid handle of a data channel.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_data_channel_ready_state(dc: *const WebrtcDataChannel) -> c_int {
+    ffi_call(-1, || Ok(ffi_ref(dc)?.dc.ready_state() as c_int))
+}
+
+/// webrtc_data_channel_send sends a binary message.
+///
+/// # Safety
+///
+/// dc is NULL or a valid handle of a data channel, and data is NULL with len 0 or points to
+/// len readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_data_channel_send(
+    dc: *const WebrtcDataChannel,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    ffi_code(|| {
+        let dc = ffi_ref(dc)?;
+        let data = Bytes::copy_from_slice(ffi_bytes(data, len)?);
+        block_on(dc.dc.send(&data)).map(|_| ())
+    })
+}
+
+/// webrtc_data_channel_send_text sends a text message.
+///
+/// # Safety
+///
+/// dc is NULL or a valid handle of a data channel, and text is NULL or a valid
+/// NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_data_channel_send_text(
+    dc: *const WebrtcDataChannel,
+    text: *const c_char,
+) -> c_int {
+    ffi_code(|| {
+        let dc = ffi_ref(dc)?;
+        let text = ffi_str(text)?.to_owned();
+        block_on(dc.dc.send_text(text)).map(|_| ())
+    })
+}
+
+/// webrtc_data_channel_on_open sets the callback invoked once the data channel is open, at
+/// once if it is already open.
+///
+/// # Safety
+///
+/// dc is NULL or a valid handle of a data channel. user_data may be used by callback from
+/// the threads of the runtime until the callback is invoked.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_data_channel_on_open(
+    dc: *const WebrtcDataChannel,
+    callback: WebrtcCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    let user_data = UserData(user_data);
+    ffi_code(|| {
+        let dc = ffi_ref(dc)?;
+        // on_open spawns the callback when the data channel is already open
+        block_on(async {
+            dc.dc.on_open(Box::new(move || {
+                if let Some(callback) = callback {
+                    callback(user_data.0);
+                }
+                Box::pin(async {})
+            }))
+        });
+        Ok(())
+    })
+}
+
+/// webrtc_data_channel_on_message sets the callback of the received messages.
+///
+/// # Safety
+///
+/// dc is NULL or a valid handle of a data channel. user_data may be used by callback from
+/// the threads of the runtime until the callback is replaced or the data channel is closed.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_data_channel_on_message(
+    dc: *const WebrtcDataChannel,
+    callback: WebrtcMessageCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    let user_data = UserData(user_data);
+    ffi_code(|| {
+        ffi_ref(dc)?.dc.on_message(Box::new(move |msg| {
+            if let Some(callback) = callback {
+                callback(
+                    user_data.0,
+                    msg.is_string as c_int,
+                    msg.data.as_ptr(),
+                    msg.data.len(),
+                );
+            }
+            Box::pin(async {})
+        }));
+        Ok(())
+    })
+}
+
+/// webrtc_data_channel_on_close sets the callback invoked once the data channel is closed.
+///
+/// # Safety
+///
+/// dc is NULL or a valid handle of a data channel. user_data may be used by callback from
+/// the threads of the runtime until the callback is replaced or invoked.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_data_channel_on_close(
+    dc: *const WebrtcDataChannel,
+    callback: WebrtcCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    let user_data = UserData(user_data);
+    ffi_code(|| {
+        ffi_ref(dc)?.dc.on_close(Box::new(move || {
+            if let Some(callback) = callback {
+                callback(user_data.0);
+            }
+            Box::pin(async {})
+        }));
+        Ok(())
+    })
+}
+
+/// webrtc_data_channel_close closes the data channel, its handle is still to be freed.
+///
+/// # Safety
+///
+/// dc is NULL or a valid handle of a data channel.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_data_channel_close(dc: *const WebrtcDataChannel) -> c_int {
+    ffi_code(|| block_on(ffi_ref(dc)?.dc.close()))
+}
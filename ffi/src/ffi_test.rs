@@ -0,0 +1,148 @@
+use crate::data_channel::*;
+use crate::peer_connection::*;
+use crate::*;
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+struct Receiving {
+    dc_tx: mpsc::SyncSender<usize>,
+    msg_tx: mpsc::SyncSender<(bool, Vec<u8>)>,
+}
+
+extern "C" fn on_data_channel(user_data: *mut c_void, dc: *mut WebrtcDataChannel) {
+    let receiving = unsafe { &*(user_data as *const Receiving) };
+    let msg_tx = &receiving.msg_tx as *const _ as *mut c_void;
+    assert_eq!(0, unsafe {
+        webrtc_data_channel_on_message(dc, Some(on_message), msg_tx)
+    });
+    let _ = receiving.dc_tx.send(dc as usize);
+}
+
+extern "C" fn on_open(user_data: *mut c_void) {
+    let dc = user_data as *const WebrtcDataChannel;
+    let text = CString::new("ping").unwrap();
+    assert_eq!(0, unsafe {
+        webrtc_data_channel_send_text(dc, text.as_ptr())
+    });
+}
+
+extern "C" fn on_message(user_data: *mut c_void, is_string: c_int, data: *const u8, len: usize) {
+    let tx = unsafe { &*(user_data as *const mpsc::SyncSender<(bool, Vec<u8>)>) };
+    let data = unsafe { std::slice::from_raw_parts(data, len) };
+    let _ = tx.send((is_string != 0, data.to_vec()));
+}
+
+unsafe fn signal_pair(
+    offer_pc: *const WebrtcPeerConnection,
+    answer_pc: *const WebrtcPeerConnection,
+) {
+    let offer = webrtc_peer_connection_create_offer(offer_pc);
+    assert!(!offer.is_null());
+    assert_eq!(
+        0,
+        webrtc_peer_connection_set_local_description(offer_pc, offer)
+    );
+    webrtc_string_free(offer);
+    assert_eq!(0, webrtc_peer_connection_gather_complete(offer_pc));
+    let offer = webrtc_peer_connection_local_description(offer_pc);
+    assert_eq!(
+        0,
+        webrtc_peer_connection_set_remote_description(answer_pc, offer)
+    );
+    webrtc_string_free(offer);
+
+    let answer = webrtc_peer_connection_create_answer(answer_pc);
+    assert!(!answer.is_null());
+    assert_eq!(
+        0,
+        webrtc_peer_connection_set_local_description(answer_pc, answer)
+    );
+    webrtc_string_free(answer);
+    assert_eq!(0, webrtc_peer_connection_gather_complete(answer_pc));
+    let answer = webrtc_peer_connection_local_description(answer_pc);
+    assert_eq!(
+        0,
+        webrtc_peer_connection_set_remote_description(offer_pc, answer)
+    );
+    webrtc_string_free(answer);
+}
+
+#[test]
+fn test_ffi_data_channel() {
+    unsafe {
+        let offer_pc = webrtc_peer_connection_new(std::ptr::null());
+        let answer_pc = webrtc_peer_connection_new(std::ptr::null());
+        assert!(!offer_pc.is_null() && !answer_pc.is_null());
+
+        let (dc_tx, dc_rx) = mpsc::sync_channel(1);
+        let (msg_tx, msg_rx) = mpsc::sync_channel(1);
+        let receiving = Box::into_raw(Box::new(Receiving { dc_tx, msg_tx }));
+        assert_eq!(
+            0,
+            webrtc_peer_connection_on_data_channel(
+                answer_pc,
+                Some(on_data_channel),
+                receiving as *mut c_void
+            )
+        );
+
+        let label = CString::new("ffi").unwrap();
+        let offer_dc = webrtc_peer_connection_create_data_channel(offer_pc, label.as_ptr());
+        assert!(!offer_dc.is_null());
+        assert_eq!(
+            0,
+            webrtc_data_channel_on_open(offer_dc, Some(on_open), offer_dc as *mut c_void)
+        );
+
+        signal_pair(offer_pc, answer_pc);
+
+        let answer_dc =
+            dc_rx.recv_timeout(Duration::from_secs(10)).unwrap() as *mut WebrtcDataChannel;
+        let label = webrtc_data_channel_label(answer_dc);
+        assert_eq!(CStr::from_ptr(label).to_str().unwrap(), "ffi");
+        webrtc_string_free(label);
+
+        let (is_string, data) = msg_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(is_string);
+        assert_eq!(data, b"ping");
+
+        webrtc_data_channel_free(offer_dc);
+        webrtc_data_channel_free(answer_dc);
+        webrtc_peer_connection_free(offer_pc);
+        webrtc_peer_connection_free(answer_pc);
+        drop(Box::from_raw(receiving));
+    }
+}
+
+#[test]
+fn test_ffi_last_error() {
+    unsafe {
+        assert_eq!(
+            -1,
+            webrtc_peer_connection_set_remote_description(std::ptr::null(), std::ptr::null())
+        );
+        let err = CStr::from_ptr(webrtc_last_error()).to_str().unwrap();
+        assert!(err.contains("null pointer"), "{}", err);
+
+        let pc = webrtc_peer_connection_new(std::ptr::null());
+        let desc = CString::new("not json").unwrap();
+        assert_eq!(
+            -1,
+            webrtc_peer_connection_set_remote_description(pc, desc.as_ptr())
+        );
+        assert!(!webrtc_last_error().is_null());
+        webrtc_peer_connection_free(pc);
+    }
+}
+
+#[test]
+fn test_ffi_panic() {
+    assert_eq!(-1, ffi_code(|| panic!("ffi panic")));
+    let err = unsafe { CStr::from_ptr(webrtc_last_error()) }
+        .to_str()
+        .unwrap();
+    assert!(err.contains("ffi panic"), "{}", err);
+
+    assert!(ffi_ptr::<c_void, _>(|| panic!("ffi panic")).is_null());
+}
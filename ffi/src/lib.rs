@@ -0,0 +1,146 @@
+#![warn(rust_2018_idioms)]
+
+//! C bindings of the WebRTC.rs stack, declared in include/webrtc.h.
+//!
+//! The objects are opaque handles which are freed with their *_free function. The calls block
+//! on the runtime of webrtc::blocking, and the callbacks are invoked on its threads with the
+//! user_data they were registered with. A call which fails or panics returns -1 or NULL, and
+//! webrtc_last_error describes the error.
+
+#[cfg(test)]
+mod ffi_test;
+
+pub mod data_channel;
+pub mod peer_connection;
+pub mod track;
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use webrtc::error::{Error, Result};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(err: &Error) {
+    let msg = CString::new(err.to_string().replace('\0', ""))
+        .unwrap_or_else(|_| CString::new("unknown error").unwrap());
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(msg));
+}
+
+fn panic_error(panic: Box<dyn Any + Send>) -> Error {
+    let msg = if let Some(msg) = panic.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = panic.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_owned()
+    };
+    Error::new(format!("panic: {}", msg))
+}
+
+/// ffi_call runs f without letting a panic unwind into the caller, it returns the value of f,
+/// or records the error or the panic and returns on_error.
+pub(crate) fn ffi_call<T, F>(on_error: T, f: F) -> T
+where
+    F: FnOnce() -> Result<T>,
+{
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|p| Err(panic_error(p)));
+    match result {
+        Ok(v) => v,
+        Err(err) => {
+            set_last_error(&err);
+            on_error
+        }
+    }
+}
+
+/// ffi_code returns 0 on success, or records the error and returns -1.
+pub(crate) fn ffi_code<F>(f: F) -> c_int
+where
+    F: FnOnce() -> Result<()>,
+{
+    ffi_call(-1, || f().map(|()| 0))
+}
+
+/// ffi_ptr returns the value on success, or records the error and returns NULL.
+pub(crate) fn ffi_ptr<T, F>(f: F) -> *mut T
+where
+    F: FnOnce() -> Result<*mut T>,
+{
+    ffi_call(ptr::null_mut(), f)
+}
+
+/// ffi_ref returns a reference to the object of a handle, or an error if it is NULL.
+pub(crate) unsafe fn ffi_ref<'a, T>(p: *const T) -> Result<&'a T> {
+    p.as_ref()
+        .ok_or_else(|| Error::new("null pointer argument".to_owned()))
+}
+
+/// ffi_str returns the string of a C string, or an error if it is NULL or not UTF-8.
+pub(crate) unsafe fn ffi_str<'a>(s: *const c_char) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(Error::new("null string argument".to_owned()));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|err| Error::new(err.to_string()))
+}
+
+/// ffi_bytes returns the bytes of a C buffer, or an error if it is NULL.
+pub(crate) unsafe fn ffi_bytes<'a>(data: *const u8, len: usize) -> Result<&'a [u8]> {
+    if data.is_null() && len != 0 {
+        return Err(Error::new("null buffer argument".to_owned()));
+    }
+    if len == 0 {
+        return Ok(&[]);
+    }
+    Ok(std::slice::from_raw_parts(data, len))
+}
+
+/// into_c_string transfers a string to the caller, who frees it with webrtc_string_free.
+pub(crate) fn into_c_string(s: String) -> *mut c_char {
+    CString::new(s.replace('\0', ""))
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
+
+/// UserData is the opaque pointer given back to a callback. The caller guarantees it may
+/// be used from the threads of the runtime.
+#[derive(Clone, Copy)]
+pub(crate) struct UserData(pub(crate) *mut c_void);
+
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+/// webrtc_last_error returns the error of the last call of the thread which failed or panicked,
+/// or NULL.
+/// The string is valid until the next call which fails on the thread.
+#[no_mangle]
+pub extern "C" fn webrtc_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |msg| msg.as_ptr())
+    })
+}
+
+/// webrtc_string_free frees a string returned by the library.
+///
+/// # Safety
+///
+/// s is NULL or a string returned by the library which is not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_string_free(s: *mut c_char) {
+    ffi_code(|| {
+        if !s.is_null() {
+            drop(CString::from_raw(s));
+        }
+        Ok(())
+    });
+}
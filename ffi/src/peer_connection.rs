@@ -0,0 +1,407 @@
+use crate::data_channel::WebrtcDataChannel;
+use crate::track::{WebrtcTrackLocal, WebrtcTrackRemote};
+use crate::*;
+
+use std::sync::Arc;
+use webrtc::api::interceptor_registry::register_default_interceptors;
+use webrtc::api::media_engine::MediaEngine;
+use webrtc::api::APIBuilder;
+use webrtc::blocking::block_on;
+use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::interceptor::registry::Registry;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+use webrtc::track::track_local::TrackLocal;
+
+/// WebrtcStateCallback is called with the new RTCPeerConnectionState, as numbered in
+/// include/webrtc.h.
+pub type WebrtcStateCallback = Option<extern "C" fn(user_data: *mut c_void, state: c_int)>;
+
+/// WebrtcIceCandidateCallback is called with the JSON of each local candidate, the
+/// RTCIceCandidateInit, and with NULL once the gathering is finished. The string is only
+/// valid during the call.
+pub type WebrtcIceCandidateCallback =
+    Option<extern "C" fn(user_data: *mut c_void, candidate: *const c_char)>;
+
+/// WebrtcDataChannelCallback is called with each data channel opened by the remote peer,
+/// which the callee frees with webrtc_data_channel_free.
+pub type WebrtcDataChannelCallback =
+    Option<extern "C" fn(user_data: *mut c_void, data_channel: *mut WebrtcDataChannel)>;
+
+/// WebrtcTrackCallback is called with each remote track, which the callee frees with
+/// webrtc_track_remote_free.
+pub type WebrtcTrackCallback =
+    Option<extern "C" fn(user_data: *mut c_void, track: *mut WebrtcTrackRemote)>;
+
+/// WebrtcPeerConnection is the handle of a RTCPeerConnection.
+pub struct WebrtcPeerConnection {
+    pub(crate) pc: Arc<RTCPeerConnection>,
+}
+
+unsafe fn new_peer_connection(ice_server_url: *const c_char) -> Result<*mut WebrtcPeerConnection> {
+    let mut configuration = RTCConfiguration::default();
+    if !ice_server_url.is_null() {
+        let url = ffi_str(ice_server_url)?;
+        configuration.ice_servers = vec![RTCIceServer {
+            urls: vec![url.to_owned()],
+            ..Default::default()
+        }];
+    }
+
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let registry = register_default_interceptors(Registry::new(), &mut m)?;
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_interceptor_registry(registry)
+        .build();
+
+    let pc = block_on(api.new_peer_connection(configuration))?;
+    Ok(Box::into_raw(Box::new(WebrtcPeerConnection {
+        pc: Arc::new(pc),
+    })))
+}
+
+fn description_to_json(desc: &RTCSessionDescription) -> Result<*mut c_char> {
+    let json = serde_json::to_string(desc).map_err(|err| Error::new(err.to_string()))?;
+    Ok(into_c_string(json))
+}
+
+unsafe fn description_from_json(json: *const c_char) -> Result<RTCSessionDescription> {
+    let json = ffi_str(json)?;
+    serde_json::from_str(json).map_err(|err| Error::new(err.to_string()))
+}
+
+/// webrtc_peer_connection_new creates a RTCPeerConnection with the default codecs and
+/// interceptors, and the ICE server of ice_server_url if it isn't NULL, e.g.
+/// "stun:stun.l.google.com:19302".
+///
+/// # Safety
+///
+/// ice_server_url is NULL or a valid NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_new(
+    ice_server_url: *const c_char,
+) -> *mut WebrtcPeerConnection {
+    ffi_ptr(|| new_peer_connection(ice_server_url))
+}
+
+/// webrtc_peer_connection_free closes the RTCPeerConnection and frees its handle.
+///
+/// # Safety
+///
+/// pc is NULL or a handle returned by webrtc_peer_connection_new which is not freed yet,
+/// and isn't used by another thread.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_free(pc: *mut WebrtcPeerConnection) {
+    ffi_code(|| {
+        if !pc.is_null() {
+            let pc = Box::from_raw(pc);
+            let _ = block_on(pc.pc.close());
+        }
+        Ok(())
+    });
+}
+
+/// webrtc_peer_connection_create_offer returns the JSON of an offer, the
+/// RTCSessionDescription, which the caller frees with webrtc_string_free.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_create_offer(
+    pc: *const WebrtcPeerConnection,
+) -> *mut c_char {
+    ffi_ptr(|| {
+        let pc = ffi_ref(pc)?;
+        description_to_json(&block_on(pc.pc.create_offer(None))?)
+    })
+}
+
+/// webrtc_peer_connection_create_answer returns the JSON of an answer, the
+/// RTCSessionDescription, which the caller frees with webrtc_string_free.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_create_answer(
+    pc: *const WebrtcPeerConnection,
+) -> *mut c_char {
+    ffi_ptr(|| {
+        let pc = ffi_ref(pc)?;
+        description_to_json(&block_on(pc.pc.create_answer(None))?)
+    })
+}
+
+/// webrtc_peer_connection_set_local_description sets the JSON of a RTCSessionDescription as
+/// the local description.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection, and desc is NULL or a valid
+/// NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_set_local_description(
+    pc: *const WebrtcPeerConnection,
+    desc: *const c_char,
+) -> c_int {
+    ffi_code(|| {
+        let pc = ffi_ref(pc)?;
+        let desc = description_from_json(desc)?;
+        block_on(pc.pc.set_local_description(desc))
+    })
+}
+
+/// webrtc_peer_connection_set_remote_description sets the JSON of a RTCSessionDescription as
+/// the remote description.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection, and desc is NULL or a valid
+/// NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_set_remote_description(
+    pc: *const WebrtcPeerConnection,
+    desc: *const c_char,
+) -> c_int {
+    ffi_code(|| {
+        let pc = ffi_ref(pc)?;
+        let desc = description_from_json(desc)?;
+        block_on(pc.pc.set_remote_description(desc))
+    })
+}
+
+/// webrtc_peer_connection_local_description returns the JSON of the local description with
+/// the candidates gathered so far, or NULL if there is none. The caller frees it with
+/// webrtc_string_free.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_local_description(
+    pc: *const WebrtcPeerConnection,
+) -> *mut c_char {
+    ffi_ptr(|| {
+        let pc = ffi_ref(pc)?;
+        match block_on(pc.pc.local_description()) {
+            Some(desc) => description_to_json(&desc),
+            None => Ok(std::ptr::null_mut()),
+        }
+    })
+}
+
+/// webrtc_peer_connection_gather_complete blocks until the ICE candidate gathering is
+/// complete, so that the local description contains all the candidates.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_gather_complete(
+    pc: *const WebrtcPeerConnection,
+) -> c_int {
+    ffi_code(|| {
+        let pc = ffi_ref(pc)?;
+        block_on(async {
+            let mut gathering_complete = pc.pc.gathering_complete_promise().await;
+            let _ = gathering_complete.recv().await;
+        });
+        Ok(())
+    })
+}
+
+/// webrtc_peer_connection_add_ice_candidate adds the JSON of a RTCIceCandidateInit of the
+/// remote peer.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection, and candidate is NULL or a valid
+/// NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_add_ice_candidate(
+    pc: *const WebrtcPeerConnection,
+    candidate: *const c_char,
+) -> c_int {
+    ffi_code(|| {
+        let pc = ffi_ref(pc)?;
+        let candidate = ffi_str(candidate)?;
+        let candidate: RTCIceCandidateInit =
+            serde_json::from_str(candidate).map_err(|err| Error::new(err.to_string()))?;
+        block_on(pc.pc.add_ice_candidate(candidate))
+    })
+}
+
+/// webrtc_peer_connection_connection_state returns the RTCPeerConnectionState, or -1.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_connection_state(
+    pc: *const WebrtcPeerConnection,
+) -> c_int {
+    ffi_call(-1, || Ok(ffi_ref(pc)?.pc.connection_state() as c_int))
+}
+
+/// webrtc_peer_connection_on_connection_state_change sets the callback of the
+/// RTCPeerConnectionState changes.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection. user_data may be used by callback
+/// from the threads of the runtime until the callback is replaced or pc is freed.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_on_connection_state_change(
+    pc: *const WebrtcPeerConnection,
+    callback: WebrtcStateCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    let user_data = UserData(user_data);
+    ffi_code(|| {
+        ffi_ref(pc)?
+            .pc
+            .on_peer_connection_state_change(Box::new(move |state| {
+                if let Some(callback) = callback {
+                    callback(user_data.0, state as c_int);
+                }
+                Box::pin(async {})
+            }));
+        Ok(())
+    })
+}
+
+/// webrtc_peer_connection_on_ice_candidate sets the callback of the local candidates.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection. user_data may be used by callback
+/// from the threads of the runtime until the callback is replaced or pc is freed.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_on_ice_candidate(
+    pc: *const WebrtcPeerConnection,
+    callback: WebrtcIceCandidateCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    let user_data = UserData(user_data);
+    ffi_code(|| {
+        ffi_ref(pc)?.pc.on_ice_candidate(Box::new(move |candidate| {
+            if let Some(callback) = callback {
+                let json = candidate
+                    .and_then(|c| c.to_json().ok())
+                    .and_then(|c| serde_json::to_string(&c).ok())
+                    .and_then(|c| CString::new(c).ok());
+                match json {
+                    Some(json) => callback(user_data.0, json.as_ptr()),
+                    None => callback(user_data.0, std::ptr::null()),
+                }
+            }
+            Box::pin(async {})
+        }));
+        Ok(())
+    })
+}
+
+/// webrtc_peer_connection_on_data_channel sets the callback of the data channels opened by
+/// the remote peer.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection. user_data may be used by callback
+/// from the threads of the runtime until the callback is replaced or pc is freed.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_on_data_channel(
+    pc: *const WebrtcPeerConnection,
+    callback: WebrtcDataChannelCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    let user_data = UserData(user_data);
+    ffi_code(|| {
+        ffi_ref(pc)?.pc.on_data_channel(Box::new(move |dc| {
+            if let Some(callback) = callback {
+                let dc = Box::into_raw(Box::new(WebrtcDataChannel { dc }));
+                callback(user_data.0, dc);
+            }
+            Box::pin(async {})
+        }));
+        Ok(())
+    })
+}
+
+/// webrtc_peer_connection_on_track sets the callback of the remote tracks.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection. user_data may be used by callback
+/// from the threads of the runtime until the callback is replaced or pc is freed.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_on_track(
+    pc: *const WebrtcPeerConnection,
+    callback: WebrtcTrackCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    let user_data = UserData(user_data);
+    ffi_code(|| {
+        ffi_ref(pc)?.pc.on_track(Box::new(move |track, _| {
+            if let (Some(callback), Some(track)) = (callback, track) {
+                let track = Box::into_raw(Box::new(WebrtcTrackRemote { track }));
+                callback(user_data.0, track);
+            }
+            Box::pin(async {})
+        }));
+        Ok(())
+    })
+}
+
+/// webrtc_peer_connection_create_data_channel creates a data channel with the label.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection, and label is NULL or a valid
+/// NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_create_data_channel(
+    pc: *const WebrtcPeerConnection,
+    label: *const c_char,
+) -> *mut WebrtcDataChannel {
+    ffi_ptr(|| {
+        let pc = ffi_ref(pc)?;
+        let label = ffi_str(label)?;
+        let dc = block_on(pc.pc.create_data_channel(label, None))?;
+        Ok(Box::into_raw(Box::new(WebrtcDataChannel { dc })))
+    })
+}
+
+/// webrtc_peer_connection_add_track adds a local track to the RTCPeerConnection.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection, and track is NULL or a valid handle
+/// of a local track.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_add_track(
+    pc: *const WebrtcPeerConnection,
+    track: *const WebrtcTrackLocal,
+) -> c_int {
+    ffi_code(|| {
+        let pc = ffi_ref(pc)?;
+        let track = ffi_ref(track)?;
+        let track = Arc::clone(&track.track) as Arc<dyn TrackLocal + Send + Sync>;
+        block_on(pc.pc.add_track(track)).map(|_| ())
+    })
+}
+
+/// webrtc_peer_connection_close closes the RTCPeerConnection, its handle is still to be
+/// freed.
+///
+/// # Safety
+///
+/// pc is NULL or a valid handle of a peer connection.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_peer_connection_close(pc: *const WebrtcPeerConnection) -> c_int {
+    ffi_code(|| block_on(ffi_ref(pc)?.pc.close()))
+}
@@ -0,0 +1,154 @@
+use crate::*;
+
+use std::sync::Arc;
+use webrtc::blocking::block_on;
+use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
+use webrtc::track::track_local::TrackLocalWriter;
+use webrtc::track::track_remote::TrackRemote;
+
+/// WebrtcTrackLocal is the handle of a local track which RTP packets are written to.
+pub struct WebrtcTrackLocal {
+    pub(crate) track: Arc<TrackLocalStaticRTP>,
+}
+
+/// WebrtcTrackRemote is the handle of a track received from the remote peer.
+pub struct WebrtcTrackRemote {
+    pub(crate) track: Arc<TrackRemote>,
+}
+
+unsafe fn new_track_local(
+    mime_type: *const c_char,
+    id: *const c_char,
+    stream_id: *const c_char,
+) -> Result<*mut WebrtcTrackLocal> {
+    let (mime_type, id, stream_id) = (ffi_str(mime_type)?, ffi_str(id)?, ffi_str(stream_id)?);
+    let track = TrackLocalStaticRTP::new(
+        RTCRtpCodecCapability {
+            mime_type: mime_type.to_owned(),
+            ..Default::default()
+        },
+        id.to_owned(),
+        stream_id.to_owned(),
+    );
+    Ok(Box::into_raw(Box::new(WebrtcTrackLocal {
+        track: Arc::new(track),
+    })))
+}
+
+/// webrtc_track_local_new creates a local track of the codec of mime_type, e.g. "video/VP8".
+///
+/// # Safety
+///
+/// mime_type, id and stream_id are NULL or valid NUL-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_track_local_new(
+    mime_type: *const c_char,
+    id: *const c_char,
+    stream_id: *const c_char,
+) -> *mut WebrtcTrackLocal {
+    ffi_ptr(|| new_track_local(mime_type, id, stream_id))
+}
+
+/// webrtc_track_local_free frees the handle of a local track.
+///
+/// # Safety
+///
+/// track is NULL or a handle returned by webrtc_track_local_new which is not freed yet, and
+/// isn't used by another thread.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_track_local_free(track: *mut WebrtcTrackLocal) {
+    ffi_code(|| {
+        if !track.is_null() {
+            drop(Box::from_raw(track));
+        }
+        Ok(())
+    });
+}
+
+/// webrtc_track_local_write_rtp writes a marshaled RTP packet to the peers the track is
+/// added to. The SSRC and the payload type are rewritten to the negotiated ones.
+///
+/// # Safety
+///
+/// track is NULL or a valid handle of a local track, and data is NULL with len 0 or points
+/// to len readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_track_local_write_rtp(
+    track: *const WebrtcTrackLocal,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    ffi_code(|| {
+        let track = ffi_ref(track)?;
+        let data = ffi_bytes(data, len)?;
+        block_on(track.track.write(data)).map(|_| ())
+    })
+}
+
+/// webrtc_track_remote_free frees the handle of a remote track.
+///
+/// # Safety
+///
+/// track is NULL or a handle returned by the library which is not freed yet, and isn't used
+/// by another thread.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_track_remote_free(track: *mut WebrtcTrackRemote) {
+    ffi_code(|| {
+        if !track.is_null() {
+            drop(Box::from_raw(track));
+        }
+        Ok(())
+    });
+}
+
+/// webrtc_track_remote_ssrc returns the SSRC of the remote track.
+///
+/// # Safety
+///
+/// track is NULL or a valid handle of a remote track.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_track_remote_ssrc(track: *const WebrtcTrackRemote) -> u32 {
+    ffi_call(0, || Ok(ffi_ref(track)?.track.ssrc()))
+}
+
+/// webrtc_track_remote_mime_type returns the mime type of the codec of the remote track,
+/// which the caller frees with webrtc_string_free.
+///
+/// # Safety
+///
+/// track is NULL or a valid handle of a remote track.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_track_remote_mime_type(
+    track: *const WebrtcTrackRemote,
+) -> *mut c_char {
+    ffi_ptr(|| {
+        let track = ffi_ref(track)?;
+        Ok(into_c_string(
+            block_on(track.track.codec()).capability.mime_type,
+        ))
+    })
+}
+
+/// webrtc_track_remote_read_rtp blocks until a RTP packet is received, copies it marshaled
+/// into buf and returns its length, or -1.
+///
+/// # Safety
+///
+/// track is NULL or a valid handle of a remote track, and buf is NULL or points to len
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn webrtc_track_remote_read_rtp(
+    track: *const WebrtcTrackRemote,
+    buf: *mut u8,
+    len: usize,
+) -> c_int {
+    ffi_call(-1, || {
+        let track = ffi_ref(track)?;
+        if buf.is_null() {
+            return Err(Error::new("null buffer argument".to_owned()));
+        }
+        let buf = std::slice::from_raw_parts_mut(buf, len);
+        block_on(track.track.read(buf)).map(|(n, _)| n as c_int)
+    })
+}
@@ -2,11 +2,15 @@ use super::*;
 use crate::error::*;
 use crate::mdns::*;
 use crate::network_type::*;
+use crate::resolver::Resolver;
+use crate::socket_factory::SocketFactory;
+use crate::tcp_mux::TCPMux;
 use crate::udp_network::UDPNetwork;
 use crate::url::*;
 
 use util::vnet::net::*;
 
+use std::collections::HashMap;
 use std::net::IpAddr;
 use std::time::Duration;
 
@@ -43,6 +47,18 @@ pub(crate) const MAX_BUFFER_SIZE: usize = 1000 * 1000; // 1MB
 /// Wait time before binding requests can be deleted.
 pub(crate) const MAX_BINDING_REQUEST_TIMEOUT: Duration = Duration::from_millis(4000);
 
+/// The default time to wait for a single STUN/TURN server while gathering candidates.
+pub(crate) const DEFAULT_GATHER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The default time to wait for a remote mDNS candidate to be resolved.
+pub(crate) const DEFAULT_MULTICAST_DNS_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The default maximum time between two re-gathering attempts after the connection failed.
+pub(crate) const DEFAULT_REGATHER_MAX_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The default minimum time a pair stays selected before the agent may switch away from it.
+pub(crate) const DEFAULT_PAIR_SWITCH_MIN_INTERVAL: Duration = Duration::from_secs(5);
+
 pub(crate) fn default_candidate_types() -> Vec<CandidateType> {
     vec![
         CandidateType::Host,
@@ -51,6 +67,33 @@ pub(crate) fn default_candidate_types() -> Vec<CandidateType> {
     ]
 }
 
+/// Determines how the controlling agent nominates candidate pairs.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum NominationMode {
+    /// Pairs are checked first and only the best valid pair is nominated with a separate
+    /// check, once its acceptance wait has passed (RFC 8445 S8.1.1).
+    Regular,
+
+    /// Every check carries USE-CANDIDATE, so the first pair that succeeds is selected without
+    /// waiting for the remaining checks. The agents then move to a higher priority pair if a
+    /// check on it succeeds later while connecting. This trades pair optimality for a shorter
+    /// time to the first selected pair (RFC 5245 S8.1.1.2).
+    Aggressive,
+}
+
+impl Default for NominationMode {
+    fn default() -> Self {
+        Self::Regular
+    }
+}
+
+/// The highest type preference a candidate type may be given, RFC 8445 S5.1.2.2.
+pub(crate) const MAX_TYPE_PREFERENCE: u16 = 126;
+
+pub type LocalPreferenceFn =
+    Box<dyn (Fn(&(dyn Candidate + Send + Sync)) -> Option<u16>) + Send + Sync>;
+pub type FoundationFn =
+    Box<dyn (Fn(&(dyn Candidate + Send + Sync)) -> Option<String>) + Send + Sync>;
 pub type InterfaceFilterFn = Box<dyn (Fn(&str) -> bool) + Send + Sync>;
 pub type IpFilterFn = Box<dyn (Fn(IpAddr) -> bool) + Send + Sync>;
 
@@ -64,6 +107,23 @@ pub struct AgentConfig {
     /// See [`UDPNetwork`]
     pub udp_network: UDPNetwork,
 
+    /// If set, passive ICE-TCP host candidates are gathered for the TCP network types, with all
+    /// inbound connections accepted through this mux. The listener of a
+    /// [`crate::tcp_mux::TCPMuxDefault`] may be created with
+    /// [`crate::socket_factory::SocketFactory::listen_tcp`] to apply socket options to it.
+    pub tcp_mux: Option<Arc<dyn TCPMux + Send + Sync>>,
+
+    /// If set, the UDP sockets the agent binds itself (ephemeral host, srflx and relay sockets)
+    /// are created through this factory, e.g. to set DSCP or bind to a device. It is not used for
+    /// sockets handed to the agent through a mux, nor on a virtual network.
+    /// See [`crate::socket_factory::SocketOptions`]
+    pub socket_factory: Option<Arc<dyn SocketFactory + Send + Sync>>,
+
+    /// If set, the hostnames of the STUN/TURN servers in `urls` are resolved through this
+    /// resolver instead of the system resolver, honoring SRV records when the resolver provides
+    /// them. See [`crate::resolver::StaticResolver`]
+    pub resolver: Option<Arc<dyn Resolver + Send + Sync>>,
+
     /// It is used to perform connectivity checks. The values MUST be unguessable, with at least
     /// 128 bits of random number generator output used to generate the password, and at least 24
     /// bits of output to generate the username fragment.
@@ -82,6 +142,16 @@ pub struct AgentConfig {
     /// Control mDNS destination address
     pub multicast_dns_dest_addr: String,
 
+    /// Specify the maximum time to wait for a remote `.local` candidate to be resolved, after
+    /// which the candidate is discarded. Defaults to 5 seconds when this property is nil.
+    pub multicast_dns_query_timeout: Option<Duration>,
+
+    /// An mDNS connection shared between agents. When set, the agent uses it instead of opening
+    /// its own, registers its host name with it when gathering mDNS candidates and unregisters it
+    /// on close. The connection is not closed by the agent. Ignored when `multicast_dns_mode` is
+    /// `Disabled`.
+    pub multicast_dns_conn: Option<Arc<DnsConn>>,
+
     /// Defaults to 5 seconds when this property is nil.
     /// If the duration is 0, the ICE Agent will never go to disconnected.
     pub disconnected_timeout: Option<Duration>,
@@ -113,14 +183,28 @@ pub struct AgentConfig {
 
     pub is_controlling: bool,
 
+    /// The component the agent gathers candidates for, `COMPONENT_RTP` when 0. An agent only
+    /// checks the pairs of a single component: a separate agent, with the same credentials,
+    /// is used for the RTCP component when RTCP isn't multiplexed with RTP.
+    pub component: u16,
+
+    /// Keeps the role given to `dial`/`accept` for the lifetime of the agent, for signaling
+    /// protocols which already establish the roles. Role conflicts are then not resolved with
+    /// tie-breakers: conflicting requests are always answered with a 487 (Role Conflict) error
+    /// and 487 errors from the remote agent never make this agent switch.
+    pub fixed_role: bool,
+
     /// lite agents do not perform connectivity check and only provide host candidates.
+    /// A lite agent only responds to checks and should be started with `accept`, unless the
+    /// remote agent is also lite (RFC 8445 S6.1.1).
     pub lite: bool,
 
     /// It is used along with nat1to1ips to specify which candidate type the 1:1 NAT IP addresses
     /// should be mapped to. If unspecified or CandidateTypeHost, nat1to1ips are used to replace
     /// host candidate IPs. If CandidateTypeServerReflexive, it will insert a srflx candidate (as
     /// if it was dervied from a STUN server) with its port number being the one for the actual host
-    /// candidate.  Other values will result in an error.
+    /// candidate.  Other values will result in an error. When the agent uses a muxed UDP network
+    /// the srflx candidate shares the socket of the host candidate, which is its base.
     pub nat_1to1_ip_candidate_type: CandidateType,
 
     /// Contains a list of public IP addresses that are to be used as a host candidate or srflx
@@ -137,6 +221,42 @@ pub struct AgentConfig {
     /// Specify a minimum wait time before selecting relay candidates.
     pub relay_acceptance_min_wait: Option<Duration>,
 
+    /// Specify the maximum time to wait for a single STUN/TURN server while gathering candidates.
+    /// Servers that do not answer in time are skipped, so gathering completes with whatever
+    /// candidates are available. Defaults to 5 seconds when this property is nil.
+    pub gather_timeout: Option<Duration>,
+
+    /// Overrides `gather_timeout` for individual STUN/TURN servers, keyed by `host:port`.
+    pub server_gather_timeouts: HashMap<String, Duration>,
+
+    /// Enables switching the selected pair while connected. When set, the controlling agent
+    /// keeps checking the remaining pairs after a pair is selected and nominates a valid pair
+    /// whose priority is at least this factor higher than the selected one (e.g. `2.0`).
+    /// A controlled agent with this set follows such later nominations. When nil, the first
+    /// selected pair is kept until it fails.
+    pub pair_switch_threshold: Option<f64>,
+
+    /// The minimum time a pair stays selected before the agent switches away from it, to avoid
+    /// flapping between pairs of similar priority. Defaults to 5 seconds when this property is
+    /// nil.
+    pub pair_switch_min_interval: Option<Duration>,
+
+    /// Controls how candidate pairs are nominated when this agent is controlling, and whether
+    /// a controlled agent follows nominations of higher priority pairs while connecting.
+    pub nomination_mode: NominationMode,
+
+    /// Enables re-gathering after the connection failed. The agent then keeps the remote
+    /// candidates, gathers new local candidates (including new STUN/TURN allocations) and
+    /// rebuilds its checklist, moving back to checking. The first attempt happens after this
+    /// interval, which doubles for every following failure until the agent connects again.
+    /// When nil, a failed agent stays failed until it is restarted. Agent::new fails with
+    /// ErrZeroRegatherInterval when it is zero.
+    pub regather_interval: Option<Duration>,
+
+    /// The maximum time between two re-gathering attempts. Defaults to 60 seconds when this
+    /// property is nil.
+    pub regather_max_interval: Option<Duration>,
+
     /// Net is the our abstracted network interface for internal development purpose only
     /// (see (github.com/pion/transport/vnet)[github.com/pion/transport/vnet]).
     pub net: Option<Arc<Net>>,
@@ -149,6 +269,19 @@ pub struct AgentConfig {
     /// the ips which are used to gather ICE candidates.
     pub ip_filter: Arc<Option<IpFilterFn>>,
 
+    /// Overrides the type preference (0-126) of local candidates per candidate type, used to
+    /// compute their priority. Types which are not listed keep the recommended preference.
+    pub type_preferences: HashMap<CandidateType, u16>,
+
+    /// A function that returns the local preference (0-65535) of a gathered candidate, used to
+    /// compute its priority, e.g. to prefer candidates on a dedicated media interface.
+    /// Returning None keeps the default local preference.
+    pub local_preference_fn: Arc<Option<LocalPreferenceFn>>,
+
+    /// A function that returns the foundation of a gathered candidate. Returning None keeps the
+    /// computed foundation.
+    pub foundation_fn: Arc<Option<FoundationFn>>,
+
     /// Controls if self-signed certificates are accepted when connecting to TURN servers via TLS or
     /// DTLS.
     pub insecure_skip_verify: bool,
@@ -205,6 +338,18 @@ impl AgentConfig {
             a.keepalive_interval = DEFAULT_KEEPALIVE_INTERVAL;
         }
 
+        if let Some(gather_timeout) = self.gather_timeout {
+            a.gather_timeout = gather_timeout;
+        } else {
+            a.gather_timeout = DEFAULT_GATHER_TIMEOUT;
+        }
+
+        if let Some(pair_switch_min_interval) = self.pair_switch_min_interval {
+            a.pair_switch_min_interval = pair_switch_min_interval;
+        } else {
+            a.pair_switch_min_interval = DEFAULT_PAIR_SWITCH_MIN_INTERVAL;
+        }
+
         if self.check_interval == Duration::from_secs(0) {
             a.check_interval = DEFAULT_CHECK_INTERVAL;
         } else {
@@ -221,7 +366,7 @@ impl AgentConfig {
             ExternalIpMapper::new(self.nat_1to1_ip_candidate_type, &self.nat_1to1_ips)?
         {
             if ext_ip_mapper.candidate_type == CandidateType::Host {
-                if mdns_mode == MulticastDnsMode::QueryAndGather {
+                if mdns_mode.is_gather_enabled() {
                     return Err(Error::ErrMulticastDnsWithNat1to1IpMapping);
                 }
                 let mut candi_host_enabled = false;
@@ -1,6 +1,7 @@
 use super::*;
 use crate::error::*;
 use crate::network_type::*;
+use crate::tcp_mux::TCPMux;
 use crate::udp_network::UDPNetwork;
 use crate::url::{ProtoType, SchemeType, Url};
 use crate::util::*;
@@ -17,10 +18,10 @@ use std::str::FromStr;
 use std::sync::Arc;
 use waitgroup::WaitGroup;
 
-const STUN_GATHER_TIMEOUT: Duration = Duration::from_secs(5);
-
+#[derive(Clone)]
 pub(crate) struct GatherCandidatesInternalParams {
     pub(crate) udp_network: UDPNetwork,
+    pub(crate) tcp_mux: Option<Arc<dyn TCPMux + Send + Sync>>,
     pub(crate) candidate_types: Vec<CandidateType>,
     pub(crate) urls: Vec<Url>,
     pub(crate) network_types: Vec<NetworkType>,
@@ -37,6 +38,7 @@ pub(crate) struct GatherCandidatesInternalParams {
 
 struct GatherCandidatesLocalParams {
     udp_network: UDPNetwork,
+    tcp_mux: Option<Arc<dyn TCPMux + Send + Sync>>,
     network_types: Vec<NetworkType>,
     mdns_mode: MulticastDnsMode,
     mdns_name: String,
@@ -57,6 +59,16 @@ struct GatherCandidatesLocalUDPMuxParams {
     udp_mux: Arc<dyn UDPMux + Send + Sync>,
 }
 
+struct GatherCandidatesLocalTCPMuxParams {
+    network_types: Vec<NetworkType>,
+    interface_filter: Arc<Option<InterfaceFilterFn>>,
+    ip_filter: Arc<Option<IpFilterFn>>,
+    ext_ip_mapper: Arc<Option<ExternalIpMapper>>,
+    net: Arc<Net>,
+    agent_internal: Arc<AgentInternal>,
+    tcp_mux: Arc<dyn TCPMux + Send + Sync>,
+}
+
 struct GatherCandidatesSrflxMappedParasm {
     network_types: Vec<NetworkType>,
     port_max: u16,
@@ -76,6 +88,83 @@ struct GatherCandidatesSrflxParams {
 }
 
 impl Agent {
+    /// Re-gathers the local candidates whenever the connection fails, backing off exponentially
+    /// between attempts until the agent connects again.
+    pub(crate) fn start_regather_routine(
+        &self,
+        mut chan_regather_rx: mpsc::Receiver<ConnectionState>,
+        interval: Duration,
+        max_interval: Duration,
+    ) {
+        let params = self.gather_candidates_params();
+        tokio::spawn(async move {
+            let mut backoff = interval;
+            while let Some(state) = chan_regather_rx.recv().await {
+                if state == ConnectionState::Connected {
+                    backoff = interval;
+                    continue;
+                }
+
+                log::info!(
+                    "[{}]: connection failed, re-gathering candidates in {:?}",
+                    params.agent_internal.get_name(),
+                    backoff
+                );
+                // Wait out the backoff, unless the connection recovers in the meantime
+                let sleep = tokio::time::sleep(backoff);
+                tokio::pin!(sleep);
+                let connected = loop {
+                    tokio::select! {
+                        _ = &mut sleep => break false,
+                        state = chan_regather_rx.recv() => match state {
+                            Some(ConnectionState::Connected) => break true,
+                            Some(_) => {}
+                            None => return,
+                        },
+                    }
+                };
+                if connected {
+                    backoff = interval;
+                    continue;
+                }
+                backoff = std::cmp::min(backoff * 2, max_interval);
+
+                Self::regather(&params).await;
+            }
+        });
+    }
+
+    /// Gathers new local candidates and rebuilds the checklist with the known remote candidates.
+    async fn regather(params: &GatherCandidatesInternalParams) {
+        let ai = &params.agent_internal;
+        // The agent may have been restarted or closed in the meantime
+        if ai.connection_state.load(Ordering::SeqCst) != ConnectionState::Failed as u8
+            || params.gathering_state.load(Ordering::SeqCst) == GatheringState::Gathering as u8
+        {
+            return;
+        }
+
+        log::info!("[{}]: re-gathering candidates", ai.get_name());
+        {
+            let mut pending_binding_requests = ai.pending_binding_requests.lock().await;
+            pending_binding_requests.clear();
+        }
+        {
+            let mut checklist = ai.agent_conn.checklist.lock().await;
+            checklist.clear();
+        }
+        ai.set_selected_pair(None).await;
+        ai.delete_local_candidates().await;
+        ai.start().await;
+
+        // New local candidates are paired with the remote candidates as they are gathered
+        ai.update_connection_state(ConnectionState::Checking).await;
+        params
+            .gathering_state
+            .store(GatheringState::New as u8, Ordering::SeqCst);
+        Self::gather_candidates_internal(params.clone()).await;
+    }
+
     pub(crate) async fn gather_candidates_internal(params: GatherCandidatesInternalParams) {
         Self::set_gathering_state(
             &params.chan_candidate_tx,
@@ -91,6 +180,7 @@ impl Agent {
                 CandidateType::Host => {
                     let local_params = GatherCandidatesLocalParams {
                         udp_network: params.udp_network.clone(),
+                        tcp_mux: params.tcp_mux.clone(),
                         network_types: params.network_types.clone(),
                         mdns_mode: params.mdns_mode,
                         mdns_name: params.mdns_name.clone(),
@@ -194,6 +284,7 @@ impl Agent {
     async fn gather_candidates_local(params: GatherCandidatesLocalParams) {
         let GatherCandidatesLocalParams {
             udp_network,
+            tcp_mux,
             network_types,
             mdns_mode,
             mdns_name,
@@ -204,8 +295,25 @@ impl Agent {
             agent_internal,
         } = params;
 
+        // Passive ICE-TCP candidates are only gathered through a TCP mux
+        if let Some(tcp_mux) = tcp_mux {
+            let result = Self::gather_candidates_local_tcp_mux(GatherCandidatesLocalTCPMuxParams {
+                network_types: network_types.clone(),
+                interface_filter: Arc::clone(&interface_filter),
+                ip_filter: Arc::clone(&ip_filter),
+                ext_ip_mapper: Arc::clone(&ext_ip_mapper),
+                net: Arc::clone(&net),
+                agent_internal: Arc::clone(&agent_internal),
+                tcp_mux,
+            })
+            .await;
+
+            if let Err(err) = result {
+                log::error!("Failed to gather local candidates using TCP mux: {}", err);
+            }
+        }
+
         // If we wanna use UDP mux, do so
-        // FIXME: We still need to support TCP in combination with this option
         if let UDPNetwork::Muxed(udp_mux) = udp_network {
             let result = Self::gather_candidates_local_udp_mux(GatherCandidatesLocalUDPMuxParams {
                 network_types,
@@ -229,7 +337,7 @@ impl Agent {
         for ip in ips {
             let mut mapped_ip = ip;
 
-            if mdns_mode != MulticastDnsMode::QueryAndGather && ext_ip_mapper.is_some() {
+            if !mdns_mode.is_gather_enabled() && ext_ip_mapper.is_some() {
                 if let Some(ext_ip_mapper2) = ext_ip_mapper.as_ref() {
                     if ext_ip_mapper2.candidate_type == CandidateType::Host {
                         if let Ok(mi) = ext_ip_mapper2.find_external_ip(&ip.to_string()) {
@@ -245,7 +353,7 @@ impl Agent {
                 }
             }
 
-            let address = if mdns_mode == MulticastDnsMode::QueryAndGather {
+            let address = if mdns_mode.is_gather_enabled() {
                 mdns_name.clone()
             } else {
                 mapped_ip.to_string()
@@ -274,6 +382,7 @@ impl Agent {
 
                 let conn: Arc<dyn Conn + Send + Sync> = match listen_udp_in_port_range(
                     &net,
+                    &agent_internal.socket_factory,
                     ephemeral_config.port_max(),
                     ephemeral_config.port_min(),
                     SocketAddr::new(ip, 0),
@@ -310,7 +419,7 @@ impl Agent {
                         network: network.clone(),
                         address,
                         port,
-                        component: COMPONENT_RTP,
+                        component: agent_internal.component,
                         conn: Some(conn),
                         ..CandidateBaseConfig::default()
                     },
@@ -320,7 +429,7 @@ impl Agent {
                 let candidate: Arc<dyn Candidate + Send + Sync> =
                     match host_config.new_candidate_host() {
                         Ok(candidate) => {
-                            if mdns_mode == MulticastDnsMode::QueryAndGather {
+                            if mdns_mode.is_gather_enabled() {
                                 if let Err(err) = candidate.set_ip(&ip) {
                                     log::warn!(
                                         "[{}]: Failed to create host candidate: {} {} {}: {:?}",
@@ -391,18 +500,21 @@ impl Agent {
         let local_ips =
             local_interfaces(&net, &interface_filter, &ip_filter, &relevant_network_types).await;
 
-        let candidate_ip = ext_ip_mapper
+        let mapped_candidate_type = ext_ip_mapper
+            .as_ref()
+            .as_ref()
+            .map_or(CandidateType::Host, |mapper| mapper.candidate_type);
+
+        // The local IP is kept with the IP it maps to, it is the related address of a srflx
+        // candidate.
+        let candidate_ips = ext_ip_mapper
             .as_ref() // Arc
             .as_ref() // Option
             .and_then(|mapper| {
-                if mapper.candidate_type != CandidateType::Host {
-                    return None;
-                }
-
                 local_ips
                     .iter()
                     .find_map(|ip| match mapper.find_external_ip(&ip.to_string()) {
-                        Ok(ip) => Some(ip),
+                        Ok(external_ip) => Some((*ip, external_ip)),
                         Err(err) => {
                             log::warn!(
                             "1:1 NAT mapping is enabled but not external IP is found for {}: {}",
@@ -413,11 +525,20 @@ impl Agent {
                         }
                     })
             })
-            .or_else(|| local_ips.iter().copied().next());
+            .or_else(|| local_ips.iter().map(|ip| (*ip, *ip)).next());
 
-        let candidate_ip = match candidate_ip {
+        let (local_ip, mapped_ip) = match candidate_ips {
             None => return Err(Error::ErrCandidateIpNotFound),
-            Some(ip) => ip,
+            Some(ips) => ips,
+        };
+
+        // A 1:1 NAT mapping to a host candidate advertises the external IP in place of the
+        // local one, a mapping to a srflx candidate keeps the host candidate and advertises
+        // the external IP next to it.
+        let host_ip = if mapped_candidate_type == CandidateType::Host {
+            mapped_ip
+        } else {
+            local_ip
         };
 
         let ufrag = {
@@ -432,10 +553,10 @@ impl Agent {
         let host_config = CandidateHostConfig {
             base_config: CandidateBaseConfig {
                 network: UDP.to_owned(),
-                address: candidate_ip.to_string(),
+                address: host_ip.to_string(),
                 port,
-                conn: Some(conn),
-                component: COMPONENT_RTP,
+                conn: Some(Arc::clone(&conn)),
+                component: agent_internal.component,
                 ..Default::default()
             },
             tcp_type: TcpType::Unspecified,
@@ -446,6 +567,127 @@ impl Agent {
 
         agent_internal.add_candidate(&candidate).await?;
 
+        if mapped_candidate_type == CandidateType::ServerReflexive && mapped_ip != local_ip {
+            // The srflx candidate shares the muxed socket, the host candidate is its base.
+            let srflx_config = CandidateServerReflexiveConfig {
+                base_config: CandidateBaseConfig {
+                    network: UDP.to_owned(),
+                    address: mapped_ip.to_string(),
+                    port,
+                    conn: Some(conn),
+                    component: agent_internal.component,
+                    ..Default::default()
+                },
+                rel_addr: local_ip.to_string(),
+                rel_port: port,
+            };
+
+            let candidate: Arc<dyn Candidate + Send + Sync> =
+                Arc::new(srflx_config.new_candidate_server_reflexive()?);
+
+            agent_internal.add_candidate(&candidate).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn gather_candidates_local_tcp_mux(
+        params: GatherCandidatesLocalTCPMuxParams,
+    ) -> Result<()> {
+        let GatherCandidatesLocalTCPMuxParams {
+            network_types,
+            interface_filter,
+            ip_filter,
+            ext_ip_mapper,
+            net,
+            agent_internal,
+            tcp_mux,
+        } = params;
+
+        // Filter out non TCP network types
+        let relevant_network_types: Vec<_> =
+            network_types.into_iter().filter(|n| n.is_tcp()).collect();
+        if relevant_network_types.is_empty() {
+            return Ok(());
+        }
+
+        let ufrag = {
+            let ufrag_pwd = agent_internal.ufrag_pwd.lock().await;
+
+            ufrag_pwd.local_ufrag.clone()
+        };
+
+        let local_ips =
+            local_interfaces(&net, &interface_filter, &ip_filter, &relevant_network_types).await;
+        for ip in local_ips {
+            let mut mapped_ip = ip;
+            if let Some(mapper) = &*ext_ip_mapper {
+                if mapper.candidate_type == CandidateType::Host {
+                    if let Ok(mi) = mapper.find_external_ip(&ip.to_string()) {
+                        mapped_ip = mi;
+                    }
+                }
+            }
+
+            let conn = match Arc::clone(&tcp_mux).get_conn_by_ufrag(&ufrag, ip).await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    log::warn!(
+                        "[{}]: error getting tcp conn by ufrag: {} {}: {}",
+                        agent_internal.get_name(),
+                        ip,
+                        ufrag,
+                        err
+                    );
+                    continue;
+                }
+            };
+            let port = conn.local_addr()?.port();
+
+            let host_config = CandidateHostConfig {
+                base_config: CandidateBaseConfig {
+                    network: TCP.to_owned(),
+                    address: mapped_ip.to_string(),
+                    port,
+                    conn: Some(conn),
+                    component: agent_internal.component,
+                    ..Default::default()
+                },
+                tcp_type: TcpType::Passive,
+            };
+
+            let candidate: Arc<dyn Candidate + Send + Sync> = match host_config.new_candidate_host()
+            {
+                Ok(candidate) => Arc::new(candidate),
+                Err(err) => {
+                    log::warn!(
+                        "[{}]: Failed to create host candidate: {} {} {}: {}",
+                        agent_internal.get_name(),
+                        TCP,
+                        mapped_ip,
+                        port,
+                        err
+                    );
+                    continue;
+                }
+            };
+
+            if let Err(err) = agent_internal.add_candidate(&candidate).await {
+                if let Err(close_err) = candidate.close().await {
+                    log::warn!(
+                        "[{}]: Failed to close candidate: {}",
+                        agent_internal.get_name(),
+                        close_err
+                    );
+                }
+                log::warn!(
+                    "[{}]: Failed to append to localCandidates and run onCandidateHdlr: {}",
+                    agent_internal.get_name(),
+                    err
+                );
+            }
+        }
+
         Ok(())
     }
 
@@ -477,6 +719,7 @@ impl Agent {
 
                 let conn: Arc<dyn Conn + Send + Sync> = match listen_udp_in_port_range(
                     &net2,
+                    &agent_internal2.socket_factory,
                     port_max,
                     port_min,
                     if network_type.is_ipv4() {
@@ -528,7 +771,7 @@ impl Agent {
                         network: network.clone(),
                         address: mapped_ip.to_string(),
                         port: laddr.port(),
-                        component: COMPONENT_RTP,
+                        component: agent_internal2.component,
                         conn: Some(conn),
                         ..CandidateBaseConfig::default()
                     },
@@ -603,10 +846,18 @@ impl Agent {
                 tokio::spawn(async move {
                     let _d = w;
 
+                    let gather_timeout = agent_internal2.gather_timeout_for(&url);
+                    let gather_start = Instant::now();
+
                     let host_port = format!("{}:{}", url.host, url.port);
-                    let server_addr = match net2.resolve_addr(is_ipv4, &host_port).await {
-                        Ok(addr) => addr,
-                        Err(err) => {
+                    let server_addr = match tokio::time::timeout(
+                        gather_timeout,
+                        resolve_server_addr(&net2, &agent_internal2.resolver, &url, is_ipv4),
+                    )
+                    .await
+                    {
+                        Ok(Ok(addr)) => addr,
+                        Ok(Err(err)) => {
                             log::warn!(
                                 "[{}]: failed to resolve stun host: {}: {}",
                                 agent_internal2.get_name(),
@@ -615,10 +866,19 @@ impl Agent {
                             );
                             return Ok(());
                         }
+                        Err(_) => {
+                            log::warn!(
+                                "[{}]: timed out resolving stun host: {}",
+                                agent_internal2.get_name(),
+                                host_port
+                            );
+                            return Ok(());
+                        }
                     };
 
                     let conn: Arc<dyn Conn + Send + Sync> = match listen_udp_in_port_range(
                         &net2,
+                        &agent_internal2.socket_factory,
                         port_max,
                         port_min,
                         if is_ipv4 {
@@ -641,20 +901,20 @@ impl Agent {
                         }
                     };
 
-                    let xoraddr =
-                        match get_xormapped_addr(&conn, server_addr, STUN_GATHER_TIMEOUT).await {
-                            Ok(xoraddr) => xoraddr,
-                            Err(err) => {
-                                log::warn!(
-                                    "[{}]: could not get server reflexive address {} {}: {}",
-                                    agent_internal2.get_name(),
-                                    network,
-                                    url,
-                                    err
-                                );
-                                return Ok(());
-                            }
-                        };
+                    let remaining = gather_timeout.saturating_sub(gather_start.elapsed());
+                    let xoraddr = match get_xormapped_addr(&conn, server_addr, remaining).await {
+                        Ok(xoraddr) => xoraddr,
+                        Err(err) => {
+                            log::warn!(
+                                "[{}]: could not get server reflexive address {} {}: {}",
+                                agent_internal2.get_name(),
+                                network,
+                                url,
+                                err
+                            );
+                            return Ok(());
+                        }
+                    };
 
                     let (ip, port) = (xoraddr.ip, xoraddr.port);
 
@@ -664,7 +924,7 @@ impl Agent {
                             network: network.clone(),
                             address: ip.to_string(),
                             port,
-                            component: COMPONENT_RTP,
+                            component: agent_internal2.component,
                             conn: Some(conn),
                             ..CandidateBaseConfig::default()
                         },
@@ -749,11 +1009,18 @@ impl Agent {
             tokio::spawn(async move {
                 let _d = w;
 
-                let turn_server_addr = format!("{}:{}", url.host, url.port);
+                let gather_timeout = agent_internal2.gather_timeout_for(&url);
+                let gather_start = Instant::now();
 
                 let (loc_conn, rel_addr, rel_port) =
                     if url.proto == ProtoType::Udp && url.scheme == SchemeType::Turn {
-                        let loc_conn = match net2.bind(SocketAddr::from_str("0.0.0.0:0")?).await {
+                        let loc_conn = match bind_udp(
+                            &net2,
+                            &agent_internal2.socket_factory,
+                            SocketAddr::from_str("0.0.0.0:0")?,
+                        )
+                        .await
+                        {
                             Ok(c) => c,
                             Err(err) => {
                                 log::warn!(
@@ -782,6 +1049,40 @@ impl Agent {
                         return Ok(());
                     };
 
+                // Resolve the server in the address family of the relay socket
+                let is_ipv4 = loc_conn.local_addr()?.is_ipv4();
+                let turn_server_addr = if agent_internal2.resolver.is_some() {
+                    match tokio::time::timeout(
+                        gather_timeout,
+                        resolve_server_addr(&net2, &agent_internal2.resolver, &url, is_ipv4),
+                    )
+                    .await
+                    {
+                        Ok(Ok(addr)) => addr.to_string(),
+                        Ok(Err(err)) => {
+                            log::warn!(
+                                "[{}]: failed to resolve turn host: {}:{}: {}",
+                                agent_internal2.get_name(),
+                                url.host,
+                                url.port,
+                                err
+                            );
+                            return Ok(());
+                        }
+                        Err(_) => {
+                            log::warn!(
+                                "[{}]: timed out resolving turn host: {}:{}",
+                                agent_internal2.get_name(),
+                                url.host,
+                                url.port
+                            );
+                            return Ok(());
+                        }
+                    }
+                } else {
+                    format!("{}:{}", url.host, url.port)
+                };
+
                 let cfg = turn::client::ClientConfig {
                     stun_serv_addr: String::new(),
                     turn_serv_addr: turn_server_addr.clone(),
@@ -816,9 +1117,10 @@ impl Agent {
                     return Ok(());
                 }
 
-                let relay_conn = match client.allocate().await {
-                    Ok(conn) => conn,
-                    Err(err) => {
+                let remaining = gather_timeout.saturating_sub(gather_start.elapsed());
+                let relay_conn = match tokio::time::timeout(remaining, client.allocate()).await {
+                    Ok(Ok(conn)) => conn,
+                    Ok(Err(err)) => {
                         let _ = client.close().await;
                         log::warn!(
                             "[{}]: Failed to allocate on turn.Client {} {}",
@@ -828,6 +1130,16 @@ impl Agent {
                         );
                         return Ok(());
                     }
+                    Err(_) => {
+                        let _ = client.close().await;
+                        log::warn!(
+                            "[{}]: Timed out allocating on turn.Client {} after {:?}",
+                            agent_internal2.get_name(),
+                            turn_server_addr,
+                            gather_timeout
+                        );
+                        return Ok(());
+                    }
                 };
 
                 let raddr = relay_conn.local_addr()?;
@@ -836,7 +1148,7 @@ impl Agent {
                         network: network.clone(),
                         address: raddr.ip().to_string(),
                         port: raddr.port(),
-                        component: COMPONENT_RTP,
+                        component: agent_internal2.component,
                         conn: Some(Arc::new(relay_conn)),
                         ..CandidateBaseConfig::default()
                     },
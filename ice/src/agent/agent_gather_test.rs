@@ -1,5 +1,9 @@
 use super::agent_vnet_test::*;
 use super::*;
+use crate::candidate::candidate_base::CandidateBaseConfig;
+use crate::candidate::candidate_host::CandidateHostConfig;
+use crate::socket_factory::{SocketFactory, SocketOptions};
+use crate::tcp_mux::{TCPMuxDefault, TCPMuxParams};
 use crate::udp_mux::{UDPMuxDefault, UDPMuxParams};
 use crate::util::*;
 
@@ -89,15 +93,15 @@ async fn test_vnet_gather_listen_udp() -> Result<()> {
     assert!(!local_ips.is_empty(), "should have one local IP");
 
     for ip in local_ips {
-        let _ = listen_udp_in_port_range(&nw, 0, 0, SocketAddr::new(ip, 0)).await?;
+        let _ = listen_udp_in_port_range(&nw, &None, 0, 0, SocketAddr::new(ip, 0)).await?;
 
-        let result = listen_udp_in_port_range(&nw, 4999, 5000, SocketAddr::new(ip, 0)).await;
+        let result = listen_udp_in_port_range(&nw, &None, 4999, 5000, SocketAddr::new(ip, 0)).await;
         assert!(
             result.is_err(),
             "listenUDP with invalid port range did not return ErrPort"
         );
 
-        let conn = listen_udp_in_port_range(&nw, 5000, 5000, SocketAddr::new(ip, 0)).await?;
+        let conn = listen_udp_in_port_range(&nw, &None, 5000, 5000, SocketAddr::new(ip, 0)).await?;
         let port = conn.local_addr()?.port();
         assert_eq!(
             port, 5000,
@@ -488,3 +492,273 @@ async fn test_vnet_gather_muxed_udp() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_gather_timeout_per_server() -> Result<()> {
+    let mut server_gather_timeouts = HashMap::new();
+    server_gather_timeouts.insert("stun.example.com:3478".to_owned(), Duration::from_secs(1));
+
+    let a = Agent::new(AgentConfig {
+        gather_timeout: Some(Duration::from_secs(3)),
+        server_gather_timeouts,
+        ..Default::default()
+    })
+    .await?;
+
+    let slow_server = Url::parse_url("stun:stun.example.com:3478")?;
+    let other_server = Url::parse_url("stun:stun.example.org:3478")?;
+    assert_eq!(
+        a.internal.gather_timeout_for(&slow_server),
+        Duration::from_secs(1),
+        "per-server deadline should override the default"
+    );
+    assert_eq!(
+        a.internal.gather_timeout_for(&other_server),
+        Duration::from_secs(3),
+        "servers without an override should use gather_timeout"
+    );
+
+    a.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_gather_tcp_with_socket_factory() -> Result<()> {
+    let a = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Tcp4],
+        candidate_types: vec![CandidateType::Host],
+        socket_factory: Some(Arc::new(SocketOptions::default())),
+        ..Default::default()
+    })
+    .await?;
+    assert!(
+        a.tcp_mux.is_none(),
+        "the agent should not listen for ICE-TCP without a TCP mux"
+    );
+    a.close().await?;
+
+    let socket_factory = SocketOptions::default();
+    let listener = socket_factory
+        .listen_tcp(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0))
+        .await?;
+    let tcp_mux = TCPMuxDefault::new(TCPMuxParams::new(listener))?;
+    let a = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Tcp4],
+        candidate_types: vec![CandidateType::Host],
+        tcp_mux: Some(Arc::clone(&tcp_mux) as Arc<dyn TCPMux + Send + Sync>),
+        socket_factory: Some(Arc::new(socket_factory)),
+        ..Default::default()
+    })
+    .await?;
+
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
+    let done_tx = Arc::new(Mutex::new(Some(done_tx)));
+    a.on_candidate(Box::new(
+        move |c: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let done_tx_clone = Arc::clone(&done_tx);
+            Box::pin(async move {
+                if c.is_none() {
+                    let mut tx = done_tx_clone.lock().await;
+                    tx.take();
+                }
+            })
+        },
+    ));
+
+    a.gather_candidates()?;
+    let _ = done_rx.recv().await;
+
+    let tcp_candidates: Vec<_> = a
+        .get_local_candidates()
+        .await?
+        .into_iter()
+        .filter(|c| c.network_type() == NetworkType::Tcp4)
+        .collect();
+    assert!(!tcp_candidates.is_empty(), "no passive ICE-TCP candidate");
+    for c in tcp_candidates {
+        assert_eq!(c.tcp_type(), TcpType::Passive);
+    }
+
+    a.close().await?;
+    tcp_mux.close().await?;
+
+    Ok(())
+}
+
+/// gather_muxed_udp_srflx gathers the candidates of a muxed agent whose local IPs are mapped
+/// to srflx candidates, and checks that it advertises the external IP on the muxed port next
+/// to the host candidate, which is the base of the srflx candidate.
+async fn gather_muxed_udp_srflx(
+    static_ips: &[&str],
+    nat_1to1_ips: &[&str],
+) -> Result<Arc<dyn Candidate + Send + Sync>> {
+    let udp_socket = UdpSocket::bind("0.0.0.0:0").await?;
+    let udp_mux = UDPMuxDefault::new(UDPMuxParams::new(udp_socket));
+
+    let lan = Arc::new(Mutex::new(router::Router::new(router::RouterConfig {
+        cidr: "10.0.0.0/24".to_owned(),
+        nat_type: Some(nat::NatType {
+            mode: nat::NatMode::Nat1To1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    })?));
+
+    let nw = Arc::new(net::Net::new(Some(net::NetConfig {
+        static_ips: static_ips.iter().map(|ip| ip.to_string()).collect(),
+        ..Default::default()
+    })));
+
+    connect_net2router(&nw, &lan).await?;
+
+    let a = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Udp4],
+        nat_1to1_ips: nat_1to1_ips.iter().map(|ip| ip.to_string()).collect(),
+        nat_1to1_ip_candidate_type: CandidateType::ServerReflexive,
+        net: Some(nw),
+        udp_network: UDPNetwork::Muxed(udp_mux),
+        ..Default::default()
+    })
+    .await?;
+
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
+    let done_tx = Arc::new(Mutex::new(Some(done_tx)));
+    a.on_candidate(Box::new(
+        move |c: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let done_tx_clone = Arc::clone(&done_tx);
+            Box::pin(async move {
+                if c.is_none() {
+                    let mut tx = done_tx_clone.lock().await;
+                    tx.take();
+                }
+            })
+        },
+    ));
+
+    a.gather_candidates()?;
+
+    let _ = done_rx.recv().await;
+
+    let candidates = a.get_local_candidates().await?;
+    assert_eq!(
+        candidates.len(),
+        2,
+        "There must be a host and a srflx candidate"
+    );
+
+    let host = candidates
+        .iter()
+        .find(|c| c.candidate_type() == CandidateType::Host)
+        .expect("should gather a host candidate");
+    let candi = candidates
+        .iter()
+        .find(|c| c.candidate_type() == CandidateType::ServerReflexive)
+        .expect("should gather a srflx candidate");
+    let laddr = candi.get_conn().unwrap().local_addr()?;
+    assert_eq!(host.port(), laddr.port());
+    assert_eq!(candi.address(), "1.2.3.4");
+    assert_eq!(candi.port(), laddr.port());
+    assert_eq!(
+        candi.related_address(),
+        Some(CandidateRelatedAddress {
+            address: host.address(),
+            port: host.port(),
+        })
+    );
+
+    // The srflx candidate is replaced by its base in the checklist
+    let remote: Arc<dyn Candidate + Send + Sync> = Arc::new(
+        CandidateHostConfig {
+            base_config: CandidateBaseConfig {
+                network: "udp".to_owned(),
+                address: "10.0.0.100".to_owned(),
+                port: 5000,
+                component: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .new_candidate_host()?,
+    );
+    a.internal.add_remote_candidate(&remote).await;
+    {
+        let checklist = a.internal.agent_conn.checklist.lock().await;
+        assert_eq!(checklist.len(), 1, "Only the host candidate must be paired");
+        assert_eq!(checklist[0].local.candidate_type(), CandidateType::Host);
+    }
+
+    let candi = Arc::clone(candi);
+    a.close().await?;
+
+    Ok(candi)
+}
+
+#[tokio::test]
+async fn test_vnet_gather_muxed_udp_srflx_mapping() -> Result<()> {
+    let candi = gather_muxed_udp_srflx(&["10.0.0.1"], &["1.2.3.4"]).await?;
+    assert_eq!(
+        candi.related_address().map(|r| r.address),
+        Some("10.0.0.1".to_owned())
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vnet_gather_muxed_udp_srflx_mapping_related_address() -> Result<()> {
+    // Only the second local IP is mapped, it is the related address of the candidate
+    let candi = gather_muxed_udp_srflx(&["10.0.0.1", "10.0.0.2"], &["1.2.3.4/10.0.0.2"]).await?;
+    assert_eq!(
+        candi.related_address().map(|r| r.address),
+        Some("10.0.0.2".to_owned())
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vnet_gather_rtcp_component() -> Result<()> {
+    let r = Arc::new(Mutex::new(router::Router::new(router::RouterConfig {
+        cidr: "1.2.3.0/24".to_owned(),
+        ..Default::default()
+    })?));
+    let nw = Arc::new(net::Net::new(Some(net::NetConfig::default())));
+    connect_net2router(&nw, &r).await?;
+
+    let a = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Udp4],
+        component: COMPONENT_RTCP,
+        net: Some(nw),
+        ..Default::default()
+    })
+    .await?;
+
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
+    let done_tx = Arc::new(Mutex::new(Some(done_tx)));
+    a.on_candidate(Box::new(
+        move |c: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let done_tx_clone = Arc::clone(&done_tx);
+            Box::pin(async move {
+                if c.is_none() {
+                    let mut tx = done_tx_clone.lock().await;
+                    tx.take();
+                }
+            })
+        },
+    ));
+
+    a.gather_candidates()?;
+    let _ = done_rx.recv().await;
+
+    let candidates = a.get_local_candidates().await?;
+    assert!(!candidates.is_empty(), "should have gathered a candidate");
+    for c in &candidates {
+        assert_eq!(c.component(), COMPONENT_RTCP);
+        assert!(c.marshal().contains(" 2 udp "), "{}", c.marshal());
+    }
+
+    a.close().await?;
+
+    Ok(())
+}
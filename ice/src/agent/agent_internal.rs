@@ -2,9 +2,14 @@ use super::agent_transport::*;
 use super::*;
 use crate::candidate::candidate_base::CandidateBaseConfig;
 use crate::candidate::candidate_peer_reflexive::CandidatePeerReflexiveConfig;
+use crate::control::{AttrControlled, AttrControlling};
+use crate::resolver::Resolver;
+use crate::socket_factory::SocketFactory;
 use crate::util::*;
 use arc_swap::ArcSwapOption;
 use std::sync::atomic::{AtomicBool, AtomicU64};
+use stun::error_code::*;
+use stun::message_view::MessageView;
 use util::sync::Mutex as SyncMutex;
 
 pub type ChanCandidateTx =
@@ -31,20 +36,24 @@ pub struct AgentInternal {
         Mutex<Option<(mpsc::Receiver<()>, mpsc::Receiver<bool>)>>,
 
     pub(crate) chan_candidate_tx: ChanCandidateTx,
-    pub(crate) chan_candidate_pair_tx: Mutex<Option<mpsc::Sender<()>>>,
+    pub(crate) chan_candidate_pair_tx: Mutex<Option<mpsc::Sender<SelectedPairChange>>>,
     pub(crate) chan_state_tx: Mutex<Option<mpsc::Sender<ConnectionState>>>,
+    pub(crate) chan_regather_tx: Mutex<Option<mpsc::Sender<ConnectionState>>>,
 
     pub(crate) on_connection_state_change_hdlr: ArcSwapOption<Mutex<OnConnectionStateChangeHdlrFn>>,
     pub(crate) on_selected_candidate_pair_change_hdlr:
         ArcSwapOption<Mutex<OnSelectedCandidatePairChangeHdlrFn>>,
     pub(crate) on_candidate_hdlr: ArcSwapOption<Mutex<OnCandidateHdlrFn>>,
+    pub(crate) on_role_change_hdlr: ArcSwapOption<Mutex<OnRoleChangeHdlrFn>>,
 
     pub(crate) tie_breaker: AtomicU64,
     pub(crate) is_controlling: AtomicBool,
+    pub(crate) fixed_role: bool,
     pub(crate) lite: AtomicBool,
 
     pub(crate) start_time: SyncMutex<Instant>,
     pub(crate) nominated_pair: Mutex<Option<Arc<CandidatePair>>>,
+    pub(crate) selected_pair_time: SyncMutex<Instant>,
 
     pub(crate) connection_state: AtomicU8, //ConnectionState,
 
@@ -79,6 +88,20 @@ pub struct AgentInternal {
     pub(crate) keepalive_interval: Duration,
     // How often should we run our internal taskLoop to check for state changes when connecting
     pub(crate) check_interval: Duration,
+    // How long to wait for a single STUN/TURN server while gathering
+    pub(crate) gather_timeout: Duration,
+    pub(crate) server_gather_timeouts: HashMap<String, Duration>,
+    // Switch the selected pair to a valid pair with a priority at least this factor higher
+    pub(crate) pair_switch_threshold: Option<f64>,
+    // How long a pair stays selected before switching away from it
+    pub(crate) pair_switch_min_interval: Duration,
+    pub(crate) nomination_mode: NominationMode,
+    pub(crate) type_preferences: HashMap<CandidateType, u16>,
+    pub(crate) local_preference_fn: Arc<Option<LocalPreferenceFn>>,
+    pub(crate) foundation_fn: Arc<Option<FoundationFn>>,
+    pub(crate) socket_factory: Option<Arc<dyn SocketFactory + Send + Sync>>,
+    pub(crate) resolver: Option<Arc<dyn Resolver + Send + Sync>>,
+    pub(crate) component: u16,
 }
 
 impl AgentInternal {
@@ -90,6 +113,12 @@ impl AgentInternal {
         let (done_tx, done_rx) = mpsc::channel(1);
         let (force_candidate_contact_tx, force_candidate_contact_rx) = mpsc::channel(1);
         let (started_ch_tx, _) = broadcast::channel(1);
+        let (chan_regather_tx, chan_regather_rx) = if config.regather_interval.is_some() {
+            let (tx, rx) = mpsc::channel(2);
+            (Some(tx), Some(rx))
+        } else {
+            (None, None)
+        };
 
         let ai = AgentInternal {
             on_connected_tx: Mutex::new(Some(on_connected_tx)),
@@ -105,17 +134,21 @@ impl AgentInternal {
             chan_candidate_tx: Arc::new(Mutex::new(Some(chan_candidate_tx))),
             chan_candidate_pair_tx: Mutex::new(Some(chan_candidate_pair_tx)),
             chan_state_tx: Mutex::new(Some(chan_state_tx)),
+            chan_regather_tx: Mutex::new(chan_regather_tx),
 
             on_connection_state_change_hdlr: ArcSwapOption::empty(),
             on_selected_candidate_pair_change_hdlr: ArcSwapOption::empty(),
             on_candidate_hdlr: ArcSwapOption::empty(),
+            on_role_change_hdlr: ArcSwapOption::empty(),
 
             tie_breaker: AtomicU64::new(rand::random::<u64>()),
             is_controlling: AtomicBool::new(config.is_controlling),
+            fixed_role: config.fixed_role,
             lite: AtomicBool::new(config.lite),
 
             start_time: SyncMutex::new(Instant::now()),
             nominated_pair: Mutex::new(None),
+            selected_pair_time: SyncMutex::new(Instant::now()),
 
             connection_state: AtomicU8::new(ConnectionState::New as u8),
 
@@ -145,6 +178,23 @@ impl AgentInternal {
             // How often should we run our internal taskLoop to check for state changes when connecting
             check_interval: Duration::from_secs(0),
 
+            // How long to wait for a single STUN/TURN server while gathering
+            gather_timeout: Duration::from_secs(0),
+            server_gather_timeouts: config.server_gather_timeouts.clone(),
+            pair_switch_threshold: config.pair_switch_threshold,
+            nomination_mode: config.nomination_mode,
+            type_preferences: config.type_preferences.clone(),
+            local_preference_fn: Arc::clone(&config.local_preference_fn),
+            foundation_fn: Arc::clone(&config.foundation_fn),
+            pair_switch_min_interval: Duration::from_secs(0),
+            socket_factory: config.socket_factory.clone(),
+            resolver: config.resolver.clone(),
+            component: if config.component == 0 {
+                COMPONENT_RTP
+            } else {
+                config.component
+            },
+
             ufrag_pwd: Mutex::new(UfragPwd::default()),
 
             local_candidates: Mutex::new(HashMap::new()),
@@ -161,6 +211,7 @@ impl AgentInternal {
             chan_state_rx,
             chan_candidate_rx,
             chan_candidate_pair_rx,
+            chan_regather_rx,
         };
         (ai, chan_receivers)
     }
@@ -302,9 +353,15 @@ impl AgentInternal {
 
     pub(crate) async fn update_connection_state(&self, new_state: ConnectionState) {
         if self.connection_state.load(Ordering::SeqCst) != new_state as u8 {
-            // Connection has gone to failed, release all gathered candidates
+            // Connection has gone to failed, release all gathered candidates. The remote
+            // candidates are kept if the checklist is rebuilt by re-gathering.
+            let regather = self.chan_regather_tx.lock().await.is_some();
             if new_state == ConnectionState::Failed {
-                self.delete_all_candidates().await;
+                if regather {
+                    self.delete_local_candidates().await;
+                } else {
+                    self.delete_all_candidates().await;
+                }
             }
 
             log::info!(
@@ -315,6 +372,15 @@ impl AgentInternal {
             self.connection_state
                 .store(new_state as u8, Ordering::SeqCst);
 
+            if regather
+                && (new_state == ConnectionState::Failed || new_state == ConnectionState::Connected)
+            {
+                let chan_regather_tx = self.chan_regather_tx.lock().await;
+                if let Some(tx) = &*chan_regather_tx {
+                    let _ = tx.try_send(new_state);
+                }
+            }
+
             // Call handler after finishing current task since we may be holding the agent lock
             // and the handler may also require it
             {
@@ -335,7 +401,17 @@ impl AgentInternal {
 
         if let Some(p) = p {
             p.nominated.store(true, Ordering::SeqCst);
-            self.agent_conn.selected_pair.store(Some(p));
+            let previous = self.agent_conn.selected_pair.swap(Some(Arc::clone(&p)));
+            *self.selected_pair_time.lock() = Instant::now();
+
+            if let Some(previous) = &previous {
+                log::debug!(
+                    "[{}]: Switched selected candidate pair from {} to {}",
+                    self.get_name(),
+                    previous,
+                    p
+                );
+            }
 
             self.update_connection_state(ConnectionState::Connected)
                 .await;
@@ -344,7 +420,7 @@ impl AgentInternal {
             {
                 let chan_candidate_pair_tx = self.chan_candidate_pair_tx.lock().await;
                 if let Some(tx) = &*chan_candidate_pair_tx {
-                    let _ = tx.send(()).await;
+                    let _ = tx.send((previous, p)).await;
                 }
             }
 
@@ -543,25 +619,68 @@ impl AgentInternal {
             }
         }
 
-        for cand in local_cands {
-            self.add_pair(cand, c.clone()).await;
+        for cand in &local_cands {
+            if !shares_host_socket(cand, &local_cands) {
+                self.add_pair(Arc::clone(cand), c.clone()).await;
+            }
         }
 
         self.request_connectivity_check();
     }
 
+    /// Applies the configured type/local preferences and foundation to a local candidate.
+    pub(crate) fn apply_candidate_preferences(&self, c: &Arc<dyn Candidate + Send + Sync>) {
+        if !self.type_preferences.is_empty() || self.local_preference_fn.is_some() {
+            let type_preference = self
+                .type_preferences
+                .get(&c.candidate_type())
+                .copied()
+                .unwrap_or_else(|| c.candidate_type().preference());
+            let local_preference = (*self.local_preference_fn)
+                .as_ref()
+                .and_then(|f| f(&**c))
+                .unwrap_or(((c.priority() >> 8) & 0xFFFF) as u16);
+
+            c.set_priority(
+                (1 << 24) * u32::from(type_preference)
+                    + (1 << 8) * u32::from(local_preference)
+                    + (256 - u32::from(c.component())),
+            );
+        }
+
+        if let Some(foundation) = (*self.foundation_fn).as_ref().and_then(|f| f(&**c)) {
+            c.set_foundation(foundation);
+        }
+    }
+
     pub(crate) async fn add_candidate(
         self: &Arc<Self>,
         c: &Arc<dyn Candidate + Send + Sync>,
     ) -> Result<()> {
-        let initialized_ch = {
-            let started_ch_tx = self.started_ch_tx.lock().await;
-            (*started_ch_tx).as_ref().map(|tx| tx.subscribe())
+        self.apply_candidate_preferences(c);
+
+        let network_type = c.network_type();
+
+        let shares_base = {
+            let local_candidates = self.local_candidates.lock().await;
+            local_candidates
+                .get(&network_type)
+                .map_or(false, |cands| shares_host_socket(c, cands))
         };
 
-        self.start_candidate(c, initialized_ch).await;
+        if shares_base {
+            // Let close work as for the other candidates, the socket is closed once.
+            let (closed_ch_tx, _) = broadcast::channel(1);
+            *c.get_closed_ch().lock().await = Some(closed_ch_tx);
+        } else {
+            let initialized_ch = {
+                let started_ch_tx = self.started_ch_tx.lock().await;
+                (*started_ch_tx).as_ref().map(|tx| tx.subscribe())
+            };
+
+            self.start_candidate(c, initialized_ch).await;
+        }
 
-        let network_type = c.network_type();
         {
             let mut local_candidates = self.local_candidates.lock().await;
             if let Some(cands) = local_candidates.get(&network_type) {
@@ -595,8 +714,10 @@ impl AgentInternal {
             }
         }
 
-        for cand in remote_cands {
-            self.add_pair(c.clone(), cand).await;
+        if !shares_base {
+            for cand in remote_cands {
+                self.add_pair(c.clone(), cand).await;
+            }
         }
 
         self.request_connectivity_check();
@@ -640,6 +761,10 @@ impl AgentInternal {
             let mut chan_state_tx = self.chan_state_tx.lock().await;
             chan_state_tx.take();
         }
+        {
+            let mut chan_regather_tx = self.chan_regather_tx.lock().await;
+            chan_regather_tx.take();
+        }
 
         self.agent_conn.done.store(true, Ordering::SeqCst);
 
@@ -651,22 +776,7 @@ impl AgentInternal {
     ///
     /// This is used for restarts, failures and on close.
     pub(crate) async fn delete_all_candidates(&self) {
-        {
-            let mut local_candidates = self.local_candidates.lock().await;
-            for cs in local_candidates.values_mut() {
-                for c in cs {
-                    if let Err(err) = c.close().await {
-                        log::warn!(
-                            "[{}]: Failed to close candidate {}: {}",
-                            self.get_name(),
-                            c,
-                            err
-                        );
-                    }
-                }
-            }
-            local_candidates.clear();
-        }
+        self.delete_local_candidates().await;
 
         {
             let mut remote_candidates = self.remote_candidates.lock().await;
@@ -686,6 +796,24 @@ impl AgentInternal {
         }
     }
 
+    /// Closes and removes the local candidates only, e.g. before re-gathering.
+    pub(crate) async fn delete_local_candidates(&self) {
+        let mut local_candidates = self.local_candidates.lock().await;
+        for cs in local_candidates.values_mut() {
+            for c in cs {
+                if let Err(err) = c.close().await {
+                    log::warn!(
+                        "[{}]: Failed to close candidate {}: {}",
+                        self.get_name(),
+                        c,
+                        err
+                    );
+                }
+            }
+        }
+        local_candidates.clear();
+    }
+
     pub(crate) async fn find_remote_candidate(
         &self,
         network_type: NetworkType,
@@ -726,6 +854,7 @@ impl AgentInternal {
                 transaction_id: m.transaction_id,
                 destination: remote.addr(),
                 is_use_candidate: m.contains(ATTR_USE_CANDIDATE),
+                is_controlling: m.contains(ATTR_ICE_CONTROLLING),
             });
         }
 
@@ -770,6 +899,170 @@ impl AgentInternal {
         }
     }
 
+    /// Answers a request with a 487 (Role Conflict) error, RFC 8445 S7.3.1.1.
+    async fn send_role_conflict(
+        &self,
+        m: &Message,
+        local: &Arc<dyn Candidate + Send + Sync>,
+        remote: &Arc<dyn Candidate + Send + Sync>,
+    ) {
+        let local_pwd = {
+            let ufrag_pwd = self.ufrag_pwd.lock().await;
+            ufrag_pwd.local_pwd.clone()
+        };
+
+        let (out, result) = {
+            let mut out = Message::new();
+            let result = out.build(&[
+                Box::new(m.clone()),
+                Box::new(MessageType::new(METHOD_BINDING, CLASS_ERROR_RESPONSE)),
+                Box::new(CODE_ROLE_CONFLICT),
+                Box::new(MessageIntegrity::new_short_term_integrity(local_pwd)),
+                Box::new(FINGERPRINT),
+            ]);
+            (out, result)
+        };
+
+        if let Err(err) = result {
+            log::warn!(
+                "[{}]: Failed to build role conflict error from: {} to: {} error: {}",
+                self.get_name(),
+                local,
+                remote,
+                err
+            );
+        } else {
+            self.send_stun(&out, local, remote).await;
+        }
+    }
+
+    /// Switches the role of the agent and restarts the selection with it.
+    pub(crate) async fn switch_role(&self, is_controlling: bool) {
+        if self.is_controlling.swap(is_controlling, Ordering::SeqCst) == is_controlling {
+            return;
+        }
+
+        let role = if is_controlling {
+            Role::Controlling
+        } else {
+            Role::Controlled
+        };
+        log::debug!("[{}]: switching role to {}", self.get_name(), role);
+
+        {
+            let checklist = self.agent_conn.checklist.lock().await;
+            for p in &*checklist {
+                p.ice_role_controlling
+                    .store(is_controlling, Ordering::SeqCst);
+            }
+        }
+        self.start().await;
+
+        if let Some(handler) = &*self.on_role_change_hdlr.load() {
+            let handler = Arc::clone(handler);
+            tokio::spawn(async move {
+                let mut f = handler.lock().await;
+                f(role).await;
+            });
+        }
+    }
+
+    /// Resolves a role conflict raised by an inbound request, RFC 8445 S7.3.1.1. Returns true if
+    /// the request should be processed.
+    async fn handle_role_conflict(
+        &self,
+        m: &Message,
+        local: &Arc<dyn Candidate + Send + Sync>,
+        remote: &Arc<dyn Candidate + Send + Sync>,
+    ) -> bool {
+        let is_controlling = self.is_controlling.load(Ordering::SeqCst);
+        let remote_tie_breaker = if is_controlling {
+            let mut attr = AttrControlling::default();
+            if attr.get_from(m).is_err() {
+                return true;
+            }
+            attr.0
+        } else {
+            let mut attr = AttrControlled::default();
+            if attr.get_from(m).is_err() {
+                return true;
+            }
+            attr.0
+        };
+
+        let local_tie_breaker = self.tie_breaker.load(Ordering::SeqCst);
+        // A lite agent cannot take the controlling role, RFC 8445 S6.1.1
+        let keep_role = if self.fixed_role || self.lite.load(Ordering::SeqCst) {
+            true
+        } else if is_controlling {
+            local_tie_breaker >= remote_tie_breaker
+        } else {
+            local_tie_breaker < remote_tie_breaker
+        };
+
+        if keep_role {
+            log::debug!(
+                "[{}]: role conflict with {}, keeping the current role",
+                self.get_name(),
+                remote
+            );
+            self.send_role_conflict(m, local, remote).await;
+            false
+        } else {
+            self.switch_role(!is_controlling).await;
+            true
+        }
+    }
+
+    /// Handles a 487 (Role Conflict) error for one of our requests, RFC 8445 S7.2.5.1.
+    async fn handle_error_response(
+        &self,
+        m: &Message,
+        local: &Arc<dyn Candidate + Send + Sync>,
+        remote: &Arc<dyn Candidate + Send + Sync>,
+    ) {
+        let mut error_code = ErrorCodeAttribute::default();
+        if error_code.get_from(m).is_err() || error_code.code != CODE_ROLE_CONFLICT {
+            log::trace!(
+                "[{}]: unhandled STUN error response from {}",
+                self.get_name(),
+                remote
+            );
+            return;
+        }
+
+        let pending_request = match self.handle_inbound_binding_success(m.transaction_id).await {
+            Some(pending_request) => pending_request,
+            None => {
+                log::warn!(
+                    "[{}]: discard role conflict from ({}), unknown TransactionID 0x{:?}",
+                    self.get_name(),
+                    remote,
+                    m.transaction_id
+                );
+                return;
+            }
+        };
+
+        // Only switch if the role has not changed since the request was sent
+        let is_controlling = self.is_controlling.load(Ordering::SeqCst);
+        if pending_request.is_controlling != is_controlling {
+            return;
+        }
+
+        if self.fixed_role {
+            log::warn!(
+                "[{}]: role conflict reported by {}, but the role is fixed",
+                self.get_name(),
+                remote
+            );
+            return;
+        }
+
+        self.switch_role(!is_controlling).await;
+        self.ping_candidate(local, remote).await;
+    }
+
     /// Removes pending binding requests that are over `maxBindingRequestTimeout` old Let HTO be the
     /// transaction timeout, which SHOULD be 2*RTT if RTT is known or 500 ms otherwise.
     ///
@@ -828,6 +1121,7 @@ impl AgentInternal {
     ) {
         if m.typ.method != METHOD_BINDING
             || !(m.typ.class == CLASS_SUCCESS_RESPONSE
+                || m.typ.class == CLASS_ERROR_RESPONSE
                 || m.typ.class == CLASS_REQUEST
                 || m.typ.class == CLASS_INDICATION)
         {
@@ -842,13 +1136,17 @@ impl AgentInternal {
             return;
         }
 
+        // Role conflicts in requests are resolved once the request is authenticated
+        let is_request = m.typ.class == CLASS_REQUEST;
         if self.is_controlling.load(Ordering::SeqCst) {
             if m.contains(ATTR_ICE_CONTROLLING) {
-                log::debug!(
-                    "[{}]: inbound isControlling && a.isControlling == true",
-                    self.get_name(),
-                );
-                return;
+                if !is_request {
+                    log::debug!(
+                        "[{}]: inbound isControlling && a.isControlling == true",
+                        self.get_name(),
+                    );
+                    return;
+                }
             } else if m.contains(ATTR_USE_CANDIDATE) {
                 log::debug!(
                     "[{}]: useCandidate && a.isControlling == true",
@@ -856,7 +1154,7 @@ impl AgentInternal {
                 );
                 return;
             }
-        } else if m.contains(ATTR_ICE_CONTROLLED) {
+        } else if m.contains(ATTR_ICE_CONTROLLED) && !is_request {
             log::debug!(
                 "[{}]: inbound isControlled && a.isControlling == false",
                 self.get_name(),
@@ -893,6 +1191,32 @@ impl AgentInternal {
                 );
                 return;
             }
+        } else if m.typ.class == CLASS_ERROR_RESPONSE {
+            {
+                let ufrag_pwd = self.ufrag_pwd.lock().await;
+                if let Err(err) =
+                    assert_inbound_message_integrity(m, ufrag_pwd.remote_pwd.as_bytes())
+                {
+                    log::warn!(
+                        "[{}]: discard message from ({}), {}",
+                        self.get_name(),
+                        remote,
+                        err
+                    );
+                    return;
+                }
+            }
+
+            if let Some(rc) = &remote_candidate {
+                self.handle_error_response(m, local, rc).await;
+            } else {
+                log::warn!(
+                    "[{}]: discard error message from ({}), no such remote",
+                    self.get_name(),
+                    remote
+                );
+                return;
+            }
         } else if m.typ.class == CLASS_REQUEST {
             {
                 let ufrag_pwd = self.ufrag_pwd.lock().await;
@@ -920,7 +1244,7 @@ impl AgentInternal {
             }
 
             if remote_candidate.is_none() {
-                let (ip, port, network_type) = (remote.ip(), remote.port(), NetworkType::Udp4);
+                let (ip, port, network_type) = (remote.ip(), remote.port(), local.network_type());
 
                 let prflx_candidate_config = CandidatePeerReflexiveConfig {
                     base_config: CandidateBaseConfig {
@@ -964,7 +1288,9 @@ impl AgentInternal {
             );
 
             if let Some(rc) = &remote_candidate {
-                self.handle_binding_request(m, local, rc).await;
+                if self.handle_role_conflict(m, local, rc).await {
+                    self.handle_binding_request(m, local, rc).await;
+                }
             }
         }
 
@@ -1053,19 +1379,16 @@ impl AgentInternal {
         self: &Arc<Self>,
         mut chan_state_rx: mpsc::Receiver<ConnectionState>,
         mut chan_candidate_rx: mpsc::Receiver<Option<Arc<dyn Candidate + Send + Sync>>>,
-        mut chan_candidate_pair_rx: mpsc::Receiver<()>,
+        mut chan_candidate_pair_rx: mpsc::Receiver<SelectedPairChange>,
     ) {
         let ai = Arc::clone(self);
         tokio::spawn(async move {
             // CandidatePair and ConnectionState are usually changed at once.
             // Blocking one by the other one causes deadlock.
-            while chan_candidate_pair_rx.recv().await.is_some() {
-                if let (Some(cb), Some(p)) = (
-                    &*ai.on_selected_candidate_pair_change_hdlr.load(),
-                    &*ai.agent_conn.selected_pair.load(),
-                ) {
+            while let Some((previous, p)) = chan_candidate_pair_rx.recv().await {
+                if let Some(cb) = &*ai.on_selected_candidate_pair_change_hdlr.load() {
                     let mut f = cb.lock().await;
-                    f(&p.local, &p.remote).await;
+                    f(previous.as_ref(), &p).await;
                 }
             }
         });
@@ -1156,6 +1479,33 @@ impl AgentInternal {
         addr: SocketAddr,
     ) {
         if stun::message::is_message(buf) {
+            // Only Binding messages are handled, look at the type in place before
+            // copying the packet into a Message
+            match MessageView::parse(buf) {
+                Ok(view) if view.typ.method != METHOD_BINDING => {
+                    log::trace!(
+                        "[{}]: unhandled STUN from {} to {} class({}) method({})",
+                        self.get_name(),
+                        src_addr,
+                        addr,
+                        view.typ.class,
+                        view.typ.method
+                    );
+                    return;
+                }
+                Err(err) => {
+                    log::warn!(
+                        "[{}]: Failed to handle decode ICE from {} to {}: {}",
+                        self.get_name(),
+                        addr,
+                        src_addr,
+                        err
+                    );
+                    return;
+                }
+                _ => {}
+            }
+
             let mut m = Message {
                 raw: vec![],
                 ..Message::default()
@@ -1186,6 +1536,16 @@ impl AgentInternal {
         }
     }
 
+    /// Returns the gathering deadline for the given STUN/TURN server, falling back to the
+    /// agent-wide `gather_timeout` if no per-server deadline is configured.
+    pub(crate) fn gather_timeout_for(&self, url: &Url) -> Duration {
+        let host_port = format!("{}:{}", url.host, url.port);
+        self.server_gather_timeouts
+            .get(&host_port)
+            .copied()
+            .unwrap_or(self.gather_timeout)
+    }
+
     pub(crate) fn get_name(&self) -> &str {
         if self.is_controlling.load(Ordering::SeqCst) {
             "controlling"
@@ -1194,3 +1554,25 @@ impl AgentInternal {
         }
     }
 }
+
+/// shares_host_socket tells whether c is a srflx candidate reading the socket of one of the
+/// host candidates, as on a muxed socket. It is only advertised: the host candidate reads the
+/// socket, and it is the base which replaces c in the checklist (RFC 8445, section 6.1.2.4).
+fn shares_host_socket(
+    c: &Arc<dyn Candidate + Send + Sync>,
+    cands: &[Arc<dyn Candidate + Send + Sync>],
+) -> bool {
+    if c.candidate_type() != CandidateType::ServerReflexive {
+        return false;
+    }
+    let conn = match c.get_conn() {
+        Some(conn) => Arc::as_ptr(conn) as *const u8,
+        None => return false,
+    };
+    cands.iter().any(|cand| {
+        cand.candidate_type() == CandidateType::Host
+            && cand
+                .get_conn()
+                .map_or(false, |base| Arc::as_ptr(base) as *const u8 == conn)
+    })
+}
@@ -1,3 +1,4 @@
+use crate::agent::agent_config::NominationMode;
 use crate::agent::agent_internal::*;
 use crate::candidate::*;
 use crate::control::*;
@@ -154,6 +155,120 @@ impl AgentInternal {
         }
     }
 
+    /// Returns true if `candidate` is enough of an improvement over the `selected` pair to switch
+    /// to it, as configured by `pair_switch_threshold`.
+    fn is_pair_switch_worthwhile(
+        &self,
+        selected: &CandidatePair,
+        candidate: &CandidatePair,
+    ) -> bool {
+        if let Some(threshold) = self.pair_switch_threshold {
+            selected != candidate
+                && candidate.priority() as f64 >= selected.priority() as f64 * threshold
+        } else {
+            false
+        }
+    }
+
+    /// Returns true if a pair that has not finished its checks yet exceeds the switch threshold
+    /// over the selected pair.
+    async fn has_pending_worthwhile_pair(&self, selected: &CandidatePair) -> bool {
+        let checklist = self.agent_conn.checklist.lock().await;
+        checklist.iter().any(|p| {
+            let state = p.state.load(Ordering::SeqCst);
+            (state == CandidatePairState::Waiting as u8
+                || state == CandidatePairState::InProgress as u8)
+                && self.is_pair_switch_worthwhile(selected, p)
+        })
+    }
+
+    /// Returns true if the nominated pair `p` should become the selected pair.
+    fn should_select_nominated_pair(&self, p: &CandidatePair) -> bool {
+        match self.agent_conn.get_selected_pair() {
+            None => true,
+            Some(selected) if *selected == *p => false,
+            // The controlling agent switched to another pair
+            Some(_) if self.pair_switch_threshold.is_some() => true,
+            // With aggressive nomination every checked pair is nominated, keep the best one
+            Some(selected) => {
+                self.nomination_mode == NominationMode::Aggressive
+                    && p.priority() > selected.priority()
+            }
+        }
+    }
+
+    /// Keeps checking the remaining pairs while connected and nominates a valid pair which is
+    /// significantly better than the selected one, once the selected pair has been used for at
+    /// least `pair_switch_min_interval`.
+    async fn switch_selected_pair_if_better(&self) {
+        if self.pair_switch_threshold.is_none() {
+            return;
+        }
+        let selected = match self.agent_conn.get_selected_pair() {
+            Some(selected) => selected,
+            None => return,
+        };
+
+        // Only keep checking while a pending pair could still beat the selected one
+        if self.has_pending_worthwhile_pair(&selected).await {
+            self.ping_all_candidates().await;
+        }
+
+        let nominated_pair = {
+            let nominated_pair = self.nominated_pair.lock().await;
+            nominated_pair.clone()
+        };
+        if let Some(nominated_pair) = nominated_pair {
+            if *nominated_pair != *selected {
+                // A switch is in progress, retry the nomination until it succeeds or we give up
+                if nominated_pair
+                    .binding_request_count
+                    .fetch_add(1, Ordering::SeqCst)
+                    > self.max_binding_requests
+                {
+                    log::debug!(
+                        "[{}]: max requests reached while switching to pair {}, keeping {}",
+                        self.get_name(),
+                        nominated_pair,
+                        selected
+                    );
+                    nominated_pair
+                        .state
+                        .store(CandidatePairState::Failed as u8, Ordering::SeqCst);
+                    let mut nominated_pair = self.nominated_pair.lock().await;
+                    *nominated_pair = Some(selected);
+                } else {
+                    self.nominate_pair().await;
+                }
+                return;
+            }
+        }
+
+        if self.selected_pair_time.lock().elapsed() < self.pair_switch_min_interval {
+            return;
+        }
+
+        if let Some(best_pair) = self.agent_conn.get_best_valid_candidate_pair().await {
+            if self.is_pair_switch_worthwhile(&selected, &best_pair)
+                && self.is_nominatable(&best_pair.local)
+                && self.is_nominatable(&best_pair.remote)
+            {
+                log::debug!(
+                    "[{}]: pair {} is better than the selected pair {}, nominating it",
+                    self.get_name(),
+                    best_pair,
+                    selected
+                );
+                best_pair.binding_request_count.store(0, Ordering::SeqCst);
+                {
+                    let mut nominated_pair = self.nominated_pair.lock().await;
+                    *nominated_pair = Some(best_pair);
+                }
+                self.nominate_pair().await;
+            }
+        }
+    }
+
     pub(crate) async fn start(&self) {
         if self.is_controlling.load(Ordering::SeqCst) {
             ControllingSelector::start(self).await;
@@ -236,6 +351,7 @@ impl ControllingSelector for AgentInternal {
             if self.validate_selected_pair().await {
                 log::trace!("[{}]: checking keepalive", self.get_name());
                 self.check_keepalive().await;
+                self.switch_selected_pair_if_better().await;
             }
         } else if nominated_pair_is_some {
             self.nominate_pair().await;
@@ -276,18 +392,28 @@ impl ControllingSelector for AgentInternal {
         let (msg, result) = {
             let ufrag_pwd = self.ufrag_pwd.lock().await;
             let username = ufrag_pwd.remote_ufrag.clone() + ":" + ufrag_pwd.local_ufrag.as_str();
-            let mut msg = Message::new();
-            let result = msg.build(&[
+            let mut setters: Vec<Box<dyn Setter>> = vec![
                 Box::new(BINDING_REQUEST),
                 Box::new(TransactionId::new()),
                 Box::new(Username::new(ATTR_USERNAME, username)),
-                Box::new(AttrControlling(self.tie_breaker.load(Ordering::SeqCst))),
-                Box::new(PriorityAttr(local.priority())),
-                Box::new(MessageIntegrity::new_short_term_integrity(
-                    ufrag_pwd.remote_pwd.clone(),
-                )),
-                Box::new(FINGERPRINT),
-            ]);
+            ];
+            // With aggressive nomination every check nominates its pair until one is selected
+            if self.nomination_mode == NominationMode::Aggressive
+                && self.agent_conn.get_selected_pair().is_none()
+            {
+                setters.push(Box::new(UseCandidateAttr::new()));
+            }
+            setters.push(Box::new(AttrControlling(
+                self.tie_breaker.load(Ordering::SeqCst),
+            )));
+            setters.push(Box::new(PriorityAttr(local.priority())));
+            setters.push(Box::new(MessageIntegrity::new_short_term_integrity(
+                ufrag_pwd.remote_pwd.clone(),
+            )));
+            setters.push(Box::new(FINGERPRINT));
+
+            let mut msg = Message::new();
+            let result = msg.build(&setters);
             (msg, result)
         };
 
@@ -332,8 +458,30 @@ impl ControllingSelector for AgentInternal {
                     pending_request.is_use_candidate,
                     selected_pair_is_none
                 );
-                if pending_request.is_use_candidate && selected_pair_is_none {
-                    self.set_selected_pair(Some(Arc::clone(&p))).await;
+                if pending_request.is_use_candidate {
+                    if selected_pair_is_none {
+                        self.set_selected_pair(Some(Arc::clone(&p))).await;
+                    } else if self.nomination_mode == NominationMode::Aggressive
+                        && self.pair_switch_threshold.is_none()
+                    {
+                        // A later check of a better pair finished after the first selection
+                        if self.should_select_nominated_pair(&p) {
+                            self.set_selected_pair(Some(Arc::clone(&p))).await;
+                        }
+                    } else if self.pair_switch_threshold.is_some() {
+                        // Complete a switch started by switch_selected_pair_if_better
+                        let is_switching = {
+                            let nominated_pair = self.nominated_pair.lock().await;
+                            nominated_pair.as_ref().map_or(false, |n| **n == *p)
+                        };
+                        let is_selected = self
+                            .agent_conn
+                            .get_selected_pair()
+                            .map_or(false, |selected| *selected == *p);
+                        if is_switching && !is_selected {
+                            self.set_selected_pair(Some(Arc::clone(&p))).await;
+                        }
+                    }
                 }
             } else {
                 // This shouldn't happen
@@ -483,6 +631,20 @@ impl ControlledSelector for AgentInternal {
                 p.state
                     .store(CandidatePairState::Succeeded as u8, Ordering::SeqCst);
                 log::trace!("Found valid candidate pair: {}", p);
+
+                // The triggered check of a nominated pair succeeded, RFC 8445 S7.3.1.5
+                if p.nominated.load(Ordering::SeqCst) {
+                    let should_select = match self.agent_conn.get_selected_pair() {
+                        None => true,
+                        Some(selected) => {
+                            self.nomination_mode == NominationMode::Aggressive
+                                && p.priority() > selected.priority()
+                        }
+                    };
+                    if should_select {
+                        self.set_selected_pair(Some(Arc::clone(&p))).await;
+                    }
+                }
             } else {
                 // This shouldn't happen
                 log::error!("Success response from invalid candidate pair");
@@ -508,7 +670,17 @@ impl ControlledSelector for AgentInternal {
 
         if let Some(p) = self.find_pair(local, remote).await {
             let use_candidate = m.contains(ATTR_USE_CANDIDATE);
-            if use_candidate {
+            if self.lite.load(Ordering::SeqCst) {
+                // https://tools.ietf.org/html/rfc8445#section-7.3.1.5
+                // A lite agent never sends checks of its own, so every pair it has answered
+                // is considered valid and a nomination from the full agent selects it directly.
+                p.state
+                    .store(CandidatePairState::Succeeded as u8, Ordering::SeqCst);
+                if use_candidate && self.agent_conn.get_selected_pair().is_none() {
+                    self.set_selected_pair(Some(Arc::clone(&p))).await;
+                }
+                self.send_binding_success(m, local, remote).await;
+            } else if use_candidate {
                 // https://tools.ietf.org/html/rfc8445#section-7.3.1.5
 
                 if p.state.load(Ordering::SeqCst) == CandidatePairState::Succeeded as u8 {
@@ -516,7 +688,8 @@ impl ControlledSelector for AgentInternal {
                     // previously sent by this pair produced a successful response and
                     // generated a valid pair (Section 7.2.5.3.2).  The agent sets the
                     // nominated flag value of the valid pair to true.
-                    if self.agent_conn.get_selected_pair().is_none() {
+                    p.nominated.store(true, Ordering::SeqCst);
+                    if self.should_select_nominated_pair(&p) {
                         self.set_selected_pair(Some(Arc::clone(&p))).await;
                     }
                     self.send_binding_success(m, local, remote).await;
@@ -529,6 +702,7 @@ impl ControlledSelector for AgentInternal {
                     // MUST remove the candidate pair from the valid list, set the
                     // candidate pair state to Failed, and set the checklist state to
                     // Failed.
+                    p.nominated.store(true, Ordering::SeqCst);
                     self.ping_candidate(local, remote).await;
                 }
             } else {
@@ -5,12 +5,14 @@ use crate::candidate::candidate_host::*;
 use crate::candidate::candidate_peer_reflexive::*;
 use crate::candidate::candidate_relay::*;
 use crate::candidate::candidate_server_reflexive::*;
-use crate::control::AttrControlling;
+use crate::control::{AttrControlled, AttrControlling, Role};
 use crate::priority::PriorityAttr;
+use crate::udp_mux::{UDPMux, UDPMuxDefault, UDPMuxParams};
 use crate::use_candidate::UseCandidateAttr;
 
 use crate::agent::agent_transport_test::pipe;
 use async_trait::async_trait;
+use std::collections::HashSet;
 use std::net::Ipv4Addr;
 use std::ops::Sub;
 use std::str::FromStr;
@@ -337,6 +339,7 @@ async fn test_handle_peer_reflexive_unknown_remote() -> Result<()> {
                 transaction_id: tid,
                 destination: SocketAddr::from_str("0.0.0.0:0")?,
                 is_use_candidate: false,
+                ..Default::default()
             }];
         }
         let ufrag_pwd = a.internal.ufrag_pwd.lock().await;
@@ -1927,6 +1930,96 @@ async fn test_agent_restart_both_side() -> Result<()> {
     Ok(())
 }
 
+// RecordingUDPMux tracks the ufrags the agent holds a muxed connection for.
+struct RecordingUDPMux {
+    udp_mux: Arc<UDPMuxDefault>,
+    ufrags: Mutex<HashSet<String>>,
+}
+
+#[async_trait]
+impl UDPMux for RecordingUDPMux {
+    async fn close(&self) -> std::result::Result<(), util::Error> {
+        self.udp_mux.close().await
+    }
+
+    async fn get_conn(
+        self: Arc<Self>,
+        ufrag: &str,
+    ) -> std::result::Result<Arc<dyn Conn + Send + Sync>, util::Error> {
+        let conn = Arc::clone(&self.udp_mux).get_conn(ufrag).await?;
+        self.ufrags.lock().await.insert(ufrag.to_owned());
+        Ok(conn)
+    }
+
+    async fn remove_conn_by_ufrag(&self, ufrag: &str) {
+        self.udp_mux.remove_conn_by_ufrag(ufrag).await;
+        self.ufrags.lock().await.remove(ufrag);
+    }
+}
+
+#[tokio::test]
+async fn test_agent_restart_releases_muxed_conn() -> Result<()> {
+    let udp_socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+    let udp_mux = Arc::new(RecordingUDPMux {
+        udp_mux: UDPMuxDefault::new(UDPMuxParams::new(udp_socket)),
+        ufrags: Mutex::new(HashSet::new()),
+    });
+
+    let lan = Arc::new(Mutex::new(router::Router::new(router::RouterConfig {
+        cidr: "10.0.0.0/24".to_owned(),
+        ..Default::default()
+    })?));
+    let nw = Arc::new(net::Net::new(Some(net::NetConfig {
+        static_ips: vec!["10.0.0.1".to_owned()],
+        ..Default::default()
+    })));
+    connect_net2router(&nw, &lan).await?;
+
+    let agent = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Udp4],
+        net: Some(nw),
+        udp_network: UDPNetwork::Muxed(Arc::clone(&udp_mux) as Arc<dyn UDPMux + Send + Sync>),
+        ..Default::default()
+    })
+    .await?;
+
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
+    agent.on_candidate(Box::new(
+        move |c: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let done_tx = done_tx.clone();
+            Box::pin(async move {
+                if c.is_none() {
+                    let _ = done_tx.send(()).await;
+                }
+            })
+        },
+    ));
+
+    agent.gather_candidates()?;
+    let _ = done_rx.recv().await;
+
+    let (old_ufrag, _) = agent.get_local_user_credentials().await;
+    assert!(udp_mux.ufrags.lock().await.contains(&old_ufrag));
+
+    agent.restart("".to_owned(), "".to_owned()).await?;
+    assert!(
+        !udp_mux.ufrags.lock().await.contains(&old_ufrag),
+        "the muxed conn of the old ufrag must be released on restart"
+    );
+
+    agent.gather_candidates()?;
+    let _ = done_rx.recv().await;
+
+    let (new_ufrag, _) = agent.get_local_user_credentials().await;
+    assert_ne!(old_ufrag, new_ufrag);
+    let ufrags = udp_mux.ufrags.lock().await.clone();
+    assert_eq!(ufrags, HashSet::from([new_ufrag]));
+
+    agent.close().await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_get_remote_credentials() -> Result<()> {
     let a = Agent::new(AgentConfig::default()).await?;
@@ -2092,7 +2185,7 @@ async fn test_run_task_in_selected_candidate_pair_change_callback() -> Result<()
     let (is_tested_tx, mut is_tested_rx) = mpsc::channel::<()>(1);
     let is_tested_tx = Arc::new(Mutex::new(Some(is_tested_tx)));
     a_agent.on_selected_candidate_pair_change(Box::new(
-        move |_: &Arc<dyn Candidate + Send + Sync>, _: &Arc<dyn Candidate + Send + Sync>| {
+        move |_: Option<&Arc<CandidatePair>>, _: &Arc<CandidatePair>| {
             let is_tested_tx_clone = Arc::clone(&is_tested_tx);
             Box::pin(async move {
                 let mut tx = is_tested_tx_clone.lock().await;
@@ -2200,3 +2293,502 @@ async fn test_lite_lifecycle() -> Result<()> {
 
     Ok(())
 }
+
+fn new_test_pair(
+    local_address: &str,
+    remote_address: &str,
+    controlling: bool,
+) -> Result<Arc<CandidatePair>> {
+    let local_config = CandidateHostConfig {
+        base_config: CandidateBaseConfig {
+            network: "udp".to_owned(),
+            address: local_address.to_owned(),
+            port: 19216,
+            component: 1,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let remote_config = CandidateRelayConfig {
+        base_config: CandidateBaseConfig {
+            network: "udp".to_owned(),
+            address: remote_address.to_owned(),
+            port: 12340,
+            component: 1,
+            ..Default::default()
+        },
+        rel_addr: "4.3.2.1".to_owned(),
+        rel_port: 43210,
+        ..Default::default()
+    };
+
+    Ok(Arc::new(CandidatePair::new(
+        Arc::new(local_config.new_candidate_host()?),
+        Arc::new(remote_config.new_candidate_relay()?),
+        controlling,
+    )))
+}
+
+#[tokio::test]
+async fn test_on_selected_candidate_pair_change_with_previous_pair() -> Result<()> {
+    let a = Agent::new(AgentConfig::default()).await?;
+    let (migration_tx, mut migration_rx) = mpsc::channel::<(Option<String>, String)>(2);
+    let cb: OnSelectedCandidatePairChangeHdlrFn = Box::new(move |previous, current| {
+        let migration_tx_clone = migration_tx.clone();
+        let previous = previous.map(|p| p.local.address());
+        let current = current.local.address();
+        Box::pin(async move {
+            let _ = migration_tx_clone.send((previous, current)).await;
+        })
+    });
+    a.on_selected_candidate_pair_change(cb);
+
+    let p1 = new_test_pair("192.168.1.1", "1.2.3.4", false)?;
+    let p2 = new_test_pair("192.168.1.2", "1.2.3.4", false)?;
+
+    a.internal.set_selected_pair(Some(p1)).await;
+    assert_eq!(
+        migration_rx.recv().await,
+        Some((None, "192.168.1.1".to_owned()))
+    );
+
+    a.internal.set_selected_pair(Some(p2)).await;
+    assert_eq!(
+        migration_rx.recv().await,
+        Some((Some("192.168.1.1".to_owned()), "192.168.1.2".to_owned()))
+    );
+
+    let _ = a.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pair_switch_nominates_better_pair() -> Result<()> {
+    let a = Agent::new(AgentConfig {
+        is_controlling: true,
+        pair_switch_threshold: Some(2.0),
+        pair_switch_min_interval: Some(Duration::from_secs(0)),
+        ..Default::default()
+    })
+    .await?;
+
+    let selected = new_test_pair("192.168.1.1", "1.2.3.4", true)?;
+
+    // A host remote candidate has a much higher priority than a relayed one
+    let host_config = CandidateHostConfig {
+        base_config: CandidateBaseConfig {
+            network: "udp".to_owned(),
+            address: "1.2.3.5".to_owned(),
+            port: 12340,
+            component: 1,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let better = Arc::new(CandidatePair::new(
+        Arc::clone(&selected.local),
+        Arc::new(host_config.new_candidate_host()?),
+        true,
+    ));
+    {
+        let mut checklist = a.internal.agent_conn.checklist.lock().await;
+        for p in [&selected, &better] {
+            p.state
+                .store(CandidatePairState::Succeeded as u8, Ordering::SeqCst);
+            checklist.push(Arc::clone(p));
+        }
+    }
+
+    selected.remote.seen(false);
+    a.internal
+        .set_selected_pair(Some(Arc::clone(&selected)))
+        .await;
+    {
+        let mut nominated_pair = a.internal.nominated_pair.lock().await;
+        *nominated_pair = Some(Arc::clone(&selected));
+    }
+
+    a.internal.contact_candidates().await;
+
+    let nominated_pair = a.internal.nominated_pair.lock().await.clone();
+    assert_eq!(
+        nominated_pair.as_deref(),
+        Some(&*better),
+        "the better pair should be nominated"
+    );
+    assert_eq!(
+        a.internal.agent_conn.get_selected_pair().as_deref(),
+        Some(&*selected),
+        "the selected pair should be kept until the nomination succeeds"
+    );
+
+    let _ = a.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pair_switch_disabled_by_default() -> Result<()> {
+    let a = Agent::new(AgentConfig {
+        is_controlling: true,
+        ..Default::default()
+    })
+    .await?;
+
+    let selected = new_test_pair("192.168.1.1", "1.2.3.4", true)?;
+    let other = new_test_pair("192.168.1.2", "1.2.3.5", true)?;
+    {
+        let mut checklist = a.internal.agent_conn.checklist.lock().await;
+        for p in [&selected, &other] {
+            p.state
+                .store(CandidatePairState::Succeeded as u8, Ordering::SeqCst);
+            checklist.push(Arc::clone(p));
+        }
+    }
+
+    selected.remote.seen(false);
+    a.internal
+        .set_selected_pair(Some(Arc::clone(&selected)))
+        .await;
+    {
+        let mut nominated_pair = a.internal.nominated_pair.lock().await;
+        *nominated_pair = Some(Arc::clone(&selected));
+    }
+
+    a.internal.contact_candidates().await;
+
+    let nominated_pair = a.internal.nominated_pair.lock().await.clone();
+    assert_eq!(nominated_pair.as_deref(), Some(&*selected));
+
+    let _ = a.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pair_switch_skips_pings_below_threshold() -> Result<()> {
+    let a = Agent::new(AgentConfig {
+        is_controlling: true,
+        pair_switch_threshold: Some(2.0),
+        ..Default::default()
+    })
+    .await?;
+
+    let selected = new_test_pair("192.168.1.1", "1.2.3.4", true)?;
+    let pending = new_test_pair("192.168.1.2", "1.2.3.5", true)?;
+    selected
+        .state
+        .store(CandidatePairState::Succeeded as u8, Ordering::SeqCst);
+    {
+        let mut checklist = a.internal.agent_conn.checklist.lock().await;
+        checklist.push(Arc::clone(&selected));
+        checklist.push(Arc::clone(&pending));
+    }
+
+    selected.remote.seen(false);
+    a.internal
+        .set_selected_pair(Some(Arc::clone(&selected)))
+        .await;
+    {
+        let mut nominated_pair = a.internal.nominated_pair.lock().await;
+        *nominated_pair = Some(Arc::clone(&selected));
+    }
+
+    a.internal.contact_candidates().await;
+
+    assert_eq!(
+        pending.state.load(Ordering::SeqCst),
+        CandidatePairState::Waiting as u8,
+        "a pair which can't exceed the threshold should not be checked"
+    );
+    assert_eq!(pending.binding_request_count.load(Ordering::SeqCst), 0);
+
+    let _ = a.close().await?;
+    Ok(())
+}
+
+fn new_mock_host_candidate() -> Result<Arc<dyn Candidate + Send + Sync>> {
+    let host_config = CandidateHostConfig {
+        base_config: CandidateBaseConfig {
+            network: "udp".to_owned(),
+            address: "192.168.0.2".to_owned(),
+            port: 777,
+            component: 1,
+            conn: Some(Arc::new(MockConn {})),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    Ok(Arc::new(host_config.new_candidate_host()?))
+}
+
+async fn new_role_conflict_request(
+    a: &Agent,
+    local: &Arc<dyn Candidate + Send + Sync>,
+    tie_breaker: u64,
+) -> Result<Message> {
+    let (username, local_pwd) = {
+        let ufrag_pwd = a.internal.ufrag_pwd.lock().await;
+        (
+            ufrag_pwd.local_ufrag.to_owned() + ":" + ufrag_pwd.remote_ufrag.as_str(),
+            ufrag_pwd.local_pwd.clone(),
+        )
+    };
+
+    let mut msg = Message::new();
+    msg.build(&[
+        Box::new(BINDING_REQUEST),
+        Box::new(TransactionId::new()),
+        Box::new(Username::new(ATTR_USERNAME, username)),
+        Box::new(AttrControlled(tie_breaker)),
+        Box::new(PriorityAttr(local.priority())),
+        Box::new(MessageIntegrity::new_short_term_integrity(local_pwd)),
+        Box::new(FINGERPRINT),
+    ])?;
+
+    Ok(msg)
+}
+
+#[tokio::test]
+async fn test_role_conflict_switches_role() -> Result<()> {
+    let a = Agent::new(AgentConfig::default()).await?;
+    assert_eq!(a.role(), Role::Controlled);
+
+    let (role_tx, mut role_rx) = mpsc::channel::<Role>(1);
+    let role_tx = Arc::new(role_tx);
+    a.on_role_change(Box::new(move |role: Role| {
+        let role_tx_clone = Arc::clone(&role_tx);
+        Box::pin(async move {
+            let _ = role_tx_clone.send(role).await;
+        })
+    }));
+
+    let local = new_mock_host_candidate()?;
+    let remote = SocketAddr::from_str("172.17.0.3:999")?;
+
+    // A tie-breaker of zero never wins, so the local agent must become controlling
+    let mut msg = new_role_conflict_request(&a, &local, 0).await?;
+    a.internal.handle_inbound(&mut msg, &local, remote).await;
+
+    assert_eq!(a.role(), Role::Controlling);
+    assert_eq!(role_rx.recv().await, Some(Role::Controlling));
+
+    let remote_candidates = a.internal.remote_candidates.lock().await;
+    assert_eq!(
+        remote_candidates.len(),
+        1,
+        "the request must be processed after the role switch"
+    );
+    drop(remote_candidates);
+
+    let _ = a.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_role_conflict_fixed_role() -> Result<()> {
+    let a = Agent::new(AgentConfig {
+        fixed_role: true,
+        ..Default::default()
+    })
+    .await?;
+
+    let local = new_mock_host_candidate()?;
+    let remote = SocketAddr::from_str("172.17.0.3:999")?;
+
+    let mut msg = new_role_conflict_request(&a, &local, 0).await?;
+    a.internal.handle_inbound(&mut msg, &local, remote).await;
+
+    assert_eq!(a.role(), Role::Controlled, "a fixed role must not change");
+
+    let _ = a.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggressive_nomination_sets_use_candidate() -> Result<()> {
+    for (nomination_mode, expected) in [
+        (NominationMode::Regular, false),
+        (NominationMode::Aggressive, true),
+    ] {
+        let a = Agent::new(AgentConfig {
+            is_controlling: true,
+            nomination_mode,
+            ..Default::default()
+        })
+        .await?;
+
+        let local = new_mock_host_candidate()?;
+        let remote = Arc::clone(&new_test_pair("192.168.0.2", "172.17.0.3", true)?.remote);
+        a.internal.ping_candidate(&local, &remote).await;
+
+        {
+            let pending_binding_requests = a.internal.pending_binding_requests.lock().await;
+            assert_eq!(pending_binding_requests.len(), 1);
+            assert_eq!(
+                pending_binding_requests[0].is_use_candidate, expected,
+                "{:?} nomination must set USE-CANDIDATE: {}",
+                nomination_mode, expected
+            );
+        }
+
+        let _ = a.close().await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_candidate_preferences() -> Result<()> {
+    let mut type_preferences = HashMap::new();
+    type_preferences.insert(CandidateType::Host, 50);
+
+    let a = Agent::new(AgentConfig {
+        type_preferences,
+        local_preference_fn: Arc::new(Some(Box::new(|c: &(dyn Candidate + Send + Sync)| {
+            if c.address() == "192.168.0.2" {
+                Some(1000)
+            } else {
+                None
+            }
+        }))),
+        foundation_fn: Arc::new(Some(Box::new(|_: &(dyn Candidate + Send + Sync)| {
+            Some("media".to_owned())
+        }))),
+        ..Default::default()
+    })
+    .await?;
+
+    let pair = new_test_pair("192.168.0.2", "172.17.0.3", false)?;
+    a.internal.apply_candidate_preferences(&pair.local);
+    assert_eq!(
+        pair.local.priority(),
+        (1 << 24) * 50 + (1 << 8) * 1000 + 255
+    );
+    assert_eq!(pair.local.foundation(), "media");
+
+    // The default local preference is kept if the function returns None
+    let pair = new_test_pair("192.168.0.3", "172.17.0.3", false)?;
+    let default_priority = pair.local.priority();
+    a.internal.apply_candidate_preferences(&pair.local);
+    assert_eq!(
+        pair.local.priority(),
+        default_priority - (1 << 24) * (126 - 50)
+    );
+
+    let _ = a.close().await?;
+
+    let mut type_preferences = HashMap::new();
+    type_preferences.insert(CandidateType::Relay, 127);
+    let result = Agent::new(AgentConfig {
+        type_preferences,
+        ..Default::default()
+    })
+    .await;
+    assert!(matches!(result, Err(Error::ErrInvalidTypePreference)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_regather_after_failure() -> Result<()> {
+    let a = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Udp4],
+        candidate_types: vec![CandidateType::Host],
+        regather_interval: Some(Duration::from_millis(50)),
+        ..Default::default()
+    })
+    .await?;
+
+    let (state_tx, mut state_rx) = mpsc::channel::<ConnectionState>(4);
+    let state_tx = Arc::new(state_tx);
+    a.on_connection_state_change(Box::new(move |c: ConnectionState| {
+        let state_tx_clone = Arc::clone(&state_tx);
+        Box::pin(async move {
+            let _ = state_tx_clone.send(c).await;
+        })
+    }));
+
+    let remote = Arc::clone(&new_test_pair("192.168.0.2", "172.17.0.3", false)?.remote);
+    {
+        let mut remote_candidates = a.internal.remote_candidates.lock().await;
+        remote_candidates.insert(remote.network_type(), vec![remote]);
+    }
+
+    a.internal
+        .update_connection_state(ConnectionState::Failed)
+        .await;
+    assert_eq!(state_rx.recv().await, Some(ConnectionState::Failed));
+
+    // The agent re-gathers and moves back to checking with the remote candidates kept
+    let state = tokio::time::timeout(Duration::from_secs(5), state_rx.recv()).await;
+    assert_eq!(state.ok().flatten(), Some(ConnectionState::Checking));
+    {
+        let remote_candidates = a.internal.remote_candidates.lock().await;
+        assert_eq!(remote_candidates.len(), 1);
+    }
+
+    let _ = a.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_regather_interval_zero() -> Result<()> {
+    let result = Agent::new(AgentConfig {
+        regather_interval: Some(Duration::ZERO),
+        ..Default::default()
+    })
+    .await;
+    assert!(matches!(result, Err(Error::ErrZeroRegatherInterval)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_regather_skipped_when_connected_again() -> Result<()> {
+    let a = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Udp4],
+        candidate_types: vec![CandidateType::Host],
+        regather_interval: Some(Duration::from_millis(200)),
+        ..Default::default()
+    })
+    .await?;
+
+    let (state_tx, mut state_rx) = mpsc::channel::<ConnectionState>(4);
+    let state_tx = Arc::new(state_tx);
+    a.on_connection_state_change(Box::new(move |c: ConnectionState| {
+        let state_tx_clone = Arc::clone(&state_tx);
+        Box::pin(async move {
+            let _ = state_tx_clone.send(c).await;
+        })
+    }));
+
+    let remote = Arc::clone(&new_test_pair("192.168.0.2", "172.17.0.3", false)?.remote);
+    {
+        let mut remote_candidates = a.internal.remote_candidates.lock().await;
+        remote_candidates.insert(remote.network_type(), vec![remote]);
+    }
+
+    // The connection recovers during the backoff, so nothing is re-gathered
+    a.internal
+        .update_connection_state(ConnectionState::Failed)
+        .await;
+    a.internal
+        .update_connection_state(ConnectionState::Connected)
+        .await;
+    assert_eq!(state_rx.recv().await, Some(ConnectionState::Failed));
+    assert_eq!(state_rx.recv().await, Some(ConnectionState::Connected));
+    let state = tokio::time::timeout(Duration::from_millis(400), state_rx.recv()).await;
+    assert!(state.is_err(), "unexpected state change {:?}", state);
+
+    // The backoff has been reset, the next failure re-gathers after the initial interval
+    a.internal
+        .update_connection_state(ConnectionState::Failed)
+        .await;
+    assert_eq!(state_rx.recv().await, Some(ConnectionState::Failed));
+    let state = tokio::time::timeout(Duration::from_millis(350), state_rx.recv()).await;
+    assert_eq!(state.ok().flatten(), Some(ConnectionState::Checking));
+
+    let _ = a.close().await?;
+    Ok(())
+}
@@ -200,14 +200,10 @@ impl TestAuthHandler {
     }
 }
 
+#[async_trait]
 impl turn::auth::AuthHandler for TestAuthHandler {
-    fn auth_handle(
-        &self,
-        username: &str,
-        _realm: &str,
-        _src_addr: SocketAddr,
-    ) -> Result<Vec<u8>, turn::Error> {
-        if let Some(pw) = self.cred_map.get(username) {
+    async fn auth_handle(&self, ctx: &turn::auth::AuthContext) -> Result<Vec<u8>, turn::Error> {
+        if let Some(pw) = self.cred_map.get(&ctx.username) {
             Ok(pw.to_vec())
         } else {
             Err(turn::Error::Other("fake error".to_owned()))
@@ -235,9 +231,11 @@ pub(crate) async fn add_vnet_stun(wan_net: Arc<net::Net>) -> Result<turn::server
                 },
             ),
         }],
+        listener_configs: vec![],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler::new()),
         channel_bind_timeout: Duration::from_secs(0),
+        event_handler: None,
     })
     .await?;
 
@@ -1011,3 +1009,37 @@ async fn test_write_use_valid_pair() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_connectivity_vnet_rtcp_component() -> Result<(), Error> {
+    let v = build_simple_vnet(nat::NatType::default(), nat::NatType::default()).await?;
+
+    let new_agent = |net: &Arc<net::Net>| {
+        Agent::new(AgentConfig {
+            network_types: vec![NetworkType::Udp4],
+            multicast_dns_mode: MulticastDnsMode::Disabled,
+            component: COMPONENT_RTCP,
+            net: Some(Arc::clone(net)),
+            ..Default::default()
+        })
+    };
+    let a_agent = Arc::new(new_agent(&v.net0).await?);
+    let b_agent = Arc::new(new_agent(&v.net1).await?);
+
+    // The candidates are exchanged marshaled, with their component
+    let (_a_conn, _b_conn) = connect_with_vnet(&a_agent, &b_agent).await?;
+
+    for agent in [&a_agent, &b_agent] {
+        let pair = agent
+            .get_selected_candidate_pair()
+            .expect("should have selected a pair");
+        assert_eq!(pair.local.component(), COMPONENT_RTCP);
+        assert_eq!(pair.remote.component(), COMPONENT_RTCP);
+    }
+
+    a_agent.close().await?;
+    b_agent.close().await?;
+    v.close().await?;
+
+    Ok(())
+}
@@ -15,11 +15,13 @@ pub mod agent_stats;
 pub mod agent_transport;
 
 use crate::candidate::*;
+use crate::control::Role;
 use crate::error::*;
 use crate::external_ip_mapper::*;
 use crate::mdns::*;
 use crate::network_type::*;
 use crate::state::*;
+use crate::tcp_mux::TCPMux;
 use crate::udp_mux::UDPMux;
 use crate::udp_network::UDPNetwork;
 use crate::url::*;
@@ -50,6 +52,7 @@ pub(crate) struct BindingRequest {
     pub(crate) transaction_id: TransactionId,
     pub(crate) destination: SocketAddr,
     pub(crate) is_use_candidate: bool,
+    pub(crate) is_controlling: bool,
 }
 
 impl Default for BindingRequest {
@@ -59,6 +62,7 @@ impl Default for BindingRequest {
             transaction_id: TransactionId::default(),
             destination: SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 0),
             is_use_candidate: false,
+            is_controlling: false,
         }
     }
 }
@@ -70,12 +74,14 @@ pub type OnConnectionStateChangeHdlrFn = Box<
 >;
 pub type OnSelectedCandidatePairChangeHdlrFn = Box<
     dyn (FnMut(
-            &Arc<dyn Candidate + Send + Sync>,
-            &Arc<dyn Candidate + Send + Sync>,
+            Option<&Arc<CandidatePair>>,
+            &Arc<CandidatePair>,
         ) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
         + Send
         + Sync,
 >;
+pub type OnRoleChangeHdlrFn =
+    Box<dyn (FnMut(Role) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
 pub type OnCandidateHdlrFn = Box<
     dyn (FnMut(
             Option<Arc<dyn Candidate + Send + Sync>>,
@@ -85,10 +91,14 @@ pub type OnCandidateHdlrFn = Box<
 >;
 pub type GatherCandidateCancelFn = Box<dyn Fn() + Send + Sync>;
 
+/// The previously selected pair, if any, and the newly selected pair.
+pub(crate) type SelectedPairChange = (Option<Arc<CandidatePair>>, Arc<CandidatePair>);
+
 struct ChanReceivers {
     chan_state_rx: mpsc::Receiver<ConnectionState>,
     chan_candidate_rx: mpsc::Receiver<Option<Arc<dyn Candidate + Send + Sync>>>,
-    chan_candidate_pair_rx: mpsc::Receiver<()>,
+    chan_candidate_pair_rx: mpsc::Receiver<SelectedPairChange>,
+    chan_regather_rx: Option<mpsc::Receiver<ConnectionState>>,
 }
 
 /// Represents the ICE agent.
@@ -96,11 +106,14 @@ pub struct Agent {
     pub(crate) internal: Arc<AgentInternal>,
 
     pub(crate) udp_network: UDPNetwork,
+    pub(crate) tcp_mux: Option<Arc<dyn TCPMux + Send + Sync>>,
     pub(crate) interface_filter: Arc<Option<InterfaceFilterFn>>,
     pub(crate) ip_filter: Arc<Option<IpFilterFn>>,
     pub(crate) mdns_mode: MulticastDnsMode,
     pub(crate) mdns_name: String,
     pub(crate) mdns_conn: Option<Arc<DnsConn>>,
+    pub(crate) mdns_conn_shared: bool,
+    pub(crate) mdns_query_timeout: Duration,
     pub(crate) net: Arc<Net>,
 
     // 1:1 D-NAT IP address mapping
@@ -125,12 +138,34 @@ impl Agent {
             return Err(Error::ErrInvalidMulticastDnshostName);
         }
 
+        if config
+            .type_preferences
+            .values()
+            .any(|preference| *preference > MAX_TYPE_PREFERENCE)
+        {
+            return Err(Error::ErrInvalidTypePreference);
+        }
+
+        if config.regather_interval == Some(Duration::ZERO) {
+            return Err(Error::ErrZeroRegatherInterval);
+        }
+
         let mut mdns_mode = config.multicast_dns_mode;
         if mdns_mode == MulticastDnsMode::Unspecified {
             mdns_mode = MulticastDnsMode::QueryOnly;
         }
 
-        let mdns_conn =
+        let mdns_conn_shared = config.multicast_dns_conn.is_some();
+        let mdns_conn = if let Some(mdns_conn) = &config.multicast_dns_conn {
+            if mdns_mode == MulticastDnsMode::Disabled {
+                None
+            } else {
+                if mdns_mode.is_gather_enabled() {
+                    mdns_conn.add_local_name(&mdns_name).await;
+                }
+                Some(Arc::clone(mdns_conn))
+            }
+        } else {
             match create_multicast_dns(mdns_mode, &mdns_name, &config.multicast_dns_dest_addr) {
                 Ok(c) => c,
                 Err(err) => {
@@ -139,13 +174,15 @@ impl Agent {
                     log::warn!("Failed to initialize mDNS {}: {}", mdns_name, err);
                     None
                 }
-            };
+            }
+        };
 
         let (mut ai, chan_receivers) = AgentInternal::new(&config);
-        let (chan_state_rx, chan_candidate_rx, chan_candidate_pair_rx) = (
+        let (chan_state_rx, chan_candidate_rx, chan_candidate_pair_rx, chan_regather_rx) = (
             chan_receivers.chan_state_rx,
             chan_receivers.chan_candidate_rx,
             chan_receivers.chan_candidate_pair_rx,
+            chan_receivers.chan_regather_rx,
         );
 
         config.init_with_defaults(&mut ai);
@@ -159,7 +196,7 @@ impl Agent {
         if ai.lite.load(Ordering::SeqCst)
             && (candidate_types.len() != 1 || candidate_types[0] != CandidateType::Host)
         {
-            Self::close_multicast_conn(&mdns_conn).await;
+            Self::close_multicast_conn(&mdns_conn, mdns_conn_shared, &mdns_name).await;
             return Err(Error::ErrLiteUsingNonHostCandidates);
         }
 
@@ -167,14 +204,14 @@ impl Agent {
             && !contains_candidate_type(CandidateType::ServerReflexive, &candidate_types)
             && !contains_candidate_type(CandidateType::Relay, &candidate_types)
         {
-            Self::close_multicast_conn(&mdns_conn).await;
+            Self::close_multicast_conn(&mdns_conn, mdns_conn_shared, &mdns_name).await;
             return Err(Error::ErrUselessUrlsProvided);
         }
 
         let ext_ip_mapper = match config.init_ext_ip_mapping(mdns_mode, &candidate_types) {
             Ok(ext_ip_mapper) => ext_ip_mapper,
             Err(err) => {
-                Self::close_multicast_conn(&mdns_conn).await;
+                Self::close_multicast_conn(&mdns_conn, mdns_conn_shared, &mdns_name).await;
                 return Err(err);
             }
         };
@@ -194,12 +231,17 @@ impl Agent {
 
         let agent = Self {
             udp_network: config.udp_network,
+            tcp_mux: config.tcp_mux,
             internal: Arc::new(ai),
             interface_filter: Arc::clone(&config.interface_filter),
             ip_filter: Arc::clone(&config.ip_filter),
             mdns_mode,
             mdns_name,
             mdns_conn,
+            mdns_conn_shared,
+            mdns_query_timeout: config
+                .multicast_dns_query_timeout
+                .unwrap_or(DEFAULT_MULTICAST_DNS_QUERY_TIMEOUT),
             net,
             ext_ip_mapper: Arc::new(ext_ip_mapper),
             gathering_state: Arc::new(AtomicU8::new(0)), //GatheringState::New,
@@ -216,9 +258,20 @@ impl Agent {
             chan_candidate_pair_rx,
         );
 
+        if let (Some(chan_regather_rx), Some(regather_interval)) =
+            (chan_regather_rx, config.regather_interval)
+        {
+            agent.start_regather_routine(
+                chan_regather_rx,
+                regather_interval,
+                config
+                    .regather_max_interval
+                    .unwrap_or(DEFAULT_REGATHER_MAX_INTERVAL),
+            );
+        }
+
         // Restart is also used to initialize the agent for the first time
         if let Err(err) = agent.restart(config.local_ufrag, config.local_pwd).await {
-            Self::close_multicast_conn(&agent.mdns_conn).await;
             let _ = agent.close().await;
             return Err(err);
         }
@@ -241,7 +294,10 @@ impl Agent {
             .store(Some(Arc::new(Mutex::new(f))))
     }
 
-    /// Sets a handler that is fired when the final candidate pair is selected.
+    /// Sets a handler that is fired whenever a candidate pair is selected, with both the
+    /// previously selected pair and the new one, so path migrations can be observed. The
+    /// previous pair is `None` for the first selection and after the selected pair failed.
+    /// See `AgentConfig::pair_switch_threshold`.
     pub fn on_selected_candidate_pair_change(&self, f: OnSelectedCandidatePairChangeHdlrFn) {
         self.internal
             .on_selected_candidate_pair_change_hdlr
@@ -269,9 +325,9 @@ impl Agent {
 
         // If we have a mDNS Candidate lets fully resolve it before adding it locally
         if c.candidate_type() == CandidateType::Host && c.address().ends_with(".local") {
-            if self.mdns_mode == MulticastDnsMode::Disabled {
+            if !self.mdns_mode.is_query_enabled() {
                 log::warn!(
-                    "remote mDNS candidate added, but mDNS is disabled: ({})",
+                    "remote mDNS candidate added, but mDNS querying is disabled: ({})",
                     c.address()
                 );
                 return Ok(());
@@ -284,10 +340,15 @@ impl Agent {
             let ai = Arc::clone(&self.internal);
             let host_candidate = Arc::clone(c);
             let mdns_conn = self.mdns_conn.clone();
+            let mdns_query_timeout = self.mdns_query_timeout;
             tokio::spawn(async move {
                 if let Some(mdns_conn) = mdns_conn {
-                    if let Ok(candidate) =
-                        Self::resolve_and_add_multicast_candidate(mdns_conn, host_candidate).await
+                    if let Ok(candidate) = Self::resolve_and_add_multicast_candidate(
+                        mdns_conn,
+                        host_candidate,
+                        mdns_query_timeout,
+                    )
+                    .await
                     {
                         ai.add_remote_candidate(&candidate).await;
                     }
@@ -343,10 +404,41 @@ impl Agent {
             udp_mux.remove_conn_by_ufrag(&ufrag).await;
         }
 
+        if let Some(tcp_mux) = &self.tcp_mux {
+            let (ufrag, _) = self.get_local_user_credentials().await;
+            tcp_mux.remove_conn_by_ufrag(&ufrag).await;
+        }
+
+        Self::close_multicast_conn(&self.mdns_conn, self.mdns_conn_shared, &self.mdns_name).await;
+
         //FIXME: deadlock here
         self.internal.close().await
     }
 
+    /// Returns the current role of the agent. It is set by `dial`/`accept` and may change
+    /// afterwards when a role conflict with the remote agent is resolved, unless
+    /// `AgentConfig::fixed_role` is set.
+    pub fn role(&self) -> Role {
+        if self.internal.is_controlling.load(Ordering::SeqCst) {
+            Role::Controlling
+        } else {
+            Role::Controlled
+        }
+    }
+
+    /// Sets a handler that is fired when the agent switches its role to resolve a role conflict
+    /// (RFC 8445 S7.3.1.1).
+    pub fn on_role_change(&self, f: OnRoleChangeHdlrFn) {
+        self.internal
+            .on_role_change_hdlr
+            .store(Some(Arc::new(Mutex::new(f))))
+    }
+
+    /// Returns true if this is an ICE-lite agent.
+    pub fn is_lite(&self) -> bool {
+        self.internal.lite.load(Ordering::SeqCst)
+    }
+
     /// Returns the selected pair or nil if there is none
     pub fn get_selected_candidate_pair(&self) -> Option<Arc<CandidatePair>> {
         self.internal.agent_conn.get_selected_pair()
@@ -401,6 +493,15 @@ impl Agent {
         // Clear all agent needed to take back to fresh state
         {
             let mut ufrag_pwd = self.internal.ufrag_pwd.lock().await;
+            // The muxed connection is keyed by ufrag, so it must not outlive the old credentials
+            if !ufrag_pwd.local_ufrag.is_empty() && ufrag_pwd.local_ufrag != ufrag {
+                if let UDPNetwork::Muxed(ref udp_mux) = self.udp_network {
+                    udp_mux.remove_conn_by_ufrag(&ufrag_pwd.local_ufrag).await;
+                }
+                if let Some(tcp_mux) = &self.tcp_mux {
+                    tcp_mux.remove_conn_by_ufrag(&ufrag_pwd.local_ufrag).await;
+                }
+            }
             ufrag_pwd.local_ufrag = ufrag;
             ufrag_pwd.local_pwd = pwd;
             ufrag_pwd.remote_ufrag = String::new();
@@ -447,8 +548,18 @@ impl Agent {
 
         //TODO: a.gatherCandidateCancel = cancel
 
-        let params = GatherCandidatesInternalParams {
+        let params = self.gather_candidates_params();
+        tokio::spawn(async move {
+            Self::gather_candidates_internal(params).await;
+        });
+
+        Ok(())
+    }
+
+    pub(crate) fn gather_candidates_params(&self) -> GatherCandidatesInternalParams {
+        GatherCandidatesInternalParams {
             udp_network: self.udp_network.clone(),
+            tcp_mux: self.tcp_mux.clone(),
             candidate_types: self.candidate_types.clone(),
             urls: self.urls.clone(),
             network_types: self.network_types.clone(),
@@ -461,12 +572,7 @@ impl Agent {
             agent_internal: Arc::clone(&self.internal),
             gathering_state: Arc::clone(&self.gathering_state),
             chan_candidate_tx: Arc::clone(&self.internal.chan_candidate_tx),
-        };
-        tokio::spawn(async move {
-            Self::gather_candidates_internal(params).await;
-        });
-
-        Ok(())
+        }
     }
 
     /// Returns a list of candidate pair stats.
@@ -487,15 +593,22 @@ impl Agent {
     async fn resolve_and_add_multicast_candidate(
         mdns_conn: Arc<DnsConn>,
         c: Arc<dyn Candidate + Send + Sync>,
+        query_timeout: Duration,
     ) -> Result<Arc<dyn Candidate + Send + Sync>> {
         //TODO: hook up _close_query_signal_tx to Agent or Candidate's Close signal?
         let (_close_query_signal_tx, close_query_signal_rx) = mpsc::channel(1);
-        let src = match mdns_conn.query(&c.address(), close_query_signal_rx).await {
-            Ok((_, src)) => src,
-            Err(err) => {
+        let address = c.address();
+        let query = mdns_conn.query(&address, close_query_signal_rx);
+        let src = match tokio::time::timeout(query_timeout, query).await {
+            Ok(Ok((_, src))) => src,
+            Ok(Err(err)) => {
                 log::warn!("Failed to discover mDNS candidate {}: {}", c.address(), err);
                 return Err(err.into());
             }
+            Err(_) => {
+                log::warn!("Timed out discovering mDNS candidate {}", c.address());
+                return Err(Error::ErrMulticastDnsQueryTimeout);
+            }
         };
 
         c.set_ip(&src.ip())?;
@@ -503,9 +616,17 @@ impl Agent {
         Ok(c)
     }
 
-    async fn close_multicast_conn(mdns_conn: &Option<Arc<DnsConn>>) {
+    async fn close_multicast_conn(
+        mdns_conn: &Option<Arc<DnsConn>>,
+        mdns_conn_shared: bool,
+        mdns_name: &str,
+    ) {
         if let Some(conn) = mdns_conn {
-            if let Err(err) = conn.close().await {
+            if mdns_conn_shared {
+                // A shared connection is owned by the application, only stop answering for
+                // this agent's name.
+                conn.remove_local_name(mdns_name).await;
+            } else if let Err(err) = conn.close().await {
                 log::warn!("failed to close mDNS Conn: {}", err);
             }
         }
@@ -8,11 +8,13 @@ use crate::util::*;
 
 use async_trait::async_trait;
 use crc::{Crc, CRC_32_ISCSI};
+use sdp::candidate::CandidateAttribute;
 use std::{
+    convert::TryFrom,
     fmt,
     ops::Add,
     sync::{
-        atomic::{AtomicU16, AtomicU64, AtomicU8, Ordering},
+        atomic::{AtomicU16, AtomicU32, AtomicU64, AtomicU8, Ordering},
         Arc,
     },
     time::{Duration, SystemTime, UNIX_EPOCH},
@@ -52,8 +54,8 @@ pub struct CandidateBase {
     pub(crate) conn: Option<Arc<dyn util::Conn + Send + Sync>>,
     pub(crate) closed_ch: Arc<Mutex<Option<broadcast::Sender<()>>>>,
 
-    pub(crate) foundation_override: String,
-    pub(crate) priority_override: u32,
+    pub(crate) foundation_override: SyncMutex<String>,
+    pub(crate) priority_override: AtomicU32,
 
     //CandidateHost
     pub(crate) network: String,
@@ -82,8 +84,8 @@ impl Default for CandidateBase {
             conn: None,
             closed_ch: Arc::new(Mutex::new(None)),
 
-            foundation_override: String::new(),
-            priority_override: 0,
+            foundation_override: SyncMutex::new(String::new()),
+            priority_override: AtomicU32::new(0),
             network: String::new(),
             relay_client: None,
         }
@@ -119,8 +121,11 @@ impl fmt::Display for CandidateBase {
 #[async_trait]
 impl Candidate for CandidateBase {
     fn foundation(&self) -> String {
-        if !self.foundation_override.is_empty() {
-            return self.foundation_override.clone();
+        {
+            let foundation_override = self.foundation_override.lock();
+            if !foundation_override.is_empty() {
+                return foundation_override.clone();
+            }
         }
 
         let mut buf = vec![];
@@ -133,6 +138,11 @@ impl Candidate for CandidateBase {
         format!("{}", checksum)
     }
 
+    /// Overrides the computed foundation. An empty foundation restores the computed one.
+    fn set_foundation(&self, foundation: String) {
+        *self.foundation_override.lock() = foundation;
+    }
+
     /// Returns Candidate ID.
     fn id(&self) -> String {
         self.id.clone()
@@ -176,8 +186,9 @@ impl Candidate for CandidateBase {
 
     /// Computes the priority for this ICE Candidate.
     fn priority(&self) -> u32 {
-        if self.priority_override != 0 {
-            return self.priority_override;
+        let priority_override = self.priority_override.load(Ordering::SeqCst);
+        if priority_override != 0 {
+            return priority_override;
         }
 
         // The local preference MUST be an integer from 0 (lowest preference) to
@@ -191,6 +202,11 @@ impl Candidate for CandidateBase {
             + (256 - u32::from(self.component()))
     }
 
+    /// Overrides the computed priority. A priority of 0 restores the computed one.
+    fn set_priority(&self, priority: u32) {
+        self.priority_override.store(priority, Ordering::SeqCst);
+    }
+
     /// Returns `Option<CandidateRelatedAddress>`.
     fn related_address(&self) -> Option<CandidateRelatedAddress> {
         self.related_address.as_ref().cloned()
@@ -207,30 +223,7 @@ impl Candidate for CandidateBase {
 
     /// Returns the string representation of the ICECandidate.
     fn marshal(&self) -> String {
-        let mut val = format!(
-            "{} {} {} {} {} {} typ {}",
-            self.foundation(),
-            self.component(),
-            self.network_type().network_short(),
-            self.priority(),
-            self.address(),
-            self.port(),
-            self.candidate_type()
-        );
-
-        if self.tcp_type != TcpType::Unspecified {
-            val += format!(" tcptype {}", self.tcp_type()).as_str();
-        }
-
-        if let Some(related_address) = self.related_address() {
-            val += format!(
-                " raddr {} rport {}",
-                related_address.address, related_address.port,
-            )
-            .as_str();
-        }
-
-        val
+        candidate_attribute(self).to_string()
     }
 
     fn addr(&self) -> SocketAddr {
@@ -391,137 +384,121 @@ impl CandidateBase {
 
 /// Creates a Candidate from its string representation.
 pub fn unmarshal_candidate(raw: &str) -> Result<impl Candidate> {
-    let split: Vec<&str> = raw.split_whitespace().collect();
-    if split.len() < 8 {
-        return Err(Error::Other(format!(
-            "{:?} ({})",
-            Error::ErrAttributeTooShortIceCandidate,
-            split.len()
-        )));
-    }
-
-    // Foundation
-    let foundation = split[0].to_owned();
-
-    // Component
-    let component: u16 = split[1].parse()?;
-
-    // Network
-    let network = split[2].to_owned();
-
-    // Priority
-    let priority: u32 = split[3].parse()?;
-
-    // Address
-    let address = split[4].to_owned();
-
-    // Port
-    let port: u16 = split[5].parse()?;
-
-    let typ = split[7];
-
-    let mut rel_addr = String::new();
-    let mut rel_port = 0;
-    let mut tcp_type = TcpType::Unspecified;
+    CandidateBase::try_from(&CandidateAttribute::unmarshal(raw)?)
+}
 
-    if split.len() > 8 {
-        let split2 = &split[8..];
+/// Returns the "a=candidate" attribute of a Candidate.
+pub fn candidate_attribute(c: &dyn Candidate) -> CandidateAttribute {
+    let tcp_type = c.tcp_type();
+    CandidateAttribute {
+        foundation: c.foundation(),
+        component: c.component(),
+        transport: c.network_type().network_short(),
+        priority: c.priority(),
+        address: c.address(),
+        port: c.port(),
+        typ: c.candidate_type().to_string(),
+        related_address: c.related_address().map(|r| (r.address, r.port)),
+        tcp_type: if tcp_type == TcpType::Unspecified {
+            None
+        } else {
+            Some(tcp_type.to_string())
+        },
+        ..Default::default()
+    }
+}
 
-        if split2[0] == "raddr" {
-            if split2.len() < 4 {
-                return Err(Error::Other(format!(
-                    "{:?}: incorrect length",
-                    Error::ErrParseRelatedAddr
-                )));
+impl TryFrom<&CandidateAttribute> for CandidateBase {
+    type Error = Error;
+
+    /// Creates a Candidate from its "a=candidate" attribute. The extensions other than
+    /// the related address and the TCP type are ignored.
+    fn try_from(attr: &CandidateAttribute) -> Result<Self> {
+        let network = attr.transport.clone();
+        let address = attr.address.clone();
+        let port = attr.port;
+        let component = attr.component;
+        let priority = attr.priority;
+        let foundation = attr.foundation.clone();
+        let (rel_addr, rel_port) = attr.related_address.clone().unwrap_or_default();
+        let tcp_type = attr
+            .tcp_type
+            .as_deref()
+            .map(TcpType::from)
+            .unwrap_or_default();
+        let typ = attr.typ.as_str();
+
+        match typ {
+            "host" => {
+                let config = CandidateHostConfig {
+                    base_config: CandidateBaseConfig {
+                        network,
+                        address,
+                        port,
+                        component,
+                        priority,
+                        foundation,
+                        ..CandidateBaseConfig::default()
+                    },
+                    tcp_type,
+                };
+                config.new_candidate_host()
             }
-
-            // RelatedAddress
-            rel_addr = split2[1].to_owned();
-
-            // RelatedPort
-            rel_port = split2[3].parse()?;
-        } else if split2[0] == "tcptype" {
-            if split2.len() < 2 {
-                return Err(Error::Other(format!(
-                    "{:?}: incorrect length",
-                    Error::ErrParseType
-                )));
+            "srflx" => {
+                let config = CandidateServerReflexiveConfig {
+                    base_config: CandidateBaseConfig {
+                        network,
+                        address,
+                        port,
+                        component,
+                        priority,
+                        foundation,
+                        ..CandidateBaseConfig::default()
+                    },
+                    rel_addr,
+                    rel_port,
+                };
+                config.new_candidate_server_reflexive()
             }
-
-            tcp_type = TcpType::from(split2[1]);
-        }
-    }
-
-    match typ {
-        "host" => {
-            let config = CandidateHostConfig {
-                base_config: CandidateBaseConfig {
-                    network,
-                    address,
-                    port,
-                    component,
-                    priority,
-                    foundation,
-                    ..CandidateBaseConfig::default()
-                },
-                tcp_type,
-            };
-            config.new_candidate_host()
-        }
-        "srflx" => {
-            let config = CandidateServerReflexiveConfig {
-                base_config: CandidateBaseConfig {
-                    network,
-                    address,
-                    port,
-                    component,
-                    priority,
-                    foundation,
-                    ..CandidateBaseConfig::default()
-                },
-                rel_addr,
-                rel_port,
-            };
-            config.new_candidate_server_reflexive()
-        }
-        "prflx" => {
-            let config = CandidatePeerReflexiveConfig {
-                base_config: CandidateBaseConfig {
-                    network,
-                    address,
-                    port,
-                    component,
-                    priority,
-                    foundation,
-                    ..CandidateBaseConfig::default()
-                },
-                rel_addr,
-                rel_port,
-            };
-
-            config.new_candidate_peer_reflexive()
-        }
-        "relay" => {
-            let config = CandidateRelayConfig {
-                base_config: CandidateBaseConfig {
-                    network,
-                    address,
-                    port,
-                    component,
-                    priority,
-                    foundation,
-                    ..CandidateBaseConfig::default()
-                },
-                rel_addr,
-                rel_port,
-                ..CandidateRelayConfig::default()
-            };
-            config.new_candidate_relay()
+            "prflx" => {
+                let config = CandidatePeerReflexiveConfig {
+                    base_config: CandidateBaseConfig {
+                        network,
+                        address,
+                        port,
+                        component,
+                        priority,
+                        foundation,
+                        ..CandidateBaseConfig::default()
+                    },
+                    rel_addr,
+                    rel_port,
+                };
+
+                config.new_candidate_peer_reflexive()
+            }
+            "relay" => {
+                let config = CandidateRelayConfig {
+                    base_config: CandidateBaseConfig {
+                        network,
+                        address,
+                        port,
+                        component,
+                        priority,
+                        foundation,
+                        ..CandidateBaseConfig::default()
+                    },
+                    rel_addr,
+                    rel_port,
+                    ..CandidateRelayConfig::default()
+                };
+                config.new_candidate_relay()
+            }
+            _ => Err(Error::Other(format!(
+                "{:?} ({})",
+                Error::ErrUnknownCandidateType,
+                typ
+            ))),
         }
-        _ => Err(Error::Other(format!(
-            "{:?} ({})",
-            Error::ErrUnknownCandidateType,
-            typ
-        ))),
     }
 }
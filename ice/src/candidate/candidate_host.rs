@@ -2,7 +2,8 @@ use super::candidate_base::*;
 use super::*;
 use crate::rand::generate_cand_id;
 
-use std::sync::atomic::{AtomicU16, AtomicU8};
+use std::sync::atomic::{AtomicU16, AtomicU32, AtomicU8};
+use util::sync::Mutex as SyncMutex;
 
 /// The config required to create a new `CandidateHost`.
 #[derive(Default)]
@@ -27,8 +28,8 @@ impl CandidateHostConfig {
             component: AtomicU16::new(self.base_config.component),
             port: self.base_config.port,
             tcp_type: self.tcp_type,
-            foundation_override: self.base_config.foundation,
-            priority_override: self.base_config.priority,
+            foundation_override: SyncMutex::new(self.base_config.foundation),
+            priority_override: AtomicU32::new(self.base_config.priority),
             network: self.base_config.network,
             network_type: AtomicU8::new(NetworkType::Udp4 as u8),
             conn: self.base_config.conn,
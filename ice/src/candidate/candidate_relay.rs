@@ -4,7 +4,7 @@ use crate::error::*;
 use crate::rand::generate_cand_id;
 use crate::util::*;
 use std::sync::{
-    atomic::{AtomicU16, AtomicU8},
+    atomic::{AtomicU16, AtomicU32, AtomicU8},
     Arc,
 };
 use util::sync::Mutex as SyncMutex;
@@ -41,8 +41,8 @@ impl CandidateRelayConfig {
             port: self.base_config.port,
             resolved_addr: SyncMutex::new(create_addr(network_type, ip, self.base_config.port)),
             component: AtomicU16::new(self.base_config.component),
-            foundation_override: self.base_config.foundation,
-            priority_override: self.base_config.priority,
+            foundation_override: SyncMutex::new(self.base_config.foundation),
+            priority_override: AtomicU32::new(self.base_config.priority),
             related_address: Some(CandidateRelatedAddress {
                 address: self.rel_addr,
                 port: self.rel_port,
@@ -9,17 +9,13 @@ use crate::url::{ProtoType, SchemeType, Url};
 use std::result::Result;
 use std::time::Duration;
 use tokio::net::UdpSocket;
-use turn::auth::AuthHandler;
+use turn::auth::{AuthContext, AuthHandler};
 
 pub(crate) struct OptimisticAuthHandler;
 
+#[async_trait]
 impl AuthHandler for OptimisticAuthHandler {
-    fn auth_handle(
-        &self,
-        _username: &str,
-        _realm: &str,
-        _src_addr: SocketAddr,
-    ) -> Result<Vec<u8>, turn::Error> {
+    async fn auth_handle(&self, _ctx: &AuthContext) -> Result<Vec<u8>, turn::Error> {
         Ok(turn::auth::generate_auth_key(
             "username",
             "webrtc.rs",
@@ -60,7 +56,9 @@ async fn test_relay_only_connection() -> Result<(), Error> {
                 net: Arc::new(util::vnet::net::Net::new(None)),
             }),
         }],
+        listener_configs: vec![],
         channel_bind_timeout: Duration::from_secs(0),
+        event_handler: None,
     })
     .await?;
 
@@ -3,7 +3,7 @@ use super::*;
 use crate::error::*;
 use crate::rand::generate_cand_id;
 use crate::util::*;
-use std::sync::atomic::{AtomicU16, AtomicU8};
+use std::sync::atomic::{AtomicU16, AtomicU32, AtomicU8};
 use util::sync::Mutex as SyncMutex;
 
 /// The config required to create a new `CandidateServerReflexive`.
@@ -37,8 +37,8 @@ impl CandidateServerReflexiveConfig {
             port: self.base_config.port,
             resolved_addr: SyncMutex::new(create_addr(network_type, ip, self.base_config.port)),
             component: AtomicU16::new(self.base_config.component),
-            foundation_override: self.base_config.foundation,
-            priority_override: self.base_config.priority,
+            foundation_override: SyncMutex::new(self.base_config.foundation),
+            priority_override: AtomicU32::new(self.base_config.priority),
             related_address: Some(CandidateRelatedAddress {
                 address: self.rel_addr,
                 port: self.rel_port,
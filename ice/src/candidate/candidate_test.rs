@@ -1,6 +1,10 @@
 use super::*;
 
+use sdp::candidate::CandidateAttribute;
+use std::convert::TryFrom;
+use std::sync::atomic::AtomicU32;
 use std::time::UNIX_EPOCH;
+use util::sync::Mutex as SyncMutex;
 
 #[test]
 fn test_candidate_priority() -> Result<()> {
@@ -309,8 +313,8 @@ fn test_candidate_marshal() -> Result<()> {
                     candidate_type:      CandidateType::Host,
                     address:            "fcd9:e3b8:12ce:9fc5:74a5:c6bb:d8b:e08a".to_owned(),
                     port:               53987,
-                    priority_override:   500,
-                    foundation_override: "750".to_owned(),
+                    priority_override:   AtomicU32::new(500),
+                    foundation_override: SyncMutex::new("750".to_owned()),
                     ..Default::default()
             }),
             "750 1 udp 500 fcd9:e3b8:12ce:9fc5:74a5:c6bb:d8b:e08a 53987 typ host",
@@ -410,3 +414,58 @@ fn test_candidate_marshal() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_candidate_marshal_rtcp_component() -> Result<()> {
+    let candidate = CandidateBase {
+        network_type: AtomicU8::new(NetworkType::Udp4 as u8),
+        candidate_type: CandidateType::Host,
+        address: "10.0.75.1".to_owned(),
+        port: 53634,
+        component: AtomicU16::new(COMPONENT_RTCP),
+        ..Default::default()
+    };
+
+    let marshaled = candidate.marshal();
+    assert_eq!(
+        "1666856576 2 udp 2130706430 10.0.75.1 53634 typ host",
+        marshaled
+    );
+
+    let actual = unmarshal_candidate(&marshaled)?;
+    assert_eq!(COMPONENT_RTCP, actual.component());
+    assert!(candidate.equal(&actual), "{} vs {}", marshaled, actual);
+    assert_eq!(marshaled, actual.marshal());
+
+    Ok(())
+}
+
+#[test]
+fn test_candidate_attribute() -> Result<()> {
+    let attr = CandidateAttribute::unmarshal(
+        "candidate:4207374051 1 udp 1685790463 191.228.238.68 53991 typ srflx \
+         raddr 192.168.0.278 rport 53991 generation 0 ufrag Ng4i network-id 3",
+    )?;
+    assert_eq!(attr.generation, Some(0));
+    assert_eq!(attr.ufrag.as_deref(), Some("Ng4i"));
+
+    let candidate = CandidateBase::try_from(&attr)?;
+    assert_eq!(candidate.candidate_type(), CandidateType::ServerReflexive);
+    assert_eq!(
+        candidate.related_address(),
+        Some(CandidateRelatedAddress {
+            address: "192.168.0.278".to_owned(),
+            port: 53991,
+        })
+    );
+
+    // the extensions unknown to the agent are not written back
+    let actual = candidate_attribute(&candidate);
+    assert_eq!(
+        actual.to_string(),
+        "4207374051 1 udp 1685790463 191.228.238.68 53991 typ srflx raddr 192.168.0.278 rport 53991"
+    );
+    assert_eq!(actual.to_string(), candidate.marshal());
+
+    Ok(())
+}
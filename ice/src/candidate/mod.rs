@@ -31,9 +31,9 @@ pub(crate) const RECEIVE_MTU: usize = 8192;
 pub(crate) const DEFAULT_LOCAL_PREFERENCE: u16 = 65535;
 
 /// Indicates that the candidate is used for RTP.
-pub(crate) const COMPONENT_RTP: u16 = 1;
-/// Indicates that the candidate is used for RTCP.
-pub(crate) const COMPONENT_RTCP: u16 = 0;
+pub const COMPONENT_RTP: u16 = 1;
+/// Indicates that the candidate is used for RTCP, when it isn't multiplexed with RTP.
+pub const COMPONENT_RTCP: u16 = 2;
 
 /// Candidate represents an ICE candidate
 #[async_trait]
@@ -44,6 +44,9 @@ pub trait Candidate: fmt::Display {
     /// and STUN or TURN server.
     fn foundation(&self) -> String;
 
+    /// Overrides the foundation, candidates which can't change it ignore the call.
+    fn set_foundation(&self, _foundation: String) {}
+
     /// A unique identifier for just this candidate
     /// Unlike the foundation this is different for each candidate.
     fn id(&self) -> String;
@@ -65,6 +68,9 @@ pub trait Candidate: fmt::Display {
 
     fn priority(&self) -> u32;
 
+    /// Overrides the priority, candidates which can't change it ignore the call.
+    fn set_priority(&self, _priority: u32) {}
+
     /// A transport address related to candidate,
     /// which is useful for diagnostics and other purposes.
     fn related_address(&self) -> Option<CandidateRelatedAddress>;
@@ -87,7 +93,7 @@ pub trait Candidate: fmt::Display {
 }
 
 /// Represents the type of candidate `CandidateType` enum.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
 pub enum CandidateType {
     #[serde(rename = "unspecified")]
     Unspecified,
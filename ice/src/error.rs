@@ -34,6 +34,11 @@ pub enum Error {
     #[error("invalid port number")]
     ErrPort,
 
+    /// Indicates a STUN/TURN server hostname did not resolve to an address of the requested
+    /// family.
+    #[error("no address found for host")]
+    ErrHostNotResolved,
+
     /// Indicates local username fragment insufficient bits are provided.
     /// Have to be at least 24 bits long.
     #[error("local username fragment is less than 24 bits long")]
@@ -96,6 +101,14 @@ pub enum Error {
     #[error("lite agents must only use host candidates")]
     ErrLiteUsingNonHostCandidates,
 
+    /// Indicates a type preference outside of the range allowed by RFC 8445.
+    #[error("type preference must be at most 126")]
+    ErrInvalidTypePreference,
+
+    /// Indicates that the re-gathering interval is zero, which would re-gather without pause.
+    #[error("regather interval must not be zero")]
+    ErrZeroRegatherInterval,
+
     /// Indicates that one or more URL was provided to the agent but no host candidate required them.
     #[error("agent does not need URL with selected candidate types")]
     ErrUselessUrlsProvided,
@@ -131,6 +144,10 @@ pub enum Error {
     #[error("invalid mDNS HostName, must end with .local and can only contain a single '.'")]
     ErrInvalidMulticastDnshostName,
 
+    /// Indicates a remote mDNS candidate could not be resolved in time.
+    #[error("timed out resolving mDNS candidate")]
+    ErrMulticastDnsQueryTimeout,
+
     /// Indicates Restart was called when Agent is in GatheringStateGathering.
     #[error("ICE Agent can not be restarted when gathering")]
     ErrRestartWhenGathering,
@@ -147,6 +164,10 @@ pub enum Error {
     #[error("conn with same remote addr already exists")]
     ErrTcpRemoteAddrAlreadyExists,
 
+    /// Indicates the socket factory can't create TCP listeners.
+    #[error("socket factory does not support TCP listeners")]
+    ErrTcpListenerUnsupported,
+
     #[error("failed to send packet")]
     ErrSendPacket,
     #[error("attribute not long enough to be ICE candidate")]
@@ -210,6 +231,8 @@ pub enum Error {
     Mdns(#[from] mdns::Error),
     #[error("{0}")]
     Turn(#[from] turn::Error),
+    #[error("{0}")]
+    Sdp(#[from] sdp::Error),
 
     #[error("{0}")]
     Other(String),
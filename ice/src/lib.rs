@@ -10,8 +10,11 @@ pub mod mdns;
 pub mod network_type;
 pub mod priority;
 pub mod rand;
+pub mod resolver;
+pub mod socket_factory;
 pub mod state;
 pub mod stats;
+pub mod tcp_mux;
 pub mod tcp_type;
 pub mod udp_mux;
 pub mod udp_network;
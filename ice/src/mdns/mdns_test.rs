@@ -5,6 +5,7 @@ use crate::error::Error;
 use crate::network_type::*;
 
 use regex::Regex;
+use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
 
 #[tokio::test]
@@ -128,6 +129,64 @@ async fn test_multicast_dns_static_host_name() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+#[cfg(not(windows))]
+async fn test_multicast_dns_shared_conn() -> Result<()> {
+    let mdns_conn = match create_multicast_dns(MulticastDnsMode::QueryOnly, "", "") {
+        Ok(Some(conn)) => conn,
+        _ => return Ok(()),
+    };
+
+    let cfg0 = AgentConfig {
+        network_types: vec![NetworkType::Udp4],
+        candidate_types: vec![CandidateType::Host],
+        multicast_dns_mode: MulticastDnsMode::GatherOnly,
+        multicast_dns_conn: Some(Arc::clone(&mdns_conn)),
+        ..Default::default()
+    };
+
+    let a_agent = Arc::new(Agent::new(cfg0).await?);
+    let (a_notifier, mut a_connected) = on_connected();
+    a_agent.on_connection_state_change(a_notifier);
+
+    let cfg1 = AgentConfig {
+        network_types: vec![NetworkType::Udp4],
+        candidate_types: vec![CandidateType::Host],
+        multicast_dns_mode: MulticastDnsMode::QueryOnly,
+        multicast_dns_conn: Some(Arc::clone(&mdns_conn)),
+        multicast_dns_query_timeout: Some(Duration::from_secs(10)),
+        ..Default::default()
+    };
+
+    let b_agent = Arc::new(Agent::new(cfg1).await?);
+    let (b_notifier, mut b_connected) = on_connected();
+    b_agent.on_connection_state_change(b_notifier);
+
+    connect_with_vnet(&a_agent, &b_agent).await?;
+    let _ = a_connected.recv().await;
+    let _ = b_connected.recv().await;
+
+    a_agent.close().await?;
+    b_agent.close().await?;
+
+    // The shared connection is owned by the caller and must still be open
+    mdns_conn.close().await?;
+
+    Ok(())
+}
+
+#[test]
+fn test_multicast_dns_mode_directions() {
+    assert!(!MulticastDnsMode::Disabled.is_query_enabled());
+    assert!(!MulticastDnsMode::Disabled.is_gather_enabled());
+    assert!(MulticastDnsMode::QueryOnly.is_query_enabled());
+    assert!(!MulticastDnsMode::QueryOnly.is_gather_enabled());
+    assert!(MulticastDnsMode::QueryAndGather.is_query_enabled());
+    assert!(MulticastDnsMode::QueryAndGather.is_gather_enabled());
+    assert!(!MulticastDnsMode::GatherOnly.is_query_enabled());
+    assert!(MulticastDnsMode::GatherOnly.is_gather_enabled());
+}
+
 #[test]
 fn test_generate_multicast_dnsname() -> Result<()> {
     let name = generate_multicast_dns_name();
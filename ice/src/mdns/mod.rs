@@ -24,6 +24,21 @@ pub enum MulticastDnsMode {
 
     /// Means remote mDNS candidates will be accepted, and local host candidates will use mDNS.
     QueryAndGather,
+
+    /// Means remote mDNS candidates will be discarded, and local host candidates will use mDNS.
+    GatherOnly,
+}
+
+impl MulticastDnsMode {
+    /// Returns true if remote mDNS candidates are resolved in this mode.
+    pub fn is_query_enabled(&self) -> bool {
+        matches!(self, Self::QueryOnly | Self::QueryAndGather)
+    }
+
+    /// Returns true if local host candidates use mDNS names in this mode.
+    pub fn is_gather_enabled(&self) -> bool {
+        matches!(self, Self::QueryAndGather | Self::GatherOnly)
+    }
 }
 
 impl Default for MulticastDnsMode {
@@ -65,7 +80,7 @@ pub(crate) fn create_multicast_dns(
             let conn = DnsConn::server(addr, Config::default())?;
             Ok(Some(Arc::new(conn)))
         }
-        MulticastDnsMode::QueryAndGather => {
+        MulticastDnsMode::QueryAndGather | MulticastDnsMode::GatherOnly => {
             let conn = DnsConn::server(
                 addr,
                 Config {
@@ -0,0 +1,176 @@
+#[cfg(test)]
+mod resolver_test;
+
+use crate::error::*;
+use crate::url::{ProtoType, SchemeType, Url};
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+/// A DNS SRV record (RFC 2782).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrvRecord {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    pub target: String,
+}
+
+/// Resolves the hostnames of STUN/TURN servers. Implement this to resolve servers through
+/// something other than the system resolver, e.g. split-horizon DNS, DNS over HTTPS or a static
+/// map.
+#[async_trait]
+pub trait Resolver {
+    /// Returns the addresses of `host`.
+    async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>>;
+
+    /// Returns the SRV records of `name`, e.g. `_stun._udp.example.com`. Resolvers which do not
+    /// support SRV records return no records.
+    async fn lookup_srv(&self, _name: &str) -> Result<Vec<SrvRecord>> {
+        Ok(vec![])
+    }
+}
+
+/// A [`Resolver`] answering from a fixed set of records.
+#[derive(Default, Debug, Clone)]
+pub struct StaticResolver {
+    hosts: HashMap<String, Vec<IpAddr>>,
+    srv_records: HashMap<String, Vec<SrvRecord>>,
+}
+
+impl StaticResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the addresses `host` resolves to.
+    pub fn add_host(&mut self, host: &str, ips: Vec<IpAddr>) {
+        self.hosts.entry(host.to_owned()).or_default().extend(ips);
+    }
+
+    /// Adds SRV records for `name`.
+    pub fn add_srv(&mut self, name: &str, records: Vec<SrvRecord>) {
+        self.srv_records
+            .entry(name.to_owned())
+            .or_default()
+            .extend(records);
+    }
+}
+
+#[async_trait]
+impl Resolver for StaticResolver {
+    async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>> {
+        Ok(self.hosts.get(host).cloned().unwrap_or_default())
+    }
+
+    async fn lookup_srv(&self, name: &str) -> Result<Vec<SrvRecord>> {
+        Ok(self.srv_records.get(name).cloned().unwrap_or_default())
+    }
+}
+
+/// Returns the SRV service name for `url` (RFC 7064 S3, RFC 7065 S3).
+pub(crate) fn srv_name(url: &Url) -> Option<String> {
+    let service = match (url.scheme, url.proto) {
+        (SchemeType::Stun, _) => "_stun._udp",
+        (SchemeType::Stuns, _) => "_stuns._tcp",
+        (SchemeType::Turn, ProtoType::Udp) => "_turn._udp",
+        (SchemeType::Turn, ProtoType::Tcp) => "_turn._tcp",
+        (SchemeType::Turns, _) => "_turns._tcp",
+        _ => return None,
+    };
+
+    Some(format!("{}.{}", service, url.host))
+}
+
+fn default_port(scheme: SchemeType) -> u16 {
+    if scheme == SchemeType::Stuns || scheme == SchemeType::Turns {
+        5349
+    } else {
+        3478
+    }
+}
+
+/// Orders SRV records as described in RFC 2782: by ascending priority, and within a priority
+/// by repeatedly picking a record at random with a probability proportional to its weight.
+pub(crate) fn order_srv_records(mut records: Vec<SrvRecord>) -> Vec<SrvRecord> {
+    // Records with weight 0 go first, so they are only picked when the random number is 0
+    records.sort_by(|a, b| a.priority.cmp(&b.priority).then(a.weight.cmp(&b.weight)));
+
+    let mut ordered = Vec::with_capacity(records.len());
+    while !records.is_empty() {
+        let priority = records[0].priority;
+        let end = records
+            .iter()
+            .position(|r| r.priority != priority)
+            .unwrap_or(records.len());
+        let mut group: Vec<SrvRecord> = records.drain(..end).collect();
+
+        while !group.is_empty() {
+            let sum: u32 = group.iter().map(|r| r.weight as u32).sum();
+            let pick = rand::random::<u32>() % (sum + 1);
+            let mut running_sum = 0;
+            let i = group
+                .iter()
+                .position(|r| {
+                    running_sum += r.weight as u32;
+                    running_sum >= pick
+                })
+                .unwrap_or(group.len() - 1);
+            ordered.push(group.remove(i));
+        }
+    }
+
+    ordered
+}
+
+async fn lookup_ip_for_family<R>(resolver: &R, host: &str, use_ipv4: bool) -> Result<IpAddr>
+where
+    R: Resolver + Send + Sync + ?Sized,
+{
+    resolver
+        .lookup_ip(host)
+        .await?
+        .into_iter()
+        .find(|ip| ip.is_ipv4() == use_ipv4)
+        .ok_or(Error::ErrHostNotResolved)
+}
+
+/// Resolves the address of the server in `url` through `resolver`.
+///
+/// SRV records are only looked up when the URL uses the default port of its scheme, as the port
+/// can not be distinguished from an omitted one. Records are tried in the order of
+/// [`order_srv_records`], falling back to the A/AAAA records of the host itself.
+pub(crate) async fn resolve_url<R>(resolver: &R, url: &Url, use_ipv4: bool) -> Result<SocketAddr>
+where
+    R: Resolver + Send + Sync + ?Sized,
+{
+    if let Ok(ip) = url.host.parse::<IpAddr>() {
+        if ip.is_ipv4() != use_ipv4 {
+            return Err(Error::ErrHostNotResolved);
+        }
+        return Ok(SocketAddr::new(ip, url.port));
+    }
+
+    if url.port == default_port(url.scheme) {
+        if let Some(name) = srv_name(url) {
+            let records = match resolver.lookup_srv(&name).await {
+                Ok(records) => records,
+                Err(err) => {
+                    log::debug!("failed to look up SRV records for {}: {}", name, err);
+                    vec![]
+                }
+            };
+            for record in order_srv_records(records) {
+                let target = record.target.trim_end_matches('.');
+                match lookup_ip_for_family(resolver, target, use_ipv4).await {
+                    Ok(ip) => return Ok(SocketAddr::new(ip, record.port)),
+                    Err(err) => log::debug!("failed to resolve SRV target {}: {}", target, err),
+                }
+            }
+        }
+    }
+
+    let ip = lookup_ip_for_family(resolver, &url.host, use_ipv4).await?;
+    Ok(SocketAddr::new(ip, url.port))
+}
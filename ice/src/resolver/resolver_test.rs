@@ -0,0 +1,142 @@
+use super::*;
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+fn test_resolver() -> StaticResolver {
+    let mut resolver = StaticResolver::new();
+    resolver.add_host(
+        "stun.example.com",
+        vec![
+            IpAddr::V6(Ipv6Addr::LOCALHOST),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+        ],
+    );
+    resolver.add_host(
+        "backup.example.com",
+        vec![Ipv4Addr::new(192, 0, 2, 3).into()],
+    );
+    resolver.add_host(
+        "primary.example.com",
+        vec![Ipv4Addr::new(192, 0, 2, 2).into()],
+    );
+    resolver.add_srv(
+        "_turn._udp.turn.example.com",
+        vec![
+            SrvRecord {
+                priority: 20,
+                weight: 0,
+                port: 3480,
+                target: "backup.example.com.".to_owned(),
+            },
+            SrvRecord {
+                priority: 10,
+                weight: 0,
+                port: 3479,
+                target: "primary.example.com.".to_owned(),
+            },
+        ],
+    );
+    resolver
+}
+
+#[tokio::test]
+async fn test_resolve_url_host() -> Result<()> {
+    let resolver = test_resolver();
+
+    let url = Url::parse_url("stun:stun.example.com:19302")?;
+    assert_eq!(
+        resolve_url(&resolver, &url, true).await?,
+        SocketAddr::new(Ipv4Addr::new(192, 0, 2, 1).into(), 19302)
+    );
+    assert_eq!(
+        resolve_url(&resolver, &url, false).await?,
+        SocketAddr::new(Ipv6Addr::LOCALHOST.into(), 19302)
+    );
+
+    let url = Url::parse_url("stun:unknown.example.com")?;
+    assert_eq!(
+        resolve_url(&resolver, &url, true).await,
+        Err(Error::ErrHostNotResolved)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resolve_url_ip_literal() -> Result<()> {
+    let resolver = StaticResolver::new();
+
+    let url = Url::parse_url("stun:192.0.2.10:3478")?;
+    assert_eq!(
+        resolve_url(&resolver, &url, true).await?,
+        SocketAddr::new(Ipv4Addr::new(192, 0, 2, 10).into(), 3478)
+    );
+    assert_eq!(
+        resolve_url(&resolver, &url, false).await,
+        Err(Error::ErrHostNotResolved)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resolve_url_srv() -> Result<()> {
+    let mut resolver = test_resolver();
+
+    // Without a port the SRV record with the lowest priority wins
+    let url = Url::parse_url("turn:turn.example.com")?;
+    assert_eq!(
+        resolve_url(&resolver, &url, true).await?,
+        SocketAddr::new(Ipv4Addr::new(192, 0, 2, 2).into(), 3479)
+    );
+
+    // SRV records are not used with a non-default port
+    resolver.add_host("turn.example.com", vec![Ipv4Addr::new(192, 0, 2, 4).into()]);
+    let url = Url::parse_url("turn:turn.example.com:3500")?;
+    assert_eq!(
+        resolve_url(&resolver, &url, true).await?,
+        SocketAddr::new(Ipv4Addr::new(192, 0, 2, 4).into(), 3500)
+    );
+
+    // SRV records are per transport
+    let url = Url::parse_url("turn:turn.example.com?transport=tcp")?;
+    assert_eq!(
+        resolve_url(&resolver, &url, true).await?,
+        SocketAddr::new(Ipv4Addr::new(192, 0, 2, 4).into(), 3478)
+    );
+
+    Ok(())
+}
+
+fn new_srv_record(priority: u16, weight: u16, port: u16) -> SrvRecord {
+    SrvRecord {
+        priority,
+        weight,
+        port,
+        target: "srv.example.com.".to_owned(),
+    }
+}
+
+#[test]
+fn test_order_srv_records() {
+    let records = vec![
+        new_srv_record(20, 0, 1),
+        new_srv_record(10, 1, 2),
+        new_srv_record(10, 0, 3),
+        new_srv_record(10, 99, 4),
+    ];
+
+    let mut first_ports = HashMap::new();
+    for _ in 0..1000 {
+        let ordered = order_srv_records(records.clone());
+        assert_eq!(ordered.len(), records.len());
+        // The lower priority is always tried first, the higher one last
+        assert!(ordered[..3].iter().all(|r| r.priority == 10));
+        assert_eq!(ordered[3].port, 1);
+        *first_ports.entry(ordered[0].port).or_insert(0) += 1;
+    }
+
+    // Within a priority, records are picked in proportion to their weight
+    assert!(first_ports[&4] > 900, "{:?}", first_ports);
+    assert!(first_ports.len() > 1, "{:?}", first_ports);
+}
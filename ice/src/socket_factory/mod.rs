@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod socket_factory_test;
+
+use crate::error::*;
+
+use async_trait::async_trait;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::{TcpListener, UdpSocket};
+use util::Conn;
+
+/// The backlog of the TCP listeners created by [`SocketOptions`].
+const TCP_LISTEN_BACKLOG: i32 = 1024;
+
+/// Creates the sockets used by an agent. Implement this to control how sockets are created,
+/// e.g. to apply socket options that are not exposed by the agent itself.
+///
+/// The factory is not used when the agent runs on a virtual network.
+#[async_trait]
+pub trait SocketFactory {
+    /// Creates a UDP socket bound to `laddr`.
+    async fn bind_udp(&self, laddr: SocketAddr) -> Result<Arc<dyn Conn + Send + Sync>>;
+
+    /// Creates a TCP listener bound to `laddr`, e.g. to build the
+    /// [`crate::tcp_mux::TCPMuxDefault`] passed to the agent as `tcp_mux`. The agent never
+    /// listens on its own. Factories which only create UDP sockets need not implement it.
+    async fn listen_tcp(&self, _laddr: SocketAddr) -> Result<TcpListener> {
+        Err(Error::ErrTcpListenerUnsupported)
+    }
+}
+
+/// Sets IPV6_TCLASS, the IPv6 counterpart of IP_TOS, which socket2 doesn't expose.
+#[cfg(any(
+    target_os = "android",
+    target_os = "linux",
+    target_os = "ios",
+    target_os = "macos",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+))]
+fn set_tclass_v6(socket: &Socket, tclass: u32) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let tclass = tclass as libc::c_int;
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_IPV6,
+            libc::IPV6_TCLASS,
+            &tclass as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(any(
+    target_os = "android",
+    target_os = "linux",
+    target_os = "ios",
+    target_os = "macos",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+)))]
+fn set_tclass_v6(_socket: &Socket, _tclass: u32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "IPV6_TCLASS is not supported",
+    ))
+}
+
+/// A [`SocketFactory`] which applies a fixed set of socket options to every socket it creates.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// The IP_TOS value set on IPv4 sockets, and the IPV6_TCLASS value set on IPv6 sockets,
+    /// e.g. `46 << 2` to mark traffic with DSCP EF. IPV6_TCLASS is only supported on Linux,
+    /// Android, the Apple platforms and the BSDs.
+    pub tos: Option<u32>,
+
+    /// Binds the socket to a particular device using SO_BINDTODEVICE. Only supported on Linux.
+    pub bind_device: Option<String>,
+
+    /// Sets SO_REUSEADDR.
+    pub reuse_address: bool,
+
+    /// Sets SO_REUSEPORT. Only supported on Unix.
+    pub reuse_port: bool,
+
+    /// Sets SO_RCVBUF.
+    pub recv_buffer_size: Option<usize>,
+
+    /// Sets SO_SNDBUF.
+    pub send_buffer_size: Option<usize>,
+}
+
+impl SocketOptions {
+    fn new_socket(&self, laddr: SocketAddr, ty: Type, protocol: Protocol) -> io::Result<Socket> {
+        let socket = Socket::new(Domain::for_address(laddr), ty, Some(protocol))?;
+
+        if let Some(tos) = self.tos {
+            if laddr.is_ipv4() {
+                socket.set_tos(tos)?;
+            } else {
+                set_tclass_v6(&socket, tos)?;
+            }
+        }
+
+        if let Some(device) = &self.bind_device {
+            #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+            socket.bind_device(Some(device.as_bytes()))?;
+
+            #[cfg(not(any(target_os = "android", target_os = "fuchsia", target_os = "linux")))]
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("binding to device {} is not supported", device),
+            ));
+        }
+
+        if self.reuse_address {
+            socket.set_reuse_address(true)?;
+        }
+
+        if self.reuse_port {
+            #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+            socket.set_reuse_port(true)?;
+
+            #[cfg(not(all(unix, not(any(target_os = "solaris", target_os = "illumos")))))]
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "SO_REUSEPORT is not supported",
+            ));
+        }
+
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+
+        socket.set_nonblocking(true)?;
+        socket.bind(&laddr.into())?;
+
+        Ok(socket)
+    }
+
+    /// Creates a UDP socket bound to `laddr` with these options applied.
+    pub fn bind_udp_socket(&self, laddr: SocketAddr) -> io::Result<UdpSocket> {
+        let socket = self.new_socket(laddr, Type::DGRAM, Protocol::UDP)?;
+
+        UdpSocket::from_std(socket.into())
+    }
+
+    /// Creates a TCP listener bound to `laddr` with these options applied. This is intended for
+    /// building a [`crate::tcp_mux::TCPMuxDefault`].
+    pub fn listen_tcp_socket(&self, laddr: SocketAddr, backlog: i32) -> io::Result<TcpListener> {
+        let socket = self.new_socket(laddr, Type::STREAM, Protocol::TCP)?;
+        socket.listen(backlog)?;
+
+        TcpListener::from_std(socket.into())
+    }
+}
+
+#[async_trait]
+impl SocketFactory for SocketOptions {
+    async fn bind_udp(&self, laddr: SocketAddr) -> Result<Arc<dyn Conn + Send + Sync>> {
+        Ok(Arc::new(self.bind_udp_socket(laddr)?))
+    }
+
+    async fn listen_tcp(&self, laddr: SocketAddr) -> Result<TcpListener> {
+        Ok(self.listen_tcp_socket(laddr, TCP_LISTEN_BACKLOG)?)
+    }
+}
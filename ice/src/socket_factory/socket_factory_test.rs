@@ -0,0 +1,110 @@
+use super::*;
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+#[tokio::test]
+async fn test_socket_options_bind_udp() -> Result<()> {
+    let options = SocketOptions {
+        tos: Some(46 << 2),
+        reuse_address: true,
+        recv_buffer_size: Some(1 << 16),
+        send_buffer_size: Some(1 << 16),
+        ..Default::default()
+    };
+
+    let a = options
+        .bind_udp(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))
+        .await?;
+    let b = options
+        .bind_udp(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))
+        .await?;
+
+    let b_addr = b.local_addr()?;
+    a.send_to(b"ping", b_addr).await?;
+
+    let mut buf = [0u8; 16];
+    let (n, from) = b.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], b"ping");
+    assert_eq!(from, a.local_addr()?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_socket_options_tos_is_applied() -> Result<()> {
+    let options = SocketOptions {
+        tos: Some(46 << 2),
+        ..Default::default()
+    };
+
+    let socket = options.bind_udp_socket(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))?;
+    assert_eq!(socket2::SockRef::from(&socket).tos()?, 46 << 2);
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[tokio::test]
+async fn test_socket_options_tclass_is_applied() -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let options = SocketOptions {
+        tos: Some(46 << 2),
+        ..Default::default()
+    };
+
+    let socket = options.bind_udp_socket(SocketAddr::new(Ipv6Addr::LOCALHOST.into(), 0))?;
+
+    let mut tclass: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_IPV6,
+            libc::IPV6_TCLASS,
+            &mut tclass as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    assert_eq!(ret, 0, "{}", io::Error::last_os_error());
+    assert_eq!(tclass, 46 << 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_socket_options_listen_tcp() -> Result<()> {
+    let options = SocketOptions {
+        reuse_address: true,
+        ..Default::default()
+    };
+
+    let listener = options
+        .listen_tcp(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))
+        .await?;
+    let addr = listener.local_addr()?;
+    let _client = tokio::net::TcpStream::connect(addr).await?;
+    let (_, peer) = listener.accept().await?;
+    assert_eq!(peer.ip(), addr.ip());
+
+    Ok(())
+}
+
+struct UdpOnlyFactory;
+
+#[async_trait]
+impl SocketFactory for UdpOnlyFactory {
+    async fn bind_udp(&self, laddr: SocketAddr) -> Result<Arc<dyn Conn + Send + Sync>> {
+        Ok(Arc::new(UdpSocket::bind(laddr).await?))
+    }
+}
+
+#[tokio::test]
+async fn test_socket_factory_listen_tcp_unsupported() -> Result<()> {
+    let result = UdpOnlyFactory
+        .listen_tcp(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))
+        .await;
+    assert_eq!(result.err(), Some(Error::ErrTcpListenerUnsupported));
+
+    Ok(())
+}
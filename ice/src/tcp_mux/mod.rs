@@ -0,0 +1,321 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use util::{Conn, Error};
+
+use async_trait::async_trait;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, Mutex};
+
+mod tcp_packet_conn;
+pub use tcp_packet_conn::TCPPacketConn;
+
+#[cfg(test)]
+mod tcp_mux_test;
+
+use stun::{
+    attributes::ATTR_USERNAME,
+    message::{is_message as is_stun_message, Message as STUNMessage},
+};
+
+use crate::candidate::RECEIVE_MTU;
+
+/// How long an accepted connection may take to send its first framed packet before it is
+/// dropped, unless TCPMuxParams::with_first_packet_timeout is used.
+pub const DEFAULT_FIRST_PACKET_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reads a single RFC 4571 framed packet (a 16-bit big-endian length followed by the payload)
+/// into `buf` and returns the payload length.
+pub(crate) async fn read_framed_packet<R>(reader: &mut R, buf: &mut [u8]) -> Result<usize, Error>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut header = [0u8; 2];
+    reader.read_exact(&mut header).await?;
+
+    let len = u16::from_be_bytes(header) as usize;
+    if len > buf.len() {
+        return Err(Error::ErrBufferShort);
+    }
+
+    reader.read_exact(&mut buf[..len]).await?;
+
+    Ok(len)
+}
+
+/// Writes `buf` as a single RFC 4571 framed packet.
+pub(crate) async fn write_framed_packet<W>(writer: &mut W, buf: &[u8]) -> Result<usize, Error>
+where
+    W: AsyncWrite + Unpin,
+{
+    if buf.len() > u16::MAX as usize {
+        return Err(Error::ErrPacketTooBig);
+    }
+
+    let mut framed = Vec::with_capacity(buf.len() + 2);
+    framed.extend_from_slice(&(buf.len() as u16).to_be_bytes());
+    framed.extend_from_slice(buf);
+    writer.write_all(&framed).await?;
+
+    Ok(buf.len())
+}
+
+#[async_trait]
+pub trait TCPMux {
+    /// Close the muxing.
+    async fn close(&self) -> Result<(), Error>;
+
+    /// Get the underlying connection for a given ufrag and local IP.
+    async fn get_conn_by_ufrag(
+        self: Arc<Self>,
+        ufrag: &str,
+        local_ip: IpAddr,
+    ) -> Result<Arc<dyn Conn + Send + Sync>, Error>;
+
+    /// Remove all the underlying connections for a given ufrag.
+    async fn remove_conn_by_ufrag(&self, ufrag: &str);
+}
+
+pub struct TCPMuxParams {
+    listener: TcpListener,
+    first_packet_timeout: Duration,
+}
+
+impl TCPMuxParams {
+    pub fn new(listener: TcpListener) -> Self {
+        Self {
+            listener,
+            first_packet_timeout: DEFAULT_FIRST_PACKET_TIMEOUT,
+        }
+    }
+
+    /// Sets how long an accepted connection may take to send the STUN binding request that
+    /// identifies its agent. Idle connections are closed when it expires.
+    pub fn with_first_packet_timeout(mut self, first_packet_timeout: Duration) -> Self {
+        self.first_packet_timeout = first_packet_timeout;
+        self
+    }
+}
+
+/// Accepts passive ICE-TCP connections (RFC 6544) on a single listening socket and routes them
+/// to the agent owning the local ufrag found in the first STUN binding request.
+pub struct TCPMuxDefault {
+    /// The address the listener is bound to.
+    local_addr: SocketAddr,
+
+    /// Maps from ufrag and local IP to the underlying connection.
+    conns: Mutex<HashMap<(String, IpAddr), TCPPacketConn>>,
+
+    /// How long an accepted connection may take to send its first packet.
+    first_packet_timeout: Duration,
+
+    // Close sender
+    closed_watch_tx: Mutex<Option<watch::Sender<()>>>,
+}
+
+impl TCPMuxDefault {
+    pub fn new(params: TCPMuxParams) -> Result<Arc<Self>, Error> {
+        let (closed_watch_tx, closed_watch_rx) = watch::channel(());
+        let local_addr = params.listener.local_addr()?;
+
+        let mux = Arc::new(Self {
+            local_addr,
+            conns: Mutex::default(),
+            first_packet_timeout: params.first_packet_timeout,
+            closed_watch_tx: Mutex::new(Some(closed_watch_tx)),
+        });
+
+        let cloned_mux = Arc::clone(&mux);
+        cloned_mux.start_accept_worker(params.listener, closed_watch_rx);
+
+        Ok(mux)
+    }
+
+    pub async fn is_closed(&self) -> bool {
+        self.closed_watch_tx.lock().await.is_none()
+    }
+
+    /// Returns the address the listener is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    fn start_accept_worker(
+        self: Arc<Self>,
+        listener: TcpListener,
+        mut closed_watch_rx: watch::Receiver<()>,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    res = listener.accept() => {
+                        match res {
+                            Ok((stream, addr)) => {
+                                let mux = Arc::clone(&self);
+                                tokio::spawn(async move {
+                                    mux.handle_stream(stream, addr).await;
+                                });
+                            }
+                            Err(err) if err.kind() == ErrorKind::ConnectionAborted => continue,
+                            Err(err) => {
+                                log::error!("Could not accept tcp connection: {}", err);
+                                break;
+                            }
+                        }
+                    }
+                    _ = closed_watch_rx.changed() => {
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    async fn handle_stream(&self, mut stream: TcpStream, addr: SocketAddr) {
+        let local_ip = match stream.local_addr() {
+            Ok(local_addr) => local_addr.ip(),
+            Err(err) => {
+                log::warn!("Failed to get local address of {}: {}", addr, err);
+                return;
+            }
+        };
+
+        // Without a deadline, idle or slow connections would each hold a task and a socket.
+        let mut buffer = vec![0u8; RECEIVE_MTU];
+        let n = match tokio::time::timeout(
+            self.first_packet_timeout,
+            read_framed_packet(&mut stream, &mut buffer),
+        )
+        .await
+        {
+            Ok(Ok(n)) => n,
+            Ok(Err(err)) => {
+                log::warn!("Failed to read first packet from {}: {}", addr, err);
+                return;
+            }
+            Err(_) => {
+                log::warn!("Timed out waiting for the first packet from {}", addr);
+                return;
+            }
+        };
+
+        if !is_stun_message(&buffer[..n]) {
+            log::warn!("Not a STUN message from {}, closing connection", addr);
+            return;
+        }
+
+        let ufrag = match Self::ufrag_from_stun_message(&buffer[..n], &addr) {
+            Some(ufrag) => ufrag,
+            None => return,
+        };
+
+        let conn = {
+            let conns = self.conns.lock().await;
+            conns.get(&(ufrag.clone(), local_ip)).cloned()
+        };
+
+        match conn {
+            Some(conn) => conn.add_stream(stream, addr, &buffer[..n]).await,
+            None => {
+                log::trace!(
+                    "No connection for ufrag {} on {}, dropping tcp connection from {}",
+                    ufrag,
+                    local_ip,
+                    addr
+                );
+            }
+        }
+    }
+
+    fn ufrag_from_stun_message(buffer: &[u8], addr: &SocketAddr) -> Option<String> {
+        let mut m = STUNMessage::new();
+        if let Err(err) = m.unmarshal_binary(buffer) {
+            log::warn!("Failed to handle decode ICE from {}: {}", addr, err);
+            return None;
+        }
+
+        let (attr, found) = m.attributes.get(ATTR_USERNAME);
+        if !found {
+            log::warn!("No username attribute in STUN message from {}", addr);
+            return None;
+        }
+
+        match String::from_utf8(attr.value) {
+            Ok(s) => s.split(':').next().map(ToOwned::to_owned),
+            Err(err) => {
+                log::warn!(
+                    "Failed to decode USERNAME from STUN message as UTF-8: {}",
+                    err
+                );
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TCPMux for TCPMuxDefault {
+    async fn close(&self) -> Result<(), Error> {
+        let mut closed_tx = self.closed_watch_tx.lock().await;
+
+        if let Some(tx) = closed_tx.take() {
+            let _ = tx.send(());
+            drop(closed_tx);
+
+            let old_conns = {
+                let mut conns = self.conns.lock().await;
+
+                std::mem::take(&mut (*conns))
+            };
+
+            for (_, conn) in old_conns {
+                conn.close();
+            }
+
+            Ok(())
+        } else {
+            Err(Error::ErrAlreadyClosed)
+        }
+    }
+
+    async fn get_conn_by_ufrag(
+        self: Arc<Self>,
+        ufrag: &str,
+        local_ip: IpAddr,
+    ) -> Result<Arc<dyn Conn + Send + Sync>, Error> {
+        if self.is_closed().await {
+            return Err(Error::ErrUseClosedNetworkConn);
+        }
+
+        let mut conns = self.conns.lock().await;
+        let key = (ufrag.to_owned(), local_ip);
+        if let Some(conn) = conns.get(&key) {
+            return Ok(Arc::new(conn.clone()) as Arc<dyn Conn + Send + Sync>);
+        }
+
+        let conn = TCPPacketConn::new(SocketAddr::new(local_ip, self.local_addr.port()));
+        conns.insert(key, conn.clone());
+
+        Ok(Arc::new(conn) as Arc<dyn Conn + Send + Sync>)
+    }
+
+    async fn remove_conn_by_ufrag(&self, ufrag: &str) {
+        let removed_conns: Vec<TCPPacketConn> = {
+            let mut conns = self.conns.lock().await;
+            let keys: Vec<(String, IpAddr)> =
+                conns.keys().filter(|(u, _)| u == ufrag).cloned().collect();
+            keys.iter().filter_map(|key| conns.remove(key)).collect()
+        };
+
+        for conn in removed_conns {
+            conn.close();
+        }
+    }
+}
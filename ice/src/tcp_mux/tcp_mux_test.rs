@@ -0,0 +1,108 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use super::*;
+use crate::error::Result;
+use stun::agent::TransactionId;
+use stun::message::{Message, BINDING_REQUEST};
+use stun::textattrs::Username;
+
+use tokio::time::timeout;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn binding_request(ufrag: &str) -> Result<Message> {
+    let mut m = Message::new();
+    m.build(&[
+        Box::new(BINDING_REQUEST),
+        Box::new(TransactionId::new()),
+        Box::new(Username::new(ATTR_USERNAME, format!("{}:remote", ufrag))),
+    ])?;
+
+    Ok(m)
+}
+
+#[tokio::test]
+async fn test_tcp_mux_routes_by_ufrag() -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let tcp_mux = TCPMuxDefault::new(TCPMuxParams::new(listener))?;
+    let listen_addr = tcp_mux.local_addr();
+
+    let conn = Arc::clone(&tcp_mux)
+        .get_conn_by_ufrag("myufrag", Ipv4Addr::LOCALHOST.into())
+        .await?;
+    assert_eq!(conn.local_addr()?.port(), listen_addr.port());
+
+    let mut client = TcpStream::connect(listen_addr).await?;
+    let client_addr = client.local_addr()?;
+
+    let m = binding_request("myufrag")?;
+    write_framed_packet(&mut client, &m.raw).await?;
+
+    let mut buf = vec![0u8; RECEIVE_MTU];
+    let (n, from) = timeout(TIMEOUT, conn.recv_from(&mut buf))
+        .await
+        .expect("timed out waiting for the first packet")?;
+    assert_eq!(&buf[..n], &m.raw[..]);
+    assert_eq!(from, client_addr);
+
+    conn.send_to(b"hello", client_addr).await?;
+    let n = timeout(TIMEOUT, read_framed_packet(&mut client, &mut buf))
+        .await
+        .expect("timed out waiting for the response")?;
+    assert_eq!(&buf[..n], b"hello");
+
+    tcp_mux.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tcp_mux_drops_unknown_ufrag() -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let tcp_mux = TCPMuxDefault::new(TCPMuxParams::new(listener))?;
+    let listen_addr = tcp_mux.local_addr();
+
+    let conn = Arc::clone(&tcp_mux)
+        .get_conn_by_ufrag("myufrag", Ipv4Addr::LOCALHOST.into())
+        .await?;
+
+    let mut client = TcpStream::connect(listen_addr).await?;
+    let m = binding_request("otherufrag")?;
+    write_framed_packet(&mut client, &m.raw).await?;
+
+    let mut buf = vec![0u8; RECEIVE_MTU];
+    assert!(
+        timeout(Duration::from_millis(200), conn.recv_from(&mut buf))
+            .await
+            .is_err(),
+        "packet for an unknown ufrag must not be delivered"
+    );
+
+    tcp_mux.remove_conn_by_ufrag("myufrag").await;
+    assert!(conn.recv_from(&mut buf).await.is_err());
+
+    tcp_mux.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tcp_mux_closes_idle_connection() -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let tcp_mux = TCPMuxDefault::new(
+        TCPMuxParams::new(listener).with_first_packet_timeout(Duration::from_millis(100)),
+    )?;
+
+    // The client never sends its binding request, so the mux closes the connection.
+    let mut client = TcpStream::connect(tcp_mux.local_addr()).await?;
+    let mut buf = [0u8; 1];
+    let n = timeout(TIMEOUT, client.read(&mut buf))
+        .await
+        .expect("idle connection was not closed")?;
+    assert_eq!(n, 0);
+
+    tcp_mux.close().await?;
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use std::{collections::HashMap, io, net::SocketAddr, sync::Arc};
+
+use async_trait::async_trait;
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, watch, Mutex};
+
+use util::{sync::Mutex as SyncMutex, Conn, Error};
+
+use super::{read_framed_packet, write_framed_packet, RECEIVE_MTU};
+
+type ConnResult<T> = Result<T, util::Error>;
+
+/// The number of inbound packets that can be queued before reading from the streams stalls.
+const INBOUND_QUEUE_SIZE: usize = 128;
+
+/// A packet oriented view over all the passive ICE-TCP streams accepted for one ufrag and local
+/// IP. Each remote address maps to its own TCP stream; packets are framed per RFC 4571.
+#[derive(Clone)]
+pub struct TCPPacketConn {
+    inner: Arc<TCPPacketConnInner>,
+}
+
+struct TCPPacketConnInner {
+    local_addr: SocketAddr,
+
+    /// Write halves of the accepted streams, keyed by remote address.
+    writers: Mutex<HashMap<SocketAddr, Arc<Mutex<OwnedWriteHalf>>>>,
+
+    inbound_tx: mpsc::Sender<(Vec<u8>, SocketAddr)>,
+    inbound_rx: Mutex<mpsc::Receiver<(Vec<u8>, SocketAddr)>>,
+
+    /// Close Sender. We'll send a value on this channel when we close
+    closed_watch_tx: SyncMutex<Option<watch::Sender<bool>>>,
+    closed_watch_rx: watch::Receiver<bool>,
+}
+
+impl TCPPacketConn {
+    /// Creates a new [`TCPPacketConn`].
+    pub fn new(local_addr: SocketAddr) -> Self {
+        let (inbound_tx, inbound_rx) = mpsc::channel(INBOUND_QUEUE_SIZE);
+        let (closed_watch_tx, closed_watch_rx) = watch::channel(false);
+
+        Self {
+            inner: Arc::new(TCPPacketConnInner {
+                local_addr,
+                writers: Mutex::default(),
+                inbound_tx,
+                inbound_rx: Mutex::new(inbound_rx),
+                closed_watch_tx: SyncMutex::new(Some(closed_watch_tx)),
+                closed_watch_rx,
+            }),
+        }
+    }
+
+    /// Returns true if this connection is closed.
+    pub fn is_closed(&self) -> bool {
+        self.inner.closed_watch_tx.lock().is_none()
+    }
+
+    /// Closes this connection and all of its streams.
+    pub fn close(&self) {
+        let mut closed_tx = self.inner.closed_watch_tx.lock();
+        if let Some(tx) = closed_tx.take() {
+            let _ = tx.send(true);
+        }
+    }
+
+    /// Attaches an accepted stream from `addr`. `first_packet` is the already consumed packet
+    /// which was used to route the stream to this connection.
+    pub(crate) async fn add_stream(
+        &self,
+        stream: TcpStream,
+        addr: SocketAddr,
+        first_packet: &[u8],
+    ) {
+        if self.is_closed() {
+            return;
+        }
+
+        let (mut reader, writer) = stream.into_split();
+        {
+            let mut writers = self.inner.writers.lock().await;
+            writers.insert(addr, Arc::new(Mutex::new(writer)));
+        }
+
+        if self
+            .inner
+            .inbound_tx
+            .send((first_packet.to_vec(), addr))
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        let inner = Arc::clone(&self.inner);
+        let mut closed_watch_rx = self.inner.closed_watch_rx.clone();
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; RECEIVE_MTU];
+            loop {
+                tokio::select! {
+                    res = read_framed_packet(&mut reader, &mut buffer) => {
+                        match res {
+                            Ok(n) => {
+                                if inner.inbound_tx.send((buffer[..n].to_vec(), addr)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Err(err) => {
+                                log::debug!("tcp connection from {} closed: {}", addr, err);
+                                break;
+                            }
+                        }
+                    }
+                    _ = closed_watch_rx.changed() => break,
+                }
+            }
+
+            let mut writers = inner.writers.lock().await;
+            writers.remove(&addr);
+        });
+    }
+}
+
+#[async_trait]
+impl Conn for TCPPacketConn {
+    async fn connect(&self, _addr: SocketAddr) -> ConnResult<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "Not applicable").into())
+    }
+
+    async fn recv(&self, _buf: &mut [u8]) -> ConnResult<usize> {
+        Err(io::Error::new(io::ErrorKind::Other, "Not applicable").into())
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> ConnResult<(usize, SocketAddr)> {
+        let mut closed_watch_rx = self.inner.closed_watch_rx.clone();
+        if *closed_watch_rx.borrow() {
+            return Err(Error::ErrUseClosedNetworkConn);
+        }
+
+        let mut inbound_rx = self.inner.inbound_rx.lock().await;
+        tokio::select! {
+            packet = inbound_rx.recv() => {
+                match packet {
+                    Some((data, addr)) => {
+                        if data.len() > buf.len() {
+                            return Err(Error::ErrBufferShort);
+                        }
+                        buf[..data.len()].copy_from_slice(&data);
+                        Ok((data.len(), addr))
+                    }
+                    None => Err(Error::ErrUseClosedNetworkConn),
+                }
+            }
+            _ = closed_watch_rx.changed() => Err(Error::ErrUseClosedNetworkConn),
+        }
+    }
+
+    async fn send(&self, _buf: &[u8]) -> ConnResult<usize> {
+        Err(io::Error::new(io::ErrorKind::Other, "Not applicable").into())
+    }
+
+    async fn send_to(&self, buf: &[u8], target: SocketAddr) -> ConnResult<usize> {
+        if self.is_closed() {
+            return Err(Error::ErrUseClosedNetworkConn);
+        }
+
+        let writer = {
+            let writers = self.inner.writers.lock().await;
+            writers.get(&target).cloned()
+        };
+
+        if let Some(writer) = writer {
+            let mut writer = writer.lock().await;
+            write_framed_packet(&mut *writer, buf).await
+        } else {
+            Err(Error::ErrNoRemAddr)
+        }
+    }
+
+    fn local_addr(&self) -> ConnResult<SocketAddr> {
+        Ok(self.inner.local_addr)
+    }
+
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    async fn close(&self) -> ConnResult<()> {
+        TCPPacketConn::close(self);
+
+        Ok(())
+    }
+}
@@ -49,6 +49,10 @@ impl EphemeralUDP {
 /// **Muxed**
 ///
 /// In muxed mode a single UDP socket is used and all connections are muxed over this single socket.
+/// Inbound packets are routed to an agent by the local ufrag in the STUN USERNAME attribute and,
+/// once a remote address has been seen, by that address. A single [`UDPMux`] can therefore be
+/// shared by any number of agents, e.g. all the peer connections of an SFU, by cloning the
+/// `Arc` into each agent's configuration.
 ///
 #[derive(Clone)]
 pub enum UDPNetwork {
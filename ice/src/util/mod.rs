@@ -4,11 +4,14 @@ mod util_test;
 use crate::agent::agent_config::{InterfaceFilterFn, IpFilterFn};
 use crate::error::*;
 use crate::network_type::*;
+use crate::resolver::{resolve_url, Resolver};
+use crate::socket_factory::SocketFactory;
+use crate::url::Url;
 
 use std::collections::HashSet;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
-use stun::{agent::*, attributes::*, integrity::*, message::*, textattrs::*, xoraddr::*};
+use stun::{agent::*, attributes::*, integrity::*, message::*, xoraddr::*};
 use tokio::time::Duration;
 use util::{vnet::net::*, Conn};
 
@@ -22,15 +25,13 @@ pub fn create_addr(_network: NetworkType, ip: IpAddr, port: u16) -> SocketAddr {
 }
 
 pub fn assert_inbound_username(m: &Message, expected_username: &str) -> Result<()> {
-    let mut username = Username::new(ATTR_USERNAME, String::new());
-    username.get_from(m)?;
-
-    if username.to_string() != expected_username {
+    let username = m.get_ref(ATTR_USERNAME)?;
+    if username != expected_username.as_bytes() {
         return Err(Error::Other(format!(
             "{:?} expected({}) actual({})",
             Error::ErrMismatchUsername,
             expected_username,
-            username,
+            String::from_utf8_lossy(username),
         )));
     }
 
@@ -131,14 +132,44 @@ pub async fn local_interfaces(
     ips
 }
 
+/// Binds a UDP socket to `laddr`, using `socket_factory` when one is provided and `vnet` is not
+/// a virtual network.
+pub(crate) async fn bind_udp(
+    vnet: &Arc<Net>,
+    socket_factory: &Option<Arc<dyn SocketFactory + Send + Sync>>,
+    laddr: SocketAddr,
+) -> Result<Arc<dyn Conn + Send + Sync>> {
+    match socket_factory {
+        Some(socket_factory) if !vnet.is_virtual() => socket_factory.bind_udp(laddr).await,
+        _ => Ok(vnet.bind(laddr).await?),
+    }
+}
+
+/// Resolves the address of the STUN/TURN server in `url`, using `resolver` when one is provided.
+pub(crate) async fn resolve_server_addr(
+    vnet: &Arc<Net>,
+    resolver: &Option<Arc<dyn Resolver + Send + Sync>>,
+    url: &Url,
+    use_ipv4: bool,
+) -> Result<SocketAddr> {
+    match resolver {
+        Some(resolver) => resolve_url(&**resolver, url, use_ipv4).await,
+        None => {
+            let host_port = format!("{}:{}", url.host, url.port);
+            Ok(vnet.resolve_addr(use_ipv4, &host_port).await?)
+        }
+    }
+}
+
 pub async fn listen_udp_in_port_range(
     vnet: &Arc<Net>,
+    socket_factory: &Option<Arc<dyn SocketFactory + Send + Sync>>,
     port_max: u16,
     port_min: u16,
     laddr: SocketAddr,
 ) -> Result<Arc<dyn Conn + Send + Sync>> {
     if laddr.port() != 0 || (port_min == 0 && port_max == 0) {
-        return Ok(vnet.bind(laddr).await?);
+        return bind_udp(vnet, socket_factory, laddr).await;
     }
     let i = if port_min == 0 { 1 } else { port_min };
     let j = if port_max == 0 { 0xFFFF } else { port_max };
@@ -150,7 +181,7 @@ pub async fn listen_udp_in_port_range(
     let mut port_current = port_start;
     loop {
         let laddr = SocketAddr::new(laddr.ip(), port_current);
-        match vnet.bind(laddr).await {
+        match bind_udp(vnet, socket_factory, laddr).await {
             Ok(c) => return Ok(c),
             Err(err) => log::debug!("failed to listen {}: {}", laddr, err),
         };
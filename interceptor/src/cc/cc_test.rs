@@ -0,0 +1,87 @@
+use super::*;
+use crate::mock::mock_stream::MockStream;
+
+fn receiver_report(fraction_lost: u8) -> Box<dyn rtcp::packet::Packet + Send + Sync> {
+    Box::new(ReceiverReport {
+        ssrc: 2,
+        reports: vec![ReceptionReport {
+            ssrc: 1,
+            fraction_lost,
+            ..Default::default()
+        }],
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_round_trip_time() {
+    assert_eq!(
+        Some(Duration::from_micros(6_125_000)),
+        round_trip_time(0xb710_8000, 0x0005_4000, 0xb705_2000)
+    );
+    assert_eq!(None, round_trip_time(0xb710_8000, 0x0005_4000, 0));
+    assert_eq!(None, round_trip_time(0x0001_0000, 0x0005_4000, 0xb705_2000));
+}
+
+#[tokio::test]
+async fn test_bandwidth_estimator() -> Result<()> {
+    let estimator = BandwidthEstimator::builder()
+        .with_initial_bitrate(1_000_000)
+        .with_min_bitrate(100_000)
+        .with_max_bitrate(2_000_000)
+        .build_estimator();
+    assert_eq!(1_000_000, estimator.estimate().target_bitrate);
+    let mut estimates = estimator.subscribe();
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            ..Default::default()
+        },
+        Arc::clone(&estimator) as Arc<dyn Interceptor + Send + Sync>,
+    )
+    .await;
+
+    // no loss increases the target bitrate
+    stream.receive_rtcp(vec![receiver_report(0)]).await;
+    stream.read_rtcp().await;
+    estimates.changed().await.unwrap();
+    assert_eq!(
+        BandwidthEstimate {
+            target_bitrate: 1_050_000,
+            loss_rate: 0.0,
+            round_trip_time: None,
+        },
+        *estimates.borrow()
+    );
+
+    // a moderate loss keeps it
+    stream.receive_rtcp(vec![receiver_report(10)]).await;
+    stream.read_rtcp().await;
+    assert_eq!(1_050_000, estimator.estimate().target_bitrate);
+
+    // a high loss decreases it
+    stream.receive_rtcp(vec![receiver_report(128)]).await;
+    stream.read_rtcp().await;
+    let estimate = estimator.estimate();
+    assert_eq!(787_500, estimate.target_bitrate);
+    assert_eq!(0.5, estimate.loss_rate);
+
+    // the REMB of the remote peer caps it, and the minimum bitrate is kept
+    stream
+        .receive_rtcp(vec![Box::new(ReceiverEstimatedMaximumBitrate {
+            sender_ssrc: 2,
+            bitrate: 50_000.0,
+            ssrcs: vec![1],
+        })])
+        .await;
+    stream.read_rtcp().await;
+    assert_eq!(100_000, estimator.estimate().target_bitrate);
+
+    // closing the estimator ends the subscriptions
+    estimates.borrow_and_update();
+    stream.close().await?;
+    assert!(estimates.changed().await.is_err());
+
+    Ok(())
+}
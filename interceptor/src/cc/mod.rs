@@ -0,0 +1,308 @@
+#[cfg(test)]
+mod cc_test;
+
+use crate::*;
+
+use rtcp::payload_feedbacks::receiver_estimated_maximum_bitrate::ReceiverEstimatedMaximumBitrate;
+use rtcp::receiver_report::ReceiverReport;
+use rtcp::reception_report::ReceptionReport;
+use rtcp::sender_report::SenderReport;
+use rtp::extension::abs_send_time_extension::unix2ntp;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use util::sync::Mutex;
+
+/// DEFAULT_INITIAL_BITRATE is the target bitrate in bits per second before any feedback
+/// is received.
+pub const DEFAULT_INITIAL_BITRATE: u64 = 1_000_000;
+/// DEFAULT_MIN_BITRATE is the lowest target bitrate in bits per second.
+pub const DEFAULT_MIN_BITRATE: u64 = 30_000;
+/// DEFAULT_MAX_BITRATE is the highest target bitrate in bits per second.
+pub const DEFAULT_MAX_BITRATE: u64 = 10_000_000;
+
+/// The loss based controller of <https://datatracker.ietf.org/doc/html/draft-ietf-rmcat-gcc-02#section-6>:
+/// the target bitrate decreases above LOSS_HIGH, and increases by INCREASE_FACTOR below LOSS_LOW.
+const LOSS_LOW: f64 = 0.02;
+const LOSS_HIGH: f64 = 0.1;
+const INCREASE_FACTOR: f64 = 1.05;
+
+/// BandwidthEstimate is the estimate of the bandwidth available to send to the remote peer.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BandwidthEstimate {
+    /// target_bitrate is the bitrate in bits per second which the media sent should not exceed.
+    pub target_bitrate: u64,
+    /// loss_rate is the fraction of the packets sent which were lost, from 0 to 1, in the
+    /// latest receiver reports.
+    pub loss_rate: f64,
+    /// round_trip_time is the latest round trip time derived from the receiver reports.
+    pub round_trip_time: Option<Duration>,
+}
+
+/// BandwidthEstimatorBuilder is a InterceptorBuilder for a BandwidthEstimator
+#[derive(Debug, Clone)]
+pub struct BandwidthEstimatorBuilder {
+    initial_bitrate: u64,
+    min_bitrate: u64,
+    max_bitrate: u64,
+}
+
+impl Default for BandwidthEstimatorBuilder {
+    fn default() -> Self {
+        BandwidthEstimatorBuilder {
+            initial_bitrate: DEFAULT_INITIAL_BITRATE,
+            min_bitrate: DEFAULT_MIN_BITRATE,
+            max_bitrate: DEFAULT_MAX_BITRATE,
+        }
+    }
+}
+
+impl BandwidthEstimatorBuilder {
+    /// with_initial_bitrate sets the target bitrate before any feedback is received.
+    pub fn with_initial_bitrate(mut self, initial_bitrate: u64) -> BandwidthEstimatorBuilder {
+        self.initial_bitrate = initial_bitrate;
+        self
+    }
+
+    /// with_min_bitrate sets the lowest target bitrate.
+    pub fn with_min_bitrate(mut self, min_bitrate: u64) -> BandwidthEstimatorBuilder {
+        self.min_bitrate = min_bitrate;
+        self
+    }
+
+    /// with_max_bitrate sets the highest target bitrate.
+    pub fn with_max_bitrate(mut self, max_bitrate: u64) -> BandwidthEstimatorBuilder {
+        self.max_bitrate = max_bitrate;
+        self
+    }
+
+    /// build_estimator constructs a new BandwidthEstimator, which is kept by the caller to
+    /// read the estimates.
+    pub fn build_estimator(&self) -> Arc<BandwidthEstimator> {
+        let min_bitrate = self.min_bitrate.min(self.max_bitrate);
+        let initial_bitrate = self.initial_bitrate.max(min_bitrate).min(self.max_bitrate);
+        let (tx, rx) = watch::channel(BandwidthEstimate {
+            target_bitrate: initial_bitrate,
+            ..Default::default()
+        });
+
+        Arc::new(BandwidthEstimator {
+            internal: Arc::new(BandwidthEstimatorInternal {
+                min_bitrate,
+                max_bitrate: self.max_bitrate,
+                state: Mutex::new(EstimatorState {
+                    target_bitrate: initial_bitrate as f64,
+                    remb_bitrate: None,
+                }),
+                tx: Mutex::new(Some(tx)),
+                now_gen: Arc::new(SystemTime::now),
+            }),
+            rx,
+        })
+    }
+}
+
+impl InterceptorBuilder for BandwidthEstimatorBuilder {
+    /// build constructs a new BandwidthEstimator
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        Ok(self.build_estimator())
+    }
+}
+
+struct EstimatorState {
+    target_bitrate: f64,
+    remb_bitrate: Option<f64>,
+}
+
+struct BandwidthEstimatorInternal {
+    min_bitrate: u64,
+    max_bitrate: u64,
+    state: Mutex<EstimatorState>,
+    tx: Mutex<Option<watch::Sender<BandwidthEstimate>>>,
+    now_gen: Arc<dyn Fn() -> SystemTime + Send + Sync>,
+}
+
+impl BandwidthEstimatorInternal {
+    fn on_rtcp(&self, pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>]) {
+        // Middle 32 bits
+        let now = (unix2ntp((self.now_gen)()) >> 16) as u32;
+
+        let mut reports: Vec<&ReceptionReport> = vec![];
+        let mut remb_bitrate = None;
+        for p in pkts {
+            if let Some(rr) = p.as_any().downcast_ref::<ReceiverReport>() {
+                reports.extend(&rr.reports);
+            } else if let Some(sr) = p.as_any().downcast_ref::<SenderReport>() {
+                reports.extend(&sr.reports);
+            } else if let Some(remb) = p.as_any().downcast_ref::<ReceiverEstimatedMaximumBitrate>()
+            {
+                remb_bitrate = Some(remb.bitrate as f64);
+            }
+        }
+        if reports.is_empty() && remb_bitrate.is_none() {
+            return;
+        }
+
+        let mut estimate = {
+            let tx = self.tx.lock();
+            match &*tx {
+                Some(tx) => *tx.borrow(),
+                None => return,
+            }
+        };
+
+        let mut state = self.state.lock();
+        if remb_bitrate.is_some() {
+            state.remb_bitrate = remb_bitrate;
+        }
+
+        if !reports.is_empty() {
+            let loss_rate = reports
+                .iter()
+                .map(|r| r.fraction_lost as f64 / 256.0)
+                .sum::<f64>()
+                / reports.len() as f64;
+            if loss_rate > LOSS_HIGH {
+                state.target_bitrate *= 1.0 - 0.5 * loss_rate;
+            } else if loss_rate < LOSS_LOW {
+                state.target_bitrate *= INCREASE_FACTOR;
+            }
+            estimate.loss_rate = loss_rate;
+
+            if let Some(rtt) = reports
+                .iter()
+                .rev()
+                .find_map(|r| round_trip_time(now, r.delay, r.last_sender_report))
+            {
+                estimate.round_trip_time = Some(rtt);
+            }
+        }
+
+        let mut max_bitrate = self.max_bitrate as f64;
+        if let Some(remb_bitrate) = state.remb_bitrate {
+            max_bitrate = max_bitrate.min(remb_bitrate);
+        }
+        state.target_bitrate = state
+            .target_bitrate
+            .min(max_bitrate)
+            .max(self.min_bitrate as f64);
+        estimate.target_bitrate = state.target_bitrate as u64;
+
+        if let Some(tx) = &*self.tx.lock() {
+            let _ = tx.send(estimate);
+        }
+    }
+}
+
+/// round_trip_time computes the round trip time from the time of arrival of a reception
+/// report and its DLSR and LSR, all in the middle 32 bits of NTP timestamps.
+fn round_trip_time(now: u32, delay: u32, last_sender_report: u32) -> Option<Duration> {
+    if last_sender_report == 0 {
+        return None;
+    }
+    let rtt = now.checked_sub(delay)?.checked_sub(last_sender_report)?;
+    Some(Duration::from_micros((rtt as u64 * 1_000_000) >> 16))
+}
+
+/// BandwidthEstimator is a congestion controller (cc) which estimates the bandwidth
+/// available to send to the remote peer, from the loss and the round trip time of the
+/// receiver reports, and from the REMB of the remote peer, with the loss based controller
+/// of Google Congestion Control.
+pub struct BandwidthEstimator {
+    internal: Arc<BandwidthEstimatorInternal>,
+    rx: watch::Receiver<BandwidthEstimate>,
+}
+
+impl BandwidthEstimator {
+    /// builder returns a new BandwidthEstimatorBuilder.
+    pub fn builder() -> BandwidthEstimatorBuilder {
+        BandwidthEstimatorBuilder::default()
+    }
+
+    /// estimate returns the latest estimate.
+    pub fn estimate(&self) -> BandwidthEstimate {
+        *self.rx.borrow()
+    }
+
+    /// subscribe returns a receiver which is notified of each new estimate, until the
+    /// BandwidthEstimator is closed.
+    pub fn subscribe(&self) -> watch::Receiver<BandwidthEstimate> {
+        self.rx.clone()
+    }
+}
+
+#[async_trait]
+impl Interceptor for BandwidthEstimator {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        Arc::new(BandwidthEstimatorReader {
+            reader,
+            internal: Arc::clone(&self.internal),
+        })
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        reader
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    /// close closes the Interceptor, which ends the receivers returned by subscribe.
+    async fn close(&self) -> Result<()> {
+        let mut tx = self.internal.tx.lock();
+        tx.take();
+        Ok(())
+    }
+}
+
+struct BandwidthEstimatorReader {
+    reader: Arc<dyn RTCPReader + Send + Sync>,
+    internal: Arc<BandwidthEstimatorInternal>,
+}
+
+#[async_trait]
+impl RTCPReader for BandwidthEstimatorReader {
+    /// read a batch of rtcp packets
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attributes) = self.reader.read(buf, attributes).await?;
+
+        let mut b = &buf[..n];
+        if let Ok(pkts) = rtcp::packet::unmarshal(&mut b) {
+            self.internal.on_rtcp(&pkts);
+        }
+
+        Ok((n, attributes))
+    }
+}
@@ -10,6 +10,7 @@ use std::sync::Arc;
 
 use stream_info::StreamInfo;
 
+pub mod cc;
 pub mod chain;
 mod error;
 pub mod mock;
@@ -3,7 +3,7 @@ use std::fmt;
 use std::sync::Arc;
 use std::time::SystemTime;
 
-use super::{inbound, outbound, StatsContainer};
+use super::{inbound, outbound, RemoteReport, StatsContainer};
 use async_trait::async_trait;
 use rtcp::extended_report::{DLRRReportBlock, ExtendedReport};
 use rtcp::payload_feedbacks::full_intra_request::FullIntraRequest;
@@ -11,8 +11,8 @@ use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
 use rtcp::receiver_report::ReceiverReport;
 use rtcp::sender_report::SenderReport;
 use rtcp::transport_feedbacks::transport_layer_nack::TransportLayerNack;
-use rtp::extension::abs_send_time_extension::unix2ntp;
-use tokio::sync::{mpsc, oneshot};
+use rtp::extension::abs_send_time_extension::{ntp2unix, unix2ntp};
+use tokio::sync::{broadcast, mpsc, oneshot};
 use tokio::time::Duration;
 
 use util::sync::Mutex;
@@ -22,6 +22,9 @@ use crate::error::Result;
 use crate::stream_info::StreamInfo;
 use crate::{Attributes, Interceptor, RTCPReader, RTCPWriter, RTPReader, RTPWriter};
 
+/// The seconds between the NTP epoch and the unix epoch.
+const NTP_UNIX_EPOCH_OFFSET: u64 = 0x83AA7E80;
+
 #[derive(Debug)]
 enum Message {
     StatUpdate {
@@ -79,6 +82,7 @@ enum StatsUpdate {
     /// Stats collected from recieved Sender Reports i.e. where we have an inbound RTP stream.
     InboundSenderRerport {
         packets_and_bytes_sent: Option<(u32, u32)>,
+        remote_timestamp: Option<SystemTime>,
         rtt_ms: Option<f64>,
     },
 }
@@ -89,6 +93,7 @@ pub struct StatsInterceptor {
     send_streams: Mutex<HashMap<u32, Arc<RTPWriteRecorder>>>,
 
     tx: mpsc::Sender<Message>,
+    reports: broadcast::Sender<RemoteReport>,
 
     id: String,
     now_gen: Arc<dyn Fn() -> SystemTime + Send + Sync>,
@@ -97,6 +102,7 @@ pub struct StatsInterceptor {
 impl StatsInterceptor {
     pub fn new(id: String) -> Self {
         let (tx, rx) = mpsc::channel(100);
+        let (reports, _) = broadcast::channel(100);
 
         tokio::spawn(run_stats_reducer(rx));
 
@@ -105,6 +111,7 @@ impl StatsInterceptor {
             recv_streams: Default::default(),
             send_streams: Default::default(),
             tx,
+            reports,
             now_gen: Arc::new(SystemTime::now),
         }
     }
@@ -114,6 +121,7 @@ impl StatsInterceptor {
         F: Fn() -> SystemTime + Send + Sync + 'static,
     {
         let (tx, rx) = mpsc::channel(100);
+        let (reports, _) = broadcast::channel(100);
         tokio::spawn(run_stats_reducer(rx));
 
         Self {
@@ -121,6 +129,7 @@ impl StatsInterceptor {
             recv_streams: Default::default(),
             send_streams: Default::default(),
             tx,
+            reports,
             now_gen: Arc::new(now_gen),
         }
     }
@@ -168,6 +177,13 @@ impl StatsInterceptor {
 
         rx.await.unwrap_or_default()
     }
+
+    /// subscribe_reports returns a receiver which is notified of each Receiver and Sender
+    /// Report read, once the stats of its stream are updated with it, so that the stats
+    /// fetched afterwards include the report.
+    pub fn subscribe_reports(&self) -> broadcast::Receiver<RemoteReport> {
+        self.reports.subscribe()
+    }
 }
 
 async fn run_stats_reducer(mut rx: mpsc::Receiver<Message>) {
@@ -282,13 +298,14 @@ fn handle_stats_update(ssrc_stats: &mut StatsContainer, ssrc: u32, update: Stats
         StatsUpdate::InboundSenderRerport {
             rtt_ms,
             packets_and_bytes_sent,
+            remote_timestamp,
         } => {
             // This is a sender report we received, as such it concerns an RTP stream that's
             // outbound at the remote.
             let stats = ssrc_stats.get_or_create_inbound_stream_stats(ssrc);
 
             if let Some((packets_sent, bytes_sent)) = packets_and_bytes_sent {
-                stats.record_sender_report(packets_sent, bytes_sent);
+                stats.record_sender_report(packets_sent, bytes_sent, remote_timestamp);
             }
             stats.record_remote_round_trip_time(rtt_ms);
 
@@ -375,6 +392,7 @@ impl Interceptor for StatsInterceptor {
         Arc::new(RTCPReadInterceptor {
             rtcp_reader: reader,
             tx: self.tx.clone(),
+            reports: self.reports.clone(),
             now_gen: move || now(),
         })
     }
@@ -383,6 +401,7 @@ impl Interceptor for StatsInterceptor {
 pub struct RTCPReadInterceptor<F> {
     rtcp_reader: Arc<dyn RTCPReader + Send + Sync>,
     tx: mpsc::Sender<Message>,
+    reports: broadcast::Sender<RemoteReport>,
     now_gen: F,
 }
 
@@ -561,6 +580,9 @@ where
                 })
                 .await;
 
+            let have_receiver_report = !receiver_reports.is_empty();
+            let have_sender_report = sender_reports.iter().any(|sr| sr.sr_packets_sent.is_some());
+
             let futures = receiver_reports.into_iter().map(|rr| {
                 self.tx.send(Message::StatUpdate {
                     ssrc,
@@ -592,6 +614,11 @@ where
                         packets_and_bytes_sent: sr
                             .sr_packets_sent
                             .and_then(|ps| sr.sr_bytes_sent.map(|bs| (ps, bs))),
+                        // NTP times before the unix epoch are left out
+                        remote_timestamp: sr
+                            .sr_ntp_time
+                            .filter(|t| t >> 32 >= NTP_UNIX_EPOCH_OFFSET)
+                            .map(ntp2unix),
                         rtt_ms,
                     },
                 })
@@ -600,6 +627,15 @@ where
                 // TODO: Use futures::join_all
                 let _ = fut.await;
             }
+
+            // The updates are queued before the notifications, so the stats fetched when
+            // notified include them. Sending fails without subscribers, which is fine.
+            if have_receiver_report {
+                let _ = self.reports.send(RemoteReport::ReceiverReport(ssrc));
+            }
+            if have_sender_report {
+                let _ = self.reports.send(RemoteReport::SenderReport(ssrc));
+            }
         }
 
         Ok((n, attributes))
@@ -852,7 +888,7 @@ mod test {
     use crate::mock::mock_stream::MockStream;
     use crate::stream_info::StreamInfo;
 
-    use super::StatsInterceptor;
+    use super::{unix2ntp, RemoteReport, StatsInterceptor};
 
     #[tokio::test]
     async fn test_stats_interceptor_rtp() -> Result<()> {
@@ -975,6 +1011,7 @@ mod test {
             .await
             .expect("Failed to write RTCP packets");
 
+        let mut reports = icpr.subscribe_reports();
         send_stream
             .receive_rtcp(vec![
                 Box::new(ReceiverReport {
@@ -1109,7 +1146,8 @@ mod test {
                 }),
                 Box::new(SenderReport {
                     ssrc: 123456,
-                    ntp_time: 23456, // Used for ordering
+                    // 10 Nov 1995 11:33:30 UTC, used for ordering
+                    ntp_time: unix2ntp(SystemTime::UNIX_EPOCH + Duration::from_secs(816003210)),
                     packet_count: 82,
                     octet_count: 10351,
                     reports: vec![],
@@ -1148,6 +1186,13 @@ mod test {
         );
 
         let _ = recv_stream.read_rtcp().await.expect("read_rtcp failed");
+        let mut received = vec![];
+        while let Ok(report) = reports.try_recv() {
+            received.push(report);
+        }
+        assert!(received.contains(&RemoteReport::ReceiverReport(234567)));
+        assert!(received.contains(&RemoteReport::SenderReport(123456)));
+        assert!(received.contains(&RemoteReport::SenderReport(9999999)));
 
         let snapshots = icpr.fetch_outbound_stats(vec![234567]).await;
         let send_snapshot = snapshots[0]
@@ -1187,6 +1232,10 @@ mod test {
             .expect("After reciving SR and DLRR we should have a round trip time ");
         assert_feq!(rtt_ms, 6125.0);
         assert_eq!(recv_snapshot.remote_reports_sent(), 2);
+        assert_eq!(
+            recv_snapshot.remote_timestamp(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(816003210))
+        );
         assert_eq!(recv_snapshot.remote_round_trip_time_measurements(), 1);
         assert_feq!(recv_snapshot.remote_total_round_trip_time(), 6125.0);
 
@@ -8,6 +8,15 @@ mod interceptor;
 
 pub use self::interceptor::StatsInterceptor;
 
+/// A report received from the remote, once the stats of its RTP stream are updated with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteReport {
+    /// A Receiver Report block about an outbound RTP stream, with the SSRC of the stream.
+    ReceiverReport(u32),
+    /// A Sender Report about an inbound RTP stream, with the SSRC of the stream.
+    SenderReport(u32),
+}
+
 pub fn make_stats_interceptor(id: &str) -> Arc<StatsInterceptor> {
     Arc::new(StatsInterceptor::new(id.to_owned()))
 }
@@ -42,6 +51,9 @@ mod inbound {
         /// The total number of sender reports sent by the remote and received.
         remote_reports_sent: u64,
 
+        /// The NTP time of the latest SR from the remote. [`None`] before the first SR.
+        remote_timestamp: Option<SystemTime>,
+
         /// The last remote round trip time measurement in ms. [`None`] if no round trip time has
         /// been derived yet, or if it wasn't possible to derive it.
         remote_round_trip_time: Option<f64>,
@@ -62,6 +74,7 @@ mod inbound {
                 remote_packets_sent: 0,
                 remote_bytes_sent: 0,
                 remote_reports_sent: 0,
+                remote_timestamp: None,
                 remote_round_trip_time: None,
                 remote_total_round_trip_time: 0.0,
                 remote_round_trip_time_measurements: 0,
@@ -82,10 +95,16 @@ mod inbound {
             self.last_update.elapsed()
         }
 
-        pub(super) fn record_sender_report(&mut self, packets_sent: u32, bytes_sent: u32) {
+        pub(super) fn record_sender_report(
+            &mut self,
+            packets_sent: u32,
+            bytes_sent: u32,
+            timestamp: Option<SystemTime>,
+        ) {
             self.remote_reports_sent += 1;
             self.remote_packets_sent = packets_sent;
             self.remote_bytes_sent = bytes_sent;
+            self.remote_timestamp = timestamp;
         }
 
         pub(super) fn record_remote_round_trip_time(&mut self, round_trip_time: Option<f64>) {
@@ -119,6 +138,9 @@ mod inbound {
         /// The total number of sender reports sent by the remote and received.
         remote_reports_sent: u64,
 
+        /// The NTP time of the latest SR from the remote. [`None`] before the first SR.
+        remote_timestamp: Option<SystemTime>,
+
         /// The last remote round trip time measurement in ms. [`None`] if no round trip time has
         /// been derived yet, or if it wasn't possible to derive it.
         remote_round_trip_time: Option<f64>,
@@ -170,6 +192,11 @@ mod inbound {
             self.remote_reports_sent
         }
 
+        /// The NTP time of the latest SR from the remote.
+        pub fn remote_timestamp(&self) -> Option<SystemTime> {
+            self.remote_timestamp
+        }
+
         pub fn remote_round_trip_time(&self) -> Option<f64> {
             self.remote_round_trip_time
         }
@@ -191,6 +218,7 @@ mod inbound {
                 remote_packets_sent: stream_stats.remote_packets_sent,
                 remote_bytes_sent: stream_stats.remote_bytes_sent,
                 remote_reports_sent: stream_stats.remote_reports_sent,
+                remote_timestamp: stream_stats.remote_timestamp,
                 remote_round_trip_time: stream_stats.remote_round_trip_time,
                 remote_total_round_trip_time: stream_stats.remote_total_round_trip_time,
                 remote_round_trip_time_measurements: stream_stats
@@ -15,6 +15,9 @@ pub struct StreamInfo {
     pub ssrc: u32,
     pub payload_type: u8,
     pub rtp_header_extensions: Vec<RTPHeaderExtension>,
+    /// rtp_header_extensions_allow_mixed is set when the one- and two-byte header
+    /// extensions may be mixed, i.e. "a=extmap-allow-mixed" was negotiated.
+    pub rtp_header_extensions_allow_mixed: bool,
     pub mime_type: String,
     pub clock_rate: u32,
     pub channels: u16,
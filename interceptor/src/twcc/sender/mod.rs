@@ -95,6 +95,7 @@ impl Interceptor for Sender {
             writer,
             Arc::clone(&self.next_sequence_nr),
             hdr_ext_id,
+            info.rtp_header_extensions_allow_mixed,
         ));
 
         {
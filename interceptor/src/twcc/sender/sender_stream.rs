@@ -4,6 +4,7 @@ pub(super) struct SenderStream {
     next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
     next_sequence_nr: Arc<AtomicU32>,
     hdr_ext_id: u8,
+    allow_mixed: bool,
 }
 
 impl SenderStream {
@@ -11,11 +12,13 @@ impl SenderStream {
         next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
         next_sequence_nr: Arc<AtomicU32>,
         hdr_ext_id: u8,
+        allow_mixed: bool,
     ) -> Self {
         SenderStream {
             next_rtp_writer,
             next_sequence_nr,
             hdr_ext_id,
+            allow_mixed,
         }
     }
 }
@@ -33,7 +36,12 @@ impl RTPWriter for SenderStream {
         let tcc_payload = tcc_ext.marshal()?;
 
         let mut pkt = pkt.clone();
-        pkt.header.set_extension(self.hdr_ext_id, tcc_payload)?;
+        if self.allow_mixed {
+            pkt.header
+                .set_extension_allow_mixed(self.hdr_ext_id, tcc_payload)?;
+        } else {
+            pkt.header.set_extension(self.hdr_ext_id, tcc_payload)?;
+        }
 
         self.next_rtp_writer.write(&pkt, a).await
     }
@@ -81,3 +81,44 @@ async fn test_twcc_sender_interceptor() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_twcc_sender_interceptor_allow_mixed() -> Result<()> {
+    // an id above 14 needs the two-byte header extensions
+    let icpr = Sender::builder().with_init_sequence_nr(0).build("")?;
+    let stream = MockStream::new(
+        &StreamInfo {
+            rtp_header_extensions: vec![RTPHeaderExtension {
+                uri: TRANSPORT_CC_URI.to_owned(),
+                id: 15,
+            }],
+            rtp_header_extensions_allow_mixed: true,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    stream
+        .write_rtp(&rtp::packet::Packet {
+            header: rtp::header::Header {
+                sequence_number: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await?;
+
+    let p = stream.written_rtp().await.unwrap();
+    assert_eq!(
+        p.header.extension_profile,
+        rtp::header::EXTENSION_PROFILE_TWO_BYTE
+    );
+    let mut extension_header = p.header.get_extension(15).unwrap();
+    let twcc = TransportCcExtension::unmarshal(&mut extension_header)?;
+    assert_eq!(twcc.transport_sequence, 0);
+
+    stream.close().await?;
+
+    Ok(())
+}
@@ -31,6 +31,7 @@ pub struct DnsConn {
 
     query_interval: Duration,
     queries: Arc<Mutex<Vec<Query>>>,
+    local_names: Arc<Mutex<Vec<String>>>,
 
     is_server_closed: Arc<atomic::AtomicBool>,
     close_server: mpsc::Sender<()>,
@@ -94,11 +95,13 @@ impl DnsConn {
 
         let socket = UdpSocket::from_std(socket.into())?;
 
-        let local_names = config
-            .local_names
-            .iter()
-            .map(|l| l.to_string() + ".")
-            .collect();
+        let local_names = Arc::new(Mutex::new(
+            config
+                .local_names
+                .iter()
+                .map(|l| l.to_string() + ".")
+                .collect(),
+        ));
 
         let dst_addr: SocketAddr = DEFAULT_DEST_ADDR.parse()?;
 
@@ -114,6 +117,7 @@ impl DnsConn {
             },
 
             queries: Arc::new(Mutex::new(vec![])),
+            local_names: Arc::clone(&local_names),
             socket: Arc::new(socket),
             dst_addr,
             is_server_closed: Arc::clone(&is_server_closed),
@@ -158,6 +162,24 @@ impl DnsConn {
         }
     }
 
+    /// Starts answering queries for `name`, in addition to the names the connection was
+    /// created with. This allows a single connection to be shared by several users, each
+    /// advertising its own name.
+    pub async fn add_local_name(&self, name: &str) {
+        let name_with_suffix = name.to_owned() + ".";
+        let mut local_names = self.local_names.lock().await;
+        if !local_names.contains(&name_with_suffix) {
+            local_names.push(name_with_suffix);
+        }
+    }
+
+    /// Stops answering queries for `name`.
+    pub async fn remove_local_name(&self, name: &str) {
+        let name_with_suffix = name.to_owned() + ".";
+        let mut local_names = self.local_names.lock().await;
+        local_names.retain(|n| *n != name_with_suffix);
+    }
+
     /// Query sends mDNS Queries for the following name until
     /// either there's a close signal or we get a result
     pub async fn query(
@@ -244,7 +266,7 @@ impl DnsConn {
         mut closed_rx: mpsc::Receiver<()>,
         close_server: Arc<atomic::AtomicBool>,
         socket: Arc<UdpSocket>,
-        local_names: Vec<String>,
+        local_names: Arc<Mutex<Vec<String>>>,
         dst_addr: SocketAddr,
         queries: Arc<Mutex<Vec<Query>>>,
     ) -> Result<()> {
@@ -284,6 +306,7 @@ impl DnsConn {
                 continue;
             }
 
+            let local_names = local_names.lock().await.clone();
             run(&mut p, &socket, &local_names, src, dst_addr, &queries).await
         }
     }
@@ -12,6 +12,8 @@ use std::sync::Arc;
 
 /// Flexible mode 15 bit picture ID
 const VP9HEADER_SIZE: usize = 3;
+/// Layer indices byte written when spatial or temporal scalability is in use
+const VP9LAYER_INDICES_SIZE: usize = 1;
 const MAX_SPATIAL_LAYERS: u8 = 5;
 const MAX_VP9REF_PICS: usize = 3;
 
@@ -23,8 +25,18 @@ pub type InitialPictureIDFn = Arc<dyn (Fn() -> u16) + Send + Sync>;
 pub struct Vp9Payloader {
     picture_id: u16,
     initialized: bool,
+    // Index of the next layer frame within the current picture and of the picture within
+    // the temporal pattern, only used when scalability is enabled.
+    spatial_index: u8,
+    temporal_index: usize,
 
     pub initial_picture_id_fn: Option<InitialPictureIDFn>,
+
+    /// Number of spatial layers produced by the encoder. Each call to `payload` carries
+    /// one layer frame, lowest spatial layer first. Zero or one disables spatial layering.
+    pub spatial_layers: u8,
+    /// Number of temporal layers produced by the encoder. Zero or one disables temporal layering.
+    pub temporal_layers: u8,
 }
 
 impl fmt::Debug for Vp9Payloader {
@@ -32,10 +44,39 @@ impl fmt::Debug for Vp9Payloader {
         f.debug_struct("Vp9Payloader")
             .field("picture_id", &self.picture_id)
             .field("initialized", &self.initialized)
+            .field("spatial_layers", &self.spatial_layers)
+            .field("temporal_layers", &self.temporal_layers)
             .finish()
     }
 }
 
+impl Vp9Payloader {
+    fn layered(&self) -> bool {
+        self.spatial_layers > 1 || self.temporal_layers > 1
+    }
+
+    /// layer_indices returns the temporal and spatial layer IDs of the next layer frame,
+    /// following the usual dyadic temporal pattern (0), (0, 1) or (0, 2, 1, 2).
+    fn layer_indices(&self) -> (u8, u8) {
+        let tid = match self.temporal_layers {
+            0 | 1 => 0,
+            2 => [0, 1][self.temporal_index % 2],
+            _ => [0, 2, 1, 2][self.temporal_index % 4],
+        };
+        (tid, self.spatial_index)
+    }
+
+    fn advance_layer(&mut self) -> bool {
+        self.spatial_index += 1;
+        if self.spatial_index < self.spatial_layers.max(1) {
+            return false;
+        }
+        self.spatial_index = 0;
+        self.temporal_index = self.temporal_index.wrapping_add(1);
+        true
+    }
+}
+
 impl Payloader for Vp9Payloader {
     /// Payload fragments an Vp9Payloader packet across one or more byte arrays
     fn payload(&mut self, mtu: usize, payload: &Bytes) -> Result<Vec<Bytes>> {
@@ -94,7 +135,14 @@ impl Payloader for Vp9Payloader {
             self.initialized = true;
         }
 
-        let max_fragment_size = mtu as isize - VP9HEADER_SIZE as isize;
+        let header_size = if self.layered() {
+            VP9HEADER_SIZE + VP9LAYER_INDICES_SIZE
+        } else {
+            VP9HEADER_SIZE
+        };
+        let (tid, sid) = self.layer_indices();
+
+        let max_fragment_size = mtu as isize - header_size as isize;
         let mut payloads = vec![];
         let mut payload_data_remaining = payload.len();
         let mut payload_data_index = 0;
@@ -106,8 +154,8 @@ impl Payloader for Vp9Payloader {
         while payload_data_remaining > 0 {
             let current_fragment_size =
                 std::cmp::min(max_fragment_size as usize, payload_data_remaining);
-            let mut out = BytesMut::with_capacity(VP9HEADER_SIZE + current_fragment_size);
-            let mut buf = vec![0u8; VP9HEADER_SIZE];
+            let mut out = BytesMut::with_capacity(header_size + current_fragment_size);
+            let mut buf = vec![0u8; header_size];
             buf[0] = 0x90; // F=1 I=1
             if payload_data_index == 0 {
                 buf[0] |= 0x08; // B=1
@@ -117,6 +165,13 @@ impl Payloader for Vp9Payloader {
             }
             buf[1] = (self.picture_id >> 8) as u8 | 0x80;
             buf[2] = (self.picture_id & 0xFF) as u8;
+            if self.layered() {
+                buf[0] |= 0x20; // L=1
+                buf[3] = (tid << 5) | ((sid & 0x07) << 1);
+                if sid > 0 {
+                    buf[3] |= 0x01; // D=1
+                }
+            }
 
             out.put(&buf[..]);
 
@@ -130,8 +185,11 @@ impl Payloader for Vp9Payloader {
             payload_data_index += current_fragment_size;
         }
 
-        self.picture_id += 1;
-        self.picture_id &= 0x7FFF;
+        // All layer frames of a picture share its picture ID
+        if self.advance_layer() {
+            self.picture_id += 1;
+            self.picture_id &= 0x7FFF;
+        }
 
         Ok(payloads)
     }
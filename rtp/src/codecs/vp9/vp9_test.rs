@@ -342,6 +342,44 @@ fn test_vp9_payloader_payload() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_vp9_payloader_payload_layered() -> Result<()> {
+    let mut pck = Vp9Payloader {
+        initial_picture_id_fn: Some(Arc::new(|| -> u16 { 8692 })),
+        spatial_layers: 2,
+        temporal_layers: 3,
+        ..Default::default()
+    };
+
+    // (picture_id, tid, sid, d) for each layer frame of four pictures in L2T3
+    let expected = vec![
+        (8692, 0, 0, false),
+        (8692, 0, 1, true),
+        (8693, 2, 0, false),
+        (8693, 2, 1, true),
+        (8694, 1, 0, false),
+        (8694, 1, 1, true),
+        (8695, 2, 0, false),
+        (8695, 2, 1, true),
+    ];
+
+    for (picture_id, tid, sid, d) in expected {
+        let res = pck.payload(10, &Bytes::from_static(&[0x01, 0x02]))?;
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].len(), 6, "layer indices must be present");
+
+        let mut p = Vp9Packet::default();
+        p.depacketize(&res[0])?;
+        assert!(p.l, "L bit must be set");
+        assert_eq!(p.picture_id, picture_id);
+        assert_eq!(p.tid, tid);
+        assert_eq!(p.sid, sid);
+        assert_eq!(p.d, d);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_vp9_partition_head_checker_is_partition_head() -> Result<()> {
     let vp9 = Vp9Packet::default();
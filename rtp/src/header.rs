@@ -387,6 +387,25 @@ impl Header {
         Ok(())
     }
 
+    /// set_extension_allow_mixed sets an RTP header extension like set_extension, but switches
+    /// the header to the two-byte profile when the extension doesn't fit the one-byte profile.
+    /// This must only be used when the one- and two-byte forms may be mixed, which is
+    /// negotiated by "a=extmap-allow-mixed" (RFC 8285 section 6).
+    pub fn set_extension_allow_mixed(&mut self, id: u8, payload: Bytes) -> Result<(), Error> {
+        let fits_one_byte = (1..=14).contains(&id) && (1..=16).contains(&payload.len());
+        if !fits_one_byte && id >= 1 && payload.len() <= 255 {
+            if !self.extension {
+                self.extension = true;
+                self.extension_profile = EXTENSION_PROFILE_TWO_BYTE;
+            } else if self.extension_profile == EXTENSION_PROFILE_ONE_BYTE {
+                // every one-byte extension is also a valid two-byte extension
+                self.extension_profile = EXTENSION_PROFILE_TWO_BYTE;
+            }
+        }
+
+        self.set_extension(id, payload)
+    }
+
     /// returns an extension id array
     pub fn get_extension_ids(&self) -> Vec<u8> {
         if self.extension {
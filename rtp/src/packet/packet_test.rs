@@ -1249,3 +1249,63 @@ fn test_round_trip() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_rfc8285_set_extension_allow_mixed() -> Result<()> {
+    let mut p = Packet {
+        header: Header {
+            version: 2,
+            payload_type: 96,
+            sequence_number: 27023,
+            timestamp: 3653407706,
+            ssrc: 476325762,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0x98u8, 0x36, 0xbe, 0x88, 0x9e]),
+    };
+
+    p.header
+        .set_extension_allow_mixed(1, Bytes::from_static(&[0xAA]))?;
+    assert_eq!(
+        p.header.extension_profile, EXTENSION_PROFILE_ONE_BYTE,
+        "an extension fitting the one-byte profile should keep it"
+    );
+
+    // an id above 14 doesn't fit the one-byte profile
+    p.header
+        .set_extension_allow_mixed(15, Bytes::from_static(&[0xBB, 0xBB]))?;
+    assert_eq!(p.header.extension_profile, EXTENSION_PROFILE_TWO_BYTE);
+
+    p.header
+        .set_extension_allow_mixed(2, Bytes::from_static(&[0xCC]))?;
+    assert_eq!(p.header.extension_profile, EXTENSION_PROFILE_TWO_BYTE);
+    assert_eq!(p.header.get_extension_ids(), vec![1, 15, 2]);
+
+    let raw = p.marshal()?;
+    let q = Packet::unmarshal(&mut raw.clone())?;
+    assert_eq!(q.header.extension_profile, EXTENSION_PROFILE_TWO_BYTE);
+    assert_eq!(q.header.get_extension(1), Some(Bytes::from_static(&[0xAA])));
+    assert_eq!(
+        q.header.get_extension(15),
+        Some(Bytes::from_static(&[0xBB, 0xBB]))
+    );
+
+    // a payload larger than 16 bytes doesn't fit the one-byte profile either,
+    // while set_extension would reject it
+    let mut h = Header {
+        extension: true,
+        extension_profile: EXTENSION_PROFILE_ONE_BYTE,
+        extensions: vec![Extension {
+            id: 1,
+            payload: Bytes::from_static(&[0xAA]),
+        }],
+        ..Default::default()
+    };
+    let large = Bytes::from_static(&[0xDD; 17]);
+    assert!(h.clone().set_extension(2, large.clone()).is_err());
+    h.set_extension_allow_mixed(2, large.clone())?;
+    assert_eq!(h.extension_profile, EXTENSION_PROFILE_TWO_BYTE);
+    assert_eq!(h.get_extension(2), Some(large));
+
+    Ok(())
+}
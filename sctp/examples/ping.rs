@@ -63,10 +63,8 @@ async fn main() -> Result<(), Error> {
     println!("connecting {}..", server);
 
     let config = Config {
-        net_conn: conn,
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(conn)
     };
     let a = Association::client(config).await?;
     println!("created a client");
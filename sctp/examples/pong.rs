@@ -62,10 +62,8 @@ async fn main() -> Result<(), Error> {
     println!("listening {}...", conn.local_addr().unwrap());
 
     let config = Config {
-        net_conn: Arc::new(conn),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "server".to_owned(),
+        ..Config::new(Arc::new(conn))
     };
     let a = Association::server(config).await?;
     println!("created a server");
@@ -8,6 +8,16 @@ use crate::param::param_unrecognized::ParamUnrecognized;
 use async_trait::async_trait;
 use std::sync::atomic::AtomicBool;
 
+/// PendingRestart holds the parameters offered in the INIT ACK answering an
+/// INIT received on an established association, until the peer echoes the
+/// cookie to restart the association.
+struct PendingRestart {
+    init: ChunkInit,
+    cookie: ParamStateCookie,
+    my_verification_tag: u32,
+    my_initial_tsn: u32,
+}
+
 #[derive(Default)]
 pub struct AssociationInternal {
     pub(crate) name: String,
@@ -34,6 +44,10 @@ pub struct AssociationInternal {
     my_next_rsn: u32,
     reconfigs: HashMap<u32, ChunkReconfig>,
     reconfig_requests: HashMap<u32, ParamOutgoingResetRequest>,
+    reconfig_results: HashMap<u32, ReconfigResult>,
+    // rsn of the peer's incoming SSN reset request to be answered by our next
+    // outgoing SSN reset request
+    incoming_reset_rsn: Option<u32>,
 
     // Non-RFC internal data
     source_port: u16,
@@ -41,24 +55,45 @@ pub struct AssociationInternal {
     pub(crate) my_max_num_inbound_streams: u16,
     pub(crate) my_max_num_outbound_streams: u16,
     my_cookie: Option<ParamStateCookie>,
+    pending_restart: Option<PendingRestart>,
     payload_queue: PayloadQueue,
     inflight_queue: PayloadQueue,
     pending_queue: Arc<PendingQueue>,
     control_queue: ControlQueue,
     pub(crate) mtu: u32,
-    max_payload_size: u32, // max DATA chunk payload size
+    max_payload_size: Arc<AtomicU32>, // max DATA chunk payload size
     cumulative_tsn_ack_point: u32,
     advanced_peer_tsn_ack_point: u32,
     use_forward_tsn: bool,
+    pub(crate) enable_message_interleaving: bool,
+    use_message_interleaving: bool,
+    // bytes of the fragments abandoned before being sent, to release from the
+    // buffered amount of their stream
+    bytes_abandoned_per_stream: HashMap<u16, i64>,
+
+    // Path MTU discovery (RFC 8899)
+    pmtud: Option<PathMtuDiscovery>,
+    pub(crate) on_pmtu_change: Arc<ArcSwapOption<Mutex<OnPmtuChangeFn>>>,
+
+    // Chunk tracing
+    pub(crate) on_chunk: Arc<ArcSwapOption<Mutex<OnChunkFn>>>,
+
+    // Peer restart (RFC 4960 Sec 5.2.4)
+    pub(crate) on_restart: Arc<ArcSwapOption<Mutex<OnRestartFn>>>,
+
+    // bytes written to the streams and not yet acknowledged
+    pub(crate) send_buffer: Arc<SendBuffer>,
 
     // Congestion control parameters
     pub(crate) max_receive_buffer_size: u32,
     pub(crate) cwnd: u32,     // my congestion window size
     rwnd: u32,                // calculated peer's receiver windows size
     pub(crate) ssthresh: u32, // slow start threshold
-    partial_bytes_acked: u32,
+    congestion_control: Box<dyn CongestionController + Send + Sync>,
     pub(crate) in_fast_recovery: bool,
     fast_recover_exit_point: u32,
+    fast_retransmit_threshold: u32,
+    max_burst: u32,
 
     // RTX & Ack timer
     pub(crate) rto_mgr: RtoManager,
@@ -67,7 +102,10 @@ pub struct AssociationInternal {
     pub(crate) t2shutdown: Option<RtxTimer<AssociationInternal>>,
     pub(crate) t3rtx: Option<RtxTimer<AssociationInternal>>,
     pub(crate) treconfig: Option<RtxTimer<AssociationInternal>>,
+    pub(crate) tpmtud: Option<RtxTimer<AssociationInternal>>,
     pub(crate) ack_timer: Option<AckTimer<AssociationInternal>>,
+    pub(crate) sack_delay: Duration,
+    pub(crate) max_init_retransmits: usize,
 
     // Chunks stored for retransmission
     pub(crate) stored_init: Option<ChunkInit>,
@@ -111,8 +149,34 @@ impl AssociationInternal {
             config.max_message_size
         };
 
+        let max_send_buffer_size = if config.max_send_buffer_size == 0 {
+            DEFAULT_MAX_SEND_BUFFER_SIZE
+        } else {
+            config.max_send_buffer_size
+        };
+
+        let rto_min = if config.rto_min == 0 {
+            RTO_MIN
+        } else {
+            config.rto_min
+        };
+        let rto_max = if config.rto_max == 0 {
+            std::cmp::max(RTO_MAX, rto_min)
+        } else {
+            std::cmp::max(config.rto_max, rto_min)
+        };
+        let rto_initial = if config.rto_initial == 0 {
+            RTO_INITIAL
+        } else {
+            config.rto_initial
+        };
+        let rto_initial = std::cmp::min(std::cmp::max(rto_initial, rto_min), rto_max);
+
         let inflight_queue_length = Arc::new(AtomicUsize::new(0));
 
+        let pending_queue = Arc::new(PendingQueue::new());
+        pending_queue.set_scheduler(config.stream_scheduler);
+
         let mut tsn = random::<u32>();
         if tsn == 0 {
             tsn += 1;
@@ -122,24 +186,51 @@ impl AssociationInternal {
             max_receive_buffer_size,
             max_message_size: Arc::new(AtomicU32::new(max_message_size)),
 
-            my_max_num_outbound_streams: u16::MAX,
-            my_max_num_inbound_streams: u16::MAX,
+            my_max_num_outbound_streams: if config.max_num_outbound_streams == 0 {
+                u16::MAX
+            } else {
+                config.max_num_outbound_streams
+            },
+            my_max_num_inbound_streams: if config.max_num_inbound_streams == 0 {
+                u16::MAX
+            } else {
+                config.max_num_inbound_streams
+            },
             payload_queue: PayloadQueue::new(Arc::new(AtomicUsize::new(0))),
             inflight_queue: PayloadQueue::new(Arc::clone(&inflight_queue_length)),
             inflight_queue_length,
-            pending_queue: Arc::new(PendingQueue::new()),
+            pending_queue,
             control_queue: ControlQueue::new(),
             mtu: INITIAL_MTU,
-            max_payload_size: INITIAL_MTU - (COMMON_HEADER_SIZE + DATA_CHUNK_HEADER_SIZE),
+            max_payload_size: Arc::new(AtomicU32::new(
+                INITIAL_MTU - (COMMON_HEADER_SIZE + DATA_CHUNK_HEADER_SIZE),
+            )),
             my_verification_tag: random::<u32>(),
             my_next_tsn: tsn,
             my_next_rsn: tsn,
             min_tsn2measure_rtt: tsn,
             state: Arc::new(AtomicU8::new(AssociationState::Closed as u8)),
-            rto_mgr: RtoManager::new(),
+            rto_mgr: RtoManager::new(rto_initial, rto_min, rto_max),
+            sack_delay: if config.sack_delay == 0 {
+                ACK_INTERVAL
+            } else {
+                Duration::from_millis(config.sack_delay)
+            },
+            fast_retransmit_threshold: if config.fast_retransmit_threshold == 0 {
+                FAST_RETRANSMIT_THRESHOLD
+            } else {
+                config.fast_retransmit_threshold
+            },
+            max_burst: config.max_burst,
+            max_init_retransmits: if config.max_init_retransmits == 0 {
+                MAX_INIT_RETRANS
+            } else {
+                config.max_init_retransmits as usize
+            },
             streams: HashMap::new(),
             reconfigs: HashMap::new(),
             reconfig_requests: HashMap::new(),
+            reconfig_results: HashMap::new(),
             accept_ch_tx: Some(accept_ch_tx),
             close_loop_ch_tx: Some(close_loop_ch_tx),
             handshake_completed_ch_tx: Some(handshake_completed_ch_tx),
@@ -148,6 +239,19 @@ impl AssociationInternal {
             silent_error: Some(Error::ErrSilentlyDiscard),
             stats: Arc::new(AssociationStats::default()),
             awake_write_loop_ch: Some(awake_write_loop_ch),
+            enable_message_interleaving: config.enable_message_interleaving,
+            congestion_control: config.congestion_control.controller(),
+            pmtud: if config.enable_pmtud {
+                let max_mtu = if config.max_mtu == 0 {
+                    DEFAULT_MAX_MTU
+                } else {
+                    config.max_mtu
+                };
+                Some(PathMtuDiscovery::new(INITIAL_MTU, max_mtu))
+            } else {
+                None
+            },
+            send_buffer: Arc::new(SendBuffer::new(max_send_buffer_size as usize)),
             ..Default::default()
         };
 
@@ -156,6 +260,7 @@ impl AssociationInternal {
         //     long idle period MUST be set to min(4*MTU, max (2*MTU, 4380
         //     bytes)).
         a.cwnd = std::cmp::min(4 * a.mtu, std::cmp::max(2 * a.mtu, 4380));
+        a.send_buffer.set_cwnd(a.cwnd);
         log::trace!(
             "[{}] updated cwnd={} ssthresh={} inflight={} (INI)",
             a.name,
@@ -175,10 +280,12 @@ impl AssociationInternal {
             self.source_port = 5000; // Spec??
             self.destination_port = 5000; // Spec??
 
+            // A packet containing an INIT chunk MUST have a zero Verification
+            // Tag, even once the peer's INIT was received on a collision.
             let outbound = Packet {
                 source_port: self.source_port,
                 destination_port: self.destination_port,
-                verification_tag: self.peer_verification_tag,
+                verification_tag: 0,
                 chunks: vec![Box::new(stored_init)],
             };
 
@@ -222,6 +329,12 @@ impl AssociationInternal {
             // awake read/write_loop to exit
             self.close_loop_ch_tx.take();
 
+            // fail a handshake still in progress
+            self.handshake_completed_ch_tx.take();
+
+            // awake accept_stream to return None
+            self.accept_ch_tx.take();
+
             for si in self.streams.keys().cloned().collect::<Vec<u16>>() {
                 self.unregister_stream(si);
             }
@@ -279,6 +392,9 @@ impl AssociationInternal {
         if let Some(treconfig) = &self.treconfig {
             treconfig.stop().await;
         }
+        if let Some(tpmtud) = &self.tpmtud {
+            tpmtud.stop().await;
+        }
         if let Some(ack_timer) = &mut self.ack_timer {
             ack_timer.stop();
         }
@@ -295,12 +411,18 @@ impl AssociationInternal {
     /// The caller should hold the association write lock.
     fn unregister_stream(&mut self, stream_identifier: u16) {
         let s = self.streams.remove(&stream_identifier);
+        self.pending_queue.remove_stream(stream_identifier);
         if let Some(s) = s {
             // NOTE: shutdown is not used here because it resets the stream.
             if !s.read_shutdown.swap(true, Ordering::SeqCst) {
                 s.read_notifier.notify_waiters();
             }
+            s.clear_received();
             s.write_shutdown.store(true, Ordering::SeqCst);
+            s.buffered_amount_low_notifier.notify_waiters();
+
+            // The data left on the stream won't be released by a SACK anymore
+            self.send_buffer.release(s.buffered_amount());
         }
     }
 
@@ -319,6 +441,8 @@ impl AssociationInternal {
             return Ok(());
         }
 
+        self.trace_chunks(&p.chunks, ChunkDirection::Inbound).await;
+
         self.handle_chunk_start();
 
         for c in &p.chunks {
@@ -390,21 +514,24 @@ impl AssociationInternal {
 
             if !sis_to_reset.is_empty() {
                 let rsn = self.generate_next_rsn();
-                let tsn = self.my_next_tsn - 1;
+                let tsn = self.my_next_tsn.wrapping_sub(1);
                 log::debug!(
                     "[{}] sending RECONFIG: rsn={} tsn={} streams={:?}",
                     self.name,
                     rsn,
-                    self.my_next_tsn - 1,
+                    tsn,
                     sis_to_reset
                 );
 
                 let c = ChunkReconfig {
                     param_a: Some(Box::new(ParamOutgoingResetRequest {
                         reconfig_request_sequence_number: rsn,
+                        reconfig_response_sequence_number: self
+                            .incoming_reset_rsn
+                            .take()
+                            .unwrap_or_default(),
                         sender_last_tsn: tsn,
                         stream_identifiers: sis_to_reset,
-                        ..Default::default()
                     })),
                     ..Default::default()
                 };
@@ -439,13 +566,19 @@ impl AssociationInternal {
             self.will_retransmit_fast = false;
 
             let mut to_fast_retrans: Vec<Box<dyn Chunk + Send + Sync>> = vec![];
+            let mut bytes_abandoned = vec![];
             let mut fast_retrans_size = COMMON_HEADER_SIZE;
+            let data_chunk_header_size = self.data_chunk_header_size();
 
             let mut i = 0;
             loop {
                 let tsn = self.cumulative_tsn_ack_point + i + 1;
                 if let Some(c) = self.inflight_queue.get_mut(tsn) {
-                    if c.acked || c.abandoned() || c.nsent > 1 || c.miss_indicator < 3 {
+                    if c.acked
+                        || c.abandoned()
+                        || c.nsent > 1
+                        || c.miss_indicator < self.fast_retransmit_threshold
+                    {
                         i += 1;
                         continue;
                     }
@@ -460,13 +593,14 @@ impl AssociationInternal {
                     //      of cwnd and SHOULD NOT delay retransmission for this single
                     //		packet.
 
-                    let data_chunk_size = DATA_CHUNK_HEADER_SIZE + c.user_data.len() as u32;
+                    let data_chunk_size = data_chunk_header_size + c.user_data.len() as u32;
                     if self.mtu < fast_retrans_size + data_chunk_size {
                         break;
                     }
 
                     fast_retrans_size += data_chunk_size;
                     self.stats.inc_fast_retrans();
+                    self.stats.inc_retrans_chunks();
                     c.nsent += 1;
                 } else {
                     break; // end of pending data
@@ -474,6 +608,10 @@ impl AssociationInternal {
 
                 if let Some(c) = self.inflight_queue.get(tsn) {
                     self.check_partial_reliability_status(c);
+                    let n_bytes = self.abandon_unsent_fragments(c);
+                    if n_bytes > 0 {
+                        bytes_abandoned.push((c.stream_identifier, n_bytes));
+                    }
                     to_fast_retrans.push(Box::new(c.clone()));
                     log::trace!(
                         "[{}] fast-retransmit: tsn={} sent={} htna={}",
@@ -485,6 +623,7 @@ impl AssociationInternal {
                 }
                 i += 1;
             }
+            self.add_bytes_abandoned(bytes_abandoned);
 
             if !to_fast_retrans.is_empty() {
                 if let Ok(raw) = self.create_packet(to_fast_retrans).marshal() {
@@ -528,8 +667,12 @@ impl AssociationInternal {
                 self.advanced_peer_tsn_ack_point,
                 self.cumulative_tsn_ack_point,
             ) {
-                let fwd_tsn = self.create_forward_tsn();
-                if let Ok(raw) = self.create_packet(vec![Box::new(fwd_tsn)]).marshal() {
+                let fwd_tsn: Box<dyn Chunk + Send + Sync> = if self.use_message_interleaving {
+                    Box::new(self.create_iforward_tsn())
+                } else {
+                    Box::new(self.create_forward_tsn())
+                };
+                if let Ok(raw) = self.create_packet(vec![fwd_tsn]).marshal() {
                     raw_packets.push(raw);
                 } else {
                     log::warn!("[{}] failed to serialize a Forward TSN packet", self.name);
@@ -540,6 +683,68 @@ impl AssociationInternal {
         raw_packets
     }
 
+    async fn gather_outbound_pmtu_probe_packets(
+        &mut self,
+        mut raw_packets: Vec<Bytes>,
+    ) -> Vec<Bytes> {
+        let probe = if let Some(pmtud) = &mut self.pmtud {
+            let probe_in_flight = pmtud.probe_in_flight();
+            let probe = pmtud.probe_to_send(Instant::now());
+            if !probe_in_flight {
+                // every probe gets a timer of its own
+                if let Some(tpmtud) = &self.tpmtud {
+                    tpmtud.stop().await;
+                }
+            }
+            probe
+        } else {
+            None
+        };
+
+        if let Some(probe) = probe {
+            log::debug!(
+                "[{}] sending PMTU probe: size={} seq={}",
+                self.name,
+                probe.size,
+                probe.sequence
+            );
+            if let Ok(raw) = self.create_pmtu_probe_packet(probe).marshal() {
+                raw_packets.push(raw);
+            } else {
+                log::warn!("[{}] failed to serialize a PMTU probe packet", self.name);
+            }
+            if let Some(tpmtud) = &self.tpmtud {
+                tpmtud.start(self.rto_mgr.get_rto()).await;
+            }
+        }
+
+        raw_packets
+    }
+
+    /// create_pmtu_probe_packet creates a HEARTBEAT chunk identifying the probe,
+    /// padded up to the size of the probe with a PAD chunk (RFC 8899 sec 6.2.1.1).
+    fn create_pmtu_probe_packet(&self, probe: Probe) -> Packet {
+        let mut heartbeat_information = BytesMut::with_capacity(PROBE_INFO_LENGTH);
+        heartbeat_information.put_u32(probe.size);
+        heartbeat_information.put_u32(probe.sequence);
+        let heartbeat = ChunkHeartbeat {
+            params: vec![Box::new(ParamHeartbeatInfo {
+                heartbeat_information: heartbeat_information.freeze(),
+            })],
+        };
+
+        let probe_size = probe.size as usize;
+        let used = COMMON_HEADER_SIZE as usize
+            + CHUNK_HEADER_SIZE
+            + heartbeat.value_length()
+            + CHUNK_HEADER_SIZE;
+        let padding = ChunkPadding {
+            padding_length: probe_size.saturating_sub(used),
+        };
+
+        self.create_packet(vec![Box::new(heartbeat), Box::new(padding)])
+    }
+
     async fn gather_outbound_shutdown_packets(
         &mut self,
         mut raw_packets: Vec<Bytes>,
@@ -599,6 +804,20 @@ impl AssociationInternal {
     /// gather_outbound gathers outgoing packets. The returned bool value set to
     /// false means the association should be closed down after the final send.
     pub(crate) async fn gather_outbound(&mut self) -> (Vec<Bytes>, bool) {
+        let (raw_packets, ok) = self.gather_outbound_packets().await;
+
+        if self.on_chunk.load().is_some() {
+            for raw in &raw_packets {
+                if let Ok(p) = Packet::unmarshal(raw) {
+                    self.trace_chunks(&p.chunks, ChunkDirection::Outbound).await;
+                }
+            }
+        }
+
+        (raw_packets, ok)
+    }
+
+    async fn gather_outbound_packets(&mut self) -> (Vec<Bytes>, bool) {
         let mut raw_packets = vec![];
 
         if !self.control_queue.is_empty() {
@@ -622,6 +841,7 @@ impl AssociationInternal {
                 raw_packets = self.gather_outbound_fast_retransmission_packets(raw_packets);
                 raw_packets = self.gather_outbound_sack_packets(raw_packets).await;
                 raw_packets = self.gather_outbound_forward_tsn_packets(raw_packets);
+                raw_packets = self.gather_outbound_pmtu_probe_packets(raw_packets).await;
                 (raw_packets, true)
             }
             AssociationState::ShutdownPending
@@ -639,6 +859,38 @@ impl AssociationInternal {
         }
     }
 
+    /// trace_chunks calls the on_chunk handler with each of the chunks.
+    async fn trace_chunks(
+        &self,
+        chunks: &[Box<dyn Chunk + Send + Sync>],
+        direction: ChunkDirection,
+    ) {
+        if let Some(handler) = &*self.on_chunk.load() {
+            let timestamp = SystemTime::now();
+            let mut f = handler.lock().await;
+            for c in chunks {
+                f(ChunkTrace::new(direction, c.as_ref(), timestamp)).await;
+            }
+        }
+    }
+
+    /// negotiate_num_streams limits the streams to those the peer accepts in
+    /// its INIT or INIT ACK chunk. (RFC 4960 sec 5.1.1)
+    fn negotiate_num_streams(&mut self, i: &ChunkInit) {
+        // We can send on no more streams than the peer accepts (MIS), and
+        // receive on no more than it may send on (OS).
+        self.my_max_num_outbound_streams =
+            std::cmp::min(i.num_inbound_streams, self.my_max_num_outbound_streams);
+        self.my_max_num_inbound_streams =
+            std::cmp::min(i.num_outbound_streams, self.my_max_num_inbound_streams);
+        log::debug!(
+            "[{}] negotiated streams: outbound={} inbound={}",
+            self.name,
+            self.my_max_num_outbound_streams,
+            self.my_max_num_inbound_streams
+        );
+    }
+
     /// set_state atomically sets the state of the Association.
     pub(crate) fn set_state(&self, new_state: AssociationState) {
         let old_state = AssociationState::from(self.state.swap(new_state as u8, Ordering::SeqCst));
@@ -649,6 +901,9 @@ impl AssociationInternal {
                 old_state,
                 new_state,
             );
+
+            // Let the writers waiting for room find out about the new state
+            self.send_buffer.wake();
         }
     }
 
@@ -668,6 +923,10 @@ impl AssociationInternal {
         // responding, the endpoint MUST send the INIT ACK back to the same
         // address that the original INIT (sent by this endpoint) was sent.
 
+        if state == AssociationState::Established {
+            return Ok(vec![self.create_restart_init_ack(p, i)]);
+        }
+
         if state != AssociationState::Closed
             && state != AssociationState::CookieWait
             && state != AssociationState::CookieEchoed
@@ -678,10 +937,7 @@ impl AssociationInternal {
         }
 
         // Should we be setting any of these permanently until we've ACKed further?
-        self.my_max_num_inbound_streams =
-            std::cmp::min(i.num_inbound_streams, self.my_max_num_inbound_streams);
-        self.my_max_num_outbound_streams =
-            std::cmp::min(i.num_outbound_streams, self.my_max_num_outbound_streams);
+        self.negotiate_num_streams(i);
         self.peer_verification_tag = i.initiate_tag;
         self.source_port = p.destination_port;
         self.destination_port = p.source_port;
@@ -696,19 +952,7 @@ impl AssociationInternal {
             i.initial_tsn - 1
         };
 
-        for param in &i.params {
-            if let Some(v) = param.as_any().downcast_ref::<ParamSupportedExtensions>() {
-                for t in &v.chunk_types {
-                    if *t == CT_FORWARD_TSN {
-                        log::debug!("[{}] use ForwardTSN (on init)", self.name);
-                        self.use_forward_tsn = true;
-                    }
-                }
-            }
-        }
-        if !self.use_forward_tsn {
-            log::warn!("[{}] not using ForwardTSN (on init)", self.name);
-        }
+        self.negotiate_extensions(i, "init");
 
         let mut outbound = Packet {
             verification_tag: self.peer_verification_tag,
@@ -756,7 +1000,7 @@ impl AssociationInternal {
             init_ack.params = vec![Box::new(my_cookie.clone())];
         }
 
-        init_ack.set_supported_extensions();
+        init_ack.set_supported_extensions(self.enable_message_interleaving);
 
         outbound.chunks = vec![Box::new(init_ack)];
 
@@ -776,10 +1020,7 @@ impl AssociationInternal {
             return Ok(vec![]);
         }
 
-        self.my_max_num_inbound_streams =
-            std::cmp::min(i.num_inbound_streams, self.my_max_num_inbound_streams);
-        self.my_max_num_outbound_streams =
-            std::cmp::min(i.num_outbound_streams, self.my_max_num_outbound_streams);
+        self.negotiate_num_streams(i);
         self.peer_verification_tag = i.initiate_tag;
         self.peer_last_tsn = if i.initial_tsn == 0 {
             u32::MAX
@@ -812,22 +1053,11 @@ impl AssociationInternal {
         }
         self.stored_init = None;
 
-        let mut cookie_param = None;
-        for param in &i.params {
-            if let Some(v) = param.as_any().downcast_ref::<ParamStateCookie>() {
-                cookie_param = Some(v);
-            } else if let Some(v) = param.as_any().downcast_ref::<ParamSupportedExtensions>() {
-                for t in &v.chunk_types {
-                    if *t == CT_FORWARD_TSN {
-                        log::debug!("[{}] use ForwardTSN (on initAck)", self.name);
-                        self.use_forward_tsn = true;
-                    }
-                }
-            }
-        }
-        if !self.use_forward_tsn {
-            log::warn!("[{}] not using ForwardTSN (on initAck)", self.name);
-        }
+        self.negotiate_extensions(i, "initAck");
+        let cookie_param = i
+            .params
+            .iter()
+            .find_map(|param| param.as_any().downcast_ref::<ParamStateCookie>());
 
         if let Some(v) = cookie_param {
             self.stored_cookie_echo = Some(ChunkCookieEcho {
@@ -848,6 +1078,199 @@ impl AssociationInternal {
         }
     }
 
+    /// negotiate_extensions enables the extensions listed in the supported
+    /// extensions of the peer's INIT or INIT ACK, and supported by us.
+    fn negotiate_extensions(&mut self, i: &ChunkInit, on: &str) {
+        for param in &i.params {
+            if let Some(v) = param.as_any().downcast_ref::<ParamSupportedExtensions>() {
+                for t in &v.chunk_types {
+                    if *t == CT_FORWARD_TSN {
+                        log::debug!("[{}] use ForwardTSN (on {})", self.name, on);
+                        self.use_forward_tsn = true;
+                    } else if *t == CT_IDATA && self.enable_message_interleaving {
+                        log::debug!("[{}] use I-DATA (on {})", self.name, on);
+                        self.set_message_interleaving();
+                    }
+                }
+            }
+        }
+        if !self.use_forward_tsn {
+            log::warn!("[{}] not using ForwardTSN (on {})", self.name, on);
+        }
+    }
+
+    /// create_restart_init_ack answers an INIT received on an established
+    /// association, which tells that the peer restarted. The association is
+    /// left untouched until the peer echoes the cookie.
+    ///
+    /// https://tools.ietf.org/html/rfc4960#section-5.2.2
+    /// the INIT ACK MUST contain a new Initiate Tag (randomly generated;
+    /// see Section 5.3.1).  Other parameters for the endpoint SHOULD be
+    /// copied from the existing parameters of the association (e.g.,
+    /// number of outbound streams) into the INIT ACK and cookie.
+    fn create_restart_init_ack(&mut self, p: &Packet, i: &ChunkInit) -> Packet {
+        log::debug!("[{}] peer restarting the association", self.name);
+
+        let mut my_initial_tsn = random::<u32>();
+        if my_initial_tsn == 0 {
+            my_initial_tsn += 1;
+        }
+        let restart = PendingRestart {
+            init: i.clone(),
+            cookie: ParamStateCookie::new(),
+            my_verification_tag: random::<u32>(),
+            my_initial_tsn,
+        };
+
+        let mut init_ack = ChunkInit {
+            is_ack: true,
+            initial_tsn: restart.my_initial_tsn,
+            num_outbound_streams: self.my_max_num_outbound_streams,
+            num_inbound_streams: self.my_max_num_inbound_streams,
+            initiate_tag: restart.my_verification_tag,
+            advertised_receiver_window_credit: self.max_receive_buffer_size,
+            params: vec![Box::new(restart.cookie.clone())],
+        };
+        init_ack.set_supported_extensions(self.enable_message_interleaving);
+        self.pending_restart = Some(restart);
+
+        Packet {
+            verification_tag: i.initiate_tag,
+            source_port: p.destination_port,
+            destination_port: p.source_port,
+            chunks: vec![Box::new(init_ack)],
+        }
+    }
+
+    /// restart re-initializes the association with the parameters exchanged
+    /// with the restarted peer. The streams are closed as the data in flight
+    /// and the stream sequence numbers are lost with the peer's state.
+    ///
+    /// https://tools.ietf.org/html/rfc4960#section-5.2.4
+    /// A) In this case, the peer may have restarted.  [...] the endpoint
+    /// [...] MUST then reset its TCB to the new parameters, and enter
+    /// the ESTABLISHED state.
+    async fn restart(&mut self, restart: PendingRestart) -> Result<Vec<Packet>> {
+        log::debug!("[{}] association restarted by the peer", self.name);
+
+        if let Some(t3rtx) = &self.t3rtx {
+            t3rtx.stop().await;
+        }
+        if let Some(treconfig) = &self.treconfig {
+            treconfig.stop().await;
+        }
+        if let Some(ack_timer) = &mut self.ack_timer {
+            ack_timer.stop();
+        }
+
+        for si in self.streams.keys().cloned().collect::<Vec<u16>>() {
+            self.unregister_stream(si);
+        }
+
+        // Local state
+        let tsn = restart.my_initial_tsn;
+        self.my_verification_tag = restart.my_verification_tag;
+        self.my_next_tsn = tsn;
+        self.my_next_rsn = tsn;
+        self.min_tsn2measure_rtt = tsn;
+        self.cumulative_tsn_ack_point = tsn - 1;
+        self.advanced_peer_tsn_ack_point = tsn - 1;
+        self.payload_queue = PayloadQueue::new(Arc::new(AtomicUsize::new(0)));
+        self.inflight_queue = PayloadQueue::new(Arc::clone(&self.inflight_queue_length));
+        self.control_queue = ControlQueue::new();
+        self.reconfigs.clear();
+        self.reconfig_requests.clear();
+        self.reconfig_results.clear();
+        self.incoming_reset_rsn = None;
+        self.bytes_abandoned_per_stream.clear();
+        self.will_send_forward_tsn = false;
+        self.will_retransmit_fast = false;
+        self.will_retransmit_reconfig = false;
+        self.ack_state = AckState::Idle;
+        self.rto_mgr.reset();
+
+        // Peer state
+        let i = &restart.init;
+        self.negotiate_num_streams(i);
+        self.peer_verification_tag = i.initiate_tag;
+        self.peer_last_tsn = if i.initial_tsn == 0 {
+            u32::MAX
+        } else {
+            i.initial_tsn - 1
+        };
+        self.use_forward_tsn = false;
+        self.use_message_interleaving = false;
+        self.pending_queue.set_interleaving(false);
+        self.set_mtu(self.mtu);
+        self.negotiate_extensions(i, "restart");
+
+        // Congestion control restarts from the initial window
+        self.rwnd = i.advertised_receiver_window_credit;
+        self.ssthresh = self.rwnd;
+        self.cwnd = std::cmp::min(4 * self.mtu, std::cmp::max(2 * self.mtu, 4380));
+        self.send_buffer.set_cwnd(self.cwnd);
+        self.in_fast_recovery = false;
+        log::trace!(
+            "[{}] updated cwnd={} ssthresh={} inflight={} (RESTART)",
+            self.name,
+            self.cwnd,
+            self.ssthresh,
+            self.inflight_queue.get_num_bytes()
+        );
+
+        self.my_cookie = Some(restart.cookie);
+
+        if let Some(handler) = &*self.on_restart.load() {
+            let mut f = handler.lock().await;
+            f().await;
+        }
+
+        Ok(vec![Packet {
+            verification_tag: self.peer_verification_tag,
+            source_port: self.source_port,
+            destination_port: self.destination_port,
+            chunks: vec![Box::new(ChunkCookieAck {})],
+        }])
+    }
+
+    /// set_message_interleaving switches to I-DATA chunks once both endpoints
+    /// listed them in their supported extensions.
+    fn set_message_interleaving(&mut self) {
+        self.use_message_interleaving = true;
+        self.set_mtu(self.mtu);
+        self.pending_queue.set_interleaving(true);
+    }
+
+    /// data_chunk_header_size returns the size of the header of the DATA
+    /// chunks, or I-DATA chunks with user message interleaving.
+    fn data_chunk_header_size(&self) -> u32 {
+        if self.use_message_interleaving {
+            IDATA_CHUNK_HEADER_SIZE
+        } else {
+            DATA_CHUNK_HEADER_SIZE
+        }
+    }
+
+    /// set_mtu sets the size of the outgoing packets, and the size of the DATA
+    /// chunks the streams fragment their messages into.
+    fn set_mtu(&mut self, mtu: u32) {
+        self.mtu = mtu;
+        self.max_payload_size.store(
+            mtu - (COMMON_HEADER_SIZE + self.data_chunk_header_size()),
+            Ordering::SeqCst,
+        );
+    }
+
+    async fn pmtu_changed(&mut self, mtu: u32) {
+        log::debug!("[{}] path MTU changed: {} -> {}", self.name, self.mtu, mtu);
+        self.set_mtu(mtu);
+
+        if let Some(handler) = &*self.on_pmtu_change.load() {
+            let mut f = handler.lock().await;
+            f(mtu).await;
+        }
+    }
+
     async fn handle_heartbeat(&self, c: &ChunkHeartbeat) -> Result<Vec<Packet>> {
         log::trace!("[{}] chunkHeartbeat", self.name);
         if let Some(p) = c.params.first() {
@@ -873,10 +1296,49 @@ impl AssociationInternal {
         Ok(vec![])
     }
 
+    /// handle_heartbeat_ack handles the acknowledgement of a PMTU probe, the only
+    /// HEARTBEAT chunks sent by the association.
+    async fn handle_heartbeat_ack(&mut self, c: &ChunkHeartbeatAck) -> Result<Vec<Packet>> {
+        log::trace!("[{}] chunkHeartbeatAck", self.name);
+        let hbi = match c
+            .params
+            .first()
+            .and_then(|p| p.as_any().downcast_ref::<ParamHeartbeatInfo>())
+        {
+            Some(hbi) if hbi.heartbeat_information.len() == PROBE_INFO_LENGTH => hbi,
+            _ => return Ok(vec![]),
+        };
+
+        let reader = &mut hbi.heartbeat_information.clone();
+        let _size = reader.get_u32();
+        let sequence = reader.get_u32();
+
+        let raised = if let Some(pmtud) = &mut self.pmtud {
+            pmtud.on_probe_acked(sequence, Instant::now())
+        } else {
+            None
+        };
+        if let Some(mtu) = raised {
+            self.pmtu_changed(mtu).await;
+        }
+
+        // send the next probe
+        self.awake_write_loop();
+
+        Ok(vec![])
+    }
+
     async fn handle_cookie_echo(&mut self, c: &ChunkCookieEcho) -> Result<Vec<Packet>> {
         let state = self.get_state();
         log::debug!("[{}] COOKIE-ECHO received in state '{}'", self.name, state);
 
+        if let Some(restart) = self.pending_restart.take() {
+            if state == AssociationState::Established && restart.cookie.cookie == c.cookie {
+                return self.restart(restart).await;
+            }
+            self.pending_restart = Some(restart);
+        }
+
         if let Some(my_cookie) = &self.my_cookie {
             match state {
                 AssociationState::Established => {
@@ -945,6 +1407,20 @@ impl AssociationInternal {
         Ok(vec![])
     }
 
+    /// handle_abort fails the handshake if the peer aborts it, before the
+    /// association gets closed.
+    async fn handle_abort(&mut self) {
+        let state = self.get_state();
+        log::debug!("[{}] ABORT received in state '{}'", self.name, state);
+        if state == AssociationState::CookieWait || state == AssociationState::CookieEchoed {
+            if let Some(handshake_completed_ch) = &self.handshake_completed_ch_tx {
+                let _ = handshake_completed_ch
+                    .send(Some(Error::ErrHandshakeAborted))
+                    .await;
+            }
+        }
+    }
+
     async fn handle_data(&mut self, d: &ChunkPayloadData) -> Result<Vec<Packet>> {
         log::trace!(
             "[{}] DATA: tsn={} immediateSack={} len={}",
@@ -955,9 +1431,28 @@ impl AssociationInternal {
         );
         self.stats.inc_datas();
 
+        // From RFC 8260 Sec 2.1:
+        //   Once I-DATA chunks are negotiated, DATA chunks MUST NOT be used,
+        //   and I-DATA chunks MUST NOT be used otherwise.
+        if d.interleaved != self.use_message_interleaving {
+            return Err(Error::ErrUnexpectedDataChunkType);
+        }
+
+        // RFC 4960 sec 6.5: DATA on a stream beyond the negotiated inbound
+        // streams is acknowledged, then discarded and reported with an ERROR
+        // chunk carrying the Invalid Stream Identifier cause.
+        let invalid_stream = d.stream_identifier >= self.my_max_num_inbound_streams;
+
         let can_push = self.payload_queue.can_push(d, self.peer_last_tsn);
         let mut stream_handle_data = false;
-        if can_push {
+        if can_push && invalid_stream {
+            log::debug!(
+                "[{}] DATA on invalid stream {}",
+                self.name,
+                d.stream_identifier
+            );
+            self.payload_queue.push(d.clone(), self.peer_last_tsn);
+        } else if can_push {
             if let Some(_s) = self.get_or_create_stream(d.stream_identifier) {
                 if self.get_my_receiver_window_credit().await > 0 {
                     // Pass the new chunk to stream level as soon as it arrives
@@ -996,7 +1491,26 @@ impl AssociationInternal {
             }
         }
 
-        self.handle_peer_last_tsn_and_acknowledgement(immediate_sack)
+        let mut reply = self.handle_peer_last_tsn_and_acknowledgement(immediate_sack)?;
+
+        if can_push && invalid_stream {
+            let mut raw = BytesMut::with_capacity(4);
+            raw.put_u16(d.stream_identifier);
+            raw.put_u16(0); // reserved
+            reply.push(Packet {
+                verification_tag: self.peer_verification_tag,
+                source_port: self.source_port,
+                destination_port: self.destination_port,
+                chunks: vec![Box::new(ChunkError {
+                    error_causes: vec![ErrorCause {
+                        code: INVALID_STREAM_IDENTIFIER,
+                        raw: raw.freeze(),
+                    }],
+                })],
+            });
+        }
+
+        Ok(reply)
     }
 
     /// A common routine for handle_data and handle_forward_tsn routines
@@ -1077,6 +1591,10 @@ impl AssociationInternal {
             return Err(Error::ErrStreamAlreadyExist);
         }
 
+        if stream_identifier >= self.my_max_num_outbound_streams {
+            return Err(Error::ErrStreamIdentifierOutOfRange);
+        }
+
         if let Some(s) = self.create_stream(stream_identifier, false) {
             s.set_default_payload_type(default_payload_type);
             Ok(Arc::clone(&s))
@@ -1090,11 +1608,12 @@ impl AssociationInternal {
         let s = Arc::new(Stream::new(
             format!("{}:{}", stream_identifier, self.name),
             stream_identifier,
-            self.max_payload_size,
+            Arc::clone(&self.max_payload_size),
             Arc::clone(&self.max_message_size),
             Arc::clone(&self.state),
             self.awake_write_loop_ch.clone(),
             Arc::clone(&self.pending_queue),
+            Arc::clone(&self.send_buffer),
         ));
 
         if accept {
@@ -1133,8 +1652,9 @@ impl AssociationInternal {
     async fn process_selective_ack(
         &mut self,
         d: &ChunkSelectiveAck,
-    ) -> Result<(HashMap<u16, i64>, u32)> {
+    ) -> Result<(HashMap<u16, i64>, i64, u32)> {
         let mut bytes_acked_per_stream = HashMap::new();
+        let mut total_bytes_acked = 0;
 
         // New ack point, so pop all ACKed packets from inflight_queue
         // We add 1 because the "currentAckPoint" has already been popped from the inflight queue
@@ -1156,9 +1676,10 @@ impl AssociationInternal {
                         }
                     }
 
-                    let n_bytes_acked = c.user_data.len() as i64;
+                    total_bytes_acked += c.user_data.len() as i64;
 
                     // Sum the number of bytes acknowledged per stream
+                    let n_bytes_acked = c.buffered_len() as i64;
                     if let Some(amount) = bytes_acked_per_stream.get_mut(&c.stream_identifier) {
                         *amount += n_bytes_acked;
                     } else {
@@ -1209,11 +1730,14 @@ impl AssociationInternal {
             for i in g.start..=g.end {
                 let tsn = d.cumulative_tsn_ack + i as u32;
 
-                let (is_existed, is_acked) = if let Some(c) = self.inflight_queue.get(tsn) {
-                    (true, c.acked)
-                } else {
-                    (false, false)
-                };
+                // the user data is released once marked as acked, so its
+                // buffered length is taken beforehand
+                let (is_existed, is_acked, n_bytes_buffered) =
+                    if let Some(c) = self.inflight_queue.get(tsn) {
+                        (true, c.acked, c.buffered_len() as i64)
+                    } else {
+                        (false, false, 0)
+                    };
                 let n_bytes_acked = if is_existed && !is_acked {
                     self.inflight_queue.mark_as_acked(tsn) as i64
                 } else {
@@ -1222,7 +1746,10 @@ impl AssociationInternal {
 
                 if let Some(c) = self.inflight_queue.get(tsn) {
                     if !is_acked {
+                        total_bytes_acked += n_bytes_acked;
+
                         // Sum the number of bytes acknowledged per stream
+                        let n_bytes_acked = n_bytes_buffered;
                         if let Some(amount) = bytes_acked_per_stream.get_mut(&c.stream_identifier) {
                             *amount += n_bytes_acked;
                         } else {
@@ -1257,7 +1784,7 @@ impl AssociationInternal {
             }
         }
 
-        Ok((bytes_acked_per_stream, htna))
+        Ok((bytes_acked_per_stream, total_bytes_acked, htna))
     }
 
     async fn on_cumulative_tsn_ack_point_advanced(&mut self, total_bytes_acked: i64) {
@@ -1281,67 +1808,53 @@ impl AssociationInternal {
         }
 
         // Update congestion control parameters
-        if self.cwnd <= self.ssthresh {
-            // RFC 4096, sec 7.2.1.  Slow-Start
-            //   o  When cwnd is less than or equal to ssthresh, an SCTP endpoint MUST
-            //		use the slow-start algorithm to increase cwnd only if the current
-            //      congestion window is being fully utilized, an incoming SACK
-            //      advances the Cumulative TSN Ack Point, and the data sender is not
-            //      in Fast Recovery.  Only when these three conditions are met can
-            //      the cwnd be increased; otherwise, the cwnd MUST not be increased.
-            //		If these conditions are met, then cwnd MUST be increased by, at
-            //      most, the lesser of 1) the total size of the previously
-            //      outstanding DATA chunk(s) acknowledged, and 2) the destination's
-            //      path MTU.
-            if !self.in_fast_recovery && self.pending_queue.len() > 0 {
-                self.cwnd += std::cmp::min(total_bytes_acked as u32, self.cwnd); // TCP way
-                                                                                 // self.cwnd += min32(uint32(total_bytes_acked), self.mtu) // SCTP way (slow)
-                log::trace!(
-                    "[{}] updated cwnd={} ssthresh={} acked={} (SS)",
-                    self.name,
-                    self.cwnd,
-                    self.ssthresh,
-                    total_bytes_acked
-                );
-            } else {
-                log::trace!(
-                    "[{}] cwnd did not grow: cwnd={} ssthresh={} acked={} FR={} pending={}",
-                    self.name,
-                    self.cwnd,
-                    self.ssthresh,
-                    total_bytes_acked,
-                    self.in_fast_recovery,
-                    self.pending_queue.len()
-                );
-            }
+        let ack = Ack {
+            bytes_acked: total_bytes_acked as u32,
+            in_fast_recovery: self.in_fast_recovery,
+            has_pending_data: !self.pending_queue.is_empty(),
+            srtt: self.rto_mgr.srtt,
+            now: Instant::now(),
+        };
+        let cwnd = self.cwnd;
+        self.update_congestion_window(|cc, w| cc.on_ack(w, &ack));
+        if self.cwnd != cwnd {
+            log::trace!(
+                "[{}] updated cwnd={} ssthresh={} acked={} (ACK)",
+                self.name,
+                self.cwnd,
+                self.ssthresh,
+                total_bytes_acked
+            );
         } else {
-            // RFC 4096, sec 7.2.2.  Congestion Avoidance
-            //   o  Whenever cwnd is greater than ssthresh, upon each SACK arrival
-            //      that advances the Cumulative TSN Ack Point, increase
-            //      partial_bytes_acked by the total number of bytes of all new chunks
-            //      acknowledged in that SACK including chunks acknowledged by the new
-            //      Cumulative TSN Ack and by Gap Ack Blocks.
-            self.partial_bytes_acked += total_bytes_acked as u32;
-
-            //   o  When partial_bytes_acked is equal to or greater than cwnd and
-            //      before the arrival of the SACK the sender had cwnd or more bytes
-            //      of data outstanding (i.e., before arrival of the SACK, flight size
-            //      was greater than or equal to cwnd), increase cwnd by MTU, and
-            //      reset partial_bytes_acked to (partial_bytes_acked - cwnd).
-            if self.partial_bytes_acked >= self.cwnd && self.pending_queue.len() > 0 {
-                self.partial_bytes_acked -= self.cwnd;
-                self.cwnd += self.mtu;
-                log::trace!(
-                    "[{}] updated cwnd={} ssthresh={} acked={} (CA)",
-                    self.name,
-                    self.cwnd,
-                    self.ssthresh,
-                    total_bytes_acked
-                );
-            }
+            log::trace!(
+                "[{}] cwnd did not grow: cwnd={} ssthresh={} acked={} FR={} pending={}",
+                self.name,
+                self.cwnd,
+                self.ssthresh,
+                total_bytes_acked,
+                self.in_fast_recovery,
+                self.pending_queue.len()
+            );
         }
     }
 
+    /// update_congestion_window lets the congestion controller adjust cwnd
+    /// and ssthresh.
+    fn update_congestion_window(
+        &mut self,
+        f: impl FnOnce(&mut (dyn CongestionController + Send + Sync), &mut CongestionWindow),
+    ) {
+        let mut w = CongestionWindow {
+            cwnd: self.cwnd,
+            ssthresh: self.ssthresh,
+            mtu: self.mtu,
+        };
+        f(self.congestion_control.as_mut(), &mut w);
+        self.cwnd = w.cwnd;
+        self.ssthresh = w.ssthresh;
+        self.send_buffer.set_cwnd(self.cwnd);
+    }
+
     fn process_fast_retransmission(
         &mut self,
         cum_tsn_ack_point: u32,
@@ -1369,17 +1882,20 @@ impl AssociationInternal {
             let mut tsn = cum_tsn_ack_point + 1;
             while sna32lt(tsn, max_tsn) {
                 if let Some(c) = self.inflight_queue.get_mut(tsn) {
-                    if !c.acked && !c.abandoned() && c.miss_indicator < 3 {
+                    if !c.acked
+                        && !c.abandoned()
+                        && c.miss_indicator < self.fast_retransmit_threshold
+                    {
                         c.miss_indicator += 1;
-                        if c.miss_indicator == 3 && !self.in_fast_recovery {
+                        if c.miss_indicator == self.fast_retransmit_threshold
+                            && !self.in_fast_recovery
+                        {
                             // 2)  If not in Fast Recovery, adjust the ssthresh and cwnd of the
                             //     destination address(es) to which the missing DATA chunks were
                             //     last sent, according to the formula described in Section 7.2.3.
                             self.in_fast_recovery = true;
                             self.fast_recover_exit_point = htna;
-                            self.ssthresh = std::cmp::max(self.cwnd / 2, 4 * self.mtu);
-                            self.cwnd = self.ssthresh;
-                            self.partial_bytes_acked = 0;
+                            self.update_congestion_window(|cc, w| cc.on_congestion(w));
                             self.will_retransmit_fast = true;
 
                             log::trace!(
@@ -1423,6 +1939,7 @@ impl AssociationInternal {
         }
 
         self.stats.inc_sacks();
+        self.stats.add_gap_ack_blocks(d.gap_ack_blocks.len() as u64);
 
         if sna32gt(self.cumulative_tsn_ack_point, d.cumulative_tsn_ack) {
             // RFC 4960 sec 6.2.1.  Processing a Received SACK
@@ -1444,12 +1961,8 @@ impl AssociationInternal {
         }
 
         // Process selective ack
-        let (bytes_acked_per_stream, htna) = self.process_selective_ack(d).await?;
-
-        let mut total_bytes_acked = 0;
-        for n_bytes_acked in bytes_acked_per_stream.values() {
-            total_bytes_acked += *n_bytes_acked;
-        }
+        let (mut bytes_acked_per_stream, total_bytes_acked, htna) =
+            self.process_selective_ack(d).await?;
 
         let mut cum_tsn_ack_point_advanced = false;
         if sna32lt(self.cumulative_tsn_ack_point, d.cumulative_tsn_ack) {
@@ -1466,6 +1979,11 @@ impl AssociationInternal {
                 .await;
         }
 
+        // The fragments abandoned before being sent leave the buffered amount too
+        for (si, n_bytes) in self.bytes_abandoned_per_stream.drain() {
+            *bytes_acked_per_stream.entry(si).or_insert(0) += n_bytes;
+        }
+
         for (si, n_bytes_acked) in &bytes_acked_per_stream {
             if let Some(s) = self.streams.get_mut(si) {
                 s.on_buffer_released(*n_bytes_acked).await;
@@ -1499,15 +2017,26 @@ impl AssociationInternal {
             }
 
             // RFC 3758 Sec 3.5 C2
+            let mut abandoned_messages = vec![];
             let mut i = self.advanced_peer_tsn_ack_point + 1;
             while let Some(c) = self.inflight_queue.get(i) {
                 if !c.abandoned() {
                     break;
                 }
+                // A user message is lost once one of its fragments is skipped unacknowledged
+                if !c.acked && c.set_abandon_notified() {
+                    abandoned_messages.push((c.stream_identifier, c.payload_type));
+                }
                 self.advanced_peer_tsn_ack_point = i;
                 i += 1;
             }
 
+            for (si, ppi) in abandoned_messages {
+                if let Some(s) = self.streams.get(&si) {
+                    s.on_message_abandoned(ppi).await;
+                }
+            }
+
             // RFC 3758 Sec 3.5 C3
             if sna32gt(
                 self.advanced_peer_tsn_ack_point,
@@ -1659,6 +2188,52 @@ impl AssociationInternal {
         fwd_tsn
     }
 
+    /// create_iforward_tsn generates I-FORWARD-TSN chunk, which replaces the
+    /// ForwardTSN chunk with user message interleaving.
+    fn create_iforward_tsn(&self) -> ChunkIForwardTsn {
+        // RFC 8260 Sec 2.3.1
+        // to report only once per SI and U bit, with the greatest MID
+        let mut stream_map: HashMap<(u16, bool), u32> = HashMap::new();
+        let mut i = self.cumulative_tsn_ack_point + 1;
+        while sna32lte(i, self.advanced_peer_tsn_ack_point) {
+            if let Some(c) = self.inflight_queue.get(i) {
+                let key = (c.stream_identifier, c.unordered);
+                match stream_map.get(&key) {
+                    Some(mid) if !sna32lt(*mid, c.message_identifier) => {}
+                    _ => {
+                        stream_map.insert(key, c.message_identifier);
+                    }
+                }
+            } else {
+                break;
+            }
+
+            i += 1;
+        }
+
+        let fwd_tsn = ChunkIForwardTsn {
+            new_cumulative_tsn: self.advanced_peer_tsn_ack_point,
+            streams: stream_map
+                .into_iter()
+                .map(
+                    |((identifier, unordered), message_identifier)| ChunkIForwardTsnStream {
+                        identifier,
+                        unordered,
+                        message_identifier,
+                    },
+                )
+                .collect(),
+        };
+        log::trace!(
+            "[{}] building ifwd_tsn: cumTSN={} - {}",
+            self.name,
+            self.cumulative_tsn_ack_point,
+            fwd_tsn
+        );
+
+        fwd_tsn
+    }
+
     /// create_packet wraps chunks in a packet.
     /// The caller should hold the read lock.
     pub(crate) fn create_packet(&self, chunks: Vec<Box<dyn Chunk + Send + Sync>>) -> Packet {
@@ -1695,20 +2270,81 @@ impl AssociationInternal {
 
         if !self.use_forward_tsn {
             log::warn!("[{}] received FwdTSN but not enabled", self.name);
-            // Return an error chunk
-            let cerr = ChunkError {
-                error_causes: vec![ErrorCauseUnrecognizedChunkType::default()],
-            };
+            return Ok(vec![self.create_unrecognized_chunk_type_packet()]);
+        }
 
-            let outbound = Packet {
-                verification_tag: self.peer_verification_tag,
-                source_port: self.source_port,
-                destination_port: self.destination_port,
-                chunks: vec![Box::new(cerr)],
-            };
-            return Ok(vec![outbound]);
+        if !self.forward_peer_last_tsn(c.new_cumulative_tsn) {
+            return Ok(vec![]);
+        }
+
+        // Report new peer_last_tsn value and abandoned largest SSN value to
+        // corresponding streams so that the abandoned chunks can be removed
+        // from the reassemblyQueue.
+        for forwarded in &c.streams {
+            if let Some(s) = self.streams.get_mut(&forwarded.identifier) {
+                s.handle_forward_tsn_for_ordered(forwarded.sequence).await;
+            }
         }
 
+        // TSN may be forewared for unordered chunks. ForwardTSN chunk does not
+        // report which stream identifier it skipped for unordered chunks.
+        // Therefore, we need to broadcast this event to all existing streams for
+        // unordered chunks.
+        // See https://github.com/pion/sctp/issues/106
+        for s in self.streams.values_mut() {
+            s.handle_forward_tsn_for_unordered(c.new_cumulative_tsn)
+                .await;
+        }
+
+        self.handle_peer_last_tsn_and_acknowledgement(false)
+    }
+
+    async fn handle_iforward_tsn(&mut self, c: &ChunkIForwardTsn) -> Result<Vec<Packet>> {
+        log::trace!("[{}] IFwdTSN: {}", self.name, c);
+
+        // From RFC 8260 Sec 2.3.1:
+        //   The I-FORWARD-TSN chunk MUST be used instead of the FORWARD-TSN
+        //   chunk if the I-DATA chunk has been negotiated.
+        if !self.use_forward_tsn || !self.use_message_interleaving {
+            log::warn!("[{}] received IFwdTSN but not enabled", self.name);
+            return Ok(vec![self.create_unrecognized_chunk_type_packet()]);
+        }
+
+        if !self.forward_peer_last_tsn(c.new_cumulative_tsn) {
+            return Ok(vec![]);
+        }
+
+        // Unlike the ForwardTSN chunk, the I-FORWARD-TSN chunk reports the
+        // abandoned unordered messages too, by their MID.
+        for forwarded in &c.streams {
+            if let Some(s) = self.streams.get_mut(&forwarded.identifier) {
+                s.handle_iforward_tsn(forwarded.unordered, forwarded.message_identifier)
+                    .await;
+            }
+        }
+
+        self.handle_peer_last_tsn_and_acknowledgement(false)
+    }
+
+    /// create_unrecognized_chunk_type_packet creates the error packet to reply
+    /// to a chunk of an extension that was not negotiated.
+    fn create_unrecognized_chunk_type_packet(&self) -> Packet {
+        let cerr = ChunkError {
+            error_causes: vec![ErrorCauseUnrecognizedChunkType::default()],
+        };
+
+        Packet {
+            verification_tag: self.peer_verification_tag,
+            source_port: self.source_port,
+            destination_port: self.destination_port,
+            chunks: vec![Box::new(cerr)],
+        }
+    }
+
+    /// forward_peer_last_tsn advances peer_last_tsn to the new cumulative TSN
+    /// of a ForwardTSN or I-FORWARD-TSN chunk. It returns false if the chunk
+    /// is out-of-date.
+    fn forward_peer_last_tsn(&mut self, new_cumulative_tsn: u32) -> bool {
         // From RFC 3758 Sec 3.6:
         //   Note, if the "New Cumulative TSN" value carried in the arrived
         //   FORWARD TSN chunk is found to be behind or at the current cumulative
@@ -1720,17 +2356,17 @@ impl AssociationInternal {
         log::trace!(
             "[{}] should send ack? newCumTSN={} peer_last_tsn={}",
             self.name,
-            c.new_cumulative_tsn,
+            new_cumulative_tsn,
             self.peer_last_tsn
         );
-        if sna32lte(c.new_cumulative_tsn, self.peer_last_tsn) {
+        if sna32lte(new_cumulative_tsn, self.peer_last_tsn) {
             log::trace!("[{}] sending ack on Forward TSN", self.name);
             self.ack_state = AckState::Immediate;
             if let Some(ack_timer) = &mut self.ack_timer {
                 ack_timer.stop();
             }
             self.awake_write_loop();
-            return Ok(vec![]);
+            return false;
         }
 
         // From RFC 3758 Sec 3.6:
@@ -1744,34 +2380,15 @@ impl AssociationInternal {
         //   chunk,
 
         // Advance peer_last_tsn
-        while sna32lt(self.peer_last_tsn, c.new_cumulative_tsn) {
+        while sna32lt(self.peer_last_tsn, new_cumulative_tsn) {
             self.payload_queue.pop(self.peer_last_tsn + 1); // may not exist
             self.peer_last_tsn += 1;
         }
 
-        // Report new peer_last_tsn value and abandoned largest SSN value to
-        // corresponding streams so that the abandoned chunks can be removed
-        // from the reassemblyQueue.
-        for forwarded in &c.streams {
-            if let Some(s) = self.streams.get_mut(&forwarded.identifier) {
-                s.handle_forward_tsn_for_ordered(forwarded.sequence).await;
-            }
-        }
-
-        // TSN may be forewared for unordered chunks. ForwardTSN chunk does not
-        // report which stream identifier it skipped for unordered chunks.
-        // Therefore, we need to broadcast this event to all existing streams for
-        // unordered chunks.
-        // See https://github.com/pion/sctp/issues/106
-        for s in self.streams.values_mut() {
-            s.handle_forward_tsn_for_unordered(c.new_cumulative_tsn)
-                .await;
-        }
-
-        self.handle_peer_last_tsn_and_acknowledgement(false)
+        true
     }
 
-    fn send_reset_request(&mut self, stream_identifier: u16) -> Result<()> {
+    pub(crate) fn send_reset_request(&mut self, stream_identifier: u16) -> Result<()> {
         let state = self.get_state();
         if state != AssociationState::Established {
             return Err(Error::ErrResetPacketInStateNotExist);
@@ -1793,28 +2410,240 @@ impl AssociationInternal {
         Ok(())
     }
 
+    /// send_add_streams_request asks the peer to add outgoing and incoming streams.
+    /// The stream counts are raised once the peer has performed the requests.
+    pub(crate) async fn send_add_streams_request(
+        &mut self,
+        num_outbound: u16,
+        num_inbound: u16,
+    ) -> Result<()> {
+        let state = self.get_state();
+        if state != AssociationState::Established {
+            return Err(Error::ErrAddStreamsInStateNotExist);
+        }
+
+        for &(is_incoming, number_of_new_streams) in &[(false, num_outbound), (true, num_inbound)] {
+            if number_of_new_streams == 0 {
+                continue;
+            }
+
+            let rsn = self.generate_next_rsn();
+            log::debug!(
+                "[{}] sending RECONFIG: rsn={} add {} {} stream(s)",
+                self.name,
+                rsn,
+                number_of_new_streams,
+                if is_incoming { "incoming" } else { "outgoing" }
+            );
+
+            let c = ChunkReconfig {
+                param_a: Some(Box::new(ParamAddStreamsRequest {
+                    is_incoming,
+                    reconfig_request_sequence_number: rsn,
+                    number_of_new_streams,
+                })),
+                param_b: None,
+            };
+            self.reconfigs.insert(rsn, c.clone()); // store in the map for retransmission
+            let p = self.create_packet(vec![Box::new(c)]);
+            self.control_queue.push_back(p);
+        }
+
+        if !self.reconfigs.is_empty() {
+            if let Some(treconfig) = &self.treconfig {
+                treconfig.start(self.rto_mgr.get_rto()).await;
+            }
+        }
+
+        self.awake_write_loop();
+        Ok(())
+    }
+
     #[allow(clippy::borrowed_box)]
     async fn handle_reconfig_param(
         &mut self,
         raw: &Box<dyn Param + Send + Sync>,
     ) -> Result<Option<Packet>> {
         if let Some(p) = raw.as_any().downcast_ref::<ParamOutgoingResetRequest>() {
-            self.reconfig_requests
-                .insert(p.reconfig_request_sequence_number, p.clone());
+            let rsn = p.reconfig_request_sequence_number;
+            // A retransmitted request which has been performed already must not
+            // reset the streams again as they may have been reopened since.
+            if let Some(result) = self.completed_reconfig_result(rsn) {
+                return Ok(Some(self.create_reconfig_response(rsn, result)));
+            }
+            self.reconfig_requests.insert(rsn, p.clone());
             Ok(Some(self.reset_streams_if_any(p)))
-        } else if let Some(p) = raw.as_any().downcast_ref::<ParamReconfigResponse>() {
-            self.reconfigs.remove(&p.reconfig_response_sequence_number);
-            if self.reconfigs.is_empty() {
-                if let Some(treconfig) = &self.treconfig {
-                    treconfig.stop().await;
-                }
+        } else if let Some(p) = raw.as_any().downcast_ref::<ParamIncomingResetRequest>() {
+            if self
+                .reconfig_results
+                .contains_key(&p.reconfig_request_sequence_number)
+            {
+                // answered by our outgoing SSN reset request, which is
+                // retransmitted on its own
+                return Ok(None);
             }
+            Ok(self.handle_incoming_reset_request(p))
+        } else if let Some(p) = raw.as_any().downcast_ref::<ParamAddStreamsRequest>() {
+            let rsn = p.reconfig_request_sequence_number;
+            if let Some(result) = self.completed_reconfig_result(rsn) {
+                return Ok(Some(self.create_reconfig_response(rsn, result)));
+            }
+            Ok(Some(self.handle_add_streams_request(p)))
+        } else if let Some(p) = raw.as_any().downcast_ref::<ParamReconfigResponse>() {
+            self.handle_reconfig_response(p).await;
             Ok(None)
         } else {
             Err(Error::ErrParamterType)
         }
     }
 
+    /// handle_incoming_reset_request resets our outgoing streams the peer asked for.
+    /// The request is answered by the outgoing SSN reset request sent once the
+    /// data queued on those streams has been sent (RFC 6525 sec 5.2.3).
+    fn handle_incoming_reset_request(&mut self, p: &ParamIncomingResetRequest) -> Option<Packet> {
+        let rsn = p.reconfig_request_sequence_number;
+        let stream_identifiers: Vec<u16> = if p.stream_identifiers.is_empty() {
+            self.streams.keys().cloned().collect()
+        } else {
+            p.stream_identifiers
+                .iter()
+                .filter(|id| self.streams.contains_key(*id))
+                .cloned()
+                .collect()
+        };
+
+        if stream_identifiers.is_empty() {
+            return Some(self.create_reconfig_response(rsn, ReconfigResult::SuccessNop));
+        }
+
+        for stream_identifier in stream_identifiers {
+            if let Err(err) = self.send_reset_request(stream_identifier) {
+                log::warn!(
+                    "[{}] failed to reset stream {}: {}",
+                    self.name,
+                    stream_identifier,
+                    err
+                );
+                return Some(self.create_reconfig_response(rsn, ReconfigResult::Denied));
+            }
+
+            // The data written after the reset starts over from the first
+            // sequence number.
+            if let Some(s) = self.streams.get(&stream_identifier) {
+                s.sequence_number.store(0, Ordering::SeqCst);
+                s.message_identifier.store(0, Ordering::SeqCst);
+                s.unordered_message_identifier.store(0, Ordering::SeqCst);
+            }
+        }
+
+        self.record_reconfig_result(rsn, ReconfigResult::SuccessPerformed);
+        self.incoming_reset_rsn = Some(rsn);
+
+        None
+    }
+
+    /// handle_add_streams_request adds the streams the peer asked for. An add
+    /// outgoing streams request adds to the peer's outgoing streams, which are
+    /// our inbound ones, and an add incoming streams request to our outbound ones.
+    fn handle_add_streams_request(&mut self, p: &ParamAddStreamsRequest) -> Packet {
+        let num_streams = if p.is_incoming {
+            &mut self.my_max_num_outbound_streams
+        } else {
+            &mut self.my_max_num_inbound_streams
+        };
+
+        let result = if let Some(n) = num_streams.checked_add(p.number_of_new_streams) {
+            *num_streams = n;
+            ReconfigResult::SuccessPerformed
+        } else {
+            ReconfigResult::Denied
+        };
+
+        log::debug!(
+            "[{}] add streams: incoming={} n={} result={} (out={} in={})",
+            self.name,
+            p.is_incoming,
+            p.number_of_new_streams,
+            result,
+            self.my_max_num_outbound_streams,
+            self.my_max_num_inbound_streams
+        );
+
+        self.create_reconfig_response(p.reconfig_request_sequence_number, result)
+    }
+
+    async fn handle_reconfig_response(&mut self, p: &ParamReconfigResponse) {
+        let rsn = p.reconfig_response_sequence_number;
+        if p.result == ReconfigResult::InProgress {
+            // The request is kept to be retransmitted until the peer has
+            // performed it.
+            log::debug!("[{}] RECONFIG rsn={} in progress", self.name, rsn);
+            return;
+        }
+
+        if let Some(c) = self.reconfigs.remove(&rsn) {
+            if matches!(
+                p.result,
+                ReconfigResult::SuccessPerformed | ReconfigResult::SuccessNop
+            ) {
+                for param in c.param_a.iter().chain(c.param_b.iter()) {
+                    if let Some(req) = param.as_any().downcast_ref::<ParamAddStreamsRequest>() {
+                        if req.is_incoming {
+                            self.my_max_num_inbound_streams = self
+                                .my_max_num_inbound_streams
+                                .saturating_add(req.number_of_new_streams);
+                        } else {
+                            self.my_max_num_outbound_streams = self
+                                .my_max_num_outbound_streams
+                                .saturating_add(req.number_of_new_streams);
+                        }
+                    }
+                }
+            } else {
+                log::warn!(
+                    "[{}] RECONFIG request rsn={} failed: {}",
+                    self.name,
+                    rsn,
+                    p.result
+                );
+            }
+        }
+
+        if self.reconfigs.is_empty() {
+            if let Some(treconfig) = &self.treconfig {
+                treconfig.stop().await;
+            }
+        }
+    }
+
+    /// completed_reconfig_result returns the result of the peer's request with the
+    /// given rsn if it has been answered with anything but "in progress".
+    fn completed_reconfig_result(&self, rsn: u32) -> Option<ReconfigResult> {
+        match self.reconfig_results.get(&rsn) {
+            None | Some(ReconfigResult::InProgress) => None,
+            Some(result) => Some(*result),
+        }
+    }
+
+    /// record_reconfig_result keeps the result of the peer's request to answer its
+    /// retransmissions, forgetting the results of older requests.
+    fn record_reconfig_result(&mut self, rsn: u32, result: ReconfigResult) {
+        self.reconfig_results.insert(rsn, result);
+        self.reconfig_results
+            .retain(|k, _| rsn.wrapping_sub(*k) < RECONFIG_RESULT_HISTORY);
+    }
+
+    fn create_reconfig_response(&mut self, rsn: u32, result: ReconfigResult) -> Packet {
+        self.record_reconfig_result(rsn, result);
+        self.create_packet(vec![Box::new(ChunkReconfig {
+            param_a: Some(Box::new(ParamReconfigResponse {
+                reconfig_response_sequence_number: rsn,
+                result,
+            })),
+            param_b: None,
+        })])
+    }
+
     fn reset_streams_if_any(&mut self, p: &ParamOutgoingResetRequest) -> Packet {
         let mut result = ReconfigResult::SuccessPerformed;
         if sna32lte(p.sender_last_tsn, self.peer_last_tsn) {
@@ -1842,13 +2671,7 @@ impl AssociationInternal {
             result = ReconfigResult::InProgress;
         }
 
-        self.create_packet(vec![Box::new(ChunkReconfig {
-            param_a: Some(Box::new(ParamReconfigResponse {
-                reconfig_response_sequence_number: p.reconfig_request_sequence_number,
-                result,
-            })),
-            param_b: None,
-        })])
+        self.create_reconfig_response(p.reconfig_request_sequence_number, result)
     }
 
     /// Move the chunk peeked with self.pending_queue.peek() to the inflight_queue.
@@ -1909,6 +2732,15 @@ impl AssociationInternal {
         //      6.2.1).  However, regardless of the value of rwnd (including if it
         //      is 0), the data sender can always have one DATA chunk in flight to
         //      the receiver if allowed by cwnd (see rule B, below).
+        // RFC 4960 sec 6.1 also allows limiting the bursts of new data:
+        //   if ((flightsize + Max.Burst*MTU) < cwnd) cwnd = flightsize + Max.Burst*MTU
+        let mut cwnd = self.cwnd as usize;
+        if self.max_burst > 0 {
+            cwnd = std::cmp::min(
+                cwnd,
+                self.inflight_queue.get_num_bytes() + (self.max_burst * self.mtu) as usize,
+            );
+        }
         while let Some(c) = self.pending_queue.peek() {
             let (beginning_fragment, unordered, data_len, stream_identifier) = (
                 c.beginning_fragment,
@@ -1929,8 +2761,14 @@ impl AssociationInternal {
                 continue;
             }
 
-            if self.inflight_queue.get_num_bytes() + data_len > self.cwnd as usize {
-                break; // would exceed cwnd
+            if !sis_to_reset.is_empty() {
+                // sender_last_tsn of the reset request must be the last TSN
+                // sent before the reset
+                break;
+            }
+
+            if self.inflight_queue.get_num_bytes() + data_len > cwnd {
+                break; // would exceed cwnd or max burst
             }
 
             if data_len > self.rwnd as usize {
@@ -1947,7 +2785,7 @@ impl AssociationInternal {
         }
 
         // the data sender can always have one DATA chunk in flight to the receiver
-        if chunks.is_empty() && self.inflight_queue.is_empty() {
+        if chunks.is_empty() && self.inflight_queue.is_empty() && sis_to_reset.is_empty() {
             // Send zero window probe
             if let Some(c) = self.pending_queue.peek() {
                 let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
@@ -1983,7 +2821,7 @@ impl AssociationInternal {
                 bytes_in_packet = COMMON_HEADER_SIZE;
             }
 
-            bytes_in_packet += DATA_CHUNK_HEADER_SIZE + c.user_data.len() as u32;
+            bytes_in_packet += self.data_chunk_header_size() + c.user_data.len() as u32;
             chunks_to_send.push(Box::new(c));
         }
 
@@ -2044,11 +2882,42 @@ impl AssociationInternal {
         }
     }
 
+    /// abandon_unsent_fragments removes the fragments not sent yet of the user message of a chunk
+    /// abandoned while being retransmitted, as the message can no longer be delivered. The
+    /// fragments in flight can then be skipped with a ForwardTSN chunk.
+    /// It returns the number of bytes removed.
+    fn abandon_unsent_fragments(&self, c: &ChunkPayloadData) -> usize {
+        if !c.abandoned_partially_sent() {
+            return 0;
+        }
+
+        let n_bytes = self.pending_queue.remove_fragments(c);
+        c.all_inflight.store(true, Ordering::SeqCst);
+        log::trace!(
+            "[{}] abandoned unsent fragments: tsn={} ppi={} len={}",
+            self.name,
+            c.tsn,
+            c.payload_type,
+            n_bytes
+        );
+
+        n_bytes
+    }
+
+    /// add_bytes_abandoned records the bytes of the fragments abandoned before being sent, to be
+    /// released along with the next bytes acknowledged.
+    fn add_bytes_abandoned(&mut self, bytes_abandoned: Vec<(u16, usize)>) {
+        for (si, n_bytes) in bytes_abandoned {
+            *self.bytes_abandoned_per_stream.entry(si).or_insert(0) += n_bytes as i64;
+        }
+    }
+
     /// get_data_packets_to_retransmit is called when T3-rtx is timed out and retransmit outstanding data chunks
     /// that are not acked or abandoned yet.
     fn get_data_packets_to_retransmit(&mut self) -> Vec<Packet> {
         let awnd = std::cmp::min(self.cwnd, self.rwnd);
         let mut chunks = vec![];
+        let mut bytes_abandoned = vec![];
         let mut bytes_to_send = 0;
         let mut done = false;
         let mut i = 0;
@@ -2072,6 +2941,7 @@ impl AssociationInternal {
                 c.retransmit = false;
                 bytes_to_send += c.user_data.len();
 
+                self.stats.inc_retrans_chunks();
                 c.nsent += 1;
             } else {
                 break; // end of pending data
@@ -2079,6 +2949,10 @@ impl AssociationInternal {
 
             if let Some(c) = self.inflight_queue.get(tsn) {
                 self.check_partial_reliability_status(c);
+                let n_bytes = self.abandon_unsent_fragments(c);
+                if n_bytes > 0 {
+                    bytes_abandoned.push((c.stream_identifier, n_bytes));
+                }
 
                 log::trace!(
                     "[{}] retransmitting tsn={} ssn={} sent={}",
@@ -2092,6 +2966,7 @@ impl AssociationInternal {
             }
             i += 1;
         }
+        self.add_bytes_abandoned(bytes_abandoned);
 
         self.bundle_data_chunks_into_packets(chunks)
     }
@@ -2158,12 +3033,17 @@ impl AssociationInternal {
             } else {
                 self.handle_init(p, c).await?
             }
-        } else if chunk_any.downcast_ref::<ChunkAbort>().is_some()
-            || chunk_any.downcast_ref::<ChunkError>().is_some()
-        {
+        } else if chunk_any.downcast_ref::<ChunkAbort>().is_some() {
+            self.handle_abort().await;
+            return Err(Error::ErrChunk);
+        } else if chunk_any.downcast_ref::<ChunkError>().is_some() {
             return Err(Error::ErrChunk);
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkHeartbeat>() {
             self.handle_heartbeat(c).await?
+        } else if let Some(c) = chunk_any.downcast_ref::<ChunkHeartbeatAck>() {
+            self.handle_heartbeat_ack(c).await?
+        } else if chunk_any.downcast_ref::<ChunkPadding>().is_some() {
+            vec![]
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkCookieEcho>() {
             self.handle_cookie_echo(c).await?
         } else if chunk_any.downcast_ref::<ChunkCookieAck>().is_some() {
@@ -2176,6 +3056,8 @@ impl AssociationInternal {
             self.handle_reconfig(c).await?
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkForwardTsn>() {
             self.handle_forward_tsn(c).await?
+        } else if let Some(c) = chunk_any.downcast_ref::<ChunkIForwardTsn>() {
+            self.handle_iforward_tsn(c).await?
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkShutdown>() {
             self.handle_shutdown(c).await?
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkShutdownAck>() {
@@ -2256,6 +3138,42 @@ impl AssociationInternal {
         Ok(())
     }
 
+    /// stats_snapshot returns the state of the congestion control, the counters
+    /// and the streams of the association.
+    pub(crate) async fn stats_snapshot(&self) -> AssociationStatsSnapshot {
+        let mut streams = vec![];
+        for s in self.streams.values() {
+            streams.push(StreamStatsSnapshot {
+                stream_identifier: s.stream_identifier,
+                buffered_amount: s.buffered_amount(),
+                bytes_in_reassembly_queue: s.get_num_bytes_in_reassembly_queue().await,
+            });
+        }
+        streams.sort_by_key(|s| s.stream_identifier);
+
+        AssociationStatsSnapshot {
+            cwnd: self.cwnd,
+            ssthresh: self.ssthresh,
+            rwnd: self.rwnd,
+            mtu: self.mtu,
+            srtt: self.rto_mgr.srtt,
+            rttvar: self.rto_mgr.rttvar,
+            rto: self.rto_mgr.get_rto(),
+            bytes_in_flight: self.inflight_queue.get_num_bytes(),
+            chunks_in_flight: self.inflight_queue.len(),
+            bytes_pending: self.pending_queue.get_num_bytes(),
+            data_chunks_received: self.stats.get_num_datas(),
+            sacks_received: self.stats.get_num_sacks(),
+            gap_ack_blocks_received: self.stats.get_num_gap_ack_blocks(),
+            retransmitted_chunks: self.stats.get_num_retrans_chunks(),
+            fast_retransmissions: self.stats.get_num_fast_retrans(),
+            t3_timeouts: self.stats.get_num_t3timeouts(),
+            ack_timeouts: self.stats.get_num_ack_timeouts(),
+            streams,
+            ..Default::default()
+        }
+    }
+
     /// buffered_amount returns total amount (in bytes) of currently buffered user data.
     /// This is used only by testing.
     pub(crate) fn buffered_amount(&self) -> usize {
@@ -2335,9 +3253,8 @@ impl RtxTimerObserver for AssociationInternal {
                 //   start by:
                 //      ssthresh = max(cwnd/2, 4*MTU)
                 //      cwnd = 1*MTU
-
-                self.ssthresh = std::cmp::max(self.cwnd / 2, 4 * self.mtu);
-                self.cwnd = self.mtu;
+                // The congestion controller may pick another ssthresh.
+                self.update_congestion_window(|cc, w| cc.on_retransmission_timeout(w));
                 log::trace!(
                     "[{}] updated cwnd={} ssthresh={} inflight={} (RTO)",
                     self.name,
@@ -2385,6 +3302,19 @@ impl RtxTimerObserver for AssociationInternal {
                     self.ssthresh
                 );
 
+                // RFC 8899 sec 4.3: the loss of the packets may be caused by a
+                // path no longer delivering packets of the current size.
+                if n_rtos >= BLACK_HOLE_RTOS {
+                    let lowered = if let Some(pmtud) = &mut self.pmtud {
+                        pmtud.on_black_hole()
+                    } else {
+                        None
+                    };
+                    if let Some(mtu) = lowered {
+                        self.pmtu_changed(mtu).await;
+                    }
+                }
+
                 self.inflight_queue.mark_all_to_retrasmit();
                 self.awake_write_loop();
             }
@@ -2393,6 +3323,13 @@ impl RtxTimerObserver for AssociationInternal {
                 self.will_retransmit_reconfig = true;
                 self.awake_write_loop();
             }
+
+            RtxTimerId::Pmtud => {
+                if let Some(pmtud) = &mut self.pmtud {
+                    pmtud.on_probe_timeout(Instant::now());
+                }
+                self.awake_write_loop();
+            }
         }
     }
 
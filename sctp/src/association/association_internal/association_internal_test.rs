@@ -1,4 +1,5 @@
 use super::*;
+use crate::chunk::chunk_selective_ack::GapAckBlock;
 use std::io;
 use std::net::SocketAddr;
 
@@ -313,6 +314,7 @@ async fn test_assoc_create_new_stream() -> Result<()> {
     let (accept_ch_tx, _accept_ch_rx) = mpsc::channel(ACCEPT_CH_SIZE);
     let mut a = AssociationInternal {
         accept_ch_tx: Some(accept_ch_tx),
+        my_max_num_inbound_streams: u16::MAX,
         ..Default::default()
     };
 
@@ -349,10 +351,8 @@ async fn test_assoc_create_new_stream() -> Result<()> {
 
 async fn handle_init_test(name: &str, initial_state: AssociationState, expect_err: bool) {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
     a.set_state(initial_state);
     let pkt = Packet {
@@ -368,7 +368,7 @@ async fn handle_init_test(name: &str, initial_state: AssociationState, expect_er
         advertised_receiver_window_credit: 512 * 1024,
         ..Default::default()
     };
-    init.set_supported_extensions();
+    init.set_supported_extensions(false);
 
     let result = a.handle_init(&pkt, &init).await;
     if expect_err {
@@ -387,8 +387,10 @@ async fn handle_init_test(name: &str, initial_state: AssociationState, expect_er
         "{} should match",
         name
     );
-    assert_eq!(1001, a.my_max_num_outbound_streams, "{} should match", name);
-    assert_eq!(1002, a.my_max_num_inbound_streams, "{} should match", name);
+    // we can send on as many streams as the peer accepts, and receive on as
+    // many as it sends on
+    assert_eq!(1002, a.my_max_num_outbound_streams, "{} should match", name);
+    assert_eq!(1001, a.my_max_num_inbound_streams, "{} should match", name);
     assert_eq!(5678, a.peer_verification_tag, "{} should match", name);
     assert_eq!(pkt.source_port, a.destination_port, "{} should match", name);
     assert_eq!(pkt.destination_port, a.source_port, "{} should match", name);
@@ -399,13 +401,6 @@ async fn handle_init_test(name: &str, initial_state: AssociationState, expect_er
 async fn test_assoc_handle_init() -> Result<()> {
     handle_init_test("normal", AssociationState::Closed, false).await;
 
-    handle_init_test(
-        "unexpected state established",
-        AssociationState::Established,
-        true,
-    )
-    .await;
-
     handle_init_test(
         "unexpected state shutdownAckSent",
         AssociationState::ShutdownAckSent,
@@ -440,10 +435,8 @@ async fn test_assoc_handle_init() -> Result<()> {
 #[test]
 fn test_assoc_max_message_size_default() -> Result<()> {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
     assert_eq!(
         65536,
@@ -485,10 +478,9 @@ fn test_assoc_max_message_size_default() -> Result<()> {
 #[test]
 fn test_assoc_max_message_size_explicit() -> Result<()> {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
         max_message_size: 30000,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
 
     assert_eq!(
@@ -527,3 +519,654 @@ fn test_assoc_max_message_size_explicit() -> Result<()> {
 
     Ok(())
 }
+
+fn reconfig_response(packet: &Packet) -> ParamReconfigResponse {
+    let c = packet.chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkReconfig>()
+        .expect("should be a RECONFIG chunk");
+    c.param_a
+        .as_ref()
+        .and_then(|p| p.as_any().downcast_ref::<ParamReconfigResponse>())
+        .expect("should be a re-configuration response")
+        .clone()
+}
+
+#[tokio::test]
+async fn test_assoc_handle_duplicate_outgoing_reset_request() -> Result<()> {
+    let mut a = AssociationInternal::default();
+    a.set_state(AssociationState::Established);
+    a.peer_last_tsn = 10;
+    a.create_stream(1, false);
+
+    let c = ChunkReconfig {
+        param_a: Some(Box::new(ParamOutgoingResetRequest {
+            reconfig_request_sequence_number: 5,
+            sender_last_tsn: 10,
+            stream_identifiers: vec![1],
+            ..Default::default()
+        })),
+        param_b: None,
+    };
+
+    let packets = a.handle_reconfig(&c).await?;
+    assert_eq!(1, packets.len(), "should reply with a response");
+    assert_eq!(
+        ReconfigResult::SuccessPerformed,
+        reconfig_response(&packets[0]).result
+    );
+    assert!(!a.streams.contains_key(&1), "stream 1 should be reset");
+
+    // The stream is reopened before the retransmitted request arrives.
+    a.create_stream(1, false);
+    let packets = a.handle_reconfig(&c).await?;
+    assert_eq!(1, packets.len(), "should reply with a response");
+    let resp = reconfig_response(&packets[0]);
+    assert_eq!(5, resp.reconfig_response_sequence_number);
+    assert_eq!(ReconfigResult::SuccessPerformed, resp.result);
+    assert!(
+        a.streams.contains_key(&1),
+        "reopened stream 1 should not be reset again"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_reset_stream_clears_received_data() -> Result<()> {
+    let mut a = AssociationInternal::default();
+    a.set_state(AssociationState::Established);
+    a.peer_last_tsn = 10;
+    let s = a.create_stream(1, false).expect("should create a stream");
+
+    // an unordered message which is ready to be read, and an ordered one in the
+    // reassembly queue
+    s.handle_data(ChunkPayloadData {
+        unordered: true,
+        beginning_fragment: true,
+        ending_fragment: true,
+        stream_identifier: 1,
+        user_data: Bytes::from_static(&[0, 1, 2, 3]),
+        ..Default::default()
+    })
+    .await;
+    s.handle_data(ChunkPayloadData {
+        beginning_fragment: true,
+        ending_fragment: true,
+        stream_identifier: 1,
+        tsn: 1,
+        user_data: Bytes::from_static(&[4, 5]),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(6, s.get_num_bytes_in_reassembly_queue().await);
+
+    let c = ChunkReconfig {
+        param_a: Some(Box::new(ParamOutgoingResetRequest {
+            reconfig_request_sequence_number: 5,
+            sender_last_tsn: 10,
+            stream_identifiers: vec![1],
+            ..Default::default()
+        })),
+        param_b: None,
+    };
+    let _ = a.handle_reconfig(&c).await?;
+    assert!(!a.streams.contains_key(&1), "stream 1 should be reset");
+
+    assert!(s.unordered_ready.lock().unwrap().is_empty());
+    assert_eq!(0, s.get_num_bytes_in_reassembly_queue().await);
+    assert!(!s.reassembly_queue.lock().await.is_readable());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_handle_reconfig_response_in_progress() -> Result<()> {
+    let mut a = AssociationInternal::default();
+    a.reconfigs.insert(3, ChunkReconfig::default());
+
+    let mut c = ChunkReconfig {
+        param_a: Some(Box::new(ParamReconfigResponse {
+            reconfig_response_sequence_number: 3,
+            result: ReconfigResult::InProgress,
+        })),
+        param_b: None,
+    };
+    let _ = a.handle_reconfig(&c).await?;
+    assert!(
+        a.reconfigs.contains_key(&3),
+        "request in progress should be retransmitted"
+    );
+
+    c.param_a = Some(Box::new(ParamReconfigResponse {
+        reconfig_response_sequence_number: 3,
+        result: ReconfigResult::SuccessPerformed,
+    }));
+    let _ = a.handle_reconfig(&c).await?;
+    assert!(a.reconfigs.is_empty(), "request should be done");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_add_streams() -> Result<()> {
+    let mut a = AssociationInternal {
+        my_max_num_outbound_streams: 10,
+        my_max_num_inbound_streams: 10,
+        ..Default::default()
+    };
+
+    assert!(
+        a.send_add_streams_request(5, 0).await.is_err(),
+        "should fail before the association is established"
+    );
+
+    a.set_state(AssociationState::Established);
+    a.send_add_streams_request(5, 0).await?;
+    assert_eq!(1, a.reconfigs.len(), "should keep the request");
+    assert_eq!(1, a.control_queue.len(), "should send the request");
+    assert_eq!(
+        10, a.my_max_num_outbound_streams,
+        "should wait for the peer"
+    );
+
+    let rsn = *a.reconfigs.keys().next().unwrap();
+    let c = ChunkReconfig {
+        param_a: Some(Box::new(ParamReconfigResponse {
+            reconfig_response_sequence_number: rsn,
+            result: ReconfigResult::SuccessPerformed,
+        })),
+        param_b: None,
+    };
+    let _ = a.handle_reconfig(&c).await?;
+    assert_eq!(15, a.my_max_num_outbound_streams);
+    assert_eq!(10, a.my_max_num_inbound_streams);
+
+    // Requests from the peer
+    let tests = vec![
+        (1, u16::MAX, ReconfigResult::Denied, 10),
+        (2, 6, ReconfigResult::SuccessPerformed, 16),
+    ];
+    for (rsn, number_of_new_streams, expected, num_inbound_streams) in tests {
+        let c = ChunkReconfig {
+            param_a: Some(Box::new(ParamAddStreamsRequest {
+                is_incoming: false,
+                reconfig_request_sequence_number: rsn,
+                number_of_new_streams,
+            })),
+            param_b: None,
+        };
+        let packets = a.handle_reconfig(&c).await?;
+        assert_eq!(1, packets.len(), "should reply with a response");
+        assert_eq!(expected, reconfig_response(&packets[0]).result);
+        assert_eq!(num_inbound_streams, a.my_max_num_inbound_streams);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_handle_incoming_reset_request() -> Result<()> {
+    let mut a = AssociationInternal::default();
+    a.set_state(AssociationState::Established);
+    a.cwnd = 10_000;
+    a.rwnd = 10_000;
+    let s = a.create_stream(1, false).unwrap();
+    s.sequence_number.store(7, Ordering::SeqCst);
+
+    let c = ChunkReconfig {
+        param_a: Some(Box::new(ParamIncomingResetRequest {
+            reconfig_request_sequence_number: 20,
+            stream_identifiers: vec![2],
+        })),
+        param_b: None,
+    };
+    let packets = a.handle_reconfig(&c).await?;
+    assert_eq!(1, packets.len(), "should reply with a response");
+    assert_eq!(
+        ReconfigResult::SuccessNop,
+        reconfig_response(&packets[0]).result,
+        "no stream 2 to reset"
+    );
+
+    let c = ChunkReconfig {
+        param_a: Some(Box::new(ParamIncomingResetRequest {
+            reconfig_request_sequence_number: 21,
+            stream_identifiers: vec![],
+        })),
+        param_b: None,
+    };
+    let packets = a.handle_reconfig(&c).await?;
+    assert!(
+        packets.is_empty(),
+        "should be answered by an outgoing reset request"
+    );
+    assert_eq!(0, s.sequence_number.load(Ordering::SeqCst));
+
+    let _ = a.gather_outbound_data_and_reconfig_packets(vec![]).await;
+    assert_eq!(
+        1,
+        a.reconfigs.len(),
+        "should send an outgoing reset request"
+    );
+    let req = a
+        .reconfigs
+        .values()
+        .next()
+        .and_then(|c| c.param_a.as_ref())
+        .and_then(|p| p.as_any().downcast_ref::<ParamOutgoingResetRequest>())
+        .cloned()
+        .expect("should be an outgoing reset request");
+    assert_eq!(21, req.reconfig_response_sequence_number);
+    assert_eq!(vec![1], req.stream_identifiers);
+
+    Ok(())
+}
+
+fn create_pmtud_association_internal() -> AssociationInternal {
+    let mut a = AssociationInternal {
+        pmtud: Some(PathMtuDiscovery::new(INITIAL_MTU, DEFAULT_MAX_MTU)),
+        ..Default::default()
+    };
+    a.set_mtu(INITIAL_MTU);
+    a
+}
+
+async fn ack_pmtu_probe(a: &mut AssociationInternal) -> Result<()> {
+    let raw_packets = a.gather_outbound_pmtu_probe_packets(vec![]).await;
+    assert_eq!(1, raw_packets.len(), "should send a probe");
+    assert_eq!(
+        DEFAULT_MAX_MTU as usize,
+        raw_packets[0].len(),
+        "probe should be padded to the probed size"
+    );
+
+    let p = Packet::unmarshal(&raw_packets[0])?;
+    let heartbeat = p.chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkHeartbeat>()
+        .expect("should be a HEARTBEAT chunk");
+    let _ = a
+        .handle_heartbeat_ack(&ChunkHeartbeatAck {
+            params: heartbeat.params.clone(),
+        })
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_pmtu_probe_raises_mtu() -> Result<()> {
+    let mut a = create_pmtud_association_internal();
+    let notified = Arc::new(AtomicU32::new(0));
+    let notified2 = Arc::clone(&notified);
+    let f: OnPmtuChangeFn = Box::new(move |mtu: u32| {
+        notified2.store(mtu, Ordering::SeqCst);
+        Box::pin(async {})
+    });
+    a.on_pmtu_change.store(Some(Arc::new(Mutex::new(f))));
+
+    ack_pmtu_probe(&mut a).await?;
+    assert_eq!(DEFAULT_MAX_MTU, a.mtu, "mtu should be raised");
+    assert_eq!(
+        DEFAULT_MAX_MTU - COMMON_HEADER_SIZE - DATA_CHUNK_HEADER_SIZE,
+        a.max_payload_size.load(Ordering::SeqCst),
+        "streams should fragment into larger chunks"
+    );
+    assert_eq!(DEFAULT_MAX_MTU, notified.load(Ordering::SeqCst));
+
+    let raw_packets = a.gather_outbound_pmtu_probe_packets(vec![]).await;
+    assert!(raw_packets.is_empty(), "search should be complete");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_pmtu_black_hole() -> Result<()> {
+    let mut a = create_pmtud_association_internal();
+    ack_pmtu_probe(&mut a).await?;
+    assert_eq!(DEFAULT_MAX_MTU, a.mtu);
+
+    a.on_retransmission_timeout(RtxTimerId::T3RTX, 1).await;
+    assert_eq!(DEFAULT_MAX_MTU, a.mtu, "a single loss is not a black hole");
+
+    a.on_retransmission_timeout(RtxTimerId::T3RTX, BLACK_HOLE_RTOS)
+        .await;
+    assert_eq!(INITIAL_MTU, a.mtu, "should fall back to the initial MTU");
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_rto_config() -> Result<()> {
+    let a = create_association_internal(Config {
+        name: "client".to_owned(),
+        rto_initial: 100,
+        rto_min: 200,
+        rto_max: 1000,
+        sack_delay: 20,
+        fast_retransmit_threshold: 2,
+        max_burst: 4,
+        ..Config::new(Arc::new(DumbConn {}))
+    });
+    assert_eq!(200, a.rto_mgr.get_rto(), "RTO.Initial is capped at RTO.Min");
+    assert_eq!(200, a.rto_mgr.rto_min);
+    assert_eq!(1000, a.rto_mgr.rto_max);
+    assert_eq!(Duration::from_millis(20), a.sack_delay);
+    assert_eq!(2, a.fast_retransmit_threshold);
+    assert_eq!(4, a.max_burst);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_max_burst_limits_new_data() -> Result<()> {
+    let mut a = AssociationInternal {
+        max_message_size: Arc::new(AtomicU32::new(DEFAULT_MAX_MESSAGE_SIZE)),
+        cwnd: 100 * INITIAL_MTU,
+        rwnd: 100 * INITIAL_MTU,
+        max_burst: 2,
+        ..Default::default()
+    };
+    a.set_mtu(INITIAL_MTU);
+    a.set_state(AssociationState::Established);
+
+    let s = a.create_stream(1, false).expect("should create a stream");
+    s.write(&Bytes::from(vec![0u8; 10 * INITIAL_MTU as usize]))?;
+
+    let (chunks, _) = a.pop_pending_data_chunks_to_send();
+    assert_eq!(2, chunks.len(), "should send two packets of new data");
+
+    let (chunks, _) = a.pop_pending_data_chunks_to_send();
+    assert_eq!(
+        2,
+        chunks.len(),
+        "should send two more above the data in flight"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_stats_snapshot() -> Result<()> {
+    let mut a = AssociationInternal {
+        max_message_size: Arc::new(AtomicU32::new(DEFAULT_MAX_MESSAGE_SIZE)),
+        cwnd: 100 * INITIAL_MTU,
+        rwnd: 100 * INITIAL_MTU,
+        my_next_tsn: 1,
+        cumulative_tsn_ack_point: 0,
+        ..Default::default()
+    };
+    a.set_mtu(INITIAL_MTU);
+    a.set_state(AssociationState::Established);
+
+    let s = a.create_stream(1, false).expect("should create a stream");
+    s.write(&Bytes::from(vec![0u8; 3000]))?;
+    a.create_stream(0, false).expect("should create a stream");
+
+    let (chunks, _) = a.pop_pending_data_chunks_to_send();
+    assert_eq!(3, chunks.len());
+    a.stats.inc_retrans_chunks();
+
+    let _ = a
+        .handle_sack(&ChunkSelectiveAck {
+            cumulative_tsn_ack: chunks[0].tsn,
+            advertised_receiver_window_credit: 100 * INITIAL_MTU,
+            gap_ack_blocks: vec![GapAckBlock { start: 2, end: 2 }],
+            duplicate_tsn: vec![],
+        })
+        .await?;
+
+    let stats = a.stats_snapshot().await;
+    assert_eq!(a.cwnd, stats.cwnd);
+    assert_eq!(INITIAL_MTU, stats.mtu);
+    assert_eq!(1, stats.sacks_received);
+    assert_eq!(1, stats.gap_ack_blocks_received);
+    assert_eq!(1, stats.retransmitted_chunks);
+    assert_eq!(2, stats.chunks_in_flight, "the second chunk is missing");
+    assert_eq!(chunks[1].user_data.len(), stats.bytes_in_flight);
+    assert_eq!(
+        vec![
+            StreamStatsSnapshot {
+                stream_identifier: 0,
+                ..Default::default()
+            },
+            StreamStatsSnapshot {
+                stream_identifier: 1,
+                buffered_amount: chunks[1].user_data.len(),
+                bytes_in_reassembly_queue: 0,
+            },
+        ],
+        stats.streams
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_trace_chunks() -> Result<()> {
+    let mut a = AssociationInternal::default();
+    let traces = Arc::new(std::sync::Mutex::new(vec![]));
+    let traces2 = Arc::clone(&traces);
+    let f: OnChunkFn = Box::new(move |trace: ChunkTrace| {
+        traces2.lock().unwrap().push(trace);
+        Box::pin(async {})
+    });
+    a.on_chunk.store(Some(Arc::new(Mutex::new(f))));
+
+    a.control_queue.push_back(Packet {
+        source_port: 5000,
+        destination_port: 5000,
+        verification_tag: 0,
+        chunks: vec![
+            Box::new(ChunkSelectiveAck {
+                cumulative_tsn_ack: 7,
+                advertised_receiver_window_credit: 1024,
+                gap_ack_blocks: vec![],
+                duplicate_tsn: vec![],
+            }),
+            Box::new(ChunkPayloadData {
+                tsn: 8,
+                stream_identifier: 3,
+                beginning_fragment: true,
+                ending_fragment: true,
+                user_data: Bytes::from_static(b"x"),
+                ..Default::default()
+            }),
+        ],
+    });
+    let (raw_packets, _) = a.gather_outbound().await;
+    assert_eq!(1, raw_packets.len());
+    let _ = a.handle_inbound(&raw_packets[0]).await;
+
+    let traces = traces.lock().unwrap();
+    assert_eq!(4, traces.len(), "each chunk should be traced both ways");
+    for (trace, direction) in traces.iter().zip(&[
+        ChunkDirection::Outbound,
+        ChunkDirection::Outbound,
+        ChunkDirection::Inbound,
+        ChunkDirection::Inbound,
+    ]) {
+        assert_eq!(*direction, trace.direction);
+    }
+    assert_eq!("SACK", traces[0].chunk_name);
+    assert_eq!(Some(7), traces[0].tsn);
+    assert_eq!(None, traces[0].stream_identifier);
+    assert_eq!("DATA", traces[1].chunk_name);
+    assert_eq!(Some(8), traces[1].tsn);
+    assert_eq!(Some(3), traces[1].stream_identifier);
+    assert_eq!(17, traces[1].length, "header, DATA fields and user data");
+    assert_eq!(traces[1].chunk_type, traces[3].chunk_type);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_negotiate_num_streams() -> Result<()> {
+    let mut a = create_association_internal(Config {
+        name: "client".to_owned(),
+        max_num_outbound_streams: 10,
+        max_num_inbound_streams: 20,
+        ..Config::new(Arc::new(DumbConn {}))
+    });
+    assert_eq!(10, a.my_max_num_outbound_streams);
+    assert_eq!(20, a.my_max_num_inbound_streams);
+
+    a.negotiate_num_streams(&ChunkInit {
+        num_outbound_streams: 5,
+        num_inbound_streams: 100,
+        ..Default::default()
+    });
+    assert_eq!(10, a.my_max_num_outbound_streams, "peer accepts more");
+    assert_eq!(5, a.my_max_num_inbound_streams, "peer sends on fewer");
+
+    assert_eq!(
+        Err(Error::ErrStreamIdentifierOutOfRange),
+        a.open_stream(10, PayloadProtocolIdentifier::Binary)
+            .map(|_| ())
+    );
+    assert!(a.open_stream(9, PayloadProtocolIdentifier::Binary).is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_handle_data_on_invalid_stream() -> Result<()> {
+    let mut a = AssociationInternal {
+        my_max_num_inbound_streams: 1,
+        ..Default::default()
+    };
+
+    let packets = a
+        .handle_data(&ChunkPayloadData {
+            tsn: 1,
+            stream_identifier: 3,
+            beginning_fragment: true,
+            ending_fragment: true,
+            user_data: Bytes::from_static(b"x"),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(1, a.peer_last_tsn, "DATA should be acknowledged");
+    assert!(a.streams.is_empty(), "no stream should be created");
+
+    assert_eq!(1, packets.len());
+    let e = packets[0].chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkError>()
+        .expect("should be an ERROR chunk");
+    assert_eq!(INVALID_STREAM_IDENTIFIER, e.error_causes[0].code);
+    assert_eq!(&[0, 3, 0, 0], &e.error_causes[0].raw[..]);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_send_init_after_init_collision() -> Result<()> {
+    let mut a = AssociationInternal {
+        stored_init: Some(ChunkInit::default()),
+        // set by the peer's INIT received in COOKIE-WAIT
+        peer_verification_tag: 5678,
+        ..Default::default()
+    };
+
+    a.send_init()?;
+    assert_eq!(
+        Some(0),
+        a.control_queue.front().map(|p| p.verification_tag),
+        "INIT must have a zero verification tag"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_peer_restart() -> Result<()> {
+    let mut a = AssociationInternal {
+        my_max_num_outbound_streams: 10,
+        my_max_num_inbound_streams: 10,
+        my_verification_tag: 1111,
+        peer_verification_tag: 2222,
+        my_next_tsn: 100,
+        peer_last_tsn: 200,
+        source_port: 5000,
+        destination_port: 5000,
+        my_cookie: Some(ParamStateCookie::new()),
+        mtu: INITIAL_MTU,
+        ..Default::default()
+    };
+    a.set_state(AssociationState::Established);
+    let s = a.create_stream(1, false).unwrap();
+
+    let restarted = Arc::new(AtomicBool::new(false));
+    let restarted2 = Arc::clone(&restarted);
+    let f: OnRestartFn = Box::new(move || {
+        restarted2.store(true, Ordering::SeqCst);
+        Box::pin(async {})
+    });
+    a.on_restart.store(Some(Arc::new(Mutex::new(f))));
+
+    // the peer reboots and sends a new INIT
+    let pkt = Packet {
+        source_port: 5000,
+        destination_port: 5000,
+        ..Default::default()
+    };
+    let init = ChunkInit {
+        initial_tsn: 3000,
+        num_outbound_streams: 5,
+        num_inbound_streams: 5,
+        initiate_tag: 3333,
+        advertised_receiver_window_credit: 64 * 1024,
+        ..Default::default()
+    };
+    let packets = a.handle_init(&pkt, &init).await?;
+    assert_eq!(1, packets.len());
+    assert_eq!(3333, packets[0].verification_tag);
+    let init_ack = packets[0].chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkInit>()
+        .unwrap();
+    assert!(init_ack.is_ack);
+    assert_ne!(1111, init_ack.initiate_tag, "INIT ACK must carry a new tag");
+    let cookie = init_ack
+        .params
+        .iter()
+        .find_map(|p| p.as_any().downcast_ref::<ParamStateCookie>())
+        .unwrap()
+        .cookie
+        .clone();
+
+    // the association is left untouched until the cookie is echoed
+    assert_eq!(AssociationState::Established, a.get_state());
+    assert_eq!(2222, a.peer_verification_tag);
+    assert_eq!(1, a.streams.len());
+
+    let packets = a
+        .handle_cookie_echo(&ChunkCookieEcho {
+            cookie: Bytes::from_static(b"not the offered cookie"),
+        })
+        .await?;
+    assert!(packets.is_empty());
+    assert!(!restarted.load(Ordering::SeqCst));
+
+    let packets = a.handle_cookie_echo(&ChunkCookieEcho { cookie }).await?;
+    assert_eq!(1, packets.len());
+    assert_eq!(3333, packets[0].verification_tag);
+    assert!(packets[0].chunks[0].as_any().is::<ChunkCookieAck>());
+
+    assert_eq!(AssociationState::Established, a.get_state());
+    assert_eq!(init_ack.initiate_tag, a.my_verification_tag);
+    assert_eq!(init_ack.initial_tsn, a.my_next_tsn);
+    assert_eq!(3333, a.peer_verification_tag);
+    assert_eq!(2999, a.peer_last_tsn);
+    assert_eq!(5, a.my_max_num_outbound_streams);
+    assert_eq!(5, a.my_max_num_inbound_streams);
+
+    // the streams opened before the restart are closed
+    assert!(a.streams.is_empty());
+    assert!(s.read_shutdown.load(Ordering::SeqCst));
+    assert!(restarted.load(Ordering::SeqCst));
+
+    Ok(())
+}
@@ -7,6 +7,8 @@ pub(crate) struct AssociationStats {
     n_t3timeouts: AtomicU64,
     n_ack_timeouts: AtomicU64,
     n_fast_retrans: AtomicU64,
+    n_retrans_chunks: AtomicU64,
+    n_gap_ack_blocks: AtomicU64,
 }
 
 impl AssociationStats {
@@ -50,11 +52,79 @@ impl AssociationStats {
         self.n_fast_retrans.load(Ordering::SeqCst)
     }
 
+    pub(crate) fn inc_retrans_chunks(&self) {
+        self.n_retrans_chunks.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub(crate) fn get_num_retrans_chunks(&self) -> u64 {
+        self.n_retrans_chunks.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn add_gap_ack_blocks(&self, n: u64) {
+        self.n_gap_ack_blocks.fetch_add(n, Ordering::SeqCst);
+    }
+
+    pub(crate) fn get_num_gap_ack_blocks(&self) -> u64 {
+        self.n_gap_ack_blocks.load(Ordering::SeqCst)
+    }
+
     pub(crate) fn reset(&self) {
         self.n_datas.store(0, Ordering::SeqCst);
         self.n_sacks.store(0, Ordering::SeqCst);
         self.n_t3timeouts.store(0, Ordering::SeqCst);
         self.n_ack_timeouts.store(0, Ordering::SeqCst);
         self.n_fast_retrans.store(0, Ordering::SeqCst);
+        self.n_retrans_chunks.store(0, Ordering::SeqCst);
+        self.n_gap_ack_blocks.store(0, Ordering::SeqCst);
     }
 }
+
+/// AssociationStatsSnapshot is the state of an association's congestion
+/// control and counters at the time it was taken.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct AssociationStatsSnapshot {
+    /// congestion window in bytes
+    pub cwnd: u32,
+    /// slow start threshold in bytes
+    pub ssthresh: u32,
+    /// receiver window of the peer in bytes
+    pub rwnd: u32,
+    /// size of the outgoing SCTP packets
+    pub mtu: u32,
+    /// smoothed round-trip time in msec
+    pub srtt: u64,
+    /// round-trip time variation in msec
+    pub rttvar: f64,
+    /// retransmission timeout in msec
+    pub rto: u64,
+    /// bytes of DATA chunks sent and not yet acknowledged
+    pub bytes_in_flight: usize,
+    /// DATA chunks sent and not yet acknowledged
+    pub chunks_in_flight: usize,
+    /// bytes of DATA chunks waiting to be sent
+    pub bytes_pending: usize,
+    pub bytes_sent: usize,
+    pub bytes_received: usize,
+    pub data_chunks_received: u64,
+    pub sacks_received: u64,
+    /// gap ack blocks in the received SACKs, each reporting lost or
+    /// reordered DATA chunks
+    pub gap_ack_blocks_received: u64,
+    /// DATA chunks retransmitted, after a T3-rtx timeout or fast
+    pub retransmitted_chunks: u64,
+    pub fast_retransmissions: u64,
+    pub t3_timeouts: u64,
+    pub ack_timeouts: u64,
+    /// streams of the association, ordered by identifier
+    pub streams: Vec<StreamStatsSnapshot>,
+}
+
+/// StreamStatsSnapshot is the state of a stream at the time it was taken.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct StreamStatsSnapshot {
+    pub stream_identifier: u16,
+    /// bytes written and not yet acknowledged
+    pub buffered_amount: usize,
+    /// bytes received and not yet read
+    pub bytes_in_reassembly_queue: usize,
+}
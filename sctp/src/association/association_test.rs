@@ -28,10 +28,9 @@ async fn create_new_association_pair(
     // Setup client
     tokio::spawn(async move {
         let client = Association::client(Config {
-            net_conn: ca,
             max_receive_buffer_size: recv_buf_size,
-            max_message_size: 0,
             name: "client".to_owned(),
+            ..Config::new(ca)
         })
         .await;
 
@@ -44,10 +43,9 @@ async fn create_new_association_pair(
     // Setup server
     tokio::spawn(async move {
         let server = Association::server(Config {
-            net_conn: cb,
             max_receive_buffer_size: recv_buf_size,
-            max_message_size: 0,
             name: "server".to_owned(),
+            ..Config::new(cb)
         })
         .await;
 
@@ -862,6 +860,14 @@ async fn test_assoc_unreliable_rexmit_ordered_fragment() -> Result<()> {
     s0.set_reliability_params(false, ReliabilityType::Rexmit, 0);
     s1.set_reliability_params(false, ReliabilityType::Rexmit, 0); // doesn't matter
 
+    let n_abandoned = Arc::new(AtomicUsize::new(0));
+    let n_abandoned2 = Arc::clone(&n_abandoned);
+    s0.on_abandoned(Box::new(move |ppi: PayloadProtocolIdentifier| {
+        assert_eq!(ppi, PayloadProtocolIdentifier::Binary, "unexpected ppi");
+        n_abandoned2.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async {})
+    }));
+
     br.drop_next_nwrites(0, 1); // drop the first packet (second one should be sacked)
 
     sbuf[0..4].copy_from_slice(&0u32.to_be_bytes());
@@ -901,6 +907,12 @@ async fn test_assoc_unreliable_rexmit_ordered_fragment() -> Result<()> {
         assert!(!q.is_readable(), "should no longer be readable");
     }
 
+    assert_eq!(
+        1,
+        n_abandoned.load(Ordering::SeqCst),
+        "the first message should be abandoned"
+    );
+
     close_association_pair(&br, a0, a1).await;
 
     Ok(())
@@ -2146,10 +2158,8 @@ async fn test_stats() -> Result<()> {
 
     let conn = Arc::new(FakeEchoConn::new());
     let a = Association::client(Config {
-        net_conn: Arc::clone(&conn) as Arc<dyn Conn + Send + Sync>,
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::clone(&conn) as Arc<dyn Conn + Send + Sync>)
     })
     .await?;
 
@@ -2181,10 +2191,8 @@ async fn create_assocs() -> Result<(Association, Association)> {
 
     tokio::spawn(async move {
         let a = Association::client(Config {
-            net_conn: Arc::new(udp1),
-            max_receive_buffer_size: 0,
-            max_message_size: 0,
             name: "client".to_owned(),
+            ..Config::new(Arc::new(udp1))
         })
         .await?;
 
@@ -2195,10 +2203,8 @@ async fn create_assocs() -> Result<(Association, Association)> {
 
     tokio::spawn(async move {
         let a = Association::server(Config {
-            net_conn: Arc::new(udp2),
-            max_receive_buffer_size: 0,
-            max_message_size: 0,
             name: "server".to_owned(),
+            ..Config::new(Arc::new(udp2))
         })
         .await?;
 
@@ -2541,10 +2547,8 @@ async fn test_association_handle_packet_before_init() -> Result<()> {
 
         let (a, _) = Association::new(
             Config {
-                net_conn: Arc::new(a_conn),
-                max_message_size: 0,
-                max_receive_buffer_size: 0,
                 name: "client".to_owned(),
+                ..Config::new(Arc::new(a_conn))
             },
             true,
         )
@@ -2563,3 +2567,78 @@ async fn test_association_handle_packet_before_init() -> Result<()> {
 
     Ok(())
 }
+
+fn create_handshake_config(
+    net_conn: Arc<dyn Conn + Send + Sync>,
+    rto_initial: u64,
+    max_init_retransmits: u32,
+    handshake_timeout: u64,
+) -> Config {
+    Config {
+        name: "client".to_owned(),
+        rto_initial,
+        max_init_retransmits,
+        handshake_timeout,
+        ..Config::new(net_conn)
+    }
+}
+
+#[tokio::test]
+async fn test_association_handshake_timeout() -> Result<()> {
+    let (a_conn, _charlie_conn) = pipe();
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(2),
+        Association::client(create_handshake_config(Arc::new(a_conn), 0, 0, 200)),
+    )
+    .await
+    .expect("the handshake should time out before");
+    assert_eq!(Some(Error::ErrHandshakeTimeout), result.err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_association_handshake_init_retransmits() -> Result<()> {
+    let (a_conn, _charlie_conn) = pipe();
+
+    // INIT is sent at 0, retransmitted at 1000 and the handshake fails at 3000
+    let result = tokio::time::timeout(
+        Duration::from_secs(5),
+        Association::client(create_handshake_config(Arc::new(a_conn), 1000, 1, 0)),
+    )
+    .await
+    .expect("the handshake should fail before");
+    assert_eq!(Some(Error::ErrHandshakeInitAck), result.err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_association_handshake_aborted() -> Result<()> {
+    let (a_conn, charlie_conn) = pipe();
+
+    let client = tokio::spawn(async move {
+        Association::client(create_handshake_config(Arc::new(a_conn), 0, 0, 0)).await
+    });
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let abort = Packet {
+        source_port: 5000,
+        destination_port: 5000,
+        verification_tag: 0,
+        chunks: vec![Box::new(ChunkAbort {
+            error_causes: vec![],
+        })],
+    };
+    let result = charlie_conn.send(&abort.marshal()?).await;
+    assert!(result.is_ok(), "charlie_conn.send should be ok");
+
+    let result = tokio::time::timeout(Duration::from_secs(2), client)
+        .await
+        .expect("the handshake should fail before")
+        .unwrap();
+    assert_eq!(Some(Error::ErrHandshakeAborted), result.err());
+
+    Ok(())
+}
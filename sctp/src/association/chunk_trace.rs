@@ -0,0 +1,89 @@
+use crate::chunk::chunk_cwr::ChunkCwr;
+use crate::chunk::chunk_ecne::ChunkEcne;
+use crate::chunk::chunk_forward_tsn::ChunkForwardTsn;
+use crate::chunk::chunk_header::CHUNK_HEADER_SIZE;
+use crate::chunk::chunk_iforward_tsn::ChunkIForwardTsn;
+use crate::chunk::chunk_payload_data::ChunkPayloadData;
+use crate::chunk::chunk_selective_ack::ChunkSelectiveAck;
+use crate::chunk::chunk_shutdown::ChunkShutdown;
+use crate::chunk::Chunk;
+
+use std::fmt;
+use std::time::SystemTime;
+
+/// ChunkDirection tells whether a chunk was received or sent.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChunkDirection {
+    Inbound,
+    Outbound,
+}
+
+impl fmt::Display for ChunkDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            ChunkDirection::Inbound => "inbound",
+            ChunkDirection::Outbound => "outbound",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// ChunkTrace describes a chunk received or sent by an association.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkTrace {
+    pub direction: ChunkDirection,
+    /// chunk type as in the chunk header
+    pub chunk_type: u8,
+    /// name of the chunk type, e.g. "DATA" or "SACK"
+    pub chunk_name: String,
+    pub flags: u8,
+    /// length of the chunk, header included
+    pub length: usize,
+    /// TSN of a DATA chunk, or the TSN acknowledged or reported by a SACK,
+    /// SHUTDOWN, FORWARD-TSN, ECNE or CWR chunk
+    pub tsn: Option<u32>,
+    /// stream of a DATA chunk
+    pub stream_identifier: Option<u16>,
+    /// time the packet carrying the chunk was received or gathered to be sent
+    pub timestamp: SystemTime,
+}
+
+impl ChunkTrace {
+    pub(crate) fn new(
+        direction: ChunkDirection,
+        c: &(dyn Chunk + Send + Sync),
+        timestamp: SystemTime,
+    ) -> Self {
+        let header = c.header();
+        let any = c.as_any();
+
+        let (tsn, stream_identifier) = if let Some(c) = any.downcast_ref::<ChunkPayloadData>() {
+            (Some(c.tsn), Some(c.stream_identifier))
+        } else if let Some(c) = any.downcast_ref::<ChunkSelectiveAck>() {
+            (Some(c.cumulative_tsn_ack), None)
+        } else if let Some(c) = any.downcast_ref::<ChunkShutdown>() {
+            (Some(c.cumulative_tsn_ack), None)
+        } else if let Some(c) = any.downcast_ref::<ChunkForwardTsn>() {
+            (Some(c.new_cumulative_tsn), None)
+        } else if let Some(c) = any.downcast_ref::<ChunkIForwardTsn>() {
+            (Some(c.new_cumulative_tsn), None)
+        } else if let Some(c) = any.downcast_ref::<ChunkEcne>() {
+            (Some(c.lowest_tsn), None)
+        } else if let Some(c) = any.downcast_ref::<ChunkCwr>() {
+            (Some(c.lowest_tsn), None)
+        } else {
+            (None, None)
+        };
+
+        ChunkTrace {
+            direction,
+            chunk_type: header.typ.0,
+            chunk_name: header.typ.to_string(),
+            flags: header.flags,
+            length: CHUNK_HEADER_SIZE + c.value_length(),
+            tsn,
+            stream_identifier,
+            timestamp,
+        }
+    }
+}
@@ -3,15 +3,21 @@ mod association_test;
 
 mod association_internal;
 mod association_stats;
+mod chunk_trace;
+mod pmtud;
+pub(crate) mod send_buffer;
 
 use crate::chunk::chunk_abort::ChunkAbort;
 use crate::chunk::chunk_cookie_ack::ChunkCookieAck;
 use crate::chunk::chunk_cookie_echo::ChunkCookieEcho;
 use crate::chunk::chunk_error::ChunkError;
 use crate::chunk::chunk_forward_tsn::{ChunkForwardTsn, ChunkForwardTsnStream};
+use crate::chunk::chunk_header::CHUNK_HEADER_SIZE;
 use crate::chunk::chunk_heartbeat::ChunkHeartbeat;
 use crate::chunk::chunk_heartbeat_ack::ChunkHeartbeatAck;
+use crate::chunk::chunk_iforward_tsn::{ChunkIForwardTsn, ChunkIForwardTsnStream};
 use crate::chunk::chunk_init::ChunkInit;
+use crate::chunk::chunk_padding::ChunkPadding;
 use crate::chunk::chunk_payload_data::{ChunkPayloadData, PayloadProtocolIdentifier};
 use crate::chunk::chunk_reconfig::ChunkReconfig;
 use crate::chunk::chunk_selective_ack::ChunkSelectiveAck;
@@ -20,10 +26,13 @@ use crate::chunk::chunk_shutdown_ack::ChunkShutdownAck;
 use crate::chunk::chunk_shutdown_complete::ChunkShutdownComplete;
 use crate::chunk::chunk_type::*;
 use crate::chunk::Chunk;
+use crate::congestion::{Ack, CongestionControl, CongestionController, CongestionWindow};
 use crate::error::{Error, Result};
 use crate::error_cause::*;
 use crate::packet::Packet;
+use crate::param::param_add_streams_request::ParamAddStreamsRequest;
 use crate::param::param_heartbeat_info::ParamHeartbeatInfo;
+use crate::param::param_incoming_reset_request::ParamIncomingResetRequest;
 use crate::param::param_outgoing_reset_request::ParamOutgoingResetRequest;
 use crate::param::param_reconfig_response::{ParamReconfigResponse, ReconfigResult};
 use crate::param::param_state_cookie::ParamStateCookie;
@@ -39,28 +48,50 @@ use crate::util::*;
 
 use association_internal::*;
 use association_stats::*;
+pub use association_stats::{AssociationStatsSnapshot, StreamStatsSnapshot};
+pub use chunk_trace::{ChunkDirection, ChunkTrace};
+use pmtud::*;
+use send_buffer::*;
 
-use bytes::Bytes;
+use arc_swap::ArcSwapOption;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use rand::random;
 use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::{broadcast, mpsc, Mutex};
 use util::Conn;
 
 pub(crate) const RECEIVE_MTU: usize = 8192;
+/// Size of the buffer the packets are received into. The chunks parsed from a
+/// packet share its memory, so a message kept alive pins the whole buffer:
+/// it is only large enough to receive a few packets before being replaced.
+pub(crate) const RECEIVE_BUFFER_SIZE: usize = 2 * RECEIVE_MTU;
+/// Packets shorter than this are copied out of the receive buffer instead, so
+/// that a small message doesn't pin a buffer many times its size.
+pub(crate) const RECEIVE_COPY_THRESHOLD: usize = RECEIVE_BUFFER_SIZE / 32;
 /// MTU for inbound packet (from DTLS)
 pub(crate) const INITIAL_MTU: u32 = 1228;
 /// initial MTU for outgoing packets (to DTLS)
 pub(crate) const INITIAL_RECV_BUF_SIZE: u32 = 1024 * 1024;
 pub(crate) const COMMON_HEADER_SIZE: u32 = 12;
 pub(crate) const DATA_CHUNK_HEADER_SIZE: u32 = 16;
+pub(crate) const IDATA_CHUNK_HEADER_SIZE: u32 = 20;
 pub(crate) const DEFAULT_MAX_MESSAGE_SIZE: u32 = 65536;
+pub(crate) const DEFAULT_MAX_SEND_BUFFER_SIZE: u32 = 1024 * 1024;
+/// number of SACKs reporting a DATA chunk missing before it is fast
+/// retransmitted (RFC 4960 sec 7.2.4)
+pub(crate) const FAST_RETRANSMIT_THRESHOLD: u32 = 3;
 
 /// other constants
 pub(crate) const ACCEPT_CH_SIZE: usize = 16;
+/// number of the peer's latest re-configuration requests whose result is kept
+/// to answer their retransmissions
+pub(crate) const RECONFIG_RESULT_HISTORY: u32 = 16;
 
 /// association state enums
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -114,6 +145,7 @@ pub(crate) enum RtxTimerId {
     T2Shutdown,
     T3RTX,
     Reconfig,
+    Pmtud,
 }
 
 impl Default for RtxTimerId {
@@ -130,6 +162,7 @@ impl fmt::Display for RtxTimerId {
             RtxTimerId::T2Shutdown => "T2Shutdown",
             RtxTimerId::T3RTX => "T3RTX",
             RtxTimerId::Reconfig => "Reconfig",
+            RtxTimerId::Pmtud => "Pmtud",
         };
         write!(f, "{}", s)
     }
@@ -191,8 +224,95 @@ pub struct Config {
     pub max_receive_buffer_size: u32,
     pub max_message_size: u32,
     pub name: String,
+    /// enable_message_interleaving offers user message interleaving (RFC 8260)
+    /// to the peer, which is used if the peer supports it too.
+    pub enable_message_interleaving: bool,
+    /// stream_scheduler decides which stream the association sends from next.
+    pub stream_scheduler: StreamScheduler,
+    /// congestion_control is the algorithm adjusting the congestion window.
+    pub congestion_control: CongestionControl,
+    /// enable_pmtud probes the path for the largest packet size it delivers
+    /// (RFC 8899), instead of keeping the packets at the initial size of 1228 bytes.
+    pub enable_pmtud: bool,
+    /// max_mtu is the largest SCTP packet size probed for, 1400 bytes if 0.
+    pub max_mtu: u32,
+    /// max_send_buffer_size is the number of bytes written to the streams and
+    /// not yet acknowledged above which the association is not writable,
+    /// 1 MiB if 0. The congestion window is used instead when it is larger.
+    pub max_send_buffer_size: u32,
+    /// rto_initial is the initial retransmission timeout in msec, 3000 if 0.
+    pub rto_initial: u64,
+    /// rto_min is the lower bound of the retransmission timeout in msec, 1000 if 0.
+    pub rto_min: u64,
+    /// rto_max is the upper bound of the retransmission timeout in msec, also
+    /// capping its back off, 60000 if 0.
+    pub rto_max: u64,
+    /// sack_delay is the time in msec a SACK is delayed for, waiting for more
+    /// DATA chunks to acknowledge, 200 if 0.
+    pub sack_delay: u64,
+    /// fast_retransmit_threshold is the number of SACKs reporting a DATA chunk
+    /// missing after which it is fast retransmitted, 3 if 0.
+    pub fast_retransmit_threshold: u32,
+    /// max_burst limits the new DATA sent at once to max_burst packets above
+    /// the data in flight (Max.Burst, RFC 4960 sec 6.1), no limit if 0.
+    pub max_burst: u32,
+    /// max_init_retransmits is the number of times INIT and COOKIE-ECHO chunks
+    /// are retransmitted before the handshake fails, 8 if 0. They are first
+    /// retransmitted after rto_initial, then after twice as long each time,
+    /// up to rto_max.
+    pub max_init_retransmits: u32,
+    /// handshake_timeout is the time in msec the association has to be
+    /// established, after which the handshake fails with
+    /// Error::ErrHandshakeTimeout. There is no deadline if 0.
+    pub handshake_timeout: u64,
+    /// max_num_outbound_streams is the number of outbound streams (OS) offered
+    /// to the peer, 65535 if 0. The peer may accept fewer.
+    pub max_num_outbound_streams: u16,
+    /// max_num_inbound_streams is the number of inbound streams (MIS) accepted
+    /// from the peer, 65535 if 0. DATA on the other streams is discarded.
+    pub max_num_inbound_streams: u16,
 }
 
+impl Config {
+    /// new returns a Config of an association over net_conn with the default
+    /// of every other setting, to be overridden with the struct update syntax:
+    /// `Config { name: "client".to_owned(), ..Config::new(net_conn) }`.
+    pub fn new(net_conn: Arc<dyn Conn + Send + Sync>) -> Self {
+        Config {
+            net_conn,
+            max_receive_buffer_size: 0,
+            max_message_size: 0,
+            name: String::new(),
+            enable_message_interleaving: false,
+            stream_scheduler: StreamScheduler::default(),
+            congestion_control: CongestionControl::default(),
+            enable_pmtud: false,
+            max_mtu: 0,
+            max_send_buffer_size: 0,
+            rto_initial: 0,
+            rto_min: 0,
+            rto_max: 0,
+            sack_delay: 0,
+            fast_retransmit_threshold: 0,
+            max_burst: 0,
+            max_init_retransmits: 0,
+            handshake_timeout: 0,
+            max_num_outbound_streams: 0,
+            max_num_inbound_streams: 0,
+        }
+    }
+}
+
+pub type OnPmtuChangeFn =
+    Box<dyn (FnMut(u32) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
+
+pub type OnChunkFn = Box<
+    dyn (FnMut(ChunkTrace) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync,
+>;
+
+pub type OnRestartFn =
+    Box<dyn (FnMut() -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
+
 ///Association represents an SCTP association
 ///13.2.  Parameters Necessary per Association (i.e., the TCB)
 ///Peer : Tag value to be sent in every packet and is received
@@ -222,6 +342,10 @@ pub struct Association {
     net_conn: Arc<dyn Conn + Send + Sync>,
     bytes_received: Arc<AtomicUsize>,
     bytes_sent: Arc<AtomicUsize>,
+    on_pmtu_change: Arc<ArcSwapOption<Mutex<OnPmtuChangeFn>>>,
+    on_chunk: Arc<ArcSwapOption<Mutex<OnChunkFn>>>,
+    on_restart: Arc<ArcSwapOption<Mutex<OnRestartFn>>>,
+    send_buffer: Arc<SendBuffer>,
 
     pub(crate) association_internal: Arc<Mutex<AssociationInternal>>,
 }
@@ -229,32 +353,55 @@ pub struct Association {
 impl Association {
     /// server accepts a SCTP stream over a conn
     pub async fn server(config: Config) -> Result<Self> {
-        let (a, mut handshake_completed_ch_rx) = Association::new(config, false).await?;
+        let handshake_timeout = config.handshake_timeout;
+        let (a, handshake_completed_ch_rx) = Association::new(config, false).await?;
 
-        if let Some(err_opt) = handshake_completed_ch_rx.recv().await {
-            if let Some(err) = err_opt {
-                Err(err)
-            } else {
-                Ok(a)
-            }
-        } else {
-            Err(Error::ErrAssociationHandshakeClosed)
-        }
+        a.wait_for_handshake(handshake_completed_ch_rx, handshake_timeout)
+            .await
     }
 
     /// Client opens a SCTP stream over a conn
     pub async fn client(config: Config) -> Result<Self> {
-        let (a, mut handshake_completed_ch_rx) = Association::new(config, true).await?;
+        let handshake_timeout = config.handshake_timeout;
+        let (a, handshake_completed_ch_rx) = Association::new(config, true).await?;
 
-        if let Some(err_opt) = handshake_completed_ch_rx.recv().await {
-            if let Some(err) = err_opt {
-                Err(err)
-            } else {
-                Ok(a)
-            }
+        a.wait_for_handshake(handshake_completed_ch_rx, handshake_timeout)
+            .await
+    }
+
+    /// wait_for_handshake waits for the association to be established. The
+    /// association is closed if the handshake fails, or doesn't complete
+    /// within handshake_timeout msec when it isn't 0.
+    async fn wait_for_handshake(
+        self,
+        mut handshake_completed_ch_rx: mpsc::Receiver<Option<Error>>,
+        handshake_timeout: u64,
+    ) -> Result<Self> {
+        let result = if handshake_timeout == 0 {
+            handshake_completed_ch_rx.recv().await
         } else {
-            Err(Error::ErrAssociationHandshakeClosed)
+            let timeout = Duration::from_millis(handshake_timeout);
+            match tokio::time::timeout(timeout, handshake_completed_ch_rx.recv()).await {
+                Ok(result) => result,
+                Err(_) => Some(Some(Error::ErrHandshakeTimeout)),
+            }
+        };
+
+        let err = match result {
+            Some(None) => return Ok(self),
+            Some(Some(err)) => err,
+            None => Error::ErrAssociationHandshakeClosed,
+        };
+
+        log::debug!("[{}] handshake failed: {}", self.name, err);
+        {
+            let mut ai = self.association_internal.lock().await;
+            if let Err(err) = ai.close().await {
+                log::warn!("[{}] failed to close association: {:?}", self.name, err);
+            }
         }
+
+        Err(err)
     }
 
     /// Shutdown initiates the shutdown sequence. The method blocks until the
@@ -322,6 +469,10 @@ impl Association {
         let max_message_size = Arc::clone(&ai.max_message_size);
         let inflight_queue_length = Arc::clone(&ai.inflight_queue_length);
         let will_send_shutdown = Arc::clone(&ai.will_send_shutdown);
+        let on_pmtu_change = Arc::clone(&ai.on_pmtu_change);
+        let on_chunk = Arc::clone(&ai.on_chunk);
+        let on_restart = Arc::clone(&ai.on_restart);
+        let send_buffer = Arc::clone(&ai.send_buffer);
 
         let mut init = ChunkInit {
             initial_tsn: ai.my_next_tsn,
@@ -331,7 +482,7 @@ impl Association {
             advertised_receiver_window_credit: ai.max_receive_buffer_size,
             ..Default::default()
         };
-        init.set_supported_extensions();
+        init.set_supported_extensions(ai.enable_message_interleaving);
 
         let name1 = name.clone();
         let name2 = name.clone();
@@ -350,34 +501,48 @@ impl Association {
             let association_internal3 = Arc::clone(&association_internal);
 
             let mut ai = association_internal.lock().await;
+            let rto_max = ai.rto_mgr.rto_max;
+            let sack_delay = ai.sack_delay;
+            let max_init_retransmits = ai.max_init_retransmits;
             ai.t1init = Some(RtxTimer::new(
                 Arc::downgrade(&association_internal3),
                 RtxTimerId::T1Init,
-                MAX_INIT_RETRANS,
+                max_init_retransmits,
+                rto_max,
             ));
             ai.t1cookie = Some(RtxTimer::new(
                 Arc::downgrade(&association_internal3),
                 RtxTimerId::T1Cookie,
-                MAX_INIT_RETRANS,
+                max_init_retransmits,
+                rto_max,
             ));
             ai.t2shutdown = Some(RtxTimer::new(
                 Arc::downgrade(&association_internal3),
                 RtxTimerId::T2Shutdown,
                 NO_MAX_RETRANS,
+                rto_max,
             )); // retransmit forever
             ai.t3rtx = Some(RtxTimer::new(
                 Arc::downgrade(&association_internal3),
                 RtxTimerId::T3RTX,
                 NO_MAX_RETRANS,
+                rto_max,
             )); // retransmit forever
             ai.treconfig = Some(RtxTimer::new(
                 Arc::downgrade(&association_internal3),
                 RtxTimerId::Reconfig,
                 NO_MAX_RETRANS,
+                rto_max,
             )); // retransmit forever
+            ai.tpmtud = Some(RtxTimer::new(
+                Arc::downgrade(&association_internal3),
+                RtxTimerId::Pmtud,
+                NO_MAX_RETRANS,
+                rto_max,
+            )); // restarted for every probe
             ai.ack_timer = Some(AckTimer::new(
                 Arc::downgrade(&association_internal3),
-                ACK_INTERVAL,
+                sack_delay,
             ));
         }
 
@@ -428,6 +593,10 @@ impl Association {
                 net_conn,
                 bytes_received,
                 bytes_sent,
+                on_pmtu_change,
+                on_chunk,
+                on_restart,
+                send_buffer,
                 association_internal,
             },
             handshake_completed_ch_rx,
@@ -443,13 +612,21 @@ impl Association {
     ) {
         log::debug!("[{}] read_loop entered", name);
 
-        let mut buffer = vec![0u8; RECEIVE_MTU];
+        let mut buffer = BytesMut::new();
         let mut done = false;
         let mut n;
         while !done {
+            if buffer.len() < RECEIVE_MTU {
+                // Reuses the memory of the buffer if the packets split off it
+                // were all dropped, or allocates a new one.
+                buffer.clear();
+                buffer.reserve(RECEIVE_BUFFER_SIZE);
+                buffer.resize(RECEIVE_BUFFER_SIZE, 0);
+            }
+
             tokio::select! {
                 _ = close_loop_ch.recv() => break,
-                result = net_conn.recv(&mut buffer) => {
+                result = net_conn.recv(&mut buffer[..RECEIVE_MTU]) => {
                     match result {
                         Ok(m) => {
                             n=m;
@@ -462,12 +639,16 @@ impl Association {
                 }
             };
 
-            // Make a buffer sized to what we read, then copy the data we
-            // read from the underlying transport. We do this because the
-            // user data is passed to the reassembly queue without
-            // copying.
+            // Split what we read off the buffer without copying it, unless
+            // it is small. The user data is passed to the reassembly queue as
+            // slices of it, so that part of the buffer is not reused until
+            // they are all dropped.
             log::debug!("[{}] recving {} bytes", name, n);
-            let inbound = Bytes::from(buffer[..n].to_vec());
+            let inbound = if n < RECEIVE_COPY_THRESHOLD {
+                Bytes::copy_from_slice(&buffer[..n])
+            } else {
+                buffer.split_to(n).freeze()
+            };
             bytes_received.fetch_add(n, Ordering::SeqCst);
 
             {
@@ -563,6 +744,99 @@ impl Association {
         ai.open_stream(stream_identifier, default_payload_type)
     }
 
+    /// reset_streams resets the outgoing side of the given streams (RFC 6525).
+    /// Each stream is reset once the data queued on it before has been sent.
+    pub async fn reset_streams(&self, stream_identifiers: &[u16]) -> Result<()> {
+        let mut ai = self.association_internal.lock().await;
+        for stream_identifier in stream_identifiers {
+            ai.send_reset_request(*stream_identifier)?;
+        }
+        Ok(())
+    }
+
+    /// add_streams asks the peer to add outbound and inbound streams to the
+    /// association (RFC 6525). The stream counts are raised once the peer has
+    /// accepted the request.
+    pub async fn add_streams(&self, num_outbound: u16, num_inbound: u16) -> Result<()> {
+        let mut ai = self.association_internal.lock().await;
+        ai.send_add_streams_request(num_outbound, num_inbound).await
+    }
+
+    /// num_outbound_streams returns the number of outbound streams of the association,
+    /// as negotiated with the peer once the association is established.
+    pub async fn num_outbound_streams(&self) -> u16 {
+        let ai = self.association_internal.lock().await;
+        ai.my_max_num_outbound_streams
+    }
+
+    /// num_inbound_streams returns the number of inbound streams of the association,
+    /// as negotiated with the peer once the association is established.
+    pub async fn num_inbound_streams(&self) -> u16 {
+        let ai = self.association_internal.lock().await;
+        ai.my_max_num_inbound_streams
+    }
+
+    /// path_mtu returns the size of the outgoing SCTP packets.
+    pub async fn path_mtu(&self) -> u32 {
+        let ai = self.association_internal.lock().await;
+        ai.mtu
+    }
+
+    /// on_pmtu_change sets the callback handler which would be called with the new
+    /// size of the outgoing SCTP packets when path MTU discovery changes it.
+    pub fn on_pmtu_change(&self, f: OnPmtuChangeFn) {
+        self.on_pmtu_change.store(Some(Arc::new(Mutex::new(f))));
+    }
+
+    /// on_chunk sets the callback handler which would be called with every chunk
+    /// received or sent by the association, to trace the protocol exchanges.
+    /// The handler should return quickly as the association waits for it.
+    pub fn on_chunk(&self, f: OnChunkFn) {
+        self.on_chunk.store(Some(Arc::new(Mutex::new(f))));
+    }
+
+    /// on_restart sets the callback handler which would be called when the peer
+    /// restarts the association. The streams opened before the restart are
+    /// closed, and the peer opens them again as new streams.
+    pub fn on_restart(&self, f: OnRestartFn) {
+        self.on_restart.store(Some(Arc::new(Mutex::new(f))));
+    }
+
+    /// stats returns a snapshot of the congestion control state, the counters and
+    /// the streams of the association.
+    pub async fn stats(&self) -> AssociationStatsSnapshot {
+        let ai = self.association_internal.lock().await;
+        AssociationStatsSnapshot {
+            bytes_sent: self.bytes_sent(),
+            bytes_received: self.bytes_received(),
+            ..ai.stats_snapshot().await
+        }
+    }
+
+    /// is_writable tells whether the bytes written to the streams of the
+    /// association and not yet acknowledged fit in its send buffer.
+    pub fn is_writable(&self) -> bool {
+        self.send_buffer.is_writable()
+    }
+
+    /// writable waits until there is room in the send buffer of the association
+    /// for more data to be written to its streams. Returns an error if the
+    /// association is not established or gets closed.
+    pub async fn writable(&self) -> Result<()> {
+        loop {
+            let notified = self.send_buffer.notified();
+            if AssociationState::from(self.state.load(Ordering::SeqCst))
+                != AssociationState::Established
+            {
+                return Err(Error::ErrPayloadDataStateNotExist);
+            }
+            if self.send_buffer.is_writable() {
+                return Ok(());
+            }
+            notified.await;
+        }
+    }
+
     /// accept_stream accepts a stream
     pub async fn accept_stream(&self) -> Option<Arc<Stream>> {
         let mut accept_ch_rx = self.accept_ch_rx.lock().await;
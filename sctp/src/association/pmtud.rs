@@ -0,0 +1,207 @@
+#[cfg(test)]
+mod pmtud_test;
+
+use std::time::{Duration, Instant};
+
+/// max_probes is the number of times a probe of a given size is sent before
+/// the path is considered not to deliver packets of that size. (RFC 8899 sec 5.1.2)
+pub(crate) const MAX_PROBES: usize = 3;
+/// The search stops once the largest confirmed size is within this many bytes
+/// of the smallest size which did not get through.
+pub(crate) const PROBE_GRANULARITY: u32 = 16;
+/// Interval after which a completed search is started again to detect a
+/// larger path MTU. (PMTU_RAISE_TIMER, RFC 8899 sec 5.1.1)
+pub(crate) const PMTU_RAISE_INTERVAL: Duration = Duration::from_secs(600);
+/// Number of consecutive T3-rtx expirations after which the path is suspected
+/// to have become a black hole for packets of the current size.
+pub(crate) const BLACK_HOLE_RTOS: usize = 2;
+/// Length of the Heartbeat Information of a probe: its size and sequence number.
+pub(crate) const PROBE_INFO_LENGTH: usize = 8;
+/// Default largest SCTP packet probed for. It fits an Ethernet path with room
+/// for the IPv6, UDP and DTLS headers.
+pub(crate) const DEFAULT_MAX_MTU: u32 = 1400;
+
+/// ProbeState is the state of the path MTU search.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum ProbeState {
+    /// Probing for a larger size
+    Searching,
+    /// The largest size the path delivers has been found
+    SearchComplete,
+}
+
+impl Default for ProbeState {
+    fn default() -> Self {
+        ProbeState::Searching
+    }
+}
+
+/// Probe is a probe packet to be sent.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Probe {
+    /// size of the whole SCTP packet
+    pub(crate) size: u32,
+    /// identifies the probe in the HEARTBEAT ACK
+    pub(crate) sequence: u32,
+}
+
+/// PathMtuDiscovery implements Datagram Packetization Layer Path MTU Discovery
+/// (RFC 8899) for an association. Starting from a size every path is expected
+/// to deliver, it sends HEARTBEAT chunks padded to larger sizes, and raises the
+/// MTU of the association to the largest size acknowledged by the peer.
+#[derive(Default, Debug)]
+pub(crate) struct PathMtuDiscovery {
+    state: ProbeState,
+    base_mtu: u32,
+    max_mtu: u32,
+    // largest size confirmed to be delivered
+    mtu: u32,
+    // smallest size known not to be delivered, max_mtu+1 if none
+    search_high: u32,
+    probe: Option<Probe>,
+    probe_sent: bool,
+    probe_count: usize,
+    next_sequence: u32,
+    search_completed_at: Option<Instant>,
+}
+
+impl PathMtuDiscovery {
+    pub(crate) fn new(base_mtu: u32, max_mtu: u32) -> Self {
+        let mut pmtud = PathMtuDiscovery {
+            base_mtu,
+            max_mtu: std::cmp::max(base_mtu, max_mtu),
+            mtu: base_mtu,
+            ..Default::default()
+        };
+        pmtud.start_search();
+        pmtud
+    }
+
+    /// mtu returns the largest packet size confirmed to be delivered.
+    pub(crate) fn mtu(&self) -> u32 {
+        self.mtu
+    }
+
+    /// probe_in_flight tells whether a probe has been sent and is neither
+    /// acknowledged nor timed out yet.
+    pub(crate) fn probe_in_flight(&self) -> bool {
+        self.probe.is_some() && self.probe_sent
+    }
+
+    /// probe_to_send returns the probe to be sent now, if any. A search which
+    /// completed long enough ago is started again.
+    pub(crate) fn probe_to_send(&mut self, now: Instant) -> Option<Probe> {
+        if self.state == ProbeState::SearchComplete {
+            match self.search_completed_at {
+                Some(at) if now.duration_since(at) >= PMTU_RAISE_INTERVAL => {
+                    self.start_search();
+                }
+                _ => return None,
+            }
+        }
+
+        if self.probe_sent {
+            return None;
+        }
+
+        let probe = self.probe?;
+        self.probe_sent = true;
+        self.probe_count += 1;
+        Some(probe)
+    }
+
+    /// on_probe_acked handles the acknowledgement of a probe and returns the
+    /// new MTU if it was raised.
+    pub(crate) fn on_probe_acked(&mut self, sequence: u32, now: Instant) -> Option<u32> {
+        let probe = match self.probe {
+            Some(probe) if probe.sequence == sequence => probe,
+            _ => return None, // a stale probe
+        };
+
+        log::debug!("PMTUD: probe of {} bytes acknowledged", probe.size);
+
+        let raised = if probe.size > self.mtu {
+            self.mtu = probe.size;
+            Some(self.mtu)
+        } else {
+            None
+        };
+        self.next_probe(now);
+
+        raised
+    }
+
+    /// on_probe_timeout handles the expiry of the probe timer. The probe is
+    /// sent again, or the next smaller size is probed once it was sent
+    /// MAX_PROBES times.
+    pub(crate) fn on_probe_timeout(&mut self, now: Instant) {
+        let probe = match self.probe {
+            Some(probe) if self.probe_sent => probe,
+            _ => return,
+        };
+
+        if self.probe_count < MAX_PROBES {
+            self.probe_sent = false;
+            return;
+        }
+
+        log::debug!("PMTUD: probe of {} bytes lost", probe.size);
+        self.search_high = probe.size;
+        self.next_probe(now);
+    }
+
+    /// on_black_hole falls back to the base MTU when packets of the current size
+    /// are suspected not to be delivered anymore, and searches again from there.
+    /// It returns the new MTU if it was lowered.
+    pub(crate) fn on_black_hole(&mut self) -> Option<u32> {
+        if self.mtu <= self.base_mtu {
+            return None;
+        }
+
+        log::debug!(
+            "PMTUD: black hole detected at {} bytes, falling back to {} bytes",
+            self.mtu,
+            self.base_mtu
+        );
+
+        self.mtu = self.base_mtu;
+        self.start_search();
+        Some(self.mtu)
+    }
+
+    fn start_search(&mut self) {
+        self.state = ProbeState::Searching;
+        self.search_high = self.max_mtu + 1;
+        self.search_completed_at = None;
+        self.probe = None;
+        self.next_probe(Instant::now());
+    }
+
+    fn next_probe(&mut self, now: Instant) {
+        self.probe_sent = false;
+        self.probe_count = 0;
+
+        if self.search_high <= self.mtu + PROBE_GRANULARITY {
+            log::debug!("PMTUD: search complete at {} bytes", self.mtu);
+            self.state = ProbeState::SearchComplete;
+            self.search_completed_at = Some(now);
+            self.probe = None;
+            return;
+        }
+
+        // Try the largest size first, as most paths are expected to deliver it,
+        // then search for the size between the bounds. Sizes are kept 4-byte
+        // aligned since the chunks are.
+        let size = if self.search_high > self.max_mtu {
+            self.max_mtu & !3
+        } else {
+            (self.mtu + (self.search_high - self.mtu) / 2) & !3
+        };
+
+        self.probe = Some(Probe {
+            size,
+            sequence: self.next_sequence,
+        });
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+    }
+}
@@ -0,0 +1,101 @@
+use super::*;
+
+const BASE_MTU: u32 = 1228;
+const MAX_MTU: u32 = 1400;
+
+/// Sends probes, acknowledging the ones of at most path_mtu bytes and letting
+/// the others time out, until the search completes.
+fn run_search(pmtud: &mut PathMtuDiscovery, path_mtu: u32, now: Instant) -> Vec<u32> {
+    let mut probed = vec![];
+    while let Some(probe) = pmtud.probe_to_send(now) {
+        probed.push(probe.size);
+        if probe.size <= path_mtu {
+            pmtud.on_probe_acked(probe.sequence, now);
+        } else {
+            pmtud.on_probe_timeout(now);
+        }
+    }
+    probed
+}
+
+#[test]
+fn test_pmtud_raises_to_max_mtu() {
+    let now = Instant::now();
+    let mut pmtud = PathMtuDiscovery::new(BASE_MTU, MAX_MTU);
+    assert_eq!(BASE_MTU, pmtud.mtu());
+
+    let probe = pmtud.probe_to_send(now).expect("should probe");
+    assert_eq!(MAX_MTU, probe.size, "should try the largest size first");
+    assert!(
+        pmtud.probe_to_send(now).is_none(),
+        "should wait for the outstanding probe"
+    );
+
+    assert_eq!(Some(MAX_MTU), pmtud.on_probe_acked(probe.sequence, now));
+    assert_eq!(ProbeState::SearchComplete, pmtud.state);
+    assert!(pmtud.probe_to_send(now).is_none());
+}
+
+#[test]
+fn test_pmtud_search_below_max_mtu() {
+    let now = Instant::now();
+    let path_mtu = 1335;
+    let mut pmtud = PathMtuDiscovery::new(BASE_MTU, MAX_MTU);
+
+    let probed = run_search(&mut pmtud, path_mtu, now);
+    assert_eq!(
+        vec![MAX_MTU; MAX_PROBES],
+        probed[..MAX_PROBES].to_vec(),
+        "should retry a lost probe up to MAX_PROBES times"
+    );
+
+    assert_eq!(ProbeState::SearchComplete, pmtud.state);
+    assert!(pmtud.mtu() <= path_mtu, "should not exceed the path MTU");
+    assert!(
+        pmtud.mtu() + PROBE_GRANULARITY > path_mtu,
+        "mtu {} should be close to the path MTU",
+        pmtud.mtu()
+    );
+    assert_eq!(0, pmtud.mtu() % 4, "mtu should be 4-byte aligned");
+}
+
+#[test]
+fn test_pmtud_ignores_stale_probe_ack() {
+    let now = Instant::now();
+    let mut pmtud = PathMtuDiscovery::new(BASE_MTU, MAX_MTU);
+
+    let probe = pmtud.probe_to_send(now).unwrap();
+    assert_eq!(None, pmtud.on_probe_acked(probe.sequence + 1, now));
+    assert_eq!(BASE_MTU, pmtud.mtu());
+    assert_eq!(Some(MAX_MTU), pmtud.on_probe_acked(probe.sequence, now));
+    assert_eq!(None, pmtud.on_probe_acked(probe.sequence, now));
+}
+
+#[test]
+fn test_pmtud_black_hole_and_raise() {
+    let now = Instant::now();
+    let mut pmtud = PathMtuDiscovery::new(BASE_MTU, MAX_MTU);
+
+    assert_eq!(None, pmtud.on_black_hole(), "already at the base MTU");
+
+    run_search(&mut pmtud, MAX_MTU, now);
+    assert_eq!(MAX_MTU, pmtud.mtu());
+
+    // The path shrinks
+    assert_eq!(Some(BASE_MTU), pmtud.on_black_hole());
+    run_search(&mut pmtud, 1300, now);
+    assert!(pmtud.mtu() <= 1300 && pmtud.mtu() > BASE_MTU);
+
+    // and grows again, which is found once the raise timer expires.
+    assert!(pmtud.probe_to_send(now).is_none());
+    run_search(&mut pmtud, MAX_MTU, now + PMTU_RAISE_INTERVAL);
+    assert_eq!(MAX_MTU, pmtud.mtu());
+}
+
+#[test]
+fn test_pmtud_max_mtu_below_base() {
+    let mut pmtud = PathMtuDiscovery::new(BASE_MTU, 1000);
+    assert_eq!(BASE_MTU, pmtud.mtu());
+    assert_eq!(ProbeState::SearchComplete, pmtud.state);
+    assert!(pmtud.probe_to_send(Instant::now()).is_none());
+}
@@ -0,0 +1,80 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::futures::Notified;
+use tokio::sync::Notify;
+
+/// SendBuffer accounts for the bytes written to the streams of an association
+/// until the peer acknowledges or abandons them, so that writers can wait for
+/// room instead of queueing without bound.
+#[derive(Default, Debug)]
+pub(crate) struct SendBuffer {
+    max_size: usize,
+    buffered: AtomicUsize,
+    cwnd: AtomicUsize,
+    notify: Notify,
+}
+
+impl SendBuffer {
+    pub(crate) fn new(max_size: usize) -> Self {
+        SendBuffer {
+            max_size,
+            ..Default::default()
+        }
+    }
+
+    /// capacity is the number of bytes which may be buffered: the configured
+    /// size, or the congestion window if it has grown larger. A size of 0
+    /// leaves the buffer unbounded.
+    pub(crate) fn capacity(&self) -> usize {
+        if self.max_size == 0 {
+            return usize::MAX;
+        }
+        std::cmp::max(self.max_size, self.cwnd.load(Ordering::SeqCst))
+    }
+
+    /// buffered returns the number of bytes written and not yet acknowledged.
+    pub(crate) fn buffered(&self) -> usize {
+        self.buffered.load(Ordering::SeqCst)
+    }
+
+    /// is_writable tells whether there is room for more data.
+    pub(crate) fn is_writable(&self) -> bool {
+        self.buffered() < self.capacity()
+    }
+
+    /// buffer accounts for n_bytes written to a stream.
+    pub(crate) fn buffer(&self, n_bytes: usize) {
+        self.buffered.fetch_add(n_bytes, Ordering::SeqCst);
+    }
+
+    /// release accounts for n_bytes acknowledged, abandoned or dropped, and
+    /// wakes the writers up if there is room again.
+    pub(crate) fn release(&self, n_bytes: usize) {
+        let _ = self
+            .buffered
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |buffered| {
+                Some(buffered.saturating_sub(n_bytes))
+            });
+        if self.is_writable() {
+            self.notify.notify_waiters();
+        }
+    }
+
+    /// set_cwnd mirrors the congestion window of the association.
+    pub(crate) fn set_cwnd(&self, cwnd: u32) {
+        self.cwnd.store(cwnd as usize, Ordering::SeqCst);
+        if self.is_writable() {
+            self.notify.notify_waiters();
+        }
+    }
+
+    /// wake wakes the writers up to check the association and stream states.
+    pub(crate) fn wake(&self) {
+        self.notify.notify_waiters();
+    }
+
+    /// notified returns a future completing on the next release or wake. It is
+    /// to be created before checking for room so that no wakeup is missed.
+    pub(crate) fn notified(&self) -> Notified<'_> {
+        self.notify.notified()
+    }
+}
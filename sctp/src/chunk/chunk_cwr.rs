@@ -0,0 +1,77 @@
+use super::chunk_ecne::LOWEST_TSN_LENGTH;
+use super::{chunk_header::*, chunk_type::*, *};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::fmt;
+
+///chunkCwr represents an SCTP Chunk of type CWR (RFC 4960 Appendix A)
+///
+///The sender of DATA chunks reports with this chunk that it reduced its
+///congestion window after an ECNE chunk.
+///
+///0                   1                   2                   3
+///0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|   Type = 13   | Chunk  Flags  |      Length = 8               |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                        Lowest TSN Number                      |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Default, Debug, Clone)]
+pub(crate) struct ChunkCwr {
+    /// The Lowest TSN Number of the ECNE chunk the congestion window was
+    /// reduced for
+    pub(crate) lowest_tsn: u32,
+}
+
+/// makes chunkCwr printable
+impl fmt::Display for ChunkCwr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\nLowest TSN: {}", self.header(), self.lowest_tsn)
+    }
+}
+
+impl Chunk for ChunkCwr {
+    fn header(&self) -> ChunkHeader {
+        ChunkHeader {
+            typ: CT_CWR,
+            flags: 0,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let header = ChunkHeader::unmarshal(raw)?;
+
+        if header.typ != CT_CWR {
+            return Err(Error::ErrChunkTypeNotCwr);
+        }
+
+        if header.value_length() != LOWEST_TSN_LENGTH {
+            return Err(Error::ErrInvalidChunkSize);
+        }
+
+        let reader = &mut raw.slice(CHUNK_HEADER_SIZE..CHUNK_HEADER_SIZE + header.value_length());
+
+        let lowest_tsn = reader.get_u32();
+
+        Ok(ChunkCwr { lowest_tsn })
+    }
+
+    fn marshal_to(&self, writer: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(writer)?;
+        writer.put_u32(self.lowest_tsn);
+        Ok(writer.len())
+    }
+
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn value_length(&self) -> usize {
+        LOWEST_TSN_LENGTH
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
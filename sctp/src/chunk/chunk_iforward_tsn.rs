@@ -0,0 +1,186 @@
+use super::chunk_forward_tsn::NEW_CUMULATIVE_TSN_LENGTH;
+use super::{chunk_header::*, chunk_type::*, *};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::fmt;
+
+///This chunk replaces the FORWARD TSN chunk when user message
+///interleaving is negotiated (RFC 8260 Sec 2.3.1). It reports the
+///skipped user messages by their Message Identifier, for ordered and
+///unordered messages alike.
+/// 0                   1                   2                   3
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|   Type = 194  |  Flags = 0x00 |      Length = Variable        |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                       New Cumulative TSN                      |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|       Stream Identifier       |          Reserved           |U|
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                       Message Identifier                      |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                              ...                              |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|       Stream Identifier       |          Reserved           |U|
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                       Message Identifier                      |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Default, Debug, Clone)]
+pub(crate) struct ChunkIForwardTsn {
+    /// This indicates the new cumulative TSN to the data receiver.
+    pub(crate) new_cumulative_tsn: u32,
+    pub(crate) streams: Vec<ChunkIForwardTsnStream>,
+}
+
+pub(crate) const IFORWARD_TSN_STREAM_LENGTH: usize = 8;
+
+/// makes ChunkIForwardTsn printable
+impl fmt::Display for ChunkIForwardTsn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut res = vec![self.header().to_string()];
+        res.push(format!("New Cumulative TSN: {}", self.new_cumulative_tsn));
+        for s in &self.streams {
+            res.push(format!(
+                " - si={}, unordered={}, mid={}",
+                s.identifier, s.unordered, s.message_identifier
+            ));
+        }
+
+        write!(f, "{}", res.join("\n"))
+    }
+}
+
+impl Chunk for ChunkIForwardTsn {
+    fn header(&self) -> ChunkHeader {
+        ChunkHeader {
+            typ: CT_IFORWARD_TSN,
+            flags: 0,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(buf: &Bytes) -> Result<Self> {
+        let header = ChunkHeader::unmarshal(buf)?;
+
+        if header.typ != CT_IFORWARD_TSN {
+            return Err(Error::ErrChunkTypeNotIForwardTsn);
+        }
+
+        if header.value_length() < NEW_CUMULATIVE_TSN_LENGTH
+            || (header.value_length() - NEW_CUMULATIVE_TSN_LENGTH) % IFORWARD_TSN_STREAM_LENGTH != 0
+        {
+            return Err(Error::ErrChunkTooShort);
+        }
+
+        let reader = &mut buf.slice(CHUNK_HEADER_SIZE..CHUNK_HEADER_SIZE + header.value_length());
+        let new_cumulative_tsn = reader.get_u32();
+
+        let mut streams = vec![];
+        while reader.remaining() >= IFORWARD_TSN_STREAM_LENGTH {
+            let s = ChunkIForwardTsnStream::unmarshal(
+                &reader.copy_to_bytes(IFORWARD_TSN_STREAM_LENGTH),
+            )?;
+            streams.push(s);
+        }
+
+        Ok(ChunkIForwardTsn {
+            new_cumulative_tsn,
+            streams,
+        })
+    }
+
+    fn marshal_to(&self, writer: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(writer)?;
+
+        writer.put_u32(self.new_cumulative_tsn);
+
+        for s in &self.streams {
+            writer.extend(s.marshal()?);
+        }
+
+        Ok(writer.len())
+    }
+
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn value_length(&self) -> usize {
+        NEW_CUMULATIVE_TSN_LENGTH + IFORWARD_TSN_STREAM_LENGTH * self.streams.len()
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ChunkIForwardTsnStream {
+    /// This field holds the stream number this entry refers to.
+    pub(crate) identifier: u16,
+
+    /// Whether the entry refers to the ordered or the unordered user
+    /// messages of the stream.
+    pub(crate) unordered: bool,
+
+    /// This field holds the largest Message Identifier of the ordered or
+    /// unordered user messages of the stream that was skipped.
+    pub(crate) message_identifier: u32,
+}
+
+/// makes ChunkIForwardTsnStream printable
+impl fmt::Display for ChunkIForwardTsnStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {}, {}",
+            self.identifier, self.unordered, self.message_identifier
+        )
+    }
+}
+
+impl Chunk for ChunkIForwardTsnStream {
+    fn header(&self) -> ChunkHeader {
+        ChunkHeader {
+            typ: ChunkType(0),
+            flags: 0,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(buf: &Bytes) -> Result<Self> {
+        if buf.len() < IFORWARD_TSN_STREAM_LENGTH {
+            return Err(Error::ErrChunkTooShort);
+        }
+
+        let reader = &mut buf.clone();
+        let identifier = reader.get_u16();
+        let unordered = (reader.get_u16() & 1) != 0;
+        let message_identifier = reader.get_u32();
+
+        Ok(ChunkIForwardTsnStream {
+            identifier,
+            unordered,
+            message_identifier,
+        })
+    }
+
+    fn marshal_to(&self, writer: &mut BytesMut) -> Result<usize> {
+        writer.put_u16(self.identifier);
+        writer.put_u16(u16::from(self.unordered));
+        writer.put_u32(self.message_identifier);
+        Ok(writer.len())
+    }
+
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn value_length(&self) -> usize {
+        IFORWARD_TSN_STREAM_LENGTH
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
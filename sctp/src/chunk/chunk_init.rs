@@ -288,13 +288,22 @@ impl Chunk for ChunkInit {
 }
 
 impl ChunkInit {
-    pub(crate) fn set_supported_extensions(&mut self) {
+    pub(crate) fn set_supported_extensions(&mut self, message_interleaving: bool) {
         // TODO RFC5061 https://tools.ietf.org/html/rfc6525#section-5.2
         // An implementation supporting this (Supported Extensions Parameter)
         // extension MUST list the ASCONF, the ASCONF-ACK, and the AUTH chunks
         // in its INIT and INIT-ACK parameters.
-        self.params.push(Box::new(ParamSupportedExtensions {
-            chunk_types: vec![CT_RECONFIG, CT_FORWARD_TSN],
-        }));
+        let mut chunk_types = vec![CT_RECONFIG, CT_FORWARD_TSN];
+
+        // RFC 8260 Sec 2.2.1
+        // An SCTP endpoint supporting user message interleaving lists the
+        // I-DATA chunk, and the I-FORWARD-TSN chunk as it supports PR-SCTP.
+        if message_interleaving {
+            chunk_types.push(CT_IDATA);
+            chunk_types.push(CT_IFORWARD_TSN);
+        }
+
+        self.params
+            .push(Box::new(ParamSupportedExtensions { chunk_types }));
     }
 }
@@ -0,0 +1,71 @@
+use super::{chunk_header::*, chunk_type::*, *};
+
+use bytes::{Bytes, BytesMut};
+use std::fmt;
+
+///chunkPadding represents an SCTP Chunk of type PAD (RFC 4820)
+///
+///This chunk only fills a packet up to a given size, which is used by the
+///probe packets of path MTU discovery. Its content is ignored by the receiver.
+///
+/// 0                   1                   2                   3
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|  Type = 132   |   Flags = 0   |             Length            |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                                                               |
+///|                Padding Data (Variable-Length)                 |
+///|                                                               |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Default, Debug, Clone)]
+pub(crate) struct ChunkPadding {
+    /// Number of bytes of the Padding Data
+    pub(crate) padding_length: usize,
+}
+
+/// makes chunkPadding printable
+impl fmt::Display for ChunkPadding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header())
+    }
+}
+
+impl Chunk for ChunkPadding {
+    fn header(&self) -> ChunkHeader {
+        ChunkHeader {
+            typ: CT_PAD,
+            flags: 0,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let header = ChunkHeader::unmarshal(raw)?;
+
+        if header.typ != CT_PAD {
+            return Err(Error::ErrChunkTypeNotPadding);
+        }
+
+        Ok(ChunkPadding {
+            padding_length: header.value_length(),
+        })
+    }
+
+    fn marshal_to(&self, writer: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(writer)?;
+        writer.resize(writer.len() + self.padding_length, 0);
+        Ok(writer.len())
+    }
+
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn value_length(&self) -> usize {
+        self.padding_length
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
@@ -11,6 +11,7 @@ pub(crate) const PAYLOAD_DATA_BEGINING_FRAGMENT_BITMASK: u8 = 2;
 pub(crate) const PAYLOAD_DATA_UNORDERED_BITMASK: u8 = 4;
 pub(crate) const PAYLOAD_DATA_IMMEDIATE_SACK: u8 = 8;
 pub(crate) const PAYLOAD_DATA_HEADER_SIZE: usize = 12;
+pub(crate) const IDATA_HEADER_SIZE: usize = 16;
 
 /// PayloadProtocolIdentifier is an enum for DataChannel payload types
 /// PayloadProtocolIdentifier enums
@@ -26,6 +27,20 @@ pub enum PayloadProtocolIdentifier {
     Unknown,
 }
 
+impl PayloadProtocolIdentifier {
+    /// is_application_data tells whether the user messages with this identifier carry
+    /// application data, as opposed to DCEP messages and the placeholder byte of empty
+    /// messages which are only protocol framing.
+    pub(crate) fn is_application_data(&self) -> bool {
+        !matches!(
+            self,
+            PayloadProtocolIdentifier::Dcep
+                | PayloadProtocolIdentifier::StringEmpty
+                | PayloadProtocolIdentifier::BinaryEmpty
+        )
+    }
+}
+
 impl Default for PayloadProtocolIdentifier {
     fn default() -> Self {
         PayloadProtocolIdentifier::Unknown
@@ -93,6 +108,30 @@ impl From<u32> for PayloadProtocolIdentifier {
 ///============================================================
 ///|             Table 1: Fragment Description Flags          |
 ///============================================================
+///
+///When user message interleaving is negotiated (RFC 8260), the same
+///chunk is sent as an I-DATA chunk instead, which identifies the user
+///message with a 32-bit Message Identifier, and its fragments with a
+///Fragment Sequence Number carried in place of the Payload Protocol
+///Identifier by all but the first fragment:
+///
+/// 0                   1                   2                   3
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|   Type = 64   |  Res  |I|U|B|E|       Length = Variable       |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                              TSN                              |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|        Stream Identifier      |           Reserved            |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                      Message Identifier                       |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|    Payload Protocol Identifier / Fragment Sequence Number     |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                                                               |
+///|                           User Data                           |
+///|                                                               |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 #[derive(Debug, Clone)]
 pub struct ChunkPayloadData {
     pub(crate) unordered: bool,
@@ -106,6 +145,14 @@ pub struct ChunkPayloadData {
     pub(crate) payload_type: PayloadProtocolIdentifier,
     pub(crate) user_data: Bytes,
 
+    /// Whether this chunk is sent as an I-DATA chunk
+    pub(crate) interleaved: bool,
+    /// I-DATA only: identifies the user message within the ordered or
+    /// unordered messages of the stream
+    pub(crate) message_identifier: u32,
+    /// I-DATA only: position of this fragment in the user message
+    pub(crate) fragment_sequence_number: u32,
+
     /// Whether this data chunk was acknowledged (received by peer)
     pub(crate) acked: bool,
     pub(crate) miss_indicator: u32,
@@ -119,6 +166,8 @@ pub struct ChunkPayloadData {
     pub(crate) abandoned: Arc<AtomicBool>,
     /// valid only with the first fragment
     pub(crate) all_inflight: Arc<AtomicBool>,
+    /// Whether the stream was notified that the user message was abandoned
+    pub(crate) abandon_notified: Arc<AtomicBool>,
 
     /// Retransmission flag set when T1-RTX timeout occurred and this
     /// chunk is still in the inflight queue
@@ -137,12 +186,16 @@ impl Default for ChunkPayloadData {
             stream_sequence_number: 0,
             payload_type: PayloadProtocolIdentifier::default(),
             user_data: Bytes::new(),
+            interleaved: false,
+            message_identifier: 0,
+            fragment_sequence_number: 0,
             acked: false,
             miss_indicator: 0,
             since: SystemTime::now(),
             nsent: 0,
             abandoned: Arc::new(AtomicBool::new(false)),
             all_inflight: Arc::new(AtomicBool::new(false)),
+            abandon_notified: Arc::new(AtomicBool::new(false)),
             retransmit: false,
         }
     }
@@ -172,7 +225,11 @@ impl Chunk for ChunkPayloadData {
         }
 
         ChunkHeader {
-            typ: CT_PAYLOAD_DATA,
+            typ: if self.interleaved {
+                CT_IDATA
+            } else {
+                CT_PAYLOAD_DATA
+            },
             flags,
             value_length: self.value_length() as u16,
         }
@@ -181,9 +238,16 @@ impl Chunk for ChunkPayloadData {
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
-        if header.typ != CT_PAYLOAD_DATA {
-            return Err(Error::ErrChunkTypeNotPayloadData);
-        }
+        let interleaved = match header.typ {
+            CT_PAYLOAD_DATA => false,
+            CT_IDATA => true,
+            _ => return Err(Error::ErrChunkTypeNotPayloadData),
+        };
+        let header_size = if interleaved {
+            IDATA_HEADER_SIZE
+        } else {
+            PAYLOAD_DATA_HEADER_SIZE
+        };
 
         let immediate_sack = (header.flags & PAYLOAD_DATA_IMMEDIATE_SACK) != 0;
         let unordered = (header.flags & PAYLOAD_DATA_UNORDERED_BITMASK) != 0;
@@ -191,7 +255,7 @@ impl Chunk for ChunkPayloadData {
         let ending_fragment = (header.flags & PAYLOAD_DATA_ENDING_FRAGMENT_BITMASK) != 0;
 
         // validity of value_length is checked in ChunkHeader::unmarshal
-        if header.value_length() < PAYLOAD_DATA_HEADER_SIZE {
+        if header.value_length() < header_size {
             return Err(Error::ErrChunkPayloadSmall);
         }
 
@@ -199,11 +263,23 @@ impl Chunk for ChunkPayloadData {
 
         let tsn = reader.get_u32();
         let stream_identifier = reader.get_u16();
-        let stream_sequence_number = reader.get_u16();
-        let payload_type: PayloadProtocolIdentifier = reader.get_u32().into();
-        let user_data = raw.slice(
-            CHUNK_HEADER_SIZE + PAYLOAD_DATA_HEADER_SIZE..CHUNK_HEADER_SIZE + header.value_length(),
-        );
+        let stream_sequence_number = reader.get_u16(); // reserved with I-DATA
+        let (message_identifier, payload_type, fragment_sequence_number) = if interleaved {
+            let message_identifier = reader.get_u32();
+            if beginning_fragment {
+                (message_identifier, reader.get_u32().into(), 0)
+            } else {
+                (
+                    message_identifier,
+                    PayloadProtocolIdentifier::Unknown,
+                    reader.get_u32(),
+                )
+            }
+        } else {
+            (0, reader.get_u32().into(), 0)
+        };
+        let user_data =
+            raw.slice(CHUNK_HEADER_SIZE + header_size..CHUNK_HEADER_SIZE + header.value_length());
 
         Ok(ChunkPayloadData {
             unordered,
@@ -213,15 +289,23 @@ impl Chunk for ChunkPayloadData {
 
             tsn,
             stream_identifier,
-            stream_sequence_number,
+            stream_sequence_number: if interleaved {
+                0
+            } else {
+                stream_sequence_number
+            },
             payload_type,
             user_data,
+            interleaved,
+            message_identifier,
+            fragment_sequence_number,
             acked: false,
             miss_indicator: 0,
             since: SystemTime::now(),
             nsent: 0,
             abandoned: Arc::new(AtomicBool::new(false)),
             all_inflight: Arc::new(AtomicBool::new(false)),
+            abandon_notified: Arc::new(AtomicBool::new(false)),
             retransmit: false,
         })
     }
@@ -231,8 +315,18 @@ impl Chunk for ChunkPayloadData {
 
         writer.put_u32(self.tsn);
         writer.put_u16(self.stream_identifier);
-        writer.put_u16(self.stream_sequence_number);
-        writer.put_u32(self.payload_type as u32);
+        if self.interleaved {
+            writer.put_u16(0); // reserved
+            writer.put_u32(self.message_identifier);
+            if self.beginning_fragment {
+                writer.put_u32(self.payload_type as u32);
+            } else {
+                writer.put_u32(self.fragment_sequence_number);
+            }
+        } else {
+            writer.put_u16(self.stream_sequence_number);
+            writer.put_u32(self.payload_type as u32);
+        }
         writer.extend(self.user_data.clone());
 
         Ok(writer.len())
@@ -243,7 +337,11 @@ impl Chunk for ChunkPayloadData {
     }
 
     fn value_length(&self) -> usize {
-        PAYLOAD_DATA_HEADER_SIZE + self.user_data.len()
+        if self.interleaved {
+            IDATA_HEADER_SIZE + self.user_data.len()
+        } else {
+            PAYLOAD_DATA_HEADER_SIZE + self.user_data.len()
+        }
     }
 
     fn as_any(&self) -> &(dyn Any + Send + Sync) {
@@ -261,6 +359,16 @@ impl ChunkPayloadData {
         abandoned && all_inflight
     }
 
+    /// buffered_len returns the number of bytes of application data of this chunk, which
+    /// are accounted in the buffered amount of its stream until acknowledged or abandoned.
+    pub(crate) fn buffered_len(&self) -> usize {
+        if self.payload_type.is_application_data() {
+            self.user_data.len()
+        } else {
+            0
+        }
+    }
+
     pub(crate) fn set_abandoned(&self, abandoned: bool) {
         self.abandoned.store(abandoned, Ordering::SeqCst);
     }
@@ -270,4 +378,22 @@ impl ChunkPayloadData {
             self.all_inflight.store(true, Ordering::SeqCst);
         }
     }
+
+    /// abandoned_partially_sent tells whether the chunk was abandoned while
+    /// some fragments of its user message are still waiting to be sent.
+    pub(crate) fn abandoned_partially_sent(&self) -> bool {
+        self.abandoned.load(Ordering::SeqCst) && !self.all_inflight.load(Ordering::SeqCst)
+    }
+
+    /// is_fragment_of tells whether both chunks carry fragments of the same
+    /// user message.
+    pub(crate) fn is_fragment_of(&self, other: &ChunkPayloadData) -> bool {
+        Arc::ptr_eq(&self.abandoned, &other.abandoned)
+    }
+
+    /// set_abandon_notified returns true only the first time it is called
+    /// for the fragments of a user message.
+    pub(crate) fn set_abandon_notified(&self) -> bool {
+        !self.abandon_notified.swap(true, Ordering::SeqCst)
+    }
 }
@@ -231,6 +231,150 @@ fn test_chunk_forward_tsn_unmarshal_failure() -> Result<()> {
     Ok(())
 }
 
+///////////////////////////////////////////////////////////////////
+//chunk_iforward_tsn_test
+///////////////////////////////////////////////////////////////////
+use super::chunk_iforward_tsn::*;
+
+#[test]
+fn test_chunk_iforward_tsn_success() -> Result<()> {
+    let tests = vec![
+        Bytes::from_static(&[0xc2, 0x0, 0x0, 0x8, 0x0, 0x0, 0x0, 0x3]),
+        Bytes::from_static(&[
+            0xc2, 0x0, 0x0, 0x10, 0x0, 0x0, 0x0, 0x3, 0x0, 0x4, 0x0, 0x1, 0x0, 0x0, 0x0, 0x5,
+        ]),
+    ];
+
+    for binary in tests {
+        let actual = ChunkIForwardTsn::unmarshal(&binary)?;
+        let b = actual.marshal()?;
+        assert_eq!(binary, b, "test not equal");
+    }
+
+    let c = ChunkIForwardTsn::unmarshal(&Bytes::from_static(&[
+        0xc2, 0x0, 0x0, 0x10, 0x0, 0x0, 0x0, 0x3, 0x0, 0x4, 0x0, 0x1, 0x0, 0x0, 0x0, 0x5,
+    ]))?;
+    assert_eq!(3, c.new_cumulative_tsn, "unexpected New Cumulative TSN");
+    assert_eq!(
+        vec![ChunkIForwardTsnStream {
+            identifier: 4,
+            unordered: true,
+            message_identifier: 5,
+        }],
+        c.streams,
+        "unexpected streams"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_chunk_iforward_tsn_unmarshal_failure() -> Result<()> {
+    let tests = vec![
+        ("chunk header to short", Bytes::from_static(&[0xc2])),
+        (
+            "missing New Cumulative TSN",
+            Bytes::from_static(&[0xc2, 0x0, 0x0, 0x4]),
+        ),
+        (
+            "missing message identifier",
+            Bytes::from_static(&[0xc2, 0x0, 0x0, 0xc, 0x0, 0x0, 0x0, 0x3, 0x0, 0x4, 0x0, 0x1]),
+        ),
+        (
+            "not an I-FORWARD-TSN chunk",
+            Bytes::from_static(&[0xc0, 0x0, 0x0, 0x8, 0x0, 0x0, 0x0, 0x3]),
+        ),
+    ];
+
+    for (name, binary) in tests {
+        let result = ChunkIForwardTsn::unmarshal(&binary);
+        assert!(result.is_err(), "expected unmarshal: {} to fail.", name);
+    }
+
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////
+//chunk_ecne_test and chunk_cwr_test
+///////////////////////////////////////////////////////////////////
+use super::chunk_cwr::*;
+use super::chunk_ecne::*;
+
+#[test]
+fn test_chunk_ecne_cwr_success() -> Result<()> {
+    let ecne = Bytes::from_static(&[0x0c, 0x0, 0x0, 0x8, 0x0, 0x0, 0x0, 0x3]);
+    let c = ChunkEcne::unmarshal(&ecne)?;
+    assert_eq!(3, c.lowest_tsn, "unexpected Lowest TSN");
+    assert_eq!(ecne, c.marshal()?, "test not equal");
+
+    let cwr = Bytes::from_static(&[0x0d, 0x0, 0x0, 0x8, 0x0, 0x0, 0x0, 0x3]);
+    let c = ChunkCwr::unmarshal(&cwr)?;
+    assert_eq!(3, c.lowest_tsn, "unexpected Lowest TSN");
+    assert_eq!(cwr, c.marshal()?, "test not equal");
+
+    Ok(())
+}
+
+#[test]
+fn test_chunk_ecne_cwr_unmarshal_failure() -> Result<()> {
+    let tests = vec![
+        ("chunk header to short", Bytes::from_static(&[0x0c])),
+        (
+            "missing Lowest TSN",
+            Bytes::from_static(&[0x0c, 0x0, 0x0, 0x4]),
+        ),
+        (
+            "not an ECNE chunk",
+            Bytes::from_static(&[0x0d, 0x0, 0x0, 0x8, 0x0, 0x0, 0x0, 0x3]),
+        ),
+    ];
+
+    for (name, binary) in tests {
+        let result = ChunkEcne::unmarshal(&binary);
+        assert!(result.is_err(), "expected unmarshal: {} to fail.", name);
+    }
+
+    let result = ChunkCwr::unmarshal(&Bytes::from_static(&[
+        0x0c, 0x0, 0x0, 0x8, 0x0, 0x0, 0x0, 0x3,
+    ]));
+    assert!(
+        result.is_err(),
+        "expected unmarshal of an ECNE chunk as CWR to fail"
+    );
+
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////
+//chunk_padding_test
+///////////////////////////////////////////////////////////////////
+use super::chunk_padding::*;
+
+#[test]
+fn test_chunk_padding_success() -> Result<()> {
+    let pad = Bytes::from_static(&[0x84, 0x0, 0x0, 0x8, 0x0, 0x0, 0x0, 0x0]);
+    let c = ChunkPadding::unmarshal(&pad)?;
+    assert_eq!(4, c.padding_length, "unexpected padding length");
+    assert_eq!(pad, c.marshal()?, "test not equal");
+
+    let c = ChunkPadding { padding_length: 0 };
+    assert_eq!(
+        Bytes::from_static(&[0x84, 0x0, 0x0, 0x4]),
+        c.marshal()?,
+        "test not equal"
+    );
+
+    let result = ChunkPadding::unmarshal(&Bytes::from_static(&[
+        0x0c, 0x0, 0x0, 0x8, 0x0, 0x0, 0x0, 0x3,
+    ]));
+    assert!(
+        result.is_err(),
+        "expected unmarshal of an ECNE chunk as PAD to fail"
+    );
+
+    Ok(())
+}
+
 ///////////////////////////////////////////////////////////////////
 //chunk_reconfig_test
 ///////////////////////////////////////////////////////////////////
@@ -654,6 +798,43 @@ fn test_payload_data_marshal_unmarshal() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_idata_chunk_marshal_unmarshal() -> Result<()> {
+    let tests = vec![
+        (
+            "beginning fragment",
+            Bytes::from_static(&[
+                0x40, 0x02, 0x00, 0x18, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x03, 0x00, 0x00, 0x00, 0x33, 0x61, 0x62, 0x63, 0x64,
+            ]),
+            PayloadProtocolIdentifier::String,
+            0,
+        ),
+        (
+            "ending fragment",
+            Bytes::from_static(&[
+                0x40, 0x05, 0x00, 0x18, 0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x65, 0x66, 0x67, 0x68,
+            ]),
+            PayloadProtocolIdentifier::Unknown,
+            1,
+        ),
+    ];
+
+    for (name, binary, ppi, fsn) in tests {
+        let c = ChunkPayloadData::unmarshal(&binary)?;
+        assert!(c.interleaved, "{}: should be an I-DATA chunk", name);
+        assert_eq!(2, c.stream_identifier, "{}: unexpected SI", name);
+        assert_eq!(3, c.message_identifier, "{}: unexpected MID", name);
+        assert_eq!(ppi, c.payload_type, "{}: unexpected PPI", name);
+        assert_eq!(fsn, c.fragment_sequence_number, "{}: unexpected FSN", name);
+        let b = c.marshal()?;
+        assert_eq!(binary, b, "{}: test not equal", name);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_select_ack_chunk() -> Result<()> {
     let raw_pkt = Bytes::from_static(&[
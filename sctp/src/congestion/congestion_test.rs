@@ -0,0 +1,176 @@
+use super::*;
+
+use std::time::Duration;
+
+const MTU: u32 = 1200;
+
+fn ack(bytes_acked: u32, now: Instant) -> Ack {
+    Ack {
+        bytes_acked,
+        in_fast_recovery: false,
+        has_pending_data: true,
+        srtt: 100,
+        now,
+    }
+}
+
+#[test]
+fn test_rfc4960_slow_start() {
+    let mut cc = Rfc4960::default();
+    let mut w = CongestionWindow {
+        cwnd: 4 * MTU,
+        ssthresh: 100 * MTU,
+        mtu: MTU,
+    };
+
+    cc.on_ack(&mut w, &ack(2 * MTU, Instant::now()));
+    assert_eq!(6 * MTU, w.cwnd, "cwnd should grow by the bytes acked");
+
+    let mut a = ack(2 * MTU, Instant::now());
+    a.in_fast_recovery = true;
+    cc.on_ack(&mut w, &a);
+    assert_eq!(6 * MTU, w.cwnd, "cwnd should not grow in fast recovery");
+
+    let mut a = ack(2 * MTU, Instant::now());
+    a.has_pending_data = false;
+    cc.on_ack(&mut w, &a);
+    assert_eq!(6 * MTU, w.cwnd, "cwnd should not grow when not fully used");
+}
+
+#[test]
+fn test_rfc4960_congestion_avoidance() {
+    let mut cc = Rfc4960::default();
+    let mut w = CongestionWindow {
+        cwnd: 10 * MTU,
+        ssthresh: 5 * MTU,
+        mtu: MTU,
+    };
+
+    cc.on_ack(&mut w, &ack(6 * MTU, Instant::now()));
+    assert_eq!(
+        10 * MTU,
+        w.cwnd,
+        "cwnd should not grow before a full window"
+    );
+    cc.on_ack(&mut w, &ack(6 * MTU, Instant::now()));
+    assert_eq!(11 * MTU, w.cwnd, "cwnd should grow by one MTU per window");
+}
+
+#[test]
+fn test_rfc4960_reductions() {
+    let mut cc = Rfc4960::default();
+    let mut w = CongestionWindow {
+        cwnd: 20 * MTU,
+        ssthresh: 5 * MTU,
+        mtu: MTU,
+    };
+
+    cc.on_congestion(&mut w);
+    assert_eq!(10 * MTU, w.ssthresh, "ssthresh should be halved");
+    assert_eq!(10 * MTU, w.cwnd, "cwnd should be set to ssthresh");
+
+    cc.on_congestion(&mut w);
+    cc.on_congestion(&mut w);
+    assert_eq!(4 * MTU, w.ssthresh, "ssthresh should be at least 4 MTUs");
+
+    cc.on_retransmission_timeout(&mut w);
+    assert_eq!(MTU, w.cwnd, "cwnd should be set to one MTU");
+}
+
+#[test]
+fn test_cubic_reductions() {
+    let mut cc = Cubic::default();
+    let mut w = CongestionWindow {
+        cwnd: 100 * MTU,
+        ssthresh: 5 * MTU,
+        mtu: MTU,
+    };
+
+    cc.on_congestion(&mut w);
+    assert_eq!(70 * MTU, w.ssthresh, "ssthresh should be 0.7 cwnd");
+    assert_eq!(70 * MTU, w.cwnd, "cwnd should be set to ssthresh");
+
+    let mut cc = Cubic::default();
+    let mut w = CongestionWindow {
+        cwnd: 100 * MTU,
+        ssthresh: 5 * MTU,
+        mtu: MTU,
+    };
+
+    cc.on_retransmission_timeout(&mut w);
+    assert_eq!(70 * MTU, w.ssthresh, "ssthresh should be 0.7 cwnd");
+    assert_eq!(MTU, w.cwnd, "cwnd should be set to one MTU");
+}
+
+#[test]
+fn test_cubic_grows_back_to_w_max() {
+    let mut cc = Cubic::default();
+    let mut w = CongestionWindow {
+        cwnd: 1000 * MTU,
+        ssthresh: 5 * MTU,
+        mtu: MTU,
+    };
+    cc.on_congestion(&mut w);
+    assert_eq!(700 * MTU, w.cwnd);
+
+    // K = cbrt(300 / 0.4) ~ 9.1s: the window grows back to w_max in about
+    // K seconds, and then goes beyond it.
+    let start = Instant::now();
+    let mut cwnd_at = vec![];
+    for i in 0..200 {
+        let now = start + Duration::from_millis(100 * i);
+        // a window of data is acknowledged every round trip
+        let n_acks = w.cwnd / MTU;
+        for _ in 0..n_acks {
+            cc.on_ack(&mut w, &ack(MTU, now));
+        }
+        cwnd_at.push(w.cwnd);
+    }
+
+    assert!(
+        cwnd_at[20] < 900 * MTU,
+        "cwnd should grow slowly near w_max: {}",
+        cwnd_at[20] / MTU
+    );
+    assert!(
+        cwnd_at[100] >= 990 * MTU,
+        "cwnd should be back around w_max: {}",
+        cwnd_at[100] / MTU
+    );
+    assert!(
+        cwnd_at[199] > 1100 * MTU,
+        "cwnd should probe beyond w_max: {}",
+        cwnd_at[199] / MTU
+    );
+}
+
+#[test]
+fn test_cubic_is_faster_than_rfc4960() {
+    let start = Instant::now();
+    let mut rfc4960 = Rfc4960::default();
+    let mut cubic = Cubic::default();
+    let mut w_rfc4960 = CongestionWindow {
+        cwnd: 100 * MTU,
+        ssthresh: 5 * MTU,
+        mtu: MTU,
+    };
+    let mut w_cubic = w_rfc4960;
+
+    for i in 0..100 {
+        let now = start + Duration::from_millis(100 * i);
+        for _ in 0..w_rfc4960.cwnd / MTU {
+            rfc4960.on_ack(&mut w_rfc4960, &ack(MTU, now));
+        }
+        for _ in 0..w_cubic.cwnd / MTU {
+            cubic.on_ack(&mut w_cubic, &ack(MTU, now));
+        }
+    }
+
+    assert_eq!(200 * MTU, w_rfc4960.cwnd, "one MTU per round trip");
+    assert!(
+        w_cubic.cwnd > 2 * w_rfc4960.cwnd,
+        "cubic should grow faster: {} vs {}",
+        w_cubic.cwnd / MTU,
+        w_rfc4960.cwnd / MTU
+    );
+}
@@ -0,0 +1,110 @@
+use super::*;
+
+/// The scaling constant of the cubic function (RFC 8312 Sec 5.1).
+const CUBIC_C: f64 = 0.4;
+/// The multiplicative decrease factor (RFC 8312 Sec 4.5).
+const CUBIC_BETA: f64 = 0.7;
+
+/// Cubic implements the CUBIC congestion control of RFC 8312, counting
+/// the window in bytes and its segments in MTUs.
+#[derive(Debug, Default)]
+pub(crate) struct Cubic {
+    /// The congestion window just before the last reduction, in bytes.
+    w_max: f64,
+    /// The start of the current congestion avoidance epoch.
+    epoch_start: Option<Instant>,
+    /// The time it takes the window to grow back to w_max from the start of
+    /// the epoch, in seconds.
+    k: f64,
+    /// The window the RFC 4960 congestion avoidance would have, which
+    /// CUBIC never grows slower than (RFC 8312 Sec 4.2).
+    w_est: f64,
+    /// The fraction of a byte the window grew by, not applied yet.
+    remainder: f64,
+}
+
+impl Cubic {
+    /// Returns the target window at time t of the epoch, in bytes
+    /// (RFC 8312 Sec 4.1).
+    fn w_cubic(&self, t: f64, mtu: f64) -> f64 {
+        self.w_max + CUBIC_C * (t - self.k).powi(3) * mtu
+    }
+
+    /// Remembers the window before a reduction, and ends the epoch.
+    fn on_reduction(&mut self, cwnd: f64) {
+        // RFC 8312 Sec 4.6: with fast convergence, release some bandwidth
+        // for new flows when the window did not grow back to w_max.
+        self.w_max = if cwnd < self.w_max {
+            cwnd * (1.0 + CUBIC_BETA) / 2.0
+        } else {
+            cwnd
+        };
+        self.epoch_start = None;
+        self.remainder = 0.0;
+    }
+}
+
+impl CongestionController for Cubic {
+    fn on_ack(&mut self, w: &mut CongestionWindow, ack: &Ack) {
+        if w.cwnd <= w.ssthresh {
+            slow_start(w, ack);
+            return;
+        }
+        if ack.in_fast_recovery || !ack.has_pending_data {
+            return;
+        }
+
+        let mtu = std::cmp::max(w.mtu, 1) as f64;
+        let cwnd = w.cwnd as f64;
+        let bytes_acked = ack.bytes_acked as f64;
+
+        let epoch_start = match self.epoch_start {
+            Some(epoch_start) => epoch_start,
+            None => {
+                self.k = if cwnd < self.w_max {
+                    ((self.w_max - cwnd) / mtu / CUBIC_C).cbrt()
+                } else {
+                    0.0
+                };
+                self.w_max = self.w_max.max(cwnd);
+                self.w_est = cwnd;
+                self.epoch_start = Some(ack.now);
+                ack.now
+            }
+        };
+
+        // The window is aimed at its target one round trip ahead.
+        let t =
+            ack.now.saturating_duration_since(epoch_start).as_secs_f64() + ack.srtt as f64 / 1000.0;
+        let target = self.w_cubic(t, mtu).min(1.5 * cwnd);
+
+        // RFC 8312 Sec 4.2: TCP-friendly region
+        self.w_est += 3.0 * (1.0 - CUBIC_BETA) / (1.0 + CUBIC_BETA) * mtu * bytes_acked / cwnd;
+
+        let mut increase = if target > cwnd {
+            (target - cwnd) * bytes_acked / cwnd
+        } else {
+            0.0
+        };
+        if self.w_est > cwnd + increase {
+            increase = self.w_est - cwnd;
+        }
+
+        self.remainder += increase;
+        let increase = self.remainder.floor();
+        self.remainder -= increase;
+        w.cwnd += increase as u32;
+    }
+
+    fn on_congestion(&mut self, w: &mut CongestionWindow) {
+        self.on_reduction(w.cwnd as f64);
+        w.ssthresh = std::cmp::max((w.cwnd as f64 * CUBIC_BETA) as u32, 4 * w.mtu);
+        w.cwnd = w.ssthresh;
+    }
+
+    fn on_retransmission_timeout(&mut self, w: &mut CongestionWindow) {
+        self.on_reduction(w.cwnd as f64);
+        w.ssthresh = std::cmp::max((w.cwnd as f64 * CUBIC_BETA) as u32, 4 * w.mtu);
+        w.cwnd = w.mtu;
+    }
+}
@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod congestion_test;
+
+pub(crate) mod cubic;
+pub(crate) mod rfc4960;
+
+use cubic::Cubic;
+use rfc4960::Rfc4960;
+
+use std::fmt;
+use std::time::Instant;
+
+/// CongestionControl is the algorithm an association uses to adjust its
+/// congestion window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub enum CongestionControl {
+    /// Rfc4960 is the congestion control of RFC 4960 Sec 7.2: slow start,
+    /// then an increase of one MTU per round trip.
+    Rfc4960 = 0,
+    /// Cubic grows the congestion window as a cubic function of the time
+    /// since the last congestion event (RFC 8312), which fills paths with a
+    /// large bandwidth-delay product much faster.
+    Cubic = 1,
+}
+
+impl Default for CongestionControl {
+    fn default() -> Self {
+        CongestionControl::Rfc4960
+    }
+}
+
+impl fmt::Display for CongestionControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            CongestionControl::Rfc4960 => "RFC4960",
+            CongestionControl::Cubic => "CUBIC",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl CongestionControl {
+    pub(crate) fn controller(&self) -> Box<dyn CongestionController + Send + Sync> {
+        match *self {
+            CongestionControl::Rfc4960 => Box::new(Rfc4960::default()),
+            CongestionControl::Cubic => Box::new(Cubic::default()),
+        }
+    }
+}
+
+/// The congestion window of an association, which its CongestionController
+/// adjusts.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct CongestionWindow {
+    pub(crate) cwnd: u32,
+    pub(crate) ssthresh: u32,
+    pub(crate) mtu: u32,
+}
+
+/// Ack describes a SACK which advanced the Cumulative TSN Ack Point.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Ack {
+    /// The number of bytes of the DATA chunks newly acknowledged.
+    pub(crate) bytes_acked: u32,
+    pub(crate) in_fast_recovery: bool,
+    /// Whether data was waiting for the congestion window to open, i.e.
+    /// the congestion window was fully utilized.
+    pub(crate) has_pending_data: bool,
+    /// The smoothed round-trip time, in milliseconds.
+    pub(crate) srtt: u64,
+    pub(crate) now: Instant,
+}
+
+/// CongestionController implements a CongestionControl algorithm.
+pub(crate) trait CongestionController {
+    /// on_ack grows the congestion window when a SACK advances the
+    /// Cumulative TSN Ack Point.
+    fn on_ack(&mut self, w: &mut CongestionWindow, ack: &Ack);
+
+    /// on_congestion shrinks the congestion window when the sender enters
+    /// Fast Recovery.
+    fn on_congestion(&mut self, w: &mut CongestionWindow);
+
+    /// on_retransmission_timeout collapses the congestion window when the
+    /// T3-rtx timer expires.
+    fn on_retransmission_timeout(&mut self, w: &mut CongestionWindow);
+}
+
+impl Default for Box<dyn CongestionController + Send + Sync> {
+    fn default() -> Self {
+        CongestionControl::default().controller()
+    }
+}
+
+/// slow_start grows the congestion window while it is less than or equal to
+/// ssthresh.
+///
+/// RFC 4960 Sec 7.2.1
+///   o  When cwnd is less than or equal to ssthresh, an SCTP endpoint MUST
+///      use the slow-start algorithm to increase cwnd only if the current
+///      congestion window is being fully utilized, an incoming SACK
+///      advances the Cumulative TSN Ack Point, and the data sender is not
+///      in Fast Recovery.  Only when these three conditions are met can
+///      the cwnd be increased; otherwise, the cwnd MUST not be increased.
+///      If these conditions are met, then cwnd MUST be increased by, at
+///      most, the lesser of 1) the total size of the previously
+///      outstanding DATA chunk(s) acknowledged, and 2) the destination's
+///      path MTU.
+pub(crate) fn slow_start(w: &mut CongestionWindow, ack: &Ack) {
+    if !ack.in_fast_recovery && ack.has_pending_data {
+        // TCP way. The SCTP way (slow) is min(ack.bytes_acked, w.mtu).
+        w.cwnd += std::cmp::min(ack.bytes_acked, w.cwnd);
+    }
+}
@@ -0,0 +1,52 @@
+use super::*;
+
+/// Rfc4960 implements the congestion control of RFC 4960 Sec 7.2.
+#[derive(Debug, Default)]
+pub(crate) struct Rfc4960 {
+    partial_bytes_acked: u32,
+}
+
+impl CongestionController for Rfc4960 {
+    fn on_ack(&mut self, w: &mut CongestionWindow, ack: &Ack) {
+        if w.cwnd <= w.ssthresh {
+            slow_start(w, ack);
+            return;
+        }
+
+        // RFC 4960 Sec 7.2.2.  Congestion Avoidance
+        //   o  Whenever cwnd is greater than ssthresh, upon each SACK arrival
+        //      that advances the Cumulative TSN Ack Point, increase
+        //      partial_bytes_acked by the total number of bytes of all new chunks
+        //      acknowledged in that SACK including chunks acknowledged by the new
+        //      Cumulative TSN Ack and by Gap Ack Blocks.
+        self.partial_bytes_acked += ack.bytes_acked;
+
+        //   o  When partial_bytes_acked is equal to or greater than cwnd and
+        //      before the arrival of the SACK the sender had cwnd or more bytes
+        //      of data outstanding (i.e., before arrival of the SACK, flight size
+        //      was greater than or equal to cwnd), increase cwnd by MTU, and
+        //      reset partial_bytes_acked to (partial_bytes_acked - cwnd).
+        if self.partial_bytes_acked >= w.cwnd && ack.has_pending_data {
+            self.partial_bytes_acked -= w.cwnd;
+            w.cwnd += w.mtu;
+        }
+    }
+
+    fn on_congestion(&mut self, w: &mut CongestionWindow) {
+        // RFC 4960 Sec 7.2.3
+        //      ssthresh = max(cwnd/2, 4*MTU)
+        //      cwnd = ssthresh
+        //      partial_bytes_acked = 0
+        w.ssthresh = std::cmp::max(w.cwnd / 2, 4 * w.mtu);
+        w.cwnd = w.ssthresh;
+        self.partial_bytes_acked = 0;
+    }
+
+    fn on_retransmission_timeout(&mut self, w: &mut CongestionWindow) {
+        // RFC 4960 Sec 7.2.3
+        //      ssthresh = max(cwnd/2, 4*MTU)
+        //      cwnd = 1*MTU
+        w.ssthresh = std::cmp::max(w.cwnd / 2, 4 * w.mtu);
+        w.cwnd = w.mtu;
+    }
+}
@@ -33,10 +33,18 @@ pub enum Error {
     ErrChunkTooShort,
     #[error("ChunkType is not of type ForwardTsn")]
     ErrChunkTypeNotForwardTsn,
+    #[error("ChunkType is not of type IForwardTsn")]
+    ErrChunkTypeNotIForwardTsn,
+    #[error("ChunkType is not of type ECNE")]
+    ErrChunkTypeNotEcne,
+    #[error("ChunkType is not of type CWR")]
+    ErrChunkTypeNotCwr,
     #[error("ChunkType is not of type HEARTBEAT")]
     ErrChunkTypeNotHeartbeat,
     #[error("ChunkType is not of type HEARTBEATACK")]
     ErrChunkTypeNotHeartbeatAck,
+    #[error("ChunkType is not of type PAD")]
+    ErrChunkTypeNotPadding,
     #[error("heartbeat is not long enough to contain Heartbeat Info")]
     ErrHeartbeatNotLongEnoughInfo,
     #[error("failed to parse param type")]
@@ -76,6 +84,10 @@ pub enum Error {
     ErrParamPacketTooShort,
     #[error("outgoing SSN reset request parameter too short")]
     ErrSsnResetRequestParamTooShort,
+    #[error("incoming SSN reset request parameter too short")]
+    ErrIncomingSsnResetRequestParamTooShort,
+    #[error("add streams request parameter too short")]
+    ErrAddStreamsRequestParamTooShort,
     #[error("reconfig response parameter too short")]
     ErrReconfigRespParamTooShort,
     #[error("invalid algorithm type")]
@@ -111,6 +123,8 @@ pub enum Error {
     ErrChunkPayloadSmall,
     #[error("ChunkType is not of type PayloadData")]
     ErrChunkTypeNotPayloadData,
+    #[error("DATA chunk type does not match the negotiated user message interleaving")]
+    ErrUnexpectedDataChunkType,
     #[error("ChunkType is not of type Reconfig")]
     ErrChunkTypeNotReconfig,
     #[error("ChunkReconfig has invalid ParamA")]
@@ -186,6 +200,8 @@ pub enum Error {
     ErrInitAckNoCookie,
     #[error("there already exists a stream with identifier")]
     ErrStreamAlreadyExist,
+    #[error("stream identifier beyond the negotiated outbound streams")]
+    ErrStreamIdentifierOutOfRange,
     #[error("Failed to create a stream with identifier")]
     ErrStreamCreateFailed,
     #[error("unable to be popped from inflight queue TSN")]
@@ -194,6 +210,8 @@ pub enum Error {
     ErrTsnRequestNotExist,
     #[error("sending reset packet in non-Established state")]
     ErrResetPacketInStateNotExist,
+    #[error("sending add streams request in non-Established state")]
+    ErrAddStreamsInStateNotExist,
     #[error("unexpected parameter type")]
     ErrParamterType,
     #[error("sending payload data in non-Established state")]
@@ -204,6 +222,10 @@ pub enum Error {
     ErrHandshakeInitAck,
     #[error("handshake failed (COOKIE ECHO)")]
     ErrHandshakeCookieEcho,
+    #[error("handshake timed out")]
+    ErrHandshakeTimeout,
+    #[error("handshake aborted by the peer")]
+    ErrHandshakeAborted,
 
     #[error("outbound packet larger than maximum message size")]
     ErrOutboundPacketTooLarge,
@@ -3,6 +3,7 @@
 
 pub mod association;
 pub mod chunk;
+pub mod congestion;
 mod error;
 pub mod error_cause;
 pub mod packet;
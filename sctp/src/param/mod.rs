@@ -1,10 +1,13 @@
 #[cfg(test)]
 mod param_test;
 
+pub(crate) mod param_add_streams_request;
 pub(crate) mod param_chunk_list;
+pub(crate) mod param_ecn_capable;
 pub(crate) mod param_forward_tsn_supported;
 pub(crate) mod param_header;
 pub(crate) mod param_heartbeat_info;
+pub(crate) mod param_incoming_reset_request;
 pub(crate) mod param_outgoing_reset_request;
 pub(crate) mod param_random;
 pub(crate) mod param_reconfig_response;
@@ -17,8 +20,10 @@ pub(crate) mod param_unrecognized;
 
 use crate::error::{Error, Result};
 use crate::param::{
-    param_chunk_list::ParamChunkList, param_forward_tsn_supported::ParamForwardTsnSupported,
+    param_add_streams_request::ParamAddStreamsRequest, param_chunk_list::ParamChunkList,
+    param_ecn_capable::ParamEcnCapable, param_forward_tsn_supported::ParamForwardTsnSupported,
     param_heartbeat_info::ParamHeartbeatInfo,
+    param_incoming_reset_request::ParamIncomingResetRequest,
     param_outgoing_reset_request::ParamOutgoingResetRequest, param_random::ParamRandom,
     param_reconfig_response::ParamReconfigResponse,
     param_requested_hmac_algorithm::ParamRequestedHmacAlgorithm,
@@ -64,12 +69,17 @@ pub(crate) fn build_param(raw_param: &Bytes) -> Result<Box<dyn Param + Send + Sy
     match raw_type.into() {
         ParamType::ForwardTsnSupp => Ok(Box::new(ParamForwardTsnSupported::unmarshal(raw_param)?)),
         ParamType::SupportedExt => Ok(Box::new(ParamSupportedExtensions::unmarshal(raw_param)?)),
+        ParamType::EcnCapable => Ok(Box::new(ParamEcnCapable::unmarshal(raw_param)?)),
         ParamType::Random => Ok(Box::new(ParamRandom::unmarshal(raw_param)?)),
         ParamType::ReqHmacAlgo => Ok(Box::new(ParamRequestedHmacAlgorithm::unmarshal(raw_param)?)),
         ParamType::ChunkList => Ok(Box::new(ParamChunkList::unmarshal(raw_param)?)),
         ParamType::StateCookie => Ok(Box::new(ParamStateCookie::unmarshal(raw_param)?)),
         ParamType::HeartbeatInfo => Ok(Box::new(ParamHeartbeatInfo::unmarshal(raw_param)?)),
         ParamType::OutSsnResetReq => Ok(Box::new(ParamOutgoingResetRequest::unmarshal(raw_param)?)),
+        ParamType::IncSsnResetReq => Ok(Box::new(ParamIncomingResetRequest::unmarshal(raw_param)?)),
+        ParamType::AddOutStreamsReq | ParamType::AddIncStreamsReq => {
+            Ok(Box::new(ParamAddStreamsRequest::unmarshal(raw_param)?))
+        }
         ParamType::ReconfigResp => Ok(Box::new(ParamReconfigResponse::unmarshal(raw_param)?)),
         _ => {
             // According to RFC https://datatracker.ietf.org/doc/html/rfc4960#section-3.2.1
@@ -0,0 +1,52 @@
+use super::{param_header::*, param_type::*, *};
+
+use bytes::{Bytes, BytesMut};
+
+/// At the initialization of the association, the sender of the INIT or
+/// INIT ACK chunk MAY include this OPTIONAL parameter to inform its peer
+/// that it is able to support Explicit Congestion Notification
+/// (RFC 4960 Appendix A)
+///
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|    Parameter Type = 32768     |  Parameter Length = 4         |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub(crate) struct ParamEcnCapable;
+
+impl fmt::Display for ParamEcnCapable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header())
+    }
+}
+
+impl Param for ParamEcnCapable {
+    fn header(&self) -> ParamHeader {
+        ParamHeader {
+            typ: ParamType::EcnCapable,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let _ = ParamHeader::unmarshal(raw)?;
+        Ok(ParamEcnCapable {})
+    }
+
+    fn marshal_to(&self, buf: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(buf)?;
+        Ok(buf.len())
+    }
+
+    fn value_length(&self) -> usize {
+        0
+    }
+
+    fn clone_to(&self) -> Box<dyn Param + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
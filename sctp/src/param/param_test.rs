@@ -10,6 +10,7 @@ fn test_parse_param_type_success() -> Result<()> {
     let tests = vec![
         (Bytes::from_static(&[0x0, 0x1]), ParamType::HeartbeatInfo),
         (Bytes::from_static(&[0x0, 0xd]), ParamType::OutSsnResetReq),
+        (Bytes::from_static(&[0x80, 0x0]), ParamType::EcnCapable),
     ];
 
     for (mut binary, expected) in tests {
@@ -103,6 +104,28 @@ fn test_param_forward_tsn_supported_failure() -> Result<()> {
     Ok(())
 }
 
+///////////////////////////////////////////////////////////////////
+//param_ecn_capable_test
+///////////////////////////////////////////////////////////////////
+use super::param_ecn_capable::*;
+
+#[test]
+fn test_param_ecn_capable_success() -> Result<()> {
+    let tests = vec![(
+        Bytes::from_static(&[0x80, 0x0, 0x0, 0x4]),
+        ParamEcnCapable {},
+    )];
+
+    for (binary, parsed) in tests {
+        let actual = ParamEcnCapable::unmarshal(&binary)?;
+        assert_eq!(parsed, actual);
+        let b = actual.marshal()?;
+        assert_eq!(binary, b);
+    }
+
+    Ok(())
+}
+
 ///////////////////////////////////////////////////////////////////
 //param_outgoing_reset_request_test
 ///////////////////////////////////////////////////////////////////
@@ -164,6 +187,124 @@ fn test_param_outgoing_reset_request_failure() -> Result<()> {
     Ok(())
 }
 
+///////////////////////////////////////////////////////////////////
+//param_incoming_reset_request_test
+///////////////////////////////////////////////////////////////////
+use super::param_incoming_reset_request::*;
+
+static CHUNK_INCOMING_RESET_PARAM_A: Bytes = Bytes::from_static(&[
+    0x0, 0xe, 0x0, 0xe, 0x0, 0x0, 0x0, 0x1, 0x0, 0x4, 0x0, 0x5, 0x0, 0x6,
+]);
+static CHUNK_INCOMING_RESET_PARAM_B: Bytes =
+    Bytes::from_static(&[0x0, 0xe, 0x0, 0x8, 0x0, 0x0, 0x0, 0x1]);
+
+#[test]
+fn test_param_incoming_reset_request_success() -> Result<()> {
+    let tests = vec![
+        (
+            CHUNK_INCOMING_RESET_PARAM_A.clone(),
+            ParamIncomingResetRequest {
+                reconfig_request_sequence_number: 1,
+                stream_identifiers: vec![4, 5, 6],
+            },
+        ),
+        (
+            CHUNK_INCOMING_RESET_PARAM_B.clone(),
+            ParamIncomingResetRequest {
+                reconfig_request_sequence_number: 1,
+                stream_identifiers: vec![],
+            },
+        ),
+    ];
+
+    for (binary, parsed) in tests {
+        let actual = ParamIncomingResetRequest::unmarshal(&binary)?;
+        assert_eq!(parsed, actual);
+        let b = actual.marshal()?;
+        assert_eq!(binary, b);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_param_incoming_reset_request_failure() -> Result<()> {
+    let tests = vec![
+        ("packet too short", CHUNK_INCOMING_RESET_PARAM_A.slice(..8)),
+        ("param too short", Bytes::from_static(&[0x0, 0xe, 0x0, 0x4])),
+    ];
+
+    for (name, binary) in tests {
+        let result = ParamIncomingResetRequest::unmarshal(&binary);
+        assert!(result.is_err(), "expected unmarshal: {} to fail.", name);
+    }
+
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////
+//param_add_streams_request_test
+///////////////////////////////////////////////////////////////////
+use super::param_add_streams_request::*;
+
+static CHUNK_ADD_OUT_STREAMS_PARAM: Bytes =
+    Bytes::from_static(&[0x0, 0x11, 0x0, 0xc, 0x0, 0x0, 0x0, 0x1, 0x0, 0x10, 0x0, 0x0]);
+static CHUNK_ADD_INC_STREAMS_PARAM: Bytes =
+    Bytes::from_static(&[0x0, 0x12, 0x0, 0xc, 0x0, 0x0, 0x0, 0x2, 0x0, 0x20, 0x0, 0x0]);
+
+#[test]
+fn test_param_add_streams_request_success() -> Result<()> {
+    let tests = vec![
+        (
+            CHUNK_ADD_OUT_STREAMS_PARAM.clone(),
+            ParamAddStreamsRequest {
+                is_incoming: false,
+                reconfig_request_sequence_number: 1,
+                number_of_new_streams: 16,
+            },
+        ),
+        (
+            CHUNK_ADD_INC_STREAMS_PARAM.clone(),
+            ParamAddStreamsRequest {
+                is_incoming: true,
+                reconfig_request_sequence_number: 2,
+                number_of_new_streams: 32,
+            },
+        ),
+    ];
+
+    for (binary, parsed) in tests {
+        let actual = ParamAddStreamsRequest::unmarshal(&binary)?;
+        assert_eq!(parsed, actual);
+        let b = actual.marshal()?;
+        assert_eq!(binary, b);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_param_add_streams_request_failure() -> Result<()> {
+    let tests = vec![
+        ("packet too short", CHUNK_ADD_OUT_STREAMS_PARAM.slice(..8)),
+        (
+            "param too short",
+            Bytes::from_static(&[0x0, 0x11, 0x0, 0x8, 0x0, 0x0, 0x0, 0x1]),
+        ),
+        (
+            "wrong param type",
+            Bytes::from_static(&[0x0, 0xd, 0x0, 0xc, 0x0, 0x0, 0x0, 0x1, 0x0, 0x10, 0x0, 0x0]),
+        ),
+    ];
+
+    for (name, binary) in tests {
+        let result = ParamAddStreamsRequest::unmarshal(&binary);
+        assert!(result.is_err(), "expected unmarshal: {} to fail.", name);
+    }
+
+    Ok(())
+}
+
 ///////////////////////////////////////////////////////////////////
 //param_reconfig_response_test
 ///////////////////////////////////////////////////////////////////
@@ -1,26 +1,229 @@
 use std::{
-    collections::VecDeque,
-    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    cmp,
+    collections::{BTreeMap, VecDeque},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
-use util::sync::RwLock;
+use util::sync::Mutex;
 
 use crate::chunk::chunk_payload_data::ChunkPayloadData;
+use crate::stream::StreamScheduler;
 
 /// Basic queue for either ordered or unordered chunks.
 pub(crate) type PendingBaseQueue = VecDeque<ChunkPayloadData>;
 
-// TODO: benchmark performance between multiple Atomic+Mutex vs one Mutex<PendingQueueInternal>
+/// Unit of the virtual time of the weighted fair queueing scheduler, so that
+/// the number of bytes sent divided by the weight of a stream keeps some
+/// precision.
+const VIRTUAL_TIME_UNIT: u64 = 1 << 16;
+
+/// The chunks waiting to be sent on a stream, with its scheduling parameters.
+#[derive(Debug)]
+struct StreamQueue {
+    unordered: PendingBaseQueue,
+    ordered: PendingBaseQueue,
+    priority: u16,
+    weight: u16,
+    /// Virtual time at which the last chunk sent on the stream finished.
+    virtual_finish_time: u64,
+}
+
+impl StreamQueue {
+    fn new() -> Self {
+        StreamQueue {
+            unordered: PendingBaseQueue::new(),
+            ordered: PendingBaseQueue::new(),
+            priority: 0,
+            weight: 1,
+            virtual_finish_time: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.unordered.is_empty() && self.ordered.is_empty()
+    }
+
+    fn queue(&self, unordered: bool) -> &PendingBaseQueue {
+        if unordered {
+            &self.unordered
+        } else {
+            &self.ordered
+        }
+    }
+
+    fn queue_mut(&mut self, unordered: bool) -> &mut PendingBaseQueue {
+        if unordered {
+            &mut self.unordered
+        } else {
+            &mut self.ordered
+        }
+    }
+
+    /// Returns the time it takes to send n_bytes on the stream, in virtual time.
+    fn virtual_length(&self, n_bytes: usize) -> u64 {
+        cmp::max(n_bytes, 1) as u64 * VIRTUAL_TIME_UNIT / cmp::max(self.weight, 1) as u64
+    }
+}
 
-/// A queue for both ordered and unordered chunks.
+#[derive(Debug, Default)]
+struct PendingQueueInternal {
+    scheduler: StreamScheduler,
+    interleaving: bool,
+    /// Chunks to send by stream identifier. All the chunks share the queue of
+    /// stream 0 with the first-come, first-served scheduler.
+    queues: BTreeMap<u16, StreamQueue>,
+    /// Stream and kind (unordered or not) of the user message being sent,
+    /// while user messages are sent whole.
+    selected: Option<(u16, bool)>,
+    /// Stream the round-robin starts from.
+    next_stream: u16,
+    /// Virtual time of the weighted fair queueing scheduler, which is the
+    /// finish time of the last chunk sent.
+    virtual_time: u64,
+}
+
+impl PendingQueueInternal {
+    fn queue_key(&self, stream_identifier: u16) -> u16 {
+        if self.scheduler == StreamScheduler::FirstComeFirstServed {
+            0
+        } else {
+            stream_identifier
+        }
+    }
+
+    /// Without user message interleaving, the fragments of a user message
+    /// must be sent in sequence.
+    fn sends_messages_whole(&self) -> bool {
+        !self.interleaving || self.scheduler == StreamScheduler::FirstComeFirstServed
+    }
+
+    fn push(&mut self, c: ChunkPayloadData) {
+        let key = self.queue_key(c.stream_identifier);
+        let unordered = c.unordered;
+        let virtual_time = self.virtual_time;
+        let q = self.queues.entry(key).or_insert_with(StreamQueue::new);
+        if q.is_empty() {
+            // A stream that was idle does not get credit for the time it
+            // had nothing to send.
+            q.virtual_finish_time = cmp::max(virtual_time, q.virtual_finish_time);
+        }
+        q.queue_mut(unordered).push_back(c);
+    }
+
+    /// Returns the key of the queue and the kind (unordered or not) of the
+    /// chunk to send next.
+    fn next(&self) -> Option<(u16, bool)> {
+        if let Some(selected) = self.selected {
+            return Some(selected);
+        }
+
+        let key = match self.scheduler {
+            StreamScheduler::FirstComeFirstServed | StreamScheduler::RoundRobin => {
+                self.next_round_robin(|_| true)
+            }
+            StreamScheduler::StrictPriority => {
+                let priority = self
+                    .queues
+                    .values()
+                    .filter(|q| !q.is_empty())
+                    .map(|q| q.priority)
+                    .min()?;
+                self.next_round_robin(|q| q.priority == priority)
+            }
+            StreamScheduler::WeightedFairQueueing => self
+                .queues
+                .iter()
+                .filter_map(|(key, q)| {
+                    let c = q.unordered.front().or_else(|| q.ordered.front())?;
+                    let finish_time = q.virtual_finish_time + q.virtual_length(c.user_data.len());
+                    Some((finish_time, *key))
+                })
+                .min()
+                .map(|(_, key)| key),
+        }?;
+
+        let unordered = !self.queues.get(&key)?.unordered.is_empty();
+        Some((key, unordered))
+    }
+
+    /// Returns the first stream with chunks to send from next_stream on,
+    /// among the streams eligible.
+    fn next_round_robin(&self, eligible: impl Fn(&StreamQueue) -> bool) -> Option<u16> {
+        self.queues
+            .range(self.next_stream..)
+            .chain(self.queues.range(..self.next_stream))
+            .find(|(_, q)| !q.is_empty() && eligible(q))
+            .map(|(key, _)| *key)
+    }
+
+    fn peek(&self) -> Option<ChunkPayloadData> {
+        let (key, unordered) = self.next()?;
+        self.queues.get(&key)?.queue(unordered).front().cloned()
+    }
+
+    fn pop(&mut self, beginning_fragment: bool, unordered: bool) -> Option<ChunkPayloadData> {
+        let (key, selected_unordered) = self.next()?;
+        if selected_unordered != unordered {
+            return None;
+        }
+
+        let sends_messages_whole = self.sends_messages_whole();
+        let q = self.queues.get_mut(&key)?;
+        if q.queue(unordered).front()?.beginning_fragment != beginning_fragment {
+            return None;
+        }
+        let c = q.queue_mut(unordered).pop_front()?;
+
+        q.virtual_finish_time += q.virtual_length(c.user_data.len());
+        self.virtual_time = q.virtual_finish_time;
+
+        if c.ending_fragment {
+            self.selected = None;
+        } else if sends_messages_whole {
+            self.selected = Some((key, unordered));
+        }
+        if c.ending_fragment || !sends_messages_whole {
+            self.next_stream = key.wrapping_add(1);
+        }
+
+        Some(c)
+    }
+
+    /// Removes the fragments of the user message of c, returning the number
+    /// of chunks and bytes removed.
+    fn remove_fragments(&mut self, c: &ChunkPayloadData) -> (usize, usize) {
+        let key = self.queue_key(c.stream_identifier);
+        let q = match self.queues.get_mut(&key) {
+            Some(q) => q.queue_mut(c.unordered),
+            None => return (0, 0),
+        };
+
+        let (mut n_chunks, mut n_bytes) = (0, 0);
+        q.retain(|p| {
+            if p.is_fragment_of(c) {
+                n_chunks += 1;
+                n_bytes += p.user_data.len();
+                false
+            } else {
+                true
+            }
+        });
+
+        if n_chunks > 0 && self.selected == Some((key, c.unordered)) {
+            self.selected = None;
+        }
+
+        (n_chunks, n_bytes)
+    }
+}
+
+/// A queue for both ordered and unordered chunks, which hands them out in
+/// the order decided by its StreamScheduler.
 #[derive(Debug, Default)]
 pub(crate) struct PendingQueue {
-    unordered_queue: RwLock<PendingBaseQueue>,
-    ordered_queue: RwLock<PendingBaseQueue>,
+    internal: Mutex<PendingQueueInternal>,
     queue_len: AtomicUsize,
     n_bytes: AtomicUsize,
-    selected: AtomicBool,
-    unordered_is_selected: AtomicBool,
 }
 
 impl PendingQueue {
@@ -28,17 +231,55 @@ impl PendingQueue {
         PendingQueue::default()
     }
 
+    /// Sets the scheduler of the queue. It must be set before any chunk is
+    /// pushed.
+    pub(crate) fn set_scheduler(&self, scheduler: StreamScheduler) {
+        self.internal.lock().scheduler = scheduler;
+    }
+
+    /// Sets whether user message interleaving (RFC 8260) was negotiated.
+    pub(crate) fn set_interleaving(&self, interleaving: bool) {
+        self.internal.lock().interleaving = interleaving;
+    }
+
+    pub(crate) fn interleaving(&self) -> bool {
+        self.internal.lock().interleaving
+    }
+
+    /// Sets the priority of a stream for the strict priority scheduler.
+    pub(crate) fn set_priority(&self, stream_identifier: u16, priority: u16) {
+        let mut internal = self.internal.lock();
+        internal
+            .queues
+            .entry(stream_identifier)
+            .or_insert_with(StreamQueue::new)
+            .priority = priority;
+    }
+
+    /// Sets the weight of a stream for the weighted fair queueing scheduler.
+    pub(crate) fn set_weight(&self, stream_identifier: u16, weight: u16) {
+        let mut internal = self.internal.lock();
+        internal
+            .queues
+            .entry(stream_identifier)
+            .or_insert_with(StreamQueue::new)
+            .weight = weight;
+    }
+
+    /// Forgets the scheduling parameters of a stream that was reset, unless
+    /// it still has chunks to send.
+    pub(crate) fn remove_stream(&self, stream_identifier: u16) {
+        let mut internal = self.internal.lock();
+        if matches!(internal.queues.get(&stream_identifier), Some(q) if q.is_empty()) {
+            internal.queues.remove(&stream_identifier);
+        }
+    }
+
     /// Appends a chunk to the back of the pending queue.
     pub(crate) fn push(&self, c: ChunkPayloadData) {
         let user_data_len = c.user_data.len();
 
-        if c.unordered {
-            let mut unordered_queue = self.unordered_queue.write();
-            unordered_queue.push_back(c);
-        } else {
-            let mut ordered_queue = self.ordered_queue.write();
-            ordered_queue.push_back(c);
-        }
+        self.internal.lock().push(c);
 
         self.n_bytes.fetch_add(user_data_len, Ordering::SeqCst);
         self.queue_len.fetch_add(1, Ordering::SeqCst);
@@ -61,17 +302,15 @@ impl PendingQueue {
             .first()
             .expect("chunks to not be empty because of the above check")
             .unordered;
-        if unordered {
-            let mut unordered_queue = self.unordered_queue.write();
+        {
+            let mut internal = self.internal.lock();
             for c in chunks {
-                assert!(c.unordered, "expected all chunks to be unordered");
-                unordered_queue.push_back(c);
-            }
-        } else {
-            let mut ordered_queue = self.ordered_queue.write();
-            for c in chunks {
-                assert!(!c.unordered, "expected all chunks to be ordered");
-                ordered_queue.push_back(c);
+                if unordered {
+                    assert!(c.unordered, "expected all chunks to be unordered");
+                } else {
+                    assert!(!c.unordered, "expected all chunks to be ordered");
+                }
+                internal.push(c);
             }
         }
 
@@ -81,78 +320,16 @@ impl PendingQueue {
     }
 
     pub(crate) fn peek(&self) -> Option<ChunkPayloadData> {
-        if self.selected.load(Ordering::SeqCst) {
-            if self.unordered_is_selected.load(Ordering::SeqCst) {
-                let unordered_queue = self.unordered_queue.read();
-                return unordered_queue.get(0).cloned();
-            } else {
-                let ordered_queue = self.ordered_queue.read();
-                return ordered_queue.get(0).cloned();
-            }
-        }
-
-        let c = {
-            let unordered_queue = self.unordered_queue.read();
-            unordered_queue.get(0).cloned()
-        };
-
-        if c.is_some() {
-            return c;
-        }
-
-        let ordered_queue = self.ordered_queue.read();
-        ordered_queue.get(0).cloned()
+        self.internal.lock().peek()
     }
 
+    /// Pops the chunk returned by peek, provided it has the given flags.
     pub(crate) fn pop(
         &self,
         beginning_fragment: bool,
         unordered: bool,
     ) -> Option<ChunkPayloadData> {
-        let popped = if self.selected.load(Ordering::SeqCst) {
-            let popped = if self.unordered_is_selected.load(Ordering::SeqCst) {
-                let mut unordered_queue = self.unordered_queue.write();
-                unordered_queue.pop_front()
-            } else {
-                let mut ordered_queue = self.ordered_queue.write();
-                ordered_queue.pop_front()
-            };
-            if let Some(p) = &popped {
-                if p.ending_fragment {
-                    self.selected.store(false, Ordering::SeqCst);
-                }
-            }
-            popped
-        } else {
-            if !beginning_fragment {
-                return None;
-            }
-            if unordered {
-                let popped = {
-                    let mut unordered_queue = self.unordered_queue.write();
-                    unordered_queue.pop_front()
-                };
-                if let Some(p) = &popped {
-                    if !p.ending_fragment {
-                        self.selected.store(true, Ordering::SeqCst);
-                        self.unordered_is_selected.store(true, Ordering::SeqCst);
-                    }
-                }
-                popped
-            } else {
-                let popped = {
-                    let mut ordered_queue = self.ordered_queue.write();
-                    ordered_queue.pop_front()
-                };
-                if let Some(p) = &popped {
-                    if !p.ending_fragment {
-                        self.selected.store(true, Ordering::SeqCst);
-                        self.unordered_is_selected.store(false, Ordering::SeqCst);
-                    }
-                }
-                popped
-            }
-        };
+        let popped = self.internal.lock().pop(beginning_fragment, unordered);
 
         if let Some(p) = &popped {
             self.n_bytes.fetch_sub(p.user_data.len(), Ordering::SeqCst);
@@ -162,6 +339,17 @@ impl PendingQueue {
         popped
     }
 
+    /// Removes the fragments of the user message of c that are still
+    /// pending, returning the number of bytes removed.
+    pub(crate) fn remove_fragments(&self, c: &ChunkPayloadData) -> usize {
+        let (n_chunks, n_bytes) = self.internal.lock().remove_fragments(c);
+
+        self.n_bytes.fetch_sub(n_bytes, Ordering::SeqCst);
+        self.queue_len.fetch_sub(n_chunks, Ordering::SeqCst);
+
+        n_bytes
+    }
+
     pub(crate) fn get_num_bytes(&self) -> usize {
         self.n_bytes.load(Ordering::SeqCst)
     }
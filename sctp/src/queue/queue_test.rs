@@ -192,6 +192,7 @@ fn test_payload_queue_reset_retransmit_flag_on_ack() -> Result<()> {
 //pending_queue_test
 ///////////////////////////////////////////////////////////////////
 use super::pending_queue::*;
+use crate::stream::StreamScheduler;
 
 const NO_FRAGMENT: usize = 0;
 const FRAG_BEGIN: usize = 1;
@@ -439,6 +440,125 @@ async fn test_pending_queue_append() -> Result<()> {
     Ok(())
 }
 
+fn make_stream_chunk(tsn: u32, si: u16, frag: usize) -> ChunkPayloadData {
+    ChunkPayloadData {
+        stream_identifier: si,
+        ..make_data_chunk(tsn, false, frag)
+    }
+}
+
+fn pop_all_tsns(pq: &PendingQueue) -> Vec<u32> {
+    let mut tsns = vec![];
+    while let Some(c) = pq.peek() {
+        let result = pq.pop(c.beginning_fragment, c.unordered);
+        assert!(result.is_some(), "should not error: {}", c.tsn);
+        tsns.push(c.tsn);
+    }
+    tsns
+}
+
+#[test]
+fn test_pending_queue_remove_fragments() -> Result<()> {
+    let pq = PendingQueue::new();
+    let mut fragments = vec![
+        make_data_chunk(0, false, FRAG_BEGIN),
+        make_data_chunk(1, false, FRAG_MIDDLE),
+        make_data_chunk(2, false, FRAG_END),
+    ];
+    let abandoned = fragments[0].abandoned.clone();
+    for c in &mut fragments {
+        c.abandoned = abandoned.clone();
+    }
+    pq.append(fragments);
+    pq.push(make_data_chunk(3, false, NO_FRAGMENT));
+
+    let c = pq.peek().unwrap();
+    assert_eq!(0, c.tsn, "TSN should match");
+    assert!(
+        pq.pop(c.beginning_fragment, c.unordered).is_some(),
+        "should pop"
+    );
+
+    assert_eq!(20, pq.remove_fragments(&c), "bytes removed mismatch");
+    assert_eq!(10, pq.get_num_bytes(), "total bytes mismatch");
+    assert_eq!(1, pq.len(), "len mismatch");
+
+    // The user message sent is not selected anymore
+    assert_eq!(vec![3], pop_all_tsns(&pq), "TSNs should match");
+
+    Ok(())
+}
+
+#[test]
+fn test_pending_queue_round_robin() -> Result<()> {
+    let pq = PendingQueue::new();
+    pq.set_scheduler(StreamScheduler::RoundRobin);
+    pq.push(make_stream_chunk(0, 1, NO_FRAGMENT));
+    pq.push(make_stream_chunk(1, 1, NO_FRAGMENT));
+    pq.push(make_stream_chunk(2, 2, FRAG_BEGIN));
+    pq.push(make_stream_chunk(3, 2, FRAG_END));
+    pq.push(make_stream_chunk(4, 3, NO_FRAGMENT));
+
+    // Without interleaving, the fragments of a user message are sent in sequence
+    assert_eq!(vec![0, 2, 3, 4, 1], pop_all_tsns(&pq), "TSNs should match");
+    assert!(pq.is_empty(), "should be empty");
+
+    Ok(())
+}
+
+#[test]
+fn test_pending_queue_round_robin_interleaving() -> Result<()> {
+    let pq = PendingQueue::new();
+    pq.set_scheduler(StreamScheduler::RoundRobin);
+    pq.set_interleaving(true);
+    pq.push(make_stream_chunk(0, 1, FRAG_BEGIN));
+    pq.push(make_stream_chunk(1, 1, FRAG_MIDDLE));
+    pq.push(make_stream_chunk(2, 1, FRAG_END));
+    pq.push(make_stream_chunk(3, 2, FRAG_BEGIN));
+    pq.push(make_stream_chunk(4, 2, FRAG_END));
+
+    assert_eq!(vec![0, 3, 1, 4, 2], pop_all_tsns(&pq), "TSNs should match");
+
+    Ok(())
+}
+
+#[test]
+fn test_pending_queue_strict_priority() -> Result<()> {
+    let pq = PendingQueue::new();
+    pq.set_scheduler(StreamScheduler::StrictPriority);
+    pq.set_priority(1, 2);
+    pq.set_priority(2, 1);
+    pq.push(make_stream_chunk(0, 1, NO_FRAGMENT));
+    pq.push(make_stream_chunk(1, 2, NO_FRAGMENT));
+    pq.push(make_stream_chunk(2, 3, NO_FRAGMENT));
+    pq.push(make_stream_chunk(3, 2, NO_FRAGMENT));
+
+    // Stream 3 has the default priority 0, the highest one
+    assert_eq!(vec![2, 1, 3, 0], pop_all_tsns(&pq), "TSNs should match");
+
+    Ok(())
+}
+
+#[test]
+fn test_pending_queue_weighted_fair_queueing() -> Result<()> {
+    let pq = PendingQueue::new();
+    pq.set_scheduler(StreamScheduler::WeightedFairQueueing);
+    pq.set_weight(1, 2);
+    for i in 0..3 {
+        pq.push(make_stream_chunk(i, 1, NO_FRAGMENT));
+        pq.push(make_stream_chunk(10 + i, 2, NO_FRAGMENT));
+    }
+
+    // Stream 1 sends twice as many bytes as stream 2
+    assert_eq!(
+        vec![0, 1, 10, 2, 11, 12],
+        pop_all_tsns(&pq),
+        "TSNs should match"
+    );
+
+    Ok(())
+}
+
 ///////////////////////////////////////////////////////////////////
 //reassembly_queue_test
 ///////////////////////////////////////////////////////////////////
@@ -801,6 +921,93 @@ fn test_reassembly_queue_detect_buffer_too_short() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_reassembly_queue_read_bytes_without_copy() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+
+    let org_ppi = PayloadProtocolIdentifier::Binary;
+    let user_data = Bytes::from_static(b"0123456789");
+
+    let chunk = ChunkPayloadData {
+        payload_type: org_ppi,
+        beginning_fragment: true,
+        ending_fragment: true,
+        tsn: 123,
+        stream_sequence_number: 0,
+        user_data: user_data.clone(),
+        ..Default::default()
+    };
+
+    let complete = rq.push(chunk);
+    assert!(complete, "the set should be complete");
+
+    let (data, ppi) = rq.read_bytes()?;
+    assert_eq!(ppi, org_ppi, "should have valid ppi");
+    assert_eq!(user_data, data, "data should match");
+    assert_eq!(
+        user_data.as_ptr(),
+        data.as_ptr(),
+        "data should not have been copied"
+    );
+    assert_eq!(0, rq.get_num_bytes(), "num bytes mismatch");
+
+    let result = rq.read_bytes();
+    assert_eq!(
+        Err(Error::ErrTryAgain),
+        result.map(|_| ()),
+        "nothing to read"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_reassembly_queue_read_bytes_fragments() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+
+    let org_ppi = PayloadProtocolIdentifier::Binary;
+
+    for ssn in 0..2u16 {
+        let tsn = 10 * (ssn as u32 + 1);
+        let chunks = vec![
+            ChunkPayloadData {
+                payload_type: org_ppi,
+                beginning_fragment: true,
+                tsn,
+                stream_sequence_number: ssn,
+                user_data: Bytes::from_static(b"ABC"),
+                ..Default::default()
+            },
+            ChunkPayloadData {
+                payload_type: org_ppi,
+                ending_fragment: true,
+                tsn: tsn + 1,
+                stream_sequence_number: ssn,
+                user_data: Bytes::from_static(b"DEFG"),
+                ..Default::default()
+            },
+        ];
+        for chunk in chunks {
+            rq.push(chunk);
+        }
+    }
+    assert_eq!(14, rq.get_num_bytes(), "num bytes mismatch");
+
+    let (first, ppi) = rq.read_bytes()?;
+    assert_eq!(ppi, org_ppi, "should have valid ppi");
+    assert_eq!(&first[..], b"ABCDEFG", "data should match");
+    assert_eq!(7, rq.get_num_bytes(), "num bytes mismatch");
+
+    // The messages are reassembled into the same buffer, without
+    // overwriting the messages still in use.
+    let (second, _) = rq.read_bytes()?;
+    assert_eq!(&second[..], b"ABCDEFG", "data should match");
+    assert_eq!(&first[..], b"ABCDEFG", "data should not be overwritten");
+    assert_eq!(0, rq.get_num_bytes(), "num bytes mismatch");
+
+    Ok(())
+}
+
 #[test]
 fn test_reassembly_queue_forward_tsn_for_ordered_framents() -> Result<()> {
     let mut rq = ReassemblyQueue::new(0);
@@ -922,6 +1129,127 @@ fn test_reassembly_queue_forward_tsn_for_unordered_framents() -> Result<()> {
     Ok(())
 }
 
+fn make_idata_chunk(
+    tsn: u32,
+    unordered: bool,
+    mid: u32,
+    fsn: u32,
+    ending_fragment: bool,
+    user_data: &'static [u8],
+) -> ChunkPayloadData {
+    ChunkPayloadData {
+        payload_type: PayloadProtocolIdentifier::Binary,
+        interleaved: true,
+        unordered,
+        beginning_fragment: fsn == 0,
+        ending_fragment,
+        tsn,
+        message_identifier: mid,
+        fragment_sequence_number: fsn,
+        user_data: Bytes::from_static(user_data),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_reassembly_queue_interleaved_fragments() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+
+    // Fragments of ordered messages 0 and 1 and of an unordered message,
+    // interleaved by the sender.
+    let chunks = vec![
+        (make_idata_chunk(1, false, 1, 0, false, b"AB"), false),
+        (make_idata_chunk(2, false, 0, 0, false, b"12"), false),
+        (make_idata_chunk(3, true, 0, 0, false, b"xy"), false),
+        (make_idata_chunk(4, false, 1, 1, true, b"CD"), true),
+        (make_idata_chunk(5, true, 0, 1, true, b"z"), true),
+        (make_idata_chunk(6, false, 0, 1, true, b"34"), true),
+    ];
+    for (chunk, expected) in chunks {
+        let complete = rq.push(chunk);
+        assert_eq!(expected, complete, "unexpected completion");
+    }
+    assert_eq!(11, rq.get_num_bytes(), "num bytes mismatch");
+
+    let mut buf = vec![0u8; 16];
+    let expects: Vec<&[u8]> = vec![b"xyz", b"1234", b"ABCD"];
+    for exp in expects {
+        let (n, ppi) = rq.read(&mut buf)?;
+        assert_eq!(
+            ppi,
+            PayloadProtocolIdentifier::Binary,
+            "should have valid ppi"
+        );
+        assert_eq!(&buf[..n], exp, "data should match");
+    }
+    assert_eq!(0, rq.get_num_bytes(), "num bytes mismatch");
+    assert_eq!(2, rq.next_mid, "next MID mismatch");
+
+    Ok(())
+}
+
+#[test]
+fn test_reassembly_queue_interleaved_missing_fragment() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+
+    let complete = rq.push(make_idata_chunk(1, false, 0, 0, false, b"AB"));
+    assert!(!complete, "chunk set should not be complete yet");
+    let complete = rq.push(make_idata_chunk(3, false, 0, 2, true, b"EF"));
+    assert!(!complete, "chunk set should not be complete yet");
+    assert!(!rq.is_readable(), "should not be readable");
+
+    let complete = rq.push(make_idata_chunk(2, false, 0, 1, false, b"CD"));
+    assert!(complete, "chunk set should be complete");
+
+    let mut buf = vec![0u8; 16];
+    let (n, _) = rq.read(&mut buf)?;
+    assert_eq!(&buf[..n], b"ABCDEF", "data should match");
+
+    Ok(())
+}
+
+#[test]
+fn test_reassembly_queue_forward_tsn_for_ordered_messages() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+
+    rq.push(make_idata_chunk(1, false, 0, 0, false, b"ABC"));
+    rq.push(make_idata_chunk(2, false, 1, 0, true, b"DEF"));
+    assert_eq!(6, rq.get_num_bytes(), "num bytes mismatch");
+    assert!(!rq.is_readable(), "should not be readable");
+
+    rq.forward_tsn_for_ordered_messages(0);
+
+    assert_eq!(1, rq.ordered.len(), "there should be one message left");
+    assert_eq!(3, rq.get_num_bytes(), "num bytes mismatch");
+    assert_eq!(1, rq.next_mid, "next MID mismatch");
+
+    let mut buf = vec![0u8; 16];
+    let (n, _) = rq.read(&mut buf)?;
+    assert_eq!(&buf[..n], b"DEF", "data should match");
+
+    Ok(())
+}
+
+#[test]
+fn test_reassembly_queue_forward_tsn_for_unordered_messages() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+
+    rq.push(make_idata_chunk(1, true, 3, 0, false, b"ABC"));
+    rq.push(make_idata_chunk(2, true, 4, 0, false, b"DEF"));
+    assert_eq!(6, rq.get_num_bytes(), "num bytes mismatch");
+
+    rq.forward_tsn_for_unordered_messages(3);
+
+    assert_eq!(
+        1,
+        rq.unordered_messages.len(),
+        "there should be one message kept"
+    );
+    assert_eq!(3, rq.get_num_bytes(), "num bytes mismatch");
+
+    Ok(())
+}
+
 #[test]
 fn test_chunk_set_empty_chunk_set() -> Result<()> {
     let cset = ChunkSet::new(0, PayloadProtocolIdentifier::default());
@@ -951,6 +1279,7 @@ fn test_chunk_set_push_dup_chunks_to_chunk_set() -> Result<()> {
 fn test_chunk_set_incomplete_chunk_set_no_beginning() -> Result<()> {
     let cset = ChunkSet {
         ssn: 0,
+        mid: 0,
         ppi: PayloadProtocolIdentifier::default(),
         chunks: vec![],
     };
@@ -965,6 +1294,7 @@ fn test_chunk_set_incomplete_chunk_set_no_beginning() -> Result<()> {
 fn test_chunk_set_incomplete_chunk_set_no_contiguous_tsn() -> Result<()> {
     let cset = ChunkSet {
         ssn: 0,
+        mid: 0,
         ppi: PayloadProtocolIdentifier::default(),
         chunks: vec![
             ChunkPayloadData {
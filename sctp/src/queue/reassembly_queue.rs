@@ -3,8 +3,13 @@ use crate::util::*;
 
 use crate::error::{Error, Result};
 
+use bytes::{Bytes, BytesMut};
 use std::cmp::Ordering;
 
+/// Size of the buffer the fragmented user messages are reassembled into. The
+/// buffer is reused once the messages read from it are dropped.
+const REASSEMBLY_BUFFER_SIZE: usize = 64 * 1024;
+
 fn sort_chunks_by_tsn(c: &mut [ChunkPayloadData]) {
     c.sort_by(|a, b| {
         if sna32lt(a.tsn, b.tsn) {
@@ -15,6 +20,26 @@ fn sort_chunks_by_tsn(c: &mut [ChunkPayloadData]) {
     });
 }
 
+fn sort_chunks_by_fsn(c: &mut [ChunkPayloadData]) {
+    c.sort_by(|a, b| {
+        if sna32lt(a.fragment_sequence_number, b.fragment_sequence_number) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    });
+}
+
+fn sort_chunks_by_mid(c: &mut [ChunkSet]) {
+    c.sort_by(|a, b| {
+        if sna32lt(a.mid, b.mid) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    });
+}
+
 fn sort_chunks_by_ssn(c: &mut [ChunkSet]) {
     c.sort_by(|a, b| {
         if sna16lt(a.ssn, b.ssn) {
@@ -25,11 +50,14 @@ fn sort_chunks_by_ssn(c: &mut [ChunkSet]) {
     });
 }
 
-/// chunkSet is a set of chunks that share the same SSN
+/// chunkSet is a set of chunks that share the same SSN, or the same MID
+/// with I-DATA chunks
 #[derive(Debug, Clone)]
 pub(crate) struct ChunkSet {
     /// used only with the ordered chunks
     pub(crate) ssn: u16,
+    /// used only with I-DATA chunks
+    pub(crate) mid: u32,
     pub(crate) ppi: PayloadProtocolIdentifier,
     pub(crate) chunks: Vec<ChunkPayloadData>,
 }
@@ -38,11 +66,26 @@ impl ChunkSet {
     pub(crate) fn new(ssn: u16, ppi: PayloadProtocolIdentifier) -> Self {
         ChunkSet {
             ssn,
+            mid: 0,
             ppi,
             chunks: vec![],
         }
     }
 
+    pub(crate) fn with_mid(mid: u32, ppi: PayloadProtocolIdentifier) -> Self {
+        ChunkSet {
+            ssn: 0,
+            mid,
+            ppi,
+            chunks: vec![],
+        }
+    }
+
+    /// is_interleaved tells whether the set is made of I-DATA chunks.
+    pub(crate) fn is_interleaved(&self) -> bool {
+        matches!(self.chunks.first(), Some(c) if c.interleaved)
+    }
+
     pub(crate) fn push(&mut self, chunk: ChunkPayloadData) -> bool {
         // check if dup
         for c in &self.chunks {
@@ -51,9 +94,19 @@ impl ChunkSet {
             }
         }
 
+        // Only the first fragment of an I-DATA message carries the PPI
+        if chunk.interleaved && chunk.beginning_fragment {
+            self.ppi = chunk.payload_type;
+        }
+
         // append and sort
+        let interleaved = chunk.interleaved;
         self.chunks.push(chunk);
-        sort_chunks_by_tsn(&mut self.chunks);
+        if interleaved {
+            sort_chunks_by_fsn(&mut self.chunks);
+        } else {
+            sort_chunks_by_tsn(&mut self.chunks);
+        }
 
         // Check if we now have a complete set
         self.is_complete()
@@ -64,7 +117,8 @@ impl ChunkSet {
         //   0. Has at least one chunk.
         //   1. Begins with beginningFragment set to true
         //   2. Ends with endingFragment set to true
-        //   3. TSN monotinically increase by 1 from beginning to end
+        //   3. TSN monotinically increase by 1 from beginning to end, or FSN
+        //      with I-DATA chunks
 
         // 0.
         let n_chunks = self.chunks.len();
@@ -83,6 +137,18 @@ impl ChunkSet {
         }
 
         // 3.
+        if self.is_interleaved() {
+            // From RFC 8260 Sec 2.1:
+            //   The FSN MUST start at 0 for the first fragment and MUST be
+            //   incremented by 1 for each following fragment of the user
+            //   message.
+            return self
+                .chunks
+                .iter()
+                .enumerate()
+                .all(|(i, c)| c.fragment_sequence_number == i as u32);
+        }
+
         let mut last_tsn = 0u32;
         for (i, c) in self.chunks.iter().enumerate() {
             if i > 0 {
@@ -108,12 +174,18 @@ impl ChunkSet {
 #[derive(Default, Debug)]
 pub(crate) struct ReassemblyQueue {
     pub(crate) si: u16,
-    pub(crate) next_ssn: u16,
     /// expected SSN for next ordered chunk
+    pub(crate) next_ssn: u16,
+    /// expected MID for next ordered I-DATA chunk
+    pub(crate) next_mid: u32,
     pub(crate) ordered: Vec<ChunkSet>,
     pub(crate) unordered: Vec<ChunkSet>,
     pub(crate) unordered_chunks: Vec<ChunkPayloadData>,
+    /// incomplete unordered I-DATA messages
+    pub(crate) unordered_messages: Vec<ChunkSet>,
     pub(crate) n_bytes: usize,
+    /// buffer the fragmented user messages are reassembled into by read_bytes
+    buffer: BytesMut,
 }
 
 impl ReassemblyQueue {
@@ -126,10 +198,13 @@ impl ReassemblyQueue {
         ReassemblyQueue {
             si,
             next_ssn: 0, // From RFC 4960 Sec 6.5:
+            next_mid: 0, // From RFC 8260 Sec 2.1
             ordered: vec![],
             unordered: vec![],
             unordered_chunks: vec![],
+            unordered_messages: vec![],
             n_bytes: 0,
+            buffer: BytesMut::new(),
         }
     }
 
@@ -138,6 +213,10 @@ impl ReassemblyQueue {
             return false;
         }
 
+        if chunk.interleaved {
+            return self.push_interleaved(chunk);
+        }
+
         if chunk.unordered {
             // First, insert into unordered_chunks array
             //atomic.AddUint64(&r.n_bytes, uint64(len(chunk.userData)))
@@ -181,6 +260,63 @@ impl ReassemblyQueue {
         }
     }
 
+    /// push_interleaved pushes an I-DATA chunk. Fragments of different user
+    /// messages may arrive interleaved, so they are grouped by MID rather than
+    /// by contiguous TSN.
+    fn push_interleaved(&mut self, chunk: ChunkPayloadData) -> bool {
+        let mid = chunk.message_identifier;
+
+        if chunk.unordered {
+            self.n_bytes += chunk.user_data.len();
+
+            let i = match self.unordered_messages.iter().position(|s| s.mid == mid) {
+                Some(i) => i,
+                None => {
+                    self.unordered_messages
+                        .push(ChunkSet::with_mid(mid, chunk.payload_type));
+                    self.unordered_messages.len() - 1
+                }
+            };
+
+            if self.unordered_messages[i].push(chunk) {
+                let cset = self.unordered_messages.remove(i);
+                self.unordered.push(cset);
+                return true;
+            }
+
+            false
+        } else {
+            if sna32lt(mid, self.next_mid) {
+                return false;
+            }
+
+            self.n_bytes += chunk.user_data.len();
+
+            for s in &mut self.ordered {
+                if s.mid == mid {
+                    return s.push(chunk);
+                }
+            }
+
+            let mut cset = ChunkSet::with_mid(mid, chunk.payload_type);
+            let ok = cset.push(chunk);
+            self.ordered.push(cset);
+            sort_chunks_by_mid(&mut self.ordered);
+
+            ok
+        }
+    }
+
+    /// is_next tells whether the ordered chunk set is not ahead of the next
+    /// expected SSN, or MID with I-DATA chunks.
+    fn is_next(&self, cset: &ChunkSet) -> bool {
+        if cset.is_interleaved() {
+            sna32lte(cset.mid, self.next_mid)
+        } else {
+            sna16lte(cset.ssn, self.next_ssn)
+        }
+    }
+
     pub(crate) fn find_complete_unordered_chunk_set(&mut self) -> Option<ChunkSet> {
         let mut start_idx = -1isize;
         let mut n_chunks = 0usize;
@@ -246,14 +382,15 @@ impl ReassemblyQueue {
         // Check ordered sets
         if !self.ordered.is_empty() {
             let cset = &self.ordered[0];
-            if cset.is_complete() && sna16lte(cset.ssn, self.next_ssn) {
+            if cset.is_complete() && self.is_next(cset) {
                 return true;
             }
         }
         false
     }
 
-    pub(crate) fn read(&mut self, buf: &mut [u8]) -> Result<(usize, PayloadProtocolIdentifier)> {
+    /// pop_readable removes the next complete user message to read.
+    fn pop_readable(&mut self) -> Result<ChunkSet> {
         // Check unordered first
         let cset = if !self.unordered.is_empty() {
             self.unordered.remove(0)
@@ -263,10 +400,14 @@ impl ReassemblyQueue {
             if !cset.is_complete() {
                 return Err(Error::ErrTryAgain);
             }
-            if sna16gt(cset.ssn, self.next_ssn) {
+            if !self.is_next(cset) {
                 return Err(Error::ErrTryAgain);
             }
-            if cset.ssn == self.next_ssn {
+            if cset.is_interleaved() {
+                if cset.mid == self.next_mid {
+                    self.next_mid = self.next_mid.wrapping_add(1);
+                }
+            } else if cset.ssn == self.next_ssn {
                 self.next_ssn += 1;
             }
             self.ordered.remove(0)
@@ -274,6 +415,12 @@ impl ReassemblyQueue {
             return Err(Error::ErrTryAgain);
         };
 
+        Ok(cset)
+    }
+
+    pub(crate) fn read(&mut self, buf: &mut [u8]) -> Result<(usize, PayloadProtocolIdentifier)> {
+        let cset = self.pop_readable()?;
+
         // Concat all fragments into the buffer
         let mut n_written = 0;
         let mut err = None;
@@ -297,6 +444,33 @@ impl ReassemblyQueue {
         }
     }
 
+    /// read_bytes reads the next user message without copying it when it was
+    /// not fragmented: the message then shares the memory of the packet it
+    /// was received in. The fragments of other messages are concatenated into
+    /// a reused buffer.
+    pub(crate) fn read_bytes(&mut self) -> Result<(Bytes, PayloadProtocolIdentifier)> {
+        let cset = self.pop_readable()?;
+
+        let n_bytes = cset.chunks.iter().fold(0, |acc, c| acc + c.user_data.len());
+        self.subtract_num_bytes(n_bytes);
+
+        if cset.chunks.len() == 1 {
+            return Ok((cset.chunks[0].user_data.clone(), cset.ppi));
+        }
+
+        if self.buffer.capacity() < n_bytes {
+            // Reclaims the memory of the buffer if the messages previously
+            // read from it were dropped.
+            self.buffer
+                .reserve(std::cmp::max(n_bytes, REASSEMBLY_BUFFER_SIZE));
+        }
+        for c in &cset.chunks {
+            self.buffer.extend_from_slice(&c.user_data);
+        }
+
+        Ok((self.buffer.split().freeze(), cset.ppi))
+    }
+
     /// Use last_ssn to locate a chunkSet then remove it if the set has
     /// not been complete
     pub(crate) fn forward_tsn_for_ordered(&mut self, last_ssn: u16) {
@@ -318,6 +492,43 @@ impl ReassemblyQueue {
         }
     }
 
+    /// Use last_mid to locate the ordered I-DATA messages abandoned by the
+    /// sender, then remove them if they have not been complete
+    pub(crate) fn forward_tsn_for_ordered_messages(&mut self, last_mid: u32) {
+        let num_bytes = self
+            .ordered
+            .iter()
+            .filter(|s| sna32lte(s.mid, last_mid) && !s.is_complete())
+            .fold(0, |n, s| {
+                n + s.chunks.iter().fold(0, |acc, c| acc + c.user_data.len())
+            });
+        self.subtract_num_bytes(num_bytes);
+
+        self.ordered
+            .retain(|s| !sna32lte(s.mid, last_mid) || s.is_complete());
+
+        // Finally, forward next_mid
+        if sna32lte(self.next_mid, last_mid) {
+            self.next_mid = last_mid.wrapping_add(1);
+        }
+    }
+
+    /// Remove the incomplete unordered I-DATA messages abandoned by the
+    /// sender, up to last_mid.
+    pub(crate) fn forward_tsn_for_unordered_messages(&mut self, last_mid: u32) {
+        let num_bytes = self
+            .unordered_messages
+            .iter()
+            .filter(|s| sna32lte(s.mid, last_mid))
+            .fold(0, |n, s| {
+                n + s.chunks.iter().fold(0, |acc, c| acc + c.user_data.len())
+            });
+        self.subtract_num_bytes(num_bytes);
+
+        self.unordered_messages
+            .retain(|s| !sna32lte(s.mid, last_mid));
+    }
+
     /// Remove all fragments in the unordered sets that contains chunks
     /// equal to or older than `new_cumulative_tsn`.
     /// We know all sets in the r.unordered are complete ones.
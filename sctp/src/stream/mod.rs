@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod stream_test;
 
+use crate::association::send_buffer::SendBuffer;
 use crate::association::AssociationState;
 use crate::chunk::chunk_payload_data::{ChunkPayloadData, PayloadProtocolIdentifier};
 use crate::error::{Error, Result};
@@ -8,8 +9,9 @@ use crate::queue::pending_queue::PendingQueue;
 use crate::queue::reassembly_queue::ReassemblyQueue;
 
 use arc_swap::ArcSwapOption;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use std::{
+    collections::VecDeque,
     fmt,
     future::Future,
     io,
@@ -62,24 +64,77 @@ impl From<u8> for ReliabilityType {
     }
 }
 
+/// StreamScheduler decides which stream the association sends from next.
+///
+/// Without user message interleaving (RFC 8260), a user message is always
+/// sent whole, so the schedulers only pick the stream of the next message.
+/// With it, they pick the stream of every DATA chunk, so that a large
+/// message on one stream does not hold back the messages of the others.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub enum StreamScheduler {
+    /// FirstComeFirstServed sends the messages in the order they were
+    /// written, unordered messages first. It never interleaves messages.
+    FirstComeFirstServed = 0,
+    /// RoundRobin serves the streams with data to send in turn.
+    RoundRobin = 1,
+    /// WeightedFairQueueing shares the bandwidth between the streams with
+    /// data to send in proportion to their weight.
+    WeightedFairQueueing = 2,
+    /// StrictPriority serves the streams with the lowest priority value
+    /// first, and the streams with the same priority in turn.
+    StrictPriority = 3,
+}
+
+impl Default for StreamScheduler {
+    fn default() -> Self {
+        StreamScheduler::FirstComeFirstServed
+    }
+}
+
+impl fmt::Display for StreamScheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            StreamScheduler::FirstComeFirstServed => "FirstComeFirstServed",
+            StreamScheduler::RoundRobin => "RoundRobin",
+            StreamScheduler::WeightedFairQueueing => "WeightedFairQueueing",
+            StreamScheduler::StrictPriority => "StrictPriority",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 pub type OnBufferedAmountLowFn =
     Box<dyn (FnMut() -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
 
+pub type OnAbandonedFn = Box<
+    dyn (FnMut(PayloadProtocolIdentifier) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
 // TODO: benchmark performance between multiple Atomic+Mutex vs one Mutex<StreamInternal>
 
 /// Stream represents an SCTP stream
 #[derive(Default)]
 pub struct Stream {
-    pub(crate) max_payload_size: u32,
+    pub(crate) max_payload_size: Arc<AtomicU32>, // clone from association
     pub(crate) max_message_size: Arc<AtomicU32>, // clone from association
     pub(crate) state: Arc<AtomicU8>,             // clone from association
     pub(crate) awake_write_loop_ch: Option<Arc<mpsc::Sender<()>>>,
     pub(crate) pending_queue: Arc<PendingQueue>,
+    pub(crate) send_buffer: Arc<SendBuffer>, // clone from association
 
     pub(crate) stream_identifier: u16,
     pub(crate) default_payload_type: AtomicU32, //PayloadProtocolIdentifier,
     pub(crate) reassembly_queue: Mutex<ReassemblyQueue>,
+    /// unordered messages which were not fragmented, delivered to the reader
+    /// without going through the reassembly_queue
+    pub(crate) unordered_ready: std::sync::Mutex<VecDeque<ChunkPayloadData>>,
+    pub(crate) unordered_ready_bytes: AtomicUsize,
     pub(crate) sequence_number: AtomicU16,
+    pub(crate) message_identifier: AtomicU32,
+    pub(crate) unordered_message_identifier: AtomicU32,
     pub(crate) read_notifier: Notify,
     pub(crate) read_shutdown: AtomicBool,
     pub(crate) write_shutdown: AtomicBool,
@@ -88,7 +143,9 @@ pub struct Stream {
     pub(crate) reliability_value: AtomicU32,
     pub(crate) buffered_amount: AtomicUsize,
     pub(crate) buffered_amount_low: AtomicUsize,
+    pub(crate) buffered_amount_low_notifier: Notify,
     pub(crate) on_buffered_amount_low: ArcSwapOption<Mutex<OnBufferedAmountLowFn>>,
+    pub(crate) on_abandoned: ArcSwapOption<Mutex<OnAbandonedFn>>,
     pub(crate) name: String,
 }
 
@@ -102,7 +159,13 @@ impl fmt::Debug for Stream {
             .field("stream_identifier", &self.stream_identifier)
             .field("default_payload_type", &self.default_payload_type)
             .field("reassembly_queue", &self.reassembly_queue)
+            .field("unordered_ready_bytes", &self.unordered_ready_bytes)
             .field("sequence_number", &self.sequence_number)
+            .field("message_identifier", &self.message_identifier)
+            .field(
+                "unordered_message_identifier",
+                &self.unordered_message_identifier,
+            )
             .field("read_shutdown", &self.read_shutdown)
             .field("write_shutdown", &self.write_shutdown)
             .field("unordered", &self.unordered)
@@ -119,11 +182,12 @@ impl Stream {
     pub(crate) fn new(
         name: String,
         stream_identifier: u16,
-        max_payload_size: u32,
+        max_payload_size: Arc<AtomicU32>,
         max_message_size: Arc<AtomicU32>,
         state: Arc<AtomicU8>,
         awake_write_loop_ch: Option<Arc<mpsc::Sender<()>>>,
         pending_queue: Arc<PendingQueue>,
+        send_buffer: Arc<SendBuffer>,
     ) -> Self {
         Stream {
             max_payload_size,
@@ -131,11 +195,16 @@ impl Stream {
             state,
             awake_write_loop_ch,
             pending_queue,
+            send_buffer,
 
             stream_identifier,
             default_payload_type: AtomicU32::new(0), //PayloadProtocolIdentifier::Unknown,
             reassembly_queue: Mutex::new(ReassemblyQueue::new(stream_identifier)),
+            unordered_ready: std::sync::Mutex::new(VecDeque::new()),
+            unordered_ready_bytes: AtomicUsize::new(0),
             sequence_number: AtomicU16::new(0),
+            message_identifier: AtomicU32::new(0),
+            unordered_message_identifier: AtomicU32::new(0),
             read_notifier: Notify::new(),
             read_shutdown: AtomicBool::new(false),
             write_shutdown: AtomicBool::new(false),
@@ -144,7 +213,9 @@ impl Stream {
             reliability_value: AtomicU32::new(0),
             buffered_amount: AtomicUsize::new(0),
             buffered_amount_low: AtomicUsize::new(0),
+            buffered_amount_low_notifier: Notify::new(),
             on_buffered_amount_low: ArcSwapOption::empty(),
+            on_abandoned: ArcSwapOption::empty(),
             name,
         }
     }
@@ -175,6 +246,22 @@ impl Stream {
         self.reliability_value.store(rel_val, Ordering::SeqCst);
     }
 
+    /// set_priority sets the priority of this stream for the
+    /// `StreamScheduler::StrictPriority` scheduler. Streams with a lower value
+    /// are served first. Defaults to 0.
+    pub fn set_priority(&self, priority: u16) {
+        self.pending_queue
+            .set_priority(self.stream_identifier, priority);
+    }
+
+    /// set_weight sets the weight of this stream for the
+    /// `StreamScheduler::WeightedFairQueueing` scheduler. Streams with data to
+    /// send share the bandwidth in proportion to their weight. Defaults to 1.
+    pub fn set_weight(&self, weight: u16) {
+        self.pending_queue
+            .set_weight(self.stream_identifier, weight);
+    }
+
     /// Reads a packet of len(p) bytes, dropping the Payload Protocol Identifier.
     ///
     /// Returns `Error::ErrShortBuffer` if `p` is too short.
@@ -194,7 +281,15 @@ impl Stream {
                 return Ok((0, PayloadProtocolIdentifier::Unknown));
             }
 
-            let result = {
+            let result = if let Some(c) = self.pop_unordered_ready() {
+                let n = std::cmp::min(c.user_data.len(), p.len());
+                p[..n].copy_from_slice(&c.user_data[..n]);
+                if n < c.user_data.len() {
+                    Err(Error::ErrShortBuffer)
+                } else {
+                    Ok((n, c.payload_type))
+                }
+            } else {
                 let mut reassembly_queue = self.reassembly_queue.lock().await;
                 reassembly_queue.read(p)
             };
@@ -209,7 +304,51 @@ impl Stream {
         }
     }
 
+    /// Reads the next message and returns it with the associated Payload Protocol Identifier.
+    /// Unlike `read_sctp`, it does not copy messages which were not fragmented: they share the
+    /// memory of the packet they were received in.
+    ///
+    /// Returns `(Bytes::new(), PayloadProtocolIdentifier::Unknown)` if the reading half of this stream is shutdown or it (the stream) was reset.
+    pub async fn read_sctp_bytes(&self) -> Result<(Bytes, PayloadProtocolIdentifier)> {
+        loop {
+            if self.read_shutdown.load(Ordering::SeqCst) {
+                return Ok((Bytes::new(), PayloadProtocolIdentifier::Unknown));
+            }
+
+            let result = if let Some(c) = self.pop_unordered_ready() {
+                Ok((c.user_data, c.payload_type))
+            } else {
+                let mut reassembly_queue = self.reassembly_queue.lock().await;
+                reassembly_queue.read_bytes()
+            };
+
+            match result {
+                Ok(_) => return result,
+                Err(_) => {
+                    // wait for the next chunk to become available
+                    self.read_notifier.notified().await;
+                }
+            }
+        }
+    }
+
     pub(crate) async fn handle_data(&self, pd: ChunkPayloadData) {
+        // Unordered messages which were not fragmented are complete as they
+        // are and need no ordering: hand them to the reader right away.
+        if pd.unordered
+            && pd.beginning_fragment
+            && pd.ending_fragment
+            && pd.stream_identifier == self.stream_identifier
+        {
+            self.unordered_ready_bytes
+                .fetch_add(pd.user_data.len(), Ordering::SeqCst);
+            if let Ok(mut unordered_ready) = self.unordered_ready.lock() {
+                unordered_ready.push_back(pd);
+            }
+            self.read_notifier.notify_one();
+            return;
+        }
+
         let readable = {
             let mut reassembly_queue = self.reassembly_queue.lock().await;
             if reassembly_queue.push(pd) {
@@ -266,6 +405,25 @@ impl Stream {
         }
     }
 
+    /// handle_iforward_tsn removes the ordered or unordered user messages up to
+    /// last_mid, abandoned by the sender, from the reassembly_queue.
+    pub(crate) async fn handle_iforward_tsn(&self, unordered: bool, last_mid: u32) {
+        let readable = {
+            let mut reassembly_queue = self.reassembly_queue.lock().await;
+            if unordered {
+                reassembly_queue.forward_tsn_for_unordered_messages(last_mid);
+            } else {
+                reassembly_queue.forward_tsn_for_ordered_messages(last_mid);
+            }
+            reassembly_queue.is_readable()
+        };
+
+        // Notify the reader asynchronously if there's a data chunk to read.
+        if readable {
+            self.read_notifier.notify_one();
+        }
+    }
+
     /// Writes `p` to the DTLS connection with the default Payload Protocol Identifier.
     ///
     /// Returns an error if the write half of this stream is shutdown or `p` is too large.
@@ -277,11 +435,28 @@ impl Stream {
     ///
     /// Returns an error if the write half of this stream is shutdown or `p` is too large.
     pub fn write_sctp(&self, p: &Bytes, ppi: PayloadProtocolIdentifier) -> Result<usize> {
+        self.write_sctp_vectored(std::slice::from_ref(p), ppi)
+    }
+
+    /// Writes the concatenation of `bufs` to the DTLS connection as a single user message
+    /// with the given Payload Protocol Identifier.
+    ///
+    /// The buffers are handed down without being joined first: a DATA chunk only copies
+    /// its user data when it straddles two of them, so e.g. a small header followed by a
+    /// large payload costs at most one copied fragment.
+    ///
+    /// Returns an error if the write half of this stream is shutdown or the message is too large.
+    pub fn write_sctp_vectored(
+        &self,
+        bufs: &[Bytes],
+        ppi: PayloadProtocolIdentifier,
+    ) -> Result<usize> {
         if self.write_shutdown.load(Ordering::SeqCst) {
             return Err(Error::ErrStreamClosed);
         }
 
-        if p.len() > self.max_message_size.load(Ordering::SeqCst) as usize {
+        let len = bufs.iter().map(|b| b.len()).sum::<usize>();
+        if len > self.max_message_size.load(Ordering::SeqCst) as usize {
             return Err(Error::ErrOutboundPacketTooLarge);
         }
 
@@ -294,15 +469,56 @@ impl Stream {
             _ => {}
         };
 
-        let chunks = self.packetize(p, ppi);
+        let chunks = self.packetize(bufs, len, ppi);
         self.send_payload_data(chunks)?;
 
-        Ok(p.len())
+        Ok(len)
     }
 
-    fn packetize(&self, raw: &Bytes, ppi: PayloadProtocolIdentifier) -> Vec<ChunkPayloadData> {
+    /// is_writable tells whether the association has room in its send buffer
+    /// for more data to be written to this stream.
+    pub fn is_writable(&self) -> bool {
+        self.send_buffer.is_writable()
+    }
+
+    /// Waits until the association has room in its send buffer for more data
+    /// to be written to this stream, instead of polling the buffered amount.
+    ///
+    /// Returns an error if the write half of this stream is shutdown or the
+    /// association is shutting down.
+    pub async fn writable(&self) -> Result<()> {
+        loop {
+            let notified = self.send_buffer.notified();
+            if self.write_shutdown.load(Ordering::SeqCst) {
+                return Err(Error::ErrStreamClosed);
+            }
+
+            let state: AssociationState = self.state.load(Ordering::SeqCst).into();
+            match state {
+                AssociationState::Closed
+                | AssociationState::ShutdownSent
+                | AssociationState::ShutdownAckSent
+                | AssociationState::ShutdownPending
+                | AssociationState::ShutdownReceived => return Err(Error::ErrStreamClosed),
+                _ => {}
+            };
+
+            if self.send_buffer.is_writable() {
+                return Ok(());
+            }
+            notified.await;
+        }
+    }
+
+    fn packetize(
+        &self,
+        bufs: &[Bytes],
+        len: usize,
+        ppi: PayloadProtocolIdentifier,
+    ) -> Vec<ChunkPayloadData> {
         let mut i = 0;
-        let mut remaining = raw.len();
+        let mut remaining = len;
+        let (mut index, mut offset) = (0, 0);
 
         // From draft-ietf-rtcweb-data-protocol-09, section 6:
         //   All Data Channel Establishment Protocol messages MUST be sent using
@@ -310,16 +526,31 @@ impl Stream {
         let unordered =
             ppi != PayloadProtocolIdentifier::Dcep && self.unordered.load(Ordering::SeqCst);
 
+        // From RFC 8260 Sec 2.1:
+        //   The MID is a 32-bit unsigned integer, which is independent for
+        //   ordered and unordered user messages of a stream.
+        let interleaved = self.pending_queue.interleaving();
+        let message_identifier = if !interleaved {
+            0
+        } else if unordered {
+            self.unordered_message_identifier
+                .fetch_add(1, Ordering::SeqCst)
+        } else {
+            self.message_identifier.fetch_add(1, Ordering::SeqCst)
+        };
+
+        let max_payload_size = self.max_payload_size.load(Ordering::SeqCst) as usize;
         let mut chunks = vec![];
 
         let head_abandoned = Arc::new(AtomicBool::new(false));
         let head_all_inflight = Arc::new(AtomicBool::new(false));
+        let head_abandon_notified = Arc::new(AtomicBool::new(false));
         while remaining != 0 {
-            let fragment_size = std::cmp::min(self.max_payload_size as usize, remaining); //self.association.max_payload_size
+            let fragment_size = std::cmp::min(max_payload_size, remaining);
 
-            // Copy the userdata since we'll have to store it until acked
-            // and the caller may re-use the buffer in the mean time
-            let user_data = raw.slice(i..i + fragment_size);
+            // Bytes are immutable, so the userdata can be stored until acked
+            // without copying it even if the caller re-uses its handle
+            let user_data = next_fragment(bufs, &mut index, &mut offset, fragment_size);
 
             let chunk = ChunkPayloadData {
                 stream_identifier: self.stream_identifier,
@@ -330,8 +561,12 @@ impl Stream {
                 immediate_sack: false,
                 payload_type: ppi,
                 stream_sequence_number: self.sequence_number.load(Ordering::SeqCst),
+                interleaved,
+                message_identifier,
+                fragment_sequence_number: chunks.len() as u32,
                 abandoned: head_abandoned.clone(), // all fragmented chunks use the same abandoned
                 all_inflight: head_all_inflight.clone(), // all fragmented chunks use the same all_inflight
+                abandon_notified: head_abandon_notified.clone(),
                 ..Default::default()
             };
 
@@ -345,12 +580,21 @@ impl Stream {
         // Note: When transmitting ordered and unordered data, an endpoint does
         // not increment its Stream Sequence Number when transmitting a DATA
         // chunk with U flag set to 1.
-        if !unordered {
+        if !unordered && !interleaved {
             self.sequence_number.fetch_add(1, Ordering::SeqCst);
         }
 
-        let old_value = self.buffered_amount.fetch_add(raw.len(), Ordering::SeqCst);
-        log::trace!("[{}] bufferedAmount = {}", self.name, old_value + raw.len());
+        // DCEP messages and the placeholder byte of empty messages are not application data
+        let buffered_len = if ppi.is_application_data() { len } else { 0 };
+        let old_value = self
+            .buffered_amount
+            .fetch_add(buffered_len, Ordering::SeqCst);
+        log::trace!(
+            "[{}] bufferedAmount = {}",
+            self.name,
+            old_value + buffered_len
+        );
+        self.send_buffer.buffer(buffered_len);
 
         chunks
     }
@@ -374,8 +618,11 @@ impl Stream {
             return Ok(());
         }
 
-        if how == Shutdown::Write || how == Shutdown::Both {
-            self.write_shutdown.store(true, Ordering::SeqCst);
+        if (how == Shutdown::Write || how == Shutdown::Both)
+            && !self.write_shutdown.swap(true, Ordering::SeqCst)
+        {
+            self.send_buffer.wake();
+            self.buffered_amount_low_notifier.notify_waiters();
         }
 
         if (how == Shutdown::Read || how == Shutdown::Both)
@@ -396,7 +643,15 @@ impl Stream {
         Ok(())
     }
 
-    /// buffered_amount returns the number of bytes of data currently queued to be sent over this stream.
+    /// is_association_established tells whether the association of the stream is established.
+    /// Once either peer started the shutdown the stream can no longer be reset.
+    pub fn is_association_established(&self) -> bool {
+        self.get_state() == AssociationState::Established
+    }
+
+    /// buffered_amount returns the number of bytes of application data written to this stream
+    /// and not yet acknowledged or abandoned, whether they are still queued in the association
+    /// or in flight. DCEP messages and the placeholder byte of empty messages are not counted.
     pub fn buffered_amount(&self) -> usize {
         self.buffered_amount.load(Ordering::SeqCst)
     }
@@ -411,6 +666,24 @@ impl Stream {
     /// See buffered_amount_low_threshold().
     pub fn set_buffered_amount_low_threshold(&self, th: usize) {
         self.buffered_amount_low.store(th, Ordering::SeqCst);
+        self.buffered_amount_low_notifier.notify_waiters();
+    }
+
+    /// Waits until the buffered amount is lower than or equal to the threshold, so that
+    /// senders can implement flow control without the on_buffered_amount_low callback.
+    ///
+    /// Returns an error if the write half of this stream is shutdown before that.
+    pub async fn wait_buffered_amount_low(&self) -> Result<()> {
+        loop {
+            let notified = self.buffered_amount_low_notifier.notified();
+            if self.buffered_amount() <= self.buffered_amount_low_threshold() {
+                return Ok(());
+            }
+            if self.write_shutdown.load(Ordering::SeqCst) {
+                return Err(Error::ErrStreamClosed);
+            }
+            notified.await;
+        }
     }
 
     /// on_buffered_amount_low sets the callback handler which would be called when the number of
@@ -420,6 +693,13 @@ impl Stream {
             .store(Some(Arc::new(Mutex::new(f))));
     }
 
+    /// on_abandoned sets the callback handler which would be called when a user message sent
+    /// with partial reliability is abandoned, with the payload protocol identifier of the message.
+    /// The message may then never be delivered to the peer.
+    pub fn on_abandoned(&self, f: OnAbandonedFn) {
+        self.on_abandoned.store(Some(Arc::new(Mutex::new(f))));
+    }
+
     /// This method is called by association's read_loop (go-)routine to notify this stream
     /// of the specified amount of outgoing data has been delivered to the peer.
     pub(crate) async fn on_buffer_released(&self, n_bytes_released: i64) {
@@ -443,8 +723,12 @@ impl Stream {
 
             from_amount - n_bytes_released as usize
         };
+        self.send_buffer.release(from_amount - new_amount);
 
         let buffered_amount_low = self.buffered_amount_low.load(Ordering::SeqCst);
+        if new_amount <= buffered_amount_low {
+            self.buffered_amount_low_notifier.notify_waiters();
+        }
 
         log::trace!(
             "[{}] bufferedAmount = {}, from_amount = {}, buffered_amount_low = {}",
@@ -462,12 +746,46 @@ impl Stream {
         }
     }
 
+    /// This method is called by association's read_loop (go-)routine to notify this stream
+    /// that an outgoing user message was abandoned.
+    pub(crate) async fn on_message_abandoned(&self, ppi: PayloadProtocolIdentifier) {
+        log::debug!("[{}] abandoned a user message: ppi={}", self.name, ppi);
+
+        if let Some(handler) = &*self.on_abandoned.load() {
+            let mut f = handler.lock().await;
+            f(ppi).await;
+        }
+    }
+
     /// get_num_bytes_in_reassembly_queue returns the number of bytes of data currently queued to
     /// be read (once chunk is complete).
     pub(crate) async fn get_num_bytes_in_reassembly_queue(&self) -> usize {
         // No lock is required as it reads the size with atomic load function.
         let reassembly_queue = self.reassembly_queue.lock().await;
-        reassembly_queue.get_num_bytes()
+        reassembly_queue.get_num_bytes() + self.unordered_ready_bytes.load(Ordering::SeqCst)
+    }
+
+    /// clear_received drops the data received but not read yet, once the stream was reset
+    /// and its reader won't get it anymore.
+    pub(crate) fn clear_received(&self) {
+        if let Ok(mut unordered_ready) = self.unordered_ready.lock() {
+            unordered_ready.clear();
+        }
+        self.unordered_ready_bytes.store(0, Ordering::SeqCst);
+
+        // A reader holds the lock only for one read, and returns once read_shutdown is set
+        if let Ok(mut reassembly_queue) = self.reassembly_queue.try_lock() {
+            *reassembly_queue = ReassemblyQueue::new(self.stream_identifier);
+        }
+    }
+
+    /// pop_unordered_ready removes the next unordered message which was
+    /// delivered without going through the reassembly_queue.
+    fn pop_unordered_ready(&self) -> Option<ChunkPayloadData> {
+        let c = self.unordered_ready.lock().ok()?.pop_front()?;
+        self.unordered_ready_bytes
+            .fetch_sub(c.user_data.len(), Ordering::SeqCst);
+        Some(c)
     }
 
     /// get_state atomically returns the state of the Association.
@@ -518,6 +836,38 @@ impl Stream {
     }
 }
 
+/// Takes the next `size` bytes of the message made of `bufs`, starting at
+/// `bufs[*index][*offset..]`, and advances the cursor past them.
+///
+/// The fragment is sliced out of the buffer it lies in, and only copied when it
+/// straddles several buffers.
+fn next_fragment(bufs: &[Bytes], index: &mut usize, offset: &mut usize, size: usize) -> Bytes {
+    while bufs[*index].len() == *offset {
+        *index += 1;
+        *offset = 0;
+    }
+
+    let buf = &bufs[*index];
+    if buf.len() - *offset >= size {
+        let fragment = buf.slice(*offset..*offset + size);
+        *offset += size;
+        return fragment;
+    }
+
+    let mut fragment = BytesMut::with_capacity(size);
+    while fragment.len() < size {
+        let buf = &bufs[*index];
+        let n = std::cmp::min(size - fragment.len(), buf.len() - *offset);
+        fragment.extend_from_slice(&buf[*offset..*offset + n]);
+        *offset += n;
+        if *offset == buf.len() {
+            *index += 1;
+            *offset = 0;
+        }
+    }
+    fragment.freeze()
+}
+
 /// Default capacity of the temporary read buffer used by [`PollStream`].
 const DEFAULT_READ_BUF_SIZE: usize = 8192;
 
@@ -555,6 +905,7 @@ pub struct PollStream {
 
     read_fut: ReadFut,
     write_fut: Option<Pin<Box<dyn Future<Output = Result<usize>> + Send>>>,
+    ready_fut: Option<Pin<Box<dyn Future<Output = Result<()>> + Send>>>,
     shutdown_fut: Option<Pin<Box<dyn Future<Output = Result<()>> + Send>>>,
 
     read_buf_cap: usize,
@@ -577,11 +928,35 @@ impl PollStream {
             stream,
             read_fut: ReadFut::Idle,
             write_fut: None,
+            ready_fut: None,
             shutdown_fut: None,
             read_buf_cap: DEFAULT_READ_BUF_SIZE,
         }
     }
 
+    /// Polls for room in the send buffer of the association, see [`Stream::writable`].
+    /// `poll_write` waits for it before writing.
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let fut = match self.ready_fut.as_mut() {
+            Some(fut) => fut,
+            None => {
+                if self.stream.is_writable() {
+                    return Poll::Ready(Ok(()));
+                }
+                let stream = self.stream.clone();
+                self.ready_fut
+                    .get_or_insert(Box::pin(async move { stream.writable().await }))
+            }
+        };
+
+        let result = match fut.as_mut().poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(result) => result,
+        };
+        self.ready_fut = None;
+        Poll::Ready(result.map_err(|e| e.into()))
+    }
+
     /// Get back the inner stream.
     #[must_use]
     pub fn into_inner(self) -> Arc<Stream> {
@@ -613,9 +988,7 @@ impl PollStream {
     /// get_num_bytes_in_reassembly_queue returns the number of bytes of data currently queued to
     /// be read (once chunk is complete).
     pub(crate) async fn get_num_bytes_in_reassembly_queue(&self) -> usize {
-        // No lock is required as it reads the size with atomic load function.
-        let reassembly_queue = self.stream.reassembly_queue.lock().await;
-        reassembly_queue.get_num_bytes()
+        self.stream.get_num_bytes_in_reassembly_queue().await
     }
 
     /// Set the capacity of the temporary read buffer (default: 8192).
@@ -697,10 +1070,16 @@ impl AsyncRead for PollStream {
 
 impl AsyncWrite for PollStream {
     fn poll_write(
-        self: Pin<&mut Self>,
-        _cx: &mut Context<'_>,
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
+        match self.poll_ready(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Ready(Ok(())) => {}
+        }
+
         let bytes = Bytes::copy_from_slice(buf);
         match self.stream.write(&bytes) {
             Ok(n) => Poll::Ready(Ok(n)),
@@ -1,6 +1,7 @@
 use super::*;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWriteExt;
 
@@ -76,11 +77,12 @@ async fn test_stream() -> std::result::Result<(), io::Error> {
     let s = Stream::new(
         "test_poll_stream".to_owned(),
         0,
-        4096,
+        Arc::new(AtomicU32::new(4096)),
         Arc::new(AtomicU32::new(4096)),
         Arc::new(AtomicU8::new(AssociationState::Established as u8)),
         None,
         Arc::new(PendingQueue::new()),
+        Arc::new(SendBuffer::default()),
     );
 
     // getters
@@ -143,16 +145,200 @@ async fn test_stream() -> std::result::Result<(), io::Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_stream_wait_buffered_amount_low() -> Result<()> {
+    let s = Arc::new(Stream::default());
+
+    s.buffered_amount.store(4096, Ordering::SeqCst);
+    s.set_buffered_amount_low_threshold(2048);
+
+    let s2 = Arc::clone(&s);
+    let mut waiter = tokio::spawn(async move { s2.wait_buffered_amount_low().await });
+
+    s.on_buffer_released(1024).await; // bufferedAmount = 3072
+    assert!(
+        tokio::time::timeout(Duration::from_millis(10), &mut waiter)
+            .await
+            .is_err(),
+        "should wait for bufferedAmount to be low"
+    );
+
+    s.on_buffer_released(1024).await; // bufferedAmount = 2048
+    tokio::time::timeout(Duration::from_secs(1), waiter)
+        .await
+        .expect("should be woken up by the release")
+        .unwrap()?;
+
+    // no wait once low, an error once the write half is shutdown
+    s.wait_buffered_amount_low().await?;
+    s.buffered_amount.store(4096, Ordering::SeqCst);
+    s.write_shutdown.store(true, Ordering::SeqCst);
+    assert_eq!(
+        Err(Error::ErrStreamClosed),
+        s.wait_buffered_amount_low().await
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stream_unordered_fast_path() -> Result<()> {
+    let s = Stream::default();
+
+    // an unordered message which was not fragmented bypasses the reassembly queue
+    s.handle_data(ChunkPayloadData {
+        unordered: true,
+        beginning_fragment: true,
+        ending_fragment: true,
+        user_data: Bytes::from_static(&[0, 1, 2, 3, 4]),
+        payload_type: PayloadProtocolIdentifier::Binary,
+        ..Default::default()
+    })
+    .await;
+    s.handle_data(ChunkPayloadData {
+        unordered: true,
+        beginning_fragment: true,
+        ending_fragment: true,
+        user_data: Bytes::from_static(&[5, 6, 7]),
+        payload_type: PayloadProtocolIdentifier::String,
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(8, s.get_num_bytes_in_reassembly_queue().await);
+
+    {
+        // no need for the reassembly queue to read them
+        let q = s.reassembly_queue.lock().await;
+        assert_eq!(0, q.get_num_bytes());
+
+        let (b, ppi) = s.read_sctp_bytes().await?;
+        assert_eq!(&b[..], &[0, 1, 2, 3, 4]);
+        assert_eq!(PayloadProtocolIdentifier::Binary, ppi);
+
+        let mut buf = [0; 2];
+        assert_eq!(Err(Error::ErrShortBuffer), s.read_sctp(&mut buf).await);
+        assert_eq!(buf, [5, 6]);
+    }
+    assert_eq!(0, s.get_num_bytes_in_reassembly_queue().await);
+
+    // fragments still go through the reassembly queue
+    s.handle_data(ChunkPayloadData {
+        unordered: true,
+        beginning_fragment: true,
+        tsn: 1,
+        user_data: Bytes::from_static(&[0, 1]),
+        payload_type: PayloadProtocolIdentifier::Binary,
+        ..Default::default()
+    })
+    .await;
+    s.handle_data(ChunkPayloadData {
+        unordered: true,
+        ending_fragment: true,
+        tsn: 2,
+        user_data: Bytes::from_static(&[2, 3]),
+        payload_type: PayloadProtocolIdentifier::Binary,
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(4, s.reassembly_queue.lock().await.get_num_bytes());
+
+    let mut buf = [0; 4];
+    assert_eq!(
+        (4, PayloadProtocolIdentifier::Binary),
+        s.read_sctp(&mut buf).await?
+    );
+    assert_eq!(buf, [0, 1, 2, 3]);
+
+    Ok(())
+}
+
+#[test]
+fn test_stream_packetize_vectored() {
+    let s = Stream::default();
+    s.max_payload_size.store(4, Ordering::SeqCst);
+
+    let header = Bytes::from_static(&[0, 1]);
+    let payload = Bytes::from(vec![2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+    let bufs = [header, Bytes::new(), payload.clone()];
+    let chunks = s.packetize(&bufs, 12, PayloadProtocolIdentifier::Binary);
+
+    let user_data: Vec<&[u8]> = chunks.iter().map(|c| &c.user_data[..]).collect();
+    assert_eq!(
+        vec![&[0, 1, 2, 3][..], &[4, 5, 6, 7][..], &[8, 9, 10, 11][..]],
+        user_data
+    );
+    assert!(chunks[0].beginning_fragment && !chunks[0].ending_fragment);
+    assert!(!chunks[2].beginning_fragment && chunks[2].ending_fragment);
+
+    // only the fragment straddling the header and the payload is copied
+    assert_eq!(payload[2..].as_ptr(), chunks[1].user_data.as_ptr());
+    assert_eq!(payload[6..].as_ptr(), chunks[2].user_data.as_ptr());
+
+    assert_eq!(12, s.buffered_amount());
+}
+
+#[tokio::test]
+async fn test_stream_writable() -> Result<()> {
+    let s = Arc::new(Stream::new(
+        "test_stream_writable".to_owned(),
+        0,
+        Arc::new(AtomicU32::new(4096)),
+        Arc::new(AtomicU32::new(4096)),
+        Arc::new(AtomicU8::new(AssociationState::Established as u8)),
+        None,
+        Arc::new(PendingQueue::new()),
+        Arc::new(SendBuffer::new(16)),
+    ));
+
+    s.write(&Bytes::from_static(&[0; 10]))?;
+    assert!(s.is_writable(), "10 of 16 bytes buffered");
+    s.writable().await?;
+
+    s.write(&Bytes::from_static(&[0; 10]))?;
+    assert!(!s.is_writable(), "20 of 16 bytes buffered");
+
+    assert!(
+        tokio::time::timeout(Duration::from_millis(10), s.writable())
+            .await
+            .is_err(),
+        "writable must wait for room"
+    );
+
+    let waiter = {
+        let s = Arc::clone(&s);
+        tokio::spawn(async move { s.writable().await })
+    };
+    tokio::task::yield_now().await;
+
+    // the peer acknowledges the first message
+    s.on_buffer_released(10).await;
+    assert!(s.is_writable(), "10 of 16 bytes buffered");
+    assert!(waiter.await.unwrap().is_ok());
+
+    // a waiter is woken up with an error when the write half is shutdown
+    s.write(&Bytes::from_static(&[0; 10]))?;
+    let waiter = {
+        let s = Arc::clone(&s);
+        tokio::spawn(async move { s.writable().await })
+    };
+    tokio::task::yield_now().await;
+    s.shutdown(Shutdown::Write).await?;
+    assert_eq!(Err(Error::ErrStreamClosed), waiter.await.unwrap());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_poll_stream() -> std::result::Result<(), io::Error> {
     let s = Arc::new(Stream::new(
         "test_poll_stream".to_owned(),
         0,
-        4096,
+        Arc::new(AtomicU32::new(4096)),
         Arc::new(AtomicU32::new(4096)),
         Arc::new(AtomicU8::new(AssociationState::Established as u8)),
         None,
         Arc::new(PendingQueue::new()),
+        Arc::new(SendBuffer::default()),
     ));
     let mut poll_stream = PollStream::new(s.clone());
 
@@ -22,13 +22,20 @@ pub(crate) struct RtoManager {
     pub(crate) rttvar: f64,
     pub(crate) rto: u64,
     pub(crate) no_update: bool,
+    pub(crate) rto_initial: u64,
+    pub(crate) rto_min: u64,
+    pub(crate) rto_max: u64,
 }
 
 impl RtoManager {
-    /// newRTOManager creates a new rtoManager.
-    pub(crate) fn new() -> Self {
+    /// newRTOManager creates a new rtoManager with the RTO.Initial, RTO.Min
+    /// and RTO.Max values in msec.
+    pub(crate) fn new(rto_initial: u64, rto_min: u64, rto_max: u64) -> Self {
         RtoManager {
-            rto: RTO_INITIAL,
+            rto: rto_initial,
+            rto_initial,
+            rto_min,
+            rto_max,
             ..Default::default()
         }
     }
@@ -52,8 +59,8 @@ impl RtoManager {
         }
 
         self.rto = std::cmp::min(
-            std::cmp::max(self.srtt + (4.0 * self.rttvar) as u64, RTO_MIN),
-            RTO_MAX,
+            std::cmp::max(self.srtt + (4.0 * self.rttvar) as u64, self.rto_min),
+            self.rto_max,
         );
 
         self.srtt
@@ -72,7 +79,7 @@ impl RtoManager {
 
         self.srtt = 0;
         self.rttvar = 0.0;
-        self.rto = RTO_INITIAL;
+        self.rto = self.rto_initial;
     }
 
     /// set RTO value for testing
@@ -82,16 +89,16 @@ impl RtoManager {
     }
 }
 
-pub(crate) fn calculate_next_timeout(rto: u64, n_rtos: usize) -> u64 {
+pub(crate) fn calculate_next_timeout(rto: u64, n_rtos: usize, rto_max: u64) -> u64 {
     // RFC 4096 sec 6.3.3.  Handle T3-rtx Expiration
     //   E2)  For the destination address for which the timer expires, set RTO
     //        <- RTO * 2 ("back off the timer").  The maximum value discussed
     //        in rule C7 above (RTO.max) may be used to provide an upper bound
     //        to this doubling operation.
     if n_rtos < 31 {
-        std::cmp::min(rto << n_rtos, RTO_MAX)
+        std::cmp::min(rto << n_rtos, rto_max)
     } else {
-        RTO_MAX
+        rto_max
     }
 }
 
@@ -110,6 +117,7 @@ pub(crate) struct RtxTimer<T: 'static + RtxTimerObserver + Send> {
     pub(crate) timeout_observer: Weak<Mutex<T>>,
     pub(crate) id: RtxTimerId,
     pub(crate) max_retrans: usize,
+    pub(crate) rto_max: u64,
     pub(crate) close_tx: Arc<Mutex<Option<mpsc::Sender<()>>>>,
 }
 
@@ -117,15 +125,18 @@ impl<T: 'static + RtxTimerObserver + Send> RtxTimer<T> {
     /// newRTXTimer creates a new retransmission timer.
     /// if max_retrans is set to 0, it will keep retransmitting until stop() is called.
     /// (it will never make on_retransmission_failure() callback.
+    /// The backed off timeouts are capped at rto_max msec.
     pub(crate) fn new(
         timeout_observer: Weak<Mutex<T>>,
         id: RtxTimerId,
         max_retrans: usize,
+        rto_max: u64,
     ) -> Self {
         RtxTimer {
             timeout_observer,
             id,
             max_retrans,
+            rto_max,
             close_tx: Arc::new(Mutex::new(None)),
         }
     }
@@ -151,6 +162,7 @@ impl<T: 'static + RtxTimerObserver + Send> RtxTimer<T> {
 
         let id = self.id;
         let max_retrans = self.max_retrans;
+        let rto_max = self.rto_max;
         let close_tx = Arc::clone(&self.close_tx);
         let timeout_observer = self.timeout_observer.clone();
 
@@ -158,7 +170,7 @@ impl<T: 'static + RtxTimerObserver + Send> RtxTimer<T> {
             let mut n_rtos = 0;
 
             loop {
-                let interval = calculate_next_timeout(rto, n_rtos);
+                let interval = calculate_next_timeout(rto, n_rtos, rto_max);
                 let timer = tokio::time::sleep(Duration::from_millis(interval));
                 tokio::pin!(timer);
 
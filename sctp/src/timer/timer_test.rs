@@ -78,7 +78,7 @@ mod test_rto_manager {
 
     #[tokio::test]
     async fn test_rto_manager_initial_values() -> Result<()> {
-        let m = RtoManager::new();
+        let m = RtoManager::new(RTO_INITIAL, RTO_MIN, RTO_MAX);
         assert_eq!(RTO_INITIAL, m.rto, "should be rtoInitial");
         assert_eq!(RTO_INITIAL, m.get_rto(), "should be rtoInitial");
         assert_eq!(0, m.srtt, "should be 0");
@@ -89,7 +89,7 @@ mod test_rto_manager {
 
     #[tokio::test]
     async fn test_rto_manager_rto_calculation_small_rtt() -> Result<()> {
-        let mut m = RtoManager::new();
+        let mut m = RtoManager::new(RTO_INITIAL, RTO_MIN, RTO_MAX);
         let exp = vec![
             1800, 1500, 1275, 1106, 1000, // capped at RTO.Min
         ];
@@ -105,7 +105,7 @@ mod test_rto_manager {
 
     #[tokio::test]
     async fn test_rto_manager_rto_calculation_large_rtt() -> Result<()> {
-        let mut m = RtoManager::new();
+        let mut m = RtoManager::new(RTO_INITIAL, RTO_MIN, RTO_MAX);
         let exp = vec![
             60000, // capped at RTO.Max
             60000, // capped at RTO.Max
@@ -124,25 +124,45 @@ mod test_rto_manager {
 
     #[tokio::test]
     async fn test_rto_manager_calculate_next_timeout() -> Result<()> {
-        let rto = calculate_next_timeout(1, 0);
+        let rto = calculate_next_timeout(1, 0, RTO_MAX);
         assert_eq!(1, rto, "should match");
-        let rto = calculate_next_timeout(1, 1);
+        let rto = calculate_next_timeout(1, 1, RTO_MAX);
         assert_eq!(2, rto, "should match");
-        let rto = calculate_next_timeout(1, 2);
+        let rto = calculate_next_timeout(1, 2, RTO_MAX);
         assert_eq!(4, rto, "should match");
-        let rto = calculate_next_timeout(1, 30);
+        let rto = calculate_next_timeout(1, 30, RTO_MAX);
         assert_eq!(60000, rto, "should match");
-        let rto = calculate_next_timeout(1, 63);
+        let rto = calculate_next_timeout(1, 63, RTO_MAX);
         assert_eq!(60000, rto, "should match");
-        let rto = calculate_next_timeout(1, 64);
+        let rto = calculate_next_timeout(1, 64, RTO_MAX);
         assert_eq!(60000, rto, "should match");
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rto_manager_custom_bounds() -> Result<()> {
+        let mut m = RtoManager::new(500, 200, 2000);
+        assert_eq!(500, m.get_rto(), "should be rtoInitial");
+
+        m.set_new_rtt(50);
+        assert_eq!(200, m.get_rto(), "should be capped at rtoMin");
+
+        m.reset();
+        assert_eq!(500, m.get_rto(), "should be rtoInitial");
+
+        m.set_new_rtt(3000);
+        assert_eq!(2000, m.get_rto(), "should be capped at rtoMax");
+
+        let rto = calculate_next_timeout(500, 3, 2000);
+        assert_eq!(2000, rto, "back off should be capped at rtoMax");
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_rto_manager_reset() -> Result<()> {
-        let mut m = RtoManager::new();
+        let mut m = RtoManager::new(RTO_INITIAL, RTO_MIN, RTO_MAX);
         for _ in 0..10 {
             m.set_new_rtt(200);
         }
@@ -224,7 +244,7 @@ mod test_rtx_timer {
             timer_id,
             ..Default::default()
         }));
-        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS);
+        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS, RTO_MAX);
 
         assert!(!rt.is_running().await, "should not be running");
 
@@ -251,7 +271,7 @@ mod test_rtx_timer {
             timer_id,
             ..Default::default()
         }));
-        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS);
+        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS, RTO_MAX);
 
         let interval = 30;
         let ok = rt.start(interval).await;
@@ -279,7 +299,7 @@ mod test_rtx_timer {
             timer_id,
             ..Default::default()
         }));
-        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS);
+        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS, RTO_MAX);
 
         let interval = 30;
         let ok = rt.start(interval).await;
@@ -304,7 +324,7 @@ mod test_rtx_timer {
             timer_id,
             ..Default::default()
         }));
-        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS);
+        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS, RTO_MAX);
 
         let interval = 30;
         let ok = rt.start(interval).await;
@@ -333,7 +353,7 @@ mod test_rtx_timer {
             timer_id,
             ..Default::default()
         }));
-        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS);
+        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS, RTO_MAX);
 
         for _ in 0..1000 {
             let ok = rt.start(30).await;
@@ -362,7 +382,7 @@ mod test_rtx_timer {
         }));
 
         let since = SystemTime::now();
-        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS);
+        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS, RTO_MAX);
 
         // RTO(msec) Total(msec)
         //  10          10    1st RTO
@@ -413,7 +433,7 @@ mod test_rtx_timer {
         }));
 
         let since = SystemTime::now();
-        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, 0);
+        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, 0, RTO_MAX);
 
         // RTO(msec) Total(msec)
         //  10          10    1st RTO
@@ -461,7 +481,7 @@ mod test_rtx_timer {
             max_rtos: usize::MAX,
             ..Default::default()
         }));
-        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS);
+        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS, RTO_MAX);
 
         for _ in 0..10 {
             rt.stop().await;
@@ -487,7 +507,7 @@ mod test_rtx_timer {
             timer_id,
             ..Default::default()
         }));
-        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS);
+        let rt = RtxTimer::new(Arc::downgrade(&obs), timer_id, PATH_MAX_RETRANS, RTO_MAX);
 
         let ok = rt.start(20).await;
         assert!(ok, "should be accepted");
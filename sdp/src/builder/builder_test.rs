@@ -0,0 +1,281 @@
+use super::*;
+use crate::extmap::{SDES_MID_URI, TRANSPORT_CC_URI};
+use crate::simulcast::SimulcastRid;
+
+use std::io::Cursor;
+use url::Url;
+
+fn opus() -> Codec {
+    Codec {
+        payload_type: 111,
+        name: "opus".to_owned(),
+        clock_rate: 48000,
+        encoding_parameters: "2".to_owned(),
+        fmtp: "minptime=10;useinbandfec=1".to_owned(),
+        rtcp_feedback: vec!["transport-cc".to_owned()],
+    }
+}
+
+fn vp8() -> Codec {
+    Codec {
+        payload_type: 96,
+        name: "VP8".to_owned(),
+        clock_rate: 90000,
+        rtcp_feedback: vec!["nack".to_owned(), "nack pli".to_owned()],
+        ..Default::default()
+    }
+}
+
+fn extmap(value: isize, uri: &str) -> ExtMap {
+    ExtMap {
+        value,
+        uri: Some(Url::parse(uri).unwrap()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_media_description_builder() -> Result<()> {
+    let md = MediaDescriptionBuilder::new("audio")
+        .mid("0")
+        .ice_credentials("ufrag", "pwd")
+        .setup("actpass")
+        .extmap(extmap(1, SDES_MID_URI))
+        .direction(Direction::SendRecv)
+        .rtcp_mux()
+        .codec(opus())
+        .ssrc(1234, "cname", Some("webrtc-rs"))
+        .candidate("1 1 udp 2130706431 10.0.0.1 50000 typ host")
+        .end_of_candidates()
+        .build()?;
+
+    assert_eq!(md.media_name.to_string(), "audio 9 UDP/TLS/RTP/SAVPF 111");
+    let attributes: Vec<String> = md.attributes.iter().map(|a| a.to_string()).collect();
+    assert_eq!(
+        attributes,
+        vec![
+            "ice-ufrag:ufrag",
+            "ice-pwd:pwd",
+            "setup:actpass",
+            "mid:0",
+            "extmap:1 urn:ietf:params:rtp-hdrext:sdes:mid",
+            "sendrecv",
+            "rtcp-mux",
+            "rtpmap:111 opus/48000/2",
+            "rtcp-fb:111 transport-cc",
+            "fmtp:111 minptime=10;useinbandfec=1",
+            "ssrc:1234 cname:webrtc-rs",
+            "candidate:1 1 udp 2130706431 10.0.0.1 50000 typ host",
+            "end-of-candidates",
+        ]
+    );
+
+    let md = MediaDescriptionBuilder::new_data_channel()
+        .mid("1")
+        .value_attribute("sctp-port", "5000")
+        .build()?;
+    assert_eq!(
+        md.media_name.to_string(),
+        "application 9 UDP/DTLS/SCTP webrtc-datachannel"
+    );
+    assert_eq!(md.attribute("sctp-port"), Some(Some("5000")));
+
+    Ok(())
+}
+
+#[test]
+fn test_session_description_builder_round_trip() -> Result<()> {
+    let sd = SessionDescriptionBuilder::new()
+        .group("BUNDLE", &["0", "1"])
+        .extmap_allow_mixed()
+        .fingerprint("sha-256", "AB:CD")
+        .media(
+            MediaDescriptionBuilder::new("video")
+                .mid("0")
+                .extmap_allow_mixed()
+                .extmap(extmap(3, TRANSPORT_CC_URI))
+                .direction(Direction::SendOnly)
+                .codec(vp8())
+                .codec(Codec {
+                    payload_type: 97,
+                    name: "rtx".to_owned(),
+                    clock_rate: 90000,
+                    fmtp: "apt=96".to_owned(),
+                    ..Default::default()
+                })
+                .ssrc(1, "cname", Some("webrtc-rs"))
+                .ssrc(2, "cname", Some("webrtc-rs"))
+                .ssrc_group("FID", &[1, 2]),
+        )
+        .media(MediaDescriptionBuilder::new_data_channel().mid("1"))
+        .build()?;
+
+    let raw = sd.marshal();
+    let parsed = SessionDescription::unmarshal(&mut Cursor::new(raw.as_bytes()))?;
+    assert_eq!(parsed.marshal(), raw);
+
+    assert_eq!(
+        parsed.attribute(ATTR_KEY_GROUP),
+        Some(&"BUNDLE 0 1".to_owned())
+    );
+    assert_eq!(parsed.media_descriptions.len(), 2);
+    assert!(parsed.extmap_allow_mixed());
+    assert!(parsed.media_descriptions[0].extmap_allow_mixed());
+    assert!(!parsed.media_descriptions[1].extmap_allow_mixed());
+
+    let codec = parsed.get_codec_for_payload_type(96)?;
+    assert_eq!(codec.name, "VP8");
+    assert_eq!(codec.rtcp_feedback, vec!["nack", "nack pli"]);
+    assert_eq!(parsed.get_codec_for_payload_type(97)?.fmtp, "apt=96");
+    assert_eq!(
+        parsed.media_descriptions[0].attribute(ATTR_KEY_SSRCGROUP),
+        Some(Some("FID 1 2"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_media_description_builder_simulcast() -> Result<()> {
+    let high = Rid {
+        formats: vec![96],
+        restrictions: vec![("max-width".to_owned(), Some("1280".to_owned()))],
+        ..Rid::new("h", RidDirection::Send)
+    };
+    let simulcast = Simulcast {
+        send: vec![
+            vec![SimulcastRid::new("h")],
+            vec![SimulcastRid {
+                id: "l".to_owned(),
+                paused: true,
+            }],
+        ],
+        recv: vec![],
+    };
+    let md = MediaDescriptionBuilder::new("video")
+        .codec(vp8())
+        .rid(high.clone())
+        .rid(Rid::new("l", RidDirection::Send))
+        .simulcast(simulcast.clone())
+        .build()?;
+
+    assert_eq!(
+        md.attribute(ATTR_KEY_RID),
+        Some(Some("h send pt=96;max-width=1280"))
+    );
+    assert_eq!(md.attribute(ATTR_KEY_SIMULCAST), Some(Some("send h;~l")));
+    assert_eq!(md.rids()?, vec![high, Rid::new("l", RidDirection::Send)]);
+    assert_eq!(md.simulcast()?, Some(simulcast));
+
+    let result = MediaDescriptionBuilder::new("video")
+        .codec(vp8())
+        .rid(Rid {
+            formats: vec![97],
+            ..Rid::new("h", RidDirection::Send)
+        })
+        .build();
+    assert_eq!(
+        result.unwrap_err(),
+        Error::SdpInvalidValue("rid \"h\" restricted to unknown payload type 97".to_owned())
+    );
+
+    let result = MediaDescriptionBuilder::new("video")
+        .codec(vp8())
+        .rid(Rid::new("h", RidDirection::Recv))
+        .simulcast(Simulcast::unmarshal("send h")?)
+        .build();
+    assert_eq!(
+        result.unwrap_err(),
+        Error::SdpInvalidValue("simulcast refers to unknown send rid \"h\"".to_owned())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_builder_validation() {
+    let tests: Vec<(&str, Result<MediaDescription>, Error)> = vec![
+        (
+            "duplicate payload type",
+            MediaDescriptionBuilder::new("video")
+                .codec(vp8())
+                .codec(vp8())
+                .build(),
+            Error::DuplicatePayloadType(96),
+        ),
+        (
+            "duplicate extmap id",
+            MediaDescriptionBuilder::new("audio")
+                .codec(opus())
+                .extmap(extmap(1, SDES_MID_URI))
+                .extmap(extmap(1, TRANSPORT_CC_URI))
+                .build(),
+            Error::DuplicateExtMapId(1),
+        ),
+        (
+            "unknown ssrc in group",
+            MediaDescriptionBuilder::new("video")
+                .codec(vp8())
+                .ssrc(1, "cname", Some("webrtc-rs"))
+                .ssrc_group("FID", &[1, 2])
+                .build(),
+            Error::UnknownSsrc(2),
+        ),
+        (
+            "codec on non-RTP media",
+            MediaDescriptionBuilder::new_data_channel()
+                .codec(opus())
+                .build(),
+            Error::SdpInvalidValue("codecs on non-RTP media \"application\"".to_owned()),
+        ),
+        (
+            "media without formats",
+            MediaDescriptionBuilder::new("audio").build(),
+            Error::SdpInvalidValue("media \"audio\" without formats".to_owned()),
+        ),
+        (
+            "line break in a value",
+            MediaDescriptionBuilder::new("audio")
+                .codec(Codec {
+                    fmtp: "minptime=10\r\na=foo".to_owned(),
+                    ..opus()
+                })
+                .build(),
+            Error::SdpInvalidValue("fmtp \"minptime=10\\r\\na=foo\"".to_owned()),
+        ),
+        (
+            "whitespace in a codec name",
+            MediaDescriptionBuilder::new("audio")
+                .codec(Codec {
+                    name: "op us".to_owned(),
+                    ..opus()
+                })
+                .build(),
+            Error::SdpInvalidValue("codec name \"op us\"".to_owned()),
+        ),
+        (
+            "colon in an attribute key",
+            MediaDescriptionBuilder::new("audio")
+                .codec(opus())
+                .property_attribute("foo:bar")
+                .build(),
+            Error::SdpInvalidValue("attribute key \"foo:bar\"".to_owned()),
+        ),
+    ];
+
+    for (name, result, expected) in tests {
+        assert_eq!(result.unwrap_err(), expected, "{}", name);
+    }
+
+    let result = SessionDescriptionBuilder::new()
+        .media(MediaDescriptionBuilder::new_data_channel().mid("0"))
+        .media(MediaDescriptionBuilder::new_data_channel().mid("0"))
+        .build();
+    assert_eq!(result.unwrap_err(), Error::DuplicateMid("0".to_owned()));
+
+    let result = SessionDescriptionBuilder::new()
+        .group("BUNDLE", &["0", "1"])
+        .media(MediaDescriptionBuilder::new_data_channel().mid("0"))
+        .build();
+    assert_eq!(result.unwrap_err(), Error::UnknownMid("1".to_owned()));
+}
@@ -0,0 +1,741 @@
+#[cfg(test)]
+mod builder_test;
+
+use std::collections::HashSet;
+
+use crate::candidate::CandidateAttribute;
+use crate::description::common::*;
+use crate::description::media::*;
+use crate::description::session::*;
+use crate::direction::Direction;
+use crate::error::{Error, Result};
+use crate::extmap::ExtMap;
+use crate::simulcast::{validate_rid_id, Rid, RidDirection, Simulcast};
+use crate::ssrc::SsrcGroup;
+use crate::util::Codec;
+
+/// The range of the extmap ids accepted by [`ExtMap::unmarshal`].
+const EXT_MAP_ID_RANGE: std::ops::RangeInclusive<isize> = 1..=246;
+
+/// The largest RTP payload type.
+const MAX_PAYLOAD_TYPE: u8 = 127;
+
+/// MediaDescriptionBuilder builds a MediaDescription out of typed values instead of
+/// hand-assembled attributes. The values are validated by [`MediaDescriptionBuilder::build`],
+/// which then lays the attributes out in the usual JSEP order.
+#[derive(Debug, Clone)]
+pub struct MediaDescriptionBuilder {
+    media: String,
+    port: isize,
+    protos: Vec<String>,
+    formats: Vec<String>,
+    connection_information: Option<ConnectionInformation>,
+    ice_credentials: Option<(String, String)>,
+    fingerprint: Option<(String, String)>,
+    setup: Option<String>,
+    mid: Option<String>,
+    extmap_allow_mixed: bool,
+    extmaps: Vec<ExtMap>,
+    direction: Option<Direction>,
+    rtcp_mux: bool,
+    codecs: Vec<Codec>,
+    ssrc_groups: Vec<(String, Vec<u32>)>,
+    ssrcs: Vec<(u32, String, Option<String>)>,
+    rids: Vec<Rid>,
+    simulcast: Option<Simulcast>,
+    attributes: Vec<Attribute>,
+    candidates: Vec<String>,
+    end_of_candidates: bool,
+}
+
+impl MediaDescriptionBuilder {
+    /// new creates a builder of RTP media of the given type, e.g. "audio" or "video",
+    /// with the port, protocols and connection information required by JSEP.
+    pub fn new(media: &str) -> Self {
+        MediaDescriptionBuilder {
+            media: media.to_owned(),
+            port: 9,
+            protos: vec![
+                "UDP".to_owned(),
+                "TLS".to_owned(),
+                "RTP".to_owned(),
+                "SAVPF".to_owned(),
+            ],
+            formats: vec![],
+            connection_information: Some(ConnectionInformation {
+                network_type: "IN".to_owned(),
+                address_type: "IP4".to_owned(),
+                address: Some(Address {
+                    address: "0.0.0.0".to_owned(),
+                    ttl: None,
+                    range: None,
+                }),
+            }),
+            ice_credentials: None,
+            fingerprint: None,
+            setup: None,
+            mid: None,
+            extmap_allow_mixed: false,
+            extmaps: vec![],
+            direction: None,
+            rtcp_mux: false,
+            codecs: vec![],
+            ssrc_groups: vec![],
+            ssrcs: vec![],
+            rids: vec![],
+            simulcast: None,
+            attributes: vec![],
+            candidates: vec![],
+            end_of_candidates: false,
+        }
+    }
+
+    /// new_data_channel creates a builder of the "application" media carrying
+    /// the data channels over SCTP.
+    pub fn new_data_channel() -> Self {
+        MediaDescriptionBuilder {
+            protos: vec!["UDP".to_owned(), "DTLS".to_owned(), "SCTP".to_owned()],
+            formats: vec!["webrtc-datachannel".to_owned()],
+            ..MediaDescriptionBuilder::new("application")
+        }
+    }
+
+    /// port sets the port of the "m=" line.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port as isize;
+        self
+    }
+
+    /// protos sets the transport protocols of the "m=" line, e.g. `["UDP", "TLS", "RTP", "SAVPF"]`.
+    pub fn protos(mut self, protos: &[&str]) -> Self {
+        self.protos = protos.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// format adds a format to the "m=" line of non-RTP media. The formats of RTP
+    /// media are the payload types of its codecs.
+    pub fn format(mut self, format: &str) -> Self {
+        self.formats.push(format.to_owned());
+        self
+    }
+
+    /// connection_information sets the "c=" line, or removes it if None.
+    pub fn connection_information(
+        mut self,
+        connection_information: Option<ConnectionInformation>,
+    ) -> Self {
+        self.connection_information = connection_information;
+        self
+    }
+
+    /// ice_credentials sets the "a=ice-ufrag" and "a=ice-pwd" attributes.
+    pub fn ice_credentials(mut self, username: &str, password: &str) -> Self {
+        self.ice_credentials = Some((username.to_owned(), password.to_owned()));
+        self
+    }
+
+    /// fingerprint sets the "a=fingerprint" attribute.
+    pub fn fingerprint(mut self, algorithm: &str, value: &str) -> Self {
+        self.fingerprint = Some((algorithm.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// setup sets the "a=setup" attribute, e.g. "actpass".
+    pub fn setup(mut self, role: &str) -> Self {
+        self.setup = Some(role.to_owned());
+        self
+    }
+
+    /// mid sets the "a=mid" attribute.
+    pub fn mid(mut self, mid: &str) -> Self {
+        self.mid = Some(mid.to_owned());
+        self
+    }
+
+    /// extmap_allow_mixed adds the "a=extmap-allow-mixed" attribute, allowing one- and
+    /// two-byte RTP header extensions to be mixed in this media.
+    pub fn extmap_allow_mixed(mut self) -> Self {
+        self.extmap_allow_mixed = true;
+        self
+    }
+
+    /// extmap adds an "a=extmap" attribute.
+    pub fn extmap(mut self, extmap: ExtMap) -> Self {
+        self.extmaps.push(extmap);
+        self
+    }
+
+    /// direction sets the direction attribute, e.g. "a=sendrecv".
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// rtcp_mux adds the "a=rtcp-mux" attribute.
+    pub fn rtcp_mux(mut self) -> Self {
+        self.rtcp_mux = true;
+        self
+    }
+
+    /// codec adds a codec with its "a=rtpmap" attribute, and its "a=fmtp" and
+    /// "a=rtcp-fb" attributes if it has parameters and feedbacks.
+    pub fn codec(mut self, codec: Codec) -> Self {
+        self.codecs.push(codec);
+        self
+    }
+
+    /// ssrc adds an "a=ssrc:<ssrc> <attribute>[:<value>]" attribute, e.g. the "cname"
+    /// of a synchronization source.
+    pub fn ssrc(mut self, ssrc: u32, attribute: &str, value: Option<&str>) -> Self {
+        self.ssrcs
+            .push((ssrc, attribute.to_owned(), value.map(|v| v.to_owned())));
+        self
+    }
+
+    /// ssrc_group adds an "a=ssrc-group" attribute, e.g. "FID" for RTX. The
+    /// synchronization sources of the group must be added with [`Self::ssrc`].
+    pub fn ssrc_group(mut self, semantics: &str, ssrcs: &[u32]) -> Self {
+        self.ssrc_groups
+            .push((semantics.to_owned(), ssrcs.to_vec()));
+        self
+    }
+
+    /// rid adds an "a=rid" attribute restricting the RTP stream with this rid.
+    pub fn rid(mut self, rid: Rid) -> Self {
+        self.rids.push(rid);
+        self
+    }
+
+    /// simulcast sets the "a=simulcast" attribute. Its rids must be added with
+    /// [`Self::rid`] in the same direction.
+    pub fn simulcast(mut self, simulcast: Simulcast) -> Self {
+        self.simulcast = Some(simulcast);
+        self
+    }
+
+    /// candidate adds an "a=candidate" attribute, with the value of the candidate
+    /// without the "candidate:" prefix. It must parse as a [`CandidateAttribute`].
+    pub fn candidate(mut self, candidate: &str) -> Self {
+        self.candidates.push(candidate.to_owned());
+        self
+    }
+
+    /// end_of_candidates adds the "a=end-of-candidates" attribute.
+    pub fn end_of_candidates(mut self) -> Self {
+        self.end_of_candidates = true;
+        self
+    }
+
+    /// property_attribute adds any other property attribute 'a=key'.
+    pub fn property_attribute(mut self, key: &str) -> Self {
+        self.attributes.push(Attribute::new(key.to_owned(), None));
+        self
+    }
+
+    /// value_attribute adds any other value attribute 'a=key:value'.
+    pub fn value_attribute(mut self, key: &str, value: &str) -> Self {
+        self.attributes
+            .push(Attribute::new(key.to_owned(), Some(value.to_owned())));
+        self
+    }
+
+    /// mid_value returns the mid set on the builder, if any.
+    pub(crate) fn mid_value(&self) -> Option<&str> {
+        self.mid.as_deref()
+    }
+
+    /// build validates the values of the builder and creates the MediaDescription.
+    ///
+    /// It fails if a value would not be parsed back, e.g. a name with whitespace or a
+    /// line break, or if the values are inconsistent: duplicate payload types, extmap
+    /// ids or rids, codecs on non-RTP media, an ssrc-group with unknown ssrcs, or
+    /// a rid or simulcast attribute referring to unknown payload types or rids.
+    pub fn build(self) -> Result<MediaDescription> {
+        validate_token("media", &self.media)?;
+        if self.protos.is_empty() {
+            return Err(Error::SdpInvalidValue("media without protos".to_owned()));
+        }
+        for proto in &self.protos {
+            validate_token("proto", proto)?;
+            if proto.contains('/') {
+                return Err(Error::SdpInvalidValue(format!("proto {:?}", proto)));
+            }
+        }
+
+        let is_rtp = self.protos.iter().any(|p| p == "RTP");
+        let formats = if is_rtp {
+            if !self.formats.is_empty() {
+                return Err(Error::SdpInvalidValue(
+                    "the formats of RTP media are the payload types of its codecs".to_owned(),
+                ));
+            }
+            self.codecs
+                .iter()
+                .map(|c| c.payload_type.to_string())
+                .collect()
+        } else {
+            if !self.codecs.is_empty() {
+                return Err(Error::SdpInvalidValue(format!(
+                    "codecs on non-RTP media {:?}",
+                    self.media
+                )));
+            }
+            self.formats
+        };
+        if formats.is_empty() {
+            return Err(Error::SdpInvalidValue(format!(
+                "media {:?} without formats",
+                self.media
+            )));
+        }
+        for format in &formats {
+            validate_token("format", format)?;
+        }
+
+        let mut attributes = vec![];
+
+        if let Some((username, password)) = self.ice_credentials {
+            validate_token("ice-ufrag", &username)?;
+            validate_token("ice-pwd", &password)?;
+            attributes.push(Attribute::new("ice-ufrag".to_owned(), Some(username)));
+            attributes.push(Attribute::new("ice-pwd".to_owned(), Some(password)));
+        }
+
+        if let Some((algorithm, value)) = self.fingerprint {
+            validate_token("fingerprint algorithm", &algorithm)?;
+            validate_token("fingerprint", &value)?;
+            attributes.push(Attribute::new(
+                "fingerprint".to_owned(),
+                Some(algorithm + " " + &value),
+            ));
+        }
+
+        if let Some(setup) = self.setup {
+            validate_token(ATTR_KEY_CONNECTION_SETUP, &setup)?;
+            attributes.push(Attribute::new(
+                ATTR_KEY_CONNECTION_SETUP.to_owned(),
+                Some(setup),
+            ));
+        }
+
+        if let Some(mid) = self.mid {
+            validate_token(ATTR_KEY_MID, &mid)?;
+            attributes.push(Attribute::new(ATTR_KEY_MID.to_owned(), Some(mid)));
+        }
+
+        if self.extmap_allow_mixed {
+            attributes.push(Attribute::new(ATTR_KEY_EXTMAP_ALLOW_MIXED.to_owned(), None));
+        }
+
+        let mut extmap_ids = HashSet::new();
+        for extmap in &self.extmaps {
+            if !EXT_MAP_ID_RANGE.contains(&extmap.value) {
+                return Err(Error::SdpInvalidValue(format!(
+                    "extmap id {} out of the range 1-246",
+                    extmap.value
+                )));
+            }
+            if !extmap_ids.insert(extmap.value) {
+                return Err(Error::DuplicateExtMapId(extmap.value));
+            }
+            if extmap.uri.is_none() {
+                return Err(Error::SdpInvalidValue(format!(
+                    "extmap {} without uri",
+                    extmap.value
+                )));
+            }
+            if let Some(ext_attr) = &extmap.ext_attr {
+                validate_token("extmap attribute", ext_attr)?;
+            }
+            attributes.push(extmap.convert());
+        }
+
+        if let Some(direction) = self.direction {
+            if direction == Direction::Unspecified {
+                return Err(Error::SdpInvalidValue("unspecified direction".to_owned()));
+            }
+            attributes.push(Attribute::new(direction.to_string(), None));
+        }
+
+        if self.rtcp_mux {
+            attributes.push(Attribute::new(ATTR_KEY_RTCPMUX.to_owned(), None));
+        }
+
+        let mut payload_types = HashSet::new();
+        for codec in self.codecs {
+            if codec.payload_type > MAX_PAYLOAD_TYPE {
+                return Err(Error::SdpInvalidValue(format!(
+                    "payload type {}",
+                    codec.payload_type
+                )));
+            }
+            if !payload_types.insert(codec.payload_type) {
+                return Err(Error::DuplicatePayloadType(codec.payload_type));
+            }
+            validate_token("codec name", &codec.name)?;
+            if codec.name.contains('/') {
+                return Err(Error::SdpInvalidValue(format!(
+                    "codec name {:?}",
+                    codec.name
+                )));
+            }
+            if codec.clock_rate == 0 {
+                return Err(Error::SdpInvalidValue(format!(
+                    "clock rate of codec {:?}",
+                    codec.name
+                )));
+            }
+            if !codec
+                .encoding_parameters
+                .chars()
+                .all(|c| c.is_ascii_digit())
+            {
+                return Err(Error::SdpInvalidValue(format!(
+                    "encoding parameters {:?}",
+                    codec.encoding_parameters
+                )));
+            }
+
+            let mut rtpmap = format!("{} {}/{}", codec.payload_type, codec.name, codec.clock_rate);
+            if !codec.encoding_parameters.is_empty() {
+                rtpmap += format!("/{}", codec.encoding_parameters).as_str();
+            }
+            attributes.push(Attribute::new("rtpmap".to_owned(), Some(rtpmap)));
+
+            for feedback in &codec.rtcp_feedback {
+                validate_value("rtcp-fb", feedback)?;
+                attributes.push(Attribute::new(
+                    "rtcp-fb".to_owned(),
+                    Some(format!("{} {}", codec.payload_type, feedback)),
+                ));
+            }
+
+            if !codec.fmtp.is_empty() {
+                validate_token("fmtp", &codec.fmtp)?;
+                attributes.push(Attribute::new(
+                    "fmtp".to_owned(),
+                    Some(format!("{} {}", codec.payload_type, codec.fmtp)),
+                ));
+            }
+        }
+
+        let ssrcs: HashSet<u32> = self.ssrcs.iter().map(|(ssrc, _, _)| *ssrc).collect();
+        for (semantics, group) in self.ssrc_groups {
+            validate_token(ATTR_KEY_SSRCGROUP, &semantics)?;
+            if group.is_empty() {
+                return Err(Error::SdpInvalidValue(format!(
+                    "empty ssrc-group {:?}",
+                    semantics
+                )));
+            }
+            if let Some(ssrc) = group.iter().find(|ssrc| !ssrcs.contains(*ssrc)) {
+                return Err(Error::UnknownSsrc(*ssrc));
+            }
+            attributes.push(SsrcGroup::new(&semantics, &group).convert());
+        }
+
+        for (ssrc, attribute, value) in self.ssrcs {
+            validate_token("ssrc attribute", &attribute)?;
+            let value = match value {
+                Some(value) => {
+                    validate_value(&attribute, &value)?;
+                    format!("{} {}:{}", ssrc, attribute, value)
+                }
+                None => format!("{} {}", ssrc, attribute),
+            };
+            attributes.push(Attribute::new(ATTR_KEY_SSRC.to_owned(), Some(value)));
+        }
+
+        let mut rid_ids = HashSet::new();
+        for rid in &self.rids {
+            validate_rid_id(&rid.id)?;
+            if !rid_ids.insert(rid.id.as_str()) {
+                return Err(Error::SdpInvalidValue(format!(
+                    "duplicate rid {:?}",
+                    rid.id
+                )));
+            }
+            if let Some(pt) = rid.formats.iter().find(|pt| !payload_types.contains(*pt)) {
+                return Err(Error::SdpInvalidValue(format!(
+                    "rid {:?} restricted to unknown payload type {}",
+                    rid.id, pt
+                )));
+            }
+            for (key, value) in &rid.restrictions {
+                validate_token("rid restriction", key)?;
+                if let Some(value) = value {
+                    validate_token(key, value)?;
+                }
+            }
+            attributes.push(rid.convert());
+        }
+
+        if let Some(simulcast) = self.simulcast {
+            if simulcast.send.is_empty() && simulcast.recv.is_empty() {
+                return Err(Error::SdpInvalidValue("empty simulcast".to_owned()));
+            }
+            for direction in [RidDirection::Send, RidDirection::Recv] {
+                for id in simulcast.rids(direction) {
+                    if !self
+                        .rids
+                        .iter()
+                        .any(|rid| rid.id == id && rid.direction == direction)
+                    {
+                        return Err(Error::SdpInvalidValue(format!(
+                            "simulcast refers to unknown {} rid {:?}",
+                            direction, id
+                        )));
+                    }
+                }
+            }
+            attributes.push(simulcast.convert());
+        }
+
+        for attribute in self.attributes {
+            validate_key(&attribute.key)?;
+            if let Some(value) = &attribute.value {
+                validate_value(&attribute.key, value)?;
+            }
+            attributes.push(attribute);
+        }
+
+        for candidate in self.candidates {
+            validate_value(ATTR_KEY_CANDIDATE, &candidate)?;
+            attributes.push(CandidateAttribute::unmarshal(&candidate)?.convert());
+        }
+
+        if self.end_of_candidates {
+            attributes.push(Attribute::new(ATTR_KEY_END_OF_CANDIDATES.to_owned(), None));
+        }
+
+        Ok(MediaDescription {
+            media_name: MediaName {
+                media: self.media,
+                port: RangedPort {
+                    value: self.port,
+                    range: None,
+                },
+                protos: self.protos,
+                formats,
+            },
+            media_title: None,
+            connection_information: self.connection_information,
+            bandwidth: vec![],
+            encryption_key: None,
+            attributes,
+        })
+    }
+}
+
+/// SessionDescriptionBuilder builds a SessionDescription out of typed values and
+/// [`MediaDescriptionBuilder`]s, see [`SessionDescriptionBuilder::build`].
+#[derive(Debug, Clone)]
+pub struct SessionDescriptionBuilder {
+    origin: Option<Origin>,
+    session_name: String,
+    ice_lite: bool,
+    ice_credentials: Option<(String, String)>,
+    fingerprint: Option<(String, String)>,
+    groups: Vec<(String, Vec<String>)>,
+    extmap_allow_mixed: bool,
+    attributes: Vec<Attribute>,
+    media: Vec<MediaDescriptionBuilder>,
+}
+
+impl Default for SessionDescriptionBuilder {
+    fn default() -> Self {
+        SessionDescriptionBuilder {
+            origin: None,
+            session_name: "-".to_owned(),
+            ice_lite: false,
+            ice_credentials: None,
+            fingerprint: None,
+            groups: vec![],
+            extmap_allow_mixed: false,
+            attributes: vec![],
+            media: vec![],
+        }
+    }
+}
+
+impl SessionDescriptionBuilder {
+    /// new creates a builder of a session description with the settings required
+    /// by JSEP, see [`SessionDescription::new_jsep_session_description`].
+    pub fn new() -> Self {
+        SessionDescriptionBuilder::default()
+    }
+
+    /// origin sets the "o=" line instead of a random session id.
+    pub fn origin(mut self, origin: Origin) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// session_name sets the "s=" line.
+    pub fn session_name(mut self, session_name: &str) -> Self {
+        self.session_name = session_name.to_owned();
+        self
+    }
+
+    /// ice_lite adds the "a=ice-lite" attribute.
+    pub fn ice_lite(mut self) -> Self {
+        self.ice_lite = true;
+        self
+    }
+
+    /// ice_credentials sets the session-level "a=ice-ufrag" and "a=ice-pwd" attributes.
+    pub fn ice_credentials(mut self, username: &str, password: &str) -> Self {
+        self.ice_credentials = Some((username.to_owned(), password.to_owned()));
+        self
+    }
+
+    /// fingerprint sets the session-level "a=fingerprint" attribute.
+    pub fn fingerprint(mut self, algorithm: &str, value: &str) -> Self {
+        self.fingerprint = Some((algorithm.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// group adds an "a=group" attribute, e.g. "BUNDLE". The media of the group
+    /// must be added with their mid.
+    pub fn group(mut self, semantics: &str, mids: &[&str]) -> Self {
+        self.groups.push((
+            semantics.to_owned(),
+            mids.iter().map(|m| m.to_string()).collect(),
+        ));
+        self
+    }
+
+    /// extmap_allow_mixed adds the session-level "a=extmap-allow-mixed" attribute, allowing
+    /// one- and two-byte RTP header extensions to be mixed in every media.
+    pub fn extmap_allow_mixed(mut self) -> Self {
+        self.extmap_allow_mixed = true;
+        self
+    }
+
+    /// property_attribute adds any other session-level property attribute 'a=key'.
+    pub fn property_attribute(mut self, key: &str) -> Self {
+        self.attributes.push(Attribute::new(key.to_owned(), None));
+        self
+    }
+
+    /// value_attribute adds any other session-level value attribute 'a=key:value'.
+    pub fn value_attribute(mut self, key: &str, value: &str) -> Self {
+        self.attributes
+            .push(Attribute::new(key.to_owned(), Some(value.to_owned())));
+        self
+    }
+
+    /// media adds a media description.
+    pub fn media(mut self, media: MediaDescriptionBuilder) -> Self {
+        self.media.push(media);
+        self
+    }
+
+    /// build validates the values of the builder and creates the SessionDescription.
+    ///
+    /// Besides the checks of [`MediaDescriptionBuilder::build`], it fails if two media
+    /// have the same mid or if a group refers to an unknown mid.
+    pub fn build(self) -> Result<SessionDescription> {
+        let mut d = SessionDescription::new_jsep_session_description(false);
+        if let Some(origin) = self.origin {
+            validate_token("origin username", &origin.username)?;
+            validate_token("origin network type", &origin.network_type)?;
+            validate_token("origin address type", &origin.address_type)?;
+            validate_token("origin address", &origin.unicast_address)?;
+            d.origin = origin;
+        }
+
+        if self.session_name.trim().is_empty() {
+            return Err(Error::SdpInvalidValue("empty session name".to_owned()));
+        }
+        validate_value("session name", &self.session_name)?;
+        d.session_name = self.session_name;
+
+        let mut mids = HashSet::new();
+        for media in &self.media {
+            if let Some(mid) = media.mid_value() {
+                if !mids.insert(mid.to_owned()) {
+                    return Err(Error::DuplicateMid(mid.to_owned()));
+                }
+            }
+        }
+
+        for (semantics, group) in self.groups {
+            validate_token(ATTR_KEY_GROUP, &semantics)?;
+            if let Some(mid) = group.iter().find(|mid| !mids.contains(*mid)) {
+                return Err(Error::UnknownMid(mid.clone()));
+            }
+            let value = if group.is_empty() {
+                semantics
+            } else {
+                format!("{} {}", semantics, group.join(" "))
+            };
+            d = d.with_value_attribute(ATTR_KEY_GROUP.to_owned(), value);
+        }
+
+        if self.extmap_allow_mixed {
+            d = d.with_property_attribute(ATTR_KEY_EXTMAP_ALLOW_MIXED.to_owned());
+        }
+
+        if self.ice_lite {
+            d = d.with_property_attribute(ATTR_KEY_ICELITE.to_owned());
+        }
+
+        if let Some((username, password)) = self.ice_credentials {
+            validate_token("ice-ufrag", &username)?;
+            validate_token("ice-pwd", &password)?;
+            d = d
+                .with_value_attribute("ice-ufrag".to_owned(), username)
+                .with_value_attribute("ice-pwd".to_owned(), password);
+        }
+
+        if let Some((algorithm, value)) = self.fingerprint {
+            validate_token("fingerprint algorithm", &algorithm)?;
+            validate_token("fingerprint", &value)?;
+            d = d.with_fingerprint(algorithm, value);
+        }
+
+        for attribute in self.attributes {
+            validate_key(&attribute.key)?;
+            if let Some(value) = &attribute.value {
+                validate_value(&attribute.key, value)?;
+            }
+            d.attributes.push(attribute);
+        }
+
+        for media in self.media {
+            d = d.with_media(media.build()?);
+        }
+
+        Ok(d)
+    }
+}
+
+/// validate_token checks that a value is a single non-empty word which can be parsed back.
+fn validate_token(what: &str, value: &str) -> Result<()> {
+    if value.is_empty() || value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        Err(Error::SdpInvalidValue(format!("{} {:?}", what, value)))
+    } else {
+        Ok(())
+    }
+}
+
+/// validate_key checks that an attribute key is a token without the ':' separating
+/// it from the value.
+fn validate_key(key: &str) -> Result<()> {
+    validate_token("attribute key", key)?;
+    if key.contains(':') {
+        Err(Error::SdpInvalidValue(format!("attribute key {:?}", key)))
+    } else {
+        Ok(())
+    }
+}
+
+/// validate_value checks that a value doesn't break the line it is written on.
+fn validate_value(what: &str, value: &str) -> Result<()> {
+    if value.chars().any(|c| c.is_control()) {
+        Err(Error::SdpInvalidValue(format!("{} {:?}", what, value)))
+    } else {
+        Ok(())
+    }
+}
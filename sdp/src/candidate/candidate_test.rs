@@ -0,0 +1,84 @@
+use super::*;
+
+#[test]
+fn test_candidate_attribute() -> Result<()> {
+    let tests = vec![
+        (
+            "1 1 udp 2130706431 10.0.0.1 50000 typ host",
+            CandidateAttribute {
+                foundation: "1".to_owned(),
+                component: 1,
+                transport: "udp".to_owned(),
+                priority: 2130706431,
+                address: "10.0.0.1".to_owned(),
+                port: 50000,
+                typ: "host".to_owned(),
+                ..Default::default()
+            },
+        ),
+        (
+            "848194626 1 udp 16777215 50.0.0.1 5000 typ relay raddr 192.168.0.1 rport 5001",
+            CandidateAttribute {
+                foundation: "848194626".to_owned(),
+                component: 1,
+                transport: "udp".to_owned(),
+                priority: 16777215,
+                address: "50.0.0.1".to_owned(),
+                port: 5000,
+                typ: "relay".to_owned(),
+                related_address: Some(("192.168.0.1".to_owned(), 5001)),
+                ..Default::default()
+            },
+        ),
+        (
+            "1052353102 1 tcp 1518280447 192.168.0.196 9 typ host tcptype active generation 0 \
+             ufrag Ng4i network-id 1 network-cost 10 foo bar",
+            CandidateAttribute {
+                foundation: "1052353102".to_owned(),
+                component: 1,
+                transport: "tcp".to_owned(),
+                priority: 1518280447,
+                address: "192.168.0.196".to_owned(),
+                port: 9,
+                typ: "host".to_owned(),
+                tcp_type: Some("active".to_owned()),
+                generation: Some(0),
+                ufrag: Some("Ng4i".to_owned()),
+                network_id: Some(1),
+                network_cost: Some(10),
+                extensions: vec![("foo".to_owned(), "bar".to_owned())],
+                ..Default::default()
+            },
+        ),
+    ];
+
+    for (value, expected) in tests {
+        let actual = CandidateAttribute::unmarshal(value)?;
+        assert_eq!(actual, expected, "{}", value);
+        assert_eq!(actual.to_string(), value);
+        assert_eq!(actual.marshal(), format!("candidate:{}", value));
+        assert_eq!(CandidateAttribute::unmarshal(&actual.marshal())?, expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_candidate_attribute_errors() {
+    let tests = vec![
+        "",
+        "1938809241",
+        "1 1 udp 2130706431 10.0.0.1 50000 host",
+        "1 99999999 udp 2130706431 10.0.0.1 50000 typ host",
+        "1 1 udp 99999999999 10.0.0.1 50000 typ host",
+        "1 1 udp 2130706431 10.0.0.1 99999999 typ host",
+        "1 1 udp 2130706431 10.0.0.1 50000 typ srflx raddr",
+        "1 1 udp 2130706431 10.0.0.1 50000 typ srflx raddr 192.168.0.1",
+        "1 1 udp 2130706431 10.0.0.1 50000 typ srflx raddr 192.168.0.1 rport 99999999",
+        "1 1 udp 2130706431 10.0.0.1 50000 typ host generation zero",
+    ];
+
+    for value in tests {
+        assert!(CandidateAttribute::unmarshal(value).is_err(), "{:?}", value);
+    }
+}
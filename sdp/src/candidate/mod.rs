@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod candidate_test;
+
+use super::error::{Error, Result};
+use crate::description::common::*;
+use crate::description::session::ATTR_KEY_CANDIDATE;
+
+use std::fmt;
+
+const CANDIDATE_PREFIX: &str = "candidate:";
+
+/// CandidateAttribute is the value of an "a=candidate" attribute, an ICE candidate
+/// with its extensions.
+///
+/// <https://tools.ietf.org/html/rfc8839#section-5.1>
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct CandidateAttribute {
+    pub foundation: String,
+    pub component: u16,
+    /// transport is the transport protocol, e.g. "udp" or "tcp".
+    pub transport: String,
+    pub priority: u32,
+    /// address is an IP address or an mDNS hostname.
+    pub address: String,
+    pub port: u16,
+    /// typ is the candidate type, e.g. "host", "srflx", "prflx" or "relay".
+    pub typ: String,
+    /// related_address is the "raddr" and "rport" of a reflexive or relayed candidate.
+    pub related_address: Option<(String, u16)>,
+    /// tcp_type is the "tcptype" of a TCP candidate, e.g. "active", "passive" or "so".
+    ///
+    /// <https://tools.ietf.org/html/rfc6544#section-4.5>
+    pub tcp_type: Option<String>,
+    pub generation: Option<u32>,
+    pub ufrag: Option<String>,
+    pub network_id: Option<u32>,
+    pub network_cost: Option<u32>,
+    /// extensions are the other extension attributes, in the order they were written.
+    pub extensions: Vec<(String, String)>,
+}
+
+impl fmt::Display for CandidateAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} typ {}",
+            self.foundation,
+            self.component,
+            self.transport,
+            self.priority,
+            self.address,
+            self.port,
+            self.typ
+        )?;
+
+        if let Some((address, port)) = &self.related_address {
+            write!(f, " raddr {} rport {}", address, port)?;
+        }
+        if let Some(tcp_type) = &self.tcp_type {
+            write!(f, " tcptype {}", tcp_type)?;
+        }
+        if let Some(generation) = self.generation {
+            write!(f, " generation {}", generation)?;
+        }
+        if let Some(ufrag) = &self.ufrag {
+            write!(f, " ufrag {}", ufrag)?;
+        }
+        if let Some(network_id) = self.network_id {
+            write!(f, " network-id {}", network_id)?;
+        }
+        if let Some(network_cost) = self.network_cost {
+            write!(f, " network-cost {}", network_cost)?;
+        }
+        for (name, value) in &self.extensions {
+            write!(f, " {} {}", name, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl CandidateAttribute {
+    /// converts this object to an Attribute
+    pub fn convert(&self) -> Attribute {
+        Attribute {
+            key: ATTR_KEY_CANDIDATE.to_owned(),
+            value: Some(self.to_string()),
+        }
+    }
+
+    /// unmarshal parses the value of an "a=candidate" attribute, e.g.
+    /// "1 1 udp 2130706431 10.0.0.1 50000 typ host generation 0". The "candidate:"
+    /// prefix of the candidates exchanged out of the SDP is accepted as well.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let value = value.strip_prefix(CANDIDATE_PREFIX).unwrap_or(value);
+
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        if fields.len() < 8 || fields[6] != "typ" {
+            return Err(Error::SdpInvalidValue(format!("candidate {:?}", value)));
+        }
+
+        let mut candidate = CandidateAttribute {
+            foundation: fields[0].to_owned(),
+            component: fields[1].parse()?,
+            transport: fields[2].to_owned(),
+            priority: fields[3].parse()?,
+            address: fields[4].to_owned(),
+            port: fields[5].parse()?,
+            typ: fields[7].to_owned(),
+            ..Default::default()
+        };
+
+        let extensions = &fields[8..];
+        if extensions.len() % 2 != 0 {
+            return Err(Error::SdpInvalidValue(format!(
+                "candidate {:?} has an extension without value",
+                value
+            )));
+        }
+
+        let mut related_address = None;
+        let mut related_port = None;
+        for pair in extensions.chunks(2) {
+            let (name, field) = (pair[0], pair[1]);
+            match name {
+                "raddr" => related_address = Some(field.to_owned()),
+                "rport" => related_port = Some(field.parse()?),
+                "tcptype" => candidate.tcp_type = Some(field.to_owned()),
+                "generation" => candidate.generation = Some(field.parse()?),
+                "ufrag" => candidate.ufrag = Some(field.to_owned()),
+                "network-id" => candidate.network_id = Some(field.parse()?),
+                "network-cost" => candidate.network_cost = Some(field.parse()?),
+                _ => candidate
+                    .extensions
+                    .push((name.to_owned(), field.to_owned())),
+            }
+        }
+
+        candidate.related_address = match (related_address, related_port) {
+            (Some(address), Some(port)) => Some((address, port)),
+            (None, None) => None,
+            _ => {
+                return Err(Error::SdpInvalidValue(format!(
+                    "candidate {:?} needs both raddr and rport",
+                    value
+                )))
+            }
+        };
+
+        Ok(candidate)
+    }
+
+    /// marshal creates a string from a CandidateAttribute
+    pub fn marshal(&self) -> String {
+        CANDIDATE_PREFIX.to_owned() + self.to_string().as_str()
+    }
+}
@@ -44,6 +44,15 @@ impl fmt::Display for Address {
     }
 }
 
+/// BANDWIDTH_TYPE_AS is the "b=AS" bandwidth type, the maximum bandwidth of the
+/// application in kilobits per second.
+pub const BANDWIDTH_TYPE_AS: &str = "AS";
+/// BANDWIDTH_TYPE_TIAS is the "b=TIAS" bandwidth type, the maximum bandwidth of the
+/// application in bits per second, without the transport overhead.
+///
+/// <https://tools.ietf.org/html/rfc3890>
+pub const BANDWIDTH_TYPE_TIAS: &str = "TIAS";
+
 /// Bandwidth describes an optional field which denotes the proposed bandwidth
 /// to be used by the session or media.
 #[derive(Debug, Default, Clone)]
@@ -592,3 +592,159 @@ fn test_unmarshal_non_nil_address() -> Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn test_media_description_munging() -> Result<()> {
+    let input = "v=0\r\n\
+         o=- 0 0 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         m=video 9 UDP/TLS/RTP/SAVPF 96 97 98 99\r\n\
+         b=AS:1000\r\n\
+         a=rtpmap:96 VP8/90000\r\n\
+         a=rtcp-fb:96 nack\r\n\
+         a=rtpmap:97 rtx/90000\r\n\
+         a=fmtp:97 apt=96\r\n\
+         a=rtpmap:98 H264/90000\r\n\
+         a=fmtp:98 level-asymmetry-allowed=1;packetization-mode=1\r\n\
+         a=rtpmap:99 rtx/90000\r\n\
+         a=fmtp:99 apt=98\r\n\
+         a=rtcp-fb:* transport-cc\r\n";
+    let mut reader = Cursor::new(input.as_bytes());
+    let mut sdp = SessionDescription::unmarshal(&mut reader)?;
+    let media = &mut sdp.media_descriptions[0];
+
+    media.set_bandwidth(BANDWIDTH_TYPE_AS, 500);
+    media.set_bandwidth(BANDWIDTH_TYPE_TIAS, 500000);
+    media.reorder_codecs(&[98, 99])?;
+    assert_eq!(media.media_name.formats, vec!["98", "99", "96", "97"]);
+    assert_eq!(
+        Err(Error::PayloadTypeNotFound),
+        media.reorder_codecs(&[100])
+    );
+
+    media.remove_codec(96)?;
+    assert_eq!(Err(Error::PayloadTypeNotFound), media.remove_codec(97));
+
+    let expected = "v=0\r\n\
+         o=- 0 0 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         m=video 9 UDP/TLS/RTP/SAVPF 98 99\r\n\
+         b=AS:500\r\n\
+         b=TIAS:500000\r\n\
+         a=rtpmap:98 H264/90000\r\n\
+         a=fmtp:98 level-asymmetry-allowed=1;packetization-mode=1\r\n\
+         a=rtpmap:99 rtx/90000\r\n\
+         a=fmtp:99 apt=98\r\n\
+         a=rtcp-fb:* transport-cc\r\n";
+    assert_eq!(sdp.marshal(), expected);
+
+    sdp.media_descriptions[0].remove_bandwidth(BANDWIDTH_TYPE_AS);
+    assert_eq!(sdp.media_descriptions[0].bandwidth.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_media_description_sctp_attributes() -> Result<()> {
+    let input = "v=0\r\n\
+         o=- 0 0 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+         a=sctp-port:5000\r\n\
+         a=max-message-size:262144\r\n\
+         m=application 9 DTLS/SCTP 5000\r\n\
+         a=sctpmap:5000 webrtc-datachannel 256\r\n";
+    let mut reader = Cursor::new(input.as_bytes());
+    let mut sdp = SessionDescription::unmarshal(&mut reader)?;
+
+    let media = &mut sdp.media_descriptions[0];
+    assert_eq!(media.sctp_port()?, Some(5000));
+    assert_eq!(media.max_message_size()?, Some(262144));
+    assert_eq!(media.sctpmap()?, None);
+    media.set_sctp_port(5001);
+    media.set_max_message_size(0);
+    assert_eq!(media.sctp_port()?, Some(5001));
+    assert_eq!(media.max_message_size()?, Some(0));
+    assert_eq!(media.attributes.len(), 2);
+
+    let legacy = &mut sdp.media_descriptions[1];
+    let sctpmap = SctpMap {
+        port: 5000,
+        protocol: "webrtc-datachannel".to_owned(),
+        streams: Some(256),
+    };
+    assert_eq!(legacy.sctpmap()?, Some(sctpmap.clone()));
+    assert_eq!(legacy.sctp_port()?, Some(5000));
+    assert_eq!(legacy.max_message_size()?, None);
+    legacy.set_sctpmap(&SctpMap {
+        streams: None,
+        ..sctpmap
+    });
+    assert_eq!(
+        legacy.attribute(ATTR_KEY_SCTPMAP),
+        Some(Some("5000 webrtc-datachannel"))
+    );
+
+    for value in [
+        "",
+        "5000",
+        "x webrtc-datachannel",
+        "5000 webrtc-datachannel 256 1",
+    ] {
+        assert!(SctpMap::unmarshal(value).is_err(), "{:?}", value);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_unmarshal_lenient() -> Result<()> {
+    let input = "v=0\r\n\
+         o=- 0 0 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         b=TIAS:64000\r\n\
+         t=0 0\r\n\
+         garbage\r\n\
+         a=group:BUNDLE 0\r\n\
+         m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+         c=IN IP4\r\n\
+         b=AS:abc\r\n\
+         y=unknown\r\n\
+         a=mid:0\r\n\
+         a=rtpmap:111 opus/48000/2\r\n";
+
+    let mut reader = Cursor::new(input.as_bytes());
+    assert!(SessionDescription::unmarshal(&mut reader).is_err());
+
+    let mut reader = Cursor::new(input.as_bytes());
+    let (sdp, warnings) = SessionDescription::unmarshal_lenient(&mut reader)?;
+    let lines: Vec<&str> = warnings.iter().map(|w| w.line.as_str()).collect();
+    assert_eq!(
+        lines,
+        vec!["b=TIAS:64000", "garbage", "b=AS:abc", "y=unknown"]
+    );
+    assert_eq!(warnings[0].error, Error::SdpInvalidValue("TIAS".to_owned()));
+
+    assert_eq!(sdp.attribute("group"), Some(&"BUNDLE 0".to_owned()));
+    assert_eq!(sdp.media_descriptions.len(), 1);
+    let media = &sdp.media_descriptions[0];
+    assert!(media.connection_information.is_some());
+    assert!(media.bandwidth.is_empty());
+    assert_eq!(media.attribute("mid"), Some(Some("0")));
+    assert_eq!(media.attribute("rtpmap"), Some(Some("111 opus/48000/2")));
+
+    // lines following an invalid media could not be attributed
+    let input = "v=0\r\n\
+         o=- 0 0 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         m=audio 9 UDP/XYZ 111\r\n\
+         a=mid:0\r\n";
+    let mut reader = Cursor::new(input.as_bytes());
+    assert!(SessionDescription::unmarshal_lenient(&mut reader).is_err());
+
+    Ok(())
+}
@@ -2,8 +2,18 @@ use std::collections::HashMap;
 use std::fmt;
 use url::Url;
 
+use crate::candidate::CandidateAttribute;
 use crate::description::common::*;
+use crate::description::session::{
+    ATTR_KEY_EXTMAP_ALLOW_MIXED, ATTR_KEY_MAX_MESSAGE_SIZE, ATTR_KEY_MSID, ATTR_KEY_RID,
+    ATTR_KEY_SCTPMAP, ATTR_KEY_SCTP_PORT, ATTR_KEY_SIMULCAST, ATTR_KEY_SSRCGROUP,
+};
+use crate::direction::Direction;
+use crate::error::{Error, Result};
 use crate::extmap::*;
+use crate::msid::Msid;
+use crate::simulcast::*;
+use crate::ssrc::SsrcGroup;
 
 /// Constants for extmap key
 pub const EXT_MAP_VALUE_TRANSPORT_CC_KEY: isize = 3;
@@ -69,6 +79,120 @@ impl MediaDescription {
         None
     }
 
+    /// direction returns the direction of the media from its "a=sendrecv", "a=sendonly",
+    /// "a=recvonly" or "a=inactive" attribute, Direction::Unspecified if it has none.
+    pub fn direction(&self) -> Direction {
+        self.attributes
+            .iter()
+            .map(|a| Direction::new(&a.key))
+            .find(|direction| *direction != Direction::Unspecified)
+            .unwrap_or_default()
+    }
+
+    /// extmap_allow_mixed returns if the media-level "a=extmap-allow-mixed" attribute is set,
+    /// allowing one- and two-byte RTP header extensions to be mixed in this media.
+    pub fn extmap_allow_mixed(&self) -> bool {
+        self.attribute(ATTR_KEY_EXTMAP_ALLOW_MIXED).is_some()
+    }
+
+    /// candidates parses the "a=candidate" attributes of the media description.
+    pub fn candidates(&self) -> Result<Vec<CandidateAttribute>> {
+        self.attributes
+            .iter()
+            .filter(|a| a.is_ice_candidate())
+            .map(|a| CandidateAttribute::unmarshal(a.value.as_deref().unwrap_or_default()))
+            .collect()
+    }
+
+    /// rids parses the "a=rid" attributes of the media description.
+    pub fn rids(&self) -> Result<Vec<Rid>> {
+        self.attributes
+            .iter()
+            .filter(|a| a.key == ATTR_KEY_RID)
+            .map(|a| Rid::unmarshal(a.value.as_deref().unwrap_or_default()))
+            .collect()
+    }
+
+    /// msids parses the "a=msid" attributes of the media description.
+    pub fn msids(&self) -> Result<Vec<Msid>> {
+        self.attributes
+            .iter()
+            .filter(|a| a.key == ATTR_KEY_MSID)
+            .map(|a| Msid::unmarshal(a.value.as_deref().unwrap_or_default()))
+            .collect()
+    }
+
+    /// ssrc_groups parses the "a=ssrc-group" attributes of the media description.
+    pub fn ssrc_groups(&self) -> Result<Vec<SsrcGroup>> {
+        self.attributes
+            .iter()
+            .filter(|a| a.key == ATTR_KEY_SSRCGROUP)
+            .map(|a| SsrcGroup::unmarshal(a.value.as_deref().unwrap_or_default()))
+            .collect()
+    }
+
+    /// simulcast parses the "a=simulcast" attribute of the media description, if any.
+    pub fn simulcast(&self) -> Result<Option<Simulcast>> {
+        match self.attribute(ATTR_KEY_SIMULCAST) {
+            Some(value) => Ok(Some(Simulcast::unmarshal(value.unwrap_or_default())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// sctp_port returns the SCTP port of a data channel media from its "a=sctp-port"
+    /// attribute, or from the legacy "a=sctpmap" attribute if there is none.
+    pub fn sctp_port(&self) -> Result<Option<u16>> {
+        match self.attribute(ATTR_KEY_SCTP_PORT) {
+            Some(value) => Ok(Some(value.unwrap_or_default().trim().parse()?)),
+            None => Ok(self.sctpmap()?.map(|sctpmap| sctpmap.port)),
+        }
+    }
+
+    /// set_sctp_port sets the "a=sctp-port" attribute, replacing the existing one if any.
+    pub fn set_sctp_port(&mut self, port: u16) {
+        self.set_value_attribute(ATTR_KEY_SCTP_PORT, port.to_string());
+    }
+
+    /// max_message_size returns the value of the "a=max-message-size" attribute, the
+    /// largest message in bytes the data channels of the media can receive, 0 meaning
+    /// there is no limit.
+    ///
+    /// <https://tools.ietf.org/html/rfc8841#section-6>
+    pub fn max_message_size(&self) -> Result<Option<u64>> {
+        match self.attribute(ATTR_KEY_MAX_MESSAGE_SIZE) {
+            Some(value) => Ok(Some(value.unwrap_or_default().trim().parse()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// set_max_message_size sets the "a=max-message-size" attribute, replacing the
+    /// existing one if any.
+    pub fn set_max_message_size(&mut self, max_message_size: u64) {
+        self.set_value_attribute(ATTR_KEY_MAX_MESSAGE_SIZE, max_message_size.to_string());
+    }
+
+    /// sctpmap parses the legacy "a=sctpmap" attribute of the media description, if any.
+    pub fn sctpmap(&self) -> Result<Option<SctpMap>> {
+        match self.attribute(ATTR_KEY_SCTPMAP) {
+            Some(value) => Ok(Some(SctpMap::unmarshal(value.unwrap_or_default())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// set_sctpmap sets the legacy "a=sctpmap" attribute, replacing the existing one if any.
+    pub fn set_sctpmap(&mut self, sctpmap: &SctpMap) {
+        self.set_value_attribute(ATTR_KEY_SCTPMAP, sctpmap.to_string());
+    }
+
+    fn set_value_attribute(&mut self, key: &str, value: String) {
+        match self.attributes.iter_mut().find(|a| a.key == key) {
+            Some(a) => a.value = Some(value),
+            None => self
+                .attributes
+                .push(Attribute::new(key.to_owned(), Some(value))),
+        }
+    }
+
     /// new_jsep_media_description creates a new MediaName with
     /// some settings that are required by the JSEP spec.
     pub fn new_jsep_media_description(codec_type: String, _codec_prefs: Vec<&str>) -> Self {
@@ -175,6 +299,30 @@ impl MediaDescription {
         self.with_property_attribute(e.marshal())
     }
 
+    /// with_rid adds an "a=rid" attribute to the media description
+    pub fn with_rid(mut self, rid: &Rid) -> Self {
+        self.attributes.push(rid.convert());
+        self
+    }
+
+    /// with_msid adds an "a=msid" attribute to the media description
+    pub fn with_msid(mut self, msid: &Msid) -> Self {
+        self.attributes.push(msid.convert());
+        self
+    }
+
+    /// with_ssrc_group adds an "a=ssrc-group" attribute to the media description
+    pub fn with_ssrc_group(mut self, group: &SsrcGroup) -> Self {
+        self.attributes.push(group.convert());
+        self
+    }
+
+    /// with_simulcast adds an "a=simulcast" attribute to the media description
+    pub fn with_simulcast(mut self, simulcast: &Simulcast) -> Self {
+        self.attributes.push(simulcast.convert());
+        self
+    }
+
     /// with_transport_cc_extmap adds an extmap to the media description
     pub fn with_transport_cc_extmap(self) -> Self {
         let uri = {
@@ -197,6 +345,138 @@ impl MediaDescription {
 
         self.with_extmap(e)
     }
+
+    /// set_bandwidth sets the "b=" line of the given type, e.g. [`BANDWIDTH_TYPE_AS`] in
+    /// kilobits per second or [`BANDWIDTH_TYPE_TIAS`] in bits per second, replacing the
+    /// existing line of that type if any.
+    pub fn set_bandwidth(&mut self, bandwidth_type: &str, bandwidth: u64) {
+        match self
+            .bandwidth
+            .iter_mut()
+            .find(|b| !b.experimental && b.bandwidth_type == bandwidth_type)
+        {
+            Some(b) => b.bandwidth = bandwidth,
+            None => self.bandwidth.push(Bandwidth {
+                experimental: false,
+                bandwidth_type: bandwidth_type.to_owned(),
+                bandwidth,
+            }),
+        }
+    }
+
+    /// remove_bandwidth removes the "b=" lines of the given type.
+    pub fn remove_bandwidth(&mut self, bandwidth_type: &str) {
+        self.bandwidth
+            .retain(|b| b.experimental || b.bandwidth_type != bandwidth_type);
+    }
+
+    /// reorder_codecs moves the given payload types to the front of the "m=" line, in the
+    /// given order, to express the preference among the codecs of the media. The other
+    /// payload types keep their relative order after them.
+    pub fn reorder_codecs(&mut self, payload_types: &[u8]) -> Result<()> {
+        let preferred: Vec<String> = payload_types.iter().map(|pt| pt.to_string()).collect();
+        if preferred
+            .iter()
+            .any(|format| !self.media_name.formats.contains(format))
+        {
+            return Err(Error::PayloadTypeNotFound);
+        }
+
+        self.media_name.formats.sort_by_key(|format| {
+            preferred
+                .iter()
+                .position(|p| p == format)
+                .unwrap_or(preferred.len())
+        });
+
+        Ok(())
+    }
+
+    /// remove_codec removes a payload type from the "m=" line together with its "a=rtpmap",
+    /// "a=fmtp" and "a=rtcp-fb" attributes. The RTX payload types associated with it by an
+    /// "apt=" parameter are removed as well.
+    pub fn remove_codec(&mut self, payload_type: u8) -> Result<()> {
+        if !self.media_name.formats.contains(&payload_type.to_string()) {
+            return Err(Error::PayloadTypeNotFound);
+        }
+
+        let apt = format!("apt={}", payload_type);
+        let mut removed = vec![payload_type];
+        for a in self.attributes.iter().filter(|a| a.key == "fmtp") {
+            let params = a
+                .value
+                .as_deref()
+                .and_then(|value| value.split_whitespace().nth(1))
+                .unwrap_or_default();
+            if params.split(';').any(|param| param.trim() == apt) {
+                if let Some(pt) = codec_attribute_payload_type(a) {
+                    removed.push(pt);
+                }
+            }
+        }
+
+        self.media_name
+            .formats
+            .retain(|format| format.parse().map_or(true, |pt: u8| !removed.contains(&pt)));
+        self.attributes
+            .retain(|a| codec_attribute_payload_type(a).map_or(true, |pt| !removed.contains(&pt)));
+
+        Ok(())
+    }
+}
+
+/// SctpMap is the value of the legacy "a=sctpmap" attribute of a data channel media,
+/// which was replaced by "a=sctp-port".
+///
+/// <https://tools.ietf.org/html/draft-ietf-mmusic-sctp-sdp-05#section-5>
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SctpMap {
+    pub port: u16,
+    /// protocol is the application protocol, e.g. "webrtc-datachannel".
+    pub protocol: String,
+    /// streams is the number of incoming SCTP streams, if advertised.
+    pub streams: Option<u16>,
+}
+
+impl fmt::Display for SctpMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.port, self.protocol)?;
+        if let Some(streams) = self.streams {
+            write!(f, " {}", streams)?;
+        }
+        Ok(())
+    }
+}
+
+impl SctpMap {
+    /// unmarshal parses the value of an "a=sctpmap" attribute, e.g.
+    /// "5000 webrtc-datachannel 256".
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        if fields.len() < 2 || fields.len() > 3 {
+            return Err(Error::SdpInvalidValue(format!("sctpmap {:?}", value)));
+        }
+
+        Ok(SctpMap {
+            port: fields[0].parse()?,
+            protocol: fields[1].to_owned(),
+            streams: match fields.get(2) {
+                Some(streams) => Some(streams.parse()?),
+                None => None,
+            },
+        })
+    }
+}
+
+/// codec_attribute_payload_type returns the payload type an "a=rtpmap", "a=fmtp" or
+/// "a=rtcp-fb" attribute applies to, None for other attributes and the "*" wildcard.
+fn codec_attribute_payload_type(a: &Attribute) -> Option<u8> {
+    match a.key.as_str() {
+        "rtpmap" | "fmtp" | "rtcp-fb" => {
+            a.value.as_deref()?.split_whitespace().next()?.parse().ok()
+        }
+        _ => None,
+    }
 }
 
 /// RangedPort supports special format for the media field "m=" port value. If
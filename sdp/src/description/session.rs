@@ -5,6 +5,7 @@ use url::Url;
 
 use crate::error::{Error, Result};
 use crate::lexer::*;
+use crate::msid::MsidSemantic;
 use crate::util::*;
 
 use super::common::*;
@@ -29,12 +30,22 @@ pub const ATTR_KEY_RECV_ONLY: &str = "recvonly";
 pub const ATTR_KEY_SEND_ONLY: &str = "sendonly";
 pub const ATTR_KEY_SEND_RECV: &str = "sendrecv";
 pub const ATTR_KEY_EXT_MAP: &str = "extmap";
+pub const ATTR_KEY_EXTMAP_ALLOW_MIXED: &str = "extmap-allow-mixed";
+pub const ATTR_KEY_RID: &str = "rid";
+pub const ATTR_KEY_SIMULCAST: &str = "simulcast";
+pub const ATTR_KEY_SCTP_PORT: &str = "sctp-port";
+pub const ATTR_KEY_MAX_MESSAGE_SIZE: &str = "max-message-size";
+pub const ATTR_KEY_SCTPMAP: &str = "sctpmap";
+pub const ATTR_KEY_BUNDLE_ONLY: &str = "bundle-only";
 
 /// Constants for semantic tokens used in JSEP
 pub const SEMANTIC_TOKEN_LIP_SYNCHRONIZATION: &str = "LS";
 pub const SEMANTIC_TOKEN_FLOW_IDENTIFICATION: &str = "FID";
 pub const SEMANTIC_TOKEN_FORWARD_ERROR_CORRECTION: &str = "FEC";
+pub const SEMANTIC_TOKEN_FEC_FR: &str = "FEC-FR";
+pub const SEMANTIC_TOKEN_SIMULCAST: &str = "SIM";
 pub const SEMANTIC_TOKEN_WEBRTC_MEDIA_STREAMS: &str = "WMS";
+pub const SEMANTIC_TOKEN_BUNDLE: &str = "BUNDLE";
 
 /// Version describes the value provided by the "v=" field which gives
 /// the version of the Session Description Protocol.
@@ -298,6 +309,14 @@ impl SessionDescription {
         self.with_value_attribute("fingerprint".to_string(), algorithm + " " + value.as_str())
     }
 
+    /// with_msid_semantic sets the "a=msid-semantic" attribute of the session description,
+    /// replacing the existing one
+    pub fn with_msid_semantic(mut self, semantic: &MsidSemantic) -> Self {
+        self.attributes.retain(|a| a.key != ATTR_KEY_MSID_SEMANTIC);
+        self.attributes.push(semantic.convert());
+        self
+    }
+
     /// WithMedia adds a media description to the session description
     pub fn with_media(mut self, md: MediaDescription) -> Self {
         self.media_descriptions.push(md);
@@ -364,6 +383,22 @@ impl SessionDescription {
         None
     }
 
+    /// extmap_allow_mixed returns if the session-level "a=extmap-allow-mixed" attribute is set,
+    /// allowing one- and two-byte RTP header extensions to be mixed in every media.
+    pub fn extmap_allow_mixed(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|a| a.key == ATTR_KEY_EXTMAP_ALLOW_MIXED)
+    }
+
+    /// msid_semantic parses the "a=msid-semantic" attribute of the session description, if any.
+    pub fn msid_semantic(&self) -> Result<Option<MsidSemantic>> {
+        match self.attribute(ATTR_KEY_MSID_SEMANTIC) {
+            Some(value) => Ok(Some(MsidSemantic::unmarshal(value)?)),
+            None => Ok(None),
+        }
+    }
+
     /// Marshal takes a SDP struct to text
     ///
     /// <https://tools.ietf.org/html/rfc4566#section-5>
@@ -534,6 +569,24 @@ impl SessionDescription {
     /// +--------+----+-------+----+-----+----+-----+---+----+----+---+---+-----+---+---+----+---+----+
     /// ```
     pub fn unmarshal<R: io::BufRead + io::Seek>(reader: &mut R) -> Result<Self> {
+        let (desc, _) = SessionDescription::unmarshal_with(reader, false)?;
+        Ok(desc)
+    }
+
+    /// unmarshal_lenient deserializes a session description like [`unmarshal`](Self::unmarshal),
+    /// but skips the lines it fails to parse or which are out of order, returning a warning
+    /// for each of them, as browsers do. An invalid "m=" line still fails the whole
+    /// description as the lines following it could not be attributed to a media.
+    pub fn unmarshal_lenient<R: io::BufRead + io::Seek>(
+        reader: &mut R,
+    ) -> Result<(Self, Vec<ParseWarning>)> {
+        SessionDescription::unmarshal_with(reader, true)
+    }
+
+    fn unmarshal_with<R: io::BufRead + io::Seek>(
+        reader: &mut R,
+        lenient: bool,
+    ) -> Result<(Self, Vec<ParseWarning>)> {
         let mut lexer = Lexer {
             desc: SessionDescription {
                 version: 0,
@@ -554,12 +607,66 @@ impl SessionDescription {
             reader,
         };
 
+        let mut warnings = vec![];
+
+        // The states alternate between reading the type of a line and reading its value,
+        // so a failed line is skipped by resuming at the state which read its type.
         let mut state = Some(StateFn { f: s1 });
+        let mut resume: StateFnType<'_, R> = s1;
+        let mut line_start = 0;
+        let mut reads_type = true;
         while let Some(s) = state {
-            state = (s.f)(&mut lexer)?;
+            if lenient && reads_type {
+                resume = s.f;
+                line_start = lexer.reader.stream_position()?;
+            }
+
+            match (s.f)(&mut lexer) {
+                Ok(next) => {
+                    state = next;
+                    reads_type = !reads_type;
+                }
+                Err(err) if lenient => {
+                    let line = skip_line(lexer.reader, line_start)?;
+                    if line.is_empty() || line.starts_with("m=") {
+                        return Err(err);
+                    }
+                    warnings.push(ParseWarning { line, error: err });
+                    state = Some(StateFn { f: resume });
+                    reads_type = true;
+                }
+                Err(err) => return Err(err),
+            }
         }
 
-        Ok(lexer.desc)
+        Ok((lexer.desc, warnings))
+    }
+}
+
+/// ParseWarning is a line skipped by [`SessionDescription::unmarshal_lenient`].
+#[derive(Debug, PartialEq)]
+pub struct ParseWarning {
+    /// line is the skipped line, e.g. "b=XYZ:128".
+    pub line: String,
+    /// error is why the line was skipped.
+    pub error: Error,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.line, self.error)
+    }
+}
+
+/// skip_line moves the reader past the first non-empty line after line_start and
+/// returns it, or an empty string if the reader ends before.
+fn skip_line<R: io::BufRead + io::Seek>(reader: &mut R, line_start: u64) -> Result<String> {
+    reader.seek(io::SeekFrom::Start(line_start))?;
+    loop {
+        let (line, num_bytes) = read_value(reader)?;
+        if !line.is_empty() || num_bytes == 0 {
+            return Ok(line);
+        }
     }
 }
 
@@ -0,0 +1,129 @@
+use super::*;
+use crate::error::Result;
+
+use std::io::Cursor;
+
+const OLD_SDP: &str = "v=0\r\n\
+     o=- 0 1 IN IP4 0.0.0.0\r\n\
+     s=-\r\n\
+     t=0 0\r\n\
+     m=audio 9 UDP/TLS/RTP/SAVPF 111 0\r\n\
+     a=mid:0\r\n\
+     a=sendrecv\r\n\
+     a=extmap:1 urn:ietf:params:rtp-hdrext:sdes:mid\r\n\
+     a=rtpmap:111 opus/48000/2\r\n\
+     a=fmtp:111 minptime=10\r\n\
+     a=rtpmap:0 PCMU/8000\r\n\
+     a=ssrc:1001 cname:user\r\n\
+     a=candidate:1 1 udp 2130706431 10.0.0.1 50000 typ host\r\n\
+     m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+     a=mid:1\r\n\
+     a=sendrecv\r\n\
+     a=extmap:1 urn:ietf:params:rtp-hdrext:sdes:mid\r\n\
+     a=extmap:2 urn:ietf:params:rtp-hdrext:ssrc-audio-level\r\n\
+     a=rtpmap:96 VP8/90000\r\n\
+     m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+     a=mid:2\r\n\
+     a=sctp-port:5000\r\n";
+
+const NEW_SDP: &str = "v=0\r\n\
+     o=- 0 2 IN IP4 0.0.0.0\r\n\
+     s=-\r\n\
+     t=0 0\r\n\
+     m=audio 9 UDP/TLS/RTP/SAVPF 111 8\r\n\
+     a=mid:0\r\n\
+     a=sendrecv\r\n\
+     a=extmap:1 urn:ietf:params:rtp-hdrext:sdes:mid\r\n\
+     a=rtpmap:111 opus/48000/2\r\n\
+     a=fmtp:111 minptime=10;useinbandfec=1\r\n\
+     a=rtpmap:8 PCMA/8000\r\n\
+     a=ssrc:1001 cname:user\r\n\
+     a=candidate:2 1 udp 2130706431 10.0.0.2 50000 typ host\r\n\
+     m=video 0 UDP/TLS/RTP/SAVPF 96\r\n\
+     a=mid:1\r\n\
+     a=inactive\r\n\
+     a=extmap:3 urn:ietf:params:rtp-hdrext:sdes:mid\r\n\
+     a=extmap:4 urn:3gpp:video-orientation\r\n\
+     a=rtpmap:96 VP8/90000\r\n\
+     a=ssrc:2001 cname:user\r\n\
+     m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+     a=mid:3\r\n\
+     a=sendonly\r\n\
+     a=rtpmap:96 VP8/90000\r\n";
+
+fn unmarshal(sdp: &str) -> Result<SessionDescription> {
+    let mut reader = Cursor::new(sdp.as_bytes());
+    SessionDescription::unmarshal(&mut reader)
+}
+
+#[test]
+fn test_diff() -> Result<()> {
+    let (old, new) = (unmarshal(OLD_SDP)?, unmarshal(NEW_SDP)?);
+
+    assert!(diff(&old, &old).is_empty());
+
+    let actual = diff(&old, &new);
+    assert!(!actual.is_empty());
+    assert_eq!(actual.added, vec![2]);
+    assert_eq!(actual.removed, vec![2]);
+    assert_eq!(
+        actual.modified,
+        vec![
+            MediaDescriptionDiff {
+                mid: Some("0".to_owned()),
+                old_index: 0,
+                new_index: 0,
+                added_codecs: vec![8],
+                removed_codecs: vec![0],
+                modified_codecs: vec![111],
+                ..Default::default()
+            },
+            MediaDescriptionDiff {
+                mid: Some("1".to_owned()),
+                old_index: 1,
+                new_index: 1,
+                direction: Some((Direction::SendRecv, Direction::Inactive)),
+                rejected: Some(true),
+                added_extensions: vec!["urn:3gpp:video-orientation".to_owned()],
+                removed_extensions: vec!["urn:ietf:params:rtp-hdrext:ssrc-audio-level".to_owned()],
+                modified_extensions: vec!["urn:ietf:params:rtp-hdrext:sdes:mid".to_owned()],
+                attributes_changed: true,
+                ..Default::default()
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_without_mid() -> Result<()> {
+    let old = "v=0\r\n\
+         o=- 0 1 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         m=audio 9 RTP/AVP 0\r\n\
+         a=sendrecv\r\n";
+    let new = "v=0\r\n\
+         o=- 0 2 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         m=audio 9 RTP/AVP 0\r\n\
+         a=recvonly\r\n\
+         m=video 9 RTP/AVP 96\r\n";
+
+    let actual = diff(&unmarshal(old)?, &unmarshal(new)?);
+    assert_eq!(actual.added, vec![1]);
+    assert!(actual.removed.is_empty());
+    assert_eq!(
+        actual.modified,
+        vec![MediaDescriptionDiff {
+            old_index: 0,
+            new_index: 0,
+            direction: Some((Direction::SendRecv, Direction::RecvOnly)),
+            ..Default::default()
+        }]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,253 @@
+#[cfg(test)]
+mod diff_test;
+
+use crate::description::media::MediaDescription;
+use crate::description::session::*;
+use crate::direction::Direction;
+use crate::util::*;
+
+use std::collections::HashMap;
+
+/// SessionDescriptionDiff is the difference between two session descriptions, as
+/// returned by [`diff`]. The media descriptions are identified by their mid, or by
+/// their index if either of them has none.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SessionDescriptionDiff {
+    /// added are the indexes of the media descriptions of the new session description
+    /// which are not in the old one.
+    pub added: Vec<usize>,
+    /// removed are the indexes of the media descriptions of the old session description
+    /// which are not in the new one.
+    pub removed: Vec<usize>,
+    /// modified are the media descriptions in both session descriptions which differ.
+    pub modified: Vec<MediaDescriptionDiff>,
+}
+
+impl SessionDescriptionDiff {
+    /// is_empty returns true if the session descriptions have the same media.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// MediaDescriptionDiff is the difference between two versions of a media description.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct MediaDescriptionDiff {
+    pub mid: Option<String>,
+    pub old_index: usize,
+    pub new_index: usize,
+    /// direction is the old and the new direction, if it changed.
+    pub direction: Option<(Direction, Direction)>,
+    /// rejected is whether the media is rejected now, if it changed.
+    pub rejected: Option<bool>,
+    /// added_codecs are the payload types of the new "m=" line which are not in the old one.
+    pub added_codecs: Vec<u8>,
+    /// removed_codecs are the payload types of the old "m=" line which are not in the new one.
+    pub removed_codecs: Vec<u8>,
+    /// modified_codecs are the payload types whose "a=rtpmap", "a=fmtp" or "a=rtcp-fb"
+    /// attributes changed.
+    pub modified_codecs: Vec<u8>,
+    /// added_extensions are the URIs of the new "a=extmap" attributes.
+    pub added_extensions: Vec<String>,
+    /// removed_extensions are the URIs of the removed "a=extmap" attributes.
+    pub removed_extensions: Vec<String>,
+    /// modified_extensions are the URIs of the "a=extmap" attributes whose id, direction
+    /// or extension attributes changed.
+    pub modified_extensions: Vec<String>,
+    /// attributes_changed is true if any other attribute changed, e.g. the "a=ssrc" of
+    /// the sent tracks. The ICE candidates are not compared.
+    pub attributes_changed: bool,
+}
+
+impl MediaDescriptionDiff {
+    /// is_empty returns true if both versions of the media description are the same.
+    pub fn is_empty(&self) -> bool {
+        self.direction.is_none()
+            && self.rejected.is_none()
+            && self.added_codecs.is_empty()
+            && self.removed_codecs.is_empty()
+            && self.modified_codecs.is_empty()
+            && self.added_extensions.is_empty()
+            && self.removed_extensions.is_empty()
+            && self.modified_extensions.is_empty()
+            && !self.attributes_changed
+    }
+}
+
+/// diff compares two session descriptions, e.g. the current local description and
+/// the one a renegotiation would create, and returns how their media differ. A media
+/// description whose kind changed is reported as removed and added.
+pub fn diff(old: &SessionDescription, new: &SessionDescription) -> SessionDescriptionDiff {
+    let mut result = SessionDescriptionDiff::default();
+    let mut matched = vec![false; old.media_descriptions.len()];
+
+    for (new_index, new_media) in new.media_descriptions.iter().enumerate() {
+        let new_mid = mid(new_media);
+        let old_index = (0..old.media_descriptions.len()).find(|&old_index| {
+            let old_media = &old.media_descriptions[old_index];
+            let same_media = match (new_mid, mid(old_media)) {
+                (Some(new_mid), Some(old_mid)) => new_mid == old_mid,
+                _ => old_index == new_index,
+            };
+            let same_kind = old_media.media_name.media == new_media.media_name.media;
+            same_media && same_kind && !matched[old_index]
+        });
+
+        match old_index {
+            Some(old_index) => {
+                matched[old_index] = true;
+                let media_diff = diff_media(
+                    &old.media_descriptions[old_index],
+                    new_media,
+                    old_index,
+                    new_index,
+                );
+                if !media_diff.is_empty() {
+                    result.modified.push(media_diff);
+                }
+            }
+            None => result.added.push(new_index),
+        }
+    }
+
+    for (old_index, matched) in matched.into_iter().enumerate() {
+        if !matched {
+            result.removed.push(old_index);
+        }
+    }
+
+    result
+}
+
+fn diff_media(
+    old: &MediaDescription,
+    new: &MediaDescription,
+    old_index: usize,
+    new_index: usize,
+) -> MediaDescriptionDiff {
+    let mut result = MediaDescriptionDiff {
+        mid: mid(new).map(|mid| mid.to_owned()),
+        old_index,
+        new_index,
+        ..Default::default()
+    };
+
+    let (old_direction, new_direction) = (old.direction(), new.direction());
+    if old_direction != new_direction {
+        result.direction = Some((old_direction, new_direction));
+    }
+
+    let (old_rejected, new_rejected) = (is_rejected(old), is_rejected(new));
+    if old_rejected != new_rejected {
+        result.rejected = Some(new_rejected);
+    }
+
+    let (old_codecs, new_codecs) = (codecs(old), codecs(new));
+    let old_formats = payload_types(old);
+    let new_formats = payload_types(new);
+    for pt in &new_formats {
+        if !old_formats.contains(pt) {
+            result.added_codecs.push(*pt);
+        } else if old_codecs.get(pt) != new_codecs.get(pt) {
+            result.modified_codecs.push(*pt);
+        }
+    }
+    for pt in &old_formats {
+        if !new_formats.contains(pt) {
+            result.removed_codecs.push(*pt);
+        }
+    }
+
+    let (old_extensions, new_extensions) = (extensions(old), extensions(new));
+    for (uri, value) in &new_extensions {
+        match old_extensions.iter().find(|(old_uri, _)| old_uri == uri) {
+            Some((_, old_value)) if old_value != value => {
+                result.modified_extensions.push(uri.to_string())
+            }
+            Some(_) => {}
+            None => result.added_extensions.push(uri.to_string()),
+        }
+    }
+    for (uri, _) in &old_extensions {
+        if !new_extensions.iter().any(|(new_uri, _)| new_uri == uri) {
+            result.removed_extensions.push(uri.to_string());
+        }
+    }
+
+    result.attributes_changed = other_attributes(old) != other_attributes(new);
+
+    result
+}
+
+fn mid(media: &MediaDescription) -> Option<&str> {
+    media.attribute(ATTR_KEY_MID).flatten()
+}
+
+fn is_rejected(media: &MediaDescription) -> bool {
+    media.media_name.port.value == 0
+}
+
+fn payload_types(media: &MediaDescription) -> Vec<u8> {
+    media
+        .media_name
+        .formats
+        .iter()
+        .filter_map(|format| format.parse().ok())
+        .collect()
+}
+
+/// codecs returns the codecs of the "a=rtpmap", "a=fmtp" and "a=rtcp-fb" attributes
+/// of a media description by payload type.
+fn codecs(media: &MediaDescription) -> HashMap<u8, Codec> {
+    let mut codecs = HashMap::new();
+    for a in &media.attributes {
+        let attr = a.to_string();
+        let codec = match a.key.as_str() {
+            "rtpmap" => parse_rtpmap(&attr),
+            "fmtp" => parse_fmtp(&attr),
+            "rtcp-fb" => parse_rtcp_fb(&attr),
+            _ => continue,
+        };
+        if let Ok(codec) = codec {
+            merge_codecs(codec, &mut codecs);
+        }
+    }
+    codecs
+}
+
+/// extensions returns the URIs of the "a=extmap" attributes of a media description
+/// with their values.
+fn extensions(media: &MediaDescription) -> Vec<(&str, &str)> {
+    media
+        .attributes
+        .iter()
+        .filter(|a| a.key == ATTR_KEY_EXT_MAP)
+        .filter_map(|a| {
+            let value = a.value.as_deref()?;
+            let uri = value.split_whitespace().nth(1)?;
+            Some((uri, value))
+        })
+        .collect()
+}
+
+/// other_attributes returns the attributes of a media description not compared
+/// by the other fields of MediaDescriptionDiff, and not the ICE candidates.
+fn other_attributes(media: &MediaDescription) -> Vec<String> {
+    media
+        .attributes
+        .iter()
+        .filter(|a| {
+            !matches!(
+                a.key.as_str(),
+                ATTR_KEY_MID
+                    | ATTR_KEY_EXT_MAP
+                    | ATTR_KEY_CANDIDATE
+                    | ATTR_KEY_END_OF_CANDIDATES
+                    | "rtpmap"
+                    | "fmtp"
+                    | "rtcp-fb"
+            ) && Direction::new(&a.key) == Direction::Unspecified
+        })
+        .map(|a| a.to_string())
+        .collect()
+}
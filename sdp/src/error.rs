@@ -34,6 +34,16 @@ pub enum Error {
     ParseUrl(#[from] url::ParseError),
     #[error("parse extmap: {0}")]
     ParseExtMap(String),
+    #[error("sdp: duplicate payload type {0}")]
+    DuplicatePayloadType(u8),
+    #[error("sdp: duplicate extmap id {0}")]
+    DuplicateExtMapId(isize),
+    #[error("sdp: ssrc {0} not found")]
+    UnknownSsrc(u32),
+    #[error("sdp: duplicate mid {0}")]
+    DuplicateMid(String),
+    #[error("sdp: mid {0} not found")]
+    UnknownMid(String),
     #[error("{} --> {} <-- {}", .s.substring(0,*.p), .s.substring(*.p, *.p+1), .s.substring(*.p+1, .s.len()))]
     SyntaxError { s: String, p: usize },
 }
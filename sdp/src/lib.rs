@@ -1,13 +1,21 @@
 #![warn(rust_2018_idioms)]
 #![allow(dead_code)]
 
+pub mod builder;
+pub mod candidate;
 pub mod description;
+pub mod diff;
 pub mod direction;
 pub mod extmap;
+pub mod msid;
+pub mod plan;
+pub mod simulcast;
+pub mod ssrc;
 pub mod util;
 
 mod error;
 pub(crate) mod lexer;
 
+pub use builder::{MediaDescriptionBuilder, SessionDescriptionBuilder};
 pub use description::{media::MediaDescription, session::SessionDescription};
 pub use error::Error;
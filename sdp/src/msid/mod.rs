@@ -0,0 +1,149 @@
+#[cfg(test)]
+mod msid_test;
+
+use super::error::{Error, Result};
+use crate::description::common::*;
+use crate::description::session::{ATTR_KEY_MSID, ATTR_KEY_MSID_SEMANTIC};
+
+use std::fmt;
+
+/// MSID_NO_STREAM is the stream id of an "a=msid" attribute whose track is not
+/// associated with any media stream.
+pub const MSID_NO_STREAM: &str = "-";
+
+/// MSID_SEMANTIC_ALL_STREAMS is the stream id of an "a=msid-semantic" attribute
+/// which applies to every media stream.
+pub const MSID_SEMANTIC_ALL_STREAMS: &str = "*";
+
+/// Msid is the value of an "a=msid" attribute, or of the "msid:" of an "a=ssrc"
+/// attribute, associating the track of a media description with a media stream.
+///
+/// <https://tools.ietf.org/html/rfc8830#section-2>
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Msid {
+    /// stream_id is the id of the media stream, MSID_NO_STREAM if the track
+    /// belongs to none.
+    pub stream_id: String,
+    /// track_id is the id of the track, the "appdata" of the attribute.
+    pub track_id: Option<String>,
+}
+
+impl fmt::Display for Msid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.track_id {
+            Some(track_id) => write!(f, "{} {}", self.stream_id, track_id),
+            None => write!(f, "{}", self.stream_id),
+        }
+    }
+}
+
+impl Msid {
+    /// new creates the msid of a track of a media stream.
+    pub fn new(stream_id: &str, track_id: &str) -> Self {
+        Msid {
+            stream_id: stream_id.to_owned(),
+            track_id: Some(track_id.to_owned()),
+        }
+    }
+
+    /// has_stream returns true if the track is associated with a media stream.
+    pub fn has_stream(&self) -> bool {
+        self.stream_id != MSID_NO_STREAM
+    }
+
+    /// converts this object to an Attribute
+    pub fn convert(&self) -> Attribute {
+        Attribute {
+            key: ATTR_KEY_MSID.to_owned(),
+            value: Some(self.to_string()),
+        }
+    }
+
+    /// unmarshal parses the value of an "a=msid" attribute, e.g. "stream track".
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let mut fields = value.split_whitespace();
+        let stream_id = fields.next().unwrap_or_default();
+        let track_id = fields.next();
+        if stream_id.is_empty() || fields.next().is_some() {
+            return Err(Error::SdpInvalidValue(format!("msid {:?}", value)));
+        }
+
+        Ok(Msid {
+            stream_id: stream_id.to_owned(),
+            track_id: track_id.map(|track_id| track_id.to_owned()),
+        })
+    }
+
+    /// marshal creates a string from a Msid
+    pub fn marshal(&self) -> String {
+        ATTR_KEY_MSID.to_owned() + ":" + self.to_string().as_str()
+    }
+}
+
+/// MsidSemantic is the value of the session-level "a=msid-semantic" attribute,
+/// e.g. "WMS *", listing the media streams of the session description which use
+/// a semantic.
+///
+/// <https://tools.ietf.org/html/draft-ietf-mmusic-msid-05#section-3>
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MsidSemantic {
+    pub semantic: String,
+    /// stream_ids are the ids of the media streams, MSID_SEMANTIC_ALL_STREAMS
+    /// if the semantic applies to all of them.
+    pub stream_ids: Vec<String>,
+}
+
+impl fmt::Display for MsidSemantic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.semantic)?;
+        for stream_id in &self.stream_ids {
+            write!(f, " {}", stream_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl MsidSemantic {
+    /// new creates a semantic which applies to every media stream.
+    pub fn new(semantic: &str) -> Self {
+        MsidSemantic {
+            semantic: semantic.to_owned(),
+            stream_ids: vec![MSID_SEMANTIC_ALL_STREAMS.to_owned()],
+        }
+    }
+
+    /// contains returns true if the semantic applies to a media stream.
+    pub fn contains(&self, stream_id: &str) -> bool {
+        self.stream_ids
+            .iter()
+            .any(|id| id == stream_id || id == MSID_SEMANTIC_ALL_STREAMS)
+    }
+
+    /// converts this object to an Attribute. The value starts with a space, as
+    /// the browsers write it.
+    pub fn convert(&self) -> Attribute {
+        Attribute {
+            key: ATTR_KEY_MSID_SEMANTIC.to_owned(),
+            value: Some(" ".to_owned() + self.to_string().as_str()),
+        }
+    }
+
+    /// unmarshal parses the value of an "a=msid-semantic" attribute, e.g. " WMS *".
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let mut fields = value.split_whitespace();
+        let semantic = match fields.next() {
+            Some(semantic) => semantic,
+            None => return Err(Error::SdpInvalidValue(format!("msid-semantic {:?}", value))),
+        };
+
+        Ok(MsidSemantic {
+            semantic: semantic.to_owned(),
+            stream_ids: fields.map(|stream_id| stream_id.to_owned()).collect(),
+        })
+    }
+
+    /// marshal creates a string from a MsidSemantic
+    pub fn marshal(&self) -> String {
+        ATTR_KEY_MSID_SEMANTIC.to_owned() + ": " + self.to_string().as_str()
+    }
+}
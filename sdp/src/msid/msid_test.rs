@@ -0,0 +1,81 @@
+use super::*;
+use crate::description::session::SessionDescription;
+
+use std::io::Cursor;
+
+#[test]
+fn test_msid() -> Result<()> {
+    let msid = Msid::unmarshal("stream track")?;
+    assert_eq!(msid, Msid::new("stream", "track"));
+    assert!(msid.has_stream());
+    assert_eq!(msid.marshal(), "msid:stream track");
+
+    let msid = Msid::unmarshal("- track")?;
+    assert!(!msid.has_stream());
+    assert_eq!(msid.track_id.as_deref(), Some("track"));
+
+    let msid = Msid::unmarshal("stream")?;
+    assert_eq!(msid.track_id, None);
+    assert_eq!(msid.to_string(), "stream");
+
+    for value in ["", " ", "stream track extra"] {
+        assert!(Msid::unmarshal(value).is_err(), "{:?}", value);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_msid_semantic() -> Result<()> {
+    let semantic = MsidSemantic::unmarshal(" WMS stream1 stream2")?;
+    assert_eq!(semantic.semantic, "WMS");
+    assert_eq!(semantic.stream_ids, vec!["stream1", "stream2"]);
+    assert!(semantic.contains("stream2"));
+    assert!(!semantic.contains("stream3"));
+    assert_eq!(semantic.marshal(), "msid-semantic: WMS stream1 stream2");
+
+    let semantic = MsidSemantic::unmarshal("WMS *")?;
+    assert_eq!(semantic, MsidSemantic::new("WMS"));
+    assert!(semantic.contains("stream"));
+
+    let semantic = MsidSemantic::unmarshal(" WMS")?;
+    assert!(semantic.stream_ids.is_empty());
+    assert_eq!(semantic.convert().to_string(), "msid-semantic: WMS");
+
+    assert!(MsidSemantic::unmarshal(" ").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_session_description_msid() -> Result<()> {
+    let sdp = "v=0\r\n\
+         o=- 0 0 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         a=msid-semantic: WMS stream\r\n\
+         m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+         a=rtpmap:111 opus/48000/2\r\n\
+         a=msid:stream mic\r\n\
+         a=msid:- mic\r\n";
+    let mut reader = Cursor::new(sdp.as_bytes());
+    let desc = SessionDescription::unmarshal(&mut reader)?;
+
+    assert_eq!(
+        desc.msid_semantic()?,
+        Some(MsidSemantic {
+            semantic: "WMS".to_owned(),
+            stream_ids: vec!["stream".to_owned()],
+        })
+    );
+    assert_eq!(
+        desc.media_descriptions[0].msids()?,
+        vec![Msid::new("stream", "mic"), Msid::new("-", "mic")]
+    );
+
+    let desc = desc.with_msid_semantic(&MsidSemantic::new("WMS"));
+    assert_eq!(desc.msid_semantic()?, Some(MsidSemantic::new("WMS")));
+    assert_eq!(desc.marshal().matches("a=msid-semantic:").count(), 1);
+
+    Ok(())
+}
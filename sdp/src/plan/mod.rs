@@ -0,0 +1,313 @@
+#[cfg(test)]
+mod plan_test;
+
+use super::error::Result;
+use crate::description::common::Attribute;
+use crate::description::media::MediaDescription;
+use crate::description::session::*;
+use crate::direction::Direction;
+use crate::ssrc::SsrcGroup;
+
+use std::collections::{HashMap, HashSet};
+
+const MEDIA_APPLICATION: &str = "application";
+const SSRC_ATTRIBUTE_MSID: &str = "msid:";
+
+/// Track is a media source of a Plan B media description: the ssrcs of its
+/// encodings and of their RTX and FEC streams, linked by "a=ssrc-group" attributes,
+/// and its "stream-id track-id" msid.
+#[derive(Default)]
+struct Track {
+    ssrcs: Vec<u32>,
+    msid: Option<String>,
+    /// attributes are the "a=ssrc-group" and "a=ssrc" attributes of the track.
+    attributes: Vec<Attribute>,
+}
+
+/// plan_b_to_unified_plan converts a Plan B session description, with one media
+/// description per kind of media listing the ssrcs of every track, to Unified Plan,
+/// with one media description per track.
+///
+/// The media description of a track is a copy of its Plan B media description with
+/// the ssrcs of the track only, an "a=msid" attribute and the first ssrc of the track
+/// as mid, so that a track keeps its mid across renegotiations. The mids of the
+/// "a=group" attributes, e.g. BUNDLE, are replaced accordingly. The media descriptions
+/// without ssrc are kept as they are.
+pub fn plan_b_to_unified_plan(desc: &SessionDescription) -> Result<SessionDescription> {
+    let mut unified = desc.clone();
+    unified.media_descriptions.clear();
+
+    let mut mids = HashMap::new();
+    for media in &desc.media_descriptions {
+        let tracks = tracks(media)?;
+        if tracks.is_empty() {
+            unified.media_descriptions.push(media.clone());
+            continue;
+        }
+
+        let mut track_mids = vec![];
+        for track in tracks {
+            let mid = track.ssrcs[0].to_string();
+
+            let mut m = media.clone();
+            m.attributes.retain(|a| !is_source_attribute(a));
+            for a in m.attributes.iter_mut().filter(|a| a.key == ATTR_KEY_MID) {
+                a.value = Some(mid.clone());
+            }
+            if let Some(msid) = track.msid {
+                m.attributes
+                    .push(Attribute::new(ATTR_KEY_MSID.to_owned(), Some(msid)));
+            }
+            m.attributes.extend(track.attributes);
+
+            track_mids.push(mid);
+            unified.media_descriptions.push(m);
+        }
+
+        if let Some(Some(mid)) = media.attribute(ATTR_KEY_MID) {
+            mids.insert(mid.to_owned(), track_mids);
+        }
+    }
+
+    replace_group_mids(&mut unified, &mids);
+
+    Ok(unified)
+}
+
+/// unified_plan_to_plan_b converts a Unified Plan session description, with one media
+/// description per track, to Plan B, with one media description per kind of media.
+///
+/// The media descriptions of a kind are merged into the first of them, which gets the
+/// kind, e.g. "audio", as mid and the direction covering all of them. The ssrcs of
+/// every track are listed with an "a=ssrc:<ssrc> msid:" attribute taken from the
+/// "a=msid" of their media description if they have none. The rejected media
+/// descriptions are dropped unless every media description of their kind is rejected,
+/// the "application" media descriptions are kept as they are, and the mids of the
+/// "a=group" attributes, e.g. BUNDLE, are replaced accordingly.
+pub fn unified_plan_to_plan_b(desc: &SessionDescription) -> Result<SessionDescription> {
+    let mut plan_b = desc.clone();
+    plan_b.media_descriptions.clear();
+
+    let accepted_kinds: HashSet<&str> = desc
+        .media_descriptions
+        .iter()
+        .filter(|media| !is_rejected(media))
+        .map(|media| media.media_name.media.as_str())
+        .collect();
+
+    let mut mids = HashMap::new();
+    let mut merged: HashMap<String, usize> = HashMap::new();
+    for media in &desc.media_descriptions {
+        let kind = &media.media_name.media;
+        if kind == MEDIA_APPLICATION {
+            plan_b.media_descriptions.push(media.clone());
+            continue;
+        }
+        if is_rejected(media) && accepted_kinds.contains(kind.as_str()) {
+            continue;
+        }
+
+        match merged.get(kind) {
+            Some(&index) => {
+                let m = &mut plan_b.media_descriptions[index];
+                let direction = merge_directions(&m.direction(), &media.direction());
+                set_direction(m, direction);
+                push_sources(m, media)?;
+            }
+            None => {
+                let mut m = media.clone();
+                m.attributes.retain(|a| !is_source_attribute(a));
+                for a in m.attributes.iter_mut().filter(|a| a.key == ATTR_KEY_MID) {
+                    a.value = Some(kind.clone());
+                }
+                push_sources(&mut m, media)?;
+
+                merged.insert(kind.clone(), plan_b.media_descriptions.len());
+                plan_b.media_descriptions.push(m);
+            }
+        }
+
+        if let Some(Some(mid)) = media.attribute(ATTR_KEY_MID) {
+            mids.insert(mid.to_owned(), vec![kind.clone()]);
+        }
+    }
+
+    replace_group_mids(&mut plan_b, &mids);
+
+    Ok(plan_b)
+}
+
+/// tracks groups the ssrcs of a Plan B media description by track, in the order
+/// they are listed.
+fn tracks(media: &MediaDescription) -> Result<Vec<Track>> {
+    let mut tracks: Vec<Track> = vec![];
+    let mut track_of: HashMap<u32, usize> = HashMap::new();
+
+    // The ssrcs of a group belong to the same track, whose first ssrc is the one of
+    // its first group, e.g. the primary ssrc of an "a=ssrc-group:FID".
+    for a in media
+        .attributes
+        .iter()
+        .filter(|a| a.key == ATTR_KEY_SSRCGROUP)
+    {
+        let ssrcs = SsrcGroup::unmarshal(a.value.as_deref().unwrap_or_default())?.ssrcs;
+        let index = match ssrcs.iter().find_map(|ssrc| track_of.get(ssrc)) {
+            Some(&index) => index,
+            None => {
+                tracks.push(Track::default());
+                tracks.len() - 1
+            }
+        };
+
+        let track = &mut tracks[index];
+        for ssrc in ssrcs {
+            track_of.entry(ssrc).or_insert(index);
+            if !track.ssrcs.contains(&ssrc) {
+                track.ssrcs.push(ssrc);
+            }
+        }
+        track.attributes.push(a.clone());
+    }
+
+    for a in media.attributes.iter().filter(|a| a.key == ATTR_KEY_SSRC) {
+        let (ssrc, attribute) = ssrc_attribute(a)?;
+        let msid = attribute.strip_prefix(SSRC_ATTRIBUTE_MSID);
+        let index = match track_of.get(&ssrc) {
+            Some(&index) => index,
+            None => {
+                let same_msid = msid.and_then(|msid| {
+                    tracks
+                        .iter()
+                        .position(|track| track.msid.as_deref() == Some(msid))
+                });
+                match same_msid {
+                    Some(index) => index,
+                    None => {
+                        tracks.push(Track::default());
+                        tracks.len() - 1
+                    }
+                }
+            }
+        };
+        track_of.insert(ssrc, index);
+
+        let track = &mut tracks[index];
+        if !track.ssrcs.contains(&ssrc) {
+            track.ssrcs.push(ssrc);
+        }
+        if track.msid.is_none() {
+            track.msid = msid.map(|msid| msid.to_owned());
+        }
+        track.attributes.push(a.clone());
+    }
+
+    Ok(tracks)
+}
+
+/// push_sources appends the "a=ssrc-group" and "a=ssrc" attributes of a Unified Plan
+/// media description to a Plan B one.
+fn push_sources(plan_b: &mut MediaDescription, media: &MediaDescription) -> Result<()> {
+    let msid = media.attribute(ATTR_KEY_MSID).flatten();
+
+    let mut ssrcs = vec![];
+    let mut ssrcs_with_msid = HashSet::new();
+    for a in &media.attributes {
+        if a.key == ATTR_KEY_SSRCGROUP {
+            plan_b.attributes.push(a.clone());
+        } else if a.key == ATTR_KEY_SSRC {
+            let (ssrc, attribute) = ssrc_attribute(a)?;
+            if !ssrcs.contains(&ssrc) {
+                ssrcs.push(ssrc);
+            }
+            if attribute.starts_with(SSRC_ATTRIBUTE_MSID) {
+                ssrcs_with_msid.insert(ssrc);
+            }
+            plan_b.attributes.push(a.clone());
+        }
+    }
+
+    if let Some(msid) = msid {
+        for ssrc in ssrcs {
+            if !ssrcs_with_msid.contains(&ssrc) {
+                plan_b.attributes.push(Attribute::new(
+                    ATTR_KEY_SSRC.to_owned(),
+                    Some(format!("{} {}{}", ssrc, SSRC_ATTRIBUTE_MSID, msid)),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// replace_group_mids replaces the mids of the "a=group" attributes by the mids
+/// they were converted to.
+fn replace_group_mids(desc: &mut SessionDescription, mids: &HashMap<String, Vec<String>>) {
+    for a in desc
+        .attributes
+        .iter_mut()
+        .filter(|a| a.key == ATTR_KEY_GROUP)
+    {
+        let value = match &a.value {
+            Some(value) => value,
+            None => continue,
+        };
+
+        let mut fields = value.split_whitespace();
+        let semantics = fields.next().unwrap_or_default().to_owned();
+        let mut group: Vec<String> = vec![];
+        for mid in fields {
+            let replaced = match mids.get(mid) {
+                Some(replaced) => replaced.clone(),
+                None => vec![mid.to_owned()],
+            };
+            for mid in replaced {
+                if !group.contains(&mid) {
+                    group.push(mid);
+                }
+            }
+        }
+
+        group.insert(0, semantics);
+        a.value = Some(group.join(" "));
+    }
+}
+
+fn is_source_attribute(a: &Attribute) -> bool {
+    a.key == ATTR_KEY_SSRC || a.key == ATTR_KEY_SSRCGROUP || a.key == ATTR_KEY_MSID
+}
+
+fn is_rejected(media: &MediaDescription) -> bool {
+    media.media_name.port.value == 0
+}
+
+/// ssrc_attribute splits the value of an "a=ssrc" attribute into its ssrc and its
+/// "<attribute>[:<value>]".
+fn ssrc_attribute(a: &Attribute) -> Result<(u32, &str)> {
+    let value = a.value.as_deref().unwrap_or_default();
+    let (ssrc, attribute) = value.split_once(' ').unwrap_or((value, ""));
+    Ok((ssrc.parse()?, attribute))
+}
+
+fn set_direction(media: &mut MediaDescription, direction: Direction) {
+    for a in media.attributes.iter_mut() {
+        if Direction::new(&a.key) != Direction::Unspecified {
+            a.key = direction.to_string();
+        }
+    }
+}
+
+/// merge_directions returns the direction sending if any of the directions sends and
+/// receiving if any of them receives.
+fn merge_directions(a: &Direction, b: &Direction) -> Direction {
+    let sends = |d: &Direction| *d == Direction::SendRecv || *d == Direction::SendOnly;
+    let receives = |d: &Direction| *d == Direction::SendRecv || *d == Direction::RecvOnly;
+
+    match (sends(a) || sends(b), receives(a) || receives(b)) {
+        (true, true) => Direction::SendRecv,
+        (true, false) => Direction::SendOnly,
+        (false, true) => Direction::RecvOnly,
+        (false, false) if *a == Direction::Unspecified => b.clone(),
+        (false, false) => Direction::Inactive,
+    }
+}
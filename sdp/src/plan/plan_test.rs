@@ -0,0 +1,161 @@
+use super::*;
+
+use std::io::Cursor;
+
+const PLAN_B_SDP: &str = "v=0\r\n\
+     o=- 0 0 IN IP4 0.0.0.0\r\n\
+     s=-\r\n\
+     t=0 0\r\n\
+     a=group:BUNDLE audio video data\r\n\
+     a=msid-semantic: WMS stream\r\n\
+     m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+     a=mid:audio\r\n\
+     a=sendrecv\r\n\
+     a=rtpmap:111 opus/48000/2\r\n\
+     a=ssrc:1001 cname:user\r\n\
+     a=ssrc:1001 msid:stream mic\r\n\
+     m=video 9 UDP/TLS/RTP/SAVPF 96 97\r\n\
+     a=mid:video\r\n\
+     a=sendrecv\r\n\
+     a=rtpmap:96 VP8/90000\r\n\
+     a=rtpmap:97 rtx/90000\r\n\
+     a=fmtp:97 apt=96\r\n\
+     a=ssrc-group:FID 2001 2002\r\n\
+     a=ssrc:2001 cname:user\r\n\
+     a=ssrc:2001 msid:stream camera\r\n\
+     a=ssrc:2002 cname:user\r\n\
+     a=ssrc:2002 msid:stream camera\r\n\
+     a=ssrc-group:FID 3001 3002\r\n\
+     a=ssrc:3001 cname:user\r\n\
+     a=ssrc:3001 msid:stream screen\r\n\
+     a=ssrc:3002 cname:user\r\n\
+     a=ssrc:3002 msid:stream screen\r\n\
+     m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+     a=mid:data\r\n\
+     a=sctp-port:5000\r\n";
+
+const UNIFIED_PLAN_SDP: &str = "v=0\r\n\
+     o=- 0 0 IN IP4 0.0.0.0\r\n\
+     s=-\r\n\
+     t=0 0\r\n\
+     a=group:BUNDLE 1001 2001 3001 data\r\n\
+     a=msid-semantic: WMS stream\r\n\
+     m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+     a=mid:1001\r\n\
+     a=sendrecv\r\n\
+     a=rtpmap:111 opus/48000/2\r\n\
+     a=msid:stream mic\r\n\
+     a=ssrc:1001 cname:user\r\n\
+     a=ssrc:1001 msid:stream mic\r\n\
+     m=video 9 UDP/TLS/RTP/SAVPF 96 97\r\n\
+     a=mid:2001\r\n\
+     a=sendrecv\r\n\
+     a=rtpmap:96 VP8/90000\r\n\
+     a=rtpmap:97 rtx/90000\r\n\
+     a=fmtp:97 apt=96\r\n\
+     a=msid:stream camera\r\n\
+     a=ssrc-group:FID 2001 2002\r\n\
+     a=ssrc:2001 cname:user\r\n\
+     a=ssrc:2001 msid:stream camera\r\n\
+     a=ssrc:2002 cname:user\r\n\
+     a=ssrc:2002 msid:stream camera\r\n\
+     m=video 9 UDP/TLS/RTP/SAVPF 96 97\r\n\
+     a=mid:3001\r\n\
+     a=sendrecv\r\n\
+     a=rtpmap:96 VP8/90000\r\n\
+     a=rtpmap:97 rtx/90000\r\n\
+     a=fmtp:97 apt=96\r\n\
+     a=msid:stream screen\r\n\
+     a=ssrc-group:FID 3001 3002\r\n\
+     a=ssrc:3001 cname:user\r\n\
+     a=ssrc:3001 msid:stream screen\r\n\
+     a=ssrc:3002 cname:user\r\n\
+     a=ssrc:3002 msid:stream screen\r\n\
+     m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+     a=mid:data\r\n\
+     a=sctp-port:5000\r\n";
+
+fn unmarshal(sdp: &str) -> Result<SessionDescription> {
+    let mut reader = Cursor::new(sdp.as_bytes());
+    SessionDescription::unmarshal(&mut reader)
+}
+
+#[test]
+fn test_plan_b_to_unified_plan() -> Result<()> {
+    let unified = plan_b_to_unified_plan(&unmarshal(PLAN_B_SDP)?)?;
+    assert_eq!(unified.marshal(), UNIFIED_PLAN_SDP);
+
+    let plan_b = unified_plan_to_plan_b(&unified)?;
+    assert_eq!(plan_b.marshal(), PLAN_B_SDP);
+
+    Ok(())
+}
+
+#[test]
+fn test_unified_plan_to_plan_b() -> Result<()> {
+    let unified = "v=0\r\n\
+         o=- 0 0 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         a=group:BUNDLE 0 1 2\r\n\
+         m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+         a=mid:0\r\n\
+         a=recvonly\r\n\
+         a=rtpmap:111 opus/48000/2\r\n\
+         m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+         a=mid:1\r\n\
+         a=sendonly\r\n\
+         a=rtpmap:111 opus/48000/2\r\n\
+         a=msid:stream mic\r\n\
+         a=ssrc:1001 cname:user\r\n\
+         m=video 0 UDP/TLS/RTP/SAVPF 96\r\n\
+         a=mid:3\r\n\
+         a=inactive\r\n\
+         a=rtpmap:96 VP8/90000\r\n\
+         m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+         a=mid:2\r\n\
+         a=sendonly\r\n\
+         a=rtpmap:96 VP8/90000\r\n\
+         a=msid:stream camera\r\n\
+         a=ssrc:2001 cname:user\r\n\
+         a=ssrc:2001 msid:stream camera\r\n";
+
+    let expected = "v=0\r\n\
+         o=- 0 0 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         a=group:BUNDLE audio video\r\n\
+         m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+         a=mid:audio\r\n\
+         a=sendrecv\r\n\
+         a=rtpmap:111 opus/48000/2\r\n\
+         a=ssrc:1001 cname:user\r\n\
+         a=ssrc:1001 msid:stream mic\r\n\
+         m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+         a=mid:video\r\n\
+         a=sendonly\r\n\
+         a=rtpmap:96 VP8/90000\r\n\
+         a=ssrc:2001 cname:user\r\n\
+         a=ssrc:2001 msid:stream camera\r\n";
+
+    let plan_b = unified_plan_to_plan_b(&unmarshal(unified)?)?;
+    assert_eq!(plan_b.marshal(), expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_plan_conversion_errors() -> Result<()> {
+    let sdp = "v=0\r\n\
+         o=- 0 0 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+         a=ssrc:abc cname:user\r\n";
+    let desc = unmarshal(sdp)?;
+
+    assert!(plan_b_to_unified_plan(&desc).is_err());
+    assert!(unified_plan_to_plan_b(&desc).is_err());
+
+    Ok(())
+}
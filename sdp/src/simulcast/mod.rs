@@ -0,0 +1,305 @@
+#[cfg(test)]
+mod simulcast_test;
+
+use super::error::{Error, Result};
+use crate::description::common::*;
+use crate::description::session::{ATTR_KEY_RID, ATTR_KEY_SIMULCAST};
+
+use std::fmt;
+
+const RID_DIRECTION_SEND_STR: &str = "send";
+const RID_DIRECTION_RECV_STR: &str = "recv";
+
+/// RidDirection is the direction of an RTP stream restricted by a rid or
+/// listed in a simulcast attribute.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum RidDirection {
+    Send,
+    Recv,
+}
+
+impl fmt::Display for RidDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            RidDirection::Send => RID_DIRECTION_SEND_STR,
+            RidDirection::Recv => RID_DIRECTION_RECV_STR,
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl RidDirection {
+    /// new parses "send" or "recv".
+    pub fn new(raw: &str) -> Result<Self> {
+        match raw {
+            RID_DIRECTION_SEND_STR => Ok(RidDirection::Send),
+            RID_DIRECTION_RECV_STR => Ok(RidDirection::Recv),
+            _ => Err(Error::SdpInvalidValue(format!("rid direction {:?}", raw))),
+        }
+    }
+}
+
+/// Rid is the value of an "a=rid" attribute, the restrictions of an RTP stream
+/// identified by its rid.
+///
+/// <https://tools.ietf.org/html/rfc8851#section-4>
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Rid {
+    pub id: String,
+    pub direction: RidDirection,
+    /// formats are the payload types of the "pt=" restriction, empty if the
+    /// stream may use any format of the media.
+    pub formats: Vec<u8>,
+    /// restrictions are the other restrictions, e.g. ("max-width", Some("1280")),
+    /// in the order they were written.
+    pub restrictions: Vec<(String, Option<String>)>,
+}
+
+impl fmt::Display for Rid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.id, self.direction)?;
+
+        let mut restrictions = vec![];
+        if !self.formats.is_empty() {
+            let formats: Vec<String> = self.formats.iter().map(|pt| pt.to_string()).collect();
+            restrictions.push(format!("pt={}", formats.join(",")));
+        }
+        for (key, value) in &self.restrictions {
+            match value {
+                Some(value) => restrictions.push(format!("{}={}", key, value)),
+                None => restrictions.push(key.clone()),
+            }
+        }
+        if !restrictions.is_empty() {
+            write!(f, " {}", restrictions.join(";"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Rid {
+    /// new creates a rid without restrictions.
+    pub fn new(id: &str, direction: RidDirection) -> Self {
+        Rid {
+            id: id.to_owned(),
+            direction,
+            formats: vec![],
+            restrictions: vec![],
+        }
+    }
+
+    /// restriction returns the value of a restriction and if it exists.
+    pub fn restriction(&self, key: &str) -> Option<Option<&str>> {
+        self.restrictions
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_deref())
+    }
+
+    /// converts this object to an Attribute
+    pub fn convert(&self) -> Attribute {
+        Attribute {
+            key: ATTR_KEY_RID.to_owned(),
+            value: Some(self.to_string()),
+        }
+    }
+
+    /// unmarshal parses the value of an "a=rid" attribute, e.g.
+    /// "f send pt=97;max-width=1280".
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let mut fields = value.split_whitespace();
+        let id = fields.next().unwrap_or_default();
+        validate_rid_id(id)?;
+        let direction = RidDirection::new(fields.next().unwrap_or_default())?;
+
+        let mut rid = Rid::new(id, direction);
+        if let Some(restrictions) = fields.next() {
+            for restriction in restrictions.split(';') {
+                let (key, value) = match restriction.split_once('=') {
+                    Some((key, value)) => (key, Some(value)),
+                    None => (restriction, None),
+                };
+                match (key, value) {
+                    ("", _) | ("pt", None) => {
+                        return Err(Error::SdpInvalidValue(format!(
+                            "rid restriction {:?}",
+                            restriction
+                        )))
+                    }
+                    ("pt", Some(formats)) => {
+                        for pt in formats.split(',') {
+                            rid.formats.push(pt.parse()?);
+                        }
+                    }
+                    (key, value) => rid
+                        .restrictions
+                        .push((key.to_owned(), value.map(|v| v.to_owned()))),
+                }
+            }
+        }
+
+        if fields.next().is_some() {
+            return Err(Error::SdpInvalidValue(format!("rid {:?}", value)));
+        }
+
+        Ok(rid)
+    }
+
+    /// marshal creates a string from a Rid
+    pub fn marshal(&self) -> String {
+        ATTR_KEY_RID.to_owned() + ":" + self.to_string().as_str()
+    }
+}
+
+/// SimulcastRid is a stream listed in an "a=simulcast" attribute, by the id of
+/// its rid, which is prefixed by '~' when the stream is paused.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SimulcastRid {
+    pub id: String,
+    pub paused: bool,
+}
+
+impl fmt::Display for SimulcastRid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.paused {
+            write!(f, "~{}", self.id)
+        } else {
+            write!(f, "{}", self.id)
+        }
+    }
+}
+
+impl SimulcastRid {
+    /// new creates a stream which is not paused.
+    pub fn new(id: &str) -> Self {
+        SimulcastRid {
+            id: id.to_owned(),
+            paused: false,
+        }
+    }
+
+    fn unmarshal(value: &str) -> Result<Self> {
+        let (id, paused) = match value.strip_prefix('~') {
+            Some(id) => (id, true),
+            None => (value, false),
+        };
+        validate_rid_id(id)?;
+        Ok(SimulcastRid {
+            id: id.to_owned(),
+            paused,
+        })
+    }
+}
+
+/// Simulcast is the value of an "a=simulcast" attribute. Each direction lists
+/// the simulcast streams in order of preference, and each stream lists the rids
+/// of its alternative formats.
+///
+/// <https://tools.ietf.org/html/rfc8853#section-5.1>
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Simulcast {
+    pub send: Vec<Vec<SimulcastRid>>,
+    pub recv: Vec<Vec<SimulcastRid>>,
+}
+
+impl fmt::Display for Simulcast {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lists = vec![];
+        for (direction, streams) in [
+            (RidDirection::Send, &self.send),
+            (RidDirection::Recv, &self.recv),
+        ] {
+            if streams.is_empty() {
+                continue;
+            }
+            let streams: Vec<String> = streams
+                .iter()
+                .map(|alternatives| {
+                    let alternatives: Vec<String> =
+                        alternatives.iter().map(|r| r.to_string()).collect();
+                    alternatives.join(",")
+                })
+                .collect();
+            lists.push(format!("{} {}", direction, streams.join(";")));
+        }
+        write!(f, "{}", lists.join(" "))
+    }
+}
+
+impl Simulcast {
+    /// streams returns the simulcast streams of a direction.
+    pub fn streams(&self, direction: RidDirection) -> &[Vec<SimulcastRid>] {
+        match direction {
+            RidDirection::Send => &self.send,
+            RidDirection::Recv => &self.recv,
+        }
+    }
+
+    /// rids returns the ids of every rid listed for a direction.
+    pub fn rids(&self, direction: RidDirection) -> impl Iterator<Item = &str> {
+        self.streams(direction)
+            .iter()
+            .flatten()
+            .map(|r| r.id.as_str())
+    }
+
+    /// converts this object to an Attribute
+    pub fn convert(&self) -> Attribute {
+        Attribute {
+            key: ATTR_KEY_SIMULCAST.to_owned(),
+            value: Some(self.to_string()),
+        }
+    }
+
+    /// unmarshal parses the value of an "a=simulcast" attribute, e.g.
+    /// "send 1;~2,3 recv 4".
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        if fields.is_empty() || fields.len() % 2 != 0 || fields.len() > 4 {
+            return Err(Error::SdpInvalidValue(format!("simulcast {:?}", value)));
+        }
+
+        let mut simulcast = Simulcast::default();
+        for pair in fields.chunks(2) {
+            let direction = RidDirection::new(pair[0])?;
+            let streams = match direction {
+                RidDirection::Send => &mut simulcast.send,
+                RidDirection::Recv => &mut simulcast.recv,
+            };
+            if !streams.is_empty() {
+                return Err(Error::SdpInvalidValue(format!(
+                    "simulcast {:?} lists {} twice",
+                    value, direction
+                )));
+            }
+            for stream in pair[1].split(';') {
+                let alternatives = stream
+                    .split(',')
+                    .map(SimulcastRid::unmarshal)
+                    .collect::<Result<Vec<_>>>()?;
+                streams.push(alternatives);
+            }
+        }
+
+        Ok(simulcast)
+    }
+
+    /// marshal creates a string from a Simulcast
+    pub fn marshal(&self) -> String {
+        ATTR_KEY_SIMULCAST.to_owned() + ":" + self.to_string().as_str()
+    }
+}
+
+/// validate_rid_id checks that a rid id is made of alphanumeric characters, '-' and '_'.
+pub(crate) fn validate_rid_id(id: &str) -> Result<()> {
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Err(Error::SdpInvalidValue(format!("rid id {:?}", id)))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use super::*;
+
+#[test]
+fn test_rid() -> Result<()> {
+    let rid = Rid::unmarshal("f send pt=97,98;max-width=1280;max-height=720")?;
+    assert_eq!(rid.id, "f");
+    assert_eq!(rid.direction, RidDirection::Send);
+    assert_eq!(rid.formats, vec![97, 98]);
+    assert_eq!(rid.restriction("max-width"), Some(Some("1280")));
+    assert_eq!(rid.restriction("max-fps"), None);
+    assert_eq!(
+        rid.marshal(),
+        "rid:f send pt=97,98;max-width=1280;max-height=720"
+    );
+
+    let rid = Rid::unmarshal("q recv")?;
+    assert_eq!(rid, Rid::new("q", RidDirection::Recv));
+    assert_eq!(rid.to_string(), "q recv");
+
+    let rid = Rid::unmarshal("h send depend=f;foo")?;
+    assert_eq!(rid.restriction("depend"), Some(Some("f")));
+    assert_eq!(rid.restriction("foo"), Some(None));
+    assert_eq!(rid.to_string(), "h send depend=f;foo");
+
+    for value in [
+        "",
+        "f",
+        "f sendrecv",
+        "f! send",
+        "f send pt",
+        "f send pt=abc",
+        "f send ;max-width=1280",
+        "f send pt=97 extra",
+    ] {
+        assert!(Rid::unmarshal(value).is_err(), "{:?}", value);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_simulcast() -> Result<()> {
+    let simulcast = Simulcast::unmarshal("send 1;~2,3 recv 4")?;
+    assert_eq!(
+        simulcast.send,
+        vec![
+            vec![SimulcastRid::new("1")],
+            vec![
+                SimulcastRid {
+                    id: "2".to_owned(),
+                    paused: true
+                },
+                SimulcastRid::new("3")
+            ],
+        ]
+    );
+    assert_eq!(simulcast.recv, vec![vec![SimulcastRid::new("4")]]);
+    assert_eq!(
+        simulcast.rids(RidDirection::Send).collect::<Vec<_>>(),
+        vec!["1", "2", "3"]
+    );
+    assert_eq!(simulcast.marshal(), "simulcast:send 1;~2,3 recv 4");
+
+    // the send list is always written first
+    let simulcast = Simulcast::unmarshal("recv h;m send f")?;
+    assert_eq!(simulcast.to_string(), "send f recv h;m");
+
+    for value in [
+        "",
+        "send",
+        "sendrecv f",
+        "send f send h",
+        "send f;;h",
+        "send f recv h extra",
+    ] {
+        assert!(Simulcast::unmarshal(value).is_err(), "{:?}", value);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod ssrc_test;
+
+use super::error::{Error, Result};
+use crate::description::common::*;
+use crate::description::session::{
+    ATTR_KEY_SSRCGROUP, SEMANTIC_TOKEN_FEC_FR, SEMANTIC_TOKEN_FLOW_IDENTIFICATION,
+};
+
+use std::fmt;
+
+/// SsrcGroup is the value of an "a=ssrc-group" attribute, the synchronization
+/// sources of a media description which are related by a semantics, e.g. "FID"
+/// for a source and its RTX repair flow, "SIM" for the simulcast streams of a
+/// track or "FEC-FR" for a source and its FEC repair flow.
+///
+/// <https://tools.ietf.org/html/rfc5576#section-4.2>
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SsrcGroup {
+    pub semantics: String,
+    pub ssrcs: Vec<u32>,
+}
+
+impl fmt::Display for SsrcGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.semantics)?;
+        for ssrc in &self.ssrcs {
+            write!(f, " {}", ssrc)?;
+        }
+        Ok(())
+    }
+}
+
+impl SsrcGroup {
+    /// new creates a group of synchronization sources.
+    pub fn new(semantics: &str, ssrcs: &[u32]) -> Self {
+        SsrcGroup {
+            semantics: semantics.to_owned(),
+            ssrcs: ssrcs.to_vec(),
+        }
+    }
+
+    /// primary_ssrc returns the first synchronization source of the group, e.g. the
+    /// source protected by the repair flow of an "FID" or "FEC-FR" group, or the
+    /// first simulcast stream of a "SIM" group.
+    pub fn primary_ssrc(&self) -> Option<u32> {
+        self.ssrcs.first().copied()
+    }
+
+    /// repair_ssrc returns the repair flow of an "FID" or "FEC-FR" group.
+    pub fn repair_ssrc(&self) -> Option<u32> {
+        if self.is_repair_group() {
+            self.ssrcs.get(1).copied()
+        } else {
+            None
+        }
+    }
+
+    fn is_repair_group(&self) -> bool {
+        self.semantics == SEMANTIC_TOKEN_FLOW_IDENTIFICATION
+            || self.semantics == SEMANTIC_TOKEN_FEC_FR
+    }
+
+    /// converts this object to an Attribute
+    pub fn convert(&self) -> Attribute {
+        Attribute {
+            key: ATTR_KEY_SSRCGROUP.to_owned(),
+            value: Some(self.to_string()),
+        }
+    }
+
+    /// unmarshal parses the value of an "a=ssrc-group" attribute, e.g.
+    /// "FID 2231627014 632943048". The "FID" and "FEC-FR" groups must list a
+    /// source and its repair flow.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let mut fields = value.split_whitespace();
+        let semantics = fields.next().unwrap_or_default();
+
+        let mut group = SsrcGroup::new(semantics, &[]);
+        for ssrc in fields {
+            group.ssrcs.push(ssrc.parse()?);
+        }
+
+        if group.ssrcs.is_empty() || (group.is_repair_group() && group.ssrcs.len() != 2) {
+            return Err(Error::SdpInvalidValue(format!("ssrc-group {:?}", value)));
+        }
+
+        Ok(group)
+    }
+
+    /// marshal creates a string from a SsrcGroup
+    pub fn marshal(&self) -> String {
+        ATTR_KEY_SSRCGROUP.to_owned() + ":" + self.to_string().as_str()
+    }
+}
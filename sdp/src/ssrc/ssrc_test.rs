@@ -0,0 +1,65 @@
+use super::*;
+use crate::description::session::{SessionDescription, SEMANTIC_TOKEN_SIMULCAST};
+
+use std::io::Cursor;
+
+#[test]
+fn test_ssrc_group() -> Result<()> {
+    let group = SsrcGroup::unmarshal("FID 2231627014 632943048")?;
+    assert_eq!(group, SsrcGroup::new("FID", &[2231627014, 632943048]));
+    assert_eq!(group.primary_ssrc(), Some(2231627014));
+    assert_eq!(group.repair_ssrc(), Some(632943048));
+    assert_eq!(group.marshal(), "ssrc-group:FID 2231627014 632943048");
+
+    let group = SsrcGroup::unmarshal("FEC-FR 1 2")?;
+    assert_eq!(group.repair_ssrc(), Some(2));
+
+    let group = SsrcGroup::unmarshal("SIM 1 2 3")?;
+    assert_eq!(group.semantics, SEMANTIC_TOKEN_SIMULCAST);
+    assert_eq!(group.primary_ssrc(), Some(1));
+    assert_eq!(group.repair_ssrc(), None);
+    assert_eq!(group.to_string(), "SIM 1 2 3");
+
+    for value in ["", "FID", "FID 1", "FID 1 2 3", "FEC-FR 1", "SIM abc"] {
+        assert!(SsrcGroup::unmarshal(value).is_err(), "{:?}", value);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_media_description_ssrc_groups() -> Result<()> {
+    let sdp = "v=0\r\n\
+         o=- 0 0 IN IP4 0.0.0.0\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         m=video 9 UDP/TLS/RTP/SAVPF 96 97\r\n\
+         a=rtpmap:96 VP8/90000\r\n\
+         a=rtpmap:97 rtx/90000\r\n\
+         a=fmtp:97 apt=96\r\n\
+         a=ssrc-group:SIM 1 2\r\n\
+         a=ssrc-group:FID 1 3\r\n\
+         a=ssrc-group:FID 2 4\r\n";
+    let mut reader = Cursor::new(sdp.as_bytes());
+    let desc = SessionDescription::unmarshal(&mut reader)?;
+    let media = &desc.media_descriptions[0];
+
+    assert_eq!(
+        media.ssrc_groups()?,
+        vec![
+            SsrcGroup::new("SIM", &[1, 2]),
+            SsrcGroup::new("FID", &[1, 3]),
+            SsrcGroup::new("FID", &[2, 4]),
+        ]
+    );
+
+    let media = media
+        .clone()
+        .with_ssrc_group(&SsrcGroup::new("FEC-FR", &[1, 5]));
+    assert_eq!(
+        media.attributes.last().map(|a| a.to_string()),
+        Some("ssrc-group:FEC-FR 1 5".to_owned())
+    );
+
+    Ok(())
+}
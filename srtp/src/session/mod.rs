@@ -17,6 +17,7 @@ use std::collections::HashSet;
 use std::{
     collections::HashMap,
     marker::{Send, Sync},
+    sync::atomic::Ordering,
     sync::Arc,
 };
 use tokio::sync::{mpsc, Mutex};
@@ -170,10 +171,11 @@ impl Session {
             match stream.buffer.write(&decrypted).await {
                 Ok(_) => {}
                 Err(err) => {
-                    // Silently drop data when the buffer is full.
+                    // Drop data when the buffer is full, the stream counts it.
                     if util::Error::ErrBufferFull != err {
                         return Err(err.into());
                     }
+                    stream.dropped.fetch_add(1, Ordering::SeqCst);
                 }
             }
         }
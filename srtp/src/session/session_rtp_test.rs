@@ -3,7 +3,7 @@ use crate::error::Result;
 use crate::protection_profile::*;
 
 use bytes::{Bytes, BytesMut};
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::{
     net::UdpSocket,
     sync::{mpsc, Mutex},
@@ -312,3 +312,45 @@ async fn test_session_srtp_replay_protection() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_session_srtp_buffer_full() -> Result<()> {
+    let test_payload = Bytes::from_static(&[0x00, 0x01, 0x03, 0x04]);
+    let mut read_buffer = BytesMut::with_capacity(RTP_HEADER_SIZE + test_payload.len());
+    read_buffer.resize(RTP_HEADER_SIZE + test_payload.len(), 0u8);
+    let (sa, sb) = build_session_srtp_pair().await?;
+
+    // Room for a single packet, with its two bytes of length
+    let read_stream = sb.open(TEST_SSRC).await;
+    read_stream
+        .set_buffer_size(2 + RTP_HEADER_SIZE + test_payload.len())
+        .await;
+
+    for sequence_number in 0..3 {
+        let packet = rtp::packet::Packet {
+            header: rtp::header::Header {
+                ssrc: TEST_SSRC,
+                sequence_number,
+                ..Default::default()
+            },
+            payload: test_payload.clone(),
+        };
+        sa.write_rtp(&packet).await?;
+    }
+
+    // The first packet fills the buffer until it is read
+    while read_stream.dropped_packets() < 2 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    read_stream.read(&mut read_buffer).await?;
+
+    let result = read_stream
+        .read_with_timeout(&mut read_buffer, Duration::from_millis(50))
+        .await;
+    assert!(result.is_err());
+
+    sa.close().await?;
+    sb.close().await?;
+
+    Ok(())
+}
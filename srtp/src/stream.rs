@@ -1,6 +1,8 @@
 use crate::error::{Error, Result};
 use util::{marshal::*, Buffer};
 
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use tokio::sync::mpsc;
 
 /// Limit the buffer size to 1MB
@@ -16,6 +18,8 @@ pub struct Stream {
     tx: mpsc::Sender<u32>,
     pub(crate) buffer: Buffer,
     is_rtp: bool,
+    /// dropped counts the packets dropped because the buffer was full
+    pub(crate) dropped: AtomicU64,
 }
 
 impl Stream {
@@ -34,6 +38,7 @@ impl Stream {
                 },
             ),
             is_rtp,
+            dropped: AtomicU64::new(0),
         }
     }
 
@@ -52,6 +57,29 @@ impl Stream {
         Ok(self.buffer.read(buf, None).await?)
     }
 
+    /// read_with_timeout is read, which fails with ErrTimeout if no packet is received
+    /// within the timeout.
+    pub async fn read_with_timeout(&self, buf: &mut [u8], timeout: Duration) -> Result<usize> {
+        Ok(self.buffer.read(buf, Some(timeout)).await?)
+    }
+
+    /// set_buffer_size sets the maximum number of bytes of the packets which are buffered
+    /// until they are read, the packets received while the buffer is full are dropped.
+    /// The default is SRTP_BUFFER_SIZE for RTP and SRTCP_BUFFER_SIZE for RTCP.
+    pub async fn set_buffer_size(&self, size: usize) {
+        self.buffer.set_limit_size(size).await;
+    }
+
+    /// buffered_packets returns the number of packets which can be read without waiting.
+    pub async fn buffered_packets(&self) -> usize {
+        self.buffer.count().await
+    }
+
+    /// dropped_packets returns the number of packets dropped because the buffer was full.
+    pub fn dropped_packets(&self) -> u64 {
+        self.dropped.load(Ordering::SeqCst)
+    }
+
     /// ReadRTP reads and decrypts full RTP packet and its header from the nextConn
     pub async fn read_rtp(&self, buf: &mut [u8]) -> Result<(usize, rtp::header::Header)> {
         if !self.is_rtp {
@@ -61,6 +61,7 @@ impl fmt::Display for AttrType {
             ATTR_RESERVATION_TOKEN => "RESERVATION-TOKEN",
             ATTR_CONNECTION_ID => "CONNECTION-ID",
             ATTR_REQUESTED_ADDRESS_FAMILY => "REQUESTED-ADDRESS-FAMILY",
+            ATTR_MOBILITY_TICKET => "MOBILITY-TICKET",
             ATTR_MESSAGE_INTEGRITY_SHA256 => "MESSAGE-INTEGRITY-SHA256",
             ATTR_PASSWORD_ALGORITHM => "PASSWORD-ALGORITHM",
             ATTR_USER_HASH => "USERHASH",
@@ -141,6 +142,9 @@ pub const ATTR_CONNECTION_ID: AttrType = AttrType(0x002a); // CONNECTION-ID
 /// Attributes from RFC 6156 TURN IPv6.
 pub const ATTR_REQUESTED_ADDRESS_FAMILY: AttrType = AttrType(0x0017); // REQUESTED-ADDRESS-FAMILY
 
+/// Attributes from RFC 8016 Mobility with TURN.
+pub const ATTR_MOBILITY_TICKET: AttrType = AttrType(0x8030); // MOBILITY-TICKET
+
 /// Attributes from An Origin Attribute for the STUN Protocol.
 pub const ATTR_ORIGIN: AttrType = AttrType(0x802F);
 
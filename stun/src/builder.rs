@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod builder_test;
+
+use crate::agent::*;
+use crate::attributes::*;
+use crate::error::*;
+use crate::fingerprint::*;
+use crate::integrity::*;
+use crate::message::*;
+
+// MAX_MESSAGE_LENGTH is the largest message length that fits in the
+// 16-bit length field of the header.
+const MAX_MESSAGE_LENGTH: u32 = u16::MAX as u32;
+
+/// MessageBuilder builds a message from an unordered set of attributes.
+///
+/// MESSAGE-INTEGRITY, MESSAGE-INTEGRITY-SHA256 and FINGERPRINT are
+/// configured separately and always appended last, in the order required
+/// by RFC 8489 Section 14, so callers no longer need to sequence setters
+/// by hand.
+///
+/// Example:
+///  let m = MessageBuilder::new(BINDING_REQUEST)
+///      .with_attribute(Username::new(ATTR_USERNAME, "user".to_owned()))
+///      .with_integrity(MessageIntegrity::new_short_term_integrity("pass".to_owned()))
+///      .with_fingerprint()
+///      .build()?;
+pub struct MessageBuilder {
+    typ: MessageType,
+    transaction_id: Option<TransactionId>,
+    setters: Vec<Box<dyn Setter>>,
+    integrity: Option<MessageIntegrity>,
+    integrity_sha256: Option<MessageIntegritySha256>,
+    fingerprint: bool,
+}
+
+impl MessageBuilder {
+    pub fn new(typ: MessageType) -> Self {
+        MessageBuilder {
+            typ,
+            transaction_id: None,
+            setters: vec![],
+            integrity: None,
+            integrity_sha256: None,
+            fingerprint: false,
+        }
+    }
+
+    /// with_transaction_id sets the transaction id. A random one is
+    /// generated if not set.
+    pub fn with_transaction_id(mut self, transaction_id: TransactionId) -> Self {
+        self.transaction_id = Some(transaction_id);
+        self
+    }
+
+    /// with_attribute adds an attribute. Attributes are written in the order
+    /// they are added.
+    pub fn with_attribute<S: Setter + 'static>(mut self, setter: S) -> Self {
+        self.setters.push(Box::new(setter));
+        self
+    }
+
+    /// with_boxed_attribute is like with_attribute, but for setters that are
+    /// already boxed.
+    pub fn with_boxed_attribute(mut self, setter: Box<dyn Setter>) -> Self {
+        self.setters.push(setter);
+        self
+    }
+
+    /// with_integrity signs the message with MESSAGE-INTEGRITY.
+    pub fn with_integrity(mut self, integrity: MessageIntegrity) -> Self {
+        self.integrity = Some(integrity);
+        self
+    }
+
+    /// with_integrity_sha256 signs the message with MESSAGE-INTEGRITY-SHA256.
+    /// If MESSAGE-INTEGRITY is configured too, it is added first.
+    pub fn with_integrity_sha256(mut self, integrity: MessageIntegritySha256) -> Self {
+        self.integrity_sha256 = Some(integrity);
+        self
+    }
+
+    /// with_fingerprint appends FINGERPRINT as the last attribute.
+    pub fn with_fingerprint(mut self) -> Self {
+        self.fingerprint = true;
+        self
+    }
+
+    /// build encodes the message. It fails if an attribute added with with_attribute
+    /// is one of the attributes managed by the builder, or if the message
+    /// does not fit in the length field.
+    pub fn build(self) -> Result<Message> {
+        let mut m = Message::new();
+        m.typ = self.typ;
+        m.transaction_id = match self.transaction_id {
+            Some(transaction_id) => transaction_id,
+            None => TransactionId::new(),
+        };
+        m.write_header();
+
+        for s in &self.setters {
+            s.add_to(&mut m)?;
+        }
+
+        for a in &m.attributes.0 {
+            if a.typ == ATTR_MESSAGE_INTEGRITY
+                || a.typ == ATTR_MESSAGE_INTEGRITY_SHA256
+                || a.typ == ATTR_FINGERPRINT
+            {
+                return Err(Error::ErrReservedAttribute(a.typ.to_string()));
+            }
+        }
+
+        if let Some(integrity) = &self.integrity {
+            integrity.add_to(&mut m)?;
+        }
+        if let Some(integrity) = &self.integrity_sha256 {
+            integrity.add_to(&mut m)?;
+        }
+        if self.fingerprint {
+            FINGERPRINT.add_to(&mut m)?;
+        }
+
+        if m.length > MAX_MESSAGE_LENGTH {
+            return Err(Error::ErrMessageTooLong);
+        }
+
+        Ok(m)
+    }
+}
@@ -0,0 +1,78 @@
+use super::*;
+use crate::textattrs::*;
+
+#[test]
+fn test_message_builder_orders_integrity_and_fingerprint() -> Result<()> {
+    let integrity = MessageIntegrity::new_short_term_integrity("pass".to_owned());
+    let integrity_sha256 = MessageIntegritySha256::new_short_term_integrity("pass".to_owned());
+    let transaction_id = TransactionId::new();
+
+    // The order of the builder calls does not matter.
+    let m = MessageBuilder::new(BINDING_REQUEST)
+        .with_fingerprint()
+        .with_integrity_sha256(integrity_sha256.clone())
+        .with_integrity(integrity.clone())
+        .with_transaction_id(transaction_id)
+        .with_attribute(Username::new(ATTR_USERNAME, "user".to_owned()))
+        .with_attribute(Software::new(ATTR_SOFTWARE, "webrtc-rs".to_owned()))
+        .build()?;
+
+    assert_eq!(m.typ, BINDING_REQUEST);
+    assert_eq!(m.transaction_id, transaction_id);
+
+    let types: Vec<AttrType> = m.attributes.0.iter().map(|a| a.typ).collect();
+    assert_eq!(
+        types,
+        vec![
+            ATTR_USERNAME,
+            ATTR_SOFTWARE,
+            ATTR_MESSAGE_INTEGRITY,
+            ATTR_MESSAGE_INTEGRITY_SHA256,
+            ATTR_FINGERPRINT,
+        ]
+    );
+
+    let mut decoded = Message::new();
+    decoded.write(&m.raw)?;
+    FINGERPRINT.check(&decoded)?;
+    integrity.check(&mut decoded.clone())?;
+    integrity_sha256.check(&mut decoded)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_message_builder_rejects_reserved_attributes() {
+    let result = MessageBuilder::new(BINDING_REQUEST)
+        .with_attribute(FINGERPRINT)
+        .with_integrity(MessageIntegrity::new_short_term_integrity(
+            "pass".to_owned(),
+        ))
+        .build();
+    assert_eq!(
+        result.err(),
+        Some(Error::ErrReservedAttribute(ATTR_FINGERPRINT.to_string()))
+    );
+
+    let result = MessageBuilder::new(BINDING_REQUEST)
+        .with_boxed_attribute(Box::new(MessageIntegrity::new_short_term_integrity(
+            "pass".to_owned(),
+        )))
+        .build();
+    assert_eq!(
+        result.err(),
+        Some(Error::ErrReservedAttribute(
+            ATTR_MESSAGE_INTEGRITY.to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_message_builder_random_transaction_id() -> Result<()> {
+    let a = MessageBuilder::new(BINDING_REQUEST).build()?;
+    let b = MessageBuilder::new(BINDING_REQUEST).build()?;
+    assert_ne!(a.transaction_id, b.transaction_id);
+    assert_eq!(a.raw.len(), MESSAGE_HEADER_SIZE);
+
+    Ok(())
+}
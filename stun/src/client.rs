@@ -18,6 +18,7 @@ use tokio::time::{self, Duration, Instant};
 const DEFAULT_TIMEOUT_RATE: Duration = Duration::from_millis(5);
 const DEFAULT_RTO: Duration = Duration::from_millis(300);
 const DEFAULT_MAX_ATTEMPTS: u32 = 7;
+const DEFAULT_RM: u32 = 16;
 const DEFAULT_MAX_BUFFER_SIZE: usize = 8;
 
 /// Collector calls function f with constant rate.
@@ -85,6 +86,9 @@ pub struct ClientTransaction {
     handler: Handler,
     start: Instant,
     rto: Duration,
+    max_attempts: u32,
+    rm: u32,
+    exponential_backoff: bool,
     raw: Vec<u8>,
 }
 
@@ -99,8 +103,19 @@ impl ClientTransaction {
         Ok(())
     }
 
+    /// next_timeout returns the deadline for the current attempt. By default the
+    /// client waits one more RTO after each attempt. With exponential backoff the
+    /// schedule follows RFC 5389 Section 7.2.1: the RTO doubles after each
+    /// retransmission, and after the last one the client waits rm times the
+    /// initial RTO.
     pub(crate) fn next_timeout(&self, now: Instant) -> Instant {
-        now.add((self.attempt + 1) * self.rto)
+        if !self.exponential_backoff || self.max_attempts == 0 {
+            now.add((self.attempt + 1) * self.rto)
+        } else if self.attempt >= self.max_attempts {
+            now.add(self.rto.saturating_mul(self.rm))
+        } else {
+            now.add(self.rto.saturating_mul(2u32.saturating_pow(self.attempt)))
+        }
     }
 }
 
@@ -109,6 +124,8 @@ struct ClientSettings {
     rto: Duration,
     rto_rate: Duration,
     max_attempts: u32,
+    rm: u32,
+    exponential_backoff: bool,
     closed: bool,
     //handler: Handler,
     collector: Option<Box<dyn Collector + Send>>,
@@ -122,6 +139,8 @@ impl Default for ClientSettings {
             rto: DEFAULT_RTO,
             rto_rate: DEFAULT_TIMEOUT_RATE,
             max_attempts: DEFAULT_MAX_ATTEMPTS,
+            rm: DEFAULT_RM,
+            exponential_backoff: false,
             closed: false,
             //handler: None,
             collector: None,
@@ -150,6 +169,29 @@ impl ClientBuilder {
         self
     }
 
+    /// with_max_attempts sets the maximum number of retransmissions of a
+    /// request, i.e. Rc - 1 in terms of RFC 5389 Section 7.2.1.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.settings.max_attempts = max_attempts;
+        self
+    }
+
+    /// with_rm sets Rm, the multiple of RTO to wait for a response after the
+    /// last retransmission before the transaction times out. It is only used
+    /// with exponential backoff.
+    pub fn with_rm(mut self, rm: u32) -> Self {
+        self.settings.rm = rm;
+        self
+    }
+
+    /// with_exponential_backoff doubles the RTO after each retransmission as
+    /// described in RFC 5389 Section 7.2.1, instead of waiting one more RTO
+    /// after each attempt.
+    pub fn with_exponential_backoff(mut self) -> Self {
+        self.settings.exponential_backoff = true;
+        self
+    }
+
     /// with_timeout_rate sets RTO timer minimum resolution.
     pub fn with_timeout_rate(mut self, d: Duration) -> Self {
         self.settings.rto_rate = d;
@@ -441,6 +483,9 @@ impl Client {
                 handler,
                 start: Instant::now(),
                 rto: self.settings.rto,
+                max_attempts: self.settings.max_attempts,
+                rm: self.settings.rm,
+                exponential_backoff: self.settings.exponential_backoff,
                 raw: m.raw.clone(),
             };
             let d = t.next_timeout(t.start);
@@ -9,4 +9,54 @@ fn ensure_client_settings_is_send() {
 
 fn ensure_send<T: Send>(_: T) {}
 
-//TODO: add more client tests
+fn new_test_transaction(
+    now: Instant,
+    max_attempts: u32,
+    exponential_backoff: bool,
+) -> ClientTransaction {
+    ClientTransaction {
+        id: TransactionId::new(),
+        attempt: 0,
+        calls: 0,
+        handler: None,
+        start: now,
+        rto: Duration::from_millis(100),
+        max_attempts,
+        rm: 16,
+        exponential_backoff,
+        raw: vec![],
+    }
+}
+
+fn assert_timeouts(ct: &mut ClientTransaction, now: Instant, expected: &[u64]) {
+    for (attempt, ms) in expected.iter().enumerate() {
+        ct.attempt = attempt as u32;
+        assert_eq!(
+            ct.next_timeout(now),
+            now + Duration::from_millis(*ms),
+            "attempt {}",
+            attempt
+        );
+    }
+}
+
+#[test]
+fn test_client_transaction_next_timeout() {
+    let now = Instant::now();
+
+    let mut ct = new_test_transaction(now, 3, false);
+    assert_timeouts(&mut ct, now, &[100, 200, 300, 400]);
+
+    let mut ct = new_test_transaction(now, 3, true);
+    assert_timeouts(&mut ct, now, &[100, 200, 400, 1600]);
+}
+
+#[test]
+fn test_client_transaction_next_timeout_no_retransmit() {
+    let now = Instant::now();
+
+    for exponential_backoff in [false, true] {
+        let mut ct = new_test_transaction(now, 0, exponential_backoff);
+        assert_timeouts(&mut ct, now, &[100]);
+    }
+}
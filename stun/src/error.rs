@@ -23,6 +23,8 @@ pub enum Error {
     ErrTransactionTimeOut,
     #[error("no default reason for ErrorCode")]
     ErrNoDefaultReason,
+    #[error("unsupported password algorithm")]
+    ErrUnsupportedPasswordAlgorithm,
     #[error("unexpected EOF")]
     ErrUnexpectedEof,
     #[error("attribute size is invalid")]
@@ -39,6 +41,10 @@ pub enum Error {
     ErrFingerprintMismatch,
     #[error("FINGERPRINT before MESSAGE-INTEGRITY attribute")]
     ErrFingerprintBeforeIntegrity,
+    #[error("{0} is added by the message builder and must not be added as an attribute")]
+    ErrReservedAttribute(String),
+    #[error("message is too long")]
+    ErrMessageTooLong,
     #[error("bad UNKNOWN-ATTRIBUTES size")]
     ErrBadUnknownAttrsSize,
     #[error("invalid length of IP value")]
@@ -51,6 +57,8 @@ pub enum Error {
     ErrNoAgent,
     #[error("collector is closed")]
     ErrCollectorClosed,
+    #[error("server is closed")]
+    ErrServerClosed,
     #[error("unsupported network")]
     ErrUnsupportedNetwork,
     #[error("invalid url")]
@@ -59,6 +67,10 @@ pub enum Error {
     ErrSchemeType,
     #[error("invalid hostname")]
     ErrHost,
+    #[error("server does not support NAT behavior discovery (no OTHER-ADDRESS)")]
+    ErrNoOtherAddress,
+    #[error("message is too large for a stream frame")]
+    ErrFrameTooLarge,
     #[error("{0}")]
     Other(String),
     #[error("url parse: {0}")]
@@ -77,7 +77,7 @@ impl Getter for ErrorCodeAttribute {
 }
 
 // ErrorCode is code for ERROR-CODE attribute.
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Default)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Default, Debug)]
 pub struct ErrorCode(pub u16);
 
 impl Setter for ErrorCode {
@@ -131,6 +131,11 @@ pub const CODE_CONN_TIMEOUT_OR_FAILURE: ErrorCode = ErrorCode(447);
 pub const CODE_ADDR_FAMILY_NOT_SUPPORTED: ErrorCode = ErrorCode(440); // Address Family not Supported
 pub const CODE_PEER_ADDR_FAMILY_MISMATCH: ErrorCode = ErrorCode(443); // Peer Address Family Mismatch
 
+// Error codes from RFC 8016.
+//
+// RFC 8016 Section 4
+pub const CODE_MOBILITY_FORBIDDEN: ErrorCode = ErrorCode(405); // Mobility Forbidden
+
 lazy_static! {
     pub static ref ERROR_REASONS:HashMap<ErrorCode, Vec<u8>> =
         [
@@ -157,6 +162,9 @@ lazy_static! {
             // RFC 6156.
             (CODE_ADDR_FAMILY_NOT_SUPPORTED, b"Address Family not Supported".to_vec()),
             (CODE_PEER_ADDR_FAMILY_MISMATCH, b"Peer Address Family Mismatch".to_vec()),
+
+            // RFC 8016.
+            (CODE_MOBILITY_FORBIDDEN, b"Mobility Forbidden".to_vec()),
         ].iter().cloned().collect();
 
 }
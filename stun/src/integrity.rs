@@ -6,8 +6,10 @@ use crate::checks::*;
 use crate::error::*;
 use crate::message::*;
 
+use crate::password_algorithm::*;
+
 use md5::{Digest, Md5};
-use ring::hmac;
+use ring::{digest, hmac};
 use std::fmt;
 
 // separator for credentials.
@@ -115,3 +117,157 @@ impl MessageIntegrity {
         check_hmac(&v, &expected)
     }
 }
+
+// MessageIntegritySha256 represents MESSAGE-INTEGRITY-SHA256 attribute.
+//
+// The key is derived the same way as for MESSAGE-INTEGRITY, using the
+// password algorithm negotiated with the server for long-term credentials.
+//
+// RFC 8489 Section 14.6
+#[derive(Default, Clone)]
+pub struct MessageIntegritySha256(pub Vec<u8>);
+
+fn new_hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mac = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&mac, message).as_ref().to_vec()
+}
+
+impl fmt::Display for MessageIntegritySha256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KEY: 0x{:x?}", self.0)
+    }
+}
+
+impl Setter for MessageIntegritySha256 {
+    // add_to adds MESSAGE-INTEGRITY-SHA256 attribute to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        for a in &m.attributes.0 {
+            // Message should not contain FINGERPRINT attribute
+            // before MESSAGE-INTEGRITY-SHA256.
+            if a.typ == ATTR_FINGERPRINT {
+                return Err(Error::ErrFingerprintBeforeIntegrity);
+            }
+        }
+        // The text used as input to HMAC is the STUN message, up to and
+        // including the attribute preceding the MESSAGE-INTEGRITY-SHA256
+        // attribute, with the length adjusted to include it.
+        let length = m.length;
+        m.length += (MESSAGE_INTEGRITY_SHA256_SIZE + ATTRIBUTE_HEADER_SIZE) as u32;
+        m.write_length();
+        let v = new_hmac_sha256(&self.0, &m.raw);
+        m.length = length;
+
+        m.add(ATTR_MESSAGE_INTEGRITY_SHA256, &v);
+
+        Ok(())
+    }
+}
+
+pub(crate) const MESSAGE_INTEGRITY_SHA256_SIZE: usize = 32;
+
+// The HMAC may be truncated to no less than 16 bytes, in multiples of 4.
+const MESSAGE_INTEGRITY_SHA256_MIN_SIZE: usize = 16;
+
+impl MessageIntegritySha256 {
+    // new_long_term_integrity returns new MessageIntegritySha256 with key for
+    // long-term credentials, derived with the negotiated password algorithm.
+    // Password, username, and realm must be SASL-prepared.
+    pub fn new_long_term_integrity(
+        username: String,
+        realm: String,
+        password: String,
+        algorithm: PasswordAlgorithm,
+    ) -> Result<Self> {
+        Ok(MessageIntegritySha256(
+            algorithm.long_term_key(&username, &realm, &password)?,
+        ))
+    }
+
+    // new_short_term_integrity returns new MessageIntegritySha256 with key for
+    // short-term credentials. Password must be SASL-prepared.
+    pub fn new_short_term_integrity(password: String) -> Self {
+        MessageIntegritySha256(password.as_bytes().to_vec())
+    }
+
+    // Check checks MESSAGE-INTEGRITY-SHA256 attribute, accepting truncated
+    // values as allowed by RFC 8489.
+    pub fn check(&self, m: &mut Message) -> Result<()> {
+        let v = m.get(ATTR_MESSAGE_INTEGRITY_SHA256)?;
+        if v.len() < MESSAGE_INTEGRITY_SHA256_MIN_SIZE
+            || v.len() > MESSAGE_INTEGRITY_SHA256_SIZE
+            || v.len() % 4 != 0
+        {
+            return Err(Error::ErrAttributeSizeInvalid);
+        }
+
+        let length = m.length as usize;
+        let mut after_integrity = false;
+        let mut size_reduced = 0;
+
+        for a in &m.attributes.0 {
+            if after_integrity {
+                size_reduced += nearest_padded_value_length(a.length as usize);
+                size_reduced += ATTRIBUTE_HEADER_SIZE;
+            }
+            if a.typ == ATTR_MESSAGE_INTEGRITY_SHA256 {
+                after_integrity = true;
+            }
+        }
+        m.length -= size_reduced as u32;
+        m.write_length();
+        let start_of_hmac =
+            MESSAGE_HEADER_SIZE + m.length as usize - (ATTRIBUTE_HEADER_SIZE + v.len());
+        let b = &m.raw[..start_of_hmac];
+        let expected = new_hmac_sha256(&self.0, b);
+        m.length = length as u32;
+        m.write_length();
+        check_hmac(&v, &expected[..v.len()])
+    }
+}
+
+// UserHash represents USERHASH attribute, which replaces USERNAME to
+// anonymize the username for servers supporting it.
+//
+// RFC 8489 Section 14.4
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct UserHash(pub Vec<u8>);
+
+pub(crate) const USER_HASH_SIZE: usize = 32;
+
+impl UserHash {
+    // new returns the USERHASH of username and realm, computed as
+    // SHA-256(username ":" realm). Username and realm must be SASL-prepared.
+    pub fn new(username: &str, realm: &str) -> Self {
+        let s = [username, realm].join(CREDENTIALS_SEP);
+        UserHash(
+            digest::digest(&digest::SHA256, s.as_bytes())
+                .as_ref()
+                .to_vec(),
+        )
+    }
+}
+
+impl fmt::Display for UserHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:x?}", self.0)
+    }
+}
+
+impl Setter for UserHash {
+    // add_to adds USERHASH attribute to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        check_size(ATTR_USER_HASH, self.0.len(), USER_HASH_SIZE)?;
+        m.add(ATTR_USER_HASH, &self.0);
+        Ok(())
+    }
+}
+
+impl Getter for UserHash {
+    // get_from decodes USERHASH attribute from message.
+    fn get_from(&mut self, m: &Message) -> Result<()> {
+        let v = m.get(ATTR_USER_HASH)?;
+        check_size(ATTR_USER_HASH, v.len(), USER_HASH_SIZE)?;
+        self.0 = v;
+        Ok(())
+    }
+}
@@ -91,3 +91,88 @@ fn test_message_integrity_before_fingerprint() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_message_integrity_sha256() -> Result<()> {
+    let mut m = Message::new();
+    m.transaction_id = TransactionId([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 0]);
+    m.write_header();
+    let a = TextAttribute {
+        attr: ATTR_SOFTWARE,
+        text: "software".to_owned(),
+    };
+    a.add_to(&mut m)?;
+
+    let i = MessageIntegritySha256::new_long_term_integrity(
+        "user".to_owned(),
+        "realm".to_owned(),
+        "pass".to_owned(),
+        PASSWORD_ALGORITHM_SHA256,
+    )?;
+    let result = i.check(&mut m);
+    assert!(result.is_err(), "should error");
+
+    // MESSAGE-INTEGRITY-SHA256 follows MESSAGE-INTEGRITY when both are used.
+    let legacy = MessageIntegrity::new_long_term_integrity(
+        "user".to_owned(),
+        "realm".to_owned(),
+        "pass".to_owned(),
+    );
+    legacy.add_to(&mut m)?;
+    i.add_to(&mut m)?;
+    FINGERPRINT.add_to(&mut m)?;
+
+    let mut d_m = Message::new();
+    d_m.write(&m.raw)?;
+    legacy.check(&mut d_m)?;
+    i.check(&mut d_m)?;
+
+    let other = MessageIntegritySha256::new_short_term_integrity("pass".to_owned());
+    let result = other.check(&mut d_m);
+    assert!(result.is_err(), "mismatch expected");
+
+    Ok(())
+}
+
+#[test]
+fn test_message_integrity_sha256_truncated() -> Result<()> {
+    let i = MessageIntegritySha256::new_short_term_integrity("password".to_owned());
+
+    // A peer may send the HMAC truncated to 16 bytes.
+    let mut m = Message::new();
+    m.write_header();
+    let length = m.length;
+    m.length += (16 + ATTRIBUTE_HEADER_SIZE) as u32;
+    m.write_length();
+    let v = new_hmac_sha256(&i.0, &m.raw);
+    m.length = length;
+    m.add(ATTR_MESSAGE_INTEGRITY_SHA256, &v[..16]);
+    i.check(&mut m)?;
+
+    let mut m = Message::new();
+    m.write_header();
+    m.add(ATTR_MESSAGE_INTEGRITY_SHA256, &v[..12]);
+    let result = i.check(&mut m);
+    assert_eq!(result, Err(Error::ErrAttributeSizeInvalid));
+
+    Ok(())
+}
+
+#[test]
+fn test_user_hash() -> Result<()> {
+    let h = UserHash::new("user", "realm");
+    assert_eq!(h.0.len(), USER_HASH_SIZE);
+
+    let mut m = Message::new();
+    m.write_header();
+    h.add_to(&mut m)?;
+
+    let mut got = UserHash::default();
+    got.get_from(&m)?;
+    assert_eq!(got, h);
+
+    let result = UserHash(vec![1, 2, 3]).add_to(&mut m);
+    assert_eq!(result, Err(Error::ErrAttributeSizeInvalid));
+
+    Ok(())
+}
@@ -7,6 +7,7 @@ extern crate lazy_static;
 pub mod addr;
 pub mod agent;
 pub mod attributes;
+pub mod builder;
 pub mod checks;
 pub mod client;
 mod error;
@@ -14,6 +15,11 @@ pub mod error_code;
 pub mod fingerprint;
 pub mod integrity;
 pub mod message;
+pub mod message_view;
+pub mod nat_behavior;
+pub mod password_algorithm;
+pub mod server;
+pub mod stream;
 pub mod textattrs;
 pub mod uattrs;
 pub mod uri;
@@ -404,6 +404,17 @@ impl Message {
         }
     }
 
+    // get_ref is like get, but borrows the attribute value instead of
+    // copying it.
+    pub fn get_ref(&self, t: AttrType) -> Result<&[u8]> {
+        self.attributes
+            .0
+            .iter()
+            .find(|a| a.typ == t)
+            .map(|a| a.value.as_slice())
+            .ok_or(Error::ErrAttributeNotFound)
+    }
+
     // Build resets message and applies setters to it in batch, returning on
     // first error. To prevent allocations, pass pointers to values.
     //
@@ -0,0 +1,217 @@
+#[cfg(test)]
+mod message_view_test;
+
+use crate::addr::*;
+use crate::agent::*;
+use crate::attributes::*;
+use crate::error::*;
+use crate::message::*;
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// MessageView is a read-only view of a STUN message which borrows the
+/// received buffer instead of copying it.
+///
+/// Unlike Message::write, parsing a MessageView does not allocate: the
+/// header and attribute layout are validated once, and attribute values
+/// are returned as slices of the original buffer. This makes it suitable
+/// for hot paths that only need to look at a few attributes, such as
+/// answering Binding requests.
+#[derive(Debug, Copy, Clone)]
+pub struct MessageView<'a> {
+    pub typ: MessageType,
+    pub transaction_id: TransactionId,
+    raw: &'a [u8],
+}
+
+impl<'a> MessageView<'a> {
+    /// parse validates the message header and attribute layout of buf,
+    /// applying the same checks as Message::decode.
+    pub fn parse(buf: &'a [u8]) -> Result<Self> {
+        if buf.len() < MESSAGE_HEADER_SIZE {
+            return Err(Error::ErrUnexpectedHeaderEof);
+        }
+
+        let t = u16::from_be_bytes([buf[0], buf[1]]);
+        let size = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        let cookie = u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]);
+        let full_size = MESSAGE_HEADER_SIZE + size;
+
+        if cookie != MAGIC_COOKIE {
+            return Err(Error::Other(format!(
+                "{:x} is invalid magic cookie (should be {:x})",
+                cookie, MAGIC_COOKIE
+            )));
+        }
+        if buf.len() < full_size {
+            return Err(Error::Other(format!(
+                "buffer length {} is less than {} (expected message size)",
+                buf.len(),
+                full_size
+            )));
+        }
+
+        let mut b = &buf[MESSAGE_HEADER_SIZE..full_size];
+        while !b.is_empty() {
+            if b.len() < ATTRIBUTE_HEADER_SIZE {
+                return Err(Error::Other(format!(
+                    "buffer length {} is less than {} (expected header size)",
+                    b.len(),
+                    ATTRIBUTE_HEADER_SIZE
+                )));
+            }
+
+            let typ = compat_attr_type(u16::from_be_bytes([b[0], b[1]]));
+            let l = u16::from_be_bytes([b[2], b[3]]) as usize;
+            let padded = nearest_padded_value_length(l);
+            b = &b[ATTRIBUTE_HEADER_SIZE..];
+            if b.len() < padded {
+                return Err(Error::Other(format!(
+                    "buffer length {} is less than {} (expected value size for {})",
+                    b.len(),
+                    padded,
+                    typ
+                )));
+            }
+            b = &b[padded..];
+        }
+
+        let mut typ = MessageType::default();
+        typ.read_value(t);
+
+        let mut transaction_id = TransactionId::default();
+        transaction_id
+            .0
+            .copy_from_slice(&buf[8..MESSAGE_HEADER_SIZE]);
+
+        Ok(MessageView {
+            typ,
+            transaction_id,
+            raw: &buf[..full_size],
+        })
+    }
+
+    /// raw returns the message bytes, excluding anything after the message
+    /// in the buffer it was parsed from.
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// length returns the length of the message not including the header.
+    pub fn length(&self) -> usize {
+        self.raw.len() - MESSAGE_HEADER_SIZE
+    }
+
+    /// attributes returns an iterator over the type and value of every
+    /// attribute, in the order they appear in the message.
+    pub fn attributes(&self) -> AttributeIter<'a> {
+        AttributeIter {
+            b: &self.raw[MESSAGE_HEADER_SIZE..],
+        }
+    }
+
+    /// contains returns true if the message contains an attribute of type t.
+    pub fn contains(&self, t: AttrType) -> bool {
+        self.attributes().any(|(typ, _)| typ == t)
+    }
+
+    /// get returns the value of the first attribute of type t, or
+    /// ErrAttributeNotFound.
+    pub fn get(&self, t: AttrType) -> Result<&'a [u8]> {
+        self.attributes()
+            .find(|(typ, _)| *typ == t)
+            .map(|(_, v)| v)
+            .ok_or(Error::ErrAttributeNotFound)
+    }
+
+    /// get_str returns the value of a text attribute such as USERNAME.
+    pub fn get_str(&self, t: AttrType) -> Result<&'a str> {
+        let v = self.get(t)?;
+        std::str::from_utf8(v).map_err(|err| Error::Other(err.to_string()))
+    }
+
+    /// get_u32 returns the value of a 32-bit attribute such as PRIORITY.
+    pub fn get_u32(&self, t: AttrType) -> Result<u32> {
+        let v = self.get(t)?;
+        if v.len() != 4 {
+            return Err(Error::ErrAttributeSizeInvalid);
+        }
+        Ok(u32::from_be_bytes([v[0], v[1], v[2], v[3]]))
+    }
+
+    /// get_u64 returns the value of a 64-bit attribute such as ICE-CONTROLLING.
+    pub fn get_u64(&self, t: AttrType) -> Result<u64> {
+        let v = self.get(t)?;
+        if v.len() != 8 {
+            return Err(Error::ErrAttributeSizeInvalid);
+        }
+        let mut b = [0u8; 8];
+        b.copy_from_slice(v);
+        Ok(u64::from_be_bytes(b))
+    }
+
+    /// get_xor_address decodes an XOR-MAPPED-ADDRESS style attribute of type t.
+    pub fn get_xor_address(&self, t: AttrType) -> Result<SocketAddr> {
+        let v = self.get(t)?;
+        if v.len() <= 4 {
+            return Err(Error::ErrUnexpectedEof);
+        }
+
+        let family = u16::from_be_bytes([v[0], v[1]]);
+        let port = u16::from_be_bytes([v[2], v[3]]) ^ (MAGIC_COOKIE >> 16) as u16;
+
+        let mut xor_value = [0u8; 4 + TRANSACTION_ID_SIZE];
+        xor_value[0..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        xor_value[4..].copy_from_slice(&self.transaction_id.0);
+
+        let ip = match family {
+            FAMILY_IPV4 => {
+                if v[4..].len() < IPV4LEN {
+                    return Err(Error::ErrAttributeSizeOverflow);
+                }
+                let mut ip = [0u8; IPV4LEN];
+                for (i, b) in ip.iter_mut().enumerate() {
+                    *b = v[4 + i] ^ xor_value[i];
+                }
+                IpAddr::V4(Ipv4Addr::from(ip))
+            }
+            FAMILY_IPV6 => {
+                if v[4..].len() < IPV6LEN {
+                    return Err(Error::ErrAttributeSizeOverflow);
+                }
+                let mut ip = [0u8; IPV6LEN];
+                for (i, b) in ip.iter_mut().enumerate() {
+                    *b = v[4 + i] ^ xor_value[i];
+                }
+                IpAddr::V6(Ipv6Addr::from(ip))
+            }
+            _ => return Err(Error::Other(format!("bad value {}", family))),
+        };
+
+        Ok(SocketAddr::new(ip, port))
+    }
+}
+
+/// AttributeIter iterates over the attributes of a MessageView.
+pub struct AttributeIter<'a> {
+    b: &'a [u8],
+}
+
+impl<'a> Iterator for AttributeIter<'a> {
+    type Item = (AttrType, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The layout has already been validated by MessageView::parse.
+        if self.b.len() < ATTRIBUTE_HEADER_SIZE {
+            return None;
+        }
+
+        let typ = compat_attr_type(u16::from_be_bytes([self.b[0], self.b[1]]));
+        let l = u16::from_be_bytes([self.b[2], self.b[3]]) as usize;
+        let padded = nearest_padded_value_length(l);
+        let value = &self.b[ATTRIBUTE_HEADER_SIZE..ATTRIBUTE_HEADER_SIZE + l];
+        self.b = &self.b[ATTRIBUTE_HEADER_SIZE + padded..];
+
+        Some((typ, value))
+    }
+}
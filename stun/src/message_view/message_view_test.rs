@@ -0,0 +1,94 @@
+use super::*;
+use crate::textattrs::*;
+use crate::xoraddr::*;
+
+#[test]
+fn test_message_view_matches_message() -> Result<()> {
+    let mut m = Message::new();
+    m.build(&[
+        Box::new(TransactionId::new()),
+        Box::new(BINDING_SUCCESS),
+        Box::new(TextAttribute::new(ATTR_USERNAME, "user:pass".to_owned())),
+        Box::new(XorMappedAddress {
+            ip: "213.1.223.5".parse().unwrap(),
+            port: 4321,
+        }),
+    ])?;
+    m.add(ATTR_PRIORITY, &0x6e0001ffu32.to_be_bytes());
+
+    // Trailing bytes after the message are ignored.
+    let mut buf = m.raw.clone();
+    buf.extend_from_slice(&[0xff; 8]);
+
+    let v = MessageView::parse(&buf)?;
+    assert_eq!(v.typ, m.typ);
+    assert_eq!(v.transaction_id, m.transaction_id);
+    assert_eq!(v.length(), m.length as usize);
+    assert_eq!(v.raw(), &m.raw[..]);
+
+    assert_eq!(v.attributes().count(), 3);
+    assert!(v.contains(ATTR_USERNAME));
+    assert!(!v.contains(ATTR_NONCE));
+    assert_eq!(v.get(ATTR_USERNAME)?, m.get_ref(ATTR_USERNAME)?);
+    assert_eq!(v.get_str(ATTR_USERNAME)?, "user:pass");
+    assert_eq!(v.get_u32(ATTR_PRIORITY)?, 0x6e0001ff);
+    assert_eq!(
+        v.get_xor_address(ATTR_XORMAPPED_ADDRESS)?,
+        "213.1.223.5:4321".parse().unwrap()
+    );
+    assert_eq!(v.get(ATTR_NONCE), Err(Error::ErrAttributeNotFound));
+    assert_eq!(
+        v.get_u64(ATTR_PRIORITY),
+        Err(Error::ErrAttributeSizeInvalid)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_message_view_xor_address_ipv6() -> Result<()> {
+    let mut m = Message::new();
+    m.build(&[
+        Box::new(TransactionId::new()),
+        Box::new(BINDING_SUCCESS),
+        Box::new(XorMappedAddress {
+            ip: "fe80::dc2b:44ff:fe20:6009".parse().unwrap(),
+            port: 21254,
+        }),
+    ])?;
+
+    let v = MessageView::parse(&m.raw)?;
+    assert_eq!(
+        v.get_xor_address(ATTR_XORMAPPED_ADDRESS)?,
+        "[fe80::dc2b:44ff:fe20:6009]:21254".parse().unwrap()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_message_view_parse_errors() -> Result<()> {
+    let mut m = Message::new();
+    m.build(&[
+        Box::new(TransactionId::new()),
+        Box::new(BINDING_REQUEST),
+        Box::new(TextAttribute::new(ATTR_SOFTWARE, "webrtc-rs".to_owned())),
+    ])?;
+
+    assert_eq!(
+        MessageView::parse(&m.raw[..10]).unwrap_err(),
+        Error::ErrUnexpectedHeaderEof
+    );
+    assert!(MessageView::parse(&m.raw[..m.raw.len() - 4]).is_err());
+
+    // Attribute length pointing past the end of the message.
+    let mut raw = m.raw.clone();
+    raw[MESSAGE_HEADER_SIZE + 3] = 0xff;
+    assert!(MessageView::parse(&raw).is_err());
+
+    let mut raw = m.raw.clone();
+    raw[4] = 0;
+    assert!(MessageView::parse(&raw).is_err());
+
+    Ok(())
+}
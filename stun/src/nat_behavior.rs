@@ -0,0 +1,315 @@
+#[cfg(test)]
+mod nat_behavior_test;
+
+use crate::addr::*;
+use crate::agent::*;
+use crate::attributes::*;
+use crate::checks::*;
+use crate::error::*;
+use crate::message::*;
+use crate::xoraddr::*;
+
+use util::Conn;
+
+use std::fmt;
+use std::net::SocketAddr;
+use tokio::time::{self, Duration, Instant};
+
+const CHANGE_REQUEST_SIZE: usize = 4;
+const CHANGE_IP_FLAG: u32 = 0x04;
+const CHANGE_PORT_FLAG: u32 = 0x02;
+
+const DEFAULT_RTO: Duration = Duration::from_millis(500);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// ChangeRequest represents CHANGE-REQUEST attribute.
+///
+/// Asks the server to send the response from its alternate IP address
+/// and/or port.
+///
+/// RFC 5780 Section 7.2
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ChangeRequest {
+    pub change_ip: bool,
+    pub change_port: bool,
+}
+
+impl Setter for ChangeRequest {
+    /// add_to adds CHANGE-REQUEST to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        let mut flags = 0u32;
+        if self.change_ip {
+            flags |= CHANGE_IP_FLAG;
+        }
+        if self.change_port {
+            flags |= CHANGE_PORT_FLAG;
+        }
+        m.add(ATTR_CHANGE_REQUEST, &flags.to_be_bytes());
+        Ok(())
+    }
+}
+
+impl Getter for ChangeRequest {
+    /// get_from decodes CHANGE-REQUEST from message.
+    fn get_from(&mut self, m: &Message) -> Result<()> {
+        let v = m.get(ATTR_CHANGE_REQUEST)?;
+        check_size(ATTR_CHANGE_REQUEST, v.len(), CHANGE_REQUEST_SIZE)?;
+        let flags = u32::from_be_bytes([v[0], v[1], v[2], v[3]]);
+        self.change_ip = flags & CHANGE_IP_FLAG != 0;
+        self.change_port = flags & CHANGE_PORT_FLAG != 0;
+        Ok(())
+    }
+}
+
+/// MappingBehavior describes how a NAT assigns public addresses to outgoing flows.
+///
+/// RFC 4787 Section 4.1
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MappingBehavior {
+    /// The local address is not translated.
+    NoNat,
+    /// The same mapping is reused for any destination.
+    EndpointIndependent,
+    /// The mapping is reused only for destinations with the same IP address.
+    AddressDependent,
+    /// The mapping is reused only for the same destination IP address and port.
+    AddressAndPortDependent,
+}
+
+impl fmt::Display for MappingBehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            MappingBehavior::NoNat => "no NAT",
+            MappingBehavior::EndpointIndependent => "endpoint-independent mapping",
+            MappingBehavior::AddressDependent => "address-dependent mapping",
+            MappingBehavior::AddressAndPortDependent => "address and port-dependent mapping",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// FilteringBehavior describes which inbound packets a NAT lets through to a mapping.
+///
+/// RFC 4787 Section 5
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FilteringBehavior {
+    /// Packets from any source are accepted.
+    EndpointIndependent,
+    /// Packets are accepted only from IP addresses that were sent to.
+    AddressDependent,
+    /// Packets are accepted only from IP addresses and ports that were sent to.
+    AddressAndPortDependent,
+}
+
+impl fmt::Display for FilteringBehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            FilteringBehavior::EndpointIndependent => "endpoint-independent filtering",
+            FilteringBehavior::AddressDependent => "address-dependent filtering",
+            FilteringBehavior::AddressAndPortDependent => "address and port-dependent filtering",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// NatBehavior is the result of NAT behavior discovery.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NatBehavior {
+    /// The public address reported by the server for the primary address.
+    pub mapped_address: SocketAddr,
+    pub mapping: MappingBehavior,
+    pub filtering: FilteringBehavior,
+}
+
+impl fmt::Display for NatBehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {} (mapped address {})",
+            self.mapping, self.filtering, self.mapped_address
+        )
+    }
+}
+
+impl NatBehavior {
+    /// needs_relay returns true if the NAT mapping depends on the destination,
+    /// in which case server reflexive candidates are unlikely to work and a
+    /// TURN relay will usually be needed.
+    pub fn needs_relay(&self) -> bool {
+        matches!(
+            self.mapping,
+            MappingBehavior::AddressDependent | MappingBehavior::AddressAndPortDependent
+        )
+    }
+}
+
+/// NatDiscovery determines the mapping and filtering behavior of the NATs
+/// between conn and a STUN server supporting RFC 5780, i.e. one which has
+/// an alternate IP address and port and returns OTHER-ADDRESS.
+///
+/// conn must not be connected, since requests are sent to several
+/// destinations and responses may arrive from any of the server addresses.
+pub struct NatDiscovery<'a> {
+    conn: &'a (dyn Conn + Send + Sync),
+    server: SocketAddr,
+    rto: Duration,
+    timeout: Duration,
+}
+
+impl<'a> NatDiscovery<'a> {
+    pub fn new(conn: &'a (dyn Conn + Send + Sync), server: SocketAddr) -> Self {
+        NatDiscovery {
+            conn,
+            server,
+            rto: DEFAULT_RTO,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// with_rto sets the interval between retransmissions of a request.
+    pub fn with_rto(mut self, rto: Duration) -> Self {
+        self.rto = rto;
+        self
+    }
+
+    /// with_timeout sets how long to wait for a response before concluding
+    /// that it was blocked. Filtering tests always wait this long when the
+    /// NAT drops the response, so it bounds the duration of discovery.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// discover runs the mapping and filtering tests.
+    pub async fn discover(&self) -> Result<NatBehavior> {
+        // Test I: binding request to the primary address.
+        let (mapped_address, other_address) = match self.request(self.server, None).await? {
+            Some(resp) => resp,
+            None => return Err(Error::ErrTransactionTimeOut),
+        };
+        let other_address = other_address.ok_or(Error::ErrNoOtherAddress)?;
+
+        let mapping = self.mapping_behavior(mapped_address, other_address).await?;
+        let filtering = self.filtering_behavior().await?;
+
+        Ok(NatBehavior {
+            mapped_address,
+            mapping,
+            filtering,
+        })
+    }
+
+    // RFC 5780 Section 4.3
+    async fn mapping_behavior(
+        &self,
+        mapped_address: SocketAddr,
+        other_address: SocketAddr,
+    ) -> Result<MappingBehavior> {
+        let local_addr = self.conn.local_addr()?;
+        if mapped_address == local_addr {
+            return Ok(MappingBehavior::NoNat);
+        }
+
+        // Test II: alternate IP address, primary port.
+        let dest = SocketAddr::new(other_address.ip(), self.server.port());
+        let mapped_address2 = match self.request(dest, None).await? {
+            Some((addr, _)) => addr,
+            None => return Err(Error::ErrTransactionTimeOut),
+        };
+        if mapped_address2 == mapped_address {
+            return Ok(MappingBehavior::EndpointIndependent);
+        }
+
+        // Test III: alternate IP address and port.
+        let mapped_address3 = match self.request(other_address, None).await? {
+            Some((addr, _)) => addr,
+            None => return Err(Error::ErrTransactionTimeOut),
+        };
+        if mapped_address3 == mapped_address2 {
+            Ok(MappingBehavior::AddressDependent)
+        } else {
+            Ok(MappingBehavior::AddressAndPortDependent)
+        }
+    }
+
+    // RFC 5780 Section 4.4
+    async fn filtering_behavior(&self) -> Result<FilteringBehavior> {
+        // Test II: response from the alternate IP address and port.
+        let change = ChangeRequest {
+            change_ip: true,
+            change_port: true,
+        };
+        if self.request(self.server, Some(change)).await?.is_some() {
+            return Ok(FilteringBehavior::EndpointIndependent);
+        }
+
+        // Test III: response from the primary IP address and alternate port.
+        let change = ChangeRequest {
+            change_ip: false,
+            change_port: true,
+        };
+        if self.request(self.server, Some(change)).await?.is_some() {
+            Ok(FilteringBehavior::AddressDependent)
+        } else {
+            Ok(FilteringBehavior::AddressAndPortDependent)
+        }
+    }
+
+    // request sends a binding request to dest and returns the XOR-MAPPED-ADDRESS
+    // and OTHER-ADDRESS of the response, or None if no response arrived in time.
+    async fn request(
+        &self,
+        dest: SocketAddr,
+        change: Option<ChangeRequest>,
+    ) -> Result<Option<(SocketAddr, Option<SocketAddr>)>> {
+        let mut req = Message::new();
+        let mut setters: Vec<Box<dyn Setter>> =
+            vec![Box::new(TransactionId::new()), Box::new(BINDING_REQUEST)];
+        if let Some(change) = change {
+            setters.push(Box::new(change));
+        }
+        req.build(&setters)?;
+
+        let deadline = Instant::now() + self.timeout;
+        let mut buf = vec![0u8; 1500];
+
+        while Instant::now() < deadline {
+            self.conn.send_to(&req.raw, dest).await?;
+
+            let retransmit_at = std::cmp::min(Instant::now() + self.rto, deadline);
+            while let Ok(result) =
+                time::timeout_at(retransmit_at, self.conn.recv_from(&mut buf)).await
+            {
+                let (n, _) = result?;
+                if !is_message(&buf[..n]) {
+                    continue;
+                }
+
+                let mut resp = Message::new();
+                if resp.write(&buf[..n]).is_err() || resp.transaction_id != req.transaction_id {
+                    continue;
+                }
+
+                if resp.typ.class != CLASS_SUCCESS_RESPONSE {
+                    return Err(Error::Other(format!("unexpected response {}", resp.typ)));
+                }
+
+                let mut xor_addr = XorMappedAddress::default();
+                xor_addr.get_from(&resp)?;
+
+                let mut other = OtherAddress::default();
+                let other_address = match other.get_from_as(&resp, ATTR_OTHER_ADDRESS) {
+                    Ok(()) => Some(SocketAddr::new(other.ip, other.port)),
+                    Err(_) => None,
+                };
+
+                return Ok(Some((
+                    SocketAddr::new(xor_addr.ip, xor_addr.port),
+                    other_address,
+                )));
+            }
+        }
+
+        Ok(None)
+    }
+}
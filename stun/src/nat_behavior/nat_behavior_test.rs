@@ -0,0 +1,143 @@
+use super::*;
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+
+// MockServer emulates a RFC 5780 server listening on two IP addresses
+// and two ports. Index 0 is the primary address.
+struct MockServer {
+    sockets: Vec<Arc<UdpSocket>>,
+}
+
+impl MockServer {
+    async fn new(with_other_address: bool) -> Result<Self> {
+        let ips = [
+            IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+            IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)),
+        ];
+
+        let primary = UdpSocket::bind(SocketAddr::new(ips[0], 0)).await?;
+        let alternate = UdpSocket::bind(SocketAddr::new(ips[0], 0)).await?;
+        let ports = [primary.local_addr()?.port(), alternate.local_addr()?.port()];
+
+        // sockets[ip_index * 2 + port_index]
+        let sockets = vec![
+            Arc::new(primary),
+            Arc::new(alternate),
+            Arc::new(UdpSocket::bind(SocketAddr::new(ips[1], ports[0])).await?),
+            Arc::new(UdpSocket::bind(SocketAddr::new(ips[1], ports[1])).await?),
+        ];
+
+        for (i, socket) in sockets.iter().enumerate() {
+            let socket = Arc::clone(socket);
+            let all = sockets.clone();
+            tokio::spawn(async move {
+                let mut buf = vec![0u8; 1500];
+                while let Ok((n, src)) = socket.recv_from(&mut buf).await {
+                    let mut req = Message::new();
+                    if req.write(&buf[..n]).is_err() {
+                        continue;
+                    }
+
+                    let mut change = ChangeRequest::default();
+                    let _ = change.get_from(&req);
+
+                    let (ip_index, port_index) = (i / 2, i % 2);
+                    let ip_index = if change.change_ip {
+                        1 - ip_index
+                    } else {
+                        ip_index
+                    };
+                    let port_index = if change.change_port {
+                        1 - port_index
+                    } else {
+                        port_index
+                    };
+                    let responder = &all[ip_index * 2 + port_index];
+
+                    let mut resp = Message::new();
+                    resp.build(&[
+                        Box::new(req.transaction_id),
+                        Box::new(BINDING_SUCCESS),
+                        Box::new(XorMappedAddress {
+                            ip: src.ip(),
+                            port: src.port(),
+                        }),
+                    ])
+                    .unwrap();
+                    if with_other_address {
+                        let other_index = (1 - i / 2) * 2 + (1 - i % 2);
+                        let other = all[other_index].local_addr().unwrap();
+                        OtherAddress {
+                            ip: other.ip(),
+                            port: other.port(),
+                        }
+                        .add_to_as(&mut resp, ATTR_OTHER_ADDRESS)
+                        .unwrap();
+                    }
+
+                    let _ = responder.send_to(&resp.raw, src).await;
+                }
+            });
+        }
+
+        Ok(MockServer { sockets })
+    }
+
+    fn addr(&self) -> SocketAddr {
+        self.sockets[0].local_addr().unwrap()
+    }
+}
+
+#[test]
+fn test_change_request() -> Result<()> {
+    let mut m = Message::new();
+    let c = ChangeRequest {
+        change_ip: true,
+        change_port: false,
+    };
+    m.build(&[Box::new(BINDING_REQUEST), Box::new(c)])?;
+    assert_eq!(m.get(ATTR_CHANGE_REQUEST)?, vec![0, 0, 0, 0x04]);
+
+    let mut decoded = Message::new();
+    decoded.write(&m.raw)?;
+    let mut got = ChangeRequest::default();
+    got.get_from(&decoded)?;
+    assert_eq!(got, c);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nat_discovery_no_nat() -> Result<()> {
+    let server = MockServer::new(true).await?;
+    let conn = UdpSocket::bind("127.0.0.1:0").await?;
+
+    let behavior = NatDiscovery::new(&conn, server.addr())
+        .with_rto(Duration::from_millis(50))
+        .with_timeout(Duration::from_millis(500))
+        .discover()
+        .await?;
+
+    assert_eq!(behavior.mapped_address, conn.local_addr()?);
+    assert_eq!(behavior.mapping, MappingBehavior::NoNat);
+    assert_eq!(behavior.filtering, FilteringBehavior::EndpointIndependent);
+    assert!(!behavior.needs_relay());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nat_discovery_without_other_address() -> Result<()> {
+    let server = MockServer::new(false).await?;
+    let conn = UdpSocket::bind("127.0.0.1:0").await?;
+
+    let result = NatDiscovery::new(&conn, server.addr())
+        .with_timeout(Duration::from_millis(500))
+        .discover()
+        .await;
+    assert_eq!(result, Err(Error::ErrNoOtherAddress));
+
+    Ok(())
+}
@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod password_algorithm_test;
+
+use crate::attributes::*;
+use crate::error::*;
+use crate::integrity::CREDENTIALS_SEP;
+use crate::message::*;
+
+use md5::{Digest, Md5};
+use ring::digest;
+use std::fmt;
+
+// PasswordAlgorithm represents PASSWORD-ALGORITHM attribute, the algorithm
+// used to derive the long-term credential key.
+//
+// RFC 8489 Section 14.12
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub struct PasswordAlgorithm(pub u16);
+
+// Password algorithms as registered by IANA, RFC 8489 Section 18.5.
+pub const PASSWORD_ALGORITHM_MD5: PasswordAlgorithm = PasswordAlgorithm(0x0001);
+pub const PASSWORD_ALGORITHM_SHA256: PasswordAlgorithm = PasswordAlgorithm(0x0002);
+
+impl Default for PasswordAlgorithm {
+    fn default() -> Self {
+        PASSWORD_ALGORITHM_MD5
+    }
+}
+
+impl fmt::Display for PasswordAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PASSWORD_ALGORITHM_MD5 => write!(f, "MD5"),
+            PASSWORD_ALGORITHM_SHA256 => write!(f, "SHA-256"),
+            _ => write!(f, "0x{:x}", self.0),
+        }
+    }
+}
+
+// size of algorithm and parameters length fields.
+const PASSWORD_ALGORITHM_HEADER_SIZE: usize = 4;
+
+impl PasswordAlgorithm {
+    // long_term_key returns the key for long-term credentials, the hash of
+    // username ":" realm ":" password. Password, username, and realm must
+    // be SASL-prepared.
+    pub fn long_term_key(&self, username: &str, realm: &str, password: &str) -> Result<Vec<u8>> {
+        let s = [username, realm, password].join(CREDENTIALS_SEP);
+        match *self {
+            PASSWORD_ALGORITHM_MD5 => {
+                let mut h = Md5::new();
+                h.update(s.as_bytes());
+                Ok(h.finalize().as_slice().to_vec())
+            }
+            PASSWORD_ALGORITHM_SHA256 => Ok(digest::digest(&digest::SHA256, s.as_bytes())
+                .as_ref()
+                .to_vec()),
+            _ => Err(Error::ErrUnsupportedPasswordAlgorithm),
+        }
+    }
+
+    fn encode(&self, v: &mut Vec<u8>) {
+        // Neither MD5 nor SHA-256 take parameters.
+        v.extend_from_slice(&self.0.to_be_bytes());
+        v.extend_from_slice(&0u16.to_be_bytes());
+    }
+
+    // decode returns the algorithm at the start of b and the number of
+    // bytes it occupies, including its padded parameters.
+    fn decode(b: &[u8]) -> Result<(Self, usize)> {
+        if b.len() < PASSWORD_ALGORITHM_HEADER_SIZE {
+            return Err(Error::ErrUnexpectedEof);
+        }
+        let algorithm = u16::from_be_bytes([b[0], b[1]]);
+        let params_length = u16::from_be_bytes([b[2], b[3]]) as usize;
+        let size = PASSWORD_ALGORITHM_HEADER_SIZE + nearest_padded_value_length(params_length);
+        if b.len() < PASSWORD_ALGORITHM_HEADER_SIZE + params_length {
+            return Err(Error::ErrUnexpectedEof);
+        }
+        Ok((PasswordAlgorithm(algorithm), size))
+    }
+}
+
+impl Setter for PasswordAlgorithm {
+    // add_to adds PASSWORD-ALGORITHM attribute to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        let mut v = Vec::with_capacity(PASSWORD_ALGORITHM_HEADER_SIZE);
+        self.encode(&mut v);
+        m.add(ATTR_PASSWORD_ALGORITHM, &v);
+        Ok(())
+    }
+}
+
+impl Getter for PasswordAlgorithm {
+    // get_from decodes PASSWORD-ALGORITHM attribute from message.
+    fn get_from(&mut self, m: &Message) -> Result<()> {
+        let v = m.get(ATTR_PASSWORD_ALGORITHM)?;
+        let (algorithm, _) = PasswordAlgorithm::decode(&v)?;
+        *self = algorithm;
+        Ok(())
+    }
+}
+
+// PasswordAlgorithms represents PASSWORD-ALGORITHMS attribute, the list of
+// algorithms a server supports, in order of preference.
+//
+// RFC 8489 Section 14.11
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct PasswordAlgorithms(pub Vec<PasswordAlgorithm>);
+
+impl fmt::Display for PasswordAlgorithms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s: Vec<String> = self.0.iter().map(|a| a.to_string()).collect();
+        write!(f, "{}", s.join(", "))
+    }
+}
+
+impl Setter for PasswordAlgorithms {
+    // add_to adds PASSWORD-ALGORITHMS attribute to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        let mut v = Vec::with_capacity(PASSWORD_ALGORITHM_HEADER_SIZE * self.0.len());
+        for a in &self.0 {
+            a.encode(&mut v);
+        }
+        m.add(ATTR_PASSWORD_ALGORITHMS, &v);
+        Ok(())
+    }
+}
+
+impl Getter for PasswordAlgorithms {
+    // get_from decodes PASSWORD-ALGORITHMS attribute from message.
+    fn get_from(&mut self, m: &Message) -> Result<()> {
+        let v = m.get(ATTR_PASSWORD_ALGORITHMS)?;
+        self.0.clear();
+        let mut first = 0;
+        while first < v.len() {
+            let (algorithm, size) = PasswordAlgorithm::decode(&v[first..])?;
+            self.0.push(algorithm);
+            first += size;
+        }
+        Ok(())
+    }
+}
+
+impl PasswordAlgorithms {
+    // negotiate picks the first algorithm offered by the server that is
+    // supported, as the server lists them in order of preference. None means
+    // that MESSAGE-INTEGRITY with an MD5 key must be used.
+    pub fn negotiate(&self) -> Option<PasswordAlgorithm> {
+        self.0
+            .iter()
+            .find(|a| **a == PASSWORD_ALGORITHM_SHA256 || **a == PASSWORD_ALGORITHM_MD5)
+            .copied()
+    }
+}
+
+// negotiate_password_algorithm returns the password algorithm to use with a
+// server, given its 401 (Unauthenticated) or 438 (Stale Nonce) response. It
+// returns None if the server did not offer PASSWORD-ALGORITHMS or none of
+// them is supported, in which case the client falls back to
+// MESSAGE-INTEGRITY (HMAC-SHA1) with an MD5 key.
+//
+// RFC 8489 Section 9.2.4
+pub fn negotiate_password_algorithm(m: &Message) -> Option<PasswordAlgorithm> {
+    let mut algorithms = PasswordAlgorithms::default();
+    if algorithms.get_from(m).is_err() {
+        return None;
+    }
+    algorithms.negotiate()
+}
@@ -0,0 +1,99 @@
+use super::*;
+
+#[test]
+fn test_password_algorithms_add_to_get_from() -> Result<()> {
+    let mut m = Message::new();
+    m.write_header();
+    let algorithms = PasswordAlgorithms(vec![PASSWORD_ALGORITHM_SHA256, PASSWORD_ALGORITHM_MD5]);
+    algorithms.add_to(&mut m)?;
+    m.write_header();
+
+    let mut decoded = Message::new();
+    decoded.write(&m.raw)?;
+    let mut got = PasswordAlgorithms::default();
+    got.get_from(&decoded)?;
+    assert_eq!(got, algorithms);
+    assert_eq!(got.to_string(), "SHA-256, MD5");
+
+    Ok(())
+}
+
+#[test]
+fn test_password_algorithm_add_to_get_from() -> Result<()> {
+    let mut m = Message::new();
+    m.write_header();
+    PASSWORD_ALGORITHM_SHA256.add_to(&mut m)?;
+
+    let mut got = PasswordAlgorithm::default();
+    assert_eq!(got, PASSWORD_ALGORITHM_MD5);
+    got.get_from(&m)?;
+    assert_eq!(got, PASSWORD_ALGORITHM_SHA256);
+
+    Ok(())
+}
+
+#[test]
+fn test_password_algorithms_skip_parameters() -> Result<()> {
+    let mut m = Message::new();
+    m.write_header();
+    // An unknown algorithm with 3 bytes of parameters, padded to 4.
+    m.add(
+        ATTR_PASSWORD_ALGORITHMS,
+        &[0x00, 0x07, 0x00, 0x03, 1, 2, 3, 0, 0x00, 0x02, 0x00, 0x00],
+    );
+
+    let mut got = PasswordAlgorithms::default();
+    got.get_from(&m)?;
+    assert_eq!(
+        got.0,
+        vec![PasswordAlgorithm(0x0007), PASSWORD_ALGORITHM_SHA256]
+    );
+    assert_eq!(got.negotiate(), Some(PASSWORD_ALGORITHM_SHA256));
+
+    m.add(ATTR_PASSWORD_ALGORITHM, &[0x00, 0x02, 0x00]);
+    let mut algorithm = PasswordAlgorithm::default();
+    assert_eq!(algorithm.get_from(&m), Err(Error::ErrUnexpectedEof));
+
+    Ok(())
+}
+
+#[test]
+fn test_negotiate_password_algorithm() -> Result<()> {
+    let mut m = Message::new();
+    m.write_header();
+    assert_eq!(
+        negotiate_password_algorithm(&m),
+        None,
+        "should fall back without PASSWORD-ALGORITHMS"
+    );
+
+    PasswordAlgorithms(vec![PasswordAlgorithm(0x0007), PASSWORD_ALGORITHM_MD5]).add_to(&mut m)?;
+    assert_eq!(
+        negotiate_password_algorithm(&m),
+        Some(PASSWORD_ALGORITHM_MD5)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_password_algorithm_long_term_key() -> Result<()> {
+    let md5 = PASSWORD_ALGORITHM_MD5.long_term_key("user", "realm", "pass")?;
+    assert_eq!(
+        md5,
+        vec![
+            0x84, 0x93, 0xfb, 0xc5, 0x3b, 0xa5, 0x82, 0xfb, 0x4c, 0x04, 0x4c, 0x45, 0x6b, 0xdc,
+            0x40, 0xeb,
+        ]
+    );
+
+    let sha256 = PASSWORD_ALGORITHM_SHA256.long_term_key("user", "realm", "pass")?;
+    assert_eq!(sha256.len(), 32);
+
+    assert_eq!(
+        PasswordAlgorithm(0x0007).long_term_key("user", "realm", "pass"),
+        Err(Error::ErrUnsupportedPasswordAlgorithm)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,341 @@
+#[cfg(test)]
+mod server_test;
+
+use crate::attributes::*;
+use crate::error::*;
+use crate::error_code::*;
+use crate::fingerprint::*;
+use crate::integrity::*;
+use crate::message::*;
+use crate::textattrs::*;
+use crate::xoraddr::*;
+
+use util::Conn;
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::{Duration, Instant};
+
+const INBOUND_MTU: usize = 1500;
+const NONCE_LIFETIME: Duration = Duration::from_secs(600);
+// Upper bound of outstanding nonces, as every unauthenticated request creates one.
+const MAX_NONCES: usize = 1024;
+
+/// AuthHandler looks up the long-term credential key of a user, i.e.
+/// MD5(username ":" realm ":" password) as returned by
+/// MessageIntegrity::new_long_term_integrity.
+pub trait AuthHandler {
+    fn auth_handle(&self, username: &str, realm: &str, src_addr: SocketAddr) -> Result<Vec<u8>>;
+}
+
+/// RateLimit limits how many requests are answered per source IP address.
+/// Requests over the limit are silently dropped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_requests: u32,
+    pub interval: Duration,
+}
+
+/// ServerConfig configures a STUN Binding server.
+pub struct ServerConfig {
+    /// conn is the socket the server answers requests on.
+    pub conn: Arc<dyn Conn + Send + Sync>,
+
+    /// software is added as SOFTWARE attribute to every response if set.
+    pub software: Option<String>,
+
+    /// realm is the realm used for long-term credentials.
+    pub realm: String,
+
+    /// auth_handler enables the long-term credential mechanism of
+    /// RFC 5389 Section 10.2. If not set, requests are not authenticated.
+    pub auth_handler: Option<Arc<dyn AuthHandler + Send + Sync>>,
+
+    /// rate_limit enables per source IP rate limiting.
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// Server answers STUN Binding requests with the XOR-MAPPED-ADDRESS of the
+/// sender, as described in RFC 5389 Section 7.3.1.
+///
+/// Requests carrying an invalid FINGERPRINT are discarded. Indications and
+/// methods other than Binding are ignored.
+pub struct Server {
+    close_tx: Mutex<Option<mpsc::Sender<()>>>,
+}
+
+impl Server {
+    /// new creates the server and starts answering requests on config.conn.
+    pub async fn new(config: ServerConfig) -> Result<Self> {
+        let (close_tx, close_rx) = mpsc::channel(1);
+
+        let handler = RequestHandler {
+            conn: Arc::clone(&config.conn),
+            software: config.software,
+            realm: config.realm,
+            auth_handler: config.auth_handler,
+            rate_limit: config.rate_limit,
+            nonces: HashMap::new(),
+            rates: HashMap::new(),
+            last_prune: Instant::now(),
+        };
+
+        tokio::spawn(Server::read_loop(config.conn, handler, close_rx));
+
+        Ok(Server {
+            close_tx: Mutex::new(Some(close_tx)),
+        })
+    }
+
+    /// close stops the server. The conn is closed as well.
+    pub async fn close(&self) -> Result<()> {
+        let mut close_tx = self.close_tx.lock().await;
+        if close_tx.take().is_none() {
+            return Err(Error::ErrServerClosed);
+        }
+        Ok(())
+    }
+
+    async fn read_loop(
+        conn: Arc<dyn Conn + Send + Sync>,
+        mut handler: RequestHandler,
+        mut close_rx: mpsc::Receiver<()>,
+    ) {
+        let mut buf = vec![0u8; INBOUND_MTU];
+
+        loop {
+            let (n, src_addr) = tokio::select! {
+                _ = close_rx.recv() => break,
+                result = conn.recv_from(&mut buf) => match result {
+                    Ok(v) => v,
+                    Err(_) => break,
+                },
+            };
+
+            let _ = handler.handle(&buf[..n], src_addr).await;
+        }
+
+        let _ = conn.close().await;
+    }
+}
+
+struct RequestHandler {
+    conn: Arc<dyn Conn + Send + Sync>,
+    software: Option<String>,
+    realm: String,
+    auth_handler: Option<Arc<dyn AuthHandler + Send + Sync>>,
+    rate_limit: Option<RateLimit>,
+    nonces: HashMap<String, Instant>,
+    rates: HashMap<IpAddr, (Instant, u32)>,
+    last_prune: Instant,
+}
+
+impl RequestHandler {
+    async fn handle(&mut self, buf: &[u8], src_addr: SocketAddr) -> Result<()> {
+        if !is_message(buf) {
+            return Ok(());
+        }
+
+        let mut m = Message::new();
+        m.write(buf)?;
+
+        if m.typ != BINDING_REQUEST {
+            return Ok(());
+        }
+
+        // RFC 5389 Section 7.3: a message with an invalid FINGERPRINT is discarded.
+        if m.contains(ATTR_FINGERPRINT) {
+            FINGERPRINT.check(&m)?;
+        }
+
+        if !self.allow(src_addr.ip()) {
+            return Ok(());
+        }
+
+        // Authenticate before collecting the setters: they are not Send and
+        // must not be held across an await point.
+        let integrity = if self.auth_handler.is_some() {
+            match self.authenticate(&mut m, src_addr).await? {
+                Some(integrity) => Some(integrity),
+                None => return Ok(()),
+            }
+        } else {
+            None
+        };
+
+        let msg = {
+            let mut setters: Vec<Box<dyn Setter>> = vec![
+                Box::new(m.clone()),
+                Box::new(BINDING_SUCCESS),
+                Box::new(XorMappedAddress {
+                    ip: src_addr.ip(),
+                    port: src_addr.port(),
+                }),
+            ];
+            if let Some(software) = &self.software {
+                setters.push(Box::new(Software::new(ATTR_SOFTWARE, software.clone())));
+            }
+            if let Some(integrity) = integrity {
+                setters.push(Box::new(integrity));
+            }
+            setters.push(Box::new(FINGERPRINT));
+
+            build_message(&setters)?
+        };
+        self.send(&msg, src_addr).await
+    }
+
+    // allow returns false if src_ip has exceeded the rate limit.
+    fn allow(&mut self, src_ip: IpAddr) -> bool {
+        let rate_limit = match self.rate_limit {
+            Some(rate_limit) => rate_limit,
+            None => return true,
+        };
+
+        let now = Instant::now();
+        if now.duration_since(self.last_prune) >= rate_limit.interval {
+            self.rates
+                .retain(|_, (start, _)| now.duration_since(*start) < rate_limit.interval);
+            self.last_prune = now;
+        }
+
+        let (start, count) = self.rates.entry(src_ip).or_insert((now, 0));
+        if now.duration_since(*start) >= rate_limit.interval {
+            *start = now;
+            *count = 0;
+        }
+
+        *count += 1;
+        *count <= rate_limit.max_requests
+    }
+
+    // authenticate implements the server side of RFC 5389 Section 10.2.2. It
+    // returns the MESSAGE-INTEGRITY to sign the response with, or None if an
+    // error response has been sent instead.
+    async fn authenticate(
+        &mut self,
+        m: &mut Message,
+        src_addr: SocketAddr,
+    ) -> Result<Option<MessageIntegrity>> {
+        if !m.contains(ATTR_MESSAGE_INTEGRITY) {
+            self.respond_with_nonce(m, CODE_UNAUTHORIZED, src_addr)
+                .await?;
+            return Ok(None);
+        }
+
+        let mut nonce = Nonce::new(ATTR_NONCE, String::new());
+        let mut username = Username::new(ATTR_USERNAME, String::new());
+        let mut realm = Realm::new(ATTR_REALM, String::new());
+        if nonce.get_from(m).is_err() || username.get_from(m).is_err() || realm.get_from(m).is_err()
+        {
+            return self
+                .respond_with_error(m, CODE_BAD_REQUEST, src_addr)
+                .await
+                .map(|_| None);
+        }
+
+        self.prune_nonces();
+        if !self.nonces.contains_key(&nonce.text) {
+            self.respond_with_nonce(m, CODE_STALE_NONCE, src_addr)
+                .await?;
+            return Ok(None);
+        }
+
+        let key = match self
+            .auth_handler
+            .as_ref()
+            .map(|h| h.auth_handle(&username.text, &realm.text, src_addr))
+        {
+            Some(Ok(key)) => key,
+            _ => {
+                return self
+                    .respond_with_error(m, CODE_UNAUTHORIZED, src_addr)
+                    .await
+                    .map(|_| None)
+            }
+        };
+
+        let integrity = MessageIntegrity(key);
+        if integrity.check(m).is_err() {
+            return self
+                .respond_with_error(m, CODE_UNAUTHORIZED, src_addr)
+                .await
+                .map(|_| None);
+        }
+
+        Ok(Some(integrity))
+    }
+
+    async fn respond_with_nonce(
+        &mut self,
+        m: &Message,
+        code: ErrorCode,
+        src_addr: SocketAddr,
+    ) -> Result<()> {
+        self.prune_nonces();
+        if self.nonces.len() >= MAX_NONCES {
+            let oldest = self
+                .nonces
+                .iter()
+                .min_by_key(|(_, created)| **created)
+                .map(|(nonce, _)| nonce.clone());
+            if let Some(oldest) = oldest {
+                self.nonces.remove(&oldest);
+            }
+        }
+
+        let nonce = format!("{:032x}", rand::random::<u128>());
+        self.nonces.insert(nonce.clone(), Instant::now());
+
+        let msg = build_message(&[
+            Box::new(m.clone()),
+            Box::new(MessageType::new(METHOD_BINDING, CLASS_ERROR_RESPONSE)),
+            Box::new(ErrorCodeAttribute {
+                code,
+                reason: vec![],
+            }),
+            Box::new(Nonce::new(ATTR_NONCE, nonce)),
+            Box::new(Realm::new(ATTR_REALM, self.realm.clone())),
+            Box::new(FINGERPRINT),
+        ])?;
+        self.send(&msg, src_addr).await
+    }
+
+    // prune_nonces forgets the nonces which have expired.
+    fn prune_nonces(&mut self) {
+        let now = Instant::now();
+        self.nonces
+            .retain(|_, created| now.duration_since(*created) < NONCE_LIFETIME);
+    }
+
+    async fn respond_with_error(
+        &self,
+        m: &Message,
+        code: ErrorCode,
+        src_addr: SocketAddr,
+    ) -> Result<()> {
+        let msg = build_message(&[
+            Box::new(m.clone()),
+            Box::new(MessageType::new(METHOD_BINDING, CLASS_ERROR_RESPONSE)),
+            Box::new(ErrorCodeAttribute {
+                code,
+                reason: vec![],
+            }),
+            Box::new(FINGERPRINT),
+        ])?;
+        self.send(&msg, src_addr).await
+    }
+
+    async fn send(&self, msg: &Message, dst: SocketAddr) -> Result<()> {
+        self.conn.send_to(&msg.raw, dst).await?;
+        Ok(())
+    }
+}
+
+fn build_message(setters: &[Box<dyn Setter>]) -> Result<Message> {
+    let mut msg = Message::new();
+    msg.build(setters)?;
+    Ok(msg)
+}
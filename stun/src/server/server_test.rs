@@ -0,0 +1,224 @@
+use super::*;
+use crate::agent::*;
+
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+const USERNAME: &str = "user";
+const REALM: &str = "webrtc.rs";
+const PASSWORD: &str = "pass";
+
+struct TestAuthHandler;
+
+impl AuthHandler for TestAuthHandler {
+    fn auth_handle(&self, username: &str, realm: &str, _src_addr: SocketAddr) -> Result<Vec<u8>> {
+        if username != USERNAME {
+            return Err(Error::Other("no such user".to_owned()));
+        }
+        Ok(MessageIntegrity::new_long_term_integrity(
+            username.to_owned(),
+            realm.to_owned(),
+            PASSWORD.to_owned(),
+        )
+        .0)
+    }
+}
+
+async fn new_server(
+    auth_handler: Option<Arc<dyn AuthHandler + Send + Sync>>,
+    rate_limit: Option<RateLimit>,
+) -> Result<(Server, SocketAddr)> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let addr = conn.local_addr()?;
+    let server = Server::new(ServerConfig {
+        conn,
+        software: Some("test".to_owned()),
+        realm: REALM.to_owned(),
+        auth_handler,
+        rate_limit,
+    })
+    .await?;
+    Ok((server, addr))
+}
+
+async fn round_trip(
+    conn: &UdpSocket,
+    req: &Message,
+    server: SocketAddr,
+) -> Result<Option<Message>> {
+    conn.send_to(&req.raw, server).await?;
+
+    let mut buf = vec![0u8; 1500];
+    match timeout(Duration::from_millis(200), conn.recv_from(&mut buf)).await {
+        Ok(result) => {
+            let (n, _) = result?;
+            let mut resp = Message::new();
+            resp.write(&buf[..n])?;
+            Ok(Some(resp))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+fn new_binding_request(extra: Vec<Box<dyn Setter>>) -> Result<Message> {
+    let mut setters: Vec<Box<dyn Setter>> =
+        vec![Box::new(TransactionId::new()), Box::new(BINDING_REQUEST)];
+    setters.extend(extra);
+    setters.push(Box::new(FINGERPRINT));
+
+    let mut m = Message::new();
+    m.build(&setters)?;
+    Ok(m)
+}
+
+#[tokio::test]
+async fn test_server_binding() -> Result<()> {
+    let (server, server_addr) = new_server(None, None).await?;
+    let conn = UdpSocket::bind("127.0.0.1:0").await?;
+
+    let req = new_binding_request(vec![])?;
+    let resp = round_trip(&conn, &req, server_addr)
+        .await?
+        .expect("no response");
+
+    assert_eq!(resp.typ, BINDING_SUCCESS);
+    assert_eq!(resp.transaction_id, req.transaction_id);
+    FINGERPRINT.check(&resp)?;
+
+    let mut xor_addr = XorMappedAddress::default();
+    xor_addr.get_from(&resp)?;
+    assert_eq!(
+        SocketAddr::new(xor_addr.ip, xor_addr.port),
+        conn.local_addr()?
+    );
+
+    let mut software = Software::new(ATTR_SOFTWARE, String::new());
+    software.get_from(&resp)?;
+    assert_eq!(software.text, "test");
+
+    server.close().await?;
+    assert_eq!(server.close().await, Err(Error::ErrServerClosed));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_discards_bad_fingerprint() -> Result<()> {
+    let (server, server_addr) = new_server(None, None).await?;
+    let conn = UdpSocket::bind("127.0.0.1:0").await?;
+
+    let mut req = new_binding_request(vec![])?;
+    let last = req.raw.len() - 1;
+    req.raw[last] ^= 0xff;
+
+    assert!(round_trip(&conn, &req, server_addr).await?.is_none());
+
+    server.close().await
+}
+
+#[tokio::test]
+async fn test_server_long_term_auth() -> Result<()> {
+    let (server, server_addr) = new_server(Some(Arc::new(TestAuthHandler)), None).await?;
+    let conn = UdpSocket::bind("127.0.0.1:0").await?;
+
+    // The first request is challenged.
+    let req = new_binding_request(vec![])?;
+    let resp = round_trip(&conn, &req, server_addr)
+        .await?
+        .expect("no response");
+    assert_eq!(resp.typ.class, CLASS_ERROR_RESPONSE);
+
+    let mut code = ErrorCodeAttribute::default();
+    code.get_from(&resp)?;
+    assert_eq!(code.code, CODE_UNAUTHORIZED);
+
+    let mut nonce = Nonce::new(ATTR_NONCE, String::new());
+    nonce.get_from(&resp)?;
+    let mut realm = Realm::new(ATTR_REALM, String::new());
+    realm.get_from(&resp)?;
+    assert_eq!(realm.text, REALM);
+
+    // Wrong password.
+    let req = new_binding_request(vec![
+        Box::new(Username::new(ATTR_USERNAME, USERNAME.to_owned())),
+        Box::new(realm.clone()),
+        Box::new(nonce.clone()),
+        Box::new(MessageIntegrity::new_long_term_integrity(
+            USERNAME.to_owned(),
+            REALM.to_owned(),
+            "wrong".to_owned(),
+        )),
+    ])?;
+    let resp = round_trip(&conn, &req, server_addr)
+        .await?
+        .expect("no response");
+    assert_eq!(resp.typ.class, CLASS_ERROR_RESPONSE);
+
+    // Valid credentials.
+    let integrity = MessageIntegrity::new_long_term_integrity(
+        USERNAME.to_owned(),
+        REALM.to_owned(),
+        PASSWORD.to_owned(),
+    );
+    let req = new_binding_request(vec![
+        Box::new(Username::new(ATTR_USERNAME, USERNAME.to_owned())),
+        Box::new(realm),
+        Box::new(nonce),
+        Box::new(integrity.clone()),
+    ])?;
+    let mut resp = round_trip(&conn, &req, server_addr)
+        .await?
+        .expect("no response");
+    assert_eq!(resp.typ, BINDING_SUCCESS);
+    integrity.check(&mut resp)?;
+
+    server.close().await
+}
+
+#[tokio::test]
+async fn test_server_rate_limit() -> Result<()> {
+    let (server, server_addr) = new_server(
+        None,
+        Some(RateLimit {
+            max_requests: 2,
+            interval: Duration::from_secs(10),
+        }),
+    )
+    .await?;
+    let conn = UdpSocket::bind("127.0.0.1:0").await?;
+
+    for _ in 0..2 {
+        let req = new_binding_request(vec![])?;
+        assert!(round_trip(&conn, &req, server_addr).await?.is_some());
+    }
+
+    let req = new_binding_request(vec![])?;
+    assert!(round_trip(&conn, &req, server_addr).await?.is_none());
+
+    server.close().await
+}
+
+#[tokio::test]
+async fn test_server_nonces_are_bounded() -> Result<()> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = UdpSocket::bind("127.0.0.1:0").await?;
+    let mut handler = RequestHandler {
+        conn,
+        software: None,
+        realm: REALM.to_owned(),
+        auth_handler: Some(Arc::new(TestAuthHandler)),
+        rate_limit: None,
+        nonces: HashMap::new(),
+        rates: HashMap::new(),
+        last_prune: Instant::now(),
+    };
+
+    // Every unauthenticated request is challenged with a fresh nonce.
+    for _ in 0..MAX_NONCES + 10 {
+        let req = new_binding_request(vec![])?;
+        handler.handle(&req.raw, client.local_addr()?).await?;
+    }
+    assert_eq!(handler.nonces.len(), MAX_NONCES);
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+#[cfg(test)]
+mod stream_test;
+
+use crate::error::*;
+#[cfg(feature = "tls")]
+use crate::uri::*;
+
+use util::Conn;
+
+use async_trait::async_trait;
+use std::io;
+use std::net::SocketAddr;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::sync::Mutex;
+
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+#[cfg(feature = "tls")]
+use tokio::net::TcpStream;
+#[cfg(feature = "tls")]
+use tokio_rustls::{client::TlsStream, rustls, webpki, TlsConnector};
+
+// FRAME_HEADER_SIZE is the size of the length prefix defined in RFC 4571 Section 2.
+const FRAME_HEADER_SIZE: usize = 2;
+const MAX_FRAME_SIZE: usize = u16::MAX as usize;
+
+/// StreamConn adapts a connection-oriented byte stream (e.g. TCP or TLS) to
+/// the datagram-like Conn interface used by the Client, framing every message
+/// with a 16-bit length prefix as described in RFC 4571 Section 2.
+///
+/// The transport handles retransmissions, so clients using a StreamConn should
+/// be built with ClientBuilder::with_no_retransmit.
+pub struct StreamConn<S> {
+    reader: Mutex<ReadHalf<S>>,
+    writer: Mutex<WriteHalf<S>>,
+    local_addr: SocketAddr,
+    remote_addr: SocketAddr,
+}
+
+impl<S: AsyncRead + AsyncWrite> StreamConn<S> {
+    pub fn new(stream: S, local_addr: SocketAddr, remote_addr: SocketAddr) -> Self {
+        let (reader, writer) = tokio::io::split(stream);
+        StreamConn {
+            reader: Mutex::new(reader),
+            writer: Mutex::new(writer),
+            local_addr,
+            remote_addr,
+        }
+    }
+}
+
+#[async_trait]
+impl<S: AsyncRead + AsyncWrite + Send + Sync> Conn for StreamConn<S> {
+    async fn connect(&self, _addr: SocketAddr) -> std::result::Result<(), util::Error> {
+        Err(io::Error::new(io::ErrorKind::Other, "Not applicable").into())
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> std::result::Result<usize, util::Error> {
+        let mut reader = self.reader.lock().await;
+
+        let mut header = [0u8; FRAME_HEADER_SIZE];
+        reader.read_exact(&mut header).await?;
+        let n = u16::from_be_bytes(header) as usize;
+
+        if buf.len() < n {
+            // Drain the frame so the stream stays in sync.
+            let mut frame = vec![0u8; n];
+            reader.read_exact(&mut frame).await?;
+            return Err(util::Error::ErrBufferShort);
+        }
+
+        reader.read_exact(&mut buf[..n]).await?;
+        Ok(n)
+    }
+
+    async fn recv_from(
+        &self,
+        buf: &mut [u8],
+    ) -> std::result::Result<(usize, SocketAddr), util::Error> {
+        let n = self.recv(buf).await?;
+        Ok((n, self.remote_addr))
+    }
+
+    async fn send(&self, buf: &[u8]) -> std::result::Result<usize, util::Error> {
+        if buf.len() > MAX_FRAME_SIZE {
+            return Err(util::Error::Other(Error::ErrFrameTooLarge.to_string()));
+        }
+
+        let mut frame = Vec::with_capacity(FRAME_HEADER_SIZE + buf.len());
+        frame.extend_from_slice(&(buf.len() as u16).to_be_bytes());
+        frame.extend_from_slice(buf);
+
+        let mut writer = self.writer.lock().await;
+        writer.write_all(&frame).await?;
+        writer.flush().await?;
+        Ok(buf.len())
+    }
+
+    async fn send_to(
+        &self,
+        buf: &[u8],
+        _target: SocketAddr,
+    ) -> std::result::Result<usize, util::Error> {
+        self.send(buf).await
+    }
+
+    fn local_addr(&self) -> std::result::Result<SocketAddr, util::Error> {
+        Ok(self.local_addr)
+    }
+
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.remote_addr)
+    }
+
+    async fn close(&self) -> std::result::Result<(), util::Error> {
+        let mut writer = self.writer.lock().await;
+        writer.shutdown().await?;
+        Ok(())
+    }
+}
+
+/// default_tls_connector returns a TlsConnector trusting the Mozilla root
+/// certificates shipped with webpki-roots.
+#[cfg(feature = "tls")]
+pub fn default_tls_connector() -> TlsConnector {
+    let mut config = rustls::ClientConfig::new();
+    config
+        .root_store
+        .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+    TlsConnector::from(Arc::new(config))
+}
+
+/// dial_tls connects to the STUN server referenced by a `stuns:` URI over TLS,
+/// using DEFAULT_TLS_PORT when the URI has no port. The connector controls
+/// certificate verification, ALPN, client authentication and so on.
+#[cfg(feature = "tls")]
+pub async fn dial_tls(
+    uri: &Uri,
+    connector: &TlsConnector,
+) -> Result<StreamConn<TlsStream<TcpStream>>> {
+    if uri.scheme != SCHEME_SECURE {
+        return Err(Error::ErrSchemeType);
+    }
+
+    let port = uri.port.unwrap_or(crate::DEFAULT_TLS_PORT);
+    let tcp = TcpStream::connect((uri.host.as_str(), port)).await?;
+    let local_addr = tcp.local_addr()?;
+    let remote_addr = tcp.peer_addr()?;
+
+    let domain = webpki::DNSNameRef::try_from_ascii_str(&uri.host).map_err(|_| Error::ErrHost)?;
+    let stream = connector.connect(domain, tcp).await?;
+
+    Ok(StreamConn::new(stream, local_addr, remote_addr))
+}
@@ -0,0 +1,69 @@
+use super::*;
+use crate::agent::TransactionId;
+use crate::message::*;
+
+fn new_pair() -> (
+    StreamConn<tokio::io::DuplexStream>,
+    StreamConn<tokio::io::DuplexStream>,
+) {
+    let (a, b) = tokio::io::duplex(1024);
+    let addr_a: SocketAddr = "127.0.0.1:1".parse().unwrap();
+    let addr_b: SocketAddr = "127.0.0.1:2".parse().unwrap();
+    (
+        StreamConn::new(a, addr_a, addr_b),
+        StreamConn::new(b, addr_b, addr_a),
+    )
+}
+
+#[tokio::test]
+async fn test_stream_conn_framing() -> Result<()> {
+    let (a, b) = new_pair();
+
+    let mut m = Message::new();
+    m.build(&[Box::new(TransactionId::new()), Box::new(BINDING_REQUEST)])?;
+
+    a.send(&m.raw).await?;
+    a.send(&m.raw).await?;
+
+    let mut buf = vec![0u8; 1024];
+    for _ in 0..2 {
+        let n = b.recv(&mut buf).await?;
+        assert_eq!(&buf[..n], &m.raw[..]);
+
+        let mut decoded = Message::new();
+        decoded.write(&buf[..n])?;
+        assert_eq!(decoded.transaction_id, m.transaction_id);
+    }
+
+    assert_eq!(b.remote_addr(), Some("127.0.0.1:1".parse().unwrap()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stream_conn_short_buffer() -> Result<()> {
+    let (a, b) = new_pair();
+
+    a.send(&[1u8; 64]).await?;
+    a.send(&[2u8; 8]).await?;
+
+    let mut buf = vec![0u8; 16];
+    let result = b.recv(&mut buf).await;
+    assert_eq!(result, Err(util::Error::ErrBufferShort));
+
+    // The oversized frame is skipped and the next one is still readable.
+    let n = b.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], &[2u8; 8]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stream_conn_frame_too_large() -> Result<()> {
+    let (a, _b) = new_pair();
+
+    let result = a.send(&vec![0u8; MAX_FRAME_SIZE + 1]).await;
+    assert!(result.is_err());
+
+    Ok(())
+}
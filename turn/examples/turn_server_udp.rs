@@ -1,11 +1,15 @@
 use turn::auth::*;
+use turn::relay::relay_pool::*;
+use turn::relay::relay_range::*;
 use turn::relay::relay_static::*;
+use turn::relay::RelayAddressGenerator;
 use turn::server::{config::*, *};
 use turn::Error;
 
+use async_trait::async_trait;
 use clap::{App, AppSettings, Arg};
 use std::collections::HashMap;
-use std::net::{IpAddr, SocketAddr};
+use std::net::IpAddr;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::net::UdpSocket;
@@ -23,14 +27,10 @@ impl MyAuthHandler {
     }
 }
 
+#[async_trait]
 impl AuthHandler for MyAuthHandler {
-    fn auth_handle(
-        &self,
-        username: &str,
-        _realm: &str,
-        _src_addr: SocketAddr,
-    ) -> Result<Vec<u8>, Error> {
-        if let Some(pw) = self.cred_map.get(username) {
+    async fn auth_handle(&self, ctx: &AuthContext) -> Result<Vec<u8>, Error> {
+        if let Some(pw) = self.cred_map.get(&ctx.username) {
             //log::debug!("username={}, password={:?}", username, pw);
             Ok(pw.to_vec())
         } else {
@@ -61,7 +61,7 @@ async fn main() -> Result<(), Error> {
                 .required_unless("FULLHELP")
                 .takes_value(true)
                 .long("public-ip")
-                .help("IP Address that TURN can be contacted by."),
+                .help("IP Addresses that TURN can be contacted by, relays are spread over them (e.g. \"1.2.3.4,5.6.7.8\")."),
         )
         .arg(
             Arg::with_name("users")
@@ -83,6 +83,20 @@ async fn main() -> Result<(), Error> {
                 .default_value("3478")
                 .long("port")
                 .help("Listening port."),
+        )
+        .arg(
+            Arg::with_name("min-port")
+                .takes_value(true)
+                .long("min-port")
+                .requires("max-port")
+                .help("Lowest port relays may be allocated on."),
+        )
+        .arg(
+            Arg::with_name("max-port")
+                .takes_value(true)
+                .long("max-port")
+                .requires("min-port")
+                .help("Highest port relays may be allocated on."),
         );
 
     let matches = app.clone().get_matches();
@@ -92,7 +106,7 @@ async fn main() -> Result<(), Error> {
         std::process::exit(0);
     }
 
-    let public_ip = matches.value_of("public-ip").unwrap();
+    let public_ips = matches.value_of("public-ip").unwrap();
     let port = matches.value_of("port").unwrap();
     let users = matches.value_of("users").unwrap();
     let realm = matches.value_of("realm").unwrap();
@@ -113,18 +127,45 @@ async fn main() -> Result<(), Error> {
     let conn = Arc::new(UdpSocket::bind(format!("0.0.0.0:{}", port)).await?);
     println!("listening {}...", conn.local_addr()?);
 
-    let server = Server::new(ServerConfig {
-        conn_configs: vec![ConnConfig {
-            conn,
-            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
-                relay_address: IpAddr::from_str(public_ip)?,
+    // Relays are allocated on arbitrary ports unless a port range is given,
+    // e.g. the one the firewall lets through
+    let port_range = match (matches.value_of("min-port"), matches.value_of("max-port")) {
+        (Some(min_port), Some(max_port)) => {
+            Some((min_port.parse::<u16>()?, max_port.parse::<u16>()?))
+        }
+        _ => None,
+    };
+    let mut generators: Vec<Box<dyn RelayAddressGenerator + Send + Sync>> = vec![];
+    for public_ip in public_ips.split(',') {
+        let relay_address = IpAddr::from_str(public_ip)?;
+        let generator: Box<dyn RelayAddressGenerator + Send + Sync> = match port_range {
+            Some((min_port, max_port)) => Box::new(RelayAddressGeneratorRanges {
+                relay_address,
+                min_port,
+                max_port,
+                max_retries: 0,
                 address: "0.0.0.0".to_owned(),
                 net: Arc::new(Net::new(None)),
             }),
+            None => Box::new(RelayAddressGeneratorStatic {
+                relay_address,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(Net::new(None)),
+            }),
+        };
+        generators.push(generator);
+    }
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorPool::new(generators)),
         }],
+        listener_configs: vec![],
         realm: realm.to_owned(),
         auth_handler: Arc::new(MyAuthHandler::new(cred_map)),
         channel_bind_timeout: Duration::from_secs(0),
+        event_handler: None,
     })
     .await?;
 
@@ -3,7 +3,11 @@ mod allocation_manager_test;
 
 use super::*;
 use crate::error::*;
+use crate::proto::connid::ConnectionId;
+use crate::proto::mobility::MobilityTicket;
+use crate::proto::reqfamily::*;
 use crate::relay::*;
+use crate::server::events::*;
 
 use futures::future;
 use std::collections::HashMap;
@@ -13,6 +17,9 @@ use util::Conn;
 // ManagerConfig a bag of config params for Manager.
 pub struct ManagerConfig {
     pub relay_addr_generator: Box<dyn RelayAddressGenerator + Send + Sync>,
+
+    // events are told about the allocations of the manager
+    pub events: Arc<Events>,
 }
 
 // Manager is used to hold active allocations
@@ -20,6 +27,7 @@ pub struct Manager {
     allocations: AllocationMap,
     reservations: Arc<Mutex<HashMap<String, u16>>>,
     relay_addr_generator: Box<dyn RelayAddressGenerator + Send + Sync>,
+    events: Arc<Events>,
 }
 
 impl Manager {
@@ -29,6 +37,7 @@ impl Manager {
             allocations: Arc::new(Mutex::new(HashMap::new())),
             reservations: Arc::new(Mutex::new(HashMap::new())),
             relay_addr_generator: config.relay_addr_generator,
+            events: config.events,
         }
     }
 
@@ -36,7 +45,8 @@ impl Manager {
     pub async fn close(&self) -> Result<()> {
         let allocations = self.allocations.lock().await;
         for a in allocations.values() {
-            a.close().await?;
+            a.close_with_reason(AllocationCloseReason::ServerClosed)
+                .await?;
         }
         Ok(())
     }
@@ -53,13 +63,15 @@ impl Manager {
 
         guarded.iter().for_each(|(five_tuple, alloc)| {
             if five_tuples.is_none() || five_tuples.as_ref().unwrap().contains(five_tuple) {
+                #[cfg(feature = "metrics")]
+                let relayed_bytes = alloc.relayed.get().to_peers as usize;
                 infos.insert(
                     *five_tuple,
                     AllocationInfo::new(
                         *five_tuple,
                         alloc.username.text.clone(),
                         #[cfg(feature = "metrics")]
-                        alloc.relayed_bytes.load(Ordering::Acquire),
+                        relayed_bytes,
                     ),
                 );
             }
@@ -74,12 +86,14 @@ impl Manager {
         allocations.get(five_tuple).map(Arc::clone)
     }
 
-    // create_allocation creates a new allocation and starts relaying
+    // create_allocation creates a new allocation with a relayed transport
+    // address of the requested family and starts relaying
     pub async fn create_allocation(
         &self,
         five_tuple: FiveTuple,
         turn_socket: Arc<dyn Conn + Send + Sync>,
         requested_port: u16,
+        requested_family: RequestedAddressFamily,
         lifetime: Duration,
         username: Username,
     ) -> Result<Arc<Allocation>> {
@@ -93,10 +107,11 @@ impl Manager {
 
         let (relay_socket, relay_addr) = self
             .relay_addr_generator
-            .allocate_conn(true, requested_port)
+            .allocate_conn(requested_family == REQUESTED_FAMILY_IPV4, requested_port)
             .await?;
         let mut a = Allocation::new(turn_socket, relay_socket, relay_addr, five_tuple, username);
         a.allocations = Some(Arc::clone(&self.allocations));
+        a.events = Arc::clone(&self.events);
 
         log::debug!("listening on relay addr: {:?}", a.relay_addr);
         a.start(lifetime).await;
@@ -107,6 +122,116 @@ impl Manager {
             let mut allocations = self.allocations.lock().await;
             allocations.insert(five_tuple, Arc::clone(&a));
         }
+        self.events
+            .allocation_created(&five_tuple, &a.username.text, a.relay_addr, a.protocol());
+
+        Ok(a)
+    }
+
+    // create_tcp_allocation creates a new TCP allocation as described in RFC 6062
+    // and starts accepting connections from peers
+    pub async fn create_tcp_allocation(
+        &self,
+        five_tuple: FiveTuple,
+        turn_socket: Arc<dyn Conn + Send + Sync>,
+        requested_family: RequestedAddressFamily,
+        lifetime: Duration,
+        username: Username,
+    ) -> Result<Arc<Allocation>> {
+        if lifetime == Duration::from_secs(0) {
+            return Err(Error::ErrLifetimeZero);
+        }
+
+        if self.get_allocation(&five_tuple).await.is_some() {
+            return Err(Error::ErrDupeFiveTuple);
+        }
+
+        let (listener, relay_addr) = self
+            .relay_addr_generator
+            .allocate_listener(requested_family == REQUESTED_FAMILY_IPV4, 0)
+            .await?;
+        let mut a = Allocation::new_tcp(turn_socket, listener, relay_addr, five_tuple, username)?;
+        a.allocations = Some(Arc::clone(&self.allocations));
+        a.events = Arc::clone(&self.events);
+
+        log::debug!("listening on TCP relay addr: {:?}", a.relay_addr);
+        a.start(lifetime).await;
+
+        let a = Arc::new(a);
+        {
+            let mut allocations = self.allocations.lock().await;
+            allocations.insert(five_tuple, Arc::clone(&a));
+        }
+        self.events
+            .allocation_created(&five_tuple, &a.username.text, a.relay_addr, a.protocol());
+
+        Ok(a)
+    }
+
+    // take_peer_connection removes the pending peer data connection with the
+    // given id from the TCP allocation of username
+    pub async fn take_peer_connection(
+        &self,
+        id: ConnectionId,
+        username: &str,
+    ) -> Option<PeerConnection> {
+        let allocations: Vec<Arc<Allocation>> = {
+            let allocations = self.allocations.lock().await;
+            allocations
+                .values()
+                .filter(|a| a.username.text == username)
+                .map(Arc::clone)
+                .collect()
+        };
+
+        for a in allocations {
+            if let Some(tcp_relay) = &a.tcp_relay {
+                if let Some(mut c) = tcp_relay.take(id).await {
+                    c.allocation = Arc::downgrade(&a);
+                    return Some(c);
+                }
+            }
+        }
+        None
+    }
+
+    // move_allocation ties the allocation the mobility ticket was issued for
+    // to five_tuple, the new 5-tuple of its client, as described in RFC 8016.
+    // The client must authenticate as the user the allocation belongs to and
+    // reach the server on the same listener.
+    pub async fn move_allocation(
+        &self,
+        ticket: &MobilityTicket,
+        five_tuple: FiveTuple,
+        username: &str,
+    ) -> Result<Arc<Allocation>> {
+        let mut allocations = self.allocations.lock().await;
+        if allocations.contains_key(&five_tuple) {
+            return Err(Error::ErrDupeFiveTuple);
+        }
+
+        let old_five_tuple = allocations
+            .iter()
+            .find(|(_, a)| a.has_mobility_ticket(ticket))
+            .map(|(old_five_tuple, _)| *old_five_tuple)
+            .ok_or(Error::ErrInvalidMobilityTicket)?;
+        if allocations[&old_five_tuple].username.text != username {
+            return Err(Error::ErrMobilityTicketWrongCredentials);
+        }
+        if old_five_tuple.protocol != five_tuple.protocol
+            || old_five_tuple.dst_addr != five_tuple.dst_addr
+        {
+            return Err(Error::ErrMobilityForbidden);
+        }
+
+        let a = match allocations.remove(&old_five_tuple) {
+            Some(a) => a,
+            None => return Err(Error::ErrInvalidMobilityTicket),
+        };
+        a.set_five_tuple(five_tuple);
+        allocations.insert(five_tuple, Arc::clone(&a));
+
+        log::debug!("allocation moved from {} to {}", old_five_tuple, five_tuple);
 
         Ok(a)
     }
@@ -177,9 +302,16 @@ impl Manager {
         reservations.get(reservation_token).copied()
     }
 
-    // get_random_even_port returns a random un-allocated udp4 port
-    pub async fn get_random_even_port(&self) -> Result<u16> {
-        let (_, addr) = self.relay_addr_generator.allocate_conn(true, 0).await?;
+    // get_random_even_port returns a random un-allocated udp port of the
+    // requested family
+    pub async fn get_random_even_port(
+        &self,
+        requested_family: RequestedAddressFamily,
+    ) -> Result<u16> {
+        let (_, addr) = self
+            .relay_addr_generator
+            .allocate_conn(requested_family == REQUESTED_FAMILY_IPV4, 0)
+            .await?;
         Ok(addr.port())
     }
 }
@@ -1,10 +1,12 @@
 use super::*;
 
 use crate::{
-    auth::{generate_auth_key, AuthHandler},
+    auth::{generate_auth_key, AuthContext, AuthHandler},
     client::{Client, ClientConfig},
     error::Result,
     proto::lifetime::DEFAULT_LIFETIME,
+    proto::mobility::MobilityTicket,
+    proto::reqfamily::REQUESTED_FAMILY_IPV4,
     relay::{relay_none::*, relay_static::RelayAddressGeneratorStatic},
     server::{
         config::{ConnConfig, ServerConfig},
@@ -12,6 +14,7 @@ use crate::{
     },
 };
 
+use async_trait::async_trait;
 use std::{
     net::{IpAddr, Ipv4Addr},
     str::FromStr,
@@ -26,6 +29,7 @@ fn new_test_manager() -> Manager {
             address: "0.0.0.0".to_owned(),
             net: Arc::new(Net::new(None)),
         }),
+        events: Arc::new(Events::default()),
     };
     Manager::new(config)
 }
@@ -73,6 +77,7 @@ async fn test_packet_handler() -> Result<()> {
             },
             Arc::new(turn_socket),
             0,
+            REQUESTED_FAMILY_IPV4,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
         )
@@ -94,7 +99,7 @@ async fn test_packet_handler() -> Result<()> {
         a.add_channel_bind(channel_bind.clone(), DEFAULT_LIFETIME)
             .await?;
 
-        a.relay_socket.local_addr()?.port()
+        a.relay_socket.as_ref().unwrap().local_addr()?.port()
     };
 
     let relay_addr_with_host_str = format!("127.0.0.1:{}", port);
@@ -178,6 +183,7 @@ async fn test_create_allocation_duplicate_five_tuple() -> Result<()> {
             five_tuple.clone(),
             Arc::clone(&turn_socket),
             0,
+            REQUESTED_FAMILY_IPV4,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
         )
@@ -188,6 +194,7 @@ async fn test_create_allocation_duplicate_five_tuple() -> Result<()> {
             five_tuple,
             Arc::clone(&turn_socket),
             0,
+            REQUESTED_FAMILY_IPV4,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
         )
@@ -213,6 +220,7 @@ async fn test_delete_allocation() -> Result<()> {
             five_tuple.clone(),
             Arc::clone(&turn_socket),
             0,
+            REQUESTED_FAMILY_IPV4,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
         )
@@ -234,6 +242,64 @@ async fn test_delete_allocation() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_move_allocation() -> Result<()> {
+    let turn_socket: Arc<dyn Conn + Send + Sync> = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+
+    let m = new_test_manager();
+
+    let five_tuple = random_five_tuple();
+    let a = m
+        .create_allocation(
+            five_tuple,
+            Arc::clone(&turn_socket),
+            0,
+            REQUESTED_FAMILY_IPV4,
+            DEFAULT_LIFETIME,
+            TextAttribute::new(ATTR_USERNAME, "user".into()),
+        )
+        .await?;
+    let ticket = a.new_mobility_ticket();
+
+    let new_five_tuple = FiveTuple {
+        src_addr: SocketAddr::new(Ipv4Addr::new(0, 0, 0, 1).into(), rand::random()),
+        ..five_tuple
+    };
+
+    let result = m
+        .move_allocation(&MobilityTicket(vec![1, 2, 3]), new_five_tuple, "user")
+        .await;
+    assert_eq!(result.err(), Some(Error::ErrInvalidMobilityTicket));
+
+    let result = m.move_allocation(&ticket, new_five_tuple, "other").await;
+    assert_eq!(result.err(), Some(Error::ErrMobilityTicketWrongCredentials));
+
+    let other_listener = FiveTuple {
+        dst_addr: SocketAddr::new(Ipv4Addr::new(0, 0, 0, 1).into(), rand::random()),
+        ..new_five_tuple
+    };
+    let result = m.move_allocation(&ticket, other_listener, "user").await;
+    assert_eq!(result.err(), Some(Error::ErrMobilityForbidden));
+
+    m.move_allocation(&ticket, new_five_tuple, "user").await?;
+    assert!(
+        m.get_allocation(&five_tuple).await.is_none(),
+        "allocation should not be found with its old 5-tuple"
+    );
+    let moved = m
+        .get_allocation(&new_five_tuple)
+        .await
+        .expect("allocation should be found with its new 5-tuple");
+    assert_eq!(moved.five_tuple(), new_five_tuple);
+
+    // A new ticket invalidates the previous one.
+    let _ = a.new_mobility_ticket();
+    let result = m.move_allocation(&ticket, five_tuple, "user").await;
+    assert_eq!(result.err(), Some(Error::ErrInvalidMobilityTicket));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_allocation_timeout() -> Result<()> {
     //env_logger::init();
@@ -254,6 +320,7 @@ async fn test_allocation_timeout() -> Result<()> {
                 five_tuple,
                 Arc::clone(&turn_socket),
                 0,
+                REQUESTED_FAMILY_IPV4,
                 lifetime,
                 TextAttribute::new(ATTR_USERNAME, "user".into()),
             )
@@ -303,6 +370,7 @@ async fn test_manager_close() -> Result<()> {
             random_five_tuple(),
             Arc::clone(&turn_socket),
             0,
+            REQUESTED_FAMILY_IPV4,
             Duration::from_millis(100),
             TextAttribute::new(ATTR_USERNAME, "user".into()),
         )
@@ -314,6 +382,7 @@ async fn test_manager_close() -> Result<()> {
             random_five_tuple(),
             Arc::clone(&turn_socket),
             0,
+            REQUESTED_FAMILY_IPV4,
             Duration::from_millis(200),
             TextAttribute::new(ATTR_USERNAME, "user".into()),
         )
@@ -351,6 +420,7 @@ async fn test_delete_allocation_by_username() -> Result<()> {
             five_tuple1.clone(),
             Arc::clone(&turn_socket),
             0,
+            REQUESTED_FAMILY_IPV4,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
         )
@@ -360,6 +430,7 @@ async fn test_delete_allocation_by_username() -> Result<()> {
             five_tuple2.clone(),
             Arc::clone(&turn_socket),
             0,
+            REQUESTED_FAMILY_IPV4,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
         )
@@ -369,6 +440,7 @@ async fn test_delete_allocation_by_username() -> Result<()> {
             five_tuple3.clone(),
             Arc::clone(&turn_socket),
             0,
+            REQUESTED_FAMILY_IPV4,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user2".into()),
         )
@@ -390,9 +462,10 @@ async fn test_delete_allocation_by_username() -> Result<()> {
 }
 
 struct TestAuthHandler;
+#[async_trait]
 impl AuthHandler for TestAuthHandler {
-    fn auth_handle(&self, username: &str, realm: &str, _src_addr: SocketAddr) -> Result<Vec<u8>> {
-        Ok(generate_auth_key(username, realm, "pass"))
+    async fn auth_handle(&self, ctx: &AuthContext) -> Result<Vec<u8>> {
+        Ok(generate_auth_key(&ctx.username, &ctx.realm, "pass"))
     }
 }
 
@@ -409,9 +482,11 @@ async fn create_server() -> Result<(Server, u16)> {
                 net: Arc::new(Net::new(None)),
             }),
         }],
+        listener_configs: vec![],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler {}),
         channel_bind_timeout: Duration::from_secs(0),
+        event_handler: None,
     })
     .await?;
 
@@ -5,18 +5,20 @@ pub mod allocation_manager;
 pub mod channel_bind;
 pub mod five_tuple;
 pub mod permission;
+pub mod tcp_relay;
 
 use crate::error::*;
-use crate::proto::{chandata::*, channum::*, data::*, peeraddr::*, *};
+use crate::proto::{chandata::*, channum::*, data::*, mobility::*, peeraddr::*, *};
+use crate::server::events::*;
 use channel_bind::*;
 use five_tuple::*;
 use permission::*;
 use stun::{agent::*, message::*, textattrs::Username};
+use tcp_relay::*;
 use util::sync::Mutex as SyncMutex;
 
 use util::Conn;
 
-use std::sync::atomic::AtomicUsize;
 use std::{
     collections::HashMap,
     marker::{Send, Sync},
@@ -24,6 +26,7 @@ use std::{
     sync::{atomic::AtomicBool, atomic::Ordering, Arc},
 };
 use tokio::{
+    net::TcpListener,
     sync::{
         mpsc,
         oneshot::{self, Sender},
@@ -33,6 +36,7 @@ use tokio::{
 };
 
 const RTP_MTU: usize = 1500;
+const MOBILITY_TICKET_SIZE: usize = 16;
 
 pub type AllocationMap = Arc<Mutex<HashMap<FiveTuple, Arc<Allocation>>>>;
 
@@ -72,16 +76,21 @@ pub struct Allocation {
     protocol: Protocol,
     turn_socket: Arc<dyn Conn + Send + Sync>,
     pub(crate) relay_addr: SocketAddr,
-    pub(crate) relay_socket: Arc<dyn Conn + Send + Sync>,
-    five_tuple: FiveTuple,
+    pub(crate) relay_socket: Option<Arc<dyn Conn + Send + Sync>>,
+    pub(crate) tcp_relay: Option<TcpRelay>,
+    // five_tuple changes when the client moves the allocation to a new
+    // transport address with a mobility ticket
+    five_tuple: Arc<SyncMutex<FiveTuple>>,
     username: Username,
     permissions: Arc<Mutex<HashMap<String, Permission>>>,
     channel_bindings: Arc<Mutex<HashMap<ChannelNumber, ChannelBind>>>,
     pub(crate) allocations: Option<AllocationMap>,
+    mobility_ticket: SyncMutex<Option<MobilityTicket>>,
     reset_tx: SyncMutex<Option<mpsc::Sender<Duration>>>,
     timer_expired: Arc<AtomicBool>,
     closed: AtomicBool, // Option<mpsc::Receiver<()>>,
-    pub(crate) relayed_bytes: AtomicUsize,
+    pub(crate) events: Arc<Events>,
+    pub(crate) relayed: Arc<RelayCounters>,
     drop_tx: Option<Sender<u32>>,
 }
 
@@ -102,20 +111,92 @@ impl Allocation {
             protocol: PROTO_UDP,
             turn_socket,
             relay_addr,
-            relay_socket,
-            five_tuple,
+            relay_socket: Some(relay_socket),
+            tcp_relay: None,
+            five_tuple: Arc::new(SyncMutex::new(five_tuple)),
             username,
             permissions: Arc::new(Mutex::new(HashMap::new())),
             channel_bindings: Arc::new(Mutex::new(HashMap::new())),
             allocations: None,
+            mobility_ticket: SyncMutex::new(None),
             reset_tx: SyncMutex::new(None),
             timer_expired: Arc::new(AtomicBool::new(false)),
             closed: AtomicBool::new(false),
-            relayed_bytes: Default::default(),
+            events: Arc::new(Events::default()),
+            relayed: Arc::new(RelayCounters::default()),
             drop_tx: None,
         }
     }
 
+    // creates a new instance of a TCP allocation as described in RFC 6062.
+    // listener accepts connections from peers at relay_addr.
+    pub fn new_tcp(
+        turn_socket: Arc<dyn Conn + Send + Sync>,
+        listener: TcpListener,
+        relay_addr: SocketAddr,
+        five_tuple: FiveTuple,
+        username: Username,
+    ) -> Result<Self> {
+        let permissions = Arc::new(Mutex::new(HashMap::new()));
+        let tcp_relay = TcpRelay::new(
+            listener,
+            Arc::clone(&turn_socket),
+            five_tuple,
+            Arc::clone(&permissions),
+        )?;
+
+        Ok(Allocation {
+            protocol: PROTO_TCP,
+            turn_socket,
+            relay_addr,
+            relay_socket: None,
+            tcp_relay: Some(tcp_relay),
+            five_tuple: Arc::new(SyncMutex::new(five_tuple)),
+            username,
+            permissions,
+            channel_bindings: Arc::new(Mutex::new(HashMap::new())),
+            allocations: None,
+            mobility_ticket: SyncMutex::new(None),
+            reset_tx: SyncMutex::new(None),
+            timer_expired: Arc::new(AtomicBool::new(false)),
+            closed: AtomicBool::new(false),
+            events: Arc::new(Events::default()),
+            relayed: Arc::new(RelayCounters::default()),
+            drop_tx: None,
+        })
+    }
+
+    // protocol returns the transport protocol of the relayed transport address
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    // five_tuple returns the 5-tuple the allocation is currently tied to
+    pub fn five_tuple(&self) -> FiveTuple {
+        *self.five_tuple.lock()
+    }
+
+    // set_five_tuple ties the allocation to a new 5-tuple. The allocation map
+    // must be updated accordingly while it is locked.
+    pub(crate) fn set_five_tuple(&self, five_tuple: FiveTuple) {
+        *self.five_tuple.lock() = five_tuple;
+    }
+
+    // new_mobility_ticket issues a new mobility ticket for the allocation,
+    // which replaces the previous one, if any.
+    // https://tools.ietf.org/html/rfc8016#section-3.1
+    pub(crate) fn new_mobility_ticket(&self) -> MobilityTicket {
+        let ticket = MobilityTicket(rand::random::<[u8; MOBILITY_TICKET_SIZE]>().to_vec());
+        self.mobility_ticket.lock().replace(ticket.clone());
+        ticket
+    }
+
+    // has_mobility_ticket tells whether ticket is the last mobility ticket
+    // issued for the allocation.
+    pub(crate) fn has_mobility_ticket(&self, ticket: &MobilityTicket) -> bool {
+        self.mobility_ticket.lock().as_ref() == Some(ticket)
+    }
+
     // has_permission gets the Permission from the allocation
     pub async fn has_permission(&self, addr: &SocketAddr) -> bool {
         let permissions = self.permissions.lock().await;
@@ -137,10 +218,14 @@ impl Allocation {
         p.permissions = Some(Arc::clone(&self.permissions));
         p.start(PERMISSION_TIMEOUT).await;
 
+        let peer = p.addr.ip();
         {
             let mut permissions = self.permissions.lock().await;
             permissions.insert(fingerprint, p);
         }
+
+        self.events
+            .permission_added(&self.five_tuple(), &self.username.text, peer);
     }
 
     // remove_permission removes the net.Addr's fingerprint from the allocation's permissions
@@ -179,6 +264,7 @@ impl Allocation {
         }
 
         let peer = c.peer;
+        let number = c.number;
 
         // Add or refresh this channel.
         c.channel_bindings = Some(Arc::clone(&self.channel_bindings));
@@ -189,6 +275,9 @@ impl Allocation {
             channel_bindings.insert(c.number, c);
         }
 
+        self.events
+            .channel_bound(&self.five_tuple(), &self.username.text, peer, number.0);
+
         // Channel binds also refresh permissions.
         self.add_permission(Permission::new(peer)).await;
 
@@ -218,8 +307,38 @@ impl Allocation {
         None
     }
 
+    // relay_channel_data relays the data of a ChannelData message to the peer
+    // its channel is bound to.
+    pub(crate) async fn relay_channel_data(&self, c: &ChannelData) -> Result<()> {
+        let peer = match self.get_channel_addr(&c.number).await {
+            Some(peer) => peer,
+            None => return Err(Error::ErrNoSuchChannelBind),
+        };
+        let relay_socket = match &self.relay_socket {
+            Some(relay_socket) => relay_socket,
+            None => return Err(Error::ErrTcpAllocationUnsupportedOperation),
+        };
+
+        let l = relay_socket.send_to(&c.data, peer).await?;
+        if l != c.data.len() {
+            Err(Error::ErrShortWrite)
+        } else {
+            self.relayed_to_peer(c.data.len());
+            Ok(())
+        }
+    }
+
+    // is_closed tells whether the allocation was closed
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
     // Close closes the allocation
     pub async fn close(&self) -> Result<()> {
+        self.close_with_reason(AllocationCloseReason::Deleted).await
+    }
+
+    pub(crate) async fn close_with_reason(&self, reason: AllocationCloseReason) -> Result<()> {
         if self.closed.load(Ordering::Acquire) {
             return Err(Error::ErrClosed);
         }
@@ -241,10 +360,22 @@ impl Allocation {
             }
         }
 
-        log::trace!("allocation with {} closed!", self.five_tuple);
+        log::trace!("allocation with {} closed!", self.five_tuple());
 
         let _ = self.turn_socket.close().await;
-        let _ = self.relay_socket.close().await;
+        if let Some(relay_socket) = &self.relay_socket {
+            let _ = relay_socket.close().await;
+        }
+        if let Some(tcp_relay) = &self.tcp_relay {
+            tcp_relay.close().await;
+        }
+
+        self.events.allocation_closed(
+            &self.five_tuple(),
+            &self.username.text,
+            self.relayed.get(),
+            reason,
+        );
 
         Ok(())
     }
@@ -254,7 +385,7 @@ impl Allocation {
         self.reset_tx.lock().replace(reset_tx);
 
         let allocations = self.allocations.clone();
-        let five_tuple = self.five_tuple;
+        let five_tuple = Arc::clone(&self.five_tuple);
         let timer_expired = Arc::clone(&self.timer_expired);
 
         tokio::spawn(async move {
@@ -267,8 +398,9 @@ impl Allocation {
                     _ = &mut timer => {
                         if let Some(allocs) = &allocations{
                             let mut alls = allocs.lock().await;
+                            let five_tuple = *five_tuple.lock();
                             if let Some(a) = alls.remove(&five_tuple) {
-                                let _ = a.close().await;
+                                let _ = a.close_with_reason(AllocationCloseReason::Expired).await;
                             }
                         }
                         done = true;
@@ -298,6 +430,19 @@ impl Allocation {
         if let Some(tx) = reset_tx {
             let _ = tx.send(lifetime).await;
         }
+
+        self.events
+            .allocation_refreshed(&self.five_tuple(), &self.username.text, lifetime);
+    }
+
+    // relayed_to_peer counts n bytes relayed from the client to a peer
+    pub(crate) fn relayed_to_peer(&self, n: usize) {
+        self.events.relayed_to_peer(&self.relayed, n);
+    }
+
+    // relayed_from_peer counts n bytes relayed from a peer to the client
+    pub(crate) fn relayed_from_peer(&self, n: usize) {
+        self.events.relayed_from_peer(&self.relayed, n);
     }
 
     //  https://tools.ietf.org/html/rfc5766#section-10.3
@@ -320,13 +465,18 @@ impl Allocation {
     //  transport address of the received UDP datagram.  The Data indication
     //  is then sent on the 5-tuple associated with the allocation.
     async fn packet_handler(&mut self) {
-        let five_tuple = self.five_tuple;
+        let five_tuple = Arc::clone(&self.five_tuple);
         let relay_addr = self.relay_addr;
-        let relay_socket = Arc::clone(&self.relay_socket);
+        let relay_socket = match &self.relay_socket {
+            Some(relay_socket) => Arc::clone(relay_socket),
+            None => return,
+        };
         let turn_socket = Arc::clone(&self.turn_socket);
         let allocations = self.allocations.clone();
         let channel_bindings = Arc::clone(&self.channel_bindings);
         let permissions = Arc::clone(&self.permissions);
+        let events = Arc::clone(&self.events);
+        let relayed = Arc::clone(&self.relayed);
         let (drop_tx, drop_rx) = oneshot::channel::<u32>();
         self.drop_tx = Some(drop_tx);
 
@@ -343,6 +493,7 @@ impl Allocation {
                             Err(_) => {
                                 if let Some(allocs) = &allocations {
                                     let mut alls = allocs.lock().await;
+                                    let five_tuple = *five_tuple.lock();
                                     alls.remove(&five_tuple);
                                 }
                                 break;
@@ -350,7 +501,7 @@ impl Allocation {
                         }
                     }
                     _ = drop_rx.as_mut() => {
-                        log::trace!("allocation has stopped, stop packet_handler. five_tuple: {:?}", five_tuple);
+                        log::trace!("allocation has stopped, stop packet_handler. five_tuple: {:?}", *five_tuple.lock());
                         break;
                     }
                 };
@@ -362,6 +513,9 @@ impl Allocation {
                     src_addr
                 );
 
+                // The client may have moved to another transport address.
+                let client_addr = five_tuple.lock().src_addr;
+
                 let cb_number = {
                     let mut cb_number = None;
                     let cbs = channel_bindings.lock().await;
@@ -382,15 +536,13 @@ impl Allocation {
                     };
                     channel_data.encode();
 
-                    if let Err(err) = turn_socket
-                        .send_to(&channel_data.raw, five_tuple.src_addr)
-                        .await
-                    {
-                        log::error!(
+                    match turn_socket.send_to(&channel_data.raw, client_addr).await {
+                        Ok(_) => events.relayed_from_peer(&relayed, n),
+                        Err(err) => log::error!(
                             "Failed to send ChannelData from allocation {} {}",
                             src_addr,
                             err
-                        );
+                        ),
                     }
                 } else {
                     let exist = {
@@ -428,16 +580,15 @@ impl Allocation {
                             log::debug!(
                                 "relaying message from {} to client at {}",
                                 src_addr,
-                                five_tuple.src_addr
+                                client_addr
                             );
-                            if let Err(err) =
-                                turn_socket.send_to(&msg.raw, five_tuple.src_addr).await
-                            {
-                                log::error!(
+                            match turn_socket.send_to(&msg.raw, client_addr).await {
+                                Ok(_) => events.relayed_from_peer(&relayed, n),
+                                Err(err) => log::error!(
                                     "Failed to send DataIndication from allocation {} {}",
                                     src_addr,
                                     err
-                                );
+                                ),
                             }
                         }
                     } else {
@@ -0,0 +1,200 @@
+#[cfg(test)]
+mod tcp_relay_test;
+
+use super::*;
+use crate::proto::connid::ConnectionId;
+use crate::relay::new_tcp_socket;
+
+use std::collections::HashSet;
+use std::sync::Weak;
+use tokio::net::{TcpListener, TcpStream};
+
+// CONNECTION_TIMEOUT bounds both connecting to a peer and how long a peer
+// data connection waits for the client's ConnectionBind request.
+// https://tools.ietf.org/html/rfc6062#section-5.2
+pub(crate) const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+// PeerConnection is a TCP connection between the relayed transport address
+// and a peer, taken over by a client data connection with ConnectionBind.
+// The peer is released from its allocation when the connection is dropped.
+pub struct PeerConnection {
+    pub peer: SocketAddr,
+    pub stream: TcpStream,
+    pub(crate) allocation: Weak<Allocation>,
+    peers: Arc<SyncMutex<HashSet<SocketAddr>>>,
+}
+
+impl Drop for PeerConnection {
+    fn drop(&mut self) {
+        self.peers.lock().remove(&self.peer);
+    }
+}
+
+// TcpRelay holds the peer data connections of a TCP allocation, as
+// described in RFC 6062.
+pub struct TcpRelay {
+    local_addr: SocketAddr,
+    pending: Arc<Mutex<HashMap<ConnectionId, (SocketAddr, TcpStream)>>>,
+    peers: Arc<SyncMutex<HashSet<SocketAddr>>>,
+    close_tx: SyncMutex<Option<oneshot::Sender<()>>>,
+}
+
+impl TcpRelay {
+    // new starts accepting peer connections on listener. Connections from peers
+    // without a permission are closed, others are announced to the client with
+    // a ConnectionAttempt indication sent on turn_socket.
+    pub(crate) fn new(
+        listener: TcpListener,
+        turn_socket: Arc<dyn Conn + Send + Sync>,
+        five_tuple: FiveTuple,
+        permissions: Arc<Mutex<HashMap<String, Permission>>>,
+    ) -> Result<Self> {
+        let (close_tx, close_rx) = oneshot::channel();
+        let r = TcpRelay {
+            local_addr: listener.local_addr()?,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            peers: Arc::new(SyncMutex::new(HashSet::new())),
+            close_tx: SyncMutex::new(Some(close_tx)),
+        };
+
+        let pending = Arc::clone(&r.pending);
+        let peers = Arc::clone(&r.peers);
+        tokio::spawn(async move {
+            tokio::pin!(close_rx);
+
+            loop {
+                let (stream, peer) = tokio::select! {
+                    result = listener.accept() => match result {
+                        Ok(v) => v,
+                        Err(err) => {
+                            log::debug!("exit accept loop of {} on error: {}", five_tuple, err);
+                            break;
+                        }
+                    },
+                    _ = close_rx.as_mut() => break,
+                };
+
+                let has_permission = {
+                    let ps = permissions.lock().await;
+                    ps.contains_key(&addr2ipfingerprint(&peer))
+                };
+                if !has_permission {
+                    log::info!(
+                        "No Permission exists for TCP connection from {} on allocation {}",
+                        peer,
+                        five_tuple
+                    );
+                    continue;
+                }
+
+                peers.lock().insert(peer);
+                let id = TcpRelay::add_pending(&pending, &peers, peer, stream).await;
+
+                let mut msg = Message::new();
+                if let Err(err) = msg.build(&[
+                    Box::new(TransactionId::new()),
+                    Box::new(connection_attempt_indication()),
+                    Box::new(PeerAddress {
+                        ip: peer.ip(),
+                        port: peer.port(),
+                    }),
+                    Box::new(id),
+                ]) {
+                    log::error!("Failed to build ConnectionAttempt for {}: {}", peer, err);
+                    continue;
+                }
+
+                if let Err(err) = turn_socket.send_to(&msg.raw, five_tuple.src_addr).await {
+                    log::error!("Failed to send ConnectionAttempt for {}: {}", peer, err);
+                }
+            }
+        });
+
+        Ok(r)
+    }
+
+    // connect opens a connection from the relayed transport address to peer and
+    // returns the id the client binds it with.
+    pub(crate) async fn connect(&self, peer: SocketAddr) -> Result<ConnectionId> {
+        if !self.peers.lock().insert(peer) {
+            return Err(Error::ErrConnectionAlreadyExists);
+        }
+
+        let local_addr = self.local_addr;
+        let result = tokio::time::timeout(CONNECTION_TIMEOUT, async move {
+            let socket = new_tcp_socket(local_addr)?;
+            socket.bind(local_addr)?;
+            Ok::<TcpStream, Error>(socket.connect(peer).await?)
+        })
+        .await;
+
+        match result {
+            Ok(Ok(stream)) => {
+                Ok(TcpRelay::add_pending(&self.pending, &self.peers, peer, stream).await)
+            }
+            Ok(Err(err)) => {
+                log::debug!("failed to connect to peer {}: {}", peer, err);
+                self.peers.lock().remove(&peer);
+                Err(Error::ErrConnectionTimeoutOrFailure)
+            }
+            Err(_) => {
+                self.peers.lock().remove(&peer);
+                Err(Error::ErrConnectionTimeoutOrFailure)
+            }
+        }
+    }
+
+    // take removes the pending connection with the given id.
+    pub(crate) async fn take(&self, id: ConnectionId) -> Option<PeerConnection> {
+        let mut pending = self.pending.lock().await;
+        pending.remove(&id).map(|(peer, stream)| PeerConnection {
+            peer,
+            stream,
+            allocation: Weak::new(),
+            peers: Arc::clone(&self.peers),
+        })
+    }
+
+    // close stops accepting peer connections and drops pending ones.
+    pub(crate) async fn close(&self) {
+        self.close_tx.lock().take();
+
+        let mut pending = self.pending.lock().await;
+        let mut peers = self.peers.lock();
+        for (peer, _) in pending.values() {
+            peers.remove(peer);
+        }
+        pending.clear();
+    }
+
+    async fn add_pending(
+        pending: &Arc<Mutex<HashMap<ConnectionId, (SocketAddr, TcpStream)>>>,
+        peers: &Arc<SyncMutex<HashSet<SocketAddr>>>,
+        peer: SocketAddr,
+        stream: TcpStream,
+    ) -> ConnectionId {
+        let id = {
+            let mut pending = pending.lock().await;
+            let mut id = ConnectionId(rand::random::<u32>());
+            while pending.contains_key(&id) {
+                id = ConnectionId(rand::random::<u32>());
+            }
+            pending.insert(id, (peer, stream));
+            id
+        };
+
+        // If no ConnectionBind is received within 30 seconds, the connection
+        // to the peer is closed.
+        let pending = Arc::clone(pending);
+        let peers = Arc::clone(peers);
+        tokio::spawn(async move {
+            tokio::time::sleep(CONNECTION_TIMEOUT).await;
+            let mut pending = pending.lock().await;
+            if let Some((peer, _)) = pending.remove(&id) {
+                peers.lock().remove(&peer);
+            }
+        });
+
+        id
+    }
+}
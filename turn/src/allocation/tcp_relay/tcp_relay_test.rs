@@ -0,0 +1,99 @@
+use super::*;
+use crate::relay::bind_tcp_listener;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UdpSocket;
+
+async fn new_test_relay(
+    turn_socket: Arc<dyn Conn + Send + Sync>,
+    client_addr: SocketAddr,
+) -> Result<TcpRelay> {
+    let listener = bind_tcp_listener("127.0.0.1:0".parse()?)?;
+    let five_tuple = FiveTuple {
+        src_addr: client_addr,
+        dst_addr: turn_socket.local_addr()?,
+        protocol: PROTO_TCP,
+    };
+
+    let permissions = Arc::new(Mutex::new(HashMap::new()));
+    {
+        let mut ps = permissions.lock().await;
+        let addr = SocketAddr::new("127.0.0.1".parse()?, 0);
+        ps.insert(addr2ipfingerprint(&addr), Permission::new(addr));
+    }
+
+    TcpRelay::new(listener, turn_socket, five_tuple, permissions)
+}
+
+#[tokio::test]
+async fn test_tcp_relay_connection_attempt() -> Result<()> {
+    let turn_socket: Arc<dyn Conn + Send + Sync> = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = UdpSocket::bind("127.0.0.1:0").await?;
+    let relay = new_test_relay(turn_socket, client.local_addr()?).await?;
+
+    let mut peer = TcpStream::connect(relay.local_addr).await?;
+
+    let mut buf = vec![0u8; 1500];
+    let (n, _) = client.recv_from(&mut buf).await?;
+    let mut msg = Message::new();
+    msg.write(&buf[..n])?;
+    assert_eq!(msg.typ, connection_attempt_indication());
+
+    let mut peer_addr = PeerAddress::default();
+    peer_addr.get_from(&msg)?;
+    assert_eq!(
+        SocketAddr::new(peer_addr.ip, peer_addr.port),
+        peer.local_addr()?
+    );
+
+    let mut id = ConnectionId::default();
+    id.get_from(&msg)?;
+
+    let mut c = relay.take(id).await.expect("pending connection");
+    assert!(
+        relay.take(id).await.is_none(),
+        "connection must be taken once"
+    );
+
+    peer.write_all(b"hello").await?;
+    let mut data = [0u8; 5];
+    c.stream.read_exact(&mut data).await?;
+    assert_eq!(&data, b"hello");
+
+    relay.close().await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tcp_relay_connect() -> Result<()> {
+    let turn_socket: Arc<dyn Conn + Send + Sync> = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let relay = new_test_relay(turn_socket, "127.0.0.1:5000".parse()?).await?;
+
+    let peer_listener = TcpListener::bind("127.0.0.1:0").await?;
+    let peer_addr = peer_listener.local_addr()?;
+
+    let id = relay.connect(peer_addr).await?;
+    let (mut peer, from) = peer_listener.accept().await?;
+    assert_eq!(
+        from, relay.local_addr,
+        "must connect from the relayed address"
+    );
+
+    let result = relay.connect(peer_addr).await;
+    assert_eq!(result, Err(Error::ErrConnectionAlreadyExists));
+
+    let mut c = relay.take(id).await.expect("pending connection");
+    c.stream.write_all(b"hello").await?;
+    let mut data = [0u8; 5];
+    peer.read_exact(&mut data).await?;
+    assert_eq!(&data, b"hello");
+
+    // Once the peer connection is dropped, the peer is released.
+    drop(c);
+    assert!(!relay.peers.lock().contains(&peer_addr));
+
+    relay.close().await;
+
+    Ok(())
+}
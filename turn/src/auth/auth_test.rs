@@ -35,6 +35,62 @@ fn test_generate_auth_key() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_rest_credentials() -> Result<()> {
+    let shared_secret = "foobar";
+
+    let (username, password) =
+        generate_rest_credentials(shared_secret, "alice", Duration::from_secs(60))?;
+    let (timestamp, user) = username.split_at(username.find(':').unwrap());
+    assert_eq!(user, ":alice");
+    assert_eq!(
+        rest_username_expiry(&username)?,
+        UNIX_EPOCH + Duration::from_secs(timestamp.parse::<u64>()?)
+    );
+    assert_eq!(password, long_term_credentials(&username, shared_secret));
+
+    validate_rest_credentials(shared_secret, &username, &password)?;
+    assert_eq!(
+        validate_rest_credentials("barfoo", &username, &password),
+        Err(Error::ErrInvalidCredentials)
+    );
+    assert_eq!(
+        validate_rest_credentials(shared_secret, &username, "not base64!"),
+        Err(Error::ErrInvalidCredentials)
+    );
+
+    let expired = "1599491771:alice";
+    let password = long_term_credentials(expired, shared_secret);
+    assert!(validate_rest_credentials(shared_secret, expired, &password).is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_long_term_auth_handler_rest_username() -> Result<()> {
+    let shared_secret = "foobar";
+    let realm = "webrtc.rs";
+    let handler = LongTermAuthHandler::new(shared_secret.to_owned());
+    let ctx = |username: &str| AuthContext {
+        username: username.to_owned(),
+        realm: realm.to_owned(),
+        src_addr: SocketAddr::from(([127, 0, 0, 1], 1234)),
+        protocol: crate::proto::PROTO_UDP,
+    };
+
+    let (username, password) =
+        generate_rest_credentials(shared_secret, "alice", Duration::from_secs(60))?;
+    assert_eq!(
+        handler.auth_handle(&ctx(&username)).await?,
+        generate_auth_key(&username, realm, &password)
+    );
+
+    assert!(handler.auth_handle(&ctx("1599491771:alice")).await.is_err());
+    assert!(handler.auth_handle(&ctx("alice")).await.is_err());
+
+    Ok(())
+}
+
 #[cfg(target_family = "unix")]
 #[tokio::test]
 async fn test_new_long_term_auth_handler() -> Result<()> {
@@ -67,9 +123,11 @@ async fn test_new_long_term_auth_handler() -> Result<()> {
                 net: Arc::new(Net::new(None)),
             }),
         }],
+        listener_configs: vec![],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(LongTermAuthHandler::new(SHARED_SECRET.to_string())),
         channel_bind_timeout: Duration::from_secs(0),
+        event_handler: None,
     })
     .await?;
 
@@ -100,3 +158,72 @@ async fn test_new_long_term_auth_handler() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(target_family = "unix")]
+#[tokio::test]
+async fn test_rest_credential_provider() -> Result<()> {
+    use crate::client::{credentials::*, *};
+    use crate::relay::relay_static::*;
+    use crate::server::{config::*, *};
+
+    use std::net::IpAddr;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use tokio::net::UdpSocket;
+    use util::vnet::net::*;
+
+    const SHARED_SECRET: &str = "HELLO_WORLD";
+
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let server_port = conn.local_addr()?.port();
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(Net::new(None)),
+            }),
+        }],
+        listener_configs: vec![],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(LongTermAuthHandler::new(SHARED_SECRET.to_string())),
+        channel_bind_timeout: Duration::from_secs(0),
+        event_handler: None,
+    })
+    .await?;
+
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: format!("0.0.0.0:{}", server_port),
+        turn_serv_addr: format!("0.0.0.0:{}", server_port),
+        username: String::new(),
+        password: String::new(),
+        realm: "webrtc.rs".to_owned(),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+
+    client.listen().await?;
+
+    let provider = Arc::new(RestCredentialProvider::new(
+        SHARED_SECRET.to_owned(),
+        "alice".to_owned(),
+        Duration::from_secs(60),
+    ));
+    client
+        .set_credential_provider(provider, Duration::from_secs(10))
+        .await?;
+
+    let _allocation = client.allocate().await?;
+
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
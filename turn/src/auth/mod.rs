@@ -2,17 +2,39 @@
 mod auth_test;
 
 use crate::error::*;
+use crate::proto::Protocol;
 
 use std::net::SocketAddr;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use async_trait::async_trait;
 use md5::{Digest, Md5};
 use ring::hmac;
 
+// AuthContext describes the request an AuthHandler authenticates.
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub username: String,
+    pub realm: String,
+    // src_addr is the transport address the request was received from
+    pub src_addr: SocketAddr,
+    // protocol is the transport protocol between the client and the server
+    pub protocol: Protocol,
+}
+
+// AuthHandler returns the key, as created by generate_auth_key, that the
+// MESSAGE-INTEGRITY of a request from ctx.username must be computed with.
+// It may look the user up in a database or ask an external service, as
+// other requests are handled meanwhile.
+#[async_trait]
 pub trait AuthHandler {
-    fn auth_handle(&self, username: &str, realm: &str, src_addr: SocketAddr) -> Result<Vec<u8>>;
+    async fn auth_handle(&self, ctx: &AuthContext) -> Result<Vec<u8>>;
 }
 
+// REST_USERNAME_SEPARATOR separates the expiry timestamp from the user name
+// in time-limited usernames, as in coturn's TURN REST API.
+pub const REST_USERNAME_SEPARATOR: char = ':';
+
 // generate_long_term_credentials can be used to create credentials valid for [duration] time
 pub fn generate_long_term_credentials(
     shared_secret: &str,
@@ -24,6 +46,58 @@ pub fn generate_long_term_credentials(
     Ok((username, password))
 }
 
+// generate_rest_credentials creates time-limited credentials for user valid
+// for [duration] time. The username is "timestamp:user", where timestamp is
+// the expiry time in seconds since the UNIX epoch, and the password is the
+// base64 encoded HMAC-SHA1 of the username keyed with shared_secret.
+pub fn generate_rest_credentials(
+    shared_secret: &str,
+    user: &str,
+    duration: Duration,
+) -> Result<(String, String)> {
+    let t = SystemTime::now().duration_since(UNIX_EPOCH)? + duration;
+    let username = format!("{}{}{}", t.as_secs(), REST_USERNAME_SEPARATOR, user);
+    let password = long_term_credentials(&username, shared_secret);
+    Ok((username, password))
+}
+
+// validate_rest_credentials checks that password was generated for username
+// from shared_secret and that username has not expired yet.
+pub fn validate_rest_credentials(
+    shared_secret: &str,
+    username: &str,
+    password: &str,
+) -> Result<()> {
+    check_rest_username_expiry(username)?;
+
+    let mac = hmac::Key::new(
+        hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+        shared_secret.as_bytes(),
+    );
+    let tag = base64::decode(password).map_err(|_| Error::ErrInvalidCredentials)?;
+    hmac::verify(&mac, username.as_bytes(), &tag).map_err(|_| Error::ErrInvalidCredentials)
+}
+
+// rest_username_expiry returns the expiry time of a time-limited username,
+// which is either "timestamp" or "timestamp:user".
+pub fn rest_username_expiry(username: &str) -> Result<SystemTime> {
+    let timestamp = match username.find(REST_USERNAME_SEPARATOR) {
+        Some(pos) => &username[..pos],
+        None => username,
+    };
+    Ok(UNIX_EPOCH + Duration::from_secs(timestamp.parse::<u64>()?))
+}
+
+fn check_rest_username_expiry(username: &str) -> Result<()> {
+    if rest_username_expiry(username)? < SystemTime::now() {
+        return Err(Error::Other(format!(
+            "Expired time-windowed username {}",
+            username
+        )));
+    }
+    Ok(())
+}
+
 fn long_term_credentials(username: &str, shared_secret: &str) -> String {
     let mac = hmac::Key::new(
         hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
@@ -46,29 +120,27 @@ pub struct LongTermAuthHandler {
     shared_secret: String,
 }
 
+#[async_trait]
 impl AuthHandler for LongTermAuthHandler {
-    fn auth_handle(&self, username: &str, realm: &str, src_addr: SocketAddr) -> Result<Vec<u8>> {
+    async fn auth_handle(&self, ctx: &AuthContext) -> Result<Vec<u8>> {
         log::trace!(
             "Authentication username={} realm={} src_addr={}",
-            username,
-            realm,
-            src_addr
+            ctx.username,
+            ctx.realm,
+            ctx.src_addr
         );
 
-        let t = Duration::from_secs(username.parse::<u64>()?);
-        if t < SystemTime::now().duration_since(UNIX_EPOCH)? {
-            return Err(Error::Other(format!(
-                "Expired time-windowed username {}",
-                username
-            )));
-        }
+        check_rest_username_expiry(&ctx.username)?;
 
-        let password = long_term_credentials(username, &self.shared_secret);
-        Ok(generate_auth_key(username, realm, &password))
+        let password = long_term_credentials(&ctx.username, &self.shared_secret);
+        Ok(generate_auth_key(&ctx.username, &ctx.realm, &password))
     }
 }
 
 impl LongTermAuthHandler {
+    // new returns a handler for the time-limited credentials created with
+    // generate_long_term_credentials or generate_rest_credentials, which are
+    // compatible with coturn's use-auth-secret mode.
     // https://tools.ietf.org/search/rfc5389#section-10.2
     pub fn new(shared_secret: String) -> Self {
         LongTermAuthHandler { shared_secret }
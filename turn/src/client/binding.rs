@@ -130,6 +130,15 @@ impl BindingManager {
         }
     }
 
+    // ready_bindings returns the bindings established with the server.
+    pub(crate) fn ready_bindings(&self) -> Vec<Binding> {
+        self.addr_map
+            .values()
+            .filter(|b| b.state() == BindingState::Ready)
+            .copied()
+            .collect()
+    }
+
     pub(crate) fn size(&self) -> usize {
         self.addr_map.len()
     }
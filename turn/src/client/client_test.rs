@@ -119,9 +119,10 @@ async fn test_client_with_stun_send_binding_request_to_timeout() -> Result<()> {
 }
 
 struct TestAuthHandler;
+#[async_trait]
 impl AuthHandler for TestAuthHandler {
-    fn auth_handle(&self, username: &str, realm: &str, _src_addr: SocketAddr) -> Result<Vec<u8>> {
-        Ok(generate_auth_key(username, realm, "pass"))
+    async fn auth_handle(&self, ctx: &AuthContext) -> Result<Vec<u8>> {
+        Ok(generate_auth_key(&ctx.username, &ctx.realm, "pass"))
     }
 }
 
@@ -147,9 +148,11 @@ async fn test_client_nonce_expiration() -> Result<()> {
                 net: Arc::new(Net::new(None)),
             }),
         }],
+        listener_configs: vec![],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler {}),
         channel_bind_timeout: Duration::from_secs(0),
+        event_handler: None,
     })
     .await?;
 
@@ -187,3 +190,203 @@ async fn test_client_nonce_expiration() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_client_allocate_with_family() -> Result<()> {
+    use crate::relay::relay_dual_stack::*;
+
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let server_port = conn.local_addr()?.port();
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorDualStack {
+                ipv4: Box::new(RelayAddressGeneratorStatic {
+                    relay_address: IpAddr::from_str("127.0.0.1")?,
+                    address: "127.0.0.1".to_owned(),
+                    net: Arc::new(Net::new(None)),
+                }),
+                ipv6: Box::new(RelayAddressGeneratorStatic {
+                    relay_address: IpAddr::from_str("::1")?,
+                    address: "::1".to_owned(),
+                    net: Arc::new(Net::new(None)),
+                }),
+            }),
+        }],
+        listener_configs: vec![],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler {}),
+        channel_bind_timeout: Duration::from_secs(0),
+        event_handler: None,
+    })
+    .await?;
+
+    for (requested_family, peer) in [
+        (REQUESTED_FAMILY_IPV6, "[::1]:8080"),
+        (REQUESTED_FAMILY_IPV4, "127.0.0.1:8080"),
+    ] {
+        let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+        let client = Client::new(ClientConfig {
+            stun_serv_addr: format!("127.0.0.1:{}", server_port),
+            turn_serv_addr: format!("127.0.0.1:{}", server_port),
+            username: "foo".to_owned(),
+            password: "pass".to_owned(),
+            realm: String::new(),
+            software: String::new(),
+            rto_in_ms: 0,
+            conn,
+            vnet: None,
+        })
+        .await?;
+        client.listen().await?;
+
+        let allocation = client.allocate_with_family(requested_family).await?;
+        let relayed_addr = allocation.local_addr()?;
+        assert_eq!(
+            relayed_addr.is_ipv4(),
+            requested_family == REQUESTED_FAMILY_IPV4,
+            "relayed address {} is not of family {}",
+            relayed_addr,
+            requested_family
+        );
+
+        // Peers of the other family cannot be reached through the allocation.
+        let other_peer = if relayed_addr.is_ipv4() {
+            SocketAddr::from_str("[::1]:8080")?
+        } else {
+            SocketAddr::from_str("127.0.0.1:8080")?
+        };
+        assert!(allocation.send_to(&[0x00], other_peer).await.is_err());
+
+        allocation
+            .send_to(&[0x00], SocketAddr::from_str(peer)?)
+            .await?;
+
+        client.close().await?;
+    }
+
+    server.close().await?;
+
+    Ok(())
+}
+
+// run_redirector answers every Allocate request received on conn with a 300
+// (Try Alternate) error response redirecting to alternate_server.
+async fn run_redirector(conn: UdpSocket, alternate_server: SocketAddr) {
+    let mut buf = vec![0u8; 1500];
+    while let Ok((n, from)) = conn.recv_from(&mut buf).await {
+        let mut req = Message::new();
+        req.raw = buf[..n].to_vec();
+        if req.decode().is_err() {
+            continue;
+        }
+
+        let mut res = Message::new();
+        if res
+            .build(&[
+                Box::new(req.transaction_id),
+                Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE)),
+                Box::new(ErrorCodeAttribute {
+                    code: CODE_TRY_ALTERNATE,
+                    reason: vec![],
+                }),
+            ])
+            .is_err()
+        {
+            continue;
+        }
+        let addr = AlternateServer {
+            ip: alternate_server.ip(),
+            port: alternate_server.port(),
+        };
+        if addr.add_to_as(&mut res, ATTR_ALTERNATE_SERVER).is_err() {
+            continue;
+        }
+        let _ = conn.send_to(&res.raw, from).await;
+    }
+}
+
+#[tokio::test]
+async fn test_client_allocate_alternate_server() -> Result<()> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let server_addr = conn.local_addr()?;
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "127.0.0.1".to_owned(),
+                net: Arc::new(Net::new(None)),
+            }),
+        }],
+        listener_configs: vec![],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler {}),
+        channel_bind_timeout: Duration::from_secs(0),
+        event_handler: None,
+    })
+    .await?;
+
+    let redirector = UdpSocket::bind("127.0.0.1:0").await?;
+    let redirector_addr = redirector.local_addr()?;
+    tokio::spawn(run_redirector(redirector, server_addr));
+
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: String::new(),
+        turn_serv_addr: redirector_addr.to_string(),
+        username: "foo".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+    client.listen().await?;
+
+    let allocation = client.allocate().await?;
+    assert_eq!(client.turn_server_addr().await, server_addr.to_string());
+    assert!(allocation.local_addr()?.ip().is_loopback());
+
+    allocation.close().await?;
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_allocate_too_many_redirects() -> Result<()> {
+    // a server redirecting to itself
+    let redirector = UdpSocket::bind("127.0.0.1:0").await?;
+    let redirector_addr = redirector.local_addr()?;
+    tokio::spawn(run_redirector(redirector, redirector_addr));
+
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: String::new(),
+        turn_serv_addr: redirector_addr.to_string(),
+        username: "foo".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+    client.listen().await?;
+
+    match client.allocate().await {
+        Err(err) => assert_eq!(err, Error::ErrTooManyRedirects),
+        Ok(_) => panic!("allocation should fail"),
+    }
+
+    client.close().await?;
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use crate::auth::{generate_rest_credentials, rest_username_expiry};
+use crate::error::*;
+
+use async_trait::async_trait;
+use std::time::{Duration, SystemTime};
+
+// RENEWAL_RETRY_INTERVAL is how long a client waits before asking its
+// CredentialProvider again when renewing credentials failed, and the
+// shortest interval between two renewals.
+pub(crate) const RENEWAL_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+// Credentials are a username and password for the long-term credential
+// mechanism, and when the server stops accepting them, if ever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+    pub expires_at: Option<SystemTime>,
+}
+
+// CredentialProvider supplies the credentials a Client authenticates with,
+// e.g. by fetching them from an application server. It is asked again for
+// credentials shortly before the current ones expire.
+#[async_trait]
+pub trait CredentialProvider {
+    async fn credentials(&self) -> Result<Credentials>;
+}
+
+// RestCredentialProvider generates time-limited credentials for user from
+// the secret shared with the TURN server, as in coturn's TURN REST API.
+pub struct RestCredentialProvider {
+    shared_secret: String,
+    user: String,
+    ttl: Duration,
+}
+
+impl RestCredentialProvider {
+    pub fn new(shared_secret: String, user: String, ttl: Duration) -> Self {
+        RestCredentialProvider {
+            shared_secret,
+            user,
+            ttl,
+        }
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for RestCredentialProvider {
+    async fn credentials(&self) -> Result<Credentials> {
+        let (username, password) =
+            generate_rest_credentials(&self.shared_secret, &self.user, self.ttl)?;
+        let expires_at = rest_username_expiry(&username)?;
+        Ok(Credentials {
+            username,
+            password,
+            expires_at: Some(expires_at),
+        })
+    }
+}
+
+// renewal_wait returns how long to wait before renewing credentials that
+// expire at expires_at, renew_before ahead of their expiry.
+pub(crate) fn renewal_wait(expires_at: SystemTime, renew_before: Duration) -> Duration {
+    let renew_at = expires_at
+        .checked_sub(renew_before)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let wait = renew_at
+        .duration_since(SystemTime::now())
+        .unwrap_or_else(|_| Duration::from_secs(0));
+    std::cmp::max(wait, RENEWAL_RETRY_INTERVAL)
+}
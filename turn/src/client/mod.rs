@@ -2,31 +2,39 @@
 mod client_test;
 
 pub mod binding;
+pub mod credentials;
 pub mod periodic_timer;
 pub mod permission;
 pub mod relay_conn;
+pub mod tcp_allocation;
 pub mod transaction;
 
 use crate::error::*;
 use crate::proto::{
-    chandata::*, data::*, lifetime::*, peeraddr::*, relayaddr::*, reqtrans::*, PROTO_UDP,
+    chandata::*, connid::*, lifetime::*, mobility::*, peeraddr::*, relayaddr::*, reqfamily::*,
+    reqtrans::*, Protocol, PROTO_TCP, PROTO_UDP,
 };
 use binding::*;
+use credentials::*;
 use relay_conn::*;
+use tcp_allocation::*;
 use transaction::*;
 
 use std::net::SocketAddr;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use stun::addr::AlternateServer;
 use stun::agent::*;
 use stun::attributes::*;
 use stun::error_code::*;
 use stun::fingerprint::*;
 use stun::integrity::*;
 use stun::message::*;
+use stun::message_view::MessageView;
 use stun::textattrs::*;
 use stun::xoraddr::*;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, oneshot, Mutex};
 use util::{conn::*, vnet::net::*};
 
 use async_trait::async_trait;
@@ -34,6 +42,9 @@ use async_trait::async_trait;
 const DEFAULT_RTO_IN_MS: u16 = 200;
 const MAX_DATA_BUFFER_SIZE: usize = u16::MAX as usize; // message size limit for Chromium
 const MAX_READ_QUEUE_SIZE: usize = 1024;
+// MAX_REDIRECTS limits how many ALTERNATE-SERVER redirects an Allocate
+// request follows.
+const MAX_REDIRECTS: usize = 3;
 
 //              interval [msec]
 // 0: 0 ms      +500
@@ -46,6 +57,9 @@ const MAX_READ_QUEUE_SIZE: usize = 1024;
 // -: 63500 ms  failed
 
 // ClientConfig is a bag of config parameters for Client.
+// conn selects the transport to the server: a UDP socket or StreamConn for
+// `turn:` URIs, and StreamConn::dial_tls (transport=tcp) or DtlsConn::dial
+// (transport=udp) for `turns:` URIs, with the `tls` and `dtls` features.
 pub struct ClientConfig {
     pub stun_serv_addr: String, // STUN server address (e.g. "stun.abc.com:3478")
     pub turn_serv_addr: String, // TURN server addrees (e.g. "turn.abc.com:3478")
@@ -71,6 +85,10 @@ struct ClientInternal {
     binding_mgr: Arc<Mutex<BindingManager>>,
     rto_in_ms: u16,
     read_ch_tx: Arc<Mutex<Option<mpsc::Sender<InboundData>>>>,
+    conn_attempt_tx: Arc<Mutex<Option<mpsc::Sender<ConnectionAttempt>>>>,
+    renewal_close_tx: Option<oneshot::Sender<()>>,
+    on_refresh_failure: Arc<Mutex<Option<OnRefreshFailureHdlrFn>>>,
+    mobility: bool,
 }
 
 #[async_trait]
@@ -90,6 +108,11 @@ impl RelayConnObserver for ClientInternal {
         self.realm.clone()
     }
 
+    // integrity returns the MessageIntegrity of the current credentials
+    fn integrity(&self) -> MessageIntegrity {
+        self.integrity.clone()
+    }
+
     // WriteTo sends data to the specified destination using the base socket.
     async fn write_to(&self, data: &[u8], to: &str) -> std::result::Result<usize, util::Error> {
         let n = self.conn.send_to(data, SocketAddr::from_str(to)?).await?;
@@ -203,9 +226,27 @@ impl ClientInternal {
             },
             integrity: MessageIntegrity::new_short_term_integrity(String::new()),
             read_ch_tx: Arc::new(Mutex::new(None)),
+            conn_attempt_tx: Arc::new(Mutex::new(None)),
+            renewal_close_tx: None,
+            on_refresh_failure: Arc::new(Mutex::new(None)),
+            mobility: false,
         })
     }
 
+    // set_credentials replaces the credentials used by the client and all
+    // its allocations.
+    fn set_credentials(&mut self, credentials: Credentials) {
+        self.username = Username::new(ATTR_USERNAME, credentials.username);
+        self.password = credentials.password;
+        if !self.realm.text.is_empty() {
+            self.integrity = MessageIntegrity::new_long_term_integrity(
+                self.username.text.clone(),
+                self.realm.text.clone(),
+                self.password.clone(),
+            );
+        }
+    }
+
     // stun_server_addr return the STUN server address
     fn stun_server_addr(&self) -> String {
         self.stun_serv_addr.clone()
@@ -219,6 +260,7 @@ impl ClientInternal {
         let stun_serv_str = self.stun_serv_addr.clone();
         let tr_map = Arc::clone(&self.tr_map);
         let read_ch_tx = Arc::clone(&self.read_ch_tx);
+        let conn_attempt_tx = Arc::clone(&self.conn_attempt_tx);
         let binding_mgr = Arc::clone(&self.binding_mgr);
 
         tokio::spawn(async move {
@@ -237,6 +279,7 @@ impl ClientInternal {
 
                 if let Err(err) = ClientInternal::handle_inbound(
                     &read_ch_tx,
+                    &conn_attempt_tx,
                     &buf[..n],
                     from,
                     &stun_serv_str,
@@ -263,6 +306,7 @@ impl ClientInternal {
     // If an error is returned, the caller should discard the packet regardless.
     async fn handle_inbound(
         read_ch_tx: &Arc<Mutex<Option<mpsc::Sender<InboundData>>>>,
+        conn_attempt_tx: &Arc<Mutex<Option<mpsc::Sender<ConnectionAttempt>>>>,
         data: &[u8],
         from: SocketAddr,
         stun_serv_str: &str,
@@ -288,7 +332,8 @@ impl ClientInternal {
         //  - Non-STUN message from the STUN server
 
         if is_message(data) {
-            ClientInternal::handle_stun_message(tr_map, read_ch_tx, data, from).await
+            ClientInternal::handle_stun_message(tr_map, read_ch_tx, conn_attempt_tx, data, from)
+                .await
         } else if ChannelData::is_channel_data(data) {
             ClientInternal::handle_channel_data(binding_mgr, read_ch_tx, data).await
         } else if !stun_serv_str.is_empty() && from.to_string() == *stun_serv_str {
@@ -304,9 +349,23 @@ impl ClientInternal {
     async fn handle_stun_message(
         tr_map: &Arc<Mutex<TransactionMap>>,
         read_ch_tx: &Arc<Mutex<Option<mpsc::Sender<InboundData>>>>,
+        conn_attempt_tx: &Arc<Mutex<Option<mpsc::Sender<ConnectionAttempt>>>>,
         data: &[u8],
         mut from: SocketAddr,
     ) -> Result<()> {
+        // Data indications carry the relayed data, read it in place instead of copying
+        // the packet into a Message
+        let view = MessageView::parse(data)?;
+        if view.typ == MessageType::new(METHOD_DATA, CLASS_INDICATION) {
+            from = view.get_xor_address(ATTR_XOR_PEER_ADDRESS)?;
+            let data = view.get(ATTR_DATA)?;
+
+            log::debug!("data indication received from {}", from);
+
+            let _ = ClientInternal::handle_inbound_relay_conn(read_ch_tx, data, from).await;
+            return Ok(());
+        }
+
         let mut msg = Message::new();
         msg.raw = data.to_vec();
         msg.decode()?;
@@ -320,17 +379,22 @@ impl ClientInternal {
         }
 
         if msg.typ.class == CLASS_INDICATION {
-            if msg.typ.method == METHOD_DATA {
+            if msg.typ.method == METHOD_CONNECTION_ATTEMPT {
                 let mut peer_addr = PeerAddress::default();
                 peer_addr.get_from(&msg)?;
                 from = SocketAddr::new(peer_addr.ip, peer_addr.port);
 
-                let mut data = Data::default();
-                data.get_from(&msg)?;
+                let mut id = ConnectionId::default();
+                id.get_from(&msg)?;
 
-                log::debug!("data indication received from {}", from);
+                log::debug!("connection attempt received from {}", from);
 
-                let _ = ClientInternal::handle_inbound_relay_conn(read_ch_tx, &data.0, from).await;
+                let conn_attempt_tx = conn_attempt_tx.lock().await;
+                if let Some(tx) = &*conn_attempt_tx {
+                    if tx.try_send(ConnectionAttempt { id, from }).is_err() {
+                        log::warn!("connection attempt queue full");
+                    }
+                }
             }
 
             return Ok(());
@@ -423,10 +487,15 @@ impl ClientInternal {
 
     // Close closes this client
     async fn close(&mut self) {
+        self.renewal_close_tx.take();
         {
             let mut read_ch_tx = self.read_ch_tx.lock().await;
             read_ch_tx.take();
         }
+        {
+            let mut conn_attempt_tx = self.conn_attempt_tx.lock().await;
+            conn_attempt_tx.take();
+        }
         {
             let mut tm = self.tr_map.lock().await;
             tm.close_and_delete_all();
@@ -480,8 +549,13 @@ impl ClientInternal {
         bm.find_by_number(ch_num).map(|b| b.addr)
     }
 
-    // Allocate sends a TURN allocation request to the given transport address
-    async fn allocate(&mut self) -> Result<RelayConnConfig> {
+    // Allocate sends a TURN allocation request to the given transport address.
+    // The relayed transport address is of the requested family if any, or of
+    // the server's default family, IPv4, otherwise.
+    async fn allocate(
+        &mut self,
+        requested_family: Option<RequestedAddressFamily>,
+    ) -> Result<RelayConnConfig> {
         {
             let read_ch_tx = self.read_ch_tx.lock().await;
             log::debug!("allocate check: read_ch_tx_opt = {}", read_ch_tx.is_some());
@@ -490,21 +564,117 @@ impl ClientInternal {
             }
         }
 
-        let mut msg = Message::new();
-        msg.build(&[
-            Box::new(TransactionId::new()),
-            Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
-            Box::new(RequestedTransport {
-                protocol: PROTO_UDP,
-            }),
-            Box::new(FINGERPRINT),
-        ])?;
+        let (res, nonce) = self
+            .send_allocate_request(PROTO_UDP, requested_family)
+            .await?;
+        self.relay_conn_config(&res, nonce).await
+    }
+
+    // allocate_tcp sends a TURN allocation request for a TCP relayed transport
+    // address as described in RFC 6062. The client must be connected to the
+    // TURN server over TCP.
+    async fn allocate_tcp(
+        &mut self,
+    ) -> Result<(RelayConnConfig, mpsc::Receiver<ConnectionAttempt>)> {
+        {
+            let read_ch_tx = self.read_ch_tx.lock().await;
+            if read_ch_tx.is_some() {
+                return Err(Error::ErrOneAllocateOnly);
+            }
+        }
+
+        let (res, nonce) = self.send_allocate_request(PROTO_TCP, None).await?;
+
+        let (conn_attempt_tx, conn_attempt_rx) = mpsc::channel(MAX_READ_QUEUE_SIZE);
+        {
+            let mut conn_attempt_tx_opt = self.conn_attempt_tx.lock().await;
+            *conn_attempt_tx_opt = Some(conn_attempt_tx);
+        }
+
+        let config = self.relay_conn_config(&res, nonce).await?;
+        Ok((config, conn_attempt_rx))
+    }
+
+    // send_allocate_request performs the Allocate transaction, following
+    // ALTERNATE-SERVER redirects, and returns the success response along with
+    // the nonce used. Once redirected, the client uses the alternate server
+    // for the allocation and everything else.
+    // https://tools.ietf.org/html/rfc5766#section-6.4
+    async fn send_allocate_request(
+        &mut self,
+        protocol: Protocol,
+        requested_family: Option<RequestedAddressFamily>,
+    ) -> Result<(Message, Nonce)> {
+        let mut redirects = 0;
+        loop {
+            match self
+                .send_allocate_request_to_server(protocol, requested_family)
+                .await?
+            {
+                AllocateResponse::Success(res, nonce) => return Ok((res, nonce)),
+                AllocateResponse::Redirect(alternate_server) => {
+                    // A connection-oriented transport can only reach the
+                    // server it is connected to.
+                    if self.conn.remote_addr().is_some() {
+                        return Err(Error::ErrRedirectOnConnectedTransport);
+                    }
+                    if redirects == MAX_REDIRECTS {
+                        return Err(Error::ErrTooManyRedirects);
+                    }
+                    redirects += 1;
+
+                    log::debug!(
+                        "redirected from {} to {}",
+                        self.turn_serv_addr,
+                        alternate_server
+                    );
+                    self.turn_serv_addr = alternate_server.to_string();
+                }
+            }
+        }
+    }
+
+    // send_allocate_request_to_server performs the Allocate transaction with
+    // the current server, authenticating with the nonce and realm of the first
+    // error response.
+    async fn send_allocate_request_to_server(
+        &mut self,
+        protocol: Protocol,
+        requested_family: Option<RequestedAddressFamily>,
+    ) -> Result<AllocateResponse> {
+        // Mobility is only available over UDP, where the client is not
+        // connected to the server.
+        // https://tools.ietf.org/html/rfc8016#section-3.1
+        let mobility = self.mobility && self.conn.remote_addr().is_none();
+
+        let mut msg = {
+            let mut setters: Vec<Box<dyn Setter>> = vec![
+                Box::new(TransactionId::new()),
+                Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
+                Box::new(RequestedTransport { protocol }),
+            ];
+            if let Some(requested_family) = requested_family {
+                setters.push(Box::new(requested_family));
+            }
+            if mobility {
+                setters.push(Box::new(MobilityTicket::default()));
+            }
+
+            setters.push(Box::new(FINGERPRINT));
+
+            let mut msg = Message::new();
+            msg.build(&setters)?;
+            msg
+        };
 
         log::debug!("client.Allocate call PerformTransaction 1");
         let tr_res = self
             .perform_transaction(&msg, &self.turn_serv_addr.clone(), false)
             .await?;
         let res = tr_res.msg;
+        if let Some(alternate_server) = alternate_server(&res) {
+            return Ok(AllocateResponse::Redirect(alternate_server));
+        }
 
         // Anonymous allocate failed, trying to authenticate.
         let nonce = Nonce::get_from_as(&res, ATTR_NONCE)?;
@@ -517,24 +687,34 @@ impl ClientInternal {
         );
 
         // Trying to authorize.
-        msg.build(&[
-            Box::new(TransactionId::new()),
-            Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
-            Box::new(RequestedTransport {
-                protocol: PROTO_UDP,
-            }),
-            Box::new(self.username.clone()),
-            Box::new(self.realm.clone()),
-            Box::new(nonce.clone()),
-            Box::new(self.integrity.clone()),
-            Box::new(FINGERPRINT),
-        ])?;
+        {
+            let mut setters: Vec<Box<dyn Setter>> = vec![
+                Box::new(TransactionId::new()),
+                Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
+                Box::new(RequestedTransport { protocol }),
+            ];
+            if let Some(requested_family) = requested_family {
+                setters.push(Box::new(requested_family));
+            }
+            if mobility {
+                setters.push(Box::new(MobilityTicket::default()));
+            }
+            setters.push(Box::new(self.username.clone()));
+            setters.push(Box::new(self.realm.clone()));
+            setters.push(Box::new(nonce.clone()));
+            setters.push(Box::new(self.integrity.clone()));
+            setters.push(Box::new(FINGERPRINT));
+            msg.build(&setters)?;
+        }
 
         log::debug!("client.Allocate call PerformTransaction 2");
         let tr_res = self
             .perform_transaction(&msg, &self.turn_serv_addr.clone(), false)
             .await?;
         let res = tr_res.msg;
+        if let Some(alternate_server) = alternate_server(&res) {
+            return Ok(AllocateResponse::Redirect(alternate_server));
+        }
 
         if res.typ.class == CLASS_ERROR_RESPONSE {
             let mut code = ErrorCodeAttribute::default();
@@ -546,14 +726,28 @@ impl ClientInternal {
             }
         }
 
+        Ok(AllocateResponse::Success(res, nonce))
+    }
+
+    // relay_conn_config creates the config of a relayed connection from the
+    // success response of an Allocate request.
+    async fn relay_conn_config(&mut self, res: &Message, nonce: Nonce) -> Result<RelayConnConfig> {
         // Getting relayed addresses from response.
         let mut relayed = RelayedAddress::default();
-        relayed.get_from(&res)?;
+        relayed.get_from(res)?;
         let relayed_addr = SocketAddr::new(relayed.ip, relayed.port);
 
         // Getting lifetime from response
         let mut lifetime = Lifetime::default();
-        lifetime.get_from(&res)?;
+        lifetime.get_from(res)?;
+
+        // Getting the mobility ticket, if the server granted one
+        let mut mobility_ticket = MobilityTicket::default();
+        let mobility_ticket = if mobility_ticket.get_from(res).is_ok() {
+            Some(mobility_ticket)
+        } else {
+            None
+        };
 
         let (read_ch_tx, read_ch_rx) = mpsc::channel(MAX_READ_QUEUE_SIZE);
         {
@@ -564,15 +758,36 @@ impl ClientInternal {
 
         Ok(RelayConnConfig {
             relayed_addr,
-            integrity: self.integrity.clone(),
             nonce,
             lifetime: lifetime.0,
             binding_mgr: Arc::clone(&self.binding_mgr),
             read_ch_rx: Arc::new(Mutex::new(read_ch_rx)),
+            on_refresh_failure: Arc::clone(&self.on_refresh_failure),
+            mobility_ticket,
         })
     }
 }
 
+enum AllocateResponse {
+    Success(Message, Nonce),
+    Redirect(SocketAddr),
+}
+
+// alternate_server returns the server a 300 (Try Alternate) error response
+// redirects to.
+fn alternate_server(res: &Message) -> Option<SocketAddr> {
+    if res.typ.class != CLASS_ERROR_RESPONSE {
+        return None;
+    }
+    let mut code = ErrorCodeAttribute::default();
+    if code.get_from(res).is_err() || code.code != CODE_TRY_ALTERNATE {
+        return None;
+    }
+    let mut addr = AlternateServer::default();
+    addr.get_from_as(res, ATTR_ALTERNATE_SERVER).ok()?;
+    Some(SocketAddr::new(addr.ip, addr.port))
+}
+
 // Client is a STUN server client
 #[derive(Clone)]
 pub struct Client {
@@ -595,12 +810,133 @@ impl Client {
     pub async fn allocate(&self) -> Result<impl Conn> {
         let config = {
             let mut ci = self.client_internal.lock().await;
-            ci.allocate().await?
+            ci.allocate(None).await?
         };
 
         Ok(RelayConn::new(Arc::clone(&self.client_internal), config).await)
     }
 
+    // allocate_with_family allocates a relayed transport address of the
+    // requested address family with REQUESTED-ADDRESS-FAMILY (RFC 6156), e.g.
+    // REQUESTED_FAMILY_IPV6 for an IPv6 relayed address. Peers of the other
+    // family cannot be reached through the allocation.
+    pub async fn allocate_with_family(
+        &self,
+        requested_family: RequestedAddressFamily,
+    ) -> Result<impl Conn> {
+        let config = {
+            let mut ci = self.client_internal.lock().await;
+            ci.allocate(Some(requested_family)).await?
+        };
+
+        Ok(RelayConn::new(Arc::clone(&self.client_internal), config).await)
+    }
+
+    // allocate_tcp creates a TCP allocation as described in RFC 6062. The
+    // client must have been created with a StreamConn to the TURN server.
+    pub async fn allocate_tcp(&self) -> Result<TcpAllocation> {
+        let (config, conn_attempt_rx) = {
+            let mut ci = self.client_internal.lock().await;
+            ci.allocate_tcp().await?
+        };
+
+        TcpAllocation::new(Arc::clone(&self.client_internal), config, conn_attempt_rx).await
+    }
+
+    // set_credential_provider makes the client authenticate with credentials
+    // from provider, asking it for new ones renew_before they expire so that
+    // allocations and permissions keep being refreshed.
+    pub async fn set_credential_provider(
+        &self,
+        provider: Arc<dyn CredentialProvider + Send + Sync>,
+        renew_before: Duration,
+    ) -> Result<()> {
+        let credentials = provider.credentials().await?;
+        let expires_at = credentials.expires_at;
+
+        let (renewal_close_tx, mut renewal_close_rx) = oneshot::channel();
+        {
+            let mut ci = self.client_internal.lock().await;
+            ci.set_credentials(credentials);
+            ci.renewal_close_tx = Some(renewal_close_tx);
+        }
+
+        let mut wait = match expires_at {
+            Some(expires_at) => renewal_wait(expires_at, renew_before),
+            None => return Ok(()),
+        };
+        let client_internal = Arc::downgrade(&self.client_internal);
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = &mut renewal_close_rx => break,
+                }
+
+                match provider.credentials().await {
+                    Ok(credentials) => {
+                        let expires_at = credentials.expires_at;
+                        if !Client::renew_credentials(&client_internal, credentials).await {
+                            break;
+                        }
+                        log::debug!("credentials renewed");
+
+                        wait = match expires_at {
+                            Some(expires_at) => renewal_wait(expires_at, renew_before),
+                            None => break,
+                        };
+                    }
+                    Err(err) => {
+                        log::warn!("failed to renew credentials: {}", err);
+                        wait = RENEWAL_RETRY_INTERVAL;
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    // on_refresh_failure sets a handler called when the client fails to
+    // refresh an allocation, permissions or channel bindings. Allocations,
+    // permissions and channel bindings are otherwise refreshed automatically
+    // for as long as the relayed connection is open.
+    pub async fn on_refresh_failure(&self, f: OnRefreshFailureHdlrFn) {
+        let ci = self.client_internal.lock().await;
+        let mut on_refresh_failure = ci.on_refresh_failure.lock().await;
+        *on_refresh_failure = Some(f);
+    }
+
+    // enable_mobility makes the client ask for a mobility ticket (RFC 8016)
+    // when allocating over UDP. With a ticket, an allocation survives changes
+    // of the client's transport address, e.g. on a handover between networks
+    // or a NAT rebinding: it is moved to the new address on the next refresh,
+    // or as soon as the server no longer recognizes the client.
+    pub async fn enable_mobility(&self) {
+        let mut ci = self.client_internal.lock().await;
+        ci.mobility = true;
+    }
+
+    async fn renew_credentials(
+        client_internal: &Weak<Mutex<ClientInternal>>,
+        credentials: Credentials,
+    ) -> bool {
+        if let Some(ci) = client_internal.upgrade() {
+            let mut ci = ci.lock().await;
+            ci.set_credentials(credentials);
+            true
+        } else {
+            false
+        }
+    }
+
+    // turn_server_addr returns the address of the TURN server the client
+    // uses, which is the last alternate server it was redirected to, if any.
+    pub async fn turn_server_addr(&self) -> String {
+        let ci = self.client_internal.lock().await;
+        ci.turn_serv_addr.clone()
+    }
+
     pub async fn close(&self) -> Result<()> {
         let mut ci = self.client_internal.lock().await;
         ci.close().await;
@@ -12,6 +12,7 @@ use async_trait::async_trait;
 pub enum TimerIdRefresh {
     Alloc,
     Perms,
+    Bindings,
 }
 
 impl Default for TimerIdRefresh {
@@ -7,6 +7,8 @@ use super::periodic_timer::*;
 use super::permission::*;
 use super::transaction::*;
 use crate::proto;
+use crate::proto::connid::ConnectionId;
+use crate::proto::mobility::MobilityTicket;
 use crate::Error;
 
 use stun::agent::*;
@@ -19,28 +21,59 @@ use stun::textattrs::*;
 
 use util::Conn;
 
+use std::future::Future;
 use std::io;
 use std::net::SocketAddr;
+use std::pin::Pin;
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 use tokio::time::{Duration, Instant};
 
 use async_trait::async_trait;
 
-const PERM_REFRESH_INTERVAL: Duration = Duration::from_secs(120);
-const MAX_RETRY_ATTEMPTS: u16 = 3;
+// Permissions expire after 5 minutes and channel bindings after 10 minutes
+// (RFC 5766 Section 8 and 11), so both are refreshed well before that.
+pub(crate) const PERM_REFRESH_INTERVAL: Duration = Duration::from_secs(4 * 60);
+pub(crate) const BINDING_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+pub(crate) const BINDING_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+pub(crate) const MAX_RETRY_ATTEMPTS: u16 = 3;
 
 pub(crate) struct InboundData {
     pub(crate) data: Vec<u8>,
     pub(crate) from: SocketAddr,
 }
 
+// RefreshKind tells what a client failed to refresh.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RefreshKind {
+    Allocation,
+    Permission,
+    ChannelBinding,
+}
+
+// RefreshFailure reports an allocation, permissions or a channel binding the
+// client could not refresh. Unless the application reacts, e.g. by allocating
+// again, they expire and data stops flowing to the peers.
+#[derive(Debug)]
+pub struct RefreshFailure {
+    pub kind: RefreshKind,
+    pub relayed_addr: SocketAddr,
+    // peers whose permissions or channel binding could not be refreshed
+    pub peers: Vec<SocketAddr>,
+    pub error: Error,
+}
+
+pub type OnRefreshFailureHdlrFn = Box<
+    dyn (FnMut(RefreshFailure) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync,
+>;
+
 // UDPConnObserver is an interface to UDPConn observer
 #[async_trait]
 pub trait RelayConnObserver {
     fn turn_server_addr(&self) -> String;
     fn username(&self) -> Username;
     fn realm(&self) -> Realm;
+    fn integrity(&self) -> MessageIntegrity;
     async fn write_to(&self, data: &[u8], to: &str) -> Result<usize, util::Error>;
     async fn perform_transaction(
         &mut self,
@@ -53,11 +86,12 @@ pub trait RelayConnObserver {
 // RelayConnConfig is a set of configuration params use by NewUDPConn
 pub(crate) struct RelayConnConfig {
     pub(crate) relayed_addr: SocketAddr,
-    pub(crate) integrity: MessageIntegrity,
     pub(crate) nonce: Nonce,
     pub(crate) lifetime: Duration,
     pub(crate) binding_mgr: Arc<Mutex<BindingManager>>,
     pub(crate) read_ch_rx: Arc<Mutex<mpsc::Receiver<InboundData>>>,
+    pub(crate) on_refresh_failure: Arc<Mutex<Option<OnRefreshFailureHdlrFn>>>,
+    pub(crate) mobility_ticket: Option<MobilityTicket>,
 }
 
 pub struct RelayConnInternal<T: 'static + RelayConnObserver + Send + Sync> {
@@ -65,9 +99,12 @@ pub struct RelayConnInternal<T: 'static + RelayConnObserver + Send + Sync> {
     relayed_addr: SocketAddr,
     perm_map: PermissionMap,
     binding_mgr: Arc<Mutex<BindingManager>>,
-    integrity: MessageIntegrity,
     nonce: Nonce,
     lifetime: Duration,
+    on_refresh_failure: Arc<Mutex<Option<OnRefreshFailureHdlrFn>>>,
+    // mobility_ticket is sent with every Refresh request so that the
+    // allocation follows the client when its transport address changes
+    mobility_ticket: Option<MobilityTicket>,
 }
 
 // RelayConn is the implementation of the Conn interfaces for UDP Relayed network connections.
@@ -77,6 +114,7 @@ pub struct RelayConn<T: 'static + RelayConnObserver + Send + Sync> {
     relay_conn: Arc<Mutex<RelayConnInternal<T>>>,
     refresh_alloc_timer: PeriodicTimer,
     refresh_perms_timer: PeriodicTimer,
+    refresh_bindings_timer: PeriodicTimer,
 }
 
 impl<T: 'static + RelayConnObserver + Send + Sync> RelayConn<T> {
@@ -87,6 +125,10 @@ impl<T: 'static + RelayConnObserver + Send + Sync> RelayConn<T> {
         let c = RelayConn {
             refresh_alloc_timer: PeriodicTimer::new(TimerIdRefresh::Alloc, config.lifetime / 2),
             refresh_perms_timer: PeriodicTimer::new(TimerIdRefresh::Perms, PERM_REFRESH_INTERVAL),
+            refresh_bindings_timer: PeriodicTimer::new(
+                TimerIdRefresh::Bindings,
+                BINDING_CHECK_INTERVAL,
+            ),
             relayed_addr: config.relayed_addr,
             read_ch_rx: Arc::clone(&config.read_ch_rx),
             relay_conn: Arc::new(Mutex::new(RelayConnInternal::new(obs, config))),
@@ -94,6 +136,7 @@ impl<T: 'static + RelayConnObserver + Send + Sync> RelayConn<T> {
 
         let rci1 = Arc::clone(&c.relay_conn);
         let rci2 = Arc::clone(&c.relay_conn);
+        let rci3 = Arc::clone(&c.relay_conn);
 
         if c.refresh_alloc_timer.start(rci1).await {
             log::debug!("refresh_alloc_timer started");
@@ -101,6 +144,9 @@ impl<T: 'static + RelayConnObserver + Send + Sync> RelayConn<T> {
         if c.refresh_perms_timer.start(rci2).await {
             log::debug!("refresh_perms_timer started");
         }
+        if c.refresh_bindings_timer.start(rci3).await {
+            log::debug!("refresh_bindings_timer started");
+        }
 
         c
     }
@@ -180,6 +226,7 @@ impl<T: RelayConnObserver + Send + Sync> Conn for RelayConn<T> {
     async fn close(&self) -> Result<(), util::Error> {
         self.refresh_alloc_timer.stop().await;
         self.refresh_perms_timer.stop().await;
+        self.refresh_bindings_timer.stop().await;
 
         let mut relay_conn = self.relay_conn.lock().await;
         let _ = relay_conn
@@ -192,15 +239,16 @@ impl<T: RelayConnObserver + Send + Sync> Conn for RelayConn<T> {
 
 impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
     // new creates a new instance of UDPConn
-    fn new(obs: Arc<Mutex<T>>, config: RelayConnConfig) -> Self {
+    pub(crate) fn new(obs: Arc<Mutex<T>>, config: RelayConnConfig) -> Self {
         RelayConnInternal {
             obs,
             relayed_addr: config.relayed_addr,
             perm_map: PermissionMap::new(),
             binding_mgr: config.binding_mgr,
-            integrity: config.integrity,
             nonce: config.nonce,
             lifetime: config.lifetime,
+            on_refresh_failure: config.on_refresh_failure,
+            mobility_ticket: config.mobility_ticket,
         }
     }
 
@@ -210,6 +258,8 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
     // see SetDeadline and SetWriteDeadline.
     // On packet-oriented connections, write timeouts are rare.
     async fn send_to(&mut self, p: &[u8], addr: SocketAddr) -> Result<usize, Error> {
+        self.check_peer_family(&addr)?;
+
         // check if we have a permission for the destination IP addr
         let perm = if let Some(perm) = self.perm_map.find(&addr) {
             Arc::clone(perm)
@@ -231,7 +281,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
         result?;
 
         let number = {
-            let (bind_st, bind_at, bind_number, bind_addr) = {
+            let (bind_st, bind_number, bind_addr) = {
                 let mut binding_mgr = self.binding_mgr.lock().await;
                 let b = if let Some(b) = binding_mgr.find_by_addr(&addr) {
                     b
@@ -240,7 +290,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                         .create(addr)
                         .ok_or_else(|| Error::Other("Addr not found".to_owned()))?
                 };
-                (b.state(), b.refreshed_at(), b.number, b.addr)
+                (b.state(), b.number, b.addr)
             };
 
             if bind_st == BindingState::Idle
@@ -254,7 +304,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                     let binding_mgr = Arc::clone(&self.binding_mgr);
                     let rc_obs = Arc::clone(&self.obs);
                     let nonce = self.nonce.clone();
-                    let integrity = self.integrity.clone();
+                    let integrity = self.obs.lock().await.integrity();
                     {
                         let mut bm = binding_mgr.lock().await;
                         if let Some(b) = bm.get_by_addr(&bind_addr) {
@@ -283,6 +333,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                                 // keep going...
                                 log::warn!("bind() failed: {}", err);
                             } else if let Some(b) = bm.get_by_addr(&bind_addr) {
+                                b.set_refreshed_at(Instant::now());
                                 b.set_state(BindingState::Ready);
                             }
                         }
@@ -306,48 +357,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                 return Ok(obs.write_to(&msg.raw, &turn_server_addr).await?);
             }
 
-            // binding is either ready
-
-            // check if the binding needs a refresh
-            if bind_st == BindingState::Ready
-                && Instant::now()
-                    .checked_duration_since(bind_at)
-                    .unwrap_or_else(|| Duration::from_secs(0))
-                    > Duration::from_secs(5 * 60)
-            {
-                let binding_mgr = Arc::clone(&self.binding_mgr);
-                let rc_obs = Arc::clone(&self.obs);
-                let nonce = self.nonce.clone();
-                let integrity = self.integrity.clone();
-                {
-                    let mut bm = binding_mgr.lock().await;
-                    if let Some(b) = bm.get_by_addr(&bind_addr) {
-                        b.set_state(BindingState::Refresh);
-                    }
-                }
-                tokio::spawn(async move {
-                    let result =
-                        RelayConnInternal::bind(rc_obs, bind_addr, bind_number, nonce, integrity)
-                            .await;
-
-                    {
-                        let mut bm = binding_mgr.lock().await;
-                        if let Err(err) = result {
-                            if Error::ErrUnexpectedResponse != err {
-                                bm.delete_by_addr(&bind_addr);
-                            } else if let Some(b) = bm.get_by_addr(&bind_addr) {
-                                b.set_state(BindingState::Failed);
-                            }
-
-                            // keep going...
-                            log::warn!("bind() for refresh failed: {}", err);
-                        } else if let Some(b) = bm.get_by_addr(&bind_addr) {
-                            b.set_refreshed_at(Instant::now());
-                            b.set_state(BindingState::Ready);
-                        }
-                    }
-                });
-            }
+            // binding is either ready, or being refreshed by refresh_bindings_timer
 
             bind_number
         };
@@ -403,7 +413,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                 setters.push(Box::new(obs.username()));
                 setters.push(Box::new(obs.realm()));
                 setters.push(Box::new(self.nonce.clone()));
-                setters.push(Box::new(self.integrity.clone()));
+                setters.push(Box::new(obs.integrity()));
                 setters.push(Box::new(FINGERPRINT));
 
                 let mut msg = Message::new();
@@ -430,6 +440,9 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
             } else if code.code == CODE_STALE_NONCE {
                 self.set_nonce_from_msg(&res);
                 return Err(Error::ErrTryAgain);
+            } else if code.code == CODE_ALLOC_MISMATCH && self.mobility_ticket.is_some() {
+                self.move_allocation().await?;
+                return Err(Error::ErrTryAgain);
             } else {
                 return Err(Error::Other(format!("{} (error {})", res.typ, code)));
             }
@@ -438,6 +451,117 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
         Ok(())
     }
 
+    // permit creates permissions for addrs and keeps refreshing them. It is used
+    // by TCP allocations, which never send data with send_to.
+    pub(crate) async fn permit(&mut self, addrs: &[SocketAddr]) -> Result<(), Error> {
+        for addr in addrs {
+            self.check_peer_family(addr)?;
+        }
+
+        let mut result = Ok(());
+        for _ in 0..MAX_RETRY_ATTEMPTS {
+            result = self.create_permissions(addrs).await;
+            if let Err(err) = &result {
+                if Error::ErrTryAgain != *err {
+                    break;
+                }
+            }
+        }
+        result?;
+
+        for addr in addrs {
+            if self.perm_map.find(addr).is_none() {
+                let perm = Arc::new(Permission::default());
+                perm.set_state(PermState::Permitted);
+                self.perm_map.insert(addr, perm);
+            }
+        }
+
+        Ok(())
+    }
+
+    // connect asks the server to open a TCP connection to peer and returns
+    // the id of the connection to bind with ConnectionBind.
+    // https://tools.ietf.org/html/rfc6062#section-4.3
+    pub(crate) async fn connect(&mut self, peer: SocketAddr) -> Result<ConnectionId, Error> {
+        self.check_peer_family(&peer)?;
+
+        for _ in 0..MAX_RETRY_ATTEMPTS {
+            let msg = self
+                .build_authenticated(vec![
+                    Box::new(TransactionId::new()),
+                    Box::new(proto::connect_request()),
+                    Box::new(socket_addr2peer_address(&peer)),
+                ])
+                .await?;
+
+            let res = {
+                let mut obs = self.obs.lock().await;
+                let turn_server_addr = obs.turn_server_addr();
+
+                log::debug!("TcpAllocation.connect call PerformTransaction 1");
+                let tr_res = obs
+                    .perform_transaction(&msg, &turn_server_addr, false)
+                    .await?;
+
+                tr_res.msg
+            };
+
+            if res.typ.class == CLASS_ERROR_RESPONSE {
+                let mut code = ErrorCodeAttribute::default();
+                let result = code.get_from(&res);
+                if result.is_err() {
+                    return Err(Error::Other(format!("{}", res.typ)));
+                } else if code.code == CODE_STALE_NONCE {
+                    self.set_nonce_from_msg(&res);
+                    continue;
+                } else {
+                    return Err(Error::Other(format!("{} (error {})", res.typ, code)));
+                }
+            }
+
+            let mut id = ConnectionId::default();
+            id.get_from(&res)?;
+            return Ok(id);
+        }
+
+        Err(Error::ErrTryAgain)
+    }
+
+    // build_authenticated builds a request from setters, adding the long-term
+    // credentials of the allocation.
+    pub(crate) async fn build_authenticated(
+        &self,
+        setters: Vec<Box<dyn Setter + Send + Sync>>,
+    ) -> Result<Message, Error> {
+        let (username, realm, integrity) = {
+            let obs = self.obs.lock().await;
+            (obs.username(), obs.realm(), obs.integrity())
+        };
+
+        let mut setters: Vec<Box<dyn Setter>> =
+            setters.into_iter().map(|s| s as Box<dyn Setter>).collect();
+        setters.push(Box::new(username));
+        setters.push(Box::new(realm));
+        setters.push(Box::new(self.nonce.clone()));
+        setters.push(Box::new(integrity));
+        setters.push(Box::new(FINGERPRINT));
+
+        let mut msg = Message::new();
+        msg.build(&setters)?;
+        Ok(msg)
+    }
+
+    // check_peer_family fails if peer cannot be reached from the relayed
+    // transport address, which only relays to peers of its own address family.
+    fn check_peer_family(&self, peer: &SocketAddr) -> Result<(), Error> {
+        if peer.is_ipv4() != self.relayed_addr.is_ipv4() {
+            Err(Error::ErrPeerAddressFamilyMismatch)
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn set_nonce_from_msg(&mut self, msg: &Message) {
         // Update nonce
         match Nonce::get_from_as(msg, ATTR_NONCE) {
@@ -464,17 +588,27 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
         let res = {
             let mut obs = self.obs.lock().await;
 
-            let mut msg = Message::new();
-            msg.build(&[
-                Box::new(TransactionId::new()),
-                Box::new(MessageType::new(METHOD_REFRESH, CLASS_REQUEST)),
-                Box::new(proto::lifetime::Lifetime(lifetime)),
-                Box::new(obs.username()),
-                Box::new(obs.realm()),
-                Box::new(self.nonce.clone()),
-                Box::new(self.integrity.clone()),
-                Box::new(FINGERPRINT),
-            ])?;
+            let msg = {
+                let mut setters: Vec<Box<dyn Setter>> = vec![
+                    Box::new(TransactionId::new()),
+                    Box::new(MessageType::new(METHOD_REFRESH, CLASS_REQUEST)),
+                    Box::new(proto::lifetime::Lifetime(lifetime)),
+                ];
+                if let Some(mobility_ticket) = &self.mobility_ticket {
+                    if lifetime != Duration::from_secs(0) {
+                        setters.push(Box::new(mobility_ticket.clone()));
+                    }
+                }
+                setters.push(Box::new(obs.username()));
+                setters.push(Box::new(obs.realm()));
+                setters.push(Box::new(self.nonce.clone()));
+                setters.push(Box::new(obs.integrity()));
+                setters.push(Box::new(FINGERPRINT));
+
+                let mut msg = Message::new();
+                msg.build(&setters)?;
+                msg
+            };
 
             log::debug!("send refresh request (dont_wait={})", dont_wait);
             let turn_server_addr = obs.turn_server_addr();
@@ -501,7 +635,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                 self.set_nonce_from_msg(&res);
                 return Err(Error::ErrTryAgain);
             } else {
-                return Ok(());
+                return Err(Error::Other(format!("{} (error {})", res.typ, code)));
             }
         }
 
@@ -511,9 +645,36 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
 
         self.lifetime = updated_lifetime.0;
         log::debug!("updated lifetime: {} seconds", self.lifetime.as_secs());
+
+        // Each successful Refresh invalidates the previous ticket.
+        if self.mobility_ticket.is_some() {
+            let mut mobility_ticket = MobilityTicket::default();
+            if mobility_ticket.get_from(&res).is_ok() {
+                self.mobility_ticket = Some(mobility_ticket);
+            }
+        }
         Ok(())
     }
 
+    // move_allocation moves the allocation to the current transport address
+    // of the client, which the server did not know the allocation for, by
+    // refreshing it with the mobility ticket.
+    // https://tools.ietf.org/html/rfc8016#section-3.2
+    async fn move_allocation(&mut self) -> Result<(), Error> {
+        log::debug!("allocation mismatch, moving the allocation to the new client address");
+        let lifetime = self.lifetime;
+        let mut result = Ok(());
+        for _ in 0..MAX_RETRY_ATTEMPTS {
+            result = self.refresh_allocation(lifetime, false).await;
+            if let Err(err) = &result {
+                if Error::ErrTryAgain != *err {
+                    break;
+                }
+            }
+        }
+        result
+    }
+
     async fn refresh_permissions(&mut self) -> Result<(), Error> {
         let addrs = self.perm_map.addrs();
         if addrs.is_empty() {
@@ -532,6 +693,141 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
         Ok(())
     }
 
+    // refresh_bindings refreshes the ready channel bindings that were last
+    // refreshed BINDING_REFRESH_INTERVAL ago, returning the peers whose binding
+    // could not be refreshed.
+    async fn refresh_bindings(&mut self) -> Result<(), (Vec<SocketAddr>, Error)> {
+        let bindings = {
+            let mut bm = self.binding_mgr.lock().await;
+            let now = Instant::now();
+            let bindings: Vec<Binding> = bm
+                .ready_bindings()
+                .into_iter()
+                .filter(|b| {
+                    now.checked_duration_since(b.refreshed_at())
+                        .unwrap_or_else(|| Duration::from_secs(0))
+                        >= BINDING_REFRESH_INTERVAL
+                })
+                .collect();
+            for b in &bindings {
+                if let Some(b) = bm.get_by_addr(&b.addr) {
+                    b.set_state(BindingState::Refresh);
+                }
+            }
+            bindings
+        };
+        if bindings.is_empty() {
+            log::debug!("no channel binding to refresh");
+            return Ok(());
+        }
+
+        let mut failed = vec![];
+        let mut last_err = None;
+        for b in bindings {
+            let mut result = Ok(());
+            for _ in 0..MAX_RETRY_ATTEMPTS {
+                result = self.refresh_binding(b.addr, b.number).await;
+                if let Err(err) = &result {
+                    if Error::ErrTryAgain != *err {
+                        break;
+                    }
+                }
+            }
+
+            let mut bm = self.binding_mgr.lock().await;
+            match result {
+                Ok(()) => {
+                    if let Some(b) = bm.get_by_addr(&b.addr) {
+                        b.set_refreshed_at(Instant::now());
+                        b.set_state(BindingState::Ready);
+                    }
+                }
+                Err(err) => {
+                    log::warn!("fail to refresh channel binding {}: {}", b.addr, err);
+                    if let Some(b) = bm.get_by_addr(&b.addr) {
+                        b.set_state(BindingState::Failed);
+                    }
+                    failed.push(b.addr);
+                    last_err = Some(err);
+                }
+            }
+        }
+
+        match last_err {
+            Some(err) => Err((failed, err)),
+            None => {
+                log::debug!("refresh channel bindings successful");
+                Ok(())
+            }
+        }
+    }
+
+    async fn refresh_binding(
+        &mut self,
+        bind_addr: SocketAddr,
+        bind_number: u16,
+    ) -> Result<(), Error> {
+        let msg = self
+            .build_authenticated(vec![
+                Box::new(TransactionId::new()),
+                Box::new(MessageType::new(METHOD_CHANNEL_BIND, CLASS_REQUEST)),
+                Box::new(socket_addr2peer_address(&bind_addr)),
+                Box::new(proto::channum::ChannelNumber(bind_number)),
+            ])
+            .await?;
+
+        let res = {
+            let mut obs = self.obs.lock().await;
+            let turn_server_addr = obs.turn_server_addr();
+            let tr_res = obs
+                .perform_transaction(&msg, &turn_server_addr, false)
+                .await?;
+            tr_res.msg
+        };
+
+        if res.typ.class == CLASS_ERROR_RESPONSE {
+            let mut code = ErrorCodeAttribute::default();
+            let result = code.get_from(&res);
+            if result.is_err() {
+                return Err(Error::Other(format!("{}", res.typ)));
+            } else if code.code == CODE_STALE_NONCE {
+                self.set_nonce_from_msg(&res);
+                return Err(Error::ErrTryAgain);
+            } else if code.code == CODE_ALLOC_MISMATCH && self.mobility_ticket.is_some() {
+                self.move_allocation().await?;
+                return Err(Error::ErrTryAgain);
+            } else {
+                return Err(Error::Other(format!("{} (error {})", res.typ, code)));
+            }
+        }
+
+        log::debug!("channel binding refreshed: {} {}", bind_addr, bind_number);
+        Ok(())
+    }
+
+    // notify_refresh_failure calls the OnRefreshFailure handler, if any, in
+    // its own task so that it may use the client and its connections.
+    async fn notify_refresh_failure(
+        &self,
+        kind: RefreshKind,
+        peers: Vec<SocketAddr>,
+        error: Error,
+    ) {
+        let failure = RefreshFailure {
+            kind,
+            relayed_addr: self.relayed_addr,
+            peers,
+            error,
+        };
+        let on_refresh_failure = Arc::clone(&self.on_refresh_failure);
+        tokio::spawn(async move {
+            let mut handler = on_refresh_failure.lock().await;
+            if let Some(f) = &mut *handler {
+                f(failure).await;
+            }
+        });
+    }
+
     async fn bind(
         rc_obs: Arc<Mutex<T>>,
         bind_addr: SocketAddr,
@@ -598,11 +894,14 @@ impl<T: RelayConnObserver + Send + Sync> PeriodicTimerTimeoutHandler for RelayCo
                         }
                     }
                 }
-                if result.is_err() {
+                if let Err(err) = result {
                     log::warn!("refresh allocation failed");
+                    self.notify_refresh_failure(RefreshKind::Allocation, vec![], err)
+                        .await;
                 }
             }
             TimerIdRefresh::Perms => {
+                let peers = self.perm_map.addrs();
                 let mut result = Ok(());
                 for _ in 0..MAX_RETRY_ATTEMPTS {
                     result = self.refresh_permissions().await;
@@ -612,8 +911,17 @@ impl<T: RelayConnObserver + Send + Sync> PeriodicTimerTimeoutHandler for RelayCo
                         }
                     }
                 }
-                if result.is_err() {
+                if let Err(err) = result {
                     log::warn!("refresh permissions failed");
+                    self.notify_refresh_failure(RefreshKind::Permission, peers, err)
+                        .await;
+                }
+            }
+            TimerIdRefresh::Bindings => {
+                if let Err((peers, err)) = self.refresh_bindings().await {
+                    log::warn!("refresh channel bindings failed");
+                    self.notify_refresh_failure(RefreshKind::ChannelBinding, peers, err)
+                        .await;
                 }
             }
         }
@@ -7,6 +7,7 @@ struct DummyRelayConnObserver {
     turn_server_addr: String,
     username: Username,
     realm: Realm,
+    integrity: MessageIntegrity,
 }
 
 #[async_trait]
@@ -23,6 +24,10 @@ impl RelayConnObserver for DummyRelayConnObserver {
         self.realm.clone()
     }
 
+    fn integrity(&self) -> MessageIntegrity {
+        self.integrity.clone()
+    }
+
     async fn write_to(&self, _data: &[u8], _to: &str) -> std::result::Result<usize, util::Error> {
         Ok(0)
     }
@@ -43,17 +48,19 @@ async fn test_relay_conn() -> Result<()> {
         turn_server_addr: String::new(),
         username: Username::new(ATTR_USERNAME, "username".to_owned()),
         realm: Realm::new(ATTR_REALM, "realm".to_owned()),
+        integrity: MessageIntegrity::default(),
     };
 
     let (_read_ch_tx, read_ch_rx) = mpsc::channel(100);
 
     let config = RelayConnConfig {
         relayed_addr: SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 0),
-        integrity: MessageIntegrity::default(),
         nonce: Nonce::new(ATTR_NONCE, "nonce".to_owned()),
         lifetime: Duration::from_secs(0),
         binding_mgr: Arc::new(Mutex::new(BindingManager::new())),
         read_ch_rx: Arc::new(Mutex::new(read_ch_rx)),
+        on_refresh_failure: Arc::new(Mutex::new(None)),
+        mobility_ticket: None,
     };
 
     let rc = RelayConn::new(Arc::new(Mutex::new(obs)), config).await;
@@ -70,7 +77,7 @@ async fn test_relay_conn() -> Result<()> {
     //let binding_mgr = Arc::clone(&rci.binding_mgr);
     let rc_obs = Arc::clone(&rci.obs);
     let nonce = rci.nonce.clone();
-    let integrity = rci.integrity.clone();
+    let integrity = rci.obs.lock().await.integrity();
 
     if let Err(err) =
         RelayConnInternal::bind(rc_obs, bind_addr, bind_number, nonce, integrity).await
@@ -82,3 +89,107 @@ async fn test_relay_conn() -> Result<()> {
 
     Ok(())
 }
+
+fn new_relay_conn_internal(
+    on_refresh_failure: Arc<Mutex<Option<OnRefreshFailureHdlrFn>>>,
+) -> RelayConnInternal<DummyRelayConnObserver> {
+    let obs = DummyRelayConnObserver {
+        turn_server_addr: String::new(),
+        username: Username::new(ATTR_USERNAME, "username".to_owned()),
+        realm: Realm::new(ATTR_REALM, "realm".to_owned()),
+        integrity: MessageIntegrity::default(),
+    };
+
+    let (_read_ch_tx, read_ch_rx) = mpsc::channel(100);
+
+    let config = RelayConnConfig {
+        relayed_addr: SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 0),
+        nonce: Nonce::new(ATTR_NONCE, "nonce".to_owned()),
+        lifetime: Duration::from_secs(600),
+        binding_mgr: Arc::new(Mutex::new(BindingManager::new())),
+        read_ch_rx: Arc::new(Mutex::new(read_ch_rx)),
+        on_refresh_failure,
+        mobility_ticket: None,
+    };
+
+    RelayConnInternal::new(Arc::new(Mutex::new(obs)), config)
+}
+
+#[tokio::test]
+async fn test_relay_conn_refresh_failure() -> Result<()> {
+    let (failure_tx, mut failure_rx) = mpsc::channel(3);
+    let on_refresh_failure: Arc<Mutex<Option<OnRefreshFailureHdlrFn>>> =
+        Arc::new(Mutex::new(Some(Box::new(
+            move |failure: RefreshFailure| -> Pin<Box<dyn Future<Output = ()> + Send + 'static>> {
+                let failure_tx = failure_tx.clone();
+                Box::pin(async move {
+                    let _ = failure_tx.send(failure).await;
+                })
+            },
+        ))));
+    let mut rci = new_relay_conn_internal(on_refresh_failure);
+
+    let peer = SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 1234);
+    let perm = Arc::new(Permission::default());
+    perm.set_state(PermState::Permitted);
+    rci.perm_map.insert(&peer, perm);
+    {
+        let mut bm = rci.binding_mgr.lock().await;
+        let number = bm.create(peer).unwrap().number;
+        let b = bm.get_by_number(number).unwrap();
+        b.set_state(BindingState::Ready);
+        if let Some(refreshed_at) = Instant::now().checked_sub(BINDING_REFRESH_INTERVAL) {
+            b.set_refreshed_at(refreshed_at);
+        }
+    }
+
+    rci.on_timeout(TimerIdRefresh::Alloc).await;
+    let failure = failure_rx.recv().await.unwrap();
+    assert_eq!(failure.kind, RefreshKind::Allocation);
+    assert!(failure.peers.is_empty());
+    assert_eq!(failure.error, Error::ErrFakeErr);
+
+    rci.on_timeout(TimerIdRefresh::Perms).await;
+    let failure = failure_rx.recv().await.unwrap();
+    assert_eq!(failure.kind, RefreshKind::Permission);
+    // permissions are installed per IP address, regardless of the port
+    assert_eq!(failure.peers, vec![SocketAddr::new(peer.ip(), 0)]);
+
+    rci.on_timeout(TimerIdRefresh::Bindings).await;
+    let failure = failure_rx.recv().await.unwrap();
+    assert_eq!(failure.kind, RefreshKind::ChannelBinding);
+    assert_eq!(failure.peers, vec![peer]);
+    {
+        let bm = rci.binding_mgr.lock().await;
+        assert_eq!(
+            bm.find_by_addr(&peer).unwrap().state(),
+            BindingState::Failed,
+            "binding should be marked failed"
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_relay_conn_refresh_recent_bindings() -> Result<()> {
+    let mut rci = new_relay_conn_internal(Arc::new(Mutex::new(None)));
+
+    let peer = SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 1234);
+    {
+        let mut bm = rci.binding_mgr.lock().await;
+        let number = bm.create(peer).unwrap().number;
+        bm.get_by_number(number)
+            .unwrap()
+            .set_state(BindingState::Ready);
+    }
+
+    // a binding refreshed less than BINDING_REFRESH_INTERVAL ago is left alone
+    assert!(rci.refresh_bindings().await.is_ok());
+    {
+        let bm = rci.binding_mgr.lock().await;
+        assert_eq!(bm.find_by_addr(&peer).unwrap().state(), BindingState::Ready);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use super::periodic_timer::*;
+use super::relay_conn::*;
+use super::ClientInternal;
+use crate::error::*;
+use crate::proto::connection_bind_request;
+use crate::proto::connid::ConnectionId;
+use crate::stream_conn::{read_frame, MAX_FRAME_SIZE};
+
+use stun::agent::*;
+use stun::error_code::*;
+use stun::message::*;
+
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Mutex};
+
+// ConnectionAttempt is a connection from a peer announced by the server.
+pub(crate) struct ConnectionAttempt {
+    pub(crate) id: ConnectionId,
+    pub(crate) from: SocketAddr,
+}
+
+// TcpAllocation is a TCP allocation as described in RFC 6062. Each
+// connection to a peer is carried by its own TCP connection to the server,
+// which is returned as a plain TcpStream once it is bound.
+pub struct TcpAllocation {
+    relayed_addr: SocketAddr,
+    turn_serv_addr: SocketAddr,
+    conn_attempt_rx: Mutex<mpsc::Receiver<ConnectionAttempt>>,
+    relay_conn: Arc<Mutex<RelayConnInternal<ClientInternal>>>,
+    refresh_alloc_timer: PeriodicTimer,
+    refresh_perms_timer: PeriodicTimer,
+}
+
+impl TcpAllocation {
+    pub(super) async fn new(
+        obs: Arc<Mutex<ClientInternal>>,
+        config: RelayConnConfig,
+        conn_attempt_rx: mpsc::Receiver<ConnectionAttempt>,
+    ) -> Result<Self> {
+        log::debug!("initial lifetime: {} seconds", config.lifetime.as_secs());
+
+        let turn_serv_addr = {
+            let ci = obs.lock().await;
+            SocketAddr::from_str(&ci.turn_server_addr())?
+        };
+
+        let a = TcpAllocation {
+            refresh_alloc_timer: PeriodicTimer::new(TimerIdRefresh::Alloc, config.lifetime / 2),
+            refresh_perms_timer: PeriodicTimer::new(TimerIdRefresh::Perms, PERM_REFRESH_INTERVAL),
+            relayed_addr: config.relayed_addr,
+            turn_serv_addr,
+            conn_attempt_rx: Mutex::new(conn_attempt_rx),
+            relay_conn: Arc::new(Mutex::new(RelayConnInternal::new(obs, config))),
+        };
+
+        if a.refresh_alloc_timer.start(Arc::clone(&a.relay_conn)).await {
+            log::debug!("refresh_alloc_timer started");
+        }
+        if a.refresh_perms_timer.start(Arc::clone(&a.relay_conn)).await {
+            log::debug!("refresh_perms_timer started");
+        }
+
+        Ok(a)
+    }
+
+    // relayed_addr returns the relayed transport address peers connect to.
+    pub fn relayed_addr(&self) -> SocketAddr {
+        self.relayed_addr
+    }
+
+    // create_permission installs permissions for peers, which is required
+    // both to connect to them and to accept connections from them.
+    pub async fn create_permission(&self, peers: &[SocketAddr]) -> Result<()> {
+        let mut relay_conn = self.relay_conn.lock().await;
+        relay_conn.permit(peers).await
+    }
+
+    // connect opens a connection to peer through the relay.
+    pub async fn connect(&self, peer: SocketAddr) -> Result<TcpStream> {
+        let id = {
+            let mut relay_conn = self.relay_conn.lock().await;
+            relay_conn.connect(peer).await?
+        };
+        self.bind_connection(id).await
+    }
+
+    // accept waits for a peer to connect to the relayed transport address.
+    pub async fn accept(&self) -> Result<(TcpStream, SocketAddr)> {
+        let attempt = {
+            let mut conn_attempt_rx = self.conn_attempt_rx.lock().await;
+            conn_attempt_rx
+                .recv()
+                .await
+                .ok_or(Error::ErrAlreadyClosed)?
+        };
+        let stream = self.bind_connection(attempt.id).await?;
+        Ok((stream, attempt.from))
+    }
+
+    // close deletes the allocation. Established peer connections are not
+    // affected.
+    pub async fn close(&self) -> Result<()> {
+        self.refresh_alloc_timer.stop().await;
+        self.refresh_perms_timer.stop().await;
+
+        let mut relay_conn = self.relay_conn.lock().await;
+        relay_conn.close().await
+    }
+
+    // bind_connection opens a data connection to the server and associates it
+    // with the peer connection id, as described in RFC 6062 Section 4.4.
+    async fn bind_connection(&self, id: ConnectionId) -> Result<TcpStream> {
+        let mut stream = TcpStream::connect(self.turn_serv_addr).await?;
+        let mut buf = vec![0u8; MAX_FRAME_SIZE];
+
+        for _ in 0..MAX_RETRY_ATTEMPTS {
+            let msg = {
+                let relay_conn = self.relay_conn.lock().await;
+                relay_conn
+                    .build_authenticated(vec![
+                        Box::new(TransactionId::new()),
+                        Box::new(connection_bind_request()),
+                        Box::new(id),
+                    ])
+                    .await?
+            };
+            stream.write_all(&msg.raw).await?;
+
+            let n = read_frame(&mut stream, &mut buf).await?;
+            let mut res = Message::new();
+            res.write(&buf[..n])?;
+            if res.transaction_id != msg.transaction_id {
+                return Err(Error::ErrUnexpectedResponse);
+            }
+
+            if res.typ.class == CLASS_ERROR_RESPONSE {
+                let mut code = ErrorCodeAttribute::default();
+                let result = code.get_from(&res);
+                if result.is_err() {
+                    return Err(Error::Other(format!("{}", res.typ)));
+                } else if code.code == CODE_STALE_NONCE {
+                    let mut relay_conn = self.relay_conn.lock().await;
+                    relay_conn.set_nonce_from_msg(&res);
+                    continue;
+                } else {
+                    return Err(Error::Other(format!("{} (error {})", res.typ, code)));
+                }
+            }
+
+            log::debug!("connection {} bound", id);
+            return Ok(stream);
+        }
+
+        Err(Error::ErrTryAgain)
+    }
+}
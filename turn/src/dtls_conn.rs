@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod dtls_conn_test;
+
+use crate::error::*;
+
+use util::Conn;
+
+use async_trait::async_trait;
+use dtls::config::Config;
+use dtls::conn::DTLSConn;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+
+// DtlsConn carries STUN and ChannelData messages to a TURN server over DTLS,
+// as used for `turns:` URIs with transport=udp. Every message is sent to the
+// server the connection was established with, whatever its target.
+pub struct DtlsConn {
+    conn: DTLSConn,
+    remote_addr: SocketAddr,
+}
+
+impl DtlsConn {
+    // new performs a DTLS client handshake with remote_addr over conn, which
+    // must already be connected to it. The server certificate is validated
+    // according to config: server_name, roots_cas, insecure_skip_verify and
+    // verify_peer_certificate, while certificates are presented to servers
+    // that request client authentication.
+    pub async fn new(
+        conn: Arc<dyn Conn + Send + Sync>,
+        remote_addr: SocketAddr,
+        config: Config,
+    ) -> Result<Self> {
+        let conn = DTLSConn::new(conn, config, true, None).await?;
+        Ok(DtlsConn { conn, remote_addr })
+    }
+
+    // dial opens a DTLS connection to a TURN server.
+    pub async fn dial(addr: SocketAddr, config: Config) -> Result<Self> {
+        let local_addr = if addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local_addr).await?;
+        socket.connect(addr).await?;
+
+        DtlsConn::new(Arc::new(socket), addr, config).await
+    }
+}
+
+#[async_trait]
+impl Conn for DtlsConn {
+    async fn connect(&self, _addr: SocketAddr) -> std::result::Result<(), util::Error> {
+        Err(io::Error::new(io::ErrorKind::Other, "Not applicable").into())
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> std::result::Result<usize, util::Error> {
+        self.conn
+            .read(buf, None)
+            .await
+            .map_err(|err| util::Error::Other(err.to_string()))
+    }
+
+    async fn recv_from(
+        &self,
+        buf: &mut [u8],
+    ) -> std::result::Result<(usize, SocketAddr), util::Error> {
+        let n = self.recv(buf).await?;
+        Ok((n, self.remote_addr))
+    }
+
+    async fn send(&self, buf: &[u8]) -> std::result::Result<usize, util::Error> {
+        self.conn
+            .write(buf, None)
+            .await
+            .map_err(|err| util::Error::Other(err.to_string()))
+    }
+
+    async fn send_to(
+        &self,
+        buf: &[u8],
+        _target: SocketAddr,
+    ) -> std::result::Result<usize, util::Error> {
+        self.send(buf).await
+    }
+
+    fn local_addr(&self) -> std::result::Result<SocketAddr, util::Error> {
+        self.conn.local_addr()
+    }
+
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.remote_addr)
+    }
+
+    async fn close(&self) -> std::result::Result<(), util::Error> {
+        self.conn
+            .close()
+            .await
+            .map_err(|err| util::Error::Other(err.to_string()))
+    }
+}
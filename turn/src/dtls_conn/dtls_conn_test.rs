@@ -0,0 +1,74 @@
+use super::*;
+
+use dtls::crypto::Certificate;
+use util::conn::Listener;
+
+#[tokio::test]
+async fn test_dtls_conn() -> Result<()> {
+    let server_config = Config {
+        certificates: vec![Certificate::generate_self_signed(vec![
+            "localhost".to_owned()
+        ])?],
+        ..Default::default()
+    };
+    let listener = dtls::listener::listen("127.0.0.1:0", server_config).await?;
+    let server_addr = listener.addr().await?;
+
+    let server = tokio::spawn(async move {
+        let (conn, _) = listener.accept().await?;
+        let mut buf = vec![0u8; 1500];
+        let n = conn.recv(&mut buf).await?;
+        conn.send(&buf[..n]).await?;
+        Ok::<(), util::Error>(())
+    });
+
+    let client_config = Config {
+        server_name: "localhost".to_owned(),
+        insecure_skip_verify: true,
+        ..Default::default()
+    };
+    let conn = DtlsConn::dial(server_addr, client_config).await?;
+    assert_eq!(conn.remote_addr(), Some(server_addr));
+
+    // The target is ignored, messages always go to the server.
+    let unrelated = SocketAddr::from(([127, 0, 0, 1], 9));
+    conn.send_to(b"hello", unrelated).await?;
+
+    let mut buf = vec![0u8; 1500];
+    let (n, from) = conn.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], b"hello");
+    assert_eq!(from, server_addr);
+
+    server.await.unwrap()?;
+    conn.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dtls_conn_rejects_unverified_server() -> Result<()> {
+    let server_config = Config {
+        certificates: vec![Certificate::generate_self_signed(vec![
+            "localhost".to_owned()
+        ])?],
+        ..Default::default()
+    };
+    let listener = dtls::listener::listen("127.0.0.1:0", server_config).await?;
+    let server_addr = listener.addr().await?;
+
+    tokio::spawn(async move {
+        let _ = listener.accept().await;
+    });
+
+    let client_config = Config {
+        server_name: "localhost".to_owned(),
+        ..Default::default()
+    };
+    let result = DtlsConn::dial(server_addr, client_config).await;
+    assert!(
+        result.is_err(),
+        "self-signed certificate should not be trusted"
+    );
+
+    Ok(())
+}
@@ -30,6 +30,8 @@ pub enum Error {
     ErrMinPortNotZero,
     #[error("turn: MaxPort less than MinPort")]
     ErrMaxPortLessThanMinPort,
+    #[error("turn: requested port is outside of the relay port range")]
+    ErrRequestedPortOutOfRange,
     #[error("turn: relay_conn cannot not be nil")]
     ErrNilConn,
     #[error("turn: TODO")]
@@ -120,6 +122,8 @@ pub enum Error {
     ErrDuplicatedNonce,
     #[error("no such user exists")]
     ErrNoSuchUser,
+    #[error("invalid credentials")]
+    ErrInvalidCredentials,
     #[error("unexpected class")]
     ErrUnexpectedClass,
     #[error("unexpected method")]
@@ -138,6 +142,24 @@ pub enum Error {
     ErrRelayAlreadyAllocatedForFiveTuple,
     #[error("RequestedTransport must be UDP")]
     ErrRequestedTransportMustBeUdp,
+    #[error("TCP allocations must be requested over a TCP connection")]
+    ErrTcpAllocationOverUdp,
+    #[error("relay address generator does not support TCP allocations")]
+    ErrTcpRelayNotSupported,
+    #[error("operation is not supported on TCP allocations")]
+    ErrTcpAllocationUnsupportedOperation,
+    #[error("connection to peer already exists")]
+    ErrConnectionAlreadyExists,
+    #[error("connection to peer timed out or failed")]
+    ErrConnectionTimeoutOrFailure,
+    #[error("no such connection")]
+    ErrNoSuchConnection,
+    #[error("requested address family is not supported")]
+    ErrRequestedFamilyNotSupported,
+    #[error("peer address family does not match the relayed address")]
+    ErrPeerAddressFamilyMismatch,
+    #[error("Request must not contain RESERVATION-TOKEN and REQUESTED-ADDRESS-FAMILY")]
+    ErrRequestWithReservationTokenAndFamily,
     #[error("no support for DONT-FRAGMENT")]
     ErrNoDontFragmentSupport,
     #[error("Request must not contain RESERVATION-TOKEN and EVEN-PORT")]
@@ -152,6 +174,18 @@ pub enum Error {
     ErrNoSuchChannelBind,
     #[error("failed writing to socket")]
     ErrFailedWriteSocket,
+    #[error("invalid TLS server name")]
+    ErrInvalidServerName,
+    #[error("too many ALTERNATE-SERVER redirects")]
+    ErrTooManyRedirects,
+    #[error("cannot follow ALTERNATE-SERVER redirect over a connection to the server")]
+    ErrRedirectOnConnectedTransport,
+    #[error("mobility is only supported for allocations over UDP")]
+    ErrMobilityForbidden,
+    #[error("no allocation for the mobility ticket")]
+    ErrInvalidMobilityTicket,
+    #[error("mobility ticket was issued to another user")]
+    ErrMobilityTicketWrongCredentials,
     #[error("parse int: {0}")]
     ParseInt(#[from] ParseIntError),
     #[error("parse addr: {0}")]
@@ -162,6 +196,9 @@ pub enum Error {
     Util(#[from] util::Error),
     #[error("{0}")]
     Stun(#[from] stun::Error),
+    #[cfg(feature = "dtls")]
+    #[error("{0}")]
+    Dtls(#[from] dtls::Error),
     #[error("{0}")]
     Other(String),
 }
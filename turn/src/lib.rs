@@ -5,9 +5,12 @@
 pub mod allocation;
 pub mod auth;
 pub mod client;
+#[cfg(feature = "dtls")]
+pub mod dtls_conn;
 mod error;
 pub mod proto;
 pub mod relay;
 pub mod server;
+pub mod stream_conn;
 
 pub use error::Error;
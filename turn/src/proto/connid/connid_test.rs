@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn test_connection_id() -> Result<(), stun::Error> {
+    let mut m = Message::new();
+    let c = ConnectionId(0x12345678);
+    m.build(&[Box::new(c)])?;
+    assert_eq!(m.get(ATTR_CONNECTION_ID)?, vec![0x12, 0x34, 0x56, 0x78]);
+
+    let mut decoded = Message::new();
+    decoded.write(&m.raw)?;
+    let mut got = ConnectionId::default();
+    got.get_from(&decoded)?;
+    assert_eq!(got, c);
+    assert_eq!(got.to_string(), "305419896");
+
+    //"HandleErr"
+    {
+        let mut m = Message::new();
+        let mut got = ConnectionId::default();
+        assert_eq!(
+            got.get_from(&m),
+            Err(stun::Error::ErrAttributeNotFound),
+            "should error"
+        );
+
+        m.add(ATTR_CONNECTION_ID, &[1, 2, 3]);
+        assert!(got.get_from(&m).is_err(), "should error");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod mobility_test;
+
+use stun::attributes::*;
+use stun::message::*;
+
+// MobilityTicket represents MOBILITY-TICKET attribute.
+//
+// The MOBILITY-TICKET attribute is used to retain an allocation on the
+// TURN server when the client's transport address changes. The client
+// includes an empty MOBILITY-TICKET in an Allocate request to ask for
+// mobility, and the server includes a ticket in the success response.
+// The client then includes the ticket in a Refresh request sent from
+// its new transport address, to move the allocation to that address.
+// The ticket is opaque to the client.
+//
+// RFC 8016 Section 3.1
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MobilityTicket(pub Vec<u8>);
+
+impl Setter for MobilityTicket {
+    // AddTo adds MOBILITY-TICKET to message.
+    fn add_to(&self, m: &mut Message) -> Result<(), stun::Error> {
+        m.add(ATTR_MOBILITY_TICKET, &self.0);
+        Ok(())
+    }
+}
+
+impl Getter for MobilityTicket {
+    // GetFrom decodes MOBILITY-TICKET from message.
+    fn get_from(&mut self, m: &Message) -> Result<(), stun::Error> {
+        self.0 = m.get(ATTR_MOBILITY_TICKET)?;
+        Ok(())
+    }
+}
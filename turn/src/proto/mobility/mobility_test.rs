@@ -0,0 +1,49 @@
+use super::*;
+
+#[test]
+fn test_mobility_ticket() -> Result<(), stun::Error> {
+    let mut m = Message::new();
+    let ticket = MobilityTicket(vec![1, 2, 3, 4, 5]);
+    ticket.add_to(&mut m)?;
+    m.write_header();
+
+    //"GetFrom"
+    {
+        let mut decoded = Message::new();
+        decoded.write(&m.raw)?;
+        let mut t = MobilityTicket::default();
+        t.get_from(&decoded)?;
+        assert_eq!(t, ticket, "Decoded {:?}, expected {:?}", t, ticket);
+
+        //"HandleErr"
+        {
+            let m = Message::new();
+            let mut handle = MobilityTicket::default();
+            if let Err(err) = handle.get_from(&m) {
+                assert_eq!(
+                    stun::Error::ErrAttributeNotFound,
+                    err,
+                    "{} should be not found",
+                    err
+                );
+            } else {
+                panic!("expected error, but got ok");
+            }
+        }
+    }
+
+    //"Empty"
+    {
+        let mut m = Message::new();
+        MobilityTicket::default().add_to(&mut m)?;
+        m.write_header();
+
+        let mut decoded = Message::new();
+        decoded.write(&m.raw)?;
+        let mut t = MobilityTicket(vec![1]);
+        t.get_from(&decoded)?;
+        assert!(t.0.is_empty(), "expected an empty ticket, got {:?}", t);
+    }
+
+    Ok(())
+}
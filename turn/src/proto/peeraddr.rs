@@ -60,6 +60,33 @@ impl Getter for PeerAddress {
     }
 }
 
+impl PeerAddress {
+    // get_all_from decodes every XOR-PEER-ADDRESS attribute in message, as
+    // a CreatePermission request may contain several of them.
+    pub fn get_all_from(m: &Message) -> Result<Vec<PeerAddress>, stun::Error> {
+        let mut peers = vec![];
+        for attr in &m.attributes.0 {
+            if attr.typ != ATTR_XOR_PEER_ADDRESS {
+                continue;
+            }
+
+            let mut single = Message::new();
+            single.transaction_id = m.transaction_id;
+            single.add(ATTR_XOR_PEER_ADDRESS, &attr.value);
+
+            let mut peer = PeerAddress::default();
+            peer.get_from(&single)?;
+            peers.push(peer);
+        }
+
+        if peers.is_empty() {
+            Err(stun::Error::ErrAttributeNotFound)
+        } else {
+            Ok(peers)
+        }
+    }
+}
+
 // XORPeerAddress implements XOR-PEER-ADDRESS attribute.
 //
 // The XOR-PEER-ADDRESS specifies the address and port of the peer as
@@ -1,6 +1,8 @@
 use super::*;
 
-use std::net::Ipv4Addr;
+use stun::agent::TransactionId;
+
+use std::net::{Ipv4Addr, Ipv6Addr};
 
 #[test]
 fn test_peer_address() -> Result<(), stun::Error> {
@@ -24,3 +26,36 @@ fn test_peer_address() -> Result<(), stun::Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_peer_address_get_all_from() -> Result<(), stun::Error> {
+    let peers = vec![
+        PeerAddress {
+            ip: IpAddr::V4(Ipv4Addr::new(111, 11, 1, 2)),
+            port: 333,
+        },
+        PeerAddress {
+            ip: IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+            port: 444,
+        },
+    ];
+
+    let mut m = Message::new();
+    m.transaction_id = TransactionId::new();
+    for peer in &peers {
+        peer.add_to(&mut m)?;
+    }
+    m.write_header();
+
+    let mut decoded = Message::new();
+    decoded.write(&m.raw)?;
+    assert_eq!(PeerAddress::get_all_from(&decoded)?, peers);
+
+    let empty = Message::new();
+    assert_eq!(
+        PeerAddress::get_all_from(&empty),
+        Err(stun::Error::ErrAttributeNotFound)
+    );
+
+    Ok(())
+}
@@ -1,14 +1,17 @@
+pub mod relay_dual_stack;
 pub mod relay_none;
+pub mod relay_pool;
 pub mod relay_range;
 pub mod relay_static;
 
-use crate::error::Result;
+use crate::error::*;
 
 use util::Conn;
 
 use async_trait::async_trait;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
+use tokio::net::{TcpListener, TcpSocket};
 
 // RelayAddressGenerator is used to generate a RelayAddress when creating an allocation.
 // You can use one of the provided ones or provide your own.
@@ -17,10 +20,66 @@ pub trait RelayAddressGenerator {
     // validate confirms that the RelayAddressGenerator is properly initialized
     fn validate(&self) -> Result<()>;
 
-    // Allocate a RelayAddress
+    // Allocate a RelayAddress of the IPv4 or IPv6 address family. Generators
+    // that cannot allocate from the requested family return
+    // ErrRequestedFamilyNotSupported.
     async fn allocate_conn(
         &self,
         use_ipv4: bool,
         requested_port: u16,
     ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)>;
+
+    // Allocate a TCP RelayAddress as described in RFC 6062. The listener accepts
+    // connections from peers, and outgoing connections to peers are made from
+    // its local address. Generators that do not support TCP relaying keep the
+    // default implementation.
+    async fn allocate_listener(
+        &self,
+        _use_ipv4: bool,
+        _requested_port: u16,
+    ) -> Result<(TcpListener, SocketAddr)> {
+        Err(Error::ErrTcpRelayNotSupported)
+    }
+}
+
+// check_family fails with ErrRequestedFamilyNotSupported if the listening
+// address or the relay address belongs to another address family than the
+// requested one, as described in RFC 6156 Section 4.2.
+pub(crate) fn check_family(
+    use_ipv4: bool,
+    address: &str,
+    relay_address: Option<IpAddr>,
+) -> Result<()> {
+    let ips = address
+        .parse::<IpAddr>()
+        .ok()
+        .into_iter()
+        .chain(relay_address);
+    for ip in ips {
+        if ip.is_ipv4() != use_ipv4 {
+            return Err(Error::ErrRequestedFamilyNotSupported);
+        }
+    }
+    Ok(())
+}
+
+// bind_tcp_listener binds a listener which allows outgoing connections to be
+// made from the same local address.
+pub(crate) fn bind_tcp_listener(addr: SocketAddr) -> Result<TcpListener> {
+    let socket = new_tcp_socket(addr)?;
+    socket.bind(addr)?;
+    Ok(socket.listen(1024)?)
+}
+
+// new_tcp_socket creates a TCP socket for addr with address reuse enabled.
+pub(crate) fn new_tcp_socket(addr: SocketAddr) -> Result<TcpSocket> {
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    socket.set_reuseaddr(true)?;
+    #[cfg(unix)]
+    socket.set_reuseport(true)?;
+    Ok(socket)
 }
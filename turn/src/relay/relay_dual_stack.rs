@@ -0,0 +1,57 @@
+use super::*;
+use crate::error::*;
+
+use async_trait::async_trait;
+use tokio::net::TcpListener;
+
+// RelayAddressGeneratorDualStack allocates IPv4 and IPv6 relay addresses from
+// separate generators, so that clients can pick the address family of their
+// allocation with REQUESTED-ADDRESS-FAMILY (RFC 6156).
+pub struct RelayAddressGeneratorDualStack {
+    // ipv4 allocates IPv4 relay addresses
+    pub ipv4: Box<dyn RelayAddressGenerator + Send + Sync>,
+
+    // ipv6 allocates IPv6 relay addresses
+    pub ipv6: Box<dyn RelayAddressGenerator + Send + Sync>,
+}
+
+impl RelayAddressGeneratorDualStack {
+    fn generator(&self, use_ipv4: bool) -> &(dyn RelayAddressGenerator + Send + Sync) {
+        if use_ipv4 {
+            self.ipv4.as_ref()
+        } else {
+            self.ipv6.as_ref()
+        }
+    }
+}
+
+#[async_trait]
+impl RelayAddressGenerator for RelayAddressGeneratorDualStack {
+    // validate confirms that both RelayAddressGenerators are properly initialized
+    fn validate(&self) -> Result<()> {
+        self.ipv4.validate()?;
+        self.ipv6.validate()
+    }
+
+    // Allocate a PacketConn (UDP) RelayAddress
+    async fn allocate_conn(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)> {
+        self.generator(use_ipv4)
+            .allocate_conn(use_ipv4, requested_port)
+            .await
+    }
+
+    // Allocate a TCP RelayAddress
+    async fn allocate_listener(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(TcpListener, SocketAddr)> {
+        self.generator(use_ipv4)
+            .allocate_listener(use_ipv4, requested_port)
+            .await
+    }
+}
@@ -2,6 +2,7 @@ use super::*;
 use crate::error::*;
 
 use async_trait::async_trait;
+use tokio::net::TcpListener;
 use util::vnet::net::*;
 
 // RelayAddressGeneratorNone returns the listener with no modifications
@@ -28,6 +29,8 @@ impl RelayAddressGenerator for RelayAddressGeneratorNone {
         use_ipv4: bool,
         requested_port: u16,
     ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)> {
+        check_family(use_ipv4, &self.address, None)?;
+
         let addr = self
             .net
             .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
@@ -36,4 +39,25 @@ impl RelayAddressGenerator for RelayAddressGeneratorNone {
         let relay_addr = conn.local_addr()?;
         Ok((conn, relay_addr))
     }
+
+    // Allocate a TCP RelayAddress
+    async fn allocate_listener(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(TcpListener, SocketAddr)> {
+        check_family(use_ipv4, &self.address, None)?;
+
+        if self.net.is_virtual() {
+            return Err(Error::ErrTcpRelayNotSupported);
+        }
+
+        let addr = self
+            .net
+            .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
+            .await?;
+        let listener = bind_tcp_listener(addr)?;
+        let relay_addr = listener.local_addr()?;
+        Ok((listener, relay_addr))
+    }
 }
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod relay_pool_test;
+
+use super::*;
+use crate::error::*;
+
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::net::TcpListener;
+
+// RelayAddressGeneratorPool allocates relay addresses from several generators,
+// e.g. one per relay IP, each restricted to a port range with
+// RelayAddressGeneratorRanges. Allocations are spread over the generators in
+// turn, and a generator that cannot allocate, because it has no address of
+// the requested family or no free port left, is skipped.
+pub struct RelayAddressGeneratorPool {
+    generators: Vec<Box<dyn RelayAddressGenerator + Send + Sync>>,
+    next: AtomicUsize,
+}
+
+impl RelayAddressGeneratorPool {
+    pub fn new(generators: Vec<Box<dyn RelayAddressGenerator + Send + Sync>>) -> Self {
+        RelayAddressGeneratorPool {
+            generators,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    // generators returns the generators in the order they should be tried for
+    // the next allocation.
+    fn generators(&self) -> impl Iterator<Item = &(dyn RelayAddressGenerator + Send + Sync)> {
+        let n = self.generators.len();
+        let first = if n == 0 {
+            0
+        } else {
+            self.next.fetch_add(1, Ordering::Relaxed) % n
+        };
+        (0..n).map(move |i| self.generators[(first + i) % n].as_ref())
+    }
+}
+
+// pool_error returns the error to report when no generator could allocate:
+// ErrRequestedFamilyNotSupported only if no generator has an address of the
+// requested family, and the last other error otherwise.
+fn pool_error(last_err: Option<Error>, err: Error) -> Option<Error> {
+    match last_err {
+        Some(last_err) if err == Error::ErrRequestedFamilyNotSupported => Some(last_err),
+        _ => Some(err),
+    }
+}
+
+#[async_trait]
+impl RelayAddressGenerator for RelayAddressGeneratorPool {
+    // validate confirms that the pool has generators which are all properly
+    // initialized
+    fn validate(&self) -> Result<()> {
+        if self.generators.is_empty() {
+            return Err(Error::ErrRelayAddressGeneratorUnset);
+        }
+        for generator in &self.generators {
+            generator.validate()?;
+        }
+        Ok(())
+    }
+
+    // Allocate a PacketConn (UDP) RelayAddress
+    async fn allocate_conn(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)> {
+        let mut last_err = None;
+        for generator in self.generators() {
+            match generator.allocate_conn(use_ipv4, requested_port).await {
+                Ok(relay) => return Ok(relay),
+                Err(err) => last_err = pool_error(last_err, err),
+            }
+        }
+        Err(last_err.unwrap_or(Error::ErrRelayAddressGeneratorUnset))
+    }
+
+    // Allocate a TCP RelayAddress
+    async fn allocate_listener(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(TcpListener, SocketAddr)> {
+        let mut last_err = None;
+        for generator in self.generators() {
+            match generator.allocate_listener(use_ipv4, requested_port).await {
+                Ok(relay) => return Ok(relay),
+                Err(err) => last_err = pool_error(last_err, err),
+            }
+        }
+        Err(last_err.unwrap_or(Error::ErrRelayAddressGeneratorUnset))
+    }
+}
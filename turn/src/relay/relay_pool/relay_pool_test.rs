@@ -0,0 +1,103 @@
+use super::*;
+use crate::relay::relay_range::RelayAddressGeneratorRanges;
+use crate::relay::relay_static::RelayAddressGeneratorStatic;
+
+use std::net::{IpAddr, Ipv4Addr};
+use util::vnet::net::Net;
+
+fn new_static(relay_address: IpAddr) -> Box<dyn RelayAddressGenerator + Send + Sync> {
+    Box::new(RelayAddressGeneratorStatic {
+        relay_address,
+        address: "127.0.0.1".to_owned(),
+        net: Arc::new(Net::new(None)),
+    })
+}
+
+#[test]
+fn test_relay_pool_validate() {
+    let pool = RelayAddressGeneratorPool::new(vec![]);
+    assert_eq!(pool.validate(), Err(Error::ErrRelayAddressGeneratorUnset));
+
+    let pool = RelayAddressGeneratorPool::new(vec![
+        new_static(Ipv4Addr::new(10, 0, 0, 1).into()),
+        Box::new(RelayAddressGeneratorRanges {
+            relay_address: Ipv4Addr::new(10, 0, 0, 2).into(),
+            min_port: 50000,
+            max_port: 40000,
+            max_retries: 10,
+            address: "127.0.0.1".to_owned(),
+            net: Arc::new(Net::new(None)),
+        }),
+    ]);
+    assert_eq!(pool.validate(), Err(Error::ErrMaxPortLessThanMinPort));
+}
+
+#[tokio::test]
+async fn test_relay_pool_allocate_conn() -> Result<()> {
+    let relay_ip1: IpAddr = Ipv4Addr::new(10, 0, 0, 1).into();
+    let relay_ip2: IpAddr = Ipv4Addr::new(10, 0, 0, 2).into();
+    let pool = RelayAddressGeneratorPool::new(vec![new_static(relay_ip1), new_static(relay_ip2)]);
+    pool.validate()?;
+
+    // Allocations are spread over the relay addresses.
+    let (_conn1, relay_addr1) = pool.allocate_conn(true, 0).await?;
+    let (_conn2, relay_addr2) = pool.allocate_conn(true, 0).await?;
+    let (_conn3, relay_addr3) = pool.allocate_conn(true, 0).await?;
+    assert_eq!(relay_addr1.ip(), relay_ip1);
+    assert_eq!(relay_addr2.ip(), relay_ip2);
+    assert_eq!(relay_addr3.ip(), relay_ip1);
+
+    let result = pool.allocate_conn(false, 0).await;
+    assert_eq!(result.err(), Some(Error::ErrRequestedFamilyNotSupported));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_relay_pool_skips_exhausted_range() -> Result<()> {
+    // Both generators listen on the same address, so the port of the first
+    // one is taken once it allocated.
+    let port = {
+        let conn = tokio::net::UdpSocket::bind("127.0.0.1:0").await?;
+        conn.local_addr()?.port()
+    };
+    let relay_ip1: IpAddr = Ipv4Addr::new(10, 0, 0, 1).into();
+    let relay_ip2: IpAddr = Ipv4Addr::new(10, 0, 0, 2).into();
+    let pool = RelayAddressGeneratorPool::new(vec![
+        Box::new(RelayAddressGeneratorRanges {
+            relay_address: relay_ip1,
+            min_port: port,
+            max_port: port,
+            max_retries: 1,
+            address: "127.0.0.1".to_owned(),
+            net: Arc::new(Net::new(None)),
+        }),
+        new_static(relay_ip2),
+    ]);
+
+    let (_conn1, relay_addr1) = pool.allocate_conn(true, 0).await?;
+    assert_eq!(relay_addr1, SocketAddr::new(relay_ip1, port));
+
+    let (_conn2, relay_addr2) = pool.allocate_conn(true, 0).await?;
+    assert_eq!(relay_addr2.ip(), relay_ip2);
+
+    // The first generator is tried again but its range is exhausted.
+    let (_conn3, relay_addr3) = pool.allocate_conn(true, 0).await?;
+    assert_eq!(relay_addr3.ip(), relay_ip2);
+
+    // A reserved port outside of the range is refused by the first
+    // generator.
+    let result = RelayAddressGeneratorRanges {
+        relay_address: relay_ip1,
+        min_port: port,
+        max_port: port,
+        max_retries: 1,
+        address: "127.0.0.1".to_owned(),
+        net: Arc::new(Net::new(None)),
+    }
+    .allocate_conn(true, port - 1)
+    .await;
+    assert_eq!(result.err(), Some(Error::ErrRequestedPortOutOfRange));
+
+    Ok(())
+}
@@ -3,6 +3,7 @@ use crate::error::*;
 
 use async_trait::async_trait;
 use std::net::IpAddr;
+use tokio::net::TcpListener;
 use util::vnet::net::*;
 
 // RelayAddressGeneratorRanges can be used to only allocate connections inside a defined port range
@@ -25,6 +26,18 @@ pub struct RelayAddressGeneratorRanges {
     pub net: Arc<Net>,
 }
 
+impl RelayAddressGeneratorRanges {
+    // check_port fails if a requested port, e.g. of a reservation, lies outside
+    // of the range, which may be the only ports the firewall lets through.
+    fn check_port(&self, port: u16) -> Result<()> {
+        if port < self.min_port || port > self.max_port {
+            Err(Error::ErrRequestedPortOutOfRange)
+        } else {
+            Ok(())
+        }
+    }
+}
+
 #[async_trait]
 impl RelayAddressGenerator for RelayAddressGeneratorRanges {
     // validate confirms that the RelayAddressGenerator is properly initialized
@@ -48,6 +61,8 @@ impl RelayAddressGenerator for RelayAddressGeneratorRanges {
         use_ipv4: bool,
         requested_port: u16,
     ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)> {
+        check_family(use_ipv4, &self.address, Some(self.relay_address))?;
+
         let max_retries = if self.max_retries == 0 {
             10
         } else {
@@ -55,6 +70,7 @@ impl RelayAddressGenerator for RelayAddressGeneratorRanges {
         };
 
         if requested_port != 0 {
+            self.check_port(requested_port)?;
             let addr = self
                 .net
                 .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
@@ -83,4 +99,53 @@ impl RelayAddressGenerator for RelayAddressGeneratorRanges {
 
         Err(Error::ErrMaxRetriesExceeded)
     }
+
+    // Allocate a TCP relay_address
+    async fn allocate_listener(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(TcpListener, SocketAddr)> {
+        check_family(use_ipv4, &self.address, Some(self.relay_address))?;
+
+        if self.net.is_virtual() {
+            return Err(Error::ErrTcpRelayNotSupported);
+        }
+
+        let max_retries = if self.max_retries == 0 {
+            10
+        } else {
+            self.max_retries
+        };
+
+        if requested_port != 0 {
+            self.check_port(requested_port)?;
+            let addr = self
+                .net
+                .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
+                .await?;
+            let listener = bind_tcp_listener(addr)?;
+            let mut relay_addr = listener.local_addr()?;
+            relay_addr.set_ip(self.relay_address);
+            return Ok((listener, relay_addr));
+        }
+
+        for _ in 0..max_retries {
+            let port = self.min_port + rand::random::<u16>() % (self.max_port - self.min_port + 1);
+            let addr = self
+                .net
+                .resolve_addr(use_ipv4, &format!("{}:{}", self.address, port))
+                .await?;
+            let listener = match bind_tcp_listener(addr) {
+                Ok(listener) => listener,
+                Err(_) => continue,
+            };
+
+            let mut relay_addr = listener.local_addr()?;
+            relay_addr.set_ip(self.relay_address);
+            return Ok((listener, relay_addr));
+        }
+
+        Err(Error::ErrMaxRetriesExceeded)
+    }
 }
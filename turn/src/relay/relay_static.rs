@@ -3,6 +3,7 @@ use crate::error::*;
 
 use async_trait::async_trait;
 use std::net::IpAddr;
+use tokio::net::TcpListener;
 use util::vnet::net::*;
 
 // RelayAddressGeneratorStatic can be used to return static IP address each time a relay is created.
@@ -34,6 +35,8 @@ impl RelayAddressGenerator for RelayAddressGeneratorStatic {
         use_ipv4: bool,
         requested_port: u16,
     ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)> {
+        check_family(use_ipv4, &self.address, Some(self.relay_address))?;
+
         let addr = self
             .net
             .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
@@ -43,4 +46,26 @@ impl RelayAddressGenerator for RelayAddressGeneratorStatic {
         relay_addr.set_ip(self.relay_address);
         return Ok((conn, relay_addr));
     }
+
+    // Allocate a TCP RelayAddress
+    async fn allocate_listener(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(TcpListener, SocketAddr)> {
+        check_family(use_ipv4, &self.address, Some(self.relay_address))?;
+
+        if self.net.is_virtual() {
+            return Err(Error::ErrTcpRelayNotSupported);
+        }
+
+        let addr = self
+            .net
+            .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
+            .await?;
+        let listener = bind_tcp_listener(addr)?;
+        let mut relay_addr = listener.local_addr()?;
+        relay_addr.set_ip(self.relay_address);
+        Ok((listener, relay_addr))
+    }
 }
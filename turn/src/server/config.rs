@@ -1,10 +1,12 @@
 use crate::auth::*;
 use crate::error::*;
 use crate::relay::*;
+use crate::server::events::EventHandler;
 
 use util::Conn;
 
 use std::sync::Arc;
+use tokio::net::TcpListener;
 use tokio::time::Duration;
 
 // ConnConfig is used for UDP listeners
@@ -22,12 +24,31 @@ impl ConnConfig {
     }
 }
 
+// ListenerConfig is used for TCP listeners
+pub struct ListenerConfig {
+    pub listener: TcpListener,
+
+    // When a TCP allocation is generated the RelayAddressGenerator
+    // creates the listener and returns the IP/Port it is available at
+    pub relay_addr_generator: Box<dyn RelayAddressGenerator + Send + Sync>,
+}
+
+impl ListenerConfig {
+    pub fn validate(&self) -> Result<()> {
+        self.relay_addr_generator.validate()
+    }
+}
+
 // ServerConfig configures the Pion TURN Server
 pub struct ServerConfig {
     // conn_configs are a list of all the turn listeners
     // Each listener can have custom behavior around the creation of Relays
     pub conn_configs: Vec<ConnConfig>,
 
+    // listener_configs are a list of all the TCP turn listeners, on which
+    // both UDP and TCP (RFC 6062) allocations can be requested
+    pub listener_configs: Vec<ListenerConfig>,
+
     // realm sets the realm for this server
     pub realm: String,
 
@@ -36,17 +57,37 @@ pub struct ServerConfig {
 
     // channel_bind_timeout sets the lifetime of channel binding. Defaults to 10 minutes.
     pub channel_bind_timeout: Duration,
+
+    // event_handler is notified of allocations being created, refreshed and
+    // closed, permissions, channel bindings and relayed data
+    pub event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
 }
 
 impl ServerConfig {
+    // new creates a ServerConfig with no listeners, the default channel_bind_timeout
+    // and no event_handler, the listeners are to be added to conn_configs or listener_configs.
+    pub fn new(realm: String, auth_handler: Arc<dyn AuthHandler + Send + Sync>) -> Self {
+        ServerConfig {
+            conn_configs: vec![],
+            listener_configs: vec![],
+            realm,
+            auth_handler,
+            channel_bind_timeout: Duration::from_secs(0),
+            event_handler: None,
+        }
+    }
+
     pub fn validate(&self) -> Result<()> {
-        if self.conn_configs.is_empty() {
+        if self.conn_configs.is_empty() && self.listener_configs.is_empty() {
             return Err(Error::ErrNoAvailableConns);
         }
 
         for cc in &self.conn_configs {
             cc.validate()?;
         }
+        for lc in &self.listener_configs {
+            lc.validate()?;
+        }
         Ok(())
     }
 }
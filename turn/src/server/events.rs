@@ -0,0 +1,251 @@
+#[cfg(test)]
+mod events_test;
+
+use crate::allocation::five_tuple::FiveTuple;
+use crate::proto::Protocol;
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::time::Duration;
+
+// AllocationCloseReason tells why an allocation was removed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AllocationCloseReason {
+    // the client did not refresh the allocation before its lifetime ran out
+    Expired,
+    // the client deleted the allocation with a zero lifetime Refresh, closed
+    // its TCP connection, or the allocation was deleted by username
+    Deleted,
+    // the server was closed
+    ServerClosed,
+}
+
+// RelayedBytes is the amount of data an allocation relayed.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct RelayedBytes {
+    // bytes relayed from the client to its peers
+    pub to_peers: u64,
+    // bytes relayed from peers to the client
+    pub from_peers: u64,
+}
+
+// RelayCounters counts the data relayed by an allocation.
+#[derive(Debug, Default)]
+pub(crate) struct RelayCounters {
+    to_peers: AtomicU64,
+    from_peers: AtomicU64,
+}
+
+impl RelayCounters {
+    pub(crate) fn get(&self) -> RelayedBytes {
+        RelayedBytes {
+            to_peers: self.to_peers.load(Ordering::Acquire),
+            from_peers: self.from_peers.load(Ordering::Acquire),
+        }
+    }
+}
+
+// EventHandler is notified of the allocations handled by a Server, e.g. to
+// export usage to a monitoring system or to bill users. Its methods are
+// called from the tasks handling requests and relaying data, so they must
+// not block.
+pub trait EventHandler {
+    fn on_allocation_created(
+        &self,
+        _five_tuple: &FiveTuple,
+        _username: &str,
+        _relay_addr: SocketAddr,
+        _protocol: Protocol,
+    ) {
+    }
+
+    fn on_allocation_refreshed(
+        &self,
+        _five_tuple: &FiveTuple,
+        _username: &str,
+        _lifetime: Duration,
+    ) {
+    }
+
+    // on_allocation_closed is called once an allocation is removed, with all
+    // the data it relayed.
+    fn on_allocation_closed(
+        &self,
+        _five_tuple: &FiveTuple,
+        _username: &str,
+        _relayed: RelayedBytes,
+        _reason: AllocationCloseReason,
+    ) {
+    }
+
+    // on_permission_added is called when a permission is installed, not when
+    // it is refreshed.
+    fn on_permission_added(&self, _five_tuple: &FiveTuple, _username: &str, _peer: IpAddr) {}
+
+    // on_channel_bound is called when a channel is bound, not when the
+    // binding is refreshed.
+    fn on_channel_bound(
+        &self,
+        _five_tuple: &FiveTuple,
+        _username: &str,
+        _peer: SocketAddr,
+        _number: u16,
+    ) {
+    }
+}
+
+// ServerMetrics are the counters aggregated over all allocations of a
+// Server since it started.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ServerMetrics {
+    pub allocations_created: u64,
+    pub allocations_refreshed: u64,
+    pub allocations_expired: u64,
+    pub allocations_closed: u64,
+    pub permissions_added: u64,
+    pub channels_bound: u64,
+    pub bytes_to_peers: u64,
+    pub bytes_from_peers: u64,
+}
+
+impl ServerMetrics {
+    // active_allocations returns the number of allocations currently open.
+    pub fn active_allocations(&self) -> u64 {
+        self.allocations_created
+            .saturating_sub(self.allocations_closed)
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsCounters {
+    allocations_created: AtomicU64,
+    allocations_refreshed: AtomicU64,
+    allocations_expired: AtomicU64,
+    allocations_closed: AtomicU64,
+    permissions_added: AtomicU64,
+    channels_bound: AtomicU64,
+    bytes_to_peers: AtomicU64,
+    bytes_from_peers: AtomicU64,
+}
+
+// Events updates the metrics of a Server and forwards the events of its
+// allocations to the EventHandler, if any.
+#[derive(Default)]
+pub struct Events {
+    handler: Option<Arc<dyn EventHandler + Send + Sync>>,
+    counters: MetricsCounters,
+}
+
+impl Events {
+    pub fn new(handler: Option<Arc<dyn EventHandler + Send + Sync>>) -> Self {
+        Events {
+            handler,
+            counters: MetricsCounters::default(),
+        }
+    }
+
+    // metrics returns the current value of the counters.
+    pub fn metrics(&self) -> ServerMetrics {
+        let c = &self.counters;
+        ServerMetrics {
+            allocations_created: c.allocations_created.load(Ordering::Acquire),
+            allocations_refreshed: c.allocations_refreshed.load(Ordering::Acquire),
+            allocations_expired: c.allocations_expired.load(Ordering::Acquire),
+            allocations_closed: c.allocations_closed.load(Ordering::Acquire),
+            permissions_added: c.permissions_added.load(Ordering::Acquire),
+            channels_bound: c.channels_bound.load(Ordering::Acquire),
+            bytes_to_peers: c.bytes_to_peers.load(Ordering::Acquire),
+            bytes_from_peers: c.bytes_from_peers.load(Ordering::Acquire),
+        }
+    }
+
+    pub(crate) fn allocation_created(
+        &self,
+        five_tuple: &FiveTuple,
+        username: &str,
+        relay_addr: SocketAddr,
+        protocol: Protocol,
+    ) {
+        self.counters
+            .allocations_created
+            .fetch_add(1, Ordering::AcqRel);
+        if let Some(handler) = &self.handler {
+            handler.on_allocation_created(five_tuple, username, relay_addr, protocol);
+        }
+    }
+
+    pub(crate) fn allocation_refreshed(
+        &self,
+        five_tuple: &FiveTuple,
+        username: &str,
+        lifetime: Duration,
+    ) {
+        self.counters
+            .allocations_refreshed
+            .fetch_add(1, Ordering::AcqRel);
+        if let Some(handler) = &self.handler {
+            handler.on_allocation_refreshed(five_tuple, username, lifetime);
+        }
+    }
+
+    pub(crate) fn allocation_closed(
+        &self,
+        five_tuple: &FiveTuple,
+        username: &str,
+        relayed: RelayedBytes,
+        reason: AllocationCloseReason,
+    ) {
+        self.counters
+            .allocations_closed
+            .fetch_add(1, Ordering::AcqRel);
+        if reason == AllocationCloseReason::Expired {
+            self.counters
+                .allocations_expired
+                .fetch_add(1, Ordering::AcqRel);
+        }
+        if let Some(handler) = &self.handler {
+            handler.on_allocation_closed(five_tuple, username, relayed, reason);
+        }
+    }
+
+    pub(crate) fn permission_added(&self, five_tuple: &FiveTuple, username: &str, peer: IpAddr) {
+        self.counters
+            .permissions_added
+            .fetch_add(1, Ordering::AcqRel);
+        if let Some(handler) = &self.handler {
+            handler.on_permission_added(five_tuple, username, peer);
+        }
+    }
+
+    pub(crate) fn channel_bound(
+        &self,
+        five_tuple: &FiveTuple,
+        username: &str,
+        peer: SocketAddr,
+        number: u16,
+    ) {
+        self.counters.channels_bound.fetch_add(1, Ordering::AcqRel);
+        if let Some(handler) = &self.handler {
+            handler.on_channel_bound(five_tuple, username, peer, number);
+        }
+    }
+
+    // relayed_to_peer counts n bytes relayed from a client to a peer on the
+    // allocation with the given counters.
+    pub(crate) fn relayed_to_peer(&self, relayed: &RelayCounters, n: usize) {
+        relayed.to_peers.fetch_add(n as u64, Ordering::AcqRel);
+        self.counters
+            .bytes_to_peers
+            .fetch_add(n as u64, Ordering::AcqRel);
+    }
+
+    // relayed_from_peer counts n bytes relayed from a peer to a client on the
+    // allocation with the given counters.
+    pub(crate) fn relayed_from_peer(&self, relayed: &RelayCounters, n: usize) {
+        relayed.from_peers.fetch_add(n as u64, Ordering::AcqRel);
+        self.counters
+            .bytes_from_peers
+            .fetch_add(n as u64, Ordering::AcqRel);
+    }
+}
@@ -0,0 +1,187 @@
+use super::*;
+use crate::auth::{generate_auth_key, AuthContext, AuthHandler};
+use crate::client::{Client, ClientConfig};
+use crate::error::Result;
+use crate::relay::relay_static::RelayAddressGeneratorStatic;
+use crate::server::{
+    config::{ConnConfig, ServerConfig},
+    Server,
+};
+
+use async_trait::async_trait;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tokio::net::UdpSocket;
+use util::vnet::net::Net;
+use util::Conn;
+
+#[derive(Default)]
+struct TestEventHandler {
+    events: Mutex<Vec<String>>,
+}
+
+impl EventHandler for TestEventHandler {
+    fn on_allocation_created(
+        &self,
+        _five_tuple: &FiveTuple,
+        username: &str,
+        _relay_addr: SocketAddr,
+        _protocol: Protocol,
+    ) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("created {}", username));
+    }
+
+    fn on_allocation_closed(
+        &self,
+        _five_tuple: &FiveTuple,
+        username: &str,
+        relayed: RelayedBytes,
+        reason: AllocationCloseReason,
+    ) {
+        self.events.lock().unwrap().push(format!(
+            "closed {} {:?} {}",
+            username, reason, relayed.to_peers
+        ));
+    }
+
+    fn on_permission_added(&self, _five_tuple: &FiveTuple, username: &str, peer: IpAddr) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("permission {} {}", username, peer));
+    }
+}
+
+struct TestAuthHandler;
+#[async_trait]
+impl AuthHandler for TestAuthHandler {
+    async fn auth_handle(&self, ctx: &AuthContext) -> Result<Vec<u8>> {
+        Ok(generate_auth_key(&ctx.username, &ctx.realm, "pass"))
+    }
+}
+
+#[test]
+fn test_events_metrics() {
+    let handler = Arc::new(TestEventHandler::default());
+    let events = Events::new(Some(
+        Arc::clone(&handler) as Arc<dyn EventHandler + Send + Sync>
+    ));
+    let five_tuple = FiveTuple::default();
+    let relayed = RelayCounters::default();
+
+    events.allocation_created(
+        &five_tuple,
+        "user",
+        SocketAddr::from_str("127.0.0.1:5000").unwrap(),
+        crate::proto::PROTO_UDP,
+    );
+    events.relayed_to_peer(&relayed, 10);
+    events.relayed_from_peer(&relayed, 20);
+    events.allocation_closed(
+        &five_tuple,
+        "user",
+        relayed.get(),
+        AllocationCloseReason::Expired,
+    );
+
+    assert_eq!(
+        events.metrics(),
+        ServerMetrics {
+            allocations_created: 1,
+            allocations_expired: 1,
+            allocations_closed: 1,
+            bytes_to_peers: 10,
+            bytes_from_peers: 20,
+            ..Default::default()
+        }
+    );
+    assert_eq!(events.metrics().active_allocations(), 0);
+    assert_eq!(
+        relayed.get(),
+        RelayedBytes {
+            to_peers: 10,
+            from_peers: 20,
+        }
+    );
+    assert_eq!(
+        *handler.events.lock().unwrap(),
+        vec![
+            "created user".to_owned(),
+            "closed user Expired 10".to_owned()
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_server_events() -> Result<()> {
+    let handler = Arc::new(TestEventHandler::default());
+
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let server_port = conn.local_addr()?.port();
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(Net::new(None)),
+            }),
+        }],
+        listener_configs: vec![],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler {}),
+        channel_bind_timeout: Duration::from_secs(0),
+        event_handler: Some(Arc::clone(&handler) as Arc<dyn EventHandler + Send + Sync>),
+    })
+    .await?;
+
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: format!("127.0.0.1:{}", server_port),
+        turn_serv_addr: format!("127.0.0.1:{}", server_port),
+        username: "user".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+    client.listen().await?;
+
+    let relay_conn = client.allocate().await?;
+    assert_eq!(server.metrics().active_allocations(), 1);
+
+    let peer = client
+        .send_binding_request_to(format!("127.0.0.1:{}", server_port).as_str())
+        .await?;
+    relay_conn.send_to(b"Hello", peer).await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    relay_conn.close().await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let metrics = server.metrics();
+    assert_eq!(metrics.allocations_created, 1);
+    assert_eq!(metrics.allocations_closed, 1);
+    assert_eq!(metrics.allocations_expired, 0);
+    assert_eq!(metrics.permissions_added, 1);
+    assert_eq!(metrics.bytes_to_peers, 5);
+    assert_eq!(
+        *handler.events.lock().unwrap(),
+        vec![
+            "created user".to_owned(),
+            format!("permission user {}", peer.ip()),
+            "closed user Deleted 5".to_owned(),
+        ]
+    );
+
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
@@ -2,23 +2,29 @@
 mod server_test;
 
 pub mod config;
+pub mod events;
 pub mod request;
+mod worker;
 
 use crate::{
     allocation::{allocation_manager::*, five_tuple::FiveTuple, AllocationInfo},
     auth::AuthHandler,
     error::*,
-    proto::lifetime::DEFAULT_LIFETIME,
+    proto::{chandata::ChannelData, lifetime::DEFAULT_LIFETIME, PROTO_TCP, PROTO_UDP},
+    stream_conn::{StreamConn, MAX_FRAME_SIZE},
 };
 use config::*;
+use events::*;
 use request::*;
+use worker::*;
 
 use std::{collections::HashMap, sync::Arc};
 
 use tokio::{
+    net::TcpListener,
     sync::{
         broadcast::{self, error::RecvError},
-        mpsc, oneshot, Mutex,
+        mpsc, oneshot, watch, Mutex,
     },
     time::{Duration, Instant},
 };
@@ -33,6 +39,7 @@ pub struct Server {
     channel_bind_timeout: Duration,
     pub(crate) nonces: Arc<Mutex<HashMap<String, Instant>>>,
     command_tx: Mutex<Option<broadcast::Sender<Command>>>,
+    events: Arc<Events>,
 }
 
 impl Server {
@@ -47,6 +54,7 @@ impl Server {
             channel_bind_timeout: config.channel_bind_timeout,
             nonces: Arc::new(Mutex::new(HashMap::new())),
             command_tx: Mutex::new(Some(command_tx.clone())),
+            events: Arc::new(Events::new(config.event_handler)),
         };
 
         if s.channel_bind_timeout == Duration::from_secs(0) {
@@ -62,6 +70,7 @@ impl Server {
             let conn = p.conn;
             let allocation_manager = Arc::new(Manager::new(ManagerConfig {
                 relay_addr_generator: p.relay_addr_generator,
+                events: Arc::clone(&s.events),
             }));
 
             tokio::spawn(Server::read_loop(
@@ -75,6 +84,29 @@ impl Server {
             ));
         }
 
+        for l in config.listener_configs.into_iter() {
+            let nonces = Arc::clone(&s.nonces);
+            let auth_handler = Arc::clone(&s.auth_handler);
+            let realm = s.realm.clone();
+            let channel_bind_timeout = s.channel_bind_timeout;
+            let handle_rx = command_tx.subscribe();
+            let listener = l.listener;
+            let allocation_manager = Arc::new(Manager::new(ManagerConfig {
+                relay_addr_generator: l.relay_addr_generator,
+                events: Arc::clone(&s.events),
+            }));
+
+            tokio::spawn(Server::accept_loop(
+                listener,
+                allocation_manager,
+                nonces,
+                auth_handler,
+                realm,
+                channel_bind_timeout,
+                handle_rx,
+            ));
+        }
+
         Ok(s)
     }
 
@@ -139,6 +171,11 @@ impl Server {
         }
     }
 
+    /// Returns the counters aggregated over all allocations of the server.
+    pub fn metrics(&self) -> ServerMetrics {
+        self.events.metrics()
+    }
+
     async fn read_loop(
         conn: Arc<dyn Conn + Send + Sync>,
         allocation_manager: Arc<Manager>,
@@ -146,42 +183,25 @@ impl Server {
         auth_handler: Arc<dyn AuthHandler + Send + Sync>,
         realm: String,
         channel_bind_timeout: Duration,
-        mut handle_rx: broadcast::Receiver<Command>,
+        handle_rx: broadcast::Receiver<Command>,
     ) {
         let mut buf = vec![0u8; INBOUND_MTU];
 
-        let (mut close_tx, mut close_rx) = oneshot::channel::<()>();
-
-        tokio::spawn({
-            let allocation_manager = Arc::clone(&allocation_manager);
-
-            async move {
-                loop {
-                    match handle_rx.recv().await {
-                        Ok(Command::DeleteAllocations(name, _)) => {
-                            allocation_manager
-                                .delete_allocations_by_username(name.as_str())
-                                .await;
-                            continue;
-                        }
-                        Ok(Command::GetAllocationsInfo(five_tuples, tx)) => {
-                            let infos = allocation_manager.get_allocations_info(five_tuples).await;
-                            let _ = tx.send(infos).await;
-
-                            continue;
-                        }
-                        Err(RecvError::Closed) | Ok(Command::Close(_)) => {
-                            close_rx.close();
-                            break;
-                        }
-                        Err(RecvError::Lagged(n)) => {
-                            log::warn!("Turn server has lagged by {} messages", n);
-                            continue;
-                        }
-                    }
-                }
+        let mut close_tx = Server::command_handler(Arc::clone(&allocation_manager), handle_rx);
+
+        // ChannelData is relayed by workers, other messages are handled in
+        // order by this loop.
+        let workers = match conn.local_addr() {
+            Ok(local_addr) => Some(RelayWorkers::new(
+                RELAY_WORKERS,
+                local_addr,
+                Arc::clone(&allocation_manager),
+            )),
+            Err(err) => {
+                log::warn!("relaying ChannelData without workers: {}", err);
+                None
             }
-        });
+        };
 
         loop {
             let (n, addr) = tokio::select! {
@@ -197,10 +217,19 @@ impl Server {
                 _ = close_tx.closed() => break
             };
 
+            if let Some(workers) = &workers {
+                if ChannelData::is_channel_data(&buf[..n]) {
+                    workers.relay(addr, buf[..n].to_vec());
+                    continue;
+                }
+            }
+
             let mut r = Request {
                 conn: Arc::clone(&conn),
                 src_addr: addr,
                 buff: buf[..n].to_vec(),
+                protocol: PROTO_UDP,
+                peer_connection: None,
                 allocation_manager: Arc::clone(&allocation_manager),
                 nonces: Arc::clone(&nonces),
                 auth_handler: Arc::clone(&auth_handler),
@@ -217,6 +246,179 @@ impl Server {
         let _ = conn.close().await;
     }
 
+    async fn accept_loop(
+        listener: TcpListener,
+        allocation_manager: Arc<Manager>,
+        nonces: Arc<Mutex<HashMap<String, Instant>>>,
+        auth_handler: Arc<dyn AuthHandler + Send + Sync>,
+        realm: String,
+        channel_bind_timeout: Duration,
+        handle_rx: broadcast::Receiver<Command>,
+    ) {
+        let mut close_tx = Server::command_handler(Arc::clone(&allocation_manager), handle_rx);
+
+        // Dropping conn_close_tx stops the read loops of all accepted connections.
+        let (conn_close_tx, conn_close_rx) = watch::channel(());
+
+        loop {
+            let stream = tokio::select! {
+                v = listener.accept() => {
+                    match v {
+                        Ok((stream, _)) => stream,
+                        Err(err) => {
+                            log::debug!("exit accept loop on error: {}", err);
+                            break;
+                        }
+                    }
+                },
+                _ = close_tx.closed() => break
+            };
+
+            let conn = match StreamConn::new(stream) {
+                Ok(conn) => Arc::new(conn),
+                Err(err) => {
+                    log::warn!("failed to accept connection: {}", err);
+                    continue;
+                }
+            };
+
+            tokio::spawn(Server::read_stream_loop(
+                conn,
+                Arc::clone(&allocation_manager),
+                Arc::clone(&nonces),
+                Arc::clone(&auth_handler),
+                realm.clone(),
+                channel_bind_timeout,
+                conn_close_rx.clone(),
+            ));
+        }
+
+        drop(conn_close_tx);
+        let _ = allocation_manager.close().await;
+    }
+
+    // read_stream_loop handles the messages received on a TCP connection. If
+    // the connection becomes a data connection of a TCP allocation, data is
+    // relayed between it and the peer until either side closes.
+    async fn read_stream_loop(
+        conn: Arc<StreamConn>,
+        allocation_manager: Arc<Manager>,
+        nonces: Arc<Mutex<HashMap<String, Instant>>>,
+        auth_handler: Arc<dyn AuthHandler + Send + Sync>,
+        realm: String,
+        channel_bind_timeout: Duration,
+        mut close_rx: watch::Receiver<()>,
+    ) {
+        let mut buf = vec![0u8; MAX_FRAME_SIZE];
+
+        let five_tuple = match (conn.remote_addr(), conn.local_addr()) {
+            (Some(src_addr), Ok(dst_addr)) => FiveTuple {
+                src_addr,
+                dst_addr,
+                protocol: PROTO_TCP,
+            },
+            _ => return,
+        };
+
+        loop {
+            let n = tokio::select! {
+                v = conn.recv(&mut buf) => {
+                    match v {
+                        Ok(n) => n,
+                        Err(err) => {
+                            log::debug!("exit read loop of {} on error: {}", five_tuple, err);
+                            break;
+                        }
+                    }
+                },
+                _ = close_rx.changed() => break
+            };
+
+            let mut r = Request {
+                conn: Arc::clone(&conn) as Arc<dyn Conn + Send + Sync>,
+                src_addr: five_tuple.src_addr,
+                buff: buf[..n].to_vec(),
+                protocol: PROTO_TCP,
+                peer_connection: None,
+                allocation_manager: Arc::clone(&allocation_manager),
+                nonces: Arc::clone(&nonces),
+                auth_handler: Arc::clone(&auth_handler),
+                realm: realm.clone(),
+                channel_bind_timeout,
+            };
+
+            if let Err(err) = r.handle_request().await {
+                log::error!("error when handling stream message: {}", err);
+            }
+
+            if let Some(mut peer_connection) = r.peer_connection.take() {
+                match conn.take_stream().await {
+                    Ok(mut stream) => {
+                        tokio::select! {
+                            result = tokio::io::copy_bidirectional(&mut stream, &mut peer_connection.stream) => {
+                                match result {
+                                    Ok((to_peer, from_peer)) => {
+                                        if let Some(a) = peer_connection.allocation.upgrade() {
+                                            a.relayed_to_peer(to_peer as usize);
+                                            a.relayed_from_peer(from_peer as usize);
+                                        }
+                                    }
+                                    Err(err) => log::debug!("relaying to {} stopped: {}", peer_connection.peer, err),
+                                }
+                            },
+                            _ = close_rx.changed() => {}
+                        }
+                    }
+                    Err(err) => log::error!("failed to take data connection: {}", err),
+                }
+                break;
+            }
+        }
+
+        // Closing the control connection deletes its allocation.
+        allocation_manager.delete_allocation(&five_tuple).await;
+        let _ = conn.close().await;
+    }
+
+    // command_handler handles the commands sent by the public methods for the
+    // allocations of allocation_manager. The returned sender is closed once
+    // the server is closed.
+    fn command_handler(
+        allocation_manager: Arc<Manager>,
+        mut handle_rx: broadcast::Receiver<Command>,
+    ) -> oneshot::Sender<()> {
+        let (close_tx, mut close_rx) = oneshot::channel::<()>();
+
+        tokio::spawn(async move {
+            loop {
+                match handle_rx.recv().await {
+                    Ok(Command::DeleteAllocations(name, _)) => {
+                        allocation_manager
+                            .delete_allocations_by_username(name.as_str())
+                            .await;
+                        continue;
+                    }
+                    Ok(Command::GetAllocationsInfo(five_tuples, tx)) => {
+                        let infos = allocation_manager.get_allocations_info(five_tuples).await;
+                        let _ = tx.send(infos).await;
+
+                        continue;
+                    }
+                    Err(RecvError::Closed) | Ok(Command::Close(_)) => {
+                        close_rx.close();
+                        break;
+                    }
+                    Err(RecvError::Lagged(n)) => {
+                        log::warn!("Turn server has lagged by {} messages", n);
+                        continue;
+                    }
+                }
+            }
+        });
+
+        close_tx
+    }
+
     /// Close stops the TURN Server. It cleans up any associated state and closes all connections it is managing
     pub async fn close(&self) -> Result<()> {
         let tx = {
@@ -5,15 +5,18 @@ use crate::allocation::allocation_manager::*;
 use crate::allocation::channel_bind::ChannelBind;
 use crate::allocation::five_tuple::*;
 use crate::allocation::permission::Permission;
+use crate::allocation::tcp_relay::PeerConnection;
 use crate::auth::*;
 use crate::error::*;
 use crate::proto::chandata::ChannelData;
 use crate::proto::channum::ChannelNumber;
-use crate::proto::data::Data;
+use crate::proto::connid::ConnectionId;
 use crate::proto::evenport::EvenPort;
 use crate::proto::lifetime::*;
+use crate::proto::mobility::MobilityTicket;
 use crate::proto::peeraddr::PeerAddress;
 use crate::proto::relayaddr::RelayedAddress;
+use crate::proto::reqfamily::*;
 use crate::proto::reqtrans::RequestedTransport;
 use crate::proto::rsrvtoken::ReservationToken;
 use crate::proto::*;
@@ -24,6 +27,7 @@ use stun::error_code::*;
 use stun::fingerprint::*;
 use stun::integrity::*;
 use stun::message::*;
+use stun::message_view::MessageView;
 use stun::textattrs::*;
 use stun::uattrs::*;
 use stun::xoraddr::*;
@@ -33,8 +37,6 @@ use util::Conn;
 use std::collections::HashMap;
 use std::marker::{Send, Sync};
 use std::net::SocketAddr;
-#[cfg(feature = "metrics")]
-use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::SystemTime;
 use tokio::sync::Mutex;
@@ -51,6 +53,12 @@ pub struct Request {
     pub conn: Arc<dyn Conn + Send + Sync>,
     pub src_addr: SocketAddr,
     pub buff: Vec<u8>,
+    pub protocol: Protocol,
+
+    // peer_connection is set once a ConnectionBind request succeeded. The
+    // connection the request was received on is then used to relay data
+    // to and from the peer.
+    pub peer_connection: Option<PeerConnection>,
 
     // Server State
     pub allocation_manager: Arc<Manager>,
@@ -73,6 +81,8 @@ impl Request {
             conn,
             src_addr,
             buff: vec![],
+            protocol: PROTO_UDP,
+            peer_connection: None,
             allocation_manager,
             nonces: Arc::new(Mutex::new(HashMap::new())),
             auth_handler,
@@ -109,6 +119,14 @@ impl Request {
 
     async fn handle_turn_packet(&mut self) -> Result<()> {
         log::debug!("handle_turn_packet");
+
+        // Send indications carry the relayed data, read it in place instead of copying
+        // the packet into a Message
+        let view = MessageView::parse(&self.buff)?;
+        if view.typ == MessageType::new(METHOD_SEND, CLASS_INDICATION) {
+            return self.handle_send_indication(&view).await;
+        }
+
         let mut m = Message {
             raw: self.buff.clone(),
             ..Default::default()
@@ -119,18 +137,15 @@ impl Request {
     }
 
     async fn process_message_handler(&mut self, m: &Message) -> Result<()> {
-        if m.typ.class == CLASS_INDICATION {
-            match m.typ.method {
-                METHOD_SEND => self.handle_send_indication(m).await,
-                _ => Err(Error::ErrUnexpectedClass),
-            }
-        } else if m.typ.class == CLASS_REQUEST {
+        if m.typ.class == CLASS_REQUEST {
             match m.typ.method {
                 METHOD_ALLOCATE => self.handle_allocate_request(m).await,
                 METHOD_REFRESH => self.handle_refresh_request(m).await,
                 METHOD_CREATE_PERMISSION => self.handle_create_permission_request(m).await,
                 METHOD_CHANNEL_BIND => self.handle_channel_bind_request(m).await,
                 METHOD_BINDING => self.handle_binding_request(m).await,
+                METHOD_CONNECT => self.handle_connect_request(m).await,
+                METHOD_CONNECTION_BIND => self.handle_connection_bind_request(m).await,
                 _ => Err(Error::ErrUnexpectedClass),
             }
         } else {
@@ -200,11 +215,13 @@ impl Request {
             return Ok(None);
         }
 
-        let our_key = match self.auth_handler.auth_handle(
-            &username_attr.to_string(),
-            &realm_attr.to_string(),
-            self.src_addr,
-        ) {
+        let ctx = AuthContext {
+            username: username_attr.to_string(),
+            realm: realm_attr.to_string(),
+            src_addr: self.src_addr,
+            protocol: self.protocol,
+        };
+        let our_key = match self.auth_handler.auth_handle(&ctx).await {
             Ok(key) => key,
             Err(_) => {
                 build_and_send_err(
@@ -297,7 +314,7 @@ impl Request {
         let five_tuple = FiveTuple {
             src_addr: self.src_addr,
             dst_addr: self.conn.local_addr()?,
-            protocol: PROTO_UDP,
+            protocol: self.protocol,
         };
         let mut requested_port = 0;
         let mut reservation_token = "".to_owned();
@@ -328,6 +345,66 @@ impl Request {
             .await;
         }
 
+        // The request may contain an empty MOBILITY-TICKET attribute to ask
+        // for a ticket that lets the client move the allocation to another
+        // transport address. Mobility is only offered to clients reaching the
+        // server over UDP, others are rejected with a 405 (Mobility
+        // Forbidden) error.
+        // https://tools.ietf.org/html/rfc8016#section-3.1
+        let mobility = m.contains(ATTR_MOBILITY_TICKET);
+        if mobility && self.protocol != PROTO_UDP {
+            let msg = build_msg(
+                m.transaction_id,
+                MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
+                vec![Box::new(ErrorCodeAttribute {
+                    code: CODE_MOBILITY_FORBIDDEN,
+                    reason: vec![],
+                })],
+            )?;
+            return build_and_send_err(&self.conn, self.src_addr, msg, Error::ErrMobilityForbidden)
+                .await;
+        }
+
+        // The request may contain a REQUESTED-ADDRESS-FAMILY attribute that
+        // selects the address family of the relayed transport address, which
+        // is IPv4 by default. If the request also contains a RESERVATION-TOKEN
+        // attribute, the server rejects it with a 400 (Bad Request) error, and
+        // if the family is unknown, with a 440 (Address Family not Supported)
+        // error.
+        // https://tools.ietf.org/html/rfc6156#section-4.2
+        let mut requested_family = REQUESTED_FAMILY_IPV4;
+        if m.contains(ATTR_REQUESTED_ADDRESS_FAMILY) {
+            if m.contains(ATTR_RESERVATION_TOKEN) {
+                let bad_request_msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code: CODE_BAD_REQUEST,
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    bad_request_msg,
+                    Error::ErrRequestWithReservationTokenAndFamily,
+                )
+                .await;
+            }
+
+            if let Err(err) = requested_family.get_from(m) {
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code: CODE_ADDR_FAMILY_NOT_SUPPORTED,
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(&self.conn, self.src_addr, msg, err.into()).await;
+            }
+        }
+
         // 3. The server checks if the request contains a REQUESTED-TRANSPORT
         //    attribute.  If the REQUESTED-TRANSPORT attribute is not included
         //    or is malformed, the server rejects the request with a 400 (Bad
@@ -346,6 +423,16 @@ impl Request {
             )?;
             return build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into())
                 .await;
+        } else if requested_transport.protocol == PROTO_TCP {
+            return self
+                .handle_tcp_allocate_request(
+                    m,
+                    five_tuple,
+                    requested_family,
+                    username,
+                    message_integrity,
+                )
+                .await;
         } else if requested_transport.protocol != PROTO_UDP {
             let msg = build_msg(
                 m.transaction_id,
@@ -431,14 +518,18 @@ impl Request {
             let mut random_port = 1;
 
             while random_port % 2 != 0 {
-                random_port = match self.allocation_manager.get_random_even_port().await {
+                random_port = match self
+                    .allocation_manager
+                    .get_random_even_port(requested_family)
+                    .await
+                {
                     Ok(port) => port,
                     Err(err) => {
                         let insufficent_capacity_msg = build_msg(
                             m.transaction_id,
                             MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
                             vec![Box::new(ErrorCodeAttribute {
-                                code: CODE_INSUFFICIENT_CAPACITY,
+                                code: allocation_error_code(&err),
                                 reason: vec![],
                             })],
                         )?;
@@ -475,6 +566,7 @@ impl Request {
                 five_tuple,
                 Arc::clone(&self.conn),
                 requested_port,
+                requested_family,
                 lifetime_duration,
                 username,
             )
@@ -486,7 +578,7 @@ impl Request {
                     m.transaction_id,
                     MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
                     vec![Box::new(ErrorCodeAttribute {
-                        code: CODE_INSUFFICIENT_CAPACITY,
+                        code: allocation_error_code(&err),
                         reason: vec![],
                     })],
                 )?;
@@ -540,6 +632,10 @@ impl Request {
                 )));
             }
 
+            if mobility {
+                response_attrs.push(Box::new(a.new_mobility_ticket()));
+            }
+
             response_attrs.push(Box::new(message_integrity));
             build_msg(
                 m.transaction_id,
@@ -551,10 +647,318 @@ impl Request {
         build_and_send(&self.conn, self.src_addr, msg).await
     }
 
+    // https://tools.ietf.org/html/rfc6062#section-5.1
+    async fn handle_tcp_allocate_request(
+        &mut self,
+        m: &Message,
+        five_tuple: FiveTuple,
+        requested_family: RequestedAddressFamily,
+        username: Username,
+        message_integrity: MessageIntegrity,
+    ) -> Result<()> {
+        let bad_request_msg = build_msg(
+            m.transaction_id,
+            MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
+            vec![Box::new(ErrorCodeAttribute {
+                code: CODE_BAD_REQUEST,
+                reason: vec![],
+            })],
+        )?;
+
+        // If the client connection transport is not TCP or TLS, the server
+        // MUST reject the request with a 400 (Bad Request) error.
+        if self.protocol != PROTO_TCP {
+            return build_and_send_err(
+                &self.conn,
+                self.src_addr,
+                bad_request_msg,
+                Error::ErrTcpAllocationOverUdp,
+            )
+            .await;
+        }
+
+        // If the request contains the DONT-FRAGMENT, EVEN-PORT, or
+        // RESERVATION-TOKEN attribute, the server MUST reject the request with
+        // a 400 (Bad Request) error.
+        if m.contains(ATTR_DONT_FRAGMENT)
+            || m.contains(ATTR_EVEN_PORT)
+            || m.contains(ATTR_RESERVATION_TOKEN)
+        {
+            return build_and_send_err(
+                &self.conn,
+                self.src_addr,
+                bad_request_msg,
+                Error::ErrTcpAllocationUnsupportedOperation,
+            )
+            .await;
+        }
+
+        let lifetime_duration = allocation_lifetime(m);
+        let a = match self
+            .allocation_manager
+            .create_tcp_allocation(
+                five_tuple,
+                Arc::clone(&self.conn),
+                requested_family,
+                lifetime_duration,
+                username,
+            )
+            .await
+        {
+            Ok(a) => a,
+            Err(err) => {
+                let insufficent_capacity_msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code: allocation_error_code(&err),
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    insufficent_capacity_msg,
+                    err,
+                )
+                .await;
+            }
+        };
+
+        let msg = build_msg(
+            m.transaction_id,
+            MessageType::new(METHOD_ALLOCATE, CLASS_SUCCESS_RESPONSE),
+            vec![
+                Box::new(RelayedAddress {
+                    ip: a.relay_addr.ip(),
+                    port: a.relay_addr.port(),
+                }),
+                Box::new(Lifetime(lifetime_duration)),
+                Box::new(XorMappedAddress {
+                    ip: self.src_addr.ip(),
+                    port: self.src_addr.port(),
+                }),
+                Box::new(message_integrity),
+            ],
+        )?;
+
+        build_and_send(&self.conn, self.src_addr, msg).await
+    }
+
+    // https://tools.ietf.org/html/rfc6062#section-5.2
+    pub(crate) async fn handle_connect_request(&mut self, m: &Message) -> Result<()> {
+        log::debug!("received ConnectRequest from {}", self.src_addr);
+
+        let (_, message_integrity) =
+            if let Some(mi) = self.authenticate_request(m, METHOD_CONNECT).await? {
+                mi
+            } else {
+                log::debug!("no MessageIntegrity");
+                return Ok(());
+            };
+
+        // If the request is received on a TCP connection for which no
+        // allocation exists, the server MUST return a 437 (Allocation
+        // Mismatch) error.
+        let a = self
+            .allocation_manager
+            .get_allocation(&FiveTuple {
+                src_addr: self.src_addr,
+                dst_addr: self.conn.local_addr()?,
+                protocol: self.protocol,
+            })
+            .await;
+        let (relay_addr, tcp_relay) = match a
+            .as_ref()
+            .and_then(|a| a.tcp_relay.as_ref().map(|r| (a.relay_addr, r)))
+        {
+            Some(v) => v,
+            None => {
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_CONNECT, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code: CODE_ALLOC_MISMATCH,
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    msg,
+                    Error::ErrNoAllocationFound,
+                )
+                .await;
+            }
+        };
+
+        // If the request does not contain an XOR-PEER-ADDRESS attribute, or if
+        // such attribute is invalid, the server MUST return a 400 (Bad Request)
+        // error.
+        let mut peer_addr = PeerAddress::default();
+        if let Err(err) = peer_addr.get_from(m) {
+            let bad_request_msg = build_msg(
+                m.transaction_id,
+                MessageType::new(METHOD_CONNECT, CLASS_ERROR_RESPONSE),
+                vec![Box::new(ErrorCodeAttribute {
+                    code: CODE_BAD_REQUEST,
+                    reason: vec![],
+                })],
+            )?;
+            return build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into())
+                .await;
+        }
+        let peer = SocketAddr::new(peer_addr.ip, peer_addr.port);
+
+        // Peers of another address family than the relayed transport address
+        // are rejected with a 443 (Peer Address Family Mismatch) error, as for
+        // CreatePermission requests.
+        if peer.is_ipv4() != relay_addr.is_ipv4() {
+            let msg = build_msg(
+                m.transaction_id,
+                MessageType::new(METHOD_CONNECT, CLASS_ERROR_RESPONSE),
+                vec![Box::new(ErrorCodeAttribute {
+                    code: CODE_PEER_ADDR_FAMILY_MISMATCH,
+                    reason: vec![],
+                })],
+            )?;
+            return build_and_send_err(
+                &self.conn,
+                self.src_addr,
+                msg,
+                Error::ErrPeerAddressFamilyMismatch,
+            )
+            .await;
+        }
+
+        // If there is already a connection to the peer, the server MUST return
+        // a 446 (Connection Already Exists) error. If the connection attempt
+        // fails or times out, the server MUST return a 447 (Connection Timeout
+        // or Failure) error.
+        let id = match tcp_relay.connect(peer).await {
+            Ok(id) => id,
+            Err(err) => {
+                let code = if err == Error::ErrConnectionAlreadyExists {
+                    CODE_CONN_ALREADY_EXISTS
+                } else {
+                    CODE_CONN_TIMEOUT_OR_FAILURE
+                };
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_CONNECT, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code,
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(&self.conn, self.src_addr, msg, err).await;
+            }
+        };
+
+        let msg = build_msg(
+            m.transaction_id,
+            MessageType::new(METHOD_CONNECT, CLASS_SUCCESS_RESPONSE),
+            vec![Box::new(id), Box::new(message_integrity)],
+        )?;
+
+        build_and_send(&self.conn, self.src_addr, msg).await
+    }
+
+    // https://tools.ietf.org/html/rfc6062#section-5.4
+    pub(crate) async fn handle_connection_bind_request(&mut self, m: &Message) -> Result<()> {
+        log::debug!("received ConnectionBindRequest from {}", self.src_addr);
+
+        let (username, message_integrity) =
+            if let Some(mi) = self.authenticate_request(m, METHOD_CONNECTION_BIND).await? {
+                mi
+            } else {
+                log::debug!("no MessageIntegrity");
+                return Ok(());
+            };
+
+        let bad_request_msg = build_msg(
+            m.transaction_id,
+            MessageType::new(METHOD_CONNECTION_BIND, CLASS_ERROR_RESPONSE),
+            vec![Box::new(ErrorCodeAttribute {
+                code: CODE_BAD_REQUEST,
+                reason: vec![],
+            })],
+        )?;
+
+        // If the client connection transport is not TCP or TLS, or if the
+        // connection is already used for an allocation, the server MUST
+        // return a 400 (Bad Request) error.
+        if self.protocol != PROTO_TCP {
+            return build_and_send_err(
+                &self.conn,
+                self.src_addr,
+                bad_request_msg,
+                Error::ErrTcpAllocationOverUdp,
+            )
+            .await;
+        }
+        if self
+            .allocation_manager
+            .get_allocation(&FiveTuple {
+                src_addr: self.src_addr,
+                dst_addr: self.conn.local_addr()?,
+                protocol: self.protocol,
+            })
+            .await
+            .is_some()
+        {
+            return build_and_send_err(
+                &self.conn,
+                self.src_addr,
+                bad_request_msg,
+                Error::ErrRelayAlreadyAllocatedForFiveTuple,
+            )
+            .await;
+        }
+
+        // If the request does not contain the CONNECTION-ID attribute, or if
+        // this attribute does not refer to a pending connection of an
+        // allocation made with the same credentials, the server MUST return
+        // a 400 (Bad Request) error.
+        let mut id = ConnectionId::default();
+        if let Err(err) = id.get_from(m) {
+            return build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into())
+                .await;
+        }
+        let peer_connection = match self
+            .allocation_manager
+            .take_peer_connection(id, &username.text)
+            .await
+        {
+            Some(c) => c,
+            None => {
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    bad_request_msg,
+                    Error::ErrNoSuchConnection,
+                )
+                .await;
+            }
+        };
+
+        let msg = build_msg(
+            m.transaction_id,
+            MessageType::new(METHOD_CONNECTION_BIND, CLASS_SUCCESS_RESPONSE),
+            vec![Box::new(message_integrity)],
+        )?;
+        build_and_send(&self.conn, self.src_addr, msg).await?;
+
+        // From now on the connection only carries application data.
+        self.peer_connection = Some(peer_connection);
+
+        Ok(())
+    }
+
     pub(crate) async fn handle_refresh_request(&mut self, m: &Message) -> Result<()> {
         log::debug!("received RefreshRequest from {}", self.src_addr);
 
-        let (_, message_integrity) =
+        let (username, message_integrity) =
             if let Some(mi) = self.authenticate_request(m, METHOD_REFRESH).await? {
                 mi
             } else {
@@ -566,13 +970,76 @@ impl Request {
         let five_tuple = FiveTuple {
             src_addr: self.src_addr,
             dst_addr: self.conn.local_addr()?,
-            protocol: PROTO_UDP,
+            protocol: self.protocol,
         };
 
+        // A Refresh request with a MOBILITY-TICKET attribute received from a
+        // transport address without allocation moves the allocation the
+        // ticket was issued for to that address. The success response
+        // carries a new ticket, which replaces the previous one.
+        // https://tools.ietf.org/html/rfc8016#section-3.2
+        let mut mobility_ticket = MobilityTicket::default();
+        let mobility = mobility_ticket.get_from(m).is_ok();
+        if mobility
+            && lifetime_duration != Duration::from_secs(0)
+            && self
+                .allocation_manager
+                .get_allocation(&five_tuple)
+                .await
+                .is_none()
+        {
+            if let Err(err) = self
+                .allocation_manager
+                .move_allocation(&mobility_ticket, five_tuple, &username.text)
+                .await
+            {
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_REFRESH, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code: mobility_error_code(&err),
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(&self.conn, self.src_addr, msg, err).await;
+            }
+        }
+
+        // If the request contains a REQUESTED-ADDRESS-FAMILY attribute that
+        // does not match the address family of the allocation, the server
+        // rejects it with a 443 (Peer Address Family Mismatch) error.
+        // https://tools.ietf.org/html/rfc6156#section-5.2
+        let mut requested_family = RequestedAddressFamily::default();
+        if requested_family.get_from(m).is_ok() {
+            if let Some(a) = self.allocation_manager.get_allocation(&five_tuple).await {
+                if a.relay_addr.is_ipv4() != (requested_family == REQUESTED_FAMILY_IPV4) {
+                    let msg = build_msg(
+                        m.transaction_id,
+                        MessageType::new(METHOD_REFRESH, CLASS_ERROR_RESPONSE),
+                        vec![Box::new(ErrorCodeAttribute {
+                            code: CODE_PEER_ADDR_FAMILY_MISMATCH,
+                            reason: vec![],
+                        })],
+                    )?;
+                    return build_and_send_err(
+                        &self.conn,
+                        self.src_addr,
+                        msg,
+                        Error::ErrPeerAddressFamilyMismatch,
+                    )
+                    .await;
+                }
+            }
+        }
+
+        let mut mobility_ticket = None;
         if lifetime_duration != Duration::from_secs(0) {
             let a = self.allocation_manager.get_allocation(&five_tuple).await;
             if let Some(a) = a {
                 a.refresh(lifetime_duration).await;
+                if mobility && self.protocol == PROTO_UDP {
+                    mobility_ticket = Some(a.new_mobility_ticket());
+                }
             } else {
                 return Err(Error::ErrNoAllocationFound);
             }
@@ -580,14 +1047,20 @@ impl Request {
             self.allocation_manager.delete_allocation(&five_tuple).await;
         }
 
-        let msg = build_msg(
-            m.transaction_id,
-            MessageType::new(METHOD_REFRESH, CLASS_SUCCESS_RESPONSE),
-            vec![
-                Box::new(Lifetime(lifetime_duration)),
-                Box::new(message_integrity),
-            ],
-        )?;
+        let msg = {
+            let mut response_attrs: Vec<Box<dyn Setter>> =
+                vec![Box::new(Lifetime(lifetime_duration))];
+            if let Some(mobility_ticket) = mobility_ticket {
+                response_attrs.push(Box::new(mobility_ticket));
+            }
+            response_attrs.push(Box::new(message_integrity));
+
+            build_msg(
+                m.transaction_id,
+                MessageType::new(METHOD_REFRESH, CLASS_SUCCESS_RESPONSE),
+                response_attrs,
+            )?
+        };
 
         build_and_send(&self.conn, self.src_addr, msg).await
     }
@@ -600,7 +1073,7 @@ impl Request {
             .get_allocation(&FiveTuple {
                 src_addr: self.src_addr,
                 dst_addr: self.conn.local_addr()?,
-                protocol: PROTO_UDP,
+                protocol: self.protocol,
             })
             .await;
 
@@ -614,32 +1087,44 @@ impl Request {
                 log::debug!("no MessageIntegrity");
                 return Ok(());
             };
-            let mut add_count = 0;
-
+            let peer_addresses = PeerAddress::get_all_from(m).unwrap_or_default();
+
+            // If an XOR-PEER-ADDRESS attribute contains an address of another
+            // family than the relayed transport address, the server rejects
+            // the request with a 443 (Peer Address Family Mismatch) error and
+            // installs none of the permissions.
+            // https://tools.ietf.org/html/rfc6156#section-6.2
+            if peer_addresses
+                .iter()
+                .any(|p| p.ip.is_ipv4() != a.relay_addr.is_ipv4())
             {
-                for attr in &m.attributes.0 {
-                    if attr.typ != ATTR_XOR_PEER_ADDRESS {
-                        continue;
-                    }
-
-                    let mut peer_address = PeerAddress::default();
-                    if peer_address.get_from(m).is_err() {
-                        add_count = 0;
-                        break;
-                    }
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_CREATE_PERMISSION, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code: CODE_PEER_ADDR_FAMILY_MISMATCH,
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    msg,
+                    Error::ErrPeerAddressFamilyMismatch,
+                )
+                .await;
+            }
 
-                    log::debug!(
-                        "adding permission for {}",
-                        format!("{}:{}", peer_address.ip, peer_address.port)
-                    );
+            let mut add_count = 0;
+            for peer_address in &peer_addresses {
+                log::debug!("adding permission for {}", peer_address);
 
-                    a.add_permission(Permission::new(SocketAddr::new(
-                        peer_address.ip,
-                        peer_address.port,
-                    )))
-                    .await;
-                    add_count += 1;
-                }
+                a.add_permission(Permission::new(SocketAddr::new(
+                    peer_address.ip,
+                    peer_address.port,
+                )))
+                .await;
+                add_count += 1;
             }
 
             let mut resp_class = CLASS_SUCCESS_RESPONSE;
@@ -659,7 +1144,7 @@ impl Request {
         }
     }
 
-    pub(crate) async fn handle_send_indication(&mut self, m: &Message) -> Result<()> {
+    pub(crate) async fn handle_send_indication(&self, m: &MessageView<'_>) -> Result<()> {
         log::debug!("received SendIndication from {}", self.src_addr);
 
         let a = self
@@ -667,31 +1152,32 @@ impl Request {
             .get_allocation(&FiveTuple {
                 src_addr: self.src_addr,
                 dst_addr: self.conn.local_addr()?,
-                protocol: PROTO_UDP,
+                protocol: self.protocol,
             })
             .await;
 
         if let Some(a) = a {
-            let mut data_attr = Data::default();
-            data_attr.get_from(m)?;
-
-            let mut peer_address = PeerAddress::default();
-            peer_address.get_from(m)?;
-
-            let msg_dst = SocketAddr::new(peer_address.ip, peer_address.port);
+            let data = m.get(ATTR_DATA)?;
+            let msg_dst = m.get_xor_address(ATTR_XOR_PEER_ADDRESS)?;
+            if msg_dst.is_ipv4() != a.relay_addr.is_ipv4() {
+                return Err(Error::ErrPeerAddressFamilyMismatch);
+            }
 
             let has_perm = a.has_permission(&msg_dst).await;
             if !has_perm {
                 return Err(Error::ErrNoPermission);
             }
 
-            let l = a.relay_socket.send_to(&data_attr.0, msg_dst).await?;
-            if l != data_attr.0.len() {
+            let relay_socket = match &a.relay_socket {
+                Some(relay_socket) => relay_socket,
+                None => return Err(Error::ErrTcpAllocationUnsupportedOperation),
+            };
+
+            let l = relay_socket.send_to(data, msg_dst).await?;
+            if l != data.len() {
                 Err(Error::ErrShortWrite)
             } else {
-                #[cfg(feature = "metrics")]
-                a.relayed_bytes
-                    .fetch_add(data_attr.0.len(), Ordering::AcqRel);
+                a.relayed_to_peer(data.len());
 
                 Ok(())
             }
@@ -708,7 +1194,7 @@ impl Request {
             .get_allocation(&FiveTuple {
                 src_addr: self.src_addr,
                 dst_addr: self.conn.local_addr()?,
-                protocol: PROTO_UDP,
+                protocol: self.protocol,
             })
             .await;
 
@@ -729,6 +1215,17 @@ impl Request {
                     log::debug!("no MessageIntegrity");
                     return Ok(());
                 };
+            // Channels are not used with TCP allocations.
+            if a.tcp_relay.is_some() {
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    bad_request_msg,
+                    Error::ErrTcpAllocationUnsupportedOperation,
+                )
+                .await;
+            }
+
             let mut channel = ChannelNumber::default();
             if let Err(err) = channel.get_from(m) {
                 return build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into())
@@ -741,6 +1238,25 @@ impl Request {
                     .await;
             }
 
+            // https://tools.ietf.org/html/rfc6156#section-7.2
+            if peer_addr.ip.is_ipv4() != a.relay_addr.is_ipv4() {
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_CHANNEL_BIND, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code: CODE_PEER_ADDR_FAMILY_MISMATCH,
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    msg,
+                    Error::ErrPeerAddressFamilyMismatch,
+                )
+                .await;
+            }
+
             log::debug!(
                 "binding channel {} to {}",
                 channel,
@@ -777,25 +1293,12 @@ impl Request {
             .get_allocation(&FiveTuple {
                 src_addr: self.src_addr,
                 dst_addr: self.conn.local_addr()?,
-                protocol: PROTO_UDP,
+                protocol: self.protocol,
             })
             .await;
 
         if let Some(a) = a {
-            let channel = a.get_channel_addr(&c.number).await;
-            if let Some(peer) = channel {
-                let l = a.relay_socket.send_to(&c.data, peer).await?;
-                if l != c.data.len() {
-                    Err(Error::ErrShortWrite)
-                } else {
-                    #[cfg(feature = "metrics")]
-                    a.relayed_bytes.fetch_add(c.data.len(), Ordering::AcqRel);
-
-                    Ok(())
-                }
-            } else {
-                Err(Error::ErrNoSuchChannelBind)
-            }
+            a.relay_channel_data(c).await
         } else {
             Err(Error::ErrNoAllocationFound)
         }
@@ -875,6 +1378,29 @@ pub(crate) fn build_msg(
     Ok(msg)
 }
 
+// allocation_error_code returns the error code of a failed Allocate request:
+// 440 (Address Family not Supported) if no relayed transport address of the
+// requested family can be allocated, and 508 (Insufficient Capacity) otherwise.
+pub(crate) fn allocation_error_code(err: &Error) -> ErrorCode {
+    if *err == Error::ErrRequestedFamilyNotSupported {
+        CODE_ADDR_FAMILY_NOT_SUPPORTED
+    } else {
+        CODE_INSUFFICIENT_CAPACITY
+    }
+}
+
+// mobility_error_code returns the error code of a Refresh request that
+// failed to move an allocation with a mobility ticket.
+// https://tools.ietf.org/html/rfc8016#section-3.2
+pub(crate) fn mobility_error_code(err: &Error) -> ErrorCode {
+    match err {
+        Error::ErrMobilityForbidden => CODE_MOBILITY_FORBIDDEN,
+        Error::ErrMobilityTicketWrongCredentials => CODE_WRONG_CREDENTIALS,
+        Error::ErrDupeFiveTuple => CODE_ALLOC_MISMATCH,
+        _ => CODE_BAD_REQUEST,
+    }
+}
+
 pub(crate) fn allocation_lifetime(m: &Message) -> Duration {
     let mut lifetime_duration = DEFAULT_LIFETIME;
 
@@ -1,6 +1,8 @@
 use super::*;
 use crate::relay::relay_none::*;
+use crate::server::events::Events;
 
+use async_trait::async_trait;
 use std::{net::IpAddr, str::FromStr};
 use tokio::{
     net::UdpSocket,
@@ -52,8 +54,9 @@ async fn test_allocation_lifetime_overflow() -> Result<()> {
 }
 
 struct TestAuthHandler;
+#[async_trait]
 impl AuthHandler for TestAuthHandler {
-    fn auth_handle(&self, _username: &str, _realm: &str, _src_addr: SocketAddr) -> Result<Vec<u8>> {
+    async fn auth_handle(&self, _ctx: &AuthContext) -> Result<Vec<u8>> {
         Ok(STATIC_KEY.as_bytes().to_vec())
     }
 }
@@ -69,6 +72,7 @@ async fn test_allocation_lifetime_deletion_zero_lifetime() -> Result<()> {
             address: "0.0.0.0".to_owned(),
             net: Arc::new(Net::new(None)),
         }),
+        events: Arc::new(Events::default()),
     }));
 
     let socket = SocketAddr::new(IpAddr::from_str("127.0.0.1")?, 5000);
@@ -91,6 +95,7 @@ async fn test_allocation_lifetime_deletion_zero_lifetime() -> Result<()> {
             five_tuple.clone(),
             Arc::clone(&r.conn),
             0,
+            REQUESTED_FAMILY_IPV4,
             Duration::from_secs(3600),
             TextAttribute::new(ATTR_USERNAME, "user".into()),
         )
@@ -117,3 +122,84 @@ async fn test_allocation_lifetime_deletion_zero_lifetime() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_refresh_with_mobility_ticket() -> Result<()> {
+    let l = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+
+    let allocation_manager = Arc::new(Manager::new(ManagerConfig {
+        relay_addr_generator: Box::new(RelayAddressGeneratorNone {
+            address: "0.0.0.0".to_owned(),
+            net: Arc::new(Net::new(None)),
+        }),
+        events: Arc::new(Events::default()),
+    }));
+
+    let five_tuple = FiveTuple {
+        src_addr: SocketAddr::new(IpAddr::from_str("127.0.0.1")?, 5000),
+        dst_addr: l.local_addr()?,
+        protocol: PROTO_UDP,
+    };
+    let a = allocation_manager
+        .create_allocation(
+            five_tuple,
+            Arc::clone(&l) as Arc<dyn Conn + Send + Sync>,
+            0,
+            REQUESTED_FAMILY_IPV4,
+            Duration::from_secs(3600),
+            TextAttribute::new(ATTR_USERNAME, STATIC_KEY.into()),
+        )
+        .await?;
+    let ticket = a.new_mobility_ticket();
+
+    // The client moved to another port.
+    let new_src_addr = SocketAddr::new(IpAddr::from_str("127.0.0.1")?, 5001);
+    let mut r = Request::new(
+        l,
+        new_src_addr,
+        Arc::clone(&allocation_manager),
+        Arc::new(TestAuthHandler {}),
+    );
+    {
+        let mut nonces = r.nonces.lock().await;
+        nonces.insert(STATIC_KEY.to_owned(), Instant::now());
+    }
+
+    let refresh = |ticket: &MobilityTicket| -> Result<Message> {
+        let mut m = Message::new();
+        Lifetime(Duration::from_secs(600)).add_to(&mut m)?;
+        ticket.add_to(&mut m)?;
+        MessageIntegrity(STATIC_KEY.as_bytes().to_vec()).add_to(&mut m)?;
+        Nonce::new(ATTR_NONCE, STATIC_KEY.to_owned()).add_to(&mut m)?;
+        Realm::new(ATTR_REALM, STATIC_KEY.to_owned()).add_to(&mut m)?;
+        Username::new(ATTR_USERNAME, STATIC_KEY.to_owned()).add_to(&mut m)?;
+        Ok(m)
+    };
+
+    let result = r
+        .handle_refresh_request(&refresh(&MobilityTicket(vec![1, 2, 3]))?)
+        .await;
+    assert_eq!(result.err(), Some(Error::ErrInvalidMobilityTicket));
+
+    r.handle_refresh_request(&refresh(&ticket)?).await?;
+
+    let new_five_tuple = FiveTuple {
+        src_addr: new_src_addr,
+        ..five_tuple
+    };
+    assert!(allocation_manager
+        .get_allocation(&five_tuple)
+        .await
+        .is_none());
+    assert!(allocation_manager
+        .get_allocation(&new_five_tuple)
+        .await
+        .is_some());
+    assert_eq!(a.five_tuple(), new_five_tuple);
+    assert!(
+        !a.has_mobility_ticket(&ticket),
+        "the ticket should be replaced once used"
+    );
+
+    Ok(())
+}
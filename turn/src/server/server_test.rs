@@ -1,11 +1,12 @@
 use super::config::*;
 use super::*;
-use crate::auth::generate_auth_key;
+use crate::auth::{generate_auth_key, AuthContext};
 use crate::client::*;
 use crate::error::*;
 use crate::relay::relay_static::*;
 
 use crate::relay::relay_none::RelayAddressGeneratorNone;
+use async_trait::async_trait;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::str::FromStr;
 use tokio::net::UdpSocket;
@@ -28,9 +29,10 @@ impl TestAuthHandler {
     }
 }
 
+#[async_trait]
 impl AuthHandler for TestAuthHandler {
-    fn auth_handle(&self, username: &str, _realm: &str, _src_addr: SocketAddr) -> Result<Vec<u8>> {
-        if let Some(pw) = self.cred_map.get(username) {
+    async fn auth_handle(&self, ctx: &AuthContext) -> Result<Vec<u8>> {
+        if let Some(pw) = self.cred_map.get(&ctx.username) {
             Ok(pw.to_vec())
         } else {
             Err(Error::ErrFakeErr)
@@ -46,20 +48,16 @@ async fn test_server_simple() -> Result<()> {
     let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
     let server_port = conn.local_addr()?.port();
 
-    let server = Server::new(ServerConfig {
-        conn_configs: vec![ConnConfig {
-            conn,
-            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
-                relay_address: IpAddr::from_str("127.0.0.1")?,
-                address: "0.0.0.0".to_owned(),
-                net: Arc::new(net::Net::new(None)),
-            }),
-        }],
-        realm: "webrtc.rs".to_owned(),
-        auth_handler: Arc::new(TestAuthHandler::new()),
-        channel_bind_timeout: Duration::from_secs(0),
-    })
-    .await?;
+    let mut config = ServerConfig::new("webrtc.rs".to_owned(), Arc::new(TestAuthHandler::new()));
+    config.conn_configs.push(ConnConfig {
+        conn,
+        relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+            relay_address: IpAddr::from_str("127.0.0.1")?,
+            address: "0.0.0.0".to_owned(),
+            net: Arc::new(net::Net::new(None)),
+        }),
+    });
+    let server = Server::new(config).await?;
 
     assert_eq!(
         DEFAULT_LIFETIME, server.channel_bind_timeout,
@@ -189,9 +187,11 @@ async fn build_vnet() -> Result<VNet> {
                 net: Arc::clone(&net0),
             }),
         }],
+        listener_configs: vec![],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler::new()),
         channel_bind_timeout: Duration::from_secs(0),
+        event_handler: None,
     })
     .await?;
 
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod worker_test;
+
+use crate::allocation::{allocation_manager::Manager, five_tuple::FiveTuple, Allocation};
+use crate::error::*;
+use crate::proto::{chandata::ChannelData, PROTO_UDP};
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+// RELAY_WORKERS is the number of tasks relaying the ChannelData messages
+// received on a UDP listener.
+pub(crate) const RELAY_WORKERS: usize = 4;
+
+// RELAY_QUEUE_SIZE is the number of ChannelData messages a worker buffers.
+// Messages received while the queue of their worker is full are dropped,
+// as the network would drop them.
+const RELAY_QUEUE_SIZE: usize = 1024;
+
+struct Packet {
+    src_addr: SocketAddr,
+    data: Vec<u8>,
+}
+
+// RelayWorkers relays ChannelData messages to peers outside of the read
+// loop of a listener. All messages from a client go to the same worker, so
+// that they are relayed in order, while different clients are relayed in
+// parallel. Workers cache the allocations they relay for, so that relaying
+// does not lock the allocation map of the manager.
+pub(crate) struct RelayWorkers {
+    queues: Vec<mpsc::Sender<Packet>>,
+}
+
+impl RelayWorkers {
+    // new starts n workers relaying the messages received on the listener
+    // with address local_addr for the allocations of allocation_manager.
+    pub(crate) fn new(n: usize, local_addr: SocketAddr, allocation_manager: Arc<Manager>) -> Self {
+        let queues = (0..n.max(1))
+            .map(|_| {
+                let (queue_tx, queue_rx) = mpsc::channel(RELAY_QUEUE_SIZE);
+                tokio::spawn(RelayWorkers::run(
+                    queue_rx,
+                    local_addr,
+                    Arc::clone(&allocation_manager),
+                ));
+                queue_tx
+            })
+            .collect();
+
+        RelayWorkers { queues }
+    }
+
+    // relay queues a ChannelData message received from src_addr.
+    pub(crate) fn relay(&self, src_addr: SocketAddr, data: Vec<u8>) {
+        let queue = &self.queues[worker_index(&src_addr, self.queues.len())];
+        if queue.try_send(Packet { src_addr, data }).is_err() {
+            log::debug!("relay queue full, dropping ChannelData from {}", src_addr);
+        }
+    }
+
+    async fn run(
+        mut queue_rx: mpsc::Receiver<Packet>,
+        local_addr: SocketAddr,
+        allocation_manager: Arc<Manager>,
+    ) {
+        let mut allocations: HashMap<SocketAddr, Arc<Allocation>> = HashMap::new();
+
+        while let Some(p) = queue_rx.recv().await {
+            if let Err(err) =
+                RelayWorkers::relay_packet(&mut allocations, local_addr, &allocation_manager, p)
+                    .await
+            {
+                log::error!("error when relaying ChannelData: {}", err);
+            }
+        }
+    }
+
+    async fn relay_packet(
+        allocations: &mut HashMap<SocketAddr, Arc<Allocation>>,
+        local_addr: SocketAddr,
+        allocation_manager: &Manager,
+        p: Packet,
+    ) -> Result<()> {
+        let mut c = ChannelData {
+            raw: p.data,
+            ..Default::default()
+        };
+        c.decode()?;
+
+        let a = match allocations.get(&p.src_addr) {
+            Some(a) if is_cached(&p.src_addr, a) => Arc::clone(a),
+            _ => {
+                // Drop the allocations closed or moved to another client
+                // address since the last miss.
+                allocations.retain(|src_addr, a| is_cached(src_addr, a));
+
+                let a = allocation_manager
+                    .get_allocation(&FiveTuple {
+                        src_addr: p.src_addr,
+                        dst_addr: local_addr,
+                        protocol: PROTO_UDP,
+                    })
+                    .await
+                    .ok_or(Error::ErrNoAllocationFound)?;
+                allocations.insert(p.src_addr, Arc::clone(&a));
+                a
+            }
+        };
+
+        a.relay_channel_data(&c).await
+    }
+}
+
+// is_cached tells whether a worker may keep relaying the messages from
+// src_addr for allocation a.
+fn is_cached(src_addr: &SocketAddr, a: &Allocation) -> bool {
+    !a.is_closed() && a.five_tuple().src_addr == *src_addr
+}
+
+// worker_index returns the worker relaying the messages from src_addr.
+fn worker_index(src_addr: &SocketAddr, workers: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    src_addr.hash(&mut hasher);
+    (hasher.finish() % workers as u64) as usize
+}
@@ -0,0 +1,85 @@
+use super::*;
+use crate::allocation::allocation_manager::ManagerConfig;
+use crate::allocation::channel_bind::ChannelBind;
+use crate::proto::channum::ChannelNumber;
+use crate::proto::reqfamily::REQUESTED_FAMILY_IPV4;
+use crate::relay::relay_none::RelayAddressGeneratorNone;
+use crate::server::events::Events;
+
+use stun::attributes::ATTR_USERNAME;
+use stun::textattrs::TextAttribute;
+use tokio::net::UdpSocket;
+use tokio::time::Duration;
+use util::vnet::net::Net;
+
+#[test]
+fn test_worker_index() {
+    let src_addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+    let index = worker_index(&src_addr, 4);
+    assert!(index < 4);
+    for _ in 0..10 {
+        assert_eq!(
+            index,
+            worker_index(&src_addr, 4),
+            "messages from a client should always go to the same worker"
+        );
+    }
+    assert_eq!(worker_index(&src_addr, 1), 0);
+}
+
+#[tokio::test]
+async fn test_relay_workers() -> Result<()> {
+    let manager = Arc::new(Manager::new(ManagerConfig {
+        relay_addr_generator: Box::new(RelayAddressGeneratorNone {
+            address: "0.0.0.0".to_owned(),
+            net: Arc::new(Net::new(None)),
+        }),
+        events: Arc::new(Events::default()),
+    }));
+
+    let turn_socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let local_addr = turn_socket.local_addr()?;
+    let src_addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+    let a = manager
+        .create_allocation(
+            FiveTuple {
+                src_addr,
+                dst_addr: local_addr,
+                protocol: PROTO_UDP,
+            },
+            turn_socket,
+            0,
+            REQUESTED_FAMILY_IPV4,
+            Duration::from_secs(60),
+            TextAttribute::new(ATTR_USERNAME, "user".into()),
+        )
+        .await?;
+
+    let peer = UdpSocket::bind("127.0.0.1:0").await?;
+    a.add_channel_bind(
+        ChannelBind::new(ChannelNumber(0x4000), peer.local_addr()?),
+        Duration::from_secs(60),
+    )
+    .await?;
+
+    let mut c = ChannelData {
+        data: b"hello".to_vec(),
+        number: ChannelNumber(0x4000),
+        ..Default::default()
+    };
+    c.encode();
+
+    let workers = RelayWorkers::new(2, local_addr, Arc::clone(&manager));
+    workers.relay(src_addr, c.raw.clone());
+
+    let mut buf = vec![0u8; 1500];
+    let (n, from) = tokio::time::timeout(Duration::from_secs(5), peer.recv_from(&mut buf))
+        .await
+        .map_err(|_| Error::Other("timed out waiting for relayed data".to_owned()))??;
+    assert_eq!(&buf[..n], b"hello");
+    assert_eq!(from.port(), a.relay_addr.port());
+
+    manager.close().await?;
+
+    Ok(())
+}
@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod stream_conn_test;
+
+use crate::error::*;
+use crate::proto::channum::ChannelNumber;
+
+use util::Conn;
+
+use async_trait::async_trait;
+use std::io;
+use std::net::SocketAddr;
+use stun::message::MESSAGE_HEADER_SIZE;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+#[cfg(feature = "tls")]
+use tokio_rustls::{client::TlsStream, webpki, TlsConnector};
+
+const FRAME_HEADER_SIZE: usize = 4;
+const PADDING: usize = 4;
+
+// MAX_FRAME_SIZE is the size of the largest STUN or ChannelData message.
+pub(crate) const MAX_FRAME_SIZE: usize = MESSAGE_HEADER_SIZE + u16::MAX as usize;
+
+// read_frame reads a single STUN message or ChannelData message from a
+// stream as described in RFC 5766 Section 2.1: STUN messages are delimited
+// by the length in their header, and ChannelData messages are padded to a
+// multiple of four bytes. It never reads past the end of the frame, so the
+// stream can be handed over to a relayed connection afterwards.
+pub(crate) async fn read_frame<R: AsyncRead + Unpin>(r: &mut R, buf: &mut [u8]) -> Result<usize> {
+    if buf.len() < FRAME_HEADER_SIZE {
+        return Err(Error::ErrShortBuffer);
+    }
+    r.read_exact(&mut buf[..FRAME_HEADER_SIZE]).await?;
+
+    let length = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+    let size = if ChannelNumber(u16::from_be_bytes([buf[0], buf[1]])).valid() {
+        let n = FRAME_HEADER_SIZE + length;
+        (n + PADDING - 1) / PADDING * PADDING
+    } else {
+        MESSAGE_HEADER_SIZE + length
+    };
+
+    if buf.len() < size {
+        return Err(Error::ErrShortBuffer);
+    }
+    r.read_exact(&mut buf[FRAME_HEADER_SIZE..size]).await?;
+
+    Ok(size)
+}
+
+// StreamConn carries STUN and ChannelData messages over a TCP or TLS
+// connection, presenting it as a Conn to the TURN client and server.
+pub struct StreamConn<S = TcpStream> {
+    reader: Mutex<Option<ReadHalf<S>>>,
+    writer: Mutex<Option<WriteHalf<S>>>,
+    local_addr: SocketAddr,
+    remote_addr: SocketAddr,
+}
+
+impl StreamConn<TcpStream> {
+    pub fn new(stream: TcpStream) -> Result<Self> {
+        let local_addr = stream.local_addr()?;
+        let remote_addr = stream.peer_addr()?;
+        Ok(StreamConn::from_stream(stream, local_addr, remote_addr))
+    }
+
+    // dial opens a TCP connection to a TURN server.
+    pub async fn dial(addr: SocketAddr) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        StreamConn::new(stream)
+    }
+}
+
+#[cfg(feature = "tls")]
+impl StreamConn<TlsStream<TcpStream>> {
+    // dial_tls opens a TLS connection to a TURN server, as used for `turns:`
+    // URIs with transport=tcp. The server certificate is validated against
+    // server_name by the connector, whose rustls ClientConfig controls trusted
+    // roots, client certificates and custom verifiers.
+    // stun::stream::default_tls_connector trusts the webpki root certificates.
+    pub async fn dial_tls(
+        addr: SocketAddr,
+        server_name: &str,
+        connector: &TlsConnector,
+    ) -> Result<Self> {
+        let domain = webpki::DNSNameRef::try_from_ascii_str(server_name)
+            .map_err(|_| Error::ErrInvalidServerName)?;
+
+        let tcp = TcpStream::connect(addr).await?;
+        let local_addr = tcp.local_addr()?;
+        let remote_addr = tcp.peer_addr()?;
+        let stream = connector.connect(domain, tcp).await?;
+
+        Ok(StreamConn::from_stream(stream, local_addr, remote_addr))
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> StreamConn<S> {
+    pub fn from_stream(stream: S, local_addr: SocketAddr, remote_addr: SocketAddr) -> Self {
+        let (reader, writer) = tokio::io::split(stream);
+        StreamConn {
+            reader: Mutex::new(Some(reader)),
+            writer: Mutex::new(Some(writer)),
+            local_addr,
+            remote_addr,
+        }
+    }
+
+    // take_stream returns the underlying stream, leaving the StreamConn closed.
+    // It is used once a ConnectionBind succeeded and the connection carries
+    // application data only.
+    pub(crate) async fn take_stream(&self) -> Result<S> {
+        let reader = self.reader.lock().await.take();
+        let writer = self.writer.lock().await.take();
+        match (reader, writer) {
+            (Some(reader), Some(writer)) => Ok(reader.unsplit(writer)),
+            _ => Err(Error::ErrClosed),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> Conn for StreamConn<S> {
+    async fn connect(&self, _addr: SocketAddr) -> std::result::Result<(), util::Error> {
+        Err(io::Error::new(io::ErrorKind::Other, "Not applicable").into())
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> std::result::Result<usize, util::Error> {
+        let mut reader = self.reader.lock().await;
+        if let Some(reader) = &mut *reader {
+            read_frame(reader, buf)
+                .await
+                .map_err(|err| util::Error::Other(err.to_string()))
+        } else {
+            Err(util::Error::ErrClosedListener)
+        }
+    }
+
+    async fn recv_from(
+        &self,
+        buf: &mut [u8],
+    ) -> std::result::Result<(usize, SocketAddr), util::Error> {
+        let n = self.recv(buf).await?;
+        Ok((n, self.remote_addr))
+    }
+
+    async fn send(&self, buf: &[u8]) -> std::result::Result<usize, util::Error> {
+        let mut writer = self.writer.lock().await;
+        if let Some(writer) = &mut *writer {
+            writer.write_all(buf).await?;
+            writer.flush().await?;
+            Ok(buf.len())
+        } else {
+            Err(util::Error::ErrClosedListener)
+        }
+    }
+
+    async fn send_to(
+        &self,
+        buf: &[u8],
+        _target: SocketAddr,
+    ) -> std::result::Result<usize, util::Error> {
+        self.send(buf).await
+    }
+
+    fn local_addr(&self) -> std::result::Result<SocketAddr, util::Error> {
+        Ok(self.local_addr)
+    }
+
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.remote_addr)
+    }
+
+    async fn close(&self) -> std::result::Result<(), util::Error> {
+        let mut writer = self.writer.lock().await;
+        if let Some(mut writer) = writer.take() {
+            writer.shutdown().await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use super::*;
+use crate::proto::chandata::ChannelData;
+use crate::proto::channum::MIN_CHANNEL_NUMBER;
+
+use stun::message::{Message, BINDING_REQUEST};
+use stun::xoraddr::XorMappedAddress;
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn test_read_frame() -> Result<()> {
+    let mut m = Message::new();
+    m.build(&[
+        Box::new(BINDING_REQUEST),
+        Box::new(XorMappedAddress {
+            ip: "127.0.0.1".parse()?,
+            port: 1234,
+        }),
+    ])?;
+
+    let mut c = ChannelData {
+        data: vec![1, 2, 3, 4, 5],
+        number: ChannelNumber(MIN_CHANNEL_NUMBER),
+        ..Default::default()
+    };
+    c.encode();
+    assert_eq!(c.raw.len(), 12, "ChannelData must be padded");
+
+    let mut stream = vec![];
+    stream.extend_from_slice(&c.raw);
+    stream.extend_from_slice(&m.raw);
+    stream.extend_from_slice(&c.raw);
+    let mut r = &stream[..];
+
+    let mut buf = vec![0u8; MAX_FRAME_SIZE];
+    let n = read_frame(&mut r, &mut buf).await?;
+    assert_eq!(&buf[..n], &c.raw[..]);
+    let n = read_frame(&mut r, &mut buf).await?;
+    assert_eq!(&buf[..n], &m.raw[..]);
+    let n = read_frame(&mut r, &mut buf).await?;
+    assert_eq!(&buf[..n], &c.raw[..]);
+    assert!(r.is_empty(), "must not read past the last frame");
+
+    let mut r = &m.raw[..];
+    let mut buf = vec![0u8; m.raw.len() - 1];
+    let result = read_frame(&mut r, &mut buf).await;
+    assert_eq!(result, Err(Error::ErrShortBuffer));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stream_conn() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let server_addr = listener.local_addr()?;
+
+    let client = StreamConn::dial(server_addr).await?;
+    let (stream, _) = listener.accept().await?;
+    let server = StreamConn::new(stream)?;
+
+    assert_eq!(client.remote_addr(), Some(server_addr));
+    assert_eq!(server.remote_addr(), Some(client.local_addr()?));
+
+    let mut m = Message::new();
+    m.build(&[Box::new(BINDING_REQUEST)])?;
+    client.send(&m.raw).await?;
+    client.send(&m.raw).await?;
+
+    let mut buf = vec![0u8; MAX_FRAME_SIZE];
+    for _ in 0..2 {
+        let (n, from) = server.recv_from(&mut buf).await?;
+        assert_eq!(&buf[..n], &m.raw[..]);
+        assert_eq!(from, client.local_addr()?);
+    }
+
+    // Once taken, the stream carries raw data.
+    let mut stream = server.take_stream().await?;
+    client.send(b"data").await?;
+    let mut data = [0u8; 4];
+    stream.read_exact(&mut data).await?;
+    assert_eq!(&data, b"data");
+    assert!(server.recv(&mut buf).await.is_err());
+
+    client.close().await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn test_stream_conn_tls() -> Result<()> {
+    use std::sync::Arc;
+    use tokio_rustls::rustls::{self, NoClientAuth, ServerConfig};
+    use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()])
+        .map_err(|err| Error::Other(err.to_string()))?;
+    let der = cert
+        .serialize_der()
+        .map_err(|err| Error::Other(err.to_string()))?;
+    let key = cert.serialize_private_key_der();
+
+    let mut server_config = ServerConfig::new(NoClientAuth::new());
+    server_config
+        .set_single_cert(
+            vec![rustls::Certificate(der.clone())],
+            rustls::PrivateKey(key),
+        )
+        .map_err(|err| Error::Other(err.to_string()))?;
+    let acceptor = TlsAcceptor::from(Arc::new(server_config));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let server_addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            if let Ok(stream) = acceptor.accept(stream).await {
+                let conn = StreamConn::from_stream(stream, server_addr, server_addr);
+                let mut buf = vec![0u8; MAX_FRAME_SIZE];
+                while let Ok(n) = conn.recv(&mut buf).await {
+                    if conn.send(&buf[..n]).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // The self-signed certificate is rejected unless it is trusted.
+    let connector = stun::stream::default_tls_connector();
+    let result = StreamConn::dial_tls(server_addr, "localhost", &connector).await;
+    assert!(result.is_err(), "untrusted certificate must be rejected");
+
+    let mut client_config = rustls::ClientConfig::new();
+    client_config
+        .root_store
+        .add(&rustls::Certificate(der))
+        .map_err(|err| Error::Other(err.to_string()))?;
+    let connector = TlsConnector::from(Arc::new(client_config));
+
+    let result = StreamConn::dial_tls(server_addr, "example.com", &connector).await;
+    assert!(result.is_err(), "certificate must match the server name");
+
+    let client = StreamConn::dial_tls(server_addr, "localhost", &connector).await?;
+    assert_eq!(client.remote_addr(), Some(server_addr));
+
+    let mut m = Message::new();
+    m.build(&[Box::new(BINDING_REQUEST)])?;
+    client.send(&m.raw).await?;
+
+    let mut buf = vec![0u8; MAX_FRAME_SIZE];
+    let n = client.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], &m.raw[..]);
+
+    client.close().await?;
+
+    Ok(())
+}
@@ -1,4 +1,16 @@
 use super::*;
+use crate::dtls_transport::dtls_parameters::DTLSParameters;
+use crate::ice_transport::ice_candidate::RTCIceCandidate;
+use crate::ice_transport::ice_parameters::RTCIceParameters;
+use crate::ice_transport::ice_role::RTCIceRole;
+use crate::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use crate::rtp_transceiver::{RTCRtpDecodingParameters, RTCRtpReceiveParameters};
+use crate::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
+use crate::track::track_local::TrackLocalWriter;
+
+use bytes::Bytes;
+use std::time::Duration;
+use tokio::sync::mpsc;
 
 #[test]
 fn test_new_api() -> Result<()> {
@@ -17,10 +29,152 @@ fn test_new_api() -> Result<()> {
         "Failed to set settings engine"
     );
     assert_eq!(
-        api.media_engine.audio_codecs.is_empty(),
+        api.media_engine.audio_codecs.lock().unwrap().is_empty(),
         false,
         "Failed to set media engine"
     );
 
     Ok(())
 }
+
+struct OrtcMediaStack {
+    gatherer: Arc<RTCIceGatherer>,
+    ice: Arc<RTCIceTransport>,
+    dtls: Arc<RTCDtlsTransport>,
+}
+
+impl OrtcMediaStack {
+    fn new(api: &API) -> Result<Self> {
+        let gatherer = Arc::new(api.new_ice_gatherer(RTCIceGatherOptions::default())?);
+        let ice = Arc::new(api.new_ice_transport(Arc::clone(&gatherer)));
+        let dtls = Arc::new(api.new_dtls_transport(Arc::clone(&ice), vec![])?);
+        Ok(OrtcMediaStack {
+            gatherer,
+            ice,
+            dtls,
+        })
+    }
+
+    async fn get_signal(&self) -> Result<(Vec<RTCIceCandidate>, RTCIceParameters, DTLSParameters)> {
+        let (gather_finished_tx, mut gather_finished_rx) = mpsc::channel::<()>(1);
+        let mut gather_finished_tx = Some(gather_finished_tx);
+        self.gatherer
+            .on_local_candidate(Box::new(move |c: Option<RTCIceCandidate>| {
+                if c.is_none() {
+                    gather_finished_tx.take();
+                }
+                Box::pin(async {})
+            }));
+        self.gatherer.gather().await?;
+        let _ = gather_finished_rx.recv().await;
+
+        Ok((
+            self.gatherer.get_local_candidates().await?,
+            self.gatherer.get_local_parameters().await?,
+            self.dtls.get_local_parameters()?,
+        ))
+    }
+
+    async fn start(
+        &self,
+        signal: (Vec<RTCIceCandidate>, RTCIceParameters, DTLSParameters),
+        role: RTCIceRole,
+    ) -> Result<()> {
+        let (candidates, ice_parameters, dtls_parameters) = signal;
+        self.ice.set_remote_candidates(&candidates).await?;
+        self.ice.start(&ice_parameters, Some(role)).await?;
+        self.dtls.start(dtls_parameters).await
+    }
+}
+
+#[tokio::test]
+async fn test_ortc_rtp_e2e() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let stack_a = OrtcMediaStack::new(&api)?;
+    let stack_b = OrtcMediaStack::new(&api)?;
+    let signal_a = stack_a.get_signal().await?;
+    let signal_b = stack_b.get_signal().await?;
+    tokio::try_join!(
+        stack_a.start(signal_b, RTCIceRole::Controlling),
+        stack_b.start(signal_a, RTCIceRole::Controlled),
+    )?;
+
+    let track = Arc::new(TrackLocalStaticRTP::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "ortc".to_owned(),
+    ));
+    let sender = api
+        .new_rtp_sender(
+            Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>,
+            Arc::clone(&stack_a.dtls),
+            api.new_interceptor()?,
+        )
+        .await;
+    let receiver = api.new_rtp_receiver(
+        RTPCodecType::Video,
+        Arc::clone(&stack_b.dtls),
+        api.new_interceptor()?,
+    );
+
+    // The parameters are signaled by the application, there is no SDP
+    sender.send(&sender.get_parameters().await).await?;
+    let send_parameters = sender.get_parameters().await;
+    let encoding = &send_parameters.encodings[0];
+    receiver
+        .receive(&RTCRtpReceiveParameters {
+            encodings: vec![RTCRtpDecodingParameters {
+                ssrc: encoding.ssrc,
+                payload_type: encoding.payload_type,
+                ..Default::default()
+            }],
+        })
+        .await?;
+    receiver
+        .set_rtp_parameters(send_parameters.rtp_parameters.clone())
+        .await;
+
+    let remote = receiver.track().await.expect("receiver has one track");
+    assert_eq!(remote.ssrc(), encoding.ssrc);
+    assert_ne!(encoding.payload_type, 0);
+
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
+    let writer = Arc::clone(&track);
+    tokio::spawn(async move {
+        loop {
+            let _ = writer
+                .write_rtp(&rtp::packet::Packet {
+                    header: rtp::header::Header {
+                        version: 2,
+                        ..Default::default()
+                    },
+                    payload: Bytes::from_static(&[0xAA; 16]),
+                })
+                .await;
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_millis(20)) => {}
+                _ = done_rx.recv() => break,
+            }
+        }
+    });
+
+    let (pkt, _) = remote.read_rtp().await?;
+    assert_eq!(pkt.header.ssrc, encoding.ssrc);
+    assert_eq!(remote.codec().await.capability.mime_type, MIME_TYPE_VP8);
+    drop(done_tx);
+
+    sender.stop().await?;
+    receiver.stop().await?;
+    stack_a.dtls.stop().await?;
+    stack_b.dtls.stop().await?;
+    stack_a.ice.stop().await?;
+    stack_b.ice.stop().await?;
+
+    Ok(())
+}
@@ -70,6 +70,26 @@ t=0 0
 
         assert!(!m.negotiated_video.load(Ordering::SeqCst));
         assert!(!m.negotiated_audio.load(Ordering::SeqCst));
+        assert!(!m.extmap_allow_mixed());
+    }
+
+    //"Extmap Allow Mixed"
+    {
+        const EXTMAP_ALLOW_MIXED: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=extmap-allow-mixed
+a=rtpmap:111 opus/48000/2
+";
+
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        m.update_from_remote_description(&must_parse(EXTMAP_ALLOW_MIXED)?)
+            .await?;
+
+        assert!(m.extmap_allow_mixed());
     }
 
     //"Enable Opus"
@@ -634,7 +654,7 @@ async fn test_media_engine_double_register() -> Result<()> {
         RTPCodecType::Audio,
     )?;
 
-    assert_eq!(m.audio_codecs.len(), 1);
+    assert_eq!(m.audio_codecs.lock().unwrap().len(), 1);
     Ok(())
 }
 
@@ -780,3 +800,381 @@ a=rtpmap:111 opus/48000/2
 
     Ok(())
 }
+
+#[test]
+fn test_scalability_mode_parse() -> Result<()> {
+    for raw in [
+        "L1T1",
+        "L1T3",
+        "L3T3",
+        "L2T2h",
+        "L3T3_KEY",
+        "L2T3_KEY_SHIFT",
+        "S3T3",
+    ] {
+        let mode: ScalabilityMode = raw.parse()?;
+        assert_eq!(raw, mode.to_string());
+    }
+
+    let mode: ScalabilityMode = "L2T3_KEY".parse()?;
+    assert_eq!(mode.spatial_layers, 2);
+    assert_eq!(mode.temporal_layers, 3);
+    assert!(mode.key);
+    assert!(!mode.simulcast);
+
+    for raw in [
+        "", "L0T1", "L4T1", "X1T1", "L1T1h", "L1T2_KEY", "S2T2_KEY", "L1T1_FOO",
+    ] {
+        assert!(
+            raw.parse::<ScalabilityMode>().is_err(),
+            "{} must not parse",
+            raw
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_media_engine_scalability_modes() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+
+    let vp9_modes = m.scalability_modes("video/vp9");
+    assert!(vp9_modes.contains(&"L3T3_KEY".parse()?));
+    assert!(m.scalability_modes(MIME_TYPE_VP8).is_empty());
+
+    m.register_scalability_modes(MIME_TYPE_AV1, &["L1T3", "S2T1"])?;
+    assert_eq!(m.clone_to().scalability_modes(MIME_TYPE_AV1).len(), 2);
+    assert!(m
+        .register_scalability_modes(MIME_TYPE_AV1, &["L9T9"])
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_codec_at_runtime() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let pc = api.new_peer_connection(RTCConfiguration::default()).await?;
+    pc.add_transceiver_from_kind(RTPCodecType::Audio, &[])
+        .await?;
+
+    pc.register_codec(
+        RTCRtpCodecParameters {
+            capability: RTCRtpCodecCapability {
+                mime_type: "audio/L16".to_owned(),
+                clock_rate: 16000,
+                channels: 1,
+                sdp_fmtp_line: "".to_owned(),
+                rtcp_feedback: vec![],
+            },
+            payload_type: 120,
+            ..Default::default()
+        },
+        RTPCodecType::Audio,
+        None,
+    )?;
+    pc.register_header_extension(
+        RTCRtpHeaderExtensionCapability {
+            uri: "urn:ietf:params:rtp-hdrext:ssrc-audio-level".to_owned(),
+        },
+        RTPCodecType::Audio,
+        None,
+    )?;
+
+    let offer = pc.create_offer(None).await?;
+
+    let re = Regex::new(r"(?m)^a=rtpmap:120 L16/16000").unwrap();
+    assert!(re.is_match(offer.sdp.as_str()));
+    let re = Regex::new(r"(?m)^a=extmap:\d+ urn:ietf:params:rtp-hdrext:ssrc-audio-level").unwrap();
+    assert!(re.is_match(offer.sdp.as_str()));
+
+    pc.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_codec_with_direction() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    m.register_codec_with_direction(
+        RTCRtpCodecParameters {
+            capability: RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_AV1.to_owned(),
+                clock_rate: 90000,
+                channels: 0,
+                sdp_fmtp_line: "".to_owned(),
+                rtcp_feedback: vec![],
+            },
+            payload_type: 45,
+            ..Default::default()
+        },
+        RTPCodecType::Video,
+        Some(RTCRtpTransceiverDirection::Recvonly),
+    )?;
+
+    let has_av1 = |params: &RTCRtpParameters| {
+        params
+            .codecs
+            .iter()
+            .any(|c| c.capability.mime_type == MIME_TYPE_AV1)
+    };
+
+    let params = m
+        .get_rtp_parameters_by_kind(RTPCodecType::Video, RTCRtpTransceiverDirection::Recvonly)
+        .await;
+    assert!(has_av1(&params));
+
+    for direction in [
+        RTCRtpTransceiverDirection::Sendonly,
+        RTCRtpTransceiverDirection::Sendrecv,
+    ] {
+        let params = m
+            .get_rtp_parameters_by_kind(RTPCodecType::Video, direction)
+            .await;
+        assert!(!has_av1(&params), "AV1 must not be used {}", direction);
+        assert!(!params.codecs.is_empty());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_codec_match_strictness() -> Result<()> {
+    let remote_codec = RTCRtpCodecParameters {
+        capability: RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_H264.to_owned(),
+            clock_rate: 90000,
+            channels: 0,
+            sdp_fmtp_line: "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e034"
+                .to_owned(),
+            rtcp_feedback: vec![],
+        },
+        payload_type: 102,
+        ..Default::default()
+    };
+
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    assert_eq!(
+        m.codec_match_strictness(MIME_TYPE_H264),
+        CodecMatchStrictness::Compatible
+    );
+    assert_eq!(
+        m.match_remote_codec(&remote_codec, RTPCodecType::Video, &[], &[])?,
+        CodecMatch::Exact
+    );
+
+    m.set_codec_match_strictness("VIDEO/h264", CodecMatchStrictness::Strict);
+    assert_eq!(
+        m.match_remote_codec(&remote_codec, RTPCodecType::Video, &[], &[])?,
+        CodecMatch::Partial
+    );
+
+    m.set_codec_match_strictness(MIME_TYPE_H264, CodecMatchStrictness::MimeTypeOnly);
+    let mut other_profile = remote_codec.clone();
+    other_profile.capability.sdp_fmtp_line = "packetization-mode=0;profile-level-id=f4001f".into();
+    assert_eq!(
+        m.match_remote_codec(&other_profile, RTPCodecType::Video, &[], &[])?,
+        CodecMatch::Exact
+    );
+    assert_eq!(
+        m.clone_to().codec_match_strictness(MIME_TYPE_H264),
+        CodecMatchStrictness::MimeTypeOnly
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_partial_codec_match_policy() -> Result<()> {
+    let must_parse = |raw: &str| -> Result<SessionDescription> {
+        let mut reader = Cursor::new(raw.as_bytes());
+        Ok(SessionDescription::unmarshal(&mut reader)?)
+    };
+
+    const PROFILE_LEVELS: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=video 60323 UDP/TLS/RTP/SAVPF 96 98
+a=rtpmap:96 H264/90000
+a=fmtp:96 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=640c1f
+a=rtpmap:98 H264/90000
+a=fmtp:98 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f
+";
+    const PARTIAL_ONLY: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=video 60323 UDP/TLS/RTP/SAVPF 96
+a=rtpmap:96 H264/90000
+a=fmtp:96 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=640c1f
+";
+    let new_media_engine = |policy: PartialCodecMatchPolicy| -> Result<MediaEngine> {
+        let mut m = MediaEngine::default();
+        m.register_codec(
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_H264.to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line:
+                        "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f"
+                            .to_string(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 127,
+                ..Default::default()
+            },
+            RTPCodecType::Video,
+        )?;
+        m.set_partial_codec_match_policy(policy);
+        Ok(m)
+    };
+
+    //"Accept"
+    {
+        let m = new_media_engine(PartialCodecMatchPolicy::Accept)?;
+        m.update_from_remote_description(&must_parse(PROFILE_LEVELS)?)
+            .await?;
+
+        let payload_types: Vec<PayloadType> = m
+            .get_codecs_by_kind(RTPCodecType::Video)
+            .await
+            .iter()
+            .map(|c| c.payload_type)
+            .collect();
+        assert_eq!(payload_types, vec![96, 98]);
+    }
+
+    //"Reject"
+    {
+        let m = new_media_engine(PartialCodecMatchPolicy::Reject)?;
+        m.update_from_remote_description(&must_parse(PARTIAL_ONLY)?)
+            .await?;
+        assert!(m.get_codecs_by_kind(RTPCodecType::Video).await.is_empty());
+
+        let m = new_media_engine(PartialCodecMatchPolicy::Fallback)?;
+        m.update_from_remote_description(&must_parse(PARTIAL_ONLY)?)
+            .await?;
+        assert!(m.get_codec_by_payload(96).await.is_ok());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_answer_codecs() -> Result<()> {
+    let mut offerer_media_engine = MediaEngine::default();
+    offerer_media_engine.register_codec(
+        RTCRtpCodecParameters {
+            capability: RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_H264.to_owned(),
+                clock_rate: 90000,
+                channels: 0,
+                sdp_fmtp_line:
+                    "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e034"
+                        .to_owned(),
+                rtcp_feedback: vec![],
+            },
+            payload_type: 102,
+            ..Default::default()
+        },
+        RTPCodecType::Video,
+    )?;
+    let offerer = APIBuilder::new()
+        .with_media_engine(offerer_media_engine)
+        .build()
+        .new_peer_connection(RTCConfiguration::default())
+        .await?;
+    offerer
+        .add_transceiver_from_kind(RTPCodecType::Video, &[])
+        .await?;
+    let offer = offerer.create_offer(None).await?;
+
+    let answer_with = |m: MediaEngine| {
+        let offer = offer.clone();
+        async move {
+            let answerer = APIBuilder::new()
+                .with_media_engine(m)
+                .build()
+                .new_peer_connection(RTCConfiguration::default())
+                .await?;
+            answerer.set_remote_description(offer).await?;
+            let answer = answerer.create_answer(None).await?;
+            answerer.close().await?;
+            Result::<String>::Ok(answer.sdp)
+        }
+    };
+
+    //"Offered"
+    {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let sdp = answer_with(m).await?;
+        assert!(sdp.contains(
+            "a=fmtp:102 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e034"
+        ));
+        assert!(!sdp.contains("VP8/90000"));
+    }
+
+    //"Registered"
+    {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        m.set_answer_fmtp("VIDEO/H264", AnswerFmtp::Registered);
+        let sdp = answer_with(m).await?;
+        assert!(sdp.contains(
+            "a=fmtp:102 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f"
+        ));
+    }
+
+    //"Parameters"
+    {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        m.set_answer_fmtp(
+            MIME_TYPE_H264,
+            AnswerFmtp::Parameters(vec!["packetization-mode".to_owned()]),
+        );
+        let sdp = answer_with(m).await?;
+        assert!(sdp.contains("a=fmtp:102 packetization-mode=1\r\n"));
+    }
+
+    //"Unoffered codecs"
+    {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        m.register_codec(
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: "video/rtx".to_owned(),
+                    clock_rate: 90000,
+                    channels: 0,
+                    sdp_fmtp_line: "apt=96".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 97,
+                ..Default::default()
+            },
+            RTPCodecType::Video,
+        )?;
+        m.set_answer_unoffered_codecs(true);
+        let sdp = answer_with(m).await?;
+        assert!(sdp.contains("a=rtpmap:102 H264/90000"));
+        assert!(sdp.contains("a=rtpmap:96 VP8/90000"));
+        assert!(sdp.contains("a=fmtp:97 apt=96"));
+        // The offered payload type isn't listed twice
+        assert_eq!(sdp.matches("a=rtpmap:102 ").count(), 1);
+    }
+
+    offerer.close().await?;
+
+    Ok(())
+}
@@ -3,13 +3,13 @@ mod media_engine_test;
 
 use crate::error::{Error, Result};
 use crate::peer_connection::sdp::{
-    codecs_from_media_description, rtp_extensions_from_media_description,
+    codecs_from_media_description, is_extmap_allow_mixed_set, rtp_extensions_from_media_description,
 };
 use crate::rtp_transceiver::fmtp;
 use crate::rtp_transceiver::rtp_codec::{
     codec_parameters_fuzzy_search, CodecMatch, RTCRtpCodecCapability, RTCRtpCodecParameters,
     RTCRtpHeaderExtensionCapability, RTCRtpHeaderExtensionParameters, RTCRtpParameters,
-    RTPCodecType,
+    RTPCodecType, ScalabilityMode,
 };
 use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
 use crate::rtp_transceiver::{PayloadType, RTCPFeedback};
@@ -76,6 +76,105 @@ impl MediaEngineHeaderExtension {
     }
 }
 
+/// CodecMatchStrictness controls how the fmtp of a remote codec must match the fmtp of a
+/// registered codec of the same mime type for the codec to be negotiated as an exact match.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CodecMatchStrictness {
+    /// Compatible matches the fmtp parameters which identify the configuration of the codec
+    /// according to its RFC, e.g. packetization-mode and profile-level-id without the level
+    /// for H264. It is the default.
+    Compatible,
+    /// Strict requires all the fmtp parameters to be equal, e.g. also the level of the
+    /// profile-level-id for H264.
+    Strict,
+    /// MimeTypeOnly matches the codecs of the same mime type whatever their fmtp.
+    MimeTypeOnly,
+}
+
+impl Default for CodecMatchStrictness {
+    fn default() -> Self {
+        CodecMatchStrictness::Compatible
+    }
+}
+
+/// PartialCodecMatchPolicy controls which remote codecs are negotiated when only some of them
+/// match a registered codec exactly, see CodecMatchStrictness.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PartialCodecMatchPolicy {
+    /// Fallback negotiates the partially matching codecs only if no codec of their kind
+    /// matches exactly. It is the default.
+    Fallback,
+    /// Accept negotiates the partially matching codecs along with the exactly matching ones.
+    Accept,
+    /// Reject only negotiates the exactly matching codecs.
+    Reject,
+}
+
+impl Default for PartialCodecMatchPolicy {
+    fn default() -> Self {
+        PartialCodecMatchPolicy::Fallback
+    }
+}
+
+/// AnswerFmtp controls the fmtp written in an answer for an offered codec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnswerFmtp {
+    /// Offered echoes the fmtp of the offer. It is the default.
+    Offered,
+    /// Registered writes the fmtp of the matching registered codec, e.g. the H264 level
+    /// actually supported by the encoder rather than the offered one. The fmtp of a
+    /// retransmission codec is still echoed, its apt refers to an offered payload type.
+    Registered,
+    /// Parameters echoes the given parameters of the offered fmtp, plus apt.
+    Parameters(Vec<String>),
+}
+
+impl Default for AnswerFmtp {
+    fn default() -> Self {
+        AnswerFmtp::Offered
+    }
+}
+
+/// answer_fmtp_line returns the fmtp of an offered codec to write in an answer.
+fn answer_fmtp_line(
+    codec: &RTCRtpCodecParameters,
+    answer_fmtp: &AnswerFmtp,
+    registered_codecs: &[RTCRtpCodecParameters],
+) -> String {
+    let line = &codec.capability.sdp_fmtp_line;
+    let is_rtx = fmtp::parse(&codec.capability.mime_type, line)
+        .parameter("apt")
+        .is_some();
+    match answer_fmtp {
+        AnswerFmtp::Offered => line.clone(),
+        AnswerFmtp::Registered if is_rtx => line.clone(),
+        AnswerFmtp::Registered => match codec_parameters_fuzzy_search(codec, registered_codecs) {
+            (_, CodecMatch::None) => line.clone(),
+            (registered, _) => registered.capability.sdp_fmtp_line,
+        },
+        AnswerFmtp::Parameters(keys) => line
+            .split(';')
+            .map(|p| p.trim())
+            .filter(|p| {
+                let key = p.split('=').next().unwrap_or_default().to_lowercase();
+                key == "apt" || keys.iter().any(|k| k.to_lowercase() == key)
+            })
+            .collect::<Vec<&str>>()
+            .join(";"),
+    }
+}
+
+/// codec_allows_direction returns true if a codec registered for allowed_direction can be
+/// used by a transceiver with the given direction, that is if it can be sent and received as
+/// the transceiver requires.
+fn codec_allows_direction(
+    allowed_direction: RTCRtpTransceiverDirection,
+    direction: RTCRtpTransceiverDirection,
+) -> bool {
+    (!direction.has_send() || allowed_direction.has_send())
+        && (!direction.has_recv() || allowed_direction.has_recv())
+}
+
 /// A MediaEngine defines the codecs supported by a PeerConnection, and the
 /// configuration of those codecs. A MediaEngine must not be shared between
 /// PeerConnections.
@@ -85,14 +184,29 @@ pub struct MediaEngine {
     pub(crate) negotiated_video: AtomicBool,
     pub(crate) negotiated_audio: AtomicBool,
 
-    pub(crate) video_codecs: Vec<RTCRtpCodecParameters>,
-    pub(crate) audio_codecs: Vec<RTCRtpCodecParameters>,
+    pub(crate) video_codecs: std::sync::Mutex<Vec<RTCRtpCodecParameters>>,
+    pub(crate) audio_codecs: std::sync::Mutex<Vec<RTCRtpCodecParameters>>,
     pub(crate) negotiated_video_codecs: Mutex<Vec<RTCRtpCodecParameters>>,
     pub(crate) negotiated_audio_codecs: Mutex<Vec<RTCRtpCodecParameters>>,
 
-    header_extensions: Vec<MediaEngineHeaderExtension>,
+    header_extensions: std::sync::Mutex<Vec<MediaEngineHeaderExtension>>,
     proposed_header_extensions: Mutex<HashMap<isize, MediaEngineHeaderExtension>>,
     pub(crate) negotiated_header_extensions: Mutex<HashMap<isize, MediaEngineHeaderExtension>>,
+    // If the remote description allows one- and two-byte header extensions to be mixed.
+    negotiated_extmap_allow_mixed: AtomicBool,
+
+    // Scalability modes supported per codec, keyed by lowercase mime type.
+    scalability_modes: HashMap<String, Vec<ScalabilityMode>>,
+    // Directions the codecs are restricted to, keyed by payload type. The codecs which
+    // aren't in it may be sent and received.
+    codec_directions: std::sync::Mutex<HashMap<PayloadType, RTCRtpTransceiverDirection>>,
+    // How strictly remote codecs are matched, keyed by lowercase mime type.
+    codec_match_strictness: HashMap<String, CodecMatchStrictness>,
+    partial_codec_match_policy: PartialCodecMatchPolicy,
+    // The fmtp written in answers, keyed by lowercase mime type.
+    answer_fmtp: HashMap<String, AnswerFmtp>,
+    // If answers list the registered codecs which weren't offered.
+    answer_unoffered_codecs: bool,
 }
 
 impl MediaEngine {
@@ -145,6 +259,28 @@ impl MediaEngine {
                 payload_type: 8,
                 ..Default::default()
             },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_TELEPHONE_EVENT.to_owned(),
+                    clock_rate: 48000,
+                    channels: 0,
+                    sdp_fmtp_line: "0-15".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 110,
+                ..Default::default()
+            },
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_TELEPHONE_EVENT.to_owned(),
+                    clock_rate: 8000,
+                    channels: 0,
+                    sdp_fmtp_line: "0-15".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 126,
+                ..Default::default()
+            },
         ] {
             self.register_codec(codec, RTPCodecType::Audio)?;
         }
@@ -294,6 +430,14 @@ impl MediaEngine {
             self.register_codec(codec, RTPCodecType::Video)?;
         }
 
+        self.register_scalability_modes(
+            MIME_TYPE_VP9,
+            &[
+                "L1T1", "L1T2", "L1T3", "L2T1", "L2T2", "L2T3", "L3T1", "L3T2", "L3T3", "L2T1_KEY",
+                "L2T2_KEY", "L2T3_KEY", "L3T1_KEY", "L3T2_KEY", "L3T3_KEY",
+            ],
+        )?;
+
         Ok(())
     }
 
@@ -314,8 +458,32 @@ impl MediaEngine {
     /// register_codec is not safe for concurrent use.
     pub fn register_codec(
         &mut self,
+        codec: RTCRtpCodecParameters,
+        typ: RTPCodecType,
+    ) -> Result<()> {
+        self.add_registered_codec(codec, typ, None)
+    }
+
+    /// register_codec_with_direction is register_codec for a codec which can only be sent or
+    /// received, e.g. because there is only an encoder or a decoder for it. The codec is only
+    /// offered and negotiated for transceivers whose direction it supports, a sendonly codec
+    /// isn't used by a sendrecv transceiver. None allows all directions, like register_codec.
+    pub fn register_codec_with_direction(
+        &mut self,
+        codec: RTCRtpCodecParameters,
+        typ: RTPCodecType,
+        allowed_direction: Option<RTCRtpTransceiverDirection>,
+    ) -> Result<()> {
+        self.add_registered_codec(codec, typ, allowed_direction)
+    }
+
+    /// add_registered_codec registers a codec, also after the MediaEngine is in use by a
+    /// PeerConnection.
+    pub(crate) fn add_registered_codec(
+        &self,
         mut codec: RTCRtpCodecParameters,
         typ: RTPCodecType,
+        allowed_direction: Option<RTCRtpTransceiverDirection>,
     ) -> Result<()> {
         codec.stats_id = format!(
             "RTPCodec-{}",
@@ -324,17 +492,26 @@ impl MediaEngine {
                 .unwrap()
                 .as_nanos()
         );
+        let payload_type = codec.payload_type;
         match typ {
             RTPCodecType::Audio => {
-                MediaEngine::add_codec(&mut self.audio_codecs, codec);
-                Ok(())
+                let mut audio_codecs = self.audio_codecs.lock().unwrap();
+                MediaEngine::add_codec(&mut audio_codecs, codec);
             }
             RTPCodecType::Video => {
-                MediaEngine::add_codec(&mut self.video_codecs, codec);
-                Ok(())
+                let mut video_codecs = self.video_codecs.lock().unwrap();
+                MediaEngine::add_codec(&mut video_codecs, codec);
             }
-            _ => Err(Error::ErrUnknownType),
+            _ => return Err(Error::ErrUnknownType),
         }
+
+        let mut codec_directions = self.codec_directions.lock().unwrap();
+        match allowed_direction {
+            Some(direction) => codec_directions.insert(payload_type, direction),
+            None => codec_directions.remove(&payload_type),
+        };
+
+        Ok(())
     }
 
     /// Adds a header extension to the MediaEngine
@@ -349,25 +526,36 @@ impl MediaEngine {
         typ: RTPCodecType,
         allowed_direction: Option<RTCRtpTransceiverDirection>,
     ) -> Result<()> {
+        self.add_registered_header_extension(extension, typ, allowed_direction)
+    }
+
+    /// add_registered_header_extension registers a header extension, also after the
+    /// MediaEngine is in use by a PeerConnection.
+    pub(crate) fn add_registered_header_extension(
+        &self,
+        extension: RTCRtpHeaderExtensionCapability,
+        typ: RTPCodecType,
+        allowed_direction: Option<RTCRtpTransceiverDirection>,
+    ) -> Result<()> {
+        let mut header_extensions = self.header_extensions.lock().unwrap();
         let ext = {
-            match self
-                .header_extensions
-                .iter_mut()
-                .find(|ext| ext.uri == extension.uri)
+            match header_extensions
+                .iter()
+                .position(|ext| ext.uri == extension.uri)
             {
-                Some(ext) => ext,
+                Some(index) => &mut header_extensions[index],
                 None => {
                     // We have registered too many extensions
-                    if self.header_extensions.len() > VALID_EXT_IDS.end as usize {
+                    if header_extensions.len() > VALID_EXT_IDS.end as usize {
                         return Err(Error::ErrRegisterHeaderExtensionNoFreeID);
                     }
-                    self.header_extensions.push(MediaEngineHeaderExtension {
+                    header_extensions.push(MediaEngineHeaderExtension {
                         allowed_direction,
                         ..Default::default()
                     });
 
                     // Unwrap is fine because we just pushed
-                    self.header_extensions.last_mut().unwrap()
+                    header_extensions.last_mut().unwrap()
                 }
             }
         };
@@ -387,16 +575,40 @@ impl MediaEngine {
         Ok(())
     }
 
+    /// register_scalability_modes sets the scalability modes, e.g. "L1T3", that the application's
+    /// encoder can produce for the codec with the given mime type. A send encoding may only use
+    /// a scalability mode registered for the negotiated codec.
+    /// register_scalability_modes is not safe for concurrent use.
+    pub fn register_scalability_modes(&mut self, mime_type: &str, modes: &[&str]) -> Result<()> {
+        let modes = modes
+            .iter()
+            .map(|mode| mode.parse())
+            .collect::<Result<Vec<ScalabilityMode>>>()?;
+        self.scalability_modes
+            .insert(mime_type.to_lowercase(), modes);
+
+        Ok(())
+    }
+
+    /// scalability_modes returns the scalability modes supported for the codec with the given
+    /// mime type.
+    pub fn scalability_modes(&self, mime_type: &str) -> Vec<ScalabilityMode> {
+        self.scalability_modes
+            .get(&mime_type.to_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+
     /// register_feedback adds feedback mechanism to already registered codecs.
     pub fn register_feedback(&mut self, feedback: RTCPFeedback, typ: RTPCodecType) {
         match typ {
             RTPCodecType::Video => {
-                for v in &mut self.video_codecs {
+                for v in self.video_codecs.get_mut().unwrap() {
                     v.capability.rtcp_feedback.push(feedback.clone());
                 }
             }
             RTPCodecType::Audio => {
-                for a in &mut self.audio_codecs {
+                for a in self.audio_codecs.get_mut().unwrap() {
                     a.capability.rtcp_feedback.push(feedback.clone());
                 }
             }
@@ -404,6 +616,197 @@ impl MediaEngine {
         }
     }
 
+    /// set_codec_match_strictness sets how strictly the fmtp of the remote codecs with the
+    /// given mime type must match the registered ones, CodecMatchStrictness::Compatible by
+    /// default. A remote codec which doesn't match strictly enough is still negotiated as a
+    /// partial match if no codec of its kind matches exactly.
+    pub fn set_codec_match_strictness(
+        &mut self,
+        mime_type: &str,
+        strictness: CodecMatchStrictness,
+    ) {
+        self.codec_match_strictness
+            .insert(mime_type.to_lowercase(), strictness);
+    }
+
+    /// codec_match_strictness returns how strictly the remote codecs with the given mime type
+    /// are matched.
+    pub fn codec_match_strictness(&self, mime_type: &str) -> CodecMatchStrictness {
+        self.codec_match_strictness
+            .get(&mime_type.to_lowercase())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// set_partial_codec_match_policy sets which remote codecs are negotiated when only some of
+    /// them match a registered codec exactly, PartialCodecMatchPolicy::Fallback by default.
+    pub fn set_partial_codec_match_policy(&mut self, policy: PartialCodecMatchPolicy) {
+        self.partial_codec_match_policy = policy;
+    }
+
+    /// set_answer_fmtp sets the fmtp written in answers for the offered codecs with the given
+    /// mime type, AnswerFmtp::Offered by default.
+    pub fn set_answer_fmtp(&mut self, mime_type: &str, answer_fmtp: AnswerFmtp) {
+        self.answer_fmtp
+            .insert(mime_type.to_lowercase(), answer_fmtp);
+    }
+
+    /// set_answer_unoffered_codecs sets whether answers also list the registered codecs which
+    /// don't match any offered codec, after the offered ones, to let the remote peer know
+    /// they can be received. They aren't negotiated until the remote peer offers them.
+    pub fn set_answer_unoffered_codecs(&mut self, answer_unoffered_codecs: bool) {
+        self.answer_unoffered_codecs = answer_unoffered_codecs;
+    }
+
+    /// answer_codecs returns the codecs of an answer for the negotiated codecs of a
+    /// transceiver, following the AnswerFmtp of each codec and set_answer_unoffered_codecs.
+    pub(crate) fn answer_codecs(
+        &self,
+        codecs: Vec<RTCRtpCodecParameters>,
+        typ: RTPCodecType,
+        direction: RTCRtpTransceiverDirection,
+    ) -> Vec<RTCRtpCodecParameters> {
+        let registered_codecs = match typ {
+            RTPCodecType::Audio => self.audio_codecs.lock().unwrap().clone(),
+            RTPCodecType::Video => self.video_codecs.lock().unwrap().clone(),
+            _ => return codecs,
+        };
+
+        let mut answer: Vec<RTCRtpCodecParameters> = codecs
+            .iter()
+            .map(|codec| {
+                let answer_fmtp = self
+                    .answer_fmtp
+                    .get(&codec.capability.mime_type.to_lowercase())
+                    .cloned()
+                    .unwrap_or_default();
+                let mut codec = codec.clone();
+                codec.capability.sdp_fmtp_line =
+                    answer_fmtp_line(&codec, &answer_fmtp, &registered_codecs);
+                codec
+            })
+            .collect();
+
+        if self.answer_unoffered_codecs {
+            let unoffered: Vec<RTCRtpCodecParameters> = registered_codecs
+                .iter()
+                .filter(|registered| {
+                    let (_, match_type) = codec_parameters_fuzzy_search(registered, &codecs);
+                    match_type != CodecMatch::Exact
+                        && answer
+                            .iter()
+                            .all(|codec| codec.payload_type != registered.payload_type)
+                })
+                .cloned()
+                .collect();
+            answer.extend(self.filter_codecs_by_direction(unoffered, typ, direction));
+
+            // Drop the retransmission codecs whose media codec isn't in the answer.
+            let payload_types: Vec<PayloadType> =
+                answer.iter().map(|codec| codec.payload_type).collect();
+            answer.retain(|codec| {
+                fmtp::parse(&codec.capability.mime_type, &codec.capability.sdp_fmtp_line)
+                    .parameter("apt")
+                    .and_then(|apt| apt.parse::<PayloadType>().ok())
+                    .map_or(true, |apt| payload_types.contains(&apt))
+            });
+        }
+
+        answer
+    }
+
+    /// match_registered_codec looks a remote codec up in the registered codecs like
+    /// codec_parameters_fuzzy_search, following the CodecMatchStrictness of its mime type.
+    fn match_registered_codec(
+        &self,
+        remote_codec: &RTCRtpCodecParameters,
+        codecs: &[RTCRtpCodecParameters],
+    ) -> CodecMatch {
+        let strictness = self.codec_match_strictness(&remote_codec.capability.mime_type);
+        if strictness == CodecMatchStrictness::Compatible {
+            let (_, match_type) = codec_parameters_fuzzy_search(remote_codec, codecs);
+            return match_type;
+        }
+
+        let remote_fmtp = fmtp::parse(
+            &remote_codec.capability.mime_type,
+            &remote_codec.capability.sdp_fmtp_line,
+        );
+        let mut match_type = CodecMatch::None;
+        for c in codecs {
+            if c.capability.mime_type.to_lowercase()
+                != remote_codec.capability.mime_type.to_lowercase()
+            {
+                continue;
+            }
+            let fmtp = fmtp::parse(&c.capability.mime_type, &c.capability.sdp_fmtp_line);
+            if strictness == CodecMatchStrictness::MimeTypeOnly || remote_fmtp.equal(&*fmtp) {
+                return CodecMatch::Exact;
+            }
+            match_type = CodecMatch::Partial;
+        }
+
+        match_type
+    }
+
+    /// filter_codecs_by_direction removes the codecs which can't be used by a transceiver with
+    /// the given direction, and the retransmission codecs of the removed codecs. The codecs
+    /// may be negotiated ones, they are looked up in the registered codecs.
+    pub(crate) fn filter_codecs_by_direction(
+        &self,
+        codecs: Vec<RTCRtpCodecParameters>,
+        typ: RTPCodecType,
+        direction: RTCRtpTransceiverDirection,
+    ) -> Vec<RTCRtpCodecParameters> {
+        let codec_directions = self.codec_directions.lock().unwrap().clone();
+        if codec_directions.is_empty() {
+            return codecs;
+        }
+
+        let registered_codecs = match typ {
+            RTPCodecType::Audio => self.audio_codecs.lock().unwrap().clone(),
+            RTPCodecType::Video => self.video_codecs.lock().unwrap().clone(),
+            _ => return codecs,
+        };
+        let is_allowed = |codec: &RTCRtpCodecParameters| {
+            let (registered, match_type) = codec_parameters_fuzzy_search(codec, &registered_codecs);
+            match_type == CodecMatch::None
+                || codec_directions
+                    .get(&registered.payload_type)
+                    .map_or(true, |allowed| codec_allows_direction(*allowed, direction))
+        };
+        let apt = |codec: &RTCRtpCodecParameters| {
+            fmtp::parse(&codec.capability.mime_type, &codec.capability.sdp_fmtp_line)
+                .parameter("apt")
+                .and_then(|apt| apt.parse::<PayloadType>().ok())
+        };
+
+        let allowed: Vec<RTCRtpCodecParameters> = codecs
+            .into_iter()
+            .filter(|codec| apt(codec).is_some() || is_allowed(codec))
+            .collect();
+        let payload_types: Vec<PayloadType> = allowed
+            .iter()
+            .filter(|codec| apt(codec).is_none())
+            .map(|codec| codec.payload_type)
+            .collect();
+        allowed
+            .into_iter()
+            .filter(|codec| apt(codec).map_or(true, |apt| payload_types.contains(&apt)))
+            .collect()
+    }
+
+    /// renegotiate_kind makes the codecs and header extensions registered since the kind was
+    /// negotiated take part in the next negotiation. The codecs negotiated so far are kept, so
+    /// the payload types in use are still recognized.
+    pub(crate) fn renegotiate_kind(&self, typ: RTPCodecType) {
+        match typ {
+            RTPCodecType::Audio => self.negotiated_audio.store(false, Ordering::SeqCst),
+            RTPCodecType::Video => self.negotiated_video.store(false, Ordering::SeqCst),
+            _ => {}
+        }
+    }
+
     /// get_header_extension_id returns the negotiated ID for a header extension.
     /// If the Header Extension isn't enabled ok will be false
     pub(crate) async fn get_header_extension_id(
@@ -424,13 +827,27 @@ impl MediaEngine {
         (0, false, false)
     }
 
+    /// extmap_allow_mixed returns if one- and two-byte header extensions may be mixed
+    /// in the RTP packets, as negotiated by "a=extmap-allow-mixed".
+    pub(crate) fn extmap_allow_mixed(&self) -> bool {
+        self.negotiated_extmap_allow_mixed.load(Ordering::SeqCst)
+    }
+
     /// clone_to copies any user modifiable state of the MediaEngine
     /// all internal state is reset
     pub(crate) fn clone_to(&self) -> Self {
         MediaEngine {
-            video_codecs: self.video_codecs.clone(),
-            audio_codecs: self.audio_codecs.clone(),
-            header_extensions: self.header_extensions.clone(),
+            video_codecs: std::sync::Mutex::new(self.video_codecs.lock().unwrap().clone()),
+            audio_codecs: std::sync::Mutex::new(self.audio_codecs.lock().unwrap().clone()),
+            header_extensions: std::sync::Mutex::new(
+                self.header_extensions.lock().unwrap().clone(),
+            ),
+            scalability_modes: self.scalability_modes.clone(),
+            codec_directions: std::sync::Mutex::new(self.codec_directions.lock().unwrap().clone()),
+            codec_match_strictness: self.codec_match_strictness.clone(),
+            partial_codec_match_policy: self.partial_codec_match_policy,
+            answer_fmtp: self.answer_fmtp.clone(),
+            answer_unoffered_codecs: self.answer_unoffered_codecs,
             ..Default::default()
         }
     }
@@ -439,19 +856,12 @@ impl MediaEngine {
         &self,
         payload_type: PayloadType,
     ) -> Result<(RTCRtpCodecParameters, RTPCodecType)> {
-        {
-            let negotiated_video_codecs = self.negotiated_video_codecs.lock().await;
-            for codec in &*negotiated_video_codecs {
-                if codec.payload_type == payload_type {
-                    return Ok((codec.clone(), RTPCodecType::Video));
-                }
-            }
-        }
-        {
-            let negotiated_audio_codecs = self.negotiated_audio_codecs.lock().await;
-            for codec in &*negotiated_audio_codecs {
+        // The registered codecs of a kind stand in until it is negotiated, for the
+        // RTPReceivers which are driven without SDP
+        for typ in [RTPCodecType::Video, RTPCodecType::Audio] {
+            for codec in self.get_codecs_by_kind(typ).await {
                 if codec.payload_type == payload_type {
-                    return Ok((codec.clone(), RTPCodecType::Audio));
+                    return Ok((codec, typ));
                 }
             }
         }
@@ -459,15 +869,15 @@ impl MediaEngine {
         Err(Error::ErrCodecNotFound)
     }
 
+    /// collect_stats reports the negotiated codecs of each kind, or the registered ones
+    /// until the kind is negotiated.
     pub(crate) async fn collect_stats(&self, collector: &StatsCollector) {
         let mut reports = HashMap::new();
 
-        for codec in &self.video_codecs {
-            reports.insert(codec.stats_id.clone(), Codec(CodecStats::from(codec)));
-        }
-
-        for codec in &self.audio_codecs {
-            reports.insert(codec.stats_id.clone(), Codec(CodecStats::from(codec)));
+        for typ in [RTPCodecType::Video, RTPCodecType::Audio] {
+            for codec in self.get_codecs_by_kind(typ).await {
+                reports.insert(codec.stats_id.clone(), Codec(CodecStats::from(&codec)));
+            }
         }
 
         collector.merge(reports);
@@ -482,9 +892,9 @@ impl MediaEngine {
         partial_matches: &[RTCRtpCodecParameters],
     ) -> Result<CodecMatch> {
         let codecs = if typ == RTPCodecType::Audio {
-            &self.audio_codecs
+            self.audio_codecs.lock().unwrap().clone()
         } else {
-            &self.video_codecs
+            self.video_codecs.lock().unwrap().clone()
         };
 
         let remote_fmtp = fmtp::parse(
@@ -516,15 +926,14 @@ impl MediaEngine {
             }
 
             // if apt's media codec is partial match, then apt codec must be partial match too
-            let (_, mut match_type) = codec_parameters_fuzzy_search(remote_codec, codecs);
+            let mut match_type = self.match_registered_codec(remote_codec, &codecs);
             if match_type == CodecMatch::Exact && apt_match == CodecMatch::Partial {
                 match_type = CodecMatch::Partial;
             }
             return Ok(match_type);
         }
 
-        let (_, match_type) = codec_parameters_fuzzy_search(remote_codec, codecs);
-        Ok(match_type)
+        Ok(self.match_registered_codec(remote_codec, &codecs))
     }
 
     /// Look up a header extension and enable if it exists
@@ -534,10 +943,11 @@ impl MediaEngine {
         extension: &str,
         typ: RTPCodecType,
     ) -> Result<()> {
+        let local_extensions = self.header_extensions.lock().unwrap().clone();
         let mut negotiated_header_extensions = self.negotiated_header_extensions.lock().await;
         let mut propsed_header_extensions = self.proposed_header_extensions.lock().await;
 
-        for local_extension in &self.header_extensions {
+        for local_extension in &local_extensions {
             if local_extension.uri != extension {
                 continue;
             }
@@ -579,7 +989,13 @@ impl MediaEngine {
     }
 
     pub(crate) async fn push_codecs(&self, codecs: Vec<RTCRtpCodecParameters>, typ: RTPCodecType) {
-        for codec in codecs {
+        for mut codec in codecs {
+            // The codecs of a remote description have no stats id, and their payload
+            // type is unique within a kind.
+            if codec.stats_id.is_empty() {
+                codec.stats_id = format!("RTPCodec-{}-{}", typ, codec.payload_type);
+            }
+
             if typ == RTPCodecType::Audio {
                 let mut negotiated_audio_codecs = self.negotiated_audio_codecs.lock().await;
                 MediaEngine::add_codec(&mut negotiated_audio_codecs, codec);
@@ -595,6 +1011,9 @@ impl MediaEngine {
         &self,
         desc: &SessionDescription,
     ) -> Result<()> {
+        self.negotiated_extmap_allow_mixed
+            .store(is_extmap_allow_mixed_set(desc), Ordering::SeqCst);
+
         for media in &desc.media_descriptions {
             let typ = if !self.negotiated_audio.load(Ordering::SeqCst)
                 && media.media_name.media.to_lowercase() == "audio"
@@ -614,27 +1033,33 @@ impl MediaEngine {
 
             let mut exact_matches = vec![]; //make([]RTPCodecParameters, 0, len(codecs))
             let mut partial_matches = vec![]; //make([]RTPCodecParameters, 0, len(codecs))
+            let mut matches = vec![];
 
             for codec in codecs {
                 let match_type =
                     self.match_remote_codec(&codec, typ, &exact_matches, &partial_matches)?;
 
                 if match_type == CodecMatch::Exact {
-                    exact_matches.push(codec);
+                    exact_matches.push(codec.clone());
+                    matches.push(codec);
                 } else if match_type == CodecMatch::Partial {
-                    partial_matches.push(codec);
+                    partial_matches.push(codec.clone());
+                    matches.push(codec);
                 }
             }
 
-            // use exact matches when they exist, otherwise fall back to partial
-            if !exact_matches.is_empty() {
-                self.push_codecs(exact_matches, typ).await;
-            } else if !partial_matches.is_empty() {
-                self.push_codecs(partial_matches, typ).await;
-            } else {
+            let negotiated = match self.partial_codec_match_policy {
+                // use exact matches when they exist, otherwise fall back to partial
+                PartialCodecMatchPolicy::Fallback if !exact_matches.is_empty() => exact_matches,
+                PartialCodecMatchPolicy::Fallback => partial_matches,
+                PartialCodecMatchPolicy::Accept => matches,
+                PartialCodecMatchPolicy::Reject => exact_matches,
+            };
+            if negotiated.is_empty() {
                 // no match, not negotiated
                 continue;
             }
+            self.push_codecs(negotiated, typ).await;
 
             let extensions = rtp_extensions_from_media_description(media)?;
 
@@ -652,14 +1077,14 @@ impl MediaEngine {
                 let negotiated_video_codecs = self.negotiated_video_codecs.lock().await;
                 negotiated_video_codecs.clone()
             } else {
-                self.video_codecs.clone()
+                self.video_codecs.lock().unwrap().clone()
             }
         } else if typ == RTPCodecType::Audio {
             if self.negotiated_audio.load(Ordering::SeqCst) {
                 let negotiated_audio_codecs = self.negotiated_audio_codecs.lock().await;
                 negotiated_audio_codecs.clone()
             } else {
-                self.audio_codecs.clone()
+                self.audio_codecs.lock().unwrap().clone()
             }
         } else {
             vec![]
@@ -689,10 +1114,11 @@ impl MediaEngine {
                 }
             }
         } else {
+            let local_extensions = self.header_extensions.lock().unwrap().clone();
             let mut proposed_header_extensions = self.proposed_header_extensions.lock().await;
             let mut negotiated_header_extensions = self.negotiated_header_extensions.lock().await;
 
-            for local_extension in &self.header_extensions {
+            for local_extension in &local_extensions {
                 let relevant = local_extension.is_matching_direction(direction)
                     && (local_extension.is_audio && typ == RTPCodecType::Audio
                         || local_extension.is_video && typ == RTPCodecType::Video);
@@ -758,9 +1184,10 @@ impl MediaEngine {
             }
         }
 
+        let codecs = self.get_codecs_by_kind(typ).await;
         RTCRtpParameters {
             header_extensions,
-            codecs: self.get_codecs_by_kind(typ).await,
+            codecs: self.filter_codecs_by_direction(codecs, typ, direction),
         }
     }
 
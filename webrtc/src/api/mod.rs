@@ -135,6 +135,16 @@ impl API {
         Ok(d)
     }
 
+    /// new_interceptor builds the interceptors of the registry, for the RTPSenders and
+    /// RTPReceivers which are constructed without a PeerConnection. The senders and
+    /// receivers of a transport share one interceptor, so that the RTCP reports cover
+    /// all of their streams.
+    /// This constructor is part of the ORTC API. It is not
+    /// meant to be used together with the basic WebRTC API.
+    pub fn new_interceptor(&self) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        Ok(self.interceptor_registry.build("")?)
+    }
+
     /// new_rtp_receiver constructs a new RTPReceiver
     pub fn new_rtp_receiver(
         &self,
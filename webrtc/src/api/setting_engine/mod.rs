@@ -1,17 +1,30 @@
 #[cfg(test)]
 mod setting_engine_test;
 
-use crate::dtls_transport::dtls_role::DTLSRole;
+use crate::dtls_transport::dtls_fingerprint::{fingerprint_value, DEFAULT_FINGERPRINT_ALGORITHM};
+use crate::dtls_transport::dtls_role::{
+    DTLSRole, DEFAULT_DTLS_ROLE_ANSWER, DEFAULT_DTLS_ROLE_OFFER,
+};
 use crate::ice_transport::ice_candidate_type::RTCIceCandidateType;
+use crate::ice_transport::ice_role::RTCIceRole;
 use dtls::extension::extension_use_srtp::SrtpProtectionProfile;
-use ice::agent::agent_config::{InterfaceFilterFn, IpFilterFn};
+use ice::agent::agent_config::{
+    FoundationFn, InterfaceFilterFn, IpFilterFn, LocalPreferenceFn, NominationMode,
+};
+use ice::candidate::CandidateType;
 use ice::mdns::MulticastDnsMode;
 use ice::network_type::NetworkType;
+use ice::resolver::Resolver;
+use ice::socket_factory::SocketFactory;
+use ice::tcp_mux::TCPMux;
 use ice::udp_network::UDPNetwork;
+use interceptor::cc::BandwidthEstimatorBuilder;
+use mdns::conn::DnsConn;
 
 use crate::error::{Error, Result};
 
 use crate::RECEIVE_MTU;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::time::Duration;
 use util::vnet::net::*;
@@ -30,11 +43,21 @@ pub struct Timeout {
     pub ice_srflx_acceptance_min_wait: Option<Duration>,
     pub ice_prflx_acceptance_min_wait: Option<Duration>,
     pub ice_relay_acceptance_min_wait: Option<Duration>,
+    pub ice_regather_interval: Option<Duration>,
+    pub ice_regather_max_interval: Option<Duration>,
+    pub dtls_retransmission_interval: Option<Duration>,
+    pub dtls_handshake_timeout: Option<Duration>,
+    pub close_timeout: Option<Duration>,
 }
 
 #[derive(Default, Clone)]
 pub struct Candidates {
     pub ice_lite: bool,
+    pub ice_role: RTCIceRole,
+    pub ice_nomination_mode: NominationMode,
+    pub type_preferences: HashMap<CandidateType, u16>,
+    pub local_preference_fn: Arc<Option<LocalPreferenceFn>>,
+    pub foundation_fn: Arc<Option<FoundationFn>>,
     pub ice_network_types: Vec<NetworkType>,
     pub interface_filter: Arc<Option<InterfaceFilterFn>>,
     pub ip_filter: Arc<Option<IpFilterFn>>,
@@ -42,6 +65,8 @@ pub struct Candidates {
     pub nat_1to1_ip_candidate_type: RTCIceCandidateType,
     pub multicast_dns_mode: MulticastDnsMode,
     pub multicast_dns_host_name: String,
+    pub multicast_dns_query_timeout: Option<Duration>,
+    pub multicast_dns_conn: Option<Arc<DnsConn>>,
     pub username_fragment: String,
     pub password: String,
 }
@@ -64,19 +89,25 @@ pub struct SettingEngine {
     pub(crate) replay_protection: ReplayProtection,
     pub(crate) sdp_media_level_fingerprints: bool,
     pub(crate) answering_dtls_role: DTLSRole,
+    pub(crate) dtls_role: DTLSRole,
+    pub(crate) dtls_fingerprint_algorithms: Vec<String>,
+    pub(crate) allow_insecure_dtls_hashes: bool,
     pub(crate) disable_certificate_fingerprint_verification: bool,
     pub(crate) allow_insecure_verification_algorithm: bool,
     pub(crate) disable_srtp_replay_protection: bool,
     pub(crate) disable_srtcp_replay_protection: bool,
     pub(crate) vnet: Option<Arc<Net>>,
     //BufferFactory                             :func(packetType packetio.BufferPacketType, ssrc uint32) io.ReadWriteCloser,
-    //iceTCPMux                                 :ice.TCPMux,?
     //iceProxyDialer                            :proxy.Dialer,?
     pub(crate) udp_network: UDPNetwork,
+    pub(crate) tcp_mux: Option<Arc<dyn TCPMux + Send + Sync>>,
+    pub(crate) socket_factory: Option<Arc<dyn SocketFactory + Send + Sync>>,
+    pub(crate) resolver: Option<Arc<dyn Resolver + Send + Sync>>,
     pub(crate) disable_media_engine_copy: bool,
     pub(crate) srtp_protection_profiles: Vec<SrtpProtectionProfile>,
     pub(crate) receive_mtu: usize,
     pub(crate) mid_generator: Option<Arc<dyn Fn(isize) -> String + Send + Sync>>,
+    pub(crate) bandwidth_estimator: Option<BandwidthEstimatorBuilder>,
 }
 
 impl SettingEngine {
@@ -88,6 +119,37 @@ impl SettingEngine {
             RECEIVE_MTU
         }
     }
+
+    /// offer_dtls_role returns the DTLS role announced in offers.
+    pub(crate) fn offer_dtls_role(&self) -> DTLSRole {
+        match self.dtls_role {
+            DTLSRole::Client | DTLSRole::Server => self.dtls_role,
+            _ => DEFAULT_DTLS_ROLE_OFFER,
+        }
+    }
+
+    /// answer_dtls_role returns the DTLS role announced in answers.
+    pub(crate) fn answer_dtls_role(&self) -> DTLSRole {
+        match (self.dtls_role, self.answering_dtls_role) {
+            (DTLSRole::Client | DTLSRole::Server, _) => self.dtls_role,
+            (_, DTLSRole::Client | DTLSRole::Server) => self.answering_dtls_role,
+            _ => DEFAULT_DTLS_ROLE_ANSWER,
+        }
+    }
+
+    /// get_dtls_fingerprint_algorithms returns the hash function algorithms of the fingerprints
+    /// of the local certificates.
+    pub(crate) fn get_dtls_fingerprint_algorithms(&self) -> Vec<&str> {
+        if self.dtls_fingerprint_algorithms.is_empty() {
+            vec![DEFAULT_FINGERPRINT_ALGORITHM]
+        } else {
+            self.dtls_fingerprint_algorithms
+                .iter()
+                .map(String::as_str)
+                .collect()
+        }
+    }
+
     /// detach_data_channels enables detaching data channels. When enabled
     /// data channels have to be detached in the OnOpen callback using the
     /// DataChannel.Detach method.
@@ -116,6 +178,19 @@ impl SettingEngine {
         self.timeout.ice_keepalive_interval = keep_alive_interval;
     }
 
+    /// set_ice_regather_interval enables re-gathering ICE candidates after the ICE connection
+    /// failed, so the connection can recover without an ICE restart from the application.
+    /// * interval is the time before the first attempt, doubling for every following attempt
+    /// * max_interval is the maximum time between two attempts. Default is 60 Seconds
+    pub fn set_ice_regather_interval(
+        &mut self,
+        interval: Option<Duration>,
+        max_interval: Option<Duration>,
+    ) {
+        self.timeout.ice_regather_interval = interval;
+        self.timeout.ice_regather_max_interval = max_interval;
+    }
+
     /// set_host_acceptance_min_wait sets the icehost_acceptance_min_wait
     pub fn set_host_acceptance_min_wait(&mut self, t: Option<Duration>) {
         self.timeout.ice_host_acceptance_min_wait = t;
@@ -143,11 +218,48 @@ impl SettingEngine {
         self.udp_network = udp_network;
     }
 
+    /// set_ice_tcp_mux enables passive ICE-TCP candidates. All inbound ICE-TCP connections are
+    /// accepted on the single listening socket owned by the mux, which should be started prior
+    /// to creating PeerConnections. TCP network types must also be enabled with
+    /// set_network_types for the candidates to be gathered.
+    pub fn set_ice_tcp_mux(&mut self, tcp_mux: Arc<dyn TCPMux + Send + Sync>) {
+        self.tcp_mux = Some(tcp_mux);
+    }
+
+    /// set_ice_socket_factory sets the factory used to create the UDP sockets that ICE binds
+    /// itself, allowing socket options such as DSCP marking or device binding to be applied.
+    /// See ice::socket_factory::SocketOptions for a factory covering the common options.
+    pub fn set_ice_socket_factory(&mut self, socket_factory: Arc<dyn SocketFactory + Send + Sync>) {
+        self.socket_factory = Some(socket_factory);
+    }
+
+    /// set_ice_resolver sets the resolver used to look up the hostnames of STUN/TURN servers
+    /// instead of the system resolver, e.g. for split-horizon DNS or a static map.
+    pub fn set_ice_resolver(&mut self, resolver: Arc<dyn Resolver + Send + Sync>) {
+        self.resolver = Some(resolver);
+    }
+
     /// set_lite configures whether or not the ice agent should be a lite agent
     pub fn set_lite(&mut self, lite: bool) {
         self.candidates.ice_lite = lite;
     }
 
+    /// set_ice_role forces the ICE role of the agent instead of deriving it from the offer/answer
+    /// exchange. This is useful when the signaling protocol already establishes the roles: the
+    /// agent then keeps its role for its whole lifetime and answers role conflicts with 487
+    /// errors rather than resolving them with tie-breakers.
+    /// RTCIceRole::Unspecified restores the default behavior.
+    pub fn set_ice_role(&mut self, role: RTCIceRole) {
+        self.candidates.ice_role = role;
+    }
+
+    /// set_ice_nomination_mode configures how the ICE agent nominates candidate pairs when it is
+    /// controlling. NominationMode::Aggressive selects the first pair that succeeds, which
+    /// shortens the time to first media at the cost of possibly using a worse pair.
+    pub fn set_ice_nomination_mode(&mut self, mode: NominationMode) {
+        self.candidates.ice_nomination_mode = mode;
+    }
+
     /// set_network_types configures what types of candidate networks are supported
     /// during local and server reflexive gathering.
     pub fn set_network_types(&mut self, candidate_types: Vec<NetworkType>) {
@@ -170,6 +282,30 @@ impl SettingEngine {
         self.candidates.ip_filter = Arc::new(Some(filter));
     }
 
+    /// set_candidate_type_preference overrides the type preference (0-126) used in the priority
+    /// of local candidates of the given type, to bias which pairs get selected.
+    pub fn set_candidate_type_preference(
+        &mut self,
+        candidate_type: CandidateType,
+        preference: u16,
+    ) {
+        self.candidates
+            .type_preferences
+            .insert(candidate_type, preference);
+    }
+
+    /// set_candidate_local_preference sets a function returning the local preference (0-65535)
+    /// used in the priority of a gathered candidate, e.g. to prefer a dedicated media VLAN.
+    pub fn set_candidate_local_preference(&mut self, f: LocalPreferenceFn) {
+        self.candidates.local_preference_fn = Arc::new(Some(f));
+    }
+
+    /// set_candidate_foundation sets a function returning the foundation of a gathered
+    /// candidate instead of the computed one.
+    pub fn set_candidate_foundation(&mut self, f: FoundationFn) {
+        self.candidates.foundation_fn = Arc::new(Some(f));
+    }
+
     /// set_nat_1to1_ips sets a list of external IP addresses of 1:1 (D)NAT
     /// and a candidate type for which the external IP address is used.
     /// This is useful when you are host a server using Pion on an AWS EC2 instance
@@ -215,6 +351,65 @@ impl SettingEngine {
         Ok(())
     }
 
+    /// set_dtls_role forces the dtls_transport role, whether offering or answering and whatever
+    /// the role of the remote peer. Offers announce it with setup:active or setup:passive
+    /// instead of setup:actpass, which isn't compliant with RFC 5763 but is required by some
+    /// gateways. DTLSRole::Auto restores the default behavior.
+    pub fn set_dtls_role(&mut self, role: DTLSRole) -> Result<()> {
+        if role == DTLSRole::Unspecified {
+            return Err(Error::ErrSettingEngineSetDTLSRole);
+        }
+
+        self.dtls_role = role;
+        Ok(())
+    }
+
+    /// set_dtls_retransmission_interval sets how long the dtls_transport waits for the remote
+    /// flight of the handshake before retransmitting its own. Leave it None for the default
+    /// of the dtls crate.
+    pub fn set_dtls_retransmission_interval(&mut self, interval: Option<Duration>) {
+        self.timeout.dtls_retransmission_interval = interval;
+    }
+
+    /// set_dtls_handshake_timeout sets how long the dtls_transport handshake may take before
+    /// the dtls_transport fails. None, the default, doesn't limit it.
+    pub fn set_dtls_handshake_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout.dtls_handshake_timeout = timeout;
+    }
+
+    /// set_close_timeout sets how long RTCPeerConnection::close waits for the remote peer
+    /// during the orderly close, the RTCP BYE, the SCTP shutdown and the DTLS close_notify,
+    /// before the transports are closed at once. Zero skips the orderly close. None uses
+    /// the default of 1 second.
+    pub fn set_close_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout.close_timeout = timeout;
+    }
+
+    /// set_dtls_fingerprint_algorithm sets the hash function algorithm of the certificate
+    /// fingerprints put in the sdp, "sha-1", "sha-256" (the default), "sha-384" or "sha-512".
+    pub fn set_dtls_fingerprint_algorithm(&mut self, algorithm: &str) -> Result<()> {
+        self.set_dtls_fingerprint_algorithms(&[algorithm])
+    }
+
+    /// set_dtls_fingerprint_algorithms sets the hash function algorithms of the certificate
+    /// fingerprints put in the sdp, a fingerprint line is put for each of them. The remote
+    /// peer verifies the certificate with the strongest one it supports.
+    pub fn set_dtls_fingerprint_algorithms(&mut self, algorithms: &[&str]) -> Result<()> {
+        let mut dtls_fingerprint_algorithms = vec![];
+        for algorithm in algorithms {
+            if fingerprint_value(algorithm, &[]).is_none() {
+                return Err(Error::ErrUnsupportedFingerprintAlgorithm);
+            }
+            let algorithm = algorithm.to_lowercase();
+            if !dtls_fingerprint_algorithms.contains(&algorithm) {
+                dtls_fingerprint_algorithms.push(algorithm);
+            }
+        }
+
+        self.dtls_fingerprint_algorithms = dtls_fingerprint_algorithms;
+        Ok(())
+    }
+
     /// set_vnet sets the VNet instance that is passed to ice
     /// VNet is a virtual network layer, allowing users to simulate
     /// different topologies, latency, loss and jitter. This can be useful for
@@ -235,6 +430,20 @@ impl SettingEngine {
         self.candidates.multicast_dns_host_name = host_name;
     }
 
+    /// set_ice_multicast_dns_query_timeout sets how long ice waits for a remote mDNS candidate to
+    /// be resolved before discarding it.
+    pub fn set_ice_multicast_dns_query_timeout(&mut self, timeout: Option<Duration>) {
+        self.candidates.multicast_dns_query_timeout = timeout;
+    }
+
+    /// set_ice_multicast_dns_conn sets an mDNS connection shared by all PeerConnections created
+    /// with this SettingEngine, instead of each of them opening its own. Every PeerConnection
+    /// registers its own HostName with it, so set_multicast_dns_host_name should not be used
+    /// along with it. The connection is not closed when the PeerConnections are closed.
+    pub fn set_ice_multicast_dns_conn(&mut self, conn: Arc<DnsConn>) {
+        self.candidates.multicast_dns_conn = Some(conn);
+    }
+
     /// set_ice_credentials sets a staic uFrag/uPwd to be used by ice
     /// This is useful if you want to do signalless WebRTC session, or having a reproducible environment with static credentials
     pub fn set_ice_credentials(&mut self, username_fragment: String, password: String) {
@@ -252,6 +461,13 @@ impl SettingEngine {
     pub fn allow_insecure_verification_algorithm(&mut self, is_allowed: bool) {
         self.allow_insecure_verification_algorithm = is_allowed;
     }
+
+    /// allow_insecure_dtls_hashes allows the dtls_transport handshake to use hash algorithms
+    /// that are known to be vulnerable or deprecated, e.g. MD5 or SHA-1.
+    pub fn allow_insecure_dtls_hashes(&mut self, is_allowed: bool) {
+        self.allow_insecure_dtls_hashes = is_allowed;
+    }
+
     /// set_dtls_replay_protection_window sets a replay attack protection window size of dtls_transport connection.
     pub fn set_dtls_replay_protection_window(&mut self, n: usize) {
         self.replay_protection.dtls = n;
@@ -324,4 +540,11 @@ impl SettingEngine {
     pub fn set_mid_generator(&mut self, f: impl Fn(isize) -> String + Send + Sync + 'static) {
         self.mid_generator = Some(Arc::new(f));
     }
+
+    /// set_bandwidth_estimator registers a congestion controller on each PeerConnection, whose
+    /// estimates are exposed by on_bandwidth_estimate and bandwidth_estimate of the
+    /// PeerConnection. None, the default, registers none.
+    pub fn set_bandwidth_estimator(&mut self, builder: Option<BandwidthEstimatorBuilder>) {
+        self.bandwidth_estimator = builder;
+    }
 }
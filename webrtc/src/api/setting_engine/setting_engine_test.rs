@@ -1,9 +1,12 @@
 use super::*;
 use crate::api::media_engine::MediaEngine;
-use crate::api::APIBuilder;
+use crate::api::{APIBuilder, API};
+use crate::peer_connection::configuration::RTCConfiguration;
+use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
 use crate::peer_connection::peer_connection_test::*;
 use crate::rtp_transceiver::rtp_codec::RTPCodecType;
 use std::sync::atomic::Ordering;
+use waitgroup::WaitGroup;
 
 #[test]
 fn test_set_connection_timeout() -> Result<()> {
@@ -93,6 +96,145 @@ fn test_set_answering_dtls_role() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_set_dtls_role() -> Result<()> {
+    let mut s = SettingEngine::default();
+    assert!(s.set_dtls_role(DTLSRole::Unspecified).is_err());
+    assert_eq!(s.offer_dtls_role(), DTLSRole::Auto);
+    assert_eq!(s.answer_dtls_role(), DTLSRole::Client);
+
+    s.set_answering_dtls_role(DTLSRole::Server)?;
+    assert_eq!(s.offer_dtls_role(), DTLSRole::Auto);
+    assert_eq!(s.answer_dtls_role(), DTLSRole::Server);
+
+    s.set_dtls_role(DTLSRole::Client)?;
+    assert_eq!(s.offer_dtls_role(), DTLSRole::Client);
+    assert_eq!(s.answer_dtls_role(), DTLSRole::Client);
+
+    s.set_dtls_role(DTLSRole::Auto)?;
+    assert_eq!(s.offer_dtls_role(), DTLSRole::Auto);
+    assert_eq!(s.answer_dtls_role(), DTLSRole::Server);
+
+    Ok(())
+}
+
+#[test]
+fn test_set_dtls_fingerprint_algorithm() -> Result<()> {
+    let mut s = SettingEngine::default();
+    assert_eq!(s.get_dtls_fingerprint_algorithms(), vec!["sha-256"]);
+
+    assert!(s.set_dtls_fingerprint_algorithm("md5").is_err());
+    s.set_dtls_fingerprint_algorithm("SHA-384")?;
+    assert_eq!(s.get_dtls_fingerprint_algorithms(), vec!["sha-384"]);
+
+    assert!(s
+        .set_dtls_fingerprint_algorithms(&["sha-256", "md5"])
+        .is_err());
+    assert_eq!(s.get_dtls_fingerprint_algorithms(), vec!["sha-384"]);
+    s.set_dtls_fingerprint_algorithms(&["sha-1", "sha-512", "SHA-1"])?;
+    assert_eq!(
+        s.get_dtls_fingerprint_algorithms(),
+        vec!["sha-1", "sha-512"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_setting_engine_dtls_tuning() -> Result<()> {
+    let new_api = |s: SettingEngine| -> Result<API> {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        Ok(APIBuilder::new()
+            .with_media_engine(m)
+            .with_setting_engine(s)
+            .build())
+    };
+
+    let mut s = SettingEngine::default();
+    s.set_dtls_role(DTLSRole::Server)?;
+    s.set_dtls_fingerprint_algorithm("sha-512")?;
+    s.set_dtls_retransmission_interval(Some(Duration::from_millis(100)));
+    s.set_dtls_handshake_timeout(Some(Duration::from_secs(10)));
+    let mut offerer = new_api(s)?
+        .new_peer_connection(RTCConfiguration::default())
+        .await?;
+
+    let mut s = SettingEngine::default();
+    s.set_dtls_fingerprint_algorithm("sha-384")?;
+    let mut answerer = new_api(s)?
+        .new_peer_connection(RTCConfiguration::default())
+        .await?;
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut offerer, &wg, RTCPeerConnectionState::Connected).await;
+    until_connection_state(&mut answerer, &wg, RTCPeerConnectionState::Connected).await;
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+
+    let offer = offerer.local_description().await.unwrap();
+    assert!(offer.sdp.contains("a=setup:passive"));
+    assert!(offer.sdp.contains("a=fingerprint:sha-512 "));
+    let answer = answerer.local_description().await.unwrap();
+    assert!(answer.sdp.contains("a=setup:active"));
+    assert!(answer.sdp.contains("a=fingerprint:sha-384 "));
+
+    wg.wait().await;
+
+    assert_eq!(offerer.sctp().dtls_transport.role().await, DTLSRole::Server);
+    assert_eq!(
+        answerer.sctp().dtls_transport.role().await,
+        DTLSRole::Client
+    );
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_setting_engine_dtls_fingerprint_negotiation() -> Result<()> {
+    let new_api = |s: SettingEngine| -> Result<API> {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        Ok(APIBuilder::new()
+            .with_media_engine(m)
+            .with_setting_engine(s)
+            .build())
+    };
+
+    let mut s = SettingEngine::default();
+    s.set_dtls_fingerprint_algorithms(&["sha-1", "sha-384"])?;
+    let mut offerer = new_api(s)?
+        .new_peer_connection(RTCConfiguration::default())
+        .await?;
+
+    let mut answerer = new_api(SettingEngine::default())?
+        .new_peer_connection(RTCConfiguration::default())
+        .await?;
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut offerer, &wg, RTCPeerConnectionState::Connected).await;
+    until_connection_state(&mut answerer, &wg, RTCPeerConnectionState::Connected).await;
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+
+    let offer = offerer.local_description().await.unwrap();
+    assert!(offer.sdp.contains("a=fingerprint:sha-1 "));
+    assert!(offer.sdp.contains("a=fingerprint:sha-384 "));
+    // The answerer adds the strongest algorithm of the offer to its own
+    let answer = answerer.local_description().await.unwrap();
+    assert!(answer.sdp.contains("a=fingerprint:sha-256 "));
+    assert!(answer.sdp.contains("a=fingerprint:sha-384 "));
+    assert!(!answer.sdp.contains("a=fingerprint:sha-1 "));
+
+    wg.wait().await;
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
+
 #[test]
 fn test_set_replay_protection() -> Result<()> {
     let mut s = SettingEngine::default();
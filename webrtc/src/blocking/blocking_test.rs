@@ -0,0 +1,77 @@
+use super::*;
+use crate::api::media_engine::MediaEngine;
+use crate::api::APIBuilder;
+
+fn signal_pair(offer_pc: &RTCPeerConnection, answer_pc: &RTCPeerConnection) -> Result<()> {
+    let offer = offer_pc.create_offer(None)?;
+    offer_pc.set_local_description(offer)?;
+    offer_pc.gather_complete();
+    answer_pc.set_remote_description(offer_pc.local_description().unwrap())?;
+
+    let answer = answer_pc.create_answer(None)?;
+    answer_pc.set_local_description(answer)?;
+    answer_pc.gather_complete();
+    offer_pc.set_remote_description(answer_pc.local_description().unwrap())
+}
+
+#[test]
+fn test_blocking_data_channel() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let offer_pc = RTCPeerConnection::new(&api, RTCConfiguration::default())?;
+    let answer_pc = RTCPeerConnection::new(&api, RTCConfiguration::default())?;
+    let mut data_channels = answer_pc.data_channels();
+
+    let offer_dc = offer_pc.create_data_channel("blocking", None)?;
+    signal_pair(&offer_pc, &answer_pc)?;
+
+    let answer_dc = data_channels.next().unwrap();
+    assert_eq!(answer_dc.label(), "blocking");
+    let mut messages = answer_dc.messages();
+
+    offer_dc.wait_open()?;
+    assert_eq!(offer_dc.ready_state(), RTCDataChannelState::Open);
+    offer_dc.send_text("ping".to_owned())?;
+
+    let msg = messages.next().unwrap();
+    assert!(msg.is_string);
+    assert_eq!(msg.data, Bytes::from_static(b"ping"));
+
+    offer_pc.close()?;
+    answer_pc.close()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_blocking_data_channel_closed_before_open() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let pc = RTCPeerConnection::new(&api, RTCConfiguration::default())?;
+
+    // Closed while wait_open is blocked on it
+    let dc = Arc::new(pc.create_data_channel("closed-while-waiting", None)?);
+    let waiter = {
+        let dc = Arc::clone(&dc);
+        std::thread::spawn(move || dc.wait_open())
+    };
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    dc.close()?;
+    assert!(
+        matches!(waiter.join().unwrap(), Err(Error::ErrDataChannelNotOpen)),
+        "wait_open should fail once the DataChannel is closed"
+    );
+
+    // Already closed when wait_open is called
+    let dc = pc.create_data_channel("closed-before-waiting", None)?;
+    dc.close()?;
+    assert!(matches!(dc.wait_open(), Err(Error::ErrDataChannelNotOpen)));
+
+    pc.close()?;
+
+    Ok(())
+}
@@ -0,0 +1,355 @@
+#[cfg(test)]
+mod blocking_test;
+
+use crate::api::API;
+use crate::data_channel::data_channel_init::RTCDataChannelInit;
+use crate::data_channel::data_channel_message::DataChannelMessage;
+use crate::data_channel::data_channel_state::RTCDataChannelState;
+use crate::error::{Error, Result};
+use crate::ice_transport::ice_candidate::{RTCIceCandidate, RTCIceCandidateInit};
+use crate::peer_connection::configuration::RTCConfiguration;
+use crate::peer_connection::offer_answer_options::{RTCAnswerOptions, RTCOfferOptions};
+use crate::peer_connection::peer_connection_event::{EventStream, RTCPeerConnectionEvent};
+use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use crate::peer_connection::sdp::session_description::RTCSessionDescription;
+use crate::rtp_transceiver::rtp_receiver::RTCRtpReceiver;
+use crate::rtp_transceiver::rtp_sender::RTCRtpSender;
+use crate::stats::StatsReport;
+use crate::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
+use crate::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+use crate::track::track_local::{TrackLocal, TrackLocalWriter};
+use crate::track::track_remote::TrackRemote;
+
+use bytes::Bytes;
+use interceptor::Attributes;
+use media::Sample;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::runtime::{Handle, Runtime};
+use tokio::sync::mpsc;
+
+lazy_static! {
+    static ref RUNTIME: Runtime = tokio::runtime::Builder::new_multi_thread()
+        .thread_name("webrtc-blocking")
+        .enable_all()
+        .build()
+        .expect("failed to build the runtime of webrtc::blocking");
+}
+
+/// block_on runs a future of the async API to completion on the runtime which backs the
+/// blocking API, e.g. for the calls this module doesn't wrap. The tasks of the PeerConnections
+/// keep running on the threads of this runtime in between the calls.
+///
+/// It may be called from the event handlers, which run on the threads of the runtime, but it
+/// panics when it is called from within a current_thread runtime, where the async API should
+/// be used instead.
+pub fn block_on<F: Future>(f: F) -> F::Output {
+    match Handle::try_current() {
+        Ok(handle) => tokio::task::block_in_place(|| handle.block_on(f)),
+        Err(_) => RUNTIME.block_on(f),
+    }
+}
+
+/// Events is a blocking Iterator over the events of a PeerConnection. It ends once the
+/// PeerConnection is dropped, or once the handler it was created with is replaced.
+pub struct Events<T> {
+    stream: EventStream<T>,
+}
+
+impl<T> Iterator for Events<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        block_on(self.stream.recv())
+    }
+}
+
+impl<T> From<EventStream<T>> for Events<T> {
+    fn from(stream: EventStream<T>) -> Self {
+        Events { stream }
+    }
+}
+
+/// RTCPeerConnection is a blocking wrapper of a crate::peer_connection::RTCPeerConnection,
+/// for applications which don't run an async runtime.
+pub struct RTCPeerConnection {
+    inner: Arc<crate::peer_connection::RTCPeerConnection>,
+}
+
+impl RTCPeerConnection {
+    /// new creates a PeerConnection with the given API and configuration.
+    pub fn new(api: &API, configuration: RTCConfiguration) -> Result<Self> {
+        let inner = block_on(api.new_peer_connection(configuration))?;
+        Ok(RTCPeerConnection {
+            inner: Arc::new(inner),
+        })
+    }
+
+    /// inner returns the wrapped PeerConnection, to be used with block_on.
+    pub fn inner(&self) -> &Arc<crate::peer_connection::RTCPeerConnection> {
+        &self.inner
+    }
+
+    /// create_offer starts the PeerConnection and generates the localDescription.
+    pub fn create_offer(&self, options: Option<RTCOfferOptions>) -> Result<RTCSessionDescription> {
+        block_on(self.inner.create_offer(options))
+    }
+
+    /// create_answer starts the PeerConnection and generates the localDescription.
+    pub fn create_answer(
+        &self,
+        options: Option<RTCAnswerOptions>,
+    ) -> Result<RTCSessionDescription> {
+        block_on(self.inner.create_answer(options))
+    }
+
+    /// set_local_description sets the SessionDescription of the local peer.
+    pub fn set_local_description(&self, desc: RTCSessionDescription) -> Result<()> {
+        block_on(self.inner.set_local_description(desc))
+    }
+
+    /// set_remote_description sets the SessionDescription of the remote peer.
+    pub fn set_remote_description(&self, desc: RTCSessionDescription) -> Result<()> {
+        block_on(self.inner.set_remote_description(desc))
+    }
+
+    /// local_description returns the pending local description, or the current one
+    /// if there is no pending one, with the candidates gathered so far.
+    pub fn local_description(&self) -> Option<RTCSessionDescription> {
+        block_on(self.inner.local_description())
+    }
+
+    /// remote_description returns the pending remote description, or the current one
+    /// if there is no pending one.
+    pub fn remote_description(&self) -> Option<RTCSessionDescription> {
+        block_on(self.inner.remote_description())
+    }
+
+    /// gather_complete blocks until the ICE candidate gathering is complete, so that the
+    /// local description contains all the candidates.
+    pub fn gather_complete(&self) {
+        block_on(async {
+            let mut gathering_complete = self.inner.gathering_complete_promise().await;
+            let _ = gathering_complete.recv().await;
+        })
+    }
+
+    /// add_ice_candidate adds a candidate of the remote peer.
+    pub fn add_ice_candidate(&self, candidate: RTCIceCandidateInit) -> Result<()> {
+        block_on(self.inner.add_ice_candidate(candidate))
+    }
+
+    /// create_data_channel creates a new DataChannel object with the given label
+    /// and optional DataChannelInit used to configure properties of the
+    /// underlying channel such as data reliability.
+    pub fn create_data_channel(
+        &self,
+        label: &str,
+        options: Option<RTCDataChannelInit>,
+    ) -> Result<RTCDataChannel> {
+        let inner = block_on(self.inner.create_data_channel(label, options))?;
+        Ok(RTCDataChannel { inner })
+    }
+
+    /// add_track adds a Track to the PeerConnection, the sample or RTP tracks of this
+    /// module are added with their inner track.
+    pub fn add_track(&self, track: Arc<dyn TrackLocal + Send + Sync>) -> Result<Arc<RTCRtpSender>> {
+        block_on(self.inner.add_track(track))
+    }
+
+    /// connection_state returns the current PeerConnectionState.
+    pub fn connection_state(&self) -> RTCPeerConnectionState {
+        self.inner.connection_state()
+    }
+
+    /// get_stats returns the stats of the PeerConnection.
+    pub fn get_stats(&self) -> StatsReport {
+        block_on(self.inner.get_stats())
+    }
+
+    /// events returns an Iterator over all the events of the PeerConnection. It replaces
+    /// the handlers set on the inner PeerConnection.
+    pub fn events(&self) -> Events<RTCPeerConnectionEvent> {
+        self.inner.events().into()
+    }
+
+    /// peer_connection_state_changes returns an Iterator over the PeerConnectionState changes.
+    pub fn peer_connection_state_changes(&self) -> Events<RTCPeerConnectionState> {
+        self.inner.peer_connection_state_changes().into()
+    }
+
+    /// ice_candidates returns an Iterator over the local ICE candidates, with None once the
+    /// gathering is finished.
+    pub fn ice_candidates(&self) -> Events<Option<RTCIceCandidate>> {
+        self.inner.ice_candidates().into()
+    }
+
+    /// tracks returns an Iterator over the remote tracks arriving from the remote peer.
+    pub fn tracks(&self) -> Events<(TrackRemoteReader, Arc<RTCRtpReceiver>)> {
+        let (tx, stream) = EventStream::channel();
+        self.inner.on_track(Box::new(move |t, r| {
+            if let (Some(t), Some(r)) = (t, r) {
+                let _ = tx.send((TrackRemoteReader { inner: t }, r));
+            }
+            Box::pin(async {})
+        }));
+        stream.into()
+    }
+
+    /// data_channels returns an Iterator over the data channels opened by the remote peer.
+    pub fn data_channels(&self) -> Events<RTCDataChannel> {
+        let (tx, stream) = EventStream::channel();
+        self.inner.on_data_channel(Box::new(move |inner| {
+            let _ = tx.send(RTCDataChannel { inner });
+            Box::pin(async {})
+        }));
+        stream.into()
+    }
+
+    /// close ends the PeerConnection.
+    pub fn close(&self) -> Result<()> {
+        block_on(self.inner.close())
+    }
+}
+
+/// RTCDataChannel is a blocking wrapper of a crate::data_channel::RTCDataChannel.
+pub struct RTCDataChannel {
+    inner: Arc<crate::data_channel::RTCDataChannel>,
+}
+
+impl RTCDataChannel {
+    /// inner returns the wrapped DataChannel, to be used with block_on.
+    pub fn inner(&self) -> &Arc<crate::data_channel::RTCDataChannel> {
+        &self.inner
+    }
+
+    /// label represents a label that can be used to distinguish this
+    /// DataChannel object from other DataChannel objects.
+    pub fn label(&self) -> &str {
+        self.inner.label()
+    }
+
+    /// ready_state represents the state of the DataChannel object.
+    pub fn ready_state(&self) -> RTCDataChannelState {
+        self.inner.ready_state()
+    }
+
+    /// wait_open blocks until the DataChannel is open, it fails if the DataChannel
+    /// is closed before.
+    pub fn wait_open(&self) -> Result<()> {
+        block_on(async {
+            // Created before the state is checked, so a close() racing with us still wakes it
+            let closed = self.inner.notify_tx.notified();
+            tokio::pin!(closed);
+
+            let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+            self.inner.on_open(Box::new(move || {
+                Box::pin(async move {
+                    let _ = open_tx.send(()).await;
+                })
+            }));
+
+            // on_open keeps its handler until the DataChannel opens, so a DataChannel
+            // closed before that never signals open_rx: watch the ready state as well.
+            match self.inner.ready_state() {
+                RTCDataChannelState::Open => return Ok(()),
+                RTCDataChannelState::Closing | RTCDataChannelState::Closed => {
+                    return Err(Error::ErrDataChannelNotOpen)
+                }
+                _ => {}
+            }
+
+            tokio::select! {
+                opened = open_rx.recv() => opened.ok_or(Error::ErrDataChannelNotOpen),
+                _ = &mut closed => Err(Error::ErrDataChannelNotOpen),
+            }
+        })
+    }
+
+    /// messages returns an Iterator over the messages received from the remote peer.
+    /// It replaces the handler set with on_message on the inner DataChannel.
+    pub fn messages(&self) -> Events<DataChannelMessage> {
+        let (tx, stream) = EventStream::channel();
+        self.inner.on_message(Box::new(move |msg| {
+            let _ = tx.send(msg);
+            Box::pin(async {})
+        }));
+        stream.into()
+    }
+
+    /// send sends the binary message to the DataChannel peer.
+    pub fn send(&self, data: &Bytes) -> Result<usize> {
+        block_on(self.inner.send(data))
+    }
+
+    /// send_text sends the text message to the DataChannel peer.
+    pub fn send_text(&self, s: String) -> Result<usize> {
+        block_on(self.inner.send_text(s))
+    }
+
+    /// close closes the DataChannel.
+    pub fn close(&self) -> Result<()> {
+        block_on(self.inner.close())
+    }
+}
+
+/// TrackRemoteReader is a blocking wrapper of a remote track.
+pub struct TrackRemoteReader {
+    inner: Arc<TrackRemote>,
+}
+
+impl TrackRemoteReader {
+    /// inner returns the wrapped TrackRemote, to be used with block_on.
+    pub fn inner(&self) -> &Arc<TrackRemote> {
+        &self.inner
+    }
+
+    /// read_rtp blocks until a RTP packet is received.
+    pub fn read_rtp(&self) -> Result<(rtp::packet::Packet, Attributes)> {
+        block_on(self.inner.read_rtp())
+    }
+}
+
+/// TrackSampleWriter is a blocking wrapper of a TrackLocalStaticSample.
+#[derive(Clone)]
+pub struct TrackSampleWriter {
+    inner: Arc<TrackLocalStaticSample>,
+}
+
+impl TrackSampleWriter {
+    pub fn new(inner: Arc<TrackLocalStaticSample>) -> Self {
+        TrackSampleWriter { inner }
+    }
+
+    /// inner returns the wrapped track, to be added to a PeerConnection.
+    pub fn inner(&self) -> &Arc<TrackLocalStaticSample> {
+        &self.inner
+    }
+
+    /// write_sample writes a Sample to the peers the track is bound to.
+    pub fn write_sample(&self, sample: &Sample) -> Result<()> {
+        block_on(self.inner.write_sample(sample))
+    }
+}
+
+/// TrackRTPWriter is a blocking wrapper of a TrackLocalStaticRTP.
+#[derive(Clone)]
+pub struct TrackRTPWriter {
+    inner: Arc<TrackLocalStaticRTP>,
+}
+
+impl TrackRTPWriter {
+    pub fn new(inner: Arc<TrackLocalStaticRTP>) -> Self {
+        TrackRTPWriter { inner }
+    }
+
+    /// inner returns the wrapped track, to be added to a PeerConnection.
+    pub fn inner(&self) -> &Arc<TrackLocalStaticRTP> {
+        &self.inner
+    }
+
+    /// write_rtp writes a RTP packet to the peers the track is bound to.
+    pub fn write_rtp(&self, p: &rtp::packet::Packet) -> Result<usize> {
+        block_on(self.inner.write_rtp(p))
+    }
+}
@@ -26,4 +26,9 @@ pub struct RTCDataChannelInit {
     /// to negotiate the channel and create an DataChannel with the same id
     /// at the other peer.
     pub negotiated: Option<u16>,
+
+    /// priority of the channel, announced to the peer in DATA_CHANNEL_OPEN.
+    /// Channels with a higher priority get a larger share of the bandwidth.
+    /// The default value of None stands for CHANNEL_PRIORITY_NORMAL.
+    pub priority: Option<u16>,
 }
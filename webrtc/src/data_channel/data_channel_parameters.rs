@@ -1,7 +1,8 @@
+use data::message::message_channel_open::CHANNEL_PRIORITY_NORMAL;
 use serde::{Deserialize, Serialize};
 
 /// DataChannelParameters describes the configuration of the DataChannel.
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataChannelParameters {
     pub label: String,
     pub protocol: String,
@@ -9,4 +10,19 @@ pub struct DataChannelParameters {
     pub max_packet_life_time: u16,
     pub max_retransmits: u16,
     pub negotiated: Option<u16>,
+    pub priority: u16,
+}
+
+impl Default for DataChannelParameters {
+    fn default() -> Self {
+        DataChannelParameters {
+            label: String::new(),
+            protocol: String::new(),
+            ordered: false,
+            max_packet_life_time: 0,
+            max_retransmits: 0,
+            negotiated: None,
+            priority: CHANNEL_PRIORITY_NORMAL,
+        }
+    }
 }
@@ -324,6 +324,35 @@ async fn test_data_channel_send_after_connected() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_data_channel_stats() -> Result<()> {
+    let dc = RTCDataChannel::new(
+        DataChannelParameters {
+            label: EXPECTED_LABEL.to_owned(),
+            ..Default::default()
+        },
+        Arc::new(SettingEngine::default()),
+    );
+
+    let stats = DataChannelStats::from(&dc).await;
+    assert_eq!(EXPECTED_LABEL, stats.label);
+    assert_eq!(RTCDataChannelState::Connecting, stats.state);
+    assert_eq!(0, stats.buffered_amount);
+    assert!(stats.connecting_timestamp.is_some());
+    assert!(stats.open_timestamp.is_none());
+
+    dc.set_ready_state(RTCDataChannelState::Open);
+    dc.set_ready_state(RTCDataChannelState::Closing);
+
+    let stats = DataChannelStats::from(&dc).await;
+    assert_eq!(RTCDataChannelState::Closing, stats.state);
+    assert!(stats.connecting_timestamp <= stats.open_timestamp);
+    assert!(stats.open_timestamp <= stats.closing_timestamp);
+    assert!(stats.closed_timestamp.is_none());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_data_channel_close() -> Result<()> {
     let mut m = MediaEngine::default();
@@ -34,15 +34,44 @@ use crate::sctp_transport::RTCSctpTransport;
 use crate::stats::stats_collector::StatsCollector;
 use crate::stats::{DataChannelStats, StatsReportType};
 
-/// message size limit for Chromium
-const DATA_CHANNEL_BUFFER_SIZE: u16 = u16::MAX;
-
 pub type OnMessageHdlrFn = Box<
     dyn (FnMut(DataChannelMessage) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
         + Send
         + Sync,
 >;
 
+/// StateTimestamps records when a DataChannel entered each of its states.
+#[derive(Default, Debug, Copy, Clone)]
+pub(crate) struct StateTimestamps {
+    pub(crate) connecting: Option<SystemTime>,
+    pub(crate) open: Option<SystemTime>,
+    pub(crate) closing: Option<SystemTime>,
+    pub(crate) closed: Option<SystemTime>,
+}
+
+impl StateTimestamps {
+    fn new() -> Self {
+        StateTimestamps {
+            connecting: Some(SystemTime::now()),
+            ..Default::default()
+        }
+    }
+
+    /// record records the time of the first transition to the given state.
+    fn record(&mut self, state: RTCDataChannelState) {
+        let timestamp = match state {
+            RTCDataChannelState::Connecting => &mut self.connecting,
+            RTCDataChannelState::Open => &mut self.open,
+            RTCDataChannelState::Closing => &mut self.closing,
+            RTCDataChannelState::Closed => &mut self.closed,
+            RTCDataChannelState::Unspecified => return,
+        };
+        if timestamp.is_none() {
+            *timestamp = Some(SystemTime::now());
+        }
+    }
+}
+
 pub type OnOpenHdlrFn =
     Box<dyn (FnOnce() -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
 
@@ -61,8 +90,10 @@ pub struct RTCDataChannel {
     pub(crate) max_retransmits: u16,
     pub(crate) protocol: String,
     pub(crate) negotiated: bool,
+    pub(crate) priority: u16,
     pub(crate) id: AtomicU16,
     pub(crate) ready_state: Arc<AtomicU8>, // DataChannelState
+    pub(crate) state_timestamps: Arc<SyncMutex<StateTimestamps>>,
     pub(crate) buffered_amount_low_threshold: AtomicUsize,
     pub(crate) detach_called: Arc<AtomicBool>,
 
@@ -105,11 +136,13 @@ impl RTCDataChannel {
             label: params.label,
             protocol: params.protocol,
             negotiated: params.negotiated.is_some(),
+            priority: params.priority,
             id: AtomicU16::new(id),
             ordered: params.ordered,
             max_packet_lifetime: params.max_packet_life_time,
             max_retransmits: params.max_retransmits,
             ready_state: Arc::new(AtomicU8::new(RTCDataChannelState::Connecting as u8)),
+            state_timestamps: Arc::new(SyncMutex::new(StateTimestamps::new())),
             detach_called: Arc::new(AtomicBool::new(false)),
 
             notify_tx: Arc::new(Notify::new()),
@@ -159,7 +192,7 @@ impl RTCDataChannel {
 
             let cfg = data::data_channel::Config {
                 channel_type,
-                priority: data::message::message_channel_open::CHANNEL_PRIORITY_NORMAL,
+                priority: self.priority,
                 reliability_parameter,
                 label: self.label.clone(),
                 protocol: self.protocol.clone(),
@@ -272,6 +305,7 @@ impl RTCDataChannel {
 
         if !self.setting_engine.detach.data_channels {
             let ready_state = Arc::clone(&self.ready_state);
+            let state_timestamps = Arc::clone(&self.state_timestamps);
             let on_message_handler = Arc::clone(&self.on_message_handler);
             let on_close_handler = Arc::clone(&self.on_close_handler);
             let on_error_handler = Arc::clone(&self.on_error_handler);
@@ -281,6 +315,7 @@ impl RTCDataChannel {
                     notify_rx,
                     dc,
                     ready_state,
+                    state_timestamps,
                     on_message_handler,
                     on_close_handler,
                     on_error_handler,
@@ -300,21 +335,22 @@ impl RTCDataChannel {
         notify_rx: Arc<Notify>,
         data_channel: Arc<data::data_channel::DataChannel>,
         ready_state: Arc<AtomicU8>,
+        state_timestamps: Arc<SyncMutex<StateTimestamps>>,
         on_message_handler: Arc<ArcSwapOption<Mutex<OnMessageHdlrFn>>>,
         on_close_handler: Arc<ArcSwapOption<Mutex<OnCloseHdlrFn>>>,
         on_error_handler: Arc<ArcSwapOption<Mutex<OnErrorHdlrFn>>>,
     ) {
-        let mut buffer = vec![0u8; DATA_CHANNEL_BUFFER_SIZE as usize];
         loop {
-            let (n, is_string) = tokio::select! {
+            let (data, is_string) = tokio::select! {
                 _ = notify_rx.notified() => break,
-                result = data_channel.read_data_channel(&mut buffer) => {
+                result = data_channel.read_data_channel_bytes() => {
                     match result{
                         // EOF (`data_channel` was either closed or the underlying stream got
                         // reset by the remote) => close and run `on_close` handler.
-                        Ok((0, _)) =>
+                        Ok((data, _)) if data.is_empty() =>
                         {
                             ready_state.store(RTCDataChannelState::Closed as u8, Ordering::SeqCst);
+                            state_timestamps.lock().record(RTCDataChannelState::Closed);
 
                             let on_close_handler2 = Arc::clone(&on_close_handler);
                             tokio::spawn(async move {
@@ -326,9 +362,10 @@ impl RTCDataChannel {
 
                             break;
                         }
-                        Ok((n, is_string)) => (n, is_string),
+                        Ok((data, is_string)) => (data, is_string),
                         Err(err) => {
                             ready_state.store(RTCDataChannelState::Closed as u8, Ordering::SeqCst);
+                            state_timestamps.lock().record(RTCDataChannelState::Closed);
 
                             let on_error_handler2 = Arc::clone(&on_error_handler);
                             tokio::spawn(async move {
@@ -354,11 +391,7 @@ impl RTCDataChannel {
 
             if let Some(handler) = &*on_message_handler.load() {
                 let mut f = handler.lock().await;
-                f(DataChannelMessage {
-                    is_string,
-                    data: Bytes::from(buffer[..n].to_vec()),
-                })
-                .await;
+                f(DataChannelMessage { is_string, data }).await;
             }
         }
     }
@@ -375,6 +408,25 @@ impl RTCDataChannel {
         }
     }
 
+    /// send_bytes sends the binary message to the DataChannel peer, handing
+    /// ownership of `data` down to the SCTP layer without copying it.
+    pub async fn send_bytes(&self, data: Bytes) -> Result<usize> {
+        self.send(&data).await
+    }
+
+    /// send_vectored sends the concatenation of `bufs` to the DataChannel peer as
+    /// a single binary message, without joining the buffers first.
+    pub async fn send_vectored(&self, bufs: &[Bytes]) -> Result<usize> {
+        self.ensure_open()?;
+
+        let data_channel = self.data_channel.lock().await;
+        if let Some(dc) = &*data_channel {
+            Ok(dc.write_data_channel_vectored(bufs, false).await?)
+        } else {
+            Err(Error::ErrClosedPipe)
+        }
+    }
+
     /// send_text sends the text message to the DataChannel peer
     pub async fn send_text(&self, s: String) -> Result<usize> {
         self.ensure_open()?;
@@ -429,10 +481,11 @@ impl RTCDataChannel {
         self.notify_tx.notify_waiters();
 
         let data_channel = self.data_channel.lock().await;
-        if let Some(dc) = &*data_channel {
-            Ok(dc.close().await?)
-        } else {
-            Ok(())
+        match &*data_channel {
+            // Once the association is shut down, e.g. because the remote peer closed first,
+            // there is no stream left to reset: the data channel closes with the association.
+            Some(dc) if dc.is_association_established() => Ok(dc.close().await?),
+            _ => Ok(()),
         }
     }
 
@@ -473,6 +526,12 @@ impl RTCDataChannel {
         self.negotiated
     }
 
+    /// priority represents the priority of this DataChannel, announced to the
+    /// peer in DATA_CHANNEL_OPEN.
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
     /// ID represents the ID for this DataChannel. The value is initially
     /// null, which is what will be returned if the ID was not provided at
     /// channel creation time, and the DTLS role of the SCTP transport has not
@@ -533,6 +592,23 @@ impl RTCDataChannel {
         }
     }
 
+    /// wait_buffered_amount_low waits until buffered_amount decreases to
+    /// buffered_amount_low_threshold or below it, as an alternative to the
+    /// on_buffered_amount_low event handler for flow control in async code.
+    pub async fn wait_buffered_amount_low(&self) -> Result<()> {
+        let dc = {
+            let data_channel = self.data_channel.lock().await;
+            data_channel.clone()
+        };
+
+        if let Some(dc) = dc {
+            Ok(dc.wait_buffered_amount_low().await?)
+        } else {
+            // Nothing can be buffered before the DataChannel is open
+            Ok(())
+        }
+    }
+
     /// on_buffered_amount_low sets an event handler which is invoked when
     /// the number of bytes of outgoing data becomes lower than the
     /// buffered_amount_low_threshold.
@@ -557,5 +633,6 @@ impl RTCDataChannel {
 
     pub(crate) fn set_ready_state(&self, r: RTCDataChannelState) {
         self.ready_state.store(r as u8, Ordering::SeqCst);
+        self.state_timestamps.lock().record(r);
     }
 }
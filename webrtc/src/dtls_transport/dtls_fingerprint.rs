@@ -1,4 +1,9 @@
+use ring::digest;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// The hash function algorithm of the fingerprints of the local certificates by default.
+pub(crate) const DEFAULT_FINGERPRINT_ALGORITHM: &str = "sha-256";
 
 /// DTLSFingerprint specifies the hash function algorithm and certificate
 /// fingerprint as described in <https://tools.ietf.org/html/rfc4572>.
@@ -13,3 +18,44 @@ pub struct RTCDtlsFingerprint {
     /// <https://tools.ietf.org/html/rfc4572#section-5>.
     pub value: String,
 }
+
+/// The hash function algorithms of the fingerprints which are supported, from the weakest to
+/// the strongest.
+pub(crate) const SUPPORTED_FINGERPRINT_ALGORITHMS: [&str; 4] =
+    ["sha-1", "sha-256", "sha-384", "sha-512"];
+
+/// fingerprint_value returns the fingerprint of a DER encoded certificate computed with the
+/// given hash function algorithm, "sha-1", "sha-256", "sha-384" or "sha-512", or None if the
+/// algorithm isn't supported.
+pub(crate) fn fingerprint_value(algorithm: &str, der: &[u8]) -> Option<String> {
+    let hashed = match algorithm.to_lowercase().as_str() {
+        "sha-1" => digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, der)
+            .as_ref()
+            .to_vec(),
+        "sha-256" => Sha256::digest(der).to_vec(),
+        "sha-384" => Sha384::digest(der).to_vec(),
+        "sha-512" => Sha512::digest(der).to_vec(),
+        _ => return None,
+    };
+    let values: Vec<String> = hashed.iter().map(|x| format! {"{:02x}", x}).collect();
+    Some(values.join(":"))
+}
+
+/// strongest_fingerprint returns the fingerprint with the strongest supported hash function
+/// algorithm, which is the one to verify the certificate of the peer with when it announces
+/// several, as described in <https://tools.ietf.org/html/rfc8122#section-5>.
+pub(crate) fn strongest_fingerprint(
+    fingerprints: &[RTCDtlsFingerprint],
+) -> Option<&RTCDtlsFingerprint> {
+    fingerprints
+        .iter()
+        .filter_map(|fp| {
+            let algorithm = fp.algorithm.to_lowercase();
+            SUPPORTED_FINGERPRINT_ALGORITHMS
+                .iter()
+                .position(|a| *a == algorithm)
+                .map(|strength| (strength, fp))
+        })
+        .max_by_key(|(strength, _)| *strength)
+        .map(|(_, fp)| fp)
+}
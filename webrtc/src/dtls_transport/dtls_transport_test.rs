@@ -2,6 +2,7 @@ use super::*;
 use crate::api::media_engine::MediaEngine;
 use crate::api::APIBuilder;
 use crate::data_channel::RTCDataChannel;
+use crate::dtls_transport::dtls_fingerprint::RTCDtlsFingerprint;
 use crate::ice_transport::ice_candidate::RTCIceCandidate;
 use crate::peer_connection::configuration::RTCConfiguration;
 use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
@@ -205,3 +206,70 @@ async fn test_peer_connection_dtls_role_setting_engine_client() -> Result<()> {
 
     run_test(DTLSRole::Client).await
 }
+
+#[tokio::test]
+async fn test_validate_fingerprint_strongest() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let pc = APIBuilder::new()
+        .with_media_engine(m)
+        .build()
+        .new_peer_connection(RTCConfiguration::default())
+        .await?;
+
+    let transport = pc.sctp().transport();
+    let cert = transport.certificates[0].dtls_certificate.certificate[0]
+        .0
+        .clone();
+    let good = |algorithm: &str| RTCDtlsFingerprint {
+        algorithm: algorithm.to_uppercase(),
+        value: fingerprint_value(algorithm, &cert).unwrap().to_uppercase(),
+    };
+    let bad = |algorithm: &str| RTCDtlsFingerprint {
+        algorithm: algorithm.to_owned(),
+        value: "AA:AA:AA:AA".to_owned(),
+    };
+
+    let tests = vec![
+        ("sha-1", vec![good("sha-1")], None),
+        (
+            "sha-256 and sha-384",
+            vec![good("sha-256"), good("sha-384")],
+            None,
+        ),
+        ("weaker mismatch", vec![bad("sha-1"), good("sha-512")], None),
+        (
+            "stronger mismatch",
+            vec![good("sha-256"), bad("sha-384")],
+            Some(Error::ErrNoMatchingCertificateFingerprint),
+        ),
+        (
+            "unsupported ignored",
+            vec![bad("md5"), good("sha-256")],
+            None,
+        ),
+        (
+            "unsupported",
+            vec![bad("md5")],
+            Some(Error::ErrUnsupportedFingerprintAlgorithm),
+        ),
+        (
+            "none",
+            vec![],
+            Some(Error::ErrNoMatchingCertificateFingerprint),
+        ),
+    ];
+
+    for (name, fingerprints, want_err) in tests {
+        *transport.remote_parameters.lock().await = DTLSParameters {
+            role: DTLSRole::Auto,
+            fingerprints,
+        };
+        let result = transport.validate_fingerprint(&cert).await;
+        assert_eq!(result.err(), want_err, "{}", name);
+    }
+
+    pc.close().await?;
+
+    Ok(())
+}
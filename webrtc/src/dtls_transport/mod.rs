@@ -11,7 +11,6 @@ use dtls::conn::DTLSConn;
 use dtls::extension::extension_use_srtp::SrtpProtectionProfile;
 use interceptor::stream_info::StreamInfo;
 use interceptor::{Interceptor, RTCPReader, RTPReader};
-use sha2::{Digest, Sha256};
 use srtp::protection_profile::ProtectionProfile;
 use srtp::session::Session;
 use srtp::stream::Stream;
@@ -21,6 +20,7 @@ use util::Conn;
 use dtls_role::*;
 
 use crate::api::setting_engine::SettingEngine;
+use crate::dtls_transport::dtls_fingerprint::{fingerprint_value, strongest_fingerprint};
 use crate::dtls_transport::dtls_parameters::DTLSParameters;
 use crate::dtls_transport::dtls_transport_state::RTCDtlsTransportState;
 use crate::error::{flatten_errs, Error, Result};
@@ -30,6 +30,7 @@ use crate::ice_transport::RTCIceTransport;
 use crate::mux::endpoint::Endpoint;
 use crate::mux::mux_func::{match_dtls, match_srtcp, match_srtp, MatchFunc};
 use crate::peer_connection::certificate::RTCCertificate;
+use crate::peer_connection::transport_state_change::RTCTransportStateReason;
 use crate::rtp_transceiver::SSRC;
 use crate::stats::stats_collector::StatsCollector;
 
@@ -66,10 +67,14 @@ pub struct RTCDtlsTransport {
 
     pub(crate) remote_parameters: Mutex<DTLSParameters>,
     pub(crate) remote_certificate: Mutex<Bytes>,
-    pub(crate) state: AtomicU8, //DTLSTransportState,
+    pub(crate) state: Arc<AtomicU8>, //DTLSTransportState,
+    pub(crate) state_reason: Arc<ArcSwapOption<RTCTransportStateReason>>,
     pub(crate) srtp_protection_profile: Mutex<ProtectionProfile>,
-    pub(crate) on_state_change_handler: ArcSwapOption<Mutex<OnDTLSTransportStateChangeHdlrFn>>,
+    pub(crate) on_state_change_handler: Arc<ArcSwapOption<Mutex<OnDTLSTransportStateChangeHdlrFn>>>,
     pub(crate) conn: Mutex<Option<Arc<DTLSConn>>>,
+    /// rtcp_conn is the DTLS connection of the RTCP component, when RTCP isn't multiplexed
+    /// with RTP. The SRTCP keys are derived from it, RFC 5764 S4.1.
+    pub(crate) rtcp_conn: Mutex<Option<Arc<DTLSConn>>>,
 
     pub(crate) srtp_session: Mutex<Option<Arc<Session>>>,
     pub(crate) srtcp_session: Mutex<Option<Arc<Session>>>,
@@ -99,7 +104,7 @@ impl RTCDtlsTransport {
             srtp_ready_signal: Arc::new(AtomicBool::new(false)),
             srtp_ready_tx: Mutex::new(Some(srtp_ready_tx)),
             srtp_ready_rx: Mutex::new(Some(srtp_ready_rx)),
-            state: AtomicU8::new(RTCDtlsTransportState::New as u8),
+            state: Arc::new(AtomicU8::new(RTCDtlsTransportState::New as u8)),
             dtls_matcher: Some(Box::new(match_dtls)),
             ..Default::default()
         }
@@ -110,6 +115,11 @@ impl RTCDtlsTransport {
         conn.clone()
     }
 
+    pub(crate) async fn rtcp_conn(&self) -> Option<Arc<DTLSConn>> {
+        let rtcp_conn = self.rtcp_conn.lock().await;
+        rtcp_conn.clone()
+    }
+
     /// returns the currently-configured ICETransport or None
     /// if one has not been configured
     pub fn ice_transport(&self) -> &RTCIceTransport {
@@ -117,14 +127,75 @@ impl RTCDtlsTransport {
     }
 
     /// state_change requires the caller holds the lock
-    async fn state_change(&self, state: RTCDtlsTransportState) {
+    async fn state_change(
+        &self,
+        state: RTCDtlsTransportState,
+        reason: Option<RTCTransportStateReason>,
+    ) {
         self.state.store(state as u8, Ordering::SeqCst);
-        if let Some(handler) = &*self.on_state_change_handler.load() {
+        RTCDtlsTransport::do_state_change(
+            &self.on_state_change_handler,
+            &self.state_reason,
+            state,
+            reason,
+        )
+        .await;
+    }
+
+    async fn do_state_change(
+        handler: &Arc<ArcSwapOption<Mutex<OnDTLSTransportStateChangeHdlrFn>>>,
+        state_reason: &Arc<ArcSwapOption<RTCTransportStateReason>>,
+        state: RTCDtlsTransportState,
+        reason: Option<RTCTransportStateReason>,
+    ) {
+        state_reason.store(reason.map(Arc::new));
+        if let Some(handler) = &*handler.load() {
             let mut f = handler.lock().await;
             f(state).await;
         }
     }
 
+    /// watch_remote_close follows the DTLS connection once it is connected, and changes the
+    /// state to closed if the remote peer closes it with a close_notify alert, or to failed
+    /// if it is closed by a fatal alert.
+    fn watch_remote_close(&self, dtls_conn: &DTLSConn) {
+        let closed = dtls_conn.closed();
+        let state = Arc::clone(&self.state);
+        let state_reason = Arc::clone(&self.state_reason);
+        let handler = Arc::clone(&self.on_state_change_handler);
+        tokio::spawn(async move {
+            let alert = match closed.await {
+                Some(alert) => alert,
+                None => return,
+            };
+            let (next, reason) = if alert.is_close_notify() {
+                (
+                    RTCDtlsTransportState::Closed,
+                    RTCTransportStateReason::RemoteClose,
+                )
+            } else {
+                (
+                    RTCDtlsTransportState::Failed,
+                    RTCTransportStateReason::Alert(alert.to_string()),
+                )
+            };
+            log::debug!("DTLS connection closed by {}", alert);
+
+            if state
+                .compare_exchange(
+                    RTCDtlsTransportState::Connected as u8,
+                    next as u8,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                )
+                .is_ok()
+            {
+                RTCDtlsTransport::do_state_change(&handler, &state_reason, next, Some(reason))
+                    .await;
+            }
+        });
+    }
+
     /// on_state_change sets a handler that is fired when the DTLS
     /// connection state changes.
     pub fn on_state_change(&self, f: OnDTLSTransportStateChangeHdlrFn) {
@@ -137,6 +208,12 @@ impl RTCDtlsTransport {
         self.state.load(Ordering::SeqCst).into()
     }
 
+    /// state_reason returns the reason of the current state if the DTLSTransport is
+    /// closed or failed.
+    pub fn state_reason(&self) -> Option<RTCTransportStateReason> {
+        self.state_reason.load().as_deref().cloned()
+    }
+
     /// write_rtcp sends a user provided RTCP packet to the connected peer. If no peer is connected the
     /// packet is discarded.
     pub async fn write_rtcp(
@@ -157,7 +234,8 @@ impl RTCDtlsTransport {
         let mut fingerprints = vec![];
 
         for c in &self.certificates {
-            fingerprints.extend(c.get_fingerprints());
+            fingerprints
+                .extend(c.fingerprints(&self.setting_engine.get_dtls_fingerprint_algorithms()));
         }
 
         Ok(DTLSParameters {
@@ -232,7 +310,11 @@ impl RTCDtlsTransport {
             srtcp_config.remote_rtcp_options = Some(srtp::option::srtcp_no_replay_protection());
         }
 
-        if let Some(conn) = self.conn().await {
+        let srtcp_conn = match self.rtcp_conn().await {
+            Some(rtcp_conn) => Some(rtcp_conn),
+            None => self.conn().await,
+        };
+        if let Some(conn) = srtcp_conn {
             let conn_state = conn.connection_state().await;
             srtcp_config
                 .extract_session_keys_from_dtls(conn_state, self.role().await == DTLSRole::Client)
@@ -282,6 +364,13 @@ impl RTCDtlsTransport {
     }
 
     pub(crate) async fn role(&self) -> DTLSRole {
+        // If SettingEngine forces a role
+        match self.setting_engine.dtls_role {
+            DTLSRole::Server => return DTLSRole::Server,
+            DTLSRole::Client => return DTLSRole::Client,
+            _ => {}
+        };
+
         // If remote has an explicit role use the inverse
         {
             let remote_parameters = self.remote_parameters.lock().await;
@@ -329,7 +418,14 @@ impl RTCDtlsTransport {
         }
         {
             let mut srtcp_endpoint = self.srtcp_endpoint.lock().await;
-            *srtcp_endpoint = self.ice_transport.new_endpoint(Box::new(match_srtcp)).await;
+            *srtcp_endpoint = match self
+                .ice_transport
+                .new_rtcp_endpoint(Box::new(match_srtcp))
+                .await
+            {
+                Some(rtcp_endpoint) => Some(rtcp_endpoint),
+                None => self.ice_transport.new_endpoint(Box::new(match_srtcp)).await,
+            };
         }
         {
             let mut rp = self.remote_parameters.lock().await;
@@ -341,7 +437,8 @@ impl RTCDtlsTransport {
         } else {
             return Err(Error::ErrNonCertificate);
         };
-        self.state_change(RTCDtlsTransportState::Connecting).await;
+        self.state_change(RTCDtlsTransportState::Connecting, None)
+            .await;
 
         Ok((
             self.role().await,
@@ -358,6 +455,7 @@ impl RTCDtlsTransport {
                 },
                 client_auth: ClientAuthType::RequireAnyClientCert,
                 insecure_skip_verify: true,
+                insecure_hashes: self.setting_engine.allow_insecure_dtls_hashes,
                 insecure_verification: self.setting_engine.allow_insecure_verification_algorithm,
                 ..Default::default()
             },
@@ -369,29 +467,31 @@ impl RTCDtlsTransport {
         let dtls_conn_result = if let Some(dtls_endpoint) =
             self.ice_transport.new_endpoint(Box::new(match_dtls)).await
         {
+            let rtcp_dtls_endpoint = self
+                .ice_transport
+                .new_rtcp_endpoint(Box::new(match_dtls))
+                .await;
             let (role, mut dtls_config) = self.prepare_transport(remote_parameters).await?;
             if self.setting_engine.replay_protection.dtls != 0 {
                 dtls_config.replay_protection_window = self.setting_engine.replay_protection.dtls;
             }
+            if let Some(interval) = self.setting_engine.timeout.dtls_retransmission_interval {
+                dtls_config.flight_interval = interval;
+            }
 
             // Connect as DTLS Client/Server, function is blocking and we
             // must not hold the DTLSTransport lock
-            if role == DTLSRole::Client {
-                dtls::conn::DTLSConn::new(
-                    dtls_endpoint as Arc<dyn Conn + Send + Sync>,
-                    dtls_config,
-                    true,
-                    None,
-                )
-                .await
-            } else {
-                dtls::conn::DTLSConn::new(
-                    dtls_endpoint as Arc<dyn Conn + Send + Sync>,
-                    dtls_config,
-                    false,
-                    None,
+            match rtcp_dtls_endpoint {
+                Some(rtcp_dtls_endpoint) => futures::future::try_join(
+                    self.handshake(dtls_endpoint, role, dtls_config.clone()),
+                    self.handshake(rtcp_dtls_endpoint, role, dtls_config),
                 )
                 .await
+                .map(|(dtls_conn, rtcp_dtls_conn)| (dtls_conn, Some(rtcp_dtls_conn))),
+                None => self
+                    .handshake(dtls_endpoint, role, dtls_config)
+                    .await
+                    .map(|dtls_conn| (dtls_conn, None)),
             }
         } else {
             Err(dtls::Error::Other(
@@ -399,14 +499,27 @@ impl RTCDtlsTransport {
             ))
         };
 
-        let dtls_conn = match dtls_conn_result {
+        let (dtls_conn, rtcp_dtls_conn) = match dtls_conn_result {
             Ok(dtls_conn) => dtls_conn,
             Err(err) => {
-                self.state_change(RTCDtlsTransportState::Failed).await;
+                let reason = if err == dtls::Error::ErrDeadlineExceeded {
+                    RTCTransportStateReason::HandshakeTimeout
+                } else {
+                    RTCTransportStateReason::HandshakeFailed(err.to_string())
+                };
+                self.state_change(RTCDtlsTransportState::Failed, Some(reason))
+                    .await;
                 return Err(err.into());
             }
         };
 
+        // Stored right away, so that it is closed along with the transport if the handshake
+        // of RTP turns out to fail
+        if let Some(rtcp_dtls_conn) = rtcp_dtls_conn {
+            let mut rtcp_conn = self.rtcp_conn.lock().await;
+            *rtcp_conn = Some(Arc::new(rtcp_dtls_conn));
+        }
+
         let srtp_profile = dtls_conn.selected_srtpprotection_profile();
         {
             let mut srtp_protection_profile = self.srtp_protection_profile.lock().await;
@@ -422,7 +535,13 @@ impl RTCDtlsTransport {
                         log::error!("{}", err);
                     }
 
-                    self.state_change(RTCDtlsTransportState::Failed).await;
+                    self.state_change(
+                        RTCDtlsTransportState::Failed,
+                        Some(RTCTransportStateReason::HandshakeFailed(
+                            Error::ErrNoSRTPProtectionProfile.to_string(),
+                        )),
+                    )
+                    .await;
                     return Err(Error::ErrNoSRTPProtectionProfile);
                 }
             };
@@ -435,7 +554,13 @@ impl RTCDtlsTransport {
                 log::error!("{}", err);
             }
 
-            self.state_change(RTCDtlsTransportState::Failed).await;
+            self.state_change(
+                RTCDtlsTransportState::Failed,
+                Some(RTCTransportStateReason::HandshakeFailed(
+                    Error::ErrNoRemoteCertificate.to_string(),
+                )),
+            )
+            .await;
             return Err(Error::ErrNoRemoteCertificate);
         }
 
@@ -453,20 +578,96 @@ impl RTCDtlsTransport {
                     log::error!("{}", close_err);
                 }
 
-                self.state_change(RTCDtlsTransportState::Failed).await;
+                self.state_change(
+                    RTCDtlsTransportState::Failed,
+                    Some(RTCTransportStateReason::FingerprintMismatch),
+                )
+                .await;
                 return Err(err);
             }
         }
 
+        if let Some(rtcp_dtls_conn) = self.rtcp_conn().await {
+            if let Err((err, reason)) = self.validate_rtcp_conn(&rtcp_dtls_conn, srtp_profile).await
+            {
+                for conn in [&dtls_conn, &*rtcp_dtls_conn] {
+                    if let Err(close_err) = conn.close().await {
+                        log::error!("{}", close_err);
+                    }
+                }
+
+                self.state_change(RTCDtlsTransportState::Failed, Some(reason))
+                    .await;
+                return Err(err);
+            }
+        }
+
+        self.watch_remote_close(&dtls_conn);
         {
             let mut conn = self.conn.lock().await;
             *conn = Some(Arc::new(dtls_conn));
         }
-        self.state_change(RTCDtlsTransportState::Connected).await;
+        self.state_change(RTCDtlsTransportState::Connected, None)
+            .await;
 
         self.start_srtp().await
     }
 
+    /// handshake connects as DTLS client or server over an endpoint of the ICE transport.
+    async fn handshake(
+        &self,
+        endpoint: Arc<Endpoint>,
+        role: DTLSRole,
+        dtls_config: dtls::config::Config,
+    ) -> std::result::Result<DTLSConn, dtls::Error> {
+        let handshake = dtls::conn::DTLSConn::new(
+            endpoint as Arc<dyn Conn + Send + Sync>,
+            dtls_config,
+            role == DTLSRole::Client,
+            None,
+        );
+        match self.setting_engine.timeout.dtls_handshake_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, handshake).await {
+                Ok(result) => result,
+                Err(_) => Err(dtls::Error::ErrDeadlineExceeded),
+            },
+            None => handshake.await,
+        }
+    }
+
+    /// validate_rtcp_conn checks the DTLS connection of the RTCP component, which must use
+    /// the SRTP protection profile of the RTP component and a certificate matching the
+    /// fingerprint of the remote peer as well.
+    async fn validate_rtcp_conn(
+        &self,
+        rtcp_dtls_conn: &DTLSConn,
+        srtp_profile: SrtpProtectionProfile,
+    ) -> std::result::Result<(), (Error, RTCTransportStateReason)> {
+        if rtcp_dtls_conn.selected_srtpprotection_profile() != srtp_profile {
+            let err = Error::ErrNoSRTPProtectionProfile;
+            let reason = RTCTransportStateReason::HandshakeFailed(err.to_string());
+            return Err((err, reason));
+        }
+
+        let remote_certs = &rtcp_dtls_conn.connection_state().await.peer_certificates;
+        if remote_certs.is_empty() {
+            let err = Error::ErrNoRemoteCertificate;
+            let reason = RTCTransportStateReason::HandshakeFailed(err.to_string());
+            return Err((err, reason));
+        }
+
+        if !self
+            .setting_engine
+            .disable_certificate_fingerprint_verification
+        {
+            if let Err(err) = self.validate_fingerprint(&remote_certs[0]).await {
+                return Err((err, RTCTransportStateReason::FingerprintMismatch));
+            }
+        }
+
+        Ok(())
+    }
+
     /// stops and closes the DTLSTransport object.
     pub async fn stop(&self) -> Result<()> {
         // Try closing everything and collect the errors
@@ -532,30 +733,40 @@ impl RTCDtlsTransport {
             }
         }
 
-        self.state_change(RTCDtlsTransportState::Closed).await;
+        if let Some(rtcp_conn) = self.rtcp_conn().await {
+            if let Err(err) = rtcp_conn.close().await {
+                if err != dtls::Error::ErrConnClosed {
+                    close_errs.push(err.into());
+                }
+            }
+        }
+
+        if self.state() != RTCDtlsTransportState::Closed {
+            self.state_change(
+                RTCDtlsTransportState::Closed,
+                Some(RTCTransportStateReason::LocalClose),
+            )
+            .await;
+        }
 
         flatten_errs(close_errs)
     }
 
     pub(crate) async fn validate_fingerprint(&self, remote_cert: &[u8]) -> Result<()> {
         let remote_parameters = self.remote_parameters.lock().await;
-        for fp in &remote_parameters.fingerprints {
-            if fp.algorithm != "sha-256" {
-                return Err(Error::ErrUnsupportedFingerprintAlgorithm);
-            }
-
-            let mut h = Sha256::new();
-            h.update(remote_cert);
-            let hashed = h.finalize();
-            let values: Vec<String> = hashed.iter().map(|x| format! {"{:02x}", x}).collect();
-            let remote_value = values.join(":").to_lowercase();
-
-            if remote_value == fp.value.to_lowercase() {
-                return Ok(());
-            }
+        if remote_parameters.fingerprints.is_empty() {
+            return Err(Error::ErrNoMatchingCertificateFingerprint);
         }
 
-        Err(Error::ErrNoMatchingCertificateFingerprint)
+        // Only the strongest fingerprint is verified, a weaker one must not be able to vouch
+        // for a certificate which doesn't match it
+        let fp = strongest_fingerprint(&remote_parameters.fingerprints)
+            .ok_or(Error::ErrUnsupportedFingerprintAlgorithm)?;
+        if fingerprint_value(&fp.algorithm, remote_cert) == Some(fp.value.to_lowercase()) {
+            Ok(())
+        } else {
+            Err(Error::ErrNoMatchingCertificateFingerprint)
+        }
     }
 
     pub(crate) fn ensure_ice_conn(&self) -> Result<()> {
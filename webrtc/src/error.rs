@@ -1,13 +1,16 @@
+#[cfg(not(target_arch = "wasm32"))]
 use rcgen::RcgenError;
 use std::future::Future;
 use std::num::ParseIntError;
 use std::pin::Pin;
 use std::string::FromUtf8Error;
 use thiserror::Error;
+#[cfg(not(target_arch = "wasm32"))]
 use tokio::sync::mpsc::error::SendError as MpscSendError;
 
 use crate::peer_connection::sdp::sdp_type::RTCSdpType;
 use crate::peer_connection::signaling_state::RTCSignalingState;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::rtp_transceiver::rtp_receiver;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -172,6 +175,11 @@ pub enum Error {
     #[error("set_remote_description called with multiple conflicting ice-pwd values")]
     ErrSessionDescriptionConflictingIcePwd,
 
+    /// ErrSessionDescriptionNotBundled indicates set_remote_description was called with a SessionDescription that
+    /// has media sections outside of its BUNDLE group, with the max-bundle policy
+    #[error("set_remote_description called with media sections which are not bundled")]
+    ErrSessionDescriptionNotBundled,
+
     /// ErrNoSRTPProtectionProfile indicates that the DTLS handshake completed and no SRTP Protection Profile was chosen
     #[error("DTLS Handshake completed and no SRTP Protection Profile was chosen")]
     ErrNoSRTPProtectionProfile,
@@ -205,6 +213,14 @@ pub enum Error {
     #[error("the requested codec does not have a payloader")]
     ErrNoPayloaderForCodec,
 
+    /// ErrInvalidScalabilityMode indicates that a scalability mode could not be parsed
+    #[error("invalid scalability mode")]
+    ErrInvalidScalabilityMode,
+
+    /// ErrUnsupportedScalabilityMode indicates that the codec does not support the requested scalability mode
+    #[error("the requested scalability mode is not supported by the codec")]
+    ErrUnsupportedScalabilityMode,
+
     /// ErrRegisterHeaderExtensionInvalidDirection indicates that a extension was registered with different
     /// directions for two different calls.
     #[error("a header extension must be registered with the same direction each time")]
@@ -311,6 +327,7 @@ pub enum Error {
     ErrRTPReceiverForSSRCTrackStreamNotFound,
     #[error("no trackStreams found for RID")]
     ErrRTPReceiverForRIDTrackStreamNotFound,
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("invalid RTP Receiver transition from {from} to {to}")]
     ErrRTPReceiverStateChangeInvalid {
         from: rtp_receiver::State,
@@ -326,6 +343,22 @@ pub enum Error {
     ErrRTPSenderDTLSTransportNil,
     #[error("Send has already been called")]
     ErrRTPSenderSendAlreadyCalled,
+    #[error("RID must not be empty when sending more than one encoding")]
+    ErrRTPSenderRidNil,
+    #[error("RID is used by more than one encoding")]
+    ErrRTPSenderRidCollision,
+    #[error("no encoding found for RID")]
+    ErrRTPSenderNoEncodingForRid,
+    #[error("RTPSender has been stopped")]
+    ErrRTPSenderStopped,
+    #[error("parameters modify read-only fields or the number of encodings")]
+    ErrRTPSenderInvalidModification,
+    #[error("encoding parameter out of range")]
+    ErrRTPSenderEncodingParameterOutOfRange,
+    #[error("DTMF can not be inserted, the sender isn't sending telephone-event")]
+    ErrDtmfCanNotInsert,
+    #[error("DTMF tones must be 0-9, A-D, #, * or ,")]
+    ErrDtmfInvalidTone,
     #[error("errRTPSenderTrackNil")]
     ErrRTPTransceiverCannotChangeMid,
     #[error("invalid state change in RTPTransceiver.setSending")]
@@ -344,6 +377,8 @@ pub enum Error {
     ErrSDPMediaSectionMultipleTrackInvalid,
     #[error("set_answering_dtlsrole must DTLSRoleClient or DTLSRoleServer")]
     ErrSettingEngineSetAnsweringDTLSRole,
+    #[error("set_dtls_role must be DTLSRoleAuto, DTLSRoleClient or DTLSRoleServer")]
+    ErrSettingEngineSetDTLSRole,
     #[error("can't rollback from stable state")]
     ErrSignalingStateCannotRollback,
     #[error(
@@ -376,29 +411,39 @@ pub enum Error {
     #[error("not long enough to be a RTP Packet")]
     ErrRTPTooShort,
 
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("{0}")]
     Util(#[from] util::Error),
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("{0}")]
     Ice(#[from] ice::Error),
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("{0}")]
     Srtp(#[from] srtp::Error),
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("{0}")]
     Dtls(#[from] dtls::Error),
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("{0}")]
     Data(#[from] data::Error),
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("{0}")]
     Sctp(#[from] sctp::Error),
     #[error("{0}")]
     Sdp(#[from] sdp::Error),
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("{0}")]
     Interceptor(#[from] interceptor::Error),
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("{0}")]
     Rtcp(#[from] rtcp::Error),
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("{0}")]
     Rtp(#[from] rtp::Error),
 
     #[error("utf-8 error: {0}")]
     Utf8(#[from] FromUtf8Error),
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("{0}")]
     RcGen(#[from] RcgenError),
     #[error("mpsc send: {0}")]
@@ -408,6 +453,21 @@ pub enum Error {
     #[error("parse url: {0}")]
     ParseUrl(#[from] url::ParseError),
 
+    #[error("http: {0}")]
+    Http(String),
+    #[error("unexpected HTTP status {0}")]
+    ErrUnexpectedHttpStatus(u16),
+    #[error("no Location header in the response to the offer")]
+    ErrWhipNoLocation,
+    #[error("no WHIP or WHEP session with this id")]
+    ErrWhipSessionNotFound,
+    #[error("unsupported content type")]
+    ErrUnsupportedContentType,
+
+    /// Error of the WebRTC API of the browser.
+    #[error("javascript: {0}")]
+    Js(String),
+
     /// Error parsing a given PEM string.
     #[error("invalid PEM: {0}")]
     InvalidPEM(String),
@@ -421,12 +481,14 @@ pub type OnErrorHdlrFn =
     Box<dyn (FnMut(Error) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
 
 // Because Tokio SendError is parameterized, we sadly lose the backtrace.
+#[cfg(not(target_arch = "wasm32"))]
 impl<T> From<MpscSendError<T>> for Error {
     fn from(e: MpscSendError<T>) -> Self {
         Error::MpscSend(e.to_string())
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl From<Error> for interceptor::Error {
     fn from(e: Error) -> Self {
         // this is a bit lol, but we do preserve the stack trace
@@ -434,6 +496,7 @@ impl From<Error> for interceptor::Error {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl PartialEq<ice::Error> for Error {
     fn eq(&self, other: &ice::Error) -> bool {
         if let Error::Ice(e) = self {
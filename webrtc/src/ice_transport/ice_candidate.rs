@@ -1,14 +1,16 @@
 use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
 use std::sync::Arc;
 
-use ice::candidate::candidate_base::CandidateBaseConfig;
-use ice::candidate::candidate_host::CandidateHostConfig;
-use ice::candidate::candidate_peer_reflexive::CandidatePeerReflexiveConfig;
-use ice::candidate::candidate_relay::CandidateRelayConfig;
-use ice::candidate::candidate_server_reflexive::CandidateServerReflexiveConfig;
-use ice::candidate::Candidate;
+#[cfg(not(target_arch = "wasm32"))]
+use ice::candidate::{
+    candidate_base::CandidateBaseConfig, candidate_host::CandidateHostConfig,
+    candidate_peer_reflexive::CandidatePeerReflexiveConfig, candidate_relay::CandidateRelayConfig,
+    candidate_server_reflexive::CandidateServerReflexiveConfig, Candidate,
+};
 use serde::{Deserialize, Serialize};
 
+#[cfg(not(target_arch = "wasm32"))]
 use crate::error::{Error, Result};
 use crate::ice_transport::ice_candidate_type::RTCIceCandidateType;
 use crate::ice_transport::ice_protocol::RTCIceProtocol;
@@ -30,12 +32,14 @@ pub struct RTCIceCandidate {
 }
 
 /// Conversion for ice_candidates
+#[cfg(not(target_arch = "wasm32"))]
 pub(crate) fn rtc_ice_candidates_from_ice_candidates(
     ice_candidates: &[Arc<dyn Candidate + Send + Sync>],
 ) -> Vec<RTCIceCandidate> {
     ice_candidates.iter().map(|c| c.into()).collect()
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl From<&Arc<dyn Candidate + Send + Sync>> for RTCIceCandidate {
     fn from(c: &Arc<dyn Candidate + Send + Sync>) -> Self {
         let typ: RTCIceCandidateType = c.candidate_type().into();
@@ -62,6 +66,7 @@ impl From<&Arc<dyn Candidate + Send + Sync>> for RTCIceCandidate {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl RTCIceCandidate {
     pub(crate) fn to_ice(&self) -> Result<impl Candidate> {
         let candidate_id = self.stats_id.clone();
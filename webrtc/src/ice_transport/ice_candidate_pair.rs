@@ -1,6 +1,8 @@
 use crate::ice_transport::ice_candidate::*;
+use crate::ice_transport::ice_candidate_type::RTCIceCandidateType;
 
 use std::fmt;
+use std::net::IpAddr;
 
 /// ICECandidatePair represents an ICE Candidate pair
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
@@ -17,18 +19,101 @@ impl fmt::Display for RTCIceCandidatePair {
 }
 
 impl RTCIceCandidatePair {
-    fn stats_id(local_id: &str, remote_id: &str) -> String {
+    fn gen_stats_id(local_id: &str, remote_id: &str) -> String {
         format!("{}-{}", local_id, remote_id)
     }
 
     /// returns an initialized ICECandidatePair
     /// for the given pair of ICECandidate instances
     pub fn new(local: RTCIceCandidate, remote: RTCIceCandidate) -> Self {
-        let stats_id = Self::stats_id(&local.stats_id, &remote.stats_id);
+        let stats_id = Self::gen_stats_id(&local.stats_id, &remote.stats_id);
         RTCIceCandidatePair {
             stats_id,
             local,
             remote,
         }
     }
+
+    /// stats_id returns the ID of the pair in the stats, made of the IDs of its candidates.
+    pub fn stats_id(&self) -> &str {
+        &self.stats_id
+    }
+
+    /// local returns the local candidate of the pair.
+    pub fn local(&self) -> &RTCIceCandidate {
+        &self.local
+    }
+
+    /// remote returns the remote candidate of the pair.
+    pub fn remote(&self) -> &RTCIceCandidate {
+        &self.remote
+    }
+
+    /// is_relayed returns true if the packets of the pair go through a TURN server, on
+    /// either side.
+    pub fn is_relayed(&self) -> bool {
+        self.local.typ == RTCIceCandidateType::Relay
+            || self.remote.typ == RTCIceCandidateType::Relay
+    }
+
+    /// is_ipv6 returns true if the packets of the pair are sent over IPv6. The addresses
+    /// of both candidates are of the same family, but one of them may be a mDNS hostname.
+    pub fn is_ipv6(&self) -> bool {
+        [&self.local.address, &self.remote.address]
+            .iter()
+            .find_map(|address| address.parse::<IpAddr>().ok())
+            .map_or(false, |ip| ip.is_ipv6())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ice_candidate_pair_path() {
+        let candidate = |typ: RTCIceCandidateType, address: &str| RTCIceCandidate {
+            stats_id: address.to_owned(),
+            address: address.to_owned(),
+            typ,
+            ..Default::default()
+        };
+
+        let tests = vec![
+            (
+                candidate(RTCIceCandidateType::Host, "192.168.1.2"),
+                candidate(RTCIceCandidateType::Srflx, "1.2.3.4"),
+                false,
+                false,
+            ),
+            (
+                candidate(RTCIceCandidateType::Relay, "1.2.3.4"),
+                candidate(RTCIceCandidateType::Host, "5.6.7.8"),
+                true,
+                false,
+            ),
+            (
+                candidate(RTCIceCandidateType::Host, "fe80::1"),
+                candidate(RTCIceCandidateType::Relay, "2001:db8::1"),
+                true,
+                true,
+            ),
+            (
+                candidate(RTCIceCandidateType::Host, "5e1c5a31-bd7c.local"),
+                candidate(RTCIceCandidateType::Prflx, "2001:db8::1"),
+                false,
+                true,
+            ),
+        ];
+
+        for (local, remote, is_relayed, is_ipv6) in tests {
+            let pair = RTCIceCandidatePair::new(local, remote);
+            assert_eq!(pair.is_relayed(), is_relayed, "{}", pair);
+            assert_eq!(pair.is_ipv6(), is_ipv6, "{}", pair);
+            assert_eq!(
+                pair.stats_id(),
+                format!("{}-{}", pair.local().address, pair.remote().address)
+            );
+        }
+    }
 }
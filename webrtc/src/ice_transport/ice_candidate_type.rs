@@ -1,3 +1,4 @@
+#[cfg(not(target_arch = "wasm32"))]
 use ice::candidate::CandidateType;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -62,6 +63,7 @@ impl From<&str> for RTCIceCandidateType {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl From<CandidateType> for RTCIceCandidateType {
     fn from(candidate_type: CandidateType) -> Self {
         match candidate_type {
@@ -1,24 +1,27 @@
 use crate::api::setting_engine::SettingEngine;
-use crate::error::{Error, Result};
+use crate::error::{flatten_errs, Error, Result};
 use crate::ice_transport::ice_candidate::*;
 use crate::ice_transport::ice_candidate_type::RTCIceCandidateType;
 use crate::ice_transport::ice_gatherer_state::RTCIceGathererState;
 use crate::ice_transport::ice_parameters::RTCIceParameters;
+use crate::ice_transport::ice_role::RTCIceRole;
 use crate::ice_transport::ice_server::RTCIceServer;
 use crate::peer_connection::policy::ice_transport_policy::RTCIceTransportPolicy;
 use crate::stats::stats_collector::StatsCollector;
 use crate::stats::SourceStatsType::*;
 use crate::stats::{ICECandidatePairStats, StatsReportType};
 
-use ice::agent::Agent;
-use ice::candidate::{Candidate, CandidateType};
+use ice::agent::agent_config::AgentConfig;
+use ice::agent::{Agent, OnCandidateHdlrFn};
+use ice::candidate::{Candidate, CandidateType, COMPONENT_RTCP};
+use ice::udp_network::UDPNetwork;
 use ice::url::Url;
 
 use arc_swap::ArcSwapOption;
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -56,6 +59,12 @@ pub struct RTCIceGatherer {
 
     pub(crate) state: Arc<AtomicU8>, //ICEGathererState,
     pub(crate) agent: Mutex<Option<Arc<ice::agent::Agent>>>,
+    /// rtcp_agent gathers the candidates of the RTCP component, when RTCP isn't multiplexed
+    /// with RTP.
+    pub(crate) rtcp_agent: Mutex<Option<Arc<ice::agent::Agent>>>,
+    /// gathering counts the agents which haven't completed gathering yet.
+    gathering: Arc<AtomicUsize>,
+    rtcp_gathering: Arc<AtomicBool>,
 
     pub(crate) on_local_candidate_handler: Arc<ArcSwapOption<Mutex<OnLocalCandidateHdlrFn>>>,
     pub(crate) on_state_change_handler: Arc<ArcSwapOption<Mutex<OnICEGathererStateChangeHdlrFn>>>,
@@ -90,6 +99,72 @@ impl RTCIceGatherer {
             return Ok(());
         }
 
+        *agent = Some(Arc::new(ice::agent::Agent::new(self.agent_config()).await?));
+
+        Ok(())
+    }
+
+    /// create_rtcp_agent creates the agent of the RTCP component, with the credentials of
+    /// the RTP agent, so that RTCP may use candidates of its own if the remote peer doesn't
+    /// multiplex it with RTP. It has no effect once the candidates are gathered.
+    pub(crate) async fn create_rtcp_agent(&self) -> Result<()> {
+        self.create_agent().await?;
+
+        let mut rtcp_agent = self.rtcp_agent.lock().await;
+        if rtcp_agent.is_some() || self.state() != RTCIceGathererState::New {
+            return Ok(());
+        }
+
+        // A mux tells the agents apart by their username fragment, which the components share
+        if matches!(self.setting_engine.udp_network, UDPNetwork::Muxed(_))
+            || self.setting_engine.tcp_mux.is_some()
+        {
+            log::warn!("RTCP can't use candidates of its own with a UDP or TCP mux");
+            return Ok(());
+        }
+
+        let (local_ufrag, local_pwd) = match self.get_agent().await {
+            Some(agent) => agent.get_local_user_credentials().await,
+            None => return Err(Error::ErrICEAgentNotExist),
+        };
+        let config = AgentConfig {
+            component: COMPONENT_RTCP,
+            local_ufrag,
+            local_pwd,
+            ..self.agent_config()
+        };
+        *rtcp_agent = Some(Arc::new(ice::agent::Agent::new(config).await?));
+
+        Ok(())
+    }
+
+    /// close_rtcp_agent closes the agent of the RTCP component, once the remote peer
+    /// multiplexes RTCP with RTP.
+    pub(crate) async fn close_rtcp_agent(&self) -> Result<()> {
+        let agent = {
+            let mut rtcp_agent = self.rtcp_agent.lock().await;
+            rtcp_agent.take()
+        };
+
+        if let Some(agent) = agent {
+            // The candidates of the RTP agent may complete the gathering on their own
+            if self.rtcp_gathering.swap(false, Ordering::SeqCst) {
+                RTCIceGatherer::do_agent_gathering_complete(
+                    &self.gathering,
+                    &self.state,
+                    &self.on_state_change_handler,
+                    &self.on_gathering_complete_handler,
+                    &self.on_local_candidate_handler,
+                )
+                .await;
+            }
+            agent.close().await?;
+        }
+
+        Ok(())
+    }
+
+    fn agent_config(&self) -> AgentConfig {
         let mut candidate_types = vec![];
         if self.setting_engine.candidates.ice_lite {
             candidate_types.push(ice::candidate::CandidateType::Host);
@@ -106,6 +181,7 @@ impl RTCIceGatherer {
         let mut mdns_mode = self.setting_engine.candidates.multicast_dns_mode;
         if mdns_mode != ice::mdns::MulticastDnsMode::Disabled
             && mdns_mode != ice::mdns::MulticastDnsMode::QueryAndGather
+            && mdns_mode != ice::mdns::MulticastDnsMode::GatherOnly
         {
             // If enum is in state we don't recognized default to MulticastDNSModeQueryOnly
             mdns_mode = ice::mdns::MulticastDnsMode::QueryOnly;
@@ -114,6 +190,8 @@ impl RTCIceGatherer {
         let mut config = ice::agent::agent_config::AgentConfig {
             udp_network: self.setting_engine.udp_network.clone(),
             lite: self.setting_engine.candidates.ice_lite,
+            fixed_role: self.setting_engine.candidates.ice_role != RTCIceRole::Unspecified,
+            nomination_mode: self.setting_engine.candidates.ice_nomination_mode,
             urls: self.validated_servers.clone(),
             disconnected_timeout: self.setting_engine.timeout.ice_disconnected_timeout,
             failed_timeout: self.setting_engine.timeout.ice_failed_timeout,
@@ -123,8 +201,13 @@ impl RTCIceGatherer {
             srflx_acceptance_min_wait: self.setting_engine.timeout.ice_srflx_acceptance_min_wait,
             prflx_acceptance_min_wait: self.setting_engine.timeout.ice_prflx_acceptance_min_wait,
             relay_acceptance_min_wait: self.setting_engine.timeout.ice_relay_acceptance_min_wait,
+            regather_interval: self.setting_engine.timeout.ice_regather_interval,
+            regather_max_interval: self.setting_engine.timeout.ice_regather_max_interval,
             interface_filter: self.setting_engine.candidates.interface_filter.clone(),
             ip_filter: self.setting_engine.candidates.ip_filter.clone(),
+            type_preferences: self.setting_engine.candidates.type_preferences.clone(),
+            local_preference_fn: self.setting_engine.candidates.local_preference_fn.clone(),
+            foundation_fn: self.setting_engine.candidates.foundation_fn.clone(),
             nat_1to1_ips: self.setting_engine.candidates.nat_1to1_ips.clone(),
             nat_1to1_ip_candidate_type: nat_1to1_cand_type,
             net: self.setting_engine.vnet.clone(),
@@ -134,9 +217,13 @@ impl RTCIceGatherer {
                 .candidates
                 .multicast_dns_host_name
                 .clone(),
+            multicast_dns_query_timeout: self.setting_engine.candidates.multicast_dns_query_timeout,
+            multicast_dns_conn: self.setting_engine.candidates.multicast_dns_conn.clone(),
             local_ufrag: self.setting_engine.candidates.username_fragment.clone(),
             local_pwd: self.setting_engine.candidates.password.clone(),
-            //TODO: TCPMux:                 self.setting_engine.iceTCPMux,
+            tcp_mux: self.setting_engine.tcp_mux.clone(),
+            socket_factory: self.setting_engine.socket_factory.clone(),
+            resolver: self.setting_engine.resolver.clone(),
             //TODO: ProxyDialer:            self.setting_engine.iceProxyDialer,
             ..Default::default()
         };
@@ -150,9 +237,7 @@ impl RTCIceGatherer {
 
         config.network_types.extend(requested_network_types);
 
-        *agent = Some(Arc::new(ice::agent::Agent::new(config).await?));
-
-        Ok(())
+        config
     }
 
     /// Gather ICE candidates.
@@ -161,54 +246,94 @@ impl RTCIceGatherer {
         self.set_state(RTCIceGathererState::Gathering).await;
 
         if let Some(agent) = self.get_agent().await {
-            let state = Arc::clone(&self.state);
-            let on_local_candidate_handler = Arc::clone(&self.on_local_candidate_handler);
-            let on_state_change_handler = Arc::clone(&self.on_state_change_handler);
-            let on_gathering_complete_handler = Arc::clone(&self.on_gathering_complete_handler);
-
-            agent.on_candidate(Box::new(
-                move |candidate: Option<Arc<dyn Candidate + Send + Sync>>| {
-                    let state_clone = Arc::clone(&state);
-                    let on_local_candidate_handler_clone = Arc::clone(&on_local_candidate_handler);
-                    let on_state_change_handler_clone = Arc::clone(&on_state_change_handler);
-                    let on_gathering_complete_handler_clone =
-                        Arc::clone(&on_gathering_complete_handler);
-
-                    Box::pin(async move {
-                        if let Some(cand) = candidate {
-                            if let Some(handler) = &*on_local_candidate_handler_clone.load() {
-                                let mut f = handler.lock().await;
-                                f(Some(RTCIceCandidate::from(&cand))).await;
-                            }
-                        } else {
-                            state_clone
-                                .store(RTCIceGathererState::Complete as u8, Ordering::SeqCst);
-
-                            if let Some(handler) = &*on_state_change_handler_clone.load() {
-                                let mut f = handler.lock().await;
-                                f(RTCIceGathererState::Complete).await;
-                            }
-
-                            if let Some(handler) = &*on_gathering_complete_handler_clone.load() {
-                                let mut f = handler.lock().await;
-                                f().await;
-                            }
-
-                            if let Some(handler) = &*on_local_candidate_handler_clone.load() {
-                                let mut f = handler.lock().await;
-                                f(None).await;
-                            }
-                        }
-                    })
-                },
-            ));
+            let rtcp_agent = self.get_rtcp_agent().await;
+            self.gathering
+                .store(1 + rtcp_agent.is_some() as usize, Ordering::SeqCst);
+            self.rtcp_gathering
+                .store(rtcp_agent.is_some(), Ordering::SeqCst);
 
+            agent.on_candidate(self.candidate_handler(None));
             agent.gather_candidates()?;
+
+            if let Some(rtcp_agent) = rtcp_agent {
+                rtcp_agent
+                    .on_candidate(self.candidate_handler(Some(Arc::clone(&self.rtcp_gathering))));
+                rtcp_agent.gather_candidates()?;
+            }
         }
 
         Ok(())
     }
 
+    /// candidate_handler forwards the candidates gathered by an agent. The gathering is
+    /// complete once all the agents are done, an agent which may be closed meanwhile tells
+    /// with the flag whether it is still gathering.
+    fn candidate_handler(&self, gathering_flag: Option<Arc<AtomicBool>>) -> OnCandidateHdlrFn {
+        let gathering = Arc::clone(&self.gathering);
+        let state = Arc::clone(&self.state);
+        let on_local_candidate_handler = Arc::clone(&self.on_local_candidate_handler);
+        let on_state_change_handler = Arc::clone(&self.on_state_change_handler);
+        let on_gathering_complete_handler = Arc::clone(&self.on_gathering_complete_handler);
+
+        Box::new(move |candidate: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let gathering_clone = Arc::clone(&gathering);
+            let state_clone = Arc::clone(&state);
+            let on_local_candidate_handler_clone = Arc::clone(&on_local_candidate_handler);
+            let on_state_change_handler_clone = Arc::clone(&on_state_change_handler);
+            let on_gathering_complete_handler_clone = Arc::clone(&on_gathering_complete_handler);
+            let gathering_flag_clone = gathering_flag.clone();
+
+            Box::pin(async move {
+                if let Some(cand) = candidate {
+                    if let Some(handler) = &*on_local_candidate_handler_clone.load() {
+                        let mut f = handler.lock().await;
+                        f(Some(RTCIceCandidate::from(&cand))).await;
+                    }
+                } else if gathering_flag_clone
+                    .map_or(true, |flag| flag.swap(false, Ordering::SeqCst))
+                {
+                    RTCIceGatherer::do_agent_gathering_complete(
+                        &gathering_clone,
+                        &state_clone,
+                        &on_state_change_handler_clone,
+                        &on_gathering_complete_handler_clone,
+                        &on_local_candidate_handler_clone,
+                    )
+                    .await;
+                }
+            })
+        })
+    }
+
+    async fn do_agent_gathering_complete(
+        gathering: &AtomicUsize,
+        state: &AtomicU8,
+        on_state_change_handler: &Arc<ArcSwapOption<Mutex<OnICEGathererStateChangeHdlrFn>>>,
+        on_gathering_complete_handler: &Arc<ArcSwapOption<Mutex<OnGatheringCompleteHdlrFn>>>,
+        on_local_candidate_handler: &Arc<ArcSwapOption<Mutex<OnLocalCandidateHdlrFn>>>,
+    ) {
+        if gathering.fetch_sub(1, Ordering::SeqCst) != 1 {
+            return;
+        }
+
+        state.store(RTCIceGathererState::Complete as u8, Ordering::SeqCst);
+
+        if let Some(handler) = &*on_state_change_handler.load() {
+            let mut f = handler.lock().await;
+            f(RTCIceGathererState::Complete).await;
+        }
+
+        if let Some(handler) = &*on_gathering_complete_handler.load() {
+            let mut f = handler.lock().await;
+            f().await;
+        }
+
+        if let Some(handler) = &*on_local_candidate_handler.load() {
+            let mut f = handler.lock().await;
+            f(None).await;
+        }
+    }
+
     /// Close prunes all local candidates, and closes the ports.
     pub async fn close(&self) -> Result<()> {
         self.set_state(RTCIceGathererState::Closed).await;
@@ -217,12 +342,19 @@ impl RTCIceGatherer {
             let mut agent_opt = self.agent.lock().await;
             agent_opt.take()
         };
+        let rtcp_agent = {
+            let mut agent_opt = self.rtcp_agent.lock().await;
+            agent_opt.take()
+        };
 
-        if let Some(agent) = agent {
-            agent.close().await?;
+        let mut errs: Vec<Error> = vec![];
+        for agent in agent.into_iter().chain(rtcp_agent) {
+            if let Err(err) = agent.close().await {
+                errs.push(err.into());
+            }
         }
 
-        Ok(())
+        flatten_errs(errs)
     }
 
     /// get_local_parameters returns the ICE parameters of the ICEGatherer.
@@ -238,7 +370,7 @@ impl RTCIceGatherer {
         Ok(RTCIceParameters {
             username_fragment: frag,
             password: pwd,
-            ice_lite: false,
+            ice_lite: self.setting_engine.candidates.ice_lite,
         })
     }
 
@@ -246,11 +378,14 @@ impl RTCIceGatherer {
     pub async fn get_local_candidates(&self) -> Result<Vec<RTCIceCandidate>> {
         self.create_agent().await?;
 
-        let ice_candidates = if let Some(agent) = self.get_agent().await {
+        let mut ice_candidates = if let Some(agent) = self.get_agent().await {
             agent.get_local_candidates().await?
         } else {
             return Err(Error::ErrICEAgentNotExist);
         };
+        if let Some(rtcp_agent) = self.get_rtcp_agent().await {
+            ice_candidates.extend(rtcp_agent.get_local_candidates().await?);
+        }
 
         Ok(rtc_ice_candidates_from_ice_candidates(&ice_candidates))
     }
@@ -293,6 +428,11 @@ impl RTCIceGatherer {
         agent.clone()
     }
 
+    pub(crate) async fn get_rtcp_agent(&self) -> Option<Arc<Agent>> {
+        let rtcp_agent = self.rtcp_agent.lock().await;
+        rtcp_agent.clone()
+    }
+
     pub(crate) async fn collect_stats(&self, collector: &StatsCollector) {
         if let Some(agent) = self.get_agent().await {
             let mut reports = HashMap::new();
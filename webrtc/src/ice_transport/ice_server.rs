@@ -1,3 +1,4 @@
+#[cfg(not(target_arch = "wasm32"))]
 use crate::error::{Error, Result};
 use crate::ice_transport::ice_credential_type::RTCIceCredentialType;
 
@@ -11,6 +12,7 @@ pub struct RTCIceServer {
     pub credential_type: RTCIceCredentialType,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl RTCIceServer {
     pub(crate) fn parse_url(&self, url_str: &str) -> Result<ice::url::Url> {
         Ok(ice::url::Url::parse_url(url_str)?)
@@ -1,8 +1,11 @@
 use super::*;
 use crate::api::media_engine::MediaEngine;
+use crate::api::setting_engine::SettingEngine;
 use crate::api::APIBuilder;
 use crate::error::Result;
+use crate::ice_transport::ice_candidate_type::RTCIceCandidateType;
 use crate::ice_transport::ice_connection_state::RTCIceConnectionState;
+use crate::peer_connection::configuration::RTCConfiguration;
 use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
 use crate::peer_connection::peer_connection_test::{
     close_pair_now, new_pair, signal_pair, until_connection_state,
@@ -34,13 +37,18 @@ async fn test_ice_transport_on_selected_candidate_pair_change() -> Result<()> {
 
     let sender_called_candidate_change = Arc::new(AtomicU32::new(0));
     let sender_called_candidate_change2 = Arc::clone(&sender_called_candidate_change);
+    let selected_pair = Arc::new(Mutex::new(None));
+    let selected_pair2 = Arc::clone(&selected_pair);
     pc_offer
         .sctp()
         .transport()
         .ice_transport()
-        .on_selected_candidate_pair_change(Box::new(move |_: RTCIceCandidatePair| {
+        .on_selected_candidate_pair_change(Box::new(move |pair: RTCIceCandidatePair| {
             sender_called_candidate_change2.store(1, Ordering::SeqCst);
-            Box::pin(async {})
+            let selected_pair3 = Arc::clone(&selected_pair2);
+            Box::pin(async move {
+                *selected_pair3.lock().await = Some(pair);
+            })
         }));
 
     signal_pair(&mut pc_offer, &mut pc_answer).await?;
@@ -51,6 +59,15 @@ async fn test_ice_transport_on_selected_candidate_pair_change() -> Result<()> {
         1,
         "Sender ICETransport OnSelectedCandidateChange was never called"
     );
+    assert_eq!(
+        *selected_pair.lock().await,
+        pc_offer
+            .sctp()
+            .transport()
+            .ice_transport()
+            .get_selected_candidate_pair()
+            .await
+    );
 
     close_pair_now(&pc_offer, &pc_answer).await;
 
@@ -115,6 +132,77 @@ async fn test_ice_transport_get_selected_candidate_pair() -> Result<()> {
         .await;
     assert!(answerer_selected_pair.is_some());
 
+    let (offerer_pair, answerer_pair) = (
+        offerer_selected_pair.unwrap(),
+        answerer_selected_pair.unwrap(),
+    );
+    assert_eq!(
+        offerer_pair.stats_id(),
+        format!(
+            "{}-{}",
+            offerer_pair.local().stats_id,
+            offerer_pair.remote().stats_id
+        )
+    );
+    assert_eq!(offerer_pair.local().port, answerer_pair.remote().port);
+    assert_eq!(offerer_pair.remote().port, answerer_pair.local().port);
+    assert_eq!(offerer_pair.local().typ, RTCIceCandidateType::Host);
+    assert!(!offerer_pair.is_relayed());
+    assert!(!answerer_pair.is_relayed());
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ice_transport_lite_answerer() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let mut lite_m = MediaEngine::default();
+    lite_m.register_default_codecs()?;
+    let mut lite_s = SettingEngine::default();
+    lite_s.set_lite(true);
+    let lite_api = APIBuilder::new()
+        .with_media_engine(lite_m)
+        .with_setting_engine(lite_s)
+        .build();
+
+    let mut offerer = api.new_peer_connection(RTCConfiguration::default()).await?;
+    let mut answerer = lite_api
+        .new_peer_connection(RTCConfiguration::default())
+        .await?;
+
+    let peer_connection_connected = WaitGroup::new();
+    until_connection_state(
+        &mut offerer,
+        &peer_connection_connected,
+        RTCPeerConnectionState::Connected,
+    )
+    .await;
+    until_connection_state(
+        &mut answerer,
+        &peer_connection_connected,
+        RTCPeerConnectionState::Connected,
+    )
+    .await;
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+
+    peer_connection_connected.wait().await;
+
+    assert_eq!(
+        offerer.sctp().transport().ice_transport().role().await,
+        RTCIceRole::Controlling
+    );
+    assert_eq!(
+        answerer.sctp().transport().ice_transport().role().await,
+        RTCIceRole::Controlled,
+        "a lite agent must always be controlled by a full agent"
+    );
+
     close_pair_now(&offerer, &answerer).await;
 
     Ok(())
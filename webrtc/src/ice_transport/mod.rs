@@ -4,7 +4,9 @@ use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
 
 use arc_swap::ArcSwapOption;
-use ice::candidate::Candidate;
+use ice::agent::Agent;
+use ice::candidate::{Candidate, CandidatePair, COMPONENT_RTCP};
+use ice::control::Role;
 use ice::state::ConnectionState;
 use tokio::sync::{mpsc, Mutex};
 use util::Conn;
@@ -20,9 +22,10 @@ use crate::ice_transport::ice_transport_state::RTCIceTransportState;
 use crate::mux::endpoint::Endpoint;
 use crate::mux::mux_func::MatchFunc;
 use crate::mux::{Config, Mux};
+use crate::peer_connection::transport_state_change::RTCTransportStateReason;
 use crate::stats::stats_collector::StatsCollector;
-use crate::stats::ICETransportStats;
 use crate::stats::StatsReportType::Transport;
+use crate::stats::{ICETransportStats, ICE_TRANSPORT_STATS_ID};
 
 #[cfg(test)]
 mod ice_transport_test;
@@ -59,6 +62,9 @@ struct ICETransportInternal {
     conn: Option<Arc<dyn Conn + Send + Sync>>, //AgentConn
     mux: Option<Mux>,
     cancel_tx: Option<mpsc::Sender<()>>,
+    rtcp_conn: Option<Arc<dyn Conn + Send + Sync>>,
+    rtcp_mux: Option<Mux>,
+    rtcp_cancel_tx: Option<mpsc::Sender<()>>,
 }
 
 /// ICETransport allows an application access to information about the ICE
@@ -70,7 +76,8 @@ pub struct RTCIceTransport {
     on_selected_candidate_pair_change_handler:
         Arc<ArcSwapOption<Mutex<OnSelectedCandidatePairChangeHdlrFn>>>,
     state: Arc<AtomicU8>, // ICETransportState
-    internal: Mutex<ICETransportInternal>,
+    state_reason: Arc<ArcSwapOption<RTCTransportStateReason>>,
+    internal: Arc<Mutex<ICETransportInternal>>,
 }
 
 impl RTCIceTransport {
@@ -106,6 +113,7 @@ impl RTCIceTransport {
 
         if let Some(agent) = self.gatherer.get_agent().await {
             let state = Arc::clone(&self.state);
+            let state_reason = Arc::clone(&self.state_reason);
 
             let on_connection_state_change_handler =
                 Arc::clone(&self.on_connection_state_change_handler);
@@ -113,7 +121,8 @@ impl RTCIceTransport {
                 let s = RTCIceTransportState::from(ice_state);
                 let on_connection_state_change_handler_clone =
                     Arc::clone(&on_connection_state_change_handler);
-                state.store(s as u8, Ordering::SeqCst);
+                let prev = RTCIceTransportState::from(state.swap(s as u8, Ordering::SeqCst));
+                state_reason.store(state_change_reason(prev, s).map(Arc::new));
                 Box::pin(async move {
                     if let Some(handler) = &*on_connection_state_change_handler_clone.load() {
                         let mut f = handler.lock().await;
@@ -125,12 +134,11 @@ impl RTCIceTransport {
             let on_selected_candidate_pair_change_handler =
                 Arc::clone(&self.on_selected_candidate_pair_change_handler);
             agent.on_selected_candidate_pair_change(Box::new(
-                move |local: &Arc<dyn Candidate + Send + Sync>,
-                      remote: &Arc<dyn Candidate + Send + Sync>| {
+                move |_: Option<&Arc<CandidatePair>>, p: &Arc<CandidatePair>| {
                     let on_selected_candidate_pair_change_handler_clone =
                         Arc::clone(&on_selected_candidate_pair_change_handler);
-                    let local = RTCIceCandidate::from(local);
-                    let remote = RTCIceCandidate::from(remote);
+                    let local = RTCIceCandidate::from(&p.local);
+                    let remote = RTCIceCandidate::from(&p.remote);
                     Box::pin(async move {
                         if let Some(handler) =
                             &*on_selected_candidate_pair_change_handler_clone.load()
@@ -142,41 +150,60 @@ impl RTCIceTransport {
                 },
             ));
 
-            let role = if let Some(role) = role {
+            let internal = Arc::clone(&self.internal);
+            agent.on_role_change(Box::new(move |role: Role| {
+                let internal_clone = Arc::clone(&internal);
+                Box::pin(async move {
+                    let mut internal = internal_clone.lock().await;
+                    internal.role = match role {
+                        Role::Controlling => RTCIceRole::Controlling,
+                        Role::Controlled => RTCIceRole::Controlled,
+                        _ => internal.role,
+                    };
+                })
+            }));
+
+            // A role forced by the SettingEngine takes precedence. Otherwise, a lite agent is
+            // always controlled unless the remote agent is lite as well. RFC 8445 S6.1.1
+            let forced_role = self.gatherer.setting_engine.candidates.ice_role;
+            let role = if forced_role != RTCIceRole::Unspecified {
+                forced_role
+            } else if agent.is_lite() && !params.ice_lite {
+                RTCIceRole::Controlled
+            } else if let Some(role) = role {
                 role
             } else {
                 RTCIceRole::Controlled
             };
 
             let (cancel_tx, cancel_rx) = mpsc::channel(1);
+            let (rtcp_cancel_tx, rtcp_cancel_rx) = mpsc::channel(1);
             {
                 let mut internal = self.internal.lock().await;
                 internal.role = role;
                 internal.cancel_tx = Some(cancel_tx);
+                internal.rtcp_cancel_tx = Some(rtcp_cancel_tx);
             }
 
-            let conn: Arc<dyn Conn + Send + Sync> = match role {
-                RTCIceRole::Controlling => {
-                    agent
-                        .dial(
-                            cancel_rx,
-                            params.username_fragment.clone(),
-                            params.password.clone(),
-                        )
-                        .await?
-                }
-
-                RTCIceRole::Controlled => {
-                    agent
-                        .accept(
-                            cancel_rx,
-                            params.username_fragment.clone(),
-                            params.password.clone(),
-                        )
-                        .await?
+            // The RTCP component, if RTCP isn't multiplexed, is connected along with RTP
+            let (conn, rtcp_conn) = match self.gatherer.get_rtcp_agent().await {
+                Some(rtcp_agent) => {
+                    rtcp_agent.on_connection_state_change(Box::new(|state: ConnectionState| {
+                        log::debug!("ICE connection state of the RTCP component: {}", state);
+                        Box::pin(async {})
+                    }));
+
+                    let (conn, rtcp_conn) = futures::future::try_join(
+                        RTCIceTransport::connect(&agent, role, cancel_rx, params),
+                        RTCIceTransport::connect(&rtcp_agent, role, rtcp_cancel_rx, params),
+                    )
+                    .await?;
+                    (conn, Some(rtcp_conn))
                 }
-
-                _ => return Err(Error::ErrICERoleUnknown),
+                None => (
+                    RTCIceTransport::connect(&agent, role, cancel_rx, params).await?,
+                    None,
+                ),
             };
 
             let config = Config {
@@ -188,6 +215,13 @@ impl RTCIceTransport {
                 let mut internal = self.internal.lock().await;
                 internal.conn = Some(conn);
                 internal.mux = Some(Mux::new(config));
+                if let Some(rtcp_conn) = rtcp_conn {
+                    internal.rtcp_mux = Some(Mux::new(Config {
+                        conn: Arc::clone(&rtcp_conn),
+                        buffer_size: self.gatherer.setting_engine.get_receive_mtu(),
+                    }));
+                    internal.rtcp_conn = Some(rtcp_conn);
+                }
             }
 
             Ok(())
@@ -196,6 +230,40 @@ impl RTCIceTransport {
         }
     }
 
+    /// connect dials the remote agent, or accepts its connection, depending on the role.
+    async fn connect(
+        agent: &Agent,
+        role: RTCIceRole,
+        cancel_rx: mpsc::Receiver<()>,
+        params: &RTCIceParameters,
+    ) -> Result<Arc<dyn Conn + Send + Sync>> {
+        let conn: Arc<dyn Conn + Send + Sync> = match role {
+            RTCIceRole::Controlling => {
+                agent
+                    .dial(
+                        cancel_rx,
+                        params.username_fragment.clone(),
+                        params.password.clone(),
+                    )
+                    .await?
+            }
+
+            RTCIceRole::Controlled => {
+                agent
+                    .accept(
+                        cancel_rx,
+                        params.username_fragment.clone(),
+                        params.password.clone(),
+                    )
+                    .await?
+            }
+
+            _ => return Err(Error::ErrICERoleUnknown),
+        };
+
+        Ok(conn)
+    }
+
     /// restart is not exposed currently because ORTC has users create a whole new ICETransport
     /// so for now lets keep it private so we don't cause ORTC users to depend on non-standard APIs
     pub(crate) async fn restart(&self) -> Result<()> {
@@ -210,6 +278,12 @@ impl RTCIceTransport {
                     self.gatherer.setting_engine.candidates.password.clone(),
                 )
                 .await?;
+
+            // The components share the credentials of the RTP agent
+            if let Some(rtcp_agent) = self.gatherer.get_rtcp_agent().await {
+                let (ufrag, pwd) = agent.get_local_user_credentials().await;
+                rtcp_agent.restart(ufrag, pwd).await?;
+            }
         } else {
             return Err(Error::ErrICEAgentNotExist);
         }
@@ -218,16 +292,29 @@ impl RTCIceTransport {
 
     /// Stop irreversibly stops the ICETransport.
     pub async fn stop(&self) -> Result<()> {
+        self.state_reason
+            .store(Some(Arc::new(RTCTransportStateReason::LocalClose)));
         self.set_state(RTCIceTransportState::Closed);
 
         let mut errs: Vec<Error> = vec![];
         {
             let mut internal = self.internal.lock().await;
             internal.cancel_tx.take();
-            if let Some(mut mux) = internal.mux.take() {
+            internal.rtcp_cancel_tx.take();
+            for mut mux in internal
+                .mux
+                .take()
+                .into_iter()
+                .chain(internal.rtcp_mux.take())
+            {
                 mux.close().await;
             }
-            if let Some(conn) = internal.conn.take() {
+            for conn in internal
+                .conn
+                .take()
+                .into_iter()
+                .chain(internal.rtcp_conn.take())
+            {
                 if let Err(err) = conn.close().await {
                     errs.push(err.into());
                 }
@@ -265,15 +352,12 @@ impl RTCIceTransport {
     pub async fn set_remote_candidates(&self, remote_candidates: &[RTCIceCandidate]) -> Result<()> {
         self.ensure_gatherer().await?;
 
-        if let Some(agent) = self.gatherer.get_agent().await {
-            for rc in remote_candidates {
-                let c: Arc<dyn Candidate + Send + Sync> = Arc::new(rc.to_ice()?);
-                agent.add_remote_candidate(&c)?;
-            }
-            Ok(())
-        } else {
-            Err(Error::ErrICEAgentNotExist)
+        for rc in remote_candidates {
+            let agent = self.agent_for(rc).await.ok_or(Error::ErrICEAgentNotExist)?;
+            let c: Arc<dyn Candidate + Send + Sync> = Arc::new(rc.to_ice()?);
+            agent.add_remote_candidate(&c)?;
         }
+        Ok(())
     }
 
     /// adds a candidate associated with the remote ICETransport.
@@ -283,16 +367,25 @@ impl RTCIceTransport {
     ) -> Result<()> {
         self.ensure_gatherer().await?;
 
-        if let Some(agent) = self.gatherer.get_agent().await {
-            if let Some(r) = remote_candidate {
-                let c: Arc<dyn Candidate + Send + Sync> = Arc::new(r.to_ice()?);
-                agent.add_remote_candidate(&c)?;
-            }
+        if let Some(r) = remote_candidate {
+            let agent = self.agent_for(&r).await.ok_or(Error::ErrICEAgentNotExist)?;
+            let c: Arc<dyn Candidate + Send + Sync> = Arc::new(r.to_ice()?);
+            agent.add_remote_candidate(&c)?;
+        }
 
-            Ok(())
-        } else {
-            Err(Error::ErrICEAgentNotExist)
+        Ok(())
+    }
+
+    /// agent_for returns the agent which checks the pairs of a remote candidate. The RTCP
+    /// candidates are checked by the agent of the RTCP component if RTCP isn't multiplexed
+    /// with RTP, and by the RTP agent otherwise.
+    async fn agent_for(&self, remote_candidate: &RTCIceCandidate) -> Option<Arc<Agent>> {
+        if remote_candidate.component == COMPONENT_RTCP {
+            if let Some(rtcp_agent) = self.gatherer.get_rtcp_agent().await {
+                return Some(rtcp_agent);
+            }
         }
+        self.gatherer.get_agent().await
     }
 
     /// State returns the current ice transport state.
@@ -300,6 +393,12 @@ impl RTCIceTransport {
         RTCIceTransportState::from(self.state.load(Ordering::SeqCst))
     }
 
+    /// state_reason returns the reason of the current state if the ICETransport is
+    /// disconnected, failed or closed.
+    pub fn state_reason(&self) -> Option<RTCTransportStateReason> {
+        self.state_reason.load().as_deref().cloned()
+    }
+
     pub(crate) fn set_state(&self, s: RTCIceTransportState) {
         self.state.store(s as u8, Ordering::SeqCst)
     }
@@ -313,6 +412,17 @@ impl RTCIceTransport {
         }
     }
 
+    /// new_rtcp_endpoint returns an endpoint on the RTCP component, which is only connected
+    /// if RTCP isn't multiplexed with RTP.
+    pub(crate) async fn new_rtcp_endpoint(&self, f: MatchFunc) -> Option<Arc<Endpoint>> {
+        let internal = self.internal.lock().await;
+        if let Some(rtcp_mux) = &internal.rtcp_mux {
+            Some(rtcp_mux.new_endpoint(f).await)
+        } else {
+            None
+        }
+    }
+
     pub(crate) async fn ensure_gatherer(&self) -> Result<()> {
         if self.gatherer.get_agent().await.is_none() {
             self.gatherer.create_agent().await
@@ -323,9 +433,9 @@ impl RTCIceTransport {
 
     pub(crate) async fn collect_stats(&self, collector: &StatsCollector) {
         if let Some(agent) = self.gatherer.get_agent().await {
-            let stats = ICETransportStats::new("ice_transport".to_string(), agent);
+            let stats = ICETransportStats::new(ICE_TRANSPORT_STATS_ID.to_owned(), agent);
 
-            collector.insert("ice_transport".to_string(), Transport(stats));
+            collector.insert(ICE_TRANSPORT_STATS_ID.to_owned(), Transport(stats));
         }
     }
 
@@ -348,9 +458,35 @@ impl RTCIceTransport {
         new_pwd: String,
     ) -> Result<()> {
         if let Some(agent) = self.gatherer.get_agent().await {
+            if let Some(rtcp_agent) = self.gatherer.get_rtcp_agent().await {
+                rtcp_agent
+                    .set_remote_credentials(new_ufrag.clone(), new_pwd.clone())
+                    .await?;
+            }
             Ok(agent.set_remote_credentials(new_ufrag, new_pwd).await?)
         } else {
             Err(Error::ErrICEAgentNotExist)
         }
     }
 }
+
+/// state_change_reason tells why the ICE agent went from the state prev to the state next:
+/// it fails before it was connected if none of the candidate pairs succeeded, and it is
+/// disconnected, then fails, once it was connected if the remote peer stops answering the
+/// connectivity checks, which keep the consent to send on the selected pair.
+fn state_change_reason(
+    prev: RTCIceTransportState,
+    next: RTCIceTransportState,
+) -> Option<RTCTransportStateReason> {
+    match next {
+        RTCIceTransportState::Disconnected => Some(RTCTransportStateReason::ConsentExpired),
+        RTCIceTransportState::Failed => match prev {
+            RTCIceTransportState::Unspecified
+            | RTCIceTransportState::New
+            | RTCIceTransportState::Checking => Some(RTCTransportStateReason::AllPairsFailed),
+            _ => Some(RTCTransportStateReason::ConsentExpired),
+        },
+        RTCIceTransportState::Closed => Some(RTCTransportStateReason::LocalClose),
+        _ => None,
+    }
+}
@@ -2,35 +2,98 @@
 #![allow(dead_code)]
 
 // re-export sub-crates
+#[cfg(not(target_arch = "wasm32"))]
 pub use data;
+#[cfg(not(target_arch = "wasm32"))]
 pub use dtls;
+#[cfg(not(target_arch = "wasm32"))]
 pub use ice;
+#[cfg(not(target_arch = "wasm32"))]
 pub use interceptor;
+#[cfg(not(target_arch = "wasm32"))]
 pub use mdns;
+#[cfg(not(target_arch = "wasm32"))]
 pub use media;
+#[cfg(not(target_arch = "wasm32"))]
 pub use rtcp;
+#[cfg(not(target_arch = "wasm32"))]
 pub use rtp;
+#[cfg(not(target_arch = "wasm32"))]
 pub use sctp;
 pub use sdp;
+#[cfg(not(target_arch = "wasm32"))]
 pub use srtp;
+#[cfg(not(target_arch = "wasm32"))]
 pub use stun;
+#[cfg(not(target_arch = "wasm32"))]
 pub use turn;
+#[cfg(not(target_arch = "wasm32"))]
 pub use util;
 
+#[cfg(not(target_arch = "wasm32"))]
 pub mod api;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod data_channel;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod dtls_transport;
 pub mod error;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod ice_transport;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod mux;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod peer_connection;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod rtp_transceiver;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod sctp_transport;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod stats;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod track;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(all(feature = "whep", not(target_arch = "wasm32")))]
+pub mod whep;
+#[cfg(all(feature = "whip", not(target_arch = "wasm32")))]
+pub mod whip;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod whip_endpoint;
+
+// On wasm32 the connections are backed by the browser, only the types shared with the wasm
+// module are built.
+#[cfg(target_arch = "wasm32")]
+pub mod data_channel {
+    pub mod data_channel_init;
+    pub mod data_channel_message;
+    pub mod data_channel_state;
+}
+#[cfg(target_arch = "wasm32")]
+pub mod ice_transport {
+    pub mod ice_candidate;
+    pub mod ice_candidate_type;
+    pub mod ice_credential_type;
+    pub mod ice_protocol;
+    pub mod ice_server;
+}
+#[cfg(target_arch = "wasm32")]
+pub mod peer_connection {
+    pub mod configuration;
+    pub mod offer_answer_options;
+    pub mod peer_connection_state;
+    pub mod policy;
+    pub mod sdp {
+        pub mod sdp_type;
+        pub mod session_description;
+    }
+    pub mod signaling_state;
+}
 
 pub use error::Error;
 
+#[cfg(not(target_arch = "wasm32"))]
 #[macro_use]
 extern crate lazy_static;
 
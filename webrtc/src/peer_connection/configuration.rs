@@ -1,4 +1,5 @@
 use crate::ice_transport::ice_server::RTCIceServer;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::peer_connection::certificate::RTCCertificate;
 use crate::peer_connection::policy::bundle_policy::RTCBundlePolicy;
 use crate::peer_connection::policy::ice_transport_policy::RTCIceTransportPolicy;
@@ -43,6 +44,7 @@ pub struct RTCConfiguration {
     /// used for a given connection; how certificates are selected is outside
     /// the scope of this specification. If this value is absent, then a default
     /// set of certificates is generated for each PeerConnection instance.
+    #[cfg(not(target_arch = "wasm32"))]
     pub certificates: Vec<RTCCertificate>,
 
     /// icecandidate_pool_size describes the size of the prefetched ICE pool.
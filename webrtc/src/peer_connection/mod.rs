@@ -5,11 +5,13 @@ pub mod certificate;
 pub mod configuration;
 pub mod offer_answer_options;
 pub(crate) mod operation;
+pub mod peer_connection_event;
 mod peer_connection_internal;
 pub mod peer_connection_state;
 pub mod policy;
 pub mod sdp;
 pub mod signaling_state;
+pub mod transport_state_change;
 
 use crate::api::media_engine::MediaEngine;
 use crate::api::setting_engine::SettingEngine;
@@ -20,9 +22,7 @@ use crate::data_channel::data_channel_state::RTCDataChannelState;
 use crate::data_channel::RTCDataChannel;
 use crate::dtls_transport::dtls_fingerprint::RTCDtlsFingerprint;
 use crate::dtls_transport::dtls_parameters::DTLSParameters;
-use crate::dtls_transport::dtls_role::{
-    DTLSRole, DEFAULT_DTLS_ROLE_ANSWER, DEFAULT_DTLS_ROLE_OFFER,
-};
+use crate::dtls_transport::dtls_role::DTLSRole;
 use crate::dtls_transport::dtls_transport_state::RTCDtlsTransportState;
 use crate::dtls_transport::RTCDtlsTransport;
 use crate::error::{flatten_errs, Error, Result};
@@ -43,16 +43,22 @@ use crate::peer_connection::certificate::RTCCertificate;
 use crate::peer_connection::configuration::RTCConfiguration;
 use crate::peer_connection::offer_answer_options::{RTCAnswerOptions, RTCOfferOptions};
 use crate::peer_connection::operation::{Operation, Operations};
+use crate::peer_connection::peer_connection_event::{EventStream, RTCPeerConnectionEvent};
 use crate::peer_connection::peer_connection_state::{
     NegotiationNeededState, RTCPeerConnectionState,
 };
+use crate::peer_connection::policy::bundle_policy::RTCBundlePolicy;
+use crate::peer_connection::policy::rtcp_mux_policy::RTCRtcpMuxPolicy;
 use crate::peer_connection::sdp::sdp_type::RTCSdpType;
 use crate::peer_connection::sdp::session_description::RTCSessionDescription;
 use crate::peer_connection::sdp::*;
 use crate::peer_connection::signaling_state::{
     check_next_signaling_state, RTCSignalingState, StateChangeOp,
 };
-use crate::rtp_transceiver::rtp_codec::{RTCRtpHeaderExtensionCapability, RTPCodecType};
+use crate::peer_connection::transport_state_change::RTCTransportStateChange;
+use crate::rtp_transceiver::rtp_codec::{
+    RTCRtpCodecParameters, RTCRtpHeaderExtensionCapability, RTPCodecType,
+};
 use crate::rtp_transceiver::rtp_receiver::RTCRtpReceiver;
 use crate::rtp_transceiver::rtp_sender::RTCRtpSender;
 use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
@@ -63,7 +69,7 @@ use crate::rtp_transceiver::{RTCRtpTransceiverInit, SSRC};
 use crate::sctp_transport::sctp_transport_capabilities::SCTPTransportCapabilities;
 use crate::sctp_transport::sctp_transport_state::RTCSctpTransportState;
 use crate::sctp_transport::RTCSctpTransport;
-use crate::stats::StatsReport;
+use crate::stats::{RemoteInboundRTPStats, RemoteOutboundRTPStats, StatsReport, StatsReportType};
 use crate::track::track_local::track_local_static_sample::TrackLocalStaticSample;
 use crate::track::track_local::TrackLocal;
 use crate::track::track_remote::TrackRemote;
@@ -71,9 +77,11 @@ use crate::track::track_remote::TrackRemote;
 use ::ice::candidate::candidate_base::unmarshal_candidate;
 use ::ice::candidate::Candidate;
 use ::sdp::description::session::*;
+use ::sdp::msid::Msid;
 use ::sdp::util::ConnectionRole;
 use arc_swap::ArcSwapOption;
 use async_trait::async_trait;
+use interceptor::cc::{BandwidthEstimate, BandwidthEstimator};
 use interceptor::{stats, Attributes, Interceptor, RTCPWriter};
 use peer_connection_internal::*;
 use rand::{thread_rng, Rng};
@@ -82,9 +90,9 @@ use srtp::stream::Stream;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::{mpsc, Mutex};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, mpsc, Mutex};
 
 /// SIMULCAST_PROBE_COUNT is the amount of RTP Packets
 /// that handleUndeclaredSSRC will read and try to dispatch from
@@ -95,6 +103,10 @@ pub(crate) const SIMULCAST_PROBE_COUNT: usize = 10;
 /// If the total amount of incoming SSRCes exceeds this new requests will be ignored
 pub(crate) const SIMULCAST_MAX_PROBE_ROUTINES: u64 = 25;
 
+/// DEFAULT_CLOSE_TIMEOUT is how long close waits for the remote peer during the orderly
+/// close, unless SettingEngine::set_close_timeout is used.
+pub(crate) const DEFAULT_CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
 pub(crate) const MEDIA_SECTION_APPLICATION: &str = "application";
 
 const RUNES_ALPHA: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
@@ -149,6 +161,30 @@ pub type OnTrackHdlrFn = Box<
 pub type OnNegotiationNeededHdlrFn =
     Box<dyn (FnMut() -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
 
+pub type OnTransportStateChangeHdlrFn = Box<
+    dyn (FnMut(RTCTransportStateChange) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
+pub type OnBandwidthEstimateHdlrFn = Box<
+    dyn (FnMut(BandwidthEstimate) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
+pub type OnRemoteInboundRTPStatsHdlrFn = Box<
+    dyn (FnMut(RemoteInboundRTPStats) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
+pub type OnRemoteOutboundRTPStatsHdlrFn = Box<
+    dyn (FnMut(RemoteOutboundRTPStats) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
 #[derive(Clone)]
 struct StartTransportsParams {
     ice_transport: Arc<RTCIceTransport>,
@@ -165,6 +201,7 @@ struct CheckNegotiationNeededParams {
     rtp_transceivers: Arc<Mutex<Vec<Arc<RTCRtpTransceiver>>>>,
     current_local_description: Arc<Mutex<Option<RTCSessionDescription>>>,
     current_remote_description: Arc<Mutex<Option<RTCSessionDescription>>>,
+    ice_restart_requested: Arc<AtomicBool>,
 }
 
 #[derive(Clone)]
@@ -191,6 +228,11 @@ pub struct RTCPeerConnection {
 
     interceptor: Arc<dyn Interceptor + Send + Sync>,
 
+    bandwidth_estimator: Option<Arc<BandwidthEstimator>>,
+    on_bandwidth_estimate_handler: Arc<ArcSwapOption<Mutex<OnBandwidthEstimateHdlrFn>>>,
+    on_remote_inbound_rtp_stats_handler: Arc<ArcSwapOption<Mutex<OnRemoteInboundRTPStatsHdlrFn>>>,
+    on_remote_outbound_rtp_stats_handler: Arc<ArcSwapOption<Mutex<OnRemoteOutboundRTPStatsHdlrFn>>>,
+
     pub(crate) internal: Arc<PeerConnectionInternal>,
 }
 
@@ -221,18 +263,59 @@ impl RTCPeerConnection {
     pub(crate) async fn new(api: &API, mut configuration: RTCConfiguration) -> Result<Self> {
         RTCPeerConnection::init_configuration(&mut configuration)?;
 
+        let bandwidth_estimator = api
+            .setting_engine
+            .bandwidth_estimator
+            .as_ref()
+            .map(|builder| builder.build_estimator());
+
         let (interceptor, stats_interceptor): (Arc<dyn Interceptor + Send + Sync>, _) = {
             let mut chain = api.interceptor_registry.build_chain("")?;
             let stats_interceptor = stats::make_stats_interceptor("");
             chain.add(stats_interceptor.clone());
+            if let Some(bandwidth_estimator) = &bandwidth_estimator {
+                chain.add(bandwidth_estimator.clone());
+            }
 
             (Arc::new(chain), stats_interceptor)
         };
 
+        let on_bandwidth_estimate_handler: Arc<ArcSwapOption<Mutex<OnBandwidthEstimateHdlrFn>>> =
+            Arc::new(ArcSwapOption::empty());
+        if let Some(bandwidth_estimator) = &bandwidth_estimator {
+            // the estimates end when the interceptors are closed with the PeerConnection
+            let mut estimates = bandwidth_estimator.subscribe();
+            let handler = Arc::clone(&on_bandwidth_estimate_handler);
+            tokio::spawn(async move {
+                while estimates.changed().await.is_ok() {
+                    let estimate = *estimates.borrow();
+                    if let Some(handler) = &*handler.load() {
+                        let mut f = handler.lock().await;
+                        f(estimate).await;
+                    }
+                }
+            });
+        }
+
+        let reports = stats_interceptor.subscribe_reports();
+
         let weak_interceptor = Arc::downgrade(&interceptor);
         let (internal, configuration) =
             PeerConnectionInternal::new(api, weak_interceptor, stats_interceptor, configuration)
                 .await?;
+
+        let on_remote_inbound_rtp_stats_handler: Arc<
+            ArcSwapOption<Mutex<OnRemoteInboundRTPStatsHdlrFn>>,
+        > = Arc::new(ArcSwapOption::empty());
+        let on_remote_outbound_rtp_stats_handler: Arc<
+            ArcSwapOption<Mutex<OnRemoteOutboundRTPStatsHdlrFn>>,
+        > = Arc::new(ArcSwapOption::empty());
+        RTCPeerConnection::do_remote_rtp_stats(
+            reports,
+            Arc::downgrade(&internal),
+            Arc::clone(&on_remote_inbound_rtp_stats_handler),
+            Arc::clone(&on_remote_outbound_rtp_stats_handler),
+        );
         let internal_rtcp_writer = Arc::clone(&internal) as Arc<dyn RTCPWriter + Send + Sync>;
         let interceptor_rtcp_writer = interceptor.bind_rtcp_writer(internal_rtcp_writer).await;
 
@@ -249,6 +332,10 @@ impl RTCPeerConnection {
             ),
             interceptor,
             interceptor_rtcp_writer,
+            bandwidth_estimator,
+            on_bandwidth_estimate_handler,
+            on_remote_inbound_rtp_stats_handler,
+            on_remote_outbound_rtp_stats_handler,
             internal,
             configuration,
             idp_login_url: None,
@@ -310,7 +397,12 @@ impl RTCPeerConnection {
     }
 
     /// on_negotiation_needed sets an event handler which is invoked when
-    /// a change has occurred which requires session negotiation
+    /// a change has occurred which requires session negotiation.
+    /// As in browsers, the updates run on the operations chain: changes made in a row
+    /// invoke the handler once, only in the stable signaling state, and not at all if the
+    /// current descriptions already reflect them. The handler is not invoked again until
+    /// the negotiation completes.
+    /// <https://w3c.github.io/webrtc-pc/#updating-the-negotiation-needed-flag>
     pub fn on_negotiation_needed(&self, f: OnNegotiationNeededHdlrFn) {
         self.internal
             .on_negotiation_needed_handler
@@ -373,10 +465,11 @@ impl RTCPeerConnection {
     }
 
     async fn negotiation_needed_op(params: NegotiationNeededParams) -> bool {
-        // Don't run NegotiatedNeeded checks if on_negotiation_needed is not set
+        // Don't run NegotiatedNeeded checks if on_negotiation_needed is not set, but reset the
+        // state so that a handler set later is invoked by the next update
         let handler = &*params.on_negotiation_needed_handler.load();
         if handler.is_none() {
-            return false;
+            return RTCPeerConnection::after_negotiation_needed_op(params).await;
         }
 
         // https://www.w3.org/TR/webrtc/#updating-the-negotiation-needed-flag
@@ -426,6 +519,10 @@ impl RTCPeerConnection {
     async fn check_negotiation_needed(params: &CheckNegotiationNeededParams) -> bool {
         // To check if negotiation is needed for connection, perform the following checks:
         // Skip 1, 2 steps
+        // Step 4, restart_ice was called
+        if params.ice_restart_requested.load(Ordering::SeqCst) {
+            return true;
+        }
         // Step 3
         let current_local_description = {
             let current_local_description = params.current_local_description.lock().await;
@@ -463,10 +560,11 @@ impl RTCPeerConnection {
                     if let Some(m) = m {
                         // Step 5.3.1
                         if t.direction().has_send() {
-                            let dmsid = match m.attribute(ATTR_KEY_MSID).and_then(|o| o) {
-                                Some(m) => m,
-                                None => return true, // doesn't contain a single a=msid line
-                            };
+                            let dmsid =
+                                match m.msids().ok().and_then(|msids| msids.into_iter().next()) {
+                                    Some(m) => m,
+                                    None => return true, // doesn't contain a single a=msid line
+                                };
 
                             let sender = match t.sender().await {
                                 Some(s) => s.clone(),
@@ -494,7 +592,7 @@ impl RTCPeerConnection {
                             }
 
                             // different stream id
-                            if dmsid.split_whitespace().next() != Some(&stream_ids[0]) {
+                            if dmsid.stream_id != stream_ids[0] {
                                 return true;
                             }
                         }
@@ -549,9 +647,10 @@ impl RTCPeerConnection {
                 if t.stopped.load(Ordering::SeqCst) && !t.mid().await.is_empty() {
                     let current_remote_description = params.current_remote_description.lock().await;
                     if let Some(remote_desc) = &*current_remote_description {
-                        if get_by_mid(t.mid().await.as_str(), local_desc).is_some()
-                            || get_by_mid(t.mid().await.as_str(), remote_desc).is_some()
-                        {
+                        let associated = |desc| {
+                            get_by_mid(mid.as_str(), desc).map_or(false, |m| !is_rejected(m))
+                        };
+                        if associated(local_desc) || associated(remote_desc) {
                             return true;
                         }
                     }
@@ -645,6 +744,233 @@ impl RTCPeerConnection {
         }
     }
 
+    /// on_transport_state_change sets an event handler which is called when the state of
+    /// the ICE, DTLS or SCTP transport changes, with the reason of the change when the
+    /// transport is disconnected, closed or failed. It tells why the PeerConnectionState,
+    /// which aggregates the states of the transports, has changed.
+    pub fn on_transport_state_change(&self, f: OnTransportStateChangeHdlrFn) {
+        self.internal
+            .on_transport_state_change_handler
+            .store(Some(Arc::new(Mutex::new(f))));
+    }
+
+    async fn do_transport_state_change(
+        handler: &Arc<ArcSwapOption<Mutex<OnTransportStateChangeHdlrFn>>>,
+        change: RTCTransportStateChange,
+    ) {
+        log::info!("{}", change);
+        if let Some(handler) = &*handler.load() {
+            let mut f = handler.lock().await;
+            f(change).await;
+        }
+    }
+
+    /// on_bandwidth_estimate sets an event handler which is called with each new estimate of
+    /// the bandwidth available to send to the remote peer, so that the encoders and the
+    /// simulcast layers can be adapted to it, for instance with set_bandwidth_estimate of the
+    /// RTCRtpSenders. The handler is only called if a bandwidth estimator is set in the
+    /// SettingEngine.
+    pub fn on_bandwidth_estimate(&self, f: OnBandwidthEstimateHdlrFn) {
+        self.on_bandwidth_estimate_handler
+            .store(Some(Arc::new(Mutex::new(f))));
+    }
+
+    /// bandwidth_estimate returns the latest estimate of the bandwidth available to send to
+    /// the remote peer, or None if no bandwidth estimator is set in the SettingEngine.
+    pub fn bandwidth_estimate(&self) -> Option<BandwidthEstimate> {
+        self.bandwidth_estimator
+            .as_ref()
+            .map(|bandwidth_estimator| bandwidth_estimator.estimate())
+    }
+
+    /// on_remote_inbound_rtp_stats sets an event handler which is called with the
+    /// remote-inbound-rtp stats of an RTP stream sent to the remote peer, each time a
+    /// Receiver Report about it arrives: the loss, the jitter and the round trip time seen
+    /// by the remote peer. The reports are read along with the RTCP packets of the
+    /// RTCRtpSender, so read_rtcp has to be called on it.
+    pub fn on_remote_inbound_rtp_stats(&self, f: OnRemoteInboundRTPStatsHdlrFn) {
+        self.on_remote_inbound_rtp_stats_handler
+            .store(Some(Arc::new(Mutex::new(f))));
+    }
+
+    /// on_remote_outbound_rtp_stats sets an event handler which is called with the
+    /// remote-outbound-rtp stats of an RTP stream received from the remote peer, each time a
+    /// Sender Report about it arrives. The reports are read along with the RTCP packets of
+    /// the RTCRtpReceiver, so read_rtcp has to be called on it.
+    pub fn on_remote_outbound_rtp_stats(&self, f: OnRemoteOutboundRTPStatsHdlrFn) {
+        self.on_remote_outbound_rtp_stats_handler
+            .store(Some(Arc::new(Mutex::new(f))));
+    }
+
+    /// do_remote_rtp_stats calls the handlers of the remote RTP stats with the stats of the
+    /// streams the reports from the remote peer are about, until the PeerConnection is
+    /// dropped.
+    fn do_remote_rtp_stats(
+        mut reports: broadcast::Receiver<stats::RemoteReport>,
+        internal: Weak<PeerConnectionInternal>,
+        on_remote_inbound_rtp_stats_handler: Arc<
+            ArcSwapOption<Mutex<OnRemoteInboundRTPStatsHdlrFn>>,
+        >,
+        on_remote_outbound_rtp_stats_handler: Arc<
+            ArcSwapOption<Mutex<OnRemoteOutboundRTPStatsHdlrFn>>,
+        >,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                let report = match reports.recv().await {
+                    Ok(report) => report,
+                    // The stats of the latest reports are the ones worth reporting
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let have_handler = match report {
+                    stats::RemoteReport::ReceiverReport(_) => {
+                        on_remote_inbound_rtp_stats_handler.load().is_some()
+                    }
+                    stats::RemoteReport::SenderReport(_) => {
+                        on_remote_outbound_rtp_stats_handler.load().is_some()
+                    }
+                };
+                if !have_handler {
+                    continue;
+                }
+
+                let internal = match internal.upgrade() {
+                    Some(internal) => internal,
+                    None => break,
+                };
+                match internal.remote_rtp_stats(report).await {
+                    Some(StatsReportType::RemoteInboundRTP(stats)) => {
+                        if let Some(handler) = &*on_remote_inbound_rtp_stats_handler.load() {
+                            let mut f = handler.lock().await;
+                            f(stats).await;
+                        }
+                    }
+                    Some(StatsReportType::RemoteOutboundRTP(stats)) => {
+                        if let Some(handler) = &*on_remote_outbound_rtp_stats_handler.load() {
+                            let mut f = handler.lock().await;
+                            f(stats).await;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    /// events returns a Stream of all the events of the PeerConnection, as an alternative to
+    /// setting a handler for each of them. It replaces the eleven handlers listed by
+    /// EventStream, and setting one of them afterwards stops the delivery of the events of
+    /// that handler only, e.g. on_track stops the Track events.
+    pub fn events(&self) -> EventStream<RTCPeerConnectionEvent> {
+        let (tx, stream) = EventStream::channel();
+
+        let tx2 = tx.clone();
+        self.on_signaling_state_change(Box::new(move |s| {
+            let _ = tx2.send(RTCPeerConnectionEvent::SignalingStateChange(s));
+            Box::pin(async {})
+        }));
+        let tx2 = tx.clone();
+        self.on_ice_connection_state_change(Box::new(move |s| {
+            let _ = tx2.send(RTCPeerConnectionEvent::ICEConnectionStateChange(s));
+            Box::pin(async {})
+        }));
+        let tx2 = tx.clone();
+        self.on_peer_connection_state_change(Box::new(move |s| {
+            let _ = tx2.send(RTCPeerConnectionEvent::PeerConnectionStateChange(s));
+            Box::pin(async {})
+        }));
+        let tx2 = tx.clone();
+        self.on_ice_gathering_state_change(Box::new(move |s| {
+            let _ = tx2.send(RTCPeerConnectionEvent::ICEGatheringStateChange(s));
+            Box::pin(async {})
+        }));
+        let tx2 = tx.clone();
+        self.on_ice_candidate(Box::new(move |c| {
+            let _ = tx2.send(RTCPeerConnectionEvent::ICECandidate(c));
+            Box::pin(async {})
+        }));
+        let tx2 = tx.clone();
+        self.on_track(Box::new(move |t, r| {
+            if let (Some(t), Some(r)) = (t, r) {
+                let _ = tx2.send(RTCPeerConnectionEvent::Track(t, r));
+            }
+            Box::pin(async {})
+        }));
+        let tx2 = tx.clone();
+        self.on_transport_state_change(Box::new(move |c| {
+            let _ = tx2.send(RTCPeerConnectionEvent::TransportStateChange(c));
+            Box::pin(async {})
+        }));
+        let tx2 = tx.clone();
+        self.on_data_channel(Box::new(move |d| {
+            let _ = tx2.send(RTCPeerConnectionEvent::DataChannel(d));
+            Box::pin(async {})
+        }));
+        let tx2 = tx.clone();
+        self.on_remote_inbound_rtp_stats(Box::new(move |s| {
+            let _ = tx2.send(RTCPeerConnectionEvent::RemoteInboundRTPStats(s));
+            Box::pin(async {})
+        }));
+        let tx2 = tx.clone();
+        self.on_remote_outbound_rtp_stats(Box::new(move |s| {
+            let _ = tx2.send(RTCPeerConnectionEvent::RemoteOutboundRTPStats(s));
+            Box::pin(async {})
+        }));
+        self.on_negotiation_needed(Box::new(move || {
+            let _ = tx.send(RTCPeerConnectionEvent::NegotiationNeeded);
+            Box::pin(async {})
+        }));
+
+        stream
+    }
+
+    /// peer_connection_state_changes returns a Stream of the PeerConnectionState changes.
+    /// It replaces the handler set with on_peer_connection_state_change.
+    pub fn peer_connection_state_changes(&self) -> EventStream<RTCPeerConnectionState> {
+        let (tx, stream) = EventStream::channel();
+        self.on_peer_connection_state_change(Box::new(move |s| {
+            let _ = tx.send(s);
+            Box::pin(async {})
+        }));
+        stream
+    }
+
+    /// ice_candidates returns a Stream of the local ICE candidates, with None once the
+    /// gathering is finished. It replaces the handler set with on_ice_candidate.
+    pub fn ice_candidates(&self) -> EventStream<Option<RTCIceCandidate>> {
+        let (tx, stream) = EventStream::channel();
+        self.on_ice_candidate(Box::new(move |c| {
+            let _ = tx.send(c);
+            Box::pin(async {})
+        }));
+        stream
+    }
+
+    /// tracks returns a Stream of the remote tracks arriving from the remote peer, with
+    /// their receivers. It replaces the handler set with on_track.
+    pub fn tracks(&self) -> EventStream<(Arc<TrackRemote>, Arc<RTCRtpReceiver>)> {
+        let (tx, stream) = EventStream::channel();
+        self.on_track(Box::new(move |t, r| {
+            if let (Some(t), Some(r)) = (t, r) {
+                let _ = tx.send((t, r));
+            }
+            Box::pin(async {})
+        }));
+        stream
+    }
+
+    /// data_channels returns a Stream of the data channels opened by the remote peer.
+    /// It replaces the handler set with on_data_channel.
+    pub fn data_channels(&self) -> EventStream<Arc<RTCDataChannel>> {
+        let (tx, stream) = EventStream::channel();
+        self.on_data_channel(Box::new(move |d| {
+            let _ = tx.send(d);
+            Box::pin(async {})
+        }));
+        stream
+    }
+
     /*TODO: // set_configuration updates the configuration of this PeerConnection object.
     pub async fn set_configuration(&mut self, configuration: Configuration) -> Result<()> {
         //nolint:gocognit
@@ -725,6 +1051,23 @@ impl RTCPeerConnection {
         self.stats_id.as_str()
     }
 
+    /// restart_ice requests an ICE restart: the next offer created by create_offer gathers
+    /// candidates with new ICE credentials, as if RTCOfferOptions::ice_restart was set.
+    /// on_negotiation_needed fires so the application can start that negotiation.
+    /// <https://w3c.github.io/webrtc-pc/#dom-rtcpeerconnection-restartice>
+    pub async fn restart_ice(&self) -> Result<()> {
+        if self.internal.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed);
+        }
+
+        self.internal
+            .ice_restart_requested
+            .store(true, Ordering::SeqCst);
+        self.internal.trigger_negotiation_needed().await;
+
+        Ok(())
+    }
+
     /// create_offer starts the PeerConnection and generates the localDescription
     /// <https://w3c.github.io/webrtc-pc/#dom-rtcpeerconnection-createoffer>
     pub async fn create_offer(
@@ -738,11 +1081,15 @@ impl RTCPeerConnection {
             return Err(Error::ErrConnectionClosed);
         }
 
-        if let Some(options) = options {
-            if options.ice_restart {
-                self.internal.ice_transport.restart().await?;
-            }
+        // A restart requested before the first negotiation has no credentials to replace
+        let ice_restart_requested = self.internal.ice_restart_requested.load(Ordering::SeqCst)
+            && self.current_local_description().await.is_some();
+        if options.map_or(false, |options| options.ice_restart) || ice_restart_requested {
+            self.internal.ice_transport.restart().await?;
         }
+        self.internal
+            .ice_restart_requested
+            .store(false, Ordering::SeqCst);
 
         // This may be necessary to recompute if, for example, createOffer was called when only an
         // audio RTCRtpTransceiver was added to connection, but while performing the in-parallel
@@ -787,7 +1134,7 @@ impl RTCPeerConnection {
                 }
             }
             for t in &current_transceivers {
-                if !t.mid().await.is_empty() {
+                if !t.mid().await.is_empty() || t.stopped.load(Ordering::SeqCst) {
                     continue;
                 }
 
@@ -827,7 +1174,10 @@ impl RTCPeerConnection {
                         current_transceivers,
                         use_identity,
                         true, /*includeUnmatched */
-                        DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
+                        self.internal
+                            .setting_engine
+                            .offer_dtls_role()
+                            .to_connection_role(),
                     )
                     .await?
             };
@@ -884,7 +1234,7 @@ impl RTCPeerConnection {
                 // Any of the RTCIceTransports or RTCDtlsTransports are in the "disconnected"
                 // state and none of them are in the "failed" or "connecting" or "checking" state.
                 RTCPeerConnectionState::Disconnected
-            } else if ice_connection_state == RTCIceConnectionState::Connected && dtls_transport_state == RTCDtlsTransportState::Connected {
+            } else if ice_connection_state == RTCIceConnectionState::Connected && (dtls_transport_state == RTCDtlsTransportState::Connected || dtls_transport_state == RTCDtlsTransportState::Closed) {
                 // All RTCIceTransports and RTCDtlsTransports are in the "connected", "completed" or "closed"
                 // state and at least one of them is in the "connected" or "completed" state.
                 RTCPeerConnectionState::Connected
@@ -928,14 +1278,11 @@ impl RTCPeerConnection {
             return Err(Error::ErrIncorrectSignalingState);
         }
 
-        let mut connection_role = self
+        let connection_role = self
             .internal
             .setting_engine
-            .answering_dtls_role
+            .answer_dtls_role()
             .to_connection_role();
-        if connection_role == ConnectionRole::Unspecified {
-            connection_role = DEFAULT_DTLS_ROLE_ANSWER.to_connection_role();
-        }
 
         let local_transceivers = self.get_transceivers().await;
         let mut d = self
@@ -1060,9 +1407,12 @@ impl RTCPeerConnection {
                                 sd.sdp_type,
                             );
                             if next_state.is_ok() {
-                                let mut pending_local_description =
-                                    self.internal.pending_local_description.lock().await;
-                                *pending_local_description = None;
+                                {
+                                    let mut pending_local_description =
+                                        self.internal.pending_local_description.lock().await;
+                                    *pending_local_description = None;
+                                }
+                                self.internal.rollback_transceivers().await?;
                             }
                             next_state
                         }
@@ -1152,9 +1502,12 @@ impl RTCPeerConnection {
                                 sd.sdp_type,
                             );
                             if next_state.is_ok() {
-                                let mut pending_remote_description =
-                                    self.internal.pending_remote_description.lock().await;
-                                *pending_remote_description = None;
+                                {
+                                    let mut pending_remote_description =
+                                        self.internal.pending_remote_description.lock().await;
+                                    *pending_remote_description = None;
+                                }
+                                self.internal.rollback_transceivers().await?;
                             }
                             next_state
                         }
@@ -1185,6 +1538,11 @@ impl RTCPeerConnection {
                     .signaling_state
                     .store(next_state as u8, Ordering::SeqCst);
                 if self.signaling_state() == RTCSignalingState::Stable {
+                    {
+                        let mut pending_remote_transceivers =
+                            self.internal.pending_remote_transceivers.lock().await;
+                        pending_remote_transceivers.clear();
+                    }
                     self.internal
                         .is_negotiation_needed
                         .store(false, Ordering::SeqCst);
@@ -1203,6 +1561,11 @@ impl RTCPeerConnection {
             return Err(Error::ErrConnectionClosed);
         }
 
+        // A rollback has no description to apply, it only restores the stable state
+        if desc.sdp_type == RTCSdpType::Rollback {
+            return self.set_description(&desc, StateChangeOp::SetLocal).await;
+        }
+
         let have_local_description = {
             let current_local_description = self.internal.current_local_description.lock().await;
             current_local_description.is_some()
@@ -1264,6 +1627,7 @@ impl RTCPeerConnection {
                     t.process_new_current_direction(previous_direction).await?;
                 }
             }
+            self.internal.remove_stopped_transceivers().await;
 
             if let Some(remote_desc) = remote_description {
                 self.start_rtp_senders().await?;
@@ -1305,18 +1669,41 @@ impl RTCPeerConnection {
         self.current_local_description().await
     }
 
+    /// stop_rejected_transceiver stops a transceiver whose m= section the remote peer rejected,
+    /// after muting the tracks it receives.
+    async fn stop_rejected_transceiver(t: &Arc<RTCRtpTransceiver>) -> Result<()> {
+        if let Some(receiver) = t.receiver().await {
+            if receiver.current_state() == crate::rtp_transceiver::rtp_receiver::State::Started {
+                for track in receiver.tracks().await {
+                    track.fire_onmute().await;
+                }
+            }
+        }
+        t.stop_internal().await?;
+        Ok(())
+    }
+
     /// set_remote_description sets the SessionDescription of the remote peer
     pub async fn set_remote_description(&self, mut desc: RTCSessionDescription) -> Result<()> {
         if self.internal.is_closed.load(Ordering::SeqCst) {
             return Err(Error::ErrConnectionClosed);
         }
 
+        if desc.sdp_type == RTCSdpType::Rollback {
+            return self.set_description(&desc, StateChangeOp::SetRemote).await;
+        }
+
         let is_renegotation = {
             let current_remote_description = self.internal.current_remote_description.lock().await;
             current_remote_description.is_some()
         };
 
-        desc.parsed = Some(desc.unmarshal()?);
+        let parsed = desc.unmarshal()?;
+        if self.internal.bundle_policy == RTCBundlePolicy::MaxBundle {
+            // All the media use the single transport of the first m= section
+            check_max_bundle(&parsed)?;
+        }
+        desc.parsed = Some(parsed);
         self.set_description(&desc, StateChangeOp::SetRemote)
             .await?;
 
@@ -1348,6 +1735,13 @@ impl RTCPeerConnection {
                             continue;
                         }
 
+                        if is_rejected(media) {
+                            if let Some(t) = find_by_mid(mid_value, &mut local_transceivers).await {
+                                RTCPeerConnection::stop_rejected_transceiver(&t).await?;
+                            }
+                            continue;
+                        }
+
                         let kind = RTPCodecType::from(media.media_name.media.as_str());
                         let direction = get_peer_direction(media);
                         if kind == RTPCodecType::Unspecified
@@ -1397,6 +1791,11 @@ impl RTCPeerConnection {
                             .await;
 
                             self.internal.add_rtp_transceiver(Arc::clone(&t)).await;
+                            {
+                                let mut pending_remote_transceivers =
+                                    self.internal.pending_remote_transceivers.lock().await;
+                                pending_remote_transceivers.push(Arc::clone(&t));
+                            }
 
                             if t.mid().await.is_empty() {
                                 t.set_mid(mid_value.to_owned()).await?;
@@ -1426,6 +1825,12 @@ impl RTCPeerConnection {
                         if media.media_name.media == MEDIA_SECTION_APPLICATION {
                             continue;
                         }
+                        if is_rejected(media) {
+                            if let Some(t) = find_by_mid(mid_value, &mut local_transceivers).await {
+                                RTCPeerConnection::stop_rejected_transceiver(&t).await?;
+                            }
+                            continue;
+                        }
                         let kind = RTPCodecType::from(media.media_name.media.as_str());
                         let direction = get_peer_direction(media);
                         if kind == RTPCodecType::Unspecified
@@ -1457,6 +1862,22 @@ impl RTCPeerConnection {
                         }
                     }
                 }
+
+                self.internal.remove_stopped_transceivers().await;
+            }
+
+            if !is_renegotation {
+                // RFC 5761 S5.1.1: RTCP gets a transport of its own unless the remote
+                // peer multiplexes it with RTP
+                if self.internal.rtcp_mux_policy == RTCRtcpMuxPolicy::Negotiate
+                    && !have_rtcp_mux(parsed)
+                {
+                    if !we_offer {
+                        self.internal.ice_gatherer.create_rtcp_agent().await?;
+                    }
+                } else {
+                    self.internal.ice_gatherer.close_rtcp_agent().await?;
+                }
             }
 
             let (remote_ufrag, remote_pwd, candidates) = extract_ice_details(parsed).await?;
@@ -1518,9 +1939,9 @@ impl RTCPeerConnection {
                 }
             }
 
-            let (fingerprint, fingerprint_hash) = extract_fingerprint(parsed)?;
+            let fingerprints = extract_fingerprints(parsed)?;
 
-            // If one of the agents is lite and the other one is not, the lite agent must be the controlling agent.
+            // If one of the agents is lite and the other one is not, the full agent must be the controlling agent.
             // If both or neither agents are lite the offering agent is controlling.
             // RFC 8445 S6.1.1
             let ice_role = if (we_offer
@@ -1549,18 +1970,25 @@ impl RTCPeerConnection {
                     move || {
                         let pc = Arc::clone(&pci);
                         let rd = Arc::clone(&remote_desc);
-                        let ru = remote_ufrag.clone();
-                        let rp = remote_pwd.clone();
-                        let fp = fingerprint.clone();
-                        let fp_hash = fingerprint_hash.clone();
+                        let remote_ice_params = RTCIceParameters {
+                            username_fragment: remote_ufrag.clone(),
+                            password: remote_pwd.clone(),
+                            ice_lite: remote_is_lite,
+                        };
+                        let fingerprints = fingerprints.clone();
                         Box::pin(async move {
                             log::trace!(
                                 "start_transports: ice_role={}, dtls_role={}",
                                 ice_role,
                                 dtls_role,
                             );
-                            pc.start_transports(ice_role, dtls_role, ru, rp, fp, fp_hash)
-                                .await;
+                            pc.start_transports(
+                                ice_role,
+                                dtls_role,
+                                remote_ice_params,
+                                fingerprints,
+                            )
+                            .await;
 
                             if we_offer {
                                 let _ = pc.start_rtp(false, rd).await;
@@ -1710,7 +2138,7 @@ impl RTCPeerConnection {
 
         let transceiver = self
             .internal
-            .new_transceiver_from_track(RTCRtpTransceiverDirection::Sendrecv, track)
+            .new_transceiver_from_track(RTCRtpTransceiverDirection::Sendrecv, track, &[])
             .await?;
         self.internal
             .add_rtp_transceiver(Arc::clone(&transceiver))
@@ -1761,6 +2189,41 @@ impl RTCPeerConnection {
         Ok(())
     }
 
+    /// register_codec adds a codec to the MediaEngine of the PeerConnection after it has been
+    /// created, for the directions allowed by allowed_direction or all of them with None, see
+    /// MediaEngine::register_codec_with_direction. The codec is offered and answered from the
+    /// next negotiation on, which the application starts with create_offer.
+    pub fn register_codec(
+        &self,
+        codec: RTCRtpCodecParameters,
+        typ: RTPCodecType,
+        allowed_direction: Option<RTCRtpTransceiverDirection>,
+    ) -> Result<()> {
+        self.internal
+            .media_engine
+            .add_registered_codec(codec, typ, allowed_direction)?;
+        self.internal.media_engine.renegotiate_kind(typ);
+        Ok(())
+    }
+
+    /// register_header_extension adds a header extension to the MediaEngine of the
+    /// PeerConnection after it has been created, see MediaEngine::register_header_extension.
+    /// Like for register_codec, it takes effect with the next negotiation.
+    pub fn register_header_extension(
+        &self,
+        extension: RTCRtpHeaderExtensionCapability,
+        typ: RTPCodecType,
+        allowed_direction: Option<RTCRtpTransceiverDirection>,
+    ) -> Result<()> {
+        self.internal.media_engine.add_registered_header_extension(
+            extension,
+            typ,
+            allowed_direction,
+        )?;
+        self.internal.media_engine.renegotiate_kind(typ);
+        Ok(())
+    }
+
     /// add_transceiver_from_kind Create a new RtpTransceiver and adds it to the set of transceivers.
     pub async fn add_transceiver_from_kind(
         &self,
@@ -1771,6 +2234,11 @@ impl RTCPeerConnection {
     }
 
     /// add_transceiver_from_track Create a new RtpTransceiver(SendRecv or SendOnly) and add it to the set of transceivers.
+    ///
+    /// With more than one send encoding in the RTCRtpTransceiverInit the track is sent as
+    /// simulcast, once per encoding. Each encoding must then have a unique rid, and the
+    /// "urn:ietf:params:rtp-hdrext:sdes:mid" and "urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id"
+    /// header extensions should be registered for the remote peer to tell the encodings apart.
     pub async fn add_transceiver_from_track<'a>(
         &'a self,
         track: Arc<dyn TrackLocal + Send + Sync>,
@@ -1780,15 +2248,15 @@ impl RTCPeerConnection {
             return Err(Error::ErrConnectionClosed);
         }
 
-        let direction = match init.len() {
-            0 => RTCRtpTransceiverDirection::Sendrecv,
-            1 => init[0].direction,
+        let (direction, send_encodings) = match init.len() {
+            0 => (RTCRtpTransceiverDirection::Sendrecv, &[][..]),
+            1 => (init[0].direction, &init[0].send_encodings[..]),
             _ => return Err(Error::ErrPeerConnAddTransceiverFromTrackOnlyAcceptsOne),
         };
 
         let t = self
             .internal
-            .new_transceiver_from_track(direction, track)
+            .new_transceiver_from_track(direction, track, send_encodings)
             .await?;
 
         self.internal.add_rtp_transceiver(Arc::clone(&t)).await;
@@ -1846,6 +2314,10 @@ impl RTCPeerConnection {
 
             // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #12)
             params.negotiated = options.negotiated;
+
+            if let Some(priority) = options.priority {
+                params.priority = priority;
+            }
         }
 
         let d = Arc::new(RTCDataChannel::new(
@@ -1907,6 +2379,28 @@ impl RTCPeerConnection {
             .signaling_state
             .store(RTCSignalingState::Closed as u8, Ordering::SeqCst);
 
+        // The orderly close tells the remote peer that the streams, the data channels and the
+        // transports go away, instead of leaving it to its timeouts: the senders send an RTCP
+        // BYE, the association is shut down and the DTLS transport sends a close_notify. What
+        // is left of it at the deadline is skipped, and the transports are closed at once.
+        let close_timeout = self
+            .internal
+            .setting_engine
+            .timeout
+            .close_timeout
+            .unwrap_or(DEFAULT_CLOSE_TIMEOUT);
+        let orderly = !close_timeout.is_zero()
+            && self.internal.dtls_transport.state() == RTCDtlsTransportState::Connected;
+        let deadline = tokio::time::Instant::now() + close_timeout;
+
+        if orderly {
+            match tokio::time::timeout_at(deadline, self.internal.send_goodbye()).await {
+                Ok(Err(err)) => log::warn!("Failed to send RTCP BYE: {}", err),
+                Err(_) => log::warn!("RTCP BYE timed out"),
+                Ok(Ok(())) => {}
+            }
+        }
+
         // Try closing everything and collect the errors
         // Shutdown strategy:
         // 1. All Conn close by closing their underlying Conn.
@@ -1942,6 +2436,13 @@ impl RTCPeerConnection {
         }
 
         // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #6)
+        if orderly {
+            match tokio::time::timeout_at(deadline, self.internal.sctp_transport.shutdown()).await {
+                Ok(Err(err)) => log::debug!("SCTP shutdown: {}", err),
+                Err(_) => log::warn!("SCTP shutdown timed out, closing the association at once"),
+                Ok(Ok(())) => {}
+            }
+        }
         if let Err(err) = self.internal.sctp_transport.stop().await {
             close_errs.push(Error::new(format!("sctp_transport: {}", err)));
         }
@@ -0,0 +1,105 @@
+use crate::data_channel::RTCDataChannel;
+use crate::ice_transport::ice_candidate::RTCIceCandidate;
+use crate::ice_transport::ice_connection_state::RTCIceConnectionState;
+use crate::ice_transport::ice_gatherer_state::RTCIceGathererState;
+use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use crate::peer_connection::signaling_state::RTCSignalingState;
+use crate::peer_connection::transport_state_change::RTCTransportStateChange;
+use crate::rtp_transceiver::rtp_receiver::RTCRtpReceiver;
+use crate::stats::{RemoteInboundRTPStats, RemoteOutboundRTPStats};
+use crate::track::track_remote::TrackRemote;
+
+use futures::Stream;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// RTCPeerConnectionEvent is an event of a PeerConnection, as it is delivered by
+/// the stream returned by RTCPeerConnection::events.
+#[derive(Clone)]
+pub enum RTCPeerConnectionEvent {
+    /// SignalingStateChange is delivered when the signaling state changes.
+    SignalingStateChange(RTCSignalingState),
+
+    /// ICEConnectionStateChange is delivered when the ICE connection state changes.
+    ICEConnectionStateChange(RTCIceConnectionState),
+
+    /// PeerConnectionStateChange is delivered when the PeerConnectionState changes.
+    PeerConnectionStateChange(RTCPeerConnectionState),
+
+    /// ICEGatheringStateChange is delivered when the ICE candidate gathering state changes.
+    ICEGatheringStateChange(RTCIceGathererState),
+
+    /// ICECandidate is delivered for each local ICE candidate that is found, and with
+    /// None once the gathering is finished.
+    ICECandidate(Option<RTCIceCandidate>),
+
+    /// Track is delivered when a remote track arrives from the remote peer.
+    Track(Arc<TrackRemote>, Arc<RTCRtpReceiver>),
+
+    /// TransportStateChange is delivered when the state of the ICE, DTLS or SCTP
+    /// transport changes, with the reason of the change.
+    TransportStateChange(RTCTransportStateChange),
+
+    /// DataChannel is delivered when the remote peer opens a data channel.
+    DataChannel(Arc<RTCDataChannel>),
+
+    /// NegotiationNeeded is delivered when a change has occurred which requires
+    /// session negotiation.
+    NegotiationNeeded,
+
+    /// RemoteInboundRTPStats is delivered when a Receiver Report arrives about an RTP
+    /// stream sent to the remote peer.
+    RemoteInboundRTPStats(RemoteInboundRTPStats),
+
+    /// RemoteOutboundRTPStats is delivered when a Sender Report arrives about an RTP
+    /// stream received from the remote peer.
+    RemoteOutboundRTPStats(RemoteOutboundRTPStats),
+}
+
+/// EventStream is a Stream of the events of a PeerConnection, which is fed by the on_*
+/// handlers set by the method that created it. Setting one of these handlers again stops the
+/// delivery of its events only, the stream ends once the PeerConnection is dropped or once
+/// all of its handlers have been replaced.
+///
+/// The events of RTCPeerConnection::events, each fed by the on_* handler of the same name:
+///
+/// * SignalingStateChange: on_signaling_state_change
+/// * ICEConnectionStateChange: on_ice_connection_state_change
+/// * PeerConnectionStateChange: on_peer_connection_state_change
+/// * ICEGatheringStateChange: on_ice_gathering_state_change
+/// * ICECandidate: on_ice_candidate
+/// * Track: on_track
+/// * TransportStateChange: on_transport_state_change
+/// * DataChannel: on_data_channel
+/// * NegotiationNeeded: on_negotiation_needed
+/// * RemoteInboundRTPStats: on_remote_inbound_rtp_stats
+/// * RemoteOutboundRTPStats: on_remote_outbound_rtp_stats
+///
+/// The streams of peer_connection_state_changes, ice_candidates, tracks and data_channels
+/// are fed by on_peer_connection_state_change, on_ice_candidate, on_track and
+/// on_data_channel respectively, and end once that handler is replaced.
+pub struct EventStream<T> {
+    rx: mpsc::UnboundedReceiver<T>,
+}
+
+impl<T> EventStream<T> {
+    pub(crate) fn channel() -> (mpsc::UnboundedSender<T>, Self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (tx, EventStream { rx })
+    }
+
+    /// recv receives the next event, or None if the stream has ended.
+    pub async fn recv(&mut self) -> Option<T> {
+        self.rx.recv().await
+    }
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.rx.poll_recv(cx)
+    }
+}
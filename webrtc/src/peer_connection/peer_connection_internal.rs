@@ -1,15 +1,19 @@
 use tokio::time::Instant;
 
 use super::*;
-use crate::rtp_transceiver::create_stream_info;
+use crate::dtls_transport::dtls_fingerprint::strongest_fingerprint;
+use crate::rtp_transceiver::rtp_codec::ScalabilityMode;
+use crate::rtp_transceiver::{create_stream_info, RTCRtpEncodingParameters};
 use crate::stats::stats_collector::StatsCollector;
 use crate::stats::{
     InboundRTPStats, OutboundRTPStats, RTCStatsType, RemoteInboundRTPStats, RemoteOutboundRTPStats,
-    StatsReportType,
+    StatsReportType, ICE_TRANSPORT_STATS_ID,
 };
 use crate::track::TrackStream;
 use crate::{SDES_REPAIR_RTP_STREAM_ID_URI, SDP_ATTRIBUTE_RID};
 use arc_swap::ArcSwapOption;
+use bytes::Bytes;
+use std::collections::HashSet;
 use std::sync::atomic::AtomicIsize;
 use std::sync::Weak;
 
@@ -32,6 +36,8 @@ pub(crate) struct PeerConnectionInternal {
     pub(crate) ops: Arc<Operations>,
     pub(super) negotiation_needed_state: Arc<AtomicU8>,
     pub(super) is_negotiation_needed: Arc<AtomicBool>,
+    /// set by restart_ice until the next offer is created with new ICE credentials
+    pub(super) ice_restart_requested: Arc<AtomicBool>,
     pub(super) signaling_state: Arc<AtomicU8>,
 
     pub(super) ice_transport: Arc<RTCIceTransport>,
@@ -43,6 +49,8 @@ pub(crate) struct PeerConnectionInternal {
 
     pub(super) sctp_transport: Arc<RTCSctpTransport>,
     pub(super) rtp_transceivers: Arc<Mutex<Vec<Arc<RTCRtpTransceiver>>>>,
+    /// transceivers created while applying the pending remote offer, removed again if it is rolled back
+    pub(super) pending_remote_transceivers: Mutex<Vec<Arc<RTCRtpTransceiver>>>,
 
     pub(super) on_track_handler: Arc<ArcSwapOption<Mutex<OnTrackHdlrFn>>>,
     pub(super) on_signaling_state_change_handler:
@@ -50,6 +58,8 @@ pub(crate) struct PeerConnectionInternal {
     pub(super) on_ice_connection_state_change_handler:
         Arc<ArcSwapOption<Mutex<OnICEConnectionStateChangeHdlrFn>>>,
     pub(super) on_data_channel_handler: Arc<ArcSwapOption<Mutex<OnDataChannelHdlrFn>>>,
+    pub(super) on_transport_state_change_handler:
+        Arc<ArcSwapOption<Mutex<OnTransportStateChangeHdlrFn>>>,
 
     pub(super) ice_gatherer: Arc<RTCIceGatherer>,
 
@@ -58,6 +68,9 @@ pub(crate) struct PeerConnectionInternal {
     pub(super) pending_local_description: Arc<Mutex<Option<RTCSessionDescription>>>,
     pub(super) pending_remote_description: Arc<Mutex<Option<RTCSessionDescription>>>,
 
+    pub(super) bundle_policy: RTCBundlePolicy,
+    pub(super) rtcp_mux_policy: RTCRtcpMuxPolicy,
+
     // A reference to the associated API state used by this connection
     pub(super) setting_engine: Arc<SettingEngine>,
     pub(crate) media_engine: Arc<MediaEngine>,
@@ -82,6 +95,7 @@ impl PeerConnectionInternal {
             ops: Arc::new(Operations::new()),
             is_closed: Arc::new(AtomicBool::new(false)),
             is_negotiation_needed: Arc::new(AtomicBool::new(false)),
+            ice_restart_requested: Arc::new(AtomicBool::new(false)),
             negotiation_needed_state: Arc::new(AtomicU8::new(NegotiationNeededState::Empty as u8)),
             signaling_state: Arc::new(AtomicU8::new(RTCSignalingState::Stable as u8)),
             ice_transport: Arc::new(Default::default()),
@@ -89,16 +103,20 @@ impl PeerConnectionInternal {
             ice_connection_state: Arc::new(AtomicU8::new(RTCIceConnectionState::New as u8)),
             sctp_transport: Arc::new(Default::default()),
             rtp_transceivers: Arc::new(Default::default()),
+            pending_remote_transceivers: Mutex::new(vec![]),
             on_track_handler: Arc::new(ArcSwapOption::empty()),
             on_signaling_state_change_handler: ArcSwapOption::empty(),
             on_ice_connection_state_change_handler: Arc::new(ArcSwapOption::empty()),
             on_data_channel_handler: Arc::new(Default::default()),
+            on_transport_state_change_handler: Arc::new(ArcSwapOption::empty()),
             ice_gatherer: Arc::new(Default::default()),
             current_local_description: Arc::new(Default::default()),
             current_remote_description: Arc::new(Default::default()),
             pending_local_description: Arc::new(Default::default()),
             peer_connection_state: Arc::new(AtomicU8::new(RTCPeerConnectionState::New as u8)),
 
+            bundle_policy: configuration.bundle_policy,
+            rtcp_mux_policy: configuration.rtcp_mux_policy,
             setting_engine: Arc::clone(&api.setting_engine),
             media_engine: if !api.setting_engine.disable_media_engine_copy {
                 Arc::new(api.media_engine.clone_to())
@@ -128,6 +146,8 @@ impl PeerConnectionInternal {
         // Create the SCTP transport
         pc.sctp_transport = Arc::new(api.new_sctp_transport(Arc::clone(&pc.dtls_transport))?);
 
+        pc.handle_transport_state_changes();
+
         // Wire up the on datachannel handler
         let on_data_channel_handler = Arc::clone(&pc.on_data_channel_handler);
         pc.sctp_transport
@@ -164,6 +184,9 @@ impl PeerConnectionInternal {
             self.undeclared_media_processor();
         } else {
             for t in &current_transceivers {
+                if t.stopped.load(Ordering::SeqCst) {
+                    continue;
+                }
                 if let Some(receiver) = t.receiver().await {
                     let tracks = receiver.tracks().await;
                     if tracks.is_empty() {
@@ -448,7 +471,8 @@ impl PeerConnectionInternal {
                     math_rand_alpha(16),
                 ));
 
-                self.new_transceiver_from_track(direction, track).await?
+                self.new_transceiver_from_track(direction, track, &[])
+                    .await?
             }
             RTCRtpTransceiverDirection::Recvonly => {
                 let interceptor = self
@@ -486,12 +510,31 @@ impl PeerConnectionInternal {
         &self,
         direction: RTCRtpTransceiverDirection,
         track: Arc<dyn TrackLocal + Send + Sync>,
+        send_encodings: &[RTCRtpEncodingParameters],
     ) -> Result<Arc<RTCRtpTransceiver>> {
         let interceptor = self
             .interceptor
             .upgrade()
             .ok_or(Error::ErrInterceptorNotBind)?;
 
+        // Each encoding of a simulcast sender is identified by its RID
+        if send_encodings.len() > 1 {
+            let mut rids = HashSet::new();
+            for encoding in send_encodings {
+                if encoding.rid.is_empty() {
+                    return Err(Error::ErrRTPSenderRidNil);
+                }
+                if !rids.insert(encoding.rid.as_str()) {
+                    return Err(Error::ErrRTPSenderRidCollision);
+                }
+            }
+        }
+        for encoding in send_encodings {
+            if let Some(mode) = &encoding.scalability_mode {
+                mode.parse::<ScalabilityMode>()?;
+            }
+        }
+
         let (r, s) = match direction {
             RTCRtpTransceiverDirection::Sendrecv => {
                 let r = Some(Arc::new(RTCRtpReceiver::new(
@@ -502,13 +545,14 @@ impl PeerConnectionInternal {
                     Arc::clone(&interceptor),
                 )));
                 let s = Some(Arc::new(
-                    RTCRtpSender::new(
+                    RTCRtpSender::new_with_send_encodings(
                         self.setting_engine.get_receive_mtu(),
                         Arc::clone(&track),
                         Arc::clone(&self.dtls_transport),
                         Arc::clone(&self.media_engine),
                         Arc::clone(&interceptor),
                         false,
+                        send_encodings,
                     )
                     .await,
                 ));
@@ -516,13 +560,14 @@ impl PeerConnectionInternal {
             }
             RTCRtpTransceiverDirection::Sendonly => {
                 let s = Some(Arc::new(
-                    RTCRtpSender::new(
+                    RTCRtpSender::new_with_send_encodings(
                         self.setting_engine.get_receive_mtu(),
                         Arc::clone(&track),
                         Arc::clone(&self.dtls_transport),
                         Arc::clone(&self.media_engine),
                         Arc::clone(&interceptor),
                         false,
+                        send_encodings,
                     )
                     .await,
                 ));
@@ -554,6 +599,108 @@ impl PeerConnectionInternal {
         self.trigger_negotiation_needed().await;
     }
 
+    /// rollback_transceivers restores the transceivers to the current local description after
+    /// a pending offer was rolled back: transceivers created by a remote offer without a track
+    /// added since are stopped and removed, the others that were associated with a media section
+    /// by the offer lose their mid again.
+    pub(super) async fn rollback_transceivers(&self) -> Result<()> {
+        let pending_remote_transceivers = {
+            let mut pending_remote_transceivers = self.pending_remote_transceivers.lock().await;
+            std::mem::take(&mut *pending_remote_transceivers)
+        };
+        let current_local_description = {
+            let current_local_description = self.current_local_description.lock().await;
+            current_local_description.clone()
+        };
+
+        let mut rtp_transceivers = self.rtp_transceivers.lock().await;
+        let mut kept = Vec::with_capacity(rtp_transceivers.len());
+        for t in rtp_transceivers.drain(..) {
+            let mid = t.mid().await;
+            let associated = !mid.is_empty()
+                && current_local_description
+                    .as_ref()
+                    .and_then(|desc| get_by_mid(&mid, desc))
+                    .is_some();
+            if associated {
+                kept.push(t);
+                continue;
+            }
+
+            if pending_remote_transceivers
+                .iter()
+                .any(|pending| Arc::ptr_eq(pending, &t))
+                && t.sender().await.is_none()
+            {
+                t.stop_internal().await?;
+                continue;
+            }
+
+            t.clear_mid().await;
+            kept.push(t);
+        }
+        *rtp_transceivers = kept;
+
+        Ok(())
+    }
+
+    /// remove_stopped_transceivers removes the stopped transceivers once their m= section is
+    /// rejected in the current local description, or they never had one, after an answer.
+    pub(super) async fn remove_stopped_transceivers(&self) {
+        let current_local_description = {
+            let current_local_description = self.current_local_description.lock().await;
+            current_local_description.clone()
+        };
+
+        let mut rtp_transceivers = self.rtp_transceivers.lock().await;
+        let mut kept = Vec::with_capacity(rtp_transceivers.len());
+        for t in rtp_transceivers.drain(..) {
+            if t.stopped.load(Ordering::SeqCst) {
+                let mid = t.mid().await;
+                let negotiated = !mid.is_empty()
+                    && current_local_description
+                        .as_ref()
+                        .and_then(|desc| get_by_mid(&mid, desc))
+                        .map_or(false, |m| !is_rejected(m));
+                if !negotiated {
+                    continue;
+                }
+            }
+            kept.push(t);
+        }
+        *rtp_transceivers = kept;
+    }
+
+    /// send_goodbye sends an RTCP BYE for the SSRCs of the senders, so that the remote
+    /// peer ends their tracks without waiting for them to time out.
+    pub(super) async fn send_goodbye(&self) -> Result<()> {
+        let mut sources = vec![];
+        {
+            let rtp_transceivers = self.rtp_transceivers.lock().await;
+            for t in &*rtp_transceivers {
+                if let Some(sender) = t.sender().await {
+                    sources.extend(sender.sending_ssrcs().await);
+                }
+            }
+        }
+        if sources.is_empty() {
+            return Ok(());
+        }
+
+        let pkts: Vec<Box<dyn rtcp::packet::Packet + Send + Sync>> = sources
+            .chunks(rtcp::header::COUNT_MAX)
+            .map(|sources| {
+                Box::new(rtcp::goodbye::Goodbye {
+                    sources: sources.to_vec(),
+                    reason: Bytes::new(),
+                }) as Box<dyn rtcp::packet::Packet + Send + Sync>
+            })
+            .collect();
+        self.dtls_transport.write_rtcp(&pkts).await?;
+
+        Ok(())
+    }
+
     /// Helper to trigger a negotiation needed.
     pub(crate) async fn trigger_negotiation_needed(&self) {
         RTCPeerConnection::do_negotiation_needed(self.create_negotiation_needed_params()).await;
@@ -573,6 +720,7 @@ impl PeerConnectionInternal {
                 rtp_transceivers: Arc::clone(&self.rtp_transceivers),
                 current_local_description: Arc::clone(&self.current_local_description),
                 current_remote_description: Arc::clone(&self.current_remote_description),
+                ice_restart_requested: Arc::clone(&self.ice_restart_requested),
             },
         }
     }
@@ -609,22 +757,13 @@ impl PeerConnectionInternal {
         self: &Arc<Self>,
         ice_role: RTCIceRole,
         dtls_role: DTLSRole,
-        remote_ufrag: String,
-        remote_pwd: String,
-        fingerprint: String,
-        fingerprint_hash: String,
+        remote_ice_params: RTCIceParameters,
+        fingerprints: Vec<RTCDtlsFingerprint>,
     ) {
         // Start the ice transport
         if let Err(err) = self
             .ice_transport
-            .start(
-                &RTCIceParameters {
-                    username_fragment: remote_ufrag,
-                    password: remote_pwd,
-                    ice_lite: false,
-                },
-                Some(ice_role),
-            )
+            .start(&remote_ice_params, Some(ice_role))
             .await
         {
             log::warn!("Failed to start manager ice: {}", err);
@@ -636,10 +775,7 @@ impl PeerConnectionInternal {
             .dtls_transport
             .start(DTLSParameters {
                 role: dtls_role,
-                fingerprints: vec![RTCDtlsFingerprint {
-                    algorithm: fingerprint_hash,
-                    value: fingerprint,
-                }],
+                fingerprints,
             })
             .await;
         RTCPeerConnection::update_connection_state(
@@ -655,6 +791,38 @@ impl PeerConnectionInternal {
         }
     }
 
+    /// local_fingerprints returns the fingerprints of the local certificate put in the sdp.
+    /// They are computed with the configured hash function algorithms, and with the strongest
+    /// one of the remote description if none of them is offered by the remote peer, which may
+    /// only verify the fingerprints computed with its own algorithms.
+    fn local_fingerprints(
+        &self,
+        remote_description: Option<&RTCSessionDescription>,
+    ) -> Result<Vec<RTCDtlsFingerprint>> {
+        let cert = self
+            .dtls_transport
+            .certificates
+            .first()
+            .ok_or(Error::ErrNonCertificate)?;
+
+        let mut algorithms = self.setting_engine.get_dtls_fingerprint_algorithms();
+        let remote_fingerprints = remote_description
+            .and_then(|desc| desc.parsed.as_ref())
+            .and_then(|parsed| extract_fingerprints(parsed).ok())
+            .unwrap_or_default();
+        if !remote_fingerprints.is_empty()
+            && !remote_fingerprints
+                .iter()
+                .any(|fp| algorithms.contains(&fp.algorithm.as_str()))
+        {
+            if let Some(fp) = strongest_fingerprint(&remote_fingerprints) {
+                algorithms.push(fp.algorithm.as_str());
+            }
+        }
+
+        Ok(cert.fingerprints(&algorithms))
+    }
+
     /// generate_unmatched_sdp generates an SDP that doesn't take remote state into account
     /// This is used for the initial call for CreateOffer
     pub(super) async fn generate_unmatched_sdp(
@@ -664,9 +832,20 @@ impl PeerConnectionInternal {
     ) -> Result<SessionDescription> {
         let d = SessionDescription::new_jsep_session_description(use_identity);
 
+        // With the negotiate policy, candidates are gathered for RTCP too, in case the
+        // remote peer doesn't multiplex it with RTP
+        if self.rtcp_mux_policy == RTCRtcpMuxPolicy::Negotiate
+            && local_transceivers
+                .iter()
+                .any(|t| !t.stopped.load(Ordering::SeqCst))
+        {
+            self.ice_gatherer.create_rtcp_agent().await?;
+        }
+
         let ice_params = self.ice_gatherer.get_local_parameters().await?;
 
         let candidates = self.ice_gatherer.get_local_candidates().await?;
+        let rtcp_candidates = self.ice_gatherer.get_rtcp_agent().await.is_some();
 
         let mut media_sections = vec![];
 
@@ -702,17 +881,17 @@ impl PeerConnectionInternal {
             });
         }
 
-        let dtls_fingerprints = if let Some(cert) = self.dtls_transport.certificates.first() {
-            cert.get_fingerprints()
-        } else {
-            return Err(Error::ErrNonCertificate);
-        };
+        let dtls_fingerprints = self.local_fingerprints(None)?;
 
         let params = PopulateSdpParams {
             media_description_fingerprint: self.setting_engine.sdp_media_level_fingerprints,
             is_icelite: self.setting_engine.candidates.ice_lite,
-            connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
+            connection_role: self.setting_engine.offer_dtls_role().to_connection_role(),
             ice_gathering_state: self.ice_gathering_state(),
+            extmap_allow_mixed: true,
+            bundle_only: self.bundle_policy == RTCBundlePolicy::MaxBundle,
+            rtcp_mux: true,
+            rtcp_candidates,
         };
         populate_sdp(
             d,
@@ -739,12 +918,28 @@ impl PeerConnectionInternal {
 
         let ice_params = self.ice_gatherer.get_local_parameters().await?;
         let candidates = self.ice_gatherer.get_local_candidates().await?;
+        // The RTCP agent is only kept when the remote peer doesn't multiplex RTCP
+        let rtcp_candidates = self.ice_gatherer.get_rtcp_agent().await.is_some();
 
         let remote_description = self.remote_description().await;
+        let remote_mids: Vec<String> = remote_description
+            .as_ref()
+            .and_then(|desc| desc.parsed.as_ref())
+            .map(|parsed| {
+                parsed
+                    .media_descriptions
+                    .iter()
+                    .filter_map(|media| get_mid_value(media).cloned())
+                    .collect()
+            })
+            .unwrap_or_default();
         let mut media_sections = vec![];
         let mut already_have_application_media_section = false;
+        let mut extmap_allow_mixed = false;
         if let Some(remote_description) = remote_description.as_ref() {
             if let Some(parsed) = &remote_description.parsed {
+                extmap_allow_mixed = is_extmap_allow_mixed_set(parsed);
+
                 for media in &parsed.media_descriptions {
                     if let Some(mid_value) = get_mid_value(media) {
                         if mid_value.is_empty() {
@@ -761,15 +956,51 @@ impl PeerConnectionInternal {
                             continue;
                         }
 
-                        let kind = RTPCodecType::from(media.media_name.media.as_str());
+                        let rejected = is_rejected(media);
                         let direction = get_peer_direction(media);
-                        if kind == RTPCodecType::Unspecified
-                            || direction == RTCRtpTransceiverDirection::Unspecified
-                        {
-                            continue;
+                        if !rejected {
+                            let kind = RTPCodecType::from(media.media_name.media.as_str());
+                            if kind == RTPCodecType::Unspecified
+                                || direction == RTCRtpTransceiverDirection::Unspecified
+                            {
+                                continue;
+                            }
                         }
 
-                        if let Some(t) = find_by_mid(mid_value, &mut local_transceivers).await {
+                        let t = match find_by_mid(mid_value, &mut local_transceivers).await {
+                            Some(t) if !rejected && !t.stopped.load(Ordering::SeqCst) => Some(t),
+                            t => {
+                                // An offer reuses the m= section of a transceiver which is
+                                // stopped and rejected for a new transceiver, instead of adding
+                                // a m= section, https://datatracker.ietf.org/doc/html/rfc8829#section-5.2.2
+                                if include_unmatched
+                                    && rejected
+                                    && t.as_ref()
+                                        .map_or(true, |t| t.stopped.load(Ordering::SeqCst))
+                                {
+                                    if let Some(i) =
+                                        first_unmatched(&local_transceivers, &remote_mids).await
+                                    {
+                                        let t = local_transceivers.remove(i);
+                                        if let Some(sender) = t.sender().await {
+                                            sender.set_negotiated();
+                                        }
+                                        media_sections.push(MediaSection {
+                                            id: t.mid().await,
+                                            transceivers: vec![t],
+                                            ..Default::default()
+                                        });
+                                        continue;
+                                    }
+                                }
+                                if t.is_none() && !rejected {
+                                    return Err(Error::ErrPeerConnTranscieverMidNil);
+                                }
+                                None
+                            }
+                        };
+
+                        if let Some(t) = t {
                             if let Some(sender) = t.sender().await {
                                 sender.set_negotiated();
                             }
@@ -787,7 +1018,13 @@ impl PeerConnectionInternal {
                                 ..Default::default()
                             });
                         } else {
-                            return Err(Error::ErrPeerConnTranscieverMidNil);
+                            // The m= section of a stopped transceiver, or rejected by the
+                            // remote peer, stays rejected
+                            media_sections.push(MediaSection {
+                                id: mid_value.to_owned(),
+                                rejected: Some(media.media_name.media.clone()),
+                                ..Default::default()
+                            });
                         }
                     }
                 }
@@ -797,6 +1034,9 @@ impl PeerConnectionInternal {
         // If we are offering also include unmatched local transceivers
         if include_unmatched {
             for t in &local_transceivers {
+                if t.stopped.load(Ordering::SeqCst) {
+                    continue;
+                }
                 if let Some(sender) = t.sender().await {
                     sender.set_negotiated();
                 }
@@ -822,17 +1062,18 @@ impl PeerConnectionInternal {
             }
         }
 
-        let dtls_fingerprints = if let Some(cert) = self.dtls_transport.certificates.first() {
-            cert.get_fingerprints()
-        } else {
-            return Err(Error::ErrNonCertificate);
-        };
+        let dtls_fingerprints = self.local_fingerprints(remote_description.as_ref())?;
 
         let params = PopulateSdpParams {
             media_description_fingerprint: self.setting_engine.sdp_media_level_fingerprints,
             is_icelite: self.setting_engine.candidates.ice_lite,
             connection_role,
             ice_gathering_state: self.ice_gathering_state(),
+            extmap_allow_mixed,
+            // bundle-only is offered, the answer bundles what the offer does
+            bundle_only: include_unmatched && self.bundle_policy == RTCBundlePolicy::MaxBundle,
+            rtcp_mux: !rtcp_candidates,
+            rtcp_candidates,
         };
         populate_sdp(
             d,
@@ -864,18 +1105,19 @@ impl PeerConnectionInternal {
         }
 
         let only_media_section = &remote_description.media_descriptions[0];
-        let mut stream_id = "";
-        let mut id = "";
+        let mut stream_id = String::new();
+        let mut id = String::new();
 
         for a in &only_media_section.attributes {
             match a.key.as_str() {
                 ATTR_KEY_MSID => {
-                    if let Some(value) = &a.value {
-                        let split: Vec<&str> = value.split(' ').collect();
-                        if split.len() == 2 {
-                            stream_id = split[0];
-                            id = split[1];
-                        }
+                    if let Ok(Msid {
+                        stream_id: sid,
+                        track_id: Some(tid),
+                    }) = Msid::unmarshal(a.value.as_deref().unwrap_or_default())
+                    {
+                        stream_id = sid;
+                        id = tid;
                     }
                 }
                 ATTR_KEY_SSRC => return Err(Error::ErrPeerConnSingleMediaSectionHasExplicitSSRC),
@@ -887,8 +1129,8 @@ impl PeerConnectionInternal {
         let mut incoming = TrackDetails {
             ssrcs: vec![ssrc],
             kind: RTPCodecType::Video,
-            stream_id: stream_id.to_owned(),
-            id: id.to_owned(),
+            stream_id,
+            id,
             ..Default::default()
         };
         if only_media_section.media_name.media == RTPCodecType::Audio.to_string() {
@@ -980,6 +1222,7 @@ impl PeerConnectionInternal {
                         params.codecs[0].payload_type,
                         params.codecs[0].capability.clone(),
                         &params.header_extensions,
+                        self.media_engine.extmap_allow_mixed(),
                     );
                     let (rtp_read_stream, rtp_interceptor, rtcp_read_stream, rtcp_interceptor) =
                         self.dtls_transport
@@ -1121,8 +1364,74 @@ impl PeerConnectionInternal {
         }
     }
 
+    /// handle_transport_state_changes reports the state changes of the DTLS and SCTP
+    /// transports with their reasons, and updates the PeerConnectionState with those of
+    /// the DTLS transport, which may be closed or fail after it was connected.
+    fn handle_transport_state_changes(&self) {
+        let dtls_transport = Arc::downgrade(&self.dtls_transport);
+        let ice_connection_state = Arc::clone(&self.ice_connection_state);
+        let peer_connection_state = Arc::clone(&self.peer_connection_state);
+        let is_closed = Arc::clone(&self.is_closed);
+        let on_peer_connection_state_change_handler =
+            Arc::clone(&self.on_peer_connection_state_change_handler);
+        let on_transport_state_change_handler = Arc::clone(&self.on_transport_state_change_handler);
+        self.dtls_transport
+            .on_state_change(Box::new(move |state: RTCDtlsTransportState| {
+                let reason = dtls_transport
+                    .upgrade()
+                    .and_then(|dtls_transport| dtls_transport.state_reason());
+                let ice_connection_state2 = Arc::clone(&ice_connection_state);
+                let peer_connection_state2 = Arc::clone(&peer_connection_state);
+                let is_closed2 = Arc::clone(&is_closed);
+                let on_peer_connection_state_change_handler2 =
+                    Arc::clone(&on_peer_connection_state_change_handler);
+                let on_transport_state_change_handler2 =
+                    Arc::clone(&on_transport_state_change_handler);
+                Box::pin(async move {
+                    RTCPeerConnection::do_transport_state_change(
+                        &on_transport_state_change_handler2,
+                        RTCTransportStateChange::Dtls { state, reason },
+                    )
+                    .await;
+
+                    // The handshake is reported by start_transports
+                    if state != RTCDtlsTransportState::Connecting
+                        && state != RTCDtlsTransportState::Connected
+                    {
+                        RTCPeerConnection::update_connection_state(
+                            &on_peer_connection_state_change_handler2,
+                            &is_closed2,
+                            &peer_connection_state2,
+                            ice_connection_state2.load(Ordering::SeqCst).into(),
+                            state,
+                        )
+                        .await;
+                    }
+                })
+            }));
+
+        let sctp_transport = Arc::downgrade(&self.sctp_transport);
+        let on_transport_state_change_handler = Arc::clone(&self.on_transport_state_change_handler);
+        self.sctp_transport
+            .on_state_change(Box::new(move |state: RTCSctpTransportState| {
+                let reason = sctp_transport
+                    .upgrade()
+                    .and_then(|sctp_transport| sctp_transport.state_reason());
+                let on_transport_state_change_handler2 =
+                    Arc::clone(&on_transport_state_change_handler);
+                Box::pin(async move {
+                    RTCPeerConnection::do_transport_state_change(
+                        &on_transport_state_change_handler2,
+                        RTCTransportStateChange::Sctp { state, reason },
+                    )
+                    .await;
+                })
+            }));
+    }
+
     pub(super) async fn create_ice_transport(&self, api: &API) -> Arc<RTCIceTransport> {
         let ice_transport = Arc::new(api.new_ice_transport(Arc::clone(&self.ice_gatherer)));
+        let weak_ice_transport = Arc::downgrade(&ice_transport);
 
         let ice_connection_state = Arc::clone(&self.ice_connection_state);
         let peer_connection_state = Arc::clone(&self.peer_connection_state);
@@ -1132,6 +1441,7 @@ impl PeerConnectionInternal {
             Arc::clone(&self.on_ice_connection_state_change_handler);
         let on_peer_connection_state_change_handler =
             Arc::clone(&self.on_peer_connection_state_change_handler);
+        let on_transport_state_change_handler = Arc::clone(&self.on_transport_state_change_handler);
 
         ice_transport.on_connection_state_change(Box::new(move |state: RTCIceTransportState| {
             let cs = match state {
@@ -1156,7 +1466,17 @@ impl PeerConnectionInternal {
             let is_closed2 = Arc::clone(&is_closed);
             let dtls_transport_state = dtls_transport.state();
             let peer_connection_state2 = Arc::clone(&peer_connection_state);
+            let reason = weak_ice_transport
+                .upgrade()
+                .and_then(|ice_transport| ice_transport.state_reason());
+            let on_transport_state_change_handler2 = Arc::clone(&on_transport_state_change_handler);
             Box::pin(async move {
+                RTCPeerConnection::do_transport_state_change(
+                    &on_transport_state_change_handler2,
+                    RTCTransportStateChange::Ice { state, reason },
+                )
+                .await;
+
                 RTCPeerConnection::do_ice_connection_state_change(
                     &on_ice_connection_state_change_handler2,
                     &ice_connection_state2,
@@ -1211,6 +1531,40 @@ impl PeerConnectionInternal {
         collector
     }
 
+    /// remote_rtp_stats returns the remote-inbound-rtp stats of the outbound RTP stream a
+    /// Receiver Report is about, or the remote-outbound-rtp stats of the inbound RTP stream
+    /// a Sender Report is about.
+    pub(super) async fn remote_rtp_stats(
+        &self,
+        report: stats::RemoteReport,
+    ) -> Option<StatsReportType> {
+        let collector = StatsCollector::new();
+        let transceivers = { self.rtp_transceivers.lock().await.clone() };
+        match report {
+            stats::RemoteReport::ReceiverReport(_) => {
+                self.collect_outbound_stats(&collector, transceivers).await
+            }
+            stats::RemoteReport::SenderReport(_) => {
+                self.collect_inbound_stats(&collector, transceivers).await
+            }
+        }
+
+        collector
+            .into_reports()
+            .into_values()
+            .find(|entry| match (entry, report) {
+                (
+                    StatsReportType::RemoteInboundRTP(remote_inbound),
+                    stats::RemoteReport::ReceiverReport(ssrc),
+                ) => remote_inbound.ssrc == ssrc,
+                (
+                    StatsReportType::RemoteOutboundRTP(remote_outbound),
+                    stats::RemoteReport::SenderReport(ssrc),
+                ) => remote_outbound.ssrc == ssrc,
+                _ => false,
+            })
+    }
+
     async fn collect_inbound_stats(
         &self,
         collector: &StatsCollector,
@@ -1222,6 +1576,7 @@ impl PeerConnectionInternal {
             mid: String,
             track_id: String,
             kind: &'static str,
+            codec_id: Option<String>,
         }
         let mut track_infos = vec![];
         for transeiver in transceivers {
@@ -1244,11 +1599,14 @@ impl PeerConnectionInternal {
                     RTPCodecType::Video => "video",
                 };
 
+                let codec_id = Some(track.codec().await.stats_id).filter(|id| !id.is_empty());
+
                 track_infos.push(TrackInfo {
                     ssrc: track.ssrc(),
                     mid: mid.clone(),
                     track_id,
                     kind,
+                    codec_id,
                 });
             }
         }
@@ -1265,6 +1623,7 @@ impl PeerConnectionInternal {
             let kind = info.kind;
 
             let id = format!("RTCInboundRTP{}Stream_{}", capitalize(kind), ssrc);
+            let remote_id = format!("RTCRemoteOutboundRTP{}Stream_{}", capitalize(kind), ssrc);
             let (
                 packets_received,
                 header_bytes_received,
@@ -1274,6 +1633,7 @@ impl PeerConnectionInternal {
                 remote_packets_sent,
                 remote_bytes_sent,
                 remote_reports_sent,
+                remote_timestamp,
                 remote_round_trip_time,
                 remote_total_round_trip_time,
                 remote_round_trip_time_measurements,
@@ -1286,6 +1646,7 @@ impl PeerConnectionInternal {
                 stats.remote_packets_sent(),
                 stats.remote_bytes_sent(),
                 stats.remote_reports_sent(),
+                stats.remote_timestamp(),
                 stats.remote_round_trip_time(),
                 stats.remote_total_round_trip_time(),
                 stats.remote_round_trip_time_measurements(),
@@ -1299,9 +1660,12 @@ impl PeerConnectionInternal {
                     id: id.clone(),
                     ssrc,
                     kind,
+                    transport_id: ICE_TRANSPORT_STATS_ID.to_owned(),
+                    codec_id: info.codec_id.clone(),
                     packets_received,
                     track_identifier: info.track_id,
                     mid: info.mid,
+                    remote_id: remote_id.clone(),
                     last_packet_received_timestamp,
                     header_bytes_received,
                     bytes_received,
@@ -1313,11 +1677,7 @@ impl PeerConnectionInternal {
             );
 
             let local_id = id;
-            let id = format!(
-                "RTCRemoteOutboundRTP{}Stream_{}",
-                capitalize(info.kind),
-                info.ssrc
-            );
+            let id = remote_id;
             collector.insert(
                 id.clone(),
                 crate::stats::StatsReportType::RemoteOutboundRTP(RemoteOutboundRTPStats {
@@ -1327,11 +1687,14 @@ impl PeerConnectionInternal {
 
                     ssrc,
                     kind,
+                    transport_id: ICE_TRANSPORT_STATS_ID.to_owned(),
+                    codec_id: info.codec_id,
 
                     packets_sent: remote_packets_sent as u64,
                     bytes_sent: remote_bytes_sent as u64,
                     local_id,
                     reports_sent: remote_reports_sent,
+                    remote_timestamp,
                     round_trip_time: remote_round_trip_time,
                     total_round_trip_time: remote_total_round_trip_time,
                     round_trip_time_measurements: remote_round_trip_time_measurements,
@@ -1352,6 +1715,8 @@ impl PeerConnectionInternal {
             mid: String,
             rid: Option<String>,
             kind: &'static str,
+            codec_id: Option<String>,
+            clock_rate: u32,
         }
         let mut track_infos = vec![];
         for transeiver in transceivers {
@@ -1376,13 +1741,28 @@ impl PeerConnectionInternal {
                 RTPCodecType::Video => "video",
             };
 
-            track_infos.push(TrackInfo {
-                track_id,
-                ssrc: sender.ssrc,
-                mid: mid.clone(),
-                rid: None,
-                kind,
-            });
+            for encoding in &sender.track_encodings {
+                let (codec_id, clock_rate) = {
+                    let context = encoding.context.lock().await;
+                    let codec = context.params.codecs.first();
+                    (
+                        codec
+                            .map(|codec| codec.stats_id.clone())
+                            .filter(|id| !id.is_empty()),
+                        codec.map_or(0, |codec| codec.capability.clock_rate),
+                    )
+                };
+
+                track_infos.push(TrackInfo {
+                    track_id: track_id.clone(),
+                    ssrc: encoding.ssrc,
+                    mid: mid.clone(),
+                    rid: Some(encoding.rid.clone()).filter(|rid| !rid.is_empty()),
+                    kind,
+                    codec_id,
+                    clock_rate,
+                });
+            }
         }
 
         let stream_stats = self
@@ -1401,6 +1781,11 @@ impl PeerConnectionInternal {
                 capitalize(info.kind),
                 info.ssrc
             );
+            let remote_id = format!(
+                "RTCRemoteInboundRTP{}Stream_{}",
+                capitalize(info.kind),
+                info.ssrc
+            );
             let (
                 packets_sent,
                 bytes_sent,
@@ -1408,6 +1793,7 @@ impl PeerConnectionInternal {
                 nack_count,
                 remote_inbound_packets_received,
                 remote_inbound_packets_lost,
+                remote_jitter,
                 remote_rtt_ms,
                 remote_total_rtt_ms,
                 remote_rtt_measurements,
@@ -1419,6 +1805,7 @@ impl PeerConnectionInternal {
                 stats.nacks_received(),
                 stats.remote_packets_received(),
                 stats.remote_total_lost(),
+                stats.remote_jitter(),
                 stats.remote_round_trip_time(),
                 stats.remote_total_round_trip_time(),
                 stats.remote_round_trip_time_measurements(),
@@ -1431,6 +1818,8 @@ impl PeerConnectionInternal {
                 rid,
                 kind,
                 track_id: track_identifier,
+                codec_id,
+                clock_rate,
             } = info;
 
             collector.insert(
@@ -1442,8 +1831,11 @@ impl PeerConnectionInternal {
                     id: id.clone(),
                     ssrc,
                     kind,
+                    transport_id: ICE_TRANSPORT_STATS_ID.to_owned(),
+                    codec_id: codec_id.clone(),
                     packets_sent,
                     mid,
+                    remote_id: remote_id.clone(),
                     rid,
                     header_bytes_sent,
                     bytes_sent,
@@ -1455,11 +1847,7 @@ impl PeerConnectionInternal {
             );
 
             let local_id = id;
-            let id = format!(
-                "RTCRemoteInboundRTP{}Stream_{}",
-                capitalize(info.kind),
-                info.ssrc
-            );
+            let id = remote_id;
 
             collector.insert(
                 id.clone(),
@@ -1469,9 +1857,17 @@ impl PeerConnectionInternal {
                     id,
                     ssrc,
                     kind,
+                    transport_id: ICE_TRANSPORT_STATS_ID.to_owned(),
+                    codec_id,
 
                     packets_received: remote_inbound_packets_received as u64,
                     packets_lost: remote_inbound_packets_lost as i64,
+                    // The jitter is reported in RTP timestamp units
+                    jitter: if clock_rate != 0 {
+                        f64::from(remote_jitter) / f64::from(clock_rate)
+                    } else {
+                        0.0
+                    },
 
                     local_id,
 
@@ -1511,3 +1907,21 @@ fn capitalize(s: &str) -> String {
 
     result
 }
+
+/// first_unmatched returns the index of the first transceiver which isn't stopped and isn't
+/// matched by a m= section of the remote description.
+async fn first_unmatched(
+    local_transceivers: &[Arc<RTCRtpTransceiver>],
+    remote_mids: &[String],
+) -> Option<usize> {
+    for (i, t) in local_transceivers.iter().enumerate() {
+        if t.stopped.load(Ordering::SeqCst) {
+            continue;
+        }
+        let mid = t.mid().await;
+        if !remote_mids.contains(&mid) {
+            return Some(i);
+        }
+    }
+    None
+}
@@ -3,10 +3,15 @@ use super::*;
 use crate::api::media_engine::MIME_TYPE_VP8;
 use crate::api::APIBuilder;
 use crate::ice_transport::ice_candidate_pair::RTCIceCandidatePair;
+use crate::peer_connection::transport_state_change::RTCTransportStateReason;
 use crate::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
 use crate::stats::StatsReportType;
+use ::ice::candidate::COMPONENT_RTCP;
 use bytes::Bytes;
+use futures::StreamExt;
 use media::Sample;
+use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use std::collections::HashSet;
 use std::sync::atomic::AtomicU32;
 use tokio::time::Duration;
 use util::vnet::net::{Net, NetConfig};
@@ -346,6 +351,25 @@ async fn test_get_stats() -> Result<()> {
         Some(StatsReportType::Transport(ice_transport_stats)) => {
             assert!(ice_transport_stats.bytes_received > 0);
             assert!(ice_transport_stats.bytes_sent > 0);
+
+            let pair_id = ice_transport_stats
+                .selected_candidate_pair_id
+                .as_ref()
+                .expect("Should have selected a candidate pair");
+            match offer_stats.reports.get(pair_id) {
+                Some(StatsReportType::CandidatePair(pair_stats)) => {
+                    assert_eq!(pair_stats.transport_id, "ice_transport");
+                    assert!(matches!(
+                        offer_stats.reports.get(&pair_stats.local_candidate_id),
+                        Some(StatsReportType::LocalCandidate(_))
+                    ));
+                    assert!(matches!(
+                        offer_stats.reports.get(&pair_stats.remote_candidate_id),
+                        Some(StatsReportType::RemoteCandidate(_))
+                    ));
+                }
+                _ => panic!("missed the selected candidate pair"),
+            }
         }
         Some(_other) => panic!("found the wrong type"),
         None => panic!("missed it"),
@@ -362,6 +386,21 @@ async fn test_get_stats() -> Result<()> {
     assert_eq!(outbound_stats.kind, "video");
     assert_eq!(outbound_stats.bytes_sent, 8);
     assert_eq!(outbound_stats.header_bytes_sent, 12);
+    assert_eq!(outbound_stats.transport_id, "ice_transport");
+    assert!(matches!(
+        offer_stats.reports.get(&outbound_stats.remote_id),
+        Some(StatsReportType::RemoteInboundRTP(_))
+    ));
+    match outbound_stats
+        .codec_id
+        .as_ref()
+        .and_then(|id| offer_stats.reports.get(id))
+    {
+        Some(StatsReportType::Codec(codec_stats)) => {
+            assert_eq!(codec_stats.mime_type, MIME_TYPE_VP8);
+        }
+        _ => panic!("missed the codec of the outbound stat"),
+    }
 
     let answer_stats = pc_answer.get_stats().await;
     let inbound_stats = answer_stats
@@ -376,8 +415,894 @@ async fn test_get_stats() -> Result<()> {
     assert_eq!(inbound_stats.kind, "video");
     assert_eq!(inbound_stats.bytes_received, 8);
     assert_eq!(inbound_stats.header_bytes_received, 12);
+    assert!(matches!(
+        answer_stats.reports.get(&inbound_stats.remote_id),
+        Some(StatsReportType::RemoteOutboundRTP(_))
+    ));
+    match inbound_stats
+        .codec_id
+        .as_ref()
+        .and_then(|id| answer_stats.reports.get(id))
+    {
+        Some(StatsReportType::Codec(codec_stats)) => {
+            assert_eq!(codec_stats.mime_type, MIME_TYPE_VP8);
+        }
+        _ => panic!("missed the codec of the inbound stat"),
+    }
 
     close_pair_now(&pc_offer, &pc_answer).await;
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_restart_ice() -> Result<()> {
+    let (mut offer_pc, mut answer_pc, _) = create_vnet_pair().await?;
+
+    let ice_ufrag = |desc: &RTCSessionDescription| {
+        desc.sdp
+            .lines()
+            .find_map(|line| line.strip_prefix("a=ice-ufrag:"))
+            .map(|ufrag| ufrag.to_owned())
+    };
+
+    let count = Arc::new(AtomicU32::new(0));
+    {
+        let count = Arc::clone(&count);
+        offer_pc.on_negotiation_needed(Box::new(move || {
+            let count = Arc::clone(&count);
+            Box::pin(async move {
+                count.fetch_add(1, Ordering::SeqCst);
+            })
+        }));
+    }
+
+    let peer_connection_connected = WaitGroup::new();
+    until_connection_state(
+        &mut offer_pc,
+        &peer_connection_connected,
+        RTCPeerConnectionState::Connected,
+    )
+    .await;
+    until_connection_state(
+        &mut answer_pc,
+        &peer_connection_connected,
+        RTCPeerConnectionState::Connected,
+    )
+    .await;
+
+    signal_pair(&mut offer_pc, &mut answer_pc).await?;
+    peer_connection_connected.wait().await;
+    offer_pc.internal.ops.done().await;
+    count.store(0, Ordering::SeqCst);
+
+    let first_ufrag = offer_pc
+        .local_description()
+        .await
+        .and_then(|d| ice_ufrag(&d));
+    assert!(first_ufrag.is_some());
+
+    offer_pc.restart_ice().await?;
+    offer_pc.internal.ops.done().await;
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+
+    let offer = offer_pc.create_offer(None).await?;
+    let restarted_ufrag = ice_ufrag(&offer);
+    assert!(restarted_ufrag.is_some());
+    assert_ne!(first_ufrag, restarted_ufrag);
+
+    // The request is consumed by the offer, the following one keeps the new credentials
+    let offer = offer_pc.create_offer(None).await?;
+    assert_eq!(restarted_ufrag, ice_ufrag(&offer));
+
+    close_pair_now(&offer_pc, &answer_pc).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_negotiation_needed() -> Result<()> {
+    let (mut offer_pc, mut answer_pc, _) = create_vnet_pair().await?;
+
+    // The initial negotiation happens without a handler, which must not keep a handler set
+    // later from being invoked
+    let peer_connection_connected = WaitGroup::new();
+    until_connection_state(
+        &mut offer_pc,
+        &peer_connection_connected,
+        RTCPeerConnectionState::Connected,
+    )
+    .await;
+    until_connection_state(
+        &mut answer_pc,
+        &peer_connection_connected,
+        RTCPeerConnectionState::Connected,
+    )
+    .await;
+    signal_pair(&mut offer_pc, &mut answer_pc).await?;
+    peer_connection_connected.wait().await;
+    offer_pc.internal.ops.done().await;
+
+    let count = Arc::new(AtomicU32::new(0));
+    {
+        let count = Arc::clone(&count);
+        offer_pc.on_negotiation_needed(Box::new(move || {
+            let count = Arc::clone(&count);
+            Box::pin(async move {
+                count.fetch_add(1, Ordering::SeqCst);
+            })
+        }));
+    }
+
+    // Changes in a row are debounced into a single event
+    for _ in 0..3 {
+        offer_pc
+            .add_transceiver_from_kind(RTPCodecType::Video, &[])
+            .await?;
+    }
+    offer_pc.internal.ops.done().await;
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+
+    // Not fired again while the negotiation is in progress
+    let offer = offer_pc.create_offer(None).await?;
+    offer_pc.set_local_description(offer.clone()).await?;
+    let transceivers = offer_pc.get_transceivers().await;
+    transceivers[0]
+        .set_direction(RTCRtpTransceiverDirection::Recvonly)
+        .await;
+    offer_pc.internal.ops.done().await;
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+
+    answer_pc.set_remote_description(offer).await?;
+    let answer = answer_pc.create_answer(None).await?;
+    answer_pc.set_local_description(answer.clone()).await?;
+    offer_pc.set_remote_description(answer).await?;
+
+    // Back in stable, the direction changed meanwhile still needs a negotiation
+    offer_pc.internal.ops.done().await;
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+
+    close_pair_now(&offer_pc, &answer_pc).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bandwidth_estimate() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+    let (pc, _) = new_pair(&api).await?;
+    assert_eq!(None, pc.bandwidth_estimate());
+    pc.close().await?;
+
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let mut s = SettingEngine::default();
+    s.set_bandwidth_estimator(Some(
+        BandwidthEstimator::builder().with_initial_bitrate(500_000),
+    ));
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_setting_engine(s)
+        .build();
+
+    let (mut offerer, mut answerer) = new_pair(&api).await?;
+    assert_eq!(
+        Some(500_000),
+        offerer.bandwidth_estimate().map(|e| e.target_bitrate)
+    );
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    offerer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (done_tx, done_rx) = mpsc::channel::<()>(1);
+    offerer.on_bandwidth_estimate(Box::new(move |estimate: BandwidthEstimate| {
+        let done_tx = done_tx.clone();
+        Box::pin(async move {
+            // the receiver reports without loss increase the target bitrate
+            if estimate.target_bitrate > 500_000 {
+                let _ = done_tx.try_send(());
+            }
+        })
+    }));
+
+    let (ssrc_tx, mut ssrc_rx) = mpsc::channel::<SSRC>(1);
+    answerer.on_track(Box::new(
+        move |track: Option<Arc<TrackRemote>>, _: Option<Arc<RTCRtpReceiver>>| {
+            let ssrc_tx = ssrc_tx.clone();
+            Box::pin(async move {
+                if let Some(t) = track {
+                    let _ = ssrc_tx.try_send(t.ssrc());
+                }
+            })
+        },
+    ));
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+
+    let answerer = Arc::new(answerer);
+    let answerer2 = Arc::clone(&answerer);
+    tokio::spawn(async move {
+        let ssrc = match ssrc_rx.recv().await {
+            Some(ssrc) => ssrc,
+            None => return,
+        };
+        loop {
+            let rr = rtcp::receiver_report::ReceiverReport {
+                ssrc: 1,
+                reports: vec![rtcp::reception_report::ReceptionReport {
+                    ssrc,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            if answerer2.write_rtcp(&[Box::new(rr)]).await.is_err() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    });
+
+    send_video_until_done(done_rx, vec![track], Bytes::from_static(&[0x00]), None).await;
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_events() -> Result<()> {
+    let (mut offer_pc, mut answer_pc, _) = create_vnet_pair().await?;
+
+    let mut offer_candidates = offer_pc.ice_candidates();
+    let mut answer_events = answer_pc.events();
+
+    signal_pair(&mut offer_pc, &mut answer_pc).await?;
+
+    // The gathering is complete once the offer is signaled
+    let mut candidates = vec![];
+    while let Some(candidate) = offer_candidates.next().await {
+        match candidate {
+            Some(candidate) => candidates.push(candidate),
+            None => break,
+        }
+    }
+    assert!(!candidates.is_empty());
+
+    let (mut data_channel, mut connected) = (None, false);
+    while data_channel.is_none() || !connected {
+        let event = tokio::time::timeout(Duration::from_secs(10), answer_events.next())
+            .await
+            .expect("timed out waiting for the events");
+        match event {
+            Some(RTCPeerConnectionEvent::DataChannel(d)) => data_channel = Some(d),
+            Some(RTCPeerConnectionEvent::PeerConnectionStateChange(
+                RTCPeerConnectionState::Connected,
+            )) => connected = true,
+            Some(_) => {}
+            None => panic!("the events ended"),
+        }
+    }
+    assert_eq!(data_channel.unwrap().label(), "initial_data_channel");
+
+    close_pair_now(&offer_pc, &answer_pc).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_close_orderly() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+    let (mut offerer, mut answerer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    offerer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+    offerer.create_data_channel("orderly", None).await?;
+
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    let (closed_tx, mut closed_rx) = mpsc::channel::<()>(1);
+    answerer.on_data_channel(Box::new(move |d: Arc<RTCDataChannel>| {
+        let (open_tx, closed_tx) = (open_tx.clone(), closed_tx.clone());
+        Box::pin(async move {
+            d.on_open(Box::new(move || {
+                Box::pin(async move {
+                    let _ = open_tx.try_send(());
+                })
+            }));
+            d.on_close(Box::new(move || {
+                let _ = closed_tx.try_send(());
+                Box::pin(async {})
+            }));
+        })
+    }));
+
+    let (track_tx, mut track_rx) = mpsc::channel::<()>(1);
+    let (bye_tx, mut bye_rx) = mpsc::channel::<()>(1);
+    answerer.on_track(Box::new(
+        move |track: Option<Arc<TrackRemote>>, receiver: Option<Arc<RTCRtpReceiver>>| {
+            let (track_tx, bye_tx) = (track_tx.clone(), bye_tx.clone());
+            Box::pin(async move {
+                let (track, receiver) = match (track, receiver) {
+                    (Some(track), Some(receiver)) => (track, receiver),
+                    _ => return,
+                };
+                let _ = track_tx.try_send(());
+                tokio::spawn(async move {
+                    while let Ok((pkts, _)) = receiver.read_rtcp().await {
+                        let bye = pkts.iter().any(|p| {
+                            p.as_any()
+                                .downcast_ref::<rtcp::goodbye::Goodbye>()
+                                .map_or(false, |bye| bye.sources.contains(&track.ssrc()))
+                        });
+                        if bye {
+                            let _ = bye_tx.try_send(());
+                            return;
+                        }
+                    }
+                });
+            })
+        },
+    ));
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+
+    let (done_tx, done_rx) = mpsc::channel::<()>(1);
+    tokio::spawn(send_video_until_done(
+        done_rx,
+        vec![track],
+        Bytes::from_static(&[0x00]),
+        None,
+    ));
+    let _ = track_rx.recv().await;
+    let _ = open_rx.recv().await;
+    drop(done_tx);
+
+    offerer.close().await?;
+
+    // The answerer learns of the close well before its ICE timeouts
+    let deadline = Duration::from_secs(1);
+    tokio::time::timeout(deadline, bye_rx.recv())
+        .await
+        .expect("the remote track didn't receive a BYE");
+    tokio::time::timeout(deadline, closed_rx.recv())
+        .await
+        .expect("the remote data channel wasn't closed");
+
+    answerer.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_close_timeout() -> Result<()> {
+    let mut s = SettingEngine::default();
+    s.set_close_timeout(Some(Duration::from_millis(200)));
+    let api = APIBuilder::new().with_setting_engine(s).build();
+    let (mut offerer, mut answerer) = new_pair(&api).await?;
+    offerer.create_data_channel("timeout", None).await?;
+
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    answerer.on_data_channel(Box::new(move |d: Arc<RTCDataChannel>| {
+        let open_tx = open_tx.clone();
+        Box::pin(async move {
+            d.on_open(Box::new(move || {
+                Box::pin(async move {
+                    let _ = open_tx.try_send(());
+                })
+            }));
+        })
+    }));
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+    let _ = open_rx.recv().await;
+
+    // Without the answerer the shutdown isn't acknowledged, close gives up at the deadline
+    answerer.internal.ice_transport.stop().await?;
+    let start = std::time::Instant::now();
+    offerer.close().await?;
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    answerer.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_close_both_peers() -> Result<()> {
+    let api = APIBuilder::new().build();
+    let (mut offerer, mut answerer) = new_pair(&api).await?;
+    offerer.create_data_channel("both", None).await?;
+
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    answerer.on_data_channel(Box::new(move |d: Arc<RTCDataChannel>| {
+        let open_tx = open_tx.clone();
+        Box::pin(async move {
+            d.on_open(Box::new(move || {
+                Box::pin(async move {
+                    let _ = open_tx.try_send(());
+                })
+            }));
+        })
+    }));
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+    let _ = open_rx.recv().await;
+
+    // On a multi-threaded runtime the answerer closes right after the SCTP shutdown, before
+    // its data channel learns of it
+    offerer.close().await?;
+    answerer.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transport_state_change_reasons() -> Result<()> {
+    let api = APIBuilder::new().build();
+    let (mut offerer, mut answerer) = new_pair(&api).await?;
+
+    let (offerer_tx, mut offerer_rx) = mpsc::unbounded_channel();
+    offerer.on_transport_state_change(Box::new(move |c: RTCTransportStateChange| {
+        let _ = offerer_tx.send(c);
+        Box::pin(async {})
+    }));
+    let (answerer_tx, mut answerer_rx) = mpsc::unbounded_channel();
+    answerer.on_transport_state_change(Box::new(move |c: RTCTransportStateChange| {
+        let _ = answerer_tx.send(c);
+        Box::pin(async {})
+    }));
+
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    answerer.on_data_channel(Box::new(move |d: Arc<RTCDataChannel>| {
+        let open_tx = open_tx.clone();
+        Box::pin(async move {
+            d.on_open(Box::new(move || {
+                Box::pin(async move {
+                    let _ = open_tx.try_send(());
+                })
+            }));
+        })
+    }));
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+    let _ = open_rx.recv().await;
+
+    offerer.close().await?;
+
+    async fn until_change(
+        rx: &mut mpsc::UnboundedReceiver<RTCTransportStateChange>,
+        expected: RTCTransportStateChange,
+    ) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while let Some(c) = rx.recv().await {
+                if c == expected {
+                    return;
+                }
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("{} wasn't reported", expected));
+    }
+
+    until_change(
+        &mut offerer_rx,
+        RTCTransportStateChange::Dtls {
+            state: RTCDtlsTransportState::Closed,
+            reason: Some(RTCTransportStateReason::LocalClose),
+        },
+    )
+    .await;
+
+    // The answerer learns of the close from the SCTP shutdown and the close_notify alert
+    until_change(
+        &mut answerer_rx,
+        RTCTransportStateChange::Sctp {
+            state: RTCSctpTransportState::Closed,
+            reason: Some(RTCTransportStateReason::RemoteClose),
+        },
+    )
+    .await;
+    until_change(
+        &mut answerer_rx,
+        RTCTransportStateChange::Dtls {
+            state: RTCDtlsTransportState::Closed,
+            reason: Some(RTCTransportStateReason::RemoteClose),
+        },
+    )
+    .await;
+    assert_eq!(
+        answerer.internal.dtls_transport.state_reason(),
+        Some(RTCTransportStateReason::RemoteClose)
+    );
+
+    answerer.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_bundle() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let config = || RTCConfiguration {
+        bundle_policy: RTCBundlePolicy::MaxBundle,
+        ..Default::default()
+    };
+    let offer_pc = api.new_peer_connection(config()).await?;
+    let answer_pc = api.new_peer_connection(config()).await?;
+
+    offer_pc
+        .add_transceiver_from_kind(RTPCodecType::Audio, &[])
+        .await?;
+    offer_pc
+        .add_transceiver_from_kind(RTPCodecType::Video, &[])
+        .await?;
+
+    // Only the first section of the offer has a transport of its own
+    let offer = offer_pc.create_offer(None).await?;
+    let parsed = offer.unmarshal()?;
+    assert_eq!(parsed.media_descriptions.len(), 2);
+    assert_ne!(parsed.media_descriptions[0].media_name.port.value, 0);
+    assert!(parsed.media_descriptions[0]
+        .attribute(ATTR_KEY_BUNDLE_ONLY)
+        .is_none());
+    assert_eq!(parsed.media_descriptions[1].media_name.port.value, 0);
+    assert!(parsed.media_descriptions[1]
+        .attribute(ATTR_KEY_BUNDLE_ONLY)
+        .is_some());
+    offer_pc.set_local_description(offer.clone()).await?;
+
+    // The answerer accepts the bundle-only section instead of treating it as rejected
+    answer_pc.set_remote_description(offer).await?;
+    let answer = answer_pc.create_answer(None).await?;
+    let parsed = answer.unmarshal()?;
+    assert_eq!(parsed.media_descriptions.len(), 2);
+    for media in &parsed.media_descriptions {
+        assert_ne!(media.media_name.port.value, 0);
+        assert!(media.attribute(ATTR_KEY_BUNDLE_ONLY).is_none());
+    }
+    answer_pc.set_local_description(answer.clone()).await?;
+
+    // An answer which doesn't bundle its sections can't be used with max-bundle
+    let unbundled = answer
+        .sdp
+        .lines()
+        .filter(|line| !line.starts_with("a=group:BUNDLE"))
+        .map(|line| format!("{}\r\n", line))
+        .collect::<String>();
+    let result = offer_pc
+        .set_remote_description(RTCSessionDescription::answer(unbundled)?)
+        .await;
+    assert_eq!(result, Err(Error::ErrSessionDescriptionNotBundled));
+
+    offer_pc.set_remote_description(answer).await?;
+
+    close_pair_now(&offer_pc, &answer_pc).await;
+
+    Ok(())
+}
+
+fn candidate_components(desc: &RTCSessionDescription) -> Result<HashSet<u16>> {
+    let parsed = desc.unmarshal()?;
+    Ok(parsed
+        .media_descriptions
+        .iter()
+        .flat_map(|media| media.attributes.iter())
+        .filter(|a| a.is_ice_candidate())
+        .filter_map(|a| a.value.as_ref()?.split_whitespace().nth(1)?.parse().ok())
+        .collect())
+}
+
+#[tokio::test]
+async fn test_rtcp_mux_negotiate() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let config = || RTCConfiguration {
+        rtcp_mux_policy: RTCRtcpMuxPolicy::Negotiate,
+        ..Default::default()
+    };
+    let offerer = api.new_peer_connection(config()).await?;
+    let answerer = api.new_peer_connection(config()).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    let sender = offerer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (pli_tx, mut pli_rx) = mpsc::channel::<()>(1);
+    tokio::spawn(async move {
+        while let Ok((pkts, _)) = sender.read_rtcp().await {
+            if pkts
+                .iter()
+                .any(|p| p.as_any().downcast_ref::<PictureLossIndication>().is_some())
+            {
+                let _ = pli_tx.try_send(());
+                return;
+            }
+        }
+    });
+
+    let (track_tx, mut track_rx) = mpsc::channel::<u32>(1);
+    answerer.on_track(Box::new(
+        move |track: Option<Arc<TrackRemote>>, _: Option<Arc<RTCRtpReceiver>>| {
+            let track_tx = track_tx.clone();
+            Box::pin(async move {
+                if let Some(track) = track {
+                    let _ = track_tx.try_send(track.ssrc());
+                }
+            })
+        },
+    ));
+
+    // The offer has candidates of its own for RTCP, and still offers to multiplex it
+    let offer = offerer.create_offer(None).await?;
+    let mut offer_gathering_complete = offerer.gathering_complete_promise().await;
+    offerer.set_local_description(offer).await?;
+    let _ = offer_gathering_complete.recv().await;
+    let offer = offerer
+        .local_description()
+        .await
+        .ok_or_else(|| Error::new("non local description".to_owned()))?;
+    assert!(offer.sdp.contains("a=rtcp-mux\r\n"));
+    assert!(candidate_components(&offer)?.contains(&COMPONENT_RTCP));
+
+    // The answerer gathers candidates for RTCP when the offer doesn't multiplex it
+    let offer = RTCSessionDescription::offer(offer.sdp.replace("a=rtcp-mux\r\n", ""))?;
+    answerer.set_remote_description(offer).await?;
+    let answer = answerer.create_answer(None).await?;
+    let mut answer_gathering_complete = answerer.gathering_complete_promise().await;
+    answerer.set_local_description(answer).await?;
+    let _ = answer_gathering_complete.recv().await;
+    let answer = answerer
+        .local_description()
+        .await
+        .ok_or_else(|| Error::new("non local description".to_owned()))?;
+    assert!(!answer.sdp.contains("a=rtcp-mux\r\n"));
+    assert!(candidate_components(&answer)?.contains(&COMPONENT_RTCP));
+
+    offerer.set_remote_description(answer).await?;
+    assert!(offerer
+        .internal
+        .ice_gatherer
+        .get_rtcp_agent()
+        .await
+        .is_some());
+
+    let (done_tx, done_rx) = mpsc::channel::<()>(1);
+    tokio::spawn(send_video_until_done(
+        done_rx,
+        vec![track],
+        Bytes::from_static(&[0x00]),
+        None,
+    ));
+
+    // RTCP flows over the transport of its own component
+    let deadline = Duration::from_secs(10);
+    let media_ssrc = tokio::time::timeout(deadline, track_rx.recv())
+        .await
+        .expect("the remote track wasn't received")
+        .unwrap();
+    tokio::time::timeout(deadline, async {
+        loop {
+            let pli = PictureLossIndication {
+                sender_ssrc: 0,
+                media_ssrc,
+            };
+            answerer.write_rtcp(&[Box::new(pli)]).await?;
+            tokio::select! {
+                _ = pli_rx.recv() => return Result::<()>::Ok(()),
+                _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+            }
+        }
+    })
+    .await
+    .expect("the PLI wasn't received")?;
+    drop(done_tx);
+
+    for pc in [&offerer, &answerer] {
+        assert!(pc.sctp().transport().rtcp_conn().await.is_some());
+    }
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtcp_mux_negotiate_muxed() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let config = || RTCConfiguration {
+        rtcp_mux_policy: RTCRtcpMuxPolicy::Negotiate,
+        ..Default::default()
+    };
+    let mut offerer = api.new_peer_connection(config()).await?;
+    let mut answerer = api.new_peer_connection(config()).await?;
+    offerer
+        .add_transceiver_from_kind(RTPCodecType::Video, &[])
+        .await?;
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut offerer, &wg, RTCPeerConnectionState::Connected).await;
+    until_connection_state(&mut answerer, &wg, RTCPeerConnectionState::Connected).await;
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+
+    // The RTCP agent of the offerer is closed once the answer multiplexes RTCP
+    let answer = answerer
+        .local_description()
+        .await
+        .ok_or_else(|| Error::new("non local description".to_owned()))?;
+    assert!(answer.sdp.contains("a=rtcp-mux\r\n"));
+    assert!(answerer
+        .internal
+        .ice_gatherer
+        .get_rtcp_agent()
+        .await
+        .is_none());
+    assert!(offerer
+        .internal
+        .ice_gatherer
+        .get_rtcp_agent()
+        .await
+        .is_none());
+
+    wg.wait().await;
+
+    for pc in [&offerer, &answerer] {
+        assert!(pc.sctp().transport().rtcp_conn().await.is_none());
+    }
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_remote_rtp_stats() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+    let (mut offerer, mut answerer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    let sender = offerer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+    // The reports are read along with the RTCP packets
+    tokio::spawn(async move { while sender.read_rtcp().await.is_ok() {} });
+
+    let (inbound_tx, mut inbound_rx) = mpsc::channel(1);
+    offerer.on_remote_inbound_rtp_stats(Box::new(move |stats| {
+        let _ = inbound_tx.try_send(stats);
+        Box::pin(async {})
+    }));
+
+    let mut events = answerer.events();
+    signal_pair(&mut offerer, &mut answerer).await?;
+
+    let (done_tx, done_rx) = mpsc::channel::<()>(1);
+    tokio::spawn(send_video_until_done(
+        done_rx,
+        vec![track],
+        Bytes::from_static(&[0x00]),
+        None,
+    ));
+
+    let deadline = Duration::from_secs(10);
+    let ssrc = tokio::time::timeout(deadline, async {
+        while let Some(event) = events.recv().await {
+            if let RTCPeerConnectionEvent::Track(track, receiver) = event {
+                tokio::spawn(async move { while receiver.read_rtcp().await.is_ok() {} });
+                return track.ssrc();
+            }
+        }
+        panic!("the events ended before the remote track");
+    })
+    .await
+    .expect("the remote track wasn't received");
+
+    // The receiver's view of the stream sent by the offerer
+    let sent_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    let remote_inbound = tokio::time::timeout(deadline, async {
+        loop {
+            let rr = rtcp::receiver_report::ReceiverReport {
+                ssrc: 1,
+                reports: vec![rtcp::reception_report::ReceptionReport {
+                    ssrc,
+                    fraction_lost: 64,
+                    total_lost: 3,
+                    jitter: 9000,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            answerer.write_rtcp(&[Box::new(rr)]).await?;
+            tokio::select! {
+                stats = inbound_rx.recv() => return Result::<_>::Ok(stats.unwrap()),
+                _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+            }
+        }
+    })
+    .await
+    .expect("the remote-inbound-rtp stats weren't reported")?;
+    assert_eq!(remote_inbound.ssrc, ssrc);
+    assert_eq!(remote_inbound.kind, "video");
+    assert_eq!(remote_inbound.packets_lost, 3);
+    assert!((remote_inbound.fraction_lost - 0.25).abs() < 0.01);
+    assert_eq!(remote_inbound.jitter, 0.1);
+
+    // The sender's view of the stream received by the answerer
+    let remote_outbound = tokio::time::timeout(deadline, async {
+        loop {
+            let sr = rtcp::sender_report::SenderReport {
+                ssrc,
+                ntp_time: rtp::extension::abs_send_time_extension::unix2ntp(sent_at),
+                packet_count: 10,
+                octet_count: 1000,
+                ..Default::default()
+            };
+            offerer.write_rtcp(&[Box::new(sr)]).await?;
+            tokio::select! {
+                event = events.recv() => {
+                    if let Some(RTCPeerConnectionEvent::RemoteOutboundRTPStats(stats)) = event {
+                        return Result::<_>::Ok(stats);
+                    }
+                }
+                _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+            }
+        }
+    })
+    .await
+    .expect("the remote-outbound-rtp stats weren't reported")?;
+    assert_eq!(remote_outbound.ssrc, ssrc);
+    assert_eq!(remote_outbound.packets_sent, 10);
+    assert_eq!(remote_outbound.bytes_sent, 1000);
+    assert_eq!(remote_outbound.remote_timestamp, Some(sent_at));
+    drop(done_tx);
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
@@ -24,7 +24,9 @@ pub enum RTCBundlePolicy {
 
     /// BundlePolicyMaxBundle indicates to gather ICE candidates for only
     /// one track. If the remote endpoint is not bundle-aware, negotiate only
-    /// one media track.
+    /// one media track. The m= sections of the offers which follow the first
+    /// one are bundle-only, and a remote description with m= sections outside
+    /// of its BUNDLE group is rejected.
     #[serde(rename = "max-bundle")]
     MaxBundle = 3,
 }
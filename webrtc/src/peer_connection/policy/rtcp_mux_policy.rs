@@ -16,14 +16,15 @@ pub enum RTCRtcpMuxPolicy {
 
     /// RTCPMuxPolicyRequire indicates to gather ICE candidates only for
     /// RTP and multiplex RTCP on the RTP candidates. If the remote endpoint is
-    /// not capable of rtcp-mux, session negotiation will fail.
+    /// not capable of rtcp-mux, session negotiation will fail. This is the
+    /// default policy.
     #[serde(rename = "require")]
     Require = 2,
 }
 
 impl Default for RTCRtcpMuxPolicy {
     fn default() -> Self {
-        RTCRtcpMuxPolicy::Negotiate
+        RTCRtcpMuxPolicy::Require
     }
 }
 
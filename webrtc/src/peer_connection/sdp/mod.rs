@@ -22,10 +22,13 @@ use crate::peer_connection::MEDIA_SECTION_APPLICATION;
 use crate::SDP_ATTRIBUTE_RID;
 use ice::candidate::candidate_base::unmarshal_candidate;
 use ice::candidate::Candidate;
-use sdp::description::common::{Address, ConnectionInformation};
+use sdp::description::common::{Address, Attribute, ConnectionInformation};
 use sdp::description::media::{MediaDescription, MediaName, RangedPort};
 use sdp::description::session::*;
 use sdp::extmap::ExtMap;
+use sdp::msid::Msid;
+use sdp::simulcast::{Rid, RidDirection, Simulcast, SimulcastRid};
+use sdp::ssrc::SsrcGroup;
 use sdp::util::ConnectionRole;
 use std::collections::HashMap;
 use std::convert::From;
@@ -75,8 +78,8 @@ pub(crate) fn track_details_from_sdp(
         let mut tracks_in_media_section = vec![];
         let mut rtx_repair_flows = HashMap::new();
 
-        let mut stream_id = "";
-        let mut track_id = "";
+        let mut stream_id = String::new();
+        let mut track_id = String::new();
 
         // If media section is recvonly or inactive skip
         if media.attribute(ATTR_KEY_RECV_ONLY).is_some()
@@ -98,35 +101,28 @@ pub(crate) fn track_details_from_sdp(
         for attr in &media.attributes {
             match attr.key.as_str() {
                 ATTR_KEY_SSRCGROUP => {
-                    if let Some(value) = &attr.value {
-                        let split: Vec<&str> = value.split(' ').collect();
-                        if split[0] == SEMANTIC_TOKEN_FLOW_IDENTIFICATION {
-                            // Add rtx ssrcs to blacklist, to avoid adding them as tracks
-                            // Essentially lines like `a=ssrc-group:FID 2231627014 632943048` are processed by this section
-                            // as this declares that the second SSRC (632943048) is a rtx repair flow (RFC4588) for the first
-                            // (2231627014) as specified in RFC5576
-                            if split.len() == 3 {
-                                let base_ssrc = match split[1].parse::<u32>() {
-                                    Ok(ssrc) => ssrc,
-                                    Err(err) => {
-                                        log::warn!("Failed to parse SSRC: {}", err);
-                                        continue;
-                                    }
-                                };
-                                let rtx_repair_flow = match split[2].parse::<u32>() {
-                                    Ok(n) => n,
-                                    Err(err) => {
-                                        log::warn!("Failed to parse SSRC: {}", err);
-                                        continue;
-                                    }
-                                };
-                                rtx_repair_flows.insert(rtx_repair_flow, base_ssrc);
-                                // Remove if rtx was added as track before
-                                filter_track_with_ssrc(
-                                    &mut tracks_in_media_section,
-                                    rtx_repair_flow as SSRC,
-                                );
+                    let group =
+                        match SsrcGroup::unmarshal(attr.value.as_deref().unwrap_or_default()) {
+                            Ok(group) => group,
+                            Err(err) => {
+                                log::warn!("Failed to parse ssrc-group: {}", err);
+                                continue;
                             }
+                        };
+                    if group.semantics == SEMANTIC_TOKEN_FLOW_IDENTIFICATION {
+                        // Add rtx ssrcs to blacklist, to avoid adding them as tracks
+                        // Essentially lines like `a=ssrc-group:FID 2231627014 632943048` are processed by this section
+                        // as this declares that the second SSRC (632943048) is a rtx repair flow (RFC4588) for the first
+                        // (2231627014) as specified in RFC5576
+                        if let (Some(base_ssrc), Some(rtx_repair_flow)) =
+                            (group.primary_ssrc(), group.repair_ssrc())
+                        {
+                            rtx_repair_flows.insert(rtx_repair_flow, base_ssrc);
+                            // Remove if rtx was added as track before
+                            filter_track_with_ssrc(
+                                &mut tracks_in_media_section,
+                                rtx_repair_flow as SSRC,
+                            );
                         }
                     }
                 }
@@ -135,15 +131,13 @@ pub(crate) fn track_details_from_sdp(
                 // in the browser and can be used to figure out which tracks belong to the same stream. The browser should
                 // figure this out automatically when an ontrack event is emitted on RTCPeerConnection.
                 ATTR_KEY_MSID => {
-                    if let Some(value) = &attr.value {
-                        let mut split = value.split(' ');
-
-                        if let (Some(sid), Some(tid), None) =
-                            (split.next(), split.next(), split.next())
-                        {
-                            stream_id = sid;
-                            track_id = tid;
-                        }
+                    if let Ok(Msid {
+                        stream_id: sid,
+                        track_id: Some(tid),
+                    }) = Msid::unmarshal(attr.value.as_deref().unwrap_or_default())
+                    {
+                        stream_id = sid;
+                        track_id = tid;
                     }
                 }
 
@@ -162,9 +156,18 @@ pub(crate) fn track_details_from_sdp(
                             continue; // This ssrc is a RTX repair flow, ignore
                         }
 
-                        if split.len() == 3 && split[1].starts_with("msid:") {
-                            stream_id = &split[1]["msid:".len()..];
-                            track_id = split[2];
+                        if let Some(msid) = value
+                            .split_once(' ')
+                            .and_then(|(_, attribute)| attribute.strip_prefix("msid:"))
+                        {
+                            if let Ok(Msid {
+                                stream_id: sid,
+                                track_id: Some(tid),
+                            }) = Msid::unmarshal(msid)
+                            {
+                                stream_id = sid;
+                                track_id = tid;
+                            }
                         }
 
                         let mut track_idx = tracks_in_media_section.len();
@@ -187,16 +190,16 @@ pub(crate) fn track_details_from_sdp(
                         if track_idx < tracks_in_media_section.len() {
                             tracks_in_media_section[track_idx].mid = mid_value.to_owned();
                             tracks_in_media_section[track_idx].kind = codec_type;
-                            tracks_in_media_section[track_idx].stream_id = stream_id.to_owned();
-                            tracks_in_media_section[track_idx].id = track_id.to_owned();
+                            tracks_in_media_section[track_idx].stream_id = stream_id.clone();
+                            tracks_in_media_section[track_idx].id = track_id.clone();
                             tracks_in_media_section[track_idx].ssrcs = vec![ssrc];
                             tracks_in_media_section[track_idx].repair_ssrc = repair_ssrc;
                         } else {
                             let track_details = TrackDetails {
                                 mid: mid_value.to_owned(),
                                 kind: codec_type,
-                                stream_id: stream_id.to_owned(),
-                                id: track_id.to_owned(),
+                                stream_id: stream_id.clone(),
+                                id: track_id.clone(),
                                 ssrcs: vec![ssrc],
                                 repair_ssrc,
                                 ..Default::default()
@@ -214,8 +217,8 @@ pub(crate) fn track_details_from_sdp(
             let mut simulcast_track = TrackDetails {
                 mid: mid_value.to_owned(),
                 kind: codec_type,
-                stream_id: stream_id.to_owned(),
-                id: track_id.to_owned(),
+                stream_id: stream_id.clone(),
+                id: track_id.clone(),
                 rids: vec![],
                 ..Default::default()
             };
@@ -237,13 +240,15 @@ pub(crate) fn track_details_from_sdp(
     incoming_tracks
 }
 
-pub(crate) fn get_rids(media: &MediaDescription) -> HashMap<String, String> {
+pub(crate) fn get_rids(media: &MediaDescription) -> HashMap<String, Rid> {
     let mut rids = HashMap::new();
     for attr in &media.attributes {
         if attr.key.as_str() == SDP_ATTRIBUTE_RID {
-            if let Some(value) = &attr.value {
-                let split: Vec<&str> = value.split(' ').collect();
-                rids.insert(split[0].to_owned(), value.to_owned());
+            match Rid::unmarshal(attr.value.as_deref().unwrap_or_default()) {
+                Ok(rid) => {
+                    rids.insert(rid.id.clone(), rid);
+                }
+                Err(err) => log::warn!("Failed to parse rid {:?}: {}", attr.value, err),
             }
         }
     }
@@ -254,6 +259,7 @@ pub(crate) async fn add_candidates_to_media_descriptions(
     candidates: &[RTCIceCandidate],
     mut m: MediaDescription,
     ice_gathering_state: RTCIceGatheringState,
+    rtcp_candidates: bool,
 ) -> Result<MediaDescription> {
     let append_candidate_if_new = |c: &dyn Candidate, m: MediaDescription| -> MediaDescription {
         let marshaled = c.marshal();
@@ -271,6 +277,13 @@ pub(crate) async fn add_candidates_to_media_descriptions(
     for c in candidates {
         let candidate = c.to_ice()?;
 
+        // The candidates of a separate RTCP agent are signaled with their own component,
+        // otherwise each candidate is signaled for both the RTP and the RTCP components.
+        if rtcp_candidates {
+            m = append_candidate_if_new(&candidate, m);
+            continue;
+        }
+
         candidate.set_component(1);
         m = append_candidate_if_new(&candidate, m);
 
@@ -292,6 +305,7 @@ pub(crate) async fn add_candidates_to_media_descriptions(
 
 pub(crate) struct AddDataMediaSectionParams {
     should_add_candidates: bool,
+    rtcp_candidates: bool,
     mid_value: String,
     ice_params: RTCIceParameters,
     dtls_role: ConnectionRole,
@@ -334,19 +348,24 @@ pub(crate) async fn add_data_media_section(
     )
     .with_value_attribute(ATTR_KEY_MID.to_owned(), params.mid_value)
     .with_property_attribute(RTCRtpTransceiverDirection::Sendrecv.to_string())
-    .with_property_attribute("sctp-port:5000".to_owned())
     .with_ice_credentials(
         params.ice_params.username_fragment,
         params.ice_params.password,
     );
+    media.set_sctp_port(5000);
 
     for f in dtls_fingerprints {
         media = media.with_fingerprint(f.algorithm.clone(), f.value.to_uppercase());
     }
 
     if params.should_add_candidates {
-        media = add_candidates_to_media_descriptions(candidates, media, params.ice_gathering_state)
-            .await?;
+        media = add_candidates_to_media_descriptions(
+            candidates,
+            media,
+            params.ice_gathering_state,
+            params.rtcp_candidates,
+        )
+        .await?;
     }
 
     Ok(d.with_media(media))
@@ -373,9 +392,15 @@ pub(crate) async fn populate_local_candidates(
         };
 
         if !parsed.media_descriptions.is_empty() {
+            let rtcp_candidates = ice.get_rtcp_agent().await.is_some();
             let mut m = parsed.media_descriptions.remove(0);
-            m = match add_candidates_to_media_descriptions(&candidates, m, ice_gathering_state)
-                .await
+            m = match add_candidates_to_media_descriptions(
+                &candidates,
+                m,
+                ice_gathering_state,
+                rtcp_candidates,
+            )
+            .await
             {
                 Ok(m) => m,
                 Err(_) => return Some(sd.clone()),
@@ -395,6 +420,8 @@ pub(crate) async fn populate_local_candidates(
 
 pub(crate) struct AddTransceiverSdpParams {
     should_add_candidates: bool,
+    rtcp_candidates: bool,
+    rtcp_mux: bool,
     mid_value: String,
     dtls_role: ConnectionRole,
     ice_gathering_state: RTCIceGatheringState,
@@ -429,11 +456,17 @@ pub(crate) async fn add_transceiver_sdp(
         .with_ice_credentials(
             ice_params.username_fragment.clone(),
             ice_params.password.clone(),
-        )
-        .with_property_attribute(ATTR_KEY_RTCPMUX.to_owned())
-        .with_property_attribute(ATTR_KEY_RTCPRSIZE.to_owned());
+        );
+    if params.rtcp_mux {
+        media = media.with_property_attribute(ATTR_KEY_RTCPMUX.to_owned());
+    }
+    media = media.with_property_attribute(ATTR_KEY_RTCPRSIZE.to_owned());
 
-    let codecs = t.get_codecs().await;
+    let mut codecs = t.get_codecs().await;
+    if params.offered_direction.is_some() && !codecs.is_empty() {
+        // This is an answer, the fmtp echoed and the unoffered codecs follow the MediaEngine
+        codecs = media_engine.answer_codecs(codecs, t.kind, t.direction());
+    }
     for codec in &codecs {
         let name = codec
             .capability
@@ -466,39 +499,7 @@ pub(crate) async fn add_transceiver_sdp(
         }
 
         // Explicitly reject track if we don't have the codec
-        d = d.with_media(MediaDescription {
-            media_name: sdp::description::media::MediaName {
-                media: t.kind.to_string(),
-                port: RangedPort {
-                    value: 0,
-                    range: None,
-                },
-                protos: vec![
-                    "UDP".to_owned(),
-                    "TLS".to_owned(),
-                    "RTP".to_owned(),
-                    "SAVPF".to_owned(),
-                ],
-                formats: vec!["0".to_owned()],
-            },
-            media_title: None,
-            // We need to include connection information even if we're rejecting a track, otherwise Firefox will fail to
-            // parse the SDP with an error like:
-            // SIPCC Failed to parse SDP: SDP Parse Error on line 50:  c= connection line not specified for every media level, validation failed.
-            // In addition this makes our SDP compliant with RFC 4566 Section 5.7: https://datatracker.ietf.org/doc/html/rfc4566#section-5.7
-            connection_information: Some(ConnectionInformation {
-                network_type: "IN".to_owned(),
-                address_type: "IP4".to_owned(),
-                address: Some(Address {
-                    address: "0.0.0.0".to_owned(),
-                    ttl: None,
-                    range: None,
-                }),
-            }),
-            bandwidth: vec![],
-            encryption_key: None,
-            attributes: vec![],
-        });
+        d = d.with_media(rejected_media_description(&t.kind.to_string(), None));
         return Ok((d, false));
     }
 
@@ -514,41 +515,37 @@ pub(crate) async fn add_transceiver_sdp(
         });
     }
 
-    if !media_section.rid_map.is_empty() {
-        let mut recv_rids: Vec<String> = vec![];
-
-        for rid in media_section.rid_map.keys() {
-            media =
-                media.with_value_attribute(SDP_ATTRIBUTE_RID.to_owned(), rid.to_owned() + " recv");
-            recv_rids.push(rid.to_owned());
-        }
-        // Simulcast
-        media = media.with_value_attribute(
-            "simulcast".to_owned(),
-            "recv ".to_owned() + recv_rids.join(";").as_str(),
-        );
+    let mut simulcast = Simulcast::default();
+    for rid in media_section.rid_map.keys() {
+        media = media.with_rid(&Rid::new(rid, RidDirection::Recv));
+        simulcast.recv.push(vec![SimulcastRid::new(rid)]);
     }
 
     for mt in transceivers {
         if let Some(sender) = mt.sender().await {
+            // A sender sending simulcast describes its encodings with rid and simulcast
+            // attributes, the order of the encodings being the order of preference.
+            for rid in sender.rids() {
+                media = media.with_rid(&Rid::new(&rid, RidDirection::Send));
+                simulcast.send.push(vec![SimulcastRid::new(&rid)]);
+            }
+
             if let Some(track) = sender.track().await {
-                media = media.with_media_source(
-                    sender.ssrc,
-                    track.stream_id().to_owned(), /* cname */
-                    track.stream_id().to_owned(), /* streamLabel */
-                    track.id().to_owned(),
-                );
+                for encoding in &sender.track_encodings {
+                    media = media.with_media_source(
+                        encoding.ssrc,
+                        track.stream_id().to_owned(), /* cname */
+                        track.stream_id().to_owned(), /* streamLabel */
+                        track.id().to_owned(),
+                    );
+                }
 
                 // Send msid based on the configured track if we haven't already
                 // sent on this sender. If we have sent we must keep the msid line consistent, this
                 // is handled below.
                 if sender.initial_track_id().is_none() {
                     for stream_id in sender.associated_media_stream_ids() {
-                        media = media.with_property_attribute(format!(
-                            "msid:{} {}",
-                            stream_id,
-                            track.id()
-                        ));
+                        media = media.with_msid(&Msid::new(&stream_id, track.id()));
                     }
 
                     sender.set_initial_track_id(track.id().to_string())?;
@@ -569,8 +566,7 @@ pub(crate) async fn add_transceiver_sdp(
                 // description, "a=msid" line(s) MUST be generated according to the
                 // same rules as for an initial offer.
                 for stream_id in sender.associated_media_stream_ids() {
-                    media =
-                        media.with_property_attribute(format!("msid:{} {}", stream_id, track_id));
+                    media = media.with_msid(&Msid::new(&stream_id, &track_id));
                 }
 
                 break;
@@ -578,6 +574,10 @@ pub(crate) async fn add_transceiver_sdp(
         }
     }
 
+    if !simulcast.send.is_empty() || !simulcast.recv.is_empty() {
+        media = media.with_simulcast(&simulcast);
+    }
+
     let direction = match params.offered_direction {
         Some(offered_direction) => {
             use RTCRtpTransceiverDirection::*;
@@ -626,20 +626,135 @@ pub(crate) async fn add_transceiver_sdp(
     }
 
     if should_add_candidates {
-        media =
-            add_candidates_to_media_descriptions(candidates, media, ice_gathering_state).await?;
+        media = add_candidates_to_media_descriptions(
+            candidates,
+            media,
+            ice_gathering_state,
+            params.rtcp_candidates,
+        )
+        .await?;
     }
 
     Ok((d.with_media(media), true))
 }
 
+/// rejected_media_description returns a m= section of media rejected with port 0. The
+/// section of a stopped transceiver keeps its mid and is marked inactive.
+pub(crate) fn rejected_media_description(media: &str, mid: Option<&str>) -> MediaDescription {
+    let mut attributes = vec![];
+    if let Some(mid) = mid {
+        attributes.push(Attribute::new(
+            ATTR_KEY_MID.to_owned(),
+            Some(mid.to_owned()),
+        ));
+        attributes.push(Attribute::new(ATTR_KEY_INACTIVE.to_owned(), None));
+    }
+
+    MediaDescription {
+        media_name: sdp::description::media::MediaName {
+            media: media.to_owned(),
+            port: RangedPort {
+                value: 0,
+                range: None,
+            },
+            protos: vec![
+                "UDP".to_owned(),
+                "TLS".to_owned(),
+                "RTP".to_owned(),
+                "SAVPF".to_owned(),
+            ],
+            formats: vec!["0".to_owned()],
+        },
+        media_title: None,
+        // We need to include connection information even if we're rejecting a track, otherwise Firefox will fail to
+        // parse the SDP with an error like:
+        // SIPCC Failed to parse SDP: SDP Parse Error on line 50:  c= connection line not specified for every media level, validation failed.
+        // In addition this makes our SDP compliant with RFC 4566 Section 5.7: https://datatracker.ietf.org/doc/html/rfc4566#section-5.7
+        connection_information: Some(ConnectionInformation {
+            network_type: "IN".to_owned(),
+            address_type: "IP4".to_owned(),
+            address: Some(Address {
+                address: "0.0.0.0".to_owned(),
+                ttl: None,
+                range: None,
+            }),
+        }),
+        bandwidth: vec![],
+        encryption_key: None,
+        attributes,
+    }
+}
+
+/// is_rejected tells if a m= section is rejected, i.e. has port 0 without being
+/// bundle-only, https://datatracker.ietf.org/doc/html/rfc8843#section-6
+pub(crate) fn is_rejected(media: &MediaDescription) -> bool {
+    media.media_name.port.value == 0 && media.attribute(ATTR_KEY_BUNDLE_ONLY).is_none()
+}
+
+/// set_bundle_only marks a m= section which is only used if the remote peer accepts to
+/// bundle it, with port 0 so that it isn't given a transport of its own otherwise.
+fn set_bundle_only(media: &mut MediaDescription) {
+    media.media_name.port.value = 0;
+    media
+        .attributes
+        .push(Attribute::new(ATTR_KEY_BUNDLE_ONLY.to_owned(), None));
+}
+
+/// bundle_group returns the mids of the BUNDLE group of a description, if it has one.
+pub(crate) fn bundle_group(desc: &SessionDescription) -> Option<Vec<&str>> {
+    desc.attributes
+        .iter()
+        .filter(|a| a.key == ATTR_KEY_GROUP)
+        .filter_map(|a| a.value.as_deref())
+        .find_map(|value| {
+            let mut fields = value.split_whitespace();
+            if fields.next() == Some(SEMANTIC_TOKEN_BUNDLE) {
+                Some(fields.collect())
+            } else {
+                None
+            }
+        })
+}
+
+/// check_max_bundle verifies that all the m= sections of a remote description which aren't
+/// rejected are in its BUNDLE group, as they can only use the single transport of the
+/// max-bundle policy.
+pub(crate) fn check_max_bundle(desc: &SessionDescription) -> Result<()> {
+    let group = bundle_group(desc).unwrap_or_default();
+    for media in &desc.media_descriptions {
+        if is_rejected(media) {
+            continue;
+        }
+        match get_mid_value(media) {
+            Some(mid) if group.contains(&mid.as_str()) => {}
+            _ => return Err(Error::ErrSessionDescriptionNotBundled),
+        }
+    }
+
+    Ok(())
+}
+
+/// have_rtcp_mux returns true unless a description has audio or video sections and none of
+/// them multiplexes RTCP with RTP (RFC 5761 S5.1.1).
+pub(crate) fn have_rtcp_mux(desc: &SessionDescription) -> bool {
+    let mut media = desc
+        .media_descriptions
+        .iter()
+        .filter(|m| m.media_name.media != MEDIA_SECTION_APPLICATION && !is_rejected(m))
+        .peekable();
+    media.peek().is_none() || media.any(|m| m.attribute(ATTR_KEY_RTCPMUX).is_some())
+}
+
 #[derive(Default)]
 pub(crate) struct MediaSection {
     pub(crate) id: String,
     pub(crate) transceivers: Vec<Arc<RTCRtpTransceiver>>,
     pub(crate) data: bool,
-    pub(crate) rid_map: HashMap<String, String>,
+    pub(crate) rid_map: HashMap<String, Rid>,
     pub(crate) offered_direction: Option<RTCRtpTransceiverDirection>,
+    /// rejected is the media of a section rejected with port 0, because its transceiver is
+    /// stopped or the remote peer rejected it.
+    pub(crate) rejected: Option<String>,
 }
 
 pub(crate) struct PopulateSdpParams {
@@ -647,6 +762,16 @@ pub(crate) struct PopulateSdpParams {
     pub(crate) is_icelite: bool,
     pub(crate) connection_role: ConnectionRole,
     pub(crate) ice_gathering_state: RTCIceGatheringState,
+    pub(crate) extmap_allow_mixed: bool,
+    /// bundle_only marks the m= sections which follow the first one bundle-only, for the
+    /// offers of the max-bundle policy.
+    pub(crate) bundle_only: bool,
+    /// rtcp_mux adds a=rtcp-mux to the audio and video sections. Only an answer which
+    /// doesn't multiplex RTCP with RTP, with the negotiate policy, leaves it out.
+    pub(crate) rtcp_mux: bool,
+    /// rtcp_candidates is set when a separate agent gathers candidates for the RTCP
+    /// component, so the candidates are signaled with their own component.
+    pub(crate) rtcp_candidates: bool,
 }
 
 /// populate_sdp serializes a PeerConnections state into an SDP
@@ -665,25 +790,34 @@ pub(crate) async fn populate_sdp(
         vec![]
     };
 
-    let mut bundle_value = "BUNDLE".to_owned();
+    let mut bundle_value = SEMANTIC_TOKEN_BUNDLE.to_owned();
     let mut bundle_count = 0;
     let append_bundle = |mid_value: &str, value: &mut String, count: &mut i32| {
         *value = value.clone() + " " + mid_value;
         *count += 1;
     };
 
-    for (i, m) in media_sections.iter().enumerate() {
+    let mut candidates_added = false;
+    for m in media_sections {
         if m.data && !m.transceivers.is_empty() {
             return Err(Error::ErrSDPMediaSectionMediaDataChanInvalid);
         } else if m.transceivers.len() > 1 {
             return Err(Error::ErrSDPMediaSectionMultipleTrackInvalid);
         }
 
-        let should_add_candidates = i == 0;
+        if let Some(media) = &m.rejected {
+            d = d.with_media(rejected_media_description(media, Some(&m.id)));
+            continue;
+        }
+
+        // The candidates go in the first section which isn't rejected
+        let should_add_candidates = !candidates_added;
+        candidates_added = true;
 
         let should_add_id = if m.data {
             let params = AddDataMediaSectionParams {
                 should_add_candidates,
+                rtcp_candidates: params.rtcp_candidates,
                 mid_value: m.id.clone(),
                 ice_params: ice_params.clone(),
                 dtls_role: params.connection_role,
@@ -694,6 +828,8 @@ pub(crate) async fn populate_sdp(
         } else {
             let params = AddTransceiverSdpParams {
                 should_add_candidates,
+                rtcp_candidates: params.rtcp_candidates,
+                rtcp_mux: params.rtcp_mux,
                 mid_value: m.id.clone(),
                 dtls_role: params.connection_role,
                 ice_gathering_state: params.ice_gathering_state,
@@ -714,6 +850,11 @@ pub(crate) async fn populate_sdp(
         };
 
         if should_add_id {
+            if params.bundle_only && bundle_count > 0 {
+                if let Some(media) = d.media_descriptions.last_mut() {
+                    set_bundle_only(media);
+                }
+            }
             append_bundle(&m.id, &mut bundle_value, &mut bundle_count);
         }
     }
@@ -732,6 +873,11 @@ pub(crate) async fn populate_sdp(
         d = d.with_value_attribute(ATTR_KEY_ICELITE.to_owned(), ATTR_KEY_ICELITE.to_owned());
     }
 
+    if params.extmap_allow_mixed {
+        // RFC 8285 S6
+        d = d.with_property_attribute(ATTR_KEY_EXTMAP_ALLOW_MIXED.to_owned());
+    }
+
     Ok(d.with_value_attribute(ATTR_KEY_GROUP.to_owned(), bundle_value))
 }
 
@@ -744,6 +890,16 @@ pub(crate) fn get_mid_value(media: &MediaDescription) -> Option<&String> {
     None
 }
 
+/// is_extmap_allow_mixed_set returns if a description allows one- and two-byte header
+/// extensions to be mixed, at session level or in any of its media.
+pub(crate) fn is_extmap_allow_mixed_set(desc: &SessionDescription) -> bool {
+    desc.extmap_allow_mixed()
+        || desc
+            .media_descriptions
+            .iter()
+            .any(|media| media.extmap_allow_mixed())
+}
+
 pub(crate) fn get_peer_direction(media: &MediaDescription) -> RTCRtpTransceiverDirection {
     for a in &media.attributes {
         let direction = RTCRtpTransceiverDirection::from(a.key.as_str());
@@ -754,16 +910,38 @@ pub(crate) fn get_peer_direction(media: &MediaDescription) -> RTCRtpTransceiverD
     RTCRtpTransceiverDirection::Unspecified
 }
 
-pub(crate) fn extract_fingerprint(desc: &SessionDescription) -> Result<(String, String)> {
-    let mut fingerprints = vec![];
-
-    if let Some(fingerprint) = desc.attribute("fingerprint") {
-        fingerprints.push(fingerprint.clone());
-    }
+/// extract_fingerprints returns the fingerprints of the session and media level, each hash
+/// function algorithm must be given a single value.
+pub(crate) fn extract_fingerprints(desc: &SessionDescription) -> Result<Vec<RTCDtlsFingerprint>> {
+    let session_fingerprints = desc
+        .attributes
+        .iter()
+        .filter(|a| a.key == "fingerprint")
+        .filter_map(|a| a.value.as_ref());
+    let media_fingerprints = desc.media_descriptions.iter().flat_map(|m| {
+        m.attributes
+            .iter()
+            .filter(|a| a.key == "fingerprint")
+            .filter_map(|a| a.value.as_ref())
+    });
+
+    let mut fingerprints: Vec<RTCDtlsFingerprint> = vec![];
+    for fingerprint in session_fingerprints.chain(media_fingerprints) {
+        let parts: Vec<&str> = fingerprint.split(' ').collect();
+        if parts.len() != 2 {
+            return Err(Error::ErrSessionDescriptionInvalidFingerprint);
+        }
+        let (algorithm, value) = (parts[0].to_lowercase(), parts[1]);
 
-    for m in &desc.media_descriptions {
-        if let Some(fingerprint) = m.attribute("fingerprint").and_then(|o| o) {
-            fingerprints.push(fingerprint.to_owned());
+        match fingerprints.iter().find(|fp| fp.algorithm == algorithm) {
+            Some(fp) if !fp.value.eq_ignore_ascii_case(value) => {
+                return Err(Error::ErrSessionDescriptionConflictingFingerprints);
+            }
+            Some(_) => {}
+            None => fingerprints.push(RTCDtlsFingerprint {
+                algorithm,
+                value: value.to_owned(),
+            }),
         }
     }
 
@@ -771,18 +949,7 @@ pub(crate) fn extract_fingerprint(desc: &SessionDescription) -> Result<(String,
         return Err(Error::ErrSessionDescriptionNoFingerprint);
     }
 
-    for m in 1..fingerprints.len() {
-        if fingerprints[m] != fingerprints[0] {
-            return Err(Error::ErrSessionDescriptionConflictingFingerprints);
-        }
-    }
-
-    let parts: Vec<&str> = fingerprints[0].split(' ').collect();
-    if parts.len() != 2 {
-        return Err(Error::ErrSessionDescriptionInvalidFingerprint);
-    }
-
-    Ok((parts[1].to_owned(), parts[0].to_owned()))
+    Ok(fingerprints)
 }
 
 pub(crate) async fn extract_ice_details(
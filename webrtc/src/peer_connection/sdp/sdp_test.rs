@@ -12,45 +12,71 @@ use rcgen::KeyPair;
 use sdp::description::common::Attribute;
 
 #[test]
-fn test_extract_fingerprint() -> Result<()> {
+fn test_extract_fingerprints() -> Result<()> {
+    let fingerprint = |value: &str| Attribute {
+        key: "fingerprint".to_owned(),
+        value: Some(value.to_owned()),
+    };
+
     //"Good Session Fingerprint"
     {
         let s = SessionDescription {
-            attributes: vec![Attribute {
-                key: "fingerprint".to_owned(),
-                value: Some("foo bar".to_owned()),
-            }],
+            attributes: vec![fingerprint("foo bar")],
             ..Default::default()
         };
 
-        let (fingerprint, hash) = extract_fingerprint(&s)?;
-        assert_eq!(fingerprint, "bar");
-        assert_eq!(hash, "foo");
+        let fingerprints = extract_fingerprints(&s)?;
+        assert_eq!(fingerprints.len(), 1);
+        assert_eq!(fingerprints[0].value, "bar");
+        assert_eq!(fingerprints[0].algorithm, "foo");
     }
 
     //"Good Media Fingerprint"
     {
         let s = SessionDescription {
             media_descriptions: vec![MediaDescription {
-                attributes: vec![Attribute {
-                    key: "fingerprint".to_owned(),
-                    value: Some("foo bar".to_owned()),
-                }],
+                attributes: vec![fingerprint("foo bar")],
                 ..Default::default()
             }],
             ..Default::default()
         };
 
-        let (fingerprint, hash) = extract_fingerprint(&s)?;
-        assert_eq!(fingerprint, "bar");
-        assert_eq!(hash, "foo");
+        let fingerprints = extract_fingerprints(&s)?;
+        assert_eq!(fingerprints.len(), 1);
+        assert_eq!(fingerprints[0].value, "bar");
+        assert_eq!(fingerprints[0].algorithm, "foo");
+    }
+
+    //"Multiple Fingerprints"
+    {
+        let s = SessionDescription {
+            attributes: vec![fingerprint("SHA-1 AB:CD")],
+            media_descriptions: vec![
+                MediaDescription {
+                    attributes: vec![fingerprint("sha-1 ab:cd"), fingerprint("sha-384 EF:01")],
+                    ..Default::default()
+                },
+                MediaDescription {
+                    attributes: vec![fingerprint("sha-384 EF:01")],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let fingerprints = extract_fingerprints(&s)?;
+        assert_eq!(fingerprints.len(), 2);
+        assert_eq!(fingerprints[0].algorithm, "sha-1");
+        assert_eq!(fingerprints[0].value, "AB:CD");
+        assert_eq!(fingerprints[1].algorithm, "sha-384");
+        assert_eq!(fingerprints[1].value, "EF:01");
     }
 
     //"No Fingerprint"
     {
         let s = SessionDescription::default();
 
-        if let Err(err) = extract_fingerprint(&s) {
+        if let Err(err) = extract_fingerprints(&s) {
             assert_eq!(Error::ErrSessionDescriptionNoFingerprint, err);
         } else {
             assert!(false);
@@ -60,14 +86,11 @@ fn test_extract_fingerprint() -> Result<()> {
     //"Invalid Fingerprint"
     {
         let s = SessionDescription {
-            attributes: vec![Attribute {
-                key: "fingerprint".to_owned(),
-                value: Some("foo".to_owned()),
-            }],
+            attributes: vec![fingerprint("foo")],
             ..Default::default()
         };
 
-        if let Err(err) = extract_fingerprint(&s) {
+        if let Err(err) = extract_fingerprints(&s) {
             assert_eq!(Error::ErrSessionDescriptionInvalidFingerprint, err);
         } else {
             assert!(false);
@@ -77,21 +100,15 @@ fn test_extract_fingerprint() -> Result<()> {
     //"Conflicting Fingerprint"
     {
         let s = SessionDescription {
-            attributes: vec![Attribute {
-                key: "fingerprint".to_owned(),
-                value: Some("foo".to_owned()),
-            }],
+            attributes: vec![fingerprint("foo bar")],
             media_descriptions: vec![MediaDescription {
-                attributes: vec![Attribute {
-                    key: "fingerprint".to_owned(),
-                    value: Some("foo bar".to_owned()),
-                }],
+                attributes: vec![fingerprint("foo baz")],
                 ..Default::default()
             }],
             ..Default::default()
         };
 
-        if let Err(err) = extract_fingerprint(&s) {
+        if let Err(err) = extract_fingerprints(&s) {
             assert_eq!(Error::ErrSessionDescriptionConflictingFingerprints, err);
         } else {
             assert!(false);
@@ -542,6 +559,10 @@ async fn fingerprint_test(
         is_icelite: false,
         connection_role: ConnectionRole::Active,
         ice_gathering_state: RTCIceGatheringState::New,
+        extmap_allow_mixed: false,
+        bundle_only: false,
+        rtcp_mux: true,
+        rtcp_candidates: false,
     };
 
     let s = populate_sdp(
@@ -584,9 +605,7 @@ async fn test_media_description_fingerprints() -> Result<()> {
                     None,
                     RTCRtpTransceiverDirection::Inactive,
                     RTPCodecType::Video,
-                    api.media_engine
-                        .get_codecs_by_kind(RTPCodecType::Video)
-                        .await,
+                    vec![],
                     Arc::clone(&api.media_engine),
                     None,
                 )
@@ -602,9 +621,7 @@ async fn test_media_description_fingerprints() -> Result<()> {
                     None,
                     RTCRtpTransceiverDirection::Inactive,
                     RTPCodecType::Audio,
-                    api.media_engine
-                        .get_codecs_by_kind(RTPCodecType::Audio)
-                        .await,
+                    vec![],
                     Arc::clone(&api.media_engine),
                     None,
                 )
@@ -667,14 +684,14 @@ async fn test_populate_sdp() -> Result<()> {
             None,
             RTCRtpTransceiverDirection::Recvonly,
             RTPCodecType::Video,
-            me.video_codecs.clone(),
+            vec![],
             Arc::clone(&me),
             None,
         )
         .await;
 
         let mut rid_map = HashMap::new();
-        rid_map.insert("ridkey".to_owned(), "some".to_owned());
+        rid_map.insert("ridkey".to_owned(), Rid::new("ridkey", RidDirection::Send));
         let media_sections = vec![MediaSection {
             id: "video".to_owned(),
             transceivers: vec![tr],
@@ -690,6 +707,10 @@ async fn test_populate_sdp() -> Result<()> {
             is_icelite: se.candidates.ice_lite,
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
+            extmap_allow_mixed: true,
+            bundle_only: false,
+            rtcp_mux: true,
+            rtcp_candidates: false,
         };
         let offer_sdp = populate_sdp(
             d,
@@ -702,6 +723,8 @@ async fn test_populate_sdp() -> Result<()> {
         )
         .await?;
 
+        assert!(is_extmap_allow_mixed_set(&offer_sdp));
+
         // Test contains rid map keys
         let mut found = false;
         for desc in &offer_sdp.media_descriptions {
@@ -727,10 +750,10 @@ async fn test_populate_sdp() -> Result<()> {
         let se = SettingEngine::default();
         let mut me = MediaEngine::default();
         me.register_default_codecs()?;
-        me.push_codecs(me.video_codecs.clone(), RTPCodecType::Video)
-            .await;
-        me.push_codecs(me.audio_codecs.clone(), RTPCodecType::Audio)
-            .await;
+        let video_codecs = me.video_codecs.lock().unwrap().clone();
+        me.push_codecs(video_codecs, RTPCodecType::Video).await;
+        let audio_codecs = me.audio_codecs.lock().unwrap().clone();
+        me.push_codecs(audio_codecs, RTPCodecType::Audio).await;
         let me = Arc::new(me);
 
         let tr = RTCRtpTransceiver::new(
@@ -738,21 +761,17 @@ async fn test_populate_sdp() -> Result<()> {
             None,
             RTCRtpTransceiverDirection::Recvonly,
             RTPCodecType::Video,
-            me.video_codecs.clone(),
+            vec![],
             Arc::clone(&me),
             None,
         )
         .await;
-        tr.set_codec_preferences(vec![RTCRtpCodecParameters {
-            capability: RTCRtpCodecCapability {
-                mime_type: MIME_TYPE_VP8.to_owned(),
-                clock_rate: 90000,
-                channels: 0,
-                sdp_fmtp_line: "".to_owned(),
-                rtcp_feedback: vec![],
-            },
-            payload_type: 96,
-            ..Default::default()
+        tr.set_codec_preferences(vec![RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            clock_rate: 90000,
+            channels: 0,
+            sdp_fmtp_line: "".to_owned(),
+            rtcp_feedback: vec![],
         }])
         .await?;
 
@@ -771,6 +790,10 @@ async fn test_populate_sdp() -> Result<()> {
             is_icelite: se.candidates.ice_lite,
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
+            extmap_allow_mixed: false,
+            bundle_only: false,
+            rtcp_mux: true,
+            rtcp_candidates: false,
         };
         let offer_sdp = populate_sdp(
             d,
@@ -833,7 +856,7 @@ async fn test_populate_sdp_reject() -> Result<()> {
         None,
         RTCRtpTransceiverDirection::Recvonly,
         RTPCodecType::Video,
-        me.video_codecs.clone(),
+        vec![],
         Arc::clone(&me),
         None,
     )
@@ -844,7 +867,7 @@ async fn test_populate_sdp_reject() -> Result<()> {
         None,
         RTCRtpTransceiverDirection::Recvonly,
         RTPCodecType::Audio,
-        me.audio_codecs.clone(),
+        vec![],
         Arc::clone(&me),
         None,
     )
@@ -874,6 +897,10 @@ async fn test_populate_sdp_reject() -> Result<()> {
         is_icelite: se.candidates.ice_lite,
         connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
         ice_gathering_state: RTCIceGatheringState::Complete,
+        extmap_allow_mixed: false,
+        bundle_only: false,
+        rtcp_mux: true,
+        rtcp_candidates: false,
     };
     let offer_sdp = populate_sdp(
         d,
@@ -917,6 +944,142 @@ async fn test_populate_sdp_reject() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_populate_sdp_bundle_only() -> Result<()> {
+    let se = SettingEngine::default();
+    let mut me = MediaEngine::default();
+    me.register_default_codecs()?;
+    let me = Arc::new(me);
+
+    let mut media_sections = vec![];
+    for (id, kind) in [
+        ("video", RTPCodecType::Video),
+        ("audio", RTPCodecType::Audio),
+    ] {
+        let t = RTCRtpTransceiver::new(
+            None,
+            None,
+            RTCRtpTransceiverDirection::Recvonly,
+            kind,
+            vec![],
+            Arc::clone(&me),
+            None,
+        )
+        .await;
+        media_sections.push(MediaSection {
+            id: id.to_owned(),
+            transceivers: vec![t],
+            ..Default::default()
+        });
+    }
+
+    let params = PopulateSdpParams {
+        media_description_fingerprint: se.sdp_media_level_fingerprints,
+        is_icelite: se.candidates.ice_lite,
+        connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
+        ice_gathering_state: RTCIceGatheringState::Complete,
+        extmap_allow_mixed: false,
+        bundle_only: true,
+        rtcp_mux: true,
+        rtcp_candidates: false,
+    };
+    let offer_sdp = populate_sdp(
+        SessionDescription::default(),
+        &[],
+        &me,
+        &[],
+        &RTCIceParameters::default(),
+        &media_sections,
+        params,
+    )
+    .await?;
+
+    let video = &offer_sdp.media_descriptions[0];
+    assert_eq!(video.media_name.port.value, 9);
+    assert!(video.attribute(ATTR_KEY_BUNDLE_ONLY).is_none());
+    assert!(!is_rejected(video));
+
+    let audio = &offer_sdp.media_descriptions[1];
+    assert_eq!(audio.media_name.port.value, 0);
+    assert!(audio.attribute(ATTR_KEY_BUNDLE_ONLY).is_some());
+    assert!(
+        !is_rejected(audio),
+        "a bundle-only section should not be rejected"
+    );
+
+    assert_eq!(bundle_group(&offer_sdp), Some(vec!["video", "audio"]));
+    check_max_bundle(&offer_sdp)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_check_max_bundle() {
+    let media = |mid: &str, port: isize| MediaDescription {
+        media_name: MediaName {
+            media: "audio".to_owned(),
+            port: RangedPort {
+                value: port,
+                range: None,
+            },
+            ..Default::default()
+        },
+        attributes: vec![Attribute::new(
+            ATTR_KEY_MID.to_owned(),
+            Some(mid.to_owned()),
+        )],
+        ..Default::default()
+    };
+    let desc = |group: Option<&str>, media_descriptions: Vec<MediaDescription>| {
+        let mut d = SessionDescription {
+            media_descriptions,
+            ..Default::default()
+        };
+        if let Some(group) = group {
+            d = d.with_value_attribute(ATTR_KEY_GROUP.to_owned(), group.to_owned());
+        }
+        d
+    };
+
+    let tests = vec![
+        (
+            "all sections bundled",
+            desc(Some("BUNDLE 0 1"), vec![media("0", 9), media("1", 9)]),
+            true,
+        ),
+        (
+            "rejected section outside of the group",
+            desc(Some("BUNDLE 0"), vec![media("0", 9), media("1", 0)]),
+            true,
+        ),
+        (
+            "section outside of the group",
+            desc(Some("BUNDLE 0"), vec![media("0", 9), media("1", 9)]),
+            false,
+        ),
+        ("no BUNDLE group", desc(None, vec![media("0", 9)]), false),
+        (
+            "other group semantics",
+            desc(Some("LS 0"), vec![media("0", 9)]),
+            false,
+        ),
+    ];
+
+    for (name, d, ok) in tests {
+        let result = check_max_bundle(&d);
+        if ok {
+            assert!(result.is_ok(), "{}: {:?}", name, result);
+        } else {
+            assert_eq!(
+                result,
+                Err(Error::ErrSessionDescriptionNotBundled),
+                "{}",
+                name
+            );
+        }
+    }
+}
+
 #[test]
 fn test_get_rids() {
     let m = vec![MediaDescription {
@@ -941,7 +1104,10 @@ fn test_get_rids() {
 
     assert!(!rids.is_empty(), "Rid mapping should be present");
 
-    assert!(rids.get("f").is_some(), "rid values should contain 'f'");
+    let rid = rids.get("f").expect("rid values should contain 'f'");
+    assert_eq!(rid.direction, RidDirection::Send);
+    assert_eq!(rid.formats, vec![97]);
+    assert_eq!(rid.restriction("max-width"), Some(Some("1280")));
 }
 
 #[test]
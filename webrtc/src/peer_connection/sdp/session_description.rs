@@ -66,6 +66,16 @@ impl RTCSessionDescription {
         Ok(desc)
     }
 
+    /// rollback returns an RTCSessionDescription that discards the pending offer when given
+    /// to set_local_description or set_remote_description, returning to the stable state.
+    /// This is how the polite peer of perfect negotiation resolves glare.
+    pub fn rollback() -> RTCSessionDescription {
+        RTCSessionDescription {
+            sdp_type: RTCSdpType::Rollback,
+            ..Default::default()
+        }
+    }
+
     /// Unmarshal is a helper to deserialize the sdp
     pub fn unmarshal(&self) -> Result<SessionDescription> {
         let mut reader = Cursor::new(self.sdp.as_bytes());
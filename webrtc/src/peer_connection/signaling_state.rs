@@ -150,6 +150,13 @@ pub(crate) fn check_next_signaling_state(
             }
         }
         RTCSignalingState::HaveLocalOffer => {
+            // have-local-offer->SetLocal(rollback)->stable
+            if op == StateChangeOp::SetLocal
+                && sdp_type == RTCSdpType::Rollback
+                && next == RTCSignalingState::Stable
+            {
+                return Ok(next);
+            }
             if op == StateChangeOp::SetRemote {
                 match sdp_type {
                     // have-local-offer->SetRemote(answer)->stable
@@ -177,6 +184,13 @@ pub(crate) fn check_next_signaling_state(
             }
         }
         RTCSignalingState::HaveRemoteOffer => {
+            // have-remote-offer->SetRemote(rollback)->stable
+            if op == StateChangeOp::SetRemote
+                && sdp_type == RTCSdpType::Rollback
+                && next == RTCSignalingState::Stable
+            {
+                return Ok(next);
+            }
             if op == StateChangeOp::SetLocal {
                 match sdp_type {
                     // have-remote-offer->SetLocal(answer)->stable
@@ -350,6 +364,34 @@ mod test {
                 RTCSdpType::Rollback,
                 Some(Error::ErrSignalingStateCannotRollback),
             ),
+            (
+                "have-local-offer->SetLocal(rollback)->stable",
+                RTCSignalingState::HaveLocalOffer,
+                RTCSignalingState::Stable,
+                StateChangeOp::SetLocal,
+                RTCSdpType::Rollback,
+                None,
+            ),
+            (
+                "have-remote-offer->SetRemote(rollback)->stable",
+                RTCSignalingState::HaveRemoteOffer,
+                RTCSignalingState::Stable,
+                StateChangeOp::SetRemote,
+                RTCSdpType::Rollback,
+                None,
+            ),
+            (
+                "(invalid) have-remote-offer->SetLocal(rollback)->stable",
+                RTCSignalingState::HaveRemoteOffer,
+                RTCSignalingState::Stable,
+                StateChangeOp::SetLocal,
+                RTCSdpType::Rollback,
+                Some(Error::ErrSignalingStateProposedTransitionInvalid {
+                    from: RTCSignalingState::HaveRemoteOffer,
+                    is_local: true,
+                    applying: RTCSdpType::Rollback,
+                }),
+            ),
         ];
 
         for (desc, cur, next, op, sdp_type, expected_err) in tests {
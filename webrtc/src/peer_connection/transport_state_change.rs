@@ -0,0 +1,99 @@
+use std::fmt;
+
+use crate::dtls_transport::dtls_transport_state::RTCDtlsTransportState;
+use crate::ice_transport::ice_transport_state::RTCIceTransportState;
+use crate::sctp_transport::sctp_transport_state::RTCSctpTransportState;
+
+/// RTCTransportStateReason tells why a transport has left its connected state, or failed
+/// to reach it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RTCTransportStateReason {
+    /// LocalClose indicates the transport was closed by the local peer.
+    LocalClose,
+
+    /// RemoteClose indicates the transport was closed by the remote peer, with a DTLS
+    /// close_notify alert or with an SCTP SHUTDOWN or ABORT.
+    RemoteClose,
+
+    /// AllPairsFailed indicates the connectivity checks of all the ICE candidate pairs
+    /// have failed before one of them could be selected.
+    AllPairsFailed,
+
+    /// ConsentExpired indicates the remote peer stopped answering the connectivity
+    /// checks of the selected ICE candidate pair.
+    ConsentExpired,
+
+    /// HandshakeTimeout indicates the DTLS handshake didn't complete in time.
+    HandshakeTimeout,
+
+    /// HandshakeFailed indicates the DTLS handshake failed, with the error it failed with.
+    HandshakeFailed(String),
+
+    /// FingerprintMismatch indicates the certificate of the remote peer doesn't match the
+    /// fingerprint of its description.
+    FingerprintMismatch,
+
+    /// Alert indicates the DTLS connection was closed by a fatal alert, received from or
+    /// sent to the remote peer.
+    Alert(String),
+}
+
+impl fmt::Display for RTCTransportStateReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RTCTransportStateReason::LocalClose => write!(f, "closed locally"),
+            RTCTransportStateReason::RemoteClose => write!(f, "closed by the remote peer"),
+            RTCTransportStateReason::AllPairsFailed => write!(f, "all candidate pairs failed"),
+            RTCTransportStateReason::ConsentExpired => write!(f, "consent expired"),
+            RTCTransportStateReason::HandshakeTimeout => write!(f, "handshake timeout"),
+            RTCTransportStateReason::HandshakeFailed(err) => {
+                write!(f, "handshake failed: {}", err)
+            }
+            RTCTransportStateReason::FingerprintMismatch => write!(f, "fingerprint mismatch"),
+            RTCTransportStateReason::Alert(alert) => write!(f, "{}", alert),
+        }
+    }
+}
+
+/// RTCTransportStateChange is a state change of one of the transports of a PeerConnection,
+/// with the reason of the change when the transport was closed or failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RTCTransportStateChange {
+    Ice {
+        state: RTCIceTransportState,
+        reason: Option<RTCTransportStateReason>,
+    },
+    Dtls {
+        state: RTCDtlsTransportState,
+        reason: Option<RTCTransportStateReason>,
+    },
+    Sctp {
+        state: RTCSctpTransportState,
+        reason: Option<RTCTransportStateReason>,
+    },
+}
+
+impl RTCTransportStateChange {
+    /// reason returns the reason of the state change, if any.
+    pub fn reason(&self) -> Option<&RTCTransportStateReason> {
+        match self {
+            RTCTransportStateChange::Ice { reason, .. }
+            | RTCTransportStateChange::Dtls { reason, .. }
+            | RTCTransportStateChange::Sctp { reason, .. } => reason.as_ref(),
+        }
+    }
+}
+
+impl fmt::Display for RTCTransportStateChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (transport, state) = match self {
+            RTCTransportStateChange::Ice { state, .. } => ("ICE", state.to_string()),
+            RTCTransportStateChange::Dtls { state, .. } => ("DTLS", state.to_string()),
+            RTCTransportStateChange::Sctp { state, .. } => ("SCTP", state.to_string()),
+        };
+        match self.reason() {
+            Some(reason) => write!(f, "{} transport {}: {}", transport, state, reason),
+            None => write!(f, "{} transport {}", transport, state),
+        }
+    }
+}
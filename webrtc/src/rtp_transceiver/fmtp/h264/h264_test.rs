@@ -161,3 +161,40 @@ fn test_h264_fmtp_compare() {
         check(a, b);
     }
 }
+
+#[test]
+fn test_h264_fmtp_parameters() {
+    let f = parse(
+        "video/h264",
+        "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f",
+    );
+    let h264 = f.as_any().downcast_ref::<H264Fmtp>().unwrap();
+    assert_eq!(
+        h264.profile_level_id(),
+        Some(H264ProfileLevelId {
+            profile_idc: 0x42,
+            profile_iop: 0xe0,
+            level_idc: 0x1f,
+        })
+    );
+    assert_eq!(h264.packetization_mode(), Some(1));
+    assert!(h264.level_asymmetry_allowed());
+
+    let f = parse("video/h264", "");
+    let h264 = f.as_any().downcast_ref::<H264Fmtp>().unwrap();
+    assert_eq!(
+        h264.profile_level_id(),
+        Some(H264ProfileLevelId {
+            profile_idc: 0x42,
+            profile_iop: 0x00,
+            level_idc: 0x0a,
+        })
+    );
+    assert_eq!(h264.packetization_mode(), Some(0));
+    assert!(!h264.level_asymmetry_allowed());
+
+    let f = parse("video/h264", "packetization-mode=x;profile-level-id=42e0");
+    let h264 = f.as_any().downcast_ref::<H264Fmtp>().unwrap();
+    assert_eq!(h264.profile_level_id(), None);
+    assert_eq!(h264.packetization_mode(), None);
+}
@@ -3,28 +3,21 @@ mod h264_test;
 
 use super::*;
 
-fn profile_level_id_matches(a: &str, b: &str) -> bool {
-    let aa = match hex::decode(a) {
-        Ok(aa) => {
-            if aa.len() < 2 {
-                return false;
-            }
-            aa
-        }
-        Err(_) => return false,
-    };
-
-    let bb = match hex::decode(b) {
-        Ok(bb) => {
-            if bb.len() < 2 {
-                return false;
-            }
-            bb
-        }
-        Err(_) => return false,
-    };
+/// H264ProfileLevelId is the decoded "profile-level-id" parameter of an H.264 fmtp.
+///
+/// <https://tools.ietf.org/html/rfc6184#section-8.1>
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub(crate) struct H264ProfileLevelId {
+    pub(crate) profile_idc: u8,
+    pub(crate) profile_iop: u8,
+    pub(crate) level_idc: u8,
+}
 
-    aa[0] == bb[0] && aa[1] == bb[1]
+impl H264ProfileLevelId {
+    /// matches returns true if both identify the same profile, whatever their levels.
+    pub(crate) fn matches(&self, other: &H264ProfileLevelId) -> bool {
+        self.profile_idc == other.profile_idc && self.profile_iop == other.profile_iop
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -32,6 +25,41 @@ pub(crate) struct H264Fmtp {
     pub(crate) parameters: HashMap<String, String>,
 }
 
+impl H264Fmtp {
+    /// profile_level_id returns the "profile-level-id" parameter, the baseline profile at
+    /// level 1 if it is absent, or None if it is invalid.
+    pub(crate) fn profile_level_id(&self) -> Option<H264ProfileLevelId> {
+        let value = self
+            .parameters
+            .get("profile-level-id")
+            .map_or("42000a", |value| value.as_str());
+        match hex::decode(value) {
+            Ok(b) if b.len() == 3 => Some(H264ProfileLevelId {
+                profile_idc: b[0],
+                profile_iop: b[1],
+                level_idc: b[2],
+            }),
+            _ => None,
+        }
+    }
+
+    /// packetization_mode returns the "packetization-mode" parameter, 0 if it is absent,
+    /// or None if it is invalid.
+    pub(crate) fn packetization_mode(&self) -> Option<u8> {
+        match self.parameters.get("packetization-mode") {
+            Some(value) => value.parse().ok(),
+            None => Some(0),
+        }
+    }
+
+    /// level_asymmetry_allowed returns true if the "level-asymmetry-allowed" parameter is 1.
+    pub(crate) fn level_asymmetry_allowed(&self) -> bool {
+        self.parameters
+            .get("level-asymmetry-allowed")
+            .map_or(false, |value| value == "1")
+    }
+}
+
 impl Fmtp for H264Fmtp {
     fn mime_type(&self) -> &str {
         "video/h264"
@@ -52,34 +80,16 @@ impl Fmtp for H264Fmtp {
     fn match_fmtp(&self, f: &(dyn Fmtp)) -> bool {
         if let Some(c) = f.as_any().downcast_ref::<H264Fmtp>() {
             // test packetization-mode
-            let hpmode = match self.parameters.get("packetization-mode") {
-                Some(s) => s,
-                None => return false,
-            };
-            let cpmode = match c.parameters.get("packetization-mode") {
-                Some(s) => s,
-                None => return false,
-            };
-
-            if hpmode != cpmode {
-                return false;
+            match (self.packetization_mode(), c.packetization_mode()) {
+                (Some(a), Some(b)) if a == b => {}
+                _ => return false,
             }
 
             // test profile-level-id
-            let hplid = match self.parameters.get("profile-level-id") {
-                Some(s) => s,
-                None => return false,
-            };
-            let cplid = match c.parameters.get("profile-level-id") {
-                Some(s) => s,
-                None => return false,
-            };
-
-            if !profile_level_id_matches(hplid, cplid) {
-                return false;
+            match (self.profile_level_id(), c.profile_level_id()) {
+                (Some(a), Some(b)) => a.matches(&b),
+                _ => false,
             }
-
-            true
         } else {
             false
         }
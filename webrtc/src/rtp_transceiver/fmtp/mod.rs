@@ -1,8 +1,12 @@
 pub(crate) mod generic;
 pub(crate) mod h264;
+pub(crate) mod opus;
+pub(crate) mod vp9;
 
 use crate::rtp_transceiver::fmtp::generic::GenericFmtp;
 use crate::rtp_transceiver::fmtp::h264::H264Fmtp;
+use crate::rtp_transceiver::fmtp::opus::OpusFmtp;
+use crate::rtp_transceiver::fmtp::vp9::Vp9Fmtp;
 use std::any::Any;
 use std::collections::HashMap;
 use std::fmt;
@@ -48,6 +52,10 @@ pub fn parse(mime_type: &str, line: &str) -> Box<dyn Fmtp> {
 
     if mime_type.to_uppercase() == "video/h264".to_uppercase() {
         Box::new(H264Fmtp { parameters })
+    } else if mime_type.to_uppercase() == "video/vp9".to_uppercase() {
+        Box::new(Vp9Fmtp { parameters })
+    } else if mime_type.to_uppercase() == "audio/opus".to_uppercase() {
+        Box::new(OpusFmtp { parameters })
     } else {
         Box::new(GenericFmtp {
             mime_type: mime_type.to_owned(),
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod opus_test;
+
+use super::*;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct OpusFmtp {
+    pub(crate) parameters: HashMap<String, String>,
+}
+
+impl OpusFmtp {
+    /// minptime returns the "minptime" parameter, the shortest packet duration in
+    /// milliseconds the receiver wants, if it is present and valid.
+    pub(crate) fn minptime(&self) -> Option<u32> {
+        self.parameters.get("minptime")?.parse().ok()
+    }
+
+    /// useinbandfec returns true if the "useinbandfec" parameter is 1.
+    pub(crate) fn useinbandfec(&self) -> bool {
+        self.flag("useinbandfec")
+    }
+
+    /// stereo returns true if the "stereo" parameter is 1.
+    pub(crate) fn stereo(&self) -> bool {
+        self.flag("stereo")
+    }
+
+    /// maxaveragebitrate returns the "maxaveragebitrate" parameter, the highest average
+    /// bitrate in bits per second the receiver wants, if it is present and valid.
+    pub(crate) fn maxaveragebitrate(&self) -> Option<u32> {
+        self.parameters.get("maxaveragebitrate")?.parse().ok()
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.parameters.get(key).map_or(false, |value| value == "1")
+    }
+}
+
+impl Fmtp for OpusFmtp {
+    fn mime_type(&self) -> &str {
+        "audio/opus"
+    }
+
+    /// Match returns true if o and b are compatible fmtp descriptions
+    /// Based on RFC7587 Section 7, the Opus parameters are preferences of
+    /// the receiver which do not identify a media format configuration,
+    /// so any two Opus fmtp descriptions are compatible.
+    fn match_fmtp(&self, f: &(dyn Fmtp)) -> bool {
+        f.as_any().downcast_ref::<OpusFmtp>().is_some()
+    }
+
+    fn parameter(&self, key: &str) -> Option<&String> {
+        self.parameters.get(key)
+    }
+
+    fn equal(&self, other: &(dyn Fmtp)) -> bool {
+        other.as_any().downcast_ref::<OpusFmtp>() == Some(self)
+    }
+
+    fn as_any(&self) -> &(dyn Any) {
+        self
+    }
+}
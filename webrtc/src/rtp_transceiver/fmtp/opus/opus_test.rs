@@ -0,0 +1,51 @@
+use super::*;
+
+#[test]
+fn test_opus_fmtp_parse() {
+    let f = parse(
+        "audio/opus",
+        "minptime=10;useinbandfec=1;stereo=0;maxaveragebitrate=64000",
+    );
+    assert_eq!(f.mime_type(), "audio/opus");
+
+    let opus = f.as_any().downcast_ref::<OpusFmtp>().unwrap();
+    assert_eq!(opus.minptime(), Some(10));
+    assert!(opus.useinbandfec());
+    assert!(!opus.stereo());
+    assert_eq!(opus.maxaveragebitrate(), Some(64000));
+
+    let f = parse("audio/OPUS", "");
+    let opus = f.as_any().downcast_ref::<OpusFmtp>().unwrap();
+    assert_eq!(opus.minptime(), None);
+    assert!(!opus.useinbandfec());
+    assert!(!opus.stereo());
+    assert_eq!(opus.maxaveragebitrate(), None);
+}
+
+#[test]
+fn test_opus_fmtp_compare() {
+    let tests = vec![
+        (
+            "Equal",
+            "minptime=10;useinbandfec=1",
+            "minptime=10;useinbandfec=1",
+        ),
+        ("Empty", "minptime=10;useinbandfec=1", ""),
+        (
+            "DifferentPreferences",
+            "minptime=10;useinbandfec=1",
+            "minptime=20;useinbandfec=0;stereo=1",
+        ),
+    ];
+
+    for (name, a, b) in tests {
+        let aa = parse("audio/opus", a);
+        let bb = parse("audio/opus", b);
+
+        assert!(aa.match_fmtp(&*bb), "{}", name);
+        assert!(bb.match_fmtp(&*aa), "{}", name);
+    }
+
+    let generic = parse("audio/pcmu", "");
+    assert!(!parse("audio/opus", "").match_fmtp(&*generic));
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod vp9_test;
+
+use super::*;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Vp9Fmtp {
+    pub(crate) parameters: HashMap<String, String>,
+}
+
+impl Vp9Fmtp {
+    /// profile_id returns the "profile-id" parameter, 0 if it is absent, or None if it
+    /// is invalid.
+    pub(crate) fn profile_id(&self) -> Option<u8> {
+        match self.parameters.get("profile-id") {
+            Some(value) => value.parse().ok(),
+            None => Some(0),
+        }
+    }
+}
+
+impl Fmtp for Vp9Fmtp {
+    fn mime_type(&self) -> &str {
+        "video/vp9"
+    }
+
+    /// Match returns true if v and b are compatible fmtp descriptions
+    /// Based on draft-ietf-payload-vp9-16 Section 6.2.1, the profile-id
+    /// identifies the media format configuration and defaults to 0.
+    fn match_fmtp(&self, f: &(dyn Fmtp)) -> bool {
+        if let Some(c) = f.as_any().downcast_ref::<Vp9Fmtp>() {
+            match (self.profile_id(), c.profile_id()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            }
+        } else {
+            false
+        }
+    }
+
+    fn parameter(&self, key: &str) -> Option<&String> {
+        self.parameters.get(key)
+    }
+
+    fn equal(&self, other: &(dyn Fmtp)) -> bool {
+        other.as_any().downcast_ref::<Vp9Fmtp>() == Some(self)
+    }
+
+    fn as_any(&self) -> &(dyn Any) {
+        self
+    }
+}
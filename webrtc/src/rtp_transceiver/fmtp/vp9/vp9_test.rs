@@ -0,0 +1,52 @@
+use super::*;
+
+#[test]
+fn test_vp9_fmtp_parse() {
+    let f = parse("video/VP9", "profile-id=2");
+    assert_eq!(f.mime_type(), "video/vp9");
+
+    let vp9 = f.as_any().downcast_ref::<Vp9Fmtp>().unwrap();
+    assert_eq!(vp9.profile_id(), Some(2));
+
+    let vp9 = Vp9Fmtp {
+        parameters: HashMap::new(),
+    };
+    assert_eq!(vp9.profile_id(), Some(0));
+
+    let f = parse("video/vp9", "profile-id=x");
+    let vp9 = f.as_any().downcast_ref::<Vp9Fmtp>().unwrap();
+    assert_eq!(vp9.profile_id(), None);
+}
+
+#[test]
+fn test_vp9_fmtp_compare() {
+    let tests = vec![
+        ("Equal", "profile-id=1", "profile-id=1", true),
+        ("DefaultProfile", "profile-id=0", "", true),
+        (
+            "OneHasExtraParam",
+            "profile-id=2",
+            "profile-id=2;foo=bar",
+            true,
+        ),
+        ("Inconsistent", "profile-id=0", "profile-id=2", false),
+        ("Inconsistent_Default", "", "profile-id=1", false),
+        (
+            "Inconsistent_InvalidProfileID",
+            "profile-id=x",
+            "profile-id=x",
+            false,
+        ),
+    ];
+
+    for (name, a, b, consist) in tests {
+        let aa = parse("video/vp9", a);
+        let bb = parse("video/vp9", b);
+
+        assert_eq!(aa.match_fmtp(&*bb), consist, "{}", name);
+        assert_eq!(bb.match_fmtp(&*aa), consist, "{}", name);
+    }
+
+    let generic = parse("video/vp8", "profile-id=0");
+    assert!(!parse("video/vp9", "profile-id=0").match_fmtp(&*generic));
+}
@@ -0,0 +1,27 @@
+use crate::error::Result;
+use crate::rtp_transceiver::{PayloadType, SSRC};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::fmt;
+
+/// EncodedFrame is an assembled media frame, as produced by the encoder before it is
+/// packetized by a RTCRtpSender, or as reassembled from the RTP packets of a TrackRemote.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct EncodedFrame {
+    /// ssrc is the synchronization source the frame is sent or received on
+    pub ssrc: SSRC,
+    /// payload_type is the negotiated payload type of the frame's codec
+    pub payload_type: PayloadType,
+    /// data is the encoded payload of the frame
+    pub data: Bytes,
+}
+
+/// FrameTransform modifies the encoded frames of a RTCRtpSender before packetization, or
+/// of a RTCRtpReceiver after depacketization, e.g. to end-to-end encrypt or watermark them.
+/// It is the equivalent of the insertable streams of the browsers.
+#[async_trait]
+pub trait FrameTransform: fmt::Debug {
+    /// transform returns the modified frame. An error drops the frame.
+    async fn transform(&self, frame: EncodedFrame) -> Result<EncodedFrame>;
+}
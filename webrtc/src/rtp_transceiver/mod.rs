@@ -4,6 +4,8 @@ mod rtp_transceiver_test;
 use crate::api::media_engine::MediaEngine;
 use crate::error::{Error, Result};
 use crate::rtp_transceiver::rtp_codec::*;
+use crate::rtp_transceiver::rtp_degradation_preference::RTCDegradationPreference;
+use crate::rtp_transceiver::rtp_priority::RTCPriorityType;
 use crate::rtp_transceiver::rtp_receiver::{RTCRtpReceiver, RTPReceiverInternal};
 use crate::rtp_transceiver::rtp_sender::RTCRtpSender;
 use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
@@ -24,9 +26,13 @@ use tokio::sync::Mutex;
 use util::Unmarshal;
 
 pub(crate) mod fmtp;
+pub mod frame_transform;
 pub mod rtp_codec;
+pub mod rtp_degradation_preference;
+pub mod rtp_priority;
 pub mod rtp_receiver;
 pub mod rtp_sender;
+pub mod rtp_source;
 pub mod rtp_transceiver_direction;
 pub(crate) mod srtp_writer_future;
 
@@ -104,10 +110,52 @@ pub struct RTCRtpCodingParameters {
 /// <http://draft.ortc.org/#dom-rtcrtpdecodingparameters>
 pub type RTCRtpDecodingParameters = RTCRtpCodingParameters;
 
-/// RTPEncodingParameters provides information relating to both encoding and decoding.
-/// This is a subset of the RFC since Pion WebRTC doesn't implement encoding itself
-/// <http://draft.ortc.org/#dom-rtcrtpencodingparameters>
-pub type RTCRtpEncodingParameters = RTCRtpCodingParameters;
+/// RTPEncodingParameters provides information relating to the encoding of a single stream
+/// sent by a RTPSender. With more than one of them the sender sends simulcast, each encoding
+/// identified by its rid and sent on its own SSRC.
+/// This is a subset of the RFC since Pion WebRTC doesn't implement encoding itself, the
+/// application's encoder is expected to follow scale_resolution_down_by, max_bitrate and
+/// max_framerate, see TrackLocalContext::encoding_parameters.
+/// <https://w3c.github.io/webrtc-pc/#dom-rtcrtpencodingparameters>
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RTCRtpEncodingParameters {
+    pub rid: String,
+    pub ssrc: SSRC,
+    pub payload_type: PayloadType,
+    pub rtx: RTCRtpRtxParameters,
+
+    /// active is false if the encoding is not sent, without requiring a renegotiation.
+    pub active: bool,
+    /// scale_resolution_down_by is the factor by which the resolution of the track is
+    /// scaled down for this encoding, e.g. 2.0 for half the width and height.
+    pub scale_resolution_down_by: Option<f64>,
+    /// max_bitrate is the maximum bitrate of this encoding in bits per second.
+    pub max_bitrate: Option<u64>,
+    /// max_framerate is the maximum number of frames per second of this encoding.
+    pub max_framerate: Option<f64>,
+    /// priority is the relative priority of this encoding.
+    pub priority: RTCPriorityType,
+    /// scalability_mode is the SVC mode produced by the encoder for this encoding, e.g. "L1T3".
+    /// It must be registered for the negotiated codec in the MediaEngine.
+    pub scalability_mode: Option<String>,
+}
+
+impl Default for RTCRtpEncodingParameters {
+    fn default() -> Self {
+        RTCRtpEncodingParameters {
+            rid: String::new(),
+            ssrc: 0,
+            payload_type: 0,
+            rtx: RTCRtpRtxParameters::default(),
+            active: true,
+            scale_resolution_down_by: None,
+            max_bitrate: None,
+            max_framerate: None,
+            priority: RTCPriorityType::Low,
+            scalability_mode: None,
+        }
+    }
+}
 
 /// RTPReceiveParameters contains the RTP stack settings used by receivers
 #[derive(Debug)]
@@ -116,10 +164,11 @@ pub struct RTCRtpReceiveParameters {
 }
 
 /// RTPSendParameters contains the RTP stack settings used by receivers
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RTCRtpSendParameters {
     pub rtp_parameters: RTCRtpParameters,
     pub encodings: Vec<RTCRtpEncodingParameters>,
+    pub degradation_preference: RTCDegradationPreference,
 }
 
 /// RTPTransceiverInit dictionary is used when calling the WebRTC function addTransceiver() to provide configuration options for the new transceiver.
@@ -135,6 +184,7 @@ pub(crate) fn create_stream_info(
     payload_type: PayloadType,
     codec: RTCRtpCodecCapability,
     webrtc_header_extensions: &[RTCRtpHeaderExtensionParameters],
+    extmap_allow_mixed: bool,
 ) -> StreamInfo {
     let mut header_extensions = vec![];
     for h in webrtc_header_extensions {
@@ -158,6 +208,7 @@ pub(crate) fn create_stream_info(
         ssrc,
         payload_type,
         rtp_header_extensions: header_extensions,
+        rtp_header_extensions_allow_mixed: extmap_allow_mixed,
         mime_type: codec.mime_type,
         clock_rate: codec.clock_rate,
         channels: codec.channels,
@@ -178,7 +229,7 @@ pub struct RTCRtpTransceiver {
     direction: AtomicU8,         //RTPTransceiverDirection
     current_direction: AtomicU8, //RTPTransceiverDirection
 
-    codecs: Arc<Mutex<Vec<RTCRtpCodecParameters>>>, // User provided codecs via set_codec_preferences
+    codecs: Arc<Mutex<Vec<RTCRtpCodecCapability>>>, // User provided codecs via set_codec_preferences
 
     pub(crate) stopped: AtomicBool,
     pub(crate) kind: RTPCodecType,
@@ -194,7 +245,7 @@ impl RTCRtpTransceiver {
         sender: Option<Arc<RTCRtpSender>>,
         direction: RTCRtpTransceiverDirection,
         kind: RTPCodecType,
-        codecs: Vec<RTCRtpCodecParameters>,
+        codecs: Vec<RTCRtpCodecCapability>,
         media_engine: Arc<MediaEngine>,
         trigger_negotiation_needed: TriggerNegotiationNeededFnOption,
     ) -> Arc<Self> {
@@ -219,12 +270,19 @@ impl RTCRtpTransceiver {
         t
     }
 
-    /// set_codec_preferences sets preferred list of supported codecs
-    /// if codecs is empty or nil we reset to default from MediaEngine
-    pub async fn set_codec_preferences(&self, codecs: Vec<RTCRtpCodecParameters>) -> Result<()> {
-        for codec in &codecs {
-            let media_engine_codecs = self.media_engine.get_codecs_by_kind(self.kind).await;
-            let (_, match_type) = codec_parameters_fuzzy_search(codec, &media_engine_codecs);
+    /// set_codec_preferences restricts the codecs offered and answered for this transceiver
+    /// to the given ones, in order of preference, e.g. to prefer VP9 over VP8. The codecs keep
+    /// the payload types of the MediaEngine, and ErrRTPTransceiverCodecUnsupported is returned
+    /// if one of them isn't supported by the MediaEngine.
+    /// if codecs is empty we reset to default from MediaEngine
+    pub async fn set_codec_preferences(&self, codecs: Vec<RTCRtpCodecCapability>) -> Result<()> {
+        let media_engine_codecs = self.media_engine.get_codecs_by_kind(self.kind).await;
+        for capability in &codecs {
+            let codec = RTCRtpCodecParameters {
+                capability: capability.clone(),
+                ..Default::default()
+            };
+            let (_, match_type) = codec_parameters_fuzzy_search(&codec, &media_engine_codecs);
             if match_type == CodecMatch::None {
                 return Err(Error::ErrRTPTransceiverCodecUnsupported);
             }
@@ -239,8 +297,12 @@ impl RTCRtpTransceiver {
 
     /// Codecs returns list of supported codecs
     pub(crate) async fn get_codecs(&self) -> Vec<RTCRtpCodecParameters> {
-        let mut codecs = self.codecs.lock().await;
-        RTPReceiverInternal::get_codecs(&mut codecs, self.kind, &self.media_engine).await
+        let codecs = {
+            let codecs = self.codecs.lock().await;
+            RTPReceiverInternal::get_codecs(&codecs, self.kind, &self.media_engine).await
+        };
+        self.media_engine
+            .filter_codecs_by_direction(codecs, self.kind, self.direction())
     }
 
     /// sender returns the RTPTransceiver's RTPSender if it has one
@@ -308,6 +370,13 @@ impl RTCRtpTransceiver {
         Ok(())
     }
 
+    /// clear_mid disassociates the RTPTransceiver from its media section when the description
+    /// that associated it is rolled back.
+    pub(crate) async fn clear_mid(&self) {
+        let mut m = self.mid.lock().await;
+        m.clear();
+    }
+
     /// mid gets the Transceiver's mid value. When not already set, this value will be set in CreateOffer or create_answer.
     pub async fn mid(&self) -> String {
         let mid = self.mid.lock().await;
@@ -424,12 +493,29 @@ impl RTCRtpTransceiver {
         Ok(())
     }
 
-    /// stop irreversibly stops the RTPTransceiver
+    /// stop irreversibly stops the RTPTransceiver. The next offer rejects its m= section with
+    /// port 0, and once that is negotiated the transceiver is removed from the PeerConnection
+    /// and a later transceiver may reuse the m= section.
     pub async fn stop(&self) -> Result<()> {
-        if self.stopped.load(Ordering::SeqCst) {
+        if !self.stop_internal().await? {
             return Ok(());
         }
 
+        let lock = self.trigger_negotiation_needed.lock().await;
+        if let Some(trigger) = &*lock {
+            (trigger)().await;
+        }
+
+        Ok(())
+    }
+
+    /// stop_internal stops the RTPTransceiver without triggering a negotiation, e.g. when the
+    /// remote peer rejected its m= section. It returns false if it was already stopped.
+    pub(crate) async fn stop_internal(&self) -> Result<bool> {
+        if self.stopped.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+
         self.stopped.store(true, Ordering::SeqCst);
 
         {
@@ -447,7 +533,7 @@ impl RTCRtpTransceiver {
 
         self.set_direction_internal(RTCRtpTransceiverDirection::Inactive);
 
-        Ok(())
+        Ok(true)
     }
 
     pub(crate) async fn set_sending_track(
@@ -4,6 +4,7 @@ use crate::error::{Error, Result};
 use crate::rtp_transceiver::fmtp;
 
 use std::fmt;
+use std::str::FromStr;
 
 /// RTPCodecType determines the type of a codec
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -68,7 +69,29 @@ pub struct RTCRtpCodecCapability {
 impl RTCRtpCodecCapability {
     /// Turn codec capability into a `packetizer::Payloader`
     pub fn payloader_for_codec(&self) -> Result<Box<dyn rtp::packetizer::Payloader + Send + Sync>> {
+        self.payloader_for_codec_with_scalability_mode(None)
+    }
+
+    /// Turn codec capability into a `packetizer::Payloader` that describes the layers of
+    /// the given scalability mode in its payload descriptor. Only VP9 supports layering,
+    /// other codecs accept no mode or L1T1.
+    pub fn payloader_for_codec_with_scalability_mode(
+        &self,
+        scalability_mode: Option<&ScalabilityMode>,
+    ) -> Result<Box<dyn rtp::packetizer::Payloader + Send + Sync>> {
         let mime_type = self.mime_type.to_lowercase();
+        if let Some(mode) = scalability_mode {
+            // Independent spatial layers are sent as simulcast encodings, not in one VP9 stream
+            if mime_type == MIME_TYPE_VP9.to_lowercase() && !mode.simulcast {
+                let mut vp9_payloader = rtp::codecs::vp9::Vp9Payloader::default();
+                vp9_payloader.spatial_layers = mode.spatial_layers;
+                vp9_payloader.temporal_layers = mode.temporal_layers;
+                return Ok(Box::new(vp9_payloader));
+            } else if mode.is_layered() || mode.simulcast && mode.spatial_layers > 1 {
+                return Err(Error::ErrUnsupportedScalabilityMode);
+            }
+        }
+
         if mime_type == MIME_TYPE_H264.to_lowercase() {
             Ok(Box::new(rtp::codecs::h264::H264Payloader::default()))
         } else if mime_type == MIME_TYPE_VP8.to_lowercase() {
@@ -91,6 +114,109 @@ impl RTCRtpCodecCapability {
     }
 }
 
+/// ScalabilityMode describes the spatial and temporal layers of an SVC encoding,
+/// e.g. "L1T3" or "L3T3_KEY".
+/// <https://www.w3.org/TR/webrtc-svc/#scalabilitymodes*>
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScalabilityMode {
+    pub spatial_layers: u8,
+    pub temporal_layers: u8,
+    /// Spatial layers are independent streams ("S" modes) instead of predicting from
+    /// each other ("L" modes).
+    pub simulcast: bool,
+    /// Spatial layers differ by a factor of 1.5 instead of 2 ("h" suffix).
+    pub ratio_1_5: bool,
+    /// Spatial layers only depend on each other in key frames ("_KEY" suffix).
+    pub key: bool,
+    /// Temporal base layers of the spatial layers are shifted ("_KEY_SHIFT" suffix).
+    pub key_shift: bool,
+}
+
+impl ScalabilityMode {
+    /// is_layered returns true if the mode has more than one spatial or temporal layer.
+    pub fn is_layered(&self) -> bool {
+        self.spatial_layers > 1 || self.temporal_layers > 1
+    }
+}
+
+impl FromStr for ScalabilityMode {
+    type Err = Error;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        let (layers, suffix) = match raw.find('_') {
+            Some(i) => (&raw[..i], &raw[i..]),
+            None => (raw, ""),
+        };
+        let (key, key_shift) = match suffix {
+            "" => (false, false),
+            "_KEY" => (true, false),
+            "_KEY_SHIFT" => (true, true),
+            _ => return Err(Error::ErrInvalidScalabilityMode),
+        };
+        let (layers, ratio_1_5) = match layers.strip_suffix('h') {
+            Some(layers) => (layers, true),
+            None => (layers, false),
+        };
+
+        let b = layers.as_bytes();
+        if b.len() != 4 || b[2] != b'T' {
+            return Err(Error::ErrInvalidScalabilityMode);
+        }
+        let simulcast = match b[0] {
+            b'L' => false,
+            b'S' => true,
+            _ => return Err(Error::ErrInvalidScalabilityMode),
+        };
+        let spatial_layers = match b[1] {
+            c @ b'1'..=b'3' => c - b'0',
+            _ => return Err(Error::ErrInvalidScalabilityMode),
+        };
+        let temporal_layers = match b[3] {
+            c @ b'1'..=b'3' => c - b'0',
+            _ => return Err(Error::ErrInvalidScalabilityMode),
+        };
+
+        // Ratio and key suffixes only make sense between dependent spatial layers
+        if (ratio_1_5 && spatial_layers == 1)
+            || (key && (simulcast || spatial_layers == 1))
+            || (key_shift && temporal_layers == 1)
+        {
+            return Err(Error::ErrInvalidScalabilityMode);
+        }
+
+        Ok(ScalabilityMode {
+            spatial_layers,
+            temporal_layers,
+            simulcast,
+            ratio_1_5,
+            key,
+            key_shift,
+        })
+    }
+}
+
+impl fmt::Display for ScalabilityMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}T{}",
+            if self.simulcast { 'S' } else { 'L' },
+            self.spatial_layers,
+            self.temporal_layers
+        )?;
+        if self.ratio_1_5 {
+            write!(f, "h")?;
+        }
+        if self.key_shift {
+            write!(f, "_KEY_SHIFT")
+        } else if self.key {
+            write!(f, "_KEY")
+        } else {
+            Ok(())
+        }
+    }
+}
+
 /// RTPHeaderExtensionCapability is used to define a RFC5285 RTP header extension supported by the codec.
 /// <https://w3c.github.io/webrtc-pc/#dom-rtcrtpcapabilities-headerextensions>
 #[derive(Default, Debug, Clone)]
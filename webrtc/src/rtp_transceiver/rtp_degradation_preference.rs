@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// RTCDegradationPreference indicates how the encoder of a sender's track should degrade the
+/// video when the bandwidth or the CPU are constrained.
+/// <https://w3c.github.io/mst-content-hint/#dom-rtcdegradationpreference>
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RTCDegradationPreference {
+    #[serde(rename = "Unspecified")]
+    Unspecified = 0,
+
+    /// MaintainFramerate indicates that the resolution should be lowered to keep the
+    /// framerate.
+    #[serde(rename = "maintain-framerate")]
+    MaintainFramerate,
+
+    /// MaintainResolution indicates that the framerate should be lowered to keep the
+    /// resolution.
+    #[serde(rename = "maintain-resolution")]
+    MaintainResolution,
+
+    /// Balanced indicates that both the framerate and the resolution may be lowered, it is
+    /// the default preference of a sender.
+    #[serde(rename = "balanced")]
+    Balanced,
+}
+
+impl Default for RTCDegradationPreference {
+    fn default() -> Self {
+        RTCDegradationPreference::Unspecified
+    }
+}
+
+const DEGRADATION_PREFERENCE_MAINTAIN_FRAMERATE_STR: &str = "maintain-framerate";
+const DEGRADATION_PREFERENCE_MAINTAIN_RESOLUTION_STR: &str = "maintain-resolution";
+const DEGRADATION_PREFERENCE_BALANCED_STR: &str = "balanced";
+
+impl From<&str> for RTCDegradationPreference {
+    fn from(raw: &str) -> Self {
+        match raw {
+            DEGRADATION_PREFERENCE_MAINTAIN_FRAMERATE_STR => {
+                RTCDegradationPreference::MaintainFramerate
+            }
+            DEGRADATION_PREFERENCE_MAINTAIN_RESOLUTION_STR => {
+                RTCDegradationPreference::MaintainResolution
+            }
+            DEGRADATION_PREFERENCE_BALANCED_STR => RTCDegradationPreference::Balanced,
+            _ => RTCDegradationPreference::Unspecified,
+        }
+    }
+}
+
+impl From<u8> for RTCDegradationPreference {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => RTCDegradationPreference::MaintainFramerate,
+            2 => RTCDegradationPreference::MaintainResolution,
+            3 => RTCDegradationPreference::Balanced,
+            _ => RTCDegradationPreference::Unspecified,
+        }
+    }
+}
+
+impl fmt::Display for RTCDegradationPreference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            RTCDegradationPreference::MaintainFramerate => {
+                DEGRADATION_PREFERENCE_MAINTAIN_FRAMERATE_STR
+            }
+            RTCDegradationPreference::MaintainResolution => {
+                DEGRADATION_PREFERENCE_MAINTAIN_RESOLUTION_STR
+            }
+            RTCDegradationPreference::Balanced => DEGRADATION_PREFERENCE_BALANCED_STR,
+            RTCDegradationPreference::Unspecified => crate::UNSPECIFIED_STR,
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_degradation_preference_string() {
+        let tests = vec![
+            (RTCDegradationPreference::Unspecified, "Unspecified"),
+            (
+                RTCDegradationPreference::MaintainFramerate,
+                "maintain-framerate",
+            ),
+            (
+                RTCDegradationPreference::MaintainResolution,
+                "maintain-resolution",
+            ),
+            (RTCDegradationPreference::Balanced, "balanced"),
+        ];
+
+        for (preference, expected_string) in tests {
+            assert_eq!(expected_string, preference.to_string());
+            assert_eq!(preference, RTCDegradationPreference::from(expected_string));
+            assert_eq!(preference, RTCDegradationPreference::from(preference as u8));
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// RTCPriorityType indicates the relative priority of an encoding, which an application
+/// can use to share the available bandwidth between its senders.
+/// <https://w3c.github.io/webrtc-priority/#rtc-priority-type>
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RTCPriorityType {
+    #[serde(rename = "Unspecified")]
+    Unspecified,
+
+    /// VeryLow indicates a priority below the default one.
+    #[serde(rename = "very-low")]
+    VeryLow,
+
+    /// Low is the default priority.
+    #[serde(rename = "low")]
+    Low,
+
+    /// Medium indicates a priority above the default one.
+    #[serde(rename = "medium")]
+    Medium,
+
+    /// High is the highest priority.
+    #[serde(rename = "high")]
+    High,
+}
+
+impl Default for RTCPriorityType {
+    fn default() -> Self {
+        RTCPriorityType::Unspecified
+    }
+}
+
+const PRIORITY_TYPE_VERY_LOW_STR: &str = "very-low";
+const PRIORITY_TYPE_LOW_STR: &str = "low";
+const PRIORITY_TYPE_MEDIUM_STR: &str = "medium";
+const PRIORITY_TYPE_HIGH_STR: &str = "high";
+
+impl From<&str> for RTCPriorityType {
+    fn from(raw: &str) -> Self {
+        match raw {
+            PRIORITY_TYPE_VERY_LOW_STR => RTCPriorityType::VeryLow,
+            PRIORITY_TYPE_LOW_STR => RTCPriorityType::Low,
+            PRIORITY_TYPE_MEDIUM_STR => RTCPriorityType::Medium,
+            PRIORITY_TYPE_HIGH_STR => RTCPriorityType::High,
+            _ => RTCPriorityType::Unspecified,
+        }
+    }
+}
+
+impl fmt::Display for RTCPriorityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            RTCPriorityType::VeryLow => PRIORITY_TYPE_VERY_LOW_STR,
+            RTCPriorityType::Low => PRIORITY_TYPE_LOW_STR,
+            RTCPriorityType::Medium => PRIORITY_TYPE_MEDIUM_STR,
+            RTCPriorityType::High => PRIORITY_TYPE_HIGH_STR,
+            RTCPriorityType::Unspecified => crate::UNSPECIFIED_STR,
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_priority_type_string() {
+        let tests = vec![
+            (RTCPriorityType::Unspecified, "Unspecified"),
+            (RTCPriorityType::VeryLow, "very-low"),
+            (RTCPriorityType::Low, "low"),
+            (RTCPriorityType::Medium, "medium"),
+            (RTCPriorityType::High, "high"),
+        ];
+
+        for (priority, expected_string) in tests {
+            assert_eq!(expected_string, priority.to_string());
+            assert_eq!(priority, RTCPriorityType::from(expected_string));
+        }
+    }
+}
@@ -5,13 +5,17 @@ use crate::api::media_engine::MediaEngine;
 use crate::dtls_transport::RTCDtlsTransport;
 use crate::error::{flatten_errs, Error, Result};
 use crate::peer_connection::sdp::TrackDetails;
+use crate::rtp_transceiver::frame_transform::FrameTransform;
 use crate::rtp_transceiver::rtp_codec::{
     codec_parameters_fuzzy_search, CodecMatch, RTCRtpCodecCapability, RTCRtpCodecParameters,
     RTCRtpParameters, RTPCodecType,
 };
+use crate::rtp_transceiver::rtp_source::{
+    RTCRtpContributingSource, RTCRtpSynchronizationSource, RTPSources,
+};
 use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
 use crate::rtp_transceiver::{
-    create_stream_info, RTCRtpDecodingParameters, RTCRtpReceiveParameters, SSRC,
+    create_stream_info, fmtp, PayloadType, RTCRtpDecodingParameters, RTCRtpReceiveParameters, SSRC,
 };
 use crate::track::track_remote::TrackRemote;
 use crate::track::{TrackStream, TrackStreams};
@@ -22,6 +26,7 @@ use log::trace;
 use std::fmt;
 
 use std::sync::Arc;
+use std::time::SystemTime;
 use tokio::sync::{watch, Mutex, RwLock};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -151,9 +156,13 @@ pub struct RTPReceiverInternal {
 
     tracks: RwLock<Vec<TrackStreams>>,
 
-    transceiver_codecs: Mutex<Option<Arc<Mutex<Vec<RTCRtpCodecParameters>>>>>,
+    transceiver_codecs: Mutex<Option<Arc<Mutex<Vec<RTCRtpCodecCapability>>>>>,
+
+    frame_transform: Mutex<Option<Arc<dyn FrameTransform + Send + Sync>>>,
+
+    sources: std::sync::Mutex<RTPSources>,
 
-    transport: Arc<RTCDtlsTransport>,
+    pub(crate) transport: Arc<RTCDtlsTransport>,
     media_engine: Arc<MediaEngine>,
     interceptor: Arc<dyn Interceptor + Send + Sync>,
 }
@@ -250,6 +259,29 @@ impl RTPReceiverInternal {
         Ok((pkts, attributes))
     }
 
+    pub(crate) async fn frame_transform(&self) -> Option<Arc<dyn FrameTransform + Send + Sync>> {
+        let frame_transform = self.frame_transform.lock().await;
+        frame_transform.clone()
+    }
+
+    /// update_sources records a packet read from a track for get_synchronization_sources and
+    /// get_contributing_sources.
+    pub(crate) fn update_sources(
+        &self,
+        header: &rtp::header::Header,
+        audio_level_id: u8,
+        csrc_audio_level_id: u8,
+    ) {
+        if let Ok(mut sources) = self.sources.lock() {
+            sources.update(
+                header,
+                audio_level_id,
+                csrc_audio_level_id,
+                SystemTime::now(),
+            );
+        }
+    }
+
     pub(crate) async fn read_rtp(&self, b: &mut [u8], tid: usize) -> Result<(usize, Attributes)> {
         let mut state_watch_rx = self.state_tx.subscribe();
 
@@ -309,6 +341,15 @@ impl RTPReceiverInternal {
         }
     }
 
+    /// rtp_read_stream returns the SRTP stream the track with the given tid is read from.
+    pub(crate) async fn rtp_read_stream(&self, tid: usize) -> Option<Arc<srtp::stream::Stream>> {
+        let tracks = self.tracks.read().await;
+        tracks
+            .iter()
+            .find(|t| t.track.tid() == tid)
+            .and_then(|t| t.stream.rtp_read_stream.clone())
+    }
+
     async fn get_parameters(&self) -> RTCRtpParameters {
         let mut parameters = self
             .media_engine
@@ -317,16 +358,19 @@ impl RTPReceiverInternal {
 
         let transceiver_codecs = self.transceiver_codecs.lock().await;
         if let Some(codecs) = &*transceiver_codecs {
-            let mut c = codecs.lock().await;
+            let c = codecs.lock().await;
             parameters.codecs =
-                RTPReceiverInternal::get_codecs(&mut c, self.kind, &self.media_engine).await;
+                RTPReceiverInternal::get_codecs(&c, self.kind, &self.media_engine).await;
         }
 
         parameters
     }
 
+    /// get_codecs returns the codecs of the media engine matching the codec preferences,
+    /// in their order, followed by the RTX codecs of the matched codecs. Every codec of
+    /// the media engine is returned if there is no preference.
     pub(crate) async fn get_codecs(
-        codecs: &mut [RTCRtpCodecParameters],
+        codecs: &[RTCRtpCodecCapability],
         kind: RTPCodecType,
         media_engine: &Arc<MediaEngine>,
     ) -> Vec<RTCRtpCodecParameters> {
@@ -334,14 +378,30 @@ impl RTPReceiverInternal {
         if codecs.is_empty() {
             return media_engine_codecs;
         }
-        let mut filtered_codecs = vec![];
-        for codec in codecs {
-            let (c, match_type) = codec_parameters_fuzzy_search(codec, &media_engine_codecs);
-            if match_type != CodecMatch::None {
-                if codec.payload_type == 0 {
-                    codec.payload_type = c.payload_type;
+
+        let mut filtered_codecs: Vec<RTCRtpCodecParameters> = vec![];
+        for capability in codecs {
+            let codec = RTCRtpCodecParameters {
+                capability: capability.clone(),
+                ..Default::default()
+            };
+            let (c, match_type) = codec_parameters_fuzzy_search(&codec, &media_engine_codecs);
+            if match_type != CodecMatch::None && !filtered_codecs.contains(&c) {
+                filtered_codecs.push(c);
+            }
+        }
+
+        // Keep the retransmissions of the preferred codecs even if they aren't listed
+        for c in &media_engine_codecs {
+            let apt = fmtp::parse(&c.capability.mime_type, &c.capability.sdp_fmtp_line)
+                .parameter("apt")
+                .and_then(|apt| apt.parse::<PayloadType>().ok());
+            if let Some(apt) = apt {
+                if filtered_codecs.iter().any(|f| f.payload_type == apt)
+                    && !filtered_codecs.contains(c)
+                {
+                    filtered_codecs.push(c.clone());
                 }
-                filtered_codecs.push(codec.clone());
             }
         }
 
@@ -388,7 +448,7 @@ impl RTPReceiverInternal {
 pub struct RTCRtpReceiver {
     receive_mtu: usize,
     kind: RTPCodecType,
-    transport: Arc<RTCDtlsTransport>,
+    pub(crate) transport: Arc<RTCDtlsTransport>,
 
     pub internal: Arc<RTPReceiverInternal>,
 }
@@ -428,6 +488,10 @@ impl RTCRtpReceiver {
                 state_rx,
 
                 transceiver_codecs: Mutex::new(None),
+
+                frame_transform: Mutex::new(None),
+
+                sources: std::sync::Mutex::new(RTPSources::default()),
             }),
         }
     }
@@ -438,12 +502,23 @@ impl RTCRtpReceiver {
 
     pub(crate) async fn set_transceiver_codecs(
         &self,
-        codecs: Option<Arc<Mutex<Vec<RTCRtpCodecParameters>>>>,
+        codecs: Option<Arc<Mutex<Vec<RTCRtpCodecCapability>>>>,
     ) {
         let mut transceiver_codecs = self.internal.transceiver_codecs.lock().await;
         *transceiver_codecs = codecs;
     }
 
+    /// set_frame_transform sets the FrameTransform applied to the encoded frames of the
+    /// receiver's tracks after they are reassembled by TrackRemote::read_sample, or removes
+    /// it with None.
+    pub async fn set_frame_transform(
+        &self,
+        frame_transform: Option<Arc<dyn FrameTransform + Send + Sync>>,
+    ) {
+        let mut ft = self.internal.frame_transform.lock().await;
+        *ft = frame_transform;
+    }
+
     /// transport returns the currently-configured *DTLSTransport or nil
     /// if one has not yet been configured
     pub fn transport(&self) -> Arc<RTCDtlsTransport> {
@@ -459,7 +534,8 @@ impl RTCRtpReceiver {
     /// SetRTPParameters applies provided RTPParameters the RTPReceiver's tracks.
     /// This method is part of the ORTC API. It is not
     /// meant to be used together with the basic WebRTC API.
-    /// The amount of provided codecs must match the number of tracks on the receiver.
+    /// Each track takes the codec of the payload type it is received with, or else the
+    /// codec at its index, the codecs which are left over are ignored.
     pub async fn set_rtp_parameters(&self, params: RTCRtpParameters) {
         let mut header_extensions = vec![];
         for h in &params.header_extensions {
@@ -470,8 +546,17 @@ impl RTCRtpReceiver {
         }
 
         let mut tracks = self.internal.tracks.write().await;
-        for (idx, codec) in params.codecs.iter().enumerate() {
-            let t = &mut tracks[idx];
+        for (idx, t) in tracks.iter_mut().enumerate() {
+            let payload_type = t.track.payload_type();
+            let codec = match params
+                .codecs
+                .iter()
+                .find(|c| payload_type != 0 && c.payload_type == payload_type)
+                .or_else(|| params.codecs.get(idx))
+            {
+                Some(codec) => codec,
+                None => break,
+            };
             if let Some(stream_info) = &mut t.stream.stream_info {
                 stream_info.rtp_header_extensions = header_extensions.clone();
             }
@@ -499,6 +584,27 @@ impl RTCRtpReceiver {
         tracks.iter().map(|t| Arc::clone(&t.track)).collect()
     }
 
+    /// get_synchronization_sources returns the SSRCs of the packets received in the last 10
+    /// seconds, with their most recent timestamps and audio levels, the most recent first.
+    /// <https://www.w3.org/TR/webrtc/#dom-rtcrtpreceiver-getsynchronizationsources>
+    pub fn get_synchronization_sources(&self) -> Vec<RTCRtpSynchronizationSource> {
+        match self.internal.sources.lock() {
+            Ok(sources) => sources.synchronization_sources(SystemTime::now()),
+            Err(_) => vec![],
+        }
+    }
+
+    /// get_contributing_sources returns the CSRCs of the packets received in the last 10
+    /// seconds, such as the speakers mixed by a conference server, with their most recent
+    /// timestamps and the audio levels of the csrc-audio-level header extension.
+    /// <https://www.w3.org/TR/webrtc/#dom-rtcrtpreceiver-getcontributingsources>
+    pub fn get_contributing_sources(&self) -> Vec<RTCRtpContributingSource> {
+        match self.internal.sources.lock() {
+            Ok(sources) => sources.contributing_sources(SystemTime::now()),
+            Err(_) => vec![],
+        }
+    }
+
     /// receive initialize the track and starts all the transports
     pub async fn receive(&self, parameters: &RTCRtpReceiveParameters) -> Result<()> {
         let receiver = Arc::downgrade(&self.internal);
@@ -517,21 +623,25 @@ impl RTCRtpReceiver {
             )
         };
 
-        let codec = if let Some(codec) = global_params.codecs.first() {
-            codec.capability.clone()
-        } else {
-            RTCRtpCodecCapability::default()
-        };
-
         for encoding in &parameters.encodings {
+            // Without SDP the payload type of the decoding parameters tells the codec
+            let codec = global_params
+                .codecs
+                .iter()
+                .find(|c| encoding.payload_type != 0 && c.payload_type == encoding.payload_type)
+                .or_else(|| global_params.codecs.first())
+                .map(|c| c.capability.clone())
+                .unwrap_or_default();
+
             let (stream_info, rtp_read_stream, rtp_interceptor, rtcp_read_stream, rtcp_interceptor) =
                 if encoding.ssrc != 0 {
                     let stream_info = create_stream_info(
                         "".to_owned(),
                         encoding.ssrc,
-                        0,
+                        encoding.payload_type,
                         codec.clone(),
                         &global_params.header_extensions,
+                        media_engine.extmap_allow_mixed(),
                     );
                     let (rtp_read_stream, rtp_interceptor, rtcp_read_stream, rtcp_interceptor) =
                         self.transport
@@ -549,16 +659,19 @@ impl RTCRtpReceiver {
                     (None, None, None, None, None)
                 };
 
+            let track = Arc::new(TrackRemote::new(
+                self.receive_mtu,
+                self.kind,
+                encoding.ssrc,
+                encoding.rid.clone(),
+                receiver.clone(),
+                Arc::clone(&media_engine),
+                Arc::clone(&interceptor),
+            ));
+            track.set_payload_type(encoding.payload_type);
+
             let t = TrackStreams {
-                track: Arc::new(TrackRemote::new(
-                    self.receive_mtu,
-                    self.kind,
-                    encoding.ssrc,
-                    encoding.rid.clone(),
-                    receiver.clone(),
-                    Arc::clone(&media_engine),
-                    Arc::clone(&interceptor),
-                )),
+                track,
                 stream: TrackStream {
                     stream_info,
                     rtp_read_stream,
@@ -589,6 +702,7 @@ impl RTCRtpReceiver {
                     0,
                     codec.clone(),
                     &global_params.header_extensions,
+                    media_engine.extmap_allow_mixed(),
                 );
                 let (rtp_read_stream, rtp_interceptor, rtcp_read_stream, rtcp_interceptor) = self
                     .transport
@@ -1,15 +1,23 @@
 use super::*;
 use crate::api::media_engine::{MIME_TYPE_OPUS, MIME_TYPE_VP8};
+use crate::api::APIBuilder;
 use crate::error::Result;
 use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
 use crate::peer_connection::peer_connection_test::{
-    close_pair_now, create_vnet_pair, signal_pair, until_connection_state,
+    close_pair_now, create_vnet_pair, new_pair, signal_pair, until_connection_state,
+};
+use crate::rtp_transceiver::frame_transform::EncodedFrame;
+use crate::rtp_transceiver::rtp_codec::{
+    RTCRtpHeaderExtensionCapability, RTCRtpHeaderExtensionParameters,
 };
-use crate::rtp_transceiver::rtp_codec::RTCRtpHeaderExtensionParameters;
 use crate::rtp_transceiver::RTCPFeedback;
+use crate::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 use crate::track::track_local::track_local_static_sample::TrackLocalStaticSample;
 use crate::track::track_local::TrackLocal;
+use crate::track::track_remote::PacketSlot;
+use async_trait::async_trait;
 use bytes::Bytes;
+use media::io::sample_builder::SampleBuilder;
 use media::Sample;
 use tokio::sync::mpsc;
 use tokio::time::Duration;
@@ -240,3 +248,259 @@ async fn test_rtp_receiver_set_read_deadline() -> Result<()> {
 
     Ok(())
 }
+
+#[derive(Debug)]
+struct TrailerFrameTransform {
+    append: bool,
+}
+
+#[async_trait]
+impl FrameTransform for TrailerFrameTransform {
+    async fn transform(&self, frame: EncodedFrame) -> Result<EncodedFrame> {
+        let mut data = frame.data.to_vec();
+        if self.append {
+            data.push(0xFF);
+        } else if data.pop() != Some(0xFF) {
+            return Err(Error::new("frame has no trailer".to_owned()));
+        }
+
+        Ok(EncodedFrame {
+            data: Bytes::from(data),
+            ..frame
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_rtp_receiver_frame_transform() -> Result<()> {
+    let (mut sender, mut receiver, wan) = create_vnet_pair().await?;
+
+    let track: Arc<dyn TrackLocal + Send + Sync> = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let rtp_sender = sender.add_track(Arc::clone(&track)).await?;
+    rtp_sender
+        .set_frame_transform(Some(Arc::new(TrailerFrameTransform { append: true })))
+        .await;
+
+    let (seen_sample_tx, mut seen_sample_rx) = mpsc::channel::<Bytes>(1);
+    receiver.on_track(Box::new(
+        move |track_remote: Option<Arc<TrackRemote>>, receiver: Option<Arc<RTCRtpReceiver>>| {
+            let seen_sample_tx2 = seen_sample_tx.clone();
+            Box::pin(async move {
+                if let (Some(track), Some(r)) = (track_remote, receiver) {
+                    r.set_frame_transform(Some(Arc::new(TrailerFrameTransform { append: false })))
+                        .await;
+
+                    let mut sample_builder =
+                        SampleBuilder::new(10, rtp::codecs::vp8::Vp8Packet::default(), 90000);
+                    if let Ok(sample) = track.read_sample(&mut sample_builder).await {
+                        let _ = seen_sample_tx2.send(sample.data).await;
+                    }
+                }
+            })
+        },
+    ));
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut sender, &wg, RTCPeerConnectionState::Connected).await;
+    until_connection_state(&mut receiver, &wg, RTCPeerConnectionState::Connected).await;
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    wg.wait().await;
+
+    let v = match track.as_any().downcast_ref::<TrackLocalStaticSample>() {
+        Some(v) => v,
+        None => panic!("track is not a TrackLocalStaticSample"),
+    };
+    let data = loop {
+        tokio::select! {
+            data = seen_sample_rx.recv() => break data,
+            _ = tokio::time::sleep(Duration::from_millis(20)) => {
+                v.write_sample(&Sample {
+                    data: Bytes::from_static(&[0xAA, 0xBB]),
+                    duration: Duration::from_millis(20),
+                    ..Default::default()
+                })
+                .await?;
+            }
+        }
+    };
+    assert_eq!(data, Some(Bytes::from_static(&[0xAA, 0xBB])));
+
+    {
+        let mut w = wan.lock().await;
+        w.stop().await?;
+    }
+    close_pair_now(&sender, &receiver).await;
+
+    Ok(())
+}
+
+// Assert that read_rtp_batch returns the packets which are already buffered at once
+#[tokio::test]
+async fn test_track_remote_read_rtp_batch() -> Result<()> {
+    let (mut sender, mut receiver, wan) = create_vnet_pair().await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (track_remote_tx, mut track_remote_rx) = mpsc::channel::<Arc<TrackRemote>>(1);
+    receiver.on_track(Box::new(
+        move |track_remote: Option<Arc<TrackRemote>>, _: Option<Arc<RTCRtpReceiver>>| {
+            let track_remote_tx = track_remote_tx.clone();
+            Box::pin(async move {
+                if let Some(track_remote) = track_remote {
+                    let _ = track_remote_tx.send(track_remote).await;
+                }
+            })
+        },
+    ));
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut sender, &wg, RTCPeerConnectionState::Connected).await;
+    until_connection_state(&mut receiver, &wg, RTCPeerConnectionState::Connected).await;
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    wg.wait().await;
+
+    let sample = Sample {
+        data: Bytes::from_static(&[0xAA]),
+        duration: Duration::from_millis(10),
+        ..Default::default()
+    };
+    track.write_sample(&sample).await?;
+    let track_remote = track_remote_rx.recv().await.unwrap();
+    track_remote.set_buffer_size(1000 * 1000).await?;
+
+    for _ in 0..5 {
+        track.write_sample(&sample).await?;
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let mut slots = vec![PacketSlot::default(); 8];
+    let n = track_remote.read_rtp_batch(&mut slots).await?;
+    assert_eq!(6, n);
+    for i in 1..n {
+        assert_eq!(
+            slots[i - 1].packet.header.sequence_number.wrapping_add(1),
+            slots[i].packet.header.sequence_number
+        );
+    }
+    assert_eq!(0, track_remote.dropped_packets().await);
+
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(100);
+    assert!(track_remote
+        .read_rtp_batch_with_deadline(&mut slots, deadline)
+        .await
+        .is_err());
+
+    {
+        let mut w = wan.lock().await;
+        w.stop().await?;
+    }
+    close_pair_now(&sender, &receiver).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_receiver_synchronization_sources() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    m.register_header_extension(
+        RTCRtpHeaderExtensionCapability {
+            uri: ::sdp::extmap::AUDIO_LEVEL_URI.to_owned(),
+        },
+        RTPCodecType::Audio,
+        None,
+    )?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticRTP::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_OPUS.to_owned(),
+            ..Default::default()
+        },
+        "audio".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (rtp_receiver_tx, mut rtp_receiver_rx) = mpsc::channel::<Arc<RTCRtpReceiver>>(1);
+    receiver.on_track(Box::new(
+        move |track_remote: Option<Arc<TrackRemote>>, rtp_receiver: Option<Arc<RTCRtpReceiver>>| {
+            let rtp_receiver_tx = rtp_receiver_tx.clone();
+            Box::pin(async move {
+                if let (Some(track_remote), Some(rtp_receiver)) = (track_remote, rtp_receiver) {
+                    if track_remote.read_rtp().await.is_ok() {
+                        let _ = rtp_receiver_tx.send(rtp_receiver).await;
+                    }
+                }
+            })
+        },
+    ));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    let packet = rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            timestamp: 960,
+            csrc: vec![7],
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0xAA]),
+    };
+    let extensions = [rtp::extension::HeaderExtension::AudioLevel(
+        rtp::extension::audio_level_extension::AudioLevelExtension {
+            level: 20,
+            voice: true,
+        },
+    )];
+    let rtp_receiver = loop {
+        track
+            .write_rtp_with_extensions(&packet, &extensions)
+            .await?;
+        let timeout = tokio::time::sleep(Duration::from_millis(20));
+        tokio::select! {
+            rtp_receiver = rtp_receiver_rx.recv() => break rtp_receiver.unwrap(),
+            _ = timeout => {}
+        }
+    };
+
+    let ssrcs = rtp_receiver.get_synchronization_sources();
+    assert_eq!(1, ssrcs.len());
+    assert_eq!(960, ssrcs[0].rtp_timestamp);
+    assert!((ssrcs[0].audio_level.unwrap() - 0.1).abs() < 1e-9);
+
+    let csrcs = rtp_receiver.get_contributing_sources();
+    assert_eq!(1, csrcs.len());
+    assert_eq!(7, csrcs[0].source);
+    assert_eq!(None, csrcs[0].audio_level);
+
+    close_pair_now(&sender, &receiver).await;
+
+    Ok(())
+}
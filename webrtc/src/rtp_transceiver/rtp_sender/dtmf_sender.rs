@@ -0,0 +1,311 @@
+use crate::api::media_engine::MIME_TYPE_TELEPHONE_EVENT;
+use crate::error::{Error, Result};
+use crate::rtp_transceiver::rtp_codec::RTCRtpCodecParameters;
+use crate::rtp_transceiver::{PayloadType, SSRC};
+use crate::track::track_local::InterceptorToTrackLocalWriter;
+
+use arc_swap::ArcSwapOption;
+use bytes::Bytes;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// DEFAULT_DURATION is the duration of a tone if none is given.
+pub const DEFAULT_DURATION: Duration = Duration::from_millis(100);
+/// DEFAULT_INTER_TONE_GAP is the gap between two tones if none is given.
+pub const DEFAULT_INTER_TONE_GAP: Duration = Duration::from_millis(70);
+
+/// The bounds of the duration and the inter tone gap of
+/// <https://www.w3.org/TR/webrtc/#dom-rtcdtmfsender-insertdtmf>.
+const MIN_DURATION: Duration = Duration::from_millis(40);
+const MAX_DURATION: Duration = Duration::from_millis(6000);
+const MIN_INTER_TONE_GAP: Duration = Duration::from_millis(30);
+const MAX_INTER_TONE_GAP: Duration = Duration::from_millis(6000);
+
+/// COMMA_DELAY is the pause of a "," in the tones.
+const COMMA_DELAY: Duration = Duration::from_secs(2);
+
+/// PACKET_INTERVAL is the interval between the packets of an event, which report its
+/// duration so far.
+const PACKET_INTERVAL: Duration = Duration::from_millis(50);
+/// END_PACKET_COUNT is the number of times the final packet of an event is sent,
+/// <https://www.rfc-editor.org/rfc/rfc4733#section-2.5.1.4>.
+const END_PACKET_COUNT: usize = 3;
+/// VOLUME is the power level of the tones in -dBm0.
+const VOLUME: u8 = 10;
+
+pub type OnToneChangeHdlrFn =
+    Box<dyn (FnMut(String) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
+
+/// event_code returns the event code of a DTMF tone, <https://www.rfc-editor.org/rfc/rfc4733#section-3.2>.
+fn event_code(tone: char) -> Option<u8> {
+    match tone {
+        '0'..='9' => Some(tone as u8 - b'0'),
+        '*' => Some(10),
+        '#' => Some(11),
+        'A'..='D' => Some(tone as u8 - b'A' + 12),
+        _ => None,
+    }
+}
+
+/// event_payload marshals the payload of a telephone-event packet,
+/// <https://www.rfc-editor.org/rfc/rfc4733#section-2.3>.
+pub(crate) fn event_payload(event: u8, end: bool, duration: u16) -> Bytes {
+    let [d0, d1] = duration.to_be_bytes();
+    Bytes::from(vec![event, (end as u8) << 7 | VOLUME, d0, d1])
+}
+
+/// DtmfBinding is what the tones are sent with, set once the sender sends.
+#[derive(Clone)]
+pub(crate) struct DtmfBinding {
+    pub(crate) write_stream: Arc<InterceptorToTrackLocalWriter>,
+    pub(crate) ssrc: SSRC,
+    pub(crate) payload_type: PayloadType,
+    pub(crate) clock_rate: u32,
+}
+
+impl DtmfBinding {
+    /// new returns a binding with the telephone-event codec of codecs with the clock rate
+    /// of the codec of the audio, or None if it wasn't negotiated.
+    pub(crate) fn new(
+        write_stream: Arc<InterceptorToTrackLocalWriter>,
+        ssrc: SSRC,
+        codecs: &[RTCRtpCodecParameters],
+        clock_rate: u32,
+    ) -> Option<Self> {
+        let codec = codecs.iter().find(|c| {
+            c.capability
+                .mime_type
+                .eq_ignore_ascii_case(MIME_TYPE_TELEPHONE_EVENT)
+                && c.capability.clock_rate == clock_rate
+        })?;
+
+        Some(DtmfBinding {
+            write_stream,
+            ssrc,
+            payload_type: codec.payload_type,
+            clock_rate,
+        })
+    }
+
+    fn packet(&self, marker: bool, timestamp: u32, payload: Bytes) -> rtp::packet::Packet {
+        rtp::packet::Packet {
+            header: rtp::header::Header {
+                version: 2,
+                marker,
+                payload_type: self.payload_type,
+                timestamp,
+                ssrc: self.ssrc,
+                ..Default::default()
+            },
+            payload,
+        }
+    }
+
+    /// play_tone sends the packets of an event of duration, in segments if the duration
+    /// doesn't fit the 16 bits of the duration field.
+    async fn play_tone(&self, event: u8, duration: Duration) -> Result<()> {
+        let total = (duration.as_secs_f64() * self.clock_rate as f64).round() as u64;
+        let interval = (PACKET_INTERVAL.as_secs_f64() * self.clock_rate as f64).round() as u64;
+
+        let mut timestamp = self.write_stream.timestamp(self.clock_rate);
+        let mut marker = true;
+        let mut segment_start = 0u64;
+        let mut sent = 0u64;
+        let mut ticker = tokio::time::interval(PACKET_INTERVAL);
+        loop {
+            ticker.tick().await;
+            sent = (sent + interval).min(total);
+            if sent - segment_start > u16::MAX as u64 {
+                // A new segment starts where the previous one ended
+                timestamp = timestamp.wrapping_add(u16::MAX as u32);
+                segment_start += u16::MAX as u64;
+            }
+            let segment_duration = (sent - segment_start) as u16;
+
+            if sent >= total {
+                for _ in 0..END_PACKET_COUNT {
+                    let mut pkt = self.packet(
+                        marker,
+                        timestamp,
+                        event_payload(event, true, segment_duration),
+                    );
+                    self.write_stream.write_inserted_rtp(&mut pkt).await?;
+                    marker = false;
+                }
+                return Ok(());
+            }
+
+            let mut pkt = self.packet(
+                marker,
+                timestamp,
+                event_payload(event, false, segment_duration),
+            );
+            self.write_stream.write_inserted_rtp(&mut pkt).await?;
+            marker = false;
+        }
+    }
+}
+
+struct DtmfState {
+    tone_buffer: String,
+    duration: Duration,
+    inter_tone_gap: Duration,
+    playing: bool,
+}
+
+struct DtmfSenderInternal {
+    binding: std::sync::Mutex<Option<DtmfBinding>>,
+    state: std::sync::Mutex<DtmfState>,
+    on_tone_change_handler: ArcSwapOption<Mutex<OnToneChangeHdlrFn>>,
+}
+
+impl DtmfSenderInternal {
+    /// next_tone removes the first tone of the tone buffer, or marks the playout as done
+    /// if there isn't any.
+    fn next_tone(&self) -> Option<(char, Duration, Duration)> {
+        let mut state = self.state.lock().unwrap();
+        if state.tone_buffer.is_empty() {
+            state.playing = false;
+            return None;
+        }
+        let tone = state.tone_buffer.remove(0);
+        Some((tone, state.duration, state.inter_tone_gap))
+    }
+
+    async fn play(self: Arc<Self>) {
+        while let Some((tone, duration, inter_tone_gap)) = self.next_tone() {
+            self.fire_tone_change(tone.to_string()).await;
+
+            if tone == ',' {
+                tokio::time::sleep(COMMA_DELAY).await;
+                continue;
+            }
+
+            let binding = self.binding.lock().unwrap().clone();
+            if let Some(binding) = binding {
+                if let Some(event) = event_code(tone) {
+                    if let Err(err) = binding.play_tone(event, duration).await {
+                        log::warn!("Failed to send DTMF tone {}: {}", tone, err);
+                    }
+                }
+            }
+            tokio::time::sleep(inter_tone_gap).await;
+        }
+
+        self.fire_tone_change(String::new()).await;
+    }
+
+    async fn fire_tone_change(&self, tone: String) {
+        if let Some(handler) = &*self.on_tone_change_handler.load() {
+            let mut f = handler.lock().await;
+            f(tone).await;
+        }
+    }
+}
+
+/// RTCDtmfSender sends DTMF tones as telephone-event packets (RFC 4733) in the RTP stream
+/// of the audio track of a RTCRtpSender.
+/// <https://www.w3.org/TR/webrtc/#rtcdtmfsender>
+pub struct RTCDtmfSender {
+    internal: Arc<DtmfSenderInternal>,
+}
+
+impl Default for RTCDtmfSender {
+    fn default() -> Self {
+        RTCDtmfSender {
+            internal: Arc::new(DtmfSenderInternal {
+                binding: std::sync::Mutex::new(None),
+                state: std::sync::Mutex::new(DtmfState {
+                    tone_buffer: String::new(),
+                    duration: DEFAULT_DURATION,
+                    inter_tone_gap: DEFAULT_INTER_TONE_GAP,
+                    playing: false,
+                }),
+                on_tone_change_handler: ArcSwapOption::empty(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Debug for RTCDtmfSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RTCDtmfSender")
+            .field("tone_buffer", &self.tone_buffer())
+            .finish()
+    }
+}
+
+impl RTCDtmfSender {
+    pub(crate) fn bind(&self, binding: Option<DtmfBinding>) {
+        let mut b = self.internal.binding.lock().unwrap();
+        *b = binding;
+    }
+
+    /// stop drops the tones not sent yet, once the sender is stopped.
+    pub(crate) fn stop(&self) {
+        self.bind(None);
+        let mut state = self.internal.state.lock().unwrap();
+        state.tone_buffer.clear();
+    }
+
+    /// can_insert_dtmf tells whether the sender sends and negotiated telephone-event with
+    /// the clock rate of its codec.
+    pub fn can_insert_dtmf(&self) -> bool {
+        self.internal.binding.lock().unwrap().is_some()
+    }
+
+    /// insert_dtmf replaces the tones waiting to be sent with tones, which are 0-9, A-D, #
+    /// and *, or a "," for a pause of 2 seconds. Each tone is sent for duration, between
+    /// 40ms and 6s, and followed by inter_tone_gap, of at least 30ms. An empty tones cancels
+    /// the tones not sent yet.
+    pub fn insert_dtmf(
+        &self,
+        tones: &str,
+        duration: Duration,
+        inter_tone_gap: Duration,
+    ) -> Result<()> {
+        if !self.can_insert_dtmf() {
+            return Err(Error::ErrDtmfCanNotInsert);
+        }
+        let tones = tones.to_ascii_uppercase();
+        if !tones.chars().all(|t| t == ',' || event_code(t).is_some()) {
+            return Err(Error::ErrDtmfInvalidTone);
+        }
+
+        let start = {
+            let mut state = self.internal.state.lock().unwrap();
+            state.tone_buffer = tones;
+            state.duration = duration.max(MIN_DURATION).min(MAX_DURATION);
+            state.inter_tone_gap = inter_tone_gap
+                .max(MIN_INTER_TONE_GAP)
+                .min(MAX_INTER_TONE_GAP);
+            let start = !state.playing && !state.tone_buffer.is_empty();
+            if start {
+                state.playing = true;
+            }
+            start
+        };
+        if start {
+            tokio::spawn(Arc::clone(&self.internal).play());
+        }
+
+        Ok(())
+    }
+
+    /// tone_buffer returns the tones which are waiting to be sent.
+    pub fn tone_buffer(&self) -> String {
+        let state = self.internal.state.lock().unwrap();
+        state.tone_buffer.clone()
+    }
+
+    /// on_tone_change sets a handler that is fired with each tone as it starts to be sent,
+    /// and with an empty string once all the tones are sent.
+    pub fn on_tone_change(&self, f: OnToneChangeHdlrFn) {
+        self.internal
+            .on_tone_change_handler
+            .store(Some(Arc::new(Mutex::new(f))));
+    }
+}
@@ -1,10 +1,15 @@
 #[cfg(test)]
 mod rtp_sender_test;
 
+pub mod dtmf_sender;
+
 use crate::api::media_engine::MediaEngine;
 use crate::dtls_transport::RTCDtlsTransport;
-use crate::error::{Error, Result};
-use crate::rtp_transceiver::rtp_codec::{RTCRtpCodecParameters, RTPCodecType};
+use crate::error::{flatten_errs, Error, Result};
+use crate::rtp_transceiver::frame_transform::FrameTransform;
+use crate::rtp_transceiver::rtp_codec::{RTCRtpCodecParameters, RTPCodecType, ScalabilityMode};
+use crate::rtp_transceiver::rtp_degradation_preference::RTCDegradationPreference;
+use crate::rtp_transceiver::rtp_sender::dtmf_sender::{DtmfBinding, RTCDtmfSender};
 use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
 use crate::rtp_transceiver::srtp_writer_future::SrtpWriterFuture;
 use crate::rtp_transceiver::{
@@ -12,46 +17,75 @@ use crate::rtp_transceiver::{
     RTCRtpTransceiver, SSRC,
 };
 use crate::track::track_local::{
-    InterceptorToTrackLocalWriter, TrackLocal, TrackLocalContext, TrackLocalWriter,
+    InterceptorToTrackLocalWriter, KeyframeRequest, KeyframeRequestHandler, TrackLocal,
+    TrackLocalContext, TrackLocalWriter,
 };
 
+use arc_swap::ArcSwapOption;
+use bytes::Bytes;
 use ice::rand::generate_crypto_random_string;
 use interceptor::stream_info::StreamInfo;
 use interceptor::{Attributes, Interceptor, RTCPReader, RTPWriter};
-use std::sync::atomic::{AtomicBool, Ordering};
+use rtcp::payload_feedbacks::full_intra_request::FullIntraRequest;
+use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
 use std::sync::{Arc, Weak};
 use tokio::sync::{mpsc, Mutex, Notify};
 
+/// RTCEncoderTarget describes what the application's encoder of the track of a RTPSender
+/// should produce, so that an adaptive encoder can follow the negotiated policy.
+#[derive(Debug, Clone)]
+pub struct RTCEncoderTarget {
+    /// bandwidth_estimate is the latest bandwidth estimate of the sender in bits per second,
+    /// if one is available.
+    pub bandwidth_estimate: Option<u64>,
+    /// degradation_preference tells whether the framerate or the resolution should be kept
+    /// when the encoder has to lower its bitrate.
+    pub degradation_preference: RTCDegradationPreference,
+    /// encodings are the current parameters of the encodings of the sender.
+    pub encodings: Vec<RTCRtpEncodingParameters>,
+}
+
+pub type OnEncoderTargetHdlrFn = Box<
+    dyn (FnMut(RTCEncoderTarget) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
+/// RTCP_READ_QUEUE_SIZE is the number of RTCP packets of an encoding which are kept for
+/// read, the newer ones are dropped while the queue is full.
+const RTCP_READ_QUEUE_SIZE: usize = 64;
+
+type RTCPReadQueue = Mutex<mpsc::Receiver<(Bytes, Attributes)>>;
+
 pub(crate) struct RTPSenderInternal {
     pub(crate) send_called_rx: Mutex<mpsc::Receiver<()>>,
     pub(crate) stop_called_rx: Arc<Notify>,
     pub(crate) stop_called_signal: Arc<AtomicBool>,
-    pub(crate) rtcp_interceptor: Mutex<Option<Arc<dyn RTCPReader + Send + Sync>>>,
 }
 
 impl RTPSenderInternal {
     /// read reads incoming RTCP for this RTPReceiver
-    async fn read(&self, b: &mut [u8]) -> Result<(usize, Attributes)> {
+    async fn read(&self, b: &mut [u8], rtcp_rx: &RTCPReadQueue) -> Result<(usize, Attributes)> {
         let mut send_called_rx = self.send_called_rx.lock().await;
 
         tokio::select! {
             _ = send_called_rx.recv() =>{
-                let rtcp_interceptor = {
-                    let rtcp_interceptor = self.rtcp_interceptor.lock().await;
-                    rtcp_interceptor.clone()
-                };
-                if let Some(rtcp_interceptor) = rtcp_interceptor{
-                    let a = Attributes::new();
-                    tokio::select! {
-                        _ = self.stop_called_rx.notified() => {
-                            Err(Error::ErrClosedPipe)
-                        }
-                        result = rtcp_interceptor.read(b, &a) => {
-                            Ok(result?)
+                let mut rtcp_rx = rtcp_rx.lock().await;
+                tokio::select! {
+                    _ = self.stop_called_rx.notified() => {
+                        Err(Error::ErrClosedPipe)
+                    }
+                    packet = rtcp_rx.recv() => {
+                        let (packet, attributes) = packet.ok_or(Error::ErrClosedPipe)?;
+                        if b.len() < packet.len() {
+                            return Err(Error::Util(util::Error::ErrBufferShort));
                         }
+                        b[..packet.len()].copy_from_slice(&packet);
+                        Ok((packet.len(), attributes))
                     }
-                }else{
-                    Err(Error::ErrInterceptorNotBind)
                 }
             }
             _ = self.stop_called_rx.notified() =>{
@@ -64,9 +98,10 @@ impl RTPSenderInternal {
     async fn read_rtcp(
         &self,
         receive_mtu: usize,
+        rtcp_rx: &RTCPReadQueue,
     ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
         let mut b = vec![0u8; receive_mtu];
-        let (n, attributes) = self.read(&mut b).await?;
+        let (n, attributes) = self.read(&mut b, rtcp_rx).await?;
 
         let mut buf = &b[..n];
         let pkts = rtcp::packet::unmarshal(&mut buf)?;
@@ -75,15 +110,117 @@ impl RTPSenderInternal {
     }
 }
 
-/// RTPSender allows an application to control how a given Track is encoded and transmitted to a remote peer
-pub struct RTCRtpSender {
-    pub(crate) track: Mutex<Option<Arc<dyn TrackLocal + Send + Sync>>>,
+/// TrackEncoding is a single encoding of the track sent by a RTPSender. A sender sending
+/// simulcast has one per layer, each sent on its own SSRC and identified by its RID.
+pub(crate) struct TrackEncoding {
+    pub(crate) rid: String,
+    pub(crate) ssrc: SSRC,
 
     pub(crate) srtp_stream: Arc<SrtpWriterFuture>,
+    pub(crate) rtcp_interceptor: Arc<dyn RTCPReader + Send + Sync>,
     pub(crate) stream_info: Mutex<StreamInfo>,
 
     pub(crate) context: Mutex<TrackLocalContext>,
 
+    /// paused is set while either the sender is paused or the encoding isn't active
+    pub(crate) paused: Arc<AtomicBool>,
+    pub(crate) parameters: Arc<std::sync::Mutex<RTCRtpEncodingParameters>>,
+
+    keyframe_request_handler: Arc<KeyframeRequestHandler>,
+    rtcp_tx: Mutex<Option<mpsc::Sender<(Bytes, Attributes)>>>,
+    rtcp_rx: RTCPReadQueue,
+}
+
+impl TrackEncoding {
+    /// context_id is the id of the TrackLocalContext of the encoding, which must be unique
+    /// for a track bound once per encoding.
+    fn context_id(&self, sender_id: &str) -> String {
+        if self.rid.is_empty() {
+            sender_id.to_owned()
+        } else {
+            format!("{}-{}", sender_id, self.rid)
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        let parameters = self.parameters.lock().unwrap();
+        parameters.active
+    }
+
+    /// start_rtcp_reader starts reading the RTCP of the encoding through the interceptors, so
+    /// that they see it and the keyframe requests for ssrc are handed to the track even if the
+    /// application doesn't read the RTCP. The packets are queued for RTCRtpSender::read.
+    async fn start_rtcp_reader(
+        &self,
+        ssrc: SSRC,
+        receive_mtu: usize,
+        internal: &RTPSenderInternal,
+    ) {
+        let rtcp_tx = match self.rtcp_tx.lock().await.take() {
+            Some(rtcp_tx) => rtcp_tx,
+            None => return,
+        };
+        let rtcp_interceptor = Arc::clone(&self.rtcp_interceptor);
+        let keyframe_request_handler = Arc::clone(&self.keyframe_request_handler);
+        let stop_called_rx = Arc::clone(&internal.stop_called_rx);
+        let stop_called_signal = Arc::clone(&internal.stop_called_signal);
+        let rid = self.rid.clone();
+
+        tokio::spawn(async move {
+            let mut b = vec![0u8; receive_mtu];
+            let a = Attributes::new();
+            while !stop_called_signal.load(Ordering::SeqCst) {
+                let (n, attributes) = tokio::select! {
+                    _ = stop_called_rx.notified() => break,
+                    result = rtcp_interceptor.read(&mut b, &a) => match result {
+                        Ok(result) => result,
+                        Err(_) => break,
+                    },
+                };
+
+                let mut buf = &b[..n];
+                if let Ok(pkts) = rtcp::packet::unmarshal(&mut buf) {
+                    for pkt in &pkts {
+                        let full_intra_request = if let Some(pli) =
+                            pkt.as_any().downcast_ref::<PictureLossIndication>()
+                        {
+                            if pli.media_ssrc != ssrc {
+                                continue;
+                            }
+                            false
+                        } else if let Some(fir) = pkt.as_any().downcast_ref::<FullIntraRequest>() {
+                            if !fir.fir.iter().any(|entry| entry.ssrc == ssrc) {
+                                continue;
+                            }
+                            true
+                        } else {
+                            continue;
+                        };
+
+                        keyframe_request_handler
+                            .fire(KeyframeRequest {
+                                ssrc,
+                                rid: rid.clone(),
+                                full_intra_request,
+                            })
+                            .await;
+                    }
+                }
+
+                let _ = rtcp_tx.try_send((Bytes::copy_from_slice(&b[..n]), attributes));
+            }
+        });
+    }
+}
+
+/// RTPSender allows an application to control how a given Track is encoded and transmitted to a remote peer
+pub struct RTCRtpSender {
+    pub(crate) track: Mutex<Option<Arc<dyn TrackLocal + Send + Sync>>>,
+
+    /// track_encodings holds at least one encoding, the first one is the one of a sender
+    /// which doesn't send simulcast.
+    pub(crate) track_encodings: Vec<TrackEncoding>,
+
     pub(crate) transport: Arc<RTCDtlsTransport>,
 
     pub(crate) payload_type: PayloadType,
@@ -113,6 +250,16 @@ pub struct RTCRtpSender {
 
     pub(crate) paused: Arc<AtomicBool>,
 
+    frame_transform: Arc<Mutex<Option<Arc<dyn FrameTransform + Send + Sync>>>>,
+
+    degradation_preference: AtomicU8,
+    /// bandwidth_estimate is the latest bandwidth estimate in bits per second, 0 if none
+    bandwidth_estimate: AtomicU64,
+    on_encoder_target_handler: ArcSwapOption<Mutex<OnEncoderTargetHdlrFn>>,
+
+    /// dtmf sends the DTMF tones of an audio sender
+    dtmf: Option<Arc<RTCDtmfSender>>,
+
     internal: Arc<RTPSenderInternal>,
 }
 
@@ -132,6 +279,31 @@ impl RTCRtpSender {
         media_engine: Arc<MediaEngine>,
         interceptor: Arc<dyn Interceptor + Send + Sync>,
         start_paused: bool,
+    ) -> RTCRtpSender {
+        RTCRtpSender::new_with_send_encodings(
+            receive_mtu,
+            track,
+            transport,
+            media_engine,
+            interceptor,
+            start_paused,
+            &[],
+        )
+        .await
+    }
+
+    /// new_with_send_encodings creates a RTPSender which sends the track once per send
+    /// encoding, as simulcast if there is more than one of them. Each encoding is sent on a
+    /// SSRC of its own, its ssrc and payload_type are ignored. Without any send encoding the
+    /// sender sends a single encoding, like RTCRtpSender::new.
+    pub async fn new_with_send_encodings(
+        receive_mtu: usize,
+        track: Arc<dyn TrackLocal + Send + Sync>,
+        transport: Arc<RTCDtlsTransport>,
+        media_engine: Arc<MediaEngine>,
+        interceptor: Arc<dyn Interceptor + Send + Sync>,
+        start_paused: bool,
+        send_encodings: &[RTCRtpEncodingParameters],
     ) -> RTCRtpSender {
         let id = generate_crypto_random_string(
             32,
@@ -140,40 +312,65 @@ impl RTCRtpSender {
         let (send_called_tx, send_called_rx) = mpsc::channel(1);
         let stop_called_tx = Arc::new(Notify::new());
         let stop_called_rx = stop_called_tx.clone();
-        let ssrc = rand::random::<u32>();
         let stop_called_signal = Arc::new(AtomicBool::new(false));
 
         let internal = Arc::new(RTPSenderInternal {
             send_called_rx: Mutex::new(send_called_rx),
             stop_called_rx,
             stop_called_signal: Arc::clone(&stop_called_signal),
-            rtcp_interceptor: Mutex::new(None),
         });
 
-        let srtp_stream = Arc::new(SrtpWriterFuture {
-            closed: AtomicBool::new(false),
-            ssrc,
-            rtp_sender: Arc::downgrade(&internal),
-            rtp_transport: Arc::clone(&transport),
-            rtcp_read_stream: Mutex::new(None),
-            rtp_write_session: Mutex::new(None),
-        });
+        let send_encodings = if send_encodings.is_empty() {
+            vec![RTCRtpEncodingParameters::default()]
+        } else {
+            send_encodings.to_vec()
+        };
 
-        let srtp_rtcp_reader = Arc::clone(&srtp_stream) as Arc<dyn RTCPReader + Send + Sync>;
-        let rtcp_interceptor = interceptor.bind_rtcp_reader(srtp_rtcp_reader).await;
-        {
-            let mut internal_rtcp_interceptor = internal.rtcp_interceptor.lock().await;
-            *internal_rtcp_interceptor = Some(rtcp_interceptor);
+        let mut track_encodings = Vec::with_capacity(send_encodings.len());
+        for mut parameters in send_encodings {
+            let ssrc = rand::random::<u32>();
+            parameters.ssrc = ssrc;
+
+            let srtp_stream = Arc::new(SrtpWriterFuture {
+                closed: AtomicBool::new(false),
+                ssrc,
+                rtp_sender: Arc::downgrade(&internal),
+                rtp_transport: Arc::clone(&transport),
+                rtcp_read_stream: Mutex::new(None),
+                rtp_write_session: Mutex::new(None),
+            });
+
+            let srtp_rtcp_reader = Arc::clone(&srtp_stream) as Arc<dyn RTCPReader + Send + Sync>;
+            let rtcp_interceptor = interceptor.bind_rtcp_reader(srtp_rtcp_reader).await;
+            let (rtcp_tx, rtcp_rx) = mpsc::channel(RTCP_READ_QUEUE_SIZE);
+
+            track_encodings.push(TrackEncoding {
+                rid: parameters.rid.clone(),
+                ssrc,
+                srtp_stream,
+                rtcp_interceptor,
+                stream_info: Mutex::new(StreamInfo::default()),
+                context: Mutex::new(TrackLocalContext::default()),
+                paused: Arc::new(AtomicBool::new(start_paused || !parameters.active)),
+                parameters: Arc::new(std::sync::Mutex::new(parameters)),
+                keyframe_request_handler: Arc::new(KeyframeRequestHandler::default()),
+                rtcp_tx: Mutex::new(Some(rtcp_tx)),
+                rtcp_rx: Mutex::new(rtcp_rx),
+            });
         }
+        let ssrc = track_encodings[0].ssrc;
 
         let stream_ids = vec![track.stream_id().to_string()];
+        let dtmf = if track.kind() == RTPCodecType::Audio {
+            Some(Arc::new(RTCDtmfSender::default()))
+        } else {
+            None
+        };
         RTCRtpSender {
             track: Mutex::new(Some(track)),
 
-            srtp_stream,
-            stream_info: Mutex::new(StreamInfo::default()),
+            track_encodings,
 
-            context: Mutex::new(TrackLocalContext::default()),
             transport,
 
             payload_type: 0,
@@ -197,6 +394,14 @@ impl RTCRtpSender {
 
             paused: Arc::new(AtomicBool::new(start_paused)),
 
+            frame_transform: Arc::new(Mutex::new(None)),
+
+            degradation_preference: AtomicU8::new(RTCDegradationPreference::Balanced as u8),
+            bandwidth_estimate: AtomicU64::new(0),
+            on_encoder_target_handler: ArcSwapOption::empty(),
+
+            dtmf,
+
             internal,
         }
     }
@@ -222,6 +427,168 @@ impl RTCRtpSender {
 
     pub(crate) fn set_paused(&self, paused: bool) {
         self.paused.store(paused, Ordering::SeqCst);
+        for encoding in &self.track_encodings {
+            encoding
+                .paused
+                .store(paused || !encoding.is_active(), Ordering::SeqCst);
+        }
+    }
+
+    /// rids returns the RIDs of the simulcast encodings of the sender, or nothing if it sends
+    /// a single encoding.
+    pub fn rids(&self) -> Vec<String> {
+        self.track_encodings
+            .iter()
+            .filter(|e| !e.rid.is_empty())
+            .map(|e| e.rid.clone())
+            .collect()
+    }
+
+    /// set_encoding_active starts or stops sending the simulcast encoding with the given RID,
+    /// which doesn't require a renegotiation. While an encoding isn't active the packets
+    /// written for it are dropped, like for a paused sender.
+    pub fn set_encoding_active(&self, rid: &str, active: bool) -> Result<()> {
+        let encoding = self.encoding_for_rid(rid)?;
+
+        {
+            let mut parameters = encoding.parameters.lock().unwrap();
+            parameters.active = active;
+        }
+        encoding.paused.store(
+            self.paused.load(Ordering::SeqCst) || !active,
+            Ordering::SeqCst,
+        );
+
+        Ok(())
+    }
+
+    /// set_parameters updates the parameters of the encodings, which takes effect without a
+    /// renegotiation: an inactive encoding stops sending, and the other values are handed to
+    /// the track through TrackLocalContext::encoding_parameters. The parameters are expected to
+    /// come from get_parameters, only the degradation_preference and active, max_bitrate,
+    /// max_framerate, priority and scale_resolution_down_by of the encodings may be changed.
+    /// The on_encoder_target handler is fired with the new parameters.
+    /// <https://w3c.github.io/webrtc-pc/#dom-rtcrtpsender-setparameters>
+    pub async fn set_parameters(&self, parameters: RTCRtpSendParameters) -> Result<()> {
+        if self.has_stopped().await {
+            return Err(Error::ErrRTPSenderStopped);
+        }
+
+        let current = self.get_parameters().await;
+        if parameters.encodings.len() != current.encodings.len()
+            || parameters.rtp_parameters.codecs != current.rtp_parameters.codecs
+            || parameters.rtp_parameters.header_extensions
+                != current.rtp_parameters.header_extensions
+        {
+            return Err(Error::ErrRTPSenderInvalidModification);
+        }
+        for (encoding, current) in parameters.encodings.iter().zip(&current.encodings) {
+            if encoding.rid != current.rid
+                || encoding.ssrc != current.ssrc
+                || encoding.payload_type != current.payload_type
+                || encoding.rtx.ssrc != current.rtx.ssrc
+                || encoding.scalability_mode != current.scalability_mode
+            {
+                return Err(Error::ErrRTPSenderInvalidModification);
+            }
+            if encoding.scale_resolution_down_by.map_or(false, |s| s < 1.0)
+                || encoding.max_framerate.map_or(false, |f| f < 0.0)
+            {
+                return Err(Error::ErrRTPSenderEncodingParameterOutOfRange);
+            }
+        }
+
+        let paused = self.paused.load(Ordering::SeqCst);
+        for (track_encoding, encoding) in self.track_encodings.iter().zip(parameters.encodings) {
+            {
+                let mut parameters = track_encoding.parameters.lock().unwrap();
+                parameters.active = encoding.active;
+                parameters.max_bitrate = encoding.max_bitrate;
+                parameters.max_framerate = encoding.max_framerate;
+                parameters.priority = encoding.priority;
+                parameters.scale_resolution_down_by = encoding.scale_resolution_down_by;
+            }
+            track_encoding
+                .paused
+                .store(paused || !encoding.active, Ordering::SeqCst);
+        }
+        let degradation_preference = match parameters.degradation_preference {
+            RTCDegradationPreference::Unspecified => RTCDegradationPreference::Balanced,
+            preference => preference,
+        };
+        self.degradation_preference
+            .store(degradation_preference as u8, Ordering::SeqCst);
+
+        self.fire_encoder_target().await;
+
+        Ok(())
+    }
+
+    /// degradation_preference returns how the encoder of the track should degrade the video
+    /// when it has to lower its bitrate.
+    pub fn degradation_preference(&self) -> RTCDegradationPreference {
+        self.degradation_preference.load(Ordering::SeqCst).into()
+    }
+
+    /// set_bandwidth_estimate hands a bandwidth estimate for the sender in bits per second,
+    /// for instance from the REMB or transport-cc feedback read with read_rtcp, to the
+    /// on_encoder_target handler.
+    pub async fn set_bandwidth_estimate(&self, bitrate: u64) {
+        self.bandwidth_estimate.store(bitrate, Ordering::SeqCst);
+        self.fire_encoder_target().await;
+    }
+
+    /// encoder_target returns what the application's encoder of the track should currently
+    /// produce.
+    pub fn encoder_target(&self) -> RTCEncoderTarget {
+        let bandwidth_estimate = self.bandwidth_estimate.load(Ordering::SeqCst);
+        RTCEncoderTarget {
+            bandwidth_estimate: if bandwidth_estimate == 0 {
+                None
+            } else {
+                Some(bandwidth_estimate)
+            },
+            degradation_preference: self.degradation_preference(),
+            encodings: self
+                .track_encodings
+                .iter()
+                .map(|e| e.parameters.lock().unwrap().clone())
+                .collect(),
+        }
+    }
+
+    /// on_encoder_target sets a handler that is fired with the encoder target whenever the
+    /// bandwidth estimate or the parameters of the sender change, so that an adaptive
+    /// encoder can follow them.
+    pub fn on_encoder_target(&self, f: OnEncoderTargetHdlrFn) {
+        self.on_encoder_target_handler
+            .store(Some(Arc::new(Mutex::new(f))));
+    }
+
+    async fn fire_encoder_target(&self) {
+        if let Some(handler) = &*self.on_encoder_target_handler.load() {
+            let target = self.encoder_target();
+            let mut f = handler.lock().await;
+            f(target).await;
+        }
+    }
+
+    /// set_frame_transform sets the FrameTransform applied to the encoded frames of the track
+    /// before they are packetized, or removes it with None. It only applies to tracks which
+    /// packetize frames themselves, like TrackLocalStaticSample, and takes effect with the
+    /// next frame, also for a track which is already sending.
+    pub async fn set_frame_transform(
+        &self,
+        frame_transform: Option<Arc<dyn FrameTransform + Send + Sync>>,
+    ) {
+        let mut ft = self.frame_transform.lock().await;
+        *ft = frame_transform;
+    }
+
+    /// dtmf returns the RTCDtmfSender which sends DTMF tones along the track, or None if the
+    /// sender isn't an audio sender.
+    pub fn dtmf(&self) -> Option<Arc<RTCDtmfSender>> {
+        self.dtmf.clone()
     }
 
     /// transport returns the currently-configured DTLSTransport
@@ -248,11 +615,18 @@ impl RTCRtpSender {
                     .media_engine
                     .get_rtp_parameters_by_kind(kind, RTCRtpTransceiverDirection::Sendonly)
                     .await,
-                encodings: vec![RTCRtpEncodingParameters {
-                    ssrc: self.ssrc,
-                    payload_type: self.payload_type,
-                    ..Default::default()
-                }],
+                encodings: self
+                    .track_encodings
+                    .iter()
+                    .map(|e| {
+                        let parameters = e.parameters.lock().unwrap();
+                        RTCRtpEncodingParameters {
+                            payload_type: self.payload_type,
+                            ..parameters.clone()
+                        }
+                    })
+                    .collect(),
+                degradation_preference: self.degradation_preference(),
             }
         };
 
@@ -270,6 +644,18 @@ impl RTCRtpSender {
         };
         send_parameters.rtp_parameters.codecs = codecs;
 
+        // Once sent, an encoding has the payload type of the codec its track is bound to
+        for (parameters, encoding) in send_parameters
+            .encodings
+            .iter_mut()
+            .zip(&self.track_encodings)
+        {
+            let context = encoding.context.lock().await;
+            if let Some(codec) = context.params.codecs.first() {
+                parameters.payload_type = codec.payload_type;
+            }
+        }
+
         send_parameters
     }
 
@@ -307,8 +693,11 @@ impl RTCRtpSender {
                 t.clone()
             };
             if let Some(t) = t {
-                let context = self.context.lock().await;
-                t.unbind(&context).await?;
+                for encoding in &self.track_encodings {
+                    let context = encoding.context.lock().await;
+                    t.unbind(&context).await?;
+                    encoding.keyframe_request_handler.store(None);
+                }
             }
         }
 
@@ -318,12 +707,21 @@ impl RTCRtpSender {
             return Ok(());
         }
 
-        let context = {
-            let context = self.context.lock().await;
-            context.clone()
+        let t = match &track {
+            Some(t) => t,
+            None => return Err(Error::ErrRTPSenderTrackNil),
         };
 
-        let result = if let Some(t) = &track {
+        let mut contexts = Vec::with_capacity(self.track_encodings.len());
+        for encoding in &self.track_encodings {
+            let context = encoding.context.lock().await;
+            contexts.push(context.clone());
+        }
+
+        // Bind the new track once per encoding
+        let mut bound = vec![];
+        let mut result = Ok(());
+        for (encoding, context) in self.track_encodings.iter().zip(&contexts) {
             let new_context = TrackLocalContext {
                 id: context.id.clone(),
                 params: self
@@ -331,40 +729,55 @@ impl RTCRtpSender {
                     .get_rtp_parameters_by_kind(t.kind(), RTCRtpTransceiverDirection::Sendonly)
                     .await,
                 ssrc: context.ssrc,
+                rid: context.rid.clone(),
+                scalability_mode: context.scalability_mode,
+                encoding_parameters: Arc::clone(&encoding.parameters),
                 write_stream: context.write_stream.clone(),
-                paused: self.paused.clone(),
+                paused: encoding.paused.clone(),
+                extmap_allow_mixed: self.media_engine.extmap_allow_mixed(),
+                frame_transform: Arc::clone(&self.frame_transform),
+                keyframe_request_handler: Arc::clone(&encoding.keyframe_request_handler),
             };
 
-            t.bind(&new_context).await
-        } else {
-            Err(Error::ErrRTPSenderTrackNil)
-        };
-
-        match result {
-            Err(err) => {
-                // Re-bind the original track
-                let track = self.track.lock().await;
-                if let Some(t) = &*track {
-                    t.bind(&context).await?;
+            match t.bind(&new_context).await {
+                Ok(codec) => bound.push((codec, new_context)),
+                Err(err) => {
+                    result = Err(err);
+                    break;
                 }
+            }
+        }
 
-                Err(err)
+        if let Err(err) = result {
+            for (_, new_context) in &bound {
+                let _ = t.unbind(new_context).await;
             }
-            Ok(codec) => {
-                // Codec has changed
-                if self.payload_type != codec.payload_type {
-                    let mut context = self.context.lock().await;
-                    context.params.codecs = vec![codec];
-                }
 
-                {
-                    let mut t = self.track.lock().await;
-                    *t = track;
+            // Re-bind the original track
+            let track = self.track.lock().await;
+            if let Some(t) = &*track {
+                for context in &contexts {
+                    t.bind(context).await?;
                 }
+            }
+
+            return Err(err);
+        }
 
-                Ok(())
+        for (encoding, (codec, _)) in self.track_encodings.iter().zip(bound) {
+            // Codec has changed
+            if self.payload_type != codec.payload_type {
+                let mut context = encoding.context.lock().await;
+                context.params.codecs = vec![codec];
             }
         }
+
+        {
+            let mut t = self.track.lock().await;
+            *t = track;
+        }
+
+        Ok(())
     }
 
     /// send Attempts to set the parameters controlling the sending of media.
@@ -373,65 +786,157 @@ impl RTCRtpSender {
             return Err(Error::ErrRTPSenderSendAlreadyCalled);
         }
 
-        let write_stream = Arc::new(InterceptorToTrackLocalWriter::new(self.paused.clone()));
-        let (context, stream_info) = {
+        let track = {
             let track = self.track.lock().await;
+            track.clone()
+        };
+        let params = self
+            .media_engine
+            .get_rtp_parameters_by_kind(
+                if let Some(t) = &track {
+                    t.kind()
+                } else {
+                    RTPCodecType::default()
+                },
+                RTCRtpTransceiverDirection::Sendonly,
+            )
+            .await;
+
+        // The encodings of a simulcast sender carry the MID and their RID in header
+        // extensions, so that the remote peer can tell the streams apart.
+        let mid = if self.track_encodings.iter().any(|e| !e.rid.is_empty()) {
+            let tr = self.rtp_transceiver.lock().await;
+            match tr.as_ref().and_then(|t| t.upgrade()) {
+                Some(t) => t.mid().await,
+                None => String::new(),
+            }
+        } else {
+            String::new()
+        };
+        let header_extension_id = |uri: &str| {
+            params
+                .header_extensions
+                .iter()
+                .find(|ext| ext.uri == uri)
+                .map(|ext| ext.id as u8)
+        };
+
+        let mut bindings = Vec::with_capacity(self.track_encodings.len());
+        for (i, encoding) in self.track_encodings.iter().enumerate() {
+            let ssrc = parameters
+                .encodings
+                .get(i)
+                .map_or(encoding.ssrc, |e| e.ssrc);
+
+            let mut header_extensions = vec![];
+            if !encoding.rid.is_empty() {
+                if let Some(id) = header_extension_id(::sdp::extmap::SDES_MID_URI) {
+                    if !mid.is_empty() {
+                        header_extensions.push((id, Bytes::from(mid.clone())));
+                    }
+                }
+                if let Some(id) = header_extension_id(::sdp::extmap::SDES_RTP_STREAM_ID_URI) {
+                    header_extensions.push((id, Bytes::from(encoding.rid.clone())));
+                }
+            }
+
+            let scalability_mode = {
+                let parameters = encoding.parameters.lock().unwrap();
+                match &parameters.scalability_mode {
+                    Some(mode) => Some(mode.parse::<ScalabilityMode>()?),
+                    None => None,
+                }
+            };
+
+            let write_stream = Arc::new(
+                InterceptorToTrackLocalWriter::new(encoding.paused.clone())
+                    .with_header_extensions(header_extensions),
+            );
             let mut context = TrackLocalContext {
-                id: self.id.clone(),
-                params: self
-                    .media_engine
-                    .get_rtp_parameters_by_kind(
-                        if let Some(t) = &*track {
-                            t.kind()
-                        } else {
-                            RTPCodecType::default()
-                        },
-                        RTCRtpTransceiverDirection::Sendonly,
-                    )
-                    .await,
-                ssrc: parameters.encodings[0].ssrc,
+                id: encoding.context_id(&self.id),
+                params: params.clone(),
+                ssrc,
+                rid: encoding.rid.clone(),
+                scalability_mode,
+                encoding_parameters: Arc::clone(&encoding.parameters),
                 write_stream: Some(
                     Arc::clone(&write_stream) as Arc<dyn TrackLocalWriter + Send + Sync>
                 ),
-                paused: self.paused.clone(),
+                paused: encoding.paused.clone(),
+                extmap_allow_mixed: self.media_engine.extmap_allow_mixed(),
+                frame_transform: Arc::clone(&self.frame_transform),
+                keyframe_request_handler: Arc::clone(&encoding.keyframe_request_handler),
             };
 
-            let codec = if let Some(t) = &*track {
-                t.bind(&context).await?
+            let codec = if let Some(t) = &track {
+                let codec = t.bind(&context).await?;
+                // The application's encoder can only produce the modes registered for the codec
+                if let Some(mode) = &scalability_mode {
+                    if !self
+                        .media_engine
+                        .scalability_modes(&codec.capability.mime_type)
+                        .contains(mode)
+                    {
+                        t.unbind(&context).await?;
+                        return Err(Error::ErrUnsupportedScalabilityMode);
+                    }
+                }
+                codec
             } else {
                 RTCRtpCodecParameters::default()
             };
             let payload_type = codec.payload_type;
             let capability = codec.capability.clone();
+            if i == 0 {
+                if let Some(dtmf) = &self.dtmf {
+                    dtmf.bind(DtmfBinding::new(
+                        Arc::clone(&write_stream),
+                        ssrc,
+                        &context.params.codecs,
+                        capability.clock_rate,
+                    ));
+                }
+            }
             context.params.codecs = vec![codec];
             let stream_info = create_stream_info(
                 self.id.clone(),
-                parameters.encodings[0].ssrc,
+                ssrc,
                 payload_type,
                 capability,
                 &parameters.rtp_parameters.header_extensions,
+                context.extmap_allow_mixed,
             );
 
-            (context, stream_info)
-        };
-
-        let srtp_rtp_writer = Arc::clone(&self.srtp_stream) as Arc<dyn RTPWriter + Send + Sync>;
-        let rtp_interceptor = self
-            .interceptor
-            .bind_local_stream(&stream_info, srtp_rtp_writer)
-            .await;
-        {
-            let mut interceptor_rtp_writer = write_stream.interceptor_rtp_writer.lock().await;
-            *interceptor_rtp_writer = Some(rtp_interceptor);
+            bindings.push((write_stream, context, stream_info));
         }
 
+        for (encoding, (write_stream, context, stream_info)) in
+            self.track_encodings.iter().zip(bindings)
         {
-            let mut ctx = self.context.lock().await;
-            *ctx = context;
-        }
-        {
-            let mut si = self.stream_info.lock().await;
-            *si = stream_info;
+            let srtp_rtp_writer =
+                Arc::clone(&encoding.srtp_stream) as Arc<dyn RTPWriter + Send + Sync>;
+            let rtp_interceptor = self
+                .interceptor
+                .bind_local_stream(&stream_info, srtp_rtp_writer)
+                .await;
+            {
+                let mut interceptor_rtp_writer = write_stream.interceptor_rtp_writer.lock().await;
+                *interceptor_rtp_writer = Some(rtp_interceptor);
+            }
+
+            let ssrc = context.ssrc;
+            {
+                let mut ctx = encoding.context.lock().await;
+                *ctx = context;
+            }
+            {
+                let mut si = encoding.stream_info.lock().await;
+                *si = stream_info;
+            }
+
+            encoding
+                .start_rtcp_reader(ssrc, self.receive_mtu, &self.internal)
+                .await;
         }
 
         {
@@ -449,6 +954,9 @@ impl RTCRtpSender {
         }
         self.stop_called_signal.store(true, Ordering::SeqCst);
         self.stop_called_tx.notify_waiters();
+        if let Some(dtmf) = &self.dtmf {
+            dtmf.stop();
+        }
 
         if !self.has_sent().await {
             return Ok(());
@@ -456,24 +964,64 @@ impl RTCRtpSender {
 
         self.replace_track(None).await?;
 
-        {
-            let stream_info = self.stream_info.lock().await;
-            self.interceptor.unbind_local_stream(&stream_info).await;
+        let mut close_errs = vec![];
+        for encoding in &self.track_encodings {
+            {
+                let stream_info = encoding.stream_info.lock().await;
+                self.interceptor.unbind_local_stream(&stream_info).await;
+            }
+
+            if let Err(err) = encoding.srtp_stream.close().await {
+                close_errs.push(err);
+            }
         }
 
-        self.srtp_stream.close().await
+        flatten_errs(close_errs)
     }
 
-    /// read reads incoming RTCP for this RTPReceiver
+    /// read reads incoming RTCP for this RTPReceiver. The sender reads the RTCP through the
+    /// interceptors itself once it sends, and keeps the latest packets for read, so an
+    /// application which only needs the keyframe requests can use on_keyframe_request of the
+    /// track instead.
     pub async fn read(&self, b: &mut [u8]) -> Result<(usize, Attributes)> {
-        self.internal.read(b).await
+        self.internal
+            .read(b, &self.track_encodings[0].rtcp_rx)
+            .await
     }
 
     /// read_rtcp is a convenience method that wraps Read and unmarshals for you.
     pub async fn read_rtcp(
         &self,
     ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
-        self.internal.read_rtcp(self.receive_mtu).await
+        self.internal
+            .read_rtcp(self.receive_mtu, &self.track_encodings[0].rtcp_rx)
+            .await
+    }
+
+    /// read_simulcast reads incoming RTCP for the simulcast encoding with the given RID, like
+    /// read does for the first encoding.
+    pub async fn read_simulcast(&self, b: &mut [u8], rid: &str) -> Result<(usize, Attributes)> {
+        let encoding = self.encoding_for_rid(rid)?;
+        self.internal.read(b, &encoding.rtcp_rx).await
+    }
+
+    /// read_simulcast_rtcp is a convenience method that wraps read_simulcast and unmarshals
+    /// for you.
+    pub async fn read_simulcast_rtcp(
+        &self,
+        rid: &str,
+    ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
+        let encoding = self.encoding_for_rid(rid)?;
+        self.internal
+            .read_rtcp(self.receive_mtu, &encoding.rtcp_rx)
+            .await
+    }
+
+    fn encoding_for_rid(&self, rid: &str) -> Result<&TrackEncoding> {
+        self.track_encodings
+            .iter()
+            .find(|e| e.rid == rid)
+            .ok_or(Error::ErrRTPSenderNoEncodingForRid)
     }
 
     /// has_sent tells if data has been ever sent for this instance
@@ -487,6 +1035,21 @@ impl RTCRtpSender {
         self.stop_called_signal.load(Ordering::SeqCst)
     }
 
+    /// sending_ssrcs returns the SSRCs of the encodings of a sender which has sent and
+    /// hasn't been stopped.
+    pub(crate) async fn sending_ssrcs(&self) -> Vec<SSRC> {
+        if !self.has_sent().await || self.has_stopped().await {
+            return vec![];
+        }
+
+        let mut ssrcs = Vec::with_capacity(self.track_encodings.len());
+        for encoding in &self.track_encodings {
+            let context = encoding.context.lock().await;
+            ssrcs.push(context.ssrc);
+        }
+        ssrcs
+    }
+
     pub(crate) fn initial_track_id(&self) -> Option<String> {
         let lock = self.initial_track_id.lock().unwrap();
 
@@ -9,7 +9,10 @@ use crate::peer_connection::peer_connection_test::{
     until_connection_state,
 };
 use crate::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use crate::rtp_transceiver::rtp_priority::RTCPriorityType;
 use crate::rtp_transceiver::rtp_receiver::RTCRtpReceiver;
+use crate::rtp_transceiver::rtp_sender::dtmf_sender::event_payload;
+use crate::rtp_transceiver::RTCRtpTransceiverInit;
 use crate::track::track_local::track_local_static_sample::TrackLocalStaticSample;
 use crate::track::track_remote::TrackRemote;
 use bytes::Bytes;
@@ -296,12 +299,8 @@ async fn test_rtp_sender_replace_track_invalid_codec_change() -> Result<()> {
         let tr = rtp_sender.rtp_transceiver.lock().await;
         if let Some(t) = &*tr {
             if let Some(t) = t.upgrade() {
-                t.set_codec_preferences(vec![RTCRtpCodecParameters {
-                    capability: RTCRtpCodecCapability {
-                        mime_type: MIME_TYPE_VP8.to_owned(),
-                        ..Default::default()
-                    },
-                    payload_type: 96,
+                t.set_codec_preferences(vec![RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_VP8.to_owned(),
                     ..Default::default()
                 }])
                 .await?;
@@ -345,3 +344,378 @@ async fn test_rtp_sender_replace_track_invalid_codec_change() -> Result<()> {
     close_pair_now(&sender, &receiver).await;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_rtp_sender_simulcast_send_encodings() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (offerer, answerer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let send_encodings: Vec<RTCRtpEncodingParameters> = [("q", 4.0), ("h", 2.0), ("f", 1.0)]
+        .iter()
+        .map(|(rid, scale)| RTCRtpEncodingParameters {
+            rid: rid.to_string(),
+            scale_resolution_down_by: Some(*scale),
+            ..Default::default()
+        })
+        .collect();
+
+    let transceiver = offerer
+        .add_transceiver_from_track(
+            Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>,
+            &[RTCRtpTransceiverInit {
+                direction: RTCRtpTransceiverDirection::Sendonly,
+                send_encodings,
+            }],
+        )
+        .await?;
+    let sender = transceiver.sender().await.unwrap();
+
+    assert_eq!(vec!["q", "h", "f"], sender.rids());
+    let parameters = sender.get_parameters().await;
+    assert_eq!(3, parameters.encodings.len());
+    assert_eq!(sender.ssrc, parameters.encodings[0].ssrc);
+    assert_ne!(parameters.encodings[0].ssrc, parameters.encodings[1].ssrc);
+    assert_ne!(parameters.encodings[1].ssrc, parameters.encodings[2].ssrc);
+    assert_eq!(Some(2.0), parameters.encodings[1].scale_resolution_down_by);
+    assert!(parameters.encodings.iter().all(|e| e.active));
+
+    let offer = offerer.create_offer(None).await?;
+    assert!(offer.sdp.contains("a=rid:q send\r\n"));
+    assert!(offer.sdp.contains("a=rid:f send\r\n"));
+    assert!(offer.sdp.contains("a=simulcast:send q;h;f\r\n"));
+    for encoding in &parameters.encodings {
+        assert!(offer
+            .sdp
+            .contains(&format!("a=ssrc:{} cname:", encoding.ssrc)));
+    }
+
+    sender.set_encoding_active("h", false)?;
+    let parameters = sender.get_parameters().await;
+    assert!(parameters.encodings[0].active);
+    assert!(!parameters.encodings[1].active);
+    assert!(sender.track_encodings[1].paused.load(Ordering::SeqCst));
+    assert!(!sender.track_encodings[2].paused.load(Ordering::SeqCst));
+
+    assert_eq!(
+        Err(Error::ErrRTPSenderNoEncodingForRid),
+        sender.set_encoding_active("x", false)
+    );
+
+    close_pair_now(&offerer, &answerer).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_sender_simulcast_invalid_rids() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let pc = api.new_peer_connection(Default::default()).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    for (rids, expected) in [
+        (vec!["a", ""], Error::ErrRTPSenderRidNil),
+        (vec!["a", "a"], Error::ErrRTPSenderRidCollision),
+    ] {
+        let result = pc
+            .add_transceiver_from_track(
+                Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>,
+                &[RTCRtpTransceiverInit {
+                    direction: RTCRtpTransceiverDirection::Sendonly,
+                    send_encodings: rids
+                        .iter()
+                        .map(|rid| RTCRtpEncodingParameters {
+                            rid: rid.to_string(),
+                            ..Default::default()
+                        })
+                        .collect(),
+                }],
+            )
+            .await;
+        assert_eq!(Some(expected), result.err());
+    }
+
+    pc.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_sender_set_parameters() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (offerer, answerer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let transceiver = offerer
+        .add_transceiver_from_track(
+            Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>,
+            &[RTCRtpTransceiverInit {
+                direction: RTCRtpTransceiverDirection::Sendonly,
+                send_encodings: ["h", "f"]
+                    .iter()
+                    .map(|rid| RTCRtpEncodingParameters {
+                        rid: rid.to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            }],
+        )
+        .await?;
+    let sender = transceiver.sender().await.unwrap();
+
+    let mut parameters = sender.get_parameters().await;
+    assert_eq!(RTCPriorityType::Low, parameters.encodings[0].priority);
+    assert!(!parameters.rtp_parameters.codecs.is_empty());
+
+    parameters.encodings[0].active = false;
+    parameters.encodings[1].max_bitrate = Some(500_000);
+    parameters.encodings[1].max_framerate = Some(15.0);
+    parameters.encodings[1].priority = RTCPriorityType::High;
+    sender.set_parameters(parameters.clone()).await?;
+
+    let updated = sender.get_parameters().await;
+    assert!(!updated.encodings[0].active);
+    assert!(updated.encodings[1].active);
+    assert_eq!(Some(500_000), updated.encodings[1].max_bitrate);
+    assert_eq!(Some(15.0), updated.encodings[1].max_framerate);
+    assert_eq!(RTCPriorityType::High, updated.encodings[1].priority);
+    assert!(sender.track_encodings[0].paused.load(Ordering::SeqCst));
+    assert!(!sender.track_encodings[1].paused.load(Ordering::SeqCst));
+
+    // Read-only fields and the encodings can't be changed
+    let mut invalid = updated.clone();
+    invalid.encodings[0].rid = "q".to_owned();
+    assert!(matches!(
+        sender.set_parameters(invalid).await,
+        Err(Error::ErrRTPSenderInvalidModification)
+    ));
+    let mut invalid = updated.clone();
+    invalid.encodings.pop();
+    assert!(matches!(
+        sender.set_parameters(invalid).await,
+        Err(Error::ErrRTPSenderInvalidModification)
+    ));
+    let mut invalid = updated.clone();
+    invalid.encodings[0].scale_resolution_down_by = Some(0.5);
+    assert!(matches!(
+        sender.set_parameters(invalid).await,
+        Err(Error::ErrRTPSenderEncodingParameterOutOfRange)
+    ));
+
+    sender.stop().await?;
+    assert!(matches!(
+        sender.set_parameters(updated).await,
+        Err(Error::ErrRTPSenderStopped)
+    ));
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_sender_degradation_preference() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (offerer, answerer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    let sender = offerer.add_track(track).await?;
+
+    let parameters = sender.get_parameters().await;
+    assert_eq!(
+        RTCDegradationPreference::Balanced,
+        parameters.degradation_preference
+    );
+    let target = sender.encoder_target();
+    assert_eq!(None, target.bandwidth_estimate);
+    assert_eq!(
+        RTCDegradationPreference::Balanced,
+        target.degradation_preference
+    );
+
+    let (target_tx, mut target_rx) = mpsc::channel(4);
+    sender.on_encoder_target(Box::new(move |target: RTCEncoderTarget| {
+        let target_tx = target_tx.clone();
+        Box::pin(async move {
+            let _ = target_tx.send(target).await;
+        })
+    }));
+
+    let mut parameters = parameters;
+    parameters.degradation_preference = RTCDegradationPreference::MaintainResolution;
+    parameters.encodings[0].max_bitrate = Some(300_000);
+    sender.set_parameters(parameters).await?;
+    assert_eq!(
+        RTCDegradationPreference::MaintainResolution,
+        sender.degradation_preference()
+    );
+    let target = target_rx.recv().await.unwrap();
+    assert_eq!(
+        RTCDegradationPreference::MaintainResolution,
+        target.degradation_preference
+    );
+    assert_eq!(Some(300_000), target.encodings[0].max_bitrate);
+    assert_eq!(None, target.bandwidth_estimate);
+
+    sender.set_bandwidth_estimate(250_000).await;
+    let target = target_rx.recv().await.unwrap();
+    assert_eq!(Some(250_000), target.bandwidth_estimate);
+    assert_eq!(
+        RTCDegradationPreference::MaintainResolution,
+        target.degradation_preference
+    );
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_sender_dtmf() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut offerer, mut answerer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_OPUS.to_owned(),
+            ..Default::default()
+        },
+        "audio".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    let sender = offerer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+    let dtmf = sender.dtmf().unwrap();
+
+    let video_sender = offerer
+        .add_track(Arc::new(TrackLocalStaticSample::new(
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_VP8.to_owned(),
+                ..Default::default()
+            },
+            "video".to_owned(),
+            "webrtc-rs".to_owned(),
+        )))
+        .await?;
+    assert!(video_sender.dtmf().is_none());
+
+    // telephone-event isn't negotiated yet
+    assert!(!dtmf.can_insert_dtmf());
+    assert_eq!(
+        Err(Error::ErrDtmfCanNotInsert),
+        dtmf.insert_dtmf("1", Duration::from_millis(100), Duration::from_millis(70))
+    );
+
+    let (done_tx, done_rx) = mpsc::channel::<()>(1);
+    let (receiving_tx, mut receiving_rx) = mpsc::channel::<()>(1);
+    answerer.on_track(Box::new(
+        move |track: Option<Arc<TrackRemote>>, _: Option<Arc<RTCRtpReceiver>>| {
+            let done_tx = done_tx.clone();
+            let receiving_tx = receiving_tx.clone();
+            Box::pin(async move {
+                let track = match track {
+                    Some(track) if track.kind() == RTPCodecType::Audio => track,
+                    _ => return,
+                };
+                tokio::spawn(async move {
+                    let mut marker = false;
+                    while let Ok((packet, _)) = track.read_rtp().await {
+                        // The 48kHz telephone-event of the default codecs
+                        if packet.header.payload_type != 110 {
+                            let _ = receiving_tx.try_send(());
+                            continue;
+                        }
+                        assert_eq!(packet.header.ssrc, track.ssrc());
+                        assert_eq!(packet.payload[0], 5);
+                        marker |= packet.header.marker;
+                        if packet.payload[1] & 0x80 != 0 {
+                            assert!(marker);
+                            assert_eq!(packet.payload, event_payload(5, true, 4800));
+                            let _ = done_tx.try_send(());
+                            break;
+                        }
+                    }
+                });
+            })
+        },
+    ));
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+
+    let (tone_tx, mut tone_rx) = mpsc::channel::<String>(4);
+    dtmf.on_tone_change(Box::new(move |tone: String| {
+        let tone_tx = tone_tx.clone();
+        Box::pin(async move {
+            let _ = tone_tx.send(tone).await;
+        })
+    }));
+
+    let dtmf2 = Arc::clone(&dtmf);
+    tokio::spawn(async move {
+        // the tones are inserted once the audio is received
+        receiving_rx.recv().await;
+        assert!(dtmf2.can_insert_dtmf());
+        assert_eq!(
+            Err(Error::ErrDtmfInvalidTone),
+            dtmf2.insert_dtmf("5X", Duration::from_millis(100), Duration::from_millis(70))
+        );
+        dtmf2
+            .insert_dtmf("5", Duration::from_millis(100), Duration::from_millis(70))
+            .unwrap();
+    });
+
+    send_video_until_done(done_rx, vec![track], Bytes::from_static(&[0x00]), None).await;
+
+    assert_eq!(Some("5".to_owned()), tone_rx.recv().await);
+    assert_eq!(Some("".to_owned()), tone_rx.recv().await);
+    assert!(dtmf.tone_buffer().is_empty());
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
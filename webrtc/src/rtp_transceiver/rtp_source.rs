@@ -0,0 +1,139 @@
+use crate::rtp_transceiver::SSRC;
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// CSRC_AUDIO_LEVEL_URI is the header extension carrying the audio levels of the contributing
+/// sources of a packet, <https://www.rfc-editor.org/rfc/rfc6465>.
+pub const CSRC_AUDIO_LEVEL_URI: &str = "urn:ietf:params:rtp-hdrext:csrc-audio-level";
+
+/// SOURCE_TIMEOUT is how long a source is reported after its last packet.
+pub(crate) const SOURCE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// RTCRtpContributingSource describes a source of the packets received by a RTCRtpReceiver,
+/// as of its most recent packet.
+/// <https://www.w3.org/TR/webrtc/#dom-rtcrtpcontributingsource>
+#[derive(Debug, Clone, PartialEq)]
+pub struct RTCRtpContributingSource {
+    /// timestamp is the time the most recent packet of the source was received at.
+    pub timestamp: SystemTime,
+    /// source is the SSRC or the CSRC of the source.
+    pub source: SSRC,
+    /// audio_level is the audio level of the most recent packet, from 0.0 for silence to 1.0
+    /// for 0 dBov, or None if the packet carried no audio level.
+    pub audio_level: Option<f64>,
+    /// rtp_timestamp is the RTP timestamp of the most recent packet.
+    pub rtp_timestamp: u32,
+}
+
+/// RTCRtpSynchronizationSource describes a SSRC of the packets received by a RTCRtpReceiver.
+/// <https://www.w3.org/TR/webrtc/#dom-rtcrtpsynchronizationsource>
+pub type RTCRtpSynchronizationSource = RTCRtpContributingSource;
+
+/// audio_level converts the level of RFC 6464 and RFC 6465, in -dBov, to the linear scale of
+/// the W3C API, where 127 is silence.
+fn audio_level(level: u8) -> f64 {
+    let level = level & 0x7F;
+    if level == 127 {
+        0.0
+    } else {
+        10f64.powf(-(level as f64) / 20.0)
+    }
+}
+
+/// RTPSources keeps the most recent packet of each SSRC and CSRC received by a RTCRtpReceiver.
+#[derive(Debug, Default)]
+pub(crate) struct RTPSources {
+    synchronization_sources: HashMap<SSRC, RTCRtpSynchronizationSource>,
+    contributing_sources: HashMap<SSRC, RTCRtpContributingSource>,
+}
+
+impl RTPSources {
+    /// update records a received packet. audio_level_id and csrc_audio_level_id are the
+    /// negotiated ids of the audio level header extensions, or 0.
+    pub(crate) fn update(
+        &mut self,
+        header: &rtp::header::Header,
+        audio_level_id: u8,
+        csrc_audio_level_id: u8,
+        now: SystemTime,
+    ) {
+        let level = if audio_level_id != 0 {
+            header
+                .get_extension(audio_level_id)
+                .and_then(|b| b.first().copied())
+                .map(audio_level)
+        } else {
+            None
+        };
+        if !self.synchronization_sources.contains_key(&header.ssrc) {
+            Self::remove_expired(&mut self.synchronization_sources, now);
+        }
+        self.synchronization_sources.insert(
+            header.ssrc,
+            RTCRtpSynchronizationSource {
+                timestamp: now,
+                source: header.ssrc,
+                audio_level: level,
+                rtp_timestamp: header.timestamp,
+            },
+        );
+
+        let levels = if csrc_audio_level_id != 0 {
+            header.get_extension(csrc_audio_level_id)
+        } else {
+            None
+        };
+        for (i, csrc) in header.csrc.iter().enumerate() {
+            if !self.contributing_sources.contains_key(csrc) {
+                Self::remove_expired(&mut self.contributing_sources, now);
+            }
+            self.contributing_sources.insert(
+                *csrc,
+                RTCRtpContributingSource {
+                    timestamp: now,
+                    source: *csrc,
+                    audio_level: levels
+                        .as_ref()
+                        .and_then(|b| b.get(i).copied())
+                        .map(audio_level),
+                    rtp_timestamp: header.timestamp,
+                },
+            );
+        }
+    }
+
+    fn remove_expired(sources: &mut HashMap<SSRC, RTCRtpContributingSource>, now: SystemTime) {
+        sources.retain(|_, s| !Self::is_expired(s, now));
+    }
+
+    fn is_expired(source: &RTCRtpContributingSource, now: SystemTime) -> bool {
+        now.duration_since(source.timestamp)
+            .map_or(false, |elapsed| elapsed > SOURCE_TIMEOUT)
+    }
+
+    /// active returns the sources of the last 10 seconds, most recent first.
+    fn active(
+        sources: &HashMap<SSRC, RTCRtpContributingSource>,
+        now: SystemTime,
+    ) -> Vec<RTCRtpContributingSource> {
+        let mut active: Vec<_> = sources
+            .values()
+            .filter(|s| !Self::is_expired(s, now))
+            .cloned()
+            .collect();
+        active.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+        active
+    }
+
+    pub(crate) fn synchronization_sources(
+        &self,
+        now: SystemTime,
+    ) -> Vec<RTCRtpSynchronizationSource> {
+        Self::active(&self.synchronization_sources, now)
+    }
+
+    pub(crate) fn contributing_sources(&self, now: SystemTime) -> Vec<RTCRtpContributingSource> {
+        Self::active(&self.contributing_sources, now)
+    }
+}
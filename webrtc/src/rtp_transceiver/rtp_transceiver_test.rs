@@ -5,17 +5,20 @@ use crate::api::media_engine::{MIME_TYPE_OPUS, MIME_TYPE_VP8, MIME_TYPE_VP9};
 use crate::api::APIBuilder;
 use crate::peer_connection::configuration::RTCConfiguration;
 use crate::peer_connection::peer_connection_test::{close_pair_now, create_vnet_pair};
+use crate::peer_connection::sdp::session_description::RTCSessionDescription;
+use crate::peer_connection::signaling_state::RTCSignalingState;
+use ::sdp::description::session::{ATTR_KEY_GROUP, ATTR_KEY_MID};
 
 #[tokio::test]
 async fn test_rtp_transceiver_set_codec_preferences() -> Result<()> {
     let mut m = MediaEngine::default();
     m.register_default_codecs()?;
-    m.push_codecs(m.video_codecs.clone(), RTPCodecType::Video)
-        .await;
-    m.push_codecs(m.audio_codecs.clone(), RTPCodecType::Audio)
-        .await;
+    let video_codecs = m.video_codecs.lock().unwrap().clone();
+    m.push_codecs(video_codecs, RTPCodecType::Video).await;
+    let audio_codecs = m.audio_codecs.lock().unwrap().clone();
+    m.push_codecs(audio_codecs, RTPCodecType::Audio).await;
 
-    let media_video_codecs = m.video_codecs.clone();
+    let media_video_codecs = m.video_codecs.lock().unwrap().clone();
 
     let api = APIBuilder::new().with_media_engine(m).build();
 
@@ -24,7 +27,10 @@ async fn test_rtp_transceiver_set_codec_preferences() -> Result<()> {
         None,
         RTCRtpTransceiverDirection::Unspecified,
         RTPCodecType::Video,
-        media_video_codecs.clone(),
+        media_video_codecs
+            .iter()
+            .map(|codec| codec.capability.clone())
+            .collect(),
         Arc::clone(&api.media_engine),
         None,
     )
@@ -33,40 +39,28 @@ async fn test_rtp_transceiver_set_codec_preferences() -> Result<()> {
     assert_eq!(&media_video_codecs, &tr.get_codecs().await);
 
     let fail_test_cases = vec![
-        vec![RTCRtpCodecParameters {
-            capability: RTCRtpCodecCapability {
+        vec![RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_OPUS.to_string(),
+            clock_rate: 48000,
+            channels: 2,
+            sdp_fmtp_line: "minptime=10;useinbandfec=1".to_string(),
+            rtcp_feedback: vec![],
+        }],
+        vec![
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_VP8.to_string(),
+                clock_rate: 90000,
+                channels: 0,
+                sdp_fmtp_line: "".to_string(),
+                rtcp_feedback: vec![],
+            },
+            RTCRtpCodecCapability {
                 mime_type: MIME_TYPE_OPUS.to_string(),
                 clock_rate: 48000,
                 channels: 2,
                 sdp_fmtp_line: "minptime=10;useinbandfec=1".to_string(),
                 rtcp_feedback: vec![],
             },
-            payload_type: 111,
-            ..Default::default()
-        }],
-        vec![
-            RTCRtpCodecParameters {
-                capability: RTCRtpCodecCapability {
-                    mime_type: MIME_TYPE_VP8.to_string(),
-                    clock_rate: 90000,
-                    channels: 0,
-                    sdp_fmtp_line: "".to_string(),
-                    rtcp_feedback: vec![],
-                },
-                payload_type: 96,
-                ..Default::default()
-            },
-            RTCRtpCodecParameters {
-                capability: RTCRtpCodecCapability {
-                    mime_type: MIME_TYPE_OPUS.to_string(),
-                    clock_rate: 48000,
-                    channels: 2,
-                    sdp_fmtp_line: "minptime=10;useinbandfec=1".to_string(),
-                    rtcp_feedback: vec![],
-                },
-                payload_type: 111,
-                ..Default::default()
-            },
         ],
     ];
 
@@ -79,39 +73,27 @@ async fn test_rtp_transceiver_set_codec_preferences() -> Result<()> {
     }
 
     let success_test_cases = vec![
-        vec![RTCRtpCodecParameters {
-            capability: RTCRtpCodecCapability {
+        vec![RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_string(),
+            clock_rate: 90000,
+            channels: 0,
+            sdp_fmtp_line: "".to_string(),
+            rtcp_feedback: vec![],
+        }],
+        vec![
+            RTCRtpCodecCapability {
                 mime_type: MIME_TYPE_VP8.to_string(),
                 clock_rate: 90000,
                 channels: 0,
                 sdp_fmtp_line: "".to_string(),
                 rtcp_feedback: vec![],
             },
-            payload_type: 96,
-            ..Default::default()
-        }],
-        vec![
-            RTCRtpCodecParameters {
-                capability: RTCRtpCodecCapability {
-                    mime_type: MIME_TYPE_VP8.to_string(),
-                    clock_rate: 90000,
-                    channels: 0,
-                    sdp_fmtp_line: "".to_string(),
-                    rtcp_feedback: vec![],
-                },
-                payload_type: 96,
-                ..Default::default()
-            },
-            RTCRtpCodecParameters {
-                capability: RTCRtpCodecCapability {
-                    mime_type: MIME_TYPE_VP9.to_string(),
-                    clock_rate: 90000,
-                    channels: 0,
-                    sdp_fmtp_line: "profile-id=0".to_string(),
-                    rtcp_feedback: vec![],
-                },
-                payload_type: 98,
-                ..Default::default()
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_VP9.to_string(),
+                clock_rate: 90000,
+                channels: 0,
+                sdp_fmtp_line: "profile-id=0".to_string(),
+                rtcp_feedback: vec![],
             },
         ],
     ];
@@ -126,9 +108,10 @@ async fn test_rtp_transceiver_set_codec_preferences() -> Result<()> {
     Ok(())
 }
 
-// Assert that SetCodecPreferences properly filters codecs and PayloadTypes are respected
+// Assert that SetCodecPreferences properly filters and orders codecs, and PayloadTypes of the
+// offer are respected
 #[tokio::test]
-async fn test_rtp_transceiver_set_codec_preferences_payload_type() -> Result<()> {
+async fn test_rtp_transceiver_set_codec_preferences_order() -> Result<()> {
     let test_codec = RTCRtpCodecParameters {
         capability: RTCRtpCodecCapability {
             mime_type: "video/test_codec".to_string(),
@@ -162,17 +145,20 @@ async fn test_rtp_transceiver_set_codec_preferences_payload_type() -> Result<()>
 
     answer_transceiver
         .set_codec_preferences(vec![
-            test_codec,
-            RTCRtpCodecParameters {
-                capability: RTCRtpCodecCapability {
-                    mime_type: MIME_TYPE_VP8.to_string(),
-                    clock_rate: 90000,
-                    channels: 0,
-                    sdp_fmtp_line: "".to_string(),
-                    rtcp_feedback: vec![],
-                },
-                payload_type: 51,
-                ..Default::default()
+            test_codec.capability,
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_VP9.to_string(),
+                clock_rate: 90000,
+                channels: 0,
+                sdp_fmtp_line: "profile-id=0".to_string(),
+                rtcp_feedback: vec![],
+            },
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_VP8.to_string(),
+                clock_rate: 90000,
+                channels: 0,
+                sdp_fmtp_line: "".to_string(),
+                rtcp_feedback: vec![],
             },
         ])
         .await?;
@@ -184,12 +170,14 @@ async fn test_rtp_transceiver_set_codec_preferences_payload_type() -> Result<()>
 
     let answer = answer_pc.create_answer(None).await?;
 
-    // VP8 with proper PayloadType
-    assert!(
-        answer.sdp.contains("a=rtpmap:51 VP8/90000"),
-        "{}",
-        answer.sdp
-    );
+    // VP9 is preferred over VP8, both with the PayloadType of the offer
+    let vp9 = answer.sdp.find("a=rtpmap:98 VP9/90000");
+    let vp8 = answer.sdp.find("a=rtpmap:96 VP8/90000");
+    assert!(vp9.is_some() && vp8.is_some(), "{}", answer.sdp);
+    assert!(vp9 < vp8, "{}", answer.sdp);
+
+    // the other codecs of the offer are removed
+    assert!(!answer.sdp.contains("H264"), "{}", answer.sdp);
 
     // test_codec is ignored since offerer doesn't support
     assert!(!answer.sdp.contains("test_codec"));
@@ -298,7 +286,6 @@ async fn test_rtp_transceiver_set_direction_causing_negotiation() -> Result<()>
     Ok(())
 }
 
-#[ignore]
 #[tokio::test]
 async fn test_rtp_transceiver_stopping() -> Result<()> {
     let (offer_pc, answer_pc, _) = create_vnet_pair().await?;
@@ -336,8 +323,214 @@ async fn test_rtp_transceiver_stopping() -> Result<()> {
         m.media_name.port.value, 0,
         "After stopping a transceiver it should be rejected in offers"
     );
+    let mid = offer_transceiver.mid().await;
+    assert!(!parsed
+        .attribute(ATTR_KEY_GROUP)
+        .unwrap()
+        .split(' ')
+        .any(|m| m == mid));
+
+    close_pair_now(&offer_pc, &answer_pc).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_transceiver_stop_reuses_m_line() -> Result<()> {
+    let (offer_pc, answer_pc, _) = create_vnet_pair().await?;
+
+    // Candidates are gathered before signaling, the transports have to connect for the
+    // operations of set_remote_description to complete
+    let negotiate = || async {
+        let offer = offer_pc.create_offer(None).await?;
+        let mut offer_gathering_complete = offer_pc.gathering_complete_promise().await;
+        offer_pc.set_local_description(offer).await?;
+        let _ = offer_gathering_complete.recv().await;
+        let offer = offer_pc.local_description().await.unwrap();
+        answer_pc.set_remote_description(offer.clone()).await?;
+
+        let answer = answer_pc.create_answer(None).await?;
+        let mut answer_gathering_complete = answer_pc.gathering_complete_promise().await;
+        answer_pc.set_local_description(answer).await?;
+        let _ = answer_gathering_complete.recv().await;
+        let answer = answer_pc.local_description().await.unwrap();
+        offer_pc.set_remote_description(answer.clone()).await?;
+
+        Result::<_>::Ok((offer.unmarshal()?, answer.unmarshal()?))
+    };
+
+    let audio = offer_pc
+        .add_transceiver_from_kind(RTPCodecType::Audio, &[])
+        .await?;
+    // The data channel keeps a section with the ICE credentials once the audio is rejected
+    offer_pc.create_data_channel("data", None).await?;
+    negotiate().await?;
+    assert_eq!(answer_pc.get_transceivers().await.len(), 1);
+    let answer_audio = answer_pc.get_transceivers().await.remove(0);
+
+    // The stopped transceiver is rejected by the offer and the answer, then removed
+    let negotiation_needed = Arc::new(AtomicUsize::new(0));
+    {
+        let negotiation_needed = Arc::clone(&negotiation_needed);
+        offer_pc.on_negotiation_needed(Box::new(move || {
+            let negotiation_needed = Arc::clone(&negotiation_needed);
+            Box::pin(async move {
+                negotiation_needed.fetch_add(1, Ordering::SeqCst);
+            })
+        }));
+    }
+    audio.stop().await?;
+    offer_pc.internal.ops.done().await;
+    assert_eq!(negotiation_needed.load(Ordering::SeqCst), 1);
+
+    let (offer, answer) = negotiate().await?;
+    for desc in [&offer, &answer] {
+        assert_eq!(desc.media_descriptions.len(), 2);
+        let m = &desc.media_descriptions[0];
+        assert_eq!(m.media_name.port.value, 0);
+        assert_eq!(m.attribute(ATTR_KEY_MID), Some(Some("0")));
+    }
+    assert!(answer_audio.stopped.load(Ordering::SeqCst));
+    assert!(offer_pc.get_transceivers().await.is_empty());
+    assert!(answer_pc.get_transceivers().await.is_empty());
+
+    // A new transceiver takes the rejected m= section over instead of adding one
+    offer_pc
+        .add_transceiver_from_kind(RTPCodecType::Video, &[])
+        .await?;
+    let (offer, answer) = negotiate().await?;
+    for desc in [&offer, &answer] {
+        assert_eq!(desc.media_descriptions.len(), 2);
+        let m = &desc.media_descriptions[0];
+        assert_eq!(m.media_name.media, "video");
+        assert_ne!(m.media_name.port.value, 0);
+        assert_eq!(m.attribute(ATTR_KEY_MID), Some(Some("2")));
+    }
+    let transceivers = answer_pc.get_transceivers().await;
+    assert_eq!(transceivers.len(), 1);
+    assert_eq!(transceivers[0].kind(), RTPCodecType::Video);
 
     close_pair_now(&offer_pc, &answer_pc).await;
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_rtp_transceiver_rollback() -> Result<()> {
+    let (impolite_pc, polite_pc, _) = create_vnet_pair().await?;
+
+    impolite_pc
+        .add_transceiver_from_kind(RTPCodecType::Video, &[])
+        .await?;
+    polite_pc
+        .add_transceiver_from_kind(RTPCodecType::Video, &[])
+        .await?;
+
+    // Glare, both peers make an offer at once
+    let impolite_offer = impolite_pc.create_offer(None).await?;
+    impolite_pc
+        .set_local_description(impolite_offer.clone())
+        .await?;
+    let polite_offer = polite_pc.create_offer(None).await?;
+    polite_pc.set_local_description(polite_offer).await?;
+
+    // The polite peer rolls its offer back and accepts the other one
+    polite_pc
+        .set_local_description(RTCSessionDescription::rollback())
+        .await?;
+    assert_eq!(polite_pc.signaling_state(), RTCSignalingState::Stable);
+    assert!(polite_pc.pending_local_description().await.is_none());
+    let transceivers = polite_pc.get_transceivers().await;
+    assert_eq!(transceivers.len(), 1);
+    assert!(transceivers[0].mid().await.is_empty());
+
+    polite_pc.set_remote_description(impolite_offer).await?;
+    let answer = polite_pc.create_answer(None).await?;
+    polite_pc.set_local_description(answer.clone()).await?;
+    impolite_pc.set_remote_description(answer).await?;
+    assert_eq!(polite_pc.get_transceivers().await.len(), 1);
+
+    // Rolling a remote offer back removes the transceivers it created
+    impolite_pc
+        .add_transceiver_from_kind(RTPCodecType::Audio, &[])
+        .await?;
+    let offer = impolite_pc.create_offer(None).await?;
+    polite_pc.set_remote_description(offer).await?;
+    assert_eq!(polite_pc.get_transceivers().await.len(), 2);
+
+    polite_pc
+        .set_remote_description(RTCSessionDescription::rollback())
+        .await?;
+    assert_eq!(polite_pc.signaling_state(), RTCSignalingState::Stable);
+    let transceivers = polite_pc.get_transceivers().await;
+    assert_eq!(transceivers.len(), 1);
+    assert_eq!(transceivers[0].kind(), RTPCodecType::Video);
+
+    // A rollback is only possible with a pending offer
+    assert!(polite_pc
+        .set_remote_description(RTCSessionDescription::rollback())
+        .await
+        .is_err());
+
+    close_pair_now(&impolite_pc, &polite_pc).await;
+
+    Ok(())
+}
+
+#[test]
+fn test_rtp_sources() -> Result<()> {
+    use crate::rtp_transceiver::rtp_source::RTPSources;
+    use bytes::Bytes;
+    use std::time::{Duration, SystemTime};
+
+    let now = SystemTime::now();
+    let mut sources = RTPSources::default();
+
+    let mut header = rtp::header::Header {
+        ssrc: 1,
+        timestamp: 960,
+        csrc: vec![7, 8],
+        ..Default::default()
+    };
+    header.set_extension(1, Bytes::from_static(&[0x80 | 20]))?;
+    header.set_extension(2, Bytes::from_static(&[0, 127]))?;
+    sources.update(&header, 1, 2, now);
+
+    let ssrcs = sources.synchronization_sources(now);
+    assert_eq!(1, ssrcs.len());
+    assert_eq!(1, ssrcs[0].source);
+    assert_eq!(960, ssrcs[0].rtp_timestamp);
+    assert!((ssrcs[0].audio_level.unwrap() - 0.1).abs() < 1e-9);
+
+    let csrcs = sources.contributing_sources(now);
+    assert_eq!(2, csrcs.len());
+    let csrc = |source| csrcs.iter().find(|s| s.source == source).unwrap();
+    assert_eq!(Some(1.0), csrc(7).audio_level);
+    assert_eq!(Some(0.0), csrc(8).audio_level);
+
+    // the most recent sources come first, and the levels are only known if negotiated
+    let later = now + Duration::from_secs(5);
+    let header = rtp::header::Header {
+        ssrc: 2,
+        timestamp: 1920,
+        csrc: vec![8],
+        ..Default::default()
+    };
+    sources.update(&header, 0, 0, later);
+    let ssrcs = sources.synchronization_sources(later);
+    assert_eq!(
+        vec![2, 1],
+        ssrcs.iter().map(|s| s.source).collect::<Vec<_>>()
+    );
+    assert_eq!(None, ssrcs[0].audio_level);
+    assert_eq!(None, sources.contributing_sources(later)[0].audio_level);
+
+    // the sources are reported for 10 seconds after their last packet
+    let expired = now + Duration::from_secs(12);
+    let ssrcs = sources.synchronization_sources(expired);
+    assert_eq!(vec![2], ssrcs.iter().map(|s| s.source).collect::<Vec<_>>());
+    let csrcs = sources.contributing_sources(expired);
+    assert_eq!(vec![8], csrcs.iter().map(|s| s.source).collect::<Vec<_>>());
+
+    Ok(())
+}
@@ -13,10 +13,11 @@ use crate::data_channel::RTCDataChannel;
 use crate::dtls_transport::dtls_role::DTLSRole;
 use crate::dtls_transport::*;
 use crate::error::*;
+use crate::peer_connection::transport_state_change::RTCTransportStateReason;
 use crate::sctp_transport::sctp_transport_capabilities::SCTPTransportCapabilities;
 use crate::stats::stats_collector::StatsCollector;
 use crate::stats::StatsReportType::{PeerConnection, SCTPTransport};
-use crate::stats::{ICETransportStats, PeerConnectionStats};
+use crate::stats::{ICETransportStats, PeerConnectionStats, SCTP_TRANSPORT_STATS_ID};
 
 use data::message::message_channel_open::ChannelType;
 use sctp::association::Association;
@@ -47,9 +48,18 @@ pub type OnDataChannelOpenedHdlrFn = Box<
         + Sync,
 >;
 
+pub type OnSCTPTransportStateChangeHdlrFn = Box<
+    dyn (FnMut(RTCSctpTransportState) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
 struct AcceptDataChannelParams {
     notify_rx: Arc<Notify>,
     sctp_association: Arc<Association>,
+    state: Arc<AtomicU8>,
+    state_reason: Arc<ArcSwapOption<RTCTransportStateReason>>,
+    on_state_change_handler: Arc<ArcSwapOption<Mutex<OnSCTPTransportStateChangeHdlrFn>>>,
     data_channels: Arc<Mutex<Vec<Arc<RTCDataChannel>>>>,
     on_error_handler: Arc<ArcSwapOption<Mutex<OnErrorHdlrFn>>>,
     on_data_channel_handler: Arc<ArcSwapOption<Mutex<OnDataChannelHdlrFn>>>,
@@ -65,7 +75,8 @@ pub struct RTCSctpTransport {
     pub(crate) dtls_transport: Arc<RTCDtlsTransport>,
 
     // State represents the current state of the SCTP transport.
-    state: AtomicU8, // RTCSctpTransportState
+    state: Arc<AtomicU8>, // RTCSctpTransportState
+    state_reason: Arc<ArcSwapOption<RTCTransportStateReason>>,
 
     // SCTPTransportState doesn't have an enum to distinguish between New/Connecting
     // so we need a dedicated field
@@ -84,6 +95,7 @@ pub struct RTCSctpTransport {
     on_error_handler: Arc<ArcSwapOption<Mutex<OnErrorHdlrFn>>>,
     on_data_channel_handler: Arc<ArcSwapOption<Mutex<OnDataChannelHdlrFn>>>,
     on_data_channel_opened_handler: Arc<ArcSwapOption<Mutex<OnDataChannelOpenedHdlrFn>>>,
+    on_state_change_handler: Arc<ArcSwapOption<Mutex<OnSCTPTransportStateChangeHdlrFn>>>,
 
     // DataChannels
     pub(crate) data_channels: Arc<Mutex<Vec<Arc<RTCDataChannel>>>>,
@@ -103,7 +115,8 @@ impl RTCSctpTransport {
     ) -> Self {
         RTCSctpTransport {
             dtls_transport,
-            state: AtomicU8::new(RTCSctpTransportState::Connecting as u8),
+            state: Arc::new(AtomicU8::new(RTCSctpTransportState::Connecting as u8)),
+            state_reason: Arc::new(ArcSwapOption::empty()),
             is_started: AtomicBool::new(false),
             max_message_size: RTCSctpTransport::calc_message_size(65536, 65536),
             max_channels: SCTP_MAX_CHANNELS,
@@ -111,6 +124,7 @@ impl RTCSctpTransport {
             on_error_handler: Arc::new(ArcSwapOption::empty()),
             on_data_channel_handler: Arc::new(ArcSwapOption::empty()),
             on_data_channel_opened_handler: Arc::new(ArcSwapOption::empty()),
+            on_state_change_handler: Arc::new(ArcSwapOption::empty()),
 
             data_channels: Arc::new(Mutex::new(vec![])),
             data_channels_opened: Arc::new(AtomicU32::new(0)),
@@ -157,10 +171,9 @@ impl RTCSctpTransport {
                         }
                     },
                     association = sctp::association::Association::client(sctp::association::Config {
-                        net_conn: Arc::clone(net_conn) as Arc<dyn Conn + Send + Sync>,
-                        max_receive_buffer_size: 0,
-                        max_message_size: 0,
-                        name: String::new(),
+                        // share the bandwidth according to the priority of the data channels
+                        stream_scheduler: sctp::stream::StreamScheduler::WeightedFairQueueing,
+                        ..sctp::association::Config::new(Arc::clone(net_conn) as Arc<dyn Conn + Send + Sync>)
                     }) => {
                         break Arc::new(association?);
                     }
@@ -171,12 +184,15 @@ impl RTCSctpTransport {
                 let mut sa = self.sctp_association.lock().await;
                 *sa = Some(Arc::clone(&sctp_association));
             }
-            self.state
-                .store(RTCSctpTransportState::Connected as u8, Ordering::SeqCst);
+            self.state_change(RTCSctpTransportState::Connected, None)
+                .await;
 
             let param = AcceptDataChannelParams {
                 notify_rx: self.notify_tx.clone(),
                 sctp_association,
+                state: Arc::clone(&self.state),
+                state_reason: Arc::clone(&self.state_reason),
+                on_state_change_handler: Arc::clone(&self.on_state_change_handler),
                 data_channels: Arc::clone(&self.data_channels),
                 on_error_handler: Arc::clone(&self.on_error_handler),
                 on_data_channel_handler: Arc::clone(&self.on_data_channel_handler),
@@ -195,8 +211,27 @@ impl RTCSctpTransport {
         }
     }
 
+    /// shutdown closes the association with the SCTP shutdown sequence, so that the remote
+    /// peer closes its data channels at once. It returns when the remote peer acknowledged
+    /// the shutdown, stop then releases the SCTPTransport.
+    pub async fn shutdown(&self) -> Result<()> {
+        self.close_state().await;
+
+        let association = {
+            let sctp_association = self.sctp_association.lock().await;
+            sctp_association.clone()
+        };
+        if let Some(association) = association {
+            association.shutdown().await?;
+        }
+
+        Ok(())
+    }
+
     /// Stop stops the SCTPTransport
     pub async fn stop(&self) -> Result<()> {
+        self.close_state().await;
+
         {
             let mut sctp_association = self.sctp_association.lock().await;
             if let Some(sa) = sctp_association.take() {
@@ -204,14 +239,56 @@ impl RTCSctpTransport {
             }
         }
 
-        self.state
-            .store(RTCSctpTransportState::Closed as u8, Ordering::SeqCst);
-
         self.notify_tx.notify_waiters();
 
         Ok(())
     }
 
+    /// close_state changes the state to closed before the association is closed locally,
+    /// so that its closing isn't taken for one by the remote peer.
+    async fn close_state(&self) {
+        let prev = self
+            .state
+            .swap(RTCSctpTransportState::Closed as u8, Ordering::SeqCst);
+        if prev != RTCSctpTransportState::Closed as u8 {
+            RTCSctpTransport::do_state_change(
+                &self.on_state_change_handler,
+                &self.state_reason,
+                RTCSctpTransportState::Closed,
+                Some(RTCTransportStateReason::LocalClose),
+            )
+            .await;
+        }
+    }
+
+    async fn state_change(
+        &self,
+        state: RTCSctpTransportState,
+        reason: Option<RTCTransportStateReason>,
+    ) {
+        self.state.store(state as u8, Ordering::SeqCst);
+        RTCSctpTransport::do_state_change(
+            &self.on_state_change_handler,
+            &self.state_reason,
+            state,
+            reason,
+        )
+        .await;
+    }
+
+    async fn do_state_change(
+        handler: &Arc<ArcSwapOption<Mutex<OnSCTPTransportStateChangeHdlrFn>>>,
+        state_reason: &Arc<ArcSwapOption<RTCTransportStateReason>>,
+        state: RTCSctpTransportState,
+        reason: Option<RTCTransportStateReason>,
+    ) {
+        state_reason.store(reason.map(Arc::new));
+        if let Some(handler) = &*handler.load() {
+            let mut f = handler.lock().await;
+            f(state).await;
+        }
+    }
+
     async fn accept_data_channels(param: AcceptDataChannelParams) {
         let dcs = param.data_channels.lock().await;
         let mut existing_data_channels = Vec::new();
@@ -233,12 +310,30 @@ impl RTCSctpTransport {
                     match result {
                         Ok(dc) => dc,
                         Err(err) => {
-                            if data::Error::ErrStreamClosed == err {
+                            if data::Error::ErrStreamClosed != err {
                                 log::error!("Failed to accept data channel: {}", err);
                                 if let Some(handler) = &*param.on_error_handler.load() {
                                     let mut f = handler.lock().await;
                                     f(err.into()).await;
                                 }
+                            } else if param
+                                .state
+                                .compare_exchange(
+                                    RTCSctpTransportState::Connected as u8,
+                                    RTCSctpTransportState::Closed as u8,
+                                    Ordering::SeqCst,
+                                    Ordering::SeqCst,
+                                )
+                                .is_ok()
+                            {
+                                // The association was closed, not by stop or shutdown
+                                RTCSctpTransport::do_state_change(
+                                    &param.on_state_change_handler,
+                                    &param.state_reason,
+                                    RTCSctpTransportState::Closed,
+                                    Some(RTCTransportStateReason::RemoteClose),
+                                )
+                                .await;
                             }
                             break;
                         }
@@ -289,6 +384,7 @@ impl RTCSctpTransport {
                     ordered,
                     max_packet_life_time: max_packet_lifetime,
                     max_retransmits,
+                    priority: dc.config.priority,
                 },
                 Arc::clone(&param.setting_engine),
             ));
@@ -354,11 +450,23 @@ impl RTCSctpTransport {
         }
     }
 
+    /// on_state_change sets an event handler which is invoked when the state of the
+    /// SCTPTransport changes.
+    pub fn on_state_change(&self, f: OnSCTPTransportStateChangeHdlrFn) {
+        self.on_state_change_handler
+            .store(Some(Arc::new(Mutex::new(f))));
+    }
+
     /// state returns the current state of the SCTPTransport
     pub fn state(&self) -> RTCSctpTransportState {
         self.state.load(Ordering::SeqCst).into()
     }
 
+    /// state_reason returns the reason of the current state if the SCTPTransport is closed.
+    pub fn state_reason(&self) -> Option<RTCTransportStateReason> {
+        self.state_reason.load().as_deref().cloned()
+    }
+
     pub(crate) async fn collect_stats(
         &self,
         collector: &StatsCollector,
@@ -388,7 +496,7 @@ impl RTCSctpTransport {
 
         // conn
         if let Some(agent) = dtls_transport.ice_transport.gatherer.get_agent().await {
-            let stats = ICETransportStats::new("sctp_transport".to_owned(), agent);
+            let stats = ICETransportStats::new(SCTP_TRANSPORT_STATS_ID.to_owned(), agent);
             reports.insert(stats.id.clone(), SCTPTransport(stats));
         }
 
@@ -405,11 +513,15 @@ impl RTCSctpTransport {
         }
 
         // Create map of ids so we can compare without double-looping each time.
+        // The streams of closed data channels have been reset and their ids
+        // can be reused.
         let mut ids_map = HashSet::new();
         {
             let data_channels = self.data_channels.lock().await;
             for dc in &*data_channels {
-                ids_map.insert(dc.id());
+                if dc.ready_state() != RTCDataChannelState::Closed {
+                    ids_map.insert(dc.id());
+                }
             }
         }
 
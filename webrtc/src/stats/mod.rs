@@ -21,7 +21,14 @@ use tokio::time::Instant;
 mod serialize;
 pub mod stats_collector;
 
-#[derive(Debug, Serialize)]
+/// ICE_TRANSPORT_STATS_ID is the id of the "transport" stats of the ICE transport, which
+/// the candidate, candidate pair, codec and RTP stream stats refer to.
+pub const ICE_TRANSPORT_STATS_ID: &str = "ice_transport";
+
+/// SCTP_TRANSPORT_STATS_ID is the id of the "transport" stats of the SCTP transport.
+pub const SCTP_TRANSPORT_STATS_ID: &str = "sctp_transport";
+
+#[derive(Debug, Clone, Serialize)]
 pub enum RTCStatsType {
     #[serde(rename = "candidate-pair")]
     CandidatePair,
@@ -146,7 +153,7 @@ pub struct ICECandidatePairStats {
     pub id: String,
 
     // RTCIceCandidatePairStats
-    // TODO: Add `transportId`
+    pub transport_id: String,
     pub local_candidate_id: String,
     pub remote_candidate_id: String,
     pub state: CandidatePairState,
@@ -212,6 +219,7 @@ impl From<CandidatePairStats> for ICECandidatePairStats {
             stats_type: RTCStatsType::CandidatePair,
             timestamp: stats.timestamp,
             total_round_trip_time: stats.total_round_trip_time,
+            transport_id: ICE_TRANSPORT_STATS_ID.to_owned(),
         }
     }
 }
@@ -227,6 +235,7 @@ pub struct ICECandidateStats {
     pub id: String,
 
     // RTCIceCandidateStats
+    pub transport_id: String,
     pub candidate_type: CandidateType,
     pub deleted: bool,
     pub ip: String,
@@ -250,6 +259,7 @@ impl ICECandidateStats {
             relay_protocol: stats.relay_protocol,
             stats_type,
             timestamp: stats.timestamp,
+            transport_id: ICE_TRANSPORT_STATS_ID.to_owned(),
             url: stats.url,
         }
     }
@@ -265,6 +275,9 @@ pub struct ICETransportStats {
     pub stats_type: RTCStatsType,
     pub id: String,
 
+    // RTCTransportStats
+    pub selected_candidate_pair_id: Option<String>,
+
     // Non-canon
     pub bytes_received: usize,
     pub bytes_sent: usize,
@@ -276,6 +289,9 @@ impl ICETransportStats {
             id,
             bytes_received: agent.get_bytes_received(),
             bytes_sent: agent.get_bytes_sent(),
+            selected_candidate_pair_id: agent
+                .get_selected_candidate_pair()
+                .map(|pair| format!("{}-{}", pair.local.id(), pair.remote.id())),
             stats_type: RTCStatsType::Transport,
             timestamp: Instant::now(),
         }
@@ -328,7 +344,7 @@ pub struct CodecStats {
     pub channels: u16,
     pub clock_rate: u32,
     pub sdp_fmtp_line: String,
-    // TODO: Add `transportId`
+    pub transport_id: String,
 }
 
 impl From<&RTCRtpCodecParameters> for CodecStats {
@@ -342,6 +358,7 @@ impl From<&RTCRtpCodecParameters> for CodecStats {
             sdp_fmtp_line: codec.capability.sdp_fmtp_line.clone(),
             stats_type: RTCStatsType::Codec,
             timestamp: Instant::now(),
+            transport_id: ICE_TRANSPORT_STATS_ID.to_owned(),
         }
     }
 }
@@ -365,6 +382,13 @@ pub struct DataChannelStats {
     pub messages_sent: usize,
     pub protocol: String,
     pub state: RTCDataChannelState,
+
+    // Non-canon
+    pub buffered_amount: usize,
+    pub connecting_timestamp: Option<SystemTime>,
+    pub open_timestamp: Option<SystemTime>,
+    pub closing_timestamp: Option<SystemTime>,
+    pub closed_timestamp: Option<SystemTime>,
 }
 
 impl DataChannelStats {
@@ -375,6 +399,7 @@ impl DataChannelStats {
         let mut bytes_sent = 0;
         let mut messages_received = 0;
         let mut messages_sent = 0;
+        let mut buffered_amount = 0;
 
         let lock = data_channel.data_channel.lock().await;
 
@@ -383,8 +408,11 @@ impl DataChannelStats {
             bytes_sent = internal.bytes_sent();
             messages_received = internal.messages_received();
             messages_sent = internal.messages_sent();
+            buffered_amount = internal.buffered_amount();
         }
 
+        let state_timestamps = *data_channel.state_timestamps.lock();
+
         Self {
             bytes_received,
             bytes_sent,
@@ -395,6 +423,11 @@ impl DataChannelStats {
             messages_sent,
             protocol: data_channel.protocol.clone(),
             state,
+            buffered_amount,
+            connecting_timestamp: state_timestamps.connecting,
+            open_timestamp: state_timestamps.open,
+            closing_timestamp: state_timestamps.closing,
+            closed_timestamp: state_timestamps.closed,
             stats_type: RTCStatsType::DataChannel,
             timestamp: Instant::now(),
         }
@@ -447,8 +480,8 @@ pub struct InboundRTPStats {
     // RTCRtpStreamStats
     pub ssrc: SSRC,
     pub kind: &'static str, // Either "video" or "audio"
-    // TODO: Add tranportId
-    // TODO: Add codecId
+    pub transport_id: String,
+    pub codec_id: Option<String>,
 
     // RTCReceivedRtpStreamStats
     pub packets_received: u64,
@@ -460,7 +493,7 @@ pub struct InboundRTPStats {
     // RTCInboundRtpStreamStats
     pub track_identifier: String,
     pub mid: String,
-    // TODO: `remoteId`
+    pub remote_id: String,
     // NB: `framesDecoded`, `frameWidth`, frameHeight`, `framesPerSecond`, `qpSum`,
     // `totalDecodeTime`, `totalInterFrameDelay`, and `totalSquaredInterFrameDelay` are all decoder
     // specific values and can't be produced since we aren't decoding.
@@ -494,8 +527,8 @@ pub struct OutboundRTPStats {
     // RTCRtpStreamStats
     pub ssrc: SSRC,
     pub kind: &'static str, // Either "video" or "audio"
-    // TODO: Add tranportId
-    // TODO: Add codecId
+    pub transport_id: String,
+    pub codec_id: Option<String>,
 
     // RTCSentRtpStreamStats
     pub packets_sent: u64,
@@ -505,7 +538,8 @@ pub struct OutboundRTPStats {
     // NB: non-canon in browsers this is available via `RTCMediaSourceStats` which we are unlikely to implement
     pub track_identifier: String,
     pub mid: String,
-    // TODO: `mediaSourceId` and `remoteId`
+    // TODO: `mediaSourceId`
+    pub remote_id: String,
     pub rid: Option<String>,
     pub header_bytes_sent: u64,
     // TODO: `retransmittedPacketsSent` and `retransmittedPacketsSent`
@@ -524,7 +558,7 @@ pub struct OutboundRTPStats {
     // encoding.
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RemoteInboundRTPStats {
     // RTCStats
@@ -537,13 +571,14 @@ pub struct RemoteInboundRTPStats {
     // RTCRtpStreamStats
     pub ssrc: SSRC,
     pub kind: &'static str, // Either "video" or "audio"
-    // TODO: Add tranportId
-    // TODO: Add codecId
+    pub transport_id: String,
+    pub codec_id: Option<String>,
 
     // RTCReceivedRtpStreamStats
     pub packets_received: u64,
     pub packets_lost: i64,
-    // TOOD: jitter(maybe, might be uattainable for the same reason as `framesDropped`)
+    /// The interarrival jitter reported by the remote, in seconds.
+    pub jitter: f64,
     // NB: `framesDropped` can't be produced since we aren't decoding, might be worth introducing a
     // way for consumers to control this in the future.
 
@@ -555,7 +590,7 @@ pub struct RemoteInboundRTPStats {
     pub round_trip_time_measurements: u64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RemoteOutboundRTPStats {
     // RTCStats
@@ -568,8 +603,8 @@ pub struct RemoteOutboundRTPStats {
     // RTCRtpStreamStats
     pub ssrc: SSRC,
     pub kind: &'static str, // Either "video" or "audio"
-    // TODO: Add tranportId
-    // TODO: Add codecId
+    pub transport_id: String,
+    pub codec_id: Option<String>,
 
     // RTCSentRtpStreamStats
     pub packets_sent: u64,
@@ -577,7 +612,8 @@ pub struct RemoteOutboundRTPStats {
 
     // RTCRemoteOutboundRtpStreamStats
     pub local_id: String,
-    // TODO: `remote_timestamp`
+    /// The time the remote sent its latest Sender Report at, on its own clock.
+    pub remote_timestamp: Option<SystemTime>,
     pub round_trip_time: Option<f64>,
     pub reports_sent: u64,
     pub total_round_trip_time: f64,
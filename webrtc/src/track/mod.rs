@@ -1,3 +1,4 @@
+pub mod track_forwarder;
 pub mod track_local;
 pub mod track_remote;
 
@@ -0,0 +1,294 @@
+#[cfg(test)]
+mod track_forwarder_test;
+
+use crate::error::{Error, Result};
+use crate::rtp_transceiver::rtp_codec::RTPCodecType;
+use crate::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
+use crate::track::track_local::{KeyframeRequest, TrackLocal, TrackLocalWriter};
+use crate::track::track_remote::{PacketSlot, TrackRemote};
+
+use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// FORWARD_BATCH_SIZE is the number of packets which are read from the source at once.
+const FORWARD_BATCH_SIZE: usize = 16;
+
+/// KEYFRAME_REQUEST_INTERVAL is the minimum interval between two keyframe requests sent to
+/// the source, so that the requests of many outputs don't flood the remote sender.
+pub(crate) const KEYFRAME_REQUEST_INTERVAL: Duration = Duration::from_millis(500);
+
+/// SequenceRewriter rewrites the sequence numbers and the timestamps of the packets forwarded
+/// to an output, so that they continue the ones already sent when the source is switched.
+#[derive(Debug, Default)]
+pub(crate) struct SequenceRewriter {
+    seq_offset: u16,
+    ts_offset: u32,
+    last: Option<(u16, u32, Instant)>,
+    rebase: bool,
+}
+
+impl SequenceRewriter {
+    /// rebase makes the next packet follow the last packet forwarded, whatever its own
+    /// sequence number and timestamp are.
+    pub(crate) fn rebase(&mut self) {
+        self.rebase = true;
+    }
+
+    pub(crate) fn rewrite(
+        &mut self,
+        header: &mut rtp::header::Header,
+        clock_rate: u32,
+        now: Instant,
+    ) {
+        if self.rebase {
+            self.rebase = false;
+            if let Some((last_seq, last_ts, last_at)) = self.last {
+                let elapsed = now.duration_since(last_at).as_micros();
+                let ts_delta = (elapsed * clock_rate as u128 / 1_000_000).max(1) as u32;
+                self.seq_offset = last_seq
+                    .wrapping_add(1)
+                    .wrapping_sub(header.sequence_number);
+                self.ts_offset = last_ts
+                    .wrapping_add(ts_delta)
+                    .wrapping_sub(header.timestamp);
+            }
+        }
+
+        header.sequence_number = header.sequence_number.wrapping_add(self.seq_offset);
+        header.timestamp = header.timestamp.wrapping_add(self.ts_offset);
+
+        // retransmissions and reordered packets don't move the last packet back
+        let newer = match self.last {
+            Some((last_seq, _, _)) => (header.sequence_number.wrapping_sub(last_seq) as i16) > 0,
+            None => true,
+        };
+        if newer {
+            self.last = Some((header.sequence_number, header.timestamp, now));
+        }
+    }
+}
+
+struct ForwarderOutput {
+    track: Arc<TrackLocalStaticRTP>,
+    rewriter: SequenceRewriter,
+}
+
+struct TrackForwarderInternal {
+    source: Mutex<Arc<TrackRemote>>,
+    outputs: Mutex<Vec<ForwarderOutput>>,
+    last_keyframe_request: Mutex<Option<Instant>>,
+}
+
+impl TrackForwarderInternal {
+    async fn request_keyframe(&self) -> Result<()> {
+        let source = Arc::clone(&*self.source.lock().await);
+        if source.kind() == RTPCodecType::Audio {
+            return Ok(());
+        }
+
+        {
+            let mut last_keyframe_request = self.last_keyframe_request.lock().await;
+            let now = Instant::now();
+            if let Some(last) = *last_keyframe_request {
+                if now.duration_since(last) < KEYFRAME_REQUEST_INTERVAL {
+                    return Ok(());
+                }
+            }
+            *last_keyframe_request = Some(now);
+        }
+
+        source
+            .write_rtcp(&[Box::new(PictureLossIndication {
+                sender_ssrc: 0,
+                media_ssrc: source.ssrc(),
+            })])
+            .await?;
+        Ok(())
+    }
+
+    async fn forward(self: Arc<Self>, source: Arc<TrackRemote>) {
+        let clock_rate = source.codec().await.capability.clock_rate;
+        let mut slots = vec![PacketSlot::default(); FORWARD_BATCH_SIZE];
+        loop {
+            let n = match source.read_rtp_batch(&mut slots).await {
+                Ok(n) => n,
+                Err(Error::Rtp(err)) => {
+                    log::trace!("TrackForwarder: dropped a malformed packet: {}", err);
+                    continue;
+                }
+                Err(err) => {
+                    log::debug!("TrackForwarder: stopped reading {}: {}", source.ssrc(), err);
+                    return;
+                }
+            };
+
+            // The packets are rewritten under the lock and written after it is released, so
+            // that a slow output doesn't block add_output, remove_output and switch_source.
+            let now = Instant::now();
+            let writes = {
+                let mut outputs = self.outputs.lock().await;
+                let mut writes = Vec::with_capacity(n * outputs.len());
+                for slot in &slots[..n] {
+                    for output in outputs.iter_mut() {
+                        let mut packet = slot.packet.clone();
+                        output.rewriter.rewrite(&mut packet.header, clock_rate, now);
+                        writes.push((Arc::clone(&output.track), packet));
+                    }
+                }
+                writes
+            };
+
+            for (track, packet) in writes {
+                if let Err(err) = track.write_rtp(&packet).await {
+                    log::trace!("TrackForwarder: failed to write to {}: {}", track.id(), err);
+                }
+            }
+        }
+    }
+}
+
+/// TrackForwarder forwards the packets of a TrackRemote to TrackLocalStaticRTP outputs in a
+/// single task, which is the fast path of a SFU: the packets don't go through the application,
+/// and their payloads are shared by the outputs instead of being copied.
+///
+/// Each binding of an output rewrites the SSRC and the payload type as usual, and the
+/// forwarder rewrites the sequence numbers and the timestamps, so that an output continues
+/// seamlessly when the source is switched with switch_source. The keyframe requests
+/// (PLI and FIR) of the outputs are routed to the sender of the source as PLIs. The NACKs of
+/// the outputs are answered by the NACK responder of their RTCRtpSender, which buffers the
+/// packets forwarded like any others, so they don't reach the sender of the source.
+pub struct TrackForwarder {
+    internal: Arc<TrackForwarderInternal>,
+    task: std::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl TrackForwarder {
+    /// new creates a TrackForwarder which starts reading the source. A TrackRemote must be
+    /// read by one reader only, so the source must not be read by the application as well.
+    pub fn new(source: Arc<TrackRemote>) -> Self {
+        let internal = Arc::new(TrackForwarderInternal {
+            source: Mutex::new(Arc::clone(&source)),
+            outputs: Mutex::new(vec![]),
+            last_keyframe_request: Mutex::new(None),
+        });
+        let task = tokio::spawn(Arc::clone(&internal).forward(source));
+
+        TrackForwarder {
+            internal,
+            task: std::sync::Mutex::new(Some(task)),
+        }
+    }
+
+    /// source returns the TrackRemote which is forwarded.
+    pub async fn source(&self) -> Arc<TrackRemote> {
+        Arc::clone(&*self.internal.source.lock().await)
+    }
+
+    /// add_output starts forwarding to the track, and requests a keyframe so that the new
+    /// receivers can start decoding. It replaces the on_keyframe_request handler of the
+    /// track to route the keyframe requests to the source.
+    pub async fn add_output(&self, track: Arc<TrackLocalStaticRTP>) {
+        let internal = Arc::downgrade(&self.internal);
+        let output = Arc::downgrade(&track);
+        track.on_keyframe_request(Box::new(move |_: KeyframeRequest| {
+            let internal = Weak::clone(&internal);
+            let output = Weak::clone(&output);
+            Box::pin(async move {
+                if let (Some(internal), Some(output)) = (internal.upgrade(), output.upgrade()) {
+                    let is_output = {
+                        let outputs = internal.outputs.lock().await;
+                        outputs.iter().any(|o| Arc::ptr_eq(&o.track, &output))
+                    };
+                    if is_output {
+                        if let Err(err) = internal.request_keyframe().await {
+                            log::warn!("TrackForwarder: failed to request a keyframe: {}", err);
+                        }
+                    }
+                }
+            })
+        }));
+
+        {
+            let mut outputs = self.internal.outputs.lock().await;
+            outputs.push(ForwarderOutput {
+                track,
+                rewriter: SequenceRewriter::default(),
+            });
+        }
+
+        if let Err(err) = self.internal.request_keyframe().await {
+            log::warn!("TrackForwarder: failed to request a keyframe: {}", err);
+        }
+    }
+
+    /// remove_output stops forwarding to the track, it returns false if the track isn't an
+    /// output of the forwarder.
+    pub async fn remove_output(&self, track: &Arc<TrackLocalStaticRTP>) -> bool {
+        let mut outputs = self.internal.outputs.lock().await;
+        let len = outputs.len();
+        outputs.retain(|o| !Arc::ptr_eq(&o.track, track));
+        outputs.len() != len
+    }
+
+    /// outputs returns the tracks which the source is forwarded to.
+    pub async fn outputs(&self) -> Vec<Arc<TrackLocalStaticRTP>> {
+        let outputs = self.internal.outputs.lock().await;
+        outputs.iter().map(|o| Arc::clone(&o.track)).collect()
+    }
+
+    /// switch_source forwards another TrackRemote to the outputs, such as another simulcast
+    /// encoding or another speaker, and requests a keyframe of it. The sequence numbers and
+    /// the timestamps sent to the outputs continue the ones of the previous source.
+    pub async fn switch_source(&self, source: Arc<TrackRemote>) {
+        self.stop();
+
+        {
+            let mut current = self.internal.source.lock().await;
+            *current = Arc::clone(&source);
+        }
+        {
+            let mut outputs = self.internal.outputs.lock().await;
+            for output in outputs.iter_mut() {
+                output.rewriter.rebase();
+            }
+        }
+        {
+            let mut last_keyframe_request = self.internal.last_keyframe_request.lock().await;
+            *last_keyframe_request = None;
+        }
+
+        let task = tokio::spawn(Arc::clone(&self.internal).forward(source));
+        if let Ok(mut current) = self.task.lock() {
+            *current = Some(task);
+        }
+
+        if let Err(err) = self.internal.request_keyframe().await {
+            log::warn!("TrackForwarder: failed to request a keyframe: {}", err);
+        }
+    }
+
+    /// request_keyframe sends a PLI to the sender of the source, unless a keyframe was
+    /// requested less than 500ms ago. It does nothing for an audio source.
+    pub async fn request_keyframe(&self) -> Result<()> {
+        self.internal.request_keyframe().await
+    }
+
+    /// stop stops forwarding, the outputs are kept until the forwarder is dropped.
+    pub fn stop(&self) {
+        if let Ok(mut task) = self.task.lock() {
+            if let Some(task) = task.take() {
+                task.abort();
+            }
+        }
+    }
+}
+
+impl Drop for TrackForwarder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
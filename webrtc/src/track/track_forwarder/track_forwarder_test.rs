@@ -0,0 +1,174 @@
+use super::*;
+use crate::api::media_engine::{MediaEngine, MIME_TYPE_VP8};
+use crate::api::APIBuilder;
+use crate::peer_connection::peer_connection_test::*;
+use crate::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use crate::rtp_transceiver::rtp_receiver::RTCRtpReceiver;
+use crate::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+
+use bytes::Bytes;
+use tokio::sync::mpsc;
+
+#[test]
+fn test_sequence_rewriter() {
+    let now = Instant::now();
+    let mut rewriter = SequenceRewriter::default();
+
+    let mut header = rtp::header::Header {
+        sequence_number: 100,
+        timestamp: 9000,
+        ..Default::default()
+    };
+    rewriter.rewrite(&mut header, 90000, now);
+    assert_eq!((header.sequence_number, header.timestamp), (100, 9000));
+
+    // a reordered packet is forwarded as is, and doesn't move the last packet back
+    let mut header = rtp::header::Header {
+        sequence_number: 99,
+        timestamp: 6000,
+        ..Default::default()
+    };
+    rewriter.rewrite(&mut header, 90000, now);
+    assert_eq!((header.sequence_number, header.timestamp), (99, 6000));
+
+    // the first packet of the new source follows the last packet of the previous one
+    rewriter.rebase();
+    let mut header = rtp::header::Header {
+        sequence_number: 65535,
+        timestamp: 1000,
+        ..Default::default()
+    };
+    rewriter.rewrite(&mut header, 90000, now + Duration::from_millis(100));
+    assert_eq!(
+        (header.sequence_number, header.timestamp),
+        (101, 9000 + 9000)
+    );
+
+    let mut header = rtp::header::Header {
+        sequence_number: 0,
+        timestamp: 4000,
+        ..Default::default()
+    };
+    rewriter.rewrite(&mut header, 90000, now + Duration::from_millis(130));
+    assert_eq!(
+        (header.sequence_number, header.timestamp),
+        (102, 18000 + 3000)
+    );
+
+    // packets are never forwarded with the same timestamp across a switch
+    rewriter.rebase();
+    let mut header = rtp::header::Header {
+        sequence_number: 7,
+        timestamp: 7,
+        ..Default::default()
+    };
+    rewriter.rewrite(&mut header, 90000, now + Duration::from_millis(130));
+    assert_eq!((header.sequence_number, header.timestamp), (103, 21001));
+}
+
+#[tokio::test]
+async fn test_track_forwarder() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let codec = RTCRtpCodecCapability {
+        mime_type: MIME_TYPE_VP8.to_owned(),
+        ..Default::default()
+    };
+
+    // the publisher sends to the SFU
+    let (mut publisher, mut sfu_in) = new_pair(&api).await?;
+    let published = Arc::new(TrackLocalStaticSample::new(
+        codec.clone(),
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    publisher
+        .add_track(Arc::clone(&published) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (keyframe_request_tx, mut keyframe_request_rx) = mpsc::channel::<()>(1);
+    published.on_keyframe_request(Box::new(move |_: KeyframeRequest| {
+        let keyframe_request_tx = keyframe_request_tx.clone();
+        Box::pin(async move {
+            let _ = keyframe_request_tx.try_send(());
+        })
+    }));
+
+    // the SFU forwards to the subscriber
+    let (mut sfu_out, mut subscriber) = new_pair(&api).await?;
+    let output = Arc::new(TrackLocalStaticRTP::new(
+        codec,
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    sfu_out
+        .add_track(Arc::clone(&output) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (source_tx, mut source_rx) = mpsc::channel::<Arc<TrackRemote>>(1);
+    sfu_in.on_track(Box::new(
+        move |track: Option<Arc<TrackRemote>>, _: Option<Arc<RTCRtpReceiver>>| {
+            let source_tx = source_tx.clone();
+            Box::pin(async move {
+                if let Some(track) = track {
+                    let _ = source_tx.try_send(track);
+                }
+            })
+        },
+    ));
+
+    let (done_tx, done_rx) = mpsc::channel::<()>(1);
+    subscriber.on_track(Box::new(
+        move |track: Option<Arc<TrackRemote>>, _: Option<Arc<RTCRtpReceiver>>| {
+            let done_tx = done_tx.clone();
+            Box::pin(async move {
+                if let Some(track) = track {
+                    tokio::spawn(async move {
+                        if let Ok((packet, _)) = track.read_rtp().await {
+                            assert_eq!(packet.header.ssrc, track.ssrc());
+                            assert!(!packet.payload.is_empty());
+                            let _ = done_tx.send(()).await;
+                        }
+                    });
+                }
+            })
+        },
+    ));
+
+    signal_pair(&mut publisher, &mut sfu_in).await?;
+    signal_pair(&mut sfu_out, &mut subscriber).await?;
+
+    let (forwarder_tx, mut forwarder_rx) = mpsc::channel::<TrackForwarder>(1);
+    let output2 = Arc::clone(&output);
+    tokio::spawn(async move {
+        if let Some(source) = source_rx.recv().await {
+            let forwarder = TrackForwarder::new(source);
+            forwarder.add_output(output2).await;
+            let _ = forwarder_tx.send(forwarder).await;
+        }
+    });
+
+    send_video_until_done(
+        done_rx,
+        vec![Arc::clone(&published)],
+        Bytes::from_static(&[0x00]),
+        None,
+    )
+    .await;
+
+    // the keyframe request of add_output reached the publisher
+    assert!(keyframe_request_rx.recv().await.is_some());
+
+    let forwarder = forwarder_rx.recv().await.unwrap();
+    assert_eq!(forwarder.outputs().await.len(), 1);
+    assert!(forwarder.remove_output(&output).await);
+    assert!(!forwarder.remove_output(&output).await);
+    forwarder.stop();
+
+    close_pair_now(&publisher, &sfu_in).await;
+    close_pair_now(&sfu_out, &subscriber).await;
+
+    Ok(())
+}
@@ -5,15 +5,21 @@ pub mod track_local_static_rtp;
 pub mod track_local_static_sample;
 
 use crate::error::{Error, Result};
+use crate::rtp_transceiver::frame_transform::FrameTransform;
 use crate::rtp_transceiver::rtp_codec::*;
 use crate::rtp_transceiver::*;
 
+use arc_swap::ArcSwapOption;
 use async_trait::async_trait;
+use bytes::Bytes;
 use interceptor::{Attributes, RTPWriter};
 use std::any::Any;
 use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Mutex;
 use util::Unmarshal;
 
@@ -27,6 +33,49 @@ pub trait TrackLocalWriter: fmt::Debug {
     async fn write(&self, b: &[u8]) -> Result<usize>;
 }
 
+/// KeyframeRequest is a request of the remote peer for a keyframe of the encoding sent on
+/// ssrc, received as a PLI or a FIR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyframeRequest {
+    pub ssrc: SSRC,
+    /// rid is the RID of the simulcast encoding, or empty if the sender sends a single one.
+    pub rid: String,
+    /// full_intra_request is set for a FIR, and unset for a PLI.
+    pub full_intra_request: bool,
+}
+
+pub type OnKeyframeRequestHdlrFn = Box<
+    dyn (FnMut(KeyframeRequest) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
+/// KeyframeRequestHandler holds the handler of the keyframe requests of an encoding, which
+/// is shared by the RTCRtpSender and the TrackLocalContext of the encoding.
+#[derive(Default)]
+pub(crate) struct KeyframeRequestHandler {
+    handler: ArcSwapOption<Mutex<OnKeyframeRequestHdlrFn>>,
+}
+
+impl KeyframeRequestHandler {
+    pub(crate) fn store(&self, f: Option<OnKeyframeRequestHdlrFn>) {
+        self.handler.store(f.map(|f| Arc::new(Mutex::new(f))));
+    }
+
+    pub(crate) async fn fire(&self, request: KeyframeRequest) {
+        if let Some(handler) = &*self.handler.load() {
+            let mut f = handler.lock().await;
+            f(request).await;
+        }
+    }
+}
+
+impl fmt::Debug for KeyframeRequestHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyframeRequestHandler").finish()
+    }
+}
+
 /// TrackLocalContext is the Context passed when a TrackLocal has been Binded/Unbinded from a PeerConnection, and used
 /// in Interceptors.
 #[derive(Default, Debug, Clone)]
@@ -34,8 +83,14 @@ pub struct TrackLocalContext {
     pub(crate) id: String,
     pub(crate) params: RTCRtpParameters,
     pub(crate) ssrc: SSRC,
+    pub(crate) rid: String,
+    pub(crate) scalability_mode: Option<ScalabilityMode>,
+    pub(crate) encoding_parameters: Arc<std::sync::Mutex<RTCRtpEncodingParameters>>,
     pub(crate) write_stream: Option<Arc<dyn TrackLocalWriter + Send + Sync>>,
     pub(crate) paused: Arc<AtomicBool>,
+    pub(crate) extmap_allow_mixed: bool,
+    pub(crate) frame_transform: Arc<Mutex<Option<Arc<dyn FrameTransform + Send + Sync>>>>,
+    pub(crate) keyframe_request_handler: Arc<KeyframeRequestHandler>,
 }
 
 impl TrackLocalContext {
@@ -51,12 +106,57 @@ impl TrackLocalContext {
         &self.params.header_extensions
     }
 
+    /// extmap_allow_mixed returns if one- and two-byte header extensions may be mixed, as
+    /// negotiated by "a=extmap-allow-mixed". If so, the header extensions which don't fit the
+    /// one-byte form can be set with rtp::header::Header::set_extension_allow_mixed.
+    pub fn extmap_allow_mixed(&self) -> bool {
+        self.extmap_allow_mixed
+    }
+
+    /// frame_transform returns the FrameTransform set on the RTCRtpSender, which a TrackLocal
+    /// that packetizes frames itself applies to them before packetization.
+    pub async fn frame_transform(&self) -> Option<Arc<dyn FrameTransform + Send + Sync>> {
+        let frame_transform = self.frame_transform.lock().await;
+        frame_transform.clone()
+    }
+
     /// ssrc requires the negotiated SSRC of this track
     /// This track may have multiple if RTX is enabled
     pub fn ssrc(&self) -> SSRC {
         self.ssrc
     }
 
+    /// rid returns the RID of the simulcast encoding this context sends, or an empty string
+    /// if the RTCRtpSender sends a single encoding. A track bound once per encoding can
+    /// write each of them with TrackLocalStaticRTP::write_rtp_for_rid or
+    /// TrackLocalStaticSample::write_sample_for_rid.
+    pub fn rid(&self) -> &str {
+        self.rid.as_str()
+    }
+
+    /// scalability_mode returns the SVC mode of the encoding this context sends, if any.
+    /// A TrackLocal that packetizes frames itself describes the layers in the payload
+    /// descriptor, see RTCRtpCodecCapability::payloader_for_codec_with_scalability_mode.
+    pub fn scalability_mode(&self) -> Option<&ScalabilityMode> {
+        self.scalability_mode.as_ref()
+    }
+
+    /// encoding_parameters returns the current parameters of the encoding this context sends.
+    /// RTCRtpSender::set_parameters changes them without a renegotiation, so an encoder
+    /// writing to this context should follow max_bitrate and max_framerate as they change.
+    pub fn encoding_parameters(&self) -> RTCRtpEncodingParameters {
+        let encoding_parameters = self.encoding_parameters.lock().unwrap();
+        encoding_parameters.clone()
+    }
+
+    /// on_keyframe_request sets a handler that is fired when the remote peer requests a
+    /// keyframe of the encoding this context sends, with a PLI or a FIR. The RTCRtpSender
+    /// reads the RTCP itself, so the handler is fired even if the application doesn't read
+    /// it. A TrackLocal sets it in bind, binding another track to the encoding replaces it.
+    pub fn on_keyframe_request(&self, f: OnKeyframeRequestHdlrFn) {
+        self.keyframe_request_handler.store(Some(f));
+    }
+
     /// write_stream returns the write_stream for this TrackLocal. The implementer writes the outbound
     /// media packets to it
     pub fn write_stream(&self) -> Option<Arc<dyn TrackLocalWriter + Send + Sync>> {
@@ -103,21 +203,101 @@ pub trait TrackLocal {
 pub(crate) struct TrackBinding {
     id: String,
     ssrc: SSRC,
+    rid: String,
     payload_type: PayloadType,
     params: RTCRtpParameters,
     write_stream: Option<Arc<dyn TrackLocalWriter + Send + Sync>>,
     sender_paused: Arc<AtomicBool>,
+    extmap_allow_mixed: bool,
+    frame_transform: Arc<Mutex<Option<Arc<dyn FrameTransform + Send + Sync>>>>,
 }
 
 impl TrackBinding {
     pub fn is_sender_paused(&self) -> bool {
         self.sender_paused.load(Ordering::SeqCst)
     }
+
+    async fn frame_transform(&self) -> Option<Arc<dyn FrameTransform + Send + Sync>> {
+        let frame_transform = self.frame_transform.lock().await;
+        frame_transform.clone()
+    }
+}
+
+/// InsertedPackets keeps the sequence space of a stream which packets are inserted into,
+/// e.g. the DTMF events of an audio track: the packets of the track written after an
+/// inserted packet are renumbered to follow it.
+#[derive(Debug, Default)]
+struct InsertedPackets {
+    /// sequence_number_offset is added to the sequence numbers of the packets of the track.
+    sequence_number_offset: u16,
+    /// last_sequence_number is the sequence number of the latest packet written.
+    last_sequence_number: Option<u16>,
+    /// last_timestamp is the timestamp of the latest packet of the track, written at last_time.
+    last_timestamp: u32,
+    last_time: Option<Instant>,
+    track_started: bool,
+}
+
+impl InsertedPackets {
+    /// on_track_packet records a packet of the track and returns its sequence number.
+    fn on_track_packet(&mut self, header: &rtp::header::Header, now: Instant) -> u16 {
+        if !self.track_started {
+            self.track_started = true;
+            if let Some(last) = self.last_sequence_number {
+                self.sequence_number_offset =
+                    last.wrapping_add(1).wrapping_sub(header.sequence_number);
+            }
+        }
+        let sequence_number = header
+            .sequence_number
+            .wrapping_add(self.sequence_number_offset);
+
+        let newer = match self.last_sequence_number {
+            Some(last) => (sequence_number.wrapping_sub(last) as i16) > 0,
+            None => true,
+        };
+        if newer {
+            self.last_sequence_number = Some(sequence_number);
+            self.last_timestamp = header.timestamp;
+            self.last_time = Some(now);
+        }
+        sequence_number
+    }
+
+    fn on_inserted_packet(&mut self, header: &mut rtp::header::Header) {
+        header.sequence_number = match self.last_sequence_number {
+            Some(last) => last.wrapping_add(1),
+            None => rand::random::<u16>(),
+        };
+        self.last_sequence_number = Some(header.sequence_number);
+        if self.track_started {
+            self.sequence_number_offset = self.sequence_number_offset.wrapping_add(1);
+        }
+    }
+
+    fn timestamp(&mut self, clock_rate: u32, now: Instant) -> u32 {
+        match self.last_time {
+            Some(last_time) => {
+                let elapsed = now.duration_since(last_time);
+                self.last_timestamp
+                    .wrapping_add((elapsed.as_secs_f64() * clock_rate as f64).round() as u64 as u32)
+            }
+            None => {
+                self.last_timestamp = rand::random::<u32>();
+                self.last_time = Some(now);
+                self.last_timestamp
+            }
+        }
+    }
 }
 
 pub(crate) struct InterceptorToTrackLocalWriter {
     pub(crate) interceptor_rtp_writer: Mutex<Option<Arc<dyn RTPWriter + Send + Sync>>>,
     sender_paused: Arc<AtomicBool>,
+    /// header_extensions are set on every packet written, e.g. the MID and RID of a
+    /// simulcast encoding.
+    header_extensions: Vec<(u8, Bytes)>,
+    inserted: std::sync::Mutex<InsertedPackets>,
 }
 
 impl InterceptorToTrackLocalWriter {
@@ -125,9 +305,45 @@ impl InterceptorToTrackLocalWriter {
         InterceptorToTrackLocalWriter {
             interceptor_rtp_writer: Mutex::new(None),
             sender_paused: paused,
+            header_extensions: vec![],
+            inserted: std::sync::Mutex::new(InsertedPackets::default()),
         }
     }
 
+    /// timestamp returns the RTP timestamp of the track at the current time, for a packet
+    /// inserted with write_inserted_rtp in a payload format of the same clock rate.
+    pub(crate) fn timestamp(&self, clock_rate: u32) -> u32 {
+        let mut inserted = self.inserted.lock().unwrap();
+        inserted.timestamp(clock_rate, Instant::now())
+    }
+
+    /// write_inserted_rtp writes a packet which isn't written by the track, e.g. a DTMF
+    /// event, in the sequence space of the track. The sequence number of the packet is set.
+    pub(crate) async fn write_inserted_rtp(&self, pkt: &mut rtp::packet::Packet) -> Result<usize> {
+        if self.is_sender_paused() {
+            return Ok(0);
+        }
+
+        let interceptor_rtp_writer = self.interceptor_rtp_writer.lock().await;
+        if let Some(writer) = &*interceptor_rtp_writer {
+            {
+                let mut inserted = self.inserted.lock().unwrap();
+                inserted.on_inserted_packet(&mut pkt.header);
+            }
+            for (id, payload) in &self.header_extensions {
+                pkt.header.set_extension(*id, payload.clone())?;
+            }
+            Ok(writer.write(pkt, &Attributes::new()).await?)
+        } else {
+            Ok(0)
+        }
+    }
+
+    pub(crate) fn with_header_extensions(mut self, header_extensions: Vec<(u8, Bytes)>) -> Self {
+        self.header_extensions = header_extensions;
+        self
+    }
+
     fn is_sender_paused(&self) -> bool {
         self.sender_paused.load(Ordering::SeqCst)
     }
@@ -149,7 +365,20 @@ impl TrackLocalWriter for InterceptorToTrackLocalWriter {
         let interceptor_rtp_writer = self.interceptor_rtp_writer.lock().await;
         if let Some(writer) = &*interceptor_rtp_writer {
             let a = Attributes::new();
-            Ok(writer.write(pkt, &a).await?)
+            let sequence_number = {
+                let mut inserted = self.inserted.lock().unwrap();
+                inserted.on_track_packet(&pkt.header, Instant::now())
+            };
+            if self.header_extensions.is_empty() && sequence_number == pkt.header.sequence_number {
+                return Ok(writer.write(pkt, &a).await?);
+            }
+
+            let mut pkt = pkt.clone();
+            pkt.header.sequence_number = sequence_number;
+            for (id, payload) in &self.header_extensions {
+                pkt.header.set_extension(*id, payload.clone())?;
+            }
+            Ok(writer.write(&pkt, &a).await?)
         } else {
             Ok(0)
         }
@@ -3,18 +3,30 @@ use std::collections::HashMap;
 use super::*;
 
 use crate::error::flatten_errs;
+use arc_swap::ArcSwapOption;
 use bytes::BytesMut;
 use tokio::sync::Mutex;
 use util::{Marshal, MarshalSize};
 
 /// TrackLocalStaticRTP  is a TrackLocal that has a pre-set codec and accepts RTP Packets.
 /// If you wish to send a media.Sample use TrackLocalStaticSample
-#[derive(Debug)]
 pub struct TrackLocalStaticRTP {
     pub(crate) bindings: Mutex<Vec<Arc<TrackBinding>>>,
     codec: RTCRtpCodecCapability,
     id: String,
     stream_id: String,
+    on_keyframe_request_handler: Arc<ArcSwapOption<Mutex<OnKeyframeRequestHdlrFn>>>,
+}
+
+impl fmt::Debug for TrackLocalStaticRTP {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrackLocalStaticRTP")
+            .field("bindings", &self.bindings)
+            .field("codec", &self.codec)
+            .field("id", &self.id)
+            .field("stream_id", &self.stream_id)
+            .finish()
+    }
 }
 
 impl TrackLocalStaticRTP {
@@ -25,6 +37,7 @@ impl TrackLocalStaticRTP {
             bindings: Mutex::new(vec![]),
             id,
             stream_id,
+            on_keyframe_request_handler: Arc::new(ArcSwapOption::empty()),
         }
     }
 
@@ -33,6 +46,14 @@ impl TrackLocalStaticRTP {
         self.codec.clone()
     }
 
+    /// on_keyframe_request sets a handler that is fired when the remote peer of any binding
+    /// of the track requests a keyframe with a PLI or a FIR, so that the application's
+    /// encoder can produce one. The request tells the SSRC and the RID of the encoding.
+    pub fn on_keyframe_request(&self, f: OnKeyframeRequestHdlrFn) {
+        self.on_keyframe_request_handler
+            .store(Some(Arc::new(Mutex::new(f))));
+    }
+
     pub async fn any_binding_paused(&self) -> bool {
         let bindings = self.bindings.lock().await;
         bindings
@@ -47,6 +68,46 @@ impl TrackLocalStaticRTP {
             .all(|b| b.sender_paused.load(Ordering::SeqCst))
     }
 
+    /// any_binding_paused_for_rid and all_binding_paused_for_rid are any_binding_paused and
+    /// all_binding_paused for the bindings of the simulcast encoding with the given RID.
+    pub(crate) async fn any_binding_paused_for_rid(&self, rid: &str) -> bool {
+        let bindings = self.bindings.lock().await;
+        bindings
+            .iter()
+            .filter(|b| b.rid == rid)
+            .any(|b| b.sender_paused.load(Ordering::SeqCst))
+    }
+
+    pub(crate) async fn all_binding_paused_for_rid(&self, rid: &str) -> bool {
+        let bindings = self.bindings.lock().await;
+        bindings
+            .iter()
+            .filter(|b| b.rid == rid)
+            .all(|b| b.sender_paused.load(Ordering::SeqCst))
+    }
+
+    /// frame_transform returns the FrameTransform of the first unpaused binding which has one,
+    /// with the SSRC and payload type of that binding. If rid is set only the bindings of
+    /// that simulcast encoding are considered.
+    pub(crate) async fn frame_transform(
+        &self,
+        rid: Option<&str>,
+    ) -> Option<(SSRC, PayloadType, Arc<dyn FrameTransform + Send + Sync>)> {
+        let bindings = {
+            let bindings = self.bindings.lock().await;
+            bindings.clone()
+        };
+        for b in bindings {
+            if b.is_sender_paused() || rid.map_or(false, |rid| b.rid != rid) {
+                continue;
+            }
+            if let Some(frame_transform) = b.frame_transform().await {
+                return Some((b.ssrc, b.payload_type, frame_transform));
+            }
+        }
+        None
+    }
+
     /// write_rtp_with_extensions writes a RTP Packet to the TrackLocalStaticRTP
     /// If one PeerConnection fails the packets will still be sent to
     /// all PeerConnections. The error message will contain the ID of the failed
@@ -63,6 +124,34 @@ impl TrackLocalStaticRTP {
         &self,
         p: &rtp::packet::Packet,
         extensions: &[rtp::extension::HeaderExtension],
+    ) -> Result<usize> {
+        self.write_rtp_to_bindings(p, extensions, None).await
+    }
+
+    /// write_rtp_for_rid writes a RTP Packet only to the bindings of the simulcast encoding
+    /// with the given RID, so that an application can send a differently encoded stream per
+    /// encoding of a RTCRtpSender. write_rtp writes the packet to the bindings of all the
+    /// encodings.
+    pub async fn write_rtp_for_rid(&self, rid: &str, p: &rtp::packet::Packet) -> Result<usize> {
+        self.write_rtp_to_bindings(p, &[], Some(rid)).await
+    }
+
+    /// write_rtp_for_rid_with_extensions is write_rtp_for_rid with the extensions of
+    /// write_rtp_with_extensions.
+    pub async fn write_rtp_for_rid_with_extensions(
+        &self,
+        rid: &str,
+        p: &rtp::packet::Packet,
+        extensions: &[rtp::extension::HeaderExtension],
+    ) -> Result<usize> {
+        self.write_rtp_to_bindings(p, extensions, Some(rid)).await
+    }
+
+    async fn write_rtp_to_bindings(
+        &self,
+        p: &rtp::packet::Packet,
+        extensions: &[rtp::extension::HeaderExtension],
+        rid: Option<&str>,
     ) -> Result<usize> {
         let mut n = 0;
         let mut write_errs = vec![];
@@ -96,6 +185,9 @@ impl TrackLocalStaticRTP {
                 // See caveat in function doc.
                 continue;
             }
+            if rid.map_or(false, |rid| b.rid != rid) {
+                continue;
+            }
             pkt.header.ssrc = b.ssrc;
             pkt.header.payload_type = b.payload_type;
 
@@ -107,7 +199,12 @@ impl TrackLocalStaticRTP {
                     .find(|ext| &ext.uri == uri)
                     .map(|ext| ext.id)
                 {
-                    if let Err(err) = pkt.header.set_extension(id as u8, data.clone()) {
+                    let result = if b.extmap_allow_mixed {
+                        pkt.header.set_extension_allow_mixed(id as u8, data.clone())
+                    } else {
+                        pkt.header.set_extension(id as u8, data.clone())
+                    };
+                    if let Err(err) = result {
                         write_errs.push(Error::Rtp(err));
                         continue;
                     }
@@ -150,14 +247,28 @@ impl TrackLocal for TrackLocalStaticRTP {
                 let mut bindings = self.bindings.lock().await;
                 bindings.push(Arc::new(TrackBinding {
                     ssrc: t.ssrc(),
+                    rid: t.rid.clone(),
                     payload_type: codec.payload_type,
                     write_stream: t.write_stream(),
                     params: t.params.clone(),
                     id: t.id(),
                     sender_paused: t.paused.clone(),
+                    extmap_allow_mixed: t.extmap_allow_mixed,
+                    frame_transform: Arc::clone(&t.frame_transform),
                 }));
             }
 
+            let on_keyframe_request_handler = Arc::clone(&self.on_keyframe_request_handler);
+            t.on_keyframe_request(Box::new(move |request: KeyframeRequest| {
+                let on_keyframe_request_handler = Arc::clone(&on_keyframe_request_handler);
+                Box::pin(async move {
+                    if let Some(handler) = &*on_keyframe_request_handler.load() {
+                        let mut f = handler.lock().await;
+                        f(request).await;
+                    }
+                })
+            }));
+
             Ok(codec)
         } else {
             Err(Error::ErrUnsupportedCodec)
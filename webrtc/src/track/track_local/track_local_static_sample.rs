@@ -1,10 +1,12 @@
 use super::track_local_static_rtp::TrackLocalStaticRTP;
 use super::*;
 use crate::error::flatten_errs;
+use crate::rtp_transceiver::frame_transform::EncodedFrame;
 
 use crate::track::RTP_OUTBOUND_MTU;
 use log::warn;
 use media::Sample;
+use std::collections::HashMap;
 use tokio::sync::Mutex;
 
 #[derive(Debug, Clone)]
@@ -15,12 +17,41 @@ struct TrackLocalStaticSampleInternal {
     did_warn_about_wonky_pause: bool,
 }
 
+impl TrackLocalStaticSampleInternal {
+    fn new(
+        codec: &RTCRtpCodecParameters,
+        scalability_mode: Option<&ScalabilityMode>,
+    ) -> Result<Self> {
+        let payloader = codec
+            .capability
+            .payloader_for_codec_with_scalability_mode(scalability_mode)?;
+        let sequencer: Box<dyn rtp::sequence::Sequencer + Send + Sync> =
+            Box::new(rtp::sequence::new_random_sequencer());
+        Ok(TrackLocalStaticSampleInternal {
+            packetizer: Some(Box::new(rtp::packetizer::new_packetizer(
+                RTP_OUTBOUND_MTU,
+                0, // Value is handled when writing
+                0, // Value is handled when writing
+                payloader,
+                sequencer.clone(),
+                codec.capability.clock_rate,
+            ))),
+            sequencer: Some(sequencer),
+            clock_rate: codec.capability.clock_rate as f64,
+            did_warn_about_wonky_pause: false,
+        })
+    }
+}
+
 /// TrackLocalStaticSample is a TrackLocal that has a pre-set codec and accepts Samples.
 /// If you wish to send a RTP Packet use TrackLocalStaticRTP
 #[derive(Debug)]
 pub struct TrackLocalStaticSample {
     rtp_track: TrackLocalStaticRTP,
     internal: Mutex<TrackLocalStaticSampleInternal>,
+    /// rid_internals holds a packetizer per simulcast encoding the track is bound to, as each
+    /// encoding is sent on its own SSRC with its own sequence numbers.
+    rid_internals: Mutex<HashMap<String, TrackLocalStaticSampleInternal>>,
 }
 
 impl TrackLocalStaticSample {
@@ -36,6 +67,7 @@ impl TrackLocalStaticSample {
                 clock_rate: 0.0f64,
                 did_warn_about_wonky_pause: false,
             }),
+            rid_internals: Mutex::new(HashMap::new()),
         }
     }
 
@@ -44,10 +76,21 @@ impl TrackLocalStaticSample {
         self.rtp_track.codec()
     }
 
+    /// on_keyframe_request sets a handler that is fired when the remote peer requests a
+    /// keyframe with a PLI or a FIR, so that the application's encoder can produce one for
+    /// the next write_sample. See TrackLocalStaticRTP::on_keyframe_request.
+    pub fn on_keyframe_request(&self, f: OnKeyframeRequestHdlrFn) {
+        self.rtp_track.on_keyframe_request(f)
+    }
+
     /// write_sample writes a Sample to the TrackLocalStaticSample
     /// If one PeerConnection fails the packets will still be sent to
     /// all PeerConnections. The error message will contain the ID of the failed
     /// PeerConnections so you can remove them
+    ///
+    /// If a FrameTransform is set on a RTCRtpSender of the track, the sample is transformed
+    /// before it is packetized. As the packets are shared by all the PeerConnections, the
+    /// transform of the first RTCRtpSender which has one applies to all of them.
     pub async fn write_sample(&self, sample: &Sample) -> Result<()> {
         self.write_sample_with_extensions(sample, &[]).await
     }
@@ -63,15 +106,61 @@ impl TrackLocalStaticSample {
         extensions: &[rtp::extension::HeaderExtension],
     ) -> Result<()> {
         let mut internal = self.internal.lock().await;
+        self.write_sample_to_bindings(&mut internal, sample, extensions, None)
+            .await
+    }
+
+    /// write_sample_for_rid writes a Sample only to the simulcast encoding with the given RID,
+    /// packetized on the sequence numbers of that encoding. The application encodes the
+    /// track once per encoding, following the scale_resolution_down_by and max_bitrate of
+    /// the RTCRtpEncodingParameters, and writes each of them with its RID.
+    ///
+    /// Samples written with write_sample go to all the encodings, the two shouldn't be mixed
+    /// on a simulcast track.
+    pub async fn write_sample_for_rid(&self, rid: &str, sample: &Sample) -> Result<()> {
+        self.write_sample_for_rid_with_extensions(rid, sample, &[])
+            .await
+    }
+
+    /// write_sample_for_rid_with_extensions is write_sample_for_rid with provided RTP
+    /// extensions.
+    pub async fn write_sample_for_rid_with_extensions(
+        &self,
+        rid: &str,
+        sample: &Sample,
+        extensions: &[rtp::extension::HeaderExtension],
+    ) -> Result<()> {
+        let mut rid_internals = self.rid_internals.lock().await;
+        match rid_internals.get_mut(rid) {
+            Some(internal) => {
+                self.write_sample_to_bindings(internal, sample, extensions, Some(rid))
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
 
+    async fn write_sample_to_bindings(
+        &self,
+        internal: &mut TrackLocalStaticSampleInternal,
+        sample: &Sample,
+        extensions: &[rtp::extension::HeaderExtension],
+        rid: Option<&str>,
+    ) -> Result<()> {
         if internal.packetizer.is_none() || internal.sequencer.is_none() {
             return Ok(());
         }
 
-        let (any_paused, all_paused) = (
-            self.rtp_track.any_binding_paused().await,
-            self.rtp_track.all_binding_paused().await,
-        );
+        let (any_paused, all_paused) = match rid {
+            Some(rid) => (
+                self.rtp_track.any_binding_paused_for_rid(rid).await,
+                self.rtp_track.all_binding_paused_for_rid(rid).await,
+            ),
+            None => (
+                self.rtp_track.any_binding_paused().await,
+                self.rtp_track.all_binding_paused().await,
+            ),
+        };
 
         if all_paused {
             // Abort already here to not increment sequence numbers.
@@ -113,23 +202,43 @@ impl TrackLocalStaticSample {
 
         let clock_rate = internal.clock_rate;
 
+        let data = match self.rtp_track.frame_transform(rid).await {
+            Some((ssrc, payload_type, frame_transform)) => {
+                let frame = EncodedFrame {
+                    ssrc,
+                    payload_type,
+                    data: sample.data.clone(),
+                };
+                frame_transform.transform(frame).await?.data
+            }
+            None => sample.data.clone(),
+        };
+
         let packets = if let Some(packetizer) = &mut internal.packetizer {
             let samples = (sample.duration.as_secs_f64() * clock_rate) as u32;
             if sample.prev_dropped_packets > 0 {
                 packetizer.skip_samples(samples * sample.prev_dropped_packets as u32);
             }
-            packetizer.packetize(&sample.data, samples).await?
+            packetizer.packetize(&data, samples).await?
         } else {
             vec![]
         };
 
         let mut write_errs = vec![];
         for p in packets {
-            if let Err(err) = self
-                .rtp_track
-                .write_rtp_with_extensions(&p, extensions)
-                .await
-            {
+            let result = match rid {
+                Some(rid) => {
+                    self.rtp_track
+                        .write_rtp_for_rid_with_extensions(rid, &p, extensions)
+                        .await
+                }
+                None => {
+                    self.rtp_track
+                        .write_rtp_with_extensions(&p, extensions)
+                        .await
+                }
+            };
+            if let Err(err) = result {
                 write_errs.push(err);
             }
         }
@@ -184,6 +293,17 @@ impl TrackLocal for TrackLocalStaticSample {
     async fn bind(&self, t: &TrackLocalContext) -> Result<RTCRtpCodecParameters> {
         let codec = self.rtp_track.bind(t).await?;
 
+        // Each simulcast encoding needs a packetizer of its own
+        if !t.rid().is_empty() {
+            let mut rid_internals = self.rid_internals.lock().await;
+            if !rid_internals.contains_key(t.rid()) {
+                rid_internals.insert(
+                    t.rid().to_owned(),
+                    TrackLocalStaticSampleInternal::new(&codec, t.scalability_mode())?,
+                );
+            }
+        }
+
         let mut internal = self.internal.lock().await;
 
         // We only need one packetizer
@@ -191,19 +311,7 @@ impl TrackLocal for TrackLocalStaticSample {
             return Ok(codec);
         }
 
-        let payloader = codec.capability.payloader_for_codec()?;
-        let sequencer: Box<dyn rtp::sequence::Sequencer + Send + Sync> =
-            Box::new(rtp::sequence::new_random_sequencer());
-        internal.packetizer = Some(Box::new(rtp::packetizer::new_packetizer(
-            RTP_OUTBOUND_MTU,
-            0, // Value is handled when writing
-            0, // Value is handled when writing
-            payloader,
-            sequencer.clone(),
-            codec.capability.clock_rate,
-        )));
-        internal.sequencer = Some(sequencer);
-        internal.clock_rate = codec.capability.clock_rate as f64;
+        *internal = TrackLocalStaticSampleInternal::new(&codec, t.scalability_mode())?;
 
         Ok(codec)
     }
@@ -435,3 +435,127 @@ async fn test_track_local_static_binding_non_blocking() -> Result<()> {
     }
 }
 */
+
+// Assert that the PLI of the remote peer is handed to the track without reading the RTCP of
+// the RTCRtpSender
+#[tokio::test]
+async fn test_track_local_static_keyframe_request() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut offerer, mut answerer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    offerer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (keyframe_request_tx, keyframe_request_rx) = mpsc::channel::<()>(1);
+    track.on_keyframe_request(Box::new(move |request: KeyframeRequest| {
+        let keyframe_request_tx = keyframe_request_tx.clone();
+        Box::pin(async move {
+            assert!(request.rid.is_empty());
+            assert!(!request.full_intra_request);
+            let _ = keyframe_request_tx.try_send(());
+        })
+    }));
+
+    let (ssrc_tx, mut ssrc_rx) = mpsc::channel::<SSRC>(1);
+    answerer.on_track(Box::new(
+        move |track: Option<Arc<TrackRemote>>, _: Option<Arc<RTCRtpReceiver>>| {
+            let ssrc_tx = ssrc_tx.clone();
+            Box::pin(async move {
+                if let Some(t) = track {
+                    let _ = ssrc_tx.try_send(t.ssrc());
+                }
+            })
+        },
+    ));
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+
+    let answerer = Arc::new(answerer);
+    let answerer2 = Arc::clone(&answerer);
+    tokio::spawn(async move {
+        let media_ssrc = match ssrc_rx.recv().await {
+            Some(ssrc) => ssrc,
+            None => return,
+        };
+        loop {
+            let pli = rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication {
+                sender_ssrc: 0,
+                media_ssrc,
+            };
+            if answerer2.write_rtcp(&[Box::new(pli)]).await.is_err() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    });
+
+    send_video_until_done(
+        keyframe_request_rx,
+        vec![track],
+        Bytes::from_static(&[0x00]),
+        None,
+    )
+    .await;
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
+
+#[test]
+fn test_inserted_packets() {
+    let now = Instant::now();
+    let mut inserted = InsertedPackets::default();
+
+    let header = |sequence_number: u16, timestamp: u32| rtp::header::Header {
+        sequence_number,
+        timestamp,
+        ..Default::default()
+    };
+
+    assert_eq!(inserted.on_track_packet(&header(65534, 1000), now), 65534);
+    assert_eq!(inserted.timestamp(48000, now), 1000);
+    assert_eq!(
+        inserted.timestamp(48000, now + std::time::Duration::from_millis(20)),
+        1960
+    );
+
+    // inserted packets follow the packets of the track, which are renumbered after them
+    let mut inserted_header = header(0, 1960);
+    inserted.on_inserted_packet(&mut inserted_header);
+    assert_eq!(inserted_header.sequence_number, 65535);
+    inserted.on_inserted_packet(&mut inserted_header);
+    assert_eq!(inserted_header.sequence_number, 0);
+    assert_eq!(inserted.on_track_packet(&header(65535, 1960), now), 1);
+
+    // a reordered packet of the track doesn't move the last sequence number back
+    assert_eq!(inserted.on_track_packet(&header(65533, 40), now), 65535);
+    inserted.on_inserted_packet(&mut inserted_header);
+    assert_eq!(inserted_header.sequence_number, 2);
+
+    // packets inserted before the track's are followed by the track's
+    let mut inserted = InsertedPackets::default();
+    let mut inserted_header = header(0, 0);
+    inserted.on_inserted_packet(&mut inserted_header);
+    let first = inserted_header.sequence_number;
+    assert_eq!(
+        inserted.on_track_packet(&header(100, 0), now),
+        first.wrapping_add(1)
+    );
+    assert_eq!(
+        inserted.on_track_packet(&header(101, 0), now),
+        first.wrapping_add(2)
+    );
+}
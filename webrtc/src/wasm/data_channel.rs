@@ -0,0 +1,135 @@
+use super::{event_closure, EventClosure};
+use crate::data_channel::data_channel_message::DataChannelMessage;
+use crate::data_channel::data_channel_state::RTCDataChannelState;
+use crate::error::Result;
+
+use bytes::Bytes;
+use js_sys::{ArrayBuffer, Uint8Array};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{MessageEvent, RtcDataChannel, RtcDataChannelType};
+
+pub type OnMessageHdlrFn =
+    Box<dyn FnMut(DataChannelMessage) -> Pin<Box<dyn Future<Output = ()> + 'static>>>;
+
+pub type OnOpenHdlrFn = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()> + 'static>>>;
+
+pub type OnCloseHdlrFn = Box<dyn FnMut() -> Pin<Box<dyn Future<Output = ()> + 'static>>>;
+
+/// RTCDataChannel is a DataChannel of the browser.
+pub struct RTCDataChannel {
+    dc: RtcDataChannel,
+    handlers: RefCell<HashMap<&'static str, EventClosure>>,
+}
+
+impl RTCDataChannel {
+    pub(crate) fn new(dc: RtcDataChannel) -> Self {
+        dc.set_binary_type(RtcDataChannelType::Arraybuffer);
+        RTCDataChannel {
+            dc,
+            handlers: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn set_handler(
+        &self,
+        event: &'static str,
+        closure: EventClosure,
+        set: fn(&RtcDataChannel, Option<&js_sys::Function>),
+    ) {
+        set(&self.dc, Some(closure.as_ref().unchecked_ref()));
+        self.handlers.borrow_mut().insert(event, closure);
+    }
+
+    /// label represents a label that can be used to distinguish this
+    /// DataChannel object from other DataChannel objects.
+    pub fn label(&self) -> String {
+        self.dc.label()
+    }
+
+    /// ready_state represents the state of the DataChannel object.
+    pub fn ready_state(&self) -> RTCDataChannelState {
+        let state = JsValue::from(self.dc.ready_state())
+            .as_string()
+            .unwrap_or_default();
+        RTCDataChannelState::from(state.as_str())
+    }
+
+    /// on_open sets an event handler which is invoked when the underlying data transport
+    /// has been established, at once if the DataChannel is already open.
+    pub fn on_open(&self, f: OnOpenHdlrFn) {
+        if self.ready_state() == RTCDataChannelState::Open {
+            spawn_local(f());
+            return;
+        }
+
+        let mut f = Some(f);
+        let closure = event_closure(move |_| {
+            if let Some(f) = f.take() {
+                spawn_local(f());
+            }
+        });
+        self.set_handler("open", closure, RtcDataChannel::set_onopen);
+    }
+
+    /// on_close sets an event handler which is invoked when
+    /// the underlying data transport has been closed.
+    pub fn on_close(&self, mut f: OnCloseHdlrFn) {
+        let closure = event_closure(move |_| spawn_local(f()));
+        self.set_handler("close", closure, RtcDataChannel::set_onclose);
+    }
+
+    /// on_message sets an event handler which is invoked on a message arrival from the
+    /// remote peer.
+    pub fn on_message(&self, mut f: OnMessageHdlrFn) {
+        let closure = event_closure(move |event| {
+            let data = event.unchecked_into::<MessageEvent>().data();
+            let msg = match data.as_string() {
+                Some(text) => DataChannelMessage {
+                    is_string: true,
+                    data: Bytes::from(text),
+                },
+                None => match data.dyn_into::<ArrayBuffer>() {
+                    Ok(buf) => DataChannelMessage {
+                        is_string: false,
+                        data: Bytes::from(Uint8Array::new(&buf).to_vec()),
+                    },
+                    Err(_) => return,
+                },
+            };
+            spawn_local(f(msg));
+        });
+        self.set_handler("message", closure, RtcDataChannel::set_onmessage);
+    }
+
+    /// send sends the binary message to the DataChannel peer.
+    pub async fn send(&self, data: &Bytes) -> Result<usize> {
+        self.dc.send_with_u8_array(data)?;
+        Ok(data.len())
+    }
+
+    /// send_text sends the text message to the DataChannel peer.
+    pub async fn send_text(&self, s: String) -> Result<usize> {
+        self.dc.send_with_str(&s)?;
+        Ok(s.len())
+    }
+
+    /// close closes the DataChannel.
+    pub async fn close(&self) -> Result<()> {
+        self.dc.close();
+        Ok(())
+    }
+}
+
+impl Drop for RTCDataChannel {
+    fn drop(&mut self) {
+        // The handlers are freed with the DataChannel, the browser must not call them
+        self.dc.set_onopen(None);
+        self.dc.set_onclose(None);
+        self.dc.set_onmessage(None);
+    }
+}
@@ -0,0 +1,324 @@
+//! wasm backs RTCPeerConnection and RTCDataChannel with the WebRTC API of the browser, for
+//! the applications which are compiled to wasm32-unknown-unknown.
+//!
+//! The descriptions, candidates, states, messages and configuration are the types of
+//! crate::peer_connection and crate::data_channel, and the methods keep the names of the
+//! native ones. The two APIs are not interchangeable though, code written against the
+//! native RTCPeerConnection does not build on wasm32 as is:
+//!
+//! * A PeerConnection is created with [`RTCPeerConnection::new`], there is no API or
+//!   APIBuilder, and only the ICE servers of the RTCConfiguration are passed to the browser.
+//! * The objects of the browser are not Send, so the DataChannels are handed out as
+//!   `Rc<RTCDataChannel>` instead of `Arc<RTCDataChannel>`.
+//! * The handlers and the futures they return don't have to be Send or Sync, they are run
+//!   with spawn_local on the event loop of the browser. The handler types of this module
+//!   are to be used instead of the native ones.
+//! * on_ice_candidate is called with an RTCIceCandidateInit, which is what the browser
+//!   hands out, instead of an RTCIceCandidate.
+//! * Only the handlers and methods below are provided, there are no tracks, transceivers,
+//!   stats or the other handlers of the native RTCPeerConnection.
+
+pub mod data_channel;
+
+use crate::data_channel::data_channel_init::RTCDataChannelInit;
+use crate::error::{Error, Result};
+use crate::ice_transport::ice_candidate::RTCIceCandidateInit;
+use crate::peer_connection::configuration::RTCConfiguration;
+use crate::peer_connection::offer_answer_options::{RTCAnswerOptions, RTCOfferOptions};
+use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use crate::peer_connection::sdp::sdp_type::RTCSdpType;
+use crate::peer_connection::sdp::session_description::RTCSessionDescription;
+use data_channel::RTCDataChannel;
+
+use js_sys::{Array, Reflect};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{
+    RtcConfiguration, RtcDataChannelEvent, RtcDataChannelInit, RtcIceCandidateInit, RtcIceServer,
+    RtcPeerConnection, RtcPeerConnectionIceEvent, RtcSdpType, RtcSessionDescriptionInit,
+};
+
+impl From<JsValue> for Error {
+    fn from(e: JsValue) -> Self {
+        let msg = match e.dyn_ref::<js_sys::Error>() {
+            Some(err) => String::from(err.message()),
+            None => e.as_string().unwrap_or_else(|| format!("{:?}", e)),
+        };
+        Error::Js(msg)
+    }
+}
+
+pub type OnICECandidateHdlrFn =
+    Box<dyn FnMut(Option<RTCIceCandidateInit>) -> Pin<Box<dyn Future<Output = ()> + 'static>>>;
+
+pub type OnPeerConnectionStateChangeHdlrFn =
+    Box<dyn FnMut(RTCPeerConnectionState) -> Pin<Box<dyn Future<Output = ()> + 'static>>>;
+
+pub type OnDataChannelHdlrFn =
+    Box<dyn FnMut(Rc<RTCDataChannel>) -> Pin<Box<dyn Future<Output = ()> + 'static>>>;
+
+pub type OnNegotiationNeededHdlrFn =
+    Box<dyn FnMut() -> Pin<Box<dyn Future<Output = ()> + 'static>>>;
+
+/// EventClosure is an event handler set on an object of the browser, which is kept alive
+/// as long as it is set.
+pub(crate) type EventClosure = Closure<dyn FnMut(JsValue)>;
+
+pub(crate) fn event_closure(f: impl FnMut(JsValue) + 'static) -> EventClosure {
+    Closure::wrap(Box::new(f) as Box<dyn FnMut(JsValue)>)
+}
+
+fn description_to_js(desc: &RTCSessionDescription) -> Result<RtcSessionDescriptionInit> {
+    let sdp_type = RtcSdpType::from_js_value(&JsValue::from_str(&desc.sdp_type.to_string()))
+        .ok_or_else(|| Error::Js(format!("invalid sdp type {}", desc.sdp_type)))?;
+    let init = RtcSessionDescriptionInit::new(sdp_type);
+    init.set_sdp(&desc.sdp);
+    Ok(init)
+}
+
+fn description_from_js(desc: &JsValue) -> Result<RTCSessionDescription> {
+    let sdp_type = Reflect::get(desc, &JsValue::from_str("type"))?
+        .as_string()
+        .unwrap_or_default();
+    let sdp = Reflect::get(desc, &JsValue::from_str("sdp"))?
+        .as_string()
+        .unwrap_or_default();
+    Ok(RTCSessionDescription {
+        sdp_type: RTCSdpType::from(sdp_type.as_str()),
+        sdp,
+        parsed: None,
+    })
+}
+
+fn connection_state(pc: &RtcPeerConnection) -> RTCPeerConnectionState {
+    let state = JsValue::from(pc.connection_state())
+        .as_string()
+        .unwrap_or_default();
+    RTCPeerConnectionState::from(state.as_str())
+}
+
+/// RTCPeerConnection is a PeerConnection of the browser.
+pub struct RTCPeerConnection {
+    pc: RtcPeerConnection,
+    handlers: RefCell<HashMap<&'static str, EventClosure>>,
+}
+
+impl RTCPeerConnection {
+    /// new creates a PeerConnection with the ICE servers of the configuration, the other
+    /// settings are left to the browser.
+    pub fn new(configuration: &RTCConfiguration) -> Result<Self> {
+        let ice_servers = Array::new();
+        for server in &configuration.ice_servers {
+            let ice_server = RtcIceServer::new();
+            let urls = server
+                .urls
+                .iter()
+                .map(|url| JsValue::from_str(url))
+                .collect::<Array>();
+            ice_server.set_urls(&urls);
+            if !server.username.is_empty() {
+                ice_server.set_username(&server.username);
+            }
+            if !server.credential.is_empty() {
+                ice_server.set_credential(&server.credential);
+            }
+            ice_servers.push(&ice_server);
+        }
+        let config = RtcConfiguration::new();
+        config.set_ice_servers(&ice_servers);
+
+        Ok(RTCPeerConnection {
+            pc: RtcPeerConnection::new_with_configuration(&config)?,
+            handlers: RefCell::new(HashMap::new()),
+        })
+    }
+
+    fn set_handler(
+        &self,
+        event: &'static str,
+        closure: EventClosure,
+        set: fn(&RtcPeerConnection, Option<&js_sys::Function>),
+    ) {
+        set(&self.pc, Some(closure.as_ref().unchecked_ref()));
+        self.handlers.borrow_mut().insert(event, closure);
+    }
+
+    /// create_offer generates an offer, without the candidates which are gathered once it
+    /// is set as the local description.
+    pub async fn create_offer(
+        &self,
+        _options: Option<RTCOfferOptions>,
+    ) -> Result<RTCSessionDescription> {
+        let offer = JsFuture::from(self.pc.create_offer()).await?;
+        description_from_js(&offer)
+    }
+
+    /// create_answer generates an answer to the remote offer.
+    pub async fn create_answer(
+        &self,
+        _options: Option<RTCAnswerOptions>,
+    ) -> Result<RTCSessionDescription> {
+        let answer = JsFuture::from(self.pc.create_answer()).await?;
+        description_from_js(&answer)
+    }
+
+    /// set_local_description sets the SessionDescription of the local peer.
+    pub async fn set_local_description(&self, desc: RTCSessionDescription) -> Result<()> {
+        let desc = description_to_js(&desc)?;
+        JsFuture::from(self.pc.set_local_description(&desc)).await?;
+        Ok(())
+    }
+
+    /// set_remote_description sets the SessionDescription of the remote peer.
+    pub async fn set_remote_description(&self, desc: RTCSessionDescription) -> Result<()> {
+        let desc = description_to_js(&desc)?;
+        JsFuture::from(self.pc.set_remote_description(&desc)).await?;
+        Ok(())
+    }
+
+    /// local_description returns the local description with the candidates gathered so far.
+    pub async fn local_description(&self) -> Option<RTCSessionDescription> {
+        self.pc
+            .local_description()
+            .and_then(|desc| description_from_js(&desc).ok())
+    }
+
+    /// remote_description returns the remote description.
+    pub async fn remote_description(&self) -> Option<RTCSessionDescription> {
+        self.pc
+            .remote_description()
+            .and_then(|desc| description_from_js(&desc).ok())
+    }
+
+    /// add_ice_candidate adds a candidate of the remote peer.
+    pub async fn add_ice_candidate(&self, candidate: RTCIceCandidateInit) -> Result<()> {
+        let init = RtcIceCandidateInit::new(&candidate.candidate);
+        init.set_sdp_mid(candidate.sdp_mid.as_deref());
+        init.set_sdp_m_line_index(candidate.sdp_mline_index);
+        JsFuture::from(
+            self.pc
+                .add_ice_candidate_with_opt_rtc_ice_candidate_init(Some(&init)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// create_data_channel creates a new DataChannel object with the given label
+    /// and optional DataChannelInit used to configure properties of the
+    /// underlying channel such as data reliability.
+    pub async fn create_data_channel(
+        &self,
+        label: &str,
+        options: Option<RTCDataChannelInit>,
+    ) -> Result<Rc<RTCDataChannel>> {
+        let dc = match options {
+            Some(options) => {
+                let init = RtcDataChannelInit::new();
+                if let Some(ordered) = options.ordered {
+                    init.set_ordered(ordered);
+                }
+                if let Some(max_packet_life_time) = options.max_packet_life_time {
+                    init.set_max_packet_life_time(max_packet_life_time);
+                }
+                if let Some(max_retransmits) = options.max_retransmits {
+                    init.set_max_retransmits(max_retransmits);
+                }
+                if let Some(protocol) = &options.protocol {
+                    init.set_protocol(protocol);
+                }
+                if let Some(id) = options.negotiated {
+                    init.set_negotiated(true);
+                    init.set_id(id);
+                }
+                self.pc
+                    .create_data_channel_with_data_channel_dict(label, &init)
+            }
+            None => self.pc.create_data_channel(label),
+        };
+        Ok(Rc::new(RTCDataChannel::new(dc)))
+    }
+
+    /// connection_state returns the current PeerConnectionState.
+    pub fn connection_state(&self) -> RTCPeerConnectionState {
+        connection_state(&self.pc)
+    }
+
+    /// on_ice_candidate sets an event handler which is invoked when a new ICE candidate is
+    /// found, and with None when the gathering is finished.
+    pub fn on_ice_candidate(&self, mut f: OnICECandidateHdlrFn) {
+        let closure = event_closure(move |event| {
+            let event: RtcPeerConnectionIceEvent = event.unchecked_into();
+            let candidate = event.candidate().map(|c| RTCIceCandidateInit {
+                candidate: c.candidate(),
+                sdp_mid: c.sdp_mid(),
+                sdp_mline_index: c.sdp_m_line_index(),
+                username_fragment: None,
+            });
+            spawn_local(f(candidate));
+        });
+        self.set_handler(
+            "icecandidate",
+            closure,
+            RtcPeerConnection::set_onicecandidate,
+        );
+    }
+
+    /// on_peer_connection_state_change sets an event handler which is called
+    /// when the PeerConnectionState has changed.
+    pub fn on_peer_connection_state_change(&self, mut f: OnPeerConnectionStateChangeHdlrFn) {
+        let pc = self.pc.clone();
+        let closure = event_closure(move |_| {
+            let state = connection_state(&pc);
+            spawn_local(f(state));
+        });
+        self.set_handler(
+            "connectionstatechange",
+            closure,
+            RtcPeerConnection::set_onconnectionstatechange,
+        );
+    }
+
+    /// on_data_channel sets an event handler which is invoked when a data
+    /// channel is opened by the remote peer.
+    pub fn on_data_channel(&self, mut f: OnDataChannelHdlrFn) {
+        let closure = event_closure(move |event| {
+            let event: RtcDataChannelEvent = event.unchecked_into();
+            spawn_local(f(Rc::new(RTCDataChannel::new(event.channel()))));
+        });
+        self.set_handler("datachannel", closure, RtcPeerConnection::set_ondatachannel);
+    }
+
+    /// on_negotiation_needed sets an event handler which is invoked when
+    /// a change has occurred which requires session negotiation.
+    pub fn on_negotiation_needed(&self, mut f: OnNegotiationNeededHdlrFn) {
+        let closure = event_closure(move |_| spawn_local(f()));
+        self.set_handler(
+            "negotiationneeded",
+            closure,
+            RtcPeerConnection::set_onnegotiationneeded,
+        );
+    }
+
+    /// close ends the PeerConnection.
+    pub async fn close(&self) -> Result<()> {
+        self.pc.close();
+        Ok(())
+    }
+}
+
+impl Drop for RTCPeerConnection {
+    fn drop(&mut self) {
+        // The handlers are freed with the PeerConnection, the browser must not call them
+        self.pc.set_onicecandidate(None);
+        self.pc.set_onconnectionstatechange(None);
+        self.pc.set_ondatachannel(None);
+        self.pc.set_onnegotiationneeded(None);
+        self.pc.close();
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod whep_test;
+
+use crate::error::{Error, Result};
+use crate::ice_transport::ice_server::RTCIceServer;
+use crate::peer_connection::RTCPeerConnection;
+use crate::rtp_transceiver::rtp_codec::RTPCodecType;
+use crate::rtp_transceiver::rtp_receiver::RTCRtpReceiver;
+use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
+use crate::rtp_transceiver::RTCRtpTransceiverInit;
+use crate::track::track_remote::TrackRemote;
+use crate::whip::{ice_servers_from_link_headers, send_request, WhipClient, WhipSession};
+
+use hyper::client::connect::Connect;
+use hyper::client::HttpConnector;
+use hyper::{Client, HeaderMap, Method};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use url::Url;
+
+/// WhepClient plays the tracks of a WHEP endpoint, the WebRTC-HTTP egress protocol of media
+/// servers, with a RTCPeerConnection. It speaks HTTP like WhipClient.
+/// <https://datatracker.ietf.org/doc/draft-ietf-wish-whep/>
+#[derive(Clone)]
+pub struct WhepClient<C = HttpConnector> {
+    whip: WhipClient<C>,
+}
+
+impl WhepClient<HttpConnector> {
+    /// new creates a WhepClient for the given endpoint URL.
+    pub fn new(endpoint: &str) -> Result<Self> {
+        Ok(WhepClient {
+            whip: WhipClient::new(endpoint)?,
+        })
+    }
+}
+
+impl<C> WhepClient<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    /// with_http_client creates a WhepClient for the given endpoint URL, sending its requests
+    /// with the given hyper Client.
+    pub fn with_http_client(http: Client<C>, endpoint: &str) -> Result<Self> {
+        Ok(WhepClient {
+            whip: WhipClient::with_http_client(http, endpoint)?,
+        })
+    }
+
+    /// with_bearer_token sets the token sent in the Authorization header of the requests.
+    pub fn with_bearer_token(mut self, token: &str) -> Self {
+        self.whip = self.whip.with_bearer_token(token);
+        self
+    }
+
+    /// with_trickle_ice sets whether the local ICE candidates are sent to the session with
+    /// PATCH requests, see WhipClient::with_trickle_ice.
+    pub fn with_trickle_ice(mut self, trickle_ice: bool) -> Self {
+        self.whip = self.whip.with_trickle_ice(trickle_ice);
+        self
+    }
+
+    /// ice_servers asks the endpoint for the ICE servers to configure the RTCPeerConnection
+    /// with, in the Link headers of the response to an OPTIONS request. Not all the servers
+    /// answer it, the ICE servers of the session are also available with
+    /// WhepSession::ice_servers.
+    pub async fn ice_servers(&self) -> Result<Vec<RTCIceServer>> {
+        let response = send_request(
+            &self.whip.http,
+            Method::OPTIONS,
+            self.whip.endpoint.clone(),
+            self.whip.bearer_token.as_deref(),
+            HeaderMap::new(),
+            String::new(),
+        )
+        .await?;
+        if !response.status.is_success() {
+            return Err(Error::ErrUnexpectedHttpStatus(response.status.as_u16()));
+        }
+
+        Ok(ice_servers_from_link_headers(&response.headers))
+    }
+
+    /// play negotiates the RTCPeerConnection with the WHEP endpoint and returns the session,
+    /// whose remote tracks are returned by WhepSession::next_track as they arrive. A recvonly
+    /// audio and video transceiver are added if the RTCPeerConnection has none. The on_track
+    /// handler of the RTCPeerConnection is replaced.
+    pub async fn play(&self, pc: Arc<RTCPeerConnection>) -> Result<WhepSession<C>> {
+        if pc.get_transceivers().await.is_empty() {
+            for kind in [RTPCodecType::Audio, RTPCodecType::Video] {
+                pc.add_transceiver_from_kind(
+                    kind,
+                    &[RTCRtpTransceiverInit {
+                        direction: RTCRtpTransceiverDirection::Recvonly,
+                        send_encodings: vec![],
+                    }],
+                )
+                .await?;
+            }
+        }
+
+        let (track_tx, track_rx) = mpsc::unbounded_channel();
+        pc.on_track(Box::new(
+            move |track: Option<Arc<TrackRemote>>, _: Option<Arc<RTCRtpReceiver>>| {
+                if let Some(track) = track {
+                    let _ = track_tx.send(track);
+                }
+                Box::pin(async {})
+            },
+        ));
+
+        let (answer, session) = self.whip.negotiate(&pc).await?;
+        pc.set_remote_description(answer).await?;
+
+        Ok(WhepSession { session, track_rx })
+    }
+}
+
+/// WhepSession is a session created on a WHEP server by WhepClient::play.
+pub struct WhepSession<C = HttpConnector> {
+    session: WhipSession<C>,
+    track_rx: mpsc::UnboundedReceiver<Arc<TrackRemote>>,
+}
+
+impl<C> WhepSession<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    /// url returns the URL of the session resource.
+    pub fn url(&self) -> &Url {
+        self.session.url()
+    }
+
+    /// ice_servers returns the ICE servers advertised by the server in the response which
+    /// created the session.
+    pub fn ice_servers(&self) -> Vec<RTCIceServer> {
+        self.session.ice_servers()
+    }
+
+    /// next_track waits for the next remote track, it returns None once the RTCPeerConnection
+    /// is dropped or its on_track handler is replaced.
+    pub async fn next_track(&mut self) -> Option<Arc<TrackRemote>> {
+        self.track_rx.recv().await
+    }
+
+    /// delete tears the session down on the server with a DELETE request. The
+    /// RTCPeerConnection is left open, it is up to the caller to close it.
+    pub async fn delete(self) -> Result<()> {
+        self.session.delete().await
+    }
+}
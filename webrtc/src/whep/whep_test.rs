@@ -0,0 +1,167 @@
+use super::*;
+use crate::api::media_engine::{MediaEngine, MIME_TYPE_VP8};
+use crate::api::APIBuilder;
+use crate::peer_connection::configuration::RTCConfiguration;
+use crate::peer_connection::peer_connection_test::send_video_until_done;
+use crate::peer_connection::sdp::session_description::RTCSessionDescription;
+use crate::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use crate::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+use crate::track::track_local::TrackLocal;
+use crate::whip_endpoint::MIME_TYPE_SDP;
+
+use bytes::Bytes;
+use hyper::header::{CONTENT_TYPE, LINK, LOCATION};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+const LINK_ICE_SERVERS: &str = "<stun:stun.example.net>; rel=\"ice-server\", \
+    <turn:turn.example.net?transport=udp>; rel=\"ice-server\"; username=\"user\"; \
+    credential=\"my;secret\"; credential-type=\"password\"";
+
+#[derive(Default)]
+struct TestServer {
+    sender: Mutex<Option<(Arc<RTCPeerConnection>, mpsc::Sender<()>)>>,
+}
+
+async fn new_peer_connection() -> Result<Arc<RTCPeerConnection>> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+    Ok(Arc::new(
+        api.new_peer_connection(RTCConfiguration::default()).await?,
+    ))
+}
+
+/// answer answers the offer of a WHEP client with a peer connection sending a video track.
+async fn answer(server: &TestServer, offer: String) -> Result<String> {
+    let sender = new_peer_connection().await?;
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "whep".to_owned(),
+    ));
+    sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    sender
+        .set_remote_description(RTCSessionDescription::offer(offer)?)
+        .await?;
+    let answer = sender.create_answer(None).await?;
+    let mut gathering_complete = sender.gathering_complete_promise().await;
+    sender.set_local_description(answer).await?;
+    let _ = gathering_complete.recv().await;
+
+    let (done_tx, done_rx) = mpsc::channel(1);
+    tokio::spawn(send_video_until_done(
+        done_rx,
+        vec![track],
+        Bytes::from_static(&[0xAA]),
+        None,
+    ));
+
+    let answer = sender.local_description().await.unwrap();
+    *server.sender.lock().await = Some((sender, done_tx));
+    Ok(answer.sdp)
+}
+
+async fn handle(
+    server: Arc<TestServer>,
+    request: Request<Body>,
+) -> std::result::Result<Response<Body>, Infallible> {
+    let response = match (request.method().clone(), request.uri().path()) {
+        (Method::OPTIONS, "/whep") => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .header(LINK, LINK_ICE_SERVERS)
+            .body(Body::empty()),
+        (Method::POST, "/whep") => {
+            let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+            let sdp = answer(&server, String::from_utf8(body.to_vec()).unwrap())
+                .await
+                .unwrap();
+            Response::builder()
+                .status(StatusCode::CREATED)
+                .header(CONTENT_TYPE, MIME_TYPE_SDP)
+                .header(LOCATION, "session/1")
+                .header(LINK, "<stun:stun.example.net>; rel=\"ice-server\"")
+                .body(Body::from(sdp))
+        }
+        (Method::DELETE, "/session/1") => Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty()),
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty()),
+    };
+    Ok(response.unwrap())
+}
+
+#[test]
+fn test_ice_servers_from_link_headers() {
+    let mut headers = HeaderMap::new();
+    headers.append(LINK, LINK_ICE_SERVERS.parse().unwrap());
+    headers.append(LINK, "</other/page>; rel=\"next\"".parse().unwrap());
+
+    let ice_servers = ice_servers_from_link_headers(&headers);
+    assert_eq!(ice_servers.len(), 2);
+    assert_eq!(
+        ice_servers[0].urls,
+        vec!["stun:stun.example.net".to_owned()]
+    );
+    assert!(ice_servers[0].username.is_empty());
+    assert_eq!(
+        ice_servers[1].urls,
+        vec!["turn:turn.example.net?transport=udp".to_owned()]
+    );
+    assert_eq!(ice_servers[1].username, "user");
+    assert_eq!(ice_servers[1].credential, "my;secret");
+}
+
+#[tokio::test]
+async fn test_whep_client_play() -> Result<()> {
+    let server = Arc::new(TestServer::default());
+    let make_service = {
+        let server = Arc::clone(&server);
+        make_service_fn(move |_| {
+            let server = Arc::clone(&server);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle(Arc::clone(&server), request)
+                }))
+            }
+        })
+    };
+    let http_server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+    let addr = http_server.local_addr();
+    tokio::spawn(http_server);
+
+    let client = WhepClient::new(&format!("http://{}/whep", addr))?;
+    assert_eq!(client.ice_servers().await?.len(), 2);
+
+    let pc = new_peer_connection().await?;
+    let mut session = client.play(Arc::clone(&pc)).await?;
+    assert_eq!(pc.get_transceivers().await.len(), 2);
+    assert_eq!(session.url().as_str(), format!("http://{}/session/1", addr));
+    assert_eq!(session.ice_servers().len(), 1);
+
+    let track = tokio::time::timeout(Duration::from_secs(10), session.next_track())
+        .await
+        .expect("no remote track")
+        .unwrap();
+    assert_eq!(track.kind(), RTPCodecType::Video);
+
+    session.delete().await?;
+    pc.close().await?;
+    if let Some((sender, done_tx)) = server.sender.lock().await.take() {
+        let _ = done_tx.send(()).await;
+        sender.close().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,431 @@
+#[cfg(test)]
+mod whip_test;
+
+use crate::error::{Error, Result};
+use crate::ice_transport::ice_candidate::RTCIceCandidate;
+use crate::ice_transport::ice_credential_type::RTCIceCredentialType;
+use crate::ice_transport::ice_server::RTCIceServer;
+use crate::peer_connection::sdp::session_description::RTCSessionDescription;
+use crate::peer_connection::RTCPeerConnection;
+use crate::whip_endpoint::{MIME_TYPE_SDP, MIME_TYPE_TRICKLE_ICE_SDPFRAG};
+
+use hyper::client::connect::Connect;
+use hyper::client::HttpConnector;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH, LINK, LOCATION};
+use hyper::{Body, Client, HeaderMap, Method, Request, StatusCode};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use url::Url;
+
+impl From<hyper::Error> for Error {
+    fn from(e: hyper::Error) -> Self {
+        Error::Http(e.to_string())
+    }
+}
+
+impl From<hyper::http::Error> for Error {
+    fn from(e: hyper::http::Error) -> Self {
+        Error::Http(e.to_string())
+    }
+}
+
+/// WhipClient publishes the tracks of a RTCPeerConnection to a WHIP endpoint, the WebRTC-HTTP
+/// ingestion protocol of media servers.
+/// <https://www.rfc-editor.org/rfc/rfc9725>
+///
+/// The tracks must be added to the RTCPeerConnection before publishing, e.g. with add_track.
+/// WhipClient::new only speaks plain HTTP, with_http_client takes a hyper Client whose
+/// connector provides HTTPS, e.g. the one of the hyper-rustls crate.
+#[derive(Clone)]
+pub struct WhipClient<C = HttpConnector> {
+    pub(crate) http: Client<C>,
+    pub(crate) endpoint: Url,
+    pub(crate) bearer_token: Option<String>,
+    pub(crate) trickle_ice: bool,
+}
+
+impl WhipClient<HttpConnector> {
+    /// new creates a WhipClient for the given endpoint URL.
+    pub fn new(endpoint: &str) -> Result<Self> {
+        WhipClient::with_http_client(Client::new(), endpoint)
+    }
+}
+
+impl<C> WhipClient<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    /// with_http_client creates a WhipClient for the given endpoint URL, sending its requests
+    /// with the given hyper Client.
+    pub fn with_http_client(http: Client<C>, endpoint: &str) -> Result<Self> {
+        Ok(WhipClient {
+            http,
+            endpoint: Url::parse(endpoint)?,
+            bearer_token: None,
+            trickle_ice: false,
+        })
+    }
+
+    /// with_bearer_token sets the token sent in the Authorization header of the requests.
+    pub fn with_bearer_token(mut self, token: &str) -> Self {
+        self.bearer_token = Some(token.to_owned());
+        self
+    }
+
+    /// with_trickle_ice sets whether the offer is sent as soon as it is created, the local ICE
+    /// candidates being sent to the session afterwards with PATCH requests, instead of once
+    /// all of them have been gathered. It is disabled by default since not all the servers
+    /// support it, a session whose server rejects trickling simply stops it.
+    pub fn with_trickle_ice(mut self, trickle_ice: bool) -> Self {
+        self.trickle_ice = trickle_ice;
+        self
+    }
+
+    /// publish negotiates the RTCPeerConnection with the WHIP endpoint: it sends the offer
+    /// in a POST request and sets the answer of the endpoint as remote description. The
+    /// returned WhipSession must be deleted to tear the session down on the server.
+    pub async fn publish(&self, pc: Arc<RTCPeerConnection>) -> Result<WhipSession<C>> {
+        let (answer, session) = self.negotiate(&pc).await?;
+        pc.set_remote_description(answer).await?;
+        Ok(session)
+    }
+
+    /// negotiate sends the offer of the RTCPeerConnection to the endpoint and returns its
+    /// answer along with the session it created, starting to trickle the local candidates
+    /// to it if enabled.
+    pub(crate) async fn negotiate(
+        &self,
+        pc: &Arc<RTCPeerConnection>,
+    ) -> Result<(RTCSessionDescription, WhipSession<C>)> {
+        let (candidate_tx, candidate_rx) = mpsc::unbounded_channel();
+        if self.trickle_ice {
+            pc.on_ice_candidate(Box::new(move |candidate: Option<RTCIceCandidate>| {
+                if let Some(candidate) = candidate {
+                    let _ = candidate_tx.send(candidate);
+                }
+                Box::pin(async {})
+            }));
+        }
+
+        let offer = pc.create_offer(None).await?;
+        let mut gathering_complete = pc.gathering_complete_promise().await;
+        pc.set_local_description(offer.clone()).await?;
+        let offer = if self.trickle_ice {
+            offer
+        } else {
+            let _ = gathering_complete.recv().await;
+            pc.local_description().await.unwrap_or(offer)
+        };
+
+        let response = send_request(
+            &self.http,
+            Method::POST,
+            self.endpoint.clone(),
+            self.bearer_token.as_deref(),
+            headers(&[(CONTENT_TYPE, MIME_TYPE_SDP)])?,
+            offer.sdp.clone(),
+        )
+        .await?;
+        if response.status != StatusCode::CREATED {
+            return Err(Error::ErrUnexpectedHttpStatus(response.status.as_u16()));
+        }
+
+        let location = response
+            .headers
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or(Error::ErrWhipNoLocation)?;
+        let mut session = WhipSession {
+            http: self.http.clone(),
+            url: self.endpoint.join(location)?,
+            bearer_token: self.bearer_token.clone(),
+            etag: Arc::new(Mutex::new(
+                response
+                    .headers
+                    .get(ETAG)
+                    .and_then(|etag| etag.to_str().ok())
+                    .map(|etag| etag.to_owned()),
+            )),
+            response_headers: response.headers,
+            trickle: None,
+        };
+        if self.trickle_ice {
+            session.start_trickle(&offer, candidate_rx)?;
+        }
+
+        Ok((RTCSessionDescription::answer(response.body)?, session))
+    }
+}
+
+/// WhipSession is a session created on a WHIP or WHEP server, the resource whose URL is
+/// returned in the Location header of the response to the offer.
+pub struct WhipSession<C = HttpConnector> {
+    http: Client<C>,
+    url: Url,
+    bearer_token: Option<String>,
+    etag: Arc<Mutex<Option<String>>>,
+    response_headers: HeaderMap,
+    trickle: Option<JoinHandle<()>>,
+}
+
+impl<C> WhipSession<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    /// url returns the URL of the session resource.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// response_headers returns the headers of the response which created the session, e.g.
+    /// its Link headers.
+    pub fn response_headers(&self) -> &HeaderMap {
+        &self.response_headers
+    }
+
+    /// ice_servers returns the ICE servers advertised by the server in the response which
+    /// created the session.
+    pub fn ice_servers(&self) -> Vec<RTCIceServer> {
+        ice_servers_from_link_headers(&self.response_headers)
+    }
+
+    /// delete tears the session down on the server with a DELETE request. The
+    /// RTCPeerConnection is left open, it is up to the caller to close it.
+    pub async fn delete(mut self) -> Result<()> {
+        if let Some(trickle) = self.trickle.take() {
+            trickle.abort();
+        }
+
+        let response = send_request(
+            &self.http,
+            Method::DELETE,
+            self.url.clone(),
+            self.bearer_token.as_deref(),
+            HeaderMap::new(),
+            String::new(),
+        )
+        .await?;
+        if !response.status.is_success() {
+            return Err(Error::ErrUnexpectedHttpStatus(response.status.as_u16()));
+        }
+
+        Ok(())
+    }
+
+    /// start_trickle spawns the task sending the local candidates to the session, in the
+    /// sdpfrag of the first media section of the offer.
+    fn start_trickle(
+        &mut self,
+        offer: &RTCSessionDescription,
+        mut candidate_rx: mpsc::UnboundedReceiver<RTCIceCandidate>,
+    ) -> Result<()> {
+        let parsed = offer.unmarshal()?;
+        let media = match parsed.media_descriptions.first() {
+            Some(media) => media,
+            None => return Ok(()),
+        };
+        let attribute = |key: &str| {
+            media
+                .attribute(key)
+                .and_then(|value| value.map(|value| value.to_owned()))
+                .or_else(|| parsed.attribute(key).cloned())
+                .unwrap_or_default()
+        };
+        let fragment = format!(
+            "a=ice-ufrag:{}\r\na=ice-pwd:{}\r\nm={} 9 {} {}\r\na=mid:{}\r\n",
+            attribute("ice-ufrag"),
+            attribute("ice-pwd"),
+            media.media_name.media,
+            media.media_name.protos.join("/"),
+            media.media_name.formats.join(" "),
+            attribute("mid"),
+        );
+
+        let http = self.http.clone();
+        let url = self.url.clone();
+        let bearer_token = self.bearer_token.clone();
+        let etag = Arc::clone(&self.etag);
+        self.trickle = Some(tokio::spawn(async move {
+            while let Some(candidate) = candidate_rx.recv().await {
+                let candidate = match candidate.to_json() {
+                    Ok(candidate) => candidate.candidate,
+                    Err(err) => {
+                        log::warn!("failed to marshal local candidate: {}", err);
+                        continue;
+                    }
+                };
+
+                let mut request_headers =
+                    match headers(&[(CONTENT_TYPE, MIME_TYPE_TRICKLE_ICE_SDPFRAG)]) {
+                        Ok(request_headers) => request_headers,
+                        Err(_) => return,
+                    };
+                if let Some(etag) = &*etag.lock().await {
+                    if let Ok(etag) = etag.parse() {
+                        request_headers.insert(IF_MATCH, etag);
+                    }
+                }
+                let body = format!("{}a={}\r\n", fragment, candidate);
+                match send_request(
+                    &http,
+                    Method::PATCH,
+                    url.clone(),
+                    bearer_token.as_deref(),
+                    request_headers,
+                    body,
+                )
+                .await
+                {
+                    Ok(response) if response.status.is_success() => {}
+                    Ok(response)
+                        if response.status == StatusCode::METHOD_NOT_ALLOWED
+                            || response.status == StatusCode::NOT_IMPLEMENTED =>
+                    {
+                        log::debug!("{} doesn't support trickle ICE", url);
+                        return;
+                    }
+                    Ok(response) => {
+                        log::warn!("trickle ICE to {} failed: {}", url, response.status);
+                    }
+                    Err(err) => log::warn!("trickle ICE to {} failed: {}", url, err),
+                }
+            }
+        }));
+
+        Ok(())
+    }
+}
+
+impl<C> Drop for WhipSession<C> {
+    fn drop(&mut self) {
+        if let Some(trickle) = self.trickle.take() {
+            trickle.abort();
+        }
+    }
+}
+
+/// ice_servers_from_link_headers returns the ICE servers advertised in the Link headers of a
+/// WHIP or WHEP response, the links whose relation type is ice-server.
+/// <https://www.rfc-editor.org/rfc/rfc9725#section-4.4>
+pub fn ice_servers_from_link_headers(headers: &HeaderMap) -> Vec<RTCIceServer> {
+    let mut ice_servers = vec![];
+    for value in headers.get_all(LINK) {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        ice_servers.extend(
+            split_unquoted(value, ',')
+                .into_iter()
+                .filter_map(parse_link),
+        );
+    }
+    ice_servers
+}
+
+/// parse_link parses a link, e.g. `<turn:turn.example.net>; rel="ice-server"; username="user";
+/// credential="pass"; credential-type="password"`, into an ICE server if it is one.
+fn parse_link(link: &str) -> Option<RTCIceServer> {
+    let mut parts = split_unquoted(link, ';').into_iter();
+    let url = parts.next()?.trim();
+    let url = url.strip_prefix('<')?.strip_suffix('>')?;
+
+    let mut ice_server = RTCIceServer {
+        urls: vec![url.to_owned()],
+        ..Default::default()
+    };
+    let mut is_ice_server = false;
+    for param in parts {
+        let (key, value) = match param.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim().trim_matches('"')),
+            None => continue,
+        };
+        match key.to_lowercase().as_str() {
+            "rel" => is_ice_server = value.split_whitespace().any(|rel| rel == "ice-server"),
+            "username" => ice_server.username = value.to_owned(),
+            "credential" => ice_server.credential = value.to_owned(),
+            "credential-type" => ice_server.credential_type = RTCIceCredentialType::from(value),
+            _ => {}
+        }
+    }
+
+    if is_ice_server {
+        Some(ice_server)
+    } else {
+        None
+    }
+}
+
+/// split_unquoted splits s at the separators which are neither quoted nor in a URL.
+fn split_unquoted(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let (mut start, mut quoted, mut in_url) = (0, false, false);
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' if !in_url => quoted = !quoted,
+            '<' if !quoted => in_url = true,
+            '>' if !quoted => in_url = false,
+            c if c == separator && !quoted && !in_url => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts.retain(|part| !part.trim().is_empty());
+    parts
+}
+
+/// HttpResponse is a response whose body has been read.
+pub(crate) struct HttpResponse {
+    pub(crate) status: StatusCode,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: String,
+}
+
+/// headers returns a HeaderMap of the given headers.
+pub(crate) fn headers(headers: &[(hyper::header::HeaderName, &str)]) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        map.insert(
+            name.clone(),
+            value
+                .parse()
+                .map_err(|_| Error::Http(format!("invalid {} header", name)))?,
+        );
+    }
+    Ok(map)
+}
+
+/// send_request sends a request with a body and reads the body of its response.
+pub(crate) async fn send_request<C>(
+    http: &Client<C>,
+    method: Method,
+    url: Url,
+    bearer_token: Option<&str>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<HttpResponse>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let mut request = Request::builder().method(method).uri(url.as_str());
+    if let Some(bearer_token) = bearer_token {
+        request = request.header(AUTHORIZATION, format!("Bearer {}", bearer_token));
+    }
+    let mut request = request.body(Body::from(body))?;
+    request.headers_mut().extend(headers);
+
+    let response = http.request(request).await?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = hyper::body::to_bytes(response.into_body()).await?;
+
+    Ok(HttpResponse {
+        status,
+        headers,
+        body: String::from_utf8(body.to_vec())?,
+    })
+}
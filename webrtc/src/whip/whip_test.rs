@@ -0,0 +1,172 @@
+use super::*;
+use crate::api::media_engine::MediaEngine;
+use crate::api::APIBuilder;
+use crate::peer_connection::configuration::RTCConfiguration;
+use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use crate::peer_connection::peer_connection_test::until_connection_state;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Response, Server};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use waitgroup::WaitGroup;
+
+#[derive(Default)]
+struct TestServer {
+    answerer: Mutex<Option<Arc<RTCPeerConnection>>>,
+    patches: AtomicUsize,
+    deleted: AtomicBool,
+}
+
+async fn new_peer_connection() -> Result<Arc<RTCPeerConnection>> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+    Ok(Arc::new(
+        api.new_peer_connection(RTCConfiguration::default()).await?,
+    ))
+}
+
+async fn answer(server: &TestServer, offer: String) -> Result<String> {
+    let answerer = new_peer_connection().await?;
+    answerer
+        .set_remote_description(RTCSessionDescription::offer(offer)?)
+        .await?;
+    let answer = answerer.create_answer(None).await?;
+    let mut gathering_complete = answerer.gathering_complete_promise().await;
+    answerer.set_local_description(answer).await?;
+    let _ = gathering_complete.recv().await;
+
+    let answer = answerer.local_description().await.unwrap();
+    *server.answerer.lock().await = Some(answerer);
+    Ok(answer.sdp)
+}
+
+async fn handle(
+    server: Arc<TestServer>,
+    request: Request<Body>,
+) -> std::result::Result<Response<Body>, Infallible> {
+    let status = |status: StatusCode| {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = status;
+        Ok(response)
+    };
+    if request.headers().get(AUTHORIZATION).unwrap() != "Bearer secret" {
+        return status(StatusCode::UNAUTHORIZED);
+    }
+
+    match (request.method().clone(), request.uri().path()) {
+        (Method::POST, "/whip") => {
+            assert_eq!(request.headers().get(CONTENT_TYPE).unwrap(), MIME_TYPE_SDP);
+            let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+            let sdp = answer(&server, String::from_utf8(body.to_vec()).unwrap())
+                .await
+                .unwrap();
+            Ok(Response::builder()
+                .status(StatusCode::CREATED)
+                .header(CONTENT_TYPE, MIME_TYPE_SDP)
+                .header(LOCATION, "/whip/session/1")
+                .header(ETAG, "\"1\"")
+                .body(Body::from(sdp))
+                .unwrap())
+        }
+        (Method::PATCH, "/whip/session/1") => {
+            assert_eq!(
+                request.headers().get(CONTENT_TYPE).unwrap(),
+                MIME_TYPE_TRICKLE_ICE_SDPFRAG
+            );
+            assert_eq!(request.headers().get(IF_MATCH).unwrap(), "\"1\"");
+            let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            assert!(body.starts_with("a=ice-ufrag:"));
+            assert!(body.contains("\r\na=mid:0\r\na=candidate:"));
+            server.patches.fetch_add(1, Ordering::SeqCst);
+            status(StatusCode::NO_CONTENT)
+        }
+        (Method::DELETE, "/whip/session/1") => {
+            server.deleted.store(true, Ordering::SeqCst);
+            status(StatusCode::OK)
+        }
+        _ => status(StatusCode::NOT_FOUND),
+    }
+}
+
+/// serve starts a WHIP server and returns the address it listens on.
+fn serve(server: Arc<TestServer>) -> SocketAddr {
+    let make_service = make_service_fn(move |_| {
+        let server = Arc::clone(&server);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle(Arc::clone(&server), request)
+            }))
+        }
+    });
+    let http_server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+    let addr = http_server.local_addr();
+    tokio::spawn(http_server);
+    addr
+}
+
+#[tokio::test]
+async fn test_whip_client_publish() -> Result<()> {
+    let server = Arc::new(TestServer::default());
+    let addr = serve(Arc::clone(&server));
+
+    let client = WhipClient::new(&format!("http://{}/whip", addr))?
+        .with_bearer_token("secret")
+        .with_trickle_ice(true);
+
+    let mut pc = new_peer_connection().await?;
+    pc.create_data_channel("data", None).await?;
+    let wg = WaitGroup::new();
+    until_connection_state(
+        Arc::get_mut(&mut pc).unwrap(),
+        &wg,
+        RTCPeerConnectionState::Connected,
+    )
+    .await;
+
+    let session = client.publish(Arc::clone(&pc)).await?;
+    assert_eq!(
+        session.url().as_str(),
+        format!("http://{}/whip/session/1", addr)
+    );
+    assert!(pc.remote_description().await.is_some());
+
+    wg.wait().await;
+    for _ in 0..100 {
+        if server.patches.load(Ordering::SeqCst) > 0 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert!(server.patches.load(Ordering::SeqCst) > 0);
+
+    session.delete().await?;
+    assert!(server.deleted.load(Ordering::SeqCst));
+
+    pc.close().await?;
+    if let Some(answerer) = server.answerer.lock().await.take() {
+        answerer.close().await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_whip_client_unauthorized() -> Result<()> {
+    let server = Arc::new(TestServer::default());
+    let addr = serve(Arc::clone(&server));
+
+    let client = WhipClient::new(&format!("http://{}/whip", addr))?.with_bearer_token("wrong");
+    let pc = new_peer_connection().await?;
+    pc.create_data_channel("data", None).await?;
+
+    let result = client.publish(Arc::clone(&pc)).await;
+    assert_eq!(result.err(), Some(Error::ErrUnexpectedHttpStatus(401)));
+
+    pc.close().await?;
+
+    Ok(())
+}
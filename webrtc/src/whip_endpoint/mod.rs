@@ -0,0 +1,237 @@
+#[cfg(test)]
+mod whip_endpoint_test;
+
+use crate::api::API;
+use crate::error::{Error, Result};
+use crate::ice_transport::ice_candidate::RTCIceCandidateInit;
+use crate::ice_transport::ice_server::RTCIceServer;
+use crate::peer_connection::configuration::RTCConfiguration;
+use crate::peer_connection::math_rand_alpha;
+use crate::peer_connection::sdp::session_description::RTCSessionDescription;
+use crate::peer_connection::RTCPeerConnection;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Content type of the SDP offers and answers.
+pub const MIME_TYPE_SDP: &str = "application/sdp";
+/// Content type of the trickled ICE candidates, see RFC 8840.
+pub const MIME_TYPE_TRICKLE_ICE_SDPFRAG: &str = "application/trickle-ice-sdpfrag";
+
+/// WhipEndpoint terminates the sessions of WHIP or WHEP clients onto RTCPeerConnections,
+/// whatever the HTTP server: its handlers take the relevant parts of the requests and return
+/// what the responses must contain, http_status maps their errors to the response status.
+///
+/// A WHIP endpoint receives the tracks of the clients, a WHEP endpoint sends tracks to them,
+/// which is up to the setup closure given to handle_offer.
+pub struct WhipEndpoint {
+    api: API,
+    configuration: RTCConfiguration,
+    resource_path: String,
+    sessions: Mutex<HashMap<String, Arc<RTCPeerConnection>>>,
+}
+
+/// WhipEndpointAnswer is the outcome of an offer, to be sent back in a 201 Created response:
+/// sdp as body, location in the Location header, etag in the ETag header and each of
+/// link_headers in a Link header.
+pub struct WhipEndpointAnswer {
+    pub session_id: String,
+    pub location: String,
+    pub etag: String,
+    pub sdp: String,
+    pub link_headers: Vec<String>,
+    pub peer_connection: Arc<RTCPeerConnection>,
+}
+
+impl WhipEndpoint {
+    /// new creates a WhipEndpoint whose RTCPeerConnections are created by api with
+    /// configuration. The session resources are located at resource_path followed by their
+    /// session id, e.g. /whip/sessions/ for /whip/sessions/<id>.
+    pub fn new(api: API, configuration: RTCConfiguration, resource_path: &str) -> Self {
+        WhipEndpoint {
+            api,
+            configuration,
+            resource_path: resource_path.to_owned(),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// link_headers returns the ICE servers of the configuration as Link headers, to be sent
+    /// in the responses to offers and OPTIONS requests.
+    pub fn link_headers(&self) -> Vec<String> {
+        link_headers_for_ice_servers(&self.configuration.ice_servers)
+    }
+
+    /// handle_offer handles the POST request of an offer: it creates a RTCPeerConnection,
+    /// lets setup prepare it, e.g. add the tracks sent to a WHEP client or set the on_track
+    /// handler of a WHIP ingest, and answers the offer once all the local candidates have been
+    /// gathered. The session lasts until handle_delete is called for it.
+    pub async fn handle_offer<F, Fut>(
+        &self,
+        content_type: &str,
+        body: &str,
+        setup: F,
+    ) -> Result<WhipEndpointAnswer>
+    where
+        F: FnOnce(Arc<RTCPeerConnection>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        check_content_type(content_type, MIME_TYPE_SDP)?;
+        let offer = RTCSessionDescription::offer(body.to_owned())?;
+
+        let pc = Arc::new(
+            self.api
+                .new_peer_connection(self.configuration.clone())
+                .await?,
+        );
+        let sdp = match self.answer(&pc, offer, setup).await {
+            Ok(sdp) => sdp,
+            Err(err) => {
+                let _ = pc.close().await;
+                return Err(err);
+            }
+        };
+
+        let session_id = math_rand_alpha(16);
+        {
+            let mut sessions = self.sessions.lock().await;
+            sessions.insert(session_id.clone(), Arc::clone(&pc));
+        }
+
+        Ok(WhipEndpointAnswer {
+            location: format!("{}{}", self.resource_path, session_id),
+            etag: format!("\"{}\"", session_id),
+            session_id,
+            sdp,
+            link_headers: self.link_headers(),
+            peer_connection: pc,
+        })
+    }
+
+    /// answer sets up the RTCPeerConnection and returns its answer to offer.
+    async fn answer<F, Fut>(
+        &self,
+        pc: &Arc<RTCPeerConnection>,
+        offer: RTCSessionDescription,
+        setup: F,
+    ) -> Result<String>
+    where
+        F: FnOnce(Arc<RTCPeerConnection>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        setup(Arc::clone(pc)).await?;
+        pc.set_remote_description(offer).await?;
+
+        let answer = pc.create_answer(None).await?;
+        let mut gathering_complete = pc.gathering_complete_promise().await;
+        pc.set_local_description(answer.clone()).await?;
+        let _ = gathering_complete.recv().await;
+
+        Ok(pc.local_description().await.unwrap_or(answer).sdp)
+    }
+
+    /// handle_patch handles the PATCH request of a session trickling its ICE candidates, the
+    /// response to send is 204 No Content. ICE restarts aren't supported, the ICE credentials
+    /// of the fragment are ignored.
+    pub async fn handle_patch(
+        &self,
+        session_id: &str,
+        content_type: &str,
+        body: &str,
+    ) -> Result<()> {
+        check_content_type(content_type, MIME_TYPE_TRICKLE_ICE_SDPFRAG)?;
+        let pc = self
+            .session(session_id)
+            .await
+            .ok_or(Error::ErrWhipSessionNotFound)?;
+
+        let mut mid = None;
+        let mut mline_index = None;
+        for line in body.lines().map(|line| line.trim()) {
+            if line.starts_with("m=") {
+                mline_index = Some(mline_index.map_or(0, |index| index + 1));
+            } else if let Some(value) = line.strip_prefix("a=mid:") {
+                mid = Some(value.to_owned());
+            } else if let Some(candidate) = line.strip_prefix("a=") {
+                if candidate.starts_with("candidate:") {
+                    pc.add_ice_candidate(RTCIceCandidateInit {
+                        candidate: candidate.to_owned(),
+                        sdp_mid: mid.clone(),
+                        sdp_mline_index: mline_index,
+                        username_fragment: None,
+                    })
+                    .await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// handle_delete handles the DELETE request of a session: it closes its RTCPeerConnection
+    /// and forgets it, the response to send is 200 OK.
+    pub async fn handle_delete(&self, session_id: &str) -> Result<()> {
+        let pc = {
+            let mut sessions = self.sessions.lock().await;
+            sessions
+                .remove(session_id)
+                .ok_or(Error::ErrWhipSessionNotFound)?
+        };
+        pc.close().await
+    }
+
+    /// session returns the RTCPeerConnection of a session.
+    pub async fn session(&self, session_id: &str) -> Option<Arc<RTCPeerConnection>> {
+        let sessions = self.sessions.lock().await;
+        sessions.get(session_id).cloned()
+    }
+}
+
+/// http_status returns the HTTP status of the response to a request whose handler failed.
+pub fn http_status(err: &Error) -> u16 {
+    match err {
+        Error::ErrWhipSessionNotFound => 404,
+        Error::ErrUnsupportedContentType => 415,
+        Error::Sdp(_)
+        | Error::ErrSessionDescriptionNoFingerprint
+        | Error::ErrSessionDescriptionInvalidFingerprint
+        | Error::ErrSessionDescriptionConflictingFingerprints
+        | Error::ErrSessionDescriptionMissingIceUfrag
+        | Error::ErrSessionDescriptionMissingIcePwd
+        | Error::ErrSessionDescriptionConflictingIceUfrag
+        | Error::ErrSessionDescriptionConflictingIcePwd => 400,
+        _ => 500,
+    }
+}
+
+/// link_headers_for_ice_servers returns the Link headers advertising ICE servers.
+/// <https://www.rfc-editor.org/rfc/rfc9725#section-4.4>
+pub fn link_headers_for_ice_servers(ice_servers: &[RTCIceServer]) -> Vec<String> {
+    let mut link_headers = vec![];
+    for ice_server in ice_servers {
+        for url in &ice_server.urls {
+            let mut link = format!("<{}>; rel=\"ice-server\"", url);
+            if !ice_server.username.is_empty() {
+                link += &format!(
+                    "; username=\"{}\"; credential=\"{}\"; credential-type=\"{}\"",
+                    ice_server.username, ice_server.credential, ice_server.credential_type
+                );
+            }
+            link_headers.push(link);
+        }
+    }
+    link_headers
+}
+
+/// check_content_type returns an error if the content type of a request, without its
+/// parameters, isn't the expected one.
+fn check_content_type(content_type: &str, expected: &str) -> Result<()> {
+    let mime_type = content_type.split(';').next().unwrap_or_default().trim();
+    if mime_type.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(Error::ErrUnsupportedContentType)
+    }
+}
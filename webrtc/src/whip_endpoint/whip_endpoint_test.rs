@@ -0,0 +1,129 @@
+use super::*;
+use crate::api::media_engine::MediaEngine;
+use crate::api::APIBuilder;
+use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use crate::peer_connection::peer_connection_test::until_connection_state;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use waitgroup::WaitGroup;
+
+fn new_api() -> Result<API> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    Ok(APIBuilder::new().with_media_engine(m).build())
+}
+
+#[test]
+fn test_link_headers_for_ice_servers() {
+    let link_headers = link_headers_for_ice_servers(&[
+        RTCIceServer {
+            urls: vec!["stun:stun.example.net".to_owned()],
+            ..Default::default()
+        },
+        RTCIceServer {
+            urls: vec!["turn:turn.example.net?transport=udp".to_owned()],
+            username: "user".to_owned(),
+            credential: "pass".to_owned(),
+            ..Default::default()
+        },
+    ]);
+
+    assert_eq!(
+        link_headers,
+        vec![
+            "<stun:stun.example.net>; rel=\"ice-server\"".to_owned(),
+            "<turn:turn.example.net?transport=udp>; rel=\"ice-server\"; username=\"user\"; \
+             credential=\"pass\"; credential-type=\"password\""
+                .to_owned(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_whip_endpoint_session() -> Result<()> {
+    let endpoint = WhipEndpoint::new(
+        new_api()?,
+        RTCConfiguration {
+            ice_servers: vec![RTCIceServer {
+                urls: vec!["stun:stun.example.net".to_owned()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        "/whip/sessions/",
+    );
+
+    let mut client = new_api()?
+        .new_peer_connection(RTCConfiguration::default())
+        .await?;
+    client.create_data_channel("data", None).await?;
+    let wg = WaitGroup::new();
+    until_connection_state(&mut client, &wg, RTCPeerConnectionState::Connected).await;
+
+    let offer = client.create_offer(None).await?;
+    let mut gathering_complete = client.gathering_complete_promise().await;
+    client.set_local_description(offer).await?;
+    let _ = gathering_complete.recv().await;
+    let offer = client.local_description().await.unwrap();
+
+    let result = endpoint
+        .handle_offer("text/plain", &offer.sdp, |_| async { Ok(()) })
+        .await;
+    assert_eq!(
+        result.as_ref().err().map(http_status),
+        Some(415),
+        "offers must be application/sdp"
+    );
+
+    let set_up = Arc::new(AtomicBool::new(false));
+    let answer = {
+        let set_up = Arc::clone(&set_up);
+        endpoint
+            .handle_offer(
+                "application/sdp; charset=utf-8",
+                &offer.sdp,
+                |_| async move {
+                    set_up.store(true, Ordering::SeqCst);
+                    Ok(())
+                },
+            )
+            .await?
+    };
+    assert!(set_up.load(Ordering::SeqCst));
+    assert_eq!(
+        answer.location,
+        format!("/whip/sessions/{}", answer.session_id)
+    );
+    assert_eq!(answer.link_headers.len(), 1);
+    assert!(endpoint.session(&answer.session_id).await.is_some());
+
+    client
+        .set_remote_description(RTCSessionDescription::answer(answer.sdp.clone())?)
+        .await?;
+    wg.wait().await;
+
+    endpoint
+        .handle_patch(
+            &answer.session_id,
+            MIME_TYPE_TRICKLE_ICE_SDPFRAG,
+            "a=ice-ufrag:abcd\r\na=ice-pwd:efgh\r\nm=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+             a=mid:0\r\na=candidate:1 1 udp 2130706431 127.0.0.1 5000 typ host\r\n",
+        )
+        .await?;
+    let result = endpoint
+        .handle_patch("unknown", MIME_TYPE_TRICKLE_ICE_SDPFRAG, "")
+        .await;
+    assert_eq!(result.as_ref().err().map(http_status), Some(404));
+
+    endpoint.handle_delete(&answer.session_id).await?;
+    assert!(endpoint.session(&answer.session_id).await.is_none());
+    assert_eq!(
+        answer.peer_connection.connection_state(),
+        RTCPeerConnectionState::Closed
+    );
+    assert!(endpoint.handle_delete(&answer.session_id).await.is_err());
+
+    client.close().await?;
+
+    Ok(())
+}